@@ -33,6 +33,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
     //       about the fields on the nested structs
     let mut params: Vec<Param> = Vec::new();
     let mut persistent_fields: Vec<PersistentField> = Vec::new();
+    let mut persist_notifiers: Vec<syn::Ident> = Vec::new();
     for field in fields.named {
         let field_name = match &field.ident {
             Some(ident) => ident,
@@ -40,10 +41,103 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         };
 
         // All attributes are mutually exclusive. If we encounter multiple or duplicate attributes,
-        // then we'll error out.
+        // then we'll error out. The exception is `linked`, which is added to an `id` field.
         let mut processed_attribute = false;
+        let mut field_link: Option<(ParamLink, proc_macro2::Span)> = None;
         for attr in &field.attrs {
-            if attr.path.is_ident("id") {
+            if attr.path.is_ident("linked") {
+                if field_link.is_some() {
+                    return syn::Error::new(attr.span(), "Duplicate linked attribute found")
+                        .to_compile_error()
+                        .into();
+                }
+
+                let mut link_group: Option<syn::LitStr> = None;
+                let mut link_mode: Option<proc_macro2::TokenStream> = None;
+                let link_attrs = match attr.parse_meta() {
+                    Ok(syn::Meta::List(syn::MetaList { nested, .. })) => nested,
+                    _ => {
+                        return syn::Error::new(
+                            attr.span(),
+                            "The linked attribute should be a list in the following format: \
+                             #[linked(group = \"group name\", mode = \"mirror\")]",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                };
+
+                for link_attr in link_attrs {
+                    match link_attr {
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                            path,
+                            lit: syn::Lit::Str(s),
+                            ..
+                        })) if path.is_ident("group") => {
+                            if s.value().is_empty() {
+                                return syn::Error::new(s.span(), "Link groups cannot be empty")
+                                    .to_compile_error()
+                                    .into();
+                            }
+
+                            link_group = Some(s);
+                        }
+                        syn::NestedMeta::Meta(syn::Meta::NameValue(syn::MetaNameValue {
+                            path,
+                            lit: syn::Lit::Str(s),
+                            ..
+                        })) if path.is_ident("mode") => {
+                            link_mode = Some(match s.value().as_str() {
+                                "mirror" => quote! { ::nih_plug::params::ParamLinkMode::Mirror },
+                                "relative" => {
+                                    quote! { ::nih_plug::params::ParamLinkMode::Relative }
+                                }
+                                _ => {
+                                    return syn::Error::new(
+                                        s.span(),
+                                        "The link mode should be either \"mirror\" or \"relative\"",
+                                    )
+                                    .to_compile_error()
+                                    .into()
+                                }
+                            });
+                        }
+                        _ => {
+                            return syn::Error::new(
+                                link_attr.span(),
+                                "Unknown attribute. See the Params trait documentation for more \
+                                 information.",
+                            )
+                            .to_compile_error()
+                            .into()
+                        }
+                    }
+                }
+
+                match link_group {
+                    Some(group) => {
+                        field_link = Some((
+                            ParamLink {
+                                group,
+                                // Mirroring is the most common use case for stereo linking
+                                mode: link_mode.unwrap_or_else(
+                                    || quote! { ::nih_plug::params::ParamLinkMode::Mirror },
+                                ),
+                            },
+                            attr.span(),
+                        ));
+                    }
+                    None => {
+                        return syn::Error::new(
+                            attr.span(),
+                            "The linked attribute is missing a group: \
+                             #[linked(group = \"group name\", mode = \"mirror\")]",
+                        )
+                        .to_compile_error()
+                        .into()
+                    }
+                }
+            } else if attr.path.is_ident("id") {
                 match attr.parse_meta() {
                     Ok(syn::Meta::NameValue(syn::MetaNameValue {
                         lit: syn::Lit::Str(s),
@@ -76,6 +170,7 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         params.push(Param::Single {
                             id: s,
                             field: field_name.clone(),
+                            link: None,
                         });
 
                         processed_attribute = true;
@@ -91,35 +186,88 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                     }
                 };
             } else if attr.path.is_ident("persist") {
-                match attr.parse_meta() {
+                // This is either `#[persist = "key"]`, or `#[persist(key = "key",
+                // exclude_from_presets, with = "module", blob)]`
+                let (key, exclude_from_presets, kind) = match attr.parse_meta() {
                     Ok(syn::Meta::NameValue(syn::MetaNameValue {
                         lit: syn::Lit::Str(s),
                         ..
+                    })) => (s, false, PersistentFieldKind::Serde { with: None }),
+                    Ok(syn::Meta::List(syn::MetaList {
+                        nested: persist_attrs,
+                        ..
                     })) => {
-                        if processed_attribute {
-                            return syn::Error::new(
-                                attr.span(),
-                                "Duplicate or incompatible attribute found",
-                            )
-                            .to_compile_error()
-                            .into();
+                        let mut persist_key: Option<syn::LitStr> = None;
+                        let mut exclude_from_presets = false;
+                        let mut with: Option<syn::Path> = None;
+                        let mut blob = false;
+                        for persist_attr in persist_attrs {
+                            match persist_attr {
+                                syn::NestedMeta::Meta(syn::Meta::Path(p))
+                                    if p.is_ident("exclude_from_presets") =>
+                                {
+                                    exclude_from_presets = true;
+                                }
+                                syn::NestedMeta::Meta(syn::Meta::Path(p)) if p.is_ident("blob") => {
+                                    blob = true;
+                                }
+                                syn::NestedMeta::Meta(syn::Meta::NameValue(
+                                    syn::MetaNameValue {
+                                        path,
+                                        lit: syn::Lit::Str(s),
+                                        ..
+                                    },
+                                )) if path.is_ident("with") => match s.parse() {
+                                    Ok(path) => with = Some(path),
+                                    Err(err) => return err.to_compile_error().into(),
+                                },
+                                syn::NestedMeta::Meta(syn::Meta::NameValue(
+                                    syn::MetaNameValue {
+                                        path,
+                                        lit: syn::Lit::Str(s),
+                                        ..
+                                    },
+                                )) if path.is_ident("key") => {
+                                    persist_key = Some(s.clone());
+                                }
+                                _ => {
+                                    return syn::Error::new(
+                                        persist_attr.span(),
+                                        "Unknown attribute. See the Params trait documentation \
+                                         for more information.",
+                                    )
+                                    .to_compile_error()
+                                    .into()
+                                }
+                            }
                         }
 
-                        if persistent_fields.iter().any(|p| p.key == s) {
+                        if blob && with.is_some() {
                             return syn::Error::new(
-                                field.span(),
-                                "Multiple persistent fields with the same key found",
+                                attr.span(),
+                                "The blob and with attributes cannot be combined",
                             )
                             .to_compile_error()
                             .into();
                         }
 
-                        persistent_fields.push(PersistentField {
-                            key: s,
-                            field: field_name.clone(),
-                        });
-
-                        processed_attribute = true;
+                        let kind = if blob {
+                            PersistentFieldKind::Blob
+                        } else {
+                            PersistentFieldKind::Serde { with }
+                        };
+                        match persist_key {
+                            Some(key) => (key, exclude_from_presets, kind),
+                            None => {
+                                return syn::Error::new(
+                                    attr.span(),
+                                    "The persist attribute is missing a key: \
+                                     #[persist(key = \"foo_bar\", exclude_from_presets)]",
+                                )
+                                .to_compile_error()
+                                .into()
+                            }
+                        }
                     }
                     _ => {
                         return syn::Error::new(
@@ -131,6 +279,55 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                         .into()
                     }
                 };
+
+                if processed_attribute {
+                    return syn::Error::new(
+                        attr.span(),
+                        "Duplicate or incompatible attribute found",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                if persistent_fields.iter().any(|p| p.key == key) {
+                    return syn::Error::new(
+                        field.span(),
+                        "Multiple persistent fields with the same key found",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                persistent_fields.push(PersistentField {
+                    key,
+                    field: field_name.clone(),
+                    exclude_from_presets,
+                    kind,
+                });
+
+                processed_attribute = true;
+            } else if attr.path.is_ident("persist_notifier") {
+                if !matches!(attr.parse_meta(), Ok(syn::Meta::Path(..))) {
+                    return syn::Error::new(
+                        attr.span(),
+                        "The persist_notifier attribute does not take any arguments",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                if processed_attribute {
+                    return syn::Error::new(
+                        attr.span(),
+                        "Duplicate or incompatible attribute found",
+                    )
+                    .to_compile_error()
+                    .into();
+                }
+
+                persist_notifiers.push(field_name.clone());
+
+                processed_attribute = true;
             } else if attr.path.is_ident("nested") {
                 // This one is more complicated. Supports an `array` attribute, an `id_prefix =
                 // "foo"` attribute, and a `group = "group name"` attribute. All are optional, and
@@ -246,6 +443,24 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                 processed_attribute = true;
             }
         }
+
+        if let Some((link, span)) = field_link {
+            match params.last_mut() {
+                Some(Param::Single {
+                    field,
+                    link: field_link,
+                    ..
+                }) if *field == *field_name => *field_link = Some(link),
+                _ => {
+                    return syn::Error::new(
+                        span,
+                        "The linked attribute can only be used on fields with an id attribute",
+                    )
+                    .to_compile_error()
+                    .into()
+                }
+            }
+        }
     }
 
     // The next step is build the gathered information into tokens that can be spliced into a
@@ -265,20 +480,99 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         }
     };
 
+    let param_links_tokens = {
+        let param_link_tokens = params.iter().map(|p| p.param_links_tokens());
+
+        quote! {
+            // This may not be in scope otherwise, used to call .as_ptr()
+            use ::nih_plug::params::Param;
+
+            #[allow(unused_mut)]
+            let mut param_links = Vec::new();
+            #(#param_link_tokens)*
+
+            param_links
+        }
+    };
+
+    // These both recurse into nested parameter structs, with the latter running the notifiers'
+    // callbacks on the wrapper's background thread
+    let nested_notifier_tokens = |method: syn::Ident| {
+        params
+            .iter()
+            .filter_map(|p| match p {
+                Param::Single { .. } => None,
+                Param::Nested(NestedParams::Inline { field, .. })
+                | Param::Nested(NestedParams::Prefixed { field, .. }) => {
+                    Some(quote! { self.#field.#method(); })
+                }
+                Param::Nested(NestedParams::Array { field, .. }) => Some(quote! {
+                    for field in self.#field.iter() {
+                        field.#method();
+                    }
+                }),
+            })
+            .collect::<Vec<_>>()
+    };
+    let fields_loaded_tokens = {
+        let nested_tokens = nested_notifier_tokens(syn::Ident::new(
+            "fields_loaded",
+            proc_macro2::Span::call_site(),
+        ));
+
+        quote! {
+            #(::nih_plug::params::persist::PersistNotifier::notify(&self.#persist_notifiers);)*
+            #(#nested_tokens)*
+        }
+    };
+    let run_load_callbacks_tokens = {
+        let nested_tokens = nested_notifier_tokens(syn::Ident::new(
+            "run_load_callbacks",
+            proc_macro2::Span::call_site(),
+        ));
+
+        quote! {
+            #(::nih_plug::params::persist::PersistNotifier::run_callbacks(&self.#persist_notifiers);)*
+            #(#nested_tokens)*
+        }
+    };
+
+    // Regular persistent fields and binary blobs are stored in two separate maps
+    let (serde_fields, blob_fields): (Vec<_>, Vec<_>) = persistent_fields
+        .into_iter()
+        .partition(|p| matches!(p.kind, PersistentFieldKind::Serde { .. }));
+
     let (serialize_fields_tokens, deserialize_fields_tokens) = {
         // Like with `param_map()`, we'll try to do the serialization for this struct and then
         // recursively call the child parameter structs. We don't know anything about the actual
         // field types, but because we can generate this function we can get type erasure for free
         // since we only need to worry about byte vectors.
         let (serialize_fields_self_tokens, deserialize_fields_match_self_tokens): (Vec<_>, Vec<_>) =
-            persistent_fields
+            serde_fields
                 .into_iter()
-                .map(|PersistentField { field, key }| {
-                    (
-                        quote! {
+                .map(
+                    |PersistentField {
+                         field,
+                         key,
+                         exclude_from_presets,
+                         kind,
+                     }| {
+                        // Fields can use custom serialization functions with the same signatures as
+                        // `serde_json::to_string()` and `serde_json::from_str()`
+                        let (serialize_fn, deserialize_fn) = match kind {
+                            PersistentFieldKind::Serde { with: Some(with) } => {
+                                (quote! { #with::serialize }, quote! { #with::deserialize })
+                            }
+                            _ => (
+                                quote! { ::nih_plug::params::persist::serialize_field },
+                                quote! { ::nih_plug::params::persist::deserialize_field },
+                            ),
+                        };
+
+                        let serialize_tokens = quote! {
                             match ::nih_plug::params::persist::PersistentField::map(
                                 &self.#field,
-                                ::nih_plug::params::persist::serialize_field,
+                                #serialize_fn,
                             ) {
                                 Ok(data) => {
                                     serialized.insert(String::from(#key), data);
@@ -291,99 +585,41 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
                                     )
                                 }
                             };
-                        },
-                        quote! {
-                            #key => {
-                                match ::nih_plug::params::persist::deserialize_field(&data) {
-                                    Ok(deserialized) => {
-                                        ::nih_plug::params::persist::PersistentField::set(
-                                            &self.#field,
-                                            deserialized,
-                                        );
-                                    }
-                                    Err(err) => {
-                                        ::nih_plug::nih_debug_assert_failure!(
-                                            "Could not deserialize '{}': {}",
-                                            #key,
-                                            err
-                                        )
-                                    }
-                                };
-                            }
-                        },
-                    )
-                })
-                .unzip();
-
-        // ID prefixes are also added for nested objects
-        let (serialize_fields_nested_tokens, deserialize_fields_nested_tokens): (Vec<_>, Vec<_>) =
-            params
-                .iter()
-                .filter_map(|p| match p {
-                    Param::Single { .. } => None,
-                    Param::Nested(nested) => Some(nested),
-                })
-                .map(|nested| match nested {
-                    NestedParams::Inline { field, .. } => (
-                        quote! { serialized.extend(self.#field.serialize_fields()); },
-                        quote! { self.#field.deserialize_fields(serialized); },
-                    ),
-                    NestedParams::Prefixed {
-                        field, id_prefix, ..
-                    } => (
-                        quote! {
-                            let prefixed = self
-                                .#field
-                                .serialize_fields()
-                                .into_iter()
-                                .map(|(key, value)| (format!("{}_{}", #id_prefix, key), value));
+                        };
 
-                            serialized.extend(prefixed);
-                        },
-                        quote! {
-                            let prefix = format!("{}_", #id_prefix);
-                            let matching_fields = serialized
-                                .iter()
-                                .filter_map(|(key, value)| {
-                                    let original_key = key.strip_prefix(&prefix)?;
-                                    Some((original_key.to_owned(), value.to_owned()))
-                                })
-                                .collect();
-
-                            self.#field.deserialize_fields(&matching_fields);
-                        },
-                    ),
-                    NestedParams::Array { field, .. } => (
-                        quote! {
-                            for (field_idx, field) in self.#field.iter().enumerate() {
-                                let idx = field_idx + 1;
-                                let suffixed = field
-                                    .serialize_fields()
-                                    .into_iter()
-                                    .map(|(key, value)| (format!("{}_{}", key, idx), value));
-
-                                serialized.extend(suffixed);
-                            }
-                        },
-                        quote! {
-                            for (field_idx, field) in self.#field.iter().enumerate() {
-                                let idx = field_idx + 1;
-                                let suffix = format!("_{}", idx);
-                                let matching_fields = serialized
-                                    .iter()
-                                    .filter_map(|(key, value)| {
-                                        let original_key = key.strip_suffix(&suffix)?;
-                                        Some((original_key.to_owned(), value.to_owned()))
-                                    })
-                                    .collect();
-
-                                field.deserialize_fields(&matching_fields);
-                            }
-                        },
-                    ),
-                })
+                        (
+                            exclude_from_presets_tokens(exclude_from_presets, serialize_tokens),
+                            quote! {
+                                #key => {
+                                    match #deserialize_fn(&data) {
+                                        Ok(deserialized) => {
+                                            ::nih_plug::params::persist::PersistentField::set(
+                                                &self.#field,
+                                                deserialized,
+                                            );
+                                        }
+                                        Err(err) => {
+                                            ::nih_plug::nih_debug_assert_failure!(
+                                                "Could not deserialize '{}': {}",
+                                                #key,
+                                                err
+                                            )
+                                        }
+                                    };
+                                }
+                            },
+                        )
+                    },
+                )
                 .unzip();
 
+        let (serialize_fields_nested_tokens, deserialize_fields_nested_tokens) =
+            nested_serialization_tokens(
+                &params,
+                quote! { serialize_fields },
+                quote! { deserialize_fields },
+            );
+
         let serialize_fields_tokens = quote! {
             #[allow(unused_mut)]
             let mut serialized = ::std::collections::BTreeMap::new();
@@ -412,24 +648,268 @@ pub fn derive_params(input: TokenStream) -> TokenStream {
         (serialize_fields_tokens, deserialize_fields_tokens)
     };
 
+    let blob_revisions_tokens = {
+        let self_tokens = blob_fields.iter().map(
+            |PersistentField {
+                 field,
+                 key,
+                 exclude_from_presets,
+                 ..
+             }| {
+                exclude_from_presets_tokens(
+                    *exclude_from_presets,
+                    quote! {
+                        serialized.insert(
+                            String::from(#key),
+                            ::nih_plug::params::persist::PersistentBlob::revision(&self.#field),
+                        );
+                    },
+                )
+            },
+        );
+        let (nested_tokens, _) = nested_serialization_tokens(
+            &params,
+            quote! { blob_revisions },
+            quote! { blob_revisions },
+        );
+
+        quote! {
+            #[allow(unused_mut)]
+            let mut serialized = ::std::collections::BTreeMap::new();
+            #(#self_tokens)*
+
+            #(#nested_tokens);*
+
+            serialized
+        }
+    };
+
+    let (serialize_blobs_tokens, deserialize_blobs_tokens) = {
+        // Blobs are written directly to byte vectors instead of being converted to strings first
+        let (serialize_blobs_self_tokens, deserialize_blobs_match_self_tokens): (Vec<_>, Vec<_>) =
+            blob_fields
+                .into_iter()
+                .map(|PersistentField { field, key, exclude_from_presets, .. }| {
+                    let serialize_tokens = quote! {
+                        let mut data: Vec<u8> = Vec::new();
+                        match ::nih_plug::params::persist::PersistentBlob::write_blob(
+                            &self.#field,
+                            &mut data,
+                        ) {
+                            Ok(()) => {
+                                serialized.insert(String::from(#key), data);
+                            }
+                            Err(err) => {
+                                ::nih_plug::nih_debug_assert_failure!(
+                                    "Could not serialize '{}': {}",
+                                    #key,
+                                    err
+                                )
+                            }
+                        };
+                    };
+
+                    (
+                        exclude_from_presets_tokens(
+                            exclude_from_presets,
+                            quote! { { #serialize_tokens } },
+                        ),
+                        quote! {
+                            #key => {
+                                if let Err(err) = ::nih_plug::params::persist::PersistentBlob::read_blob(
+                                    &self.#field,
+                                    &mut data.as_slice(),
+                                ) {
+                                    ::nih_plug::nih_debug_assert_failure!(
+                                        "Could not deserialize '{}': {}",
+                                        #key,
+                                        err
+                                    )
+                                }
+                            }
+                        },
+                    )
+                })
+                .unzip();
+
+        let (serialize_blobs_nested_tokens, deserialize_blobs_nested_tokens) =
+            nested_serialization_tokens(
+                &params,
+                quote! { serialize_blobs },
+                quote! { deserialize_blobs },
+            );
+
+        let serialize_blobs_tokens = quote! {
+            #[allow(unused_mut)]
+            let mut serialized = ::std::collections::BTreeMap::new();
+            #(#serialize_blobs_self_tokens);*
+
+            #(#serialize_blobs_nested_tokens);*
+
+            serialized
+        };
+
+        let deserialize_blobs_tokens = quote! {
+            for (field_name, data) in serialized {
+                match field_name.as_str() {
+                    #(#deserialize_blobs_match_self_tokens)*
+                    _ => ::nih_plug::nih_trace!("Unknown serialized blob name: {} (this may not be accurate when using nested param structs)", field_name),
+                }
+            }
+
+            #(#deserialize_blobs_nested_tokens);*
+        };
+
+        (serialize_blobs_tokens, deserialize_blobs_tokens)
+    };
+
     quote! {
         unsafe impl #impl_generics Params for #struct_name #ty_generics #where_clause {
             fn param_map(&self) -> Vec<(String, nih_plug::prelude::ParamPtr, String)> {
                 #param_map_tokens
             }
 
-            fn serialize_fields(&self) -> ::std::collections::BTreeMap<String, String> {
+            fn serialize_fields(
+                &self,
+                #[allow(unused_variables)] context: ::nih_plug::wrapper::state::StateSaveContext,
+            ) -> ::std::collections::BTreeMap<String, String> {
                 #serialize_fields_tokens
             }
 
             fn deserialize_fields(&self, serialized: &::std::collections::BTreeMap<String, String>) {
                 #deserialize_fields_tokens
             }
+
+            fn serialize_blobs(
+                &self,
+                #[allow(unused_variables)] context: ::nih_plug::wrapper::state::StateSaveContext,
+            ) -> ::std::collections::BTreeMap<String, Vec<u8>> {
+                #serialize_blobs_tokens
+            }
+
+            fn deserialize_blobs(&self, serialized: &::std::collections::BTreeMap<String, Vec<u8>>) {
+                #deserialize_blobs_tokens
+            }
+
+            fn blob_revisions(
+                &self,
+                #[allow(unused_variables)] context: ::nih_plug::wrapper::state::StateSaveContext,
+            ) -> ::std::collections::BTreeMap<String, Option<u64>> {
+                #blob_revisions_tokens
+            }
+
+            fn fields_loaded(&self) {
+                #fields_loaded_tokens
+            }
+
+            fn run_load_callbacks(&self) {
+                #run_load_callbacks_tokens
+            }
+
+            fn param_links(
+                &self,
+            ) -> Vec<(nih_plug::prelude::ParamPtr, String, ::nih_plug::params::ParamLinkMode)> {
+                #param_links_tokens
+            }
         }
     }
     .into()
 }
 
+/// Only run `serialize_tokens` when the state is not being saved as a preset if
+/// `exclude_from_presets` is set.
+fn exclude_from_presets_tokens(
+    exclude_from_presets: bool,
+    serialize_tokens: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    if exclude_from_presets {
+        quote! {
+            if context != ::nih_plug::wrapper::state::StateSaveContext::Preset {
+                #serialize_tokens
+            }
+        }
+    } else {
+        serialize_tokens
+    }
+}
+
+/// Generate the code for calling `serialize_method` and `deserialize_method` on nested parameter
+/// structs. These are either `serialize_fields()` and `deserialize_fields()`, or
+/// `serialize_blobs()` and `deserialize_blobs()`. ID prefixes and array suffixes are added to the
+/// nested structs' keys in the same way as for parameter IDs.
+fn nested_serialization_tokens(
+    params: &[Param],
+    serialize_method: proc_macro2::TokenStream,
+    deserialize_method: proc_macro2::TokenStream,
+) -> (Vec<proc_macro2::TokenStream>, Vec<proc_macro2::TokenStream>) {
+    params
+        .iter()
+        .filter_map(|p| match p {
+            Param::Single { .. } => None,
+            Param::Nested(nested) => Some(nested),
+        })
+        .map(|nested| match nested {
+            NestedParams::Inline { field, .. } => (
+                quote! { serialized.extend(self.#field.#serialize_method(context)); },
+                quote! { self.#field.#deserialize_method(serialized); },
+            ),
+            NestedParams::Prefixed {
+                field, id_prefix, ..
+            } => (
+                quote! {
+                    let prefixed = self
+                        .#field
+                        .#serialize_method(context)
+                        .into_iter()
+                        .map(|(key, value)| (format!("{}_{}", #id_prefix, key), value));
+
+                    serialized.extend(prefixed);
+                },
+                quote! {
+                    let prefix = format!("{}_", #id_prefix);
+                    let matching_fields = serialized
+                        .iter()
+                        .filter_map(|(key, value)| {
+                            let original_key = key.strip_prefix(&prefix)?;
+                            Some((original_key.to_owned(), value.to_owned()))
+                        })
+                        .collect();
+
+                    self.#field.#deserialize_method(&matching_fields);
+                },
+            ),
+            NestedParams::Array { field, .. } => (
+                quote! {
+                    for (field_idx, field) in self.#field.iter().enumerate() {
+                        let idx = field_idx + 1;
+                        let suffixed = field
+                            .#serialize_method(context)
+                            .into_iter()
+                            .map(|(key, value)| (format!("{}_{}", key, idx), value));
+
+                        serialized.extend(suffixed);
+                    }
+                },
+                quote! {
+                    for (field_idx, field) in self.#field.iter().enumerate() {
+                        let idx = field_idx + 1;
+                        let suffix = format!("_{}", idx);
+                        let matching_fields = serialized
+                            .iter()
+                            .filter_map(|(key, value)| {
+                                let original_key = key.strip_suffix(&suffix)?;
+                                Some((original_key.to_owned(), value.to_owned()))
+                            })
+                            .collect();
+
+                        field.#deserialize_method(&matching_fields);
+                    }
+                },
+            ),
+        })
+        .unzip()
+}
+
 /// A parameter defined on this struct using the `#[id = "..."]` attribute, or another object that
 /// also implements `Params` tagged with one of the variations on the `#[nested]` attribute.
 #[derive(Debug)]
@@ -440,6 +920,8 @@ enum Param {
         field: syn::Ident,
         /// The parameter's unique ID.
         id: syn::LitStr,
+        /// The parameter's link group, if it has a `#[linked(...)]` attribute.
+        link: Option<ParamLink>,
     },
     /// Another struct also implementing `Params`. This object's parameters are inlined in the
     /// parameter list.
@@ -451,12 +933,46 @@ impl Param {
     /// parameter map.
     fn param_map_tokens(&self) -> proc_macro2::TokenStream {
         match self {
-            Param::Single { field, id } => {
+            Param::Single { field, id, .. } => {
                 quote! { [(String::from(#id), self.#field.as_ptr(), String::new())] }
             }
             Param::Nested(params) => params.param_map_tokens(),
         }
     }
+
+    /// Generate the tokens needed for a linked field (or nested parameter struct) to add its links
+    /// to the `param_links` vector.
+    fn param_links_tokens(&self) -> proc_macro2::TokenStream {
+        match self {
+            Param::Single {
+                field,
+                link: Some(ParamLink { group, mode }),
+                ..
+            } => quote! {
+                param_links.push((self.#field.as_ptr(), String::from(#group), #mode));
+            },
+            Param::Single { link: None, .. } => quote! {},
+            Param::Nested(
+                NestedParams::Inline { field, .. } | NestedParams::Prefixed { field, .. },
+            ) => quote! {
+                param_links.extend(self.#field.param_links());
+            },
+            Param::Nested(NestedParams::Array { field, .. }) => quote! {
+                for params in self.#field.iter() {
+                    param_links.extend(params.param_links());
+                }
+            },
+        }
+    }
+}
+
+/// The `#[linked(...)]` attribute on a parameter field.
+#[derive(Debug)]
+struct ParamLink {
+    /// The name of the link group.
+    group: syn::LitStr,
+    /// The `ParamLinkMode` variant, as tokens.
+    mode: proc_macro2::TokenStream,
 }
 
 /// A field containing data that must be stored in the plugin's state.
@@ -466,6 +982,21 @@ struct PersistentField {
     field: syn::Ident,
     /// The field's unique key.
     key: syn::LitStr,
+    /// Whether the field should be left out when the state is saved as a preset.
+    exclude_from_presets: bool,
+    /// How the field is stored in the plugin's state.
+    kind: PersistentFieldKind,
+}
+
+/// How a [`PersistentField`] is stored in the plugin's state.
+#[derive(Debug)]
+enum PersistentFieldKind {
+    /// The field is serialized to a string, using the `serialize()` and `deserialize()` functions
+    /// from the module set with `#[persist(with = "module")]`, or JSON if that attribute is not
+    /// set.
+    Serde { with: Option<syn::Path> },
+    /// The field is a `PersistentBlob` that is stored as raw binary data.
+    Blob,
 }
 
 /// A field containing another object whose parameters and persistent fields should be added to this
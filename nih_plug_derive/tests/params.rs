@@ -109,6 +109,44 @@ impl Default for NestedArrayParams {
     }
 }
 
+#[derive(Params)]
+struct ChannelParams {
+    #[id = "gain"]
+    #[linked(group = "gain", mode = "relative")]
+    pub gain: FloatParam,
+
+    #[id = "mute"]
+    pub mute: BoolParam,
+}
+
+impl Default for ChannelParams {
+    fn default() -> Self {
+        ChannelParams {
+            gain: FloatParam::new("gain", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 }),
+            mute: BoolParam::new("mute", false),
+        }
+    }
+}
+
+#[derive(Params)]
+struct LinkedParams {
+    #[linked(group = "width")]
+    #[id = "width"]
+    pub width: FloatParam,
+
+    #[nested(array, group = "Channel")]
+    pub channels: [ChannelParams; 2],
+}
+
+impl Default for LinkedParams {
+    fn default() -> Self {
+        LinkedParams {
+            width: FloatParam::new("width", 1.0, FloatRange::Linear { min: 0.0, max: 2.0 }),
+            channels: [ChannelParams::default(), ChannelParams::default()],
+        }
+    }
+}
+
 mod param_order {
     use super::*;
 
@@ -316,3 +354,42 @@ mod param_groups {
         );
     }
 }
+
+mod param_links {
+    use super::*;
+
+    #[test]
+    fn unlinked() {
+        let p = FlatParams::default();
+
+        assert!(p.param_links().is_empty());
+    }
+
+    #[test]
+    fn linked_nested_array() {
+        let p = LinkedParams::default();
+
+        // Link groups are shared between all elements of the array, and `mirror` is the default
+        let param_links: Vec<_> = p.param_links();
+        assert_eq!(
+            param_links,
+            [
+                (
+                    p.width.as_ptr(),
+                    String::from("width"),
+                    ParamLinkMode::Mirror
+                ),
+                (
+                    p.channels[0].gain.as_ptr(),
+                    String::from("gain"),
+                    ParamLinkMode::Relative
+                ),
+                (
+                    p.channels[1].gain.as_ptr(),
+                    String::from("gain"),
+                    ParamLinkMode::Relative
+                ),
+            ]
+        );
+    }
+}
@@ -15,6 +15,49 @@ struct ArrayWrapperParams {
     pub inners: [InnerParams; 3],
 }
 
+#[derive(Params, Default)]
+struct PresetExclusionParams {
+    #[persist = "plain"]
+    pub plain: Mutex<u32>,
+    #[persist(key = "recording", exclude_from_presets)]
+    pub recording: Mutex<Vec<f32>>,
+}
+
+#[derive(Params, Default)]
+struct CustomPersistParams {
+    #[persist(key = "hex", with = "hex_format")]
+    pub hex: Mutex<u32>,
+    #[persist(key = "wavetable", blob)]
+    pub wavetable: Mutex<Vec<f32>>,
+    #[persist(key = "recording", blob, exclude_from_presets)]
+    pub recording: Mutex<Vec<u8>>,
+}
+
+#[derive(Params, Default)]
+struct NotifierParams {
+    #[persist_notifier]
+    pub loaded: nih_plug::params::persist::PersistNotifier,
+    #[nested(array)]
+    pub inners: [NestedNotifierParams; 2],
+}
+
+#[derive(Params, Default)]
+struct NestedNotifierParams {
+    #[persist_notifier]
+    pub loaded: nih_plug::params::persist::PersistNotifier,
+}
+
+/// Stores a number as a hexadecimal string instead of as JSON.
+mod hex_format {
+    pub fn serialize(value: &u32) -> Result<String, std::convert::Infallible> {
+        Ok(format!("{value:x}"))
+    }
+
+    pub fn deserialize(data: &str) -> Result<u32, std::num::ParseIntError> {
+        u32::from_str_radix(data, 16)
+    }
+}
+
 #[derive(Default)]
 struct InnerParams {
     /// The value `deserialize()` has been called with so we can check that the prefix has been
@@ -27,7 +70,7 @@ unsafe impl Params for InnerParams {
         Vec::new()
     }
 
-    fn serialize_fields(&self) -> BTreeMap<String, String> {
+    fn serialize_fields(&self, _context: StateSaveContext) -> BTreeMap<String, String> {
         // When nested in another struct, the ID prefix will be added to `bar`
         let mut data = BTreeMap::new();
         data.insert(String::from("bar"), String::from("baz"));
@@ -50,7 +93,7 @@ mod persist {
             let params = WrapperParams::default();
 
             // This should have had a prefix added to the serialized value
-            let serialized = params.serialize_fields();
+            let serialized = params.serialize_fields(StateSaveContext::Project);
             assert_eq!(serialized.len(), 1);
             assert_eq!(serialized["foo_bar"], "baz");
         }
@@ -107,7 +150,7 @@ mod persist {
         fn serialize() {
             let params = ArrayWrapperParams::default();
 
-            let serialized = params.serialize_fields();
+            let serialized = params.serialize_fields(StateSaveContext::Project);
             assert_eq!(serialized.len(), 3);
             assert_eq!(serialized["bar_1"], "baz");
             assert_eq!(serialized["bar_2"], "baz");
@@ -135,4 +178,96 @@ mod persist {
             }
         }
     }
+
+    mod exclude_from_presets {
+        use super::super::*;
+
+        #[test]
+        fn serialize_project() {
+            let params = PresetExclusionParams::default();
+
+            let serialized = params.serialize_fields(StateSaveContext::Project);
+            assert_eq!(serialized.len(), 2);
+            assert!(serialized.contains_key("plain"));
+            assert!(serialized.contains_key("recording"));
+        }
+
+        #[test]
+        fn serialize_preset() {
+            let params = PresetExclusionParams::default();
+
+            // The recording should only be stored in the project
+            let serialized = params.serialize_fields(StateSaveContext::Preset);
+            assert_eq!(serialized.len(), 1);
+            assert!(serialized.contains_key("plain"));
+        }
+    }
+
+    mod custom {
+        use super::super::*;
+
+        #[test]
+        fn with() {
+            let params = CustomPersistParams::default();
+            *params.hex.lock().unwrap() = 255;
+
+            let serialized = params.serialize_fields(StateSaveContext::Project);
+            assert_eq!(serialized.len(), 1);
+            assert_eq!(serialized["hex"], "ff");
+
+            let restored = CustomPersistParams::default();
+            restored.deserialize_fields(&serialized);
+            assert_eq!(*restored.hex.lock().unwrap(), 255);
+        }
+
+        #[test]
+        fn blobs() {
+            let params = CustomPersistParams::default();
+            *params.wavetable.lock().unwrap() = vec![0.0, 0.5, -1.0];
+            *params.recording.lock().unwrap() = vec![1, 2, 3];
+
+            let serialized = params.serialize_blobs(StateSaveContext::Project);
+            assert_eq!(serialized.len(), 2);
+            assert_eq!(serialized["wavetable"].len(), 12);
+            assert_eq!(serialized["recording"], [1, 2, 3]);
+            assert_eq!(
+                params.serialize_blobs(StateSaveContext::Preset).len(),
+                1,
+                "The recording should only be stored in the project"
+            );
+
+            let restored = CustomPersistParams::default();
+            restored.deserialize_blobs(&serialized);
+            assert_eq!(*restored.wavetable.lock().unwrap(), [0.0, 0.5, -1.0]);
+            assert_eq!(*restored.recording.lock().unwrap(), [1, 2, 3]);
+        }
+    }
+
+    mod notifier {
+        use super::super::*;
+
+        #[test]
+        fn fields_loaded() {
+            let params = NotifierParams::default();
+            let (sender, receiver) = std::sync::mpsc::channel();
+            let sender = Mutex::new(sender);
+            params
+                .loaded
+                .on_load(move |generation| sender.lock().unwrap().send(generation).unwrap());
+            assert_eq!(params.loaded.generation(), 0);
+
+            params.fields_loaded();
+            assert_eq!(params.loaded.generation(), 1);
+            for inner in &params.inners {
+                assert_eq!(inner.loaded.generation(), 1);
+            }
+
+            // The callbacks only run when the wrapper's background thread asks them to, and only
+            // once per generation
+            assert!(receiver.try_recv().is_err());
+            params.run_load_callbacks();
+            params.run_load_callbacks();
+            assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [1]);
+        }
+    }
 }
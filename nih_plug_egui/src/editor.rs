@@ -1,18 +1,28 @@
 //! An [`Editor`] implementation for egui.
 
+#[cfg(not(target_arch = "wasm32"))]
 use baseview::gl::GlConfig;
-use baseview::{Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
+#[cfg(not(target_arch = "wasm32"))]
+use baseview::{PhySize, Size, WindowHandle, WindowOpenOptions, WindowScalePolicy};
 use crossbeam::atomic::AtomicCell;
-use egui_baseview::egui::Context;
+#[cfg(not(target_arch = "wasm32"))]
 use egui_baseview::EguiWindow;
-use nih_plug::prelude::{Editor, GuiContext, ParamSetter, ParentWindowHandle};
+use nih_plug::prelude::{
+    Editor, EditorSizeConstraints, GuiContext, ParamSetter, ParentWindowHandle,
+};
 use parking_lot::RwLock;
+#[cfg(not(target_arch = "wasm32"))]
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
-use std::sync::atomic::Ordering;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
+use crate::egui::Context;
 use crate::EguiState;
 
+#[cfg(target_arch = "wasm32")]
+mod web;
+
 /// An [`Editor`] implementation that calls an egui draw loop.
 pub(crate) struct EguiEditor<T> {
     pub(crate) egui_state: Arc<EguiState>,
@@ -31,8 +41,10 @@ pub(crate) struct EguiEditor<T> {
 
 /// This version of `baseview` uses a different version of `raw_window_handle than NIH-plug, so we
 /// need to adapt it ourselves.
+#[cfg(not(target_arch = "wasm32"))]
 struct ParentWindowHandleAdapter(nih_plug::editor::ParentWindowHandle);
 
+#[cfg(not(target_arch = "wasm32"))]
 unsafe impl HasRawWindowHandle for ParentWindowHandleAdapter {
     fn raw_window_handle(&self) -> RawWindowHandle {
         match self.0 {
@@ -51,6 +63,11 @@ unsafe impl HasRawWindowHandle for ParentWindowHandleAdapter {
                 handle.hwnd = hwnd;
                 RawWindowHandle::Win32(handle)
             }
+            ParentWindowHandle::WebCanvas(id) => {
+                let mut handle = raw_window_handle::WebWindowHandle::empty();
+                handle.id = id;
+                RawWindowHandle::Web(handle)
+            }
         }
     }
 }
@@ -59,6 +76,17 @@ impl<T> Editor for EguiEditor<T>
 where
     T: 'static + Send + Sync,
 {
+    #[cfg(target_arch = "wasm32")]
+    fn spawn(
+        &self,
+        parent: ParentWindowHandle,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn std::any::Any + Send> {
+        // In the browser the editor draws to an HTML canvas using WebGL instead of opening a window
+        web::spawn(self, parent, context)
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
     fn spawn(
         &self,
         parent: ParentWindowHandle,
@@ -67,9 +95,11 @@ where
         let build = self.build.clone();
         let update = self.update.clone();
         let state = self.user_state.clone();
+        let egui_state = self.egui_state.clone();
 
         let (unscaled_width, unscaled_height) = self.egui_state.size();
         let scaling_factor = self.scaling_factor.load();
+        let has_keyboard_focus = AtomicBool::new(false);
         let window = EguiWindow::open_parented(
             &ParentWindowHandleAdapter(parent),
             WindowOpenOptions {
@@ -82,6 +112,10 @@ where
                     .map(|factor| WindowScalePolicy::ScaleFactor(factor as f64))
                     .unwrap_or(WindowScalePolicy::SystemScaleFactor),
 
+                // FIXME: There's no software rendering fallback if creating this OpenGL context
+                //        fails, e.g. on headless Linux machines, VMs, or over remote desktop.
+                //        egui-baseview only ships an OpenGL renderer, so a CPU/softbuffer based
+                //        renderer would need to be added there first.
                 #[cfg(feature = "opengl")]
                 gl_config: Some(GlConfig {
                     version: (3, 2),
@@ -97,18 +131,56 @@ where
                     vsync: true,
                     ..Default::default()
                 }),
+                // The wgpu renderer creates its own surface from the window handle
+                #[cfg(not(feature = "opengl"))]
+                gl_config: None,
             },
             state,
             move |egui_ctx, _queue, state| build(egui_ctx, &mut state.write()),
-            move |egui_ctx, _queue, state| {
+            move |egui_ctx, queue, state| {
                 let setter = ParamSetter::new(context.as_ref());
 
+                // Resizes requested from within the GUI first need to be approved by the host. If
+                // the host rejects the new size, then the old size is restored.
+                if let Some(new_size) = egui_state.requested_size.take() {
+                    let old_size = egui_state.size.swap(new_size);
+                    if new_size != old_size {
+                        if context.request_resize() {
+                            egui_state.pending_window_size.store(Some(new_size));
+                        } else {
+                            egui_state.size.store(old_size);
+                        }
+                    }
+                }
+
+                // This is set both for the resizes from above and for resizes initiated by the host
+                if let Some((width, height)) = egui_state.pending_window_size.take() {
+                    let scaling_factor = scaling_factor.unwrap_or(1.0);
+                    queue.resize(PhySize::new(
+                        (width as f32 * scaling_factor).round() as u32,
+                        (height as f32 * scaling_factor).round() as u32,
+                    ));
+                }
+
                 // For now, just always redraw. Most plugin GUIs have meters, and those almost always
                 // need a redraw. Later we can try to be a bit more sophisticated about this. Without
                 // this we would also have a blank GUI when it gets first opened because most DAWs open
                 // their GUI while the window is still unmapped.
                 egui_ctx.request_repaint();
                 (update)(egui_ctx, &setter, &mut state.write());
+
+                // Let the host know when a text field is being edited so typing doesn't trigger
+                // the host's shortcuts
+                let wants_keyboard_input = egui_ctx.wants_keyboard_input();
+                if has_keyboard_focus.swap(wants_keyboard_input, Ordering::Relaxed)
+                    != wants_keyboard_input
+                {
+                    if wants_keyboard_input {
+                        context.request_keyboard_focus();
+                    } else {
+                        context.release_keyboard_focus();
+                    }
+                }
             },
         );
 
@@ -123,6 +195,25 @@ where
         self.egui_state.size()
     }
 
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        self.egui_state.size_constraints()
+    }
+
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        if self.egui_state.size_constraints().is_none() {
+            return false;
+        }
+
+        self.egui_state.size.store((width, height));
+        if self.egui_state.is_open() {
+            self.egui_state
+                .pending_window_size
+                .store(Some((width, height)));
+        }
+
+        true
+    }
+
     fn set_scale_factor(&self, factor: f32) -> bool {
         // If the editor is currently open then the host must not change the current HiDPI scale as
         // we don't have a way to handle that. Ableton Live does this.
@@ -134,6 +225,15 @@ where
         true
     }
 
+    fn ui_scale(&self) -> Option<f32> {
+        self.egui_state.ui_scale()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.egui_state.ui_scale.store(scale);
+        true
+    }
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
         // As mentioned above, for now we'll always force a redraw to allow meter widgets to work
         // correctly. In the future we can use an `Arc<AtomicBool>` and only force a redraw when
@@ -148,6 +248,7 @@ where
 }
 
 /// The window handle used for [`EguiEditor`].
+#[cfg(not(target_arch = "wasm32"))]
 struct EguiEditorHandle {
     egui_state: Arc<EguiState>,
     window: WindowHandle,
@@ -155,8 +256,10 @@ struct EguiEditorHandle {
 
 /// The window handle enum stored within 'WindowHandle' contains raw pointers. Is there a way around
 /// having this requirement?
+#[cfg(not(target_arch = "wasm32"))]
 unsafe impl Send for EguiEditorHandle {}
 
+#[cfg(not(target_arch = "wasm32"))]
 impl Drop for EguiEditorHandle {
     fn drop(&mut self) {
         self.egui_state.open.store(false, Ordering::Release);
@@ -0,0 +1,434 @@
+//! A WebGL backend for the egui editor, used when the plugin is exported as a Web Audio Module.
+//! Instead of opening a window, the editor draws to the HTML canvas passed by the wrapper.
+
+use egui::{Event, Modifiers, PointerButton, Pos2, RawInput, Rect, ViewportId};
+use egui_glow::Painter;
+use nih_plug::prelude::{nih_error, GuiContext, ParamSetter, ParentWindowHandle};
+use std::any::Any;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::JsCast;
+use web_sys::{HtmlCanvasElement, KeyboardEvent, MouseEvent, WebGl2RenderingContext, WheelEvent};
+
+use super::EguiEditor;
+use crate::EguiState;
+
+/// The number of pixels scrolled for every line when the browser reports scroll deltas in lines.
+const PIXELS_PER_LINE: f32 = 16.0;
+
+/// Open the editor in the canvas with a `data-raw-handle` attribute matching the parent handle's
+/// ID. Returns an empty handle if the canvas could not be found or if it does not support WebGL 2.
+pub(crate) fn spawn<T>(
+    editor: &EguiEditor<T>,
+    parent: ParentWindowHandle,
+    context: Arc<dyn GuiContext>,
+) -> Box<dyn Any + Send>
+where
+    T: 'static + Send + Sync,
+{
+    let ParentWindowHandle::WebCanvas(canvas_id) = parent else {
+        nih_error!("The egui editor can only be embedded in an HTML canvas in the browser");
+        return Box::new(());
+    };
+    let Some((canvas, gl)) = find_canvas(canvas_id) else {
+        nih_error!("Could not create a WebGL 2 context for canvas {canvas_id}");
+        return Box::new(());
+    };
+    let painter = match Painter::new(gl.clone(), "", None, true) {
+        Ok(painter) => painter,
+        Err(err) => {
+            nih_error!("Could not create the egui painter: {err}");
+            return Box::new(());
+        }
+    };
+
+    let scaling_factor = editor.scaling_factor.load().unwrap_or(1.0);
+    let egui_ctx = egui::Context::default();
+    (editor.build)(&egui_ctx, &mut editor.user_state.write());
+
+    let runner = Rc::new(RefCell::new(WebRunner {
+        egui_state: editor.egui_state.clone(),
+        user_state: editor.user_state.clone(),
+        update: editor.update.clone(),
+        context,
+
+        canvas: canvas.clone(),
+        gl,
+        painter,
+        egui_ctx,
+        scaling_factor,
+
+        events: Vec::new(),
+        modifiers: Modifiers::default(),
+    }));
+
+    // The canvas needs to be focusable to receive keyboard events
+    let _ = canvas.set_attribute("tabindex", "0");
+    let (width, height) = editor.egui_state.size();
+    resize_canvas(&canvas, width, height, scaling_factor);
+
+    let listeners = register_listeners(&canvas, &runner);
+    let animation_frame = AnimationFrame::start(runner.clone());
+
+    editor.egui_state.open.store(true, Ordering::Release);
+    Box::new(EguiWebHandle {
+        egui_state: editor.egui_state.clone(),
+        canvas,
+        runner,
+        listeners,
+        animation_frame,
+    })
+}
+
+/// Draws the editor and keeps track of the input events received since the last frame.
+struct WebRunner<T> {
+    egui_state: Arc<EguiState>,
+    user_state: Arc<parking_lot::RwLock<T>>,
+    update: Arc<dyn Fn(&egui::Context, &ParamSetter, &mut T) + 'static + Send + Sync>,
+    context: Arc<dyn GuiContext>,
+
+    canvas: HtmlCanvasElement,
+    gl: Arc<glow::Context>,
+    painter: Painter,
+    egui_ctx: egui::Context,
+    /// The browser's device pixel ratio, or the user's UI scale if they have chosen one.
+    scaling_factor: f32,
+
+    /// The input events received since the last frame.
+    events: Vec<Event>,
+    /// The modifier keys held down during the last input event.
+    modifiers: Modifiers,
+}
+
+/// Type erased so the event listeners and the animation frame callback don't need to know about
+/// the user state type.
+trait Runner {
+    fn frame(&mut self, time_ms: f64);
+    fn push_event(&mut self, event: Event, modifiers: Modifiers);
+    fn destroy(&mut self);
+}
+
+impl<T> Runner for WebRunner<T>
+where
+    T: 'static + Send + Sync,
+{
+    fn frame(&mut self, time_ms: f64) {
+        let setter = ParamSetter::new(self.context.as_ref());
+
+        // Resizes requested from within the GUI first need to be approved by the host. If the host
+        // rejects the new size, then the old size is restored.
+        if let Some(new_size) = self.egui_state.requested_size.take() {
+            let old_size = self.egui_state.size.swap(new_size);
+            if new_size != old_size {
+                if self.context.request_resize() {
+                    self.egui_state.pending_window_size.store(Some(new_size));
+                } else {
+                    self.egui_state.size.store(old_size);
+                }
+            }
+        }
+
+        // This is set both for the resizes from above and for resizes initiated by the host
+        if let Some((width, height)) = self.egui_state.pending_window_size.take() {
+            resize_canvas(&self.canvas, width, height, self.scaling_factor);
+        }
+
+        let (width, height) = self.egui_state.size();
+        let mut raw_input = RawInput {
+            screen_rect: Some(Rect::from_min_size(
+                Pos2::ZERO,
+                egui::vec2(width as f32, height as f32),
+            )),
+            time: Some(time_ms / 1000.0),
+            modifiers: self.modifiers,
+            events: std::mem::take(&mut self.events),
+            ..Default::default()
+        };
+        raw_input
+            .viewports
+            .entry(ViewportId::ROOT)
+            .or_default()
+            .native_pixels_per_point = Some(self.scaling_factor);
+
+        let full_output = self.egui_ctx.run(raw_input, |egui_ctx| {
+            (self.update)(egui_ctx, &setter, &mut self.user_state.write())
+        });
+        let clipped_primitives = self
+            .egui_ctx
+            .tessellate(full_output.shapes, full_output.pixels_per_point);
+
+        let screen_size_px = [self.canvas.width(), self.canvas.height()];
+        egui_glow::painter::clear(&self.gl, screen_size_px, [0.0, 0.0, 0.0, 1.0]);
+        self.painter.paint_and_update_textures(
+            screen_size_px,
+            full_output.pixels_per_point,
+            &clipped_primitives,
+            &full_output.textures_delta,
+        );
+    }
+
+    fn push_event(&mut self, event: Event, modifiers: Modifiers) {
+        self.events.push(event);
+        self.modifiers = modifiers;
+    }
+
+    fn destroy(&mut self) {
+        self.painter.destroy();
+    }
+}
+
+/// Keeps requesting animation frames from the browser until it is stopped.
+struct AnimationFrame {
+    /// The callback passed to `requestAnimationFrame()`. The callback holds a reference to this
+    /// cell to be able to request the next frame, so this needs to be cleared to break the cycle.
+    callback: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>>,
+    /// The ID of the last requested animation frame, used to cancel it.
+    request_id: Rc<Cell<i32>>,
+}
+
+impl AnimationFrame {
+    fn start(runner: Rc<RefCell<dyn Runner>>) -> Self {
+        let callback: Rc<RefCell<Option<Closure<dyn FnMut(f64)>>>> = Rc::new(RefCell::new(None));
+        let request_id = Rc::new(Cell::new(0));
+
+        *callback.borrow_mut() = Some(Closure::new({
+            let callback = callback.clone();
+            let request_id = request_id.clone();
+
+            move |time_ms: f64| {
+                runner.borrow_mut().frame(time_ms);
+
+                if let Some(callback) = callback.borrow().as_ref() {
+                    request_id.set(request_animation_frame(callback));
+                }
+            }
+        }));
+        if let Some(callback) = callback.borrow().as_ref() {
+            request_id.set(request_animation_frame(callback));
+        }
+
+        Self {
+            callback,
+            request_id,
+        }
+    }
+
+    fn stop(&self) {
+        if let Some(window) = web_sys::window() {
+            let _ = window.cancel_animation_frame(self.request_id.get());
+        }
+        self.callback.borrow_mut().take();
+    }
+}
+
+/// The handle for an editor drawing to an HTML canvas. Dropping the handle stops the draw loop and
+/// removes the event listeners from the canvas.
+struct EguiWebHandle {
+    egui_state: Arc<EguiState>,
+    canvas: HtmlCanvasElement,
+    runner: Rc<RefCell<dyn Runner>>,
+    listeners: Vec<(&'static str, Closure<dyn FnMut(web_sys::Event)>)>,
+    animation_frame: AnimationFrame,
+}
+
+/// WebAssembly instances only have a single thread, so the handle is never actually sent to another
+/// thread.
+unsafe impl Send for EguiWebHandle {}
+
+impl Drop for EguiWebHandle {
+    fn drop(&mut self) {
+        self.egui_state.open.store(false, Ordering::Release);
+        self.animation_frame.stop();
+        for (event_type, listener) in self.listeners.drain(..) {
+            let _ = self
+                .canvas
+                .remove_event_listener_with_callback(event_type, listener.as_ref().unchecked_ref());
+        }
+        self.runner.borrow_mut().destroy();
+    }
+}
+
+/// Find the canvas with a matching `data-raw-handle` attribute and create a WebGL 2 context for it.
+fn find_canvas(canvas_id: u32) -> Option<(HtmlCanvasElement, Arc<glow::Context>)> {
+    let document = web_sys::window()?.document()?;
+    let canvas: HtmlCanvasElement = document
+        .query_selector(&format!("canvas[data-raw-handle=\"{canvas_id}\"]"))
+        .ok()??
+        .dyn_into()
+        .ok()?;
+    let webgl2_context: WebGl2RenderingContext =
+        canvas.get_context("webgl2").ok()??.dyn_into().ok()?;
+
+    Some((
+        canvas,
+        Arc::new(glow::Context::from_webgl2_context(webgl2_context)),
+    ))
+}
+
+/// Resize the canvas to a size in logical pixels. The canvas' backing buffer uses physical pixels.
+fn resize_canvas(canvas: &HtmlCanvasElement, width: u32, height: u32, scaling_factor: f32) {
+    canvas.set_width((width as f32 * scaling_factor).round() as u32);
+    canvas.set_height((height as f32 * scaling_factor).round() as u32);
+
+    let style = canvas.style();
+    let _ = style.set_property("width", &format!("{width}px"));
+    let _ = style.set_property("height", &format!("{height}px"));
+}
+
+fn request_animation_frame(callback: &Closure<dyn FnMut(f64)>) -> i32 {
+    web_sys::window()
+        .and_then(|window| {
+            window
+                .request_animation_frame(callback.as_ref().unchecked_ref())
+                .ok()
+        })
+        .unwrap_or(0)
+}
+
+/// Add event listeners to the canvas that forward pointer and keyboard input to the runner.
+fn register_listeners<R: Runner + 'static>(
+    canvas: &HtmlCanvasElement,
+    runner: &Rc<RefCell<R>>,
+) -> Vec<(&'static str, Closure<dyn FnMut(web_sys::Event)>)> {
+    let mut listeners = Vec::new();
+    let mut listen = |event_type: &'static str,
+                      mut handler: Box<dyn FnMut(web_sys::Event, &mut R)>| {
+        let runner = runner.clone();
+        let listener = Closure::<dyn FnMut(web_sys::Event)>::new(move |event: web_sys::Event| {
+            handler(event, &mut runner.borrow_mut())
+        });
+        let _ =
+            canvas.add_event_listener_with_callback(event_type, listener.as_ref().unchecked_ref());
+        listeners.push((event_type, listener));
+    };
+
+    listen(
+        "pointermove",
+        Box::new(|event, runner| {
+            let event: MouseEvent = event.unchecked_into();
+            runner.push_event(
+                Event::PointerMoved(pointer_pos(&event)),
+                mouse_modifiers(&event),
+            );
+        }),
+    );
+    for (event_type, pressed) in [("pointerdown", true), ("pointerup", false)] {
+        let canvas = canvas.clone();
+        listen(
+            event_type,
+            Box::new(move |event, runner| {
+                let event: MouseEvent = event.unchecked_into();
+                let button = match event.button() {
+                    0 => PointerButton::Primary,
+                    1 => PointerButton::Middle,
+                    2 => PointerButton::Secondary,
+                    3 => PointerButton::Extra1,
+                    4 => PointerButton::Extra2,
+                    _ => return,
+                };
+                if pressed {
+                    let _ = canvas.focus();
+                }
+
+                let modifiers = mouse_modifiers(&event);
+                runner.push_event(
+                    Event::PointerButton {
+                        pos: pointer_pos(&event),
+                        button,
+                        pressed,
+                        modifiers,
+                    },
+                    modifiers,
+                );
+                event.prevent_default();
+            }),
+        );
+    }
+    listen(
+        "pointerleave",
+        Box::new(|event, runner| {
+            let event: MouseEvent = event.unchecked_into();
+            runner.push_event(Event::PointerGone, mouse_modifiers(&event));
+        }),
+    );
+    listen(
+        "wheel",
+        Box::new(|event, runner| {
+            let event: WheelEvent = event.unchecked_into();
+            let scale = match event.delta_mode() {
+                WheelEvent::DOM_DELTA_LINE => PIXELS_PER_LINE,
+                _ => 1.0,
+            };
+            let delta = egui::vec2(
+                -event.delta_x() as f32 * scale,
+                -event.delta_y() as f32 * scale,
+            );
+            runner.push_event(Event::Scroll(delta), mouse_modifiers(&event));
+            event.prevent_default();
+        }),
+    );
+    listen(
+        "contextmenu",
+        Box::new(|event, _runner| event.prevent_default()),
+    );
+    for (event_type, pressed) in [("keydown", true), ("keyup", false)] {
+        listen(
+            event_type,
+            Box::new(move |event, runner| {
+                let event: KeyboardEvent = event.unchecked_into();
+                let modifiers = keyboard_modifiers(&event);
+                let key_name = event.key();
+                if let Some(key) = egui::Key::from_name(&key_name) {
+                    runner.push_event(
+                        Event::Key {
+                            key,
+                            physical_key: None,
+                            pressed,
+                            repeat: event.repeat(),
+                            modifiers,
+                        },
+                        modifiers,
+                    );
+                }
+
+                // Printable characters have a single character key name
+                if pressed && key_name.chars().count() == 1 && !modifiers.ctrl && !modifiers.mac_cmd
+                {
+                    runner.push_event(Event::Text(key_name), modifiers);
+                }
+
+                // Otherwise the host's keyboard shortcuts would also trigger while typing
+                event.stop_propagation();
+            }),
+        );
+    }
+
+    listeners
+}
+
+fn pointer_pos(event: &MouseEvent) -> Pos2 {
+    Pos2::new(event.offset_x() as f32, event.offset_y() as f32)
+}
+
+fn mouse_modifiers(event: &MouseEvent) -> Modifiers {
+    Modifiers {
+        alt: event.alt_key(),
+        ctrl: event.ctrl_key(),
+        shift: event.shift_key(),
+        mac_cmd: event.meta_key(),
+        command: event.ctrl_key() || event.meta_key(),
+    }
+}
+
+fn keyboard_modifiers(event: &KeyboardEvent) -> Modifiers {
+    Modifiers {
+        alt: event.alt_key(),
+        ctrl: event.ctrl_key(),
+        shift: event.shift_key(),
+        mac_cmd: event.meta_key(),
+        command: event.ctrl_key() || event.meta_key(),
+    }
+}
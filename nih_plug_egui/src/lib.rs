@@ -8,16 +8,22 @@
 use crossbeam::atomic::AtomicCell;
 use egui::Context;
 use nih_plug::params::persist::PersistentField;
-use nih_plug::prelude::{Editor, ParamSetter};
+use nih_plug::prelude::{Editor, EditorSizeConstraints, ParamSetter};
 use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
-#[cfg(not(feature = "opengl"))]
+#[cfg(not(any(feature = "opengl", feature = "wgpu")))]
 compile_error!("There's currently no software rendering support for egui");
+#[cfg(all(feature = "opengl", feature = "wgpu"))]
+compile_error!("The 'opengl' and 'wgpu' features are mutually exclusive");
 
 /// Re-export for convenience.
+#[cfg(target_arch = "wasm32")]
+pub use egui;
+/// Re-export for convenience.
+#[cfg(not(target_arch = "wasm32"))]
 pub use egui_baseview::egui;
 
 mod editor;
@@ -32,7 +38,7 @@ pub mod widgets;
 /// persisted when restoring a plugin instance, then you can store it in a `#[persist = "key"]`
 /// field on your parameters struct.
 ///
-/// See [`EguiState::from_size()`].
+/// See [`EguiState::from_size()`] and [`EguiState::from_size_resizable()`].
 pub fn create_egui_editor<T, B, U>(
     egui_state: Arc<EguiState>,
     user_state: T,
@@ -66,6 +72,22 @@ pub struct EguiState {
     /// The window's size in logical pixels before applying `scale_factor`.
     #[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]
     size: AtomicCell<(u32, u32)>,
+    /// The constraints for resizing the window, or `None` if the window cannot be resized.
+    #[serde(skip)]
+    size_constraints: Option<EditorSizeConstraints>,
+    /// A new size in logical pixels requested from within the GUI, for instance using the
+    /// [`ResizeHandle`][widgets::ResizeHandle] widget. The editor will ask the host to resize the
+    /// window to this size during the next frame.
+    #[serde(skip)]
+    requested_size: AtomicCell<Option<(u32, u32)>>,
+    /// A new size in logical pixels set by the host. The editor's window will be resized to match
+    /// this during the next frame.
+    #[serde(skip)]
+    pending_window_size: AtomicCell<Option<(u32, u32)>>,
+    /// A UI scale chosen by the user that overrides the host's DPI scaling factor. See
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    #[serde(default, with = "nih_plug::params::persist::serialize_atomic_cell")]
+    ui_scale: AtomicCell<Option<f32>>,
     /// Whether the editor's window is currently open.
     #[serde(skip)]
     open: AtomicBool,
@@ -74,6 +96,7 @@ pub struct EguiState {
 impl<'a> PersistentField<'a, EguiState> for Arc<EguiState> {
     fn set(&self, new_value: EguiState) {
         self.size.store(new_value.size.load());
+        self.ui_scale.store(new_value.ui_scale.load());
     }
 
     fn map<F, R>(&self, f: F) -> R
@@ -90,6 +113,29 @@ impl EguiState {
     pub fn from_size(width: u32, height: u32) -> Arc<EguiState> {
         Arc::new(EguiState {
             size: AtomicCell::new((width, height)),
+            size_constraints: None,
+            requested_size: AtomicCell::new(None),
+            pending_window_size: AtomicCell::new(None),
+            ui_scale: AtomicCell::new(None),
+            open: AtomicBool::new(false),
+        })
+    }
+
+    /// The same as [`from_size()`][Self::from_size()], but the window can be resized by the user
+    /// within the specified constraints. The initial size is constrained to fit within them. Store
+    /// this state in a `#[persist = "key"]` field on your parameters struct to restore the chosen
+    /// size when the plugin's state gets loaded.
+    pub fn from_size_resizable(
+        width: u32,
+        height: u32,
+        size_constraints: EditorSizeConstraints,
+    ) -> Arc<EguiState> {
+        Arc::new(EguiState {
+            size: AtomicCell::new(size_constraints.constrain((width, height))),
+            size_constraints: Some(size_constraints),
+            requested_size: AtomicCell::new(None),
+            pending_window_size: AtomicCell::new(None),
+            ui_scale: AtomicCell::new(None),
             open: AtomicBool::new(false),
         })
     }
@@ -99,6 +145,27 @@ impl EguiState {
         self.size.load()
     }
 
+    /// Returns the constraints for resizing the GUI, or `None` if the GUI cannot be resized.
+    pub fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        self.size_constraints
+    }
+
+    /// Ask the host to resize the GUI to a new size in logical pixels. The size is constrained
+    /// using [`size_constraints()`][Self::size_constraints()] and the resize is performed during
+    /// the next frame. This does nothing if the GUI cannot be resized.
+    pub fn set_requested_size(&self, new_size: (u32, u32)) {
+        if let Some(size_constraints) = self.size_constraints {
+            self.requested_size
+                .store(Some(size_constraints.constrain(new_size)));
+        }
+    }
+
+    /// Returns the UI scale chosen by the user, if any. This is set through
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    pub fn ui_scale(&self) -> Option<f32> {
+        self.ui_scale.load()
+    }
+
     /// Whether the GUI is currently visible.
     // Called `is_open()` instead of `open()` to avoid the ambiguity.
     pub fn is_open(&self) -> bool {
@@ -5,8 +5,20 @@
 //! None of these widgets are finalized, and their sizes or looks can change at any point. Feel free
 //! to copy the widgets and modify them to your personal taste.
 
+mod ab_toggle;
 pub mod generic_ui;
+mod modulation_matrix;
+mod param_inspector;
 mod param_slider;
+mod piano_keyboard;
+mod preset_browser;
+mod resize_handle;
 pub mod util;
 
+pub use ab_toggle::AbToggle;
+pub use modulation_matrix::ModulationMatrixEditor;
+pub use param_inspector::{ParamInspector, ParamInspectorState};
 pub use param_slider::ParamSlider;
+pub use piano_keyboard::PianoKeyboard;
+pub use preset_browser::{PresetBrowser, PresetBrowserState};
+pub use resize_handle::ResizeHandle;
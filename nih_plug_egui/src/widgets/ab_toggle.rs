@@ -0,0 +1,43 @@
+use crate::egui::{Response, Ui, Widget};
+use nih_plug::prelude::{ParamSetter, StateSlot};
+
+/// A toggle for comparing two versions of the plugin's state. Clicking on the inactive slot stores
+/// the current state in the active slot and restores the other one, and the copy button copies the
+/// A slot to the B slot. See [`GuiContext::swap_ab()`][nih_plug::prelude::GuiContext::swap_ab()].
+#[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
+pub struct AbToggle<'a> {
+    setter: &'a ParamSetter<'a>,
+}
+
+impl<'a> AbToggle<'a> {
+    /// Create a new A/B toggle. The state slots are managed by the setter's
+    /// [`GuiContext`][nih_plug::prelude::GuiContext].
+    pub fn new(setter: &'a ParamSetter<'a>) -> Self {
+        Self { setter }
+    }
+}
+
+impl Widget for AbToggle<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let context = self.setter.raw_context;
+        let active_slot = context.active_state_slot();
+
+        ui.horizontal(|ui| {
+            for (slot, label) in [(StateSlot::A, "A"), (StateSlot::B, "B")] {
+                if ui.selectable_label(active_slot == slot, label).clicked() && active_slot != slot
+                {
+                    context.swap_ab();
+                }
+            }
+
+            if ui
+                .button("A → B")
+                .on_hover_text("Copy the A state to B")
+                .clicked()
+            {
+                context.copy_a_to_b();
+            }
+        })
+        .response
+    }
+}
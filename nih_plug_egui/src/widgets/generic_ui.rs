@@ -3,7 +3,7 @@
 
 use std::sync::Arc;
 
-use egui_baseview::egui::{self, TextStyle, Ui, Vec2};
+use crate::egui::{self, TextStyle, Ui, Vec2};
 use nih_plug::prelude::{Param, ParamFlags, ParamPtr, ParamSetter, Params};
 
 use super::ParamSlider;
@@ -24,6 +24,8 @@ pub trait ParamWidget {
             ParamPtr::IntParam(p) => self.add_widget(ui, &**p, setter),
             ParamPtr::BoolParam(p) => self.add_widget(ui, &**p, setter),
             ParamPtr::EnumParam(p) => self.add_widget(ui, &**p, setter),
+            ParamPtr::MacroParam(p) => self.add_widget(ui, &**p, setter),
+            ParamPtr::DynamicParam(p) => self.add_widget(ui, &**p, setter),
         }
     }
 }
@@ -0,0 +1,96 @@
+use crate::egui::{self, Response, Ui, Widget};
+use nih_plug::prelude::{ParamFlags, Params};
+use nih_plug::util::modulation::{ModulationMatrix, ModulationRouting, ModulationSource};
+
+/// An editor for a [`ModulationMatrix`]'s routings. Every routing gets a row with its source, its
+/// target, and its depth, and new routings can be added with the button below the list. The
+/// parameters from `params` that are not hidden can be used as modulation targets.
+#[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
+pub struct ModulationMatrixEditor<'a> {
+    matrix: &'a ModulationMatrix,
+    params: &'a dyn Params,
+}
+
+impl<'a> ModulationMatrixEditor<'a> {
+    /// Create a new editor for a modulation matrix that modulates `params`.
+    pub fn new(matrix: &'a ModulationMatrix, params: &'a dyn Params) -> Self {
+        Self { matrix, params }
+    }
+}
+
+impl Widget for ModulationMatrixEditor<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        // SAFETY: The parameters are borrowed from `self.params`, so they're still alive
+        let targets: Vec<(String, String)> = self
+            .params
+            .param_map()
+            .into_iter()
+            .filter(|(_, param_ptr, _)| !unsafe { param_ptr.flags() }.contains(ParamFlags::HIDDEN))
+            .map(|(id, param_ptr, _)| (id, unsafe { param_ptr.name() }.to_owned()))
+            .collect();
+        let target_name = |id: &str| {
+            targets
+                .iter()
+                .find(|(target_id, _)| target_id == id)
+                .map_or(id, |(_, name)| name.as_str())
+                .to_owned()
+        };
+
+        let old_config = self.matrix.config();
+        let mut config = old_config.as_ref().clone();
+        let mut removed_routing = None;
+
+        let response = ui
+            .vertical(|ui| {
+                egui::Grid::new("modulation_matrix")
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        for (idx, routing) in config.routings.iter_mut().enumerate() {
+                            egui::ComboBox::from_id_source(("modulation_source", idx))
+                                .selected_text(routing.source.name())
+                                .show_ui(ui, |ui| {
+                                    for source in ModulationSource::ALL {
+                                        ui.selectable_value(
+                                            &mut routing.source,
+                                            source,
+                                            source.name(),
+                                        );
+                                    }
+                                });
+                            egui::ComboBox::from_id_source(("modulation_target", idx))
+                                .selected_text(target_name(&routing.target))
+                                .show_ui(ui, |ui| {
+                                    for (id, name) in &targets {
+                                        ui.selectable_value(&mut routing.target, id.clone(), name);
+                                    }
+                                });
+                            ui.add(egui::Slider::new(&mut routing.depth, -1.0..=1.0));
+                            if ui.button("Remove").clicked() {
+                                removed_routing = Some(idx);
+                            }
+                            ui.end_row();
+                        }
+                    });
+
+                if let Some((first_target, _)) = targets.first() {
+                    if ui.button("Add routing").clicked() {
+                        config.routings.push(ModulationRouting {
+                            source: ModulationSource::Lfo(0),
+                            target: first_target.clone(),
+                            depth: 0.0,
+                        });
+                    }
+                }
+            })
+            .response;
+
+        if let Some(idx) = removed_routing {
+            config.routings.remove(idx);
+        }
+        if config != *old_config {
+            self.matrix.set_config(config);
+        }
+
+        response
+    }
+}
@@ -0,0 +1,176 @@
+//! A debug window for inspecting all of a plugin's parameters.
+
+use crate::egui::{self, Key, Modifiers, ScrollArea};
+use nih_plug::prelude::{ParamPtr, Params};
+
+/// The key combination that toggles the [`ParamInspector`] in debug builds.
+const TOGGLE_MODIFIERS: Modifiers = Modifiers {
+    shift: true,
+    command: true,
+    ..Modifiers::NONE
+};
+/// The key that toggles the [`ParamInspector`] in debug builds when combined with
+/// [`TOGGLE_MODIFIERS`].
+const TOGGLE_KEY: Key = Key::P;
+
+/// A debug window listing all of a [`Params`] object's parameters with their live values,
+/// normalized values, and smoothing state, including parameters hidden from the generic UI. This is
+/// useful when developing a custom GUI that only exposes a handful of the plugin's parameters. The
+/// parameters can be filtered by group.
+///
+/// In debug builds the window can be toggled with Control+Shift+P (Command+Shift+P on macOS). In
+/// release builds it's only shown after calling [`ParamInspectorState::set_open()`].
+pub struct ParamInspector<'a, P: Params> {
+    params: &'a P,
+    state: &'a mut ParamInspectorState,
+}
+
+/// State for a [`ParamInspector`]. This should be stored in the editor's user state.
+#[derive(Debug, Default)]
+pub struct ParamInspectorState {
+    /// Whether the inspector's window is currently shown.
+    open: bool,
+    /// Only parameters in this group are shown. `None` shows all parameters.
+    group: Option<String>,
+}
+
+impl ParamInspectorState {
+    /// Whether the inspector's window is currently shown.
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Show or hide the inspector's window.
+    pub fn set_open(&mut self, open: bool) {
+        self.open = open;
+    }
+}
+
+impl<'a, P: Params> ParamInspector<'a, P> {
+    /// Create a new inspector for a parameters object.
+    pub fn new(params: &'a P, state: &'a mut ParamInspectorState) -> Self {
+        Self { params, state }
+    }
+
+    /// Handle the toggle shortcut and draw the inspector's window if it's open. Call this at the
+    /// end of the editor's update function so the window is drawn on top of the rest of the GUI.
+    pub fn show(self, egui_ctx: &egui::Context) {
+        if cfg!(debug_assertions)
+            && egui_ctx.input_mut(|i| i.consume_key(TOGGLE_MODIFIERS, TOGGLE_KEY))
+        {
+            self.state.open = !self.state.open;
+        }
+        if !self.state.open {
+            return;
+        }
+
+        let param_map = self.params.param_map();
+        let mut groups: Vec<&str> = param_map
+            .iter()
+            .map(|(_, _, group)| group.as_str())
+            .collect();
+        groups.sort_unstable();
+        groups.dedup();
+
+        let state = self.state;
+        egui::Window::new("Parameters")
+            .open(&mut state.open)
+            .default_width(600.0)
+            .show(egui_ctx, |ui| {
+                egui::ComboBox::from_label("Group")
+                    .selected_text(match &state.group {
+                        Some(group) => group_name(group),
+                        None => "All groups",
+                    })
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut state.group, None, "All groups");
+                        for group in &groups {
+                            ui.selectable_value(
+                                &mut state.group,
+                                Some(group.to_string()),
+                                group_name(group),
+                            );
+                        }
+                    });
+                ui.separator();
+
+                ScrollArea::both()
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        egui::Grid::new("param_inspector")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("ID");
+                                ui.strong("Name");
+                                ui.strong("Value");
+                                ui.strong("Normalized");
+                                ui.strong("Modulated");
+                                ui.strong("Smoothed");
+                                ui.end_row();
+
+                                for (id, param_ptr, group) in &param_map {
+                                    if matches!(&state.group, Some(selected) if selected != group) {
+                                        continue;
+                                    }
+
+                                    // SAFETY: The parameter pointers are valid for as long as the
+                                    //         `Params` object is alive
+                                    unsafe {
+                                        ui.monospace(id);
+                                        ui.label(param_ptr.name());
+                                        ui.label(param_ptr.normalized_value_to_string(
+                                            param_ptr.unmodulated_normalized_value(),
+                                            true,
+                                        ));
+                                        ui.monospace(format!(
+                                            "{:.4}",
+                                            param_ptr.unmodulated_normalized_value()
+                                        ));
+                                        ui.monospace(format!(
+                                            "{:.4}",
+                                            param_ptr.modulated_normalized_value()
+                                        ));
+                                        ui.monospace(smoothing_status(param_ptr));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        // The values are read directly from the parameters, so they need to be redrawn continuously
+        egui_ctx.request_repaint();
+    }
+}
+
+/// The name shown for a parameter group. Parameters outside of any group have an empty group name.
+fn group_name(group: &str) -> &str {
+    if group.is_empty() {
+        "(top level)"
+    } else {
+        group
+    }
+}
+
+/// Describe the smoother's current value and the number of steps until it reaches its target. Only
+/// float and integer parameters are smoothed.
+///
+/// # Safety
+///
+/// `param_ptr` needs to point to a valid parameter.
+unsafe fn smoothing_status(param_ptr: &ParamPtr) -> String {
+    let (current, steps_left) = match param_ptr {
+        ParamPtr::FloatParam(p) => ((**p).smoothed.previous_value(), (**p).smoothed.steps_left()),
+        ParamPtr::IntParam(p) => (
+            (**p).smoothed.previous_value() as f32,
+            (**p).smoothed.steps_left(),
+        ),
+        _ => return String::from("-"),
+    };
+
+    if steps_left > 0 {
+        format!("{current:.4} ({steps_left} steps left)")
+    } else {
+        format!("{current:.4}")
+    }
+}
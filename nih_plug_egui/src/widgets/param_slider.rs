@@ -1,11 +1,11 @@
 use std::sync::Arc;
 
-use egui_baseview::egui::{
+use crate::egui::{
     self, emath, vec2, Key, Response, Sense, Stroke, TextEdit, TextStyle, Ui, Vec2, Widget,
-    WidgetText,
+    WidgetInfo, WidgetText, WidgetType,
 };
 use lazy_static::lazy_static;
-use nih_plug::prelude::{Param, ParamSetter};
+use nih_plug::prelude::{FloatParam, Param, ParamSetter};
 use parking_lot::Mutex;
 
 use super::util;
@@ -13,6 +13,8 @@ use super::util;
 /// When shift+dragging a parameter, one pixel dragged corresponds to this much change in the
 /// noramlized parameter.
 const GRANULAR_DRAG_MULTIPLIER: f32 = 0.0015;
+/// The frequency octave ticks are centered around, see [`ParamSlider::with_octave_ticks()`].
+const OCTAVE_TICKS_REFERENCE_HZ: f32 = 1000.0;
 
 lazy_static! {
     static ref DRAG_NORMALIZED_START_VALUE_MEMORY_ID: egui::Id = egui::Id::new((file!(), 0));
@@ -22,13 +24,17 @@ lazy_static! {
 
 /// A slider widget similar to [`egui::widgets::Slider`] that knows about NIH-plug parameters ranges
 /// and can get values for it. The slider supports double click and control click to reset,
-/// shift+drag for granular dragging, text value entry by clicking on the value text.
+/// shift+drag for granular dragging, text value entry by clicking on the value text or by right
+/// clicking on the slider, and alt+drag for snapping to the slider's [tick
+/// marks][Self::with_ticks()]. Control+right click shows the host's context menu for the parameter.
+/// The slider can be focused using the Tab key, after which the arrow keys step through the
+/// parameter's values. Control+C and Control+V copy the parameter's value to and from the clipboard
+/// while the slider is hovered or focused.
 ///
 /// TODO: Vertical orientation
 /// TODO: Check below for more input methods that should be added
 /// TODO: Decouple the logic from the drawing so we can also do things like nobs without having to
 ///       repeat everything
-/// TODO: Add WidgetInfo annotations for accessibility
 #[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
 pub struct ParamSlider<'a, P: Param> {
     param: &'a P,
@@ -36,6 +42,8 @@ pub struct ParamSlider<'a, P: Param> {
 
     draw_value: bool,
     slider_width: Option<f32>,
+    /// The normalized values tick marks are drawn at, sorted in ascending order.
+    ticks: Vec<f32>,
 
     /// Will be set in the `ui()` function so we can request keyboard input focus on Alt+click.
     keyboard_focus_id: Option<egui::Id>,
@@ -51,6 +59,7 @@ impl<'a, P: Param> ParamSlider<'a, P> {
 
             draw_value: true,
             slider_width: None,
+            ticks: Vec::new(),
 
             keyboard_focus_id: None,
         }
@@ -68,6 +77,21 @@ impl<'a, P: Param> ParamSlider<'a, P> {
         self
     }
 
+    /// Draw tick marks at the given plain values. Values outside of the parameter's range end up at
+    /// the edges of the slider. Holding down Alt while dragging snaps the slider to the nearest tick. For instance,
+    /// `.with_ticks([util::db_to_gain(0.0)])` marks unity gain on a gain parameter.
+    pub fn with_ticks(mut self, values: impl IntoIterator<Item = P::Plain>) -> Self {
+        let param = self.param;
+        self.ticks.extend(
+            values
+                .into_iter()
+                .map(|value| param.preview_normalized(value)),
+        );
+        self.ticks.sort_by(f32::total_cmp);
+        self.ticks.dedup();
+        self
+    }
+
     fn plain_value(&self) -> P::Plain {
         self.param.modulated_plain_value()
     }
@@ -113,10 +137,26 @@ impl<'a, P: Param> ParamSlider<'a, P> {
         }
     }
 
+    /// The tick closest to a normalized value, if the slider has any ticks.
+    fn nearest_tick(&self, normalized_value: f32) -> Option<f32> {
+        self.ticks.iter().copied().min_by(|a, b| {
+            (a - normalized_value)
+                .abs()
+                .total_cmp(&(b - normalized_value).abs())
+        })
+    }
+
     /// Begin and end drag still need to be called when using this. Returns `false` if the string
-    /// could no tbe parsed.
+    /// could no tbe parsed. If the parameter's parser doesn't accept the string, then it's parsed
+    /// again with the parameter's unit removed so `-6dB` and `-6 db` are also accepted for a
+    /// parameter with a ` dB` unit.
     fn set_from_string(&self, string: &str) -> bool {
-        match self.param.string_to_normalized_value(string) {
+        let normalized_value = self.param.string_to_normalized_value(string).or_else(|| {
+            strip_unit(string, self.param.unit())
+                .and_then(|value| self.param.string_to_normalized_value(value))
+        });
+
+        match normalized_value {
             Some(normalized_value) => {
                 self.set_normalized_value(normalized_value);
                 true
@@ -131,6 +171,22 @@ impl<'a, P: Param> ParamSlider<'a, P> {
             .set_parameter(self.param, self.param.default_plain_value());
     }
 
+    /// Move the parameter one step up or down as a single gesture.
+    fn step_value(&self, increment: bool, use_finer_steps: bool) {
+        let current_value = self.param.unmodulated_normalized_value();
+        let new_value = if increment {
+            self.param
+                .next_normalized_step(current_value, use_finer_steps)
+        } else {
+            self.param
+                .previous_normalized_step(current_value, use_finer_steps)
+        };
+
+        self.begin_drag();
+        self.set_normalized_value(new_value);
+        self.end_drag();
+    }
+
     fn granular_drag(&self, ui: &Ui, drag_delta: Vec2) {
         // Remember the intial position when we started with the granular drag. This value gets
         // reset whenever we have a normal itneraction with the slider.
@@ -179,6 +235,20 @@ impl<'a, P: Param> ParamSlider<'a, P> {
     fn slider_ui(&self, ui: &Ui, response: &mut Response) {
         // Handle user input
         // TODO: Optionally (since it can be annoying) add scrolling behind a builder option
+
+        // Right clicking opens the text entry field, and Control+right clicking shows the host's
+        // context menu for the parameter. The text entry field is part of the value display, so
+        // without it right clicking always shows the context menu.
+        if response.secondary_clicked() && self.draw_value && !ui.input(|i| i.modifiers.command) {
+            self.begin_keyboard_entry(ui);
+        } else if response.secondary_clicked() {
+            if let Some(click_pos) = response.interact_pointer_pos() {
+                self.setter
+                    .raw_context
+                    .show_param_context_menu(self.param.as_ptr(), (click_pos.x, click_pos.y));
+            }
+        }
+
         if response.drag_started() {
             // When beginning a drag or dragging normally, reset the memory used to keep track of
             // our granular drag
@@ -201,9 +271,13 @@ impl<'a, P: Param> ParamSlider<'a, P> {
                 self.granular_drag(ui, response.drag_delta());
                 response.mark_changed();
             } else {
-                let proportion =
-                    emath::remap_clamp(click_pos.x, response.rect.x_range(), 0.0..=1.0) as f64;
-                self.set_normalized_value(proportion as f32);
+                let mut proportion =
+                    emath::remap_clamp(click_pos.x, response.rect.x_range(), 0.0..=1.0);
+                // Alt snaps to the nearest tick mark
+                if ui.input(|i| i.modifiers.alt) {
+                    proportion = self.nearest_tick(proportion).unwrap_or(proportion);
+                }
+                self.set_normalized_value(proportion);
                 response.mark_changed();
                 Self::set_drag_amount_memory(ui, 0.0);
             }
@@ -216,6 +290,59 @@ impl<'a, P: Param> ParamSlider<'a, P> {
             self.end_drag();
         }
 
+        // Clicking on the slider gives it keyboard focus, after which the arrow keys step through
+        // the parameter's values. Shift can be held down for finer steps.
+        if response.clicked() || response.drag_started() {
+            response.request_focus();
+        }
+        if response.has_focus() && !response.dragged() {
+            let (increment, decrement, use_finer_steps) = ui.input(|i| {
+                (
+                    i.key_pressed(Key::ArrowUp) || i.key_pressed(Key::ArrowRight),
+                    i.key_pressed(Key::ArrowDown) || i.key_pressed(Key::ArrowLeft),
+                    i.modifiers.shift,
+                )
+            });
+            if increment != decrement {
+                self.step_value(increment, use_finer_steps);
+                response.mark_changed();
+            }
+        }
+
+        // Control+C copies the parameter's displayed value to the clipboard, and Control+V sets the
+        // parameter from the clipboard's contents
+        if (response.hovered() || response.has_focus()) && !self.keyboard_entry_active(ui) {
+            let (copy, paste) = ui.input(|i| {
+                (
+                    i.events.iter().any(|e| matches!(e, egui::Event::Copy))
+                        || (i.modifiers.command && i.key_pressed(Key::C)),
+                    i.events.iter().any(|e| matches!(e, egui::Event::Paste(_)))
+                        || (i.modifiers.command && i.key_pressed(Key::V)),
+                )
+            });
+            if copy {
+                self.setter
+                    .raw_context
+                    .set_clipboard_text(&self.string_value());
+            } else if paste {
+                if let Some(text) = self.setter.raw_context.clipboard_text() {
+                    self.begin_drag();
+                    if self.set_from_string(&text) {
+                        response.mark_changed();
+                    }
+                    self.end_drag();
+                }
+            }
+        }
+
+        // This exposes the slider's name and current value to screen readers
+        response.widget_info(|| {
+            let mut info = WidgetInfo::labeled(WidgetType::Slider, self.param.name());
+            info.value = Some(self.param.unmodulated_normalized_value() as f64);
+            info.current_text_value = Some(self.string_value());
+            info
+        });
+
         // And finally draw the thing
         if ui.is_rect_visible(response.rect) {
             // We'll do a flat widget with background -> filled foreground -> slight border
@@ -234,6 +361,29 @@ impl<'a, P: Param> ParamSlider<'a, P> {
                 ui.painter().rect_filled(filled_rect, 0.0, filled_bg);
             }
 
+            // The ticks are drawn on top of the fill, with the same color as the border
+            let tick_stroke = Stroke::new(1.0, ui.visuals().widgets.active.bg_fill);
+            let tick_height = response.rect.height() * 0.3;
+            for &tick in &self.ticks {
+                let x = ui
+                    .painter()
+                    .round_to_pixel(response.rect.left() + (response.rect.width() * tick));
+                ui.painter().line_segment(
+                    [
+                        egui::pos2(x, response.rect.top()),
+                        egui::pos2(x, response.rect.top() + tick_height),
+                    ],
+                    tick_stroke,
+                );
+                ui.painter().line_segment(
+                    [
+                        egui::pos2(x, response.rect.bottom() - tick_height),
+                        egui::pos2(x, response.rect.bottom()),
+                    ],
+                    tick_stroke,
+                );
+            }
+
             ui.painter().rect_stroke(
                 response.rect,
                 0.0,
@@ -314,6 +464,43 @@ impl<'a, P: Param> ParamSlider<'a, P> {
     }
 }
 
+impl<'a> ParamSlider<'a, FloatParam> {
+    /// Draw tick marks at every octave of 1 kHz within the parameter's range, so at 125 Hz, 250 Hz,
+    /// 500 Hz, 1 kHz, 2 kHz, etc. Useful for frequency parameters using a skewed range. See
+    /// [`with_ticks()`][Self::with_ticks()].
+    pub fn with_octave_ticks(self) -> Self {
+        let min = self.param.preview_plain(0.0);
+        let max = self.param.preview_plain(1.0);
+        if min <= 0.0 || max <= min {
+            return self;
+        }
+
+        let first_octave = (min / OCTAVE_TICKS_REFERENCE_HZ).log2().ceil() as i32;
+        let last_octave = (max / OCTAVE_TICKS_REFERENCE_HZ).log2().floor() as i32;
+        self.with_ticks(
+            (first_octave..=last_octave)
+                .map(|octave| OCTAVE_TICKS_REFERENCE_HZ * 2.0f32.powi(octave)),
+        )
+    }
+}
+
+/// Remove a parameter's unit from the end of a string, ignoring case and whitespace. Returns `None`
+/// if the string does not end with the unit.
+fn strip_unit<'s>(string: &'s str, unit: &str) -> Option<&'s str> {
+    let string = string.trim();
+    let unit = unit.trim();
+    if unit.is_empty() || string.len() < unit.len() {
+        return None;
+    }
+
+    let split_idx = string.len() - unit.len();
+    if !string.is_char_boundary(split_idx) || !string[split_idx..].eq_ignore_ascii_case(unit) {
+        return None;
+    }
+
+    Some(string[..split_idx].trim_end())
+}
+
 impl<P: Param> Widget for ParamSlider<'_, P> {
     fn ui(mut self, ui: &mut Ui) -> Response {
         let slider_width = self
@@ -0,0 +1,151 @@
+//! An on-screen piano keyboard that sends note events to the plugin.
+
+use std::ops::RangeInclusive;
+
+use crate::egui::{self, pos2, vec2, Color32, Rect, Response, Sense, Stroke, Ui, Vec2, Widget};
+use nih_plug::prelude::{GuiContext, NoteEvent};
+use nih_plug::util::keyboard::{is_black_key, KeyboardLayout};
+
+/// The keyboard's default size when no size has been set.
+const DEFAULT_SIZE: Vec2 = vec2(420.0, 60.0);
+
+/// A piano keyboard that can be played with the mouse. Pressing a key sends a `NoteOn` event to the
+/// plugin through [`GuiContext::send_note_event()`], and releasing it or dragging to another key
+/// sends the matching `NoteOff`. The plugin needs to accept note events through
+/// [`Plugin::MIDI_INPUT`][nih_plug::prelude::Plugin::MIDI_INPUT] for this to have any effect.
+#[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
+pub struct PianoKeyboard<'a> {
+    context: &'a dyn GuiContext,
+    notes: RangeInclusive<u8>,
+    channel: u8,
+    velocity: f32,
+    size: Option<Vec2>,
+}
+
+impl<'a> PianoKeyboard<'a> {
+    /// Create a keyboard spanning the given range of MIDI notes. The range is extended to start and
+    /// end on a white key. Use `ParamSetter::raw_context` to get the GUI context in an egui editor.
+    pub fn new(context: &'a dyn GuiContext, notes: RangeInclusive<u8>) -> Self {
+        Self {
+            context,
+            notes,
+            channel: 0,
+            velocity: 1.0,
+            size: None,
+        }
+    }
+
+    /// The MIDI channel notes are sent on. Defaults to the first channel.
+    pub fn with_channel(mut self, channel: u8) -> Self {
+        self.channel = channel.min(15);
+        self
+    }
+
+    /// The velocity used for the sent notes, in `[0, 1]`. Defaults to full velocity.
+    pub fn with_velocity(mut self, velocity: f32) -> Self {
+        self.velocity = velocity.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Set a custom size for the keyboard.
+    pub fn with_size(mut self, size: Vec2) -> Self {
+        self.size = Some(size);
+        self
+    }
+
+    fn note_on(&self, note: u8) {
+        self.context.send_note_event(NoteEvent::NoteOn {
+            timing: 0,
+            voice_id: None,
+            channel: self.channel,
+            note,
+            velocity: self.velocity,
+        });
+    }
+
+    fn note_off(&self, note: u8) {
+        self.context.send_note_event(NoteEvent::NoteOff {
+            timing: 0,
+            voice_id: None,
+            channel: self.channel,
+            note,
+            velocity: 0.0,
+        });
+    }
+
+    fn get_held_note_memory(ui: &Ui, id: egui::Id) -> Option<u8> {
+        ui.memory(|mem| mem.data.get_temp(id))
+    }
+
+    fn set_held_note_memory(ui: &Ui, id: egui::Id, note: Option<u8>) {
+        ui.memory_mut(|mem| match note {
+            Some(note) => {
+                mem.data.insert_temp(id, note);
+            }
+            None => {
+                mem.data.remove::<u8>(id);
+            }
+        });
+    }
+}
+
+impl Widget for PianoKeyboard<'_> {
+    fn ui(self, ui: &mut Ui) -> Response {
+        let size = self.size.unwrap_or(DEFAULT_SIZE);
+        let (rect, response) = ui.allocate_exact_size(size, Sense::click_and_drag());
+        let layout = KeyboardLayout::new(self.notes.clone(), rect.width(), rect.height());
+
+        // The currently held note is stored in egui's memory so notes can be released on the next
+        // frame. Dragging to another key releases the old note and plays the new one.
+        let held_note = Self::get_held_note_memory(ui, response.id);
+        let pointer_note = if response.is_pointer_button_down_on() {
+            response.interact_pointer_pos().and_then(|pos| {
+                let pos = pos - rect.min;
+                layout.note_at(pos.x, pos.y)
+            })
+        } else {
+            None
+        };
+        if pointer_note != held_note {
+            if let Some(note) = held_note {
+                self.note_off(note);
+            }
+            if let Some(note) = pointer_note {
+                self.note_on(note);
+            }
+
+            Self::set_held_note_memory(ui, response.id, pointer_note);
+        }
+
+        if ui.is_rect_visible(rect) {
+            let visuals = ui.style().visuals.clone();
+            let key_rect = |note: u8| {
+                layout.key_rect(note).map(|key| {
+                    Rect::from_min_size(
+                        pos2(rect.min.x + key.x, rect.min.y + key.y),
+                        vec2(key.width, key.height),
+                    )
+                })
+            };
+            let key_color = |note: u8| {
+                if pointer_note == Some(note) {
+                    visuals.selection.bg_fill
+                } else if is_black_key(note) {
+                    Color32::from_gray(20)
+                } else {
+                    Color32::from_gray(235)
+                }
+            };
+
+            // Black keys are drawn on top of the white keys
+            let stroke = Stroke::new(1.0, Color32::from_gray(20));
+            for note in layout.white_keys().chain(layout.black_keys()) {
+                if let Some(key) = key_rect(note) {
+                    ui.painter().rect(key, 0.0, key_color(note), stroke);
+                }
+            }
+        }
+
+        response
+    }
+}
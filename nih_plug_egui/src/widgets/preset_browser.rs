@@ -0,0 +1,197 @@
+use crate::egui::{self, Response, ScrollArea, TextEdit, Ui, Widget};
+use nih_plug::prelude::ParamSetter;
+use nih_plug::util::{PresetEntry, PresetLibrary};
+
+/// A preset browser for a [`PresetLibrary`]. Presets can be searched by name and filtered by
+/// clicking on their tags, and clicking on a preset loads it. The save button opens a form for
+/// saving the plugin's current state as a new preset. The widget uses the `Ui`'s current style, so
+/// its look can be changed by modifying the style before adding the widget.
+#[must_use = "You should put this widget in an ui with `ui.add(widget);`"]
+pub struct PresetBrowser<'a> {
+    library: &'a PresetLibrary,
+    state: &'a mut PresetBrowserState,
+    setter: &'a ParamSetter<'a>,
+
+    list_height: f32,
+}
+
+/// State for a [`PresetBrowser`]. This should be stored in the editor's user state.
+#[derive(Debug, Default)]
+pub struct PresetBrowserState {
+    /// The text in the search field.
+    query: String,
+    /// The tags presets need to have to be shown.
+    selected_tags: Vec<String>,
+    /// The last preset that was loaded or saved.
+    current_preset: Option<PresetEntry>,
+
+    /// Whether the save form is currently shown.
+    save_form_open: bool,
+    /// The name entered in the save form.
+    save_name: String,
+    /// The comma separated tags entered in the save form.
+    save_tags: String,
+
+    /// The last error that occurred while loading or saving a preset.
+    error: Option<String>,
+}
+
+impl<'a> PresetBrowser<'a> {
+    /// Create a new preset browser for a preset library. Presets are loaded and saved through the
+    /// setter's [`GuiContext`][nih_plug::prelude::GuiContext].
+    pub fn new(
+        library: &'a PresetLibrary,
+        state: &'a mut PresetBrowserState,
+        setter: &'a ParamSetter<'a>,
+    ) -> Self {
+        Self {
+            library,
+            state,
+            setter,
+
+            list_height: 200.0,
+        }
+    }
+
+    /// Set the maximum height of the preset list.
+    pub fn with_list_height(mut self, height: f32) -> Self {
+        self.list_height = height;
+        self
+    }
+
+    fn load(&mut self, preset: &PresetEntry) {
+        match self.library.load(preset) {
+            Ok(plugin_state) => {
+                self.setter.raw_context.set_state(plugin_state);
+                self.state.current_preset = Some(preset.clone());
+                self.state.error = None;
+            }
+            Err(err) => self.state.error = Some(format!("{err:#}")),
+        }
+    }
+
+    fn save(&mut self) {
+        let tags: Vec<String> = self.state.save_tags.split(',').map(String::from).collect();
+        let plugin_state = self.setter.raw_context.get_state();
+        match self
+            .library
+            .save(&self.state.save_name, &tags, &plugin_state)
+        {
+            Ok(preset) => {
+                self.state.current_preset = Some(preset);
+                self.state.save_form_open = false;
+                self.state.error = None;
+            }
+            Err(err) => self.state.error = Some(format!("{err:#}")),
+        }
+    }
+
+    fn open_save_form(&mut self) {
+        // The form starts out with the current preset's name and tags so it can easily be
+        // overwritten
+        let (name, tags) = match &self.state.current_preset {
+            Some(preset) => (preset.name.clone(), preset.tags.join(", ")),
+            None => (String::new(), String::new()),
+        };
+        self.state.save_name = name;
+        self.state.save_tags = tags;
+        self.state.save_form_open = true;
+    }
+}
+
+impl Widget for PresetBrowser<'_> {
+    fn ui(mut self, ui: &mut Ui) -> Response {
+        // The scan happens on a background thread, so the widget needs to be redrawn until the
+        // scan has finished
+        if self.library.is_scanning() {
+            ui.ctx().request_repaint();
+        }
+
+        ui.vertical(|ui| {
+            ui.horizontal(|ui| {
+                ui.add(TextEdit::singleline(&mut self.state.query).hint_text("Search presets"));
+                if ui.button("Save...").clicked() {
+                    self.open_save_form();
+                }
+                if self.library.is_scanning() {
+                    ui.spinner();
+                }
+            });
+
+            let tags = self.library.tags();
+            if !tags.is_empty() {
+                ui.horizontal_wrapped(|ui| {
+                    for tag in tags {
+                        let selected = self.state.selected_tags.contains(&tag);
+                        if ui.selectable_label(selected, &tag).clicked() {
+                            if selected {
+                                self.state.selected_tags.retain(|t| t != &tag);
+                            } else {
+                                self.state.selected_tags.push(tag);
+                            }
+                        }
+                    }
+                });
+            }
+
+            let presets = self
+                .library
+                .search(&self.state.query, &self.state.selected_tags);
+            ScrollArea::vertical()
+                .max_height(self.list_height)
+                .show(ui, |ui| {
+                    if presets.is_empty() {
+                        ui.weak("No presets found");
+                    }
+
+                    for preset in &presets {
+                        let selected = self
+                            .state
+                            .current_preset
+                            .as_ref()
+                            .map_or(false, |current| current.path == preset.path);
+                        let response = ui.selectable_label(selected, &preset.name);
+                        let response = if preset.tags.is_empty() {
+                            response
+                        } else {
+                            response.on_hover_text(preset.tags.join(", "))
+                        };
+                        if response.clicked() {
+                            self.load(preset);
+                        }
+                    }
+                });
+
+            if self.state.save_form_open {
+                ui.separator();
+                egui::Grid::new("preset_browser_save_form")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label("Name");
+                        ui.text_edit_singleline(&mut self.state.save_name);
+                        ui.end_row();
+
+                        ui.label("Tags");
+                        ui.add(
+                            TextEdit::singleline(&mut self.state.save_tags)
+                                .hint_text("Comma separated"),
+                        );
+                        ui.end_row();
+                    });
+                ui.horizontal(|ui| {
+                    if ui.button("Save").clicked() {
+                        self.save();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.state.save_form_open = false;
+                    }
+                });
+            }
+
+            if let Some(error) = &self.state.error {
+                ui.colored_label(ui.visuals().error_fg_color, error);
+            }
+        })
+        .response
+    }
+}
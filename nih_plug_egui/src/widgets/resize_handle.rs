@@ -0,0 +1,62 @@
+//! A resize handle for resizable editors.
+
+use crate::egui::{self, pos2, vec2, Align2, Context, Sense};
+
+use crate::EguiState;
+
+/// The size of the handle's square hitbox in logical pixels.
+const HANDLE_SIZE: f32 = 18.0;
+
+/// A handle that can be dragged to resize the editor's window. This is drawn on top of everything
+/// else in the window's bottom right corner. The editor needs to have been created using
+/// [`EguiState::from_size_resizable()`] for this to have any effect.
+///
+/// Call [`ResizeHandle::show()`] at the end of your update function.
+#[must_use = "You should show this widget using `ResizeHandle::show()`"]
+pub struct ResizeHandle<'a> {
+    egui_state: &'a EguiState,
+}
+
+impl<'a> ResizeHandle<'a> {
+    /// Create a resize handle for the editor with this state.
+    pub fn for_state(egui_state: &'a EguiState) -> Self {
+        Self { egui_state }
+    }
+
+    /// Draw the handle and handle resize drags.
+    pub fn show(self, ctx: &Context) {
+        egui::Area::new(egui::Id::new((file!(), 0)))
+            .anchor(Align2::RIGHT_BOTTOM, vec2(0.0, 0.0))
+            .show(ctx, |ui| {
+                let (rect, response) =
+                    ui.allocate_exact_size(vec2(HANDLE_SIZE, HANDLE_SIZE), Sense::drag());
+
+                // The handle sits in the window's bottom right corner, so the pointer's position
+                // relative to the window's top left corner is the window's new size
+                if response.dragged() {
+                    if let Some(pointer_pos) = response.interact_pointer_pos() {
+                        let new_size = (
+                            pointer_pos.x.max(1.0).round() as u32,
+                            pointer_pos.y.max(1.0).round() as u32,
+                        );
+                        if new_size != self.egui_state.size() {
+                            self.egui_state.set_requested_size(new_size);
+                        }
+                    }
+                }
+
+                let stroke = ui.style().interact(&response).fg_stroke;
+                let painter = ui.painter();
+                for i in 1..=3 {
+                    let offset = i as f32 * HANDLE_SIZE / 4.0;
+                    painter.line_segment(
+                        [
+                            pos2(rect.right() - offset, rect.bottom()),
+                            pos2(rect.right(), rect.bottom() - offset),
+                        ],
+                        stroke,
+                    );
+                }
+            });
+    }
+}
@@ -2,16 +2,16 @@
 
 use baseview::{WindowOpenOptions, WindowScalePolicy};
 use crossbeam::atomic::AtomicCell;
-use crossbeam::channel;
-pub use iced_baseview::*;
-use nih_plug::prelude::{Editor, GuiContext, ParentWindowHandle};
+use iced_baseview::settings::IcedBaseviewSettings;
+use iced_baseview::Settings;
+use nih_plug::prelude::{Editor, EditorSizeConstraints, GuiContext, ParentWindowHandle};
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
-use crate::{wrapper, IcedEditor, IcedState, ParameterUpdate};
+use crate::{wrapper, IcedEditor, IcedState, ParameterUpdates};
 
-/// An [`Editor`] implementation that renders an iced [`Application`].
+/// An [`Editor`] implementation that renders an [`IcedEditor`] using `iced_baseview`.
 pub(crate) struct IcedEditorWrapper<E: IcedEditor> {
     pub(crate) iced_state: Arc<IcedState>,
     pub(crate) initialization_flags: E::InitializationFlags,
@@ -20,9 +20,9 @@ pub(crate) struct IcedEditorWrapper<E: IcedEditor> {
     /// should use the system scaling factor instead.
     pub(crate) scaling_factor: AtomicCell<Option<f32>>,
 
-    /// A subscription for sending messages about parameter updates to the `IcedEditor`.
-    pub(crate) parameter_updates_sender: channel::Sender<ParameterUpdate>,
-    pub(crate) parameter_updates_receiver: Arc<channel::Receiver<ParameterUpdate>>,
+    /// Used to notify the `IcedEditor` about parameter updates so the GUI can be redrawn. This is
+    /// shared with the editor's parameter update subscription.
+    pub(crate) parameter_updates: Arc<ParameterUpdates>,
 }
 
 /// This version of `baseview` uses a different version of `raw_window_handle than NIH-plug, so we
@@ -33,20 +33,25 @@ unsafe impl HasRawWindowHandle for ParentWindowHandleAdapter {
     fn raw_window_handle(&self) -> RawWindowHandle {
         match self.0 {
             ParentWindowHandle::X11Window(window) => {
-                let mut handle = raw_window_handle::XcbHandle::empty();
+                let mut handle = raw_window_handle::XcbWindowHandle::empty();
                 handle.window = window;
                 RawWindowHandle::Xcb(handle)
             }
             ParentWindowHandle::AppKitNsView(ns_view) => {
-                let mut handle = raw_window_handle::AppKitHandle::empty();
+                let mut handle = raw_window_handle::AppKitWindowHandle::empty();
                 handle.ns_view = ns_view;
                 RawWindowHandle::AppKit(handle)
             }
             ParentWindowHandle::Win32Hwnd(hwnd) => {
-                let mut handle = raw_window_handle::Win32Handle::empty();
+                let mut handle = raw_window_handle::Win32WindowHandle::empty();
                 handle.hwnd = hwnd;
                 RawWindowHandle::Win32(handle)
             }
+            ParentWindowHandle::WebCanvas(id) => {
+                let mut handle = raw_window_handle::WebWindowHandle::empty();
+                handle.id = id;
+                RawWindowHandle::Web(handle)
+            }
         }
     }
 }
@@ -62,7 +67,7 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
 
         // TODO: iced_baseview does not have gracefuly error handling for context creation failures.
         //       This will panic if the context could not be created.
-        let window = IcedWindow::<wrapper::IcedEditorWrapperApplication<E>>::open_parented(
+        let window = iced_baseview::open_parented::<wrapper::IcedEditorWrapperApplication<E>, _>(
             &ParentWindowHandleAdapter(parent),
             Settings {
                 window: WindowOpenOptions {
@@ -74,39 +79,20 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
                     scale: scaling_factor
                         .map(|factor| WindowScalePolicy::ScaleFactor(factor as f64))
                         .unwrap_or(WindowScalePolicy::SystemScaleFactor),
-
-                    #[cfg(feature = "opengl")]
-                    gl_config: Some(baseview::gl::GlConfig {
-                        // FIXME: glow_glyph forgot to add an `#extension`, so this won't work under
-                        //        OpenGL 3.2 at the moment. With that change applied this should work on
-                        //        OpenGL 3.2/macOS.
-                        version: (3, 3),
-                        red_bits: 8,
-                        blue_bits: 8,
-                        green_bits: 8,
-                        alpha_bits: 8,
-                        depth_bits: 24,
-                        stencil_bits: 8,
-                        samples: None,
-                        srgb: true,
-                        double_buffer: true,
-                        vsync: true,
-                        ..Default::default()
-                    }),
-                    // FIXME: Rust analyzer always thinks baseview/opengl is enabled even if we
-                    //        don't explicitly enable it, so you'd get a compile error if this line
-                    //        is missing
-                    #[cfg(not(feature = "opengl"))]
-                    gl_config: None,
                 },
                 iced_baseview: IcedBaseviewSettings {
                     ignore_non_modifier_keys: false,
                     always_redraw: true,
                 },
+                // With both the `wgpu` and `tiny-skia` features enabled, iced falls back to the
+                // tiny-skia software renderer if no suitable GPU adapter could be found
+                graphics_settings: E::renderer_settings(),
+                fonts: E::fonts(),
                 // We use this wrapper to be able to pass the GUI context to the editor
                 flags: (
                     context,
-                    self.parameter_updates_receiver.clone(),
+                    self.parameter_updates.clone(),
+                    self.iced_state.clone(),
                     self.initialization_flags.clone(),
                 ),
             },
@@ -123,6 +109,23 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
         self.iced_state.size()
     }
 
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        self.iced_state.size_constraints()
+    }
+
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        if self.iced_state.size_constraints().is_none() {
+            return false;
+        }
+
+        self.iced_state.size.store((width, height));
+        if self.iced_state.is_open() {
+            self.iced_state.set_pending_window_size((width, height));
+        }
+
+        true
+    }
+
     fn set_scale_factor(&self, factor: f32) -> bool {
         // If the editor is currently open then the host must not change the current HiDPI scale as
         // we don't have a way to handle that. Ableton Live does this.
@@ -134,20 +137,28 @@ impl<E: IcedEditor> Editor for IcedEditorWrapper<E> {
         true
     }
 
+    fn ui_scale(&self) -> Option<f32> {
+        self.iced_state.ui_scale()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.iced_state.ui_scale.store(scale);
+        true
+    }
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
-        // If there's already a paramter change notification in the channel then we don't need
-        // to do anything else. This avoids queueing up redundant GUI redraws.
+        // Multiple notifications in a row get coalesced into a single redraw.
         // NOTE: We could add an event containing the parameter's ID and the normalized value, but
-        //       these events aren't really necessary for Vizia.
-        let _ = self.parameter_updates_sender.try_send(ParameterUpdate);
+        //       these events aren't really necessary for iced.
+        self.parameter_updates.notify();
     }
 
     fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {
-        let _ = self.parameter_updates_sender.try_send(ParameterUpdate);
+        self.parameter_updates.notify();
     }
 
     fn param_values_changed(&self) {
-        let _ = self.parameter_updates_sender.try_send(ParameterUpdate);
+        self.parameter_updates.notify();
     }
 }
 
@@ -0,0 +1,124 @@
+//! Streaming events from the audio thread to an [`IcedEditor`][crate::IcedEditor].
+//!
+//! [`AnalysisChannel`][nih_plug::util::AnalysisChannel]s only ever hold the latest value, and they
+//! need to be polled by the editor. For events where every single event matters, like MIDI note
+//! activity, or for editors that should only be redrawn when new data arrives, an
+//! [`event_channel()`] can be used instead. The [`EventSender`] half is stored in the plugin and
+//! used from the audio thread, and the [`EventReceiver`] half is passed to the editor through its
+//! [`InitializationFlags`][crate::IcedEditor::InitializationFlags]. The editor can then turn the
+//! receiver into a [`Subscription`] in [`IcedEditor::subscription()`][crate::IcedEditor::subscription()]
+//! to receive the events as regular messages:
+//!
+//! ```ignore
+//! fn subscription(&self) -> Subscription<Self::Message> {
+//!     self.note_events.subscription().map(Message::NoteEvent)
+//! }
+//! ```
+
+use crossbeam::queue::ArrayQueue;
+use futures_util::stream;
+use futures_util::task::AtomicWaker;
+use std::sync::Arc;
+use std::task::Poll;
+
+use crate::Subscription;
+
+/// Create a bounded channel for sending events from the audio thread to the editor. The channel can
+/// hold up to `capacity` events that have not yet been received by the editor. Events sent while
+/// the channel is full are dropped, so the capacity should be large enough to hold all events sent
+/// in between two GUI frames.
+///
+/// # Panics
+///
+/// Panics if `capacity` is zero.
+pub fn event_channel<T: Send + 'static>(capacity: usize) -> (EventSender<T>, EventReceiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: ArrayQueue::new(capacity),
+        waker: AtomicWaker::new(),
+    });
+
+    (
+        EventSender {
+            shared: shared.clone(),
+        },
+        EventReceiver { shared },
+    )
+}
+
+/// The sending half of an [`event_channel()`]. Sending events does not allocate or block, so this
+/// can be used from the audio thread.
+pub struct EventSender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The receiving half of an [`event_channel()`]. This can be cloned so it can be part of the
+/// editor's [`InitializationFlags`][crate::IcedEditor::InitializationFlags], but only a single
+/// [`subscription()`][Self::subscription()] should be active at a time.
+pub struct EventReceiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The data shared between the sender and the receiver.
+struct Shared<T> {
+    queue: ArrayQueue<T>,
+    /// Wakes up the editor's subscription after new events have been sent.
+    waker: AtomicWaker,
+}
+
+impl<T> Clone for EventSender<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Clone for EventReceiver<T> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T: Send + 'static> EventSender<T> {
+    /// Send an event to the editor. If the channel is full, then the event is returned as an error.
+    pub fn send(&self, event: T) -> Result<(), T> {
+        self.shared.queue.push(event)?;
+
+        // Waking up the editor's task hands it over to the executor. This doesn't allocate for the
+        // executors supported by iced, but we can't guarantee that for every executor.
+        nih_plug::util::permit_alloc(|| self.shared.waker.wake());
+
+        Ok(())
+    }
+}
+
+impl<T: Send + 'static> EventReceiver<T> {
+    /// Create a subscription that emits every event sent through the channel. Use
+    /// [`Subscription::map()`] with one of your message type's variants to turn this into a
+    /// subscription for the editor's messages. The events are delivered on the editor's executor,
+    /// and the editor is redrawn after each batch of events. Events that were sent while the editor
+    /// was closed are delivered when the editor is opened again.
+    pub fn subscription(&self) -> Subscription<T> {
+        // The subscription's identity is tied to the channel so iced only creates this stream once
+        let id = Arc::as_ptr(&self.shared) as *const () as usize;
+        let shared = self.shared.clone();
+        let events = stream::poll_fn(move |cx| {
+            // The waker needs to be registered before checking the queue, otherwise an event sent
+            // in between could get lost
+            shared.waker.register(cx.waker());
+            match shared.queue.pop() {
+                Some(event) => Poll::Ready(Some(event)),
+                None => Poll::Pending,
+            }
+        });
+
+        Subscription::run_with_id((std::any::type_name::<T>(), id), events)
+    }
+
+    /// Whether there are events that have not yet been received.
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.is_empty()
+    }
+}
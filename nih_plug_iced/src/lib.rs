@@ -21,8 +21,6 @@
 //! struct FooEditor {
 //!     params: Arc<FooParams>,
 //!     context: Arc<dyn GuiContext>,
-//!
-//!     foo_slider_state: nih_widgets::param_slider::State,
 //! }
 //!
 //! #[derive(Debug, Clone, Copy)]
@@ -39,82 +37,112 @@
 //!     fn new(
 //!         params: Self::InitializationFlags,
 //!         context: Arc<dyn GuiContext>,
-//!     ) -> (Self, Command<Self::Message>) {
-//!         let editor = FooEditor {
-//!             params,
-//!             context,
-//!
-//!             foo_slider_state: Default::default(),
-//!         };
+//!     ) -> (Self, Task<Self::Message>) {
+//!         let editor = FooEditor { params, context };
 //!
-//!         (editor, Command::none())
+//!         (editor, Task::none())
 //!     }
 //!
 //!     fn context(&self) -> &dyn GuiContext {
 //!         self.context.as_ref()
 //!     }
 //!
-//!     fn update(
-//!         &mut self,
-//!         _window: &mut WindowQueue,
-//!         message: Self::Message,
-//!     ) -> Command<Self::Message> {
+//!     fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
 //!         match message {
 //!             Message::ParamUpdate(message) => self.handle_param_message(message),
 //!         }
 //!
-//!         Command::none()
+//!         Task::none()
 //!     }
 //!
-//!     fn view(&mut self) -> Element<'_, Self::Message> {
+//!     fn view(&self) -> Element<'_, Self::Message> {
 //!         Column::new()
-//!             .align_items(Alignment::Center)
+//!             .align_x(Alignment::Center)
 //!             .push(
 //!                 Text::new("Foo")
-//!                     .height(20.into())
+//!                     .height(20)
 //!                     .width(Length::Fill)
-//!                     .horizontal_alignment(alignment::Horizontal::Center)
-//!                     .vertical_alignment(alignment::Vertical::Center),
-//!             )
-//!             .push(
-//!                 nih_widgets::ParamSlider::new(
-//!                     &mut self.foo_slider_state,
-//!                     &self.params.foo,
-//!                     self.context.as_ref(),
-//!                 )
-//!                 .map(Message::ParamUpdate),
+//!                     .align_x(alignment::Horizontal::Center)
+//!                     .align_y(alignment::Vertical::Center),
 //!             )
+//!             .push(nih_widgets::ParamSlider::new(&self.params.foo).map(Message::ParamUpdate))
 //!             .into()
 //!     }
 //! }
 //! ```
+//!
+//! Events from the audio thread can be sent to the editor as regular messages using the channels
+//! from the [`events`] module.
+//!
+//! Widget state is stored in iced's widget tree, so unlike with older iced versions the editor
+//! doesn't need to store state objects for its widgets. Custom widgets can be written using the
+//! traits and types from the [`advanced`] module.
 
 use baseview::WindowScalePolicy;
 use crossbeam::atomic::AtomicCell;
-use crossbeam::channel;
+use futures_util::task::AtomicWaker;
 use nih_plug::params::persist::PersistentField;
-use nih_plug::prelude::{Editor, GuiContext};
+use nih_plug::prelude::{Editor, EditorSizeConstraints, GuiContext};
 use serde::{Deserialize, Serialize};
-// This doesn't need to be re-export but otherwise the compiler complains about
-// `hidden_glob_reexports`
-pub use std::fmt::Debug;
+use std::borrow::Cow;
+use std::fmt::Debug;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 
 use crate::widgets::ParamMessage;
 
-/// Re-export for convenience.
-// FIXME: Running `cargo doc` on nightly compilers without this attribute triggers an ICE
-#[doc(no_inline)]
-pub use iced_baseview::*;
+// These re-exports mirror the `iced` crate's top level module so code written for iced can be used
+// with `nih_plug_iced` with minimal changes
+pub use iced_core::{
+    alignment, border, event, font, gradient, keyboard, mouse, padding, theme, touch, Alignment,
+    Background, Border, Color, Event, Font, Length, Padding, Pixels, Point, Rectangle, Shadow,
+    Size, Theme, Vector,
+};
+pub use iced_futures::Subscription;
+pub use iced_runtime::{task, Task};
+pub use iced_widget::{
+    Button, Checkbox, Column, Container, PickList, Row, Scrollable, Slider, Space, Text, TextInput,
+};
+
+/// The renderer used by `nih_plug_iced` editors. Depending on the enabled features this is either
+/// wgpu, tiny-skia, or wgpu with a tiny-skia fallback.
+pub type Renderer = iced_renderer::Renderer;
+
+/// A generic [`Widget`][advanced::Widget] that uses `nih_plug_iced`'s [`Theme`] and [`Renderer`]
+/// by default.
+pub type Element<'a, Message, Theme = crate::Theme, Renderer = crate::Renderer> =
+    iced_core::Element<'a, Message, Theme, Renderer>;
+
+/// Iced's built-in widgets. See [`widgets`] for NIH-plug's own widgets.
+pub mod widget {
+    pub use iced_widget::*;
+}
+
+/// The executors that can be used to run an [`IcedEditor`]'s [`Task`]s and [`Subscription`]s.
+pub mod executor {
+    pub use iced_futures::backend::default::Executor as Default;
+    pub use iced_futures::Executor;
+}
+
+/// The traits and types needed to write custom widgets. This mirrors `iced::advanced`.
+pub mod advanced {
+    pub use iced_core::clipboard::{self, Clipboard};
+    pub use iced_core::layout::{self, Layout};
+    pub use iced_core::renderer::{self, Renderer};
+    pub use iced_core::widget::{self, Widget};
+    pub use iced_core::{mouse, overlay, text, Shell};
+}
+
+pub use iced_futures::Executor;
 
 pub mod assets;
 mod editor;
+pub mod events;
 pub mod widgets;
 mod wrapper;
 
 /// Create an [`Editor`] instance using [iced](https://github.com/iced-rs/iced). The rough idea is
-/// that you implement [`IcedEditor`], which is roughly analogous to iced's regular [`Application`]
+/// that you implement [`IcedEditor`], which is roughly analogous to iced's regular application
 /// trait except that it receives the [`GuiContext`] alongside its initialization flags so it can
 /// update the parameter values. The [`IcedState`] passed to this function contains the GUI's
 /// intitial size, and this is kept in sync whenever the GUI gets resized. You can also use this to
@@ -127,12 +155,6 @@ pub fn create_iced_editor<E: IcedEditor>(
     iced_state: Arc<IcedState>,
     initialization_flags: E::InitializationFlags,
 ) -> Option<Box<dyn Editor>> {
-    // We need some way to communicate parameter changes to the `IcedEditor` since parameter updates
-    // come from outside of the editor's reactive model. This contains only capacity to store only
-    // one parameter update, since we're only storing _that_ a parameter update has happened and not
-    // which parameter so we'd need to redraw the entire GUI either way.
-    let (parameter_updates_sender, parameter_updates_receiver) = channel::bounded(1);
-
     Some(Box::new(editor::IcedEditorWrapper::<E> {
         iced_state,
         initialization_flags,
@@ -145,78 +167,82 @@ pub fn create_iced_editor<E: IcedEditor>(
         #[cfg(not(target_os = "macos"))]
         scaling_factor: AtomicCell::new(Some(1.0)),
 
-        parameter_updates_sender,
-        parameter_updates_receiver: Arc::new(parameter_updates_receiver),
+        parameter_updates: Arc::new(ParameterUpdates::default()),
     }))
 }
 
-/// A plugin editor using `iced`. This wraps around [`Application`] with the only change being that
-/// the usual `new()` function now additionally takes a `Arc<dyn GuiContext>` that the editor can
-/// store to interact with the parameters. The editor should have a `Arc<impl Params>` as part
-/// of their [`InitializationFlags`][Self::InitializationFlags] so it can read the current parameter
-/// values. See [`Application`] for more information.
+/// A plugin editor using `iced`. This is analogous to iced's application trait with the only
+/// change being that the usual `new()` function now additionally takes a `Arc<dyn GuiContext>`
+/// that the editor can store to interact with the parameters. The editor should have a
+/// `Arc<impl Params>` as part of their [`InitializationFlags`][Self::InitializationFlags] so it can
+/// read the current parameter values.
 pub trait IcedEditor: 'static + Send + Sync + Sized {
-    /// See [`Application::Executor`]. You'll likely want to use [`crate::executor::Default`].
+    /// The executor used to run [`Task`]s and [`Subscription`]s. You'll likely want to use
+    /// [`executor::Default`].
     type Executor: Executor;
-    /// See [`Application::Message`]. You should have one variant containing a [`ParamMessage`].
+    /// The editor's message type. You should have one variant containing a [`ParamMessage`].
     type Message: 'static + Clone + Debug + Send;
-    /// See [`Application::Flags`].
+    /// The data passed to [`new()`][Self::new()] when the editor is opened.
     type InitializationFlags: 'static + Clone + Send + Sync;
 
-    /// See [`Application::new`]. This also receivs the GUI context in addition to the flags.
+    /// Create the editor's state. This also receivs the GUI context in addition to the flags.
     fn new(
         initialization_fags: Self::InitializationFlags,
         context: Arc<dyn GuiContext>,
-    ) -> (Self, Command<Self::Message>);
+    ) -> (Self, Task<Self::Message>);
 
     /// Returns a reference to the GUI context.
     /// [`handle_param_message()`][Self::handle_param_message()] uses this to interact with the
     /// parameters.
     fn context(&self) -> &dyn GuiContext;
 
-    /// See [`Application::update`]. When receiving the variant that contains a
-    /// [`widgets::ParamMessage`] you can call
-    /// [`handle_param_message()`][Self::handle_param_message()] to handle the parameter update.
-    fn update(
-        &mut self,
-        window: &mut WindowQueue,
-        message: Self::Message,
-    ) -> Command<Self::Message>;
-
-    /// See [`Application::subscription`].
-    fn subscription(
-        &self,
-        _window_subs: &mut WindowSubs<Self::Message>,
-    ) -> Subscription<Self::Message> {
+    /// Handle a message. When receiving the variant that contains a [`widgets::ParamMessage`] you
+    /// can call [`handle_param_message()`][Self::handle_param_message()] to handle the parameter
+    /// update.
+    fn update(&mut self, message: Self::Message) -> Task<Self::Message>;
+
+    /// Subscriptions for external events the editor wants to listen to. The editor is
+    /// automatically redrawn when a parameter changes, so this doesn't need to be used for that.
+    fn subscription(&self) -> Subscription<Self::Message> {
         Subscription::none()
     }
 
-    /// See [`Application::view`].
-    fn view(&mut self) -> Element<'_, Self::Message>;
+    /// Build the editor's widgets from its current state.
+    fn view(&self) -> Element<'_, Self::Message>;
 
-    /// See [`Application::background_color`].
-    fn background_color(&self) -> Color {
-        Color::WHITE
+    /// The theme used to style the editor's widgets. This also determines the window's background
+    /// color. Use [`Theme::custom()`] to change the theme's colors.
+    fn theme(&self) -> Theme {
+        Theme::Light
     }
 
-    /// See [`Application::scale_policy`].
+    /// The window's scale policy.
     ///
     /// TODO: Is this needed? Editors shouldn't change the scale policy.
     fn scale_policy(&self) -> WindowScalePolicy {
         WindowScalePolicy::SystemScaleFactor
     }
 
-    /// See [`Application::renderer_settings`].
-    fn renderer_settings() -> iced_baseview::backend::settings::Settings {
-        iced_baseview::backend::settings::Settings {
+    /// The fonts that should be loaded when the editor is opened. These can then be used through
+    /// [`Font::with_name()`]. Defaults to all of the Noto Sans fonts in [`assets`].
+    fn fonts() -> Vec<Cow<'static, [u8]>> {
+        assets::NOTO_SANS_FONTS
+            .iter()
+            .map(|font| Cow::Borrowed(*font))
+            .collect()
+    }
+
+    /// The renderer's settings.
+    fn renderer_settings() -> iced_graphics::Settings {
+        iced_graphics::Settings {
             // Enable some anti-aliasing by default. Since GUIs are likely very simple and most of
             // the work will be on the CPU anyways this should not affect performance much.
-            antialiasing: Some(iced_baseview::backend::settings::Antialiasing::MSAAx4),
+            antialiasing: Some(iced_graphics::Antialiasing::MSAAx4),
             // Use Noto Sans as the default font as that renders a bit more cleanly than the default
-            // Lato font. This crate also contains other weights and versions of this font you can
-            // use for individual widgets.
-            default_font: Some(crate::assets::fonts::NOTO_SANS_REGULAR),
-            ..iced_baseview::backend::settings::Settings::default()
+            // font. This crate also contains other weights and versions of this font you can use
+            // for individual widgets.
+            default_font: assets::NOTO_SANS_REGULAR,
+            ..iced_graphics::Settings::default()
         }
     }
 
@@ -230,6 +256,23 @@ pub trait IcedEditor: 'static + Send + Sync + Sized {
                 context.raw_set_parameter_normalized(p, v)
             },
             ParamMessage::EndSetParameter(p) => unsafe { context.raw_end_set_parameter(p) },
+            ParamMessage::ShowParamContextMenu(p, position) => {
+                context.show_param_context_menu(p, position);
+            }
+            ParamMessage::CopyToClipboard(p) => unsafe {
+                let value = p.normalized_value_to_string(p.modulated_normalized_value(), true);
+                context.set_clipboard_text(&value);
+            },
+            ParamMessage::PasteFromClipboard(p) => unsafe {
+                if let Some(normalized_value) = context
+                    .clipboard_text()
+                    .and_then(|text| p.string_to_normalized_value(&text))
+                {
+                    context.raw_begin_set_parameter(p);
+                    context.raw_set_parameter_normalized(p, normalized_value);
+                    context.raw_end_set_parameter(p);
+                }
+            },
         }
     }
 }
@@ -240,6 +283,25 @@ pub struct IcedState {
     /// The window's size in logical pixels before applying `scale_factor`.
     #[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]
     size: AtomicCell<(u32, u32)>,
+    /// The constraints for resizing the window, or `None` if the window cannot be resized.
+    #[serde(skip)]
+    size_constraints: Option<EditorSizeConstraints>,
+    /// A new size in logical pixels requested from within the GUI, for instance using the
+    /// [`ResizeHandle`][widgets::ResizeHandle] widget. The editor will ask the host to resize the
+    /// window to this size.
+    #[serde(skip)]
+    requested_size: AtomicCell<Option<(u32, u32)>>,
+    /// A new size in logical pixels set by the host. The editor's window will be resized to match
+    /// this.
+    #[serde(skip)]
+    pending_window_size: AtomicCell<Option<(u32, u32)>>,
+    /// Wakes up the editor's resize subscription when either of the sizes above has been set.
+    #[serde(skip)]
+    resize_waker: AtomicWaker,
+    /// A UI scale chosen by the user that overrides the host's DPI scaling factor. See
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    #[serde(default, with = "nih_plug::params::persist::serialize_atomic_cell")]
+    ui_scale: AtomicCell<Option<f32>>,
     /// Whether the editor's window is currently open.
     #[serde(skip)]
     open: AtomicBool,
@@ -248,6 +310,7 @@ pub struct IcedState {
 impl<'a> PersistentField<'a, IcedState> for Arc<IcedState> {
     fn set(&self, new_value: IcedState) {
         self.size.store(new_value.size.load());
+        self.ui_scale.store(new_value.ui_scale.load());
     }
 
     fn map<F, R>(&self, f: F) -> R
@@ -264,6 +327,31 @@ impl IcedState {
     pub fn from_size(width: u32, height: u32) -> Arc<IcedState> {
         Arc::new(IcedState {
             size: AtomicCell::new((width, height)),
+            size_constraints: None,
+            requested_size: AtomicCell::new(None),
+            pending_window_size: AtomicCell::new(None),
+            resize_waker: AtomicWaker::new(),
+            ui_scale: AtomicCell::new(None),
+            open: AtomicBool::new(false),
+        })
+    }
+
+    /// The same as [`from_size()`][Self::from_size()], but the window can be resized by the user
+    /// within the specified constraints. The initial size is constrained to fit within them. Store
+    /// this state in a `#[persist = "key"]` field on your parameters struct to restore the chosen
+    /// size when the plugin's state gets loaded.
+    pub fn from_size_resizable(
+        width: u32,
+        height: u32,
+        size_constraints: EditorSizeConstraints,
+    ) -> Arc<IcedState> {
+        Arc::new(IcedState {
+            size: AtomicCell::new(size_constraints.constrain((width, height))),
+            size_constraints: Some(size_constraints),
+            requested_size: AtomicCell::new(None),
+            pending_window_size: AtomicCell::new(None),
+            resize_waker: AtomicWaker::new(),
+            ui_scale: AtomicCell::new(None),
             open: AtomicBool::new(false),
         })
     }
@@ -273,6 +361,34 @@ impl IcedState {
         self.size.load()
     }
 
+    /// Returns the constraints for resizing the GUI, or `None` if the GUI cannot be resized.
+    pub fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        self.size_constraints
+    }
+
+    /// Ask the host to resize the GUI to a new size in logical pixels. The size is constrained
+    /// using [`size_constraints()`][Self::size_constraints()]. This does nothing if the GUI cannot
+    /// be resized.
+    pub fn set_requested_size(&self, new_size: (u32, u32)) {
+        if let Some(size_constraints) = self.size_constraints {
+            self.requested_size
+                .store(Some(size_constraints.constrain(new_size)));
+            self.resize_waker.wake();
+        }
+    }
+
+    /// Resize the open window to match a size set by the host.
+    pub(crate) fn set_pending_window_size(&self, new_size: (u32, u32)) {
+        self.pending_window_size.store(Some(new_size));
+        self.resize_waker.wake();
+    }
+
+    /// Returns the UI scale chosen by the user, if any. This is set through
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    pub fn ui_scale(&self) -> Option<f32> {
+        self.ui_scale.load()
+    }
+
     /// Whether the GUI is currently visible.
     // Called `is_open()` instead of `open()` to avoid the ambiguity.
     pub fn is_open(&self) -> bool {
@@ -280,5 +396,19 @@ impl IcedState {
     }
 }
 
-/// A marker struct to indicate that a parameter update has happened.
-pub(crate) struct ParameterUpdate;
+/// Used to notify the [`IcedEditor`] that a parameter has changed. This only stores _that_ a
+/// parameter update has happened and not which parameter, since the entire GUI needs to be redrawn
+/// either way.
+#[derive(Default)]
+pub(crate) struct ParameterUpdates {
+    pub(crate) pending: AtomicBool,
+    pub(crate) waker: AtomicWaker,
+}
+
+impl ParameterUpdates {
+    /// Mark that a parameter has changed, and wake up the editor's subscription if it's waiting.
+    pub fn notify(&self) {
+        self.pending.store(true, Ordering::Release);
+        self.waker.wake();
+    }
+}
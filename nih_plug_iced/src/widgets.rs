@@ -7,13 +7,29 @@
 
 use nih_plug::prelude::ParamPtr;
 
+pub mod ab_toggle;
+pub mod curve_editor;
+pub mod eq_curve;
 pub mod generic_ui;
+pub mod modulation_matrix;
+pub mod oscilloscope;
 pub mod param_slider;
 pub mod peak_meter;
+pub mod preset_browser;
+pub mod resize_handle;
+pub mod spectrum;
 pub mod util;
 
+pub use ab_toggle::AbToggle;
+pub use curve_editor::CurveEditor;
+pub use eq_curve::EqCurve;
+pub use modulation_matrix::ModulationMatrixEditor;
+pub use oscilloscope::Oscilloscope;
 pub use param_slider::ParamSlider;
 pub use peak_meter::PeakMeter;
+pub use preset_browser::PresetBrowser;
+pub use resize_handle::ResizeHandle;
+pub use spectrum::Spectrum;
 
 /// A message to update a parameter value. Since NIH-plug manages the parameters, interacting with
 /// parameter values with iced works a little different from updating any other state. This main
@@ -29,4 +45,14 @@ pub enum ParamMessage {
     SetParameterNormalized(ParamPtr, f32),
     /// End an automation gesture for a parameter.
     EndSetParameter(ParamPtr),
+    /// Ask the host to show its context menu for a parameter at a position in the editor window.
+    /// See [`GuiContext::show_param_context_menu()`][nih_plug::prelude::GuiContext::show_param_context_menu()].
+    ShowParamContextMenu(ParamPtr, (f32, f32)),
+    /// Copy a parameter's current value to the clipboard as a string. See
+    /// [`GuiContext::set_clipboard_text()`][nih_plug::prelude::GuiContext::set_clipboard_text()].
+    CopyToClipboard(ParamPtr),
+    /// Set a parameter from the clipboard's contents as a single automation gesture. Does nothing
+    /// if the clipboard does not contain a valid value for the parameter. See
+    /// [`GuiContext::clipboard_text()`][nih_plug::prelude::GuiContext::clipboard_text()].
+    PasteFromClipboard(ParamPtr),
 }
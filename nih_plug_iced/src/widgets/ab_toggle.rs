@@ -0,0 +1,107 @@
+//! A toggle for comparing two versions of the plugin's state.
+
+use nih_plug::prelude::{GuiContext, StateSlot};
+
+use crate::widget::button;
+use crate::{Background, Border, Button, Color, Element, Pixels, Row, Text, Theme};
+
+/// A toggle for comparing two versions of the plugin's state. Clicking on the inactive slot stores
+/// the current state in the active slot and restores the other one, and the copy button copies the
+/// A slot to the B slot. The editor should pass the [`AbMessage`]s emitted by this widget to
+/// [`AbMessage::apply()`]. See [`GuiContext::swap_ab()`].
+pub struct AbToggle {
+    active_slot: StateSlot,
+
+    text_size: Option<Pixels>,
+    spacing: Pixels,
+}
+
+/// A message emitted by an [`AbToggle`]. These should be handled with [`AbMessage::apply()`].
+#[derive(Debug, Clone, Copy)]
+pub enum AbMessage {
+    /// Switch to the other state slot.
+    Swap,
+    /// Copy the A slot's state to the B slot.
+    CopyAToB,
+}
+
+impl AbMessage {
+    /// Apply this message using the editor's [`GuiContext`].
+    pub fn apply(self, context: &dyn GuiContext) {
+        match self {
+            AbMessage::Swap => {
+                context.swap_ab();
+            }
+            AbMessage::CopyAToB => context.copy_a_to_b(),
+        }
+    }
+}
+
+/// The style for the A and B buttons. The active slot is highlighted.
+fn slot_style(theme: &Theme, status: button::Status, active: bool) -> button::Style {
+    button::Style {
+        background: if active {
+            Some(Background::Color(Color::from_rgb(0.8, 0.8, 1.0)))
+        } else {
+            None
+        },
+        border: Border {
+            color: Color::BLACK,
+            width: 1.0,
+            radius: 2.0.into(),
+        },
+        ..button::secondary(theme, status)
+    }
+}
+
+impl AbToggle {
+    /// Creates a new [`AbToggle`] that highlights the context's active state slot.
+    pub fn new(context: &dyn GuiContext) -> Self {
+        Self {
+            active_slot: context.active_state_slot(),
+
+            text_size: None,
+            spacing: Pixels(4.0),
+        }
+    }
+
+    /// Sets the text size of the [`AbToggle`].
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Sets the spacing between the [`AbToggle`]'s buttons.
+    pub fn spacing(mut self, spacing: impl Into<Pixels>) -> Self {
+        self.spacing = spacing.into();
+        self
+    }
+}
+
+impl<'a> From<AbToggle> for Element<'a, AbMessage> {
+    fn from(toggle: AbToggle) -> Self {
+        let text_size = toggle.text_size;
+        let text = move |label: &'static str| match text_size {
+            Some(size) => Text::new(label).size(size),
+            None => Text::new(label),
+        };
+        let active_slot = toggle.active_slot;
+        let slot_button = |slot: StateSlot, label: &'static str| {
+            let active = active_slot == slot;
+            let button = Button::new(text(label))
+                .style(move |theme, status| slot_style(theme, status, active));
+            if active {
+                button
+            } else {
+                button.on_press(AbMessage::Swap)
+            }
+        };
+
+        Row::new()
+            .spacing(toggle.spacing)
+            .push(slot_button(StateSlot::A, "A"))
+            .push(slot_button(StateSlot::B, "B"))
+            .push(Button::new(text("A → B")).on_press(AbMessage::CopyAToB))
+            .into()
+    }
+}
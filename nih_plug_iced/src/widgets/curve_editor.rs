@@ -0,0 +1,359 @@
+//! An editor for [`SharedCurve`]s.
+
+use nih_plug::util::curve::{CurveShape, SharedCurve};
+
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::widget::{tree, Tree};
+use crate::advanced::{Clipboard, Shell, Widget};
+use crate::{
+    event, mouse, Background, Border, Color, Element, Event, Length, Point, Rectangle, Renderer,
+    Size, Theme,
+};
+
+use super::util;
+
+/// The thickness of this widget's borders.
+const BORDER_WIDTH: f32 = 1.0;
+/// The thickness of the curve's line.
+const LINE_WIDTH: f32 = 1.5;
+/// The radius of the breakpoints' handles.
+const POINT_RADIUS: f32 = 4.0;
+/// The maximum distance in pixels between the cursor and a breakpoint for clicks to hit it.
+const POINT_HIT_DISTANCE: f32 = 8.0;
+
+/// An editor for a [`SharedCurve`]'s breakpoints. Changes are written to the curve directly, so
+/// the audio thread picks them up immediately.
+///
+/// - Clicking on an empty spot adds a new breakpoint, which can then immediately be dragged.
+/// - Breakpoints can be moved by dragging them.
+/// - Double clicking or right clicking on a breakpoint removes it.
+/// - Right clicking on a segment cycles through that segment's [`CurveShape`]s.
+///
+/// TODO: There are currently no styling options at all
+pub struct CurveEditor<'a, Message> {
+    curve: &'a SharedCurve,
+    /// A message that's published whenever the curve has been changed.
+    on_change: Option<Message>,
+
+    height: Length,
+    width: Length,
+}
+
+/// State for a [`CurveEditor`]. This is stored in iced's widget tree.
+#[derive(Debug, Default)]
+struct State {
+    /// The index of the breakpoint that's currently being dragged, if any.
+    drag_point: Option<usize>,
+    /// Track clicks for double clicks.
+    last_click: Option<mouse::Click>,
+}
+
+impl<'a, Message> CurveEditor<'a, Message> {
+    /// Creates a new [`CurveEditor`] for a curve.
+    pub fn new(curve: &'a SharedCurve) -> Self {
+        Self {
+            curve,
+            on_change: None,
+
+            width: Length::Fixed(240.0),
+            height: Length::Fixed(120.0),
+        }
+    }
+
+    /// Publish a message whenever the curve gets changed.
+    pub fn on_change(mut self, message: Message) -> Self {
+        self.on_change = Some(message);
+        self
+    }
+
+    /// Sets the width of the [`CurveEditor`].
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Sets the height of the [`CurveEditor`].
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// The position of a point within the editor's bounds. Higher values are drawn higher up.
+    fn point_position(bounds: &Rectangle, x: f32, y: f32) -> Point {
+        Point::new(
+            util::remap_rect_x_t(bounds, x),
+            util::remap_rect_y_t(bounds, 1.0 - y),
+        )
+    }
+
+    /// The curve coordinates corresponding to a position within the editor's bounds.
+    fn curve_coordinates(bounds: &Rectangle, position: Point) -> (f32, f32) {
+        (
+            util::remap_rect_x_coordinate(bounds, position.x),
+            1.0 - util::remap_rect_y_coordinate(bounds, position.y),
+        )
+    }
+
+    /// The index of the breakpoint closest to the cursor, if the cursor is close enough to hit it.
+    fn point_at(&self, bounds: &Rectangle, cursor_position: Point) -> Option<usize> {
+        self.curve
+            .load()
+            .points()
+            .iter()
+            .enumerate()
+            .map(|(idx, point)| {
+                let position = Self::point_position(bounds, point.x, point.y);
+                (idx, position.distance(cursor_position))
+            })
+            .filter(|(_, distance)| *distance <= POINT_HIT_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+}
+
+impl<'a, Message> Widget<Message, Theme, Renderer> for CurveEditor<'a, Message>
+where
+    Message: Clone,
+{
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(self.width, self.height)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.width, self.height)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+
+        // Compensate for the border when handling these events
+        let bounds = layout.bounds().shrink(BORDER_WIDTH);
+
+        let changed = match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
+                if cursor.is_over(bounds) =>
+            {
+                let Some(cursor_position) = cursor.position() else {
+                    return event::Status::Ignored;
+                };
+                let click =
+                    mouse::Click::new(cursor_position, mouse::Button::Left, state.last_click);
+                state.last_click = Some(click);
+
+                match self.point_at(&bounds, cursor_position) {
+                    Some(idx) if matches!(click.kind(), mouse::click::Kind::Double) => {
+                        state.drag_point = None;
+                        self.curve.update(|curve| curve.remove_point(idx));
+                    }
+                    Some(idx) => state.drag_point = Some(idx),
+                    None => {
+                        let (x, y) = Self::curve_coordinates(&bounds, cursor_position);
+                        let idx = self.curve.update(|curve| curve.add_point(x, y));
+                        state.drag_point = Some(idx);
+                    }
+                }
+
+                true
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right))
+                if cursor.is_over(bounds) =>
+            {
+                let Some(cursor_position) = cursor.position() else {
+                    return event::Status::Ignored;
+                };
+                state.drag_point = None;
+                match self.point_at(&bounds, cursor_position) {
+                    Some(idx) => {
+                        self.curve.update(|curve| curve.remove_point(idx));
+                    }
+                    None => {
+                        // The segment under the cursor starts at the last point before the cursor
+                        let (x, _) = Self::curve_coordinates(&bounds, cursor_position);
+                        self.curve.update(|curve| {
+                            let idx = curve.points().partition_point(|point| point.x <= x);
+                            if idx > 0 && idx < curve.points().len() {
+                                let shape = curve.points()[idx - 1].shape;
+                                let shape_idx = CurveShape::ALL
+                                    .iter()
+                                    .position(|s| *s == shape)
+                                    .unwrap_or(0);
+                                curve.set_shape(
+                                    idx - 1,
+                                    CurveShape::ALL[(shape_idx + 1) % CurveShape::ALL.len()],
+                                );
+                            }
+                        });
+                    }
+                }
+
+                true
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.drag_point.take().is_some() {
+                    return event::Status::Captured;
+                }
+
+                false
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => match state.drag_point {
+                Some(idx) => {
+                    let (x, y) = Self::curve_coordinates(&bounds, position);
+                    self.curve.update(|curve| curve.move_point(idx, x, y));
+
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+
+        if changed {
+            if let Some(message) = &self.on_change {
+                shell.publish(message.clone());
+            }
+
+            event::Status::Captured
+        } else {
+            event::Status::Ignored
+        }
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        if state.drag_point.is_some() {
+            mouse::Interaction::Grabbing
+        } else if let Some(cursor_position) = cursor.position_over(bounds) {
+            if self.point_at(&bounds, cursor_position).is_some() {
+                mouse::Interaction::Grab
+            } else {
+                mouse::Interaction::Crosshair
+            }
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: Color::BLACK,
+                    width: BORDER_WIDTH,
+                    radius: 0.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            Background::Color(Color::TRANSPARENT),
+        );
+
+        let bounds_without_borders = bounds.shrink(BORDER_WIDTH);
+
+        // There's no way to draw arbitrary paths without a canvas, so the curve is drawn as a
+        // series of one pixel wide quads connecting the curve's value in each pixel column
+        let curve = self.curve.load();
+        let columns = bounds_without_borders.width.floor().max(1.0) as usize;
+        let mut previous_y: Option<f32> = None;
+        for column in 0..columns {
+            let x = column as f32 / (columns - 1).max(1) as f32;
+            let position = Self::point_position(&bounds_without_borders, x, curve.value_at(x));
+            let (top, bottom) = match previous_y {
+                Some(previous_y) => (position.y.min(previous_y), position.y.max(previous_y)),
+                None => (position.y, position.y),
+            };
+            previous_y = Some(position.y);
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: position.x - (LINE_WIDTH / 2.0),
+                        y: top - (LINE_WIDTH / 2.0),
+                        width: LINE_WIDTH,
+                        height: (bottom - top) + LINE_WIDTH,
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(style.text_color),
+            );
+        }
+
+        for (idx, point) in curve.points().iter().enumerate() {
+            let position = Self::point_position(&bounds_without_borders, point.x, point.y);
+            let fill_color = if state.drag_point == Some(idx) {
+                style.text_color
+            } else {
+                Color::WHITE
+            };
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: position.x - POINT_RADIUS,
+                        y: position.y - POINT_RADIUS,
+                        width: POINT_RADIUS * 2.0,
+                        height: POINT_RADIUS * 2.0,
+                    },
+                    border: Border {
+                        color: style.text_color,
+                        width: BORDER_WIDTH,
+                        radius: POINT_RADIUS.into(),
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(fill_color),
+            );
+        }
+    }
+}
+
+impl<'a, Message> From<CurveEditor<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a + Clone,
+{
+    fn from(widget: CurveEditor<'a, Message>) -> Self {
+        Element::new(widget)
+    }
+}
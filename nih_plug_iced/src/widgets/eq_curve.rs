@@ -0,0 +1,546 @@
+//! A display for an equalizer's combined magnitude response with draggable band handles.
+
+use atomic_refcell::AtomicRefCell;
+use nih_plug::prelude::{FloatParam, Param};
+use nih_plug::util::AnalysisOutput;
+
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::widget::{tree, Tree};
+use crate::advanced::{Clipboard, Shell, Widget};
+use crate::{
+    event, keyboard, mouse, Background, Border, Color, Element, Event, Length, Point, Rectangle,
+    Renderer, Size, Theme,
+};
+
+use super::util;
+use super::ParamMessage;
+
+/// The filter coefficients used in [`EqCurveData`]. These can be computed with the constructors on
+/// this type, and they can also be used directly with NIH-plug's
+/// [`Biquad`][nih_plug::dsp::filter::Biquad] filter.
+pub use nih_plug::dsp::filter::BiquadCoefficients;
+
+/// The thickness of this widget's borders.
+const BORDER_WIDTH: f32 = 1.0;
+/// The thickness of the response curve's line.
+const LINE_WIDTH: f32 = 1.5;
+/// The radius of the bands' handles.
+const HANDLE_RADIUS: f32 = 5.0;
+/// The maximum distance in pixels between the cursor and a handle for clicks to hit it.
+const HANDLE_HIT_DISTANCE: f32 = 10.0;
+/// The number of pixels of smooth scrolling that corresponds to a single scroll wheel line.
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+/// The filters that make up the equalizer, sent from the audio thread to the editor through an
+/// [`AnalysisChannel`][nih_plug::util::AnalysisChannel]. Create the channel with
+/// `AnalysisChannel::new(EqCurveData::with_capacity(num_bands)).split()`, keep the input half in
+/// the plugin, and pass the output half to the editor. The sample rate the coefficients were
+/// computed for is set on the input with `AnalysisInput::set_sample_rate()`. To avoid allocations
+/// on the audio thread, the `bands` vector should be cleared and refilled in place using
+/// `AnalysisInput::input_buffer()` followed by `AnalysisInput::publish()`.
+#[derive(Debug, Clone, Default)]
+pub struct EqCurveData {
+    /// The coefficients for all of the equalizer's active filters. The response of all of these
+    /// filters is combined into a single curve.
+    pub bands: Vec<BiquadCoefficients>,
+}
+
+/// The parameters controlled by one of the [`EqCurve`]'s handles.
+#[derive(Debug, Clone, Copy)]
+pub struct EqBand<'a> {
+    /// The band's frequency in Hertz. Dragging the handle horizontally changes this parameter.
+    pub frequency: &'a FloatParam,
+    /// The band's gain in decibel. Dragging the handle vertically changes this parameter. Bands
+    /// without a gain parameter, like high-pass filters, are drawn at 0 dB.
+    pub gain: Option<&'a FloatParam>,
+    /// The band's Q or bandwidth. Scrolling over the handle changes this parameter.
+    pub q: Option<&'a FloatParam>,
+}
+
+/// Displays the combined magnitude response of a set of biquad filters on a logarithmic frequency
+/// axis, with a handle for each of the equalizer's bands. Dragging a handle changes the band's
+/// frequency and gain parameters, scrolling over a handle changes its Q parameter, and double
+/// clicking on a handle resets those parameters to their default values.
+///
+/// TODO: There are currently no styling options at all
+pub struct EqCurve<'a> {
+    data: &'a AtomicRefCell<AnalysisOutput<EqCurveData>>,
+    bands: Vec<EqBand<'a>>,
+
+    frequency_range: (f32, f32),
+    gain_range_db: f32,
+
+    height: Length,
+    width: Length,
+}
+
+/// State for an [`EqCurve`]. This is stored in iced's widget tree.
+#[derive(Debug, Default)]
+struct State {
+    keyboard_modifiers: keyboard::Modifiers,
+    /// The index of the band whose handle is currently being dragged, if any.
+    drag_band: Option<usize>,
+    /// Track clicks for double clicks.
+    last_click: Option<mouse::Click>,
+    /// The number of (fractional) scrolled lines that have not yet been turned into parameter
+    /// change events. This is needed to support trackpads with smooth scrolling.
+    scrolled_lines: f32,
+}
+
+impl EqCurveData {
+    /// Create an empty data object with room for `num_bands` filters.
+    pub fn with_capacity(num_bands: usize) -> Self {
+        Self {
+            bands: Vec::with_capacity(num_bands),
+        }
+    }
+
+    /// The combined magnitude response of all bands at a frequency in Hertz, in decibel, for
+    /// coefficients computed at `sample_rate`.
+    pub fn magnitude_db(&self, sample_rate: f32, frequency: f32) -> f32 {
+        self.bands
+            .iter()
+            .map(|band| nih_plug::util::gain_to_db(band.magnitude(sample_rate, frequency)))
+            .sum()
+    }
+}
+
+impl<'a> EqCurve<'a> {
+    /// Creates a new [`EqCurve`] that displays the filters read from `data` with a handle for each
+    /// band in `bands`.
+    pub fn new(
+        data: &'a AtomicRefCell<AnalysisOutput<EqCurveData>>,
+        bands: Vec<EqBand<'a>>,
+    ) -> Self {
+        Self {
+            data,
+            bands,
+
+            frequency_range: (20.0, 20_000.0),
+            gain_range_db: 24.0,
+
+            width: Length::Fixed(400.0),
+            height: Length::Fixed(200.0),
+        }
+    }
+
+    /// Sets the displayed frequency range in Hertz. Defaults to 20 Hz to 20 kHz.
+    pub fn frequency_range(mut self, min: f32, max: f32) -> Self {
+        self.frequency_range = (min, max);
+        self
+    }
+
+    /// Sets the displayed gain range. The curve is drawn between `-range_db` and `range_db`.
+    /// Defaults to 24 dB.
+    pub fn gain_range(mut self, range_db: f32) -> Self {
+        self.gain_range_db = range_db;
+        self
+    }
+
+    /// Sets the width of the [`EqCurve`].
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Sets the height of the [`EqCurve`].
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Map a frequency in Hertz to a `[0, 1]` position on the logarithmic frequency axis.
+    fn frequency_to_t(&self, frequency: f32) -> f32 {
+        let (min, max) = self.frequency_range;
+        (frequency.max(f32::MIN_POSITIVE) / min).ln() / (max / min).ln()
+    }
+
+    /// The inverse of [`frequency_to_t()`][Self::frequency_to_t()].
+    fn t_to_frequency(&self, t: f32) -> f32 {
+        let (min, max) = self.frequency_range;
+        min * (max / min).powf(t)
+    }
+
+    /// Map a gain in decibel to a `[0, 1]` position where 0 is the top of the widget.
+    fn gain_to_t(&self, gain_db: f32) -> f32 {
+        0.5 - (gain_db / (self.gain_range_db * 2.0))
+    }
+
+    /// The inverse of [`gain_to_t()`][Self::gain_to_t()].
+    fn t_to_gain(&self, t: f32) -> f32 {
+        (0.5 - t) * self.gain_range_db * 2.0
+    }
+
+    /// The position of a band's handle within the widget's bounds.
+    fn handle_position(&self, bounds: &Rectangle, band: &EqBand) -> Point {
+        Point::new(
+            util::remap_rect_x_t(bounds, self.frequency_to_t(band.frequency.value())),
+            util::remap_rect_y_t(
+                bounds,
+                self.gain_to_t(band.gain.map(|gain| gain.value()).unwrap_or(0.0)),
+            ),
+        )
+    }
+
+    /// The index of the band whose handle is closest to the cursor, if the cursor is close enough
+    /// to hit it.
+    fn band_at(&self, bounds: &Rectangle, cursor_position: Point) -> Option<usize> {
+        self.bands
+            .iter()
+            .enumerate()
+            .map(|(idx, band)| {
+                (
+                    idx,
+                    self.handle_position(bounds, band).distance(cursor_position),
+                )
+            })
+            .filter(|(_, distance)| *distance <= HANDLE_HIT_DISTANCE)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(idx, _)| idx)
+    }
+
+    /// Move the dragged band's handle to the cursor's position. The gestures for the band's
+    /// frequency and gain parameters need to be started before calling this function.
+    fn drag_band_to(
+        &self,
+        shell: &mut Shell<'_, ParamMessage>,
+        bounds: &Rectangle,
+        band: &EqBand,
+        cursor_position: Point,
+    ) {
+        let frequency =
+            self.t_to_frequency(util::remap_rect_x_coordinate(bounds, cursor_position.x));
+        Self::set_plain_value(shell, band.frequency, frequency);
+
+        if let Some(gain) = band.gain {
+            let gain_db = self.t_to_gain(util::remap_rect_y_coordinate(bounds, cursor_position.y));
+            Self::set_plain_value(shell, gain, gain_db);
+        }
+    }
+
+    /// Set a parameter to a new plain value if that would change the parameter's value. The begin-
+    /// and end set parameter messages need to be sent before calling this function.
+    fn set_plain_value(shell: &mut Shell<'_, ParamMessage>, param: &FloatParam, plain_value: f32) {
+        let normalized_value = param.preview_normalized(plain_value);
+        if normalized_value != param.unmodulated_normalized_value() {
+            shell.publish(ParamMessage::SetParameterNormalized(
+                param.as_ptr(),
+                normalized_value,
+            ));
+        }
+    }
+
+    /// Reset a parameter to its default value as a single automation gesture.
+    fn reset_param(shell: &mut Shell<'_, ParamMessage>, param: &FloatParam) {
+        shell.publish(ParamMessage::BeginSetParameter(param.as_ptr()));
+        Self::set_plain_value(shell, param, param.default_plain_value());
+        shell.publish(ParamMessage::EndSetParameter(param.as_ptr()));
+    }
+
+    /// Move a parameter `steps` steps up or down as a single automation gesture.
+    fn step_param(
+        shell: &mut Shell<'_, ParamMessage>,
+        param: &FloatParam,
+        steps: i32,
+        use_finer_steps: bool,
+    ) {
+        if steps == 0 {
+            return;
+        }
+
+        let mut current_value = param.unmodulated_normalized_value();
+        for _ in 0..steps.unsigned_abs() {
+            current_value = if steps > 0 {
+                param.next_normalized_step(current_value, use_finer_steps)
+            } else {
+                param.previous_normalized_step(current_value, use_finer_steps)
+            };
+        }
+
+        shell.publish(ParamMessage::BeginSetParameter(param.as_ptr()));
+        Self::set_plain_value(shell, param, param.preview_plain(current_value));
+        shell.publish(ParamMessage::EndSetParameter(param.as_ptr()));
+    }
+
+    /// Convert this [`EqCurve`] into an [`Element`] with the correct message. You should have a
+    /// variant on your own message type that wraps around [`ParamMessage`] so you can forward those
+    /// messages to
+    /// [`IcedEditor::handle_param_message()`][crate::IcedEditor::handle_param_message()].
+    pub fn map<Message, F>(self, f: F) -> Element<'a, Message>
+    where
+        Message: 'a,
+        F: Fn(ParamMessage) -> Message + 'a,
+    {
+        Element::from(self).map(f)
+    }
+}
+
+impl<'a> Widget<ParamMessage, Theme, Renderer> for EqCurve<'a> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(self.width, self.height)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.width, self.height)
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        shell: &mut Shell<'_, ParamMessage>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+
+        // Compensate for the border when handling these events
+        let bounds = layout.bounds().shrink(BORDER_WIDTH);
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                let Some(cursor_position) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+                let click =
+                    mouse::Click::new(cursor_position, mouse::Button::Left, state.last_click);
+                state.last_click = Some(click);
+
+                if let Some(idx) = self.band_at(&bounds, cursor_position) {
+                    let band = self.bands[idx];
+                    if state.keyboard_modifiers.command()
+                        || matches!(click.kind(), mouse::click::Kind::Double)
+                    {
+                        // Resetting a band should not let you immediately drag it to a new position
+                        state.drag_band = None;
+
+                        for param in std::iter::once(band.frequency)
+                            .chain(band.gain)
+                            .chain(band.q)
+                        {
+                            Self::reset_param(shell, param);
+                        }
+                    } else {
+                        for param in std::iter::once(band.frequency).chain(band.gain) {
+                            shell.publish(ParamMessage::BeginSetParameter(param.as_ptr()));
+                        }
+                        state.drag_band = Some(idx);
+                    }
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                let Some(cursor_position) = cursor.position_over(bounds) else {
+                    return event::Status::Ignored;
+                };
+
+                // Right clicking on a handle shows the host's context menu for the band's frequency
+                if let Some(idx) = self.band_at(&bounds, cursor_position) {
+                    shell.publish(ParamMessage::ShowParamContextMenu(
+                        self.bands[idx].frequency.as_ptr(),
+                        (cursor_position.x, cursor_position.y),
+                    ));
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if let Some(idx) = state.drag_band.take() {
+                    let band = self.bands[idx];
+                    for param in std::iter::once(band.frequency).chain(band.gain) {
+                        shell.publish(ParamMessage::EndSetParameter(param.as_ptr()));
+                    }
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                if let Some(idx) = state.drag_band {
+                    let band = self.bands[idx];
+                    self.drag_band_to(shell, &bounds, &band, position);
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                let hovered_q = cursor
+                    .position()
+                    .and_then(|cursor_position| self.band_at(&bounds, cursor_position))
+                    .and_then(|idx| self.bands[idx].q);
+                if let Some(q) = hovered_q {
+                    state.scrolled_lines += match delta {
+                        mouse::ScrollDelta::Lines { y, .. } => y,
+                        mouse::ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_SCROLL_LINE,
+                    };
+
+                    let steps = state.scrolled_lines.trunc();
+                    state.scrolled_lines -= steps;
+                    Self::step_param(shell, q, steps as i32, state.keyboard_modifiers.shift());
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
+                state.keyboard_modifiers = modifiers;
+            }
+            _ => {}
+        }
+
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        if state.drag_band.is_some() {
+            mouse::Interaction::Grabbing
+        } else if cursor
+            .position_over(bounds)
+            .is_some_and(|cursor_position| self.band_at(&bounds, cursor_position).is_some())
+        {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: Color::BLACK,
+                    width: BORDER_WIDTH,
+                    radius: 0.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            Background::Color(Color::TRANSPARENT),
+        );
+
+        let bounds_without_borders = bounds.shrink(BORDER_WIDTH);
+
+        // A faint line at 0 dB to make boosts and cuts easier to tell apart
+        let zero_db_y = util::remap_rect_y_t(&bounds_without_borders, self.gain_to_t(0.0));
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds: Rectangle {
+                    x: bounds_without_borders.x,
+                    y: zero_db_y - (BORDER_WIDTH / 2.0),
+                    width: bounds_without_borders.width,
+                    height: BORDER_WIDTH,
+                },
+                ..renderer::Quad::default()
+            },
+            Background::Color(Color::from_rgba(0.5, 0.5, 0.5, 0.5)),
+        );
+
+        // Like the curve editor, the response is drawn as a series of one pixel wide quads
+        // connecting the magnitude response in each pixel column. The data is read once per frame,
+        // so the coefficients stay consistent while drawing.
+        let mut data = self.data.borrow_mut();
+        let frame = data.read_frame();
+        let (curve_data, sample_rate) = (&frame.data, frame.sample_rate);
+        let columns = bounds_without_borders.width.floor().max(1.0) as usize;
+        let mut previous_y: Option<f32> = None;
+        for column in 0..columns {
+            let x_t = column as f32 / (columns - 1).max(1) as f32;
+            let gain_db = if sample_rate > 0.0 {
+                curve_data.magnitude_db(sample_rate, self.t_to_frequency(x_t))
+            } else {
+                0.0
+            };
+            let y = util::remap_rect_y_t(
+                &bounds_without_borders,
+                self.gain_to_t(gain_db).clamp(0.0, 1.0),
+            );
+            let (top, bottom) = match previous_y {
+                Some(previous_y) => (y.min(previous_y), y.max(previous_y)),
+                None => (y, y),
+            };
+            previous_y = Some(y);
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: util::remap_rect_x_t(&bounds_without_borders, x_t) - (LINE_WIDTH / 2.0),
+                        y: top - (LINE_WIDTH / 2.0),
+                        width: LINE_WIDTH,
+                        height: (bottom - top) + LINE_WIDTH,
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(style.text_color),
+            );
+        }
+
+        for (idx, band) in self.bands.iter().enumerate() {
+            let position = self.handle_position(&bounds_without_borders, band);
+            let fill_color = if state.drag_band == Some(idx) {
+                style.text_color
+            } else {
+                Color::WHITE
+            };
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: position.x - HANDLE_RADIUS,
+                        y: position.y - HANDLE_RADIUS,
+                        width: HANDLE_RADIUS * 2.0,
+                        height: HANDLE_RADIUS * 2.0,
+                    },
+                    border: Border {
+                        color: style.text_color,
+                        width: BORDER_WIDTH,
+                        radius: HANDLE_RADIUS.into(),
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(fill_color),
+            );
+        }
+    }
+}
+
+impl<'a> From<EqCurve<'a>> for Element<'a, ParamMessage> {
+    fn from(widget: EqCurve<'a>) -> Self {
+        Element::new(widget)
+    }
+}
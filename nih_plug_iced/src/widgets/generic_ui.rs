@@ -1,48 +1,42 @@
 //! A simple generic UI widget that renders all parameters in a [`Params`] object as a scrollable
 //! list of sliders and labels.
 
-use atomic_refcell::AtomicRefCell;
-use std::borrow::Borrow;
-use std::collections::HashMap;
 use std::marker::PhantomData;
 use std::sync::Arc;
 
 use nih_plug::prelude::{Param, ParamFlags, ParamPtr, Params};
 
 use super::{ParamMessage, ParamSlider};
-use crate::backend::Renderer;
-use crate::text::Renderer as TextRenderer;
 use crate::{
-    alignment, event, layout, renderer, widget, Alignment, Clipboard, Element, Event, Layout,
-    Length, Point, Rectangle, Row, Scrollable, Shell, Space, Text, Widget,
+    alignment, Alignment, Column, Container, Element, Length, Row, Scrollable, Space, Text,
 };
 
+/// The spacing between the rows, and between the labels and the widgets. This and the padding
+/// around the list are based on iced's default 16 pixel text size.
+const SPACING: f32 = 3.0;
+/// The padding around the list of parameters.
+const PADDING: f32 = 8.0;
+
 /// A widget that can be used to create a generic UI with. This is used in conjuction with empty
 /// structs to emulate existential types.
 pub trait ParamWidget {
-    /// The type of state stores by this parameter type.
-    type State: Default;
-
     /// Create an [`Element`] for a widget for the specified parameter.
-    fn into_widget_element<'a, P: Param>(
-        param: &'a P,
-        state: &'a mut Self::State,
-    ) -> Element<'a, ParamMessage>;
+    fn into_widget_element<'a, P: Param>(param: &'a P) -> Element<'a, ParamMessage>;
 
     /// The same as [`into_widget_element()`][Self::into_widget_element()], but for a `ParamPtr`.
     ///
     /// # Safety
     ///
-    /// Undefined behavior of the `ParamPtr` does not point to a valid parameter.
-    unsafe fn into_widget_element_raw<'a>(
-        param: &ParamPtr,
-        state: &'a mut Self::State,
-    ) -> Element<'a, ParamMessage> {
+    /// Undefined behavior of the `ParamPtr` does not point to a valid parameter, or if the
+    /// parameter does not outlive the element.
+    unsafe fn into_widget_element_raw<'a>(param: &ParamPtr) -> Element<'a, ParamMessage> {
         match param {
-            ParamPtr::FloatParam(p) => Self::into_widget_element(&**p, state),
-            ParamPtr::IntParam(p) => Self::into_widget_element(&**p, state),
-            ParamPtr::BoolParam(p) => Self::into_widget_element(&**p, state),
-            ParamPtr::EnumParam(p) => Self::into_widget_element(&**p, state),
+            ParamPtr::FloatParam(p) => Self::into_widget_element(&**p),
+            ParamPtr::IntParam(p) => Self::into_widget_element(&**p),
+            ParamPtr::BoolParam(p) => Self::into_widget_element(&**p),
+            ParamPtr::EnumParam(p) => Self::into_widget_element(&**p),
+            ParamPtr::MacroParam(p) => Self::into_widget_element(&**p),
+            ParamPtr::DynamicParam(p) => Self::into_widget_element(&**p),
         }
     }
 }
@@ -55,9 +49,7 @@ pub struct GenericSlider;
 /// determines what widget to use for this.
 ///
 /// TODO: There's no way to configure the individual widgets.
-pub struct GenericUi<'a, W: ParamWidget> {
-    state: &'a mut State<W>,
-
+pub struct GenericUi<W: ParamWidget> {
     params: Arc<dyn Params>,
 
     width: Length,
@@ -66,29 +58,17 @@ pub struct GenericUi<'a, W: ParamWidget> {
     max_height: u32,
     pad_scrollbar: bool,
 
-    /// We don't emit any messages or store the actual widgets, but iced requires us to define some
-    /// message type anyways.
+    /// We don't store the actual widgets, but we need to know which widget type to use.
     _phantom: PhantomData<W>,
 }
 
-/// State for a [`GenericUi`].
-#[derive(Debug, Default)]
-pub struct State<W: ParamWidget> {
-    /// The internal state for each parameter's widget.
-    scrollable_state: AtomicRefCell<widget::scrollable::State>,
-    /// The internal state for each parameter's widget.
-    widget_state: AtomicRefCell<HashMap<ParamPtr, W::State>>,
-}
-
-impl<'a, W> GenericUi<'a, W>
+impl<W> GenericUi<W>
 where
     W: ParamWidget,
 {
     /// Creates a new [`GenericUi`] for all provided parameters.
-    pub fn new(state: &'a mut State<W>, params: Arc<dyn Params>) -> Self {
+    pub fn new(params: Arc<dyn Params>) -> Self {
         Self {
-            state,
-
             params,
 
             width: Length::Fill,
@@ -102,14 +82,14 @@ where
     }
 
     /// Sets the width of the [`GenericUi`].
-    pub fn width(mut self, width: Length) -> Self {
-        self.width = width;
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
         self
     }
 
     /// Sets the height of the [`GenericUi`].
-    pub fn height(mut self, height: Length) -> Self {
-        self.height = height;
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
         self
     }
 
@@ -131,178 +111,75 @@ where
         self
     }
 
-    /// Create a temporary [`Scrollable`]. This needs to be created on demand because it needs to
-    /// mutably borrow the `Scrollable`'s widget state.
-    fn with_scrollable_widget<T, R, F>(
-        &'a self,
-        scrollable_state: &'a mut widget::scrollable::State,
-        widget_state: &'a mut HashMap<ParamPtr, W::State>,
-        renderer: R,
-        f: F,
-    ) -> T
+    /// Convert this [`GenericUi`] into an [`Element`] with the correct message. You should have a
+    /// variant on your own message type that wraps around [`ParamMessage`] so you can forward those
+    /// messages to
+    /// [`IcedEditor::handle_param_message()`][crate::IcedEditor::handle_param_message()].
+    pub fn map<'a, Message, F>(self, f: F) -> Element<'a, Message>
     where
-        F: FnOnce(Scrollable<'a, ParamMessage>, R) -> T,
-        R: Borrow<Renderer>,
+        Message: 'a,
+        F: Fn(ParamMessage) -> Message + 'a,
     {
-        let text_size = renderer.borrow().default_size();
-        let spacing = (text_size as f32 * 0.2).round() as u16;
-        let padding = (text_size as f32 * 0.5).round() as u16;
-
-        let mut scrollable = Scrollable::new(scrollable_state)
-            .width(self.width)
-            .height(self.height)
-            .max_width(self.max_width)
-            .max_height(self.max_height)
-            .spacing(spacing)
-            .padding(padding)
-            .align_items(Alignment::Center);
-
-        // Make sure we already have widget state for each widget
-        let param_map = self.params.param_map();
-        for (_, param_ptr, _) in &param_map {
-            let flags = unsafe { param_ptr.flags() };
-            if flags.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
-                continue;
-            }
+        Element::from(self).map(f)
+    }
+}
 
-            if !widget_state.contains_key(param_ptr) {
-                widget_state.insert(*param_ptr, Default::default());
-            }
-        }
+impl ParamWidget for GenericSlider {
+    fn into_widget_element<'a, P: Param>(param: &'a P) -> Element<'a, ParamMessage> {
+        ParamSlider::new(param).into()
+    }
+}
 
-        for (_, param_ptr, _) in param_map {
+impl<'a, W> From<GenericUi<W>> for Element<'a, ParamMessage>
+where
+    W: ParamWidget,
+{
+    fn from(generic_ui: GenericUi<W>) -> Self {
+        let mut column = Column::new()
+            .width(Length::Fill)
+            .spacing(SPACING)
+            .padding(PADDING)
+            .align_x(Alignment::Center);
+
+        for (_, param_ptr, _) in generic_ui.params.param_map() {
             let flags = unsafe { param_ptr.flags() };
             if flags.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
                 continue;
             }
 
-            // SAFETY: We only borrow each item once, and the plugin framework statically asserted
-            //         that parameter indices are unique and this widget state cannot outlive this
-            //         function
-            let widget_state: &'a mut W::State =
-                unsafe { &mut *(widget_state.get_mut(&param_ptr).unwrap() as *mut _) };
-
             // Show the label next to the parameter for better use of the space
+            // SAFETY: The parameters are owned by the plugin, and the editor's elements are
+            //         recreated for every view so they cannot outlive the plugin
             let mut row = Row::new()
                 .width(Length::Fill)
-                .align_items(Alignment::Center)
-                .spacing(spacing * 2)
+                .align_y(Alignment::Center)
+                .spacing(SPACING * 2.0)
                 .push(
                     Text::new(unsafe { param_ptr.name() })
-                        .height(20.into())
+                        .height(20)
                         .width(Length::Fill)
-                        .horizontal_alignment(alignment::Horizontal::Right)
-                        .vertical_alignment(alignment::Vertical::Center),
+                        .align_x(alignment::Horizontal::Right)
+                        .align_y(alignment::Vertical::Center),
                 )
-                .push(unsafe { W::into_widget_element_raw(&param_ptr, widget_state) });
-            if self.pad_scrollbar {
+                .push(unsafe { W::into_widget_element_raw(&param_ptr) });
+            if generic_ui.pad_scrollbar {
                 // There's already spacing applied, so this element doesn't actually need to hae any
                 // size of its own
-                row = row.push(Space::with_width(Length::Units(0)));
+                row = row.push(Space::with_width(0));
             }
 
-            scrollable = scrollable.push(row);
+            column = column.push(row);
         }
 
-        f(scrollable, renderer)
-    }
-}
-
-impl<'a, W> Widget<ParamMessage, Renderer> for GenericUi<'a, W>
-where
-    W: ParamWidget,
-{
-    fn width(&self) -> Length {
-        self.width
-    }
-
-    fn height(&self) -> Length {
-        self.height
-    }
-
-    fn layout(&self, renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
-        let mut scrollable_state = self.state.scrollable_state.borrow_mut();
-        let mut widget_state = self.state.widget_state.borrow_mut();
-        self.with_scrollable_widget(
-            &mut scrollable_state,
-            &mut widget_state,
-            renderer,
-            |scrollable, _| scrollable.layout(renderer, limits),
-        )
-    }
-
-    fn draw(
-        &self,
-        renderer: &mut Renderer,
-        style: &renderer::Style,
-        layout: Layout<'_>,
-        cursor_position: Point,
-        viewport: &Rectangle,
-    ) {
-        let mut scrollable_state = self.state.scrollable_state.borrow_mut();
-        let mut widget_state = self.state.widget_state.borrow_mut();
-        self.with_scrollable_widget(
-            &mut scrollable_state,
-            &mut widget_state,
-            renderer,
-            |scrollable, renderer| {
-                scrollable.draw(renderer, style, layout, cursor_position, viewport)
-            },
-        )
-    }
-
-    fn on_event(
-        &mut self,
-        event: Event,
-        layout: Layout<'_>,
-        cursor_position: Point,
-        renderer: &Renderer,
-        clipboard: &mut dyn Clipboard,
-        shell: &mut Shell<'_, ParamMessage>,
-    ) -> event::Status {
-        let mut scrollable_state = self.state.scrollable_state.borrow_mut();
-        let mut widget_state = self.state.widget_state.borrow_mut();
-        self.with_scrollable_widget(
-            &mut scrollable_state,
-            &mut widget_state,
-            renderer,
-            |mut scrollable, _| {
-                scrollable.on_event(event, layout, cursor_position, renderer, clipboard, shell)
-            },
+        Container::new(
+            Scrollable::new(column)
+                .width(Length::Fill)
+                .height(Length::Fill),
         )
-    }
-}
-
-impl ParamWidget for GenericSlider {
-    type State = super::param_slider::State;
-
-    fn into_widget_element<'a, P: Param>(
-        param: &'a P,
-        state: &'a mut Self::State,
-    ) -> Element<'a, ParamMessage> {
-        ParamSlider::new(state, param).into()
-    }
-}
-
-impl<'a, W: ParamWidget> GenericUi<'a, W> {
-    /// Convert this [`GenericUi`] into an [`Element`] with the correct message. You should have a
-    /// variant on your own message type that wraps around [`ParamMessage`] so you can forward those
-    /// messages to
-    /// [`IcedEditor::handle_param_message()`][crate::IcedEditor::handle_param_message()].
-    pub fn map<Message, F>(self, f: F) -> Element<'a, Message>
-    where
-        Message: 'static,
-        F: Fn(ParamMessage) -> Message + 'static,
-    {
-        Element::from(self).map(f)
-    }
-}
-
-impl<'a, W> From<GenericUi<'a, W>> for Element<'a, ParamMessage>
-where
-    W: ParamWidget,
-{
-    fn from(widget: GenericUi<'a, W>) -> Self {
-        Element::new(widget)
+        .width(generic_ui.width)
+        .height(generic_ui.height)
+        .max_width(generic_ui.max_width as f32)
+        .max_height(generic_ui.max_height as f32)
+        .into()
     }
 }
@@ -0,0 +1,189 @@
+//! An editor for a [`ModulationMatrix`]'s routings.
+
+use nih_plug::prelude::{ParamFlags, Params};
+use nih_plug::util::modulation::{
+    ModulationConfig, ModulationMatrix, ModulationRouting, ModulationSource,
+};
+use std::fmt::Display;
+
+use crate::{Alignment, Button, Column, Element, Length, PickList, Pixels, Row, Slider, Text};
+
+/// An editor for a [`ModulationMatrix`]'s routings. Every routing gets a row with its source, its
+/// target, and its depth, and new routings can be added with the button below the list. The
+/// parameters from `params` that are not hidden can be used as modulation targets. The editor
+/// should pass the [`ModulationMessage`]s emitted by this widget to
+/// [`ModulationMessage::apply()`].
+pub struct ModulationMatrixEditor {
+    config: ModulationConfig,
+    targets: Vec<TargetOption>,
+
+    text_size: Option<Pixels>,
+    spacing: Pixels,
+}
+
+/// A message emitted by a [`ModulationMatrixEditor`]. These should be handled with
+/// [`ModulationMessage::apply()`].
+#[derive(Debug, Clone)]
+pub enum ModulationMessage {
+    /// Change the source of the routing at an index.
+    SetSource(usize, ModulationSource),
+    /// Change the target parameter ID of the routing at an index.
+    SetTarget(usize, String),
+    /// Change the depth of the routing at an index.
+    SetDepth(usize, f32),
+    /// Remove the routing at an index.
+    Remove(usize),
+    /// Add a new routing.
+    Add(ModulationRouting),
+}
+
+/// A [`ModulationSource`] in a pick list.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SourceOption(ModulationSource);
+
+/// A modulation target in a pick list.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct TargetOption {
+    id: String,
+    name: String,
+}
+
+impl Display for SourceOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0.name())
+    }
+}
+
+impl Display for TargetOption {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+impl ModulationMessage {
+    /// Apply this message to the modulation matrix.
+    pub fn apply(self, matrix: &ModulationMatrix) {
+        match self {
+            ModulationMessage::SetSource(idx, source) => {
+                update_routing(matrix, idx, |routing| routing.source = source)
+            }
+            ModulationMessage::SetTarget(idx, target) => {
+                update_routing(matrix, idx, |routing| routing.target = target)
+            }
+            ModulationMessage::SetDepth(idx, depth) => {
+                update_routing(matrix, idx, |routing| routing.depth = depth)
+            }
+            ModulationMessage::Remove(idx) => matrix.remove_routing(idx),
+            ModulationMessage::Add(routing) => matrix.add_routing(routing),
+        }
+    }
+}
+
+fn update_routing(matrix: &ModulationMatrix, idx: usize, f: impl FnOnce(&mut ModulationRouting)) {
+    matrix.update_config(|config| {
+        if let Some(routing) = config.routings.get_mut(idx) {
+            f(routing);
+        }
+    });
+}
+
+impl ModulationMatrixEditor {
+    /// Creates a new [`ModulationMatrixEditor`] for a modulation matrix that modulates `params`.
+    pub fn new(matrix: &ModulationMatrix, params: &dyn Params) -> Self {
+        // SAFETY: The parameters are borrowed from `params`, so they're still alive
+        let targets = params
+            .param_map()
+            .into_iter()
+            .filter(|(_, param_ptr, _)| !unsafe { param_ptr.flags() }.contains(ParamFlags::HIDDEN))
+            .map(|(id, param_ptr, _)| TargetOption {
+                id,
+                name: unsafe { param_ptr.name() }.to_owned(),
+            })
+            .collect();
+
+        Self {
+            config: matrix.config().as_ref().clone(),
+            targets,
+
+            text_size: None,
+            spacing: Pixels(4.0),
+        }
+    }
+
+    /// Sets the text size of the [`ModulationMatrixEditor`].
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Sets the spacing between the [`ModulationMatrixEditor`]'s elements.
+    pub fn spacing(mut self, spacing: impl Into<Pixels>) -> Self {
+        self.spacing = spacing.into();
+        self
+    }
+}
+
+impl<'a> From<ModulationMatrixEditor> for Element<'a, ModulationMessage> {
+    fn from(editor: ModulationMatrixEditor) -> Self {
+        let text_size = editor.text_size;
+        let text = move |label: &'static str| match text_size {
+            Some(size) => Text::new(label).size(size),
+            None => Text::new(label),
+        };
+        let sources: Vec<SourceOption> = ModulationSource::ALL
+            .into_iter()
+            .map(SourceOption)
+            .collect();
+        let spacing = editor.spacing;
+
+        let mut column = Column::new().spacing(spacing);
+        for (idx, routing) in editor.config.routings.into_iter().enumerate() {
+            let selected_target = editor
+                .targets
+                .iter()
+                .find(|target| target.id == routing.target)
+                .cloned();
+
+            let mut source_list = PickList::new(
+                sources.clone(),
+                Some(SourceOption(routing.source)),
+                move |source| ModulationMessage::SetSource(idx, source.0),
+            );
+            let mut target_list =
+                PickList::new(editor.targets.clone(), selected_target, move |target| {
+                    ModulationMessage::SetTarget(idx, target.id)
+                });
+            if let Some(size) = text_size {
+                source_list = source_list.text_size(size);
+                target_list = target_list.text_size(size);
+            }
+
+            column = column.push(
+                Row::new()
+                    .spacing(spacing)
+                    .align_y(Alignment::Center)
+                    .push(source_list)
+                    .push(target_list)
+                    .push(
+                        Slider::new(-1.0..=1.0, routing.depth, move |depth| {
+                            ModulationMessage::SetDepth(idx, depth)
+                        })
+                        .step(0.01)
+                        .width(Length::Fixed(100.0)),
+                    )
+                    .push(Button::new(text("Remove")).on_press(ModulationMessage::Remove(idx))),
+            );
+        }
+
+        let mut add_button = Button::new(text("Add routing"));
+        if let Some(first_target) = editor.targets.first() {
+            add_button = add_button.on_press(ModulationMessage::Add(ModulationRouting {
+                source: ModulationSource::Lfo(0),
+                target: first_target.id.clone(),
+                depth: 0.0,
+            }));
+        }
+
+        column.push(add_button).into()
+    }
+}
@@ -0,0 +1,175 @@
+//! An oscilloscope display that reads its data from an [`AnalysisOutput`].
+
+use atomic_refcell::AtomicRefCell;
+use nih_plug::util::AnalysisOutput;
+use std::marker::PhantomData;
+
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::widget::Tree;
+use crate::advanced::Widget;
+use crate::{mouse, Background, Border, Color, Element, Length, Rectangle, Renderer, Size, Theme};
+
+use super::util;
+
+/// The thickness of this widget's borders.
+const BORDER_WIDTH: f32 = 1.0;
+/// The thickness of the waveform's line.
+const LINE_WIDTH: f32 = 1.5;
+
+/// Displays a waveform read from an [`AnalysisOutput`] containing a block of samples. The samples
+/// are stretched to fit the widget's width, so the displayed time span is determined by the number
+/// of samples the plugin publishes.
+///
+/// TODO: There are currently no styling options at all
+pub struct Oscilloscope<'a, Message> {
+    data: &'a AtomicRefCell<AnalysisOutput<Vec<f32>>>,
+
+    amplitude_range: f32,
+
+    height: Length,
+    width: Length,
+
+    /// We don't emit any messages, but iced requires us to define some message type anyways.
+    _phantom: PhantomData<Message>,
+}
+
+impl<'a, Message> Oscilloscope<'a, Message> {
+    /// Creates a new [`Oscilloscope`] that displays the samples read from `data`.
+    pub fn new(data: &'a AtomicRefCell<AnalysisOutput<Vec<f32>>>) -> Self {
+        Self {
+            data,
+
+            amplitude_range: 1.0,
+
+            width: Length::Fixed(400.0),
+            height: Length::Fixed(200.0),
+
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets the displayed amplitude range. The waveform is drawn between `-range` and `range`.
+    /// Defaults to 1.0.
+    pub fn amplitude_range(mut self, range: f32) -> Self {
+        self.amplitude_range = range;
+        self
+    }
+
+    /// Sets the width of the [`Oscilloscope`].
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Sets the height of the [`Oscilloscope`].
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Map a sample to a `[0, 1]` position where 0 is the top of the widget.
+    fn sample_to_t(&self, sample: f32) -> f32 {
+        0.5 - (sample / (self.amplitude_range * 2.0))
+    }
+}
+
+impl<'a, Message> Widget<Message, Theme, Renderer> for Oscilloscope<'a, Message> {
+    fn size(&self) -> Size<Length> {
+        Size::new(self.width, self.height)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.width, self.height)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: Color::BLACK,
+                    width: BORDER_WIDTH,
+                    radius: 0.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            Background::Color(Color::TRANSPARENT),
+        );
+
+        let bounds_without_borders = bounds.shrink(BORDER_WIDTH);
+
+        let mut data = self.data.borrow_mut();
+        let samples = data.read();
+        if samples.is_empty() {
+            return;
+        }
+
+        // Every pixel column covers the range between the lowest and highest sample within that
+        // column, extended to connect to the previous column so the waveform stays continuous
+        let columns = bounds_without_borders.width.floor().max(1.0) as usize;
+        let mut previous_y: Option<f32> = None;
+        for column in 0..columns {
+            let start_idx = column * samples.len() / columns;
+            let end_idx = ((column + 1) * samples.len() / columns)
+                .max(start_idx + 1)
+                .min(samples.len());
+            if start_idx >= samples.len() {
+                break;
+            }
+
+            let (min, max) = samples[start_idx..end_idx]
+                .iter()
+                .fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), sample| {
+                    (min.min(*sample), max.max(*sample))
+                });
+            let max_y = util::remap_rect_y_t(&bounds_without_borders, self.sample_to_t(max));
+            let min_y = util::remap_rect_y_t(&bounds_without_borders, self.sample_to_t(min));
+            let (top, bottom) = match previous_y {
+                Some(previous_y) => (max_y.min(previous_y), min_y.max(previous_y)),
+                None => (max_y, min_y),
+            };
+            previous_y = Some((max_y + min_y) / 2.0);
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: util::remap_rect_x_t(
+                            &bounds_without_borders,
+                            column as f32 / columns as f32,
+                        ) - (LINE_WIDTH / 2.0),
+                        y: top - (LINE_WIDTH / 2.0),
+                        width: LINE_WIDTH,
+                        height: (bottom - top) + LINE_WIDTH,
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(style.text_color),
+            );
+        }
+    }
+}
+
+impl<'a, Message> From<Oscilloscope<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(widget: Oscilloscope<'a, Message>) -> Self {
+        Element::new(widget)
+    }
+}
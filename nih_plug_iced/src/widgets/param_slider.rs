@@ -1,16 +1,16 @@
 //! A slider that integrates with NIH-plug's [`Param`] types.
 
-use atomic_refcell::AtomicRefCell;
 use nih_plug::prelude::Param;
-use std::borrow::Borrow;
 
-use crate::backend::widget;
-use crate::backend::Renderer;
-use crate::renderer::Renderer as GraphicsRenderer;
-use crate::text::Renderer as TextRenderer;
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::text::{self, Paragraph as _, Renderer as _};
+use crate::advanced::widget::{tree, Tree};
+use crate::advanced::{Clipboard, Shell, Widget};
+use crate::widget::text_input;
 use crate::{
-    alignment, event, keyboard, layout, mouse, renderer, text, touch, Background, Clipboard, Color,
-    Element, Event, Font, Layout, Length, Point, Rectangle, Shell, Size, TextInput, Vector, Widget,
+    alignment, event, keyboard, mouse, touch, Background, Border, Color, Element, Event, Font,
+    Length, Pixels, Point, Rectangle, Renderer, Size, TextInput, Theme,
 };
 
 use super::util;
@@ -23,42 +23,77 @@ const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
 /// The thickness of this widget's borders.
 const BORDER_WIDTH: f32 = 1.0;
 
+/// The number of pixels of smooth scrolling that corresponds to a single scroll wheel line.
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+/// The embedded [`TextInput`]'s widget state.
+type TextInputState = text_input::State<<Renderer as text::Renderer>::Paragraph>;
+
 /// A slider that integrates with NIH-plug's [`Param`] types.
 ///
+/// The parameter can be stepped using the scroll wheel or the arrow keys while the mouse hovers
+/// over the slider. Holding down Shift while doing so results in finer steps. The Home and End keys
+/// set the parameter to its minimum and maximum values. Right clicking shows the host's context
+/// menu for the parameter. Control+C and Control+V copy the parameter's value to and from the
+/// clipboard while the mouse hovers over the slider.
+///
+/// TODO: Touch drags are tracked per finger so multiple sliders could be dragged at the same time,
+///       but baseview does not emit touch events yet so this is currently unreachable
+///
 /// TODO: There are currently no styling options at all
-/// TODO: Handle scrolling for steps (and shift+scroll for smaller steps?)
 pub struct ParamSlider<'a, P: Param> {
-    state: &'a mut State,
-
     param: &'a P,
 
     height: Length,
     width: Length,
-    text_size: Option<u16>,
-    font: Font,
+    text_size: Option<Pixels>,
+    font: Option<Font>,
 }
 
-/// State for a [`ParamSlider`].
-#[derive(Debug, Default)]
-pub struct State {
+/// State for a [`ParamSlider`]. This is stored in iced's widget tree.
+struct State {
     keyboard_modifiers: keyboard::Modifiers,
     /// Will be set to `true` if we're dragging the parameter. Resetting the parameter or entering a
     /// text value should not initiate a drag.
     drag_active: bool,
+    /// The finger that started the current drag, or `None` if the drag was started with the mouse.
+    /// While dragging, only events from this finger or from the mouse respectively move the
+    /// slider, so other fingers can be used to drag other sliders at the same time.
+    drag_finger: Option<touch::Finger>,
     /// We keep track of the start coordinate and normalized value holding down Shift while dragging
     /// for higher precision dragging. This is a `None` value when granular dragging is not active.
     granular_drag_start_x_value: Option<(f32, f32)>,
     /// Track clicks for double clicks.
     last_click: Option<mouse::Click>,
+    /// The number of (fractional) scrolled lines that have not yet been turned into parameter
+    /// change events. This is needed to support trackpads with smooth scrolling.
+    scrolled_lines: f32,
 
-    /// State for the text input overlay that will be shown when this widget is alt+clicked.
-    text_input_state: AtomicRefCell<widget::text_input::State>,
+    /// The widget tree for the text input overlay that will be shown when this widget is
+    /// alt+clicked.
+    text_input_tree: Tree,
     /// The text that's currently in the text input. If this is set to `None`, then the text input
     /// is not visible.
     text_input_value: Option<String>,
 }
 
-/// An internal message for intercep- I mean handling output from the embedded [`TextInpu`] widget.
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            keyboard_modifiers: keyboard::Modifiers::default(),
+            drag_active: false,
+            drag_finger: None,
+            granular_drag_start_x_value: None,
+            last_click: None,
+            scrolled_lines: 0.0,
+
+            text_input_tree: Tree::empty(),
+            text_input_value: None,
+        }
+    }
+}
+
+/// An internal message for intercep- I mean handling output from the embedded [`TextInput`] widget.
 #[derive(Debug, Clone)]
 enum TextInputMessage {
     /// A new value was entered in the text input dialog.
@@ -67,120 +102,108 @@ enum TextInputMessage {
     Submit,
 }
 
-/// The default text input style with the border removed.
-struct TextInputStyle;
-
-impl widget::text_input::StyleSheet for TextInputStyle {
-    fn active(&self) -> widget::text_input::Style {
-        widget::text_input::Style {
-            background: Background::Color(Color::TRANSPARENT),
-            border_radius: 0.0,
-            border_width: 0.0,
-            border_color: Color::TRANSPARENT,
-        }
-    }
-
-    fn focused(&self) -> widget::text_input::Style {
-        self.active()
-    }
-
-    fn placeholder_color(&self) -> Color {
-        Color::from_rgb(0.7, 0.7, 0.7)
-    }
-
-    fn value_color(&self) -> Color {
-        Color::from_rgb(0.3, 0.3, 0.3)
-    }
-
-    fn selection_color(&self) -> Color {
-        Color::from_rgb(0.8, 0.8, 1.0)
+/// The default text input style with the border and the background removed.
+fn text_input_style(_theme: &Theme, _status: text_input::Status) -> text_input::Style {
+    text_input::Style {
+        background: Background::Color(Color::TRANSPARENT),
+        border: Border::default(),
+        icon: Color::from_rgb(0.3, 0.3, 0.3),
+        placeholder: Color::from_rgb(0.7, 0.7, 0.7),
+        value: Color::from_rgb(0.3, 0.3, 0.3),
+        selection: Color::from_rgb(0.8, 0.8, 1.0),
     }
 }
 
 impl<'a, P: Param> ParamSlider<'a, P> {
     /// Creates a new [`ParamSlider`] for the given parameter.
-    pub fn new(state: &'a mut State, param: &'a P) -> Self {
+    pub fn new(param: &'a P) -> Self {
         Self {
-            state,
-
             param,
 
-            width: Length::Units(180),
-            height: Length::Units(30),
+            width: Length::Fixed(180.0),
+            height: Length::Fixed(30.0),
             text_size: None,
-            font: <Renderer as TextRenderer>::Font::default(),
+            font: None,
         }
     }
 
     /// Sets the width of the [`ParamSlider`].
-    pub fn width(mut self, width: Length) -> Self {
-        self.width = width;
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
         self
     }
 
     /// Sets the height of the [`ParamSlider`].
-    pub fn height(mut self, height: Length) -> Self {
-        self.height = height;
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
         self
     }
 
     /// Sets the text size of the [`ParamSlider`].
-    pub fn text_size(mut self, size: u16) -> Self {
-        self.text_size = Some(size);
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
         self
     }
 
-    /// Sets the font of the [`ParamSlider`].
+    /// Sets the font of the [`ParamSlider`]. Defaults to the editor's default font.
     pub fn font(mut self, font: Font) -> Self {
-        self.font = font;
+        self.font = Some(font);
         self
     }
 
-    /// Create a temporary [`TextInput`] hooked up to [`State::text_input_value`] and outputting
-    /// [`TextInputMessage`] messages and do something with it. This can be used to
-    fn with_text_input<T, R, F>(&self, layout: Layout, renderer: R, current_value: &str, f: F) -> T
-    where
-        F: FnOnce(TextInput<'_, TextInputMessage>, Layout, R) -> T,
-        R: Borrow<Renderer>,
-    {
-        let mut text_input_state = self.state.text_input_state.borrow_mut();
-        text_input_state.focus();
+    /// The text used to display `content` centered within a `bounds` sized area.
+    fn text<T>(&self, renderer: &Renderer, content: T, bounds: Size) -> text::Text<T, Font> {
+        text::Text {
+            content,
+            bounds,
+            size: self.text_size.unwrap_or_else(|| renderer.default_size()),
+            line_height: text::LineHeight::default(),
+            font: self.font.unwrap_or_else(|| renderer.default_font()),
+            horizontal_alignment: alignment::Horizontal::Center,
+            vertical_alignment: alignment::Vertical::Center,
+            shaping: text::Shaping::Advanced,
+            wrapping: text::Wrapping::None,
+        }
+    }
 
-        let text_size = self
-            .text_size
-            .unwrap_or_else(|| renderer.borrow().default_size());
-        let text_width = renderer
-            .borrow()
-            .measure_width(current_value, text_size, self.font);
-        let text_input = TextInput::new(
-            &mut text_input_state,
-            "",
-            current_value,
-            TextInputMessage::Value,
-        )
-        .font(self.font)
-        .size(text_size)
-        .width(Length::Units(text_width.ceil() as u16))
-        .style(TextInputStyle)
-        .on_submit(TextInputMessage::Submit);
-
-        // Make sure to not draw over the borders, and center the text
-        let offset_node = layout::Node::with_children(
-            Size {
-                width: text_width,
-                height: layout.bounds().size().height - (BORDER_WIDTH * 2.0),
-            },
-            vec![layout::Node::new(layout.bounds().size())],
-        );
-        let offset_layout = Layout::with_offset(
-            Vector {
-                x: layout.bounds().center_x() - (text_width / 2.0),
-                y: layout.position().y + BORDER_WIDTH,
-            },
-            &offset_node,
-        );
+    /// Create a temporary [`TextInput`] for editing `current_value` that outputs
+    /// [`TextInputMessage`] messages. Its widget state lives in [`State::text_input_tree`]. Iced's
+    /// [`Widget`] methods need to be called using the fully qualified syntax since `TextInput` has
+    /// inherent methods with the same names.
+    fn text_input(
+        &self,
+        renderer: &Renderer,
+        current_value: &str,
+    ) -> TextInput<'a, TextInputMessage, Theme, Renderer> {
+        let text = self.text(renderer, current_value, Size::INFINITY);
+        let (font, size) = (text.font, text.size);
+        let text_width = <Renderer as text::Renderer>::Paragraph::with_text(text).min_width();
+
+        TextInput::new("", current_value)
+            .on_input(TextInputMessage::Value)
+            .on_submit(TextInputMessage::Submit)
+            .font(font)
+            .size(size)
+            .padding(0)
+            // Leave some room for the cursor
+            .width(Length::Fixed(text_width.ceil() + BORDER_WIDTH))
+            .style(text_input_style)
+    }
+
+    /// Show the text input with the parameter's current value selected.
+    fn open_text_input(&self, state: &mut State, renderer: &Renderer) {
+        let current_value = self.param.to_string();
+        let text_input = self.text_input(renderer, &current_value);
+        state
+            .text_input_tree
+            .diff(&text_input as &dyn Widget<TextInputMessage, Theme, Renderer>);
+
+        let text_input_state = state.text_input_tree.state.downcast_mut::<TextInputState>();
+        text_input_state.focus();
+        text_input_state.move_cursor_to_end();
+        text_input_state.select_all();
 
-        f(text_input, offset_layout, renderer)
+        state.text_input_value = Some(current_value);
     }
 
     /// Set the normalized value for a parameter if that would change the parameter's plain value
@@ -202,172 +225,311 @@ impl<'a, P: Param> ParamSlider<'a, P> {
             ));
         }
     }
+
+    /// Move the parameter `steps` steps up or down using the parameter's
+    /// [`next_normalized_step()`][Param::next_normalized_step()] and
+    /// [`previous_normalized_step()`][Param::previous_normalized_step()] functions. This takes care
+    /// of sending the begin- and end set parameter messages if the parameter is not currently
+    /// being dragged.
+    fn step_normalized_value(
+        &self,
+        state: &State,
+        shell: &mut Shell<'_, ParamMessage>,
+        steps: i32,
+        use_finer_steps: bool,
+    ) {
+        if steps == 0 {
+            return;
+        }
+
+        // Stepping while dragging needs to be taken into account here
+        if !state.drag_active {
+            shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
+        }
+
+        let mut current_value = self.param.unmodulated_normalized_value();
+        for _ in 0..steps.unsigned_abs() {
+            current_value = if steps > 0 {
+                self.param
+                    .next_normalized_step(current_value, use_finer_steps)
+            } else {
+                self.param
+                    .previous_normalized_step(current_value, use_finer_steps)
+            };
+        }
+        self.set_normalized_value(shell, current_value);
+
+        if !state.drag_active {
+            shell.publish(ParamMessage::EndSetParameter(self.param.as_ptr()));
+        }
+    }
+
+    /// Set the parameter to a normalized value as a single automation gesture. Used for resetting
+    /// the parameter and for the Home and End keys.
+    fn set_normalized_value_gesture(
+        &self,
+        state: &State,
+        shell: &mut Shell<'_, ParamMessage>,
+        normalized_value: f32,
+    ) {
+        if !state.drag_active {
+            shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
+        }
+        self.set_normalized_value(shell, normalized_value);
+        if !state.drag_active {
+            shell.publish(ParamMessage::EndSetParameter(self.param.as_ptr()));
+        }
+    }
 }
 
-impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
-    fn width(&self) -> Length {
-        self.width
+impl<'a, P: Param> Widget<ParamMessage, Theme, Renderer> for ParamSlider<'a, P> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
     }
 
-    fn height(&self) -> Length {
-        self.height
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
     }
 
-    fn layout(&self, _renderer: &Renderer, limits: &layout::Limits) -> layout::Node {
-        let limits = limits.width(self.width).height(self.height);
-        let size = limits.resolve(Size::ZERO);
+    fn size(&self) -> Size<Length> {
+        Size::new(self.width, self.height)
+    }
 
-        layout::Node::new(size)
+    fn layout(
+        &self,
+        tree: &mut Tree,
+        renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        let node = layout::atomic(limits, self.width, self.height);
+
+        // While the text input is shown it's laid out as this node's only child, centered within
+        // the slider's borders
+        let state = tree.state.downcast_mut::<State>();
+        match &state.text_input_value {
+            Some(current_value) => {
+                let size = node.size();
+                let text_input = self.text_input(renderer, current_value);
+                let text_input_limits = layout::Limits::new(
+                    Size::ZERO,
+                    Size {
+                        width: size.width - (BORDER_WIDTH * 2.0),
+                        height: size.height - (BORDER_WIDTH * 2.0),
+                    },
+                );
+
+                state
+                    .text_input_tree
+                    .diff(&text_input as &dyn Widget<TextInputMessage, Theme, Renderer>);
+                let text_input_node = Widget::<TextInputMessage, Theme, Renderer>::layout(
+                    &text_input,
+                    &mut state.text_input_tree,
+                    renderer,
+                    &text_input_limits,
+                );
+                let text_input_size = text_input_node.size();
+                let text_input_node = text_input_node.move_to(Point {
+                    x: (size.width - text_input_size.width) / 2.0,
+                    y: (size.height - text_input_size.height) / 2.0,
+                });
+
+                layout::Node::with_children(size, vec![text_input_node])
+            }
+            None => node,
+        }
     }
 
     fn on_event(
         &mut self,
+        tree: &mut Tree,
         event: Event,
         layout: Layout<'_>,
-        cursor_position: Point,
+        cursor: mouse::Cursor,
         renderer: &Renderer,
         clipboard: &mut dyn Clipboard,
         shell: &mut Shell<'_, ParamMessage>,
+        viewport: &Rectangle,
     ) -> event::Status {
-        // The pressence of a value in `self.state.text_input_value` indicates that the field should
-        // be focussed. The field handles defocussing by itself
+        let state = tree.state.downcast_mut::<State>();
+
+        // The pressence of a value in `state.text_input_value` indicates that the field should be
+        // focussed. The field handles defocussing by itself
         // FIMXE: This is super hacky, I have no idea how you can reuse the text input widget
         //        otherwise. Widgets are not supposed to handle messages from other widgets, but
         //        we'll do so anyways by using a special `TextInputMessage` type and our own
         //        `Shell`.
-        let text_input_status = if let Some(current_value) = &self.state.text_input_value {
-            let event = event.clone();
-            let mut messages = Vec::new();
-            let mut text_input_shell = Shell::new(&mut messages);
-            let status = self.with_text_input(
-                layout,
-                renderer,
-                current_value,
-                |mut text_input, layout, renderer| {
-                    text_input.on_event(
-                        event,
-                        layout,
-                        cursor_position,
-                        renderer,
-                        clipboard,
-                        &mut text_input_shell,
-                    )
-                },
-            );
+        let text_input_status = match (&state.text_input_value, layout.children().next()) {
+            (Some(current_value), Some(text_input_layout)) => {
+                let mut text_input = self.text_input(renderer, current_value);
+                let mut messages = Vec::new();
+                let mut text_input_shell = Shell::new(&mut messages);
+                let status = Widget::<TextInputMessage, Theme, Renderer>::on_event(
+                    &mut text_input,
+                    &mut state.text_input_tree,
+                    event.clone(),
+                    text_input_layout,
+                    cursor,
+                    renderer,
+                    clipboard,
+                    &mut text_input_shell,
+                    viewport,
+                );
+
+                // The text input uses this to blink its cursor
+                if let Some(redraw_request) = text_input_shell.redraw_request() {
+                    shell.request_redraw(redraw_request);
+                }
 
-            // Pressing escape will unfocus the text field, so we should propagate that change in
-            // our own model
-            if self.state.text_input_state.borrow().is_focused() {
-                for message in messages {
-                    match message {
-                        TextInputMessage::Value(s) => self.state.text_input_value = Some(s),
-                        TextInputMessage::Submit => {
-                            if let Some(normalized_value) = self
-                                .state
-                                .text_input_value
-                                .as_ref()
-                                .and_then(|s| self.param.string_to_normalized_value(s))
-                            {
-                                shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
-                                self.set_normalized_value(shell, normalized_value);
-                                shell.publish(ParamMessage::EndSetParameter(self.param.as_ptr()));
+                // Pressing escape or clicking somewhere else will unfocus the text field, so we
+                // should propagate that change in our own model
+                if state
+                    .text_input_tree
+                    .state
+                    .downcast_ref::<TextInputState>()
+                    .is_focused()
+                {
+                    for message in messages {
+                        match message {
+                            TextInputMessage::Value(s) => state.text_input_value = Some(s),
+                            TextInputMessage::Submit => {
+                                if let Some(normalized_value) = state
+                                    .text_input_value
+                                    .as_ref()
+                                    .and_then(|s| self.param.string_to_normalized_value(s))
+                                {
+                                    shell.publish(ParamMessage::BeginSetParameter(
+                                        self.param.as_ptr(),
+                                    ));
+                                    self.set_normalized_value(shell, normalized_value);
+                                    shell.publish(ParamMessage::EndSetParameter(
+                                        self.param.as_ptr(),
+                                    ));
+                                }
+
+                                // And defocus the text input widget again
+                                state.text_input_value = None;
                             }
-
-                            // And defocus the text input widget again
-                            self.state.text_input_value = None;
                         }
                     }
+                } else {
+                    state.text_input_value = None;
                 }
-            } else {
-                self.state.text_input_value = None;
-            }
 
-            status
-        } else {
-            event::Status::Ignored
+                // Both the text input's width and its visibility may have changed
+                shell.invalidate_layout();
+
+                status
+            }
+            _ => event::Status::Ignored,
         };
         if text_input_status == event::Status::Captured {
             return event::Status::Captured;
         }
 
         // Compensate for the border when handling these events
-        let bounds = layout.bounds();
-        let bounds = Rectangle {
-            x: bounds.x + BORDER_WIDTH,
-            y: bounds.y + BORDER_WIDTH,
-            width: bounds.width - (BORDER_WIDTH * 2.0),
-            height: bounds.height - (BORDER_WIDTH * 2.0),
+        let bounds = layout.bounds().shrink(BORDER_WIDTH);
+
+        // Touch events carry their own position since there may be multiple fingers on the screen
+        // at the same time
+        let (pointer_position, finger) = match event {
+            Event::Touch(
+                touch::Event::FingerPressed { id, position }
+                | touch::Event::FingerMoved { id, position }
+                | touch::Event::FingerLifted { id, position }
+                | touch::Event::FingerLost { id, position },
+            ) => (Some(position), Some(id)),
+            _ => (cursor.position(), None),
         };
 
         match event {
             Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left))
             | Event::Touch(touch::Event::FingerPressed { .. }) => {
-                if bounds.contains(cursor_position) {
-                    let click = mouse::Click::new(cursor_position, self.state.last_click);
-                    self.state.last_click = Some(click);
-                    if self.state.keyboard_modifiers.alt() {
+                if let Some(cursor_position) =
+                    pointer_position.filter(|position| bounds.contains(*position))
+                {
+                    // A second finger or a mouse click should not take over an existing drag
+                    if state.drag_active {
+                        return event::Status::Captured;
+                    }
+
+                    let click =
+                        mouse::Click::new(cursor_position, mouse::Button::Left, state.last_click);
+                    state.last_click = Some(click);
+                    if state.keyboard_modifiers.alt() {
                         // Alt+click should not start a drag, instead it should show the text entry
                         // widget
-                        self.state.drag_active = false;
+                        state.drag_active = false;
 
                         // Changing the parameter happens in the TextInput event handler above
-                        let mut text_input_state = self.state.text_input_state.borrow_mut();
-                        self.state.text_input_value = Some(self.param.to_string());
-                        text_input_state.move_cursor_to_end();
-                        text_input_state.select_all();
-                    } else if self.state.keyboard_modifiers.command()
+                        self.open_text_input(state, renderer);
+                        shell.invalidate_layout();
+                    } else if state.keyboard_modifiers.command()
                         || matches!(click.kind(), mouse::click::Kind::Double)
                     {
                         // Likewise resetting a parameter should not let you immediately drag it to a new value
-                        self.state.drag_active = false;
+                        state.drag_active = false;
 
+                        self.set_normalized_value_gesture(
+                            state,
+                            shell,
+                            self.param.default_normalized_value(),
+                        );
+                    } else if state.keyboard_modifiers.shift() {
                         shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
-                        self.set_normalized_value(shell, self.param.default_normalized_value());
-                        shell.publish(ParamMessage::EndSetParameter(self.param.as_ptr()));
-                    } else if self.state.keyboard_modifiers.shift() {
-                        shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
-                        self.state.drag_active = true;
+                        state.drag_active = true;
+                        state.drag_finger = finger;
 
                         // When holding down shift while clicking on a parameter we want to
                         // granuarly edit the parameter without jumping to a new value
-                        self.state.granular_drag_start_x_value =
+                        state.granular_drag_start_x_value =
                             Some((cursor_position.x, self.param.modulated_normalized_value()));
                     } else {
                         shell.publish(ParamMessage::BeginSetParameter(self.param.as_ptr()));
-                        self.state.drag_active = true;
+                        state.drag_active = true;
+                        state.drag_finger = finger;
 
                         self.set_normalized_value(
                             shell,
                             util::remap_rect_x_coordinate(&bounds, cursor_position.x),
                         );
-                        self.state.granular_drag_start_x_value = None;
+                        state.granular_drag_start_x_value = None;
                     }
 
                     return event::Status::Captured;
                 }
             }
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Right)) => {
+                if let Some(cursor_position) = cursor.position_over(bounds) {
+                    // Right clicking shows the host's context menu for the parameter
+                    shell.publish(ParamMessage::ShowParamContextMenu(
+                        self.param.as_ptr(),
+                        (cursor_position.x, cursor_position.y),
+                    ));
+
+                    return event::Status::Captured;
+                }
+            }
             Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left))
             | Event::Touch(touch::Event::FingerLifted { .. } | touch::Event::FingerLost { .. }) => {
-                if self.state.drag_active {
+                if state.drag_active && state.drag_finger == finger {
                     shell.publish(ParamMessage::EndSetParameter(self.param.as_ptr()));
 
-                    self.state.drag_active = false;
+                    state.drag_active = false;
+                    state.drag_finger = None;
 
                     return event::Status::Captured;
                 }
             }
-            Event::Mouse(mouse::Event::CursorMoved { .. })
-            | Event::Touch(touch::Event::FingerMoved { .. }) => {
+            Event::Mouse(mouse::Event::CursorMoved { position })
+            | Event::Touch(touch::Event::FingerMoved { position, .. }) => {
                 // Don't do anything when we just reset the parameter because that would be weird
-                if self.state.drag_active {
+                if state.drag_active && state.drag_finger == finger {
                     // If shift is being held then the drag should be more granular instead of
                     // absolute
-                    if self.state.keyboard_modifiers.shift() {
-                        let (drag_start_x, drag_start_value) = *self
-                            .state
-                            .granular_drag_start_x_value
-                            .get_or_insert_with(|| {
-                                (cursor_position.x, self.param.modulated_normalized_value())
+                    if state.keyboard_modifiers.shift() {
+                        let (drag_start_x, drag_start_value) =
+                            *state.granular_drag_start_x_value.get_or_insert_with(|| {
+                                (position.x, self.param.modulated_normalized_value())
                             });
 
                         self.set_normalized_value(
@@ -375,36 +537,102 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
                             util::remap_rect_x_coordinate(
                                 &bounds,
                                 util::remap_rect_x_t(&bounds, drag_start_value)
-                                    + (cursor_position.x - drag_start_x) * GRANULAR_DRAG_MULTIPLIER,
+                                    + (position.x - drag_start_x) * GRANULAR_DRAG_MULTIPLIER,
                             ),
                         );
                     } else {
-                        self.state.granular_drag_start_x_value = None;
+                        state.granular_drag_start_x_value = None;
 
                         self.set_normalized_value(
                             shell,
-                            util::remap_rect_x_coordinate(&bounds, cursor_position.x),
+                            util::remap_rect_x_coordinate(&bounds, position.x),
                         );
                     }
 
                     return event::Status::Captured;
                 }
             }
+            Event::Mouse(mouse::Event::WheelScrolled { delta }) => {
+                if cursor.is_over(bounds) {
+                    // With a regular scroll wheel this will only ever be -1 or 1, but with smooth
+                    // scrolling trackpads being a thing this could be anything
+                    state.scrolled_lines += match delta {
+                        mouse::ScrollDelta::Lines { y, .. } => y,
+                        mouse::ScrollDelta::Pixels { y, .. } => y / PIXELS_PER_SCROLL_LINE,
+                    };
+
+                    let steps = state.scrolled_lines.trunc();
+                    state.scrolled_lines -= steps;
+                    self.step_normalized_value(
+                        state,
+                        shell,
+                        steps as i32,
+                        state.keyboard_modifiers.shift(),
+                    );
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Character(c),
+                modifiers,
+                ..
+            }) if modifiers.command() && cursor.is_over(bounds) => {
+                match c.as_str() {
+                    "c" | "C" => {
+                        shell.publish(ParamMessage::CopyToClipboard(self.param.as_ptr()));
+                    }
+                    // Pasting while dragging would interrupt the drag's automation gesture
+                    "v" | "V" if !state.drag_active => {
+                        shell.publish(ParamMessage::PasteFromClipboard(self.param.as_ptr()));
+                    }
+                    _ => return event::Status::Ignored,
+                }
+
+                return event::Status::Captured;
+            }
+            Event::Keyboard(keyboard::Event::KeyPressed {
+                key: keyboard::Key::Named(key),
+                modifiers,
+                ..
+            }) if cursor.is_over(bounds) => {
+                let use_finer_steps = modifiers.shift();
+                match key {
+                    keyboard::key::Named::ArrowUp | keyboard::key::Named::ArrowRight => {
+                        self.step_normalized_value(state, shell, 1, use_finer_steps);
+                    }
+                    keyboard::key::Named::ArrowDown | keyboard::key::Named::ArrowLeft => {
+                        self.step_normalized_value(state, shell, -1, use_finer_steps);
+                    }
+                    keyboard::key::Named::Home => {
+                        self.set_normalized_value_gesture(state, shell, 0.0)
+                    }
+                    keyboard::key::Named::End => {
+                        self.set_normalized_value_gesture(state, shell, 1.0)
+                    }
+                    _ => return event::Status::Ignored,
+                }
+
+                return event::Status::Captured;
+            }
             Event::Keyboard(keyboard::Event::ModifiersChanged(modifiers)) => {
-                self.state.keyboard_modifiers = modifiers;
+                state.keyboard_modifiers = modifiers;
 
                 // If this happens while dragging, snap back to reality uh I mean the current screen
                 // position
-                if self.state.drag_active
-                    && self.state.granular_drag_start_x_value.is_some()
+                if state.drag_active
+                    && state.granular_drag_start_x_value.is_some()
                     && !modifiers.shift()
                 {
-                    self.state.granular_drag_start_x_value = None;
+                    state.granular_drag_start_x_value = None;
 
-                    self.set_normalized_value(
-                        shell,
-                        util::remap_rect_x_coordinate(&bounds, cursor_position.x),
-                    );
+                    // Touch drags will snap to the finger's position on the next move event
+                    if let (None, Some(cursor_position)) = (state.drag_finger, cursor.position()) {
+                        self.set_normalized_value(
+                            shell,
+                            util::remap_rect_x_coordinate(&bounds, cursor_position.x),
+                        );
+                    }
                 }
 
                 return event::Status::Captured;
@@ -417,15 +645,13 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
 
     fn mouse_interaction(
         &self,
+        _tree: &Tree,
         layout: Layout<'_>,
-        cursor_position: Point,
+        cursor: mouse::Cursor,
         _viewport: &Rectangle,
         _renderer: &Renderer,
     ) -> mouse::Interaction {
-        let bounds = layout.bounds();
-        let is_mouse_over = bounds.contains(cursor_position);
-
-        if is_mouse_over {
+        if cursor.is_over(layout.bounds()) {
             mouse::Interaction::Pointer
         } else {
             mouse::Interaction::default()
@@ -434,26 +660,24 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
 
     fn draw(
         &self,
+        tree: &Tree,
         renderer: &mut Renderer,
+        theme: &Theme,
         style: &renderer::Style,
         layout: Layout<'_>,
-        cursor_position: Point,
-        _viewport: &Rectangle,
+        cursor: mouse::Cursor,
+        viewport: &Rectangle,
     ) {
+        let state = tree.state.downcast_ref::<State>();
         let bounds = layout.bounds();
         // I'm sure there's some philosophical meaning behind this
-        let bounds_without_borders = Rectangle {
-            x: bounds.x + BORDER_WIDTH,
-            y: bounds.y + BORDER_WIDTH,
-            width: bounds.width - (BORDER_WIDTH * 2.0),
-            height: bounds.height - (BORDER_WIDTH * 2.0),
-        };
-        let is_mouse_over = bounds.contains(cursor_position);
+        let bounds_without_borders = bounds.shrink(BORDER_WIDTH);
+        let is_mouse_over = cursor.is_over(bounds);
 
         // The bar itself, show a different background color when the value is being edited or when
         // the mouse is hovering over it to indicate that it's interactive
         let background_color =
-            if is_mouse_over || self.state.drag_active || self.state.text_input_value.is_some() {
+            if is_mouse_over || state.drag_active || state.text_input_value.is_some() {
                 Color::new(0.5, 0.5, 0.5, 0.1)
             } else {
                 Color::TRANSPARENT
@@ -462,24 +686,32 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
         renderer.fill_quad(
             renderer::Quad {
                 bounds,
-                border_color: Color::BLACK,
-                border_width: BORDER_WIDTH,
-                border_radius: 0.0,
+                border: Border {
+                    color: Color::BLACK,
+                    width: BORDER_WIDTH,
+                    radius: 0.0.into(),
+                },
+                ..renderer::Quad::default()
             },
             background_color,
         );
 
         // Only draw the text input widget when it gets focussed. Otherwise, overlay the label with
         // the slider.
-        if let Some(current_value) = &self.state.text_input_value {
-            self.with_text_input(
-                layout,
+        if let (Some(current_value), Some(text_input_layout)) =
+            (&state.text_input_value, layout.children().next())
+        {
+            let text_input = self.text_input(renderer, current_value);
+            Widget::<TextInputMessage, Theme, Renderer>::draw(
+                &text_input,
+                &state.text_input_tree,
                 renderer,
-                current_value,
-                |text_input, layout, renderer| {
-                    text_input.draw(renderer, layout, cursor_position, None)
-                },
-            )
+                theme,
+                style,
+                text_input_layout,
+                cursor,
+                viewport,
+            );
         } else {
             // We'll visualize the difference between the current value and the default value if the
             // default value lies somewhere in the middle and the parameter is continuous. Otherwise
@@ -505,9 +737,7 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
             renderer.fill_quad(
                 renderer::Quad {
                     bounds: fill_rect,
-                    border_color: Color::TRANSPARENT,
-                    border_width: 0.0,
-                    border_radius: 0.0,
+                    ..renderer::Quad::default()
                 },
                 fill_color,
             );
@@ -515,35 +745,12 @@ impl<'a, P: Param> Widget<ParamMessage, Renderer> for ParamSlider<'a, P> {
             // To make it more readable (and because it looks cool), the parts that overlap with the
             // fill rect will be rendered in white while the rest will be rendered in black.
             let display_value = self.param.to_string();
-            let text_size = self.text_size.unwrap_or_else(|| renderer.default_size()) as f32;
-            let text_bounds = Rectangle {
-                x: bounds.center_x(),
-                y: bounds.center_y(),
-                ..bounds
-            };
-            renderer.fill_text(text::Text {
-                content: &display_value,
-                font: self.font,
-                size: text_size,
-                bounds: text_bounds,
-                color: style.text_color,
-                horizontal_alignment: alignment::Horizontal::Center,
-                vertical_alignment: alignment::Vertical::Center,
-            });
+            let text = self.text(renderer, display_value, bounds.size());
+            renderer.fill_text(text.clone(), bounds.center(), style.text_color, *viewport);
 
             // This will clip to the filled area
-            renderer.with_layer(fill_rect, |renderer| {
-                let filled_text_color = Color::from_rgb8(80, 80, 80);
-                renderer.fill_text(text::Text {
-                    content: &display_value,
-                    font: self.font,
-                    size: text_size,
-                    bounds: text_bounds,
-                    color: filled_text_color,
-                    horizontal_alignment: alignment::Horizontal::Center,
-                    vertical_alignment: alignment::Vertical::Center,
-                });
-            });
+            let filled_text_color = Color::from_rgb8(80, 80, 80);
+            renderer.fill_text(text, bounds.center(), filled_text_color, fill_rect);
         }
     }
 }
@@ -555,8 +762,8 @@ impl<'a, P: Param> ParamSlider<'a, P> {
     /// [`IcedEditor::handle_param_message()`][crate::IcedEditor::handle_param_message()].
     pub fn map<Message, F>(self, f: F) -> Element<'a, Message>
     where
-        Message: 'static,
-        F: Fn(ParamMessage) -> Message + 'static,
+        Message: 'a,
+        F: Fn(ParamMessage) -> Message + 'a,
     {
         Element::from(self).map(f)
     }
@@ -0,0 +1,301 @@
+//! A preset browser built on top of [`PresetLibrary`].
+
+use nih_plug::prelude::GuiContext;
+use nih_plug::util::{PresetEntry, PresetLibrary};
+
+use crate::widget::button;
+use crate::widget::text::IntoFragment;
+use crate::{
+    Alignment, Background, Border, Button, Color, Column, Element, Length, Pixels, Row, Scrollable,
+    Text, TextInput, Theme,
+};
+
+/// A preset browser for a [`PresetLibrary`]. Presets can be searched by name and filtered by
+/// clicking on their tags, and clicking on a preset loads it. The save button opens a form for
+/// saving the plugin's current state as a new preset. The editor should pass the
+/// [`PresetMessage`]s emitted by this widget to [`State::update()`].
+///
+/// The preset list is rebuilt whenever the editor's view is updated. Since the library is scanned on
+/// a background thread, editors may want to periodically emit [`PresetMessage::Refresh`] while
+/// [`PresetLibrary::is_scanning()`] returns true.
+pub struct PresetBrowser<'a> {
+    state: &'a State,
+    library: &'a PresetLibrary,
+
+    list_height: Length,
+    text_size: Option<Pixels>,
+    spacing: Pixels,
+    button_style: fn(&Theme, button::Status, bool) -> button::Style,
+}
+
+/// State for a [`PresetBrowser`].
+#[derive(Debug, Default)]
+pub struct State {
+    /// The text in the search field.
+    query: String,
+    /// The tags presets need to have to be shown.
+    selected_tags: Vec<String>,
+    /// The last preset that was loaded or saved.
+    current_preset: Option<PresetEntry>,
+
+    /// Whether the save form is currently shown.
+    save_form_open: bool,
+    /// The name entered in the save form.
+    save_name: String,
+    /// The comma separated tags entered in the save form.
+    save_tags: String,
+
+    /// The last error that occurred while loading or saving a preset.
+    error: Option<String>,
+}
+
+/// A message emitted by a [`PresetBrowser`]. These should be handled with [`State::update()`].
+#[derive(Debug, Clone)]
+pub enum PresetMessage {
+    /// The search query has changed.
+    QueryChanged(String),
+    /// A tag has been clicked on.
+    ToggleTag(String),
+    /// A preset has been clicked on.
+    LoadPreset(PresetEntry),
+    /// The save button has been clicked on.
+    OpenSaveForm,
+    /// The name in the save form has changed.
+    SaveNameChanged(String),
+    /// The tags in the save form have changed.
+    SaveTagsChanged(String),
+    /// The save form's save button has been clicked on.
+    Save,
+    /// The save form's cancel button has been clicked on.
+    CancelSave,
+    /// Doesn't change anything, but causes the preset list to be rebuilt.
+    Refresh,
+}
+
+/// The default style for the preset and tag buttons. Selected items get a highlighted background,
+/// and the other items are drawn as plain text.
+pub fn default_button_style(
+    theme: &Theme,
+    status: button::Status,
+    selected: bool,
+) -> button::Style {
+    let hovered = matches!(status, button::Status::Hovered | button::Status::Pressed);
+    let background = match (selected, hovered) {
+        (true, false) => Some(Color::from_rgb(0.8, 0.8, 1.0)),
+        (true, true) => Some(Color::from_rgb(0.75, 0.75, 1.0)),
+        (false, false) => None,
+        (false, true) => Some(Color::from_rgb(0.9, 0.9, 0.9)),
+    };
+
+    button::Style {
+        background: background.map(Background::Color),
+        text_color: theme.palette().text,
+        border: Border {
+            radius: 2.0.into(),
+            ..Border::default()
+        },
+        ..button::Style::default()
+    }
+}
+
+/// A [`Text`] with an optional text size.
+fn text<'a>(label: impl IntoFragment<'a>, size: Option<Pixels>) -> Text<'a> {
+    match size {
+        Some(size) => Text::new(label).size(size),
+        None => Text::new(label),
+    }
+}
+
+impl State {
+    /// Handle a message emitted by the [`PresetBrowser`]. Presets are loaded and saved using the
+    /// editor's [`GuiContext`].
+    pub fn update(
+        &mut self,
+        library: &PresetLibrary,
+        context: &dyn GuiContext,
+        message: PresetMessage,
+    ) {
+        match message {
+            PresetMessage::QueryChanged(query) => self.query = query,
+            PresetMessage::ToggleTag(tag) => {
+                if self.selected_tags.contains(&tag) {
+                    self.selected_tags.retain(|t| t != &tag);
+                } else {
+                    self.selected_tags.push(tag);
+                }
+            }
+            PresetMessage::LoadPreset(preset) => match library.load(&preset) {
+                Ok(plugin_state) => {
+                    context.set_state(plugin_state);
+                    self.current_preset = Some(preset);
+                    self.error = None;
+                }
+                Err(err) => self.error = Some(format!("{err:#}")),
+            },
+            PresetMessage::OpenSaveForm => {
+                // The form starts out with the current preset's name and tags so it can easily be
+                // overwritten
+                let (name, tags) = match &self.current_preset {
+                    Some(preset) => (preset.name.clone(), preset.tags.join(", ")),
+                    None => (String::new(), String::new()),
+                };
+                self.save_name = name;
+                self.save_tags = tags;
+                self.save_form_open = true;
+            }
+            PresetMessage::SaveNameChanged(name) => self.save_name = name,
+            PresetMessage::SaveTagsChanged(tags) => self.save_tags = tags,
+            PresetMessage::Save => {
+                let tags: Vec<String> = self.save_tags.split(',').map(String::from).collect();
+                match library.save(&self.save_name, &tags, &context.get_state()) {
+                    Ok(preset) => {
+                        self.current_preset = Some(preset);
+                        self.save_form_open = false;
+                        self.error = None;
+                    }
+                    Err(err) => self.error = Some(format!("{err:#}")),
+                }
+            }
+            PresetMessage::CancelSave => self.save_form_open = false,
+            PresetMessage::Refresh => (),
+        }
+    }
+}
+
+impl<'a> PresetBrowser<'a> {
+    /// Creates a new [`PresetBrowser`] for a preset library.
+    pub fn new(state: &'a State, library: &'a PresetLibrary) -> Self {
+        Self {
+            state,
+            library,
+
+            list_height: Length::Fixed(200.0),
+            text_size: None,
+            spacing: Pixels(4.0),
+            button_style: default_button_style,
+        }
+    }
+
+    /// Sets the height of the preset list.
+    pub fn list_height(mut self, height: impl Into<Length>) -> Self {
+        self.list_height = height.into();
+        self
+    }
+
+    /// Sets the text size of the [`PresetBrowser`].
+    pub fn text_size(mut self, size: impl Into<Pixels>) -> Self {
+        self.text_size = Some(size.into());
+        self
+    }
+
+    /// Sets the spacing between the [`PresetBrowser`]'s elements.
+    pub fn spacing(mut self, spacing: impl Into<Pixels>) -> Self {
+        self.spacing = spacing.into();
+        self
+    }
+
+    /// Sets the style used for the preset and tag buttons. In addition to the theme and the
+    /// button's status, the function receives whether the preset or tag is currently selected. See
+    /// [`default_button_style()`] for the default style.
+    pub fn button_style(
+        mut self,
+        style: fn(&Theme, button::Status, bool) -> button::Style,
+    ) -> Self {
+        self.button_style = style;
+        self
+    }
+}
+
+impl<'a> From<PresetBrowser<'a>> for Element<'a, PresetMessage> {
+    fn from(browser: PresetBrowser<'a>) -> Self {
+        let tags = browser.library.tags();
+        let presets = browser
+            .library
+            .search(&browser.state.query, &browser.state.selected_tags);
+        let text_size = browser.text_size;
+        let button_style = browser.button_style;
+        let spacing = browser.spacing;
+
+        let state = browser.state;
+        let mut header = Row::new()
+            .spacing(spacing)
+            .align_y(Alignment::Center)
+            .push(
+                TextInput::new("Search presets", &state.query)
+                    .on_input(PresetMessage::QueryChanged)
+                    .padding(spacing.0),
+            )
+            .push(Button::new(text("Save...", text_size)).on_press(PresetMessage::OpenSaveForm));
+        if browser.library.is_scanning() {
+            header = header.push(text("Scanning...", text_size));
+        }
+
+        let mut tag_row = Row::new().spacing(spacing);
+        for tag in tags {
+            let selected = state.selected_tags.contains(&tag);
+            tag_row = tag_row.push(
+                Button::new(text(tag.clone(), text_size))
+                    .style(move |theme, status| button_style(theme, status, selected))
+                    .on_press(PresetMessage::ToggleTag(tag)),
+            );
+        }
+
+        let mut preset_column = Column::new().spacing(spacing.0 / 2.0);
+        if presets.is_empty() {
+            preset_column = preset_column.push(text("No presets found", text_size));
+        }
+        for preset in presets {
+            let selected = state
+                .current_preset
+                .as_ref()
+                .map_or(false, |current| current.path == preset.path);
+            preset_column = preset_column.push(
+                Button::new(text(preset.name.clone(), text_size))
+                    .width(Length::Fill)
+                    .style(move |theme, status| button_style(theme, status, selected))
+                    .on_press(PresetMessage::LoadPreset(preset)),
+            );
+        }
+        let preset_list = Scrollable::new(preset_column)
+            .width(Length::Fill)
+            .height(browser.list_height);
+
+        let mut column = Column::new()
+            .spacing(spacing)
+            .push(header)
+            .push(tag_row)
+            .push(preset_list);
+
+        if state.save_form_open {
+            column = column
+                .push(
+                    TextInput::new("Preset name", &state.save_name)
+                        .on_input(PresetMessage::SaveNameChanged)
+                        .padding(spacing.0)
+                        .on_submit(PresetMessage::Save),
+                )
+                .push(
+                    TextInput::new("Tags, comma separated", &state.save_tags)
+                        .on_input(PresetMessage::SaveTagsChanged)
+                        .padding(spacing.0)
+                        .on_submit(PresetMessage::Save),
+                )
+                .push(
+                    Row::new()
+                        .spacing(spacing)
+                        .push(Button::new(text("Save", text_size)).on_press(PresetMessage::Save))
+                        .push(
+                            Button::new(text("Cancel", text_size))
+                                .on_press(PresetMessage::CancelSave),
+                        ),
+                );
+        }
+
+        if let Some(error) = &state.error {
+            column =
+                column.push(text(error.as_str(), text_size).color(Color::from_rgb(0.8, 0.1, 0.1)));
+        }
+
+        column.into()
+    }
+}
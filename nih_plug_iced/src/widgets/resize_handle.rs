@@ -0,0 +1,192 @@
+//! A resize handle for resizable editors.
+
+use std::marker::PhantomData;
+
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::widget::{tree, Tree};
+use crate::advanced::{Clipboard, Shell, Widget};
+use crate::{
+    event, mouse, Background, Color, Element, Event, IcedState, Length, Point, Rectangle, Renderer,
+    Size, Theme,
+};
+
+/// The size of the handle's square hitbox in logical pixels.
+const HANDLE_SIZE: f32 = 18.0;
+/// The size of the dots that make up the handle's grip.
+const DOT_SIZE: f32 = 2.0;
+
+/// A handle that can be dragged to resize the editor's window. Place this in the window's bottom
+/// right corner, for instance by stacking it on top of the editor's other contents. The editor
+/// needs to have been created using [`IcedState::from_size_resizable()`] for this to have any
+/// effect.
+pub struct ResizeHandle<'a, Message> {
+    iced_state: &'a IcedState,
+
+    /// We don't emit any messages, but iced requires us to define some message type anyways.
+    _phantom: PhantomData<Message>,
+}
+
+/// State for a [`ResizeHandle`]. This is stored in iced's widget tree.
+#[derive(Debug, Default)]
+struct State {
+    /// The cursor's position and the window's size in logical pixels when the drag started, or
+    /// `None` if the handle is not being dragged.
+    drag_start: Option<(Point, (u32, u32))>,
+}
+
+impl<'a, Message> ResizeHandle<'a, Message> {
+    /// Create a resize handle for the editor with this state.
+    pub fn new(iced_state: &'a IcedState) -> Self {
+        Self {
+            iced_state,
+
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<'a, Message> Widget<Message, Theme, Renderer> for ResizeHandle<'a, Message> {
+    fn tag(&self) -> tree::Tag {
+        tree::Tag::of::<State>()
+    }
+
+    fn state(&self) -> tree::State {
+        tree::State::new(State::default())
+    }
+
+    fn size(&self) -> Size<Length> {
+        Size::new(Length::Fixed(HANDLE_SIZE), Length::Fixed(HANDLE_SIZE))
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(
+            limits,
+            Length::Fixed(HANDLE_SIZE),
+            Length::Fixed(HANDLE_SIZE),
+        )
+    }
+
+    fn on_event(
+        &mut self,
+        tree: &mut Tree,
+        event: Event,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _renderer: &Renderer,
+        _clipboard: &mut dyn Clipboard,
+        _shell: &mut Shell<'_, Message>,
+        _viewport: &Rectangle,
+    ) -> event::Status {
+        let state = tree.state.downcast_mut::<State>();
+
+        match event {
+            Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)) => {
+                if let Some(position) = cursor.position_over(layout.bounds()) {
+                    state.drag_start = Some((position, self.iced_state.size()));
+
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)) => {
+                if state.drag_start.take().is_some() {
+                    return event::Status::Captured;
+                }
+            }
+            Event::Mouse(mouse::Event::CursorMoved { position }) => {
+                // The window grows by the distance the cursor has moved since the drag started
+                if let Some((start_position, (start_width, start_height))) = state.drag_start {
+                    let new_size = (
+                        (start_width as f32 + position.x - start_position.x)
+                            .max(1.0)
+                            .round() as u32,
+                        (start_height as f32 + position.y - start_position.y)
+                            .max(1.0)
+                            .round() as u32,
+                    );
+                    if new_size != self.iced_state.size() {
+                        self.iced_state.set_requested_size(new_size);
+                    }
+
+                    return event::Status::Captured;
+                }
+            }
+            _ => {}
+        }
+
+        event::Status::Ignored
+    }
+
+    fn mouse_interaction(
+        &self,
+        tree: &Tree,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+        _renderer: &Renderer,
+    ) -> mouse::Interaction {
+        let state = tree.state.downcast_ref::<State>();
+        if state.drag_start.is_some() {
+            mouse::Interaction::Grabbing
+        } else if cursor.is_over(layout.bounds()) {
+            mouse::Interaction::Grab
+        } else {
+            mouse::Interaction::default()
+        }
+    }
+
+    fn draw(
+        &self,
+        tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let state = tree.state.downcast_ref::<State>();
+        let bounds = layout.bounds();
+        let color = if state.drag_start.is_some() || cursor.is_over(bounds) {
+            style.text_color
+        } else {
+            Color {
+                a: style.text_color.a * 0.5,
+                ..style.text_color
+            }
+        };
+
+        // The grip is a triangle of dots in the bottom right corner
+        let spacing = HANDLE_SIZE / 4.0;
+        for row in 1..=3 {
+            for column in (4 - row)..=3 {
+                renderer.fill_quad(
+                    renderer::Quad {
+                        bounds: Rectangle {
+                            x: bounds.x + column as f32 * spacing - (DOT_SIZE / 2.0),
+                            y: bounds.y + row as f32 * spacing - (DOT_SIZE / 2.0),
+                            width: DOT_SIZE,
+                            height: DOT_SIZE,
+                        },
+                        ..renderer::Quad::default()
+                    },
+                    Background::Color(color),
+                );
+            }
+        }
+    }
+}
+
+impl<'a, Message> From<ResizeHandle<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(widget: ResizeHandle<'a, Message>) -> Self {
+        Element::new(widget)
+    }
+}
@@ -0,0 +1,190 @@
+//! A spectrum analyzer display that reads its data from an [`AnalysisOutput`].
+
+use atomic_refcell::AtomicRefCell;
+use nih_plug::util::AnalysisOutput;
+use std::marker::PhantomData;
+
+use crate::advanced::layout::{self, Layout};
+use crate::advanced::renderer::{self, Renderer as _};
+use crate::advanced::widget::Tree;
+use crate::advanced::Widget;
+use crate::{mouse, Background, Border, Color, Element, Length, Rectangle, Renderer, Size, Theme};
+
+use super::util;
+
+/// The thickness of this widget's borders.
+const BORDER_WIDTH: f32 = 1.0;
+
+/// Displays a magnitude spectrum on a logarithmic frequency axis. The spectrum is read from an
+/// [`AnalysisOutput`] containing the linear magnitudes of an FFT's bins, starting with the DC bin
+/// and ending with the Nyquist bin. So for an FFT of size `N` the vector contains `N / 2 + 1`
+/// values. The frequencies of the bins are derived from the sample rate the spectrum was published
+/// with.
+///
+/// TODO: There are currently no styling options at all
+pub struct Spectrum<'a, Message> {
+    data: &'a AtomicRefCell<AnalysisOutput<Vec<f32>>>,
+
+    frequency_range: (f32, f32),
+    db_range: (f32, f32),
+
+    height: Length,
+    width: Length,
+
+    /// We don't emit any messages, but iced requires us to define some message type anyways.
+    _phantom: PhantomData<Message>,
+}
+
+impl<'a, Message> Spectrum<'a, Message> {
+    /// Creates a new [`Spectrum`] that displays the spectrum read from `data`.
+    pub fn new(data: &'a AtomicRefCell<AnalysisOutput<Vec<f32>>>) -> Self {
+        Self {
+            data,
+
+            frequency_range: (20.0, 20_000.0),
+            db_range: (-80.0, 6.0),
+
+            width: Length::Fixed(400.0),
+            height: Length::Fixed(200.0),
+
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Sets the displayed frequency range in Hertz. Defaults to 20 Hz to 20 kHz.
+    pub fn frequency_range(mut self, min: f32, max: f32) -> Self {
+        self.frequency_range = (min, max);
+        self
+    }
+
+    /// Sets the displayed magnitude range in decibel. Defaults to -80 dB to 6 dB.
+    pub fn db_range(mut self, min: f32, max: f32) -> Self {
+        self.db_range = (min, max);
+        self
+    }
+
+    /// Sets the width of the [`Spectrum`].
+    pub fn width(mut self, width: impl Into<Length>) -> Self {
+        self.width = width.into();
+        self
+    }
+
+    /// Sets the height of the [`Spectrum`].
+    pub fn height(mut self, height: impl Into<Length>) -> Self {
+        self.height = height.into();
+        self
+    }
+
+    /// Map a `[0, 1]` position on the logarithmic frequency axis to a frequency in Hertz.
+    fn t_to_frequency(&self, t: f32) -> f32 {
+        let (min, max) = self.frequency_range;
+        min * (max / min).powf(t)
+    }
+
+    /// Map a magnitude in decibel to a `[0, 1]` position where 0 is the top of the widget.
+    fn db_to_t(&self, db: f32) -> f32 {
+        let (min, max) = self.db_range;
+        1.0 - ((db - min) / (max - min))
+    }
+}
+
+impl<'a, Message> Widget<Message, Theme, Renderer> for Spectrum<'a, Message> {
+    fn size(&self) -> Size<Length> {
+        Size::new(self.width, self.height)
+    }
+
+    fn layout(
+        &self,
+        _tree: &mut Tree,
+        _renderer: &Renderer,
+        limits: &layout::Limits,
+    ) -> layout::Node {
+        layout::atomic(limits, self.width, self.height)
+    }
+
+    fn draw(
+        &self,
+        _tree: &Tree,
+        renderer: &mut Renderer,
+        _theme: &Theme,
+        style: &renderer::Style,
+        layout: Layout<'_>,
+        _cursor: mouse::Cursor,
+        _viewport: &Rectangle,
+    ) {
+        let bounds = layout.bounds();
+        renderer.fill_quad(
+            renderer::Quad {
+                bounds,
+                border: Border {
+                    color: Color::BLACK,
+                    width: BORDER_WIDTH,
+                    radius: 0.0.into(),
+                },
+                ..renderer::Quad::default()
+            },
+            Background::Color(Color::TRANSPARENT),
+        );
+
+        let bounds_without_borders = bounds.shrink(BORDER_WIDTH);
+
+        let mut data = self.data.borrow_mut();
+        let frame = data.read_frame();
+        let (spectrum, sample_rate) = (&frame.data, frame.sample_rate);
+        if spectrum.len() < 2 || sample_rate <= 0.0 {
+            return;
+        }
+
+        // Every pixel column is filled up to the loudest bin within that column's frequency range,
+        // so narrow peaks in the high frequencies don't disappear between two columns
+        let bins_per_hz = (spectrum.len() - 1) as f32 / (sample_rate / 2.0);
+        let columns = bounds_without_borders.width.floor().max(1.0) as usize;
+        let fill_color = Color {
+            a: 0.5,
+            ..style.text_color
+        };
+        for column in 0..columns {
+            let start_t = column as f32 / columns as f32;
+            let end_t = (column + 1) as f32 / columns as f32;
+            let start_bin = (self.t_to_frequency(start_t) * bins_per_hz).round() as usize;
+            let end_bin = (self.t_to_frequency(end_t) * bins_per_hz).round() as usize;
+            if start_bin >= spectrum.len() {
+                break;
+            }
+
+            let magnitude = spectrum[start_bin..(end_bin + 1).min(spectrum.len())]
+                .iter()
+                .fold(0.0f32, |max, magnitude| max.max(*magnitude));
+            let y = util::remap_rect_y_t(
+                &bounds_without_borders,
+                self.db_to_t(nih_plug::util::gain_to_db(magnitude)),
+            );
+            let bottom = bounds_without_borders.y + bounds_without_borders.height;
+            if y >= bottom {
+                continue;
+            }
+
+            renderer.fill_quad(
+                renderer::Quad {
+                    bounds: Rectangle {
+                        x: util::remap_rect_x_t(&bounds_without_borders, start_t),
+                        y,
+                        width: 1.0,
+                        height: bottom - y,
+                    },
+                    ..renderer::Quad::default()
+                },
+                Background::Color(fill_color),
+            );
+        }
+    }
+}
+
+impl<'a, Message> From<Spectrum<'a, Message>> for Element<'a, Message>
+where
+    Message: 'a,
+{
+    fn from(widget: Spectrum<'a, Message>) -> Self {
+        Element::new(widget)
+    }
+}
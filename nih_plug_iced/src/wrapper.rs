@@ -1,33 +1,44 @@
 //! An [`Application`] wrapper around an [`IcedEditor`] to bridge between `iced_baseview` and
 //! `nih_plug_iced`.
 
-use crossbeam::channel;
+use futures_util::stream;
+use iced_baseview::{Application, WindowSubs};
+use iced_runtime::window;
 use nih_plug::prelude::GuiContext;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
+use std::task::Poll;
 
-use crate::futures::FutureExt;
 use crate::{
-    futures, subscription, Application, Color, Command, Element, IcedEditor, ParameterUpdate,
-    Subscription, WindowQueue, WindowScalePolicy, WindowSubs,
+    Element, IcedEditor, IcedState, ParameterUpdates, Size, Subscription, Task, Theme,
+    WindowScalePolicy,
 };
 
 /// Wraps an `iced_baseview` [`Application`] around [`IcedEditor`]. Needed to allow editors to
 /// always receive a copy of the GUI context.
 pub(crate) struct IcedEditorWrapperApplication<E: IcedEditor> {
     editor: E,
+    context: Arc<dyn GuiContext>,
+    iced_state: Arc<IcedState>,
 
-    /// We will receive notifications about parameters being changed on here. Whenever a parameter
-    /// update gets sent, we will trigger a [`Message::parameterUpdate`] which causes the UI to be
-    /// redrawn.
-    parameter_updates_receiver: Arc<channel::Receiver<ParameterUpdate>>,
+    /// We will receive notifications about parameters being changed through this. Whenever a
+    /// parameter update gets sent, we will trigger a [`Message::ParameterUpdate`] which causes the
+    /// UI to be redrawn.
+    parameter_updates: Arc<ParameterUpdates>,
 }
 
 /// This wraps around `E::Message` to add a parameter update message which can be handled directly
 /// by this wrapper. That parameter update message simply forces a redraw of the GUI whenever there
-/// is a parameter update.
+/// is a parameter update. The resize messages are used to resize resizable editors.
 pub enum Message<E: IcedEditor> {
     EditorMessage(E::Message),
     ParameterUpdate,
+    /// The GUI requested a new size in logical pixels through [`IcedState::set_requested_size()`].
+    /// This first needs to be approved by the host.
+    RequestResize((u32, u32)),
+    /// Resize the window to a new size in logical pixels. The size has already been stored in the
+    /// [`IcedState`].
+    ResizeWindow((u32, u32)),
 }
 
 impl<E: IcedEditor> std::fmt::Debug for Message<E> {
@@ -35,6 +46,8 @@ impl<E: IcedEditor> std::fmt::Debug for Message<E> {
         match self {
             Self::EditorMessage(arg0) => f.debug_tuple("EditorMessage").field(arg0).finish(),
             Self::ParameterUpdate => write!(f, "ParameterUpdate"),
+            Self::RequestResize(arg0) => f.debug_tuple("RequestResize").field(arg0).finish(),
+            Self::ResizeWindow(arg0) => f.debug_tuple("ResizeWindow").field(arg0).finish(),
         }
     }
 }
@@ -44,6 +57,8 @@ impl<E: IcedEditor> Clone for Message<E> {
         match self {
             Self::EditorMessage(arg0) => Self::EditorMessage(arg0.clone()),
             Self::ParameterUpdate => Self::ParameterUpdate,
+            Self::RequestResize(arg0) => Self::RequestResize(*arg0),
+            Self::ResizeWindow(arg0) => Self::ResizeWindow(*arg0),
         }
     }
 }
@@ -51,108 +66,118 @@ impl<E: IcedEditor> Clone for Message<E> {
 impl<E: IcedEditor> Application for IcedEditorWrapperApplication<E> {
     type Executor = E::Executor;
     type Message = Message<E>;
+    type Theme = Theme;
     type Flags = (
         Arc<dyn GuiContext>,
-        Arc<channel::Receiver<ParameterUpdate>>,
+        Arc<ParameterUpdates>,
+        Arc<IcedState>,
         E::InitializationFlags,
     );
 
     fn new(
-        (context, parameter_updates_receiver, flags): Self::Flags,
-    ) -> (Self, Command<Self::Message>) {
-        let (editor, command) = E::new(flags, context);
+        (context, parameter_updates, iced_state, flags): Self::Flags,
+    ) -> (Self, Task<Self::Message>) {
+        let (editor, task) = E::new(flags, context.clone());
 
         (
             Self {
                 editor,
-                parameter_updates_receiver,
+                context,
+                iced_state,
+                parameter_updates,
             },
-            command.map(Message::EditorMessage),
+            task.map(Message::EditorMessage),
         )
     }
 
     #[inline]
-    fn update(
-        &mut self,
-        window: &mut WindowQueue,
-        message: Self::Message,
-    ) -> Command<Self::Message> {
+    fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
         match message {
-            Message::EditorMessage(message) => self
-                .editor
-                .update(window, message)
-                .map(Message::EditorMessage),
+            Message::EditorMessage(message) => {
+                self.editor.update(message).map(Message::EditorMessage)
+            }
             // This message only exists to force a redraw
-            Message::ParameterUpdate => Command::none(),
+            Message::ParameterUpdate => Task::none(),
+            // Resizes requested from within the GUI first need to be approved by the host. If the
+            // host rejects the new size, then the old size is restored.
+            Message::RequestResize(new_size) => {
+                let old_size = self.iced_state.size.swap(new_size);
+                if new_size == old_size {
+                    Task::none()
+                } else if self.context.request_resize() {
+                    resize_window(new_size)
+                } else {
+                    self.iced_state.size.store(old_size);
+                    Task::none()
+                }
+            }
+            Message::ResizeWindow(new_size) => resize_window(new_size),
         }
     }
 
     #[inline]
     fn subscription(
         &self,
-        window_subs: &mut WindowSubs<Self::Message>,
+        _window_subs: &mut WindowSubs<Self::Message>,
     ) -> Subscription<Self::Message> {
-        // Since we're wrapping around `E::Message`, we need to do this transformation ourselves
-        let mut editor_window_subs = WindowSubs {
-            on_frame: match &window_subs.on_frame {
-                Some(Message::EditorMessage(message)) => Some(message.clone()),
-                _ => None,
-            },
-            on_window_will_close: match &window_subs.on_window_will_close {
-                Some(Message::EditorMessage(message)) => Some(message.clone()),
-                _ => None,
-            },
-        };
-
-        let subscription = Subscription::batch([
-            // For some reason there's no adapter to just convert `futures::channel::mpsc::Receiver`
-            // into a stream that doesn't require consuming that receiver (which wouldn't work in
-            // this case since the subscriptions function gets called repeatedly). So we'll just use
-            // a crossbeam queue and this unfold instead.
-            subscription::unfold(
-                "parameter updates",
-                self.parameter_updates_receiver.clone(),
-                |parameter_updates_receiver| match parameter_updates_receiver.try_recv() {
-                    Ok(_) => futures::future::ready((
-                        Some(Message::ParameterUpdate),
-                        parameter_updates_receiver,
-                    ))
-                    .boxed(),
-                    Err(_) => futures::future::pending().boxed(),
-                },
-            ),
-            self.editor
-                .subscription(&mut editor_window_subs)
-                .map(Message::EditorMessage),
-        ]);
-
-        if let Some(message) = editor_window_subs.on_frame {
-            window_subs.on_frame = Some(Message::EditorMessage(message));
-        }
-        if let Some(message) = editor_window_subs.on_window_will_close {
-            window_subs.on_window_will_close = Some(Message::EditorMessage(message));
-        }
-
-        subscription
+        // The subscription's identity is based on the ID, so this stream is only created once even
+        // though this function gets called after every update
+        let parameter_updates = self.parameter_updates.clone();
+        let parameter_updates_stream = stream::poll_fn(move |cx| {
+            // The waker needs to be registered before checking the flag, otherwise a notification
+            // sent in between could get lost
+            parameter_updates.waker.register(cx.waker());
+            if parameter_updates.pending.swap(false, Ordering::AcqRel) {
+                Poll::Ready(Some(Message::ParameterUpdate))
+            } else {
+                Poll::Pending
+            }
+        });
+
+        // Resizes requested from within the GUI and resizes initiated by the host are handled the
+        // same way
+        let iced_state = self.iced_state.clone();
+        let resizes_stream = stream::poll_fn(move |cx| {
+            iced_state.resize_waker.register(cx.waker());
+            if let Some(new_size) = iced_state.requested_size.take() {
+                Poll::Ready(Some(Message::RequestResize(new_size)))
+            } else if let Some(new_size) = iced_state.pending_window_size.take() {
+                Poll::Ready(Some(Message::ResizeWindow(new_size)))
+            } else {
+                Poll::Pending
+            }
+        });
+
+        Subscription::batch([
+            Subscription::run_with_id("parameter updates", parameter_updates_stream),
+            Subscription::run_with_id("resizes", resizes_stream),
+            self.editor.subscription().map(Message::EditorMessage),
+        ])
     }
 
     #[inline]
-    fn view(&mut self) -> Element<'_, Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         self.editor.view().map(Message::EditorMessage)
     }
 
     #[inline]
-    fn background_color(&self) -> Color {
-        self.editor.background_color()
+    fn theme(&self) -> Self::Theme {
+        self.editor.theme()
     }
 
     #[inline]
-    fn scale_policy(&self) -> WindowScalePolicy {
-        self.editor.scale_policy()
+    fn title(&self) -> String {
+        String::from("iced window")
     }
 
     #[inline]
-    fn renderer_settings() -> iced_baseview::backend::settings::Settings {
-        E::renderer_settings()
+    fn scale_policy(&self) -> WindowScalePolicy {
+        self.editor.scale_policy()
     }
 }
+
+/// Resize the editor's window to a new size in logical pixels.
+fn resize_window<Message: Send + 'static>((width, height): (u32, u32)) -> Task<Message> {
+    window::get_oldest()
+        .and_then(move |id| window::resize(id, Size::new(width as f32, height as f32)))
+}
@@ -3,13 +3,13 @@
 use baseview::{WindowHandle, WindowScalePolicy};
 use crossbeam::atomic::AtomicCell;
 use nih_plug::debug::*;
-use nih_plug::prelude::{Editor, GuiContext, ParentWindowHandle};
+use nih_plug::prelude::{Editor, EditorSizeConstraints, GuiContext, ParentWindowHandle};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use vizia::context::backend::TextConfig;
 use vizia::prelude::*;
 
-use crate::widgets::RawParamEvent;
+use crate::widgets::{HostResizeEvent, RawParamEvent};
 use crate::{assets, widgets, ViziaState, ViziaTheming};
 
 /// An [`Editor`] implementation that calls an vizia draw loop.
@@ -94,7 +94,16 @@ impl Editor for ViziaEditor {
         })
         .on_idle({
             let emit_parameters_changed_event = self.emit_parameters_changed_event.clone();
+            let vizia_state = self.vizia_state.clone();
             move |cx| {
+                // Resizes initiated by the host are applied by changing the user scale factor
+                if let Some(scale_factor) = vizia_state.pending_scale_factor.take() {
+                    cx.emit_custom(
+                        Event::new(HostResizeEvent::SetUserScaleFactor(scale_factor))
+                            .propagate(Propagation::Subtree),
+                    );
+                }
+
                 if emit_parameters_changed_event
                     .compare_exchange(true, false, Ordering::AcqRel, Ordering::Relaxed)
                     .is_ok()
@@ -126,6 +135,44 @@ impl Editor for ViziaEditor {
         self.vizia_state.scaled_logical_size()
     }
 
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        let (min_scale_factor, max_scale_factor) = self.vizia_state.scale_factor_range?;
+        let (inner_width, inner_height) = self.vizia_state.inner_logical_size();
+        let scale_size = |scale_factor: f64| {
+            (
+                (inner_width as f64 * scale_factor).round() as u32,
+                (inner_height as f64 * scale_factor).round() as u32,
+            )
+        };
+
+        // Since resizing changes the user scale factor, the aspect ratio can't change
+        Some(
+            EditorSizeConstraints::new(scale_size(min_scale_factor), scale_size(max_scale_factor))
+                .with_aspect_ratio(inner_width as f32 / inner_height.max(1) as f32),
+        )
+    }
+
+    fn set_size(&self, width: u32, _height: u32) -> bool {
+        if self.vizia_state.scale_factor_range.is_none() {
+            return false;
+        }
+
+        // The size has already been constrained to the aspect ratio, so the width is enough to
+        // compute the new scale factor
+        let (inner_width, _) = self.vizia_state.inner_logical_size();
+        let scale_factor = self
+            .vizia_state
+            .clamp_user_scale_factor(width as f64 / inner_width.max(1) as f64);
+        self.vizia_state.scale_factor.store(scale_factor);
+        if self.vizia_state.is_open() {
+            self.vizia_state
+                .pending_scale_factor
+                .store(Some(scale_factor));
+        }
+
+        true
+    }
+
     fn set_scale_factor(&self, factor: f32) -> bool {
         // If the editor is currently open then the host must not change the current HiDPI scale as
         // we don't have a way to handle that. Ableton Live does this.
@@ -140,6 +187,15 @@ impl Editor for ViziaEditor {
         true
     }
 
+    fn ui_scale(&self) -> Option<f32> {
+        self.vizia_state.ui_scale()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.vizia_state.ui_scale.store(scale);
+        true
+    }
+
     fn param_value_changed(&self, _id: &str, _normalized_value: f32) {
         // This will cause a future idle callback to send a parameters changed event.
         // NOTE: We could add an event containing the parameter's ID and the normalized value, but
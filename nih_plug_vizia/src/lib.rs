@@ -4,6 +4,7 @@
 #![allow(clippy::type_complexity)]
 
 use crossbeam::atomic::AtomicCell;
+use nih_plug::debug::*;
 use nih_plug::params::persist::PersistentField;
 use nih_plug::prelude::{Editor, GuiContext};
 use serde::{Deserialize, Serialize};
@@ -95,6 +96,18 @@ pub struct ViziaState {
     /// This can be used to allow GUIs to be scaled uniformly.
     #[serde(with = "nih_plug::params::persist::serialize_atomic_cell")]
     scale_factor: AtomicCell<f64>,
+    /// The minimum and maximum values for `scale_factor` if the host is allowed to resize the
+    /// editor, or `None` if the editor can only be resized from within the GUI.
+    #[serde(skip)]
+    scale_factor_range: Option<(f64, f64)>,
+    /// A new scale factor set by the host. This is applied to the open window during the next
+    /// idle callback.
+    #[serde(skip)]
+    pending_scale_factor: AtomicCell<Option<f64>>,
+    /// A UI scale chosen by the user that overrides the host's DPI scaling factor. See
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    #[serde(default, with = "nih_plug::params::persist::serialize_atomic_cell")]
+    ui_scale: AtomicCell<Option<f32>>,
     /// Whether the editor's window is currently open.
     #[serde(skip)]
     open: AtomicBool,
@@ -112,6 +125,8 @@ impl Debug for ViziaState {
         f.debug_struct("ViziaState")
             .field("size_fn", &format!("<fn> ({}, {})", width, height))
             .field("scale_factor", &self.scale_factor)
+            .field("scale_factor_range", &self.scale_factor_range)
+            .field("ui_scale", &self.ui_scale)
             .field("open", &self.open)
             .finish()
     }
@@ -120,6 +135,7 @@ impl Debug for ViziaState {
 impl<'a> PersistentField<'a, ViziaState> for Arc<ViziaState> {
     fn set(&self, new_value: ViziaState) {
         self.scale_factor.store(new_value.scale_factor.load());
+        self.ui_scale.store(new_value.ui_scale.load());
     }
 
     fn map<F, R>(&self, f: F) -> R
@@ -139,6 +155,9 @@ impl ViziaState {
         Arc::new(ViziaState {
             size_fn: Box::new(size_fn),
             scale_factor: AtomicCell::new(1.0),
+            scale_factor_range: None,
+            pending_scale_factor: AtomicCell::new(None),
+            ui_scale: AtomicCell::new(None),
             open: AtomicBool::new(false),
         })
     }
@@ -153,6 +172,34 @@ impl ViziaState {
         Arc::new(ViziaState {
             size_fn: Box::new(size_fn),
             scale_factor: AtomicCell::new(default_scale_factor),
+            scale_factor_range: None,
+            pending_scale_factor: AtomicCell::new(None),
+            ui_scale: AtomicCell::new(None),
+            open: AtomicBool::new(false),
+        })
+    }
+
+    /// The same as [`new_with_default_scale_factor()`][Self::new_with_default_scale_factor()], but
+    /// the host is also allowed to resize the editor. The window's aspect ratio stays fixed, and
+    /// resizing the window changes the user scale factor within `min_scale_factor` and
+    /// `max_scale_factor`. The [`ResizeHandle`][widgets::ResizeHandle] widget is limited to the
+    /// same range.
+    pub fn new_with_scale_factor_range(
+        size_fn: impl Fn() -> (u32, u32) + Send + Sync + 'static,
+        default_scale_factor: f64,
+        min_scale_factor: f64,
+        max_scale_factor: f64,
+    ) -> Arc<ViziaState> {
+        nih_debug_assert!(min_scale_factor > 0.0 && min_scale_factor <= max_scale_factor);
+
+        Arc::new(ViziaState {
+            size_fn: Box::new(size_fn),
+            scale_factor: AtomicCell::new(
+                default_scale_factor.clamp(min_scale_factor, max_scale_factor),
+            ),
+            scale_factor_range: Some((min_scale_factor, max_scale_factor)),
+            pending_scale_factor: AtomicCell::new(None),
+            ui_scale: AtomicCell::new(None),
             open: AtomicBool::new(false),
         })
     }
@@ -181,6 +228,23 @@ impl ViziaState {
         self.scale_factor.load()
     }
 
+    /// Clamp a user scale factor to the range set in
+    /// [`new_with_scale_factor_range()`][Self::new_with_scale_factor_range()], if any.
+    pub(crate) fn clamp_user_scale_factor(&self, scale_factor: f64) -> f64 {
+        match self.scale_factor_range {
+            Some((min_scale_factor, max_scale_factor)) => {
+                scale_factor.clamp(min_scale_factor, max_scale_factor)
+            }
+            None => scale_factor,
+        }
+    }
+
+    /// Returns the UI scale chosen by the user, if any. This is set through
+    /// [`GuiContext::set_ui_scale()`][nih_plug::prelude::GuiContext::set_ui_scale()].
+    pub fn ui_scale(&self) -> Option<f32> {
+        self.ui_scale.load()
+    }
+
     /// Whether the GUI is currently visible.
     // Called `is_open()` instead of `open()` to avoid the ambiguity.
     pub fn is_open(&self) -> bool {
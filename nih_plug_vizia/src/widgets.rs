@@ -7,24 +7,32 @@
 
 use crossbeam::atomic::AtomicCell;
 use nih_plug::debug::*;
-use nih_plug::prelude::{GuiContext, Param, ParamPtr};
+use nih_plug::prelude::{GuiContext, NoteEvent, Param, ParamPtr};
 use std::sync::Arc;
 use vizia::prelude::*;
 
 use super::ViziaState;
 
+mod ab_toggle;
 mod generic_ui;
+mod modulation_matrix;
 pub mod param_base;
 mod param_button;
 mod param_slider;
 mod peak_meter;
+mod piano_keyboard;
+mod preset_browser;
 mod resize_handle;
 pub mod util;
 
+pub use ab_toggle::AbToggle;
 pub use generic_ui::GenericUi;
+pub use modulation_matrix::{ModulationMatrixEditor, ModulationMatrixRow};
 pub use param_button::{ParamButton, ParamButtonExt};
 pub use param_slider::{ParamSlider, ParamSliderExt, ParamSliderStyle};
 pub use peak_meter::PeakMeter;
+pub use piano_keyboard::{PianoKeyboard, PianoKeyboardExt};
+pub use preset_browser::{PresetBrowser, PresetBrowserItem};
 pub use resize_handle::ResizeHandle;
 
 /// Register the default theme for the widgets exported by this module. This is automatically called
@@ -66,12 +74,25 @@ pub enum RawParamEvent {
     SetParameterNormalized(ParamPtr, f32),
     /// End an automation gesture for a parameter.
     EndSetParameter(ParamPtr),
+    /// Ask the host to show its context menu for a parameter at a position in the editor window,
+    /// in logical pixels. If the host does not support this, then the parameter is reset to its
+    /// default value instead. See
+    /// [`GuiContext::show_param_context_menu()`][nih_plug::prelude::GuiContext::show_param_context_menu()].
+    ShowParamContextMenu(ParamPtr, (f32, f32)),
+    /// Copy a parameter's current value to the clipboard as a string. See
+    /// [`GuiContext::set_clipboard_text()`][nih_plug::prelude::GuiContext::set_clipboard_text()].
+    CopyToClipboard(ParamPtr),
+    /// Set a parameter from the clipboard's contents as a single automation gesture. Does nothing
+    /// if the clipboard does not contain a valid value for the parameter. See
+    /// [`GuiContext::clipboard_text()`][nih_plug::prelude::GuiContext::clipboard_text()].
+    PasteFromClipboard(ParamPtr),
     /// Sent by the wrapper to indicate that one or more parameter values have changed. Useful when
     /// using properties based on a parameter's value that are computed inside of an event handler.
     ParametersChanged,
 }
 
-/// Events that directly interact with the [`GuiContext`]. Used to trigger resizes.
+/// Events that directly interact with the [`GuiContext`]. Used to trigger resizes and to send note
+/// events to the plugin.
 pub enum GuiContextEvent {
     /// Resize the window to match the current size reported by the [`ViziaState`]'s size function.
     /// By changing the plugin's state that is used to determine the window's size before emitting
@@ -106,6 +127,18 @@ pub enum GuiContextEvent {
     /// }
     /// ```
     Resize,
+    /// Send a note event to the plugin, for instance from an on-screen keyboard like
+    /// [`PianoKeyboard`]. See
+    /// [`GuiContext::send_note_event()`][nih_plug::prelude::GuiContext::send_note_event()].
+    SendNoteEvent(NoteEvent<()>),
+}
+
+/// Events used to apply resizes initiated by the host to the open window. Emitted from the editor's
+/// idle callback and handled by [`WindowModel`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum HostResizeEvent {
+    /// Change the window's user scale factor. This has already been stored in the `ViziaState`.
+    SetUserScaleFactor(f64),
 }
 
 /// Handles parameter updates for VIZIA GUIs. Registered in
@@ -138,6 +171,34 @@ impl Model for ParamModel {
                 self.context.raw_set_parameter_normalized(p, v)
             },
             RawParamEvent::EndSetParameter(p) => unsafe { self.context.raw_end_set_parameter(p) },
+            RawParamEvent::ShowParamContextMenu(p, position) => {
+                if !self.context.show_param_context_menu(p, position) {
+                    // Right clicking used to reset the parameter, so we'll keep doing that when
+                    // the host doesn't have a context menu for us
+                    unsafe {
+                        self.context.raw_begin_set_parameter(p);
+                        self.context
+                            .raw_set_parameter_normalized(p, p.default_normalized_value());
+                        self.context.raw_end_set_parameter(p);
+                    }
+                }
+            }
+            RawParamEvent::CopyToClipboard(p) => unsafe {
+                let value = p.normalized_value_to_string(p.modulated_normalized_value(), true);
+                self.context.set_clipboard_text(&value);
+            },
+            RawParamEvent::PasteFromClipboard(p) => unsafe {
+                if let Some(normalized_value) = self
+                    .context
+                    .clipboard_text()
+                    .and_then(|text| p.string_to_normalized_value(&text))
+                {
+                    self.context.raw_begin_set_parameter(p);
+                    self.context
+                        .raw_set_parameter_normalized(p, normalized_value);
+                    self.context.raw_end_set_parameter(p);
+                }
+            },
             // This can be used by widgets to be notified when parameter values have changed
             RawParamEvent::ParametersChanged => (),
         });
@@ -153,6 +214,21 @@ impl Model for WindowModel {
                 let (width, height) = self.vizia_state.inner_logical_size();
                 cx.set_window_size(WindowSize { width, height });
 
+                meta.consume();
+            }
+            GuiContextEvent::SendNoteEvent(note_event) => {
+                self.context.send_note_event(*note_event);
+
+                meta.consume();
+            }
+        });
+
+        event.map(|host_resize_event, meta| match *host_resize_event {
+            HostResizeEvent::SetUserScaleFactor(scale_factor) => {
+                // Since the `ViziaState` already contains this scale factor, the handler below
+                // won't send another resize request to the host
+                cx.set_user_scale_factor(scale_factor);
+
                 meta.consume();
             }
         });
@@ -160,6 +236,16 @@ impl Model for WindowModel {
         // This gets fired whenever the inner window gets resized
         event.map(|window_event, _| {
             if let WindowEvent::GeometryChanged { .. } = window_event {
+                // The `ResizeHandle` doesn't know about the scale factor range, so the scale factor
+                // is clamped here. Changing the scale factor will fire this event again.
+                let user_scale_factor = cx.user_scale_factor();
+                let clamped_scale_factor =
+                    self.vizia_state.clamp_user_scale_factor(user_scale_factor);
+                if clamped_scale_factor != user_scale_factor {
+                    cx.set_user_scale_factor(clamped_scale_factor);
+                    return;
+                }
+
                 let logical_size = (cx.window_size().width, cx.window_size().height);
                 // `self.vizia_state.inner_logical_size()` should match `logical_size`. Since it's
                 // computed we need to store the last logical size on this object.
@@ -0,0 +1,67 @@
+//! A toggle for comparing two versions of the plugin's state.
+
+use nih_plug::prelude::{GuiContext, StateSlot};
+use std::sync::Arc;
+use vizia::prelude::*;
+
+/// A toggle for comparing two versions of the plugin's state. Clicking on the inactive slot stores
+/// the current state in the active slot and restores the other one, and the copy button copies the
+/// A slot to the B slot. See [`GuiContext::swap_ab()`].
+///
+/// The active slot gets the `:checked` pseudoclass.
+#[derive(Lens)]
+pub struct AbToggle {
+    #[lens(ignore)]
+    context: Arc<dyn GuiContext>,
+
+    /// Whether [`StateSlot::B`] is the active slot.
+    b_active: bool,
+}
+
+enum AbToggleEvent {
+    Swap,
+    CopyAToB,
+}
+
+impl AbToggle {
+    /// Creates a new [`AbToggle`]. The state slots are managed by the editor's [`GuiContext`].
+    pub fn new(cx: &mut Context, context: Arc<dyn GuiContext>) -> Handle<Self> {
+        Self {
+            b_active: context.active_state_slot() == StateSlot::B,
+            context,
+        }
+        .build(cx, |cx| {
+            Label::new(cx, "A")
+                .class("slot")
+                .checked(AbToggle::b_active.map(|b_active| !b_active))
+                .on_press(|cx| cx.emit(AbToggleEvent::Swap));
+            Label::new(cx, "B")
+                .class("slot")
+                .checked(AbToggle::b_active)
+                .on_press(|cx| cx.emit(AbToggleEvent::Swap));
+            Label::new(cx, "A → B")
+                .class("copy")
+                .on_press(|cx| cx.emit(AbToggleEvent::CopyAToB));
+        })
+    }
+}
+
+impl View for AbToggle {
+    fn element(&self) -> Option<&'static str> {
+        Some("ab-toggle")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|ab_toggle_event, meta| {
+            match ab_toggle_event {
+                AbToggleEvent::Swap => {
+                    self.context.swap_ab();
+                }
+                AbToggleEvent::CopyAToB => self.context.copy_a_to_b(),
+            }
+
+            self.b_active = self.context.active_state_slot() == StateSlot::B;
+            meta.consume();
+        });
+    }
+}
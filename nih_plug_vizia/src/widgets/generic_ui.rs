@@ -83,6 +83,8 @@ impl GenericUi {
                 ParamPtr::IntParam(p) => ParamSlider::new(cx, params, move |_| &*p),
                 ParamPtr::BoolParam(p) => ParamSlider::new(cx, params, move |_| &*p),
                 ParamPtr::EnumParam(p) => ParamSlider::new(cx, params, move |_| &*p),
+                ParamPtr::MacroParam(p) => ParamSlider::new(cx, params, move |_| &*p),
+                ParamPtr::DynamicParam(p) => ParamSlider::new(cx, params, move |_| &*p),
             }
         }
         .set_style(match unsafe { param_ptr.step_count() } {
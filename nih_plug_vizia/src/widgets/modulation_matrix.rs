@@ -0,0 +1,191 @@
+//! An editor for a [`ModulationMatrix`]'s routings.
+
+use nih_plug::prelude::{ParamFlags, Params};
+use nih_plug::util::modulation::{ModulationMatrix, ModulationRouting, ModulationSource};
+use std::sync::Arc;
+use vizia::prelude::*;
+
+/// An editor for a [`ModulationMatrix`]'s routings. Every routing gets a row with its source, its
+/// target, and its depth. Clicking on a routing's source or target cycles through the available
+/// sources and targets, and new routings can be added with the button below the list. The
+/// parameters from `params` that are not hidden can be used as modulation targets.
+///
+/// Everything is styled through the `modulation-matrix` element and the classes used by its
+/// children in the style sheet.
+#[derive(Lens)]
+pub struct ModulationMatrixEditor {
+    #[lens(ignore)]
+    matrix: Arc<ModulationMatrix>,
+    /// The IDs and names of the parameters that can be modulated.
+    #[lens(ignore)]
+    targets: Vec<(String, String)>,
+
+    /// The routings' sources and targets as they are displayed in the editor. The list is only
+    /// rebuilt when these change.
+    routings: Vec<ModulationMatrixRow>,
+    /// The routings' depths. These are stored separately so dragging a depth slider doesn't
+    /// rebuild the list.
+    depths: Vec<f32>,
+}
+
+/// A routing in a [`ModulationMatrixEditor`].
+// TODO: Vizia's lens derive macro requires this to be marked as pub
+#[derive(Debug, Clone, PartialEq, Data)]
+pub struct ModulationMatrixRow {
+    pub source: String,
+    pub target: String,
+}
+
+enum ModulationMatrixEvent {
+    NextSource(usize),
+    NextTarget(usize),
+    SetDepth(usize, f32),
+    Remove(usize),
+    Add,
+}
+
+impl ModulationMatrixEditor {
+    /// Creates a new [`ModulationMatrixEditor`] for a modulation matrix that modulates `params`.
+    pub fn new(
+        cx: &mut Context,
+        matrix: Arc<ModulationMatrix>,
+        params: &dyn Params,
+    ) -> Handle<Self> {
+        // SAFETY: The parameters are borrowed from `params`, so they're still alive
+        let targets = params
+            .param_map()
+            .into_iter()
+            .filter(|(_, param_ptr, _)| !unsafe { param_ptr.flags() }.contains(ParamFlags::HIDDEN))
+            .map(|(id, param_ptr, _)| (id, unsafe { param_ptr.name() }.to_owned()))
+            .collect();
+
+        let mut editor = Self {
+            matrix,
+            targets,
+
+            routings: Vec::new(),
+            depths: Vec::new(),
+        };
+        editor.refresh();
+
+        editor.build(cx, |cx| {
+            Binding::new(cx, ModulationMatrixEditor::routings, |cx, routings| {
+                for (idx, routing) in routings.get(cx).into_iter().enumerate() {
+                    HStack::new(cx, |cx| {
+                        Label::new(cx, &routing.source)
+                            .class("source")
+                            .on_press(move |cx| cx.emit(ModulationMatrixEvent::NextSource(idx)));
+                        Label::new(cx, &routing.target)
+                            .class("target")
+                            .on_press(move |cx| cx.emit(ModulationMatrixEvent::NextTarget(idx)));
+                        Slider::new(
+                            cx,
+                            ModulationMatrixEditor::depths
+                                .map(move |depths| depths.get(idx).copied().unwrap_or(0.0)),
+                        )
+                        .range(-1.0..1.0)
+                        .on_changing(move |cx, depth| {
+                            cx.emit(ModulationMatrixEvent::SetDepth(idx, depth))
+                        })
+                        .class("depth");
+                        Label::new(cx, "Remove")
+                            .class("button")
+                            .on_press(move |cx| cx.emit(ModulationMatrixEvent::Remove(idx)));
+                    })
+                    .class("routing");
+                }
+            });
+
+            Label::new(cx, "Add routing")
+                .class("button")
+                .on_press(|cx| cx.emit(ModulationMatrixEvent::Add));
+        })
+    }
+
+    /// Update the displayed routings from the modulation matrix's configuration.
+    fn refresh(&mut self) {
+        let config = self.matrix.config();
+        self.routings = config
+            .routings
+            .iter()
+            .map(|routing| ModulationMatrixRow {
+                source: routing.source.name(),
+                target: self
+                    .targets
+                    .iter()
+                    .find(|(id, _)| id == &routing.target)
+                    .map_or_else(|| routing.target.clone(), |(_, name)| name.clone()),
+            })
+            .collect();
+        self.depths = config
+            .routings
+            .iter()
+            .map(|routing| routing.depth)
+            .collect();
+    }
+
+    /// Modify the routing at `idx`, if it exists.
+    fn update_routing(&self, idx: usize, f: impl FnOnce(&mut ModulationRouting)) {
+        self.matrix.update_config(|config| {
+            if let Some(routing) = config.routings.get_mut(idx) {
+                f(routing);
+            }
+        });
+    }
+}
+
+impl View for ModulationMatrixEditor {
+    fn element(&self) -> Option<&'static str> {
+        Some("modulation-matrix")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|modulation_matrix_event, meta| {
+            match *modulation_matrix_event {
+                ModulationMatrixEvent::NextSource(idx) => self.update_routing(idx, |routing| {
+                    let current_idx = ModulationSource::ALL
+                        .iter()
+                        .position(|source| *source == routing.source);
+                    routing.source = ModulationSource::ALL
+                        [current_idx.map_or(0, |idx| (idx + 1) % ModulationSource::ALL.len())];
+                }),
+                ModulationMatrixEvent::NextTarget(idx) => {
+                    let targets = &self.targets;
+                    if !targets.is_empty() {
+                        self.update_routing(idx, |routing| {
+                            let current_idx =
+                                targets.iter().position(|(id, _)| id == &routing.target);
+                            routing.target = targets
+                                [current_idx.map_or(0, |idx| (idx + 1) % targets.len())]
+                            .0
+                            .clone();
+                        })
+                    }
+                }
+                ModulationMatrixEvent::SetDepth(idx, depth) => {
+                    self.update_routing(idx, |routing| routing.depth = depth)
+                }
+                ModulationMatrixEvent::Remove(idx) => self.matrix.remove_routing(idx),
+                ModulationMatrixEvent::Add => {
+                    if let Some((target, _)) = self.targets.first() {
+                        self.matrix.add_routing(ModulationRouting {
+                            source: ModulationSource::Lfo(0),
+                            target: target.clone(),
+                            depth: 0.0,
+                        });
+                    }
+                }
+            }
+
+            self.refresh();
+            meta.consume();
+        });
+
+        // The configuration may also have been changed by loading a preset
+        event.map(|window_event, _| {
+            if let WindowEvent::MouseEnter = window_event {
+                self.refresh();
+            }
+        });
+    }
+}
@@ -194,6 +194,31 @@ impl ParamWidgetBase {
         })
     }
 
+    /// Ask the host to show its context menu for the parameter at the current mouse position. The
+    /// parameter is reset to its default value instead if the host does not support this.
+    pub fn show_param_context_menu(&self, cx: &mut EventContext) {
+        let scale_factor = cx.scale_factor();
+        let position = (
+            cx.mouse().cursorx / scale_factor,
+            cx.mouse().cursory / scale_factor,
+        );
+        cx.emit(RawParamEvent::ShowParamContextMenu(
+            self.param_ptr,
+            position,
+        ));
+    }
+
+    /// Copy the parameter's current value to the clipboard as a string.
+    pub fn copy_to_clipboard(&self, cx: &mut EventContext) {
+        cx.emit(RawParamEvent::CopyToClipboard(self.param_ptr));
+    }
+
+    /// Set the parameter from the clipboard's contents as a single automation gesture. Does
+    /// nothing if the clipboard does not contain a valid value for the parameter.
+    pub fn paste_from_clipboard(&self, cx: &mut EventContext) {
+        cx.emit(RawParamEvent::PasteFromClipboard(self.param_ptr));
+    }
+
     /// Start an automation gesture. This **must** be called before `set_normalized_value()`
     /// is called. Usually this is done on mouse down.
     pub fn begin_set_parameter(&self, cx: &mut EventContext) {
@@ -38,6 +38,11 @@ impl ParamButton {
         P: Param + 'static,
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
+        // Screen readers announce the button using the parameter's name
+        let name_lens = params
+            .clone()
+            .map(move |params| params_to_param(params).name().to_owned());
+
         Self {
             param_base: ParamWidgetBase::new(cx, params, params_to_param),
 
@@ -66,6 +71,10 @@ impl ParamButton {
             params_to_param,
             |param| param.modulated_normalized_value() >= 0.5,
         ))
+        // This makes the button reachable using the Tab key and exposes it to screen readers
+        .role(Role::CheckBox)
+        .name(name_lens)
+        .navigable(true)
     }
 
     /// Set the parameter's normalized value to either 0.0 or 1.0 depending on its current value.
@@ -93,6 +102,15 @@ impl View for ParamButton {
                 self.toggle_value(cx);
                 meta.consume();
             }
+            // Space and Enter toggle the button while it has keyboard focus
+            WindowEvent::KeyDown(Code::Space | Code::Enter, _) => {
+                self.toggle_value(cx);
+                meta.consume();
+            }
+            WindowEvent::ActionRequest(request) if request.action == Action::Default => {
+                self.toggle_value(cx);
+                meta.consume();
+            }
             WindowEvent::MouseScroll(_scroll_x, scroll_y) if self.use_scroll_wheel => {
                 // With a regular scroll wheel `scroll_y` will only ever be -1 or 1, but with smooth
                 // scrolling trackpads being a thing `scroll_y` could be anything.
@@ -3,7 +3,7 @@
 use nih_plug::prelude::Param;
 use vizia::prelude::*;
 
-use super::param_base::ParamWidgetBase;
+use super::param_base::{ParamWidgetBase, ParamWidgetData};
 use super::util::{self, ModifiersExt};
 
 /// When shift+dragging a parameter, one pixel dragged corresponds to this much change in the
@@ -12,6 +12,8 @@ const GRANULAR_DRAG_MULTIPLIER: f32 = 0.1;
 
 /// A slider that integrates with NIH-plug's [`Param`] types. Use the
 /// [`set_style()`][ParamSliderExt::set_style()] method to change how the value gets displayed.
+/// Control+C and Control+V copy the parameter's value to and from the clipboard while the slider
+/// has keyboard focus.
 #[derive(Lens)]
 pub struct ParamSlider {
     param_base: ParamWidgetBase,
@@ -37,6 +39,10 @@ pub struct ParamSlider {
     style: ParamSliderStyle,
     /// A specific label to use instead of displaying the parameter's value.
     label_override: Option<String>,
+    /// The range of normalized polyphonic modulation offsets applied on top of the parameter's
+    /// modulated value, as `(min_offset, max_offset)`. Set through
+    /// [`ParamSliderExt::with_poly_modulation()`].
+    poly_modulation_range: (f32, f32),
 }
 
 /// How the [`ParamSlider`] should display its values. Set this using
@@ -96,6 +102,11 @@ impl ParamSlider {
         P: Param + 'static,
         FMap: Fn(&Params) -> &P + Copy + 'static,
     {
+        // Screen readers announce the slider using the parameter's name
+        let name_lens = params
+            .clone()
+            .map(move |params| params_to_param(params).name().to_owned());
+
         // We'll visualize the difference between the current value and the default value if the
         // default value lies somewhere in the middle and the parameter is continuous. Otherwise
         // this approach looks a bit jarring.
@@ -110,6 +121,7 @@ impl ParamSlider {
             scrolled_lines: 0.0,
             style: ParamSliderStyle::Centered,
             label_override: None,
+            poly_modulation_range: (0.0, 0.0),
         }
         .build(
             cx,
@@ -168,6 +180,7 @@ impl ParamSlider {
                                         fill_start_delta_lens,
                                         modulation_start_delta_lens,
                                     );
+                                    Self::slider_poly_modulation_view(cx, param_data, style);
                                     Self::slider_label_view(
                                         cx,
                                         param_data.param(),
@@ -184,6 +197,10 @@ impl ParamSlider {
                 });
             }),
         )
+        // This makes the slider reachable using the Tab key and exposes it to screen readers
+        .role(Role::Slider)
+        .name(name_lens)
+        .navigable(true)
     }
 
     /// Create a text input that's shown in place of the slider.
@@ -250,6 +267,50 @@ impl ParamSlider {
             .hoverable(false);
     }
 
+    /// Create the bar showing the range of polyphonic modulation offsets set with
+    /// [`ParamSliderExt::with_poly_modulation()`]. This is drawn on top of the regular fill, and it
+    /// spans all values the parameter currently has across the plugin's active voices.
+    fn slider_poly_modulation_view<L, Params, P, FMap>(
+        cx: &mut Context,
+        param_data: ParamWidgetData<L, Params, P, FMap>,
+        style: ParamSliderStyle,
+    ) where
+        L: Lens<Target = Params> + Copy,
+        Params: 'static,
+        P: Param + 'static,
+        FMap: Fn(&Params) -> &P + Copy + 'static,
+    {
+        Binding::new(
+            cx,
+            ParamSlider::poly_modulation_range,
+            move |cx, poly_modulation_range| {
+                let poly_modulation_range = poly_modulation_range.get(cx);
+                let poly_modulation_start_delta_lens = param_data.make_lens(move |param| {
+                    Self::compute_poly_modulation_fill_start_delta(
+                        style,
+                        param,
+                        poly_modulation_range,
+                    )
+                });
+
+                Element::new(cx)
+                    .class("fill")
+                    .class("fill--poly-modulation")
+                    .height(Stretch(1.0))
+                    .visibility(poly_modulation_start_delta_lens.map(|(_, delta)| *delta > 0.0))
+                    .left(
+                        poly_modulation_start_delta_lens
+                            .map(|(start_t, _)| Percentage(start_t * 100.0)),
+                    )
+                    .width(
+                        poly_modulation_start_delta_lens
+                            .map(|(_, delta)| Percentage(delta * 100.0)),
+                    )
+                    .hoverable(false);
+            },
+        );
+    }
+
     /// Create the text part of the slider. Shown on top of the fill using a `ZStack`.
     fn slider_label_view<P: Param, L: Lens<Target = String>>(
         cx: &mut Context,
@@ -380,6 +441,28 @@ impl ParamSlider {
         }
     }
 
+    /// The same as `compute_modulation_fill_start_delta`, but for the range of polyphonic
+    /// modulation offsets. These are applied on top of the monophonic modulation, and the
+    /// resulting range is clamped to `[0, 1]`. The delta is never negative.
+    fn compute_poly_modulation_fill_start_delta<P: Param>(
+        style: ParamSliderStyle,
+        param: &P,
+        (min_offset, max_offset): (f32, f32),
+    ) -> (f32, f32) {
+        match style {
+            ParamSliderStyle::CurrentStep { .. } | ParamSliderStyle::CurrentStepLabeled { .. } => {
+                (0.0, 0.0)
+            }
+            ParamSliderStyle::Centered | ParamSliderStyle::FromLeft => {
+                let modulated_value = param.modulated_normalized_value();
+                let start = (modulated_value + min_offset.min(max_offset)).clamp(0.0, 1.0);
+                let end = (modulated_value + max_offset.max(min_offset)).clamp(0.0, 1.0);
+
+                (start, end - start)
+            }
+        }
+    }
+
     /// `self.param_base.set_normalized_value()`, but resulting from a mouse drag. When using the
     /// 'even' stepped slider styles from [`ParamSliderStyle`] this will remap the normalized range
     /// to match up with the fill value display. This still needs to be wrapped in a parameter
@@ -403,6 +486,33 @@ impl ParamSlider {
 
         self.param_base.set_normalized_value(cx, normalized_value);
     }
+
+    /// Set the parameter to a new normalized value as a single gesture, used for keyboard input and
+    /// for actions requested by assistive technologies. If the slider is currently being dragged,
+    /// then this becomes part of the drag's gesture.
+    fn set_normalized_value_gesture(&self, cx: &mut EventContext, normalized_value: f32) {
+        if !self.drag_active {
+            self.param_base.begin_set_parameter(cx);
+        }
+        self.param_base.set_normalized_value(cx, normalized_value);
+        if !self.drag_active {
+            self.param_base.end_set_parameter(cx);
+        }
+    }
+
+    /// Move the parameter one step up or down.
+    fn step_value(&self, cx: &mut EventContext, increment: bool, use_finer_steps: bool) {
+        let current_value = self.param_base.unmodulated_normalized_value();
+        let new_value = if increment {
+            self.param_base
+                .next_normalized_step(current_value, use_finer_steps)
+        } else {
+            self.param_base
+                .previous_normalized_step(current_value, use_finer_steps)
+        };
+
+        self.set_normalized_value_gesture(cx, new_value);
+    }
 }
 
 impl View for ParamSlider {
@@ -410,6 +520,22 @@ impl View for ParamSlider {
         Some("param-slider")
     }
 
+    fn accessibility(&self, _cx: &mut AccessContext, node: &mut AccessNode) {
+        // The numeric value is the normalized value, and the text value contains the formatted
+        // value screen readers should announce
+        let normalized_value = self.param_base.unmodulated_normalized_value();
+        node.set_min_numeric_value(0.0);
+        node.set_max_numeric_value(1.0);
+        if let Some(step_count) = self.param_base.step_count() {
+            node.set_numeric_value_step(1.0 / step_count.max(1) as f64);
+        }
+        node.set_numeric_value(normalized_value as f64);
+        node.set_text_value(
+            self.param_base
+                .normalized_value_to_string(normalized_value, true),
+        );
+    }
+
     fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
         event.map(|param_slider_event, meta| match param_slider_event {
             ParamSliderEvent::CancelTextInput => {
@@ -443,8 +569,8 @@ impl View for ParamSlider {
                     self.text_input_active = true;
                     cx.set_active(true);
                 } else if cx.modifiers().command() {
-                    // Ctrl+Click, double click, and right clicks should reset the parameter instead
-                    // of initiating a drag operation
+                    // Ctrl+Click and double clicks should reset the parameter instead of initiating
+                    // a drag operation
                     self.param_base.begin_set_parameter(cx);
                     self.param_base
                         .set_normalized_value(cx, self.param_base.default_normalized_value());
@@ -478,12 +604,18 @@ impl View for ParamSlider {
 
                 meta.consume();
             }
+            WindowEvent::MouseDown(MouseButton::Right) => {
+                // Right clicking shows the host's context menu for the parameter. If the host does
+                // not support this, then this resets the parameter instead.
+                self.param_base.show_param_context_menu(cx);
+
+                meta.consume();
+            }
             WindowEvent::MouseDoubleClick(MouseButton::Left)
-            | WindowEvent::MouseDown(MouseButton::Right)
             | WindowEvent::MouseDoubleClick(MouseButton::Right)
             | WindowEvent::MouseTripleClick(MouseButton::Right) => {
-                // Ctrl+Click, double click, and right clicks should reset the parameter instead of
-                // initiating a drag operation
+                // Ctrl+Click and double clicks should reset the parameter instead of initiating a
+                // drag operation
                 self.param_base.begin_set_parameter(cx);
                 self.param_base
                     .set_normalized_value(cx, self.param_base.default_normalized_value());
@@ -548,6 +680,55 @@ impl View for ParamSlider {
                     );
                 }
             }
+            // The arrow keys step through the parameter's values while the slider has keyboard
+            // focus, with Shift for finer steps
+            WindowEvent::KeyDown(code, _) if !self.text_input_active => {
+                let use_finer_steps = cx.modifiers().shift();
+                match code {
+                    Code::KeyC if cx.modifiers().command() => self.param_base.copy_to_clipboard(cx),
+                    // Pasting while dragging would interrupt the drag's automation gesture
+                    Code::KeyV if cx.modifiers().command() && !self.drag_active => {
+                        self.param_base.paste_from_clipboard(cx)
+                    }
+                    Code::ArrowUp | Code::ArrowRight => self.step_value(cx, true, use_finer_steps),
+                    Code::ArrowDown | Code::ArrowLeft => {
+                        self.step_value(cx, false, use_finer_steps)
+                    }
+                    Code::Home => self.set_normalized_value_gesture(cx, 0.0),
+                    Code::End => self.set_normalized_value_gesture(cx, 1.0),
+                    Code::Enter => {
+                        // Enter opens the text entry, just like Alt+Click
+                        self.text_input_active = true;
+                        cx.set_active(true);
+                    }
+                    _ => return,
+                }
+
+                meta.consume();
+            }
+            WindowEvent::ActionRequest(request) => {
+                match request.action {
+                    Action::Increment => self.step_value(cx, true, false),
+                    Action::Decrement => self.step_value(cx, false, false),
+                    Action::SetValue => match request.data {
+                        Some(ActionData::NumericValue(value)) => {
+                            self.set_normalized_value_gesture(cx, value.clamp(0.0, 1.0) as f32)
+                        }
+                        Some(ActionData::Value(ref string)) => {
+                            if let Some(normalized_value) =
+                                self.param_base.string_to_normalized_value(string)
+                            {
+                                self.set_normalized_value_gesture(cx, normalized_value);
+                            }
+                        }
+                        _ => return,
+                    },
+                    Action::Focus => cx.focus(),
+                    _ => return,
+                }
+
+                meta.consume();
+            }
             WindowEvent::MouseScroll(_scroll_x, scroll_y) if self.use_scroll_wheel => {
                 // With a regular scroll wheel `scroll_y` will only ever be -1 or 1, but with smooth
                 // scrolling trackpads being a thing `scroll_y` could be anything.
@@ -603,6 +784,15 @@ pub trait ParamSliderExt {
     /// Manually set a fixed label for the slider instead of displaying the current value. This is
     /// currently not reactive.
     fn with_label(self, value: impl Into<String>) -> Self;
+
+    /// Display the range of polyphonic modulation offsets the parameter is currently being
+    /// modulated by as an additional bar. The lens should return the lowest and highest normalized
+    /// offset across the plugin's active voices as a `(min_offset, max_offset)` tuple, and
+    /// `(0.0, 0.0)` if there is no polyphonic modulation. Hosts send these offsets to the plugin
+    /// through [`NoteEvent::PolyModulation`][nih_plug::prelude::NoteEvent::PolyModulation] events,
+    /// so the plugin needs to track them and share them with the editor, for instance through a
+    /// pair of `AtomicF32`s. Monophonic modulation is always displayed.
+    fn with_poly_modulation<L: Lens<Target = (f32, f32)>>(self, range: L) -> Self;
 }
 
 impl ParamSliderExt for Handle<'_, ParamSlider> {
@@ -619,4 +809,13 @@ impl ParamSliderExt for Handle<'_, ParamSlider> {
             param_slider.label_override = Some(value.into())
         })
     }
+
+    fn with_poly_modulation<L: Lens<Target = (f32, f32)>>(self, range: L) -> Self {
+        self.bind(range, |handle, range| {
+            let range = range.get(&handle);
+            handle.modify(|param_slider: &mut ParamSlider| {
+                param_slider.poly_modulation_range = range
+            });
+        })
+    }
 }
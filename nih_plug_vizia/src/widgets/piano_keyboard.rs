@@ -0,0 +1,195 @@
+//! An on-screen piano keyboard that sends note events to the plugin.
+
+use nih_plug::prelude::NoteEvent;
+use nih_plug::util::keyboard::{is_black_key, KeyboardLayout};
+use std::ops::RangeInclusive;
+use vizia::prelude::*;
+use vizia::vg;
+
+use super::GuiContextEvent;
+
+/// A piano keyboard that can be played with the mouse. Pressing a key sends a `NoteOn` event to the
+/// plugin through [`GuiContext::send_note_event()`][nih_plug::prelude::GuiContext::send_note_event()],
+/// and releasing it or dragging to another key sends the matching `NoteOff`. The plugin needs to
+/// accept note events through [`Plugin::MIDI_INPUT`][nih_plug::prelude::Plugin::MIDI_INPUT] for this
+/// to have any effect.
+///
+/// The white keys use the `background-color` property and the black keys use the `color` property.
+/// The `:active` pseudoclass is set while a key is held down.
+pub struct PianoKeyboard {
+    notes: RangeInclusive<u8>,
+
+    // These fields are set through modifiers:
+    /// The MIDI channel notes are sent on.
+    channel: u8,
+    /// The velocity used for the sent notes, in `[0, 1]`.
+    velocity: f32,
+
+    /// The note that is currently being held down with the mouse, if any.
+    held_note: Option<u8>,
+}
+
+impl PianoKeyboard {
+    /// Creates a new [`PianoKeyboard`] spanning the given range of MIDI notes. The range is extended
+    /// to start and end on a white key.
+    pub fn new(cx: &mut Context, notes: RangeInclusive<u8>) -> Handle<Self> {
+        // Styling is done in the style sheet
+        Self {
+            notes,
+
+            channel: 0,
+            velocity: 1.0,
+
+            held_note: None,
+        }
+        .build(cx, |_| {})
+    }
+
+    /// The keyboard's layout for the current entity's bounding box.
+    fn layout(&self, bounds: BoundingBox) -> KeyboardLayout {
+        KeyboardLayout::new(self.notes.clone(), bounds.w, bounds.h)
+    }
+
+    /// Release the currently held note and play `note` instead, if they differ.
+    fn set_held_note(&mut self, cx: &mut EventContext, note: Option<u8>) {
+        if note == self.held_note {
+            return;
+        }
+
+        if let Some(note) = self.held_note {
+            cx.emit(GuiContextEvent::SendNoteEvent(NoteEvent::NoteOff {
+                timing: 0,
+                voice_id: None,
+                channel: self.channel,
+                note,
+                velocity: 0.0,
+            }));
+        }
+        if let Some(note) = note {
+            cx.emit(GuiContextEvent::SendNoteEvent(NoteEvent::NoteOn {
+                timing: 0,
+                voice_id: None,
+                channel: self.channel,
+                note,
+                velocity: self.velocity,
+            }));
+        }
+
+        self.held_note = note;
+        cx.needs_redraw();
+    }
+
+    /// The note under the mouse cursor, if any.
+    fn note_at_cursor(&self, cx: &EventContext, x: f32, y: f32) -> Option<u8> {
+        let bounds = cx.cache.get_bounds(cx.current());
+        self.layout(bounds).note_at(x - bounds.x, y - bounds.y)
+    }
+}
+
+impl View for PianoKeyboard {
+    fn element(&self) -> Option<&'static str> {
+        Some("piano-keyboard")
+    }
+
+    fn event(&mut self, cx: &mut EventContext, event: &mut Event) {
+        event.map(|window_event, meta| match *window_event {
+            WindowEvent::MouseDown(MouseButton::Left) => {
+                let note = self.note_at_cursor(cx, cx.mouse().cursorx, cx.mouse().cursory);
+                if note.is_some() {
+                    cx.capture();
+                    cx.set_active(true);
+
+                    self.set_held_note(cx, note);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseUp(MouseButton::Left) => {
+                if self.held_note.is_some() {
+                    cx.release();
+                    cx.set_active(false);
+
+                    self.set_held_note(cx, None);
+
+                    meta.consume();
+                }
+            }
+            WindowEvent::MouseMove(x, y) => {
+                // Dragging over the keys plays the key under the cursor, glissando style. Dragging
+                // outside of the keyboard keeps the last note held until the mouse is released.
+                if self.held_note.is_some() {
+                    if let Some(note) = self.note_at_cursor(cx, x, y) {
+                        self.set_held_note(cx, Some(note));
+                    }
+                }
+            }
+            _ => {}
+        });
+    }
+
+    fn draw(&self, cx: &mut DrawContext, canvas: &mut Canvas) {
+        let bounds = cx.bounds();
+        if bounds.w == 0.0 || bounds.h == 0.0 {
+            return;
+        }
+
+        let opacity = cx.opacity();
+        let mut white_key_color: vg::Color = cx.background_color().into();
+        white_key_color.set_alphaf(white_key_color.a * opacity);
+        let mut black_key_color: vg::Color = cx.font_color().into();
+        black_key_color.set_alphaf(black_key_color.a * opacity);
+        let mut border_color: vg::Color = cx.border_color().into();
+        border_color.set_alphaf(border_color.a * opacity);
+        let border_width = cx.border_width().max(1.0);
+        let held_key_color = vg::Color::rgbaf(0.5, 0.5, 0.5, opacity);
+
+        // Black keys are drawn on top of the white keys
+        let layout = self.layout(bounds);
+        for note in layout.white_keys().chain(layout.black_keys()) {
+            let key = match layout.key_rect(note) {
+                Some(key) => key,
+                None => continue,
+            };
+
+            let mut path = vg::Path::new();
+            path.rect(
+                bounds.x + key.x + border_width / 2.0,
+                bounds.y + key.y + border_width / 2.0,
+                key.width - border_width,
+                key.height - border_width,
+            );
+
+            let fill_color = if self.held_note == Some(note) {
+                held_key_color
+            } else if is_black_key(note) {
+                black_key_color
+            } else {
+                white_key_color
+            };
+            canvas.fill_path(&path, &vg::Paint::color(fill_color));
+
+            let mut paint = vg::Paint::color(border_color);
+            paint.set_line_width(border_width);
+            canvas.stroke_path(&path, &paint);
+        }
+    }
+}
+
+/// Extension methods for [`PianoKeyboard`] handles.
+pub trait PianoKeyboardExt {
+    /// The MIDI channel notes are sent on, in `[0, 15]`. Defaults to the first channel.
+    fn channel(self, channel: u8) -> Self;
+
+    /// The velocity used for the sent notes, in `[0, 1]`. Defaults to full velocity.
+    fn velocity(self, velocity: f32) -> Self;
+}
+
+impl PianoKeyboardExt for Handle<'_, PianoKeyboard> {
+    fn channel(self, channel: u8) -> Self {
+        self.modify(|keyboard: &mut PianoKeyboard| keyboard.channel = channel.min(15))
+    }
+
+    fn velocity(self, velocity: f32) -> Self {
+        self.modify(|keyboard: &mut PianoKeyboard| keyboard.velocity = velocity.clamp(0.0, 1.0))
+    }
+}
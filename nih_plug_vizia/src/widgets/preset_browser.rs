@@ -0,0 +1,276 @@
+//! A preset browser built on top of [`PresetLibrary`].
+
+use nih_plug::prelude::GuiContext;
+use nih_plug::util::{PresetEntry, PresetLibrary};
+use std::sync::Arc;
+use vizia::prelude::*;
+
+/// A preset browser for a [`PresetLibrary`]. Presets can be searched by name and filtered by
+/// clicking on their tags, and clicking on a preset loads it. The save button opens a form for
+/// saving the plugin's current state as a new preset.
+///
+/// The library is scanned on a background thread, so the preset list is refreshed whenever the
+/// mouse enters the browser. Everything is styled through the `preset-browser` element and the
+/// classes used by its children in the style sheet. Selected presets and tags get the `:checked`
+/// pseudoclass.
+#[derive(Lens)]
+pub struct PresetBrowser {
+    #[lens(ignore)]
+    library: Arc<PresetLibrary>,
+    #[lens(ignore)]
+    context: Arc<dyn GuiContext>,
+
+    /// The presets matching the current query and tags, in the same order as `items`.
+    #[lens(ignore)]
+    presets: Vec<PresetEntry>,
+    /// The last preset that was loaded or saved.
+    #[lens(ignore)]
+    current_preset: Option<PresetEntry>,
+
+    /// The text in the search field.
+    query: String,
+    /// The tags presets need to have to be shown.
+    selected_tags: Vec<String>,
+    /// Every tag used in the library, and whether it is selected.
+    tags: Vec<PresetBrowserItem>,
+    /// Every preset matching the query and selected tags, and whether it is the current preset.
+    items: Vec<PresetBrowserItem>,
+
+    /// Whether the save form is currently shown.
+    save_form_open: bool,
+    /// The name entered in the save form.
+    save_name: String,
+    /// The comma separated tags entered in the save form.
+    save_tags: String,
+
+    /// The last error that occurred while loading or saving a preset, or an empty string.
+    error: String,
+}
+
+/// A preset or tag in a [`PresetBrowser`]'s lists.
+// TODO: Vizia's lens derive macro requires this to be marked as pub
+#[derive(Debug, Clone, PartialEq, Data)]
+pub struct PresetBrowserItem {
+    pub name: String,
+    pub selected: bool,
+}
+
+enum PresetBrowserEvent {
+    SetQuery(String),
+    ToggleTag(String),
+    LoadPreset(usize),
+    Rescan,
+    OpenSaveForm,
+    SetSaveName(String),
+    SetSaveTags(String),
+    Save,
+    CancelSave,
+}
+
+impl PresetBrowser {
+    /// Creates a new [`PresetBrowser`] for a preset library. Presets are loaded and saved using
+    /// the editor's [`GuiContext`].
+    pub fn new(
+        cx: &mut Context,
+        library: Arc<PresetLibrary>,
+        context: Arc<dyn GuiContext>,
+    ) -> Handle<Self> {
+        let mut browser = Self {
+            library,
+            context,
+
+            presets: Vec::new(),
+            current_preset: None,
+
+            query: String::new(),
+            selected_tags: Vec::new(),
+            tags: Vec::new(),
+            items: Vec::new(),
+
+            save_form_open: false,
+            save_name: String::new(),
+            save_tags: String::new(),
+
+            error: String::new(),
+        };
+        browser.refresh();
+
+        browser.build(cx, |cx| {
+            HStack::new(cx, |cx| {
+                Textbox::new(cx, PresetBrowser::query)
+                    .on_edit(|cx, query| cx.emit(PresetBrowserEvent::SetQuery(query)))
+                    .class("search");
+                Label::new(cx, "Rescan")
+                    .class("button")
+                    .on_press(|cx| cx.emit(PresetBrowserEvent::Rescan));
+                Label::new(cx, "Save...")
+                    .class("button")
+                    .on_press(|cx| cx.emit(PresetBrowserEvent::OpenSaveForm));
+            })
+            .class("header");
+
+            Binding::new(cx, PresetBrowser::tags, |cx, tags| {
+                HStack::new(cx, |cx| {
+                    for tag in tags.get(cx) {
+                        let name = tag.name.clone();
+                        Label::new(cx, &tag.name)
+                            .class("tag")
+                            .checked(tag.selected)
+                            .on_press(move |cx| {
+                                cx.emit(PresetBrowserEvent::ToggleTag(name.clone()))
+                            });
+                    }
+                })
+                .class("tags");
+            });
+
+            ScrollView::new(cx, 0.0, 0.0, false, true, |cx| {
+                Binding::new(cx, PresetBrowser::items, |cx, items| {
+                    let items = items.get(cx);
+                    if items.is_empty() {
+                        Label::new(cx, "No presets found").class("empty");
+                    }
+
+                    for (idx, item) in items.into_iter().enumerate() {
+                        Label::new(cx, &item.name)
+                            .class("preset")
+                            .checked(item.selected)
+                            .on_press(move |cx| cx.emit(PresetBrowserEvent::LoadPreset(idx)));
+                    }
+                });
+            })
+            .class("presets");
+
+            Binding::new(cx, PresetBrowser::save_form_open, |cx, save_form_open| {
+                if !save_form_open.get(cx) {
+                    return;
+                }
+
+                VStack::new(cx, |cx| {
+                    Textbox::new(cx, PresetBrowser::save_name)
+                        .on_edit(|cx, name| cx.emit(PresetBrowserEvent::SetSaveName(name)));
+                    Textbox::new(cx, PresetBrowser::save_tags)
+                        .on_edit(|cx, tags| cx.emit(PresetBrowserEvent::SetSaveTags(tags)));
+                    HStack::new(cx, |cx| {
+                        Label::new(cx, "Save")
+                            .class("button")
+                            .on_press(|cx| cx.emit(PresetBrowserEvent::Save));
+                        Label::new(cx, "Cancel")
+                            .class("button")
+                            .on_press(|cx| cx.emit(PresetBrowserEvent::CancelSave));
+                    });
+                })
+                .class("save-form");
+            });
+
+            Binding::new(cx, PresetBrowser::error, |cx, error| {
+                let error = error.get(cx);
+                if !error.is_empty() {
+                    Label::new(cx, &error).class("error");
+                }
+            });
+        })
+    }
+
+    /// Update the tag and preset lists from the library.
+    fn refresh(&mut self) {
+        self.presets = self.library.search(&self.query, &self.selected_tags);
+        self.items = self
+            .presets
+            .iter()
+            .map(|preset| PresetBrowserItem {
+                name: preset.name.clone(),
+                selected: self
+                    .current_preset
+                    .as_ref()
+                    .map_or(false, |current| current.path == preset.path),
+            })
+            .collect();
+        self.tags = self
+            .library
+            .tags()
+            .into_iter()
+            .map(|tag| PresetBrowserItem {
+                selected: self.selected_tags.contains(&tag),
+                name: tag,
+            })
+            .collect();
+    }
+
+    fn load(&mut self, idx: usize) {
+        let Some(preset) = self.presets.get(idx).cloned() else {
+            return;
+        };
+
+        match self.library.load(&preset) {
+            Ok(plugin_state) => {
+                self.context.set_state(plugin_state);
+                self.current_preset = Some(preset);
+                self.error.clear();
+            }
+            Err(err) => self.error = format!("{err:#}"),
+        }
+    }
+
+    fn save(&mut self) {
+        let tags: Vec<String> = self.save_tags.split(',').map(String::from).collect();
+        match self
+            .library
+            .save(&self.save_name, &tags, &self.context.get_state())
+        {
+            Ok(preset) => {
+                self.current_preset = Some(preset);
+                self.save_form_open = false;
+                self.error.clear();
+            }
+            Err(err) => self.error = format!("{err:#}"),
+        }
+    }
+}
+
+impl View for PresetBrowser {
+    fn element(&self) -> Option<&'static str> {
+        Some("preset-browser")
+    }
+
+    fn event(&mut self, _cx: &mut EventContext, event: &mut Event) {
+        event.map(|preset_browser_event, meta| {
+            match preset_browser_event {
+                PresetBrowserEvent::SetQuery(query) => self.query = query.clone(),
+                PresetBrowserEvent::ToggleTag(tag) => {
+                    if self.selected_tags.contains(tag) {
+                        self.selected_tags.retain(|t| t != tag);
+                    } else {
+                        self.selected_tags.push(tag.clone());
+                    }
+                }
+                PresetBrowserEvent::LoadPreset(idx) => self.load(*idx),
+                PresetBrowserEvent::Rescan => self.library.rescan(),
+                PresetBrowserEvent::OpenSaveForm => {
+                    // The form starts out with the current preset's name and tags so it can easily
+                    // be overwritten
+                    let (name, tags) = match &self.current_preset {
+                        Some(preset) => (preset.name.clone(), preset.tags.join(", ")),
+                        None => (String::new(), String::new()),
+                    };
+                    self.save_name = name;
+                    self.save_tags = tags;
+                    self.save_form_open = true;
+                }
+                PresetBrowserEvent::SetSaveName(name) => self.save_name = name.clone(),
+                PresetBrowserEvent::SetSaveTags(tags) => self.save_tags = tags.clone(),
+                PresetBrowserEvent::Save => self.save(),
+                PresetBrowserEvent::CancelSave => self.save_form_open = false,
+            }
+
+            self.refresh();
+            meta.consume();
+        });
+
+        event.map(|window_event, _| {
+            if let WindowEvent::MouseEnter = window_event {
+                self.refresh();
+            }
+        });
+    }
+}
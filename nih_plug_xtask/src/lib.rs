@@ -1,15 +1,21 @@
 use anyhow::Context;
 use serde::Deserialize;
 use std::collections::HashMap;
+use std::ffi::CString;
 use std::fs;
+use std::os::raw::c_char;
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
 #[cfg(unix)]
 use std::os::unix::fs::PermissionsExt;
 
+mod package;
+mod sign;
 mod symbols;
 mod util;
+mod validate;
+mod wam;
 
 /// Re-export for the main function.
 pub use anyhow::Result;
@@ -23,6 +29,14 @@ fn build_usage_string(command_name: &str) -> String {
   {command_name} bundle-universal <package> [--release]  (macOS only)
   {command_name} bundle-universal -p <package1> -p <package2> ... [--release]  (macOS only)
 
+  {command_name} wam <package> [--release]  (Web Audio Module)
+
+  {command_name} package <package> [--release]
+  {command_name} package -p <package1> -p <package2> ... [--release]
+
+  {command_name} validate <package> [--release]
+  {command_name} validate -p <package1> -p <package2> ... [--release]
+
   All other 'cargo build' options are supported, including '--target' and '--profile'."
     )
 }
@@ -34,6 +48,29 @@ type BundlerConfig = HashMap<String, PackageConfig>;
 #[derive(Debug, Clone, Deserialize)]
 struct PackageConfig {
     name: Option<String>,
+    /// The Audio Unit component type, either `aufx`, `aumf`, or `aumu`. Only needed when the
+    /// plugin exports an Audio Unit.
+    au_type: Option<String>,
+    /// The Audio Unit's four character subtype code. Must match `AuPlugin::AU_SUBTYPE`.
+    au_subtype: Option<String>,
+    /// The Audio Unit's four character manufacturer code. Must match `AuPlugin::AU_MANUFACTURER`.
+    au_manufacturer: Option<String>,
+    /// The vendor name shown by Audio Unit hosts. Defaults to the manufacturer code.
+    au_vendor: Option<String>,
+    /// The identity used to sign macOS bundles. Bundles are ad-hoc signed if this is not set. Can
+    /// be overridden with the `CODESIGN_IDENTITY` environment variable.
+    codesign_identity: Option<String>,
+    /// The `notarytool` keychain profile used to notarize signed macOS bundles. Can be overridden
+    /// with the `NOTARIZATION_KEYCHAIN_PROFILE` environment variable.
+    notarization_keychain_profile: Option<String>,
+    /// The certificate subject name or `.pfx` file used to sign Windows binaries with `signtool`.
+    /// Can be overridden with the `SIGNTOOL_CERTIFICATE` environment variable.
+    signtool_certificate: Option<String>,
+    /// The version used for the packages created by `cargo xtask package`. Defaults to the
+    /// package's version from its `Cargo.toml` file.
+    version: Option<String>,
+    /// The identifier for the macOS installer package. Defaults to `com.nih-plug.<package>`.
+    installer_identifier: Option<String>,
 }
 
 /// The target we're generating a plugin for. This can be either the native target or a cross
@@ -135,7 +172,7 @@ pub fn main_with_args(command_name: &str, args: impl IntoIterator<Item = String>
             build(&packages, &aarch64_args)?;
 
             // This `true` indicates a universal build. This will cause the two sets of built
-            // binaries to beq lipo'd together into universal binaries before bundling
+            // binaries to be lipo'd together into universal binaries before bundling
             bundle(target_dir, &packages[0], &other_args, true)?;
             for package in packages.into_iter().skip(1) {
                 bundle(target_dir, &package, &other_args, true)?;
@@ -143,6 +180,93 @@ pub fn main_with_args(command_name: &str, args: impl IntoIterator<Item = String>
 
             Ok(())
         }
+        "wam" => {
+            // Web Audio Modules are always compiled for the same target, and every module only
+            // contains a single plugin
+            let (packages, mut other_args) = split_bundle_args(args, &usage_string)?;
+            if packages.len() != 1 {
+                anyhow::bail!("'{command_name} wam' only accepts a single package");
+            }
+            for arg in &other_args {
+                if arg == "--target" || arg.starts_with("--target=") {
+                    anyhow::bail!("'{command_name} wam' is incompatible with the '{arg}' option.")
+                }
+            }
+
+            other_args.push(format!("--target={}", wam::WASM_TARGET));
+            build(&packages, &other_args)?;
+
+            bundle_wam(target_dir, &packages[0], &other_args)
+        }
+        "package" => {
+            let (packages, other_args) = split_bundle_args(args, &usage_string)?;
+            let mut cross_compile_target: Option<&str> = None;
+            for (arg_idx, arg) in other_args.iter().enumerate() {
+                match arg.as_str() {
+                    "--target" => {
+                        cross_compile_target = Some(
+                            other_args
+                                .get(arg_idx + 1)
+                                .context("Missing cross-compile target")?
+                                .as_str(),
+                        );
+                    }
+                    arg if arg.starts_with("--target=") => {
+                        cross_compile_target = arg.strip_prefix("--target=");
+                    }
+                    _ => (),
+                }
+            }
+            let compilation_target = compilation_target(cross_compile_target)?;
+
+            build(&packages, &other_args)?;
+            for package in &packages {
+                bundle(target_dir, package, &other_args, false)?;
+            }
+            for package in &packages {
+                let version = cargo_metadata
+                    .packages
+                    .iter()
+                    .find(|p| &p.name == package)
+                    .map(|p| p.version.to_string())
+                    .unwrap_or_else(|| String::from("0.0.0"));
+                package::package(target_dir, package, &version, compilation_target)?;
+            }
+
+            Ok(())
+        }
+        "validate" => {
+            // The validators need to be able to load the plugins, so cross-compiling is not an
+            // option here
+            let (packages, other_args) = split_bundle_args(args, &usage_string)?;
+            for arg in &other_args {
+                if arg == "--target" || arg.starts_with("--target=") {
+                    anyhow::bail!(
+                        "'{command_name} validate' is incompatible with the '{arg}' option."
+                    )
+                }
+            }
+
+            build(&packages, &other_args)?;
+            for package in &packages {
+                bundle(target_dir, package, &other_args, false)?;
+            }
+
+            // All packages are validated before failing so every failure is reported at once
+            let compilation_target = compilation_target(None)?;
+            let mut failed_packages = Vec::new();
+            for package in &packages {
+                if let Err(err) = validate::validate(target_dir, package, compilation_target) {
+                    eprintln!("\nERROR: {err:#}");
+                    failed_packages.push(package.as_str());
+                }
+            }
+            if !failed_packages.is_empty() {
+                anyhow::bail!("Validation failed for {}", failed_packages.join(", "));
+            }
+
+            Ok(())
+        }
         // This is only meant to be used by the CI, since using awk for this can be a bit spotty on
         // macOS
         "known-packages" => list_known_packages(),
@@ -341,10 +465,14 @@ fn bundle_binary(
     compilation_target: CompilationTarget,
 ) -> Result<()> {
     let bundle_home_dir = bundle_home(target_dir);
-    let bundle_name = match load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
-        Some(PackageConfig { name: Some(name) }) => name,
+    let package_config = load_bundler_config()?.and_then(|c| c.get(package).cloned());
+    let bundle_name = match &package_config {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => name.clone(),
         _ => package.to_string(),
     };
+    let signing_config = sign::SigningConfig::new(package_config.as_ref());
 
     // On MacOS the standalone target needs to be in a bundle
     let standalone_bundle_binary_name =
@@ -385,7 +513,12 @@ fn bundle_binary(
         compilation_target,
         BundleType::Binary,
     )?;
-    maybe_codesign(&standalone_bundle_home, compilation_target);
+    sign::sign_bundle(
+        &standalone_bundle_home,
+        &standalone_binary_path,
+        compilation_target,
+        &signing_config,
+    )?;
 
     eprintln!(
         "Created a standalone bundle at '{}'",
@@ -405,10 +538,14 @@ fn bundle_plugin(
     compilation_target: CompilationTarget,
 ) -> Result<()> {
     let bundle_home_dir = bundle_home(target_dir);
-    let bundle_name = match load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
-        Some(PackageConfig { name: Some(name) }) => name,
+    let package_config = load_bundler_config()?.and_then(|c| c.get(package).cloned());
+    let bundle_name = match &package_config {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => name.clone(),
         _ => package.to_string(),
     };
+    let signing_config = sign::SigningConfig::new(package_config.as_ref());
 
     // We'll detect the plugin formats supported by the plugin binary and create bundled accordingly.
     // If `lib_path` contains paths to multiple plugins that need to be combined into a macOS
@@ -426,7 +563,15 @@ fn bundle_plugin(
         .with_context(|| format!("Could not parse '{}'", first_lib_path.display()))?;
     let bundle_vst3 = symbols::exported(first_lib_path, "GetPluginFactory")
         .with_context(|| format!("Could not parse '{}'", first_lib_path.display()))?;
-    let bundled_plugin = bundle_clap || bundle_vst2 || bundle_vst3;
+    let bundle_lv2 = symbols::exported(first_lib_path, "lv2_descriptor")
+        .with_context(|| format!("Could not parse '{}'", first_lib_path.display()))?;
+    // Audio Units only exist on macOS
+    let bundle_au = matches!(
+        compilation_target,
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal
+    ) && symbols::exported(first_lib_path, "NihPlugAuFactory")
+        .with_context(|| format!("Could not parse '{}'", first_lib_path.display()))?;
+    let bundled_plugin = bundle_clap || bundle_vst2 || bundle_vst3 || bundle_lv2 || bundle_au;
 
     if bundle_clap {
         let clap_bundle_library_name = clap_bundle_library_name(&bundle_name, compilation_target);
@@ -452,7 +597,12 @@ fn bundle_plugin(
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(&clap_bundle_home, compilation_target);
+        sign::sign_bundle(
+            &clap_bundle_home,
+            &clap_lib_path,
+            compilation_target,
+            &signing_config,
+        )?;
 
         eprintln!("Created a CLAP bundle at '{}'", clap_bundle_home.display());
     }
@@ -480,7 +630,12 @@ fn bundle_plugin(
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(&vst2_bundle_home, compilation_target);
+        sign::sign_bundle(
+            &vst2_bundle_home,
+            &vst2_lib_path,
+            compilation_target,
+            &signing_config,
+        )?;
 
         eprintln!("Created a VST2 bundle at '{}'", vst2_bundle_home.display());
     }
@@ -507,10 +662,84 @@ fn bundle_plugin(
             compilation_target,
             BundleType::Plugin,
         )?;
-        maybe_codesign(vst3_bundle_home, compilation_target);
+        sign::sign_bundle(
+            vst3_bundle_home,
+            &vst3_lib_path,
+            compilation_target,
+            &signing_config,
+        )?;
 
         eprintln!("Created a VST3 bundle at '{}'", vst3_bundle_home.display());
     }
+    if bundle_lv2 {
+        let lv2_bundle_library_name = lv2_bundle_library_name(&bundle_name, compilation_target);
+        let lv2_lib_path = bundle_home_dir.join(&lv2_bundle_library_name);
+
+        fs::create_dir_all(lv2_lib_path.parent().unwrap())
+            .context("Could not create LV2 bundle directory")?;
+        util::reflink_or_combine(lib_paths, &lv2_lib_path, compilation_target)
+            .context("Could not create LV2 bundle")?;
+
+        // LV2 hosts discover plugins through the bundle's Turtle files, and these are generated by
+        // the plugin library itself. This requires loading the library, which is not possible when
+        // cross-compiling.
+        let lv2_bundle_home = lv2_lib_path.parent().unwrap();
+        match write_lv2_ttl(&lv2_lib_path, lv2_bundle_home) {
+            Ok(()) => eprintln!("Created an LV2 bundle at '{}'", lv2_bundle_home.display()),
+            Err(err) => eprintln!(
+                "WARNING: Created an LV2 bundle at '{}', but its Turtle files could not be \
+                 generated: {err:#}",
+                lv2_bundle_home.display()
+            ),
+        }
+    }
+    if bundle_au {
+        match package_config
+            .as_ref()
+            .and_then(|config| au_component_entry(config, &bundle_name))
+        {
+            Some(au_component_entry) => {
+                let au_lib_path = bundle_home_dir.join(format!(
+                    "{bundle_name}.component/Contents/MacOS/{bundle_name}"
+                ));
+
+                fs::create_dir_all(au_lib_path.parent().unwrap())
+                    .context("Could not create AU bundle directory")?;
+                util::reflink_or_combine(lib_paths, &au_lib_path, compilation_target)
+                    .context("Could not create AU bundle")?;
+
+                let au_bundle_home = au_lib_path
+                    .parent()
+                    .unwrap()
+                    .parent()
+                    .unwrap()
+                    .parent()
+                    .unwrap();
+                create_macos_bundle_metadata(
+                    package,
+                    &bundle_name,
+                    au_bundle_home,
+                    BundleType::Plugin,
+                    &format!(
+                        "{}{au_component_entry}",
+                        macos_target_plist_entries(compilation_target)
+                    ),
+                )?;
+                sign::sign_bundle(
+                    au_bundle_home,
+                    &au_lib_path,
+                    compilation_target,
+                    &signing_config,
+                )?;
+
+                eprintln!("Created an AU bundle at '{}'", au_bundle_home.display());
+            }
+            None => eprintln!(
+                "WARNING: Not creating an AU bundle because 'au_type', 'au_subtype', or \
+                 'au_manufacturer' is missing from the package's bundler.toml entry"
+            ),
+        }
+    }
     if !bundled_plugin {
         eprintln!("Not creating any plugin bundles because the package does not export any plugins")
     }
@@ -518,6 +747,62 @@ fn bundle_plugin(
     Ok(())
 }
 
+/// Create a Web Audio Module for a package that was previously built for
+/// [`wam::WASM_TARGET`] using [`build()`]. The module is written to `bundled/<package>.wam`.
+pub fn bundle_wam(target_dir: &Path, package: &str, args: &[String]) -> Result<()> {
+    let mut build_type_dir = "debug";
+    for (arg_idx, arg) in args.iter().enumerate() {
+        match arg.as_str() {
+            "--profile" => {
+                build_type_dir = args.get(arg_idx + 1).context("Missing profile name")?;
+            }
+            "--release" => build_type_dir = "release",
+            arg if arg.starts_with("--profile=") => {
+                build_type_dir = arg
+                    .strip_prefix("--profile=")
+                    .context("Missing profile name")?;
+            }
+            _ => (),
+        }
+    }
+
+    // Cargo will replace dashes with underscores
+    let wasm_path = target_dir
+        .join(wam::WASM_TARGET)
+        .join(build_type_dir)
+        .join(format!("{}.wasm", package.replace('-', "_")));
+    if !wasm_path.exists() {
+        anyhow::bail!(
+            r#"Could not find a built WebAssembly module at '{}'.
+
+Hint: Maybe you forgot to add:
+
+[lib]
+crate-type = ["cdylib"]
+
+to your Cargo.toml file?"#,
+            wasm_path.display()
+        );
+    }
+
+    let bundle_name = match load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => name,
+        _ => package.to_string(),
+    };
+    let wam_bundle_home = bundle_home(target_dir).join(format!("{bundle_name}.wam"));
+    wam::bundle_wam(&wasm_path, &wam_bundle_home, &bundle_name)?;
+
+    eprintln!();
+    eprintln!(
+        "Created a Web Audio Module at '{}'",
+        wam_bundle_home.display()
+    );
+
+    Ok(())
+}
+
 /// This lists the packages configured in `bundler.toml`. This is only used as part of the CI when
 /// bundling plugins.
 pub fn list_known_packages() -> Result<()> {
@@ -727,6 +1012,91 @@ fn vst3_bundle_library_name(package: &str, target: CompilationTarget) -> String
     }
 }
 
+/// The full path to the library file inside of an LV2 bundle, including the leading `.lv2`
+/// directory. LV2 bundles use the same layout on every platform.
+fn lv2_bundle_library_name(package: &str, target: CompilationTarget) -> String {
+    match target {
+        CompilationTarget::Linux(_) => format!("{package}.lv2/{package}.so"),
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal => {
+            format!("{package}.lv2/{package}.dylib")
+        }
+        CompilationTarget::Windows(_) => format!("{package}.lv2/{package}.dll"),
+    }
+}
+
+/// Load the LV2 plugin library at `lib_path` and call the function exported by
+/// `nih_export_lv2!()` to write the bundle's `manifest.ttl` and plugin description files to
+/// `bundle_home`.
+fn write_lv2_ttl(lib_path: &Path, bundle_home: &Path) -> Result<()> {
+    let bundle_home = bundle_home
+        .to_str()
+        .and_then(|path| CString::new(path).ok())
+        .context("The bundle path is not a valid C string")?;
+    let binary_name = lib_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| CString::new(name).ok())
+        .context("The library name is not a valid C string")?;
+
+    // SAFETY: This runs the library's initializers, which is no different from a host loading the
+    //         plugin. The function's signature is defined by `nih_export_lv2!()`.
+    unsafe {
+        let library = libloading::Library::new(lib_path).with_context(|| {
+            format!(
+                "Could not load '{}', the library may have been compiled for another platform",
+                lib_path.display()
+            )
+        })?;
+        let write_ttl: libloading::Symbol<
+            unsafe extern "C" fn(*const c_char, *const c_char) -> bool,
+        > = library
+            .get(b"nih_plug_lv2_write_ttl\0")
+            .context("The library does not export 'nih_plug_lv2_write_ttl'")?;
+
+        if write_ttl(bundle_home.as_ptr(), binary_name.as_ptr()) {
+            Ok(())
+        } else {
+            anyhow::bail!("The plugin library failed to write the Turtle files")
+        }
+    }
+}
+
+/// The `AudioComponents` entry for an Audio Unit bundle's `Info.plist` file, or `None` if the
+/// package's `bundler.toml` entry is missing the Audio Unit codes.
+///
+/// See <https://developer.apple.com/library/archive/technotes/tn2276/_index.html>.
+fn au_component_entry(config: &PackageConfig, display_name: &str) -> Option<String> {
+    let au_type = config.au_type.as_deref()?;
+    let au_subtype = config.au_subtype.as_deref()?;
+    let au_manufacturer = config.au_manufacturer.as_deref()?;
+    let au_vendor = config.au_vendor.as_deref().unwrap_or(au_manufacturer);
+
+    Some(format!(
+        r#"    <key>AudioComponents</key>
+    <array>
+      <dict>
+        <key>type</key>
+        <string>{au_type}</string>
+        <key>subtype</key>
+        <string>{au_subtype}</string>
+        <key>manufacturer</key>
+        <string>{au_manufacturer}</string>
+        <key>name</key>
+        <string>{au_vendor}: {display_name}</string>
+        <key>description</key>
+        <string>{display_name}</string>
+        <key>version</key>
+        <integer>65536</integer>
+        <key>factoryFunction</key>
+        <string>NihPlugAuFactory</string>
+        <key>sandboxSafe</key>
+        <true/>
+      </dict>
+    </array>
+"#
+    ))
+}
+
 /// If compiling for macOS, create all of the bundl-y stuff Steinberg and Apple require you to have.
 ///
 /// This still requires you to move the dylib file to `{bundle_home}/Contents/macOS/{package}`
@@ -745,6 +1115,51 @@ pub fn maybe_create_macos_bundle_metadata(
         return Ok(());
     }
 
+    create_macos_bundle_metadata(
+        package,
+        display_name,
+        bundle_home,
+        bundle_type,
+        &macos_target_plist_entries(target),
+    )
+}
+
+/// `Info.plist` entries that depend on the compilation target. Universal bundles prefer the native
+/// AArch64 slice on Apple Silicon so the host doesn't need to load the plugin through Rosetta, and
+/// the minimum macOS version is taken from `MACOSX_DEPLOYMENT_TARGET` when it is set so macOS
+/// doesn't offer the bundle on systems the binaries can't run on.
+fn macos_target_plist_entries(target: CompilationTarget) -> String {
+    let mut entries = String::new();
+    if matches!(target, CompilationTarget::MacOSUniversal) {
+        entries.push_str(
+            r#"    <key>LSArchitecturePriority</key>
+    <array>
+      <string>arm64</string>
+      <string>x86_64</string>
+    </array>
+"#,
+        );
+    }
+    if let Ok(deployment_target) = std::env::var("MACOSX_DEPLOYMENT_TARGET") {
+        entries.push_str(&format!(
+            r#"    <key>LSMinimumSystemVersion</key>
+    <string>{deployment_target}</string>
+"#
+        ));
+    }
+
+    entries
+}
+
+/// Create the `PkgInfo` and `Info.plist` files for a macOS bundle. `extra_plist_entries` is
+/// inserted as is into the `Info.plist` file's top level dictionary.
+fn create_macos_bundle_metadata(
+    package: &str,
+    display_name: &str,
+    bundle_home: &Path,
+    bundle_type: BundleType,
+    extra_plist_entries: &str,
+) -> Result<()> {
     let package_type = match bundle_type {
         BundleType::Plugin => "BNDL",
         BundleType::Binary => "APPL",
@@ -786,7 +1201,7 @@ pub fn maybe_create_macos_bundle_metadata(
     <string></string>
     <key>NSHighResolutionCapable</key>
     <true/>
-  </dict>
+{extra_plist_entries}  </dict>
 </plist>
 "#),
     )
@@ -0,0 +1,415 @@
+//! Turns the bundles created by [`bundle()`][crate::bundle()] into something that can be handed to
+//! users. On macOS this is an installer package that installs the bundles system-wide, on Windows
+//! this is a ZIP archive with an `install.bat` script, and on Linux this is a tarball with an
+//! `install.sh` script. The packages are written to `target/packages`.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{CompilationTarget, PackageConfig};
+
+/// A plugin format that can be packaged, along with the standard installation directories for that
+/// format on every platform.
+struct PluginFormat {
+    /// The name of the format's directory in the package. This is also the directory name used on
+    /// macOS.
+    name: &'static str,
+    /// The file or directory name of the bundle in `target/bundled`, based on the bundle's name.
+    bundle_name: fn(&str, CompilationTarget) -> String,
+    /// Whether this format exists on Windows and Linux. Audio Units only exist on macOS.
+    cross_platform: bool,
+    windows_dir: &'static str,
+    linux_user_dir: &'static str,
+    linux_system_dir: &'static str,
+}
+
+const PLUGIN_FORMATS: &[PluginFormat] = &[
+    PluginFormat {
+        name: "CLAP",
+        bundle_name: |bundle_name, target| {
+            top_level_component(&crate::clap_bundle_library_name(bundle_name, target))
+        },
+        cross_platform: true,
+        windows_dir: r"%COMMONPROGRAMFILES%\CLAP",
+        linux_user_dir: "$HOME/.clap",
+        linux_system_dir: "/usr/lib/clap",
+    },
+    PluginFormat {
+        name: "VST3",
+        bundle_name: |bundle_name, _| format!("{bundle_name}.vst3"),
+        cross_platform: true,
+        windows_dir: r"%COMMONPROGRAMFILES%\VST3",
+        linux_user_dir: "$HOME/.vst3",
+        linux_system_dir: "/usr/lib/vst3",
+    },
+    PluginFormat {
+        name: "LV2",
+        bundle_name: |bundle_name, _| format!("{bundle_name}.lv2"),
+        cross_platform: true,
+        windows_dir: r"%COMMONPROGRAMFILES%\LV2",
+        linux_user_dir: "$HOME/.lv2",
+        linux_system_dir: "/usr/lib/lv2",
+    },
+    PluginFormat {
+        name: "Components",
+        bundle_name: |bundle_name, _| format!("{bundle_name}.component"),
+        cross_platform: false,
+        windows_dir: "",
+        linux_user_dir: "",
+        linux_system_dir: "",
+    },
+];
+
+/// Package the bundles for a package that was previously bundled using
+/// [`bundle()`][crate::bundle()] for `compilation_target`. `version` is used in the package's file
+/// name unless `bundler.toml` overrides it. Returns the path to the created package.
+pub fn package(
+    target_dir: &Path,
+    package: &str,
+    version: &str,
+    compilation_target: CompilationTarget,
+) -> Result<PathBuf> {
+    let bundle_home_dir = crate::bundle_home(target_dir);
+    let package_config = crate::load_bundler_config()?.and_then(|c| c.get(package).cloned());
+    let bundle_name = match &package_config {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => name.clone(),
+        _ => package.to_string(),
+    };
+    let version = match &package_config {
+        Some(PackageConfig {
+            version: Some(version),
+            ..
+        }) => version.clone(),
+        _ => version.to_string(),
+    };
+
+    let is_macos = matches!(
+        compilation_target,
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal
+    );
+    let bundles: Vec<(&PluginFormat, String)> = PLUGIN_FORMATS
+        .iter()
+        .filter(|format| format.cross_platform || is_macos)
+        .map(|format| {
+            (
+                format,
+                (format.bundle_name)(&bundle_name, compilation_target),
+            )
+        })
+        .filter(|(_, bundle)| bundle_home_dir.join(bundle).exists())
+        .collect();
+    if bundles.is_empty() {
+        anyhow::bail!("'{package}' does not have any plugin bundles that can be packaged");
+    }
+
+    let platform_name = match compilation_target {
+        CompilationTarget::Linux(_) => "linux",
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal => "macos",
+        CompilationTarget::Windows(_) => "windows",
+    };
+    // The bundle name is meant for humans and may contain spaces, so the package name is used for
+    // the file names instead
+    let package_name = format!("{package}-{version}-{platform_name}");
+    let packages_dir = target_dir.join("packages");
+    let staging_dir = packages_dir.join(&package_name);
+    if staging_dir.exists() {
+        fs::remove_dir_all(&staging_dir)
+            .with_context(|| format!("Could not remove '{}'", staging_dir.display()))?;
+    }
+
+    let package_path = match compilation_target {
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal => {
+            let identifier = match &package_config {
+                Some(PackageConfig {
+                    installer_identifier: Some(identifier),
+                    ..
+                }) => identifier.clone(),
+                _ => format!("com.nih-plug.{package}"),
+            };
+
+            create_macos_pkg(
+                &bundle_home_dir,
+                &bundles,
+                &staging_dir,
+                &packages_dir.join(format!("{package_name}.pkg")),
+                &identifier,
+                &version,
+            )?
+        }
+        CompilationTarget::Windows(_) => create_windows_zip(
+            &bundle_home_dir,
+            &bundles,
+            &staging_dir,
+            &packages_dir.join(format!("{package_name}.zip")),
+            &bundle_name,
+        )?,
+        CompilationTarget::Linux(_) => create_linux_tarball(
+            &bundle_home_dir,
+            &bundles,
+            &staging_dir,
+            &packages_dir.join(format!("{package_name}.tar.gz")),
+            &bundle_name,
+        )?,
+    };
+
+    fs::remove_dir_all(&staging_dir)
+        .with_context(|| format!("Could not remove '{}'", staging_dir.display()))?;
+    eprintln!("Created a package at '{}'", package_path.display());
+
+    Ok(package_path)
+}
+
+/// Create a `.pkg` installer that installs the bundles to `/Library/Audio/Plug-Ins`.
+fn create_macos_pkg(
+    bundle_home_dir: &Path,
+    bundles: &[(&PluginFormat, String)],
+    staging_dir: &Path,
+    pkg_path: &Path,
+    identifier: &str,
+    version: &str,
+) -> Result<PathBuf> {
+    let root_dir = staging_dir.join("root");
+    let mut component_entries = String::new();
+    for (format, bundle) in bundles {
+        let relative_path = format!("Library/Audio/Plug-Ins/{}/{bundle}", format.name);
+        copy_recursively(
+            &bundle_home_dir.join(bundle),
+            &root_dir.join(&relative_path),
+        )?;
+
+        // By default the installer would move the bundles to wherever the user has another copy
+        // with the same bundle identifier, which is never what you want for plugins
+        component_entries.push_str(&format!(
+            r#"  <dict>
+    <key>BundleHasStrictIdentifier</key>
+    <false/>
+    <key>BundleIsRelocatable</key>
+    <false/>
+    <key>BundleIsVersionChecked</key>
+    <false/>
+    <key>BundleOverwriteAction</key>
+    <string>upgrade</string>
+    <key>RootRelativeBundlePath</key>
+    <string>{relative_path}</string>
+  </dict>
+"#
+        ));
+    }
+
+    let component_plist_path = staging_dir.join("components.plist");
+    fs::write(
+        &component_plist_path,
+        format!(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<array>
+{component_entries}</array>
+</plist>
+"#
+        ),
+    )
+    .context("Could not write the component property list")?;
+
+    let status = Command::new("pkgbuild")
+        .arg("--root")
+        .arg(&root_dir)
+        .arg("--component-plist")
+        .arg(&component_plist_path)
+        .args(["--identifier", identifier, "--version", version])
+        .args(["--install-location", "/"])
+        .arg(pkg_path)
+        .status()
+        .context("Could not call 'pkgbuild'")?;
+    if !status.success() {
+        anyhow::bail!("Could not create '{}'", pkg_path.display());
+    }
+
+    Ok(pkg_path.to_owned())
+}
+
+/// Create a ZIP archive containing the bundles and an `install.bat` script that copies them to the
+/// standard directories in `%COMMONPROGRAMFILES%`.
+fn create_windows_zip(
+    bundle_home_dir: &Path,
+    bundles: &[(&PluginFormat, String)],
+    staging_dir: &Path,
+    zip_path: &Path,
+    bundle_name: &str,
+) -> Result<PathBuf> {
+    let mut install_script = format!(
+        "@echo off\r\n\
+         rem Installs {bundle_name} for all users. This needs to be run as an administrator.\r\n\
+         \r\n"
+    );
+    for (format, bundle) in bundles {
+        let source_path = bundle_home_dir.join(bundle);
+        copy_recursively(&source_path, &staging_dir.join(format.name).join(bundle))?;
+
+        let target_dir = format.windows_dir;
+        install_script.push_str(&format!(
+            "if not exist \"{target_dir}\" mkdir \"{target_dir}\"\r\n"
+        ));
+        // The CLAP plugin is a single file while the other formats use bundles
+        if source_path.is_dir() {
+            install_script.push_str(&format!(
+                "xcopy /E /I /Y \"%~dp0{}\\{bundle}\" \"{target_dir}\\{bundle}\" || goto :error\r\n",
+                format.name
+            ));
+        } else {
+            install_script.push_str(&format!(
+                "copy /Y \"%~dp0{}\\{bundle}\" \"{target_dir}\\\" || goto :error\r\n",
+                format.name
+            ));
+        }
+    }
+    install_script.push_str(&format!(
+        "\r\n\
+         echo {bundle_name} has been installed.\r\n\
+         pause\r\n\
+         exit /b 0\r\n\
+         \r\n\
+         :error\r\n\
+         echo Could not install {bundle_name}. Make sure to run this script as an administrator.\r\n\
+         pause\r\n\
+         exit /b 1\r\n"
+    ));
+    fs::write(staging_dir.join("install.bat"), install_script)
+        .context("Could not write 'install.bat'")?;
+
+    // Windows' `tar` can write ZIP archives, and other platforms usually have `zip` installed
+    let (staging_parent, staging_name) = split_path(staging_dir)?;
+    let mut command = if cfg!(windows) {
+        let mut command = Command::new("tar");
+        command.arg("-a").arg("-c").arg("-f").arg(zip_path);
+        command
+    } else {
+        let mut command = Command::new("zip");
+        command.arg("-r").arg("-q").arg(zip_path);
+        command
+    };
+    if zip_path.exists() {
+        fs::remove_file(zip_path)
+            .with_context(|| format!("Could not remove '{}'", zip_path.display()))?;
+    }
+    let status = command
+        .arg(staging_name)
+        .current_dir(staging_parent)
+        .status()
+        .context("Could not call the archiver to create the ZIP file")?;
+    if !status.success() {
+        anyhow::bail!("Could not create '{}'", zip_path.display());
+    }
+
+    Ok(zip_path.to_owned())
+}
+
+/// Create a tarball containing the bundles and an `install.sh` script that copies them to the
+/// user's home directory, or to `/usr/lib` when run as root.
+fn create_linux_tarball(
+    bundle_home_dir: &Path,
+    bundles: &[(&PluginFormat, String)],
+    staging_dir: &Path,
+    tarball_path: &Path,
+    bundle_name: &str,
+) -> Result<PathBuf> {
+    let mut install_script = format!(
+        r#"#!/bin/sh
+# Installs {bundle_name} for the current user, or for all users when run as root.
+
+set -e
+cd "$(dirname "$0")"
+
+"#
+    );
+    for (format, bundle) in bundles {
+        copy_recursively(
+            &bundle_home_dir.join(bundle),
+            &staging_dir.join(format.name).join(bundle),
+        )?;
+
+        install_script.push_str(&format!(
+            r#"if [ "$(id -u)" -eq 0 ]; then
+  target_dir="{system_dir}"
+else
+  target_dir="{user_dir}"
+fi
+mkdir -p "$target_dir"
+rm -rf "$target_dir/{bundle}"
+cp -r "{format_name}/{bundle}" "$target_dir/"
+echo "Installed '{bundle}' to '$target_dir'"
+
+"#,
+            system_dir = format.linux_system_dir,
+            user_dir = format.linux_user_dir,
+            format_name = format.name,
+        ));
+    }
+
+    let install_script_path = staging_dir.join("install.sh");
+    fs::write(&install_script_path, install_script).context("Could not write 'install.sh'")?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        fs::set_permissions(&install_script_path, fs::Permissions::from_mode(0o755))
+            .context("Could not make 'install.sh' executable")?;
+    }
+
+    let (staging_parent, staging_name) = split_path(staging_dir)?;
+    let status = Command::new("tar")
+        .arg("-czf")
+        .arg(tarball_path)
+        .arg("-C")
+        .arg(staging_parent)
+        .arg(staging_name)
+        .status()
+        .context("Could not call 'tar'")?;
+    if !status.success() {
+        anyhow::bail!("Could not create '{}'", tarball_path.display());
+    }
+
+    Ok(tarball_path.to_owned())
+}
+
+/// Copy a file or a directory to `to`, creating all parent directories as needed.
+fn copy_recursively(from: &Path, to: &Path) -> Result<()> {
+    if from.is_dir() {
+        fs::create_dir_all(to).with_context(|| format!("Could not create '{}'", to.display()))?;
+        for entry in
+            fs::read_dir(from).with_context(|| format!("Could not read '{}'", from.display()))?
+        {
+            let entry = entry?;
+            copy_recursively(&entry.path(), &to.join(entry.file_name()))?;
+        }
+    } else {
+        fs::create_dir_all(to.parent().unwrap())
+            .with_context(|| format!("Could not create '{}'", to.display()))?;
+        crate::util::reflink(from, to)?;
+    }
+
+    Ok(())
+}
+
+/// The first component of a path relative to the bundle directory.
+fn top_level_component(path: &str) -> String {
+    Path::new(path)
+        .components()
+        .next()
+        .expect("Malformed library path")
+        .as_os_str()
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Split a path into its parent directory and its file name.
+fn split_path(path: &Path) -> Result<(&Path, &std::ffi::OsStr)> {
+    Ok((
+        path.parent().context("Path has no parent directory")?,
+        path.file_name().context("Path has no file name")?,
+    ))
+}
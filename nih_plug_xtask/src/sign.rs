@@ -0,0 +1,214 @@
+//! Code signing for bundles. By default macOS bundles are only ad-hoc signed, see
+//! [`maybe_codesign()`][crate::maybe_codesign()]. When a signing identity is configured, either
+//! through the environment or through the package's `bundler.toml` entry, bundles are signed with
+//! that identity instead and can optionally be notarized. Windows binaries are signed using
+//! `signtool` when a certificate is configured.
+//!
+//! The environment variables take precedence over the values in `bundler.toml`:
+//!
+//! - `CODESIGN_IDENTITY`: the macOS signing identity, e.g.
+//!   `Developer ID Application: Foo (ABCDE12345)`.
+//! - `NOTARIZATION_KEYCHAIN_PROFILE`: the name of a keychain profile created with `xcrun notarytool
+//!   store-credentials`. Bundles are only notarized if this is set in addition to the identity.
+//! - `SIGNTOOL_CERTIFICATE`: either the subject name of a certificate in the Windows certificate
+//!   store, or a path to a `.pfx` file.
+//! - `SIGNTOOL_CERTIFICATE_PASSWORD`: the password for the `.pfx` file, if needed.
+//! - `SIGNTOOL_TIMESTAMP_URL`: the RFC 3161 timestamp server. Defaults to
+//!   [`DEFAULT_TIMESTAMP_URL`].
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+use crate::{CompilationTarget, PackageConfig};
+
+/// The timestamp server used by `signtool` when `SIGNTOOL_TIMESTAMP_URL` is not set.
+const DEFAULT_TIMESTAMP_URL: &str = "http://timestamp.digicert.com";
+
+/// The signing options for a single package.
+#[derive(Debug, Clone, Default)]
+pub struct SigningConfig {
+    macos_identity: Option<String>,
+    notarization_keychain_profile: Option<String>,
+    windows_certificate: Option<String>,
+    windows_certificate_password: Option<String>,
+    windows_timestamp_url: Option<String>,
+}
+
+impl SigningConfig {
+    /// Read the signing options from the environment, falling back to the package's `bundler.toml`
+    /// entry.
+    pub fn new(package_config: Option<&PackageConfig>) -> Self {
+        let env_or_config = |name: &str, config_value: Option<&String>| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .or_else(|| config_value.cloned())
+        };
+
+        SigningConfig {
+            macos_identity: env_or_config(
+                "CODESIGN_IDENTITY",
+                package_config.and_then(|c| c.codesign_identity.as_ref()),
+            ),
+            notarization_keychain_profile: env_or_config(
+                "NOTARIZATION_KEYCHAIN_PROFILE",
+                package_config.and_then(|c| c.notarization_keychain_profile.as_ref()),
+            ),
+            windows_certificate: env_or_config(
+                "SIGNTOOL_CERTIFICATE",
+                package_config.and_then(|c| c.signtool_certificate.as_ref()),
+            ),
+            windows_certificate_password: env_or_config("SIGNTOOL_CERTIFICATE_PASSWORD", None),
+            windows_timestamp_url: env_or_config("SIGNTOOL_TIMESTAMP_URL", None),
+        }
+    }
+}
+
+/// Sign a bundle after it has been created. On macOS `bundle_home` is signed with the configured
+/// identity and then notarized if a keychain profile has been configured, or it is ad-hoc signed
+/// if there is no identity. On Windows `binary_path` is signed with `signtool` if a certificate
+/// has been configured. Other platforms are left alone.
+///
+/// In contrast to ad-hoc signing, failing to sign with a configured identity or certificate is an
+/// error since the resulting bundle would not be fit for distribution.
+pub fn sign_bundle(
+    bundle_home: &Path,
+    binary_path: &Path,
+    target: CompilationTarget,
+    config: &SigningConfig,
+) -> Result<()> {
+    match target {
+        CompilationTarget::MacOS(_) | CompilationTarget::MacOSUniversal => {
+            match &config.macos_identity {
+                Some(identity) => {
+                    codesign(bundle_home, identity)?;
+                    if let Some(keychain_profile) = &config.notarization_keychain_profile {
+                        notarize(bundle_home, keychain_profile)?;
+                    }
+
+                    Ok(())
+                }
+                None => {
+                    if config.notarization_keychain_profile.is_some() {
+                        eprintln!(
+                            "WARNING: Not notarizing '{}' because no signing identity has been \
+                             configured",
+                            bundle_home.display()
+                        );
+                    }
+
+                    crate::maybe_codesign(bundle_home, target);
+                    Ok(())
+                }
+            }
+        }
+        CompilationTarget::Windows(_) => match &config.windows_certificate {
+            Some(certificate) => signtool(binary_path, certificate, config),
+            None => Ok(()),
+        },
+        CompilationTarget::Linux(_) => Ok(()),
+    }
+}
+
+/// Sign a macOS bundle with a hardened runtime and a secure timestamp, as required for
+/// notarization.
+fn codesign(bundle_home: &Path, identity: &str) -> Result<()> {
+    let status = Command::new("codesign")
+        .args(["--force", "--options", "runtime", "--timestamp", "--sign"])
+        .arg(identity)
+        .arg(bundle_home)
+        .status()
+        .context("Could not call 'codesign'")?;
+    if !status.success() {
+        anyhow::bail!(
+            "Could not sign '{}' with '{identity}'",
+            bundle_home.display()
+        );
+    }
+
+    eprintln!("Signed '{}' with '{identity}'", bundle_home.display());
+    Ok(())
+}
+
+/// Submit a signed bundle to Apple's notary service, wait for the result, and then staple the
+/// notarization ticket to the bundle so it can be verified offline.
+fn notarize(bundle_home: &Path, keychain_profile: &str) -> Result<()> {
+    // The notary service only accepts ZIP archives, disk images, and installer packages
+    let archive_path = bundle_home.with_extension("notarization.zip");
+    let status = Command::new("ditto")
+        .args(["-c", "-k", "--keepParent"])
+        .arg(bundle_home)
+        .arg(&archive_path)
+        .status()
+        .context("Could not call 'ditto'")?;
+    if !status.success() {
+        anyhow::bail!("Could not archive '{}'", bundle_home.display());
+    }
+
+    eprintln!(
+        "Notarizing '{}', this may take a while",
+        bundle_home.display()
+    );
+    let status = Command::new("xcrun")
+        .args(["notarytool", "submit"])
+        .arg(&archive_path)
+        .arg("--keychain-profile")
+        .arg(keychain_profile)
+        .arg("--wait")
+        .status()
+        .context("Could not call 'xcrun notarytool'");
+    fs::remove_file(&archive_path)
+        .with_context(|| format!("Could not remove '{}'", archive_path.display()))?;
+    if !status?.success() {
+        anyhow::bail!("Could not notarize '{}'", bundle_home.display());
+    }
+
+    let status = Command::new("xcrun")
+        .args(["stapler", "staple"])
+        .arg(bundle_home)
+        .status()
+        .context("Could not call 'xcrun stapler'")?;
+    if !status.success() {
+        anyhow::bail!(
+            "Could not staple the notarization ticket to '{}'",
+            bundle_home.display()
+        );
+    }
+
+    eprintln!("Notarized '{}'", bundle_home.display());
+    Ok(())
+}
+
+/// Sign a Windows binary using `signtool`. `certificate` is either a path to a `.pfx` file or the
+/// subject name of a certificate in the certificate store.
+fn signtool(binary_path: &Path, certificate: &str, config: &SigningConfig) -> Result<()> {
+    let mut command = Command::new("signtool");
+    command.args(["sign", "/fd", "SHA256", "/td", "SHA256", "/tr"]);
+    command.arg(
+        config
+            .windows_timestamp_url
+            .as_deref()
+            .unwrap_or(DEFAULT_TIMESTAMP_URL),
+    );
+    if Path::new(certificate).is_file() {
+        command.arg("/f").arg(certificate);
+        if let Some(password) = &config.windows_certificate_password {
+            command.arg("/p").arg(password);
+        }
+    } else {
+        command.arg("/n").arg(certificate);
+    }
+
+    let status = command
+        .arg(binary_path)
+        .status()
+        .context("Could not call 'signtool', make sure the Windows SDK is in the PATH")?;
+    if !status.success() {
+        anyhow::bail!("Could not sign '{}'", binary_path.display());
+    }
+
+    eprintln!("Signed '{}'", binary_path.display());
+    Ok(())
+}
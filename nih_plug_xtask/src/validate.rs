@@ -0,0 +1,366 @@
+//! Runs [clap-validator](https://github.com/free-audio/clap-validator) and
+//! [pluginval](https://github.com/Tracktion/pluginval) on bundled plugins. Pinned versions of both
+//! validators are downloaded to the target directory the first time they're needed, and the
+//! downloaded archives are checked against pinned SHA-256 checksums before they're extracted. The
+//! `CLAP_VALIDATOR` and `PLUGINVAL` environment variables can be used to point at existing
+//! binaries instead.
+
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::env::consts::{ARCH, OS};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::{CompilationTarget, PackageConfig};
+
+/// The clap-validator release that is downloaded when `CLAP_VALIDATOR` is not set.
+const CLAP_VALIDATOR_VERSION: &str = "0.3.2";
+/// The pluginval release that is downloaded when `PLUGINVAL` is not set.
+const PLUGINVAL_VERSION: &str = "1.0.3";
+
+/// A validator release archive that can run on one host platform.
+struct ValidatorArchive {
+    /// The host operating system the archive is for, as in [`std::env::consts::OS`].
+    os: &'static str,
+    /// The host architectures the archive's binary runs on, as in [`std::env::consts::ARCH`].
+    archs: &'static [&'static str],
+    /// The archive's file name on the release page, without the version prefix for
+    /// clap-validator.
+    archive_name: &'static str,
+    /// The binary's path relative to the root of the extracted archive.
+    binary_name: &'static str,
+    /// The archive's SHA-256 checksum as a lowercase hexadecimal string. Archives without a
+    /// pinned checksum are never downloaded.
+    sha256: Option<&'static str>,
+}
+
+/// The clap-validator archives for [`CLAP_VALIDATOR_VERSION`].
+const CLAP_VALIDATOR_ARCHIVES: &[ValidatorArchive] = &[
+    ValidatorArchive {
+        os: "linux",
+        archs: &["x86_64"],
+        archive_name: "ubuntu-20.04.tar.gz",
+        binary_name: "clap-validator",
+        sha256: None,
+    },
+    ValidatorArchive {
+        os: "macos",
+        archs: &["x86_64", "aarch64"],
+        archive_name: "macos-universal.tar.gz",
+        binary_name: "clap-validator",
+        sha256: None,
+    },
+    ValidatorArchive {
+        os: "windows",
+        archs: &["x86_64"],
+        archive_name: "windows.zip",
+        binary_name: "clap-validator.exe",
+        sha256: None,
+    },
+];
+
+/// The pluginval archives for [`PLUGINVAL_VERSION`].
+const PLUGINVAL_ARCHIVES: &[ValidatorArchive] = &[
+    ValidatorArchive {
+        os: "linux",
+        archs: &["x86_64"],
+        archive_name: "pluginval_Linux.zip",
+        binary_name: "pluginval",
+        sha256: None,
+    },
+    ValidatorArchive {
+        os: "macos",
+        archs: &["x86_64", "aarch64"],
+        archive_name: "pluginval_macOS.zip",
+        binary_name: "pluginval.app/Contents/MacOS/pluginval",
+        sha256: None,
+    },
+    ValidatorArchive {
+        os: "windows",
+        archs: &["x86_64"],
+        archive_name: "pluginval_Windows.zip",
+        binary_name: "pluginval.exe",
+        sha256: None,
+    },
+];
+
+/// The options pluginval is run with. GUI tests are skipped since they need a display, and the
+/// plugin is loaded in-process so crashes are attributed to the right test.
+const PLUGINVAL_ARGS: &[&str] = &[
+    "--strictness-level",
+    "5",
+    "--skip-gui-tests",
+    "--validate-in-process",
+];
+
+/// Validate the CLAP and VST3 bundles for a package that was previously bundled using
+/// [`bundle()`][crate::bundle()]. Returns an error if the package has neither a CLAP nor a VST3
+/// bundle, or if one of the validators reports an error.
+pub fn validate(
+    target_dir: &Path,
+    package: &str,
+    compilation_target: CompilationTarget,
+) -> Result<()> {
+    let bundle_home_dir = crate::bundle_home(target_dir);
+    let bundle_name = match crate::load_bundler_config()?.and_then(|c| c.get(package).cloned()) {
+        Some(PackageConfig {
+            name: Some(name), ..
+        }) => name,
+        _ => package.to_string(),
+    };
+
+    // Just like in `bundle_plugin()`, CLAP plugins are only bundles on macOS
+    let clap_bundle_home = bundle_home_dir.join(
+        Path::new(&crate::clap_bundle_library_name(
+            &bundle_name,
+            compilation_target,
+        ))
+        .components()
+        .next()
+        .expect("Malformed CLAP library path"),
+    );
+    let vst3_bundle_home = bundle_home_dir.join(format!("{bundle_name}.vst3"));
+    if !clap_bundle_home.exists() && !vst3_bundle_home.exists() {
+        anyhow::bail!("'{package}' does not have a CLAP or a VST3 bundle that can be validated");
+    }
+
+    let mut failed_validators = Vec::new();
+    if clap_bundle_home.exists() {
+        let clap_validator = clap_validator_path(target_dir)?;
+        eprintln!("\nValidating '{}'", clap_bundle_home.display());
+
+        let status = Command::new(&clap_validator)
+            .arg("validate")
+            .arg(&clap_bundle_home)
+            .status()
+            .with_context(|| format!("Could not run '{}'", clap_validator.display()))?;
+        if !status.success() {
+            failed_validators.push("clap-validator");
+        }
+    }
+    if vst3_bundle_home.exists() {
+        let pluginval = pluginval_path(target_dir)?;
+        eprintln!("\nValidating '{}'", vst3_bundle_home.display());
+
+        let status = Command::new(&pluginval)
+            .args(PLUGINVAL_ARGS)
+            .arg("--validate")
+            .arg(&vst3_bundle_home)
+            .status()
+            .with_context(|| format!("Could not run '{}'", pluginval.display()))?;
+        if !status.success() {
+            failed_validators.push("pluginval");
+        }
+    }
+
+    if failed_validators.is_empty() {
+        eprintln!("\n'{package}' passed validation");
+        Ok(())
+    } else {
+        anyhow::bail!(
+            "'{package}' failed validation with {}",
+            failed_validators.join(" and ")
+        )
+    }
+}
+
+/// The path to the clap-validator binary. Uses `CLAP_VALIDATOR` if it is set, and downloads the
+/// pinned version for the host platform otherwise.
+fn clap_validator_path(target_dir: &Path) -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("CLAP_VALIDATOR") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let archive = host_archive(CLAP_VALIDATOR_ARCHIVES, "clap-validator", "CLAP_VALIDATOR")?;
+    let archive_name = format!(
+        "clap-validator-{CLAP_VALIDATOR_VERSION}-{}",
+        archive.archive_name
+    );
+
+    download_validator(
+        target_dir,
+        &format!("clap-validator-{CLAP_VALIDATOR_VERSION}"),
+        &format!(
+            "https://github.com/free-audio/clap-validator/releases/download/\
+             {CLAP_VALIDATOR_VERSION}/{archive_name}"
+        ),
+        &archive_name,
+        archive,
+        "CLAP_VALIDATOR",
+    )
+}
+
+/// The path to the pluginval binary. Uses `PLUGINVAL` if it is set, and downloads the pinned
+/// version for the host platform otherwise.
+fn pluginval_path(target_dir: &Path) -> Result<PathBuf> {
+    if let Some(path) = std::env::var_os("PLUGINVAL") {
+        return Ok(PathBuf::from(path));
+    }
+
+    let archive = host_archive(PLUGINVAL_ARCHIVES, "pluginval", "PLUGINVAL")?;
+
+    download_validator(
+        target_dir,
+        &format!("pluginval-{PLUGINVAL_VERSION}"),
+        &format!(
+            "https://github.com/Tracktion/pluginval/releases/download/v{PLUGINVAL_VERSION}/{}",
+            archive.archive_name
+        ),
+        archive.archive_name,
+        archive,
+        "PLUGINVAL",
+    )
+}
+
+/// Find the archive in `archives` that runs on the host this xtask is running on. The validators
+/// run on the host, so this does not depend on the target the plugin was compiled for.
+fn host_archive<'a>(
+    archives: &'a [ValidatorArchive],
+    validator: &str,
+    env_var: &str,
+) -> Result<&'a ValidatorArchive> {
+    archives
+        .iter()
+        .find(|archive| archive.os == OS && archive.archs.contains(&ARCH))
+        .with_context(|| {
+            format!(
+                "{validator} does not provide binaries for {OS}-{ARCH}. Set the '{env_var}' \
+                 environment variable to use an existing binary instead."
+            )
+        })
+}
+
+/// Download and extract a validator to `target/validators/<name>` if it has not already been
+/// downloaded, and return the path to its binary. This uses the `curl` and `tar` binaries that
+/// ship with all supported platforms, and `unzip` for ZIP archives on Linux and macOS. The
+/// downloaded archive is removed again without being extracted if its checksum does not match
+/// the one pinned in `archive`.
+fn download_validator(
+    target_dir: &Path,
+    name: &str,
+    url: &str,
+    archive_name: &str,
+    archive: &ValidatorArchive,
+    env_var: &str,
+) -> Result<PathBuf> {
+    let binary_name = archive.binary_name;
+    let validator_dir = target_dir.join("validators").join(name);
+    if let Some(binary_path) = find_file(&validator_dir, binary_name) {
+        return Ok(binary_path);
+    }
+
+    let expected_sha256 = archive.sha256.with_context(|| {
+        format!(
+            "There is no pinned checksum for '{archive_name}', so it will not be downloaded. Set \
+             the '{env_var}' environment variable to use an existing binary instead."
+        )
+    })?;
+
+    eprintln!("Downloading {name} from '{url}'");
+    fs::create_dir_all(&validator_dir)
+        .with_context(|| format!("Could not create '{}'", validator_dir.display()))?;
+
+    let archive_path = validator_dir.join(archive_name);
+    let status = Command::new("curl")
+        .args([
+            "--fail",
+            "--location",
+            "--silent",
+            "--show-error",
+            "--output",
+        ])
+        .arg(&archive_path)
+        .arg(url)
+        .status()
+        .context("Could not call 'curl' to download the validator")?;
+    if !status.success() {
+        anyhow::bail!("Could not download '{url}'");
+    }
+
+    let actual_sha256 = sha256_hex(&archive_path)?;
+    if actual_sha256 != expected_sha256 {
+        // The archive should not be left behind where it could be mistaken for a verified one
+        let _ = fs::remove_file(&archive_path);
+        anyhow::bail!(
+            "The checksum of '{url}' does not match the pinned checksum (expected \
+             {expected_sha256}, got {actual_sha256})"
+        );
+    }
+
+    // Windows' `tar` can also extract ZIP archives
+    let mut extract_command = if archive_name.ends_with(".zip") && !cfg!(windows) {
+        let mut command = Command::new("unzip");
+        command
+            .arg("-o")
+            .arg(&archive_path)
+            .arg("-d")
+            .arg(&validator_dir);
+        command
+    } else {
+        let mut command = Command::new("tar");
+        command
+            .arg("-xf")
+            .arg(&archive_path)
+            .arg("-C")
+            .arg(&validator_dir);
+        command
+    };
+    let status = extract_command
+        .status()
+        .with_context(|| format!("Could not extract '{}'", archive_path.display()))?;
+    if !status.success() {
+        anyhow::bail!("Could not extract '{}'", archive_path.display());
+    }
+    fs::remove_file(&archive_path)
+        .with_context(|| format!("Could not remove '{}'", archive_path.display()))?;
+
+    // Some archives contain the binary in a subdirectory
+    let binary_path = find_file(&validator_dir, binary_name).with_context(|| {
+        format!(
+            "'{archive_name}' does not contain '{binary_name}'. Set the '{env_var}' environment \
+             variable to use an existing binary instead."
+        )
+    })?;
+
+    // Just like with the standalone bundles, the archives don't always preserve the executable bit
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+
+        if let Ok(metadata) = fs::metadata(&binary_path) {
+            let mut permissions = metadata.permissions();
+            permissions.set_mode(permissions.mode() | 0b0001001001);
+            fs::set_permissions(&binary_path, permissions).with_context(|| {
+                format!("Could not make '{}' executable", binary_path.display())
+            })?;
+        }
+    }
+
+    Ok(binary_path)
+}
+
+/// Compute a file's SHA-256 checksum as a lowercase hexadecimal string.
+fn sha256_hex(path: &Path) -> Result<String> {
+    let contents =
+        fs::read(path).with_context(|| format!("Could not read '{}'", path.display()))?;
+
+    Ok(Sha256::digest(contents)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect())
+}
+
+/// Find a file with the relative path `name` in `dir` or in one of its subdirectories.
+fn find_file(dir: &Path, name: &str) -> Option<PathBuf> {
+    let candidate = dir.join(name);
+    if candidate.is_file() {
+        return Some(candidate);
+    }
+
+    fs::read_dir(dir)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().map(|t| t.is_dir()).unwrap_or(false))
+        .find_map(|entry| find_file(&entry.path(), name))
+}
@@ -0,0 +1,89 @@
+//! Turns a plugin compiled for `wasm32-unknown-unknown` into a Web Audio Module. The module's
+//! JavaScript glue code is generated from the templates in the `wam` directory.
+
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// The main thread side of the Web Audio Module. This is the module's entry point.
+const INDEX_JS: &str = include_str!("wam/index.js");
+/// The audio worklet side of the Web Audio Module.
+const PROCESSOR_JS: &str = include_str!("wam/processor.js");
+/// A minimal host page for trying out the module.
+const INDEX_HTML: &str = include_str!("wam/index.html");
+/// Instantiates the WebAssembly module when the plugin does not use `wasm-bindgen`.
+const BINDINGS_JS: &str = include_str!("wam/bindings.js");
+
+/// The target Web Audio Modules are compiled for.
+pub const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
+/// Create a Web Audio Module in `bundle_home` from a compiled WebAssembly module. If the module
+/// uses `wasm-bindgen`, for instance because the plugin has an egui editor, then the
+/// `wasm-bindgen` CLI is used to generate the bindings for the module.
+pub fn bundle_wam(wasm_path: &Path, bundle_home: &Path, bundle_name: &str) -> Result<()> {
+    let wasm =
+        fs::read(wasm_path).with_context(|| format!("Could not read '{}'", wasm_path.display()))?;
+    if !contains(&wasm, b"nih_plug_wam_descriptor") {
+        anyhow::bail!(
+            "'{}' does not export a Web Audio Module. Make sure the plugin calls \
+             'nih_export_wam!()' and that NIH-plug's 'wam' feature is enabled.",
+            wasm_path.display()
+        );
+    }
+
+    fs::create_dir_all(bundle_home).context("Could not create the WAM bundle directory")?;
+    if contains(&wasm, b"__wbindgen_placeholder__") {
+        let status = Command::new("wasm-bindgen")
+            .args([
+                "--target",
+                "web",
+                "--no-typescript",
+                "--out-name",
+                "bindings",
+            ])
+            .arg("--out-dir")
+            .arg(bundle_home)
+            .arg(wasm_path)
+            .status()
+            .context(
+                "Could not call 'wasm-bindgen'. The CLI can be installed with 'cargo install \
+                 wasm-bindgen-cli', and its version needs to match the version of the \
+                 'wasm-bindgen' crate used by the plugin.",
+            )?;
+        if !status.success() {
+            anyhow::bail!("Could not generate bindings for '{}'", wasm_path.display());
+        }
+    } else {
+        fs::write(bundle_home.join("bindings_bg.wasm"), &wasm)
+            .context("Could not copy the WebAssembly module")?;
+        fs::write(bundle_home.join("bindings.js"), BINDINGS_JS)
+            .context("Could not write 'bindings.js'")?;
+    }
+
+    fs::write(bundle_home.join("index.js"), INDEX_JS).context("Could not write 'index.js'")?;
+    fs::write(bundle_home.join("processor.js"), PROCESSOR_JS)
+        .context("Could not write 'processor.js'")?;
+    fs::write(
+        bundle_home.join("index.html"),
+        INDEX_HTML.replace("{{name}}", &html_escape(bundle_name)),
+    )
+    .context("Could not write 'index.html'")?;
+
+    Ok(())
+}
+
+/// Whether `haystack` contains `needle`. Export and import names are stored as plain strings in
+/// WebAssembly modules, so this is enough to detect them without parsing the module.
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    haystack
+        .windows(needle.len())
+        .any(|window| window == needle)
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
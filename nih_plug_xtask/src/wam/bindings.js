@@ -0,0 +1,18 @@
+// Generated by NIH-plug's `cargo xtask wam` command. This mirrors the `init()` function generated by
+// `wasm-bindgen --target web` for plugins that don't use `wasm-bindgen`.
+
+/**
+ * Instantiate the plugin's WebAssembly module and return the instance's exports.
+ *
+ * @param {WebAssembly.Module | URL | string} moduleOrPath
+ * @returns {Promise<WebAssembly.Exports>}
+ */
+export default async function init(moduleOrPath) {
+  const module =
+    moduleOrPath instanceof WebAssembly.Module
+      ? moduleOrPath
+      : await WebAssembly.compile(await (await fetch(moduleOrPath)).arrayBuffer());
+  const instance = await WebAssembly.instantiate(module, {});
+
+  return instance.exports;
+}
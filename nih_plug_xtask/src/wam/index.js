@@ -0,0 +1,246 @@
+// Generated by NIH-plug's `cargo xtask wam` command.
+
+import { WebAudioModule, WamNode, addFunctionModule } from "@webaudiomodules/sdk";
+import getProcessor from "./processor.js";
+import init from "./bindings.js";
+
+const wasmUrl = new URL("./bindings_bg.wasm", import.meta.url);
+
+/** The largest block size the plugin is asked to process. This is the Web Audio render quantum. */
+const MAX_BLOCK_SIZE = 128;
+/** How often the editor's parameter values are synced with the audio worklet, in milliseconds. */
+const PARAMETER_SYNC_INTERVAL = 100;
+
+/** Canvases are matched with editors using these handles. */
+let nextCanvasHandle = 1;
+
+/**
+ * Read a null terminated UTF-8 string from the module's memory.
+ *
+ * @param {WebAssembly.Exports} exports
+ * @param {number} ptr
+ */
+const readCString = (exports, ptr) => {
+  const memory = new Uint8Array(exports.memory.buffer);
+  let end = ptr;
+  while (memory[end] !== 0) {
+    end++;
+  }
+
+  return new TextDecoder().decode(memory.subarray(ptr, end));
+};
+
+class NihPlugNode extends WamNode {
+  constructor(module, options) {
+    super(module, options);
+
+    this._pendingStateRequests = new Map();
+    this._nextStateRequestId = 0;
+
+    // The plugin's state is sent over a separate channel so it doesn't interfere with the messages
+    // used by the WAM SDK
+    const channel = new MessageChannel();
+    this._statePort = channel.port1;
+    this._statePort.onmessage = ({ data }) => {
+      const resolve = this._pendingStateRequests.get(data.id);
+      this._pendingStateRequests.delete(data.id);
+      resolve?.(data);
+    };
+    this.port.postMessage({ nihPlugStatePort: channel.port2 }, [channel.port2]);
+  }
+
+  _requestState(request, state) {
+    const id = this._nextStateRequestId++;
+    return new Promise((resolve) => {
+      this._pendingStateRequests.set(id, resolve);
+      this._statePort.postMessage({ id, request, state });
+    });
+  }
+
+  async getState() {
+    const { state } = await this._requestState("get");
+    return { nihPlugState: state };
+  }
+
+  async setState(state) {
+    if (!(state?.nihPlugState instanceof Uint8Array)) {
+      return;
+    }
+
+    await this._requestState("set", state.nihPlugState);
+    this.module._loadEditorState(state.nihPlugState);
+  }
+}
+
+export default class NihPlugModule extends WebAudioModule {
+  async initialize(state) {
+    const response = await fetch(wasmUrl);
+    this._wasmModule = await WebAssembly.compile(await response.arrayBuffer());
+
+    // This instance hosts the editor. The audio is processed by a separate instance living in the
+    // audio worklet.
+    this._exports = await init(this._wasmModule);
+    this._moduleDescriptor = JSON.parse(
+      readCString(this._exports, this._exports.nih_plug_wam_descriptor()),
+    );
+    this._instance = this._exports.nih_plug_wam_create(this.audioContext.sampleRate, MAX_BLOCK_SIZE);
+    if (this._instance === 0) {
+      throw new Error("The plugin failed to initialize");
+    }
+
+    // Used as an out parameter for `nih_plug_wam_poll_param_change()`
+    this._valuePtr = this._exports.nih_plug_wam_alloc(4);
+    this._gui = null;
+
+    await this._loadDescriptor();
+    return super.initialize(state);
+  }
+
+  async _loadDescriptor() {
+    Object.assign(this._descriptor, this._moduleDescriptor.descriptor);
+    return this._descriptor;
+  }
+
+  async createAudioNode(initialState) {
+    await WamNode.addModules(this.audioContext, this.moduleId);
+    await addFunctionModule(this.audioContext.audioWorklet, getProcessor, this.moduleId);
+
+    const { audioInputChannels, audioOutputChannels } = this._moduleDescriptor;
+    const node = new NihPlugNode(this, {
+      numberOfInputs: audioInputChannels > 0 ? 1 : 0,
+      numberOfOutputs: audioOutputChannels > 0 ? 1 : 0,
+      outputChannelCount: audioOutputChannels > 0 ? [audioOutputChannels] : [],
+      processorOptions: {
+        wasmModule: this._wasmModule,
+        moduleDescriptor: this._moduleDescriptor,
+        maxBlockSize: MAX_BLOCK_SIZE,
+      },
+    });
+    await node._initialize();
+    if (initialState) {
+      await node.setState(initialState);
+    }
+
+    return node;
+  }
+
+  /** Load state sent to the audio worklet into the editor's instance as well. */
+  _loadEditorState(state) {
+    const ptr = this._exports.nih_plug_wam_alloc(state.length);
+    new Uint8Array(this._exports.memory.buffer, ptr, state.length).set(state);
+    this._exports.nih_plug_wam_load_state(this._instance, ptr, state.length);
+    this._exports.nih_plug_wam_free(ptr, state.length);
+  }
+
+  async createGui() {
+    if (!this._exports.nih_plug_wam_has_editor(this._instance)) {
+      return document.createElement("div");
+    }
+    if (this._gui) {
+      throw new Error("The plugin's editor can only be opened once");
+    }
+
+    const container = document.createElement("div");
+    const canvas = document.createElement("canvas");
+    const canvasHandle = nextCanvasHandle++;
+    canvas.dataset.rawHandle = String(canvasHandle);
+    canvas.style.display = "block";
+    canvas.style.outline = "none";
+    container.appendChild(canvas);
+
+    const gui = {
+      container,
+      canvasHandle,
+      open: false,
+      animationFrame: 0,
+      lastParameterSync: 0,
+      parameterSyncPending: false,
+    };
+    const frame = (now) => {
+      this._idle(gui, now);
+      gui.animationFrame = requestAnimationFrame(frame);
+    };
+    gui.animationFrame = requestAnimationFrame(frame);
+    this._gui = gui;
+
+    return container;
+  }
+
+  destroyGui(container) {
+    const gui = this._gui;
+    if (!gui || gui.container !== container) {
+      return;
+    }
+
+    cancelAnimationFrame(gui.animationFrame);
+    if (gui.open) {
+      this._exports.nih_plug_wam_editor_close(this._instance);
+    }
+    this._gui = null;
+  }
+
+  _idle(gui, now) {
+    const exports = this._exports;
+    const instance = this._instance;
+
+    // The editor looks up its canvas in the document, so it can only be opened once the host has
+    // added the GUI to the page
+    if (!gui.open && gui.container.isConnected) {
+      gui.open = exports.nih_plug_wam_editor_open(
+        instance,
+        gui.canvasHandle,
+        window.devicePixelRatio || 1,
+      );
+      this._resizeGui(gui);
+    }
+
+    exports.nih_plug_wam_idle(instance, now);
+
+    // Parameter changes and state changes made in the editor are forwarded to the audio worklet
+    const parameters = this._moduleDescriptor.parameters;
+    const changedValues = {};
+    let paramIdx;
+    while ((paramIdx = exports.nih_plug_wam_poll_param_change(instance, this._valuePtr)) >= 0) {
+      const { id } = parameters[paramIdx];
+      const value = new Float32Array(exports.memory.buffer, this._valuePtr, 1)[0];
+      changedValues[id] = { id, value, normalized: false };
+    }
+    if (Object.keys(changedValues).length > 0) {
+      this.audioNode.setParameterValues(changedValues);
+    }
+    if (exports.nih_plug_wam_poll_state_change(instance)) {
+      const len = exports.nih_plug_wam_save_state(instance);
+      const ptr = exports.nih_plug_wam_state_ptr(instance);
+      const state = new Uint8Array(exports.memory.buffer, ptr, len).slice();
+      this.audioNode._requestState("set", state);
+    }
+    if (exports.nih_plug_wam_poll_editor_resize(instance)) {
+      this._resizeGui(gui);
+    }
+
+    // And automation from the host is periodically synced back to the editor
+    if (!gui.parameterSyncPending && now - gui.lastParameterSync >= PARAMETER_SYNC_INTERVAL) {
+      gui.parameterSyncPending = true;
+      gui.lastParameterSync = now;
+      this.audioNode
+        .getParameterValues(false)
+        .then((values) => {
+          parameters.forEach(({ id }, idx) => {
+            const value = values[id]?.value;
+            if (value !== undefined && value !== exports.nih_plug_wam_get_param(instance, idx)) {
+              exports.nih_plug_wam_set_param(instance, idx, value);
+            }
+          });
+        })
+        .finally(() => {
+          gui.parameterSyncPending = false;
+        });
+    }
+  }
+
+  _resizeGui(gui) {
+    const size = this._exports.nih_plug_wam_editor_size(this._instance);
+    gui.container.style.width = `${size >>> 16}px`;
+    gui.container.style.height = `${size & 0xffff}px`;
+  }
+}
@@ -0,0 +1,211 @@
+// Generated by NIH-plug's `cargo xtask wam` command.
+
+/**
+ * Register the audio worklet side of a NIH-plug Web Audio Module. This function is serialized and
+ * evaluated in the `AudioWorkletGlobalScope` by `addFunctionModule()`, so it cannot refer to
+ * anything outside of its own body.
+ *
+ * @param {string} moduleId
+ */
+const getProcessor = (moduleId) => {
+  const audioWorkletGlobalScope = globalThis;
+  const { registerProcessor } = audioWorkletGlobalScope;
+  const ModuleScope = audioWorkletGlobalScope.webAudioModules.getModuleScope(moduleId);
+  const { WamProcessor, WamParameterInfo } = ModuleScope;
+
+  /**
+   * The plugin's `wasm-bindgen` imports are only used by the editor, which never runs in the audio
+   * worklet. They're replaced by functions that throw when called.
+   *
+   * @param {WebAssembly.Module} wasmModule
+   */
+  const stubImports = (wasmModule) => {
+    const imports = {};
+    for (const { module, name, kind } of WebAssembly.Module.imports(wasmModule)) {
+      if (kind !== "function") {
+        continue;
+      }
+
+      imports[module] ??= {};
+      imports[module][name] = () => {
+        throw new Error(`'${module}.${name}' is not available in the audio worklet`);
+      };
+    }
+
+    return imports;
+  };
+
+  /** The module descriptor for the processor that's currently being constructed. */
+  let pendingModuleDescriptor = null;
+
+  class NihPlugProcessor extends WamProcessor {
+    constructor(options) {
+      const { wasmModule, moduleDescriptor, maxBlockSize } = options.processorOptions;
+
+      // `_generateWamParameterInfo()` is called from the base class' constructor
+      pendingModuleDescriptor = moduleDescriptor;
+      super(options);
+      pendingModuleDescriptor = null;
+
+      this._moduleDescriptor = moduleDescriptor;
+      this._parameterIds = moduleDescriptor.parameters.map((parameter) => parameter.id);
+      this._lastParameterValues = new Float32Array(this._parameterIds.length).fill(NaN);
+
+      const wasmInstance = new WebAssembly.Instance(wasmModule, stubImports(wasmModule));
+      this._exports = wasmInstance.exports;
+      this._instance = this._exports.nih_plug_wam_create(sampleRate, maxBlockSize);
+      if (this._instance === 0) {
+        throw new Error("The plugin failed to initialize");
+      }
+
+      /** @type {MessagePort | null} Used by the node to send and request the plugin's state. */
+      this._statePort = null;
+    }
+
+    _generateWamParameterInfo() {
+      const parameterInfo = {};
+      for (const { id, ...config } of pendingModuleDescriptor.parameters) {
+        parameterInfo[id] = new WamParameterInfo(id, config);
+      }
+
+      return parameterInfo;
+    }
+
+    _onMessage(message) {
+      if (message.data?.nihPlugStatePort) {
+        this._statePort = message.data.nihPlugStatePort;
+        this._statePort.onmessage = (stateMessage) => this._onStateMessage(stateMessage.data);
+        return;
+      }
+
+      super._onMessage(message);
+    }
+
+    _onStateMessage({ id, request, state }) {
+      switch (request) {
+        case "get": {
+          const len = this._exports.nih_plug_wam_save_state(this._instance);
+          const ptr = this._exports.nih_plug_wam_state_ptr(this._instance);
+          const state = new Uint8Array(this._exports.memory.buffer, ptr, len).slice();
+          this._statePort.postMessage({ id, state }, [state.buffer]);
+          break;
+        }
+        case "set": {
+          const ptr = this._exports.nih_plug_wam_alloc(state.length);
+          new Uint8Array(this._exports.memory.buffer, ptr, state.length).set(state);
+          const success = this._exports.nih_plug_wam_load_state(this._instance, ptr, state.length);
+          this._exports.nih_plug_wam_free(ptr, state.length);
+
+          // The host's parameter values need to be updated to match the new state
+          if (success) {
+            for (let i = 0; i < this._parameterIds.length; i++) {
+              const value = this._exports.nih_plug_wam_get_param(this._instance, i);
+              this._parameterState[this._parameterIds[i]].value = value;
+              this._lastParameterValues[i] = value;
+            }
+          }
+
+          this._statePort.postMessage({ id, success });
+          break;
+        }
+      }
+    }
+
+    _onMidi(midiData) {
+      const [status, data1, data2] = midiData.bytes;
+      this._exports.nih_plug_wam_midi_event(this._instance, status, data1 ?? 0, data2 ?? 0);
+    }
+
+    _onTransport(transportData) {
+      this._exports.nih_plug_wam_set_transport(
+        this._instance,
+        transportData.playing,
+        transportData.tempo,
+        transportData.timeSigNumerator,
+        transportData.timeSigDenominator,
+        transportData.currentBar,
+        transportData.currentBarStarted,
+      );
+    }
+
+    _process(startSample, endSample, inputs, outputs) {
+      const numSamples = endSample - startSample;
+      if (numSamples <= 0) {
+        return;
+      }
+
+      // Parameters are only sent to the plugin when they have changed
+      for (let i = 0; i < this._parameterIds.length; i++) {
+        const value = this._parameterState[this._parameterIds[i]].value;
+        if (value !== this._lastParameterValues[i]) {
+          this._exports.nih_plug_wam_set_param(this._instance, i, value);
+          this._lastParameterValues[i] = value;
+        }
+      }
+
+      // The typed arrays need to be recreated every time since the module's memory may have grown
+      const input = inputs[0] ?? [];
+      for (let channel = 0; channel < this._moduleDescriptor.audioInputChannels; channel++) {
+        const ptr = this._exports.nih_plug_wam_input_buffer(this._instance, channel);
+        const buffer = new Float32Array(this._exports.memory.buffer, ptr, numSamples);
+        if (input[channel]) {
+          buffer.set(input[channel].subarray(startSample, endSample));
+        } else {
+          buffer.fill(0);
+        }
+      }
+
+      this._exports.nih_plug_wam_process(
+        this._instance,
+        numSamples,
+        currentTime + startSample / sampleRate,
+      );
+
+      const output = outputs[0] ?? [];
+      for (let channel = 0; channel < this._moduleDescriptor.audioOutputChannels; channel++) {
+        if (!output[channel]) {
+          continue;
+        }
+
+        const ptr = this._exports.nih_plug_wam_output_buffer(this._instance, channel);
+        output[channel].set(new Float32Array(this._exports.memory.buffer, ptr, numSamples), startSample);
+      }
+
+      // Output events are stored as `[timing, status | data1 << 8 | data2 << 16]` pairs
+      const numEvents = this._exports.nih_plug_wam_output_event_count(this._instance);
+      if (numEvents > 0) {
+        const ptr = this._exports.nih_plug_wam_output_events(this._instance);
+        const events = new Uint32Array(this._exports.memory.buffer, ptr, numEvents * 2);
+        const wamEvents = [];
+        for (let i = 0; i < numEvents; i++) {
+          const timing = events[i * 2];
+          const message = events[i * 2 + 1];
+          wamEvents.push({
+            type: "wam-midi",
+            time: currentTime + (startSample + timing) / sampleRate,
+            data: { bytes: [message & 0xff, (message >> 8) & 0xff, (message >> 16) & 0xff] },
+          });
+        }
+
+        this.emitEvents(...wamEvents);
+      }
+    }
+
+    destroy() {
+      this._exports.nih_plug_wam_destroy(this._instance);
+      this._instance = 0;
+      super.destroy();
+    }
+  }
+
+  try {
+    registerProcessor(moduleId, NihPlugProcessor);
+  } catch (error) {
+    // The processor has already been registered by another instance of the module
+    console.warn(error);
+  }
+
+  return NihPlugProcessor;
+};
+
+export default getProcessor;
@@ -201,7 +201,7 @@ impl DiopserParams {
                         None
                     }
                 }))
-                .hide_in_generic_ui(),
+                .hide_from_generic_ui(),
 
             automation_precision: FloatParam::new(
                 "Automation precision",
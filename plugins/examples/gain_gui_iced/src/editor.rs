@@ -25,9 +25,6 @@ struct GainEditor {
     context: Arc<dyn GuiContext>,
 
     peak_meter: Arc<AtomicF32>,
-
-    gain_slider_state: nih_widgets::param_slider::State,
-    peak_meter_state: nih_widgets::peak_meter::State,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -44,76 +41,66 @@ impl IcedEditor for GainEditor {
     fn new(
         (params, peak_meter): Self::InitializationFlags,
         context: Arc<dyn GuiContext>,
-    ) -> (Self, Command<Self::Message>) {
+    ) -> (Self, Task<Self::Message>) {
         let editor = GainEditor {
             params,
             context,
 
             peak_meter,
-
-            gain_slider_state: Default::default(),
-            peak_meter_state: Default::default(),
         };
 
-        (editor, Command::none())
+        (editor, Task::none())
     }
 
     fn context(&self) -> &dyn GuiContext {
         self.context.as_ref()
     }
 
-    fn update(
-        &mut self,
-        _window: &mut WindowQueue,
-        message: Self::Message,
-    ) -> Command<Self::Message> {
+    fn update(&mut self, message: Self::Message) -> Task<Self::Message> {
         match message {
             Message::ParamUpdate(message) => self.handle_param_message(message),
         }
 
-        Command::none()
+        Task::none()
     }
 
-    fn view(&mut self) -> Element<'_, Self::Message> {
+    fn view(&self) -> Element<'_, Self::Message> {
         Column::new()
-            .align_items(Alignment::Center)
+            .align_x(Alignment::Center)
             .push(
                 Text::new("Gain GUI")
                     .font(assets::NOTO_SANS_LIGHT)
                     .size(40)
-                    .height(50.into())
+                    .height(50)
                     .width(Length::Fill)
-                    .horizontal_alignment(alignment::Horizontal::Center)
-                    .vertical_alignment(alignment::Vertical::Bottom),
+                    .align_x(alignment::Horizontal::Center)
+                    .align_y(alignment::Vertical::Bottom),
             )
             .push(
                 Text::new("Gain")
-                    .height(20.into())
+                    .height(20)
                     .width(Length::Fill)
-                    .horizontal_alignment(alignment::Horizontal::Center)
-                    .vertical_alignment(alignment::Vertical::Center),
+                    .align_x(alignment::Horizontal::Center)
+                    .align_y(alignment::Vertical::Center),
             )
+            .push(nih_widgets::ParamSlider::new(&self.params.gain).map(Message::ParamUpdate))
+            .push(Space::with_height(10))
             .push(
-                nih_widgets::ParamSlider::new(&mut self.gain_slider_state, &self.params.gain)
-                    .map(Message::ParamUpdate),
-            )
-            .push(Space::with_height(10.into()))
-            .push(
-                nih_widgets::PeakMeter::new(
-                    &mut self.peak_meter_state,
-                    util::gain_to_db(self.peak_meter.load(std::sync::atomic::Ordering::Relaxed)),
-                )
+                nih_widgets::PeakMeter::new(util::gain_to_db(
+                    self.peak_meter.load(std::sync::atomic::Ordering::Relaxed),
+                ))
                 .hold_time(Duration::from_millis(600)),
             )
             .into()
     }
 
-    fn background_color(&self) -> nih_plug_iced::Color {
-        nih_plug_iced::Color {
-            r: 0.98,
-            g: 0.98,
-            b: 0.98,
-            a: 1.0,
-        }
+    fn theme(&self) -> Theme {
+        Theme::custom(
+            String::from("Gain GUI"),
+            theme::Palette {
+                background: Color::from_rgb(0.98, 0.98, 0.98),
+                ..theme::Palette::LIGHT
+            },
+        )
     }
 }
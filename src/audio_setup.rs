@@ -89,8 +89,11 @@ pub struct BufferConfig {
     pub process_mode: ProcessMode,
 }
 
-/// The plugin's current processing mode. Exposed through [`BufferConfig::process_mode`]. The host
-/// will reinitialize the plugin whenever this changes.
+/// The plugin's current processing mode. Exposed through [`BufferConfig::process_mode`] and
+/// [`ProcessContext::process_mode()`][crate::prelude::ProcessContext::process_mode()]. The host
+/// will usually reinitialize the plugin whenever this changes, and
+/// [`Plugin::process_mode_changed()`][crate::prelude::Plugin::process_mode_changed()] is called when
+/// it does.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ProcessMode {
     /// The plugin is processing audio in real time at a fixed rate.
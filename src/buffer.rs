@@ -4,6 +4,8 @@ use std::marker::PhantomData;
 
 mod blocks;
 mod samples;
+#[cfg(test)]
+pub(crate) mod test_util;
 
 pub use blocks::{Block, BlockChannelsIter, BlocksIter};
 pub use samples::{ChannelSamples, ChannelSamplesIter, SamplesIter};
@@ -0,0 +1,34 @@
+//! Helpers for the unit tests of code that processes [`Buffer`]s.
+
+use super::Buffer;
+
+/// Run `f` with a single channel buffer containing `samples`.
+pub fn with_buffer(samples: &mut [f32], f: impl FnOnce(&mut Buffer)) {
+    let num_samples = samples.len();
+    let mut buffer = Buffer::default();
+    unsafe {
+        buffer.set_slices(num_samples, |output_slices| {
+            *output_slices = vec![std::slice::from_raw_parts_mut(
+                samples.as_mut_ptr(),
+                num_samples,
+            )];
+        })
+    };
+
+    f(&mut buffer);
+}
+
+/// Run a mono signal through `process` in blocks of `block_size` samples, and return the processed
+/// signal.
+pub fn process_in_blocks(
+    signal: &[f32],
+    block_size: usize,
+    mut process: impl FnMut(&mut Buffer),
+) -> Vec<f32> {
+    let mut output = signal.to_vec();
+    for block in output.chunks_mut(block_size) {
+        with_buffer(block, &mut process);
+    }
+
+    output
+}
@@ -13,17 +13,50 @@ pub mod remote_controls;
 /// plugin's GUI for debugging purposes.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PluginApi {
+    Au,
     Clap,
+    Embedded,
+    Lv2,
     Standalone,
     Vst3,
+    Wam,
+}
+
+/// Information about the mixer track the plugin is inserted on, as provided by the host. This can
+/// be used to label a channel strip plugin the same way as the host's mixer track. Fields the host
+/// does not provide are set to `None`. See
+/// [`ProcessContext::track_info()`][crate::prelude::ProcessContext::track_info()] and
+/// [`Plugin::track_info_changed()`][crate::prelude::Plugin::track_info_changed()].
+///
+/// CLAP hosts provide this information through the track info extension, and VST3 hosts through
+/// the `IInfoListener` channel context interface. VST3 hosts only provide the track's name and
+/// color.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackInfo {
+    /// The track's name.
+    pub name: Option<String>,
+    /// The track's color in 8-bit sRGB as `[r, g, b, a]`.
+    pub color: Option<[u8; 4]>,
+    /// The number of audio channels on the track.
+    pub audio_channel_count: Option<u32>,
+    /// Whether the plugin is inserted on a return or FX track.
+    pub is_return_track: bool,
+    /// Whether the plugin is inserted on a bus or group track.
+    pub is_bus: bool,
+    /// Whether the plugin is inserted on the master track.
+    pub is_master: bool,
 }
 
 impl Display for PluginApi {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            PluginApi::Au => write!(f, "AU"),
             PluginApi::Clap => write!(f, "CLAP"),
+            PluginApi::Embedded => write!(f, "embedded"),
+            PluginApi::Lv2 => write!(f, "LV2"),
             PluginApi::Standalone => write!(f, "standalone"),
             PluginApi::Vst3 => write!(f, "VST3"),
+            PluginApi::Wam => write!(f, "WAM"),
         }
     }
 }
@@ -1,9 +1,28 @@
 //! A context passed to a plugin's editor.
 
+use anyhow::{Context, Result};
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::PluginApi;
-use crate::prelude::{Param, ParamPtr, Plugin, PluginState};
+use crate::prelude::{
+    DynamicParam, DynamicParamLayout, NoteEvent, Param, ParamPtr, Plugin, PluginState,
+};
+use crate::wrapper::state::{deserialize_state_string, serialize_state_string};
+
+mod history;
+mod messages;
+mod param_changes;
+mod state_slots;
+
+pub use crate::event_loop::TimerHandle;
+pub use history::ParamEditHistory;
+pub use messages::GuiMessage;
+pub(crate) use messages::GuiMessageQueue;
+pub(crate) use param_changes::ParamChangeBroadcaster;
+pub use param_changes::{ParamChange, ParamChangeReceiver};
+pub use state_slots::StateSlot;
+pub(crate) use state_slots::StateSlots;
 
 /// Callbacks the plugin can make when the user interacts with its GUI such as updating parameter
 /// values. This is passed to the plugin during [`Editor::spawn()`][crate::prelude::Editor::spawn()]. All of
@@ -23,9 +42,67 @@ pub trait GuiContext: Send + Sync + 'static {
     /// somehow didn't like this and rejected the resize, in which case the window should revert to
     /// its old size. You should only actually resize your embedded window once this returns `true`.
     ///
-    /// TODO: Host->Plugin resizing has not been implemented yet
+    /// Resizes initiated by the host are handled through
+    /// [`Editor::size_constraints()`][crate::prelude::Editor::size_constraints()] and
+    /// [`Editor::set_size()`][crate::prelude::Editor::set_size()].
     fn request_resize(&self) -> bool;
 
+    /// Override the DPI scaling factor reported by the host with a scale chosen by the user, or
+    /// pass `None` to use the host's scaling factor again. This is useful on Linux, where many
+    /// hosts don't report a scaling factor at all. The value is stored in the editor's state
+    /// through [`Editor::set_ui_scale()`][crate::prelude::Editor::set_ui_scale()] so it can be
+    /// persisted with the plugin's state.
+    ///
+    /// Returns `true` if the new scale has been applied and the host has been asked to resize the
+    /// window. If this returns `false` while the editor supports a UI scale, then the new scale
+    /// will be used the next time the editor is opened. Scaling is handled by the operating system
+    /// on macOS, so this always returns `false` there.
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool;
+
+    /// Send a note event from the editor to the plugin, for instance from an on-screen keyboard.
+    /// The event is added to the start of the next processing cycle's input events, so its timing
+    /// is ignored. SysEx events cannot be sent this way. Returns `false` if the event could not be
+    /// queued, either because the plugin does not accept note events as configured through
+    /// [`Plugin::MIDI_INPUT`] or because the queue is full.
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool;
+
+    /// Send a type erased message to the audio thread. Use `send_to_audio()` on a
+    /// `dyn GuiContext` instead for a type safe, user friendly API.
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool;
+
+    /// Update the current latency of the plugin, for instance after the user changed a lookahead
+    /// setting in the editor. The host is informed about the new latency, which may cause audio
+    /// playback to be restarted. This can also be called from the audio thread through
+    /// [`ProcessContext::set_latency_samples()`][crate::prelude::ProcessContext::set_latency_samples()].
+    fn set_latency_samples(&self, samples: u32);
+
+    /// The plugin's current DSP load. This is a rolling average of the time spent in the plugin's
+    /// process function relative to the duration of the audio it processed, so a value of 1.0
+    /// means the plugin is barely keeping up with the host. Time spent in the host or in other
+    /// plugins is not included, and blocks processed during offline rendering are not measured.
+    fn dsp_load(&self) -> f32;
+
+    /// Run `callback` on the GUI thread once after `delay` has passed. This can be used for things
+    /// like hiding a tooltip after a while without having to spawn a thread. The timer is cancelled
+    /// when the returned handle is dropped, unless [`TimerHandle::detach()`] is called on it.
+    ///
+    /// On Linux there is no GUI thread NIH-plug can post tasks to on its own. CLAP plugins and
+    /// VST3 plugins with an open editor run the callback on the host's GUI thread, and the callback
+    /// is run on a background thread otherwise.
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle;
+
+    /// Run `callback` on the GUI thread every `interval`, starting after the first interval. This
+    /// is useful for driving meter decay, autosaving, or polling without spawning a thread. Ticks
+    /// are skipped instead of piling up if the GUI thread falls behind. The timer runs until the
+    /// returned handle is dropped, or until the plugin instance is destroyed if
+    /// [`TimerHandle::detach()`] is called on it. See [`schedule_once()`][Self::schedule_once()]
+    /// for Linux specific behavior.
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle;
+
     /// Inform the host a parameter will be automated. Create a [`ParamSetter`] and use
     /// [`ParamSetter::begin_set_parameter()`] instead for a safe, user friendly API.
     ///
@@ -54,6 +131,21 @@ pub trait GuiContext: Send + Sync + 'static {
     /// mostly marked as unsafe for API reasons.
     unsafe fn raw_end_set_parameter(&self, param: ParamPtr);
 
+    /// Start grouping parameter changes into a single undoable edit. Create a [`ParamSetter`] and
+    /// use [`ParamSetter::begin_group_edit()`] instead. Group edits can be nested. The default
+    /// implementation does nothing.
+    fn raw_begin_group_edit(&self) {}
+
+    /// End a group edit started with [`raw_begin_group_edit()`][Self::raw_begin_group_edit()].
+    /// Create a [`ParamSetter`] and use [`ParamSetter::end_group_edit()`] instead. The default
+    /// implementation does nothing.
+    fn raw_end_group_edit(&self) {}
+
+    /// Tell the host that the names, units, or ranges of one or more
+    /// [`DynamicParam`][crate::prelude::DynamicParam]s have changed. Create a [`ParamSetter`] and
+    /// use [`ParamSetter::set_dynamic_layout()`] instead. The default implementation does nothing.
+    fn raw_param_info_changed(&self) {}
+
     /// Serialize the plugin's current state to a serde-serializable object. Useful for implementing
     /// preset handling within a plugin's GUI.
     fn get_state(&self) -> PluginState;
@@ -63,6 +155,105 @@ pub trait GuiContext: Send + Sync + 'static {
     /// host. If the plugin is currently processing audio, then the parameter values will be
     /// restored at the end of the current processing cycle.
     fn set_state(&self, state: PluginState);
+
+    /// Store a snapshot of the plugin's current state, including persistent fields, in one of the
+    /// two A/B comparison slots. This does not change which slot is active.
+    fn store_state_slot(&self, slot: StateSlot);
+
+    /// Switch between the A and B state slots. The current state is stored in the active slot, and
+    /// the other slot's state is restored using [`set_state()`][Self::set_state()]. If the other
+    /// slot is still empty, then it starts out as a copy of the current state. Returns the newly
+    /// active slot.
+    fn swap_ab(&self) -> StateSlot;
+
+    /// Copy the A slot's state to the B slot. If the B slot is currently active, then A's state is
+    /// also restored.
+    fn copy_a_to_b(&self);
+
+    /// The A/B comparison slot the plugin's current state belongs to. This is [`StateSlot::A`]
+    /// until [`swap_ab()`][Self::swap_ab()] is called.
+    fn active_state_slot(&self) -> StateSlot;
+
+    /// Ask the host to show its context menu for a parameter. This lets the user access the host's
+    /// automation and modulation options for the parameter from the plugin's GUI. Widgets typically
+    /// call this when the parameter is right clicked. `position` is the position in the editor
+    /// window where the menu should be shown, in logical pixels. Returns `false` if the host does
+    /// not support this, in which case the widget may fall back to some other behavior.
+    fn show_param_context_menu(&self, param: ParamPtr, position: (f32, f32)) -> bool;
+
+    /// Tell the host that a text entry field in the editor has gained keyboard focus. Until
+    /// [`release_keyboard_focus()`][Self::release_keyboard_focus()] is called, keystrokes are
+    /// reported as handled by the plugin so the host doesn't also use them as transport or other
+    /// shortcuts. The editor's window still receives the keystrokes through the operating system's
+    /// regular input handling. Returns `false` if the plugin API has no way to negotiate keyboard
+    /// focus with the host, in which case the host may still intercept some keys.
+    fn request_keyboard_focus(&self) -> bool;
+
+    /// Give keyboard focus back to the host after a call to
+    /// [`request_keyboard_focus()`][Self::request_keyboard_focus()], for instance because the text
+    /// entry field lost focus.
+    fn release_keyboard_focus(&self);
+
+    /// Replace the system clipboard's contents with `text`. Returns `false` if the clipboard could
+    /// not be accessed. This requires NIH-plug's `clipboard` feature, which the GUI adapter crates
+    /// enable.
+    fn set_clipboard_text(&self, text: &str) -> bool;
+
+    /// Get the system clipboard's contents, if it contains text. Like
+    /// [`set_clipboard_text()`][Self::set_clipboard_text()], this requires the `clipboard`
+    /// feature.
+    fn clipboard_text(&self) -> Option<String>;
+
+    /// Get the mapping and automation indication the host has set for a parameter, if any. This is
+    /// used by hosts that support CLAP's `param-indication` extension to tell the plugin that a
+    /// parameter has been mapped to one of the host's controls so the GUI can display this. Always
+    /// returns `None` for other plugin APIs.
+    fn param_indication(&self, param: ParamPtr) -> Option<ParamIndication>;
+
+    /// Subscribe to the plugin's parameter changes. The returned receiver gets a [`ParamChange`]
+    /// whenever the host changes a parameter's value or modulation, or when a new state is loaded.
+    /// Editors can use this to only redraw when something has changed instead of polling the
+    /// parameters every frame. The receiver stays subscribed until it is dropped.
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver;
+}
+
+/// Information provided by the host about how one of the plugin's parameters is being controlled
+/// by the host. See [`GuiContext::param_indication()`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamIndication {
+    /// The host's mapping for this parameter, if it has mapped the parameter to one of its own
+    /// physical or virtual controls.
+    pub mapping: Option<ParamMappingIndication>,
+    /// The current automation state for the parameter.
+    pub automation: ParamAutomationState,
+}
+
+/// Describes the host control a parameter has been mapped to.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ParamMappingIndication {
+    /// The color the host uses for the mapping as an RGBA tuple, if it has one. Plugin GUIs can use
+    /// this to highlight the parameter.
+    pub color: Option<[u8; 4]>,
+    /// A short label for the mapping, e.g. `"MIDI CC 7"`.
+    pub label: Option<String>,
+    /// A longer description for the mapping.
+    pub description: Option<String>,
+}
+
+/// The automation state the host has indicated for a parameter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ParamAutomationState {
+    /// The host has no automation for this parameter.
+    #[default]
+    None,
+    /// The host has automation for this parameter, but it isn't currently playing it back.
+    Present,
+    /// The host is playing back automation for this parameter.
+    Playing,
+    /// The host is recording automation for this parameter.
+    Recording,
+    /// The host has automation for this parameter, but the user has overridden it.
+    Overriding,
 }
 
 /// An way to run background tasks from the plugin's GUI, equivalent to the
@@ -83,6 +274,7 @@ pub trait GuiContext: Send + Sync + 'static {
 pub struct AsyncExecutor<P: Plugin> {
     pub(crate) execute_background: Arc<dyn Fn(P::BackgroundTask) + Send + Sync>,
     pub(crate) execute_gui: Arc<dyn Fn(P::BackgroundTask) + Send + Sync>,
+    pub(crate) cancel_background: Arc<dyn Fn(u32) + Send + Sync>,
 }
 
 // Can't derive this since Rust then requires `P` to also be `Clone`able
@@ -91,6 +283,7 @@ impl<P: Plugin> Clone for AsyncExecutor<P> {
         Self {
             execute_background: self.execute_background.clone(),
             execute_gui: self.execute_gui.clone(),
+            cancel_background: self.cancel_background.clone(),
         }
     }
 }
@@ -100,6 +293,9 @@ impl<P: Plugin> Clone for AsyncExecutor<P> {
 /// functions should only be called from the main thread.
 pub struct ParamSetter<'a> {
     pub raw_context: &'a dyn GuiContext,
+    /// An optional history that records the gestures made through this setter. See
+    /// [`with_history()`][Self::with_history()].
+    history: Option<&'a ParamEditHistory>,
 }
 
 impl<P: Plugin> AsyncExecutor<P> {
@@ -126,18 +322,82 @@ impl<P: Plugin> AsyncExecutor<P> {
     pub fn execute_gui(&self, task: P::BackgroundTask) {
         (self.execute_gui)(task);
     }
+
+    /// Cancel all pending background tasks that were given the tag `tag` through
+    /// [`Plugin::background_task_options()`]. Tasks that are already running are not affected.
+    pub fn cancel_background_tasks(&self, tag: u32) {
+        (self.cancel_background)(tag);
+    }
+}
+
+impl dyn GuiContext {
+    /// Send a message from the editor to the audio thread, for instance to trigger a sample load, a
+    /// tap tempo, or to preview a sound. The plugin receives these messages in its process function
+    /// through [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    /// All messages should have the same type, so use an enum if the editor needs to send different
+    /// kinds of messages. Returns `false` if the message could not be queued because the audio
+    /// thread has not handled the previously sent messages yet.
+    ///
+    /// Sending a message allocates, but the audio thread never needs to allocate or deallocate
+    /// anything to receive it.
+    pub fn send_to_audio<T: Send + 'static>(&self, message: T) -> bool {
+        self.raw_send_to_audio(GuiMessage::new(message))
+    }
+
+    /// Copy the plugin's current state to the clipboard as a compact string so it can be shared
+    /// or pasted into another instance of the plugin using
+    /// [`paste_state_from_clipboard()`][Self::paste_state_from_clipboard()]. Returns `false` if
+    /// the state could not be copied. See
+    /// [`serialize_state_string()`][crate::wrapper::state::serialize_state_string()] for the
+    /// format.
+    pub fn copy_state_to_clipboard(&self) -> bool {
+        match serialize_state_string(&self.get_state()) {
+            Ok(state) => self.set_clipboard_text(&state),
+            Err(err) => {
+                nih_debug_assert_failure!("Could not serialize the plugin's state: {:#}", err);
+                false
+            }
+        }
+    }
+
+    /// Restore a state copied using [`copy_state_to_clipboard()`][Self::copy_state_to_clipboard()].
+    /// Returns an error if the clipboard does not contain a state string.
+    pub fn paste_state_from_clipboard(&self) -> Result<()> {
+        let text = self
+            .clipboard_text()
+            .context("The clipboard does not contain any text")?;
+        let state = deserialize_state_string(&text)?;
+        self.set_state(state);
+
+        Ok(())
+    }
 }
 
 impl<'a> ParamSetter<'a> {
     pub fn new(context: &'a dyn GuiContext) -> Self {
         Self {
             raw_context: context,
+            history: None,
+        }
+    }
+
+    /// The same as [`new()`][Self::new()], but any parameter changes made through this setter are
+    /// also recorded in `history`. This can then be used to undo and redo those changes from the
+    /// plugin's GUI. See [`ParamEditHistory`] for more information.
+    pub fn with_history(context: &'a dyn GuiContext, history: &'a ParamEditHistory) -> Self {
+        Self {
+            raw_context: context,
+            history: Some(history),
         }
     }
 
     /// Inform the host that you will start automating a parameter. This needs to be called before
     /// calling [`set_parameter()`][Self::set_parameter()] for the specified parameter.
     pub fn begin_set_parameter<P: Param>(&self, param: &P) {
+        if let Some(history) = self.history {
+            history.begin_gesture(param.as_ptr());
+        }
+
         unsafe { self.raw_context.raw_begin_set_parameter(param.as_ptr()) };
     }
 
@@ -153,6 +413,10 @@ impl<'a> ParamSetter<'a> {
     pub fn set_parameter<P: Param>(&self, param: &P, value: P::Plain) {
         let ptr = param.as_ptr();
         let normalized = param.preview_normalized(value);
+        if let Some(history) = self.history {
+            history.set_value(ptr, normalized);
+        }
+
         unsafe {
             self.raw_context
                 .raw_set_parameter_normalized(ptr, normalized)
@@ -168,6 +432,10 @@ impl<'a> ParamSetter<'a> {
     /// normalized value known to the host matches `param.normalized_value()`.
     pub fn set_parameter_normalized<P: Param>(&self, param: &P, normalized: f32) {
         let ptr = param.as_ptr();
+        if let Some(history) = self.history {
+            history.set_value(ptr, normalized);
+        }
+
         unsafe {
             self.raw_context
                 .raw_set_parameter_normalized(ptr, normalized)
@@ -178,6 +446,44 @@ impl<'a> ParamSetter<'a> {
     /// or more [`set_parameter()`][Self::set_parameter()] calls for a parameter so the host knows
     /// the automation gesture has finished.
     pub fn end_set_parameter<P: Param>(&self, param: &P) {
+        if let Some(history) = self.history {
+            history.end_gesture(param.as_ptr());
+        }
+
         unsafe { self.raw_context.raw_end_set_parameter(param.as_ptr()) };
     }
+
+    /// Start a group edit. All parameter gestures made until the matching
+    /// [`end_group_edit()`][Self::end_group_edit()] call are combined into a single undo step in
+    /// the host, and in the [`ParamEditHistory`] if this setter was created using
+    /// [`with_history()`][Self::with_history()]. This is useful for XY pads, linked controls, and
+    /// randomize buttons that change multiple parameters at once. The individual parameter changes
+    /// still need to be wrapped in [`begin_set_parameter()`][Self::begin_set_parameter()] and
+    /// [`end_set_parameter()`][Self::end_set_parameter()] calls. Group edits can be nested, and
+    /// they may span multiple `ParamSetter`s as long as they use the same [`GuiContext`].
+    pub fn begin_group_edit(&self) {
+        if let Some(history) = self.history {
+            history.begin_group();
+        }
+
+        self.raw_context.raw_begin_group_edit();
+    }
+
+    /// End a group edit started with [`begin_group_edit()`][Self::begin_group_edit()].
+    pub fn end_group_edit(&self) {
+        if let Some(history) = self.history {
+            history.end_group();
+        }
+
+        self.raw_context.raw_end_group_edit();
+    }
+
+    /// Change a [`DynamicParam`]'s layout and tell the host to rescan the parameters' names and
+    /// values. The slot's normalized value is kept as is, so call
+    /// [`set_parameter()`][Self::set_parameter()] afterwards if the slot should be reset to its new
+    /// default value.
+    pub fn set_dynamic_layout(&self, param: &DynamicParam, layout: DynamicParamLayout) {
+        param.set_layout(layout);
+        self.raw_context.raw_param_info_changed();
+    }
 }
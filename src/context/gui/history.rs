@@ -0,0 +1,515 @@
+//! An optional undo/redo history for parameter changes made from the plugin's GUI.
+
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+use super::GuiContext;
+use crate::prelude::ParamPtr;
+
+/// The default maximum number of entries stored in a [`ParamEditHistory`].
+const DEFAULT_CAPACITY: usize = 100;
+
+/// A plugin-local undo/redo history for parameter edits made from the plugin's editor. This can be
+/// used to offer undo and redo functionality in hosts that don't keep track of parameter changes
+/// themselves. Create one of these in your editor, and then use
+/// [`ParamSetter::with_history()`][super::ParamSetter::with_history()] instead of
+/// [`ParamSetter::new()`][super::ParamSetter::new()] to record the begin- and end-gestures sent
+/// through that setter.
+///
+/// Every automation gesture results in a single history entry, so dragging a slider around only
+/// needs a single undo to be reverted. If gestures for multiple parameters overlap, for instance
+/// when a single widget controls multiple parameters, then those changes are also combined into a
+/// single entry that's recorded when the last gesture has ended. The same happens for all gestures
+/// made during a [group edit][super::ParamSetter::begin_group_edit()].
+#[derive(Debug)]
+pub struct ParamEditHistory {
+    inner: Mutex<HistoryInner>,
+}
+
+#[derive(Debug)]
+struct HistoryInner {
+    /// The maximum number of entries in `undo_stack`. Older entries are discarded.
+    capacity: usize,
+    /// Entries that can be undone, with the most recent entry at the back.
+    undo_stack: VecDeque<HistoryEntry>,
+    /// Entries that have been undone and can be redone again, with the most recently undone entry
+    /// at the back. This is cleared when a new entry is recorded.
+    redo_stack: Vec<HistoryEntry>,
+    /// The parameters that currently have an active gesture.
+    active_gestures: Vec<ActiveGesture>,
+    /// Changes from gestures that have already ended while other gestures were still active. These
+    /// are combined into a single entry once `active_gestures` is empty.
+    pending_changes: Vec<ParamChange>,
+    /// The number of nested group edits. Changes are only recorded as an entry once this is zero
+    /// and there are no more active gestures.
+    group_depth: usize,
+}
+
+/// A gesture that has been started but not yet ended.
+#[derive(Debug, Clone, Copy)]
+struct ActiveGesture {
+    param: ParamPtr,
+    /// The normalized value at the start of the gesture.
+    old_normalized: f32,
+    /// The last normalized value set during this gesture, if any. This needs to be stored
+    /// explicitly because the wrapper may only update the parameter's value at a later point.
+    new_normalized: Option<f32>,
+}
+
+/// A single undoable action. This may contain changes for multiple parameters.
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    changes: Vec<ParamChange>,
+}
+
+/// A change for a single parameter.
+#[derive(Debug, Clone, Copy)]
+struct ParamChange {
+    param: ParamPtr,
+    /// The normalized value before the change.
+    old_normalized: f32,
+    /// The normalized value after the change.
+    new_normalized: f32,
+}
+
+impl Default for ParamEditHistory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParamEditHistory {
+    /// Create an empty history that holds on to up to 100 entries.
+    pub fn new() -> Self {
+        Self::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create an empty history that holds on to up to `capacity` entries. Older entries are
+    /// discarded when this limit is reached.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            inner: Mutex::new(HistoryInner {
+                capacity,
+                undo_stack: VecDeque::with_capacity(capacity),
+                redo_stack: Vec::new(),
+                active_gestures: Vec::new(),
+                pending_changes: Vec::new(),
+                group_depth: 0,
+            }),
+        }
+    }
+
+    /// Whether there is an entry that can be undone.
+    pub fn can_undo(&self) -> bool {
+        !self.inner.lock().undo_stack.is_empty()
+    }
+
+    /// Whether there is an entry that can be redone.
+    pub fn can_redo(&self) -> bool {
+        !self.inner.lock().redo_stack.is_empty()
+    }
+
+    /// Remove all entries from the history. This is useful after loading a preset, since undoing
+    /// changes made to the previous preset would otherwise result in a mix of both presets.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.undo_stack.clear();
+        inner.redo_stack.clear();
+        inner.active_gestures.clear();
+        inner.pending_changes.clear();
+        inner.group_depth = 0;
+    }
+
+    /// Revert the most recent entry by setting the parameters back to their old values through
+    /// `context`. Returns `false` if there was nothing to undo. This does nothing while a gesture
+    /// is still active.
+    pub fn undo(&self, context: &dyn GuiContext) -> bool {
+        let entry = {
+            let mut inner = self.inner.lock();
+            if !inner.active_gestures.is_empty() || inner.group_depth > 0 {
+                return false;
+            }
+
+            match inner.undo_stack.pop_back() {
+                Some(entry) => {
+                    inner.redo_stack.push(entry.clone());
+                    entry
+                }
+                None => return false,
+            }
+        };
+
+        // The changes are reverted in the reverse order they were made in
+        apply_changes(
+            context,
+            entry
+                .changes
+                .iter()
+                .rev()
+                .map(|change| (change.param, change.old_normalized)),
+        );
+
+        true
+    }
+
+    /// Reapply the most recently undone entry through `context`. Returns `false` if there was
+    /// nothing to redo. This does nothing while a gesture is still active.
+    pub fn redo(&self, context: &dyn GuiContext) -> bool {
+        let entry = {
+            let mut inner = self.inner.lock();
+            if !inner.active_gestures.is_empty() || inner.group_depth > 0 {
+                return false;
+            }
+
+            match inner.redo_stack.pop() {
+                Some(entry) => {
+                    inner.undo_stack.push_back(entry.clone());
+                    entry
+                }
+                None => return false,
+            }
+        };
+
+        apply_changes(
+            context,
+            entry
+                .changes
+                .iter()
+                .map(|change| (change.param, change.new_normalized)),
+        );
+
+        true
+    }
+
+    /// Record the start of an automation gesture. This is called automatically by
+    /// [`ParamSetter`][super::ParamSetter], but it can also be called manually when a GUI adapter
+    /// sends parameter changes to the [`GuiContext`] directly.
+    pub fn begin_gesture(&self, param: ParamPtr) {
+        let mut inner = self.inner.lock();
+        if inner.active_gestures.iter().any(|g| g.param == param) {
+            nih_debug_assert_failure!("Nested gestures for the same parameter, ignoring...");
+            return;
+        }
+
+        inner.active_gestures.push(ActiveGesture {
+            param,
+            old_normalized: unsafe { param.unmodulated_normalized_value() },
+            new_normalized: None,
+        });
+    }
+
+    /// Record a parameter change during an automation gesture. See
+    /// [`begin_gesture()`][Self::begin_gesture()].
+    pub fn set_value(&self, param: ParamPtr, normalized: f32) {
+        let mut inner = self.inner.lock();
+        match inner.active_gestures.iter_mut().find(|g| g.param == param) {
+            Some(gesture) => gesture.new_normalized = Some(normalized),
+            None => nih_debug_assert_failure!("Parameter set without an active gesture"),
+        }
+    }
+
+    /// Record the end of an automation gesture. See [`begin_gesture()`][Self::begin_gesture()].
+    pub fn end_gesture(&self, param: ParamPtr) {
+        let mut inner = self.inner.lock();
+        let gesture = match inner.active_gestures.iter().position(|g| g.param == param) {
+            Some(idx) => inner.active_gestures.remove(idx),
+            None => {
+                nih_debug_assert_failure!("Gesture ended without a matching begin gesture");
+                return;
+            }
+        };
+
+        if let Some(new_normalized) = gesture.new_normalized {
+            if new_normalized != gesture.old_normalized {
+                inner.pending_changes.push(ParamChange {
+                    param,
+                    old_normalized: gesture.old_normalized,
+                    new_normalized,
+                });
+            }
+        }
+
+        inner.commit_pending_changes();
+    }
+
+    /// Start a group edit. All changes made until the matching [`end_group()`][Self::end_group()]
+    /// call are combined into a single entry. This is called automatically by
+    /// [`ParamSetter::begin_group_edit()`][super::ParamSetter::begin_group_edit()].
+    pub fn begin_group(&self) {
+        self.inner.lock().group_depth += 1;
+    }
+
+    /// End a group edit. See [`begin_group()`][Self::begin_group()].
+    pub fn end_group(&self) {
+        let mut inner = self.inner.lock();
+        if inner.group_depth == 0 {
+            nih_debug_assert_failure!("Group edit ended without a matching begin");
+            return;
+        }
+
+        inner.group_depth -= 1;
+        inner.commit_pending_changes();
+    }
+}
+
+impl HistoryInner {
+    /// Turn the pending changes into a new history entry if there are no more active gestures or
+    /// group edits.
+    fn commit_pending_changes(&mut self) {
+        if self.active_gestures.is_empty()
+            && self.group_depth == 0
+            && !self.pending_changes.is_empty()
+        {
+            let changes = std::mem::take(&mut self.pending_changes);
+            if self.capacity > 0 {
+                if self.undo_stack.len() >= self.capacity {
+                    self.undo_stack.pop_front();
+                }
+                self.undo_stack.push_back(HistoryEntry { changes });
+            }
+            self.redo_stack.clear();
+        }
+    }
+}
+
+/// Set parameters to new normalized values with a gesture around each change, without recording
+/// anything in the history.
+fn apply_changes(context: &dyn GuiContext, changes: impl Iterator<Item = (ParamPtr, f32)>) {
+    for (param, normalized) in changes {
+        unsafe {
+            context.raw_begin_set_parameter(param);
+            context.raw_set_parameter_normalized(param, normalized);
+            context.raw_end_set_parameter(param);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::context::gui::{
+        GuiMessage, ParamChangeReceiver, ParamIndication, StateSlot, TimerHandle,
+    };
+    use crate::context::PluginApi;
+    use crate::prelude::{FloatParam, FloatRange, NoteEvent, Param, PluginState};
+
+    /// A context that sets the parameters' values directly, the way the wrappers would after the
+    /// host has been informed about the change.
+    struct TestContext;
+
+    impl GuiContext for TestContext {
+        fn plugin_api(&self) -> PluginApi {
+            PluginApi::Clap
+        }
+
+        fn request_resize(&self) -> bool {
+            unimplemented!()
+        }
+
+        fn set_ui_scale(&self, _scale: Option<f32>) -> bool {
+            unimplemented!()
+        }
+
+        fn send_note_event(&self, _event: NoteEvent<()>) -> bool {
+            unimplemented!()
+        }
+
+        fn raw_send_to_audio(&self, _message: GuiMessage) -> bool {
+            unimplemented!()
+        }
+
+        fn set_latency_samples(&self, _samples: u32) {
+            unimplemented!()
+        }
+
+        fn dsp_load(&self) -> f32 {
+            unimplemented!()
+        }
+
+        fn schedule_once(
+            &self,
+            _delay: Duration,
+            _callback: Box<dyn FnOnce() + Send>,
+        ) -> TimerHandle {
+            unimplemented!()
+        }
+
+        fn schedule_repeating(
+            &self,
+            _interval: Duration,
+            _callback: Box<dyn FnMut() + Send>,
+        ) -> TimerHandle {
+            unimplemented!()
+        }
+
+        unsafe fn raw_begin_set_parameter(&self, _param: ParamPtr) {}
+
+        unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+            param.set_normalized_value(normalized);
+        }
+
+        unsafe fn raw_end_set_parameter(&self, _param: ParamPtr) {}
+
+        fn get_state(&self) -> PluginState {
+            unimplemented!()
+        }
+
+        fn set_state(&self, _state: PluginState) {
+            unimplemented!()
+        }
+
+        fn store_state_slot(&self, _slot: StateSlot) {
+            unimplemented!()
+        }
+
+        fn swap_ab(&self) -> StateSlot {
+            unimplemented!()
+        }
+
+        fn copy_a_to_b(&self) {
+            unimplemented!()
+        }
+
+        fn active_state_slot(&self) -> StateSlot {
+            unimplemented!()
+        }
+
+        fn show_param_context_menu(&self, _param: ParamPtr, _position: (f32, f32)) -> bool {
+            unimplemented!()
+        }
+
+        fn request_keyboard_focus(&self) -> bool {
+            unimplemented!()
+        }
+
+        fn release_keyboard_focus(&self) {
+            unimplemented!()
+        }
+
+        fn set_clipboard_text(&self, _text: &str) -> bool {
+            unimplemented!()
+        }
+
+        fn clipboard_text(&self) -> Option<String> {
+            unimplemented!()
+        }
+
+        fn param_indication(&self, _param: ParamPtr) -> Option<ParamIndication> {
+            unimplemented!()
+        }
+
+        fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+            unimplemented!()
+        }
+    }
+
+    fn make_param() -> FloatParam {
+        FloatParam::new("Test", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+    }
+
+    /// Set a parameter to each of `values` in turn within a single gesture, like dragging a slider.
+    fn drag(history: &ParamEditHistory, param: &FloatParam, values: &[f32]) {
+        let param = param.as_ptr();
+        history.begin_gesture(param);
+        for &value in values {
+            unsafe { TestContext.raw_set_parameter_normalized(param, value) };
+            history.set_value(param, value);
+        }
+        history.end_gesture(param);
+    }
+
+    #[test]
+    fn test_undo_coalesced_drag() {
+        let history = ParamEditHistory::new();
+        let param = make_param();
+        drag(&history, &param, &[0.2, 0.4, 0.6]);
+        assert_eq!(param.unmodulated_normalized_value(), 0.6);
+
+        // The entire drag is reverted with a single undo
+        assert!(history.undo(&TestContext));
+        assert_eq!(param.unmodulated_normalized_value(), 0.0);
+        assert!(!history.can_undo());
+
+        assert!(history.redo(&TestContext));
+        assert_eq!(param.unmodulated_normalized_value(), 0.6);
+        assert!(!history.can_redo());
+    }
+
+    #[test]
+    fn test_new_edit_clears_redo() {
+        let history = ParamEditHistory::new();
+        let param = make_param();
+        drag(&history, &param, &[0.3]);
+        drag(&history, &param, &[0.7]);
+
+        assert!(history.undo(&TestContext));
+        assert_eq!(param.unmodulated_normalized_value(), 0.3);
+        assert!(history.can_redo());
+
+        drag(&history, &param, &[0.9]);
+        assert!(!history.can_redo());
+        assert!(!history.redo(&TestContext));
+        assert_eq!(param.unmodulated_normalized_value(), 0.9);
+
+        assert!(history.undo(&TestContext));
+        assert_eq!(param.unmodulated_normalized_value(), 0.3);
+    }
+
+    #[test]
+    fn test_gesture_boundaries() {
+        let history = ParamEditHistory::new();
+        let first = make_param();
+        let second = make_param();
+
+        // Gestures without any changes are not recorded
+        history.begin_gesture(first.as_ptr());
+        history.end_gesture(first.as_ptr());
+        assert!(!history.can_undo());
+
+        // Overlapping gestures are combined into a single entry once the last one has ended, and
+        // nothing can be undone in the meantime
+        history.begin_gesture(first.as_ptr());
+        history.begin_gesture(second.as_ptr());
+        unsafe {
+            TestContext.raw_set_parameter_normalized(first.as_ptr(), 0.25);
+            TestContext.raw_set_parameter_normalized(second.as_ptr(), 0.75);
+        }
+        history.set_value(first.as_ptr(), 0.25);
+        history.set_value(second.as_ptr(), 0.75);
+        history.end_gesture(first.as_ptr());
+        assert!(!history.undo(&TestContext));
+        history.end_gesture(second.as_ptr());
+
+        // Separate gestures result in separate entries
+        drag(&history, &first, &[0.5]);
+
+        assert!(history.undo(&TestContext));
+        assert_eq!(first.unmodulated_normalized_value(), 0.25);
+        assert_eq!(second.unmodulated_normalized_value(), 0.75);
+
+        assert!(history.undo(&TestContext));
+        assert_eq!(first.unmodulated_normalized_value(), 0.0);
+        assert_eq!(second.unmodulated_normalized_value(), 0.0);
+        assert!(!history.can_undo());
+    }
+
+    #[test]
+    fn test_group_edit() {
+        let history = ParamEditHistory::new();
+        let first = make_param();
+        let second = make_param();
+
+        history.begin_group();
+        drag(&history, &first, &[0.1]);
+        drag(&history, &second, &[0.2]);
+        assert!(!history.can_undo());
+        history.end_group();
+
+        assert!(history.undo(&TestContext));
+        assert_eq!(first.unmodulated_normalized_value(), 0.0);
+        assert_eq!(second.unmodulated_normalized_value(), 0.0);
+        assert!(!history.can_undo());
+    }
+}
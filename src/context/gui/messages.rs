@@ -0,0 +1,103 @@
+//! A bounded, type erased queue for sending messages from the editor to the audio thread.
+
+use crossbeam::queue::ArrayQueue;
+use std::any::{self, Any};
+
+use crate::util::permit_alloc;
+
+/// The number of messages that can be queued up before the next processing cycle. Sending more
+/// messages than this will cause `send_to_audio()` to return `false`.
+const GUI_MESSAGE_QUEUE_CAPACITY: usize = 512;
+
+/// A message sent from the editor through `send_to_audio()` on a [`GuiContext`][super::GuiContext].
+/// The message is stored in an `Option` so the audio thread can move it out of its allocation
+/// without deallocating that memory.
+pub struct GuiMessage(Box<dyn Any + Send>);
+
+/// The queue backing `send_to_audio()` on a [`GuiContext`][super::GuiContext] and
+/// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+/// Every wrapper owns one of these. Messages are allocated on the GUI thread, and their emptied
+/// allocations are sent back to be freed on the GUI thread the next time a message is sent, so the
+/// audio thread never has to allocate or deallocate.
+pub(crate) struct GuiMessageQueue {
+    messages: ArrayQueue<GuiMessage>,
+    /// Messages that have been handled by the audio thread. These are dropped when the editor
+    /// sends its next message.
+    spent_messages: ArrayQueue<GuiMessage>,
+}
+
+impl GuiMessage {
+    /// Wrap a message so it can be sent to the audio thread.
+    pub fn new<T: Send + 'static>(message: T) -> Self {
+        Self(Box::new(Some(message)))
+    }
+
+    /// Move the message out of this object if it has type `T`.
+    fn take<T: Send + 'static>(&mut self) -> Option<T> {
+        self.0.downcast_mut::<Option<T>>().and_then(Option::take)
+    }
+}
+
+impl Default for GuiMessageQueue {
+    fn default() -> Self {
+        Self {
+            messages: ArrayQueue::new(GUI_MESSAGE_QUEUE_CAPACITY),
+            // The editor frees this queue before every message it sends, so this can only contain
+            // as many messages as the message queue can hold. The extra capacity covers the
+            // messages sent while the audio thread is draining the queue.
+            spent_messages: ArrayQueue::new(GUI_MESSAGE_QUEUE_CAPACITY * 2),
+        }
+    }
+}
+
+impl GuiMessageQueue {
+    /// Queue a message for the audio thread. Called from the GUI thread. Returns `false` if the
+    /// queue is full.
+    pub fn send(&self, message: GuiMessage) -> bool {
+        while self.spent_messages.pop().is_some() {}
+
+        self.messages.push(message).is_ok()
+    }
+
+    /// Call `handler` on every queued message. Called from the audio thread. Messages that are not
+    /// of type `T` are discarded.
+    pub fn drain<T: Send + 'static>(&self, mut handler: impl FnMut(T)) {
+        while let Some(mut message) = self.messages.pop() {
+            match message.take::<T>() {
+                Some(message) => handler(message),
+                None => nih_debug_assert_failure!(
+                    "Discarding a GUI message that is not of type '{}'",
+                    any::type_name::<T>()
+                ),
+            }
+
+            // The message's allocation is freed on the GUI thread. This should never fail, but if
+            // it does then the memory needs to be freed here instead.
+            if let Err(message) = self.spent_messages.push(message) {
+                nih_debug_assert_failure!("The spent GUI message queue is full");
+                permit_alloc(|| drop(message));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_drain_messages() {
+        let queue = GuiMessageQueue::default();
+        assert!(queue.send(GuiMessage::new(String::from("sample.wav"))));
+        assert!(queue.send(GuiMessage::new(String::from("other.wav"))));
+
+        let mut received = Vec::new();
+        queue.drain(|message: String| received.push(message));
+        assert_eq!(received, ["sample.wav", "other.wav"]);
+
+        // The emptied messages are freed when the next message is sent
+        assert_eq!(queue.spent_messages.len(), 2);
+        assert!(queue.send(GuiMessage::new(String::new())));
+        assert_eq!(queue.spent_messages.len(), 0);
+    }
+}
@@ -0,0 +1,177 @@
+//! Parameter change notifications for editors that don't want to poll their parameters every frame.
+
+use crossbeam::channel::{self, Receiver, Sender, TrySendError};
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+/// The number of changes that can be pending for a single [`ParamChangeReceiver`]. If the editor
+/// doesn't keep up, then the next change it receives is a [`ParamChange::All`].
+const PARAM_CHANGE_QUEUE_CAPACITY: usize = 1024;
+
+/// A parameter change received through a [`ParamChangeReceiver`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParamChange {
+    /// A parameter's value has been changed, usually by the host or through automation.
+    Value { id: String, normalized_value: f32 },
+    /// A parameter's monophonic modulation offset has been changed by the host.
+    Modulation { id: String, modulation_offset: f32 },
+    /// Any number of parameters may have changed, for instance because a preset was loaded or
+    /// because the receiver was not drained in time.
+    All,
+}
+
+/// Receives the plugin's parameter changes on the GUI thread. Obtained through
+/// [`GuiContext::subscribe_param_changes()`][super::GuiContext::subscribe_param_changes()]. This can
+/// be used to only redraw the editor when a parameter has actually changed, and it can be sent to
+/// another thread to drive an external update loop. Dropping the receiver unsubscribes it.
+pub struct ParamChangeReceiver {
+    receiver: Receiver<ParamChange>,
+    /// Set when a change could not be queued because the receiver was full.
+    overflowed: Arc<AtomicBool>,
+}
+
+/// Sends parameter changes to every [`ParamChangeReceiver`]. Every wrapper owns one of these and
+/// broadcasts to it from the same GUI thread tasks that call
+/// [`Editor::param_value_changed()`][crate::prelude::Editor::param_value_changed()].
+#[derive(Default)]
+pub(crate) struct ParamChangeBroadcaster {
+    subscribers: Mutex<Vec<Subscriber>>,
+}
+
+struct Subscriber {
+    sender: Sender<ParamChange>,
+    overflowed: Arc<AtomicBool>,
+}
+
+impl ParamChangeReceiver {
+    /// Get the next pending change, if there is one. This never blocks.
+    pub fn try_recv(&self) -> Option<ParamChange> {
+        if self.overflowed.swap(false, Ordering::AcqRel) {
+            while self.receiver.try_recv().is_ok() {}
+            return Some(ParamChange::All);
+        }
+
+        self.receiver.try_recv().ok()
+    }
+
+    /// Block until the next change arrives. Returns `None` once the plugin's wrapper has been
+    /// dropped and no more changes will be sent.
+    pub fn recv(&self) -> Option<ParamChange> {
+        let change = self.receiver.recv().ok()?;
+        if self.overflowed.swap(false, Ordering::AcqRel) {
+            while self.receiver.try_recv().is_ok() {}
+            return Some(ParamChange::All);
+        }
+
+        Some(change)
+    }
+
+    /// Iterate over all pending changes without blocking.
+    pub fn try_iter(&self) -> impl Iterator<Item = ParamChange> + '_ {
+        std::iter::from_fn(|| self.try_recv())
+    }
+
+    /// Discard all pending changes and return whether there were any. This is useful for editors
+    /// that only need to know whether they should redraw.
+    pub fn has_changed(&self) -> bool {
+        let mut changed = self.overflowed.swap(false, Ordering::AcqRel);
+        while self.receiver.try_recv().is_ok() {
+            changed = true;
+        }
+
+        changed
+    }
+}
+
+impl ParamChangeBroadcaster {
+    /// Create a new receiver that gets all changes broadcast from this point on.
+    pub fn subscribe(&self) -> ParamChangeReceiver {
+        let (sender, receiver) = channel::bounded(PARAM_CHANGE_QUEUE_CAPACITY);
+        let overflowed = Arc::new(AtomicBool::new(false));
+        self.subscribers.lock().push(Subscriber {
+            sender,
+            overflowed: overflowed.clone(),
+        });
+
+        ParamChangeReceiver {
+            receiver,
+            overflowed,
+        }
+    }
+
+    /// Send a change to all subscribers. `change` is only called if there are any subscribers, so
+    /// looking up the parameter's ID doesn't cost anything when nothing is listening. Receivers
+    /// that have been dropped are removed.
+    pub fn broadcast(&self, change: impl FnOnce() -> Option<ParamChange>) {
+        let mut subscribers = self.subscribers.lock();
+        if subscribers.is_empty() {
+            return;
+        }
+        let Some(change) = change() else {
+            return;
+        };
+
+        subscribers.retain(
+            |subscriber| match subscriber.sender.try_send(change.clone()) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_)) => {
+                    subscriber.overflowed.store(true, Ordering::Release);
+                    true
+                }
+                Err(TrySendError::Disconnected(_)) => false,
+            },
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value_change(id: &str) -> Option<ParamChange> {
+        Some(ParamChange::Value {
+            id: id.to_owned(),
+            normalized_value: 0.5,
+        })
+    }
+
+    #[test]
+    fn test_broadcast() {
+        let broadcaster = ParamChangeBroadcaster::default();
+        let first = broadcaster.subscribe();
+        let second = broadcaster.subscribe();
+        assert!(!first.has_changed());
+
+        broadcaster.broadcast(|| value_change("gain"));
+        assert_eq!(first.try_recv(), value_change("gain"));
+        assert_eq!(first.try_recv(), None);
+        assert!(second.has_changed());
+        assert!(!second.has_changed());
+    }
+
+    #[test]
+    fn test_unsubscribe() {
+        let broadcaster = ParamChangeBroadcaster::default();
+        let receiver = broadcaster.subscribe();
+        drop(receiver);
+
+        broadcaster.broadcast(|| value_change("gain"));
+        assert!(broadcaster.subscribers.lock().is_empty());
+
+        // Without subscribers the change is never created
+        broadcaster.broadcast(|| unreachable!());
+    }
+
+    #[test]
+    fn test_overflow() {
+        let broadcaster = ParamChangeBroadcaster::default();
+        let receiver = broadcaster.subscribe();
+        for _ in 0..PARAM_CHANGE_QUEUE_CAPACITY + 1 {
+            broadcaster.broadcast(|| value_change("gain"));
+        }
+
+        assert_eq!(receiver.try_recv(), Some(ParamChange::All));
+        assert_eq!(receiver.try_recv(), None);
+    }
+}
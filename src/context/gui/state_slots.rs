@@ -0,0 +1,111 @@
+//! A/B comparison slots for the plugin's state.
+
+use parking_lot::Mutex;
+
+use super::GuiContext;
+use crate::prelude::PluginState;
+
+/// One of the two state slots used for A/B comparisons. See
+/// [`GuiContext::swap_ab()`][super::GuiContext::swap_ab()].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum StateSlot {
+    #[default]
+    A,
+    B,
+}
+
+/// The A/B state slots backing [`GuiContext::store_state_slot()`], [`GuiContext::swap_ab()`], and
+/// [`GuiContext::copy_a_to_b()`]. Every wrapper owns one of these. Snapshots are taken and restored
+/// on the GUI thread using [`GuiContext::get_state()`] and [`GuiContext::set_state()`], so this
+/// never blocks the audio thread. Restoring a slot while the plugin is processing audio applies the
+/// state at the end of the current processing cycle.
+#[derive(Debug, Default)]
+pub(crate) struct StateSlots {
+    inner: Mutex<StateSlotsInner>,
+}
+
+#[derive(Debug, Default)]
+struct StateSlotsInner {
+    /// The slot the plugin's current state belongs to. Changes made to the plugin's state are
+    /// stored in this slot when switching to the other slot.
+    active: StateSlot,
+    a: Option<PluginState>,
+    b: Option<PluginState>,
+}
+
+impl StateSlot {
+    /// The slot that's not `self`.
+    pub fn other(self) -> Self {
+        match self {
+            StateSlot::A => StateSlot::B,
+            StateSlot::B => StateSlot::A,
+        }
+    }
+}
+
+impl StateSlotsInner {
+    fn slot_mut(&mut self, slot: StateSlot) -> &mut Option<PluginState> {
+        match slot {
+            StateSlot::A => &mut self.a,
+            StateSlot::B => &mut self.b,
+        }
+    }
+}
+
+impl StateSlots {
+    /// The slot the plugin's current state belongs to.
+    pub fn active(&self) -> StateSlot {
+        self.inner.lock().active
+    }
+
+    /// Store a snapshot of the plugin's current state in `slot`.
+    pub fn store(&self, context: &dyn GuiContext, slot: StateSlot) {
+        let state = context.get_state();
+        *self.inner.lock().slot_mut(slot) = Some(state);
+    }
+
+    /// Store the current state in the active slot, and then restore the other slot's state. If the
+    /// other slot is still empty, then it starts out as a copy of the current state. Returns the
+    /// newly active slot.
+    pub fn swap(&self, context: &dyn GuiContext) -> StateSlot {
+        let current_state = context.get_state();
+
+        let mut inner = self.inner.lock();
+        let old_slot = inner.active;
+        let new_slot = old_slot.other();
+        *inner.slot_mut(old_slot) = Some(current_state.clone());
+        inner.active = new_slot;
+        let new_state = inner
+            .slot_mut(new_slot)
+            .get_or_insert(current_state)
+            .clone();
+
+        // The lock should not be held while the wrapper restores the state
+        drop(inner);
+        context.set_state(new_state);
+
+        new_slot
+    }
+
+    /// Copy slot A's state to slot B. If A is the active slot or if it's still empty, then the
+    /// current state is stored in both slots. Otherwise A's state is restored since that is now
+    /// also B's state.
+    pub fn copy_a_to_b(&self, context: &dyn GuiContext) {
+        let stored_a = match self.active() {
+            StateSlot::A => None,
+            StateSlot::B => self.inner.lock().a.clone(),
+        };
+        let needs_restore = stored_a.is_some();
+        let state = stored_a.unwrap_or_else(|| context.get_state());
+
+        {
+            let mut inner = self.inner.lock();
+            inner.a = Some(state.clone());
+            inner.b = Some(state.clone());
+        }
+
+        if needs_restore {
+            context.set_state(state);
+        }
+    }
+}
@@ -1,7 +1,13 @@
 //! A context passed during the process function.
 
-use super::PluginApi;
-use crate::prelude::{Plugin, PluginNoteEvent};
+use std::sync::Arc;
+
+use super::{PluginApi, TrackInfo};
+use crate::prelude::{Param, ParamPtr, Plugin, PluginNoteEvent, ProcessMode};
+
+mod task_results;
+
+pub use task_results::TaskResults;
 
 /// Contains both context data and callbacks the plugin can use during processing. Most notably this
 /// is how a plugin sends and receives note events, gets transport information, and accesses
@@ -18,7 +24,9 @@ pub trait ProcessContext<P: Plugin> {
 
     /// Execute a task on a background thread using `[Plugin::task_executor]`. This allows you to
     /// defer expensive tasks for later without blocking either the process function or the GUI
-    /// thread. As long as creating the `task` is realtime-safe, this operation is too.
+    /// thread. As long as creating the `task` is realtime-safe, this operation is too. The task's
+    /// results can be sent back to the audio thread using a [`TaskResults`] channel, which makes
+    /// it possible to stream samples or load impulse responses while processing audio.
     ///
     /// # Note
     ///
@@ -37,9 +45,43 @@ pub trait ProcessContext<P: Plugin> {
     /// your task executor.
     fn execute_gui(&self, task: P::BackgroundTask);
 
+    /// Cancel all pending background tasks that were given the tag `tag` through
+    /// [`Plugin::background_task_options()`][crate::prelude::Plugin::background_task_options()].
+    /// Tasks that are already running are not affected. This is realtime-safe.
+    fn cancel_background_tasks(&self, tag: u32);
+
     /// Get information about the current transport position and status.
     fn transport(&self) -> &Transport;
 
+    /// Whether the host is currently rendering in realtime or offline, as reported through CLAP's
+    /// render extension or VST3's process setup. During offline rendering there is no deadline, so
+    /// the plugin can use more expensive algorithms. The standalone target always processes audio
+    /// in realtime. See also
+    /// [`Plugin::process_mode_changed()`][crate::prelude::Plugin::process_mode_changed()].
+    fn process_mode(&self) -> ProcessMode;
+
+    /// Information about the track the plugin is inserted on, if the host has provided it. This
+    /// uses CLAP's track info extension and VST3's `IInfoListener` interface, and it is always
+    /// `None` for standalone builds. Reading this is realtime-safe. See also
+    /// [`Plugin::track_info_changed()`][crate::prelude::Plugin::track_info_changed()].
+    fn track_info(&self) -> Option<Arc<TrackInfo>>;
+
+    /// Whether the plugin's bypass parameter is currently enabled. Always returns `false` if the
+    /// plugin does not have a bypass parameter. When
+    /// [`Plugin::MANAGED_BYPASS`][crate::prelude::Plugin::MANAGED_BYPASS] is enabled, the wrapper
+    /// crossfades the main output to the dry signal by itself, and this can be used to skip the
+    /// plugin's own processing while bypassed.
+    fn bypassed(&self) -> bool;
+
+    /// Whether the auxiliary input at `index` in
+    /// [`AudioIOLayout::aux_input_ports`][crate::prelude::AudioIOLayout::aux_input_ports] is
+    /// currently active. VST3 hosts can deactivate auxiliary inputs, for instance when nothing is
+    /// routed to a sidechain input. Deactivated inputs are passed to the plugin as silent buffers,
+    /// so a sidechain compressor can use this to skip its sidechain processing. The other plugin
+    /// APIs don't have a concept of inactive busses, so there this returns `true` for every
+    /// auxiliary input. Always returns `false` for out of bounds indices.
+    fn bus_active(&self, index: usize) -> bool;
+
     /// Returns the next note event, if there is one. Use
     /// [`NoteEvent::timing()`][crate::prelude::NoteEvent::timing()] to get the event's timing
     /// within the buffer. Only available when
@@ -80,10 +122,82 @@ pub trait ProcessContext<P: Plugin> {
     /// otherwise.
     fn send_event(&mut self, event: PluginNoteEvent<P>);
 
+    /// The same as [`next_event()`][Self::next_event()], but also returns the index of the note
+    /// input port in [`Plugin::MIDI_INPUT_PORTS`][crate::prelude::Plugin::MIDI_INPUT_PORTS] the
+    /// event was received on. Events for all ports are returned in order. Wrappers that only
+    /// support a single note port always return port 0.
+    fn next_event_with_port(&mut self) -> Option<(usize, PluginNoteEvent<P>)> {
+        self.next_event().map(|event| (0, event))
+    }
+
+    /// Schedule an event to be sent to the host at an absolute sample position, which may lie
+    /// beyond the end of the current block. The event's own timing is ignored. The event is held
+    /// on to by the wrapper and sent during the process call that contains that position, which is
+    /// useful for arpeggiators and MIDI delays. Positions are counted in samples since the plugin
+    /// was last reset, and [`sample_position()`][Self::sample_position()] returns the position of
+    /// the current block's first sample. Events scheduled in the past are sent at the start of the
+    /// current block. All scheduled events are dropped when the plugin is reset. This is
+    /// realtime-safe, and events scheduled while the wrapper's queue is full are dropped.
+    ///
+    /// Only the CLAP and VST3 wrappers currently support scheduling events. The other wrappers
+    /// drop these events.
+    fn send_event_at(&mut self, sample: u64, event: PluginNoteEvent<P>) {
+        let _ = (sample, event);
+        nih_debug_assert_failure!("This wrapper does not support scheduling output events");
+    }
+
+    /// The absolute position of the current block's first sample, counted in samples since the
+    /// plugin was last reset. See [`send_event_at()`][Self::send_event_at()]. Wrappers that don't
+    /// support scheduling events always return 0.
+    fn sample_position(&self) -> u64 {
+        0
+    }
+
+    /// The same as [`send_event()`][Self::send_event()], but sends the event to a specific note
+    /// output port in [`Plugin::MIDI_OUTPUT_PORTS`][crate::prelude::Plugin::MIDI_OUTPUT_PORTS].
+    /// Wrappers that only support a single note port will drop events for the other ports.
+    fn send_event_to_port(&mut self, port_index: usize, event: PluginNoteEvent<P>) {
+        if port_index == 0 {
+            self.send_event(event);
+        }
+    }
+
     /// Update the current latency of the plugin. If the plugin is currently processing audio, then
-    /// this may cause audio playback to be restarted.
+    /// this may cause audio playback to be restarted. This is realtime-safe, the host is informed
+    /// about the new latency from the main thread.
     fn set_latency_samples(&self, samples: u32);
 
+    /// Run `task` for every index in `0..count`. If the host provides a thread pool for audio
+    /// processing, then these tasks will be run in parallel on the host's worker threads. This
+    /// blocks until all tasks have finished. This can be used to spread expensive work like
+    /// per-channel convolution over multiple cores. If the host does not support this, then the
+    /// tasks are run sequentially on the audio thread. Only the CLAP wrapper currently supports
+    /// running tasks in parallel. Calling this again from within a task always runs the nested
+    /// tasks sequentially.
+    fn run_tasks_parallel(&self, count: usize, task: impl Fn(usize) + Sync) {
+        for task_index in 0..count {
+            task(task_index);
+        }
+    }
+
+    /// Get the frequency in Hz for a MIDI note on a MIDI channel. In CLAP hosts that support the
+    /// tuning extension this takes the host's tuning into account, so microtonal tunings set up in
+    /// the host also apply to the plugin. `tuning_id` selects one of the host's tunings by its CLAP
+    /// tuning ID, and `None` uses the first tuning the host provides. Without a host tuning this
+    /// returns 12-TET with A4 = 440 Hz. See [`Tuning`][crate::util::Tuning] for loading `.scl`
+    /// and `.kbm` files yourself. This is realtime-safe.
+    fn note_frequency(&self, note: u8, channel: u8, tuning_id: Option<u32>) -> f32;
+
+    /// Set the length of the plugin's tail, for instance a reverb's decay time or the length of a
+    /// delay's feedback. This lets the host keep processing the plugin after its input has gone
+    /// silent so the tail doesn't get cut off. Use `f32::INFINITY` if the plugin should never be
+    /// suspended, or 0.0 to remove the tail again. If the tail is longer than what is returned
+    /// through [`ProcessStatus::Tail`][crate::prelude::ProcessStatus::Tail], then this value is
+    /// reported to the host instead. See
+    /// [`DecayTracker`][crate::util::DecayTracker] for a way to handle tails automatically based on
+    /// the plugin's output.
+    fn set_tail_seconds(&self, seconds: f32);
+
     /// Set the current voice **capacity** for this plugin (so not the number of currently active
     /// voices). This may only be called if
     /// [`ClapPlugin::CLAP_POLY_MODULATION_CONFIG`][crate::prelude::ClapPlugin::CLAP_POLY_MODULATION_CONFIG]
@@ -92,10 +206,36 @@ pub trait ProcessContext<P: Plugin> {
     /// monophonic modulation when dropping the capacity down to 1.
     fn set_current_voice_capacity(&self, capacity: u32);
 
-    // TODO: Add this, this works similar to [GuiContext::set_parameter] but it adds the parameter
-    //       change to a queue (or directly to the VST3 plugin's parameter output queues) instead of
-    //       using main thread host automation (and all the locks involved there).
-    // fn set_parameter<P: Param>(&self, param: &P, value: P::Plain);
+    /// Handle all messages sent from the editor using `send_to_audio()` on its
+    /// [`GuiContext`][crate::prelude::GuiContext] since the last call to this function. The
+    /// messages are passed to `handler` in the order they were sent. This is realtime-safe. Messages
+    /// that are not of type `T` are discarded, so all messages should have the same type.
+    ///
+    /// ```ignore
+    /// context.drain_gui_messages(|message: EditorMessage| match message {
+    ///     EditorMessage::TapTempo => self.tap_tempo.tap(),
+    ///     EditorMessage::PreviewNote(note) => self.voices.preview(note),
+    /// });
+    /// ```
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T));
+
+    /// Change a parameter's value from the audio thread and send the change to the host, for
+    /// instance when an internal LFO or a step sequencer writes back to one of the plugin's
+    /// parameters. Hosts can record these changes as automation and display the parameter's
+    /// motion. `timing` is the change's sample index within the current block, just like for note
+    /// events, and changes should be sent in order. The parameter's value is updated after the
+    /// process function returns. This is realtime-safe.
+    ///
+    /// Only the CLAP wrapper currently supports this. The other wrappers ignore these changes.
+    fn set_parameter<T: Param>(&mut self, timing: u32, param: &T, value: T::Plain) {
+        self.raw_set_parameter_normalized(timing, param.as_ptr(), param.preview_normalized(value));
+    }
+
+    /// The same as [`set_parameter()`][Self::set_parameter()], but using an already normalized
+    /// value and a type erased parameter pointer.
+    fn raw_set_parameter_normalized(&mut self, timing: u32, param: ParamPtr, normalized: f32) {
+        let _ = (timing, param, normalized);
+    }
 }
 
 /// Information about the plugin's transport. Depending on the plugin API and the host not all
@@ -0,0 +1,89 @@
+//! A realtime-safe channel for sending the results of background tasks back to the audio thread.
+
+use crossbeam::queue::ArrayQueue;
+
+/// The default number of results that can be waiting to be handled by the audio thread.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// A bounded channel for sending the results of background tasks scheduled through
+/// [`ProcessContext::execute_background()`][super::ProcessContext::execute_background()] back to
+/// the audio thread. Store this in an `Arc` on your plugin struct and move a clone into the
+/// closure returned by [`Plugin::task_executor()`][crate::prelude::Plugin::task_executor()]. The
+/// task executor then sends its results using [`send()`][Self::send()], and the plugin handles them
+/// in its process function using [`drain()`][Self::drain()].
+///
+/// The results are moved through preallocated storage, so neither sending nor receiving results
+/// allocates. The audio thread does become responsible for the result's own heap data, like a
+/// loaded sample's [`Vec`]. If that data needs to be replaced later, then consider sending the old
+/// data back to a background task so it can be deallocated there.
+///
+/// ```ignore
+/// // In `Plugin::task_executor()`:
+/// let results = self.ir_results.clone();
+/// Box::new(move |task| match task {
+///     Task::LoadIr(path) => {
+///         let ir = load_ir(&path);
+///         if results.send(ir).is_err() {
+///             nih_log!("Dropping an impulse response, the audio thread is not keeping up");
+///         }
+///     }
+/// })
+///
+/// // In `Plugin::process()`:
+/// self.ir_results.drain(|ir| self.convolver.set_ir(ir));
+/// ```
+#[derive(Debug)]
+pub struct TaskResults<T> {
+    results: ArrayQueue<T>,
+}
+
+impl<T: Send> Default for TaskResults<T> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CAPACITY)
+    }
+}
+
+impl<T: Send> TaskResults<T> {
+    /// Create a channel that can hold up to `capacity` unhandled results.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            results: ArrayQueue::new(capacity.max(1)),
+        }
+    }
+
+    /// Send a result to the audio thread. Returns the result back if the channel is full.
+    pub fn send(&self, result: T) -> Result<(), T> {
+        self.results.push(result)
+    }
+
+    /// Call `handler` on every result that has been sent since the last call, in the order they
+    /// were sent. This is realtime-safe.
+    pub fn drain(&self, mut handler: impl FnMut(T)) {
+        while let Some(result) = self.results.pop() {
+            handler(result);
+        }
+    }
+
+    /// Whether there are any unhandled results.
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bounded_results() {
+        let results = TaskResults::new(2);
+        assert_eq!(results.send(1), Ok(()));
+        assert_eq!(results.send(2), Ok(()));
+        assert_eq!(results.send(3), Err(3));
+
+        let mut received = Vec::new();
+        results.drain(|result| received.push(result));
+        assert_eq!(received, [1, 2]);
+        assert!(results.is_empty());
+    }
+}
@@ -1,9 +1,9 @@
 //! Macros for logging and debug assertions. [`nih_dbg!()`], [`nih_trace!()`], and the
 //! `nih_debug_assert_*!()` macros are compiled out during release builds, so they can be used for
 //! asserting adiditonal invariants in debug builds. Check the [`nih_log!()`] macro for more
-//! information on NIH-plug's logger. None of the logging functions are realtime-safe, and you
-//! should avoid using them during release builds in any of the functions that may be called from an
-//! audio thread.
+//! information on NIH-plug's logger. Except for [`nih_log_rt!()`], none of the logging functions
+//! are realtime-safe, and you should avoid using them during release builds in any of the functions
+//! that may be called from an audio thread.
 
 // NOTE: Exporting macros in Rust is a bit weird. `#[macro_export]` causes them to be exported to
 //       the crate root, but that makes it difficult to include just the macros without using
@@ -49,6 +49,23 @@ macro_rules! nih_error {
 #[doc(inline)]
 pub use nih_error;
 
+/// A realtime-safe version of `nih_log!()` that can be used from the plugin's `process()` function.
+/// The message is formatted into a fixed size buffer without allocating or locking, and it is
+/// written to the logger shortly after by the plugin's background thread. Messages longer than
+/// [`MAX_MESSAGE_LEN`][crate::util::rt_log::MAX_MESSAGE_LEN] bytes are truncated. If the queue is
+/// full then the message is dropped, and the number of dropped messages is logged instead.
+///
+/// Formatting values is realtime-safe as long as their `Display` and `Debug` implementations don't
+/// allocate, which is the case for numbers and strings.
+#[macro_export]
+macro_rules! nih_log_rt {
+    ($($args:tt)*) => (
+        $crate::util::rt_log::log(::std::format_args!($($args)*))
+    );
+}
+#[doc(inline)]
+pub use nih_log_rt;
+
 /// The same as `nih_log!()`, but with source and thread information. Like the
 /// `nih_debug_assert*!()` macros, this is only shown when compiling in debug mode.
 #[macro_export]
@@ -0,0 +1,19 @@
+//! Small DSP building blocks for filtering, convolution, modulation, and dynamics processing.
+
+#[cfg(feature = "convolution")]
+mod convolver;
+mod envelope_follower;
+pub mod filter;
+mod lfo;
+mod limiter;
+
+#[cfg(feature = "convolution")]
+pub use convolver::{Convolver, ConvolverHandle};
+pub use envelope_follower::{EnvelopeFollower, EnvelopeMode};
+pub use lfo::{Lfo, LfoRate, LfoShape};
+pub use limiter::Limiter;
+
+/// States with an absolute value below this threshold are flushed to zero. Feedback loops decaying
+/// towards zero would otherwise end up producing denormals, which are very slow to process on most
+/// CPUs.
+const DENORMAL_THRESHOLD: f32 = 1.0e-30;
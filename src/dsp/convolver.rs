@@ -0,0 +1,428 @@
+//! Uniformly partitioned FFT convolution with realtime-safe impulse response swapping.
+
+use crossbeam::queue::ArrayQueue;
+use realfft::num_complex::Complex32;
+use realfft::{ComplexToReal, RealFftPlanner, RealToComplex};
+use std::sync::Arc;
+
+use crate::buffer::Buffer;
+use crate::context::init::InitContext;
+use crate::plugin::Plugin;
+
+/// Convolves audio with an impulse response using uniformly partitioned overlap-save convolution.
+/// This is the basis for convolution reverbs and cabinet simulations. The convolver outputs only
+/// the wet signal, and it introduces `partition_size` samples of latency. Smaller partitions result
+/// in lower latency at the cost of more CPU usage.
+///
+/// The convolver is created in [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] since
+/// creating it allocates memory. Impulse responses are loaded through a [`ConvolverHandle`] obtained
+/// with [`handle()`][Self::handle()], usually from a background task. Transforming an impulse
+/// response is done on the calling thread, after which it is handed over to the audio thread
+/// without locks. The convolver switches to the new impulse response at the start of its next
+/// partition and crossfades between the old and new impulse responses' outputs over that
+/// partition. Old impulse responses are deallocated by the handle, never on the audio thread.
+pub struct Convolver {
+    partition_size: usize,
+    /// The maximum number of partitions in an impulse response. This is also the length of the
+    /// channels' frequency domain delay lines.
+    max_partitions: usize,
+
+    r2c_plan: Arc<dyn RealToComplex<f32>>,
+    c2r_plan: Arc<dyn ComplexToReal<f32>>,
+    slot: Arc<IrSlot>,
+
+    /// The impulse response that's currently being used, if one has been loaded.
+    impulse_response: Option<Box<ImpulseResponse>>,
+    channels: Vec<ChannelState>,
+    /// The index in the channels' frequency domain delay lines containing the most recent input
+    /// partition's spectrum.
+    fdl_head: usize,
+    /// The current position within the partition that's being filled.
+    position: usize,
+
+    /// Scratch buffers for the FFTs. These are all preallocated.
+    time_buffer: Vec<f32>,
+    accumulator: Vec<Complex32>,
+    fade_buffer: Vec<f32>,
+    r2c_scratch: Vec<Complex32>,
+    c2r_scratch: Vec<Complex32>,
+}
+
+/// Loads impulse responses into a [`Convolver`]. This can be cloned and sent to other threads, and
+/// loading an impulse response is safe to do while the convolver is processing audio.
+#[derive(Clone)]
+pub struct ConvolverHandle {
+    slot: Arc<IrSlot>,
+}
+
+/// The lock-free handover between [`ConvolverHandle`]s and the [`Convolver`].
+struct IrSlot {
+    partition_size: usize,
+    max_partitions: usize,
+    r2c_plan: Arc<dyn RealToComplex<f32>>,
+
+    /// The next impulse response, waiting to be picked up by the audio thread.
+    pending: ArrayQueue<Box<ImpulseResponse>>,
+    /// Impulse responses that are no longer used by the audio thread. These are deallocated the
+    /// next time an impulse response is loaded.
+    retired: ArrayQueue<Box<ImpulseResponse>>,
+}
+
+/// An impulse response that has been split into partitions and transformed to the frequency domain.
+struct ImpulseResponse {
+    /// The partitions' spectra for every channel of the impulse response, already scaled to
+    /// compensate for the unnormalized FFTs.
+    channels: Vec<Vec<Vec<Complex32>>>,
+}
+
+struct ChannelState {
+    /// The previous partition's input followed by the partition that's currently being filled.
+    input: Vec<f32>,
+    /// The convolved output for the partition that's currently being played back.
+    output: Vec<f32>,
+    /// The spectra of the last `max_partitions` input partitions, as a ring buffer.
+    spectra: Vec<Vec<Complex32>>,
+}
+
+impl Convolver {
+    /// Create a convolver for `num_channels` channels. Impulse responses longer than
+    /// `max_ir_length` samples are truncated. `partition_size` is also the convolver's latency in
+    /// samples, and it should be a power of two.
+    pub fn new(num_channels: usize, partition_size: usize, max_ir_length: usize) -> Self {
+        nih_debug_assert!(partition_size > 0);
+        nih_debug_assert!(partition_size.is_power_of_two());
+
+        let fft_size = partition_size * 2;
+        let num_bins = partition_size + 1;
+        let max_partitions = ((max_ir_length + partition_size - 1) / partition_size).max(1);
+
+        let mut planner = RealFftPlanner::new();
+        let r2c_plan = planner.plan_fft_forward(fft_size);
+        let c2r_plan = planner.plan_fft_inverse(fft_size);
+
+        Self {
+            partition_size,
+            max_partitions,
+
+            r2c_scratch: r2c_plan.make_scratch_vec(),
+            c2r_scratch: c2r_plan.make_scratch_vec(),
+            slot: Arc::new(IrSlot {
+                partition_size,
+                max_partitions,
+                r2c_plan: r2c_plan.clone(),
+
+                pending: ArrayQueue::new(1),
+                retired: ArrayQueue::new(2),
+            }),
+            r2c_plan,
+            c2r_plan,
+
+            impulse_response: None,
+            channels: (0..num_channels)
+                .map(|_| ChannelState {
+                    input: vec![0.0; fft_size],
+                    output: vec![0.0; partition_size],
+                    spectra: vec![vec![Complex32::default(); num_bins]; max_partitions],
+                })
+                .collect(),
+            fdl_head: 0,
+            position: 0,
+
+            time_buffer: vec![0.0; fft_size],
+            accumulator: vec![Complex32::default(); num_bins],
+            fade_buffer: vec![0.0; partition_size],
+        }
+    }
+
+    /// Get a handle for loading impulse responses into this convolver.
+    pub fn handle(&self) -> ConvolverHandle {
+        ConvolverHandle {
+            slot: self.slot.clone(),
+        }
+    }
+
+    /// The latency introduced by the convolver in samples.
+    pub fn latency_samples(&self) -> u32 {
+        self.partition_size as u32
+    }
+
+    /// Report the convolver's latency to the host. Call this from
+    /// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] after creating the
+    /// convolver. If the plugin introduces additional latency, then it should instead add
+    /// [`latency_samples()`][Self::latency_samples()] to that latency and report the sum.
+    pub fn report_latency<P: Plugin>(&self, context: &impl InitContext<P>) {
+        context.set_latency_samples(self.latency_samples());
+    }
+
+    /// Clear the convolver's input history and pending output. Call this from
+    /// [`Plugin::reset()`][crate::prelude::Plugin::reset()]. The loaded impulse response is kept.
+    pub fn reset(&mut self) {
+        for channel in &mut self.channels {
+            channel.input.fill(0.0);
+            channel.output.fill(0.0);
+            for spectrum in &mut channel.spectra {
+                spectrum.fill(Complex32::default());
+            }
+        }
+        self.fdl_head = 0;
+        self.position = 0;
+    }
+
+    /// Replace the buffer's contents with the convolved signal. Channels beyond the number of
+    /// channels the convolver was created for are left untouched.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        let num_samples = buffer.samples();
+        let channels = buffer.as_slice();
+
+        let mut sample_idx = 0;
+        while sample_idx < num_samples {
+            let block_len = (self.partition_size - self.position).min(num_samples - sample_idx);
+            for (channel, state) in channels.iter_mut().zip(self.channels.iter_mut()) {
+                let samples = &mut channel[sample_idx..sample_idx + block_len];
+                state.input[self.partition_size + self.position..][..block_len]
+                    .copy_from_slice(samples);
+                samples.copy_from_slice(&state.output[self.position..][..block_len]);
+            }
+
+            sample_idx += block_len;
+            self.position += block_len;
+            if self.position == self.partition_size {
+                self.process_partition();
+                self.position = 0;
+            }
+        }
+    }
+
+    /// Convolve the partition that has just been filled, and store the results in the channels'
+    /// output buffers.
+    fn process_partition(&mut self) {
+        // The previous impulse response is faded out during this partition. Nothing is faded out
+        // when the first impulse response is loaded, since the output was silent before that.
+        let mut fading_impulse_response = None;
+        if let Some(impulse_response) = self.slot.pending.pop() {
+            fading_impulse_response = self.impulse_response.replace(impulse_response);
+        }
+
+        self.fdl_head = (self.fdl_head + 1) % self.max_partitions;
+        for (channel_idx, state) in self.channels.iter_mut().enumerate() {
+            self.time_buffer.copy_from_slice(&state.input);
+            self.r2c_plan
+                .process_with_scratch(
+                    &mut self.time_buffer,
+                    &mut state.spectra[self.fdl_head],
+                    &mut self.r2c_scratch,
+                )
+                .unwrap();
+            state.input.copy_within(self.partition_size.., 0);
+
+            let mut convolve = |impulse_response: Option<&ImpulseResponse>, output: &mut [f32]| {
+                convolve_partition(
+                    impulse_response,
+                    channel_idx,
+                    &state.spectra,
+                    self.fdl_head,
+                    &mut self.accumulator,
+                    &mut self.time_buffer,
+                    self.c2r_plan.as_ref(),
+                    &mut self.c2r_scratch,
+                    output,
+                )
+            };
+            convolve(self.impulse_response.as_deref(), &mut state.output);
+
+            if let Some(fading_impulse_response) = &fading_impulse_response {
+                convolve(Some(fading_impulse_response), &mut self.fade_buffer);
+
+                let fade_step = (self.partition_size as f32).recip();
+                for (i, (new, old)) in state
+                    .output
+                    .iter_mut()
+                    .zip(self.fade_buffer.iter())
+                    .enumerate()
+                {
+                    let t = (i + 1) as f32 * fade_step;
+                    *new = (*new * t) + (*old * (1.0 - t));
+                }
+            }
+        }
+
+        if let Some(fading_impulse_response) = fading_impulse_response {
+            // This can only fail if the handle hasn't been used to drain the queue, in which case
+            // deallocating on the audio thread is the only option left
+            if let Err(impulse_response) = self.slot.retired.push(fading_impulse_response) {
+                nih_debug_assert_failure!("The convolver's retired impulse response queue is full");
+                crate::util::permit_alloc(|| drop(impulse_response));
+            }
+        }
+    }
+}
+
+/// Convolve a channel's input history with an impulse response, and write the last partition's
+/// output to `output`. Writes silence if there is no impulse response.
+#[allow(clippy::too_many_arguments)]
+fn convolve_partition(
+    impulse_response: Option<&ImpulseResponse>,
+    channel_idx: usize,
+    spectra: &[Vec<Complex32>],
+    fdl_head: usize,
+    accumulator: &mut [Complex32],
+    time_buffer: &mut [f32],
+    c2r_plan: &dyn ComplexToReal<f32>,
+    c2r_scratch: &mut [Complex32],
+    output: &mut [f32],
+) {
+    let ir_channel = match impulse_response {
+        Some(impulse_response) if !impulse_response.channels.is_empty() => {
+            &impulse_response.channels[channel_idx % impulse_response.channels.len()]
+        }
+        _ => {
+            output.fill(0.0);
+            return;
+        }
+    };
+
+    accumulator.fill(Complex32::default());
+    for (partition_idx, ir_spectrum) in ir_channel.iter().enumerate() {
+        let input_spectrum = &spectra[(fdl_head + spectra.len() - partition_idx) % spectra.len()];
+        for ((acc, x), h) in accumulator
+            .iter_mut()
+            .zip(input_spectrum.iter())
+            .zip(ir_spectrum.iter())
+        {
+            *acc += x * h;
+        }
+    }
+
+    // These bins should already be real, but rounding errors would make the inverse FFT fail
+    accumulator[0].im = 0.0;
+    accumulator[accumulator.len() - 1].im = 0.0;
+    c2r_plan
+        .process_with_scratch(accumulator, time_buffer, c2r_scratch)
+        .unwrap();
+
+    // With overlap-save only the second half is free of circular convolution artifacts
+    output.copy_from_slice(&time_buffer[output.len()..]);
+}
+
+impl ConvolverHandle {
+    /// Load an impulse response. `channels` contains one slice per channel. A mono impulse
+    /// response is used for all of the convolver's channels, and otherwise the convolver's
+    /// channels are matched with the impulse response's channels in order. Impulse responses
+    /// longer than the `max_ir_length` passed to [`Convolver::new()`] are truncated.
+    ///
+    /// This allocates memory and performs FFTs, so it should be called from a background thread
+    /// or from the GUI, never from the audio thread. It does not block the audio thread.
+    pub fn set_impulse_response(&self, channels: &[&[f32]]) {
+        let partition_size = self.slot.partition_size;
+        let fft_size = partition_size * 2;
+        let scale = (fft_size as f32).recip();
+
+        let mut time_buffer = vec![0.0; fft_size];
+        let mut scratch = self.slot.r2c_plan.make_scratch_vec();
+        let channels = channels
+            .iter()
+            .map(|samples| {
+                samples
+                    .chunks(partition_size)
+                    .take(self.slot.max_partitions)
+                    .map(|partition| {
+                        time_buffer.fill(0.0);
+                        time_buffer[..partition.len()].copy_from_slice(partition);
+                        for sample in &mut time_buffer[..partition.len()] {
+                            *sample *= scale;
+                        }
+
+                        let mut spectrum = self.slot.r2c_plan.make_output_vec();
+                        self.slot
+                            .r2c_plan
+                            .process_with_scratch(&mut time_buffer, &mut spectrum, &mut scratch)
+                            .unwrap();
+
+                        spectrum
+                    })
+                    .collect()
+            })
+            .collect();
+
+        self.replace_pending(Box::new(ImpulseResponse { channels }));
+    }
+
+    /// Unload the impulse response, making the convolver output silence.
+    pub fn clear_impulse_response(&self) {
+        self.replace_pending(Box::new(ImpulseResponse {
+            channels: Vec::new(),
+        }));
+    }
+
+    fn replace_pending(&self, mut impulse_response: Box<ImpulseResponse>) {
+        // Old impulse responses are deallocated here so the audio thread never has to
+        while self.slot.retired.pop().is_some() {}
+
+        // If the audio thread hasn't picked up the previous impulse response yet, then that one is
+        // simply replaced
+        loop {
+            match self.slot.pending.push(impulse_response) {
+                Ok(()) => break,
+                Err(rejected) => {
+                    impulse_response = rejected;
+                    self.slot.pending.pop();
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::test_util::process_in_blocks;
+
+    #[test]
+    fn test_matches_direct_convolution() {
+        let partition_size = 8;
+        let impulse_response: Vec<f32> = (0..37).map(|i| ((i * 7) % 5) as f32 - 2.0).collect();
+        let signal: Vec<f32> = (0..200).map(|i| ((i * 3) % 11) as f32 / 11.0).collect();
+
+        let mut convolver = Convolver::new(1, partition_size, impulse_response.len());
+        convolver
+            .handle()
+            .set_impulse_response(&[&impulse_response]);
+        // Odd block sizes make sure partitions are assembled correctly across blocks
+        let output = process_in_blocks(&signal, 13, |buffer| convolver.process(buffer));
+
+        for (i, sample) in output.iter().enumerate().skip(partition_size) {
+            let expected: f32 = (0..=(i - partition_size))
+                .filter(|j| i - partition_size - j < impulse_response.len())
+                .map(|j| signal[j] * impulse_response[i - partition_size - j])
+                .sum();
+            approx::assert_relative_eq!(*sample, expected, epsilon = 1e-3);
+        }
+    }
+
+    #[test]
+    fn test_swap_impulse_response() {
+        let assert_samples_eq = |samples: &[f32], expected: f32| {
+            for sample in samples {
+                approx::assert_relative_eq!(*sample, expected, epsilon = 1e-6);
+            }
+        };
+
+        let mut convolver = Convolver::new(1, 4, 4);
+        let handle = convolver.handle();
+        handle.set_impulse_response(&[&[1.0]]);
+        let output = process_in_blocks(&[1.0; 16], 16, |buffer| convolver.process(buffer));
+        assert_samples_eq(&output[..4], 0.0);
+        assert_samples_eq(&output[4..], 1.0);
+
+        // The new impulse response is faded in over a single partition
+        handle.set_impulse_response(&[&[0.5]]);
+        let output = process_in_blocks(&[1.0; 16], 16, |buffer| convolver.process(buffer));
+        assert_samples_eq(&output[..4], 1.0);
+        assert_samples_eq(&output[4..5], 0.875);
+        assert_samples_eq(&output[8..], 0.5);
+
+        handle.clear_impulse_response();
+        let output = process_in_blocks(&[1.0; 16], 16, |buffer| convolver.process(buffer));
+        assert_samples_eq(&output[8..], 0.0);
+    }
+}
@@ -0,0 +1,170 @@
+//! An envelope follower with separate attack and release times.
+
+use super::DENORMAL_THRESHOLD;
+
+/// Whether an [`EnvelopeFollower`] follows the signal's peak level or its RMS level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EnvelopeMode {
+    /// Follows the absolute value of the signal.
+    Peak,
+    /// Follows the squared signal and outputs the square root of the result.
+    Rms,
+}
+
+/// A one pole envelope follower with separate attack and release times. The attack and release
+/// times are the time it takes for the envelope to reach roughly 63% of a step in the input level.
+/// Call [`set_sample_rate()`][Self::set_sample_rate()] from
+/// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] before using the envelope
+/// follower, and [`reset()`][Self::reset()] from [`Plugin::reset()`][crate::prelude::Plugin::reset()].
+#[derive(Debug, Clone)]
+pub struct EnvelopeFollower {
+    mode: EnvelopeMode,
+    attack_ms: f32,
+    release_ms: f32,
+
+    sample_rate: f32,
+    /// The one pole filter coefficient used while the input is above the envelope.
+    attack_coefficient: f32,
+    /// The one pole filter coefficient used while the input is below the envelope.
+    release_coefficient: f32,
+
+    /// The filter's state. For [`EnvelopeMode::Rms`] this is the squared envelope.
+    state: f32,
+}
+
+impl EnvelopeFollower {
+    /// Create a new envelope follower. The sample rate still needs to be set using
+    /// [`set_sample_rate()`][Self::set_sample_rate()].
+    pub fn new(mode: EnvelopeMode, attack_ms: f32, release_ms: f32) -> Self {
+        let mut follower = Self {
+            mode,
+            attack_ms,
+            release_ms,
+
+            sample_rate: 44_100.0,
+            attack_coefficient: 0.0,
+            release_coefficient: 0.0,
+
+            state: 0.0,
+        };
+        follower.update_coefficients();
+
+        follower
+    }
+
+    /// Set the sample rate and recompute the filter coefficients.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+
+        self.sample_rate = sample_rate;
+        self.update_coefficients();
+    }
+
+    /// Change the attack time in milliseconds. An attack time of zero makes the envelope jump to
+    /// the input level immediately.
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        self.attack_ms = attack_ms;
+        self.update_coefficients();
+    }
+
+    /// Change the release time in milliseconds. A release time of zero makes the envelope drop to
+    /// the input level immediately.
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms;
+        self.update_coefficients();
+    }
+
+    /// Change whether to follow the peak or the RMS level. This resets the envelope.
+    pub fn set_mode(&mut self, mode: EnvelopeMode) {
+        if mode != self.mode {
+            self.mode = mode;
+            self.reset();
+        }
+    }
+
+    /// Reset the envelope to zero.
+    pub fn reset(&mut self) {
+        self.state = 0.0;
+    }
+
+    /// The current envelope value.
+    pub fn value(&self) -> f32 {
+        match self.mode {
+            EnvelopeMode::Peak => self.state,
+            EnvelopeMode::Rms => self.state.sqrt(),
+        }
+    }
+
+    /// Process a single sample and return the new envelope value.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let input = match self.mode {
+            EnvelopeMode::Peak => sample.abs(),
+            EnvelopeMode::Rms => sample * sample,
+        };
+        let coefficient = if input > self.state {
+            self.attack_coefficient
+        } else {
+            self.release_coefficient
+        };
+
+        self.state = input + ((self.state - input) * coefficient);
+        if self.state < DENORMAL_THRESHOLD {
+            self.state = 0.0;
+        }
+
+        self.value()
+    }
+
+    /// Process a block of samples and return the envelope value after the last sample.
+    pub fn process_block(&mut self, samples: &[f32]) -> f32 {
+        for sample in samples {
+            self.process(*sample);
+        }
+
+        self.value()
+    }
+
+    fn update_coefficients(&mut self) {
+        self.attack_coefficient = Self::coefficient(self.attack_ms, self.sample_rate);
+        self.release_coefficient = Self::coefficient(self.release_ms, self.sample_rate);
+    }
+
+    fn coefficient(time_ms: f32, sample_rate: f32) -> f32 {
+        if time_ms > 0.0 {
+            (-1000.0 / (time_ms * sample_rate)).exp()
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_attack_time_constant() {
+        // After the attack time has passed the envelope should be at roughly 63% of the step
+        let mut follower = EnvelopeFollower::new(EnvelopeMode::Peak, 10.0, 100.0);
+        follower.set_sample_rate(48_000.0);
+        let value = follower.process_block(&[1.0; 480]);
+        approx::assert_relative_eq!(value, 1.0 - (-1.0f32).exp(), epsilon = 1.0e-3);
+    }
+
+    #[test]
+    fn test_rms() {
+        let mut follower = EnvelopeFollower::new(EnvelopeMode::Rms, 0.0, 0.0);
+        follower.set_sample_rate(48_000.0);
+        approx::assert_relative_eq!(follower.process(-0.5), 0.5);
+    }
+
+    #[test]
+    fn test_release_flushes_denormals() {
+        let mut follower = EnvelopeFollower::new(EnvelopeMode::Peak, 0.0, 1.0);
+        follower.set_sample_rate(48_000.0);
+        follower.process(1.0);
+        let value = follower.process_block(&[0.0; 48_000]);
+        assert_eq!(value, 0.0);
+    }
+}
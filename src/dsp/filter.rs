@@ -0,0 +1,10 @@
+//! Biquad and state variable filters, along with helpers for smoothly changing their coefficients
+//! and for evaluating their frequency responses.
+
+mod biquad;
+mod smoothing;
+mod svf;
+
+pub use biquad::{Biquad, BiquadCoefficients};
+pub use smoothing::{CoefficientSmoother, SmoothableCoefficients};
+pub use svf::{Svf, SvfCoefficients, SvfOutputs};
@@ -0,0 +1,355 @@
+//! Biquad filters using the RBJ Audio EQ Cookbook's coefficients.
+
+use std::f32::consts::{PI, TAU};
+
+use super::SmoothableCoefficients;
+use crate::dsp::DENORMAL_THRESHOLD;
+
+/// A biquad filter in the transposed direct form II. Set the filter's response by assigning new
+/// [`BiquadCoefficients`] to the `coefficients` field.
+///
+/// Based on <https://en.wikipedia.org/wiki/Digital_biquad_filter#Transposed_direct_forms>.
+#[derive(Debug, Clone, Copy)]
+pub struct Biquad {
+    pub coefficients: BiquadCoefficients,
+    s1: f32,
+    s2: f32,
+}
+
+/// The coefficients `[b0, b1, b2, a1, a2]` for a [`Biquad`]. These coefficients have already been
+/// divided by `a0`. The constructors compute the coefficients from the RBJ Audio EQ Cookbook.
+///
+/// Based on <http://shepazu.github.io/Audio-EQ-Cookbook/audio-eq-cookbook.html>.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BiquadCoefficients {
+    pub b0: f32,
+    pub b1: f32,
+    pub b2: f32,
+    pub a1: f32,
+    pub a2: f32,
+}
+
+impl Default for Biquad {
+    /// Before setting coefficients the filter should just act as an identity function.
+    fn default() -> Self {
+        Self::new(BiquadCoefficients::identity())
+    }
+}
+
+impl Biquad {
+    /// Create a new biquad filter with a set of coefficients.
+    pub fn new(coefficients: BiquadCoefficients) -> Self {
+        Self {
+            coefficients,
+            s1: 0.0,
+            s2: 0.0,
+        }
+    }
+
+    /// Process a single sample.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> f32 {
+        let result = self.coefficients.b0 * sample + self.s1;
+
+        self.s1 = self.coefficients.b1 * sample - self.coefficients.a1 * result + self.s2;
+        self.s2 = self.coefficients.b2 * sample - self.coefficients.a2 * result;
+        if self.s1.abs() < DENORMAL_THRESHOLD {
+            self.s1 = 0.0;
+        }
+        if self.s2.abs() < DENORMAL_THRESHOLD {
+            self.s2 = 0.0;
+        }
+
+        result
+    }
+
+    /// Process a block of samples in place.
+    pub fn process_block(&mut self, samples: &mut [f32]) {
+        for sample in samples {
+            *sample = self.process(*sample);
+        }
+    }
+
+    /// Reset the state to zero, useful after making large, non-interpolatable changes to the filter
+    /// coefficients.
+    pub fn reset(&mut self) {
+        self.s1 = 0.0;
+        self.s2 = 0.0;
+    }
+}
+
+impl BiquadCoefficients {
+    /// Filter coefficients that would cause the sound to be passed through as is.
+    pub fn identity() -> Self {
+        Self {
+            b0: 1.0,
+            b1: 0.0,
+            b2: 0.0,
+            a1: 0.0,
+            a2: 0.0,
+        }
+    }
+
+    /// Compute the coefficients for a low-pass filter.
+    pub fn lowpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+
+        let b1 = 1.0 - cos_omega0;
+        Self::normalize(
+            b1 / 2.0,
+            b1,
+            b1 / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_omega0,
+            1.0 - alpha,
+        )
+    }
+
+    /// Compute the coefficients for a high-pass filter.
+    pub fn highpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+
+        let b1 = -(1.0 + cos_omega0);
+        Self::normalize(
+            -b1 / 2.0,
+            b1,
+            -b1 / 2.0,
+            1.0 + alpha,
+            -2.0 * cos_omega0,
+            1.0 - alpha,
+        )
+    }
+
+    /// Compute the coefficients for a band-pass filter with a constant 0 dB peak gain.
+    pub fn bandpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+
+        Self::normalize(
+            alpha,
+            0.0,
+            -alpha,
+            1.0 + alpha,
+            -2.0 * cos_omega0,
+            1.0 - alpha,
+        )
+    }
+
+    /// Compute the coefficients for a notch filter.
+    pub fn notch(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+
+        Self::normalize(
+            1.0,
+            -2.0 * cos_omega0,
+            1.0,
+            1.0 + alpha,
+            -2.0 * cos_omega0,
+            1.0 - alpha,
+        )
+    }
+
+    /// Compute the coefficients for an all-pass filter.
+    pub fn allpass(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+
+        Self::normalize(
+            1.0 - alpha,
+            -2.0 * cos_omega0,
+            1.0 + alpha,
+            1.0 + alpha,
+            -2.0 * cos_omega0,
+            1.0 - alpha,
+        )
+    }
+
+    /// Compute the coefficients for a peaking EQ filter with a gain in decibel.
+    pub fn peaking(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+
+        Self::normalize(
+            1.0 + alpha * a,
+            -2.0 * cos_omega0,
+            1.0 - alpha * a,
+            1.0 + alpha / a,
+            -2.0 * cos_omega0,
+            1.0 - alpha / a,
+        )
+    }
+
+    /// Compute the coefficients for a low shelf filter with a gain in decibel. A `q` of
+    /// `1 / sqrt(2)` results in the steepest slope without overshoot.
+    pub fn low_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let sqrt_a_alpha_2 = 2.0 * a.sqrt() * alpha;
+
+        Self::normalize(
+            a * ((a + 1.0) - (a - 1.0) * cos_omega0 + sqrt_a_alpha_2),
+            2.0 * a * ((a - 1.0) - (a + 1.0) * cos_omega0),
+            a * ((a + 1.0) - (a - 1.0) * cos_omega0 - sqrt_a_alpha_2),
+            (a + 1.0) + (a - 1.0) * cos_omega0 + sqrt_a_alpha_2,
+            -2.0 * ((a - 1.0) + (a + 1.0) * cos_omega0),
+            (a + 1.0) + (a - 1.0) * cos_omega0 - sqrt_a_alpha_2,
+        )
+    }
+
+    /// Compute the coefficients for a high shelf filter with a gain in decibel. A `q` of
+    /// `1 / sqrt(2)` results in the steepest slope without overshoot.
+    pub fn high_shelf(sample_rate: f32, frequency: f32, q: f32, gain_db: f32) -> Self {
+        let (cos_omega0, alpha) = Self::omega_alpha(sample_rate, frequency, q);
+        let a = 10.0f32.powf(gain_db / 40.0);
+        let sqrt_a_alpha_2 = 2.0 * a.sqrt() * alpha;
+
+        Self::normalize(
+            a * ((a + 1.0) + (a - 1.0) * cos_omega0 + sqrt_a_alpha_2),
+            -2.0 * a * ((a - 1.0) + (a + 1.0) * cos_omega0),
+            a * ((a + 1.0) + (a - 1.0) * cos_omega0 - sqrt_a_alpha_2),
+            (a + 1.0) - (a - 1.0) * cos_omega0 + sqrt_a_alpha_2,
+            2.0 * ((a - 1.0) - (a + 1.0) * cos_omega0),
+            (a + 1.0) - (a - 1.0) * cos_omega0 - sqrt_a_alpha_2,
+        )
+    }
+
+    /// Compute the filter's magnitude response at a frequency in Hertz as a linear gain ratio.
+    pub fn magnitude(&self, sample_rate: f32, frequency: f32) -> f32 {
+        // This evaluates the transfer function at `z = e^(jw)`
+        let omega = 2.0 * PI * frequency / sample_rate;
+        let (sin_1, cos_1) = omega.sin_cos();
+        let (sin_2, cos_2) = (2.0 * omega).sin_cos();
+
+        let numerator_re = self.b0 + self.b1 * cos_1 + self.b2 * cos_2;
+        let numerator_im = self.b1 * sin_1 + self.b2 * sin_2;
+        let denominator_re = 1.0 + self.a1 * cos_1 + self.a2 * cos_2;
+        let denominator_im = self.a1 * sin_1 + self.a2 * sin_2;
+
+        ((numerator_re * numerator_re + numerator_im * numerator_im)
+            / (denominator_re * denominator_re + denominator_im * denominator_im))
+            .sqrt()
+    }
+
+    /// Compute the filter's magnitude response at a frequency in Hertz in decibel.
+    pub fn magnitude_db(&self, sample_rate: f32, frequency: f32) -> f32 {
+        crate::util::gain_to_db(self.magnitude(sample_rate, frequency))
+    }
+
+    /// The cosine of the normalized angular frequency and the RBJ cookbook's alpha term.
+    fn omega_alpha(sample_rate: f32, frequency: f32, q: f32) -> (f32, f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(frequency > 0.0);
+        nih_debug_assert!(frequency < sample_rate / 2.0);
+        nih_debug_assert!(q > 0.0);
+
+        let omega0 = TAU * (frequency / sample_rate);
+        let (sin_omega0, cos_omega0) = omega0.sin_cos();
+
+        (cos_omega0, sin_omega0 / (2.0 * q))
+    }
+
+    /// Divide all coefficients by `a0`.
+    fn normalize(b0: f32, b1: f32, b2: f32, a0: f32, a1: f32, a2: f32) -> Self {
+        Self {
+            b0: b0 / a0,
+            b1: b1 / a0,
+            b2: b2 / a0,
+            a1: a1 / a0,
+            a2: a2 / a0,
+        }
+    }
+}
+
+impl SmoothableCoefficients for BiquadCoefficients {
+    /// Linearly interpolating between two stable biquads' coefficients always results in another
+    /// stable filter, since the set of stable `(a1, a2)` pairs is convex.
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        Self {
+            b0: self.b0 + (target.b0 - self.b0) * t,
+            b1: self.b1 + (target.b1 - self.b1) * t,
+            b2: self.b2 + (target.b2 - self.b2) * t,
+            a1: self.a1 + (target.a1 - self.a1) * t,
+            a2: self.a2 + (target.a2 - self.a2) * t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    #[test]
+    fn test_lowpass_response() {
+        let coefficients = BiquadCoefficients::lowpass(SAMPLE_RATE, 1_000.0, 1.0);
+        approx::assert_relative_eq!(
+            coefficients.magnitude(SAMPLE_RATE, 1.0),
+            1.0,
+            epsilon = 1e-4
+        );
+        approx::assert_relative_eq!(
+            coefficients.magnitude(SAMPLE_RATE, 1_000.0),
+            1.0,
+            epsilon = 1e-4
+        );
+        assert!(coefficients.magnitude(SAMPLE_RATE, 10_000.0) < 0.02);
+    }
+
+    #[test]
+    fn test_peaking_gain() {
+        let coefficients = BiquadCoefficients::peaking(SAMPLE_RATE, 2_000.0, 0.7, 6.0);
+        approx::assert_relative_eq!(
+            coefficients.magnitude_db(SAMPLE_RATE, 2_000.0),
+            6.0,
+            epsilon = 1e-3
+        );
+        approx::assert_relative_eq!(
+            coefficients.magnitude_db(SAMPLE_RATE, 20.0),
+            0.0,
+            epsilon = 1e-2
+        );
+    }
+
+    #[test]
+    fn test_shelves() {
+        let low_shelf = BiquadCoefficients::low_shelf(SAMPLE_RATE, 200.0, 0.707, -12.0);
+        approx::assert_relative_eq!(
+            low_shelf.magnitude_db(SAMPLE_RATE, 10.0),
+            -12.0,
+            epsilon = 1e-2
+        );
+        approx::assert_relative_eq!(
+            low_shelf.magnitude_db(SAMPLE_RATE, 15_000.0),
+            0.0,
+            epsilon = 1e-2
+        );
+
+        let high_shelf = BiquadCoefficients::high_shelf(SAMPLE_RATE, 5_000.0, 0.707, 12.0);
+        approx::assert_relative_eq!(
+            high_shelf.magnitude_db(SAMPLE_RATE, 20.0),
+            0.0,
+            epsilon = 1e-2
+        );
+        approx::assert_relative_eq!(
+            high_shelf.magnitude_db(SAMPLE_RATE, 23_000.0),
+            12.0,
+            epsilon = 0.1
+        );
+    }
+
+    #[test]
+    fn test_filter_matches_response() {
+        // A sine wave at the notch frequency should be almost entirely removed once the filter has
+        // settled
+        let frequency = 1_000.0;
+        let mut filter = Biquad::new(BiquadCoefficients::notch(SAMPLE_RATE, frequency, 2.0));
+        let mut samples: Vec<f32> = (0..4800)
+            .map(|i| (TAU * frequency * i as f32 / SAMPLE_RATE).sin())
+            .collect();
+        filter.process_block(&mut samples);
+
+        let peak = samples[2400..]
+            .iter()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+        assert!(peak < 0.01, "{peak}");
+    }
+}
@@ -0,0 +1,132 @@
+//! Smoothing for filter coefficients.
+
+/// Filter coefficients that can be linearly interpolated by a [`CoefficientSmoother`].
+pub trait SmoothableCoefficients: Copy {
+    /// Interpolate between `self` and `target`, where `t` is in `[0, 1]`.
+    fn lerp(&self, target: &Self, t: f32) -> Self;
+}
+
+/// Linearly interpolates between the old and the new coefficients over a number of samples. This
+/// avoids zipper noise when a filter's parameters change, without having to recompute the
+/// coefficients for every sample. Interpolating the coefficients does not result in the same
+/// response as interpolating the filter's frequency, but it's usually close enough for short
+/// smoothing times.
+///
+/// ```
+/// # use nih_plug::dsp::filter::{Biquad, BiquadCoefficients, CoefficientSmoother};
+/// let mut filter = Biquad::default();
+/// let mut smoother = CoefficientSmoother::new(BiquadCoefficients::lowpass(44_100.0, 1_000.0, 0.7));
+///
+/// // Once per block, or whenever the parameters change
+/// smoother.set_target(BiquadCoefficients::lowpass(44_100.0, 2_000.0, 0.7), 64);
+///
+/// // Once per sample
+/// filter.coefficients = smoother.next_value();
+/// let output = filter.process(0.0);
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct CoefficientSmoother<C: SmoothableCoefficients> {
+    /// The coefficients at the start of the current ramp.
+    start: C,
+    /// The coefficients at the end of the current ramp.
+    target: C,
+    /// The most recently computed coefficients.
+    current: C,
+
+    /// The ramp's length in samples.
+    num_steps: u32,
+    /// The number of steps left in the current ramp.
+    steps_left: u32,
+}
+
+impl<C: SmoothableCoefficients> CoefficientSmoother<C> {
+    /// Create a smoother that starts out at `coefficients`.
+    pub fn new(coefficients: C) -> Self {
+        Self {
+            start: coefficients,
+            target: coefficients,
+            current: coefficients,
+
+            num_steps: 0,
+            steps_left: 0,
+        }
+    }
+
+    /// Start interpolating from the current coefficients to `target` over `num_steps` samples. If
+    /// `num_steps` is zero, then the coefficients are changed immediately.
+    pub fn set_target(&mut self, target: C, num_steps: u32) {
+        if num_steps == 0 {
+            self.reset(target);
+            return;
+        }
+
+        self.start = self.current;
+        self.target = target;
+        self.num_steps = num_steps;
+        self.steps_left = num_steps;
+    }
+
+    /// Jump to `coefficients` immediately without any smoothing.
+    pub fn reset(&mut self, coefficients: C) {
+        self.start = coefficients;
+        self.target = coefficients;
+        self.current = coefficients;
+        self.num_steps = 0;
+        self.steps_left = 0;
+    }
+
+    /// Advance the smoother by one sample and return the coefficients to use for that sample.
+    #[inline]
+    pub fn next_value(&mut self) -> C {
+        if self.steps_left > 0 {
+            self.steps_left -= 1;
+            self.current = if self.steps_left == 0 {
+                self.target
+            } else {
+                let t = (self.num_steps - self.steps_left) as f32 / self.num_steps as f32;
+                self.start.lerp(&self.target, t)
+            };
+        }
+
+        self.current
+    }
+
+    /// The most recently returned coefficients.
+    pub fn current(&self) -> C {
+        self.current
+    }
+
+    /// The coefficients the smoother is moving towards.
+    pub fn target(&self) -> C {
+        self.target
+    }
+
+    /// Whether the coefficients are still changing.
+    pub fn is_smoothing(&self) -> bool {
+        self.steps_left > 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dsp::filter::BiquadCoefficients;
+
+    #[test]
+    fn test_reaches_target() {
+        let start = BiquadCoefficients::identity();
+        let target = BiquadCoefficients::lowpass(48_000.0, 1_000.0, 0.7);
+        let mut smoother = CoefficientSmoother::new(start);
+        smoother.set_target(target, 4);
+
+        smoother.next_value();
+        let halfway = smoother.next_value();
+        approx::assert_relative_eq!(halfway.b0, start.lerp(&target, 0.5).b0);
+        assert!(smoother.is_smoothing());
+
+        smoother.next_value();
+        assert_eq!(smoother.next_value(), target);
+        assert!(!smoother.is_smoothing());
+        assert_eq!(smoother.next_value(), target);
+    }
+}
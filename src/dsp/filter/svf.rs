@@ -0,0 +1,220 @@
+//! A topology-preserving transform state variable filter.
+
+use std::f32::consts::PI;
+
+use super::SmoothableCoefficients;
+use crate::dsp::DENORMAL_THRESHOLD;
+
+/// A trapezoidal integrated state variable filter that computes low-pass, band-pass, and high-pass
+/// outputs at the same time. Unlike a biquad, this filter behaves well when its frequency is
+/// modulated at audio rates. Set the filter's response by assigning new [`SvfCoefficients`] to the
+/// `coefficients` field.
+///
+/// Based on Andrew Simper's "Linear Trap Integrated State Variable Filter" paper,
+/// <https://cytomic.com/files/dsp/SvfLinearTrapOptimised2.pdf>.
+#[derive(Debug, Clone, Copy)]
+pub struct Svf {
+    pub coefficients: SvfCoefficients,
+    ic1eq: f32,
+    ic2eq: f32,
+}
+
+/// The coefficients for an [`Svf`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvfCoefficients {
+    /// The prewarped cutoff frequency, `tan(pi * frequency / sample_rate)`.
+    g: f32,
+    /// The damping, `1 / q`.
+    k: f32,
+
+    a1: f32,
+    a2: f32,
+    a3: f32,
+}
+
+/// All of an [`Svf`]'s outputs for a single sample.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SvfOutputs {
+    pub lowpass: f32,
+    pub bandpass: f32,
+    pub highpass: f32,
+}
+
+impl Default for Svf {
+    fn default() -> Self {
+        Self::new(SvfCoefficients::new(
+            44_100.0,
+            1_000.0,
+            std::f32::consts::FRAC_1_SQRT_2,
+        ))
+    }
+}
+
+impl Svf {
+    /// Create a new state variable filter with a set of coefficients.
+    pub fn new(coefficients: SvfCoefficients) -> Self {
+        Self {
+            coefficients,
+            ic1eq: 0.0,
+            ic2eq: 0.0,
+        }
+    }
+
+    /// Process a single sample and return all of the filter's outputs.
+    #[inline]
+    pub fn process(&mut self, sample: f32) -> SvfOutputs {
+        let SvfCoefficients { k, a1, a2, a3, .. } = self.coefficients;
+
+        let v3 = sample - self.ic2eq;
+        let v1 = a1 * self.ic1eq + a2 * v3;
+        let v2 = self.ic2eq + a2 * self.ic1eq + a3 * v3;
+        self.ic1eq = 2.0 * v1 - self.ic1eq;
+        self.ic2eq = 2.0 * v2 - self.ic2eq;
+        if self.ic1eq.abs() < DENORMAL_THRESHOLD {
+            self.ic1eq = 0.0;
+        }
+        if self.ic2eq.abs() < DENORMAL_THRESHOLD {
+            self.ic2eq = 0.0;
+        }
+
+        SvfOutputs {
+            lowpass: v2,
+            bandpass: v1,
+            highpass: sample - k * v1 - v2,
+        }
+    }
+
+    /// Reset the filter's state to zero.
+    pub fn reset(&mut self) {
+        self.ic1eq = 0.0;
+        self.ic2eq = 0.0;
+    }
+}
+
+impl SvfCoefficients {
+    /// Compute the coefficients for a cutoff or center frequency in Hertz and a resonance `q`.
+    pub fn new(sample_rate: f32, frequency: f32, q: f32) -> Self {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(frequency > 0.0);
+        nih_debug_assert!(frequency < sample_rate / 2.0);
+        nih_debug_assert!(q > 0.0);
+
+        Self::from_g_k((PI * frequency / sample_rate).tan(), q.recip())
+    }
+
+    fn from_g_k(g: f32, k: f32) -> Self {
+        let a1 = (1.0 + g * (g + k)).recip();
+        let a2 = g * a1;
+        let a3 = g * a2;
+
+        Self { g, k, a1, a2, a3 }
+    }
+
+    /// Compute the magnitude response of one of the filter's outputs at a frequency in Hertz as a
+    /// linear gain ratio. `output` selects the output, for instance `|outputs| outputs.lowpass`.
+    /// Outputs can also be mixed, so `|outputs| outputs.lowpass + outputs.highpass` results in the
+    /// response of a notch filter.
+    pub fn magnitude(
+        &self,
+        sample_rate: f32,
+        frequency: f32,
+        output: impl Fn(SvfOutputs) -> f32,
+    ) -> f32 {
+        // The filter is linear, so its transfer functions can be evaluated by applying `output` to
+        // the transfer functions' numerators. With `s = j * tan(pi * f / fs) / g` the analog
+        // prototype's transfer functions are `s^2 / d`, `s / d`, and `1 / d` with
+        // `d = s^2 + k * s + 1`. Those numerators are complex, so they're evaluated separately for
+        // the real and imaginary parts.
+        let w = (PI * frequency / sample_rate).tan() / self.g;
+        let numerator_re = output(SvfOutputs {
+            lowpass: 1.0,
+            bandpass: 0.0,
+            highpass: -w * w,
+        });
+        let numerator_im = output(SvfOutputs {
+            lowpass: 0.0,
+            bandpass: w,
+            highpass: 0.0,
+        });
+        let denominator_re = 1.0 - w * w;
+        let denominator_im = self.k * w;
+
+        ((numerator_re * numerator_re + numerator_im * numerator_im)
+            / (denominator_re * denominator_re + denominator_im * denominator_im))
+            .sqrt()
+    }
+}
+
+impl SmoothableCoefficients for SvfCoefficients {
+    /// Interpolates the prewarped frequency and the damping, and then recomputes the other
+    /// coefficients. The filter stays stable throughout the ramp.
+    fn lerp(&self, target: &Self, t: f32) -> Self {
+        Self::from_g_k(
+            self.g + (target.g - self.g) * t,
+            self.k + (target.k - self.k) * t,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE_RATE: f32 = 48_000.0;
+
+    /// The filter's measured gain for a sine wave at `frequency` for one of the outputs. This
+    /// compares the output's RMS level to the input's once the filter has settled, so `frequency`
+    /// should fit an integer number of periods in half a second.
+    fn measured_gain(
+        coefficients: SvfCoefficients,
+        frequency: f32,
+        output: impl Fn(SvfOutputs) -> f32,
+    ) -> f32 {
+        let mut filter = Svf::new(coefficients);
+        let num_samples = SAMPLE_RATE as usize / 2;
+        let sum_squares: f32 =
+            (0..(num_samples * 2))
+                .map(|i| {
+                    output(filter.process(
+                        (std::f32::consts::TAU * frequency * i as f32 / SAMPLE_RATE).sin(),
+                    ))
+                })
+                .skip(num_samples)
+                .map(|sample| sample * sample)
+                .sum();
+
+        (sum_squares / num_samples as f32).sqrt() * std::f32::consts::SQRT_2
+    }
+
+    #[test]
+    fn test_outputs_at_cutoff() {
+        let coefficients = SvfCoefficients::new(SAMPLE_RATE, 1_000.0, 2.0);
+        // At the cutoff frequency all three outputs have a gain equal to the filter's Q
+        let outputs: [fn(SvfOutputs) -> f32; 3] = [|o| o.lowpass, |o| o.bandpass, |o| o.highpass];
+        for output in outputs {
+            approx::assert_relative_eq!(
+                coefficients.magnitude(SAMPLE_RATE, 1_000.0, output),
+                2.0,
+                epsilon = 1e-4
+            );
+            approx::assert_relative_eq!(
+                measured_gain(coefficients, 1_000.0, output),
+                2.0,
+                epsilon = 1e-2
+            );
+        }
+    }
+
+    #[test]
+    fn test_response_matches_filter() {
+        let coefficients = SvfCoefficients::new(SAMPLE_RATE, 500.0, 0.7);
+        let notch = |o: SvfOutputs| o.lowpass + o.highpass;
+        for frequency in [100.0, 500.0, 4_000.0] {
+            approx::assert_relative_eq!(
+                coefficients.magnitude(SAMPLE_RATE, frequency, notch),
+                measured_gain(coefficients, frequency, notch),
+                epsilon = 1e-2
+            );
+        }
+    }
+}
@@ -0,0 +1,254 @@
+//! A low frequency oscillator that can be synced to the host's tempo.
+
+use serde::{Deserialize, Serialize};
+use std::f32::consts::TAU;
+
+use crate::context::process::Transport;
+
+/// The waveform of an [`Lfo`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LfoShape {
+    Sine,
+    Triangle,
+    Saw,
+    Square,
+}
+
+/// How fast an [`Lfo`] oscillates.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LfoRate {
+    /// A free running rate in Hertz.
+    Hertz(f32),
+    /// A period synced to the host's tempo, in quarter notes. For instance, `4.0` means one cycle
+    /// per 4/4 bar and `0.5` means one cycle per eighth note.
+    Beats(f64),
+}
+
+/// A low frequency oscillator with a couple of basic shapes. The oscillator outputs values in
+/// `[-1, 1]`. Call [`set_sample_rate()`][Self::set_sample_rate()] from
+/// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] before using the LFO, and
+/// [`sync()`][Self::sync()] at the start of every process call so tempo synced LFOs follow the
+/// host's tempo and position.
+///
+/// When the LFO is tempo synced and the host reports the song position, the LFO's phase is derived
+/// from that position so it stays locked to the song even when the playhead jumps around. Free
+/// running LFOs restart their cycle when playback starts.
+#[derive(Debug, Clone)]
+pub struct Lfo {
+    shape: LfoShape,
+    rate: LfoRate,
+
+    sample_rate: f32,
+    /// The host's tempo during the last [`sync()`][Self::sync()], used for tempo synced rates.
+    tempo: f64,
+    /// Whether the transport was playing during the last [`sync()`][Self::sync()].
+    was_playing: bool,
+
+    /// The phase in `[0, 1)`.
+    phase: f32,
+    /// The amount `phase` is incremented by every sample.
+    phase_delta: f32,
+}
+
+impl LfoShape {
+    /// All available shapes, useful for building a selection menu in a GUI.
+    pub const ALL: [LfoShape; 4] = [
+        LfoShape::Sine,
+        LfoShape::Triangle,
+        LfoShape::Saw,
+        LfoShape::Square,
+    ];
+
+    /// The waveform's value in `[-1, 1]` at a phase in `[0, 1)`.
+    #[inline]
+    pub fn value(self, phase: f32) -> f32 {
+        match self {
+            LfoShape::Sine => (phase * TAU).sin(),
+            LfoShape::Triangle => 1.0 - (4.0 * (phase - 0.5).abs()),
+            LfoShape::Saw => (2.0 * phase) - 1.0,
+            LfoShape::Square => {
+                if phase < 0.5 {
+                    1.0
+                } else {
+                    -1.0
+                }
+            }
+        }
+    }
+
+    /// A human readable name for the shape.
+    pub fn name(self) -> &'static str {
+        match self {
+            LfoShape::Sine => "Sine",
+            LfoShape::Triangle => "Triangle",
+            LfoShape::Saw => "Saw",
+            LfoShape::Square => "Square",
+        }
+    }
+}
+
+impl Lfo {
+    /// Create a new LFO. The sample rate still needs to be set using
+    /// [`set_sample_rate()`][Self::set_sample_rate()].
+    pub fn new(shape: LfoShape, rate: LfoRate) -> Self {
+        let mut lfo = Self {
+            shape,
+            rate,
+
+            sample_rate: 44_100.0,
+            tempo: 120.0,
+            was_playing: false,
+
+            phase: 0.0,
+            phase_delta: 0.0,
+        };
+        lfo.update_phase_delta();
+
+        lfo
+    }
+
+    /// Set the sample rate.
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        nih_debug_assert!(sample_rate > 0.0);
+
+        self.sample_rate = sample_rate;
+        self.update_phase_delta();
+    }
+
+    /// Change the LFO's shape.
+    pub fn set_shape(&mut self, shape: LfoShape) {
+        self.shape = shape;
+    }
+
+    /// Change the LFO's rate.
+    pub fn set_rate(&mut self, rate: LfoRate) {
+        self.rate = rate;
+        self.update_phase_delta();
+    }
+
+    /// Restart the LFO's cycle from the start.
+    pub fn reset(&mut self) {
+        self.retrigger(0.0);
+    }
+
+    /// Jump to a phase in `[0, 1)`.
+    pub fn retrigger(&mut self, phase: f32) {
+        self.phase = phase.rem_euclid(1.0);
+    }
+
+    /// The LFO's current phase in `[0, 1)`.
+    pub fn phase(&self) -> f32 {
+        self.phase
+    }
+
+    /// Update the LFO's tempo and phase from the host's transport. Call this at the start of every
+    /// process call, or at the start of every block when processing audio in smaller blocks.
+    pub fn sync(&mut self, transport: &Transport) {
+        if let Some(tempo) = transport.tempo {
+            if tempo > 0.0 && tempo != self.tempo {
+                self.tempo = tempo;
+                self.update_phase_delta();
+            }
+        }
+
+        match (self.rate, transport.pos_beats()) {
+            (LfoRate::Beats(beats), Some(pos_beats)) if transport.playing && beats > 0.0 => {
+                self.phase = (pos_beats / beats).rem_euclid(1.0) as f32;
+            }
+            _ => {
+                if transport.playing && !self.was_playing {
+                    self.reset();
+                }
+            }
+        }
+        self.was_playing = transport.playing;
+    }
+
+    /// The LFO's value at the current phase, without advancing the LFO.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.shape.value(self.phase)
+    }
+
+    /// Return the LFO's value and advance it by one sample.
+    #[inline]
+    pub fn next_value(&mut self) -> f32 {
+        let value = self.value();
+
+        self.phase += self.phase_delta;
+        if self.phase >= 1.0 {
+            self.phase -= self.phase.floor();
+        }
+
+        value
+    }
+
+    /// Advance the LFO by a number of samples without computing its values.
+    pub fn skip(&mut self, num_samples: usize) {
+        self.phase = (self.phase + (self.phase_delta * num_samples as f32)).rem_euclid(1.0);
+    }
+
+    fn update_phase_delta(&mut self) {
+        let frequency = match self.rate {
+            LfoRate::Hertz(frequency) => frequency,
+            LfoRate::Beats(beats) if beats > 0.0 => (self.tempo / 60.0 / beats) as f32,
+            LfoRate::Beats(_) => 0.0,
+        };
+
+        // Negative rates are not supported since the phase only wraps around in one direction
+        self.phase_delta = (frequency / self.sample_rate).max(0.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shapes() {
+        approx::assert_relative_eq!(LfoShape::Sine.value(0.25), 1.0);
+        approx::assert_relative_eq!(LfoShape::Triangle.value(0.0), -1.0);
+        approx::assert_relative_eq!(LfoShape::Triangle.value(0.5), 1.0);
+        approx::assert_relative_eq!(LfoShape::Saw.value(0.75), 0.5);
+        approx::assert_relative_eq!(LfoShape::Square.value(0.75), -1.0);
+    }
+
+    #[test]
+    fn test_free_running() {
+        let mut lfo = Lfo::new(LfoShape::Saw, LfoRate::Hertz(1.0));
+        lfo.set_sample_rate(4.0);
+        let values: Vec<f32> = (0..5).map(|_| lfo.next_value()).collect();
+        assert_eq!(values, [-1.0, -0.5, 0.0, 0.5, -1.0]);
+    }
+
+    #[test]
+    fn test_tempo_sync() {
+        let mut lfo = Lfo::new(LfoShape::Saw, LfoRate::Beats(4.0));
+        lfo.set_sample_rate(48_000.0);
+
+        let mut transport = Transport::new(48_000.0);
+        transport.playing = true;
+        transport.tempo = Some(120.0);
+        transport.pos_beats = Some(5.0);
+        lfo.sync(&transport);
+        approx::assert_relative_eq!(lfo.phase(), 0.25);
+
+        // At 120 BPM a bar takes two seconds
+        lfo.skip(48_000);
+        approx::assert_relative_eq!(lfo.phase(), 0.75, epsilon = 1.0e-4);
+    }
+
+    #[test]
+    fn test_retrigger_on_transport_start() {
+        let mut lfo = Lfo::new(LfoShape::Sine, LfoRate::Hertz(3.0));
+        lfo.set_sample_rate(48_000.0);
+        lfo.skip(1000);
+        assert_ne!(lfo.phase(), 0.0);
+
+        let mut transport = Transport::new(48_000.0);
+        transport.playing = true;
+        lfo.sync(&transport);
+        assert_eq!(lfo.phase(), 0.0);
+    }
+}
@@ -0,0 +1,329 @@
+//! A lookahead brickwall limiter.
+
+use atomic_float::AtomicF32;
+use std::collections::VecDeque;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::buffer::Buffer;
+use crate::context::init::InitContext;
+use crate::plugin::Plugin;
+use crate::util;
+
+/// A brickwall limiter with lookahead. The limiter delays the signal by the lookahead time so it
+/// can start reducing the gain before a peak arrives, which means that the output never exceeds
+/// the threshold. The gain reduction is faded in linearly over the attack time, which can be at
+/// most as long as the lookahead time, and it's faded out exponentially over the release time.
+///
+/// The limiter is created in [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] since
+/// creating it allocates memory. The gain reduction is linked between all channels.
+///
+/// The most recent gain reduction can be displayed in the editor through
+/// [`gain_reduction_meter()`][Self::gain_reduction_meter()].
+pub struct Limiter {
+    sample_rate: f32,
+    /// The threshold as a linear gain value.
+    threshold: f32,
+    lookahead_ms: f32,
+    attack_ms: f32,
+    release_ms: f32,
+
+    /// The signal delay in samples. The gain for an input sample is applied this many samples later.
+    lookahead_samples: usize,
+    /// The length of the linear gain reduction ramp in samples. This is at least one sample, and at
+    /// most `lookahead_samples + 1` samples.
+    attack_samples: usize,
+    /// The one pole filter coefficient used when the gain recovers.
+    release_coefficient: f32,
+
+    max_lookahead_samples: usize,
+    /// The delay lines for each channel. These are all `max_lookahead_samples + 1` samples long.
+    delay_lines: Vec<Vec<f32>>,
+    /// The position in the delay lines the next sample will be written to.
+    write_pos: usize,
+
+    /// Counts the processed samples. Used to expire old entries from `min_window`.
+    sample_idx: usize,
+    /// Pairs of sample indices and the gain those samples need to stay below the threshold, used
+    /// to compute the minimum over the lookahead window. Both the indices and the gains are in
+    /// ascending order. This has a fixed capacity and never allocates during processing.
+    min_window: VecDeque<(usize, f32)>,
+    /// The held minimum gains from the last `attack_samples` samples, used to compute the moving
+    /// average that turns the held gain into a linear ramp.
+    attack_window: Vec<f32>,
+    attack_window_pos: usize,
+    /// The sum of `attack_window`. This is accumulated in double precision to avoid drift.
+    attack_window_sum: f64,
+
+    /// The gain that was applied to the last sample.
+    gain: f32,
+    /// The lowest gain that was applied during the last [`process()`][Self::process()] call, as a
+    /// linear gain value.
+    gain_reduction_meter: Arc<AtomicF32>,
+}
+
+impl Limiter {
+    /// Create a new limiter for `num_channels` channels. The lookahead time can later be changed
+    /// to any value up to `max_lookahead_ms`. The limiter starts out with a threshold of 0 dBFS, a
+    /// lookahead and attack time of `max_lookahead_ms`, and a release time of 100 milliseconds.
+    pub fn new(num_channels: usize, sample_rate: f32, max_lookahead_ms: f32) -> Self {
+        nih_debug_assert!(sample_rate > 0.0);
+        nih_debug_assert!(max_lookahead_ms >= 0.0);
+
+        let max_lookahead_samples = Self::ms_to_samples(max_lookahead_ms.max(0.0), sample_rate);
+        let mut limiter = Self {
+            sample_rate,
+            threshold: 1.0,
+            lookahead_ms: max_lookahead_ms,
+            attack_ms: max_lookahead_ms,
+            release_ms: 100.0,
+
+            lookahead_samples: 0,
+            attack_samples: 1,
+            release_coefficient: 0.0,
+
+            max_lookahead_samples,
+            delay_lines: vec![vec![0.0; max_lookahead_samples + 1]; num_channels],
+            write_pos: 0,
+
+            sample_idx: 0,
+            min_window: VecDeque::with_capacity(max_lookahead_samples + 2),
+            attack_window: vec![1.0; max_lookahead_samples + 1],
+            attack_window_pos: 0,
+            attack_window_sum: 0.0,
+
+            gain: 1.0,
+            gain_reduction_meter: Arc::new(AtomicF32::new(1.0)),
+        };
+        limiter.update_times();
+        limiter.reset();
+
+        limiter
+    }
+
+    /// A shared value containing the lowest gain the limiter applied during the last processed
+    /// block, as a linear gain value. This is `1.0` when the limiter isn't reducing the gain. The
+    /// value can be converted using [`util::gain_to_db()`] and displayed with the GUI crates' peak
+    /// meter widgets.
+    pub fn gain_reduction_meter(&self) -> Arc<AtomicF32> {
+        self.gain_reduction_meter.clone()
+    }
+
+    /// The limiter's latency in samples. This is equal to the lookahead time.
+    pub fn latency_samples(&self) -> u32 {
+        self.lookahead_samples as u32
+    }
+
+    /// Report the limiter's latency to the host. Call this from
+    /// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] after creating the limiter.
+    /// If the plugin introduces additional latency, then it should instead add
+    /// [`latency_samples()`][Self::latency_samples()] to that latency and report the sum. When the
+    /// lookahead time is changed during processing, the new latency can be reported through
+    /// [`ProcessContext::set_latency_samples()`][crate::prelude::ProcessContext::set_latency_samples()].
+    pub fn report_latency<P: Plugin>(&self, context: &impl InitContext<P>) {
+        context.set_latency_samples(self.latency_samples());
+    }
+
+    /// Set the level the output should never exceed, in decibels.
+    pub fn set_threshold_db(&mut self, threshold_db: f32) {
+        self.threshold = util::db_to_gain(threshold_db);
+    }
+
+    /// Change the lookahead time in milliseconds. This is clamped to the maximum lookahead time
+    /// the limiter was created with. Changing the lookahead time changes the limiter's latency and
+    /// resets the limiter, so this should not be automated.
+    pub fn set_lookahead_ms(&mut self, lookahead_ms: f32) {
+        if lookahead_ms != self.lookahead_ms {
+            self.lookahead_ms = lookahead_ms;
+            self.update_times();
+            self.reset();
+        }
+    }
+
+    /// Change the attack time in milliseconds. The attack time is limited to the lookahead time.
+    pub fn set_attack_ms(&mut self, attack_ms: f32) {
+        if attack_ms != self.attack_ms {
+            self.attack_ms = attack_ms;
+            self.update_times();
+            self.reset_attack_window();
+        }
+    }
+
+    /// Change the release time in milliseconds.
+    pub fn set_release_ms(&mut self, release_ms: f32) {
+        self.release_ms = release_ms;
+        self.update_times();
+    }
+
+    /// Clear the limiter's delay lines and gain reduction. Call this from
+    /// [`Plugin::reset()`][crate::prelude::Plugin::reset()].
+    pub fn reset(&mut self) {
+        for delay_line in &mut self.delay_lines {
+            delay_line.fill(0.0);
+        }
+        self.write_pos = 0;
+
+        self.sample_idx = 0;
+        self.min_window.clear();
+        self.reset_attack_window();
+
+        self.gain = 1.0;
+        self.gain_reduction_meter.store(1.0, Ordering::Relaxed);
+    }
+
+    /// Limit the buffer's audio in place. The buffer should have the same number of channels the
+    /// limiter was created with.
+    pub fn process(&mut self, buffer: &mut Buffer) {
+        nih_debug_assert_eq!(buffer.channels(), self.delay_lines.len());
+
+        let delay_line_len = self.max_lookahead_samples + 1;
+        let read_offset = delay_line_len - self.lookahead_samples;
+        let mut min_gain = 1.0f32;
+        for mut channel_samples in buffer.iter_samples() {
+            let mut peak = 0.0f32;
+            for sample in channel_samples.iter_mut() {
+                peak = peak.max(sample.abs());
+            }
+
+            let required_gain = if peak > self.threshold {
+                self.threshold / peak
+            } else {
+                1.0
+            };
+            let gain = self.next_gain(required_gain);
+            min_gain = min_gain.min(gain);
+
+            let read_pos = (self.write_pos + read_offset) % delay_line_len;
+            for (sample, delay_line) in channel_samples.iter_mut().zip(self.delay_lines.iter_mut())
+            {
+                delay_line[self.write_pos] = *sample;
+                *sample = delay_line[read_pos] * gain;
+            }
+
+            self.write_pos = (self.write_pos + 1) % delay_line_len;
+        }
+
+        self.gain_reduction_meter.store(min_gain, Ordering::Relaxed);
+    }
+
+    /// Compute the gain for the sample leaving the delay lines, given the gain required for the
+    /// sample entering them.
+    fn next_gain(&mut self, required_gain: f32) -> f32 {
+        // The minimum required gain over the last `lookahead_samples + 1` samples is held so the
+        // gain reduction stays active until the peak has left the delay lines
+        let window_len = self.lookahead_samples + 1;
+        while let Some(&(_, gain)) = self.min_window.back() {
+            if gain < required_gain {
+                break;
+            }
+            self.min_window.pop_back();
+        }
+        self.min_window.push_back((self.sample_idx, required_gain));
+        while let Some(&(idx, _)) = self.min_window.front() {
+            if self.sample_idx.wrapping_sub(idx) < window_len {
+                break;
+            }
+            self.min_window.pop_front();
+        }
+        let held_gain = self.min_window[0].1;
+        self.sample_idx = self.sample_idx.wrapping_add(1);
+
+        // Averaging the held gain over the attack time turns steps into linear ramps. Since the
+        // attack time is at most the lookahead time, the ramp reaches a peak's required gain by the
+        // time that peak leaves the delay lines.
+        self.attack_window_sum += (held_gain - self.attack_window[self.attack_window_pos]) as f64;
+        self.attack_window[self.attack_window_pos] = held_gain;
+        self.attack_window_pos = (self.attack_window_pos + 1) % self.attack_samples;
+        let target_gain = (self.attack_window_sum / self.attack_samples as f64) as f32;
+
+        // Reducing the gain always happens immediately, only recovering is smoothed
+        self.gain = if target_gain < self.gain {
+            target_gain
+        } else {
+            target_gain + ((self.gain - target_gain) * self.release_coefficient)
+        };
+
+        self.gain
+    }
+
+    /// Fill the attack window with the lowest gain needed for the samples in the delay lines. This
+    /// keeps the output below the threshold when the attack time changes during processing.
+    fn reset_attack_window(&mut self) {
+        let held_gain = self.min_window.front().map_or(1.0, |(_, gain)| *gain);
+        self.attack_window.fill(held_gain);
+        self.attack_window_pos = 0;
+        self.attack_window_sum = held_gain as f64 * self.attack_samples as f64;
+    }
+
+    fn update_times(&mut self) {
+        self.lookahead_samples = Self::ms_to_samples(self.lookahead_ms.max(0.0), self.sample_rate)
+            .min(self.max_lookahead_samples);
+        self.attack_samples = Self::ms_to_samples(self.attack_ms.max(0.0), self.sample_rate)
+            .clamp(1, self.lookahead_samples + 1);
+        self.release_coefficient = if self.release_ms > 0.0 {
+            (-1000.0 / (self.release_ms * self.sample_rate)).exp()
+        } else {
+            0.0
+        };
+    }
+
+    fn ms_to_samples(time_ms: f32, sample_rate: f32) -> usize {
+        (time_ms / 1000.0 * sample_rate).round() as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::test_util::process_in_blocks;
+
+    #[test]
+    fn test_output_stays_below_threshold() {
+        let mut limiter = Limiter::new(1, 1_000.0, 10.0);
+        limiter.set_threshold_db(-6.0);
+        limiter.set_attack_ms(5.0);
+        limiter.set_release_ms(20.0);
+        let signal: Vec<f32> = (0..500)
+            .map(|i| ((i * 37) % 23) as f32 / 5.0 - 2.0)
+            .collect();
+        let output = process_in_blocks(&signal, 7, |buffer| limiter.process(buffer));
+
+        let threshold = util::db_to_gain(-6.0);
+        for sample in output {
+            assert!(sample.abs() <= threshold * 1.0001);
+        }
+        assert!(limiter.gain_reduction_meter().load(Ordering::Relaxed) < 1.0);
+    }
+
+    #[test]
+    fn test_quiet_signal_is_delayed() {
+        let mut limiter = Limiter::new(1, 1_000.0, 4.0);
+        assert_eq!(limiter.latency_samples(), 4);
+
+        let mut signal = vec![0.0; 16];
+        signal[0] = 0.5;
+        let output = process_in_blocks(&signal, 16, |buffer| limiter.process(buffer));
+        assert_eq!(output[4], 0.5);
+        assert_eq!(output.iter().filter(|sample| **sample != 0.0).count(), 1);
+        assert_eq!(limiter.gain_reduction_meter().load(Ordering::Relaxed), 1.0);
+    }
+
+    #[test]
+    fn test_gain_ramps_before_peak() {
+        let mut limiter = Limiter::new(1, 1_000.0, 4.0);
+        limiter.set_release_ms(0.0);
+        let mut signal = vec![1.0; 16];
+        signal[8] = 2.0;
+        let output = process_in_blocks(&signal, 16, |buffer| limiter.process(buffer));
+
+        // The four samples of latency are followed by a four sample ramp towards the peak's
+        // required gain, and the same ramp back up afterwards
+        assert!(output[..4].iter().all(|sample| *sample == 0.0));
+        let expected = [
+            1.0, 1.0, 1.0, 1.0, 0.875, 0.75, 0.625, 0.5, 1.0, 0.625, 0.75, 0.875,
+        ];
+        for (sample, expected) in output[4..].iter().zip(expected) {
+            approx::assert_relative_eq!(*sample, expected, epsilon = 1e-6);
+        }
+    }
+}
@@ -1,8 +1,10 @@
 //! Traits for working with plugin editors.
 
+use atomic_float::AtomicF32;
 use raw_window_handle::{HasRawWindowHandle, RawWindowHandle};
 use std::any::Any;
 use std::ffi::c_void;
+use std::sync::atomic::Ordering;
 use std::sync::Arc;
 
 use crate::prelude::GuiContext;
@@ -40,11 +42,54 @@ pub trait Editor: Send {
         context: Arc<dyn GuiContext>,
     ) -> Box<dyn Any + Send>;
 
+    /// Whether the editor can be opened in its own top-level window using
+    /// [`spawn_floating()`][Self::spawn_floating()]. Some hosts, like hosts running natively on
+    /// Wayland, can only show plugin editors in floating windows. Currently only the CLAP wrapper
+    /// uses floating windows.
+    fn supports_floating_window(&self) -> bool {
+        false
+    }
+
+    /// Create an instance of the plugin's editor in a new top-level window instead of embedding it
+    /// in a window provided by the host. This is only called when
+    /// [`supports_floating_window()`][Self::supports_floating_window()] returns `true`. `title` is
+    /// the window title suggested by the host. If `transient_parent` is set, then the window
+    /// should be kept on top of that host window. The returned handle works the same way as the
+    /// one returned from [`spawn()`][Self::spawn()]. Dropping it should close the window. Return
+    /// `None` if the window could not be opened.
+    fn spawn_floating(
+        &self,
+        _title: &str,
+        _transient_parent: Option<ParentWindowHandle>,
+        _context: Arc<dyn GuiContext>,
+    ) -> Option<Box<dyn Any + Send>> {
+        None
+    }
+
     /// Returns the (current) size of the editor in pixels as a `(width, height)` pair. This size
     /// must be reported in _logical pixels_, i.e. the size before being multiplied by the DPI
     /// scaling factor to get the actual physical screen pixels.
     fn size(&self) -> (u32, u32);
 
+    /// Returns the constraints for resizing the editor, or `None` if the editor cannot be resized
+    /// by the user. If this returns a value, then the host will allow the user to resize the
+    /// editor window within these constraints and it will call [`set_size()`][Self::set_size()]
+    /// with the new size.
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        None
+    }
+
+    /// Called by the wrapper when the host resizes the editor window. The size is specified in
+    /// logical pixels, and it has already been constrained using
+    /// [`size_constraints()`][Self::size_constraints()]. If the editor is open, then it should
+    /// resize its window to match this size. [`size()`][Self::size()] should return the new size
+    /// after this function has returned `true`. Return `false` to reject the new size.
+    ///
+    /// This is only called when [`size_constraints()`][Self::size_constraints()] returns a value.
+    fn set_size(&self, _width: u32, _height: u32) -> bool {
+        false
+    }
+
     /// Set the DPI scaling factor, if supported. The plugin APIs don't make any guarantees on when
     /// this is called, but for now just assume it will be the first function that gets called
     /// before creating the editor. If this is set, then any windows created by this editor should
@@ -54,6 +99,23 @@ pub trait Editor: Send {
     /// there.
     fn set_scale_factor(&self, factor: f32) -> bool;
 
+    /// Returns the UI scale chosen by the user, if any. This overrides the DPI scaling factor
+    /// reported by the host, which is useful on platforms and in hosts that don't report a scaling
+    /// factor. The wrapper passes this value to [`set_scale_factor()`][Self::set_scale_factor()]
+    /// in place of the host's scaling factor. Editors should store this value in their persisted
+    /// state so it gets restored together with the plugin's state.
+    fn ui_scale(&self) -> Option<f32> {
+        None
+    }
+
+    /// Store a new UI scale chosen by the user, or `None` to use the host's scaling factor again.
+    /// This is called by the wrapper as part of
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()]. Return `false`
+    /// if the editor does not support overriding the scaling factor.
+    fn set_ui_scale(&self, _scale: Option<f32>) -> bool {
+        false
+    }
+
     /// Called whenever a specific parameter's value has changed while the editor is open. You don't
     /// need to do anything with this, but this can be used to force a redraw when the host sends a
     /// new value for a parameter or when a parameter change sent to the host gets processed.
@@ -73,9 +135,184 @@ pub trait Editor: Send {
     //       and API agnostic, add a way to ask the GuiContext if the wrapper already provides a
     //       tick function. If it does not, then the Editor implementation must handle this by
     //       itself. This would also need an associated `PREFERRED_FRAME_RATE` constant.
-    // TODO: Host->Plugin resizing
 }
 
+/// A minimal editor interface for plugins that do their own window management and rendering, for
+/// instance using a custom Vulkan renderer. Unlike [`Editor`], this only deals with the parent
+/// window's [`RawWindowHandle`], so it does not depend on baseview or any of NIH-plug's GUI
+/// adapters. Wrap the editor in a [`RawWindowEditorAdapter`] to return it from
+/// [`Plugin::editor()`][crate::prelude::Plugin::editor()]. The adapter takes care of the DPI
+/// scaling factor and forwards parameter changes and resize requests.
+pub trait RawWindowEditor: Send + Sync + 'static {
+    /// Create a child window for the editor in `parent`. `size` is the editor's size in physical
+    /// pixels, and `scale_factor` is the DPI scaling factor that was used to compute that size.
+    /// Parameters can be changed through `context`. The returned handle is dropped when the editor
+    /// gets closed, so it should close the window in its [`Drop`] implementation.
+    fn open(
+        &self,
+        parent: RawWindowHandle,
+        size: (u32, u32),
+        scale_factor: f32,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn Any + Send>;
+
+    /// Returns the editor's current size in logical pixels. See [`Editor::size()`].
+    fn size(&self) -> (u32, u32);
+
+    /// Returns the constraints for resizing the editor, or `None` if the editor cannot be resized.
+    /// See [`Editor::size_constraints()`].
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        None
+    }
+
+    /// Called when the host resizes the editor. The size is in logical pixels. Return `false` to
+    /// reject the new size. See [`Editor::set_size()`].
+    fn set_size(&self, _width: u32, _height: u32) -> bool {
+        false
+    }
+
+    /// Called when a parameter's value has changed while the editor is open.
+    fn param_value_changed(&self, _id: &str, _normalized_value: f32) {}
+
+    /// Called when a parameter's monophonic modulation value has changed while the editor is open.
+    fn param_modulation_changed(&self, _id: &str, _modulation_offset: f32) {}
+
+    /// Called when one or more parameter values have changed at the same time, for instance
+    /// because a preset was loaded.
+    fn param_values_changed(&self) {}
+}
+
+/// Implements [`Editor`] for a [`RawWindowEditor`].
+pub struct RawWindowEditorAdapter<E: RawWindowEditor> {
+    editor: Arc<E>,
+    /// The DPI scaling factor set by the host. Defaults to 1.0.
+    scale_factor: AtomicF32,
+}
+
+impl<E: RawWindowEditor> RawWindowEditorAdapter<E> {
+    /// Wrap a [`RawWindowEditor`] so it can be returned from
+    /// [`Plugin::editor()`][crate::prelude::Plugin::editor()].
+    pub fn new(editor: E) -> Self {
+        Self {
+            editor: Arc::new(editor),
+            scale_factor: AtomicF32::new(1.0),
+        }
+    }
+
+    /// Get a reference to the wrapped editor.
+    pub fn editor(&self) -> &Arc<E> {
+        &self.editor
+    }
+}
+
+impl<E: RawWindowEditor> Editor for RawWindowEditorAdapter<E> {
+    fn spawn(
+        &self,
+        parent: ParentWindowHandle,
+        context: Arc<dyn GuiContext>,
+    ) -> Box<dyn Any + Send> {
+        let scale_factor = self.scale_factor.load(Ordering::Relaxed);
+        let (width, height) = self.editor.size();
+        let physical_size = (
+            (width as f32 * scale_factor).round() as u32,
+            (height as f32 * scale_factor).round() as u32,
+        );
+
+        self.editor.open(
+            parent.raw_window_handle(),
+            physical_size,
+            scale_factor,
+            context,
+        )
+    }
+
+    fn size(&self) -> (u32, u32) {
+        self.editor.size()
+    }
+
+    fn size_constraints(&self) -> Option<EditorSizeConstraints> {
+        self.editor.size_constraints()
+    }
+
+    fn set_size(&self, width: u32, height: u32) -> bool {
+        self.editor.set_size(width, height)
+    }
+
+    fn set_scale_factor(&self, factor: f32) -> bool {
+        self.scale_factor.store(factor, Ordering::Relaxed);
+        true
+    }
+
+    fn param_value_changed(&self, id: &str, normalized_value: f32) {
+        self.editor.param_value_changed(id, normalized_value)
+    }
+
+    fn param_modulation_changed(&self, id: &str, modulation_offset: f32) {
+        self.editor.param_modulation_changed(id, modulation_offset)
+    }
+
+    fn param_values_changed(&self) {
+        self.editor.param_values_changed()
+    }
+}
+
+/// Constraints for user resizable editors. See [`Editor::size_constraints()`]. All sizes are in
+/// logical pixels.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct EditorSizeConstraints {
+    /// The minimum `(width, height)` for the editor.
+    pub min_size: (u32, u32),
+    /// The maximum `(width, height)` for the editor.
+    pub max_size: (u32, u32),
+    /// If set, the editor's `width / height` ratio must always match this ratio.
+    pub aspect_ratio: Option<f32>,
+}
+
+impl EditorSizeConstraints {
+    /// Constraints for an editor that can be resized freely between `min_size` and `max_size`.
+    pub fn new(min_size: (u32, u32), max_size: (u32, u32)) -> Self {
+        nih_debug_assert!(min_size.0 <= max_size.0 && min_size.1 <= max_size.1);
+
+        Self {
+            min_size,
+            max_size,
+            aspect_ratio: None,
+        }
+    }
+
+    /// Require the editor to have a fixed `width / height` aspect ratio.
+    pub fn with_aspect_ratio(mut self, aspect_ratio: f32) -> Self {
+        nih_debug_assert!(aspect_ratio > 0.0);
+
+        self.aspect_ratio = Some(aspect_ratio);
+        self
+    }
+
+    /// Adjust a `(width, height)` size so it fits within these constraints. When the aspect ratio
+    /// is fixed, the width is kept and the height is derived from it, unless that would violate
+    /// the minimum or maximum size.
+    pub fn constrain(&self, (width, height): (u32, u32)) -> (u32, u32) {
+        let width = width.clamp(self.min_size.0, self.max_size.0);
+        let height = height.clamp(self.min_size.1, self.max_size.1);
+
+        match self.aspect_ratio {
+            Some(aspect_ratio) => {
+                let height = ((width as f32 / aspect_ratio).round() as u32)
+                    .clamp(self.min_size.1, self.max_size.1);
+                let width = ((height as f32 * aspect_ratio).round() as u32)
+                    .clamp(self.min_size.0, self.max_size.0);
+
+                (width, height)
+            }
+            None => (width, height),
+        }
+    }
+}
+
+// TODO: There is no Wayland variant because Wayland does not support embedding windows from other
+//       clients, and baseview, which all of NIH-plug's GUI adapters use, does not have a Wayland
+//       backend. The CLAP wrapper does offer floating windows to Wayland hosts, but none of the
+//       GUI adapters implement `Editor::spawn_floating()` yet.
 /// A raw window handle for platform and GUI framework agnostic editors. This implements
 /// [`HasRawWindowHandle`] so it can be used directly with GUI libraries that use the same
 /// [`raw_window_handle`] version. If the library links against a different version of
@@ -88,6 +325,9 @@ pub enum ParentWindowHandle {
     AppKitNsView(*mut c_void),
     /// A handle to the host's parent window. Used only on Windows.
     Win32Hwnd(*mut c_void),
+    /// The ID of an HTML canvas element with a matching `data-raw-handle` attribute. Used only
+    /// when the plugin is exported as a Web Audio Module.
+    WebCanvas(u32),
 }
 
 unsafe impl HasRawWindowHandle for ParentWindowHandle {
@@ -108,6 +348,11 @@ unsafe impl HasRawWindowHandle for ParentWindowHandle {
                 handle.hwnd = hwnd;
                 RawWindowHandle::Win32(handle)
             }
+            ParentWindowHandle::WebCanvas(id) => {
+                let mut handle = raw_window_handle::WebWindowHandle::empty();
+                handle.id = id;
+                RawWindowHandle::Web(handle)
+            }
         }
     }
 }
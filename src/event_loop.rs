@@ -1,17 +1,31 @@
 //! An internal event loop for spooling tasks to the/a GUI thread.
 
 use std::sync::Weak;
+use std::time::Duration;
 
+use crate::prelude::TaskOptions;
+
+// WebAssembly instances cannot spawn threads, so the WebAssembly event loop doesn't use these
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
 mod background_thread;
+#[cfg_attr(target_arch = "wasm32", allow(dead_code))]
+mod timer_thread;
 
 #[cfg(all(target_family = "unix", not(target_os = "macos")))]
 mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
 #[cfg(target_os = "windows")]
 mod windows;
 
+#[cfg_attr(target_arch = "wasm32", allow(unused_imports))]
 pub(crate) use self::background_thread::BackgroundThread;
+pub(crate) use self::timer_thread::TimerCallback;
+pub use self::timer_thread::TimerHandle;
+#[cfg_attr(target_arch = "wasm32", allow(unused_imports))]
+pub(crate) use self::timer_thread::Timers;
 
 #[cfg_attr(not(feature = "vst3"), allow(unused_imports))]
 #[cfg(all(target_family = "unix", not(target_os = "macos")))]
@@ -20,6 +34,9 @@ pub(crate) use self::linux::LinuxEventLoop as OsEventLoop;
 #[cfg(target_os = "macos")]
 pub(crate) use self::macos::MacOSEventLoop as OsEventLoop;
 #[cfg_attr(not(feature = "vst3"), allow(unused_imports))]
+#[cfg(target_arch = "wasm32")]
+pub(crate) use self::wasm::WasmEventLoop as OsEventLoop;
+#[cfg_attr(not(feature = "vst3"), allow(unused_imports))]
 #[cfg(target_os = "windows")]
 pub(crate) use self::windows::WindowsEventLoop as OsEventLoop;
 
@@ -34,7 +51,8 @@ pub(crate) const TASK_QUEUE_CAPACITY: usize = 4096;
 ///
 /// Additionally, this trait also allows posting tasks to a background thread that's completely
 /// detached from the GUI. This makes it possible for a plugin to execute long running jobs without
-/// blocking GUI rendering.
+/// blocking GUI rendering. Tasks can also be posted to the GUI thread on a timer, which is useful
+/// for things like meter decay, autosaving, or polling.
 ///
 /// This is never used generically, but having this as a trait will cause any missing functions on
 /// an implementation to show up as compiler errors even when using a different platform. And since
@@ -58,11 +76,31 @@ where
 
     /// Post a task to the background task queue so it can be run in a dedicated background thread
     /// without blocking the plugin's GUI. This function needs to be callable at any time without
-    /// blocking.
+    /// blocking. Tasks with a higher [`TaskPriority`][crate::prelude::TaskPriority] are run before
+    /// tasks with a lower priority.
     ///
     /// If the task queue is full, then this will return false.
     #[must_use]
-    fn schedule_background(&self, task: T) -> bool;
+    fn schedule_background(&self, task: T, options: TaskOptions) -> bool;
+
+    /// Cancel all pending tasks that were posted to the background task queue with the tag `tag`.
+    /// Tasks that are already running are not affected.
+    fn cancel_background(&self, tag: u32);
+
+    /// Post a task to the GUI thread after `delay` has passed, as if it was scheduled using
+    /// [`schedule_gui()`][Self::schedule_gui()] at that point. The timer is cancelled when the
+    /// returned handle is dropped, unless the handle is detached.
+    fn schedule_once(&self, delay: Duration, task: T) -> TimerHandle;
+
+    /// Post the task returned by `make_task` to the GUI thread every `interval`, starting after the
+    /// first interval. Ticks are skipped instead of piling up if the timer falls behind. The timer
+    /// runs until the returned handle is dropped or, if the handle is detached, until the event
+    /// loop is dropped.
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        make_task: impl FnMut() -> T + Send + 'static,
+    ) -> TimerHandle;
 
     /// Whether the calling thread is the event loop's main thread. This is usually the thread the
     /// event loop instance was initialized on.
@@ -74,4 +112,10 @@ pub(crate) trait MainThreadExecutor<T>: Send + Sync {
     /// Execute a task on the current thread. This is either called from the GUI thread or from
     /// another background thread, depending on how the task was scheduled in the [`EventContext`].
     fn execute(&self, task: T, is_gui_thread: bool);
+
+    /// Post a task fired by one of the event loop's timers to the GUI thread the same way the
+    /// wrapper would post its own GUI tasks. This is used by event loops that don't have access to
+    /// a GUI thread of their own, like the one on Linux, so the wrapper can run the task on the
+    /// host's GUI thread if it has access to one. Returns `false` if the task could not be posted.
+    fn schedule_timer_task(&self, task: T) -> bool;
 }
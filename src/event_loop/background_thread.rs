@@ -1,16 +1,24 @@
 //! Used by the other [`EventLoop`][super::EventLoop] implementations to spawn threads for running
 //! tasks in the background without blocking the GUI thread.
 //!
-//! This is essentially a slimmed down version of the `LinuxEventLoop`.
+//! This is essentially a slimmed down version of the `LinuxEventLoop`. Tasks are run in order of
+//! their [`TaskPriority`], and pending tasks can be cancelled by their tag. The worker thread also
+//! writes the messages logged with [`nih_log_rt!()`][crate::nih_log_rt!()] to the logger.
 
 use anymap::Entry;
-use crossbeam::channel;
+use crossbeam::channel::{self, Select, TryRecvError};
 use parking_lot::Mutex;
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread::{self, JoinHandle};
 
 use super::MainThreadExecutor;
-use crate::util::permit_alloc;
+use crate::prelude::{TaskOptions, TaskPriority};
+use crate::util::{permit_alloc, rt_log};
+
+/// The number of distinct task tags a single [`BackgroundThread`] can keep track of. Tasks with
+/// additional tags cannot be cancelled.
+const MAX_TASK_TAGS: usize = 64;
 
 /// See the module's documentation. This is a background thread that can be used to run tasks on.
 /// The implementation shares a single thread between all of a plugin's instances hosted in the same
@@ -24,12 +32,33 @@ pub(crate) struct BackgroundThread<T, E> {
     /// this thread will be woken up to execute the task on the executor. When the last worker
     /// thread handle gets dropped the thread is shut down.
     worker_thread: Arc<WorkerThread<T, E>>,
+    /// The current generation for each of the tags used by this instance's tasks. This is not
+    /// shared with other instances using the same worker thread.
+    task_tags: Arc<TaskTags>,
+}
+
+/// Keeps track of a generation counter for every task tag. Tagged tasks store the tag's generation
+/// when they are scheduled, and cancelling a tag increments its generation. Tasks with an outdated
+/// generation are skipped by the worker thread. This is a fixed size lock-free table so it can be
+/// used from the audio thread.
+struct TaskTags {
+    /// The tags stored in each slot, offset by one so zero can mark an empty slot.
+    tags: [AtomicU64; MAX_TASK_TAGS],
+    generations: [AtomicU32; MAX_TASK_TAGS],
+}
+
+/// A tagged task's tag slot and the slot's generation at the time the task was scheduled.
+struct TaskGeneration {
+    task_tags: Arc<TaskTags>,
+    slot: usize,
+    generation: u32,
 }
 
 /// A handle for the singleton worker thread. This lets multiple instances of the same plugin share
 /// a worker thread, and when the last instance gets dropped the worker thread gets terminated.
 struct WorkerThread<T, E> {
-    tasks_sender: channel::Sender<Message<T, E>>,
+    /// A task queue for each priority level, from high to low.
+    tasks_senders: [channel::Sender<Message<T, E>>; 3],
     /// The thread's join handle. Joined when the WorkerThread is dropped.
     join_handle: Option<JoinHandle<()>>,
 }
@@ -38,12 +67,23 @@ struct WorkerThread<T, E> {
 enum Message<T, E> {
     /// A new task for the event loop to execute along with the executor that should execute the
     /// task. A reference to the executor is sent alongside because multiple plugin instances may
-    /// share the same background thread.
-    Task((T, Weak<E>)),
+    /// share the same background thread. Tagged tasks are skipped if they have been cancelled.
+    Task((T, Weak<E>, Option<TaskGeneration>)),
     /// Shut down the worker thread. Send when the last reference to the thread is dropped.
     Shutdown,
 }
 
+// Deriving `Clone` would require `T` and `E` to also implement `Clone`
+impl<T, E> Clone for BackgroundThread<T, E> {
+    fn clone(&self) -> Self {
+        Self {
+            executor: self.executor.clone(),
+            worker_thread: self.worker_thread.clone(),
+            task_tags: self.task_tags.clone(),
+        }
+    }
+}
+
 impl<T, E> BackgroundThread<T, E>
 where
     T: Send + 'static,
@@ -55,19 +95,81 @@ where
             // The same worker thread can be shared by multiple instances. Lifecycle management
             // happens through reference counting.
             worker_thread: get_or_create_worker_thread(),
+            task_tags: Arc::new(TaskTags::default()),
         }
     }
 
-    pub fn schedule(&self, task: T) -> bool {
+    pub fn schedule(&self, task: T, options: TaskOptions) -> bool {
+        let generation = options.tag.and_then(|tag| match self.task_tags.slot(tag) {
+            Some(slot) => Some(TaskGeneration {
+                task_tags: self.task_tags.clone(),
+                slot,
+                generation: self.task_tags.generations[slot].load(Ordering::SeqCst),
+            }),
+            None => {
+                nih_debug_assert_failure!(
+                    "Too many distinct task tags, task {tag} cannot be cancelled"
+                );
+                None
+            }
+        });
+
         // NOTE: This may check the current thread ID, which involves an allocation whenever this
         //       first happens on a new thread because of the way thread local storage works
         permit_alloc(|| {
-            self.worker_thread
-                .tasks_sender
-                .try_send(Message::Task((task, self.executor.clone())))
+            self.worker_thread.tasks_senders[priority_index(options.priority)]
+                .try_send(Message::Task((task, self.executor.clone(), generation)))
                 .is_ok()
         })
     }
+
+    /// Cancel all pending tasks scheduled by this instance with the tag `tag`. Tasks that are
+    /// already running are not affected.
+    pub fn cancel(&self, tag: u32) {
+        if let Some(slot) = self.task_tags.slot(tag) {
+            self.task_tags.generations[slot].fetch_add(1, Ordering::SeqCst);
+        }
+    }
+}
+
+impl Default for TaskTags {
+    fn default() -> Self {
+        Self {
+            tags: std::array::from_fn(|_| AtomicU64::new(0)),
+            generations: std::array::from_fn(|_| AtomicU32::new(0)),
+        }
+    }
+}
+
+impl TaskTags {
+    /// Find or claim the slot for a tag. Returns `None` if all slots are taken by other tags.
+    fn slot(&self, tag: u32) -> Option<usize> {
+        let key = tag as u64 + 1;
+        for (slot, slot_key) in self.tags.iter().enumerate() {
+            match slot_key.compare_exchange(0, key, Ordering::SeqCst, Ordering::SeqCst) {
+                Ok(_) => return Some(slot),
+                Err(existing_key) if existing_key == key => return Some(slot),
+                Err(_) => continue,
+            }
+        }
+
+        None
+    }
+}
+
+impl TaskGeneration {
+    fn is_cancelled(&self) -> bool {
+        self.task_tags.generations[self.slot].load(Ordering::SeqCst) != self.generation
+    }
+}
+
+/// The index of a priority's queue in [`WorkerThread::tasks_senders`].
+fn priority_index(priority: TaskPriority) -> usize {
+    match priority {
+        TaskPriority::High => 0,
+        TaskPriority::Normal => 1,
+        TaskPriority::Low => 2,
+    }
 }
 
 // Rust does not allow us to use the `T` and `E` type variable in statics, so this is a
@@ -80,23 +182,29 @@ lazy_static::lazy_static! {
 
 impl<T: Send + 'static, E: MainThreadExecutor<T> + 'static> WorkerThread<T, E> {
     fn spawn() -> Self {
-        let (tasks_sender, tasks_receiver) = channel::bounded(super::TASK_QUEUE_CAPACITY);
+        // The realtime log queue is allocated here so the audio thread never has to
+        rt_log::initialize();
+
+        let (high_sender, high_receiver) = channel::bounded(super::TASK_QUEUE_CAPACITY);
+        let (normal_sender, normal_receiver) = channel::bounded(super::TASK_QUEUE_CAPACITY);
+        let (low_sender, low_receiver) = channel::bounded(super::TASK_QUEUE_CAPACITY);
         let join_handle = thread::Builder::new()
             .name(String::from("bg-worker"))
-            .spawn(move || worker_thread(tasks_receiver))
+            .spawn(move || worker_thread([high_receiver, normal_receiver, low_receiver]))
             .expect("Could not spawn background worker thread");
 
         Self {
             join_handle: Some(join_handle),
-            tasks_sender,
+            tasks_senders: [high_sender, normal_sender, low_sender],
         }
     }
 }
 
 impl<T, E> Drop for WorkerThread<T, E> {
     fn drop(&mut self) {
-        // The thread is shut down and joined when the handle is dropped
-        self.tasks_sender
+        // The thread is shut down and joined when the handle is dropped. The shutdown request is
+        // sent with the highest priority so pending tasks don't delay it.
+        self.tasks_senders[priority_index(TaskPriority::High)]
             .send(Message::Shutdown)
             .expect("Failed while sending worker thread shutdown request");
         self.join_handle
@@ -138,32 +246,142 @@ where
 }
 
 /// The worker thread used in [`EventLoop`] that executes incoming tasks on the event loop's
-/// executor.
-fn worker_thread<T, E>(tasks_receiver: channel::Receiver<Message<T, E>>)
+/// executor. `tasks_receivers` contains a queue for every priority level, from high to low.
+fn worker_thread<T, E>(tasks_receivers: [channel::Receiver<Message<T, E>>; 3])
 where
     T: Send,
     E: MainThreadExecutor<T> + 'static,
 {
+    let rt_log_wakeup = rt_log::wakeup_receiver();
     loop {
-        match tasks_receiver.recv() {
-            Ok(Message::Task((task, executor))) => match executor.upgrade() {
-                Some(e) => e.execute(task, true),
-                None => {
-                    nih_trace!(
-                        "Received a new task but the executor is no longer alive, shutting down \
-                         worker"
-                    );
-                    return;
+        match next_message(&tasks_receivers, &rt_log_wakeup, rt_log::drain) {
+            Some(Message::Task((task, executor, generation))) => {
+                if generation.map_or(false, |generation| generation.is_cancelled()) {
+                    continue;
                 }
-            },
-            Ok(Message::Shutdown) => return,
-            Err(err) => {
-                nih_trace!(
-                    "Worker thread got disconnected unexpectedly, shutting down: {}",
-                    err
-                );
+
+                match executor.upgrade() {
+                    Some(e) => e.execute(task, true),
+                    None => {
+                        nih_trace!(
+                            "Received a new task but the executor is no longer alive, shutting \
+                             down worker"
+                        );
+                        return;
+                    }
+                }
+            }
+            Some(Message::Shutdown) => return,
+            None => {
+                nih_trace!("Worker thread got disconnected unexpectedly, shutting down");
                 return;
             }
         }
     }
 }
+
+/// Wait for the next message from the highest priority queue that has one. `idle` is called before
+/// waiting, and then again whenever `wakeup` receives a message while waiting. Returns `None` if all
+/// queues have been disconnected.
+fn next_message<M>(
+    receivers: &[channel::Receiver<M>; 3],
+    wakeup: &channel::Receiver<()>,
+    mut idle: impl FnMut(),
+) -> Option<M> {
+    loop {
+        let mut all_disconnected = true;
+        for receiver in receivers {
+            match receiver.try_recv() {
+                Ok(message) => return Some(message),
+                Err(TryRecvError::Empty) => all_disconnected = false,
+                Err(TryRecvError::Disconnected) => (),
+            }
+        }
+        if all_disconnected {
+            return None;
+        }
+
+        // Block until any of the queues has a new message or until we get woken up, and then check
+        // them again in order
+        idle();
+        let mut select = Select::new();
+        for receiver in receivers {
+            select.recv(receiver);
+        }
+        select.recv(wakeup);
+        let _ = select.ready();
+        let _ = wakeup.try_recv();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages_are_prioritized() {
+        let (high_sender, high_receiver) = channel::unbounded();
+        let (normal_sender, normal_receiver) = channel::unbounded();
+        let (low_sender, low_receiver) = channel::unbounded();
+        let receivers = [high_receiver, normal_receiver, low_receiver];
+        let (_wakeup_sender, wakeup_receiver) = channel::bounded(1);
+
+        low_sender.send("low").unwrap();
+        normal_sender.send("normal").unwrap();
+        high_sender.send("high").unwrap();
+        assert_eq!(
+            next_message(&receivers, &wakeup_receiver, || ()),
+            Some("high")
+        );
+        assert_eq!(
+            next_message(&receivers, &wakeup_receiver, || ()),
+            Some("normal")
+        );
+        assert_eq!(
+            next_message(&receivers, &wakeup_receiver, || ()),
+            Some("low")
+        );
+
+        drop((high_sender, normal_sender, low_sender));
+        assert_eq!(next_message(&receivers, &wakeup_receiver, || ()), None);
+    }
+
+    #[test]
+    fn test_wakeup_runs_idle_callback() {
+        let (high_sender, high_receiver) = channel::unbounded();
+        let (_normal_sender, normal_receiver) = channel::unbounded::<&str>();
+        let (_low_sender, low_receiver) = channel::unbounded();
+        let receivers = [high_receiver, normal_receiver, low_receiver];
+        let (wakeup_sender, wakeup_receiver) = channel::bounded(1);
+
+        // The idle callback runs once before waiting, and then again after the wakeup. The message
+        // sent from the second call ends the wait.
+        let mut idle_calls = 0;
+        wakeup_sender.send(()).unwrap();
+        let message = next_message(&receivers, &wakeup_receiver, || {
+            idle_calls += 1;
+            if idle_calls == 2 {
+                high_sender.send("message").unwrap();
+            }
+        });
+        assert_eq!(message, Some("message"));
+        assert_eq!(idle_calls, 2);
+    }
+
+    #[test]
+    fn test_cancel_task_tags() {
+        let task_tags = Arc::new(TaskTags::default());
+        let slot = task_tags.slot(7).unwrap();
+        assert_eq!(task_tags.slot(7), Some(slot));
+        assert_ne!(task_tags.slot(8), Some(slot));
+
+        let generation = TaskGeneration {
+            task_tags: task_tags.clone(),
+            slot,
+            generation: task_tags.generations[slot].load(Ordering::SeqCst),
+        };
+        assert!(!generation.is_cancelled());
+        task_tags.generations[slot].fetch_add(1, Ordering::SeqCst);
+        assert!(generation.is_cancelled());
+    }
+}
@@ -4,10 +4,20 @@
 
 use std::sync::Weak;
 use std::thread::{self, ThreadId};
+use std::time::Duration;
 
-use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use super::{BackgroundThread, EventLoop, MainThreadExecutor, TimerHandle, Timers};
+use crate::prelude::{TaskOptions, TaskPriority};
 use crate::util::permit_alloc;
 
+/// The options for tasks scheduled through `schedule_gui()`. These run on the background thread
+/// because there is no real GUI thread, but they should not have to wait for the plugin's own
+/// background tasks.
+const GUI_TASK_OPTIONS: TaskOptions = TaskOptions {
+    priority: TaskPriority::High,
+    tag: None,
+};
+
 /// See [`EventLoop`][super::EventLoop].
 pub(crate) struct LinuxEventLoop<T, E> {
     /// The thing that ends up executing these tasks. The tasks are usually executed from the worker
@@ -18,6 +28,8 @@ pub(crate) struct LinuxEventLoop<T, E> {
     /// The actual background thread. The implementation is shared with the background thread used
     /// in other backends.
     background_thread: BackgroundThread<T, E>,
+    /// Fires the timers created through `schedule_once()` and `schedule_repeating()`.
+    timers: Timers,
 
     /// The ID of the main thread. In practice this is the ID of the thread that created this task
     /// queue.
@@ -33,6 +45,7 @@ where
         Self {
             executor: executor.clone(),
             background_thread: BackgroundThread::get_or_create(executor),
+            timers: Timers::get_or_create(),
             main_thread_id: thread::current().id(),
         }
     }
@@ -48,14 +61,44 @@ where
 
             true
         } else {
-            self.background_thread.schedule(task)
+            self.background_thread.schedule(task, GUI_TASK_OPTIONS)
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
+    fn schedule_background(&self, task: T, options: TaskOptions) -> bool {
         // This event loop implementation already uses a thread that's completely decoupled from the
         // operating system's or the host's main thread, so we don't need _another_ thread here
-        self.background_thread.schedule(task)
+        self.background_thread.schedule(task, options)
+    }
+
+    fn cancel_background(&self, tag: u32) {
+        self.background_thread.cancel(tag)
+    }
+
+    fn schedule_once(&self, delay: Duration, task: T) -> TimerHandle {
+        // There is no GUI thread this event loop can post tasks to, so the wrapper gets to decide
+        // how fired timers are run. The wrappers post them to the host's GUI thread if the host
+        // provides a way to do that.
+        let executor = self.executor.clone();
+        self.timers.schedule_once(delay, task, move |task| {
+            executor
+                .upgrade()
+                .map_or(false, |executor| executor.schedule_timer_task(task))
+        })
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        make_task: impl FnMut() -> T + Send + 'static,
+    ) -> TimerHandle {
+        let executor = self.executor.clone();
+        self.timers
+            .schedule_repeating(interval, make_task, move |task| {
+                executor
+                    .upgrade()
+                    .map_or(false, |executor| executor.schedule_timer_task(task))
+            })
     }
 
     fn is_main_thread(&self) -> bool {
@@ -10,10 +10,13 @@ use crossbeam::channel::{self, Receiver, Sender};
 use objc::{class, msg_send, sel, sel_impl};
 use std::os::raw::c_void;
 use std::sync::Weak;
+use std::time::Duration;
 
-use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use super::{BackgroundThread, EventLoop, MainThreadExecutor, TimerHandle, Timers};
+use crate::prelude::TaskOptions;
 
 /// Wrapping the `CFRunLoopSourceRef` type is required to be able to annotate it as thread-safe.
+#[derive(Clone, Copy)]
 struct LoopSourceWrapper(CFRunLoopSourceRef);
 
 unsafe impl Send for LoopSourceWrapper {}
@@ -29,6 +32,9 @@ pub(crate) struct MacOSEventLoop<T, E> {
     /// A background thread for running tasks independently from the host's GUI thread. Useful for
     /// longer, blocking tasks.
     background_thread: BackgroundThread<T, E>,
+    /// Fires the timers created through `schedule_once()` and `schedule_repeating()`. These are
+    /// cancelled when the event loop is dropped.
+    timers: Timers,
 
     /// The reference to the run-loop source so that it can be torn down when this struct is
     /// dropped.
@@ -80,6 +86,7 @@ where
         Self {
             executor: executor.clone(),
             background_thread: BackgroundThread::get_or_create(executor),
+            timers: Timers::get_or_create(),
             loop_source: LoopSourceWrapper(loop_source),
             main_thread_sender,
             _callback_data: callback_data,
@@ -95,21 +102,37 @@ where
 
             true
         } else {
-            // Only signal the main thread callback to be called if the task was added to the queue.
-            let success = self.main_thread_sender.try_send(task).is_ok();
-            if success {
-                unsafe {
-                    CFRunLoopSourceSignal(self.loop_source.0);
-                    CFRunLoopWakeUp(CFRunLoopGetMain());
-                }
-            }
-
-            success
+            post_to_main_thread(&self.main_thread_sender, self.loop_source, task)
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
-        self.background_thread.schedule(task)
+    fn schedule_background(&self, task: T, options: TaskOptions) -> bool {
+        self.background_thread.schedule(task, options)
+    }
+
+    fn cancel_background(&self, tag: u32) {
+        self.background_thread.cancel(tag)
+    }
+
+    fn schedule_once(&self, delay: Duration, task: T) -> TimerHandle {
+        let main_thread_sender = self.main_thread_sender.clone();
+        let loop_source = self.loop_source;
+        self.timers.schedule_once(delay, task, move |task| {
+            post_to_main_thread(&main_thread_sender, loop_source, task)
+        })
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        make_task: impl FnMut() -> T + Send + 'static,
+    ) -> TimerHandle {
+        let main_thread_sender = self.main_thread_sender.clone();
+        let loop_source = self.loop_source;
+        self.timers
+            .schedule_repeating(interval, make_task, move |task| {
+                post_to_main_thread(&main_thread_sender, loop_source, task)
+            })
     }
 
     fn is_main_thread(&self) -> bool {
@@ -130,6 +153,21 @@ impl<T, E> Drop for MacOSEventLoop<T, E> {
     }
 }
 
+/// Add a task to the main thread's task queue and wake up the run loop so it gets executed. Returns
+/// `false` if the queue is full.
+fn post_to_main_thread<T>(sender: &Sender<T>, loop_source: LoopSourceWrapper, task: T) -> bool {
+    // Only signal the main thread callback to be called if the task was added to the queue.
+    let success = sender.try_send(task).is_ok();
+    if success {
+        unsafe {
+            CFRunLoopSourceSignal(loop_source.0);
+            CFRunLoopWakeUp(CFRunLoopGetMain());
+        }
+    }
+
+    success
+}
+
 extern "C" fn loop_source_callback<T, E>(info: *const c_void)
 where
     T: Send + 'static,
@@ -0,0 +1,420 @@
+//! Used by the [`EventLoop`][super::EventLoop] implementations to fire one-shot and repeating
+//! timers. A single thread keeps track of the timers for all event loops in the process, and the
+//! event loops decide how a fired timer's task is posted to their thread. Event loops that have
+//! access to a timer provided by the host can instead use polled timers, which are fired from that
+//! host timer without involving the timer thread.
+
+use crossbeam::channel;
+use parking_lot::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// A handle for a timer created through
+/// [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()] or
+/// [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()]. The
+/// timer is cancelled when this handle is dropped. Use [`detach()`][Self::detach()] to let a
+/// one-shot timer fire without keeping the handle around.
+#[must_use = "The timer is cancelled when its handle is dropped"]
+pub struct TimerHandle {
+    cancelled: Arc<AtomicBool>,
+    detached: bool,
+}
+
+/// A callback passed to [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()]
+/// or [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()]. The
+/// wrappers wrap this in a task so it can be posted to the GUI thread when the timer fires. A
+/// repeating timer posts a new task referring to the same callback every time it fires.
+#[derive(Clone)]
+pub(crate) struct TimerCallback(Arc<Mutex<Box<dyn FnMut() + Send>>>);
+
+/// The timers owned by a single event loop. All of the event loop's timers are cancelled when this
+/// is dropped, since their callbacks may refer to resources owned by the event loop.
+pub(crate) struct Timers {
+    backend: Backend,
+    alive: Arc<AtomicBool>,
+}
+
+/// Determines what fires the timers created through a [`Timers`] object.
+enum Backend {
+    /// The timers are fired by the process-wide timer thread.
+    Thread(Arc<TimerThread>),
+    /// The timers are fired when the event loop calls [`Timers::fire_due_timers()`].
+    Polled(Mutex<Vec<Timer>>),
+}
+
+/// A handle for the process-wide timer thread. The thread is shut down and joined when the last
+/// event loop using it is dropped.
+struct TimerThread {
+    sender: channel::Sender<Message>,
+    /// The thread's join handle. Joined when the `TimerThread` is dropped.
+    join_handle: Option<JoinHandle<()>>,
+}
+
+/// A message for communicating with the timer thread.
+enum Message {
+    Add(Timer),
+    /// Shut down the timer thread. Sent when the last reference to the thread is dropped.
+    Shutdown,
+}
+
+struct Timer {
+    deadline: Instant,
+    /// The interval for repeating timers. One-shot timers are removed after they have fired.
+    interval: Option<Duration>,
+    /// Set when the timer's [`TimerHandle`] is dropped.
+    cancelled: Arc<AtomicBool>,
+    /// Cleared when the event loop that created the timer is dropped.
+    owner_alive: Arc<AtomicBool>,
+    /// Posts the timer's task to the event loop.
+    callback: Box<dyn FnMut() + Send>,
+}
+
+lazy_static::lazy_static! {
+    static ref TIMER_THREAD: Mutex<Weak<TimerThread>> = Mutex::new(Weak::new());
+}
+
+impl TimerHandle {
+    /// Create a handle for a timer that's managed by the event loop itself instead of by the timer
+    /// thread. The event loop should stop firing the timer once `cancelled` has been set.
+    pub(crate) fn new(cancelled: Arc<AtomicBool>) -> Self {
+        Self {
+            cancelled,
+            detached: false,
+        }
+    }
+
+    /// Keep the timer running after this handle has been dropped. A detached repeating timer runs
+    /// until the plugin instance is destroyed.
+    pub fn detach(mut self) {
+        self.detached = true;
+    }
+}
+
+impl Drop for TimerHandle {
+    fn drop(&mut self) {
+        if !self.detached {
+            self.cancelled.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+impl TimerCallback {
+    /// Wrap a callback for a repeating timer.
+    pub fn new(callback: Box<dyn FnMut() + Send>) -> Self {
+        Self(Arc::new(Mutex::new(callback)))
+    }
+
+    /// Wrap a callback for a one-shot timer.
+    pub fn new_once(callback: Box<dyn FnOnce() + Send>) -> Self {
+        let mut callback = Some(callback);
+        Self::new(Box::new(move || {
+            if let Some(callback) = callback.take() {
+                callback();
+            }
+        }))
+    }
+
+    /// Run the callback. This is called on the GUI thread.
+    pub fn call(&self) {
+        (self.0.lock())();
+    }
+}
+
+impl Timers {
+    /// Get a handle for the timer thread, spawning the thread if it is not yet running.
+    pub fn get_or_create() -> Self {
+        let mut timer_thread = TIMER_THREAD.lock();
+        let timer_thread = match timer_thread.upgrade() {
+            Some(arc) => arc,
+            None => {
+                let arc = Arc::new(TimerThread::spawn());
+                *timer_thread = Arc::downgrade(&arc);
+                arc
+            }
+        };
+
+        Self {
+            backend: Backend::Thread(timer_thread),
+            alive: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Create timers that are only fired when [`fire_due_timers()`][Self::fire_due_timers()] is
+    /// called. This is used when the host provides its own timers, so the timers' callbacks are
+    /// run on the host's main thread. The timers can only fire as often as `fire_due_timers()` is
+    /// called.
+    #[allow(dead_code)]
+    pub fn new_polled() -> Self {
+        Self {
+            backend: Backend::Polled(Mutex::new(Vec::new())),
+            alive: Arc::new(AtomicBool::new(true)),
+        }
+    }
+
+    /// Fire all polled timers whose deadlines have passed. Does nothing when the timers are driven
+    /// by the timer thread.
+    #[allow(dead_code)]
+    pub fn fire_due_timers(&self) {
+        let Backend::Polled(timers) = &self.backend else {
+            return;
+        };
+
+        // The callbacks may schedule new timers, so the lock cannot be held while firing them
+        let mut due_timers = std::mem::take(&mut *timers.lock());
+        fire_due(&mut due_timers, Instant::now());
+        due_timers.retain(|timer| !timer.is_cancelled());
+
+        let mut timers = timers.lock();
+        due_timers.append(&mut timers);
+        *timers = due_timers;
+    }
+
+    /// Call `post` with `task` on the timer thread once after `delay`. `post` should post the task to
+    /// the event loop without blocking, and return `false` if the task could not be posted.
+    pub fn schedule_once<T: Send + 'static>(
+        &self,
+        delay: Duration,
+        task: T,
+        mut post: impl FnMut(T) -> bool + Send + 'static,
+    ) -> TimerHandle {
+        let mut task = Some(task);
+        self.schedule(
+            delay,
+            None,
+            Box::new(move || {
+                if let Some(task) = task.take() {
+                    let task_posted = post(task);
+                    nih_debug_assert!(
+                        task_posted,
+                        "The task queue is full, dropping timer task..."
+                    );
+                }
+            }),
+        )
+    }
+
+    /// Call `post` with the task returned by `make_task` on the timer thread every `interval`,
+    /// starting after the first interval. See [`schedule_once()`][Self::schedule_once()].
+    pub fn schedule_repeating<T: Send + 'static>(
+        &self,
+        interval: Duration,
+        mut make_task: impl FnMut() -> T + Send + 'static,
+        mut post: impl FnMut(T) -> bool + Send + 'static,
+    ) -> TimerHandle {
+        self.schedule(
+            interval,
+            Some(interval),
+            Box::new(move || {
+                let task_posted = post(make_task());
+                nih_debug_assert!(
+                    task_posted,
+                    "The task queue is full, dropping timer task..."
+                );
+            }),
+        )
+    }
+
+    /// Call `callback` on the timer thread after `delay`, and then every `interval` if it is set.
+    fn schedule(
+        &self,
+        delay: Duration,
+        interval: Option<Duration>,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let timer = Timer {
+            deadline: Instant::now() + delay,
+            // A zero interval would cause the timer thread to spin
+            interval: interval.map(|interval| interval.max(Duration::from_millis(1))),
+            cancelled: cancelled.clone(),
+            owner_alive: self.alive.clone(),
+            callback,
+        };
+
+        match &self.backend {
+            Backend::Thread(timer_thread) => {
+                if timer_thread.sender.send(Message::Add(timer)).is_err() {
+                    nih_debug_assert_failure!("The timer thread is no longer running");
+                    cancelled.store(true, Ordering::Relaxed);
+                }
+            }
+            Backend::Polled(timers) => timers.lock().push(timer),
+        }
+
+        TimerHandle {
+            cancelled,
+            detached: false,
+        }
+    }
+}
+
+impl Drop for Timers {
+    fn drop(&mut self) {
+        self.alive.store(false, Ordering::Relaxed);
+    }
+}
+
+impl TimerThread {
+    fn spawn() -> Self {
+        let (sender, receiver) = channel::unbounded();
+        let join_handle = thread::Builder::new()
+            .name(String::from("timer-worker"))
+            .spawn(move || timer_thread(receiver))
+            .expect("Could not spawn timer thread");
+
+        Self {
+            sender,
+            join_handle: Some(join_handle),
+        }
+    }
+}
+
+impl Drop for TimerThread {
+    fn drop(&mut self) {
+        self.sender
+            .send(Message::Shutdown)
+            .expect("Failed while sending timer thread shutdown request");
+        self.join_handle
+            .take()
+            .expect("Missing timer thread JoinHandle")
+            .join()
+            .expect("Timer thread panicked");
+    }
+}
+
+impl Timer {
+    fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed) || !self.owner_alive.load(Ordering::Relaxed)
+    }
+}
+
+/// The timer thread's main loop. Sleeps until either the next timer's deadline or until a new timer
+/// is added.
+fn timer_thread(receiver: channel::Receiver<Message>) {
+    let mut timers: Vec<Timer> = Vec::new();
+    loop {
+        timers.retain(|timer| !timer.is_cancelled());
+
+        let message = match timers.iter().map(|timer| timer.deadline).min() {
+            Some(deadline) => match receiver.recv_deadline(deadline) {
+                Ok(message) => Some(message),
+                Err(channel::RecvTimeoutError::Timeout) => None,
+                Err(channel::RecvTimeoutError::Disconnected) => return,
+            },
+            None => match receiver.recv() {
+                Ok(message) => Some(message),
+                Err(_) => return,
+            },
+        };
+
+        match message {
+            Some(Message::Add(timer)) => timers.push(timer),
+            Some(Message::Shutdown) => return,
+            None => (),
+        }
+
+        fire_due(&mut timers, Instant::now());
+    }
+}
+
+/// Run the callbacks for all timers with a deadline before `now`, and update their deadlines.
+/// One-shot timers are marked as cancelled after they have fired.
+fn fire_due(timers: &mut [Timer], now: Instant) {
+    for timer in timers.iter_mut() {
+        if timer.deadline > now || timer.is_cancelled() {
+            continue;
+        }
+
+        (timer.callback)();
+        match timer.interval {
+            // If the thread fell behind then the missed ticks are skipped instead of firing them all
+            // at once
+            Some(interval) => {
+                timer.deadline += interval;
+                if timer.deadline <= now {
+                    timer.deadline = now + interval;
+                }
+            }
+            None => timer.cancelled.store(true, Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+
+    /// How long the tests wait for a timer to fire before failing. This is only reached when a test
+    /// fails, so it can be generous.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    #[test]
+    fn test_timers_fire_until_cancelled() {
+        let timers = Timers::get_or_create();
+        let (sender, receiver) = channel::unbounded();
+
+        let _one_shot = timers.schedule_once(Duration::from_millis(1), "once", {
+            let sender = sender.clone();
+            move |task| sender.send(task).is_ok()
+        });
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok("once"));
+
+        let repeating = timers.schedule_repeating(Duration::from_millis(1), || "repeating", {
+            let sender = sender.clone();
+            move |task| sender.send(task).is_ok()
+        });
+        for _ in 0..3 {
+            assert_eq!(receiver.recv_timeout(TIMEOUT), Ok("repeating"));
+        }
+
+        // A tick that was already being fired when the handle got dropped may still arrive, but
+        // after that the timer should no longer fire
+        drop(repeating);
+        let _ = receiver.recv_timeout(Duration::from_millis(20));
+        assert_eq!(
+            receiver.recv_timeout(Duration::from_millis(20)),
+            Err(channel::RecvTimeoutError::Timeout)
+        );
+    }
+
+    #[test]
+    fn test_detached_one_shot_timer_fires() {
+        let timers = Timers::get_or_create();
+        let (sender, receiver) = channel::unbounded();
+
+        timers
+            .schedule_once(Duration::from_millis(1), (), move |task| {
+                sender.send(task).is_ok()
+            })
+            .detach();
+        assert_eq!(receiver.recv_timeout(TIMEOUT), Ok(()));
+    }
+
+    #[test]
+    fn test_polled_timers_only_fire_when_polled() {
+        let timers = Timers::new_polled();
+
+        let count = Arc::new(AtomicUsize::new(0));
+        let _repeating = timers.schedule(Duration::ZERO, Some(Duration::from_millis(50)), {
+            let count = count.clone();
+            Box::new(move || {
+                count.fetch_add(1, Ordering::Relaxed);
+            })
+        });
+
+        thread::sleep(Duration::from_millis(5));
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+
+        timers.fire_due_timers();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+        timers.fire_due_timers();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        thread::sleep(Duration::from_millis(60));
+        timers.fire_due_timers();
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+    }
+}
@@ -0,0 +1,178 @@
+//! An event loop implementation for WebAssembly. A WebAssembly instance only ever runs on a single
+//! thread and it cannot spawn threads of its own, so all tasks are queued and then run when the
+//! wrapper calls [`WasmEventLoop::run_pending_tasks()`] from the host's idle callback.
+
+use crossbeam::queue::ArrayQueue;
+use parking_lot::Mutex;
+use std::cmp::Reverse;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Weak};
+use std::time::Duration;
+
+use super::{EventLoop, MainThreadExecutor, TimerHandle, TASK_QUEUE_CAPACITY};
+use crate::prelude::TaskOptions;
+
+/// See [`EventLoop`][super::EventLoop].
+pub(crate) struct WasmEventLoop<T, E> {
+    /// The thing that ends up executing these tasks.
+    executor: Weak<E>,
+
+    /// Tasks posted through `schedule_gui()` and by fired timers.
+    gui_tasks: ArrayQueue<T>,
+    /// Tasks posted through `schedule_background()`. These are run after the GUI tasks, in order of
+    /// their priority.
+    background_tasks: ArrayQueue<(T, TaskOptions)>,
+    /// The timers created through `schedule_once()` and `schedule_repeating()`.
+    timers: Mutex<Vec<Timer<T>>>,
+    /// The time passed to the last [`run_pending_tasks()`][Self::run_pending_tasks()] call. There
+    /// is no monotonic clock available to the WebAssembly instance, so timers are scheduled
+    /// relative to this.
+    now: Mutex<Duration>,
+}
+
+struct Timer<T> {
+    deadline: Duration,
+    /// The interval for repeating timers. One-shot timers are removed after they have fired.
+    interval: Option<Duration>,
+    /// Set when the timer's [`TimerHandle`] is dropped.
+    cancelled: Arc<AtomicBool>,
+    /// Produces the timer's task. This returns `None` once a one-shot timer has already fired.
+    task: Box<dyn FnMut() -> Option<T> + Send>,
+}
+
+impl<T, E> EventLoop<T, E> for WasmEventLoop<T, E>
+where
+    T: Send + 'static,
+    E: MainThreadExecutor<T> + 'static,
+{
+    fn new_and_spawn(executor: Weak<E>) -> Self {
+        Self {
+            executor,
+            gui_tasks: ArrayQueue::new(TASK_QUEUE_CAPACITY),
+            background_tasks: ArrayQueue::new(TASK_QUEUE_CAPACITY),
+            timers: Mutex::new(Vec::new()),
+            now: Mutex::new(Duration::ZERO),
+        }
+    }
+
+    fn schedule_gui(&self, task: T) -> bool {
+        // Unlike the other event loops, tasks are never executed directly. Everything runs on the
+        // same thread, and the wrapper may still be holding on to locks the task needs.
+        self.gui_tasks.push(task).is_ok()
+    }
+
+    fn schedule_background(&self, task: T, options: TaskOptions) -> bool {
+        self.background_tasks.push((task, options)).is_ok()
+    }
+
+    fn cancel_background(&self, tag: u32) {
+        let num_tasks = self.background_tasks.len();
+        for _ in 0..num_tasks {
+            let Some((task, options)) = self.background_tasks.pop() else {
+                break;
+            };
+
+            if options.tag != Some(tag) {
+                // This cannot fail since a task was just removed from the queue
+                let _ = self.background_tasks.push((task, options));
+            }
+        }
+    }
+
+    fn schedule_once(&self, delay: Duration, task: T) -> TimerHandle {
+        let mut task = Some(task);
+        self.add_timer(delay, None, Box::new(move || task.take()))
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        mut make_task: impl FnMut() -> T + Send + 'static,
+    ) -> TimerHandle {
+        self.add_timer(
+            interval,
+            Some(interval),
+            Box::new(move || Some(make_task())),
+        )
+    }
+
+    fn is_main_thread(&self) -> bool {
+        true
+    }
+}
+
+impl<T, E> WasmEventLoop<T, E>
+where
+    T: Send + 'static,
+    E: MainThreadExecutor<T> + 'static,
+{
+    /// Fire the timers whose deadlines have passed and run all pending tasks. `now` is the time
+    /// according to the host's clock. This is always relative to the same point in time, but the
+    /// exact reference point does not matter.
+    pub fn run_pending_tasks(&self, now: Duration) {
+        *self.now.lock() = now;
+        let Some(executor) = self.executor.upgrade() else {
+            nih_debug_assert_failure!("Tasks were run after the executor was dropped");
+            return;
+        };
+
+        let mut fired_tasks = Vec::new();
+        self.timers.lock().retain_mut(|timer| {
+            if timer.cancelled.load(Ordering::Relaxed) {
+                return false;
+            }
+            if timer.deadline > now {
+                return true;
+            }
+
+            if let Some(task) = (timer.task)() {
+                fired_tasks.push(task);
+            }
+            match timer.interval {
+                Some(interval) => {
+                    // Ticks are skipped instead of piling up if the timer falls behind
+                    timer.deadline += interval;
+                    if timer.deadline <= now {
+                        timer.deadline = now + interval;
+                    }
+
+                    true
+                }
+                None => false,
+            }
+        });
+
+        for task in fired_tasks {
+            executor.execute(task, true);
+        }
+        while let Some(task) = self.gui_tasks.pop() {
+            executor.execute(task, true);
+        }
+
+        // Tasks with the same priority are still run in the order they were scheduled since the
+        // sort is stable
+        let mut background_tasks: Vec<_> =
+            std::iter::from_fn(|| self.background_tasks.pop()).collect();
+        background_tasks.sort_by_key(|(_, options)| Reverse(options.priority));
+        for (task, _) in background_tasks {
+            executor.execute(task, false);
+        }
+    }
+
+    fn add_timer(
+        &self,
+        delay: Duration,
+        interval: Option<Duration>,
+        task: Box<dyn FnMut() -> Option<T> + Send>,
+    ) -> TimerHandle {
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.timers.lock().push(Timer {
+            deadline: *self.now.lock() + delay,
+            interval,
+            cancelled: cancelled.clone(),
+            task,
+        });
+
+        TimerHandle::new(cancelled)
+    }
+}
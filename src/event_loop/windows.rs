@@ -7,6 +7,7 @@ use std::mem;
 use std::ptr;
 use std::sync::Weak;
 use std::thread::{self, ThreadId};
+use std::time::Duration;
 use windows::core::PCSTR;
 use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, WPARAM};
 use windows::Win32::System::{
@@ -18,7 +19,8 @@ use windows::Win32::UI::WindowsAndMessaging::{
     WINDOW_EX_STYLE, WINDOW_STYLE, WM_CREATE, WM_DESTROY, WM_USER, WNDCLASSEXA,
 };
 
-use super::{BackgroundThread, EventLoop, MainThreadExecutor};
+use super::{BackgroundThread, EventLoop, MainThreadExecutor, TimerHandle, Timers};
+use crate::prelude::TaskOptions;
 use crate::util::permit_alloc;
 
 /// The custom message ID for our notify event. If the hidden event loop window receives this, then
@@ -57,6 +59,9 @@ pub(crate) struct WindowsEventLoop<T, E> {
     /// A background thread for running tasks independently from the host's GUI thread. Useful for
     /// longer, blocking tasks.
     background_thread: BackgroundThread<T, E>,
+    /// Fires the timers created through `schedule_once()` and `schedule_repeating()`. These are
+    /// cancelled when the event loop is dropped.
+    timers: Timers,
 }
 
 impl<T, E> EventLoop<T, E> for WindowsEventLoop<T, E>
@@ -133,6 +138,7 @@ where
             message_window_class_name: class_name,
             tasks_sender,
             background_thread: BackgroundThread::get_or_create(executor),
+            timers: Timers::get_or_create(),
         }
     }
 
@@ -147,21 +153,37 @@ where
 
             true
         } else {
-            let success = self.tasks_sender.try_send(task).is_ok();
-            if success {
-                // Instead of polling on a timer, we can just wake up the window whenever there's a
-                // new message.
-                unsafe {
-                    PostMessageA(self.message_window, NOTIFY_MESSAGE_ID, WPARAM(0), LPARAM(0))
-                };
-            }
-
-            success
+            post_to_message_window(&self.tasks_sender, self.message_window, task)
         }
     }
 
-    fn schedule_background(&self, task: T) -> bool {
-        self.background_thread.schedule(task)
+    fn schedule_background(&self, task: T, options: TaskOptions) -> bool {
+        self.background_thread.schedule(task, options)
+    }
+
+    fn cancel_background(&self, tag: u32) {
+        self.background_thread.cancel(tag)
+    }
+
+    fn schedule_once(&self, delay: Duration, task: T) -> TimerHandle {
+        let tasks_sender = self.tasks_sender.clone();
+        let message_window = self.message_window;
+        self.timers.schedule_once(delay, task, move |task| {
+            post_to_message_window(&tasks_sender, message_window, task)
+        })
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        make_task: impl FnMut() -> T + Send + 'static,
+    ) -> TimerHandle {
+        let tasks_sender = self.tasks_sender.clone();
+        let message_window = self.message_window;
+        self.timers
+            .schedule_repeating(interval, make_task, move |task| {
+                post_to_message_window(&tasks_sender, message_window, task)
+            })
     }
 
     fn is_main_thread(&self) -> bool {
@@ -183,6 +205,23 @@ impl<T, E> Drop for WindowsEventLoop<T, E> {
     }
 }
 
+/// Add a task to the task queue and wake up the message window so it gets executed. Returns `false`
+/// if the queue is full.
+fn post_to_message_window<T>(
+    tasks_sender: &channel::Sender<T>,
+    message_window: HWND,
+    task: T,
+) -> bool {
+    let success = tasks_sender.try_send(task).is_ok();
+    if success {
+        // Instead of polling on a timer, we can just wake up the window whenever there's a new
+        // message.
+        unsafe { PostMessageA(message_window, NOTIFY_MESSAGE_ID, WPARAM(0), LPARAM(0)) };
+    }
+
+    success
+}
+
 unsafe extern "system" fn window_proc(
     handle: HWND,
     message: u32,
@@ -17,17 +17,56 @@ use crate::util;
 /// negative zero values to make sure string->value->string roundtrips work correctly. Otherwise
 /// `-0.001` rounded to two digits would result in `-0.00`.
 pub fn v2s_f32_rounded(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
-    let rounding_multiplier = 10u32.pow(digits as u32) as f32;
+    Arc::new(move |value| format_f32_rounded(value, digits))
+}
+
+/// Round an `f32` value to a fixed number of significant digits, so the number of decimal digits
+/// adapts to the value's magnitude. With three significant digits `0.01234` is formatted as
+/// `0.0123`, `1.234` as `1.23`, and `1234.6` as `1235`. Values are never rounded to fewer than zero
+/// decimal digits. Like [`v2s_f32_rounded()`], this never returns negative zero values.
+pub fn v2s_f32_significant_digits(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
     Arc::new(move |value| {
-        // See above
-        if (value * rounding_multiplier).round() / rounding_multiplier == 0.0 {
-            format!("{:.digits$}", 0.0)
+        let decimals = if value == 0.0 || !value.is_finite() {
+            digits.saturating_sub(1)
         } else {
-            format!("{value:.digits$}")
-        }
+            let magnitude = value.abs().log10().floor() as i32;
+            (digits as i32 - 1 - magnitude).max(0) as usize
+        };
+
+        format_f32_rounded(value, decimals)
     })
 }
 
+/// Parse a floating point number. Unlike [`str::parse()`], this also accepts a comma as the
+/// decimal separator and it ignores thousands separators, since hosts and users may format numbers
+/// according to the system's locale. If the string contains both periods and commas, then the
+/// last one is used as the decimal separator. The string should not contain a unit.
+pub fn parse_f32(string: &str) -> Option<f32> {
+    let string = string.trim();
+    if let Ok(value) = string.parse() {
+        return Some(value);
+    }
+
+    let decimal_separator = match (string.rfind('.'), string.rfind(',')) {
+        (Some(period_idx), Some(comma_idx)) if comma_idx > period_idx => ',',
+        (Some(_), _) => '.',
+        // A single comma is a decimal comma, multiple commas are thousands separators
+        (None, Some(_)) if string.matches(',').count() == 1 => ',',
+        (None, _) => '.',
+    };
+
+    let cleaned: String = string
+        .chars()
+        .filter_map(|c| match c {
+            c if c == decimal_separator => Some('.'),
+            '.' | ',' | ' ' | '\u{a0}' | '\u{202f}' | '\'' => None,
+            c => Some(c),
+        })
+        .collect();
+
+    cleaned.parse().ok()
+}
+
 /// Format a `[0, 1]` number as a percentage. Does not include the percent sign, you should specify
 /// this as the parameter's unit.
 pub fn v2s_f32_percentage(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
@@ -37,13 +76,7 @@ pub fn v2s_f32_percentage(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + S
 /// Parse a `[0, 100]` percentage to a `[0, 1]` number. Handles the percentage unit for you. Used in
 /// conjunction with [`v2s_f32_percentage()`].
 pub fn s2v_f32_percentage() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
-    Arc::new(|string| {
-        string
-            .trim_end_matches(&[' ', '%'])
-            .parse()
-            .ok()
-            .map(|x: f32| x / 100.0)
-    })
+    Arc::new(|string| parse_f32(string.trim_end_matches(&[' ', '%'])).map(|x| x / 100.0))
 }
 
 /// Format a positive number as a compression ratio. A value of 4 will be formatted as `4.0:1` while
@@ -83,19 +116,11 @@ pub fn s2v_compression_ratio() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync>
 /// string->value->string roundtrips work correctly. Otherwise `-0.001` rounded to two digits
 /// would result in `-0.00`.
 pub fn v2s_f32_gain_to_db(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
-    let rounding_multiplier = 10u32.pow(digits as u32) as f32;
     Arc::new(move |value| {
         if value < util::MINUS_INFINITY_GAIN {
             String::from("-inf")
         } else {
-            let value_db = util::gain_to_db(value);
-
-            // See above
-            if (value_db * rounding_multiplier).round() / rounding_multiplier == 0.0 {
-                format!("{:.digits$}", 0.0)
-            } else {
-                format!("{value_db:.digits$}")
-            }
+            format_f32_rounded(util::gain_to_db(value), digits)
         }
     })
 }
@@ -109,7 +134,7 @@ pub fn s2v_f32_gain_to_db() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
         if string.eq_ignore_ascii_case("-in") {
             Some(0.0)
         } else {
-            string.parse().ok().map(util::db_to_gain)
+            parse_f32(string).map(util::db_to_gain)
         }
     })
 }
@@ -255,6 +280,34 @@ pub fn s2v_f32_hz_then_khz() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
     })
 }
 
+/// Format a `f32` millisecond value as a rounded `ms` value below one second, and as a rounded `s`
+/// value above one second. This already includes the unit.
+pub fn v2s_f32_ms_then_s(digits: usize) -> Arc<dyn Fn(f32) -> String + Send + Sync> {
+    Arc::new(move |value| {
+        if value.abs() < 1000.0 {
+            format!("{} ms", format_f32_rounded(value, digits))
+        } else {
+            format!("{} s", format_f32_rounded(value / 1000.0, digits.max(2)))
+        }
+    })
+}
+
+/// Convert an input in the same format at that of [`v2s_f32_ms_then_s()`] to a millisecond value.
+/// Values without a unit are assumed to be in milliseconds.
+pub fn s2v_f32_ms_then_s() -> Arc<dyn Fn(&str) -> Option<f32> + Send + Sync> {
+    Arc::new(|string| {
+        let string = string.trim();
+        let value = parse_f32(string.trim_end_matches([' ', 'm', 'M', 's', 'S']))?;
+
+        let unit = string.trim_start_matches(|c: char| !c.is_ascii_alphabetic());
+        if unit.eq_ignore_ascii_case("s") {
+            Some(value * 1000.0)
+        } else {
+            Some(value)
+        }
+    })
+}
+
 /// Format an order/power of two. Useful in conjunction with [`s2v_i32_power_of_two()`] to limit
 /// integer parameter ranges to be only powers of two.
 pub fn v2s_i32_power_of_two() -> Arc<dyn Fn(i32) -> String + Send + Sync> {
@@ -336,6 +389,17 @@ pub fn s2v_bool_bypass() -> Arc<dyn Fn(&str) -> Option<bool> + Send + Sync> {
     })
 }
 
+/// Round `value` to `digits` decimal digits without producing negative zero values. See
+/// [`v2s_f32_rounded()`].
+pub(crate) fn format_f32_rounded(value: f32, digits: usize) -> String {
+    let rounding_multiplier = 10.0f32.powi(digits as i32);
+    if (value * rounding_multiplier).round() / rounding_multiplier == 0.0 {
+        format!("{:.digits$}", 0.0)
+    } else {
+        format!("{value:.digits$}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -352,6 +416,42 @@ mod tests {
         assert_eq!("0.01", v2s(0.009));
     }
 
+    #[test]
+    fn v2s_f32_significant_digits_adapts_to_magnitude() {
+        let v2s = v2s_f32_significant_digits(3);
+
+        assert_eq!("0.0123", v2s(0.012345));
+        assert_eq!("1.23", v2s(1.2345));
+        assert_eq!("123", v2s(123.45));
+        assert_eq!("1235", v2s(1234.6));
+        assert_eq!("0.00", v2s(0.0));
+        assert_eq!("0.00", v2s(-0.0));
+    }
+
+    #[test]
+    fn parse_f32_locales() {
+        assert_eq!(Some(1.5), parse_f32("1.5"));
+        assert_eq!(Some(1.5), parse_f32(" 1,5 "));
+        assert_eq!(Some(-0.25), parse_f32("-0,25"));
+        assert_eq!(Some(1234.5), parse_f32("1,234.5"));
+        assert_eq!(Some(1234.5), parse_f32("1.234,5"));
+        assert_eq!(Some(1234567.0), parse_f32("1,234,567"));
+        assert_eq!(Some(1234.5), parse_f32("1 234,5"));
+        assert_eq!(None, parse_f32("abc"));
+    }
+
+    #[test]
+    fn f32_ms_then_s_roundtrip() {
+        let v2s = v2s_f32_ms_then_s(1);
+        let s2v = s2v_f32_ms_then_s();
+
+        assert_eq!("12.5 ms", v2s(12.5));
+        assert_eq!("1.50 s", v2s(1500.0));
+        assert_eq!(Some(12.5), s2v("12.5 ms"));
+        assert_eq!(Some(1500.0), s2v("1,5 s"));
+        assert_eq!(Some(80.0), s2v("80"));
+    }
+
     // More of these validators could use tests, but this one in particular is tricky and I noticed
     // an issue where it didn't roundtrip correctly
     #[test]
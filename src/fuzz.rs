@@ -0,0 +1,410 @@
+//! Entry points for fuzzing a plugin together with the parts of NIH-plug that parse untrusted
+//! input. Project files, presets, and host events can all contain arbitrary data, and these
+//! functions make it possible to find crashes caused by that data before users do. Each function
+//! takes the raw bytes from a fuzzer and decodes them into structured input, so they can be used
+//! directly as [`cargo fuzz`](https://github.com/rust-fuzz/cargo-fuzz) targets:
+//!
+//! ```ignore
+//! #![no_main]
+//!
+//! use libfuzzer_sys::fuzz_target;
+//!
+//! fuzz_target!(|data: &[u8]| nih_plug::fuzz::fuzz_state::<my_plugin::MyPlugin>(data));
+//! ```
+//!
+//! The plugin is hosted using an [`InProcessHost`], so the plugin's own state loading, event
+//! handling, and DSP code is fuzzed as well. The plugin crate needs to be built as an `rlib` in
+//! addition to a `cdylib` for the fuzz targets to be able to use it.
+
+use std::num::NonZeroU32;
+
+use crate::host::InProcessHost;
+use crate::midi::MidiResult;
+use crate::params::Param;
+use crate::prelude::{BufferConfig, NoteEvent, ParamPtr, Plugin, PluginNoteEvent, ProcessMode};
+use crate::wrapper::state;
+use crate::wrapper::util::clamp_output_event_timing;
+
+/// The sample rate the plugin is hosted at.
+const SAMPLE_RATE: f32 = 44100.0;
+/// The maximum block size the plugin is hosted with. This is kept small so every fuzzer iteration
+/// is fast.
+const MAX_BUFFER_SIZE: u32 = 64;
+
+/// Load arbitrary bytes as the plugin's state, both as a state file saved by one of the plugin
+/// APIs and as a CLAP preset. If the state loads, then the plugin processes a block of audio and
+/// the state is saved again.
+///
+/// # Panics
+///
+/// Panics if a state that could be loaded can not be saved again. Any other panic comes from the
+/// plugin itself.
+pub fn fuzz_state<P: Plugin>(data: &[u8]) {
+    let Some(mut host) = make_host::<P>() else {
+        return;
+    };
+
+    let mut loaded = host.load_state(data);
+    if let Ok(mut preset_state) = state::deserialize_clap_preset(data) {
+        loaded |= host.set_state_object(&mut preset_state);
+    }
+
+    if loaded {
+        process_block(&mut host, &[]);
+        if let Err(err) = host.save_state() {
+            panic!("State could be loaded but not saved again: {err:#}");
+        }
+    }
+}
+
+/// Decode arbitrary bytes as a sequence of note events and send them to the plugin. The events are
+/// either parsed from raw MIDI data, just like the wrappers do for MIDI input, or they are
+/// constructed directly with arbitrary values to mimic CLAP and VST3 note events. Events parsed
+/// from MIDI are also converted back to MIDI.
+///
+/// # Panics
+///
+/// Panics if an event parsed from MIDI can not be parsed again after converting it back to MIDI.
+/// Any other panic comes from the plugin itself.
+pub fn fuzz_events<P: Plugin>(data: &[u8]) {
+    let Some(mut host) = make_host::<P>() else {
+        return;
+    };
+
+    let mut input = FuzzInput::new(data);
+    let mut events: Vec<PluginNoteEvent<P>> = Vec::new();
+    while !input.is_empty() {
+        let timing = input.u32() % MAX_BUFFER_SIZE;
+        let event = match input.u8() % 12 {
+            0 => {
+                let len = input.u8() as usize;
+                let midi_data = input.bytes(len);
+
+                match NoteEvent::from_midi(timing, midi_data) {
+                    Ok(event) => {
+                        check_midi_round_trip::<P>(event.clone());
+                        event
+                    }
+                    Err(_) => continue,
+                }
+            }
+            1 => NoteEvent::NoteOn {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                velocity: input.f32(),
+            },
+            2 => NoteEvent::NoteOff {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                velocity: input.f32(),
+            },
+            3 => NoteEvent::Choke {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+            },
+            4 => NoteEvent::PolyModulation {
+                timing,
+                voice_id: input.u32() as i32,
+                poly_modulation_id: input.u32(),
+                normalized_offset: input.f32(),
+            },
+            5 => NoteEvent::MonoAutomation {
+                timing,
+                poly_modulation_id: input.u32(),
+                normalized_value: input.f32(),
+            },
+            6 => NoteEvent::PolyPressure {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                pressure: input.f32(),
+            },
+            7 => NoteEvent::PolyVolume {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                gain: input.f32(),
+            },
+            8 => NoteEvent::PolyPan {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                pan: input.f32(),
+            },
+            9 => NoteEvent::PolyTuning {
+                timing,
+                voice_id: input.voice_id(),
+                channel: input.channel(),
+                note: input.note(),
+                tuning: input.f32(),
+            },
+            10 => NoteEvent::MidiPitchBend {
+                timing,
+                channel: input.channel(),
+                value: input.f32(),
+            },
+            _ => NoteEvent::MidiCC {
+                timing,
+                channel: input.channel(),
+                cc: input.u8() % 128,
+                value: input.f32(),
+            },
+        };
+
+        events.push(event);
+    }
+
+    // Hosts always send events in order
+    events.sort_by_key(|event| event.timing());
+    process_block(&mut host, &events);
+}
+
+/// Decode arbitrary bytes as values for the plugin's parameters. These values are passed to the
+/// parameters' conversion and preview functions, and are then set as the parameters' values before
+/// the plugin processes a block of audio. Strings are also decoded from the input to fuzz the
+/// parameters' string to value conversions.
+///
+/// # Panics
+///
+/// Any panic comes from the parameters or from the plugin itself.
+pub fn fuzz_param_values<P: Plugin>(data: &[u8]) {
+    let Some(mut host) = make_host::<P>() else {
+        return;
+    };
+
+    let param_ids: Vec<String> = host.param_ids().map(String::from).collect();
+    if param_ids.is_empty() {
+        return;
+    }
+
+    let mut input = FuzzInput::new(data);
+    while !input.is_empty() {
+        let param_id = &param_ids[input.u8() as usize % param_ids.len()];
+        let param_ptr = host.param(param_id).unwrap();
+        let value = input.f32();
+        let other_value = input.f32();
+        let len = input.u8() as usize;
+        let string = String::from_utf8_lossy(input.bytes(len)).into_owned();
+
+        // SAFETY: The parameters are owned by the plugin, which outlives this block
+        unsafe {
+            let formatted = param_ptr.normalized_value_to_string(value, input.bool());
+            param_ptr.string_to_normalized_value(&formatted);
+            param_ptr.string_to_normalized_value(&string);
+            param_ptr.previous_normalized_step(value, input.bool());
+            param_ptr.next_normalized_step(value, input.bool());
+
+            match param_ptr {
+                ParamPtr::FloatParam(p) => fuzz_param_previews(&*p, value, other_value),
+                ParamPtr::IntParam(p) => {
+                    fuzz_param_previews(&*p, value, other_value as i32);
+                }
+                ParamPtr::BoolParam(p) => {
+                    fuzz_param_previews(&*p, value, other_value.is_sign_negative());
+                }
+                ParamPtr::EnumParam(p) => {
+                    fuzz_param_previews(&*p, value, other_value as i32);
+                }
+                ParamPtr::MacroParam(p) => fuzz_param_previews(&*p, value, other_value),
+                ParamPtr::DynamicParam(p) => fuzz_param_previews(&*p, value, other_value),
+            }
+        }
+
+        host.set_param_normalized(param_id, value);
+    }
+
+    process_block(&mut host, &[]);
+}
+
+/// Call the preview functions on a parameter with arbitrary values.
+fn fuzz_param_previews<T: Param>(param: &T, normalized: f32, plain: T::Plain) {
+    param.preview_plain(normalized);
+    param.preview_normalized(plain);
+    param.preview_modulated(normalized);
+}
+
+/// Convert an event parsed from MIDI back to MIDI and parse it again. Both parsed events need to be
+/// identical.
+fn check_midi_round_trip<P: Plugin>(event: PluginNoteEvent<P>) {
+    let timing = event.timing();
+    let midi_data = match event.clone().as_midi() {
+        Some(MidiResult::Basic(midi_data)) => midi_data.to_vec(),
+        Some(MidiResult::SysEx(buffer, len)) => {
+            let buffer: &[u8] = std::borrow::Borrow::borrow(&buffer);
+            match buffer.get(..len) {
+                Some(midi_data) => midi_data.to_vec(),
+                None => panic!(
+                    "SysEx message reported a length of {len} bytes, but its buffer only \
+                     contains {} bytes",
+                    buffer.len()
+                ),
+            }
+        }
+        None => return,
+    };
+
+    match NoteEvent::<P::SysExMessage>::from_midi(timing, &midi_data) {
+        Ok(reparsed_event) => {
+            let reparsed_midi_data = match reparsed_event.as_midi() {
+                Some(MidiResult::Basic(midi_data)) => midi_data.to_vec(),
+                Some(MidiResult::SysEx(buffer, len)) => {
+                    std::borrow::Borrow::<[u8]>::borrow(&buffer)[..len].to_vec()
+                }
+                None => Vec::new(),
+            };
+            assert_eq!(
+                midi_data, reparsed_midi_data,
+                "Converting {event:?} to MIDI and back changed the MIDI data"
+            );
+        }
+        Err(_) => panic!("{event:?} was converted to MIDI data that can't be parsed again"),
+    }
+}
+
+/// Host the plugin with its first audio IO layout. Returns `None` if the plugin fails to
+/// initialize, since that is not caused by the fuzzer's input.
+fn make_host<P: Plugin>() -> Option<InProcessHost<P>> {
+    let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+    let buffer_config = BufferConfig {
+        sample_rate: SAMPLE_RATE,
+        min_buffer_size: None,
+        max_buffer_size: MAX_BUFFER_SIZE,
+        process_mode: ProcessMode::Realtime,
+    };
+
+    InProcessHost::new(audio_io_layout, buffer_config).ok()
+}
+
+/// Process a single block of silence with the plugin. Output events are checked the same way the
+/// wrappers check them.
+fn process_block<P: Plugin>(host: &mut InProcessHost<P>, events: &[PluginNoteEvent<P>]) {
+    let audio_io_layout = *host.audio_io_layout();
+    let num_samples = MAX_BUFFER_SIZE as usize;
+
+    let mut main_io = vec![
+        vec![0.0f32; num_samples];
+        audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize
+    ];
+    let mut aux_inputs: Vec<Vec<Vec<f32>>> = audio_io_layout
+        .aux_input_ports
+        .iter()
+        .map(|channel_count| vec![vec![0.0; num_samples]; channel_count.get() as usize])
+        .collect();
+    let mut aux_outputs: Vec<Vec<Vec<f32>>> = audio_io_layout
+        .aux_output_ports
+        .iter()
+        .map(|channel_count| vec![vec![0.0; num_samples]; channel_count.get() as usize])
+        .collect();
+
+    let mut main_io: Vec<&mut [f32]> = main_io.iter_mut().map(Vec::as_mut_slice).collect();
+    let mut aux_inputs: Vec<Vec<&mut [f32]>> = aux_inputs
+        .iter_mut()
+        .map(|port| port.iter_mut().map(Vec::as_mut_slice).collect())
+        .collect();
+    let mut aux_inputs: Vec<&mut [&mut [f32]]> =
+        aux_inputs.iter_mut().map(Vec::as_mut_slice).collect();
+    let mut aux_outputs: Vec<Vec<&mut [f32]>> = aux_outputs
+        .iter_mut()
+        .map(|port| port.iter_mut().map(Vec::as_mut_slice).collect())
+        .collect();
+    let mut aux_outputs: Vec<&mut [&mut [f32]]> =
+        aux_outputs.iter_mut().map(Vec::as_mut_slice).collect();
+
+    let mut output_events = Vec::new();
+    host.process_with_aux(
+        &mut main_io,
+        &mut aux_inputs,
+        &mut aux_outputs,
+        events,
+        &mut output_events,
+    );
+    for event in output_events {
+        clamp_output_event_timing(event.timing(), num_samples as u32);
+        if let Some(MidiResult::SysEx(buffer, len)) = event.as_midi() {
+            let buffer: &[u8] = std::borrow::Borrow::borrow(&buffer);
+            assert!(
+                len <= buffer.len(),
+                "Output SysEx message reported a length of {len} bytes, but its buffer only \
+                 contains {} bytes",
+                buffer.len()
+            );
+        }
+    }
+
+    host.run_pending_tasks();
+}
+
+/// Decodes structured input from the fuzzer's bytes. Reading past the end of the input returns
+/// zeroes, so every byte sequence decodes to something.
+struct FuzzInput<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> FuzzInput<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn bytes(&mut self, len: usize) -> &'a [u8] {
+        let (bytes, rest) = self.data.split_at(len.min(self.data.len()));
+        self.data = rest;
+
+        bytes
+    }
+
+    fn u8(&mut self) -> u8 {
+        self.bytes(1).first().copied().unwrap_or(0)
+    }
+
+    fn bool(&mut self) -> bool {
+        self.u8() & 1 == 1
+    }
+
+    fn u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        let input = self.bytes(4);
+        bytes[..input.len()].copy_from_slice(input);
+
+        u32::from_le_bytes(bytes)
+    }
+
+    /// Any bit pattern is a valid value, so this also produces NaNs, infinities, and denormals.
+    fn f32(&mut self) -> f32 {
+        f32::from_bits(self.u32())
+    }
+
+    /// A voice ID, or `None` if the event should not have one.
+    fn voice_id(&mut self) -> Option<i32> {
+        if self.bool() {
+            Some(self.u32() as i32)
+        } else {
+            None
+        }
+    }
+
+    /// A MIDI channel. The wrappers never send events for channels outside of this range.
+    fn channel(&mut self) -> u8 {
+        self.u8() % 16
+    }
+
+    /// A MIDI note number. The wrappers never send events for notes outside of this range.
+    fn note(&mut self) -> u8 {
+        self.u8() % 128
+    }
+}
@@ -0,0 +1,701 @@
+//! Use a plugin as a library. [`InProcessHost`] instantiates a plugin and drives it directly from
+//! Rust code without going through a plugin API or a DAW. This can be used to embed a plugin's DSP
+//! in a game or another application, or to write integration tests that process audio with a
+//! plugin.
+//!
+//! ```ignore
+//! let mut host = InProcessHost::<Gain>::new(Gain::AUDIO_IO_LAYOUTS[0], BufferConfig {
+//!     sample_rate: 48000.0,
+//!     min_buffer_size: None,
+//!     max_buffer_size: 512,
+//!     process_mode: ProcessMode::Offline,
+//! })?;
+//! host.set_param_normalized("gain", 0.75);
+//!
+//! let mut left = vec![0.0f32; 512];
+//! let mut right = vec![0.0f32; 512];
+//! let mut output_events = Vec::new();
+//! host.process(&mut [&mut left, &mut right], &[], &mut output_events);
+//! ```
+//!
+//! The plugin's editor is never created, and background tasks and tasks scheduled for the GUI
+//! thread are queued until [`InProcessHost::run_pending_tasks()`] is called.
+
+use atomic_float::AtomicF32;
+use crossbeam::queue::ArrayQueue;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::num::NonZeroU32;
+use std::ptr::NonNull;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use self::context::{HostInitContext, HostProcessContext};
+use crate::prelude::{
+    AudioIOLayout, AuxiliaryBuffers, BufferConfig, ParamPtr, Params, Plugin, PluginNoteEvent,
+    ProcessStatus, StateSaveContext, TaskExecutor, Transport,
+};
+use crate::util::modulation::ModulationMatrix;
+use crate::wrapper::state;
+use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::{bypass_param_enabled, find_bypass_param, process_wrapper};
+
+mod context;
+
+/// How many background tasks can be scheduled from the audio thread before
+/// [`InProcessHost::run_pending_tasks()`] needs to be called. Tasks scheduled after that will be
+/// dropped.
+const TASK_QUEUE_CAPACITY: usize = 512;
+
+/// A plugin instance that is hosted directly by the application. The host owns the plugin, its
+/// parameters, and its state. Audio is processed in place using the application's own buffers
+/// through [`process()`][Self::process()].
+///
+/// The plugin is initialized when the host is created, and it is deactivated when the host is
+/// dropped.
+pub struct InProcessHost<P: Plugin> {
+    /// The hosted plugin instance.
+    plugin: P,
+    /// Everything the plugin's contexts need access to. This is stored separately from `plugin` so
+    /// the contexts can borrow it while the plugin is being called.
+    shared: HostShared<P>,
+
+    /// Creates the plugin's [`Buffer`][crate::prelude::Buffer]s from the application's buffers.
+    buffer_manager: BufferManager,
+    /// Crossfades the main output to the dry signal when the plugin is bypassed. This is only used
+    /// when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: BypassProcessor,
+    /// Scratch storage for the channel pointers passed to `buffer_manager`. These are preallocated
+    /// so processing audio doesn't allocate.
+    main_io_channel_pointers: Vec<*mut f32>,
+    aux_input_channel_pointers: Vec<Vec<*mut f32>>,
+    aux_output_channel_pointers: Vec<Vec<*mut f32>>,
+}
+
+// SAFETY: The raw pointers in the channel pointer vectors are only used as scratch storage inside of
+//         `process_with_aux()`. Everything else is owned by the host.
+unsafe impl<P: Plugin> Send for InProcessHost<P> {}
+
+/// The part of [`InProcessHost`] that is shared with the plugin's contexts.
+pub(crate) struct HostShared<P: Plugin> {
+    /// The plugin's background task executor. Tasks scheduled during initialization are run
+    /// immediately, while tasks scheduled from the audio thread are stored in `pending_tasks`.
+    task_executor: TaskExecutor<P>,
+    /// Tasks scheduled from the audio thread that have not yet been run. See
+    /// [`InProcessHost::run_pending_tasks()`].
+    pending_tasks: ArrayQueue<P::BackgroundTask>,
+    /// The plugin's parameters. These are fetched once during initialization so the `ParamPtr`s are
+    /// guaranteed to live at least as long as this object.
+    params: Arc<dyn Params>,
+    /// A mapping from parameter string IDs to parameter pointers. Used for serialization and
+    /// deserialization, and for setting parameters from the application.
+    param_id_to_ptr: HashMap<String, ParamPtr>,
+    /// The parameter IDs in the order they were declared in by the plugin.
+    param_ids: Vec<String>,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    modulation_matrix: Option<Arc<ModulationMatrix>>,
+    /// The plugin's bypass parameter, if it has one.
+    bypass_param: Option<ParamPtr>,
+
+    /// The bus and buffer configurations are fixed when the host is created.
+    audio_io_layout: AudioIOLayout,
+    buffer_config: BufferConfig,
+    /// The transport information passed to the plugin. The position is advanced after every
+    /// processing cycle while the transport is playing.
+    transport: Transport,
+
+    /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
+    /// [`ProcessContext`].
+    ///
+    /// [`InitContext`]: crate::prelude::InitContext
+    /// [`ProcessContext`]: crate::prelude::ProcessContext
+    current_latency: AtomicU32,
+    /// The current tail length in seconds, as set through the
+    /// [`ProcessContext`][crate::prelude::ProcessContext].
+    current_tail_seconds: AtomicF32,
+}
+
+/// Errors that may arise while creating an [`InProcessHost`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HostError {
+    /// The plugin does not support the requested audio IO layout.
+    UnsupportedAudioIOLayout,
+    /// The plugin returned `false` during initialization.
+    InitializationFailed,
+}
+
+impl Display for HostError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HostError::UnsupportedAudioIOLayout => {
+                write!(f, "The plugin does not support the audio IO layout")
+            }
+            HostError::InitializationFailed => write!(f, "The plugin failed to initialize"),
+        }
+    }
+}
+
+impl std::error::Error for HostError {}
+
+impl<P: Plugin> InProcessHost<P> {
+    /// Create a default instance of the plugin and initialize it with the specified audio IO
+    /// layout and buffer configuration. The layout needs to be one of the plugin's
+    /// [`Plugin::AUDIO_IO_LAYOUTS`].
+    pub fn new(
+        audio_io_layout: AudioIOLayout,
+        buffer_config: BufferConfig,
+    ) -> Result<Self, HostError> {
+        Self::with_plugin(P::default(), audio_io_layout, buffer_config)
+    }
+
+    /// Initialize an existing plugin instance with the specified audio IO layout and buffer
+    /// configuration. The layout needs to be one of the plugin's [`Plugin::AUDIO_IO_LAYOUTS`].
+    pub fn with_plugin(
+        mut plugin: P,
+        audio_io_layout: AudioIOLayout,
+        buffer_config: BufferConfig,
+    ) -> Result<Self, HostError> {
+        let layout_supported = if P::AUDIO_IO_LAYOUTS.is_empty() {
+            audio_io_layout == AudioIOLayout::default()
+        } else {
+            P::AUDIO_IO_LAYOUTS.contains(&audio_io_layout)
+        };
+        if !layout_supported {
+            return Err(HostError::UnsupportedAudioIOLayout);
+        }
+
+        let task_executor = plugin.task_executor();
+        let params = plugin.params();
+        let modulation_matrix = plugin.modulation_matrix();
+
+        let param_map = params.param_map();
+        let bypass_param = find_bypass_param::<P>(param_map.iter().map(|(_, ptr, _)| *ptr));
+
+        let mut transport = Transport::new(buffer_config.sample_rate);
+        transport.pos_samples = Some(0);
+
+        let shared = HostShared {
+            task_executor,
+            pending_tasks: ArrayQueue::new(TASK_QUEUE_CAPACITY),
+            params,
+            param_ids: param_map
+                .iter()
+                .map(|(param_id, _, _)| param_id.clone())
+                .collect(),
+            param_id_to_ptr: param_map
+                .into_iter()
+                .map(|(param_id, param_ptr, _)| (param_id, param_ptr))
+                .collect(),
+            modulation_matrix,
+            bypass_param,
+
+            audio_io_layout,
+            buffer_config,
+            transport,
+
+            current_latency: AtomicU32::new(0),
+            current_tail_seconds: AtomicF32::new(0.0),
+        };
+
+        // Before initializing the plugin, make sure all smoothers are set the the default values
+        for param in shared.param_id_to_ptr.values() {
+            unsafe { param.update_smoother(buffer_config.sample_rate, true) };
+        }
+
+        let mut bypass_processor = BypassProcessor::default();
+        if !Self::initialize_plugin(&mut plugin, &shared, &mut bypass_processor) {
+            return Err(HostError::InitializationFailed);
+        }
+
+        Ok(Self {
+            plugin,
+            shared,
+
+            buffer_manager: BufferManager::for_audio_io_layout(
+                buffer_config.max_buffer_size as usize,
+                audio_io_layout,
+            ),
+            bypass_processor,
+            main_io_channel_pointers: Vec::with_capacity(
+                audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+            ),
+            aux_input_channel_pointers: audio_io_layout
+                .aux_input_ports
+                .iter()
+                .map(|channel_count| Vec::with_capacity(channel_count.get() as usize))
+                .collect(),
+            aux_output_channel_pointers: audio_io_layout
+                .aux_output_ports
+                .iter()
+                .map(|channel_count| Vec::with_capacity(channel_count.get() as usize))
+                .collect(),
+        })
+    }
+
+    /// The hosted plugin.
+    pub fn plugin(&self) -> &P {
+        &self.plugin
+    }
+
+    /// The hosted plugin. Parameters should be changed through
+    /// [`set_param_normalized()`][Self::set_param_normalized()] instead of through the plugin
+    /// object so their smoothers and macro mappings are updated.
+    pub fn plugin_mut(&mut self) -> &mut P {
+        &mut self.plugin
+    }
+
+    /// The audio IO layout the plugin was initialized with.
+    pub fn audio_io_layout(&self) -> &AudioIOLayout {
+        &self.shared.audio_io_layout
+    }
+
+    /// The buffer configuration the plugin was initialized with.
+    pub fn buffer_config(&self) -> &BufferConfig {
+        &self.shared.buffer_config
+    }
+
+    /// The transport information passed to the plugin during the next processing cycle. The tempo,
+    /// time signature, and playback state can be changed through this object. The position starts
+    /// at zero, and it is advanced after every processing cycle while the transport is playing. Use
+    /// [`seek()`][Self::seek()] to change it.
+    pub fn transport_mut(&mut self) -> &mut Transport {
+        &mut self.shared.transport
+    }
+
+    /// Move the transport to a position in samples.
+    pub fn seek(&mut self, pos_samples: i64) {
+        self.shared.transport.pos_samples = Some(pos_samples);
+    }
+
+    /// The latency in samples the plugin last reported.
+    pub fn latency_samples(&self) -> u32 {
+        self.shared.current_latency.load(Ordering::SeqCst)
+    }
+
+    /// The tail length in seconds the plugin last reported. This is zero if the plugin never set
+    /// its tail length.
+    pub fn tail_seconds(&self) -> f32 {
+        self.shared.current_tail_seconds.load(Ordering::SeqCst)
+    }
+
+    /// The IDs of the plugin's parameters, in the order they were declared in.
+    pub fn param_ids(&self) -> impl Iterator<Item = &str> {
+        self.shared
+            .param_ids
+            .iter()
+            .map(|param_id| param_id.as_str())
+    }
+
+    /// Get a parameter by its ID. This can be used to read the parameter's value and its other
+    /// properties.
+    pub fn param(&self, param_id: &str) -> Option<ParamPtr> {
+        self.shared.param_id_to_ptr.get(param_id).copied()
+    }
+
+    /// Get a parameter's current normalized value, or `None` if the plugin doesn't have a
+    /// parameter with this ID.
+    pub fn param_normalized(&self, param_id: &str) -> Option<f32> {
+        self.param(param_id)
+            .map(|param_ptr| unsafe { param_ptr.unmodulated_normalized_value() })
+    }
+
+    /// Set a parameter to a normalized value. The new value is used starting from the next
+    /// processing cycle. Returns `false` if the plugin doesn't have a parameter with this ID.
+    pub fn set_param_normalized(&mut self, param_id: &str, normalized: f32) -> bool {
+        let Some(param_ptr) = self.param(param_id) else {
+            return false;
+        };
+
+        let sample_rate = self.shared.buffer_config.sample_rate;
+        unsafe {
+            if param_ptr.set_normalized_value(normalized) {
+                param_ptr.update_smoother(sample_rate, false);
+                param_ptr.apply_macro_mappings(
+                    |param_id| self.shared.param_id_to_ptr.get(param_id).copied(),
+                    Some(sample_rate),
+                    |_, _| (),
+                );
+            }
+        }
+
+        true
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        self.shared.is_bypassed()
+    }
+
+    /// Process a block of audio in place. `main_io` contains one slice for each of the main output
+    /// channels. If the plugin has main inputs, then the first channels also contain the input
+    /// when this function is called. All slices must have the same length, which may not exceed
+    /// the buffer configuration's maximum buffer size.
+    ///
+    /// `input_events` must be ordered by timing, and any events sent by the plugin are added to
+    /// `output_events`. The output events are not cleared first. If `output_events` has enough
+    /// spare capacity, then this function does not allocate. Use
+    /// [`process_with_aux()`][Self::process_with_aux()] if the audio IO layout has auxiliary ports.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel counts don't match the audio IO layout, or if the buffers are larger
+    /// than the maximum buffer size.
+    pub fn process(
+        &mut self,
+        main_io: &mut [&mut [f32]],
+        input_events: &[PluginNoteEvent<P>],
+        output_events: &mut Vec<PluginNoteEvent<P>>,
+    ) -> ProcessStatus {
+        self.process_with_aux(main_io, &mut [], &mut [], input_events, output_events)
+    }
+
+    /// The same as [`process()`][Self::process()], but with buffers for the plugin's auxiliary
+    /// input and output ports. `aux_inputs` and `aux_outputs` contain one entry for each of the
+    /// audio IO layout's auxiliary ports, and each entry contains one slice per channel.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the channel counts don't match the audio IO layout, or if the buffers are larger
+    /// than the maximum buffer size.
+    pub fn process_with_aux(
+        &mut self,
+        main_io: &mut [&mut [f32]],
+        aux_inputs: &mut [&mut [&mut [f32]]],
+        aux_outputs: &mut [&mut [&mut [f32]]],
+        input_events: &[PluginNoteEvent<P>],
+        output_events: &mut Vec<PluginNoteEvent<P>>,
+    ) -> ProcessStatus {
+        let audio_io_layout = self.shared.audio_io_layout;
+        let num_output_channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        let num_input_channels = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        assert_eq!(
+            main_io.len(),
+            num_output_channels,
+            "The number of main channels does not match the audio IO layout"
+        );
+        assert_eq!(
+            aux_inputs.len(),
+            audio_io_layout.aux_input_ports.len(),
+            "The number of auxiliary inputs does not match the audio IO layout"
+        );
+        assert_eq!(
+            aux_outputs.len(),
+            audio_io_layout.aux_output_ports.len(),
+            "The number of auxiliary outputs does not match the audio IO layout"
+        );
+
+        let mut channel_lengths = main_io
+            .iter()
+            .map(|channel| channel.len())
+            .chain(
+                aux_inputs
+                    .iter()
+                    .flat_map(|port| port.iter().map(|c| c.len())),
+            )
+            .chain(
+                aux_outputs
+                    .iter()
+                    .flat_map(|port| port.iter().map(|c| c.len())),
+            );
+        let num_samples = channel_lengths.next().unwrap_or(0);
+        assert!(
+            channel_lengths.all(|len| len == num_samples),
+            "All channels need to have the same length"
+        );
+        assert!(
+            num_samples <= self.shared.buffer_config.max_buffer_size as usize,
+            "The buffers are larger than the maximum buffer size"
+        );
+
+        self.main_io_channel_pointers.clear();
+        self.main_io_channel_pointers
+            .extend(main_io.iter_mut().map(|channel| channel.as_mut_ptr()));
+        for ((channel_pointers, port), channel_count) in self
+            .aux_input_channel_pointers
+            .iter_mut()
+            .zip(aux_inputs.iter_mut())
+            .zip(audio_io_layout.aux_input_ports)
+        {
+            assert_eq!(
+                port.len(),
+                channel_count.get() as usize,
+                "The number of auxiliary input channels does not match the audio IO layout"
+            );
+
+            channel_pointers.clear();
+            channel_pointers.extend(port.iter_mut().map(|channel| channel.as_mut_ptr()));
+        }
+        for ((channel_pointers, port), channel_count) in self
+            .aux_output_channel_pointers
+            .iter_mut()
+            .zip(aux_outputs.iter_mut())
+            .zip(audio_io_layout.aux_output_ports)
+        {
+            assert_eq!(
+                port.len(),
+                channel_count.get() as usize,
+                "The number of auxiliary output channels does not match the audio IO layout"
+            );
+
+            channel_pointers.clear();
+            channel_pointers.extend(port.iter_mut().map(|channel| channel.as_mut_ptr()));
+        }
+
+        let Self {
+            plugin,
+            shared,
+            buffer_manager,
+            bypass_processor,
+            main_io_channel_pointers,
+            aux_input_channel_pointers,
+            aux_output_channel_pointers,
+        } = self;
+        let result = process_wrapper(|| {
+            // The main IO buffers are processed in place, just like in the dummy standalone backend
+            let buffers = unsafe {
+                buffer_manager.create_buffers(0, num_samples, |buffer_sources| {
+                    *buffer_sources.main_output_channel_pointers = Some(ChannelPointers {
+                        ptrs: NonNull::new(main_io_channel_pointers.as_mut_ptr()).unwrap(),
+                        num_channels: main_io_channel_pointers.len(),
+                    });
+                    *buffer_sources.main_input_channel_pointers = Some(ChannelPointers {
+                        ptrs: NonNull::new(main_io_channel_pointers.as_mut_ptr()).unwrap(),
+                        num_channels: num_input_channels.min(main_io_channel_pointers.len()),
+                    });
+
+                    for (input_source_channel_pointers, input_channel_pointers) in buffer_sources
+                        .aux_input_channel_pointers
+                        .iter_mut()
+                        .zip(aux_input_channel_pointers.iter_mut())
+                    {
+                        *input_source_channel_pointers = Some(ChannelPointers {
+                            ptrs: NonNull::new(input_channel_pointers.as_mut_ptr()).unwrap(),
+                            num_channels: input_channel_pointers.len(),
+                        });
+                    }
+
+                    for (output_source_channel_pointers, output_channel_pointers) in buffer_sources
+                        .aux_output_channel_pointers
+                        .iter_mut()
+                        .zip(aux_output_channel_pointers.iter_mut())
+                    {
+                        *output_source_channel_pointers = Some(ChannelPointers {
+                            ptrs: NonNull::new(output_channel_pointers.as_mut_ptr()).unwrap(),
+                            num_channels: output_channel_pointers.len(),
+                        });
+                    }
+                })
+            };
+
+            let sample_rate = shared.buffer_config.sample_rate;
+            if let Some(modulation_matrix) = &shared.modulation_matrix {
+                // SAFETY: The parameters are owned by the plugin, which outlives this function call
+                unsafe {
+                    modulation_matrix.process_block(
+                        buffers.main_buffer,
+                        input_events.iter(),
+                        sample_rate,
+                        |param_id| shared.param_id_to_ptr.get(param_id).copied(),
+                    )
+                };
+            }
+
+            if P::MANAGED_BYPASS {
+                bypass_processor.write_dry(buffers.main_buffer);
+            }
+            let mut aux = AuxiliaryBuffers {
+                inputs: buffers.aux_inputs,
+                outputs: buffers.aux_outputs,
+            };
+            let result = plugin.process(
+                buffers.main_buffer,
+                &mut aux,
+                &mut HostProcessContext {
+                    host: shared,
+                    input_events,
+                    input_events_idx: 0,
+                    output_events,
+                },
+            );
+            if P::MANAGED_BYPASS {
+                bypass_processor.process(
+                    buffers.main_buffer,
+                    shared.current_latency.load(Ordering::Relaxed),
+                    shared.is_bypassed(),
+                );
+            }
+
+            result
+        });
+
+        let transport = &mut self.shared.transport;
+        if transport.playing {
+            transport.pos_samples = Some(transport.pos_samples.unwrap_or(0) + num_samples as i64);
+        }
+
+        result
+    }
+
+    /// Run the background tasks and GUI tasks the plugin scheduled while processing audio. This
+    /// runs the tasks on the calling thread, so this should not be called from a realtime thread.
+    /// Returns the number of tasks that were run.
+    pub fn run_pending_tasks(&mut self) -> usize {
+        let mut num_tasks = 0;
+        while let Some(task) = self.shared.pending_tasks.pop() {
+            (self.shared.task_executor)(task);
+            num_tasks += 1;
+        }
+
+        num_tasks
+    }
+
+    /// Clear the plugin's internal buffers, for instance when the application seeks to a different
+    /// position. This calls [`Plugin::reset()`].
+    pub fn reset(&mut self) {
+        process_wrapper(|| self.plugin.reset());
+        if P::MANAGED_BYPASS {
+            self.bypass_processor.reset(self.shared.is_bypassed());
+        }
+    }
+
+    /// Serialize the plugin's parameters and persistent fields. The state uses the same format as
+    /// the plugin APIs, so it can be shared with the plugin's CLAP and VST3 versions.
+    pub fn save_state(&self) -> anyhow::Result<Vec<u8>> {
+        unsafe {
+            state::serialize_json::<P>(
+                self.shared.params.clone(),
+                self.shared
+                    .param_id_to_ptr
+                    .iter()
+                    .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+                StateSaveContext::Project,
+            )
+        }
+    }
+
+    /// Restore state previously saved with [`save_state()`][Self::save_state()]. The plugin is
+    /// reinitialized afterwards. Returns `false` if the state could not be loaded.
+    pub fn load_state(&mut self, state: &[u8]) -> bool {
+        match unsafe { state::deserialize_json(state) } {
+            Some(mut state) => self.set_state_object(&mut state),
+            None => false,
+        }
+    }
+
+    /// Get the plugin's state as a [`PluginState`][crate::prelude::PluginState] object.
+    pub fn state_object(&self) -> state::PluginState {
+        unsafe {
+            state::serialize_object::<P>(
+                self.shared.params.clone(),
+                self.shared
+                    .param_id_to_ptr
+                    .iter()
+                    .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+                StateSaveContext::Project,
+            )
+        }
+    }
+
+    /// Restore a [`PluginState`][crate::prelude::PluginState] object. The plugin is reinitialized
+    /// afterwards. Returns `false` if the state could not be loaded.
+    pub fn set_state_object(&mut self, state: &mut state::PluginState) -> bool {
+        let success = unsafe {
+            state::deserialize_object::<P>(
+                state,
+                self.shared.params.clone(),
+                |param_id| self.shared.param_id_to_ptr.get(param_id).copied(),
+                Some(&self.shared.buffer_config),
+            )
+        };
+        if !success {
+            nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
+            return false;
+        }
+
+        // There is no audio thread to protect here, so the plugin's `PersistNotifier` callbacks
+        // can run right away
+        self.shared.params.run_load_callbacks();
+
+        // The plugin needs to be reinitialized so it can respond to the new parameter values
+        let success =
+            Self::initialize_plugin(&mut self.plugin, &self.shared, &mut self.bypass_processor);
+        nih_debug_assert!(
+            success,
+            "Plugin returned false when reinitializing after loading state"
+        );
+
+        success
+    }
+
+    /// Initialize and reset the plugin, and set up the managed bypass. Returns `false` if the
+    /// plugin failed to initialize.
+    fn initialize_plugin(
+        plugin: &mut P,
+        shared: &HostShared<P>,
+        bypass_processor: &mut BypassProcessor,
+    ) -> bool {
+        if !plugin.initialize(
+            &shared.audio_io_layout,
+            &shared.buffer_config,
+            &mut HostInitContext { host: shared },
+        ) {
+            return false;
+        }
+        process_wrapper(|| plugin.reset());
+
+        if P::MANAGED_BYPASS {
+            bypass_processor.initialize(
+                shared
+                    .audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+                shared.current_latency.load(Ordering::SeqCst),
+                shared.buffer_config.max_buffer_size,
+                shared.buffer_config.sample_rate,
+                shared.is_bypassed(),
+            );
+        }
+
+        true
+    }
+}
+
+impl<P: Plugin> Drop for InProcessHost<P> {
+    fn drop(&mut self) {
+        self.plugin.deactivate();
+    }
+}
+
+impl<P: Plugin> HostShared<P> {
+    /// Whether the plugin's bypass parameter is currently enabled.
+    fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
+    /// Drop the pending tasks with a matching [`TaskOptions::tag`][crate::prelude::TaskOptions].
+    /// This doesn't allocate, so it can be called from the audio thread.
+    fn cancel_background_tasks(&self, tag: u32) {
+        for _ in 0..self.pending_tasks.len() {
+            match self.pending_tasks.pop() {
+                Some(task) if P::background_task_options(&task).tag == Some(tag) => (),
+                Some(task) => {
+                    // This can't fail since the task was just popped from the same queue
+                    let _ = self.pending_tasks.push(task);
+                }
+                None => break,
+            }
+        }
+    }
+}
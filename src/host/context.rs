@@ -0,0 +1,121 @@
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::HostShared;
+use crate::prelude::{
+    InitContext, Plugin, PluginApi, PluginNoteEvent, ProcessContext, ProcessMode, TrackInfo,
+    Transport,
+};
+use crate::wrapper::util::equal_temperament_frequency;
+
+/// An [`InitContext`] implementation for the in-process host.
+pub(crate) struct HostInitContext<'a, P: Plugin> {
+    pub(super) host: &'a HostShared<P>,
+}
+
+/// A [`ProcessContext`] implementation for the in-process host.
+pub(crate) struct HostProcessContext<'a, P: Plugin> {
+    pub(super) host: &'a HostShared<P>,
+    pub(super) input_events: &'a [PluginNoteEvent<P>],
+    // The current index in `input_events`, since the events are owned by the application
+    pub(super) input_events_idx: usize,
+    pub(super) output_events: &'a mut Vec<PluginNoteEvent<P>>,
+}
+
+impl<P: Plugin> InitContext<P> for HostInitContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Embedded
+    }
+
+    fn execute(&self, task: P::BackgroundTask) {
+        (self.host.task_executor)(task);
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.host.current_latency.store(samples, Ordering::SeqCst);
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+}
+
+impl<P: Plugin> ProcessContext<P> for HostProcessContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Embedded
+    }
+
+    fn execute_background(&self, task: P::BackgroundTask) {
+        let task_posted = self.host.pending_tasks.push(task).is_ok();
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn execute_gui(&self, task: P::BackgroundTask) {
+        // There is no GUI thread, so these tasks are run together with the background tasks
+        let task_posted = self.host.pending_tasks.push(task).is_ok();
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.host.cancel_background_tasks(tag);
+    }
+
+    #[inline]
+    fn transport(&self) -> &Transport {
+        &self.host.transport
+    }
+
+    fn process_mode(&self) -> ProcessMode {
+        self.host.buffer_config.process_mode
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        // The application is not a DAW, so there is no track
+        None
+    }
+
+    fn bypassed(&self) -> bool {
+        self.host.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.host.audio_io_layout.aux_input_ports.len()
+    }
+
+    fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
+        if self.input_events_idx < self.input_events.len() {
+            let event = self.input_events[self.input_events_idx].clone();
+            self.input_events_idx += 1;
+
+            Some(event)
+        } else {
+            None
+        }
+    }
+
+    fn send_event(&mut self, event: PluginNoteEvent<P>) {
+        self.output_events.push(event);
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.host.current_latency.store(samples, Ordering::SeqCst);
+    }
+
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
+        equal_temperament_frequency(note)
+    }
+
+    fn set_tail_seconds(&self, seconds: f32) {
+        self.host
+            .current_tail_seconds
+            .store(seconds, Ordering::SeqCst);
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, _handler: impl FnMut(T)) {
+        // Editors are not supported, so nothing can send messages to the audio thread
+    }
+}
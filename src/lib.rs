@@ -106,11 +106,15 @@ pub mod util;
 pub mod audio_setup;
 pub mod buffer;
 pub mod context;
+pub mod dsp;
 pub mod editor;
 mod event_loop;
+pub mod fuzz;
+pub mod host;
 pub mod midi;
 pub mod params;
 pub mod plugin;
+pub mod test;
 pub mod wrapper;
 
 // This is also re-exported from the prelude but since the other export entry points are macros and
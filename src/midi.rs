@@ -5,6 +5,7 @@ use midi_consts::channel_event as midi;
 use self::sysex::SysExMessage;
 use crate::prelude::Plugin;
 
+pub mod filter;
 pub mod sysex;
 
 pub use midi_consts::channel_event::control_change;
@@ -36,6 +37,59 @@ pub enum MidiConfig {
     MidiCCs,
 }
 
+/// A named note input or output port. Plugins can declare multiple ports through
+/// [`Plugin::MIDI_INPUT_PORTS`] and [`Plugin::MIDI_OUTPUT_PORTS`]. Events for the first port are
+/// read and written with [`ProcessContext::next_event()`][crate::prelude::ProcessContext::next_event()]
+/// and [`ProcessContext::send_event()`][crate::prelude::ProcessContext::send_event()]. The port
+/// specific variants of those functions can be used to handle the other ports. Only the CLAP
+/// wrapper currently exposes more than one note port. The other wrappers only use the first port.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotePort {
+    /// The port's name as shown by the host.
+    pub name: &'static str,
+    /// The event dialects this port can send or receive. The first dialect in the order of CLAP,
+    /// MIDI, MPE, and MIDI 2.0 is reported as the preferred dialect.
+    pub dialects: NoteDialects,
+}
+
+bitflags::bitflags! {
+    /// The event formats a [`NotePort`] supports. This is only relevant for CLAP plugins, since
+    /// CLAP lets the host and the plugin negotiate the format used for note events. Regardless of
+    /// the dialect, the plugin always receives and sends [`NoteEvent`]s.
+    #[repr(transparent)]
+    pub struct NoteDialects: u32 {
+        /// CLAP's own note and note expression events. This is needed for polyphonic modulation
+        /// and note expressions.
+        const CLAP = 1 << 0;
+        /// Raw MIDI 1.0 messages.
+        const MIDI = 1 << 1;
+        /// MIDI 1.0 messages using MPE.
+        const MIDI_MPE = 1 << 2;
+        /// MIDI 2.0 universal MIDI packets. This is only supported on input ports. MIDI 2.0 channel
+        /// voice messages are converted to their MIDI 1.0 equivalent [`NoteEvent`]s.
+        const MIDI2 = 1 << 3;
+    }
+}
+
+impl NotePort {
+    /// A port supporting both CLAP note events and MIDI. This is what NIH-plug used before ports
+    /// could be configured.
+    pub const fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            dialects: NoteDialects::from_bits_truncate(
+                NoteDialects::CLAP.bits() | NoteDialects::MIDI.bits(),
+            ),
+        }
+    }
+
+    /// Change the dialects supported by this port.
+    pub const fn with_dialects(mut self, dialects: NoteDialects) -> Self {
+        self.dialects = dialects;
+        self
+    }
+}
+
 // FIXME: Like the voice ID, channel and note number can also be omitted in CLAP. And instead of an
 //        Option, maybe this should use a dedicated type to more clearly indicate that missing
 //        values should be treated as wildcards.
@@ -413,6 +467,206 @@ impl<S> NoteEvent<S> {
     }
 }
 
+impl NoteEvent<()> {
+    /// Convert an event sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()] to the
+    /// plugin's event type. The event's timing is reset to the start of the buffer. Returns `None`
+    /// for SysEx events, since those cannot be sent from the editor.
+    pub(crate) fn into_gui_event<S>(self) -> Option<NoteEvent<S>> {
+        let event = match self {
+            NoteEvent::NoteOn {
+                voice_id,
+                channel,
+                note,
+                velocity,
+                ..
+            } => NoteEvent::NoteOn {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                velocity,
+            },
+            NoteEvent::NoteOff {
+                voice_id,
+                channel,
+                note,
+                velocity,
+                ..
+            } => NoteEvent::NoteOff {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                velocity,
+            },
+            NoteEvent::Choke {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => NoteEvent::Choke {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+            },
+            NoteEvent::VoiceTerminated {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => NoteEvent::VoiceTerminated {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+            },
+            NoteEvent::PolyModulation {
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+                ..
+            } => NoteEvent::PolyModulation {
+                timing: 0,
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+            },
+            NoteEvent::MonoAutomation {
+                poly_modulation_id,
+                normalized_value,
+                ..
+            } => NoteEvent::MonoAutomation {
+                timing: 0,
+                poly_modulation_id,
+                normalized_value,
+            },
+            NoteEvent::PolyPressure {
+                voice_id,
+                channel,
+                note,
+                pressure,
+                ..
+            } => NoteEvent::PolyPressure {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                pressure,
+            },
+            NoteEvent::PolyVolume {
+                voice_id,
+                channel,
+                note,
+                gain,
+                ..
+            } => NoteEvent::PolyVolume {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                gain,
+            },
+            NoteEvent::PolyPan {
+                voice_id,
+                channel,
+                note,
+                pan,
+                ..
+            } => NoteEvent::PolyPan {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                pan,
+            },
+            NoteEvent::PolyTuning {
+                voice_id,
+                channel,
+                note,
+                tuning,
+                ..
+            } => NoteEvent::PolyTuning {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                tuning,
+            },
+            NoteEvent::PolyVibrato {
+                voice_id,
+                channel,
+                note,
+                vibrato,
+                ..
+            } => NoteEvent::PolyVibrato {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                vibrato,
+            },
+            NoteEvent::PolyExpression {
+                voice_id,
+                channel,
+                note,
+                expression,
+                ..
+            } => NoteEvent::PolyExpression {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                expression,
+            },
+            NoteEvent::PolyBrightness {
+                voice_id,
+                channel,
+                note,
+                brightness,
+                ..
+            } => NoteEvent::PolyBrightness {
+                timing: 0,
+                voice_id,
+                channel,
+                note,
+                brightness,
+            },
+            NoteEvent::MidiChannelPressure {
+                channel, pressure, ..
+            } => NoteEvent::MidiChannelPressure {
+                timing: 0,
+                channel,
+                pressure,
+            },
+            NoteEvent::MidiPitchBend { channel, value, .. } => NoteEvent::MidiPitchBend {
+                timing: 0,
+                channel,
+                value,
+            },
+            NoteEvent::MidiCC {
+                channel, cc, value, ..
+            } => NoteEvent::MidiCC {
+                timing: 0,
+                channel,
+                cc,
+                value,
+            },
+            NoteEvent::MidiProgramChange {
+                channel, program, ..
+            } => NoteEvent::MidiProgramChange {
+                timing: 0,
+                channel,
+                program,
+            },
+            NoteEvent::MidiSysEx { .. } => return None,
+        };
+
+        Some(event)
+    }
+}
+
 impl<S: SysExMessage> NoteEvent<S> {
     /// Parse MIDI into a [`NoteEvent`]. Supports both basic three bytes messages as well as SysEx.
     /// Will return `Err(event_type)` if the parsing failed.
@@ -524,6 +778,94 @@ impl<S: SysExMessage> NoteEvent<S> {
         }
     }
 
+    /// Parse a MIDI 2.0 universal MIDI packet into a [`NoteEvent`]. Both MIDI 1.0 channel voice
+    /// messages and MIDI 2.0 channel voice messages are supported. The higher resolution MIDI 2.0
+    /// values are converted to NIH-plug's floating point representation, and per-note attributes
+    /// are ignored. Will return `Err(status)` with the packet's message type in the upper nibble
+    /// and its status in the lower nibble if the packet could not be parsed.
+    pub fn from_midi2(timing: u32, ump: &[u32]) -> Result<Self, u8> {
+        let word = ump.first().copied().unwrap_or_default();
+        let message_type = (word >> 28) as u8;
+        let status = ((word >> 20) & 0xf) as u8;
+        let channel = ((word >> 16) & 0xf) as u8;
+        let index = ((word >> 8) & 0x7f) as u8;
+        let data = ump.get(1).copied().unwrap_or_default();
+
+        match message_type {
+            // MIDI 1.0 channel voice messages are simply wrapped in a packet
+            0x2 => {
+                return NoteEvent::from_midi(
+                    timing,
+                    &[(word >> 16) as u8, (word >> 8) as u8, word as u8],
+                )
+            }
+            0x4 => match status << 4 {
+                midi::NOTE_ON => {
+                    // Unlike in MIDI 1.0, a zero velocity note on is still a note on
+                    return Ok(NoteEvent::NoteOn {
+                        timing,
+                        voice_id: None,
+                        channel,
+                        note: index,
+                        velocity: (data >> 16) as f32 / u16::MAX as f32,
+                    });
+                }
+                midi::NOTE_OFF => {
+                    return Ok(NoteEvent::NoteOff {
+                        timing,
+                        voice_id: None,
+                        channel,
+                        note: index,
+                        velocity: (data >> 16) as f32 / u16::MAX as f32,
+                    });
+                }
+                midi::POLYPHONIC_KEY_PRESSURE => {
+                    return Ok(NoteEvent::PolyPressure {
+                        timing,
+                        voice_id: None,
+                        channel,
+                        note: index,
+                        pressure: data as f32 / u32::MAX as f32,
+                    });
+                }
+                midi::CONTROL_CHANGE => {
+                    return Ok(NoteEvent::MidiCC {
+                        timing,
+                        channel,
+                        cc: index,
+                        value: data as f32 / u32::MAX as f32,
+                    });
+                }
+                midi::PROGRAM_CHANGE => {
+                    return Ok(NoteEvent::MidiProgramChange {
+                        timing,
+                        channel,
+                        program: ((data >> 24) & 0x7f) as u8,
+                    });
+                }
+                midi::CHANNEL_KEY_PRESSURE => {
+                    return Ok(NoteEvent::MidiChannelPressure {
+                        timing,
+                        channel,
+                        pressure: data as f32 / u32::MAX as f32,
+                    });
+                }
+                midi::PITCH_BEND_CHANGE => {
+                    return Ok(NoteEvent::MidiPitchBend {
+                        timing,
+                        channel,
+                        value: data as f32 / u32::MAX as f32,
+                    });
+                }
+                _ => (),
+            },
+            _ => (),
+        }
+
+        nih_trace!("Unhandled MIDI 2.0 packet: {word:#010x}");
+        Err((message_type << 4) | status)
+    }
+
     /// Create a MIDI message from this note event. Returns `None` if this even does not have a
     /// direct MIDI equivalent. `PolyPressure` will be converted to polyphonic key pressure, but the
     /// other polyphonic note expression types will not be converted to MIDI CC messages.
@@ -626,30 +968,35 @@ impl<S: SysExMessage> NoteEvent<S> {
         }
     }
 
+    /// A mutable reference to this event's timing. Used to move events between blocks when a
+    /// wrapper or the test harness splits the buffer.
+    pub(crate) fn timing_mut(&mut self) -> &mut u32 {
+        match self {
+            NoteEvent::NoteOn { timing, .. } => timing,
+            NoteEvent::NoteOff { timing, .. } => timing,
+            NoteEvent::Choke { timing, .. } => timing,
+            NoteEvent::VoiceTerminated { timing, .. } => timing,
+            NoteEvent::PolyModulation { timing, .. } => timing,
+            NoteEvent::MonoAutomation { timing, .. } => timing,
+            NoteEvent::PolyPressure { timing, .. } => timing,
+            NoteEvent::PolyVolume { timing, .. } => timing,
+            NoteEvent::PolyPan { timing, .. } => timing,
+            NoteEvent::PolyTuning { timing, .. } => timing,
+            NoteEvent::PolyVibrato { timing, .. } => timing,
+            NoteEvent::PolyExpression { timing, .. } => timing,
+            NoteEvent::PolyBrightness { timing, .. } => timing,
+            NoteEvent::MidiChannelPressure { timing, .. } => timing,
+            NoteEvent::MidiPitchBend { timing, .. } => timing,
+            NoteEvent::MidiCC { timing, .. } => timing,
+            NoteEvent::MidiProgramChange { timing, .. } => timing,
+            NoteEvent::MidiSysEx { timing, .. } => timing,
+        }
+    }
+
     /// Subtract a sample offset from this event's timing, needed to compensate for the block
     /// splitting in the VST3 wrapper implementation because all events have to be read upfront.
-    #[cfg_attr(not(feature = "vst3"), allow(dead_code))]
     pub(crate) fn subtract_timing(&mut self, samples: u32) {
-        match self {
-            NoteEvent::NoteOn { timing, .. } => *timing -= samples,
-            NoteEvent::NoteOff { timing, .. } => *timing -= samples,
-            NoteEvent::Choke { timing, .. } => *timing -= samples,
-            NoteEvent::VoiceTerminated { timing, .. } => *timing -= samples,
-            NoteEvent::PolyModulation { timing, .. } => *timing -= samples,
-            NoteEvent::MonoAutomation { timing, .. } => *timing -= samples,
-            NoteEvent::PolyPressure { timing, .. } => *timing -= samples,
-            NoteEvent::PolyVolume { timing, .. } => *timing -= samples,
-            NoteEvent::PolyPan { timing, .. } => *timing -= samples,
-            NoteEvent::PolyTuning { timing, .. } => *timing -= samples,
-            NoteEvent::PolyVibrato { timing, .. } => *timing -= samples,
-            NoteEvent::PolyExpression { timing, .. } => *timing -= samples,
-            NoteEvent::PolyBrightness { timing, .. } => *timing -= samples,
-            NoteEvent::MidiChannelPressure { timing, .. } => *timing -= samples,
-            NoteEvent::MidiPitchBend { timing, .. } => *timing -= samples,
-            NoteEvent::MidiCC { timing, .. } => *timing -= samples,
-            NoteEvent::MidiProgramChange { timing, .. } => *timing -= samples,
-            NoteEvent::MidiSysEx { timing, .. } => *timing -= samples,
-        }
+        *self.timing_mut() -= samples;
     }
 }
 
@@ -696,6 +1043,72 @@ mod tests {
         assert_eq!(roundtrip_basic_event(event), event);
     }
 
+    #[test]
+    fn test_gui_event_conversion() {
+        let event = NoteEvent::<()>::NoteOn {
+            timing: TIMING,
+            voice_id: Some(3),
+            channel: 1,
+            note: 2,
+            velocity: 0.5,
+        };
+
+        assert_eq!(
+            event.into_gui_event::<()>(),
+            Some(NoteEvent::NoteOn {
+                timing: 0,
+                voice_id: Some(3),
+                channel: 1,
+                note: 2,
+                velocity: 0.5,
+            })
+        );
+        assert_eq!(
+            NoteEvent::<()>::MidiSysEx {
+                timing: TIMING,
+                message: ()
+            }
+            .into_gui_event::<()>(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_midi2_conversion() {
+        // A MIDI 2.0 note on for note 60 on channel 2 with full velocity
+        assert_eq!(
+            NoteEvent::<()>::from_midi2(TIMING, &[0x4091_3c00, 0xffff_0000]),
+            Ok(NoteEvent::NoteOn {
+                timing: TIMING,
+                voice_id: None,
+                channel: 1,
+                note: 60,
+                velocity: 1.0,
+            })
+        );
+        // And the same note as a MIDI 1.0 message wrapped in a packet
+        assert_eq!(
+            NoteEvent::<()>::from_midi2(TIMING, &[0x2091_3c7f]),
+            Ok(NoteEvent::NoteOn {
+                timing: TIMING,
+                voice_id: None,
+                channel: 1,
+                note: 60,
+                velocity: 1.0,
+            })
+        );
+        assert_eq!(
+            NoteEvent::<()>::from_midi2(TIMING, &[0x40b1_0700, 0x0000_0000]),
+            Ok(NoteEvent::MidiCC {
+                timing: TIMING,
+                channel: 1,
+                cc: 7,
+                value: 0.0,
+            })
+        );
+        assert!(NoteEvent::<()>::from_midi2(TIMING, &[0x1000_0000]).is_err());
+    }
+
     #[test]
     fn test_poly_pressure_midi_conversion() {
         let event = NoteEvent::<()>::PolyPressure {
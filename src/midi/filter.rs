@@ -0,0 +1,307 @@
+//! Composable transformations for a plugin's incoming note events, like transposing notes or
+//! filtering MIDI channels. This lets instruments offer MIDI effect style preprocessing without
+//! having to implement it in every plugin's process function.
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use super::{NoteEvent, PluginNoteEvent};
+use crate::prelude::{Plugin, ProcessContext, Transport};
+
+/// A single transformation in a [`NoteEventFilterChain`].
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NoteEventFilter {
+    /// Shift all notes by a number of semitones. Notes that end up outside of the MIDI note range
+    /// are dropped.
+    Transpose { semitones: i8 },
+    /// Only let through events on the MIDI channels whose bits are set, where the least significant
+    /// bit corresponds to the first channel. Events without a channel, like polyphonic modulation,
+    /// are always let through.
+    Channels { mask: u16 },
+    /// Reshape note on velocities. The velocity is first raised to the power of `exponent`, and
+    /// then scaled to the `[min, max]` range. An exponent above 1 makes soft notes softer.
+    VelocityCurve { exponent: f32, min: f32, max: f32 },
+    /// Move note on and note off events to the nearest multiple of `grid_beats` quarter notes
+    /// while the transport is playing. Events are only moved within the current block, so they may
+    /// end up slightly out of order. Those events are handled as soon as the plugin reads them.
+    Quantize { grid_beats: f64 },
+}
+
+/// An ordered list of [`NoteEventFilter`]s. Each filter is applied to the output of the previous
+/// one. Use [`SharedNoteEventFilterChain`] to configure the chain from the editor and to persist it
+/// as part of the plugin's state.
+///
+/// In the plugin's process function, use [`next_event()`][Self::next_event()] instead of
+/// [`ProcessContext::next_event()`] to receive the filtered events:
+///
+/// ```ignore
+/// let filters = self.params.note_filters.load();
+/// let mut next_event = filters.next_event(context, buffer.samples());
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct NoteEventFilterChain {
+    filters: Vec<NoteEventFilter>,
+}
+
+/// A [`NoteEventFilterChain`] that can be edited from the GUI and used from the audio thread at
+/// the same time. Store this in an `Arc<SharedNoteEventFilterChain>` on your parameters struct
+/// with a `#[persist = "key"]` attribute to save the chain as part of the plugin's state.
+#[derive(Debug)]
+pub struct SharedNoteEventFilterChain {
+    chain: ArcSwap<NoteEventFilterChain>,
+    /// The chain that was replaced by the last update. See
+    /// [`SharedCurve`][crate::util::curve::SharedCurve] for why this is needed.
+    retired: Mutex<Option<Arc<NoteEventFilterChain>>>,
+}
+
+impl NoteEventFilter {
+    /// Apply the filter to an event. `block_len` is the length of the current block in samples.
+    /// Returns `false` if the event should be dropped.
+    pub fn apply<S>(
+        &self,
+        event: &mut NoteEvent<S>,
+        transport: &Transport,
+        block_len: usize,
+    ) -> bool {
+        match *self {
+            NoteEventFilter::Transpose { semitones } => match note_mut(event) {
+                Some(note) => match note.checked_add_signed(semitones) {
+                    Some(transposed) if transposed <= 127 => {
+                        *note = transposed;
+                        true
+                    }
+                    _ => false,
+                },
+                None => true,
+            },
+            NoteEventFilter::Channels { mask } => match event.channel() {
+                Some(channel) => channel < 16 && mask & (1 << channel) != 0,
+                None => true,
+            },
+            NoteEventFilter::VelocityCurve { exponent, min, max } => {
+                if let NoteEvent::NoteOn { velocity, .. } = event {
+                    *velocity = (min + (max - min) * velocity.powf(exponent)).clamp(0.0, 1.0);
+                }
+
+                true
+            }
+            NoteEventFilter::Quantize { grid_beats } => {
+                if matches!(event, NoteEvent::NoteOn { .. } | NoteEvent::NoteOff { .. }) {
+                    if let Some(timing) =
+                        quantized_timing(event.timing(), grid_beats, transport, block_len)
+                    {
+                        *event.timing_mut() = timing;
+                    }
+                }
+
+                true
+            }
+        }
+    }
+}
+
+impl NoteEventFilterChain {
+    /// Create a chain from a list of filters. The filters are applied in order.
+    pub fn new(filters: Vec<NoteEventFilter>) -> Self {
+        Self { filters }
+    }
+
+    /// The chain's filters, in the order they're applied.
+    pub fn filters(&self) -> &[NoteEventFilter] {
+        &self.filters
+    }
+
+    /// Mutable access to the chain's filters, for instance to add, remove, or reorder filters.
+    pub fn filters_mut(&mut self) -> &mut Vec<NoteEventFilter> {
+        &mut self.filters
+    }
+
+    /// Apply all filters to an event. Returns `None` if one of the filters dropped the event.
+    pub fn process<S>(
+        &self,
+        mut event: NoteEvent<S>,
+        transport: &Transport,
+        block_len: usize,
+    ) -> Option<NoteEvent<S>> {
+        self.filters
+            .iter()
+            .all(|filter| filter.apply(&mut event, transport, block_len))
+            .then_some(event)
+    }
+
+    /// Read the next event from the context that makes it through the filter chain. This can be
+    /// used as a drop-in replacement for [`ProcessContext::next_event()`]. `block_len` is the
+    /// length of the buffer passed to the process function. This is realtime-safe.
+    pub fn next_event<P: Plugin>(
+        &self,
+        context: &mut impl ProcessContext<P>,
+        block_len: usize,
+    ) -> Option<PluginNoteEvent<P>> {
+        loop {
+            let event = context.next_event()?;
+            if let Some(event) = self.process(event, context.transport(), block_len) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+impl Default for SharedNoteEventFilterChain {
+    fn default() -> Self {
+        Self::new(NoteEventFilterChain::default())
+    }
+}
+
+impl SharedNoteEventFilterChain {
+    /// Create a shared filter chain with an initial value.
+    pub fn new(chain: NoteEventFilterChain) -> Self {
+        Self {
+            chain: ArcSwap::from_pointee(chain),
+            retired: Mutex::new(None),
+        }
+    }
+
+    /// Get the current filter chain. This is realtime-safe, so it can be used from the audio
+    /// thread. The returned guard should not be held on to for longer than a single processing
+    /// cycle.
+    #[inline]
+    pub fn load(&self) -> arc_swap::Guard<Arc<NoteEventFilterChain>> {
+        self.chain.load()
+    }
+
+    /// Replace the filter chain. This allocates, so it should only be called from the GUI or the
+    /// main thread.
+    pub fn store(&self, chain: NoteEventFilterChain) {
+        let old_chain = self.chain.swap(Arc::new(chain));
+        *self.retired.lock() = Some(old_chain);
+    }
+
+    /// Modify a copy of the current filter chain and then replace the chain with it. This
+    /// allocates, so it should only be called from the GUI or the main thread.
+    pub fn update<R>(&self, f: impl FnOnce(&mut NoteEventFilterChain) -> R) -> R {
+        let mut chain = NoteEventFilterChain::clone(&self.chain.load());
+        let result = f(&mut chain);
+        self.store(chain);
+
+        result
+    }
+}
+
+/// A mutable reference to the event's note number, if it has one.
+fn note_mut<S>(event: &mut NoteEvent<S>) -> Option<&mut u8> {
+    match event {
+        NoteEvent::NoteOn { note, .. }
+        | NoteEvent::NoteOff { note, .. }
+        | NoteEvent::Choke { note, .. }
+        | NoteEvent::VoiceTerminated { note, .. }
+        | NoteEvent::PolyPressure { note, .. }
+        | NoteEvent::PolyVolume { note, .. }
+        | NoteEvent::PolyPan { note, .. }
+        | NoteEvent::PolyTuning { note, .. }
+        | NoteEvent::PolyVibrato { note, .. }
+        | NoteEvent::PolyExpression { note, .. }
+        | NoteEvent::PolyBrightness { note, .. } => Some(note),
+        _ => None,
+    }
+}
+
+/// Compute the timing of the grid line nearest to an event at `timing`, clamped to the current
+/// block. Returns `None` if the transport is not playing or if the host didn't provide enough
+/// information to compute the song position.
+fn quantized_timing(
+    timing: u32,
+    grid_beats: f64,
+    transport: &Transport,
+    block_len: usize,
+) -> Option<u32> {
+    if !transport.playing || grid_beats <= 0.0 || block_len == 0 {
+        return None;
+    }
+
+    let samples_per_beat = transport.sample_rate as f64 * 60.0 / transport.tempo?;
+    let block_start_beats = transport.pos_beats()?;
+    let event_beats = block_start_beats + timing as f64 / samples_per_beat;
+    let grid_line_beats = (event_beats / grid_beats).round() * grid_beats;
+    let grid_line_timing = ((grid_line_beats - block_start_beats) * samples_per_beat).round();
+
+    Some(grid_line_timing.clamp(0.0, (block_len - 1) as f64) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_on(timing: u32, channel: u8, note: u8, velocity: f32) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing,
+            voice_id: None,
+            channel,
+            note,
+            velocity,
+        }
+    }
+
+    #[test]
+    fn test_transpose_and_channels() {
+        let transport = Transport::new(44_100.0);
+        let chain = NoteEventFilterChain::new(vec![
+            NoteEventFilter::Channels { mask: 0b10 },
+            NoteEventFilter::Transpose { semitones: 12 },
+        ]);
+
+        assert_eq!(
+            chain.process(note_on(0, 1, 60, 1.0), &transport, 128),
+            Some(note_on(0, 1, 72, 1.0))
+        );
+        assert_eq!(chain.process(note_on(0, 0, 60, 1.0), &transport, 128), None);
+        assert_eq!(
+            chain.process(note_on(0, 1, 120, 1.0), &transport, 128),
+            None
+        );
+    }
+
+    #[test]
+    fn test_velocity_curve() {
+        let transport = Transport::new(44_100.0);
+        let filter = NoteEventFilter::VelocityCurve {
+            exponent: 2.0,
+            min: 0.2,
+            max: 1.0,
+        };
+
+        let mut event = note_on(0, 0, 60, 0.5);
+        assert!(filter.apply(&mut event, &transport, 128));
+        match event {
+            NoteEvent::NoteOn { velocity, .. } => approx::assert_relative_eq!(velocity, 0.4),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_quantize() {
+        let mut transport = Transport::new(48_000.0);
+        transport.playing = true;
+        transport.tempo = Some(120.0);
+        transport.pos_beats = Some(0.9);
+
+        // At 120 BPM a beat lasts 24000 samples, so the next beat starts 2400 samples into the
+        // block
+        let filter = NoteEventFilter::Quantize { grid_beats: 1.0 };
+        let mut event = note_on(2000, 0, 60, 1.0);
+        assert!(filter.apply(&mut event, &transport, 4096));
+        assert_eq!(event.timing(), 2400);
+
+        // Events are never moved outside of the current block
+        let mut event = note_on(100, 0, 60, 1.0);
+        assert!(filter.apply(&mut event, &transport, 1024));
+        assert_eq!(event.timing(), 1023);
+
+        transport.playing = false;
+        let mut event = note_on(2000, 0, 60, 1.0);
+        assert!(filter.apply(&mut event, &transport, 4096));
+        assert_eq!(event.timing(), 2000);
+    }
+}
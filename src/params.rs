@@ -1,23 +1,28 @@
-//! NIH-plug can handle floating point, integer, boolean, and enum parameters. Parameters are
-//! managed by creating a struct deriving the [`Params`][Params] trait containing fields
-//! for those parameter types, and then returning a reference to that object from your
-//! [`Plugin::params()`][crate::prelude::Plugin::params()] method. See the `Params` trait for more
-//! information.
+//! NIH-plug can handle floating point, integer, boolean, and enum parameters, as well as macro
+//! parameters that control other parameters. Parameters are managed by creating a struct deriving
+//! the [`Params`][Params] trait containing fields for those parameter types, and then returning a
+//! reference to that object from your [`Plugin::params()`][crate::prelude::Plugin::params()]
+//! method. See the `Params` trait for more information.
 
 use std::collections::BTreeMap;
 use std::fmt::{Debug, Display};
 use std::sync::Arc;
 
 use self::internals::ParamPtr;
+use crate::wrapper::state::StateSaveContext;
 
 // The proc-macro for deriving `Params`
 pub use nih_plug_derive::Params;
 
 // Parameter types
 mod boolean;
+mod dynamic;
 pub mod enums;
 mod float;
 mod integer;
+mod macro_param;
+mod output;
+mod text;
 
 pub mod internals;
 pub mod persist;
@@ -25,9 +30,13 @@ pub mod range;
 pub mod smoothing;
 
 pub use boolean::BoolParam;
+pub use dynamic::{DynamicParam, DynamicParamLayout};
 pub use enums::EnumParam;
 pub use float::FloatParam;
 pub use integer::IntParam;
+pub use macro_param::{MacroMapping, MacroParam};
+pub use output::OutputParam;
+pub use text::{PathParam, StringParam};
 
 bitflags::bitflags! {
     /// Flags for controlling a parameter's behavior.
@@ -37,7 +46,8 @@ bitflags::bitflags! {
         /// When applied to a [`BoolParam`], this will cause the parameter to be linked to the
         /// host's bypass control. Only a single parameter can be marked as a bypass parameter. If
         /// you don't have a bypass parameter, then NIH-plug will add one for you. You will need to
-        /// implement this yourself if your plugin introduces latency.
+        /// implement this yourself if your plugin introduces latency, unless
+        /// [`Plugin::MANAGED_BYPASS`][crate::prelude::Plugin::MANAGED_BYPASS] is enabled.
         const BYPASS = 1 << 0;
         /// The parameter cannot be changed from an automation lane. The parameter can however still
         /// be manually changed by the user from either the plugin's own GUI or from the host's
@@ -50,9 +60,25 @@ bitflags::bitflags! {
         /// Don't show this parameter when generating a generic UI for the plugin using one of
         /// NIH-plug's generic UI widgets.
         const HIDE_IN_GENERIC_UI = 1 << 3;
+        /// The parameter can't be changed by the host or by the user from the host's generic UI.
+        /// This is useful for exposing values computed by the plugin, like a meter or a detected
+        /// pitch, to the host. This also implies `NON_AUTOMATABLE`. The plugin can still change the
+        /// parameter from its editor GUI.
+        const READ_ONLY = 1 << 4;
     }
 }
 
+/// How the parameters in a link group follow each other when one of them is changed from the
+/// plugin's editor. See the `#[linked(...)]` attribute on the [`Params`] trait.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamLinkMode {
+    /// All parameters in the group are set to the same normalized value.
+    Mirror,
+    /// All parameters in the group are moved by the same normalized amount, preserving the offsets
+    /// between them.
+    Relative,
+}
+
 // See https://rust-lang.github.io/api-guidelines/future-proofing.html for more information
 mod sealed {
     /// Dummy trait to prevent [`Param`] from being implemented outside of NIH-plug. This is not
@@ -249,6 +275,45 @@ pub(crate) trait ParamMut: Param {
 /// with the `#[persist = "key"]` attribute containing types that can be serialized and deserialized
 /// with [Serde](https://serde.rs/).
 ///
+/// ## `#[persist(key = "key", exclude_from_presets)]`
+///
+/// The same as `#[persist = "key"]`, but the field is not stored when the host saves the plugin's
+/// state as a preset. This is useful for per-instance data like recorded audio, which should be
+/// saved with the project but not with presets. Hosts that don't say why they're saving the state
+/// will always include the field.
+///
+/// ## `#[persist(key = "key", with = "module")]`
+///
+/// Serialize the field using `module::serialize()` and `module::deserialize()` instead of JSON.
+/// These functions have the same signatures as [`persist::serialize_field()`] and
+/// [`persist::deserialize_field()`]. This can be used to store a field in a more compact format, or
+/// to stay compatible with data saved by an older version of the plugin.
+///
+/// ## `#[persist(key = "key", blob)]`
+///
+/// Store a [`PersistentBlob`][persist::PersistentBlob] as raw binary data instead of as a JSON
+/// string. This is much more efficient for large fields like recorded audio or wavetables, since
+/// the data is stored as is in a binary section after the state's JSON data instead of being
+/// encoded as JSON. This can be combined with `exclude_from_presets`.
+///
+/// ## `#[persist_notifier]`
+///
+/// Mark a [`PersistNotifier`][persist::PersistNotifier] field that should be notified whenever the
+/// plugin's state has been loaded. This can be used to (re)load files referenced by other
+/// persistent fields on a background thread.
+///
+/// ## `#[linked(group = "name", mode = "mirror")]`
+///
+/// This can be added to a parameter field next to its `#[id = "stable"]` attribute to link it to
+/// all other parameters with the same link group name, including parameters from nested structs.
+/// When one of these parameters is changed from the plugin's editor, the wrapper also changes the
+/// other parameters in the group, complete with their own automation gestures. `mode` is either
+/// `"mirror"` to give all parameters the same normalized value, or `"relative"` to move them by the
+/// same normalized amount. See [`ParamLinkMode`]. This is useful for stereo-linked channel controls
+/// and mid/side pairs. Linking a parameter declared in a `#[nested(array)]` struct links it with
+/// the same parameter in every other array element. The attribute is called `linked` because
+/// `link` is already a built-in Rust attribute.
+///
 /// ## `#[nested]`, `#[nested(group_name = "group name")]`
 ///
 /// Finally, the `Params` object may include parameters from other objects. Setting a group name is
@@ -299,8 +364,12 @@ pub unsafe trait Params: 'static + Send + Sync {
 
     /// Serialize all fields marked with `#[persist = "stable_name"]` into a hash map containing
     /// JSON-representations of those fields so they can be written to the plugin's state and
-    /// recalled later. This uses [`persist::serialize_field()`] under the hood.
-    fn serialize_fields(&self) -> BTreeMap<String, String> {
+    /// recalled later. This uses [`persist::serialize_field()`] under the hood. `context` describes
+    /// why the state is being saved. Fields marked with `#[persist(key = "stable_name",
+    /// exclude_from_presets)]` are left out when saving a [`StateSaveContext::Preset`]. Those fields
+    /// keep their current values when a preset without them is loaded.
+    #[allow(unused_variables)]
+    fn serialize_fields(&self, context: StateSaveContext) -> BTreeMap<String, String> {
         BTreeMap::new()
     }
 
@@ -311,6 +380,50 @@ pub unsafe trait Params: 'static + Send + Sync {
     /// [`persist::deserialize_field()`] under the hood.
     #[allow(unused_variables)]
     fn deserialize_fields(&self, serialized: &BTreeMap<String, String>) {}
+
+    /// Serialize all fields marked with `#[persist(key = "stable_name", blob)]` into a map
+    /// containing the fields' binary data. These are stored in the plugin's state separately from
+    /// the JSON data. See [`serialize_fields()`][Self::serialize_fields()] for the meaning of
+    /// `context`.
+    #[allow(unused_variables)]
+    fn serialize_blobs(&self, context: StateSaveContext) -> BTreeMap<String, Vec<u8>> {
+        BTreeMap::new()
+    }
+
+    /// Restore all fields marked with `#[persist(key = "stable_name", blob)]` from a map created by
+    /// [`serialize_blobs()`][Self::serialize_blobs()]. These fields should implement
+    /// [`persist::PersistentBlob`].
+    #[allow(unused_variables)]
+    fn deserialize_blobs(&self, serialized: &BTreeMap<String, Vec<u8>>) {}
+
+    /// The [`revision()`][persist::PersistentBlob::revision()] for every field that would be
+    /// included in [`serialize_blobs()`][Self::serialize_blobs()] with the same `context`, using the
+    /// same keys. The wrappers use this to decide whether previously serialized blobs can be
+    /// reused.
+    #[allow(unused_variables)]
+    fn blob_revisions(&self, context: StateSaveContext) -> BTreeMap<String, Option<u64>> {
+        BTreeMap::new()
+    }
+
+    /// Called after the plugin's state has been loaded, once all persistent fields have been
+    /// restored. This notifies all fields marked with `#[persist_notifier]`, including those in
+    /// nested structs. See [`persist::PersistNotifier`].
+    fn fields_loaded(&self) {}
+
+    /// Run the callbacks registered on the fields marked with `#[persist_notifier]`, including
+    /// those in nested structs, if the state has been loaded since they last ran. The wrappers call
+    /// this on their background thread after [`fields_loaded()`][Self::fields_loaded()]. See
+    /// [`persist::PersistNotifier`].
+    fn run_load_callbacks(&self) {}
+
+    /// Returns every linked parameter as a `(param_ptr, link_group, mode)` triple. Parameters with
+    /// the same link group are changed together when one of them is changed from the editor. The
+    /// derive macro does this for every parameter field marked with `#[linked(...)]`, and it also
+    /// includes the links from nested child `Params` structs. Link group names are not prefixed for
+    /// nested structs.
+    fn param_links(&self) -> Vec<(ParamPtr, String, ParamLinkMode)> {
+        Vec::new()
+    }
 }
 
 /// This may be useful when building generic UIs using nested `Params` objects.
@@ -319,11 +432,35 @@ unsafe impl<P: Params> Params for Arc<P> {
         self.as_ref().param_map()
     }
 
-    fn serialize_fields(&self) -> BTreeMap<String, String> {
-        self.as_ref().serialize_fields()
+    fn serialize_fields(&self, context: StateSaveContext) -> BTreeMap<String, String> {
+        self.as_ref().serialize_fields(context)
     }
 
     fn deserialize_fields(&self, serialized: &BTreeMap<String, String>) {
         self.as_ref().deserialize_fields(serialized)
     }
+
+    fn serialize_blobs(&self, context: StateSaveContext) -> BTreeMap<String, Vec<u8>> {
+        self.as_ref().serialize_blobs(context)
+    }
+
+    fn deserialize_blobs(&self, serialized: &BTreeMap<String, Vec<u8>>) {
+        self.as_ref().deserialize_blobs(serialized)
+    }
+
+    fn blob_revisions(&self, context: StateSaveContext) -> BTreeMap<String, Option<u64>> {
+        self.as_ref().blob_revisions(context)
+    }
+
+    fn fields_loaded(&self) {
+        self.as_ref().fields_loaded()
+    }
+
+    fn run_load_callbacks(&self) {
+        self.as_ref().run_load_callbacks()
+    }
+
+    fn param_links(&self) -> Vec<(ParamPtr, String, ParamLinkMode)> {
+        self.as_ref().param_links()
+    }
 }
@@ -304,7 +304,8 @@ impl BoolParam {
     /// Mark this parameter as a bypass parameter. Plugin hosts can integrate this parameter into
     /// their UI. Only a single [`BoolParam`] can be a bypass parameter, and NIH-plug will add one
     /// if you don't create one yourself. You will need to implement this yourself if your plugin
-    /// introduces latency.
+    /// introduces latency, unless
+    /// [`Plugin::MANAGED_BYPASS`][crate::prelude::Plugin::MANAGED_BYPASS] is enabled.
     pub fn make_bypass(mut self) -> Self {
         self.flags.insert(ParamFlags::BYPASS);
         self
@@ -321,15 +322,44 @@ impl BoolParam {
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
-    pub fn hide(mut self) -> Self {
-        self.flags.insert(ParamFlags::HIDDEN);
+    pub fn hide_from_host(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::HIDDEN | ParamFlags::NON_AUTOMATABLE);
         self
     }
 
     /// Don't show this parameter when generating a generic UI for the plugin using one of
     /// NIH-plug's generic UI widgets.
-    pub fn hide_in_generic_ui(mut self) -> Self {
+    pub fn hide_from_generic_ui(mut self) -> Self {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as an internal parameter that is only changed from the plugin's own
+    /// editor GUI. This combines [`hide_from_host()`][Self::hide_from_host()] and
+    /// [`hide_from_generic_ui()`][Self::hide_from_generic_ui()]. The parameter can still be
+    /// smoothed, and its value is still stored as part of the plugin's state.
+    pub fn internal(self) -> Self {
+        self.hide_from_host().hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as read-only. The host can display the parameter's value, but it cannot
+    /// change it. This also implies `NON_AUTOMATABLE`. Setting this does not prevent you from
+    /// changing the parameter in the plugin's editor GUI.
+    pub fn read_only(mut self) -> Self {
+        self.flags.insert(ParamFlags::READ_ONLY);
+        self
+    }
 }
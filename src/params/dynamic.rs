@@ -0,0 +1,362 @@
+//! Generic parameter slots that can be relabeled at runtime.
+
+use arc_swap::ArcSwap;
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use super::internals::ParamPtr;
+use super::range::FloatRange;
+use super::smoothing::SmoothingStyle;
+use super::{FloatParam, Param, ParamFlags, ParamMut};
+use crate::formatters;
+
+/// A generic parameter slot whose name, unit, and range can be changed at runtime. Plugin APIs
+/// require the list of parameters to be fixed, so modular plugins can instead declare a pool of
+/// these slots up front and assign them to their modules as needed. Every slot has a fixed,
+/// generic name like `Slot 1` that is used as the fallback name. The slot's current
+/// [`DynamicParamLayout`] determines what the parameter looks like to the host and to the plugin.
+///
+/// The host sees a continuous parameter with a normalized `[0, 1]` range, and the normalized value
+/// is what gets stored in the plugin's state. Changing the layout keeps the normalized value, so
+/// the plain value will change along with the range. The layouts themselves are not stored in the
+/// plugin's state. The plugin should restore them from its own persisted data, for instance from a
+/// `#[persist = "..."]` field describing the modules.
+///
+/// Use [`ParamSetter::set_dynamic_layout()`][crate::prelude::ParamSetter::set_dynamic_layout()]
+/// to change a slot's layout from the editor. This tells the host to rescan the parameters' names
+/// and values. This is supported by the CLAP and VST3 wrappers. Other plugin APIs only show the
+/// slot's fixed name in the host's UI.
+pub struct DynamicParam {
+    /// The slot's normalized value is stored in a regular float parameter with a `[0, 1]` range.
+    inner: FloatParam,
+    /// The slot's current layout. This is read from the audio thread, so it's swapped out
+    /// atomically as a whole.
+    layout: ArcSwap<DynamicParamLayout>,
+}
+
+/// Describes what a [`DynamicParam`] currently represents.
+#[derive(Debug, Clone)]
+pub struct DynamicParamLayout {
+    /// The parameter's name as shown in the host.
+    pub name: String,
+    /// The unit label appended to the parameter's value, including a leading space if needed. For
+    /// instance `" Hz"`.
+    pub unit: String,
+    /// The range of plain values.
+    pub range: FloatRange,
+    /// The default plain value.
+    pub default: f32,
+    /// The number of digits after the decimal point used when displaying the value.
+    pub digits: usize,
+}
+
+impl DynamicParamLayout {
+    /// Create a layout with a name, range and default value. The unit is empty and values are
+    /// displayed with two digits.
+    pub fn new(name: impl Into<String>, default: f32, range: FloatRange) -> Self {
+        range.assert_validity();
+
+        Self {
+            name: name.into(),
+            unit: String::new(),
+            range,
+            default,
+            digits: 2,
+        }
+    }
+
+    /// Set the unit label. See [`unit`][Self::unit].
+    pub fn with_unit(mut self, unit: impl Into<String>) -> Self {
+        self.unit = unit.into();
+        self
+    }
+
+    /// Set the number of digits after the decimal point used when displaying the value.
+    pub fn with_digits(mut self, digits: usize) -> Self {
+        self.digits = digits;
+        self
+    }
+}
+
+impl Display for DynamicParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let layout = self.layout.load();
+        write!(
+            f,
+            "{}{}",
+            formatters::format_f32_rounded(self.value(), layout.digits),
+            layout.unit
+        )
+    }
+}
+
+impl Debug for DynamicParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({}): {}",
+            self.name(),
+            self.layout.load().name,
+            &self
+        )
+    }
+}
+
+// `Params` can not be implemented outside of NIH-plug itself because `ParamPtr` is also closed
+impl super::Sealed for DynamicParam {}
+
+impl Param for DynamicParam {
+    type Plain = f32;
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn unit(&self) -> &'static str {
+        // The unit can change at runtime, so it's included in the formatted value instead
+        ""
+    }
+
+    fn poly_modulation_id(&self) -> Option<u32> {
+        None
+    }
+
+    #[inline]
+    fn modulated_plain_value(&self) -> Self::Plain {
+        self.preview_plain(self.inner.modulated_normalized_value())
+    }
+
+    #[inline]
+    fn modulated_normalized_value(&self) -> f32 {
+        self.inner.modulated_normalized_value()
+    }
+
+    #[inline]
+    fn unmodulated_plain_value(&self) -> Self::Plain {
+        self.preview_plain(self.inner.unmodulated_normalized_value())
+    }
+
+    #[inline]
+    fn unmodulated_normalized_value(&self) -> f32 {
+        self.inner.unmodulated_normalized_value()
+    }
+
+    #[inline]
+    fn default_plain_value(&self) -> Self::Plain {
+        self.layout.load().default
+    }
+
+    fn step_count(&self) -> Option<usize> {
+        // The host sees a fixed continuous range, since a changing step count would require the
+        // plugin to be deactivated first
+        None
+    }
+
+    fn previous_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.layout.load().range.previous_step(from, None, finer)
+    }
+
+    fn next_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.layout.load().range.next_step(from, None, finer)
+    }
+
+    fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
+        let layout = self.layout.load();
+        let value = formatters::format_f32_rounded(
+            layout.range.unnormalize(normalized.clamp(0.0, 1.0)),
+            layout.digits,
+        );
+        if include_unit {
+            format!("{}{}", value, layout.unit)
+        } else {
+            value
+        }
+    }
+
+    fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
+        let layout = self.layout.load();
+        let string = string.trim();
+        let string = string.strip_suffix(layout.unit.trim()).unwrap_or(string);
+
+        formatters::parse_f32(string).map(|plain| layout.range.normalize(plain))
+    }
+
+    #[inline]
+    fn preview_normalized(&self, plain: Self::Plain) -> f32 {
+        self.layout.load().range.normalize(plain)
+    }
+
+    #[inline]
+    fn preview_plain(&self, normalized: f32) -> Self::Plain {
+        self.layout
+            .load()
+            .range
+            .unnormalize(normalized.clamp(0.0, 1.0))
+    }
+
+    fn flags(&self) -> ParamFlags {
+        self.inner.flags()
+    }
+
+    fn as_ptr(&self) -> ParamPtr {
+        ParamPtr::DynamicParam(self as *const _ as *mut _)
+    }
+}
+
+impl ParamMut for DynamicParam {
+    fn set_plain_value(&self, plain: Self::Plain) -> bool {
+        self.inner.set_plain_value(self.preview_normalized(plain))
+    }
+
+    fn set_normalized_value(&self, normalized: f32) -> bool {
+        self.inner.set_normalized_value(normalized)
+    }
+
+    fn modulate_value(&self, modulation_offset: f32) -> bool {
+        self.inner.modulate_value(modulation_offset)
+    }
+
+    fn update_smoother(&self, sample_rate: f32, reset: bool) {
+        self.inner.update_smoother(sample_rate, reset)
+    }
+}
+
+impl DynamicParam {
+    /// Build a new [`DynamicParam`]. `slot_name` is the slot's fixed name, and `layout` is the
+    /// slot's initial layout. Use the other associated functions to modify the behavior of the
+    /// parameter.
+    pub fn new(slot_name: impl Into<String>, layout: DynamicParamLayout) -> Self {
+        let default_normalized = layout.range.normalize(layout.default);
+
+        Self {
+            inner: FloatParam::new(
+                slot_name,
+                default_normalized,
+                FloatRange::Linear { min: 0.0, max: 1.0 },
+            ),
+            layout: ArcSwap::from_pointee(layout),
+        }
+    }
+
+    /// The slot's current plain value, after monophonic modulation has been applied.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.modulated_plain_value()
+    }
+
+    /// Get the next smoothed plain value. The smoothing is done on the normalized values, so this
+    /// does not jump when the layout changes. See [`with_smoother()`][Self::with_smoother()].
+    #[inline]
+    pub fn smoothed_next(&self) -> f32 {
+        self.preview_plain(self.inner.smoothed.next())
+    }
+
+    /// The slot's current layout.
+    pub fn layout(&self) -> Arc<DynamicParamLayout> {
+        self.layout.load_full()
+    }
+
+    /// Replace the slot's layout. The normalized value is kept as is. This does not notify the host
+    /// about the change. Use
+    /// [`ParamSetter::set_dynamic_layout()`][crate::prelude::ParamSetter::set_dynamic_layout()]
+    /// for that instead.
+    pub fn set_layout(&self, layout: DynamicParamLayout) {
+        layout.range.assert_validity();
+        self.layout.store(Arc::new(layout));
+    }
+
+    /// Set the slot's initial layout.
+    pub fn with_layout(self, layout: DynamicParamLayout) -> Self {
+        self.set_layout(layout);
+        self
+    }
+
+    /// Set up a smoother for the slot's normalized value. See [`FloatParam::with_smoother()`].
+    pub fn with_smoother(mut self, style: SmoothingStyle) -> Self {
+        self.inner = self.inner.with_smoother(style);
+        self
+    }
+
+    /// Run a callback whenever this parameter's value changes. The argument passed to this function
+    /// is the parameter's new normalized value. This should not do anything expensive as it may be
+    /// called multiple times in rapid succession, and it can be run from both the GUI and the audio
+    /// thread.
+    pub fn with_callback(mut self, callback: Arc<dyn Fn(f32) + Send + Sync>) -> Self {
+        self.inner = self.inner.with_callback(callback);
+        self
+    }
+
+    /// Mark the parameter as non-automatable. See [`FloatParam::non_automatable()`].
+    pub fn non_automatable(mut self) -> Self {
+        self.inner = self.inner.non_automatable();
+        self
+    }
+
+    /// Hide the parameter in the host's generic UI. This is useful for slots that are not assigned
+    /// to a module. See [`FloatParam::hide_from_host()`].
+    pub fn hide_from_host(mut self) -> Self {
+        self.inner = self.inner.hide_from_host();
+        self
+    }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relabel_keeps_normalized_value() {
+        let param = DynamicParam::new(
+            "Slot 1",
+            DynamicParamLayout::new(
+                "Cutoff",
+                1000.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 2000.0,
+                },
+            )
+            .with_unit(" Hz"),
+        );
+        assert_eq!(param.modulated_normalized_value(), 0.5);
+        assert_eq!(param.value(), 1000.0);
+        assert_eq!(param.normalized_value_to_string(0.5, true), "1000.00 Hz");
+
+        param.set_layout(DynamicParamLayout::new(
+            "Mix",
+            0.0,
+            FloatRange::Linear {
+                min: 0.0,
+                max: 100.0,
+            },
+        ));
+        assert_eq!(param.modulated_normalized_value(), 0.5);
+        assert_eq!(param.value(), 50.0);
+        assert_eq!(param.default_plain_value(), 0.0);
+        assert_eq!(param.name(), "Slot 1");
+    }
+
+    #[test]
+    fn parse_with_unit() {
+        let param = DynamicParam::new(
+            "Slot 1",
+            DynamicParamLayout::new(
+                "Cutoff",
+                1000.0,
+                FloatRange::Linear {
+                    min: 0.0,
+                    max: 2000.0,
+                },
+            )
+            .with_unit(" Hz"),
+        );
+        assert_eq!(param.string_to_normalized_value("500 Hz"), Some(0.25));
+        assert_eq!(param.string_to_normalized_value("1500"), Some(0.75));
+    }
+}
@@ -402,15 +402,43 @@ impl<T: Enum + PartialEq + 'static> EnumParam<T> {
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
-    pub fn hide(mut self) -> Self {
-        self.inner.inner = self.inner.inner.hide();
+    pub fn hide_from_host(mut self) -> Self {
+        self.inner.inner = self.inner.inner.hide_from_host();
         self
     }
 
     /// Don't show this parameter when generating a generic UI for the plugin using one of
     /// NIH-plug's generic UI widgets.
-    pub fn hide_in_generic_ui(mut self) -> Self {
-        self.inner.inner = self.inner.inner.hide_in_generic_ui();
+    pub fn hide_from_generic_ui(mut self) -> Self {
+        self.inner.inner = self.inner.inner.hide_from_generic_ui();
+        self
+    }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as an internal parameter that is only changed from the plugin's own
+    /// editor GUI. This combines [`hide_from_host()`][Self::hide_from_host()] and
+    /// [`hide_from_generic_ui()`][Self::hide_from_generic_ui()]. The parameter's value is still
+    /// stored as part of the plugin's state.
+    pub fn internal(self) -> Self {
+        self.hide_from_host().hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as read-only. The host can display the parameter's value, but it cannot
+    /// change it. This also implies `NON_AUTOMATABLE`. Setting this does not prevent you from
+    /// changing the parameter in the plugin's editor GUI.
+    pub fn read_only(mut self) -> Self {
+        self.inner.inner = self.inner.inner.read_only();
         self
     }
 }
@@ -9,6 +9,7 @@ use super::internals::ParamPtr;
 use super::range::FloatRange;
 use super::smoothing::{Smoother, SmoothingStyle};
 use super::{Param, ParamFlags, ParamMut};
+use crate::formatters;
 
 /// A floating point parameter that's stored unnormalized. The range is used for the normalization
 /// process.
@@ -27,6 +28,13 @@ pub struct FloatParam {
     /// `unmodulated_normalized_`. This needs to be stored separately since the normalized values are
     /// clamped, and this value persists after new automation events.
     modulation_offset: AtomicF32,
+    /// The lower bound of the parameter's effective range set through
+    /// [`set_effective_range()`][Self::set_effective_range()]. This is negative infinity if the
+    /// effective range has not been narrowed.
+    effective_min: AtomicF32,
+    /// The upper bound of the parameter's effective range. This is positive infinity if the
+    /// effective range has not been narrowed.
+    effective_max: AtomicF32,
     /// The field's default plain, unnormalized value.
     default: f32,
     /// An optional smoother that will automatically interpolate between the new automation values
@@ -51,6 +59,10 @@ pub struct FloatParam {
     /// input. If this is set and if [`value_to_string`][Self::value_to_string] is not set, then
     /// this is also used when formatting the parameter. This must be a positive, nonzero number.
     step_size: Option<f32>,
+    /// The number of decimal digits used when formatting the parameter. If
+    /// [`value_to_string`][Self::value_to_string] is not set, then this takes precedence over the
+    /// number of digits derived from [`step_size`][Self::step_size].
+    display_digits: Option<usize>,
     /// The parameter's human readable display name.
     name: String,
     /// The parameter value's unit, added after [`value_to_string`][Self::value_to_string] if that
@@ -75,14 +87,7 @@ pub struct FloatParam {
 
 impl Display for FloatParam {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match (&self.value_to_string, &self.step_size) {
-            (Some(func), _) => write!(f, "{}{}", func(self.value()), self.unit),
-            (None, Some(step_size)) => {
-                let num_digits = decimals_from_step_size(*step_size);
-                write!(f, "{:.num_digits$}{}", self.value(), self.unit)
-            }
-            _ => write!(f, "{}{}", self.value(), self.unit),
-        }
+        write!(f, "{}{}", self.format_value(self.value()), self.unit)
     }
 }
 
@@ -153,31 +158,25 @@ impl Param for FloatParam {
     }
 
     fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
-        let value = self.preview_plain(normalized);
-        match (&self.value_to_string, &self.step_size, include_unit) {
-            (Some(f), _, true) => format!("{}{}", f(value), self.unit),
-            (Some(f), _, false) => f(value),
-            (None, Some(step_size), true) => {
-                let num_digits = decimals_from_step_size(*step_size);
-                format!("{:.num_digits$}{}", value, self.unit)
-            }
-            (None, Some(step_size), false) => {
-                let num_digits = decimals_from_step_size(*step_size);
-                format!("{value:.num_digits$}")
-            }
-            (None, None, true) => format!("{}{}", value, self.unit),
-            (None, None, false) => format!("{value}"),
+        // Values outside of the effective range are shown as the value the plugin will actually use
+        let value =
+            self.format_value(self.clamp_to_effective_range(self.preview_plain(normalized)));
+        if include_unit {
+            format!("{}{}", value, self.unit)
+        } else {
+            value
         }
     }
 
     fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
         let value = match &self.string_to_value {
             Some(f) => f(string.trim()),
-            // In the CLAP wrapper the unit will be included, so make sure to handle that
-            None => string.trim().trim_end_matches(self.unit).parse().ok(),
+            // In the CLAP wrapper the unit will be included, so make sure to handle that. Decimal
+            // commas are also accepted since some hosts format numbers using the system's locale.
+            None => formatters::parse_f32(string.trim().trim_end_matches(self.unit)),
         }?;
 
-        Some(self.preview_normalized(value))
+        Some(self.preview_normalized(self.clamp_to_effective_range(value)))
     }
 
     #[inline]
@@ -218,6 +217,23 @@ impl ParamMut for FloatParam {
             (self.preview_plain(normalized_value), normalized_value)
         };
 
+        // The effective range only applies to the value seen by the plugin. The unmodulated value
+        // is left alone so the original value is restored when the effective range is widened
+        // again.
+        let clamped_value = self.clamp_to_effective_range(value);
+        let (value, normalized_value) = if clamped_value != value {
+            (clamped_value, self.preview_normalized(clamped_value))
+        } else {
+            (value, normalized_value)
+        };
+
+        // With a narrowed effective range the value seen by the plugin may stay the same while the
+        // host's value changes, so the unmodulated value is always stored
+        self.unmodulated_value
+            .store(unmodulated_value, Ordering::Relaxed);
+        self.unmodulated_normalized_value
+            .store(unmodulated_normalized_value, Ordering::Relaxed);
+
         // REAPER spams automation events with the same value. This prevents callbacks from firing
         // multiple times. This can be problematic when they're used to trigger expensive
         // computations when a parameter changes.
@@ -225,10 +241,6 @@ impl ParamMut for FloatParam {
         if value != old_value {
             self.normalized_value
                 .store(normalized_value, Ordering::Relaxed);
-            self.unmodulated_value
-                .store(unmodulated_value, Ordering::Relaxed);
-            self.unmodulated_normalized_value
-                .store(unmodulated_normalized_value, Ordering::Relaxed);
             if let Some(f) = &self.value_changed {
                 f(value);
             }
@@ -277,6 +289,8 @@ impl FloatParam {
             unmodulated_value: AtomicF32::new(default),
             unmodulated_normalized_value: AtomicF32::new(range.normalize(default)),
             modulation_offset: AtomicF32::new(0.0),
+            effective_min: AtomicF32::new(f32::NEG_INFINITY),
+            effective_max: AtomicF32::new(f32::INFINITY),
             default,
             smoothed: Smoother::none(),
 
@@ -285,6 +299,7 @@ impl FloatParam {
 
             range,
             step_size: None,
+            display_digits: None,
             name: name.into(),
             unit: "",
             poly_modulation_id: None,
@@ -372,6 +387,16 @@ impl FloatParam {
         self
     }
 
+    /// Always format the parameter's value with this many decimal digits. This controls the
+    /// rounding of the strings shown by the host, and it overrides the number of digits derived
+    /// from the [step size][Self::with_step_size()]. This is not used if a
+    /// [`value_to_string`][Self::with_value_to_string()] function is set. Use one of the
+    /// [`formatters`][crate::formatters] for more elaborate formatting.
+    pub fn with_display_digits(mut self, digits: usize) -> Self {
+        self.display_digits = Some(digits);
+        self
+    }
+
     /// Use a custom conversion function to convert the plain, unnormalized value to a
     /// string.
     pub fn with_value_to_string(
@@ -407,17 +432,114 @@ impl FloatParam {
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
-    pub fn hide(mut self) -> Self {
-        self.flags.insert(ParamFlags::HIDDEN);
+    pub fn hide_from_host(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::HIDDEN | ParamFlags::NON_AUTOMATABLE);
         self
     }
 
     /// Don't show this parameter when generating a generic UI for the plugin using one of
     /// NIH-plug's generic UI widgets.
-    pub fn hide_in_generic_ui(mut self) -> Self {
+    pub fn hide_from_generic_ui(mut self) -> Self {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as an internal parameter that is only changed from the plugin's own
+    /// editor GUI. This combines [`hide_from_host()`][Self::hide_from_host()] and
+    /// [`hide_from_generic_ui()`][Self::hide_from_generic_ui()]. The parameter can still be
+    /// smoothed, and its value is still stored as part of the plugin's state.
+    pub fn internal(self) -> Self {
+        self.hide_from_host().hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as read-only. The host can display the parameter's value, but it cannot
+    /// change it. This also implies `NON_AUTOMATABLE`. Setting this does not prevent you from
+    /// changing the parameter in the plugin's editor GUI.
+    pub fn read_only(mut self) -> Self {
+        self.flags.insert(ParamFlags::READ_ONLY);
+        self
+    }
+
+    /// Narrow the range of values the plugin sees for this parameter at runtime, for instance
+    /// because the parameter's useful range depends on another parameter or on the sample rate.
+    /// Pass `None` to use the parameter's full range again. The host still sees the parameter's
+    /// full range, but the parameter's [`value()`][Self::value()] and the smoother are clamped to
+    /// `(min, max)`, and values from the host and from automation are clamped as well. Values are
+    /// displayed as the clamped value the plugin actually uses.
+    ///
+    /// This is non-destructive. The host's unclamped value is kept, so it's used again once the
+    /// effective range is widened. If the clamped value changes, then the smoother is reset to the
+    /// new value without smoothing. Hosts pick up the new display values the next time they query
+    /// them.
+    pub fn set_effective_range(&self, range: Option<(f32, f32)>) {
+        let (min, max) = range.unwrap_or((f32::NEG_INFINITY, f32::INFINITY));
+        nih_debug_assert!(
+            min <= max,
+            "The effective range's minimum exceeds its maximum"
+        );
+
+        self.effective_min.store(min, Ordering::Relaxed);
+        self.effective_max.store(max, Ordering::Relaxed);
+
+        // This clamps the current value to the new range. The value changed callback is still
+        // called if this changes the value.
+        let old_value = self.value.load(Ordering::Relaxed);
+        self.set_plain_value(self.unmodulated_plain_value());
+        let new_value = self.value.load(Ordering::Relaxed);
+        if new_value != old_value {
+            self.smoothed.reset(new_value);
+        }
+    }
+
+    /// The parameter's effective range as set through
+    /// [`set_effective_range()`][Self::set_effective_range()], or `None` if the parameter uses its
+    /// full range.
+    pub fn effective_range(&self) -> Option<(f32, f32)> {
+        let min = self.effective_min.load(Ordering::Relaxed);
+        let max = self.effective_max.load(Ordering::Relaxed);
+        if min == f32::NEG_INFINITY && max == f32::INFINITY {
+            None
+        } else {
+            Some((min, max))
+        }
+    }
+
+    /// Clamp a plain value to the parameter's effective range.
+    fn clamp_to_effective_range(&self, value: f32) -> f32 {
+        value.clamp(
+            self.effective_min.load(Ordering::Relaxed),
+            self.effective_max.load(Ordering::Relaxed),
+        )
+    }
+
+    /// Format a plain value without the unit.
+    fn format_value(&self, value: f32) -> String {
+        if let Some(f) = &self.value_to_string {
+            return f(value);
+        }
+
+        match (self.display_digits, self.step_size) {
+            (Some(num_digits), _) => formatters::format_f32_rounded(value, num_digits),
+            (None, Some(step_size)) => {
+                let num_digits = decimals_from_step_size(step_size);
+                format!("{value:.num_digits$}")
+            }
+            (None, None) => format!("{value}"),
+        }
+    }
 }
 
 /// Calculate how many decimals to round to when displaying a floating point value with a specific
@@ -437,3 +559,48 @@ fn decimals_from_step_size(step_size: f32) -> usize {
 
     num_digits as usize
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_param() -> FloatParam {
+        FloatParam::new("Test", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+    }
+
+    #[test]
+    fn internal_flags() {
+        let param = make_param().internal();
+        assert!(param.flags().contains(
+            ParamFlags::HIDDEN | ParamFlags::NON_AUTOMATABLE | ParamFlags::HIDE_IN_GENERIC_UI
+        ));
+    }
+
+    #[test]
+    fn effective_range_clamps_value() {
+        let param = make_param();
+        param.set_plain_value(0.8);
+        param.set_effective_range(Some((0.2, 0.6)));
+
+        assert_eq!(param.value(), 0.6);
+        // The host's value is left alone
+        assert_eq!(param.unmodulated_plain_value(), 0.8);
+        assert_eq!(param.normalized_value_to_string(0.8, false), "0.6");
+
+        param.set_plain_value(0.1);
+        assert_eq!(param.value(), 0.2);
+        assert_eq!(param.unmodulated_plain_value(), 0.1);
+    }
+
+    #[test]
+    fn effective_range_is_non_destructive() {
+        let param = make_param();
+        param.set_effective_range(Some((0.0, 0.5)));
+        param.set_plain_value(0.9);
+        assert_eq!(param.value(), 0.5);
+
+        param.set_effective_range(None);
+        assert_eq!(param.value(), 0.9);
+        assert_eq!(param.effective_range(), None);
+    }
+}
@@ -133,7 +133,14 @@ impl Param for IntParam {
     }
 
     fn step_count(&self) -> Option<usize> {
-        Some(self.range.step_count())
+        // The steps of non-linear ranges are not evenly spaced in the normalized range. The CLAP
+        // and VST3 wrappers still expose these parameters to the host as discrete parameters
+        // through `ParamPtr::host_step_count()`.
+        if self.range.is_linear() {
+            Some(self.range.step_count())
+        } else {
+            None
+        }
     }
 
     fn previous_step(&self, from: Self::Plain, _finer: bool) -> Self::Plain {
@@ -308,9 +315,12 @@ impl IntParam {
         // Logarithmic smoothing will cause problems if the range goes through zero since then you
         // end up multiplying by zero
         let goes_through_zero = match (&style, &self.range) {
-            (SmoothingStyle::Logarithmic(_), IntRange::Linear { min, max }) => {
-                *min == 0 || *max == 0 || min.signum() != max.signum()
-            }
+            (
+                SmoothingStyle::Logarithmic(_),
+                IntRange::Linear { min, max }
+                | IntRange::Skewed { min, max, .. }
+                | IntRange::Custom { min, max, .. },
+            ) => *min == 0 || *max == 0 || min.signum() != max.signum(),
             _ => false,
         };
         nih_debug_assert!(
@@ -376,15 +386,44 @@ impl IntParam {
     /// Hide the parameter in the host's generic UI for this plugin. This also implies
     /// `NON_AUTOMATABLE`. Setting this does not prevent you from changing the parameter in the
     /// plugin's editor GUI.
-    pub fn hide(mut self) -> Self {
-        self.flags.insert(ParamFlags::HIDDEN);
+    pub fn hide_from_host(mut self) -> Self {
+        self.flags
+            .insert(ParamFlags::HIDDEN | ParamFlags::NON_AUTOMATABLE);
         self
     }
 
     /// Don't show this parameter when generating a generic UI for the plugin using one of
     /// NIH-plug's generic UI widgets.
-    pub fn hide_in_generic_ui(mut self) -> Self {
+    pub fn hide_from_generic_ui(mut self) -> Self {
         self.flags.insert(ParamFlags::HIDE_IN_GENERIC_UI);
         self
     }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as an internal parameter that is only changed from the plugin's own
+    /// editor GUI. This combines [`hide_from_host()`][Self::hide_from_host()] and
+    /// [`hide_from_generic_ui()`][Self::hide_from_generic_ui()]. The parameter can still be
+    /// smoothed, and its value is still stored as part of the plugin's state.
+    pub fn internal(self) -> Self {
+        self.hide_from_host().hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as read-only. The host can display the parameter's value, but it cannot
+    /// change it. This also implies `NON_AUTOMATABLE`. Setting this does not prevent you from
+    /// changing the parameter in the plugin's editor GUI.
+    pub fn read_only(mut self) -> Self {
+        self.flags.insert(ParamFlags::READ_ONLY);
+        self
+    }
 }
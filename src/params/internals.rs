@@ -1,5 +1,7 @@
 //! Implementation details for the parameter management.
 
+use std::borrow::Cow;
+
 use super::{Param, ParamFlags, ParamMut};
 
 /// Internal pointers to parameters. This is an implementation detail used by the wrappers for type
@@ -12,6 +14,8 @@ pub enum ParamPtr {
     /// Since we can't encode the actual enum here, this inner parameter struct contains all of the
     /// relevant information from the enum so it can be type erased.
     EnumParam(*const super::enums::EnumParamInner),
+    MacroParam(*const super::MacroParam),
+    DynamicParam(*const super::DynamicParam),
 }
 
 // These pointers only point to fields on structs kept in an `Arc<dyn Params>`, and the caller
@@ -37,6 +41,8 @@ macro_rules! param_ptr_forward(
                 ParamPtr::IntParam(p) => (**p).$method($($arg_name),*),
                 ParamPtr::BoolParam(p) => (**p).$method($($arg_name),*),
                 ParamPtr::EnumParam(p) => (**p).$method($($arg_name),*),
+                ParamPtr::MacroParam(p) => (**p).$method($($arg_name),*),
+                ParamPtr::DynamicParam(p) => (**p).$method($($arg_name),*),
             }
         }
     };
@@ -55,6 +61,8 @@ macro_rules! param_ptr_forward(
                 ParamPtr::IntParam(p) => (**p).$method($($arg_name),*),
                 ParamPtr::BoolParam(p) => (**p).$method($($arg_name),*),
                 ParamPtr::EnumParam(p) => (**p).$method($($arg_name),*),
+                ParamPtr::MacroParam(p) => (**p).$method($($arg_name),*),
+                ParamPtr::DynamicParam(p) => (**p).$method($($arg_name),*),
             }
         }
     };
@@ -96,6 +104,8 @@ impl ParamPtr {
             ParamPtr::IntParam(p) => (**p).modulated_plain_value() as f32,
             ParamPtr::BoolParam(p) => (**p).modulated_normalized_value(),
             ParamPtr::EnumParam(p) => (**p).modulated_plain_value() as f32,
+            ParamPtr::MacroParam(p) => (**p).modulated_plain_value(),
+            ParamPtr::DynamicParam(p) => (**p).modulated_plain_value(),
         }
     }
 
@@ -118,6 +128,8 @@ impl ParamPtr {
             ParamPtr::IntParam(p) => (**p).unmodulated_plain_value() as f32,
             ParamPtr::BoolParam(p) => (**p).unmodulated_normalized_value(),
             ParamPtr::EnumParam(p) => (**p).unmodulated_plain_value() as f32,
+            ParamPtr::MacroParam(p) => (**p).unmodulated_plain_value(),
+            ParamPtr::DynamicParam(p) => (**p).unmodulated_plain_value(),
         }
     }
 
@@ -133,6 +145,8 @@ impl ParamPtr {
             ParamPtr::IntParam(p) => (**p).default_plain_value() as f32,
             ParamPtr::BoolParam(p) => (**p).modulated_normalized_value(),
             ParamPtr::EnumParam(p) => (**p).default_plain_value() as f32,
+            ParamPtr::MacroParam(p) => (**p).default_plain_value(),
+            ParamPtr::DynamicParam(p) => (**p).default_plain_value(),
         }
     }
 
@@ -149,6 +163,8 @@ impl ParamPtr {
             ParamPtr::IntParam(p) => (**p).preview_normalized(plain as i32),
             ParamPtr::BoolParam(_) => plain,
             ParamPtr::EnumParam(p) => (**p).preview_normalized(plain as i32),
+            ParamPtr::MacroParam(p) => (**p).preview_normalized(plain),
+            ParamPtr::DynamicParam(p) => (**p).preview_normalized(plain),
         }
     }
 
@@ -165,6 +181,104 @@ impl ParamPtr {
             ParamPtr::IntParam(p) => (**p).preview_plain(normalized) as f32,
             ParamPtr::BoolParam(_) => normalized,
             ParamPtr::EnumParam(p) => (**p).preview_plain(normalized) as f32,
+            ParamPtr::MacroParam(p) => (**p).preview_plain(normalized),
+            ParamPtr::DynamicParam(p) => (**p).preview_plain(normalized),
+        }
+    }
+
+    /// Get the number of evenly spaced steps the host should use for this parameter, if it is
+    /// discrete. This is the same as [`step_count()`][Self::step_count()], except for
+    /// [`IntParam`][super::IntParam]s with skewed or custom ranges. Those are still exposed to the
+    /// host as discrete parameters, with their values converted using
+    /// [`to_host_normalized()`][Self::to_host_normalized()] and
+    /// [`from_host_normalized()`][Self::from_host_normalized()].
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for is
+    /// still alive.
+    pub unsafe fn host_step_count(&self) -> Option<usize> {
+        match self {
+            ParamPtr::IntParam(p) => Some((**p).range().step_count()),
+            _ => self.step_count(),
+        }
+    }
+
+    /// Convert a normalized value to the normalized value used by the host. These are only
+    /// different for discrete parameters whose steps are not evenly spaced. See
+    /// [`host_step_count()`][Self::host_step_count()].
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for is
+    /// still alive.
+    pub unsafe fn to_host_normalized(&self, normalized: f32) -> f32 {
+        match self {
+            ParamPtr::IntParam(p) => (**p).range().to_linear_normalized(normalized),
+            _ => normalized,
+        }
+    }
+
+    /// The inverse of [`to_host_normalized()`][Self::to_host_normalized()].
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for is
+    /// still alive.
+    pub unsafe fn from_host_normalized(&self, host_normalized: f32) -> f32 {
+        match self {
+            ParamPtr::IntParam(p) => (**p).range().from_linear_normalized(host_normalized),
+            _ => host_normalized,
+        }
+    }
+
+    /// Get the name that should be shown to the host. This is the same as
+    /// [`name()`][Self::name()], except for [`DynamicParam`][super::DynamicParam]s where this is
+    /// the name from the slot's current layout.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for is
+    /// still alive.
+    pub unsafe fn display_name(&self) -> Cow<'_, str> {
+        match self {
+            ParamPtr::DynamicParam(p) => Cow::Owned((**p).layout().name.clone()),
+            _ => Cow::Borrowed(self.name()),
+        }
+    }
+
+    /// Get the unit label that should be shown to the host. This is the same as
+    /// [`unit()`][Self::unit()], except for [`DynamicParam`][super::DynamicParam]s where this is
+    /// the unit from the slot's current layout.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for is
+    /// still alive.
+    pub unsafe fn display_unit(&self) -> Cow<'static, str> {
+        match self {
+            ParamPtr::DynamicParam(p) => Cow::Owned((**p).layout().unit.clone()),
+            _ => Cow::Borrowed(self.unit()),
+        }
+    }
+
+    /// If this is a [`MacroParam`][super::MacroParam], then update the macro's target parameters
+    /// from its current value. Does nothing for other parameter types. Used by the wrappers after
+    /// a parameter's value has changed. `on_target_changed` is called with the new normalized value
+    /// of every target parameter that changed.
+    ///
+    /// # Safety
+    ///
+    /// Calling this function is only safe as long as the object this `ParamPtr` was created for and
+    /// the parameters returned by `params_getter` are still alive.
+    pub(crate) unsafe fn apply_macro_mappings(
+        &self,
+        params_getter: impl Fn(&str) -> Option<ParamPtr>,
+        sample_rate: Option<f32>,
+        on_target_changed: impl FnMut(ParamPtr, f32),
+    ) {
+        if let ParamPtr::MacroParam(p) = self {
+            (**p).apply_mappings(params_getter, sample_rate, on_target_changed);
         }
     }
 }
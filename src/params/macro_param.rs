@@ -0,0 +1,392 @@
+//! Macro parameters that control multiple other parameters at once.
+
+use arc_swap::ArcSwap;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use super::internals::ParamPtr;
+use super::range::FloatRange;
+use super::{FloatParam, Param, ParamFlags, ParamMut};
+use crate::formatters;
+
+/// A parameter that controls one or more other parameters. The macro's value is a percentage in
+/// the `[0, 1]` range, and every [`MacroMapping`] maps that value onto a normalized range of a
+/// target parameter. This can be used to offer 'macro knob' style performance controls.
+///
+/// The mappings can be changed at runtime from the plugin's GUI, and they are stored as part of the
+/// plugin's state. Whenever the host or the GUI changes the macro's value, the plugin wrapper
+/// updates the target parameters before their smoothers are updated, so the targets are smoothed
+/// just like regular automation. The new target values are sent to the editor, but they are not
+/// reported back to the host as automation. Target parameters can still be changed directly, in
+/// which case they keep that value until the macro's value changes again.
+pub struct MacroParam {
+    /// The macro's value is stored in a regular float parameter.
+    inner: FloatParam,
+    /// The target parameters. These are read from the audio thread, so they're swapped out
+    /// atomically as a whole.
+    mappings: ArcSwap<Vec<MacroMapping>>,
+}
+
+/// Maps a [`MacroParam`]'s value onto a range of a target parameter's normalized values.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MacroMapping {
+    /// The target parameter's ID, as set with `#[id = "..."]` including any nested ID prefixes.
+    pub target: String,
+    /// The target's normalized value when the macro is at 0%.
+    pub start: f32,
+    /// The target's normalized value when the macro is at 100%. This may be lower than `start` to
+    /// invert the mapping.
+    pub end: f32,
+}
+
+impl MacroMapping {
+    /// Map a target parameter's entire range onto the macro's range.
+    pub fn new(target: impl Into<String>) -> Self {
+        Self::with_range(target, 0.0, 1.0)
+    }
+
+    /// Map the macro's range onto `[start, end]` of a target parameter's normalized range.
+    pub fn with_range(target: impl Into<String>, start: f32, end: f32) -> Self {
+        Self {
+            target: target.into(),
+            start: start.clamp(0.0, 1.0),
+            end: end.clamp(0.0, 1.0),
+        }
+    }
+
+    /// The target's normalized value for a macro value.
+    pub fn map(&self, macro_value: f32) -> f32 {
+        self.start + ((self.end - self.start) * macro_value)
+    }
+}
+
+impl Display for MacroParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl Debug for MacroParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.inner, f)
+    }
+}
+
+// `Params` can not be implemented outside of NIH-plug itself because `ParamPtr` is also closed
+impl super::Sealed for MacroParam {}
+
+impl Param for MacroParam {
+    type Plain = f32;
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn unit(&self) -> &'static str {
+        self.inner.unit()
+    }
+
+    fn poly_modulation_id(&self) -> Option<u32> {
+        None
+    }
+
+    #[inline]
+    fn modulated_plain_value(&self) -> Self::Plain {
+        self.inner.modulated_plain_value()
+    }
+
+    #[inline]
+    fn modulated_normalized_value(&self) -> f32 {
+        self.inner.modulated_normalized_value()
+    }
+
+    #[inline]
+    fn unmodulated_plain_value(&self) -> Self::Plain {
+        self.inner.unmodulated_plain_value()
+    }
+
+    #[inline]
+    fn unmodulated_normalized_value(&self) -> f32 {
+        self.inner.unmodulated_normalized_value()
+    }
+
+    #[inline]
+    fn default_plain_value(&self) -> Self::Plain {
+        self.inner.default_plain_value()
+    }
+
+    fn step_count(&self) -> Option<usize> {
+        None
+    }
+
+    fn previous_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.inner.previous_step(from, finer)
+    }
+
+    fn next_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.inner.next_step(from, finer)
+    }
+
+    fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
+        self.inner
+            .normalized_value_to_string(normalized, include_unit)
+    }
+
+    fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
+        self.inner.string_to_normalized_value(string)
+    }
+
+    #[inline]
+    fn preview_normalized(&self, plain: Self::Plain) -> f32 {
+        self.inner.preview_normalized(plain)
+    }
+
+    #[inline]
+    fn preview_plain(&self, normalized: f32) -> Self::Plain {
+        self.inner.preview_plain(normalized)
+    }
+
+    fn flags(&self) -> ParamFlags {
+        self.inner.flags()
+    }
+
+    fn as_ptr(&self) -> ParamPtr {
+        ParamPtr::MacroParam(self as *const _ as *mut _)
+    }
+}
+
+impl ParamMut for MacroParam {
+    fn set_plain_value(&self, plain: Self::Plain) -> bool {
+        self.inner.set_plain_value(plain)
+    }
+
+    fn set_normalized_value(&self, normalized: f32) -> bool {
+        self.inner.set_normalized_value(normalized)
+    }
+
+    fn modulate_value(&self, modulation_offset: f32) -> bool {
+        self.inner.modulate_value(modulation_offset)
+    }
+
+    fn update_smoother(&self, sample_rate: f32, reset: bool) {
+        self.inner.update_smoother(sample_rate, reset)
+    }
+}
+
+impl MacroParam {
+    /// Build a new [`MacroParam`] without any mappings. `default` is the macro's default value in
+    /// the `[0, 1]` range. Use the other associated functions to modify the behavior of the
+    /// parameter.
+    pub fn new(name: impl Into<String>, default: f32) -> Self {
+        Self {
+            inner: FloatParam::new(name, default, FloatRange::Linear { min: 0.0, max: 1.0 })
+                .with_unit("%")
+                .with_value_to_string(formatters::v2s_f32_percentage(0))
+                .with_string_to_value(formatters::s2v_f32_percentage()),
+            mappings: ArcSwap::from_pointee(Vec::new()),
+        }
+    }
+
+    /// The macro's current value in the `[0, 1]` range, after monophonic modulation has been
+    /// applied.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.modulated_plain_value()
+    }
+
+    /// The macro's current mappings.
+    pub fn mappings(&self) -> Arc<Vec<MacroMapping>> {
+        self.mappings.load_full()
+    }
+
+    /// Replace the macro's mappings. The targets are updated the next time the macro's value
+    /// changes. This should be called from the GUI thread, for instance in response to the user
+    /// editing the mappings.
+    pub fn set_mappings(&self, mappings: Vec<MacroMapping>) {
+        self.mappings.store(Arc::new(mappings));
+    }
+
+    /// Add a mapping, replacing any existing mapping for the same target.
+    pub fn add_mapping(&self, mapping: MacroMapping) {
+        let mut mappings = self.mappings().as_ref().clone();
+        mappings.retain(|m| m.target != mapping.target);
+        mappings.push(mapping);
+        self.set_mappings(mappings);
+    }
+
+    /// Remove the mapping for a target parameter, if there is one.
+    pub fn remove_mapping(&self, target: &str) {
+        let mut mappings = self.mappings().as_ref().clone();
+        mappings.retain(|m| m.target != target);
+        self.set_mappings(mappings);
+    }
+
+    /// Set the macro's initial mappings. These can still be changed at runtime, and they will be
+    /// overwritten when loading a state that contains mappings for this macro.
+    pub fn with_mappings(self, mappings: Vec<MacroMapping>) -> Self {
+        self.set_mappings(mappings);
+        self
+    }
+
+    /// Run a callback whenever the macro's value changes. The argument passed to this function is
+    /// the macro's new value. This should not do anything expensive as it may be called multiple
+    /// times in rapid succession, and it can be run from both the GUI and the audio thread.
+    pub fn with_callback(mut self, callback: Arc<dyn Fn(f32) + Send + Sync>) -> Self {
+        self.inner = self.inner.with_callback(callback);
+        self
+    }
+
+    /// Display a unit when rendering this parameter to a string. Defaults to `%`.
+    pub fn with_unit(mut self, unit: &'static str) -> Self {
+        self.inner = self.inner.with_unit(unit);
+        self
+    }
+
+    /// Use a custom conversion function to convert the macro's value to a string. Defaults to a
+    /// percentage.
+    pub fn with_value_to_string(
+        mut self,
+        callback: Arc<dyn Fn(f32) -> String + Send + Sync>,
+    ) -> Self {
+        self.inner = self.inner.with_value_to_string(callback);
+        self
+    }
+
+    /// Use a custom conversion function to convert from a string back to the macro's value.
+    pub fn with_string_to_value(
+        mut self,
+        callback: Arc<dyn Fn(&str) -> Option<f32> + Send + Sync>,
+    ) -> Self {
+        self.inner = self.inner.with_string_to_value(callback);
+        self
+    }
+
+    /// Mark the parameter as non-automatable. See [`ParamFlags::NON_AUTOMATABLE`].
+    pub fn non_automatable(mut self) -> Self {
+        self.inner = self.inner.non_automatable();
+        self
+    }
+
+    /// Hide the parameter in the host's generic UI for this plugin. See [`ParamFlags::HIDDEN`].
+    pub fn hide_from_host(mut self) -> Self {
+        self.inner = self.inner.hide_from_host();
+        self
+    }
+
+    /// Don't show this parameter when generating a generic UI for the plugin using one of
+    /// NIH-plug's generic UI widgets. See [`ParamFlags::HIDE_IN_GENERIC_UI`].
+    pub fn hide_from_generic_ui(mut self) -> Self {
+        self.inner = self.inner.hide_from_generic_ui();
+        self
+    }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+
+    /// Mark the parameter as an internal parameter. See [`FloatParam::internal()`].
+    pub fn internal(mut self) -> Self {
+        self.inner = self.inner.internal();
+        self
+    }
+
+    /// Mark the parameter as read-only. See [`ParamFlags::READ_ONLY`].
+    pub fn read_only(mut self) -> Self {
+        self.inner = self.inner.read_only();
+        self
+    }
+
+    /// Update the target parameters from the macro's current value. Called by the plugin wrappers
+    /// after the macro's value has changed. `on_target_changed` is called with the target's new
+    /// normalized value for every target parameter that has changed.
+    ///
+    /// # Safety
+    ///
+    /// The parameters returned by `params_getter` need to be alive.
+    pub(crate) unsafe fn apply_mappings(
+        &self,
+        params_getter: impl Fn(&str) -> Option<ParamPtr>,
+        sample_rate: Option<f32>,
+        mut on_target_changed: impl FnMut(ParamPtr, f32),
+    ) {
+        let macro_value = self.modulated_normalized_value();
+        for mapping in self.mappings.load().iter() {
+            let target = match params_getter(&mapping.target) {
+                // Macros controlling other macros could result in cycles
+                Some(ParamPtr::MacroParam(_)) => {
+                    nih_debug_assert_failure!(
+                        "Macro target \"{}\" is also a macro, ignoring",
+                        mapping.target
+                    );
+                    continue;
+                }
+                Some(target) => target,
+                None => {
+                    nih_debug_assert_failure!("Unknown macro target: {}", mapping.target);
+                    continue;
+                }
+            };
+
+            let normalized_value = mapping.map(macro_value);
+            if target.set_normalized_value(normalized_value) {
+                if let Some(sample_rate) = sample_rate {
+                    target.update_smoother(sample_rate, false);
+                }
+
+                on_target_changed(target, normalized_value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mapping_range() {
+        let mapping = MacroMapping::with_range("cutoff", 0.25, 0.75);
+        assert_eq!(mapping.map(0.0), 0.25);
+        assert_eq!(mapping.map(0.5), 0.5);
+        assert_eq!(mapping.map(1.0), 0.75);
+
+        let inverted = MacroMapping::with_range("cutoff", 1.0, 0.0);
+        assert_eq!(inverted.map(0.25), 0.75);
+    }
+
+    #[test]
+    fn test_apply_mappings() {
+        let macro_param = MacroParam::new("Macro", 0.0).with_mappings(vec![
+            MacroMapping::with_range("a", 0.0, 0.5),
+            MacroMapping::with_range("b", 1.0, 0.0),
+        ]);
+        let a = FloatParam::new("A", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let b = FloatParam::new("B", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let params_getter = |id: &str| match id {
+            "a" => Some(a.as_ptr()),
+            "b" => Some(b.as_ptr()),
+            _ => None,
+        };
+
+        macro_param.set_normalized_value(0.5);
+        let mut changed = Vec::new();
+        unsafe {
+            macro_param.apply_mappings(params_getter, None, |target, value| {
+                changed.push((target, value))
+            })
+        };
+
+        assert_eq!(a.value(), 0.25);
+        assert_eq!(b.value(), 0.5);
+        assert_eq!(changed, vec![(a.as_ptr(), 0.25), (b.as_ptr(), 0.5)]);
+    }
+}
@@ -0,0 +1,210 @@
+//! Read-only parameters for sending values computed by the plugin to the host.
+
+use std::fmt::{Debug, Display};
+use std::sync::Arc;
+
+use super::internals::ParamPtr;
+use super::range::FloatRange;
+use super::{FloatParam, Param, ParamFlags, ParamMut};
+
+/// A read-only parameter that is updated by the plugin from its
+/// [`process()`][crate::prelude::Plugin::process()] function, for instance to show gain reduction
+/// or levels in the host's mixer or on a control surface. The host cannot change this parameter,
+/// and it is marked as read-only for CLAP and VST3. The plugin wrappers send changed values to the
+/// host at a limited rate of about 30 times per second, so [`set()`][Self::set()] can be called
+/// every process cycle.
+///
+/// Unlike regular parameters, the plugin sets this parameter's value directly. Under the hood this
+/// is a [`FloatParam`] with the [`ParamFlags::READ_ONLY`] flag set, so it can be used with the
+/// same GUI widgets.
+pub struct OutputParam {
+    inner: FloatParam,
+}
+
+impl Display for OutputParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Display::fmt(&self.inner, f)
+    }
+}
+
+impl Debug for OutputParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        Debug::fmt(&self.inner, f)
+    }
+}
+
+// `Params` can not be implemented outside of NIH-plug itself because `ParamPtr` is also closed
+impl super::Sealed for OutputParam {}
+
+impl Param for OutputParam {
+    type Plain = f32;
+
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn unit(&self) -> &'static str {
+        self.inner.unit()
+    }
+
+    fn poly_modulation_id(&self) -> Option<u32> {
+        None
+    }
+
+    #[inline]
+    fn modulated_plain_value(&self) -> Self::Plain {
+        self.inner.modulated_plain_value()
+    }
+
+    #[inline]
+    fn modulated_normalized_value(&self) -> f32 {
+        self.inner.modulated_normalized_value()
+    }
+
+    #[inline]
+    fn unmodulated_plain_value(&self) -> Self::Plain {
+        self.inner.unmodulated_plain_value()
+    }
+
+    #[inline]
+    fn unmodulated_normalized_value(&self) -> f32 {
+        self.inner.unmodulated_normalized_value()
+    }
+
+    #[inline]
+    fn default_plain_value(&self) -> Self::Plain {
+        self.inner.default_plain_value()
+    }
+
+    fn step_count(&self) -> Option<usize> {
+        self.inner.step_count()
+    }
+
+    fn previous_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.inner.previous_step(from, finer)
+    }
+
+    fn next_step(&self, from: Self::Plain, finer: bool) -> Self::Plain {
+        self.inner.next_step(from, finer)
+    }
+
+    fn normalized_value_to_string(&self, normalized: f32, include_unit: bool) -> String {
+        self.inner
+            .normalized_value_to_string(normalized, include_unit)
+    }
+
+    fn string_to_normalized_value(&self, string: &str) -> Option<f32> {
+        self.inner.string_to_normalized_value(string)
+    }
+
+    #[inline]
+    fn preview_normalized(&self, plain: Self::Plain) -> f32 {
+        self.inner.preview_normalized(plain)
+    }
+
+    #[inline]
+    fn preview_plain(&self, normalized: f32) -> Self::Plain {
+        self.inner.preview_plain(normalized)
+    }
+
+    fn flags(&self) -> ParamFlags {
+        self.inner.flags()
+    }
+
+    fn as_ptr(&self) -> ParamPtr {
+        // The wrappers treat this as a regular read-only float parameter
+        self.inner.as_ptr()
+    }
+}
+
+impl OutputParam {
+    /// Build a new [`OutputParam`]. Use the other associated functions to modify the behavior of
+    /// the parameter.
+    pub fn new(name: impl Into<String>, default: f32, range: FloatRange) -> Self {
+        Self {
+            inner: FloatParam::new(name, default, range).read_only(),
+        }
+    }
+
+    /// The parameter's current value.
+    #[inline]
+    pub fn value(&self) -> f32 {
+        self.inner.value()
+    }
+
+    /// Update the parameter's value. Values outside of the parameter's range are clamped. This is
+    /// realtime-safe, and it should be called from the plugin's
+    /// [`process()`][crate::prelude::Plugin::process()] function. The new value is sent to the host
+    /// at the end of the process cycle, as long as the previous value was not sent too recently.
+    #[inline]
+    pub fn set(&self, value: f32) {
+        let range = self.inner.range();
+        self.inner
+            .set_plain_value(range.unnormalize(range.normalize(value)));
+    }
+
+    /// Display a unit when rendering this parameter to a string. See [`FloatParam::with_unit()`].
+    pub fn with_unit(mut self, unit: &'static str) -> Self {
+        self.inner = self.inner.with_unit(unit);
+        self
+    }
+
+    /// Use a custom conversion function to convert the plain, unnormalized value to a string. See
+    /// [`FloatParam::with_value_to_string()`].
+    pub fn with_value_to_string(
+        mut self,
+        callback: Arc<dyn Fn(f32) -> String + Send + Sync>,
+    ) -> Self {
+        self.inner = self.inner.with_value_to_string(callback);
+        self
+    }
+
+    /// Hide the parameter in the host's generic UI for this plugin. See
+    /// [`FloatParam::hide_from_host()`].
+    pub fn hide_from_host(mut self) -> Self {
+        self.inner = self.inner.hide_from_host();
+        self
+    }
+
+    /// Don't show this parameter when generating a generic UI for the plugin using one of
+    /// NIH-plug's generic UI widgets. See [`FloatParam::hide_from_generic_ui()`].
+    pub fn hide_from_generic_ui(mut self) -> Self {
+        self.inner = self.inner.hide_from_generic_ui();
+        self
+    }
+
+    /// Deprecated alias for [`hide_from_host()`][Self::hide_from_host()].
+    #[deprecated(note = "Use `hide_from_host()` instead")]
+    pub fn hide(self) -> Self {
+        self.hide_from_host()
+    }
+
+    /// Deprecated alias for [`hide_from_generic_ui()`][Self::hide_from_generic_ui()].
+    #[deprecated(note = "Use `hide_from_generic_ui()` instead")]
+    pub fn hide_in_generic_ui(self) -> Self {
+        self.hide_from_generic_ui()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_clamps_to_range() {
+        let param = OutputParam::new(
+            "Gain Reduction",
+            0.0,
+            FloatRange::Linear {
+                min: 0.0,
+                max: 24.0,
+            },
+        );
+        assert!(param.flags().contains(ParamFlags::READ_ONLY));
+
+        param.set(6.0);
+        assert_eq!(param.value(), 6.0);
+        param.set(48.0);
+        assert_eq!(param.value(), 24.0);
+    }
+}
@@ -1,6 +1,9 @@
 //! Traits and helpers for persistent fields. See the [`Params`][super::Params] trait for more
 //! information.
 
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use std::io::{Read, Write};
+use std::sync::atomic::{AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 
 /// Re-export for use in the [`Params`][super::Params] proc-macro.
@@ -217,6 +220,391 @@ where
 impl_persistent_arc!(crossbeam::atomic::AtomicCell<T>,
                      T: serde::Serialize + serde::Deserialize<'a> + Copy + Send);
 
+impl PersistentField<'_, crate::util::curve::Curve> for crate::util::curve::SharedCurve {
+    fn set(&self, new_value: crate::util::curve::Curve) {
+        self.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&crate::util::curve::Curve) -> R,
+    {
+        f(&self.load())
+    }
+}
+impl_persistent_arc!(crate::util::curve::SharedCurve, crate::util::curve::Curve);
+
+impl PersistentField<'_, crate::util::modulation::ModulationConfig>
+    for crate::util::modulation::ModulationMatrix
+{
+    fn set(&self, new_value: crate::util::modulation::ModulationConfig) {
+        self.set_config(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&crate::util::modulation::ModulationConfig) -> R,
+    {
+        f(&self.config())
+    }
+}
+impl_persistent_arc!(
+    crate::util::modulation::ModulationMatrix,
+    crate::util::modulation::ModulationConfig
+);
+
+impl PersistentField<'_, crate::util::automation::AutomationRecording>
+    for crate::util::automation::AutomationRecorder
+{
+    fn set(&self, new_value: crate::util::automation::AutomationRecording) {
+        self.set_recording(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&crate::util::automation::AutomationRecording) -> R,
+    {
+        f(&self.recording())
+    }
+}
+impl_persistent_arc!(
+    crate::util::automation::AutomationRecorder,
+    crate::util::automation::AutomationRecording
+);
+
+impl PersistentField<'_, crate::midi::filter::NoteEventFilterChain>
+    for crate::midi::filter::SharedNoteEventFilterChain
+{
+    fn set(&self, new_value: crate::midi::filter::NoteEventFilterChain) {
+        self.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&crate::midi::filter::NoteEventFilterChain) -> R,
+    {
+        f(&self.load())
+    }
+}
+impl_persistent_arc!(
+    crate::midi::filter::SharedNoteEventFilterChain,
+    crate::midi::filter::NoteEventFilterChain
+);
+
+/// Handles the functionality needed for persisting large non-parameter fields as raw binary data.
+/// These types can be used with [`Params`][super::Params]' `#[persist(key = "...", blob)]`
+/// attributes. Unlike [`PersistentField`]s, these fields are not converted to JSON strings first.
+/// Instead their data is written to a byte buffer that is stored as is in a binary section after
+/// the state's JSON data, which keeps the state small and saving fast for things like recorded
+/// audio or wavetables. The whole blob is still held in memory while the state is being saved or
+/// loaded.
+///
+/// This is implemented for `Vec<u8>`s and `Vec<f32>`s wrapped in the same types with interior
+/// mutability as [`PersistentField`]. Samples are stored as little endian 32-bit floats.
+pub trait PersistentBlob: Send + Sync {
+    /// Write the field's data to `writer`.
+    fn write_blob(&self, writer: &mut dyn Write) -> std::io::Result<()>;
+
+    /// Replace the field's data with the data read from `reader`. The reader contains exactly the
+    /// data written by [`write_blob()`][Self::write_blob()].
+    fn read_blob(&self, reader: &mut dyn Read) -> std::io::Result<()>;
+
+    /// A number that changes whenever the field's data changes, if the field keeps track of that.
+    /// This allows the CLAP and VST3 wrappers to serialize the field ahead of time on a background
+    /// thread, so saving the plugin's state does not need to serialize it again when it hasn't
+    /// changed. See [`SharedBlob`].
+    fn revision(&self) -> Option<u64> {
+        None
+    }
+}
+
+/// A [`PersistentBlob`] that keeps track of when its data changes. When the plugin's state is saved
+/// through CLAP or VST3, large blobs stored in a `SharedBlob` are serialized and compressed ahead
+/// of time on a background thread once their data has stopped changing. Saving the state then
+/// reuses that data, so saving a project containing large recordings or sample data does not stall
+/// the host's GUI. This is implemented for `Vec<u8>` and `Vec<f32>`.
+#[derive(Debug, Default)]
+pub struct SharedBlob<T> {
+    data: RwLock<T>,
+    /// Incremented whenever `data` is modified.
+    revision: AtomicU64,
+}
+
+impl<T> SharedBlob<T> {
+    /// Create a blob containing `data`.
+    pub fn new(data: T) -> Self {
+        Self {
+            data: RwLock::new(data),
+            revision: AtomicU64::new(0),
+        }
+    }
+
+    /// Get read access to the blob's data.
+    pub fn read(&self) -> RwLockReadGuard<'_, T> {
+        self.data.read()
+    }
+
+    /// Replace the blob's data.
+    pub fn set(&self, data: T) {
+        self.modify(|old_data| *old_data = data);
+    }
+
+    /// Modify the blob's data in place.
+    pub fn modify<R>(&self, f: impl FnOnce(&mut T) -> R) -> R {
+        let mut data = self.data.write();
+        let result = f(&mut data);
+        self.revision.fetch_add(1, Ordering::AcqRel);
+
+        result
+    }
+}
+
+/// The binary representation of the types that can be stored in a [`PersistentBlob`].
+trait BlobData: Sized {
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<()>;
+    fn read_from(reader: &mut dyn Read) -> std::io::Result<Self>;
+}
+
+impl BlobData for Vec<u8> {
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        writer.write_all(self)
+    }
+
+    fn read_from(reader: &mut dyn Read) -> std::io::Result<Self> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+
+        Ok(data)
+    }
+}
+
+impl BlobData for Vec<f32> {
+    fn write_to(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+        // Converting the samples in chunks avoids both a write call per sample and a second copy
+        // of the entire buffer
+        let mut bytes = [0u8; 4096];
+        for samples in self.chunks(bytes.len() / 4) {
+            for (sample, sample_bytes) in samples.iter().zip(bytes.chunks_exact_mut(4)) {
+                sample_bytes.copy_from_slice(&sample.to_le_bytes());
+            }
+            writer.write_all(&bytes[..samples.len() * 4])?;
+        }
+
+        Ok(())
+    }
+
+    fn read_from(reader: &mut dyn Read) -> std::io::Result<Self> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes)?;
+        if bytes.len() % 4 != 0 {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The data does not contain a whole number of samples",
+            ));
+        }
+
+        Ok(bytes
+            .chunks_exact(4)
+            .map(|sample_bytes| f32::from_le_bytes(sample_bytes.try_into().unwrap()))
+            .collect())
+    }
+}
+
+macro_rules! impl_persistent_blob {
+    ($ty:ty, $data_ty:ty, |$this:ident| $borrow:expr, $borrow_mut:expr) => {
+        impl PersistentBlob for $ty {
+            fn write_blob(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+                let $this = self;
+                $borrow.write_to(writer)
+            }
+
+            fn read_blob(&self, reader: &mut dyn Read) -> std::io::Result<()> {
+                let data = <$data_ty>::read_from(reader)?;
+                let $this = self;
+                *$borrow_mut = data;
+
+                Ok(())
+            }
+        }
+
+        impl PersistentBlob for Arc<$ty> {
+            fn write_blob(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+                self.as_ref().write_blob(writer)
+            }
+
+            fn read_blob(&self, reader: &mut dyn Read) -> std::io::Result<()> {
+                self.as_ref().read_blob(reader)
+            }
+
+            fn revision(&self) -> Option<u64> {
+                self.as_ref().revision()
+            }
+        }
+    };
+}
+
+macro_rules! impl_persistent_blob_for_data {
+    ($data_ty:ty) => {
+        impl_persistent_blob!(
+            std::sync::RwLock<$data_ty>,
+            $data_ty,
+            |this| this.read().expect("Poisoned RwLock on read"),
+            this.write().expect("Poisoned RwLock on write")
+        );
+        impl_persistent_blob!(
+            std::sync::Mutex<$data_ty>,
+            $data_ty,
+            |this| this.lock().expect("Poisoned Mutex"),
+            this.lock().expect("Poisoned Mutex")
+        );
+        impl_persistent_blob!(
+            parking_lot::RwLock<$data_ty>,
+            $data_ty,
+            |this| this.read(),
+            this.write()
+        );
+        impl_persistent_blob!(
+            parking_lot::Mutex<$data_ty>,
+            $data_ty,
+            |this| this.lock(),
+            this.lock()
+        );
+        impl_persistent_blob!(
+            atomic_refcell::AtomicRefCell<$data_ty>,
+            $data_ty,
+            |this| this.borrow(),
+            this.borrow_mut()
+        );
+
+        impl PersistentBlob for SharedBlob<$data_ty> {
+            fn write_blob(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+                self.read().write_to(writer)
+            }
+
+            fn read_blob(&self, reader: &mut dyn Read) -> std::io::Result<()> {
+                self.set(<$data_ty>::read_from(reader)?);
+
+                Ok(())
+            }
+
+            fn revision(&self) -> Option<u64> {
+                Some(self.revision.load(Ordering::Acquire))
+            }
+        }
+
+        impl PersistentBlob for Arc<SharedBlob<$data_ty>> {
+            fn write_blob(&self, writer: &mut dyn Write) -> std::io::Result<()> {
+                self.as_ref().write_blob(writer)
+            }
+
+            fn read_blob(&self, reader: &mut dyn Read) -> std::io::Result<()> {
+                self.as_ref().read_blob(reader)
+            }
+
+            fn revision(&self) -> Option<u64> {
+                self.as_ref().revision()
+            }
+        }
+    };
+}
+
+impl_persistent_blob_for_data!(Vec<u8>);
+impl_persistent_blob_for_data!(Vec<f32>);
+
+/// A callback registered with [`PersistNotifier::on_load()`].
+type LoadCallback = Box<dyn Fn(u32) + Send + Sync>;
+
+/// Notifies the plugin when its persistent fields have been restored, for instance because the
+/// host opened a project or loaded a preset. Add this to a [`Params`][super::Params] struct with
+/// the `#[persist_notifier]` attribute. Whenever the plugin's state is loaded, the notifier's
+/// generation counter is incremented and the registered callbacks are run on the wrapper's
+/// background thread.
+///
+/// The generation counter can be read from the audio thread using
+/// [`generation()`][Self::generation()]. Comparing it to the last generation the plugin has seen
+/// is a realtime-safe way to find out that, for instance, a sample path has changed and that the
+/// corresponding sample has been (re)loaded by one of the callbacks.
+///
+/// ```ignore
+/// #[derive(Params)]
+/// struct MyParams {
+///     #[persist = "sample"]
+///     sample: PathParam,
+///     #[persist_notifier]
+///     loaded: PersistNotifier,
+/// }
+/// ```
+#[derive(Default)]
+pub struct PersistNotifier {
+    generation: AtomicU32,
+    callbacks: Mutex<LoadCallbacks>,
+}
+
+/// The callbacks for a [`PersistNotifier`], together with the last generation they were run for.
+#[derive(Default)]
+struct LoadCallbacks {
+    callbacks: Vec<LoadCallback>,
+    last_generation: u32,
+}
+
+impl std::fmt::Debug for PersistNotifier {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PersistNotifier")
+            .field("generation", &self.generation())
+            .finish_non_exhaustive()
+    }
+}
+
+impl PersistNotifier {
+    /// Create a notifier without any callbacks. The generation starts at 0.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of times the plugin's state has been loaded. This is realtime-safe.
+    #[inline]
+    pub fn generation(&self) -> u32 {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Register a callback that is run on a background thread after the plugin's state has been
+    /// loaded. The callback receives the new generation. Callbacks for the same notifier never run
+    /// concurrently, and they run in the order they were registered in. If the state is loaded
+    /// again before the callbacks for the previous load have run, then they only run once for the
+    /// latest generation. The callbacks cannot register new callbacks. Since the generation is
+    /// incremented before the callbacks run, a callback that loads data for the audio thread should
+    /// publish that data together with the generation it received.
+    pub fn on_load(&self, callback: impl Fn(u32) + Send + Sync + 'static) {
+        self.callbacks.lock().callbacks.push(Box::new(callback));
+    }
+
+    /// Increment the generation. This is called by the plugin wrappers after the plugin's
+    /// persistent fields have been restored through
+    /// [`Params::fields_loaded()`][super::Params::fields_loaded()], and it should not be called
+    /// directly. This may be called from the audio thread, so it only touches the atomic
+    /// generation counter. The wrappers then run the callbacks on their background thread using
+    /// [`run_callbacks()`][Self::run_callbacks()].
+    pub fn notify(&self) {
+        self.generation.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// Run the registered callbacks if the generation has changed since they last ran. This is
+    /// called by the plugin wrappers on their background thread through
+    /// [`Params::run_load_callbacks()`][super::Params::run_load_callbacks()], and it should not
+    /// be called directly.
+    pub fn run_callbacks(&self) {
+        // Holding the lock for the entire duration serializes the callbacks when the state is
+        // loaded several times in a row. If a later load has already been handled by the time
+        // this gets the lock, then there's nothing left to do.
+        let mut callbacks = self.callbacks.lock();
+        let generation = self.generation();
+        if generation == callbacks.last_generation {
+            return;
+        }
+
+        callbacks.last_generation = generation;
+        for callback in &callbacks.callbacks {
+            callback(generation);
+        }
+    }
+}
+
 /// Can be used with the `#[serde(with = "nih_plug::params::internals::serialize_atomic_cell")]`
 /// attribute to serialize `AtomicCell<T>`s.
 pub mod serialize_atomic_cell {
@@ -26,13 +26,29 @@ pub enum FloatRange {
     Reversed(&'static FloatRange),
 }
 
-/// A distribution for an integer parameter's range. All range endpoints are inclusive. Only linear
-/// ranges are supported for integers since hosts expect discrete parameters to have a fixed step
-/// size.
+/// A distribution for an integer parameter's range. All range endpoints are inclusive. Hosts
+/// expect discrete parameters to have a fixed step size, so the CLAP and VST3 wrappers convert the
+/// normalized values of the non-linear [`IntRange::Skewed`] and [`IntRange::Custom`] ranges using
+/// [`IntRange::to_linear_normalized()`] before passing them to the host. This way parameters using
+/// these ranges are still exposed to the host as discrete parameters.
 #[derive(Debug, Clone, Copy)]
 pub enum IntRange {
     /// The values are uniformly distributed between `min` and `max`.
     Linear { min: i32, max: i32 },
+    /// The range is skewed by a factor, just like [`FloatRange::Skewed`]. Use
+    /// [`FloatRange::skew_factor()`] to calculate the factor. This is useful for parameters like
+    /// buffer sizes where the smaller values need more resolution.
+    Skewed { min: i32, max: i32, factor: f32 },
+    /// A range with custom normalization functions. `normalize` maps a value in `[min, max]` to
+    /// `[0, 1]` and `unnormalize` does the opposite. Both functions should be monotonically
+    /// increasing and each other's inverse, and values passed to them are already clamped to the
+    /// range's bounds.
+    Custom {
+        min: i32,
+        max: i32,
+        normalize: fn(i32) -> f32,
+        unnormalize: fn(f32) -> i32,
+    },
     /// A reversed range that goes from high to low instead of from low to high.
     Reversed(&'static IntRange),
 }
@@ -222,6 +238,15 @@ impl IntRange {
     pub fn normalize(&self, plain: i32) -> f32 {
         match self {
             IntRange::Linear { min, max } => (plain - min) as f32 / (max - min) as f32,
+            IntRange::Skewed { min, max, factor } => {
+                ((plain.clamp(*min, *max) - min) as f32 / (max - min) as f32).powf(*factor)
+            }
+            IntRange::Custom {
+                min,
+                max,
+                normalize,
+                ..
+            } => normalize(plain.clamp(*min, *max)),
             IntRange::Reversed(range) => 1.0 - range.normalize(plain),
         }
         .clamp(0.0, 1.0)
@@ -233,6 +258,15 @@ impl IntRange {
         let normalized = normalized.clamp(0.0, 1.0);
         match self {
             IntRange::Linear { min, max } => (normalized * (max - min) as f32).round() as i32 + min,
+            IntRange::Skewed { min, max, factor } => {
+                (normalized.powf(factor.recip()) * (max - min) as f32).round() as i32 + min
+            }
+            IntRange::Custom {
+                min,
+                max,
+                unnormalize,
+                ..
+            } => unnormalize(normalized).clamp(*min, *max),
             IntRange::Reversed(range) => range.unnormalize(1.0 - normalized),
         }
     }
@@ -240,7 +274,9 @@ impl IntRange {
     /// The range's previous discrete step from a certain value.
     pub fn previous_step(&self, from: i32) -> i32 {
         match self {
-            IntRange::Linear { min, max } => (from - 1).clamp(*min, *max),
+            IntRange::Linear { min, max }
+            | IntRange::Skewed { min, max, .. }
+            | IntRange::Custom { min, max, .. } => (from - 1).clamp(*min, *max),
             IntRange::Reversed(range) => range.next_step(from),
         }
     }
@@ -248,7 +284,9 @@ impl IntRange {
     /// The range's next discrete step from a certain value.
     pub fn next_step(&self, from: i32) -> i32 {
         match self {
-            IntRange::Linear { min, max } => (from + 1).clamp(*min, *max),
+            IntRange::Linear { min, max }
+            | IntRange::Skewed { min, max, .. }
+            | IntRange::Custom { min, max, .. } => (from + 1).clamp(*min, *max),
             IntRange::Reversed(range) => range.previous_step(from),
         }
     }
@@ -256,15 +294,57 @@ impl IntRange {
     /// The number of steps in this range. Used for the host's generic UI.
     pub fn step_count(&self) -> usize {
         match self {
-            IntRange::Linear { min, max } => (max - min) as usize,
+            IntRange::Linear { min, max }
+            | IntRange::Skewed { min, max, .. }
+            | IntRange::Custom { min, max, .. } => (max - min) as usize,
             IntRange::Reversed(range) => range.step_count(),
         }
     }
 
+    /// Whether the steps in this range are evenly spaced in the normalized `[0, 1]` range. The
+    /// normalized values of other ranges need to be converted using
+    /// [`to_linear_normalized()`][Self::to_linear_normalized()] before they're passed to the host.
+    pub fn is_linear(&self) -> bool {
+        match self {
+            IntRange::Linear { .. } => true,
+            IntRange::Skewed { .. } | IntRange::Custom { .. } => false,
+            IntRange::Reversed(range) => range.is_linear(),
+        }
+    }
+
+    /// Convert a normalized value to a normalized value where every step in the range is evenly
+    /// spaced. This is the same as the normalized value for linear ranges. Hosts expect the steps of
+    /// discrete parameters to be evenly spaced, so this is used to expose skewed and custom ranges
+    /// to the host as discrete parameters.
+    pub fn to_linear_normalized(&self, normalized: f32) -> f32 {
+        match self {
+            IntRange::Linear { .. } => normalized,
+            IntRange::Skewed { min, max, .. } | IntRange::Custom { min, max, .. } => {
+                (self.unnormalize(normalized) - min) as f32 / (max - min) as f32
+            }
+            IntRange::Reversed(range) => 1.0 - range.to_linear_normalized(1.0 - normalized),
+        }
+    }
+
+    /// The inverse of [`to_linear_normalized()`][Self::to_linear_normalized()].
+    pub fn from_linear_normalized(&self, linear_normalized: f32) -> f32 {
+        match self {
+            IntRange::Linear { .. } => linear_normalized,
+            IntRange::Skewed { min, max, .. } | IntRange::Custom { min, max, .. } => {
+                let plain =
+                    (linear_normalized.clamp(0.0, 1.0) * (max - min) as f32).round() as i32 + min;
+                self.normalize(plain)
+            }
+            IntRange::Reversed(range) => {
+                1.0 - range.from_linear_normalized(1.0 - linear_normalized)
+            }
+        }
+    }
+
     /// If this range is wrapped in an adapter, like `Reversed`, then return the wrapped range.
     pub fn inner_range(&self) -> Self {
         match self {
-            IntRange::Linear { .. } => *self,
+            IntRange::Linear { .. } | IntRange::Skewed { .. } | IntRange::Custom { .. } => *self,
             IntRange::Reversed(range) => range.inner_range(),
         }
     }
@@ -273,7 +353,9 @@ impl IntRange {
     /// that they are not equal.
     pub(super) fn assert_validity(&self) {
         match self {
-            IntRange::Linear { min, max } => {
+            IntRange::Linear { min, max }
+            | IntRange::Skewed { min, max, .. }
+            | IntRange::Custom { min, max, .. } => {
                 nih_debug_assert!(
                     min < max,
                     "The range minimum ({}) needs to be less than the range maximum ({}) and they \
@@ -442,6 +524,96 @@ mod tests {
         }
     }
 
+    mod int_skewed {
+        use super::*;
+
+        const fn make_skewed_int_range(factor: f32) -> IntRange {
+            IntRange::Skewed {
+                min: 0,
+                max: 16,
+                factor,
+            }
+        }
+
+        #[test]
+        fn range_normalize_int() {
+            let range = make_skewed_int_range(0.5);
+            assert_eq!(range.normalize(4), 0.5);
+        }
+
+        #[test]
+        fn range_unnormalize_int() {
+            let range = make_skewed_int_range(0.5);
+            assert_eq!(range.unnormalize(0.5), 4);
+        }
+
+        #[test]
+        fn range_linear_normalized_int() {
+            let range = make_skewed_int_range(0.5);
+            assert_eq!(range.to_linear_normalized(0.5), 0.25);
+            assert_eq!(range.from_linear_normalized(0.25), 0.5);
+        }
+
+        #[test]
+        fn range_linear_equiv_int() {
+            let linear_range = make_linear_int_range();
+            let skewed_range = IntRange::Skewed {
+                min: -10,
+                max: 10,
+                factor: 1.0,
+            };
+            assert_eq!(linear_range.normalize(5), skewed_range.normalize(5));
+            assert_eq!(
+                linear_range.unnormalize(0.73),
+                skewed_range.unnormalize(0.73)
+            );
+        }
+    }
+
+    mod int_custom {
+        use super::*;
+
+        fn normalize_fft_order(order: i32) -> f32 {
+            ((1 << order) - 64) as f32 / (1024 - 64) as f32
+        }
+
+        fn unnormalize_fft_order(normalized: f32) -> i32 {
+            ((normalized * (1024 - 64) as f32) + 64.0).log2().round() as i32
+        }
+
+        /// An FFT order range where the normalized value is linear in the FFT window size.
+        const fn make_custom_int_range() -> IntRange {
+            IntRange::Custom {
+                min: 6,
+                max: 10,
+                normalize: normalize_fft_order,
+                unnormalize: unnormalize_fft_order,
+            }
+        }
+
+        #[test]
+        fn range_normalize_int() {
+            let range = make_custom_int_range();
+            assert_eq!(range.normalize(6), 0.0);
+            assert_eq!(range.normalize(10), 1.0);
+            assert_eq!(range.normalize(20), 1.0);
+        }
+
+        #[test]
+        fn range_unnormalize_int() {
+            let range = make_custom_int_range();
+            assert_eq!(range.unnormalize(range.normalize(8)), 8);
+            assert_eq!(range.unnormalize(-1.0), 6);
+        }
+
+        #[test]
+        fn range_is_linear() {
+            const WRAPPED_RANGE: IntRange = make_custom_int_range();
+            assert!(!IntRange::Reversed(&WRAPPED_RANGE).is_linear());
+            assert!(make_linear_int_range().is_linear());
+        }
+    }
+
     mod reversed_skewed {
         use super::*;
 
@@ -6,6 +6,12 @@ use std::sync::Arc;
 // Re-exported here because it's sued in `SmoothingStyle`.
 pub use atomic_float::AtomicF32;
 
+mod block;
+mod poly;
+
+pub use block::{SmoothedBlock, SmoothedBlockIter};
+pub use poly::PolySmoother;
+
 /// Controls if and how parameters gets smoothed.
 #[derive(Debug, Clone)]
 pub enum SmoothingStyle {
@@ -37,8 +43,25 @@ pub enum SmoothingStyle {
     /// This results in a smoother transition, with the caveat being that there will be a tiny jump
     /// at the end. Unlike the `Logarithmic` option, this does support crossing the zero value.
     Exponential(f32),
+    /// Smooth parameter changes along the curve of a logarithmic function. Like `Exponential` this
+    /// starts out fast and then slows down, but the target value is reached exactly in this many
+    /// milliseconds without a jump at the end. This also supports crossing the zero value. Since
+    /// the step size depends on the position within the transition, the smoother computes these
+    /// values using [`SmoothingStyle::value_at()`] instead of [`SmoothingStyle::next()`].
+    LogarithmicApproach(f32),
+    /// Smooth parameter changes along an S-shaped `tanh()` curve, starting out slow, speeding up
+    /// halfway through, and then slowing down again until the target value is reached in exactly
+    /// this many milliseconds. This avoids the sudden changes in slope at the start and end of the
+    /// other smoothing styles. Since the step size depends on the position within the
+    /// transition, the smoother computes these values using [`SmoothingStyle::value_at()`] instead
+    /// of [`SmoothingStyle::next()`].
+    SCurve(f32),
 }
 
+/// The steepness of the `tanh()` curve used for [`SmoothingStyle::SCurve`]. The curve is
+/// evaluated between `-S_CURVE_STEEPNESS` and `S_CURVE_STEEPNESS`, and then normalized.
+const S_CURVE_STEEPNESS: f32 = 3.0;
+
 /// A smoother, providing a smoothed value for each sample.
 //
 // TODO: We need to use atomics here so we can share the params object with the GUI. Is there a
@@ -61,6 +84,9 @@ pub struct Smoother<T: Smoothable> {
     step_size: AtomicF32,
     /// The value for the current sample. Always stored as floating point for obvious reasons.
     current: AtomicF32,
+    /// The value at the start of the current transition. Used for the smoothing styles that compute
+    /// their values using [`SmoothingStyle::value_at()`].
+    start: AtomicF32,
     /// The value we're smoothing towards
     target: T::Atomic,
 }
@@ -86,7 +112,11 @@ impl SmoothingStyle {
             }
 
             Self::None => 1,
-            Self::Linear(time) | Self::Logarithmic(time) | Self::Exponential(time) => {
+            Self::Linear(time)
+            | Self::Logarithmic(time)
+            | Self::Exponential(time)
+            | Self::LogarithmicApproach(time)
+            | Self::SCurve(time) => {
                 nih_debug_assert!(*time >= 0.0);
                 (sample_rate * time / 1000.0).round() as u32
             }
@@ -115,6 +145,9 @@ impl SmoothingStyle {
             // reaches 99.99% of the target value after `num_steps`. The smoother will snap to the
             // target value after that point.
             Self::Exponential(_) => 0.0001f64.powf((num_steps as f64).recip()) as f32,
+            // These styles compute their values from the position within the transition, so the
+            // step size is the amount that position changes each step
+            Self::LogarithmicApproach(_) | Self::SCurve(_) => (num_steps as f32).recip(),
         }
     }
 
@@ -124,6 +157,8 @@ impl SmoothingStyle {
     /// computing the target number of steps.
     ///
     /// See the docstring on the [`SmoothingStyle::next_step()`] function for the formulas used.
+    /// The `LogarithmicApproach` and `SCurve` styles can't compute their next value from the
+    /// current value alone. Use [`value_at()`][Self::value_at()] for those styles instead.
     #[inline]
     pub fn next(&self, current: f32, target: f32, step_size: f32) -> f32 {
         match self {
@@ -133,6 +168,13 @@ impl SmoothingStyle {
             Self::Linear(_) => current + step_size,
             Self::Logarithmic(_) => current * step_size,
             Self::Exponential(_) => (current * step_size) + (target * (1.0 - step_size)),
+            Self::LogarithmicApproach(_) | Self::SCurve(_) => {
+                nih_debug_assert_failure!(
+                    "'SmoothingStyle::next()' does not support this smoothing style, use \
+                     'SmoothingStyle::value_at()' instead"
+                );
+                target
+            }
         }
     }
 
@@ -158,6 +200,54 @@ impl SmoothingStyle {
                 let coefficient = step_size.powi(steps as i32);
                 (current * coefficient) + (target * (1.0 - coefficient))
             }
+            Self::LogarithmicApproach(_) | Self::SCurve(_) => {
+                nih_debug_assert_failure!(
+                    "'SmoothingStyle::next_step()' does not support this smoothing style, use \
+                     'SmoothingStyle::value_at()' instead"
+                );
+                target
+            }
+        }
+    }
+
+    /// Compute the value at a position within a transition from `start` to `target`, where
+    /// `progress` is in `[0, 1]`. A transition that takes `num_steps` steps is at position
+    /// `steps_taken / num_steps`. This works for all smoothing styles, and it's how the smoother
+    /// computes the values for the `LogarithmicApproach` and `SCurve` styles. For the
+    /// `Exponential` style the value at the end of the transition is 99.99% of the way towards
+    /// `target`.
+    #[inline]
+    pub fn value_at(&self, start: f32, target: f32, progress: f32) -> f32 {
+        match self {
+            Self::OversamplingAware(_, style) => style.value_at(start, target, progress),
+
+            Self::None => target,
+            Self::Linear(_) => start + ((target - start) * progress),
+            Self::Logarithmic(_) => {
+                nih_debug_assert_ne!(start, 0.0);
+                start * ((target / start) as f64).powf(progress as f64) as f32
+            }
+            Self::Exponential(_) => target + ((start - target) * 0.0001f32.powf(progress)),
+            // This is `log10(1 + 9x)`, which goes from 0 to 1 for `x` in `[0, 1]`
+            Self::LogarithmicApproach(_) => {
+                start + ((target - start) * (9.0 * progress).ln_1p() / 10.0f32.ln())
+            }
+            Self::SCurve(_) => {
+                let shaped = (S_CURVE_STEEPNESS * ((2.0 * progress) - 1.0)).tanh()
+                    / S_CURVE_STEEPNESS.tanh();
+                start + ((target - start) * (shaped + 1.0) / 2.0)
+            }
+        }
+    }
+
+    /// Whether this style computes its values using [`value_at()`][Self::value_at()] instead of
+    /// [`next()`][Self::next()].
+    #[inline]
+    fn uses_value_at(&self) -> bool {
+        match self {
+            Self::OversamplingAware(_, style) => style.uses_value_at(),
+            Self::LogarithmicApproach(_) | Self::SCurve(_) => true,
+            _ => false,
         }
     }
 }
@@ -185,6 +275,7 @@ impl<T: Smoothable> Default for Smoother<T> {
             steps_left: AtomicI32::new(0),
             step_size: Default::default(),
             current: AtomicF32::new(0.0),
+            start: AtomicF32::new(0.0),
             target: Default::default(),
         }
     }
@@ -208,6 +299,7 @@ impl<T: Smoothable> Clone for Smoother<T> {
             steps_left: AtomicI32::new(self.steps_left.load(Ordering::Relaxed)),
             step_size: AtomicF32::new(self.step_size.load(Ordering::Relaxed)),
             current: AtomicF32::new(self.current.load(Ordering::Relaxed)),
+            start: AtomicF32::new(self.start.load(Ordering::Relaxed)),
             target: T::atomic_new(T::atomic_load(&self.target)),
         }
     }
@@ -265,6 +357,7 @@ impl<T: Smoothable> Smoother<T> {
 
         let current = self.current.load(Ordering::Relaxed);
         let target_f32 = target.to_f32();
+        self.start.store(current, Ordering::Relaxed);
         self.step_size.store(
             if steps_left > 0 {
                 self.style.step_size(current, target_f32, steps_left as u32)
@@ -300,7 +393,7 @@ impl<T: Smoothable> Smoother<T> {
                 self.steps_left.store(0, Ordering::Relaxed);
                 target_f32
             } else {
-                self.style.next(current, target_f32, step_size)
+                self.step_value(current, target_f32, step_size, old_steps_left - 1)
             };
             self.current.store(new, Ordering::Relaxed);
 
@@ -334,6 +427,11 @@ impl<T: Smoothable> Smoother<T> {
             let new = if old_steps_left <= steps as i32 {
                 self.steps_left.store(0, Ordering::Relaxed);
                 target_f32
+            } else if self.style.uses_value_at() {
+                let start = self.start.load(Ordering::Relaxed);
+                let new_steps_left = old_steps_left - steps as i32;
+                self.style
+                    .value_at(start, target_f32, 1.0 - (new_steps_left as f32 * step_size))
             } else {
                 self.style.next_step(current, target_f32, step_size, steps)
             };
@@ -382,11 +480,13 @@ impl<T: Smoothable> Smoother<T> {
             let target_f32 = target.to_f32();
             let step_size = self.step_size.load(Ordering::Relaxed);
 
+            let mut new_steps_left = steps_left as i32;
             if num_smoothed_values == steps_left {
                 // This is the same as calling `next()` `num_smoothed_values` times, but with some
                 // conditionals optimized out
                 block_values[..num_smoothed_values - 1].fill_with(|| {
-                    current = self.style.next(current, target_f32, step_size);
+                    new_steps_left -= 1;
+                    current = self.step_value(current, target_f32, step_size, new_steps_left);
                     T::from_f32(current)
                 });
 
@@ -396,7 +496,8 @@ impl<T: Smoothable> Smoother<T> {
                 block_values[num_smoothed_values - 1] = target;
             } else {
                 block_values[..num_smoothed_values].fill_with(|| {
-                    current = self.style.next(current, target_f32, step_size);
+                    new_steps_left -= 1;
+                    current = self.step_value(current, target_f32, step_size, new_steps_left);
                     T::from_f32(current)
                 });
             }
@@ -427,10 +528,25 @@ impl<T: Smoothable> Smoother<T> {
 
     /// The same as [`next_block_exact()`][Self::next_block()], but with a function applied to each
     /// produced value. Useful when applying modulation to a smoothed parameter.
-    pub fn next_block_exact_mapped(
+    pub fn next_block_exact_mapped(&self, block_values: &mut [T], f: impl FnMut(usize, f32) -> T) {
+        self.next_block_exact_mapped_generic(block_values, f)
+    }
+
+    /// The same as [`next_block_exact()`][Self::next_block_exact()], but writing the values to an
+    /// `f32` slice regardless of the smoother's type. This can be used to fill any `f32` buffer,
+    /// like an aligned buffer used for SIMD processing or a slice of a larger buffer, including for
+    /// integer parameters.
+    pub fn next_block_exact_into(&self, block_values: &mut [f32]) {
+        self.next_block_exact_mapped_generic(block_values, |_, value| T::from_f32(value).to_f32())
+    }
+
+    /// The implementation for [`next_block_exact_mapped()`][Self::next_block_exact_mapped()] and
+    /// [`next_block_exact_into()`][Self::next_block_exact_into()], which can write to slices of
+    /// any type.
+    fn next_block_exact_mapped_generic<O>(
         &self,
-        block_values: &mut [T],
-        mut f: impl FnMut(usize, f32) -> T,
+        block_values: &mut [O],
+        mut f: impl FnMut(usize, f32) -> O,
     ) {
         // This works exactly the same as `next_block_exact()`, except for the addition of the
         // mapping function
@@ -443,13 +559,15 @@ impl<T: Smoothable> Smoother<T> {
             let step_size = self.step_size.load(Ordering::Relaxed);
 
             // See `next_block_exact()` for more details
+            let mut new_steps_left = steps_left as i32;
             if num_smoothed_values == steps_left {
                 for (idx, value) in block_values
                     .iter_mut()
                     .enumerate()
                     .take(num_smoothed_values - 1)
                 {
-                    current = self.style.next(current, target_f32, step_size);
+                    new_steps_left -= 1;
+                    current = self.step_value(current, target_f32, step_size, new_steps_left);
                     *value = f(idx, current);
                 }
 
@@ -461,7 +579,8 @@ impl<T: Smoothable> Smoother<T> {
                     .enumerate()
                     .take(num_smoothed_values)
                 {
-                    current = self.style.next(current, target_f32, step_size);
+                    new_steps_left -= 1;
+                    current = self.step_value(current, target_f32, step_size, new_steps_left);
                     *value = f(idx, current);
                 }
             }
@@ -483,6 +602,18 @@ impl<T: Smoothable> Smoother<T> {
             }
         }
     }
+
+    /// Compute the value for the step that leaves `steps_left` steps in the current transition.
+    #[inline]
+    fn step_value(&self, current: f32, target: f32, step_size: f32, steps_left: i32) -> f32 {
+        if self.style.uses_value_at() {
+            let start = self.start.load(Ordering::Relaxed);
+            self.style
+                .value_at(start, target, 1.0 - (steps_left as f32 * step_size))
+        } else {
+            self.style.next(current, target, step_size)
+        }
+    }
 }
 
 impl Smoothable for f32 {
@@ -713,5 +844,112 @@ mod tests {
         assert_eq!(smoother.next(), 20);
     }
 
-    // TODO: Tests for the exponential smoothing
+    /// All smoothing styles that have a duration, using the same 20 millisecond duration. At the
+    /// sample rates used in the tests this is always an even number of steps.
+    fn timed_styles() -> [SmoothingStyle; 5] {
+        [
+            SmoothingStyle::Linear(20.0),
+            SmoothingStyle::Logarithmic(20.0),
+            SmoothingStyle::Exponential(20.0),
+            SmoothingStyle::LogarithmicApproach(20.0),
+            SmoothingStyle::SCurve(20.0),
+        ]
+    }
+
+    /// The smoothers should reach the target value after the same amount of time at every sample
+    /// rate, and the values halfway through the transition should also match.
+    #[test]
+    fn sample_rate_independent_timing() {
+        for style in timed_styles() {
+            let expected_halfway_value = style.value_at(10.0, 20.0, 0.5);
+            for sample_rate in [44_100.0, 48_000.0, 88_200.0, 96_000.0, 192_000.0] {
+                let num_steps = style.num_steps(sample_rate);
+                assert_eq!(num_steps, (sample_rate * 0.02).round() as u32);
+
+                let smoother: Smoother<f32> = Smoother::new(style.clone());
+                smoother.reset(10.0);
+                smoother.set_target(sample_rate, 20.0);
+                for _ in 0..(num_steps / 2 - 1) {
+                    smoother.next();
+                }
+                approx::assert_relative_eq!(
+                    smoother.next(),
+                    expected_halfway_value,
+                    epsilon = 1e-3
+                );
+
+                for _ in 0..(num_steps / 2 - 1) {
+                    assert_ne!(smoother.next(), 20.0);
+                }
+                assert_eq!(smoother.next(), 20.0);
+                assert!(!smoother.is_smoothing());
+            }
+        }
+    }
+
+    #[test]
+    fn curve_shapes() {
+        let linear = SmoothingStyle::Linear(20.0).value_at(0.0, 1.0, 0.1);
+        let logarithmic_approach =
+            SmoothingStyle::LogarithmicApproach(20.0).value_at(0.0, 1.0, 0.1);
+        let s_curve = SmoothingStyle::SCurve(20.0).value_at(0.0, 1.0, 0.1);
+        assert!(logarithmic_approach > linear);
+        assert!(s_curve < linear);
+
+        for style in [
+            SmoothingStyle::LogarithmicApproach(20.0),
+            SmoothingStyle::SCurve(20.0),
+        ] {
+            approx::assert_relative_eq!(style.value_at(-1.0, 1.0, 0.0), -1.0, epsilon = 1e-6);
+            approx::assert_relative_eq!(style.value_at(-1.0, 1.0, 1.0), 1.0, epsilon = 1e-6);
+        }
+        approx::assert_relative_eq!(SmoothingStyle::SCurve(20.0).value_at(-1.0, 1.0, 0.5), 0.0);
+    }
+
+    /// Same as [`linear_f32_smoothing`], but for the S-curve and skipping steps.
+    #[test]
+    fn skipping_s_curve_f32_smoothing() {
+        let smoother: Smoother<f32> = Smoother::new(SmoothingStyle::SCurve(100.0));
+        smoother.reset(10.0);
+
+        smoother.set_target(100.0, 20.0);
+        approx::assert_relative_eq!(smoother.next_step(5), 15.0, epsilon = 1e-5);
+        smoother.next_step(3);
+        assert_ne!(smoother.next(), 20.0);
+        assert_eq!(smoother.next(), 20.0);
+    }
+
+    /// Filling a block should produce the same values as calling `next()` for every sample,
+    /// including when the transition ends in the middle of a block.
+    #[test]
+    fn next_block_exact_into_equivalence() {
+        for style in timed_styles() {
+            let block_smoother: Smoother<f32> = Smoother::new(style.clone());
+            block_smoother.reset(10.0);
+            block_smoother.set_target(48_000.0, 20.0);
+            let smoother = block_smoother.clone();
+
+            let mut block_values = [0.0; 1024];
+            for block in block_values.chunks_mut(100) {
+                block_smoother.next_block_exact_into(block);
+            }
+            for value in block_values {
+                assert_eq!(value, smoother.next());
+            }
+        }
+    }
+
+    #[test]
+    fn next_block_exact_into_i32() {
+        let smoother: Smoother<i32> = Smoother::new(SmoothingStyle::Linear(100.0));
+        smoother.reset(10);
+        smoother.set_target(100.0, 20);
+
+        let mut block_values = [0.0; 12];
+        smoother.next_block_exact_into(&mut block_values);
+        assert_eq!(
+            block_values,
+            [11.0, 12.0, 13.0, 14.0, 15.0, 16.0, 17.0, 18.0, 19.0, 20.0, 20.0, 20.0]
+        );
+    }
 }
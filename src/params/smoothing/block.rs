@@ -0,0 +1,169 @@
+//! Computing the smoothed values for several parameters at once.
+
+use crate::params::FloatParam;
+
+/// Preallocated storage for the per-sample smoothed values of `N` [`FloatParam`]s. The values are
+/// stored as a struct of arrays, with one contiguous slice per parameter. Filling this once per
+/// block and then iterating over the samples avoids calling every parameter's smoother for every
+/// sample, which helps in plugins where many parameters are automated at the same time.
+///
+/// ```
+/// # use nih_plug::prelude::*;
+/// # fn process(gain: &FloatParam, pan: &FloatParam, buffer: &mut Buffer) {
+/// // This should be allocated in `Plugin::initialize()`
+/// let mut smoothed = SmoothedBlock::<2>::new(512);
+///
+/// for (_, block) in buffer.iter_blocks(512) {
+///     let block_len = block.samples();
+///     for (sample_idx, [gain, pan]) in smoothed
+///         .iter_smoothed_block([gain, pan], block_len)
+///         .enumerate()
+///     {
+///         // Use `gain` and `pan` for the sample at `sample_idx`
+///     }
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct SmoothedBlock<const N: usize> {
+    /// The smoothed values for each parameter, in the same order as the parameters passed to
+    /// [`iter_smoothed_block()`][Self::iter_smoothed_block()].
+    values: [Vec<f32>; N],
+    /// The number of values computed during the last call to
+    /// [`iter_smoothed_block()`][Self::iter_smoothed_block()].
+    block_len: usize,
+}
+
+/// An iterator over the samples in a [`SmoothedBlock`]. Yields every parameter's smoothed value
+/// for one sample at a time.
+#[derive(Debug)]
+pub struct SmoothedBlockIter<'a, const N: usize> {
+    values: &'a [Vec<f32>; N],
+    sample_idx: usize,
+    block_len: usize,
+}
+
+impl<const N: usize> SmoothedBlock<N> {
+    /// Allocate storage for blocks of up to `max_block_size` samples.
+    pub fn new(max_block_size: usize) -> Self {
+        Self {
+            values: std::array::from_fn(|_| vec![0.0; max_block_size]),
+            block_len: 0,
+        }
+    }
+
+    /// The maximum block size this object can hold.
+    pub fn max_block_size(&self) -> usize {
+        self.values.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// Compute the next `block_len` smoothed values for each of the parameters, and iterate over
+    /// them one sample at a time. This advances the parameters' smoothers just like
+    /// [`Smoother::next_block()`][super::Smoother::next_block()]. `block_len` cannot be larger
+    /// than the maximum block size passed to [`new()`][Self::new()].
+    pub fn iter_smoothed_block(
+        &mut self,
+        params: [&FloatParam; N],
+        block_len: usize,
+    ) -> SmoothedBlockIter<'_, N> {
+        nih_debug_assert!(
+            block_len <= self.max_block_size(),
+            "The block length exceeds the maximum block size"
+        );
+        let block_len = block_len.min(self.max_block_size());
+
+        for (values, param) in self.values.iter_mut().zip(params) {
+            param.smoothed.next_block(values, block_len);
+        }
+        self.block_len = block_len;
+
+        SmoothedBlockIter {
+            values: &self.values,
+            sample_idx: 0,
+            block_len,
+        }
+    }
+
+    /// The smoothed values computed for the parameter at `index` during the last call to
+    /// [`iter_smoothed_block()`][Self::iter_smoothed_block()].
+    pub fn values(&self, index: usize) -> &[f32] {
+        &self.values[index][..self.block_len]
+    }
+}
+
+impl<const N: usize> Iterator for SmoothedBlockIter<'_, N> {
+    type Item = [f32; N];
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.sample_idx < self.block_len {
+            let sample_idx = self.sample_idx;
+            self.sample_idx += 1;
+
+            Some(std::array::from_fn(|param_idx| {
+                self.values[param_idx][sample_idx]
+            }))
+        } else {
+            None
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.block_len - self.sample_idx;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<const N: usize> ExactSizeIterator for SmoothedBlockIter<'_, N> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::range::FloatRange;
+    use crate::params::smoothing::SmoothingStyle;
+    use crate::params::ParamMut;
+
+    #[test]
+    fn yields_every_sample() {
+        let a = FloatParam::new("A", 0.25, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let b = FloatParam::new("B", 0.75, FloatRange::Linear { min: 0.0, max: 1.0 });
+        a.update_smoother(44_100.0, true);
+        b.update_smoother(44_100.0, true);
+
+        let mut block = SmoothedBlock::<2>::new(8);
+        let frames: Vec<[f32; 2]> = block.iter_smoothed_block([&a, &b], 4).collect();
+        assert_eq!(frames, vec![[0.25, 0.75]; 4]);
+        assert_eq!(block.values(1), &[0.75; 4]);
+    }
+
+    #[test]
+    fn ramps_after_mid_block_target_change() {
+        let a = FloatParam::new("A", 0.0, FloatRange::Linear { min: 0.0, max: 1.0 })
+            .with_smoother(SmoothingStyle::Linear(4.0));
+        let b = FloatParam::new("B", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 });
+        a.update_smoother(1000.0, true);
+        b.update_smoother(1000.0, true);
+
+        // The buffer is split at the sample where the target changes, so the smoothed values after
+        // that point should ramp from the old value to the new one over the next four samples
+        let mut block = SmoothedBlock::<2>::new(8);
+        let frames: Vec<[f32; 2]> = block.iter_smoothed_block([&a, &b], 2).collect();
+        assert_eq!(frames, vec![[0.0, 0.5]; 2]);
+
+        a.smoothed.set_target(1000.0, 1.0);
+        let frames: Vec<[f32; 2]> = block.iter_smoothed_block([&a, &b], 6).collect();
+        assert_eq!(
+            frames,
+            vec![
+                [0.25, 0.5],
+                [0.5, 0.5],
+                [0.75, 0.5],
+                [1.0, 0.5],
+                [1.0, 0.5],
+                [1.0, 0.5]
+            ]
+        );
+        assert_eq!(block.values(0), &[0.25, 0.5, 0.75, 1.0, 1.0, 1.0]);
+    }
+}
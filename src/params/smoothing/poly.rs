@@ -0,0 +1,321 @@
+//! Per-voice smoothing for polyphonically modulated parameters.
+
+use super::Smoother;
+use crate::midi::sysex::SysExMessage;
+use crate::midi::NoteEvent;
+use crate::params::{FloatParam, Param};
+
+/// Smooths a polyphonically modulated [`FloatParam`]'s value separately for each voice. This
+/// combines the parameter's global automation with the per-voice modulation offsets sent through
+/// [`NoteEvent::PolyModulation`] and [`NoteEvent::MonoAutomation`] events, so a synthesizer only
+/// needs to forward its note events and look up its voices' values by voice ID. Voices that don't
+/// receive polyphonic modulation use the parameter's global smoothed values.
+///
+/// The parameter needs to have a poly modulation ID set through
+/// [`FloatParam::with_poly_modulation_id()`], and the plugin needs to have a
+/// [`PolyModulationConfig`][crate::prelude::PolyModulationConfig]. The plugin is responsible for
+/// calling [`terminate_voice()`][Self::terminate_voice()] when a voice ends, usually at the same
+/// time it sends a [`NoteEvent::VoiceTerminated`] event to the host.
+///
+/// ```
+/// # use nih_plug::prelude::*;
+/// # fn process(param: &FloatParam, context: &mut impl ProcessContext<impl Plugin>, voice_ids: &[i32]) {
+/// let mut gain = PolySmoother::new(16);
+///
+/// // Forward the note events to the smoother while handling them
+/// while let Some(event) = context.next_event() {
+///     gain.handle_event(param, 44_100.0, &event);
+/// }
+///
+/// // Then compute the global values once per block, and each voice's values from those
+/// let mut global_gain = [0.0; 64];
+/// let mut voice_gain = [0.0; 64];
+/// param.smoothed.next_block_exact(&mut global_gain);
+/// for voice_id in voice_ids {
+///     let values = gain.next_block(*voice_id, &global_gain, &mut voice_gain);
+/// }
+/// # }
+/// ```
+#[derive(Debug, Clone)]
+pub struct PolySmoother {
+    /// The active voices. This never grows beyond its initial capacity, so adding voices doesn't
+    /// allocate.
+    voices: Vec<PolySmootherVoice>,
+}
+
+#[derive(Debug, Clone)]
+struct PolySmootherVoice {
+    /// The voice's ID. If the note on event did not include a voice ID, then this is computed from
+    /// the note and channel.
+    voice_id: i32,
+    channel: u8,
+    note: u8,
+    /// Whether values have been produced for this voice yet. Polyphonic modulation for a voice that
+    /// has not yet produced any values is applied immediately instead of being smoothed.
+    started: bool,
+    /// The voice's normalized modulation offset and the smoother for its modulated value, if it
+    /// has received polyphonic modulation.
+    modulation: Option<(f32, Smoother<f32>)>,
+}
+
+impl PolySmoother {
+    /// Create a new poly smoother that can track up to `max_voices` voices at the same time. This
+    /// should be the same as the plugin's maximum number of voices.
+    pub fn new(max_voices: usize) -> Self {
+        Self {
+            voices: Vec::with_capacity(max_voices),
+        }
+    }
+
+    /// Update the voices based on a note event. `param` is the parameter this smoother is for.
+    /// Note on events start tracking a voice, choke events stop tracking the voice, and polyphonic
+    /// modulation and monophonic automation events for the parameter's poly modulation ID update
+    /// the voices' smoothers. Other events are ignored.
+    pub fn handle_event<S: SysExMessage>(
+        &mut self,
+        param: &FloatParam,
+        sample_rate: f32,
+        event: &NoteEvent<S>,
+    ) {
+        match *event {
+            NoteEvent::NoteOn {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => {
+                let voice_id = voice_id.unwrap_or_else(|| compute_fallback_voice_id(note, channel));
+                self.terminate_voice(voice_id);
+                if self.voices.len() == self.voices.capacity() {
+                    nih_debug_assert_failure!(
+                        "The poly smoother can't track more than {} voices",
+                        self.voices.capacity()
+                    );
+                    return;
+                }
+
+                self.voices.push(PolySmootherVoice {
+                    voice_id,
+                    channel,
+                    note,
+                    started: false,
+                    modulation: None,
+                });
+            }
+            NoteEvent::Choke {
+                voice_id,
+                channel,
+                note,
+                ..
+            } => {
+                self.voices.retain(|voice| match voice_id {
+                    Some(voice_id) => voice.voice_id != voice_id,
+                    None => voice.channel != channel || voice.note != note,
+                });
+            }
+            NoteEvent::PolyModulation {
+                voice_id,
+                poly_modulation_id,
+                normalized_offset,
+                ..
+            } if param.poly_modulation_id() == Some(poly_modulation_id) => {
+                // Hosts also send modulation for voices that have not started yet or that have
+                // already ended, so these are silently ignored
+                let Some(voice) = self
+                    .voices
+                    .iter_mut()
+                    .find(|voice| voice.voice_id == voice_id)
+                else {
+                    return;
+                };
+
+                let target = param.preview_modulated(normalized_offset);
+                let (offset, smoother) = voice
+                    .modulation
+                    .get_or_insert_with(|| (normalized_offset, param.smoothed.clone()));
+                *offset = normalized_offset;
+                if voice.started {
+                    smoother.set_target(sample_rate, target);
+                } else {
+                    smoother.reset(target);
+                }
+            }
+            NoteEvent::MonoAutomation {
+                poly_modulation_id,
+                normalized_value,
+                ..
+            } if param.poly_modulation_id() == Some(poly_modulation_id) => {
+                // The modulation offsets are relative to the parameter's automated value, so the
+                // modulated voices' targets need to move along with it
+                for voice in &self.voices {
+                    if let Some((offset, smoother)) = &voice.modulation {
+                        smoother.set_target(
+                            sample_rate,
+                            param.preview_plain(normalized_value + offset),
+                        );
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Stop tracking a voice. Call this when the voice has ended.
+    pub fn terminate_voice(&mut self, voice_id: i32) {
+        self.voices.retain(|voice| voice.voice_id != voice_id);
+    }
+
+    /// Stop tracking all voices. Call this from [`Plugin::reset()`][crate::prelude::Plugin::reset()].
+    pub fn reset(&mut self) {
+        self.voices.clear();
+    }
+
+    /// Whether a voice has received polyphonic modulation for this parameter.
+    pub fn is_modulated(&self, voice_id: i32) -> bool {
+        self.voices
+            .iter()
+            .any(|voice| voice.voice_id == voice_id && voice.modulation.is_some())
+    }
+
+    /// Get the next value for a voice. `global_value` is the parameter's next smoothed value, which
+    /// is returned if the voice is not modulated. This should be called exactly once per voice per
+    /// sample, after obtaining the global value using [`Smoother::next()`].
+    pub fn next(&mut self, voice_id: i32, global_value: f32) -> f32 {
+        match self.voice_mut(voice_id) {
+            Some(PolySmootherVoice {
+                modulation: Some((_, smoother)),
+                ..
+            }) => smoother.next(),
+            _ => global_value,
+        }
+    }
+
+    /// Produce the values for a voice for an entire block. `global_values` contains the
+    /// parameter's smoothed values for the block, for instance computed using
+    /// [`Smoother::next_block()`]. If the voice is modulated, then its values are written to
+    /// `voice_values` and that slice is returned. Otherwise `global_values` is returned. This
+    /// should be called exactly once per voice per block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `voice_values` is shorter than `global_values`.
+    pub fn next_block<'a>(
+        &mut self,
+        voice_id: i32,
+        global_values: &'a [f32],
+        voice_values: &'a mut [f32],
+    ) -> &'a [f32] {
+        match self.voice_mut(voice_id) {
+            Some(PolySmootherVoice {
+                modulation: Some((_, smoother)),
+                ..
+            }) => {
+                let voice_values = &mut voice_values[..global_values.len()];
+                smoother.next_block_exact(voice_values);
+                voice_values
+            }
+            _ => global_values,
+        }
+    }
+
+    /// Find a voice and mark it as started, since this is only called when producing values.
+    fn voice_mut(&mut self, voice_id: i32) -> Option<&mut PolySmootherVoice> {
+        let voice = self
+            .voices
+            .iter_mut()
+            .find(|voice| voice.voice_id == voice_id)?;
+        voice.started = true;
+
+        Some(voice)
+    }
+}
+
+/// Compute a voice ID for a note on event that did not include one. This is only used internally
+/// to match polyphonic modulation events to voices.
+const fn compute_fallback_voice_id(note: u8, channel: u8) -> i32 {
+    note as i32 | ((channel as i32) << 16)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::params::range::FloatRange;
+    use crate::params::smoothing::SmoothingStyle;
+
+    const SAMPLE_RATE: f32 = 100.0;
+    const POLY_MOD_ID: u32 = 0;
+
+    fn param() -> FloatParam {
+        let param = FloatParam::new("Gain", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 })
+            .with_poly_modulation_id(POLY_MOD_ID)
+            .with_smoother(SmoothingStyle::Linear(100.0));
+        param.smoothed.reset(0.5);
+
+        param
+    }
+
+    fn note_on(voice_id: i32) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing: 0,
+            voice_id: Some(voice_id),
+            channel: 0,
+            note: 60,
+            velocity: 1.0,
+        }
+    }
+
+    fn poly_modulation(voice_id: i32, normalized_offset: f32) -> NoteEvent<()> {
+        NoteEvent::PolyModulation {
+            timing: 0,
+            voice_id,
+            poly_modulation_id: POLY_MOD_ID,
+            normalized_offset,
+        }
+    }
+
+    #[test]
+    fn test_unmodulated_voice_uses_global_values() {
+        let param = param();
+        let mut smoother = PolySmoother::new(4);
+        smoother.handle_event(&param, SAMPLE_RATE, &note_on(1));
+
+        let global_values = [0.5; 4];
+        let mut voice_values = [0.0; 4];
+        assert!(!smoother.is_modulated(1));
+        assert_eq!(
+            smoother.next_block(1, &global_values, &mut voice_values),
+            &global_values
+        );
+    }
+
+    #[test]
+    fn test_modulation_is_per_voice() {
+        let param = param();
+        let mut smoother = PolySmoother::new(4);
+        smoother.handle_event(&param, SAMPLE_RATE, &note_on(1));
+        smoother.handle_event(&param, SAMPLE_RATE, &note_on(2));
+        smoother.handle_event(&param, SAMPLE_RATE, &poly_modulation(1, 0.25));
+
+        // Modulation for a voice that hasn't produced any values yet is applied immediately
+        assert_eq!(smoother.next(1, 0.5), 0.75);
+        assert_eq!(smoother.next(2, 0.5), 0.5);
+
+        // Afterwards it's smoothed, and changes to the automated value move the modulated target
+        smoother.handle_event(
+            &param,
+            SAMPLE_RATE,
+            &NoteEvent::MonoAutomation {
+                timing: 0,
+                poly_modulation_id: POLY_MOD_ID,
+                normalized_value: 0.25,
+            },
+        );
+        let mut voice_values = [0.0; 10];
+        let values = smoother.next_block(1, &[0.25; 10], &mut voice_values);
+        assert_ne!(values[0], 0.5);
+        assert_eq!(values[9], 0.5);
+
+        smoother.terminate_voice(1);
+        assert!(!smoother.is_modulated(1));
+    }
+}
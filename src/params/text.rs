@@ -0,0 +1,239 @@
+//! Non-automatable string and file path parameters that are stored as part of the plugin's state.
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use std::fmt::Debug;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use super::persist::PersistentField;
+
+/// A text value that can be changed from the GUI or the main thread and read from the audio thread
+/// without locking. This is used for things like user defined labels. Unlike the other parameter
+/// types, strings cannot be automated, and none of the plugin APIs currently supported by NIH-plug
+/// can expose string parameters to a host's generic UI. Because of that, these are stored in the
+/// plugin's state as a persistent field using the `#[persist = "key"]` attribute instead of the
+/// `#[id = "..."]` attribute:
+///
+/// ```ignore
+/// #[derive(Params)]
+/// struct MyParams {
+///     #[persist = "label"]
+///     label: StringParam,
+/// }
+/// ```
+pub struct StringParam {
+    name: String,
+    value: SharedValue<String>,
+}
+
+/// A file path that can be changed from the GUI or the main thread and read from the audio thread
+/// without locking, for instance for sample or impulse response files. Like [`StringParam`], this is
+/// stored in the plugin's state through the `#[persist = "key"]` attribute. Only the path is
+/// stored, so the plugin is still responsible for (re)loading the file after the state has been
+/// restored. [`take_changed()`][Self::take_changed()] can be used to detect this.
+pub struct PathParam {
+    name: String,
+    value: SharedValue<Option<PathBuf>>,
+}
+
+/// The shared implementation for [`StringParam`] and [`PathParam`].
+struct SharedValue<T> {
+    value: ArcSwap<T>,
+    /// The value that was replaced by the last update. Holding on to this makes it very unlikely
+    /// that the audio thread ends up dropping the last reference to an old value, which would
+    /// deallocate memory on the audio thread.
+    retired: Mutex<Option<Arc<T>>>,
+    /// Set whenever the value changes. Cleared by `take_changed()`.
+    changed: AtomicBool,
+}
+
+impl<T> SharedValue<T> {
+    fn new(value: T) -> Self {
+        Self {
+            value: ArcSwap::from_pointee(value),
+            retired: Mutex::new(None),
+            // This way the plugin will also pick up the default value the first time it checks
+            changed: AtomicBool::new(true),
+        }
+    }
+
+    fn store(&self, value: T) {
+        let old_value = self.value.swap(Arc::new(value));
+        *self.retired.lock() = Some(old_value);
+        self.changed.store(true, Ordering::Release);
+    }
+
+    fn take_changed(&self) -> bool {
+        self.changed.swap(false, Ordering::AcqRel)
+    }
+}
+
+impl Debug for StringParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:?}", self.name, self.value.value.load().as_str())
+    }
+}
+
+impl Debug for PathParam {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {:?}", self.name, self.value.value.load().as_deref())
+    }
+}
+
+impl StringParam {
+    /// Build a new [`StringParam`]. The name is only used for display purposes.
+    pub fn new(name: impl Into<String>, default: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: SharedValue::new(default.into()),
+        }
+    }
+
+    /// The human readable name for this parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the current value. This is realtime-safe, so it can be used from the audio thread. The
+    /// returned guard should not be held on to for longer than a single processing cycle.
+    #[inline]
+    pub fn load(&self) -> arc_swap::Guard<Arc<String>> {
+        self.value.value.load()
+    }
+
+    /// Replace the value. This allocates, so it should only be called from the GUI or the main
+    /// thread.
+    pub fn set(&self, value: impl Into<String>) {
+        self.value.store(value.into());
+    }
+
+    /// Returns `true` if the value has changed since the last time this function was called, or if
+    /// this function has never been called before.
+    pub fn take_changed(&self) -> bool {
+        self.value.take_changed()
+    }
+}
+
+impl PathParam {
+    /// Build a new [`PathParam`] that does not point to any file yet. The name is only used for
+    /// display purposes.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            value: SharedValue::new(None),
+        }
+    }
+
+    /// The human readable name for this parameter.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Get the current path, if one has been set. This is realtime-safe, so it can be used from the
+    /// audio thread. The returned guard should not be held on to for longer than a single
+    /// processing cycle.
+    #[inline]
+    pub fn load(&self) -> arc_swap::Guard<Arc<Option<PathBuf>>> {
+        self.value.value.load()
+    }
+
+    /// Replace the path. This allocates, so it should only be called from the GUI or the main
+    /// thread.
+    pub fn set(&self, path: impl AsRef<Path>) {
+        self.value.store(Some(path.as_ref().to_owned()));
+    }
+
+    /// Clear the path.
+    pub fn clear(&self) {
+        self.value.store(None);
+    }
+
+    /// Returns `true` if the path has changed since the last time this function was called, or if
+    /// this function has never been called before. This can be used to (re)load the file when the
+    /// path changes, including when the plugin's state is restored.
+    pub fn take_changed(&self) -> bool {
+        self.value.take_changed()
+    }
+}
+
+impl PersistentField<'_, String> for StringParam {
+    fn set(&self, new_value: String) {
+        self.value.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&String) -> R,
+    {
+        f(&self.load())
+    }
+}
+
+impl PersistentField<'_, String> for Arc<StringParam> {
+    fn set(&self, new_value: String) {
+        self.as_ref().value.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&String) -> R,
+    {
+        self.as_ref().map(f)
+    }
+}
+
+impl PersistentField<'_, Option<PathBuf>> for PathParam {
+    fn set(&self, new_value: Option<PathBuf>) {
+        self.value.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&Option<PathBuf>) -> R,
+    {
+        f(&self.load())
+    }
+}
+
+impl PersistentField<'_, Option<PathBuf>> for Arc<PathParam> {
+    fn set(&self, new_value: Option<PathBuf>) {
+        self.as_ref().value.store(new_value);
+    }
+    fn map<F, R>(&self, f: F) -> R
+    where
+        F: Fn(&Option<PathBuf>) -> R,
+    {
+        self.as_ref().map(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn string_roundtrip() {
+        let param = StringParam::new("Label", "Default");
+        assert!(param.take_changed());
+        assert!(!param.take_changed());
+
+        let serialized = param.map(|value| serde_json::to_string(value).unwrap());
+        let restored = StringParam::new("Label", "");
+        PersistentField::set(&restored, serde_json::from_str(&serialized).unwrap());
+        assert_eq!(restored.load().as_str(), "Default");
+    }
+
+    #[test]
+    fn path_changes() {
+        let param = PathParam::new("Impulse Response");
+        assert!(param.take_changed());
+        assert!(param.load().is_none());
+
+        param.set("/tmp/ir.wav");
+        assert!(param.take_changed());
+        assert_eq!(param.load().as_deref(), Some(Path::new("/tmp/ir.wav")));
+
+        param.clear();
+        assert!(param.take_changed());
+        assert!(param.load().is_none());
+    }
+}
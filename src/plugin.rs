@@ -5,18 +5,52 @@ use std::sync::Arc;
 
 use crate::prelude::{
     AsyncExecutor, AudioIOLayout, AuxiliaryBuffers, Buffer, BufferConfig, Editor, InitContext,
-    MidiConfig, Params, PluginState, ProcessContext, SysExMessage,
+    MidiConfig, NotePort, Params, PluginState, PluginStateFormat, ProcessContext, ProcessMode,
+    SysExMessage, TrackInfo,
 };
+use crate::util::modulation::ModulationMatrix;
+use crate::util::PresetLibrary;
 
+#[cfg(feature = "ara")]
+pub mod ara;
+#[cfg(feature = "au")]
+pub mod au;
 pub mod clap;
+#[cfg(feature = "lv2")]
+pub mod lv2;
 #[cfg(feature = "vst3")]
 pub mod vst3;
+#[cfg(feature = "wam")]
+pub mod wam;
 
 /// A function that can execute a plugin's [`BackgroundTask`][Plugin::BackgroundTask]s. A plugin can
 /// dispatch these tasks from the `initialize()` function, the `process()` function, or the GUI, so
 /// they can be deferred for later to avoid blocking realtime contexts.
 pub type TaskExecutor<P> = Box<dyn Fn(<P as Plugin>::BackgroundTask) + Send>;
 
+/// The order in which pending background tasks are run. Tasks with a higher priority are always run
+/// before tasks with a lower priority, and tasks with the same priority are run in the order they
+/// were scheduled.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub enum TaskPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+}
+
+/// Scheduling options for a background task. See [`Plugin::background_task_options()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TaskOptions {
+    /// The task's priority. Defaults to [`TaskPriority::Normal`].
+    pub priority: TaskPriority,
+    /// An optional tag for the task. Pending tasks with this tag can be cancelled using
+    /// [`AsyncExecutor::cancel_background_tasks()`] or
+    /// [`ProcessContext::cancel_background_tasks()`], for instance to drop an obsolete waveform
+    /// rendering task before scheduling a new one.
+    pub tag: Option<u32>,
+}
+
 /// The main plugin trait covering functionality common across most plugin formats. Most formats
 /// also have another trait with more specific data and functionality that needs to be implemented
 /// before the plugin can be exported to that format. The wrappers will use this to expose the
@@ -106,6 +140,17 @@ pub trait Plugin: Default + Send + 'static {
     /// the plugin will consume all note and MIDI CC input. If you don't want that, then you will
     /// need to forward those events yourself.
     const MIDI_OUTPUT: MidiConfig = MidiConfig::None;
+    /// The plugin's note input ports. These are only used when [`MIDI_INPUT`][Self::MIDI_INPUT] is
+    /// set to [`MidiConfig::Basic`] or higher. Events for the other ports can be read using
+    /// [`ProcessContext::next_event_with_port()`][crate::prelude::ProcessContext::next_event_with_port()].
+    /// This should contain at least one port. Only the CLAP wrapper currently exposes more than one
+    /// port.
+    const MIDI_INPUT_PORTS: &'static [NotePort] = &[NotePort::new("Note Input")];
+    /// The plugin's note output ports. These are only used when [`MIDI_OUTPUT`][Self::MIDI_OUTPUT]
+    /// is set to [`MidiConfig::Basic`] or higher. Events can be sent to the other ports using
+    /// [`ProcessContext::send_event_to_port()`][crate::prelude::ProcessContext::send_event_to_port()].
+    /// Output ports should support either the CLAP or the MIDI dialect.
+    const MIDI_OUTPUT_PORTS: &'static [NotePort] = &[NotePort::new("Note Output")];
     /// If enabled, the audio processing cycle may be split up into multiple smaller chunks if
     /// parameter values change occur in the middle of the buffer. Depending on the host these
     /// blocks may be as small as a single sample. Bitwig Studio sends at most one parameter change
@@ -117,6 +162,34 @@ pub trait Plugin: Default + Send + 'static {
     /// to do offline processing.
     const HARD_REALTIME_ONLY: bool = false;
 
+    /// If this is set to true, then NIH-plug will handle the plugin's bypass parameter (the
+    /// [`BoolParam`][crate::prelude::BoolParam] created with
+    /// [`make_bypass()`][crate::prelude::BoolParam::make_bypass()]) for you. When the parameter is
+    /// enabled, the plugin's main output is crossfaded to its main input. That input is delayed by
+    /// the plugin's reported latency so the two signals line up. The plugin's `process()` function
+    /// is still called while bypassed, so it can check
+    /// [`ProcessContext::bypassed()`][crate::prelude::ProcessContext::bypassed()] to skip
+    /// expensive processing or to implement custom bypass behavior for its other outputs.
+    const MANAGED_BYPASS: bool = false;
+
+    /// The version of the plugin's state format. Increment this when renaming or removing
+    /// parameters or persistent fields, and implement [`Plugin::migrate_state()`] to convert states
+    /// saved with older versions. States saved before this was added have version 0.
+    const STATE_VERSION: u32 = 0;
+
+    /// Whether the plugin's state should be compressed using Zstandard when it is saved. This only
+    /// has an effect when NIH-plug's `zstd` feature is enabled. Compressed states are recognized by
+    /// their header, so states saved with and without compression can always be loaded regardless
+    /// of this setting. Disable this if the plugin's state is small, or if its blobs are already
+    /// compressed.
+    const COMPRESS_STATE: bool = true;
+
+    /// The format used to encode the plugin's state when it is saved. The default
+    /// [`PluginStateFormat::Json`] format is human readable, while the binary
+    /// `PluginStateFormat::MessagePack` format enabled by the `msgpack` feature is faster to save
+    /// and load for plugins with many parameters. States in either format can always be loaded.
+    const STATE_FORMAT: PluginStateFormat = PluginStateFormat::Json;
+
     /// The plugin's SysEx message type if it supports sending or receiving MIDI SysEx messages, or
     /// `()` if it does not. This type can be a struct or enum wrapping around one or more message
     /// types, and the [`SysExMessage`] trait is then used to convert between this type and basic
@@ -133,9 +206,17 @@ pub trait Plugin: Default + Send + 'static {
     // NOTE: Sadly it's not yet possible to default this and the `async_executor()` function to
     //       `()`: https://github.com/rust-lang/rust/issues/29661
     type BackgroundTask: Send;
+    /// Determine the priority and the cancellation tag for a background task scheduled using
+    /// [`ProcessContext::execute_background()`] or [`AsyncExecutor::execute_background()`]. By
+    /// default all tasks have a normal priority and no tag.
+    #[allow(unused_variables)]
+    fn background_task_options(task: &Self::BackgroundTask) -> TaskOptions {
+        TaskOptions::default()
+    }
     /// A function that executes the plugin's tasks. When implementing this you will likely want to
     /// pattern match on the task type, and then send any resulting data back over a channel or
-    /// triple buffer. See [`BackgroundTask`][Self::BackgroundTask].
+    /// triple buffer. [`TaskResults`][crate::prelude::TaskResults] is a realtime-safe channel for
+    /// sending results back to the audio thread. See [`BackgroundTask`][Self::BackgroundTask].
     ///
     /// Queried only once immediately after the plugin instance is created. This function takes
     /// `&mut self` to make it easier to move data into the closure.
@@ -151,6 +232,26 @@ pub trait Plugin: Default + Send + 'static {
     /// Queried only once immediately after the plugin instance is created.
     fn params(&self) -> Arc<dyn Params>;
 
+    /// Returns the plugin's [`ModulationMatrix`], if it uses one. The wrapper evaluates the
+    /// modulation matrix and modulates the routed parameters just before every call to
+    /// [`process()`][Self::process()]. The matrix should also be stored on the parameters object
+    /// with a `#[persist = "key"]` attribute so the routings are saved with the plugin's state.
+    ///
+    /// Queried only once immediately after the plugin instance is created.
+    fn modulation_matrix(&self) -> Option<Arc<ModulationMatrix>> {
+        None
+    }
+
+    /// Returns the plugin's [`PresetLibrary`], if it has one. The presets found in the library are
+    /// exposed to the host as a list of programs on plugin APIs that support this, so they can be
+    /// selected from the host's own UI. Right now this is only done for VST3.
+    ///
+    /// Queried only once immediately after the plugin instance is created. Presets that are added
+    /// to the library afterwards will not show up in the host until the plugin is reloaded.
+    fn preset_library(&self) -> Option<Arc<PresetLibrary>> {
+        None
+    }
+
     /// Returns an extension struct for interacting with the plugin's editor, if it has one. Later
     /// the host may call [`Editor::spawn()`] to create an editor instance. To read the current
     /// parameter values, you will need to clone and move the `Arc` containing your `Params` object
@@ -166,18 +267,53 @@ pub trait Plugin: Default + Send + 'static {
         None
     }
 
-    /// This function is always called just before a [`PluginState`] is loaded. This lets you
-    /// directly modify old plugin state to perform migrations based on the [`PluginState::version`]
-    /// field. Some examples of use cases for this are renaming parameter indices, remapping
-    /// parameter values, and preserving old preset compatibility when introducing new parameters
-    /// with default values that would otherwise change the sound of a preset. Keep in mind that
-    /// automation may still be broken in the first two use cases.
+    /// Called on the main thread after the plugin's editor has been opened. This can be used to
+    /// only collect expensive visualization data, like spectrum analyzer FFTs or waveform ring
+    /// buffers, while a GUI is attached. Every call is followed by a call to
+    /// [`editor_closed()`][Self::editor_closed()] when the editor is closed again.
+    ///
+    /// This may be called at any time, including while the plugin is processing audio, so the
+    /// processing function needs to be able to handle that. Since the audio thread cannot be
+    /// processing while this function holds on to the plugin, setting a field on `self` is enough.
+    fn editor_opened(&mut self) {}
+
+    /// Called on the main thread after the plugin's editor has been closed. See
+    /// [`editor_opened()`][Self::editor_opened()].
+    fn editor_closed(&mut self) {}
+
+    /// The plugin's ARA factory. Plugins implementing [`AraPlugin`][ara::AraPlugin] should return
+    /// `Some(AraFactory::for_plugin::<Self>())` here. The VST3 and CLAP wrappers use this to expose
+    /// the ARA factory alongside their regular entry points, and to bind plugin instances to the
+    /// host's ARA document controllers.
+    #[cfg(feature = "ara")]
+    fn ara_factory() -> Option<&'static crate::wrapper::ara::AraFactory> {
+        None
+    }
+
+    /// This function is always called just before a [`PluginState`] is loaded, after it has been
+    /// migrated to the current [`Plugin::STATE_VERSION`] using [`Plugin::migrate_state()`]. This
+    /// lets you directly modify old plugin state to perform migrations based on the
+    /// [`PluginState::version`] field. Some examples of use cases for this are renaming parameter
+    /// indices, remapping parameter values, and preserving old preset compatibility when
+    /// introducing new parameters with default values that would otherwise change the sound of a
+    /// preset. Keep in mind that automation may still be broken in the first two use cases.
     ///
     /// # Note
     ///
     /// This is an advanced feature that the vast majority of plugins won't need to implement.
     fn filter_state(state: &mut PluginState) {}
 
+    /// Migrate a [`PluginState`] in its JSON representation from state version `from_version` to
+    /// `from_version + 1`. When a state saved with an older [`Plugin::STATE_VERSION`] is loaded,
+    /// this is called once for every version in between before the state is passed to
+    /// [`Plugin::filter_state()`]. This can be used to rename parameter IDs in the `params` object,
+    /// remove obsolete values, or convert persistent fields in the `fields` object, so old sessions
+    /// keep working after the plugin's parameters change. The state is not loaded if the returned
+    /// value is no longer a valid `PluginState`.
+    fn migrate_state(from_version: u32, state: serde_json::Value) -> serde_json::Value {
+        state
+    }
+
     //
     // The following functions follow the lifetime of the plugin.
     //
@@ -215,12 +351,31 @@ pub trait Plugin: Default + Send + 'static {
     /// audio thread. You should thus not do any allocations in this function.
     fn reset(&mut self) {}
 
+    /// Called on the main thread when the host switches between realtime and offline rendering,
+    /// for instance when bouncing a project. The initial mode is passed to
+    /// [`initialize()`][Self::initialize()] through [`BufferConfig::process_mode`], and the current
+    /// mode is also available during processing through
+    /// [`ProcessContext::process_mode()`]. Plugins can use this to switch to higher quality
+    /// algorithms that are not realtime-safe during offline rendering. The plugin may still be
+    /// activated when this is called, so any expensive changes should be deferred until the next
+    /// `initialize()` call if possible.
+    #[allow(unused_variables)]
+    fn process_mode_changed(&mut self, mode: ProcessMode) {}
+
+    /// Called on the main thread when the host provides new information about the track the plugin
+    /// is inserted on, for instance because the user renamed the track. This may be called before
+    /// the plugin is initialized. The same information is also available during processing through
+    /// [`ProcessContext::track_info()`]. Only supported by CLAP and VST3 hosts.
+    #[allow(unused_variables)]
+    fn track_info_changed(&mut self, info: &TrackInfo) {}
+
     /// Process audio. The host's input buffers have already been copied to the output buffers if
     /// they are not processing audio in place (most hosts do however). All channels are also
     /// guaranteed to contain the same number of samples. Lastly, denormals have already been taken
     /// case of by NIH-plug, and you can optionally enable the `assert_process_allocs` feature to
     /// abort the program when any allocation occurs in the process function while running in debug
-    /// mode.
+    /// mode. The `detect_process_allocs` feature instead reports these allocations along with a
+    /// backtrace and lets the plugin continue running.
     ///
     /// The framework provides convenient iterators on the [`Buffer`] object to process audio either
     /// either per-sample per-channel, or per-block per-channel per-sample. The first approach is
@@ -0,0 +1,116 @@
+use super::Plugin;
+use crate::wrapper::ara::{
+    AraBinding, AraDocument, AraHost, AraObjectId, AraPlaybackTransformations,
+};
+
+/// Provides the metadata and the document controller needed to expose a plugin through ARA 2. ARA
+/// lets plugins like Melodyne directly access the audio sources in the host's arrangement, analyze
+/// them ahead of time, and render edited versions of the host's playback regions. ARA plugins are
+/// exported using the regular VST3 and CLAP export macros. In addition to implementing this trait,
+/// the plugin needs to implement [`Plugin::ara_factory()`] like this:
+///
+/// ```ignore
+/// fn ara_factory() -> Option<&'static AraFactory> {
+///     Some(AraFactory::for_plugin::<Self>())
+/// }
+/// ```
+///
+/// The host creates one document controller per document. Plugin instances are then bound to a
+/// document controller, after which [`ara_bound()`][Self::ara_bound()] is called with an
+/// [`AraBinding`] that gives the plugin access to the document and to the playback regions it
+/// should render.
+pub trait AraPlugin: Plugin {
+    /// A unique identifier for the plugin's ARA factory in reverse domain name notation, for
+    /// instance `com.moist-plugins-gmbh.pitch-editor.ara`.
+    const ARA_FACTORY_ID: &'static str;
+    /// An identifier for the plugin's document archive format. Hosts only pass archives to plugins
+    /// that use the same archive ID or that list it in
+    /// [`ARA_COMPATIBLE_DOCUMENT_ARCHIVE_IDS`][Self::ARA_COMPATIBLE_DOCUMENT_ARCHIVE_IDS].
+    const ARA_DOCUMENT_ARCHIVE_ID: &'static str;
+    /// Archive IDs from older versions of the plugin that can still be restored.
+    const ARA_COMPATIBLE_DOCUMENT_ARCHIVE_IDS: &'static [&'static str] = &[];
+    /// The transformations the plugin supports when rendering playback regions. Hosts will not
+    /// create playback regions using other transformations.
+    const ARA_PLAYBACK_TRANSFORMATIONS: AraPlaybackTransformations =
+        AraPlaybackTransformations::empty();
+
+    /// The plugin's document controller. This holds the plugin's analysis results and edits, and it
+    /// is shared between all plugin instances bound to the same document.
+    type DocumentController: AraDocumentController;
+
+    /// Create a document controller for a new document. The [`AraHost`] can be stored on the
+    /// document controller to read samples from the document's audio sources and to inform the
+    /// host about analysis progress.
+    fn create_document_controller(host: AraHost) -> Self::DocumentController;
+
+    /// Called when the host binds this plugin instance to a document controller. The binding should
+    /// be stored on the plugin so the playback regions assigned to this instance can be rendered in
+    /// [`process()`][Plugin::process()]. This is called at most once per plugin instance, before
+    /// the plugin is initialized.
+    #[allow(unused_variables)]
+    fn ara_bound(&mut self, binding: AraBinding<Self>) {}
+}
+
+/// The plugin's per-document state. All of these functions are called from the main thread, with
+/// the document reflecting the changes that have already been made. The host batches changes to
+/// the document in editing cycles, and [`editing_finished()`][Self::editing_finished()] is called
+/// at the end of every cycle.
+#[allow(unused_variables)]
+pub trait AraDocumentController: Send + 'static {
+    /// Called at the end of an editing cycle, after all changes to the document have been applied.
+    /// This is a good place to start analyzing new audio sources.
+    fn editing_finished(&mut self, document: &AraDocument) {}
+
+    /// Called after an audio source has been added to the document. Samples can only be read once
+    /// [`AraAudioSource::samples_access_enabled`][crate::prelude::AraAudioSource::samples_access_enabled]
+    /// has been set.
+    fn audio_source_added(&mut self, document: &AraDocument, audio_source: AraObjectId) {}
+    /// Called after an audio source's name, sample rate, sample count, or channel count has
+    /// changed. Existing analysis results may need to be invalidated.
+    fn audio_source_properties_changed(
+        &mut self,
+        document: &AraDocument,
+        audio_source: AraObjectId,
+    ) {
+    }
+    /// Called when the host informs the plugin that the samples of an audio source have changed.
+    fn audio_source_content_changed(&mut self, document: &AraDocument, audio_source: AraObjectId) {}
+    /// Called after the host has enabled or disabled reading samples from an audio source.
+    fn audio_source_samples_access_changed(
+        &mut self,
+        document: &AraDocument,
+        audio_source: AraObjectId,
+    ) {
+    }
+    /// Called just before an audio source is removed from the document.
+    fn audio_source_removed(&mut self, document: &AraDocument, audio_source: AraObjectId) {}
+
+    /// Called after a playback region has been added to the document.
+    fn playback_region_added(&mut self, document: &AraDocument, playback_region: AraObjectId) {}
+    /// Called after a playback region's timing or transformations have changed.
+    fn playback_region_changed(&mut self, document: &AraDocument, playback_region: AraObjectId) {}
+    /// Called just before a playback region is removed from the document.
+    fn playback_region_removed(&mut self, document: &AraDocument, playback_region: AraObjectId) {}
+
+    /// Serialize the analysis results and edits for an audio source so they can be stored in the
+    /// host's project. Returning `None` stores nothing for this audio source.
+    fn store_audio_source(
+        &mut self,
+        document: &AraDocument,
+        audio_source: AraObjectId,
+    ) -> Option<Vec<u8>> {
+        None
+    }
+    /// Restore the data previously returned from
+    /// [`store_audio_source()`][Self::store_audio_source()] for an audio source with the same
+    /// persistent ID. Return `false` if the data could not be restored, in which case the host will
+    /// be informed that restoring the document failed.
+    fn restore_audio_source(
+        &mut self,
+        document: &AraDocument,
+        audio_source: AraObjectId,
+        data: &[u8],
+    ) -> bool {
+        true
+    }
+}
@@ -0,0 +1,44 @@
+use super::Plugin;
+
+/// Provides auxiliary metadata needed for an Audio Unit plugin. The same type, subtype, and
+/// manufacturer codes also need to be added to the package's `bundler.toml` entry so the bundler
+/// can register the component with macOS.
+pub trait AuPlugin: Plugin {
+    /// The kind of Audio Unit this plugin is exposed as. This determines how hosts like Logic
+    /// insert the plugin.
+    const AU_TYPE: AuType;
+    /// A four character code identifying this plugin, for instance `*b"Gain"`. Together with
+    /// [`AU_MANUFACTURER`][Self::AU_MANUFACTURER] this needs to be unique, and Apple requires at
+    /// least one character to be uppercase.
+    const AU_SUBTYPE: [u8; 4];
+    /// A four character code identifying the plugin's vendor, for instance `*b"NIHP"`. Codes
+    /// consisting only of lowercase characters are reserved by Apple.
+    const AU_MANUFACTURER: [u8; 4];
+}
+
+/// The kind of Audio Unit a plugin is exposed as. See [`AuPlugin::AU_TYPE`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuType {
+    /// An audio effect (`aufx`). Effects must have a main audio input.
+    Effect,
+    /// An audio effect that also receives MIDI (`aumf`). Effects must have a main audio input.
+    MusicEffect,
+    /// An instrument (`aumu`). Instruments receive MIDI and don't have a main audio input.
+    MusicDevice,
+}
+
+impl AuType {
+    /// The four character code for this component type.
+    pub const fn as_four_cc(self) -> [u8; 4] {
+        match self {
+            AuType::Effect => *b"aufx",
+            AuType::MusicEffect => *b"aumf",
+            AuType::MusicDevice => *b"aumu",
+        }
+    }
+
+    /// Whether hosts send MIDI to components of this type through the `MusicDevice` API.
+    pub const fn receives_midi(self) -> bool {
+        matches!(self, AuType::MusicEffect | AuType::MusicDevice)
+    }
+}
@@ -21,9 +21,13 @@ pub trait ClapPlugin: Plugin {
     const CLAP_POLY_MODULATION_CONFIG: Option<PolyModulationConfig> = None;
 
     /// This function can be implemented to define plugin-specific [remote control
-    /// pages](https://github.com/free-audio/clap/blob/main/include/clap/ext/draft/remote-controls.h)
-    /// that the host can use to provide better hardware mapping for a plugin. See the linked
-    /// extension for more information.
+    /// pages](https://github.com/free-audio/clap/blob/main/include/clap/ext/remote-controls.h)
+    /// that the host can use to provide better hardware mapping for a plugin. Hardware controllers
+    /// and Bitwig Studio's remote controls will otherwise show the plugin's first eight
+    /// parameters. Each page holds up to eight parameters, and longer pages are split
+    /// automatically. See the linked extension for more information.
+    ///
+    /// This is called once when the plugin gets created.
     fn remote_controls(&self, context: &mut impl RemoteControlsContext) {}
 }
 
@@ -0,0 +1,14 @@
+use super::Plugin;
+
+/// Provides auxiliary metadata needed for an LV2 plugin. The bundler uses this information to
+/// generate the bundle's `manifest.ttl` and plugin description files.
+pub trait Lv2Plugin: Plugin {
+    /// A URI that uniquely identifies this plugin, for instance
+    /// `https://example.com/plugins/gain`. This URI should never change once the plugin has been
+    /// released, since hosts use it to refer to the plugin in saved projects.
+    const LV2_URI: &'static str;
+    /// The LV2 plugin classes this plugin belongs to, without the `lv2:` prefix. Hosts use these to
+    /// categorize the plugin, for instance `&["ReverbPlugin"]` or `&["InstrumentPlugin"]`. See the
+    /// [LV2 core vocabulary](https://lv2plug.in/ns/lv2core) for the available classes.
+    const LV2_CLASSES: &'static [&'static str] = &[];
+}
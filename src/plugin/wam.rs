@@ -0,0 +1,17 @@
+use super::Plugin;
+
+/// Provides auxiliary metadata needed for a Web Audio Module. The JavaScript glue code generated by
+/// `cargo xtask wam` reads this information from the compiled WebAssembly module to build the
+/// module's WAM descriptor.
+pub trait WamPlugin: Plugin {
+    /// A unique identifier for the plugin in reverse domain name notation, for instance
+    /// `com.moist-plugins-gmbh.gain`. Hosts use this to refer to the plugin in saved projects, so it
+    /// should never change once the plugin has been released.
+    const WAM_ID: &'static str;
+    /// A short description of the plugin, shown by hosts when browsing for plugins.
+    const WAM_DESCRIPTION: Option<&'static str> = None;
+    /// Keywords hosts can use to categorize the plugin, for instance `&["reverb", "effect"]`.
+    const WAM_KEYWORDS: &'static [&'static str] = &[];
+    /// Whether hosts should treat the plugin as an instrument.
+    const WAM_IS_INSTRUMENT: bool = false;
+}
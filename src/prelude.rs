@@ -4,9 +4,15 @@ pub use std::num::NonZeroU32;
 // Re-export the macros, derive macros are already re-exported from their respective modules
 pub use crate::debug::*;
 
+#[cfg(feature = "au")]
+pub use crate::nih_export_au;
 pub use crate::nih_export_clap;
+#[cfg(feature = "lv2")]
+pub use crate::nih_export_lv2;
 #[cfg(feature = "vst3")]
 pub use crate::nih_export_vst3;
+#[cfg(feature = "wam")]
+pub use crate::nih_export_wam;
 #[cfg(feature = "standalone")]
 pub use crate::wrapper::standalone::{nih_export_standalone, nih_export_standalone_with_args};
 
@@ -17,28 +23,54 @@ pub use crate::audio_setup::{
     new_nonzero_u32, AudioIOLayout, AuxiliaryBuffers, BufferConfig, PortNames, ProcessMode,
 };
 pub use crate::buffer::Buffer;
-pub use crate::context::gui::{AsyncExecutor, GuiContext, ParamSetter};
+pub use crate::context::gui::{
+    AsyncExecutor, GuiContext, ParamAutomationState, ParamChange, ParamChangeReceiver,
+    ParamEditHistory, ParamIndication, ParamMappingIndication, ParamSetter, StateSlot, TimerHandle,
+};
 pub use crate::context::init::InitContext;
-pub use crate::context::process::{ProcessContext, Transport};
+pub use crate::context::process::{ProcessContext, TaskResults, Transport};
 pub use crate::context::remote_controls::{
     RemoteControlsContext, RemoteControlsPage, RemoteControlsSection,
 };
-pub use crate::context::PluginApi;
+pub use crate::context::{PluginApi, TrackInfo};
 // This also includes the derive macro
-pub use crate::editor::{Editor, ParentWindowHandle};
+pub use crate::editor::{
+    Editor, EditorSizeConstraints, ParentWindowHandle, RawWindowEditor, RawWindowEditorAdapter,
+};
 pub use crate::midi::sysex::SysExMessage;
-pub use crate::midi::{control_change, MidiConfig, NoteEvent, PluginNoteEvent};
+pub use crate::midi::{
+    control_change, MidiConfig, NoteDialects, NoteEvent, NotePort, PluginNoteEvent,
+};
 pub use crate::params::enums::{Enum, EnumParam};
 pub use crate::params::internals::ParamPtr;
 pub use crate::params::range::{FloatRange, IntRange};
-pub use crate::params::smoothing::{AtomicF32, Smoothable, Smoother, SmoothingStyle};
+pub use crate::params::smoothing::{
+    AtomicF32, PolySmoother, Smoothable, SmoothedBlock, Smoother, SmoothingStyle,
+};
 pub use crate::params::Params;
-pub use crate::params::{BoolParam, FloatParam, IntParam, Param, ParamFlags};
+pub use crate::params::{
+    BoolParam, DynamicParam, DynamicParamLayout, FloatParam, IntParam, MacroMapping, MacroParam,
+    OutputParam, Param, ParamFlags, ParamLinkMode, PathParam, StringParam,
+};
+#[cfg(feature = "ara")]
+pub use crate::plugin::ara::{AraDocumentController, AraPlugin};
+#[cfg(feature = "au")]
+pub use crate::plugin::au::{AuPlugin, AuType};
 pub use crate::plugin::clap::{ClapPlugin, PolyModulationConfig};
+#[cfg(feature = "lv2")]
+pub use crate::plugin::lv2::Lv2Plugin;
 #[cfg(feature = "vst3")]
 pub use crate::plugin::vst3::Vst3Plugin;
-pub use crate::plugin::{Plugin, ProcessStatus, TaskExecutor};
+#[cfg(feature = "wam")]
+pub use crate::plugin::wam::WamPlugin;
+pub use crate::plugin::{Plugin, ProcessStatus, TaskExecutor, TaskOptions, TaskPriority};
+#[cfg(feature = "ara")]
+pub use crate::wrapper::ara::{
+    AraAnalysisProgress, AraAudioModification, AraAudioReader, AraAudioSource, AraBinding,
+    AraDocument, AraFactory, AraHost, AraObjectId, AraPlaybackRegion, AraPlaybackTransformations,
+    AraRoles,
+};
 pub use crate::wrapper::clap::features::ClapFeature;
-pub use crate::wrapper::state::PluginState;
+pub use crate::wrapper::state::{PluginState, PluginStateFormat, StateSaveContext};
 #[cfg(feature = "vst3")]
 pub use crate::wrapper::vst3::subcategories::Vst3SubCategory;
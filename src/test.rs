@@ -0,0 +1,465 @@
+//! An offline test harness for plugins. [`PluginTester`] hosts a plugin using an
+//! [`InProcessHost`], renders synthetic input signals through it, and applies scripted parameter
+//! changes and note events at exact sample positions. The assertion helpers panic with a
+//! descriptive message, so they can be used directly in `cargo test`:
+//!
+//! ```ignore
+//! #[test]
+//! fn gain_is_well_behaved() {
+//!     let mut tester = PluginTester::<Gain>::new();
+//!     tester.schedule_param_change(4800, "gain", 1.0);
+//!
+//!     tester
+//!         .render(TestSignal::Sine { frequency: 440.0, amplitude: 0.5 }, 48000)
+//!         .assert_finite()
+//!         .assert_no_denormals();
+//!     tester.assert_latency_consistent();
+//!     tester.assert_state_round_trip();
+//! }
+//! ```
+
+use std::num::NonZeroU32;
+
+use crate::host::{HostError, InProcessHost};
+use crate::prelude::{
+    AudioIOLayout, BufferConfig, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus,
+};
+
+/// The sample rate used by [`PluginTester::new()`].
+pub const DEFAULT_SAMPLE_RATE: f32 = 48000.0;
+/// The maximum buffer size used by [`PluginTester::new()`].
+pub const DEFAULT_MAX_BUFFER_SIZE: u32 = 512;
+
+/// Drives a plugin through initialization, processing, and state saving and loading without a
+/// host. See the [module documentation][self] for an example.
+pub struct PluginTester<P: Plugin> {
+    host: InProcessHost<P>,
+
+    /// Parameter changes that will be applied during the next call to
+    /// [`render()`][Self::render()], as `(sample, param_id, normalized_value)` tuples.
+    param_changes: Vec<(u32, String, f32)>,
+    /// Note events that will be sent to the plugin during the next call to
+    /// [`render()`][Self::render()]. Their timings are relative to the start of that call.
+    events: Vec<PluginNoteEvent<P>>,
+    /// Note events the plugin sent during the last call to [`render()`][Self::render()].
+    output_events: Vec<PluginNoteEvent<P>>,
+    /// The seed for [`TestSignal::Noise`], so rendering noise is deterministic.
+    noise_state: u32,
+}
+
+/// A synthetic signal that is sent to the plugin's main and auxiliary inputs in
+/// [`PluginTester::render()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TestSignal {
+    /// Digital silence.
+    Silence,
+    /// A single sample at full scale at the start of the rendered block, followed by silence.
+    Impulse,
+    /// A sine wave with a frequency in Hertz and a linear gain.
+    Sine { frequency: f32, amplitude: f32 },
+    /// Deterministic white noise with a linear gain.
+    Noise { amplitude: f32 },
+}
+
+/// The audio rendered by [`PluginTester::render()`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderedAudio {
+    /// The main output's samples, indexed by `[channel][sample]`.
+    pub channels: Vec<Vec<f32>>,
+}
+
+impl<P: Plugin> Default for PluginTester<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: Plugin> PluginTester<P> {
+    /// Host the plugin using its first audio IO layout, a [`DEFAULT_SAMPLE_RATE`] Hz sample rate,
+    /// and a maximum buffer size of [`DEFAULT_MAX_BUFFER_SIZE`] samples in offline processing mode.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin fails to initialize.
+    pub fn new() -> Self {
+        let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+        let buffer_config = BufferConfig {
+            sample_rate: DEFAULT_SAMPLE_RATE,
+            min_buffer_size: None,
+            max_buffer_size: DEFAULT_MAX_BUFFER_SIZE,
+            process_mode: ProcessMode::Offline,
+        };
+
+        match Self::with_config(audio_io_layout, buffer_config) {
+            Ok(tester) => tester,
+            Err(err) => panic!("Could not host '{}': {err}", P::NAME),
+        }
+    }
+
+    /// Host the plugin with a specific audio IO layout and buffer configuration.
+    pub fn with_config(
+        audio_io_layout: AudioIOLayout,
+        buffer_config: BufferConfig,
+    ) -> Result<Self, HostError> {
+        Ok(Self {
+            host: InProcessHost::new(audio_io_layout, buffer_config)?,
+
+            param_changes: Vec::new(),
+            events: Vec::new(),
+            output_events: Vec::new(),
+            noise_state: 0x9e37_79b9,
+        })
+    }
+
+    /// The host used to drive the plugin. This can be used to access the plugin and its parameters,
+    /// or to change the transport.
+    pub fn host(&self) -> &InProcessHost<P> {
+        &self.host
+    }
+
+    /// The host used to drive the plugin. This can be used to access the plugin and its parameters,
+    /// or to change the transport.
+    pub fn host_mut(&mut self) -> &mut InProcessHost<P> {
+        &mut self.host
+    }
+
+    /// Change a parameter `sample` samples after the start of the next call to
+    /// [`render()`][Self::render()]. The block being rendered is split at that position so the
+    /// change is sample accurate.
+    pub fn schedule_param_change(&mut self, sample: u32, param_id: &str, normalized: f32) {
+        self.param_changes
+            .push((sample, param_id.to_owned(), normalized));
+    }
+
+    /// Send a note event to the plugin during the next call to [`render()`][Self::render()]. The
+    /// event's timing is relative to the start of that call, and it may exceed the maximum buffer
+    /// size. Events scheduled past the end of the rendered audio are dropped.
+    pub fn schedule_event(&mut self, event: PluginNoteEvent<P>) {
+        self.events.push(event);
+    }
+
+    /// The note events the plugin output during the last call to [`render()`][Self::render()].
+    /// Their timings are relative to the start of that call.
+    pub fn output_events(&self) -> &[PluginNoteEvent<P>] {
+        &self.output_events
+    }
+
+    /// Reset the plugin, just like a host would do when the transport jumps.
+    pub fn reset(&mut self) {
+        self.host.reset();
+    }
+
+    /// Render `num_samples` samples of `signal` through the plugin. The audio is processed in
+    /// blocks of at most the maximum buffer size, and the blocks are split further at scheduled
+    /// parameter changes. All scheduled parameter changes and events are consumed, and background
+    /// tasks are run after every block.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the plugin returns an error, or if a scheduled parameter does not exist.
+    pub fn render(&mut self, signal: TestSignal, num_samples: usize) -> RenderedAudio {
+        let audio_io_layout = *self.host.audio_io_layout();
+        let sample_rate = self.host.buffer_config().sample_rate;
+        let max_buffer_size = self.host.buffer_config().max_buffer_size as usize;
+        let num_output_channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        let num_input_channels = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+
+        // The input signal is rendered upfront and copied to every input channel
+        let input: Vec<f32> = (0..num_samples)
+            .map(|sample_idx| self.signal_sample(signal, sample_idx, sample_rate))
+            .collect();
+
+        let mut param_changes = std::mem::take(&mut self.param_changes);
+        param_changes.sort_by_key(|(sample, _, _)| *sample);
+        let mut events = std::mem::take(&mut self.events);
+        events.sort_by_key(|event| event.timing());
+        self.output_events.clear();
+
+        let mut output = vec![vec![0.0f32; num_samples]; num_output_channels];
+        let mut aux_input_storage: Vec<Vec<Vec<f32>>> = audio_io_layout
+            .aux_input_ports
+            .iter()
+            .map(|channel_count| vec![vec![0.0; max_buffer_size]; channel_count.get() as usize])
+            .collect();
+        let mut aux_output_storage: Vec<Vec<Vec<f32>>> = audio_io_layout
+            .aux_output_ports
+            .iter()
+            .map(|channel_count| vec![vec![0.0; max_buffer_size]; channel_count.get() as usize])
+            .collect();
+
+        let mut param_changes = param_changes.into_iter().peekable();
+        let mut events = events.into_iter().peekable();
+        let mut block_events = Vec::new();
+        let mut block_output_events = Vec::new();
+        let mut block_start = 0;
+        while block_start < num_samples {
+            while let Some((_, param_id, normalized)) =
+                param_changes.next_if(|(sample, _, _)| *sample as usize <= block_start)
+            {
+                assert!(
+                    self.host.set_param_normalized(&param_id, normalized),
+                    "'{}' does not have a parameter with ID '{param_id}'",
+                    P::NAME
+                );
+            }
+
+            let mut block_end = (block_start + max_buffer_size).min(num_samples);
+            if let Some((sample, _, _)) = param_changes.peek() {
+                block_end = block_end.min(*sample as usize);
+            }
+            let block_len = block_end - block_start;
+
+            block_events.clear();
+            while let Some(mut event) =
+                events.next_if(|event| (event.timing() as usize) < block_end)
+            {
+                event.subtract_timing(block_start as u32);
+                block_events.push(event);
+            }
+
+            for (channel_idx, channel) in output.iter_mut().enumerate() {
+                let channel = &mut channel[block_start..block_end];
+                if channel_idx < num_input_channels {
+                    channel.copy_from_slice(&input[block_start..block_end]);
+                } else {
+                    channel.fill(0.0);
+                }
+            }
+            for channel in aux_input_storage.iter_mut().flatten() {
+                channel[..block_len].copy_from_slice(&input[block_start..block_end]);
+            }
+
+            let mut main_io: Vec<&mut [f32]> = output
+                .iter_mut()
+                .map(|channel| &mut channel[block_start..block_end])
+                .collect();
+            let mut aux_inputs: Vec<Vec<&mut [f32]>> = aux_input_storage
+                .iter_mut()
+                .map(|port| {
+                    port.iter_mut()
+                        .map(|channel| &mut channel[..block_len])
+                        .collect()
+                })
+                .collect();
+            let mut aux_inputs: Vec<&mut [&mut [f32]]> = aux_inputs
+                .iter_mut()
+                .map(|port| port.as_mut_slice())
+                .collect();
+            let mut aux_outputs: Vec<Vec<&mut [f32]>> = aux_output_storage
+                .iter_mut()
+                .map(|port| {
+                    port.iter_mut()
+                        .map(|channel| &mut channel[..block_len])
+                        .collect()
+                })
+                .collect();
+            let mut aux_outputs: Vec<&mut [&mut [f32]]> = aux_outputs
+                .iter_mut()
+                .map(|port| port.as_mut_slice())
+                .collect();
+
+            block_output_events.clear();
+            let status = self.host.process_with_aux(
+                &mut main_io,
+                &mut aux_inputs,
+                &mut aux_outputs,
+                &block_events,
+                &mut block_output_events,
+            );
+            if let ProcessStatus::Error(err) = status {
+                panic!(
+                    "'{}' returned an error while processing samples {block_start}..{block_end}: \
+                     {err}",
+                    P::NAME
+                );
+            }
+
+            for mut event in block_output_events.drain(..) {
+                *event.timing_mut() += block_start as u32;
+                self.output_events.push(event);
+            }
+            self.host.run_pending_tasks();
+
+            block_start = block_end;
+        }
+
+        RenderedAudio { channels: output }
+    }
+
+    /// Check that the latency reported by the plugin does not change when the plugin is reset or
+    /// while it is processing audio. If the plugin has a main input and passes an impulse through
+    /// to its output, then the impulse also needs to appear exactly at the reported latency. The
+    /// plugin is reset afterwards.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the latency is inconsistent.
+    pub fn assert_latency_consistent(&mut self) {
+        let latency = self.host.latency_samples();
+
+        self.host.reset();
+        assert_eq!(
+            self.host.latency_samples(),
+            latency,
+            "'{}' reported a different latency after being reset",
+            P::NAME
+        );
+
+        let num_samples = latency as usize + self.host.buffer_config().max_buffer_size as usize * 4;
+        let output = self.render(TestSignal::Impulse, num_samples);
+        assert_eq!(
+            self.host.latency_samples(),
+            latency,
+            "'{}' changed its latency while processing audio",
+            P::NAME
+        );
+
+        if self.host.audio_io_layout().main_input_channels.is_some() {
+            if let Some(first_sample) = output.first_non_silent_sample() {
+                assert_eq!(
+                    first_sample,
+                    latency as usize,
+                    "'{}' reports {latency} samples of latency, but an impulse appeared at sample \
+                     {first_sample}",
+                    P::NAME
+                );
+            }
+        }
+
+        self.host.reset();
+    }
+
+    /// Check that the plugin's state survives a round trip. The state is saved, loaded into a new
+    /// instance of the plugin, and then saved again. Both saved states need to be identical.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the state could not be saved or loaded, or if the states are different.
+    pub fn assert_state_round_trip(&self) {
+        let state = match self.host.save_state() {
+            Ok(state) => state,
+            Err(err) => panic!("Could not save the state of '{}': {err:#}", P::NAME),
+        };
+
+        let mut other_host =
+            match InProcessHost::<P>::new(*self.host.audio_io_layout(), *self.host.buffer_config())
+            {
+                Ok(host) => host,
+                Err(err) => panic!("Could not host a second instance of '{}': {err}", P::NAME),
+            };
+        assert!(
+            other_host.load_state(&state),
+            "'{}' could not load its own state",
+            P::NAME
+        );
+
+        // The states are compared as JSON since compression would hide the differences
+        let original_state = serde_json::to_string_pretty(&self.host.state_object())
+            .expect("Could not format the state as JSON");
+        let restored_state = serde_json::to_string_pretty(&other_host.state_object())
+            .expect("Could not format the state as JSON");
+        assert_eq!(
+            original_state,
+            restored_state,
+            "The state of '{}' changed after a round trip",
+            P::NAME
+        );
+    }
+
+    /// Compute a sample for `signal`. `sample_idx` is relative to the start of the rendered block.
+    fn signal_sample(&mut self, signal: TestSignal, sample_idx: usize, sample_rate: f32) -> f32 {
+        match signal {
+            TestSignal::Silence => 0.0,
+            TestSignal::Impulse => {
+                if sample_idx == 0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            }
+            TestSignal::Sine {
+                frequency,
+                amplitude,
+            } => {
+                let phase = (sample_idx as f64 * frequency as f64 / sample_rate as f64).fract();
+                (phase * std::f64::consts::TAU).sin() as f32 * amplitude
+            }
+            TestSignal::Noise { amplitude } => {
+                // A xorshift PRNG is more than good enough for test signals
+                self.noise_state ^= self.noise_state << 13;
+                self.noise_state ^= self.noise_state >> 17;
+                self.noise_state ^= self.noise_state << 5;
+
+                ((self.noise_state as f32 / u32::MAX as f32) * 2.0 - 1.0) * amplitude
+            }
+        }
+    }
+}
+
+impl RenderedAudio {
+    /// The number of samples in each channel.
+    pub fn num_samples(&self) -> usize {
+        self.channels.first().map(Vec::len).unwrap_or(0)
+    }
+
+    /// The largest absolute sample value across all channels.
+    pub fn peak(&self) -> f32 {
+        self.channels
+            .iter()
+            .flatten()
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()))
+    }
+
+    /// The index of the first sample that is not zero in any of the channels, if there is one.
+    pub fn first_non_silent_sample(&self) -> Option<usize> {
+        (0..self.num_samples()).find(|&sample_idx| {
+            self.channels
+                .iter()
+                .any(|channel| channel[sample_idx] != 0.0)
+        })
+    }
+
+    /// Check that the output does not contain any NaN or infinite values.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the location of the first non-finite sample.
+    pub fn assert_finite(&self) -> &Self {
+        for (channel_idx, channel) in self.channels.iter().enumerate() {
+            if let Some(sample_idx) = channel.iter().position(|sample| !sample.is_finite()) {
+                panic!(
+                    "Channel {channel_idx} contains a non-finite value ({}) at sample {sample_idx}",
+                    channel[sample_idx]
+                );
+            }
+        }
+
+        self
+    }
+
+    /// Check that the output does not contain any denormal values. Denormals are very slow to
+    /// process on most CPUs, and they usually indicate that a filter or feedback loop is decaying
+    /// without being flushed to zero.
+    ///
+    /// # Panics
+    ///
+    /// Panics with the location of the first denormal sample.
+    pub fn assert_no_denormals(&self) -> &Self {
+        for (channel_idx, channel) in self.channels.iter().enumerate() {
+            if let Some(sample_idx) = channel.iter().position(|sample| sample.is_subnormal()) {
+                panic!(
+                    "Channel {channel_idx} contains a denormal value ({:e}) at sample {sample_idx}",
+                    channel[sample_idx]
+                );
+            }
+        }
+
+        self
+    }
+}
@@ -1,9 +1,25 @@
 //! General conversion functions and utilities.
 
+mod analysis;
+pub mod automation;
+pub mod bus;
+pub mod curve;
+mod decay_tracker;
+mod dry_wet_mixer;
+pub mod keyboard;
+pub mod modulation;
+mod preset_library;
+pub mod rt_log;
 mod stft;
+mod tuning;
 pub mod window;
 
+pub use analysis::{AnalysisChannel, AnalysisFrame, AnalysisInput, AnalysisOutput};
+pub use decay_tracker::DecayTracker;
+pub use dry_wet_mixer::{DryWetMixer, MixingStyle};
+pub use preset_library::{PresetEntry, PresetFormat, PresetLibrary};
 pub use stft::StftHelper;
+pub use tuning::Tuning;
 
 pub const MINUS_INFINITY_DB: f32 = -100.0;
 pub const MINUS_INFINITY_GAIN: f32 = 1e-5; // 10f32.powf(MINUS_INFINITY_DB / 20)
@@ -19,8 +35,18 @@ pub fn permit_alloc<T, F: FnOnce() -> T>(func: F) -> T {
 }
 
 /// Temporarily allow allocations within `func` if NIH-plug was configured with the
-/// `assert_process_allocs` feature.
-#[cfg(not(all(debug_assertions, feature = "assert_process_allocs")))]
+/// `detect_process_allocs` feature.
+#[cfg(all(debug_assertions, feature = "detect_process_allocs"))]
+pub fn permit_alloc<T, F: FnOnce() -> T>(func: F) -> T {
+    crate::wrapper::util::alloc_detector::permit_alloc(func)
+}
+
+/// Temporarily allow allocations within `func` if NIH-plug was configured with the
+/// `assert_process_allocs` or `detect_process_allocs` features.
+#[cfg(not(all(
+    debug_assertions,
+    any(feature = "assert_process_allocs", feature = "detect_process_allocs")
+)))]
 pub fn permit_alloc<T, F: FnOnce() -> T>(func: F) -> T {
     func()
 }
@@ -0,0 +1,263 @@
+//! A lock-free channel for sending visualization data from the audio thread to the editor. See
+//! [`AnalysisChannel`].
+
+use std::cell::UnsafeCell;
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Arc;
+
+/// The bits of [`Shared::back_slot`] containing the slot's index.
+const SLOT_INDEX_MASK: u8 = 0b11;
+/// Set in [`Shared::back_slot`] when the back slot contains data the output has not read yet.
+const NEW_DATA_BIT: u8 = 0b100;
+
+/// The rate at which [`AnalysisInput::tick()`] returns `true` by default, in Hertz.
+const DEFAULT_UPDATE_RATE: f32 = 60.0;
+
+/// A triple buffered channel for sending visualization data like peak meter values, spectra, or
+/// oscilloscope waveforms from the audio thread to the editor. Use
+/// [`split()`][Self::split()] to get the [`AnalysisInput`] for the audio thread and the
+/// [`AnalysisOutput`] for the editor.
+///
+/// Publishing and reading data is wait-free and does not allocate, so unlike an
+/// `Arc<Mutex<Vec<f32>>>` this is realtime-safe. The output always sees the most recently published
+/// data, and data published while the editor is not reading is simply overwritten. Every published
+/// frame is tagged with the sample rate it was computed at, so the editor can map spectrum bins to
+/// frequencies or samples to time without any additional synchronization.
+///
+/// ```ignore
+/// // In `Plugin::initialize()`
+/// self.spectrum_input.set_sample_rate(buffer_config.sample_rate);
+///
+/// // In `Plugin::process()`
+/// if self.spectrum_input.tick(buffer.samples()) {
+///     let spectrum = self.spectrum_input.input_buffer();
+///     spectrum.copy_from_slice(&self.spectrum_scratch);
+///     self.spectrum_input.publish();
+/// }
+///
+/// // In the editor
+/// let frame = spectrum_output.read_frame();
+/// draw_spectrum(&frame.data, frame.sample_rate);
+/// ```
+pub struct AnalysisChannel<T> {
+    shared: Arc<Shared<T>>,
+}
+
+/// The audio thread's half of an [`AnalysisChannel`].
+pub struct AnalysisInput<T> {
+    shared: Arc<Shared<T>>,
+    /// The slot currently owned by the input.
+    slot: u8,
+
+    /// The sample rate published frames are tagged with.
+    sample_rate: f32,
+    /// The number of samples between updates, see [`tick()`][Self::tick()].
+    update_interval: f32,
+    /// The number of samples ticked since the last update.
+    samples_since_update: f32,
+}
+
+/// The editor's half of an [`AnalysisChannel`].
+pub struct AnalysisOutput<T> {
+    shared: Arc<Shared<T>>,
+    /// The slot currently owned by the output.
+    slot: u8,
+}
+
+/// Data published through an [`AnalysisChannel`], together with the sample rate it was computed at.
+#[derive(Debug, Clone)]
+pub struct AnalysisFrame<T> {
+    pub data: T,
+    /// The sample rate set with [`AnalysisInput::set_sample_rate()`] at the time the data was
+    /// published. This is 0 if nothing has been published yet.
+    pub sample_rate: f32,
+}
+
+/// The state shared between the input and the output. At any point in time one slot is owned by
+/// the input, one slot is owned by the output, and the remaining back slot is swapped with either
+/// of them when the input publishes new data or when the output reads it.
+struct Shared<T> {
+    slots: [UnsafeCell<AnalysisFrame<T>>; 3],
+    /// The index of the back slot, optionally combined with [`NEW_DATA_BIT`].
+    back_slot: AtomicU8,
+}
+
+// SAFETY: The input and output each have exclusive access to their own slot, and slots only change
+//         owners through atomic swaps of `back_slot`
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T: Clone + Send> AnalysisChannel<T> {
+    /// Create a new channel. All of the channel's slots are initialized with a copy of `initial`.
+    /// If `T` contains vectors, then they should have enough capacity to avoid allocations on the
+    /// audio thread.
+    pub fn new(initial: T) -> Self {
+        let frame = || {
+            UnsafeCell::new(AnalysisFrame {
+                data: initial.clone(),
+                sample_rate: 0.0,
+            })
+        };
+
+        Self {
+            shared: Arc::new(Shared {
+                slots: [frame(), frame(), frame()],
+                back_slot: AtomicU8::new(2),
+            }),
+        }
+    }
+}
+
+impl<T: Send> AnalysisChannel<T> {
+    /// Split the channel into an input for the audio thread and an output for the editor.
+    pub fn split(self) -> (AnalysisInput<T>, AnalysisOutput<T>) {
+        (
+            AnalysisInput {
+                shared: self.shared.clone(),
+                slot: 0,
+
+                sample_rate: 0.0,
+                update_interval: 0.0,
+                samples_since_update: 0.0,
+            },
+            AnalysisOutput {
+                shared: self.shared,
+                slot: 1,
+            },
+        )
+    }
+}
+
+impl<T: Send> AnalysisInput<T> {
+    /// Set the sample rate that published frames are tagged with. This also determines the number
+    /// of samples between updates for [`tick()`][Self::tick()]. Call this from
+    /// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()].
+    pub fn set_sample_rate(&mut self, sample_rate: f32) {
+        self.sample_rate = sample_rate;
+        self.set_update_rate(DEFAULT_UPDATE_RATE);
+    }
+
+    /// Set how often [`tick()`][Self::tick()] returns `true`, in Hertz. Defaults to 60 Hz. This is
+    /// reset when the sample rate changes.
+    pub fn set_update_rate(&mut self, rate: f32) {
+        self.update_interval = self.sample_rate / rate;
+    }
+
+    /// Advance the input's clock by `num_samples` samples. Returns `true` if it's time to publish a
+    /// new frame according to the [update rate][Self::set_update_rate()]. This makes it easy to
+    /// limit expensive analysis to the rate at which the editor can display it.
+    pub fn tick(&mut self, num_samples: usize) -> bool {
+        self.samples_since_update += num_samples as f32;
+        if self.samples_since_update >= self.update_interval {
+            self.samples_since_update %= self.update_interval.max(1.0);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Get the data that will be sent when [`publish()`][Self::publish()] is called, so it can be
+    /// modified in place. This contains the data from an earlier frame, so it should be
+    /// overwritten completely.
+    pub fn input_buffer(&mut self) -> &mut T {
+        // SAFETY: The input's slot is only accessed by the input
+        unsafe { &mut (*self.shared.slots[self.slot as usize].get()).data }
+    }
+
+    /// Send the data written to [`input_buffer()`][Self::input_buffer()] to the output.
+    pub fn publish(&mut self) {
+        // SAFETY: See above
+        unsafe { (*self.shared.slots[self.slot as usize].get()).sample_rate = self.sample_rate };
+
+        let previous_back_slot = self
+            .shared
+            .back_slot
+            .swap(self.slot | NEW_DATA_BIT, Ordering::AcqRel);
+        self.slot = previous_back_slot & SLOT_INDEX_MASK;
+    }
+
+    /// Replace the input's data with `data` and publish it. This moves `data` into the channel and
+    /// drops the old data, so prefer [`input_buffer()`][Self::input_buffer()] for data that
+    /// allocates.
+    pub fn write(&mut self, data: T) {
+        *self.input_buffer() = data;
+        self.publish();
+    }
+}
+
+impl<T: Send> AnalysisOutput<T> {
+    /// Whether the input has published new data since the last call to [`read()`][Self::read()].
+    pub fn updated(&self) -> bool {
+        self.shared.back_slot.load(Ordering::Relaxed) & NEW_DATA_BIT != 0
+    }
+
+    /// Get the most recently published data. Returns the same data as the last call if nothing
+    /// new has been published since.
+    pub fn read(&mut self) -> &T {
+        &self.read_frame().data
+    }
+
+    /// The same as [`read()`][Self::read()], but also returns the sample rate the data was
+    /// computed at.
+    pub fn read_frame(&mut self) -> &AnalysisFrame<T> {
+        if self.updated() {
+            let previous_back_slot = self.shared.back_slot.swap(self.slot, Ordering::AcqRel);
+            self.slot = previous_back_slot & SLOT_INDEX_MASK;
+        }
+
+        self.frame()
+    }
+
+    /// The sample rate the data returned by the last call to [`read()`][Self::read()] was computed
+    /// at. This is 0 if nothing has been published yet.
+    pub fn sample_rate(&self) -> f32 {
+        self.frame().sample_rate
+    }
+
+    fn frame(&self) -> &AnalysisFrame<T> {
+        // SAFETY: The output's slot is only accessed by the output
+        unsafe { &*self.shared.slots[self.slot as usize].get() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_publish_read() {
+        let (mut input, mut output) = AnalysisChannel::new(0.0f32).split();
+        assert!(!output.updated());
+        assert_eq!(*output.read(), 0.0);
+
+        input.set_sample_rate(48_000.0);
+        input.write(1.0);
+        assert!(output.updated());
+        assert_eq!(*output.read(), 1.0);
+        assert_eq!(output.sample_rate(), 48_000.0);
+        assert!(!output.updated());
+        assert_eq!(*output.read(), 1.0);
+    }
+
+    #[test]
+    fn test_latest_data_wins() {
+        let (mut input, mut output) = AnalysisChannel::new(vec![0.0f32; 4]).split();
+        for value in 1..=5 {
+            input.input_buffer().fill(value as f32);
+            input.publish();
+        }
+
+        assert_eq!(output.read(), &[5.0; 4]);
+    }
+
+    #[test]
+    fn test_tick() {
+        let (mut input, _output) = AnalysisChannel::new(0.0f32).split();
+        input.set_sample_rate(48_000.0);
+        input.set_update_rate(100.0);
+
+        assert!(!input.tick(256));
+        assert!(input.tick(256));
+        assert!(!input.tick(256));
+        assert!(input.tick(256));
+    }
+}
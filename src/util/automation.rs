@@ -0,0 +1,451 @@
+//! An automation recorder for capturing parameter changes during playback and replaying them
+//! relative to the host's transport, independently of the host's own automation.
+
+use atomic_refcell::AtomicRefCell;
+use crossbeam::atomic::AtomicCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::context::process::Transport;
+use crate::params::internals::ParamPtr;
+use crate::params::Params;
+
+/// The maximum number of events in a single recording. Recording stops once this limit is reached.
+/// The space for these events is allocated when the recorder is armed so the audio thread never
+/// has to allocate.
+pub const MAX_EVENTS: usize = 1 << 16;
+
+/// The number of events that can be buffered on the audio thread while the GUI or the host holds
+/// on to the recording.
+const PENDING_EVENTS_CAPACITY: usize = 1024;
+
+/// Records parameter changes with sample accurate song positions while the host's transport is
+/// playing, and plays them back again later. This can be used for internal motion recording that
+/// works the same in every host, regardless of whether the host supports automation for the
+/// plugin's parameters.
+///
+/// To use the recorder, store an `Arc<AutomationRecorder>` on your parameters struct with a
+/// `#[persist = "key"]` attribute so the recording is saved as part of the plugin's state. Call
+/// [`initialize()`][Self::initialize()] from your plugin's `initialize()` function, and call
+/// [`process()`][Self::process()] at the start of every `process()` call. The recorder is
+/// controlled from the GUI using [`arm()`][Self::arm()], [`play()`][Self::play()], and
+/// [`stop()`][Self::stop()].
+///
+/// Changes are detected by comparing the parameters' unmodulated values at the start of every
+/// block, so enabling sample accurate automation makes the recorded timings more precise. A
+/// recording ends when the transport stops or when it jumps to another position, e.g. because
+/// it looped. During playback the recorded values are applied at the start of the block they fall
+/// in, and they are chased when the transport jumps. The host's automation for the same parameters
+/// will still override the played back values.
+#[derive(Debug)]
+pub struct AutomationRecorder {
+    mode: AtomicCell<RecorderMode>,
+    /// The recording along with the parameters the recorded events refer to. The audio thread only
+    /// ever tries to lock this, and it buffers new events in [`Engine::pending`] if that fails.
+    shared: Mutex<Shared>,
+    /// The plugin's parameters, set in [`initialize()`][Self::initialize()].
+    params: Mutex<Vec<(String, ParamPtr)>>,
+    /// The recorder's state on the audio thread. This is only accessed from the audio thread and
+    /// from [`initialize()`][Self::initialize()].
+    engine: AtomicRefCell<Engine>,
+}
+
+/// What the recorder is currently doing.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecorderMode {
+    /// The recorder doesn't do anything.
+    #[default]
+    Idle,
+    /// Recording starts the next time the transport is playing.
+    Armed,
+    /// Parameter changes are being recorded.
+    Recording,
+    /// The recording is played back while the transport is playing.
+    Playing,
+}
+
+/// A recording of parameter changes. This is what gets persisted as part of the plugin's state.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutomationRecording {
+    /// The sample rate the recording was made at. Event times are scaled accordingly when played
+    /// back at a different sample rate.
+    pub sample_rate: f32,
+    /// The IDs of the parameters the events refer to, as set with `#[id = "..."]` including any
+    /// nested ID prefixes.
+    pub param_ids: Vec<String>,
+    /// The recorded events, sorted by their time.
+    pub events: Vec<AutomationEvent>,
+}
+
+/// A single recorded parameter change.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AutomationEvent {
+    /// The song position in samples the change was recorded at.
+    pub time: i64,
+    /// The index of the parameter's ID in [`AutomationRecording::param_ids`].
+    pub param_index: u32,
+    /// The parameter's new normalized value.
+    pub normalized_value: f32,
+}
+
+#[derive(Debug, Default)]
+struct Shared {
+    recording: AutomationRecording,
+    /// The indices in [`Engine::params`] for the parameters in the recording's `param_ids`.
+    targets: Vec<Option<u32>>,
+}
+
+/// The recorder's state on the audio thread.
+#[derive(Debug, Default)]
+struct Engine {
+    params: Vec<ParamPtr>,
+    /// The parameters' values during the last recorded block. `NaN` values are always recorded.
+    /// This is also used as scratch space when chasing values during playback.
+    last_values: Vec<f32>,
+    /// Events that could not be added to the recording yet because it was locked.
+    pending: Vec<AutomationEvent>,
+    /// The song position the next block is expected to start at. Used to detect jumps.
+    next_pos_samples: Option<i64>,
+}
+
+impl Default for AutomationRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AutomationRecorder {
+    /// Create an idle recorder without a recording.
+    pub fn new() -> Self {
+        Self {
+            mode: AtomicCell::new(RecorderMode::Idle),
+            shared: Mutex::new(Shared::default()),
+            params: Mutex::new(Vec::new()),
+            engine: AtomicRefCell::new(Engine::default()),
+        }
+    }
+
+    /// Let the recorder know about the plugin's parameters. This should be called from the
+    /// plugin's `initialize()` function.
+    ///
+    /// # Safety
+    ///
+    /// `params` needs to outlive every following [`process()`][Self::process()] call. This is the
+    /// case when the recorder is stored on the same parameters object.
+    pub unsafe fn initialize(&self, params: &impl Params) {
+        self.set_params(
+            params
+                .param_map()
+                .into_iter()
+                .map(|(id, param_ptr, _)| (id, param_ptr))
+                .collect(),
+        );
+    }
+
+    /// The implementation for [`initialize()`][Self::initialize()].
+    pub(crate) unsafe fn set_params(&self, params: Vec<(String, ParamPtr)>) {
+        {
+            let mut engine = self.engine.borrow_mut();
+            engine.params = params.iter().map(|(_, param_ptr)| *param_ptr).collect();
+            engine.last_values = vec![f32::NAN; params.len()];
+            engine.pending = Vec::with_capacity(PENDING_EVENTS_CAPACITY);
+            engine.next_pos_samples = None;
+        }
+
+        let mut params_guard = self.params.lock();
+        *params_guard = params;
+        update_targets(&mut self.shared.lock(), &params_guard);
+    }
+
+    /// What the recorder is currently doing.
+    pub fn mode(&self) -> RecorderMode {
+        self.mode.load()
+    }
+
+    /// Discard the current recording and start recording the next time the transport is playing.
+    /// This should be called from the GUI thread.
+    pub fn arm(&self) {
+        let params = self.params.lock();
+        let mut shared = self.shared.lock();
+        shared.recording.param_ids = params.iter().map(|(id, _)| id.clone()).collect();
+        shared.recording.events = Vec::with_capacity(MAX_EVENTS);
+        shared.targets = (0..params.len() as u32).map(Some).collect();
+
+        self.mode.store(RecorderMode::Armed);
+    }
+
+    /// Start playing back the recording whenever the transport is playing. This also stops any
+    /// ongoing recording.
+    pub fn play(&self) {
+        self.mode.store(RecorderMode::Playing);
+    }
+
+    /// Stop recording or playing back.
+    pub fn stop(&self) {
+        self.mode.store(RecorderMode::Idle);
+    }
+
+    /// A copy of the current recording.
+    pub fn recording(&self) -> AutomationRecording {
+        self.shared.lock().recording.clone()
+    }
+
+    /// The number of recorded events.
+    pub fn num_events(&self) -> usize {
+        self.shared.lock().recording.events.len()
+    }
+
+    /// Replace the recording. This should be called from the GUI thread or when loading state, and
+    /// never from the audio thread.
+    pub fn set_recording(&self, mut recording: AutomationRecording) {
+        recording.events.sort_by_key(|event| event.time);
+
+        let params = self.params.lock();
+        let mut shared = self.shared.lock();
+        shared.recording = recording;
+        update_targets(&mut shared, &params);
+    }
+
+    /// Remove the current recording.
+    pub fn clear(&self) {
+        self.set_recording(AutomationRecording::default());
+    }
+
+    /// Record or play back parameter changes for the next block. This should be called at the start
+    /// of the plugin's `process()` function. Does nothing if the recorder has not been initialized
+    /// yet.
+    pub fn process(&self, transport: &Transport, num_samples: usize) {
+        let mut engine = match self.engine.try_borrow_mut() {
+            Ok(engine) => engine,
+            Err(_) => {
+                nih_debug_assert_failure!(
+                    "The automation recorder is being processed concurrently"
+                );
+                return;
+            }
+        };
+
+        let pos_samples = transport.pos_samples().filter(|_| transport.playing);
+        let jumped = pos_samples != engine.next_pos_samples;
+        engine.next_pos_samples = pos_samples.map(|pos| pos + num_samples as i64);
+
+        match (self.mode.load(), pos_samples) {
+            (RecorderMode::Armed, Some(pos_samples)) => {
+                if self
+                    .mode
+                    .compare_exchange(RecorderMode::Armed, RecorderMode::Recording)
+                    .is_ok()
+                {
+                    engine.last_values.fill(f32::NAN);
+                    if let Some(mut shared) = self.shared.try_lock() {
+                        shared.recording.sample_rate = transport.sample_rate;
+                    }
+                    self.record(&mut engine, pos_samples);
+                }
+            }
+            (RecorderMode::Recording, Some(pos_samples)) if !jumped => {
+                self.record(&mut engine, pos_samples);
+            }
+            (RecorderMode::Recording, _) => {
+                let _ = self
+                    .mode
+                    .compare_exchange(RecorderMode::Recording, RecorderMode::Idle);
+            }
+            (RecorderMode::Playing, Some(pos_samples)) => {
+                self.play_back(
+                    &mut engine,
+                    transport.sample_rate,
+                    pos_samples,
+                    num_samples,
+                    jumped,
+                );
+            }
+            _ => (),
+        }
+
+        // Events that could not be added while the recording was locked are added as soon as
+        // possible, even if recording has already stopped
+        if !engine.pending.is_empty() {
+            if let Some(mut shared) = self.shared.try_lock() {
+                self.add_events(&mut shared, &mut engine.pending);
+            }
+        }
+    }
+
+    /// Record the changed parameter values at the start of the block.
+    fn record(&self, engine: &mut Engine, pos_samples: i64) {
+        let Engine {
+            params,
+            last_values,
+            pending,
+            ..
+        } = engine;
+        for (param_index, (param_ptr, last_value)) in
+            params.iter().zip(last_values.iter_mut()).enumerate()
+        {
+            let value = unsafe { param_ptr.unmodulated_normalized_value() };
+            if value == *last_value {
+                continue;
+            }
+
+            *last_value = value;
+            if pending.len() < PENDING_EVENTS_CAPACITY {
+                pending.push(AutomationEvent {
+                    time: pos_samples,
+                    param_index: param_index as u32,
+                    normalized_value: value,
+                });
+            } else {
+                nih_log_rt!("The automation recorder's event buffer is full, dropping events");
+                break;
+            }
+        }
+    }
+
+    /// Move the pending events to the recording, stopping the recording if it's full.
+    fn add_events(&self, shared: &mut Shared, pending: &mut Vec<AutomationEvent>) {
+        let events = &mut shared.recording.events;
+        let num_events = pending.len().min(events.capacity() - events.len());
+        events.extend(pending.drain(..num_events));
+
+        if !pending.is_empty() {
+            pending.clear();
+            if self
+                .mode
+                .compare_exchange(RecorderMode::Recording, RecorderMode::Idle)
+                .is_ok()
+            {
+                nih_log_rt!("The automation recording is full, stopping the recording");
+            }
+        }
+    }
+
+    /// Apply the recorded events that fall within this block. When the transport has jumped, the
+    /// parameters are first set to the last values recorded before the block's start.
+    fn play_back(
+        &self,
+        engine: &mut Engine,
+        sample_rate: f32,
+        pos_samples: i64,
+        num_samples: usize,
+        jumped: bool,
+    ) {
+        // If the recording is being replaced then this block is skipped, and the values are chased
+        // during the next block
+        let Some(shared) = self.shared.try_lock() else {
+            engine.next_pos_samples = None;
+            return;
+        };
+
+        let recording = &shared.recording;
+        let time_scale = if recording.sample_rate > 0.0 {
+            recording.sample_rate as f64 / sample_rate as f64
+        } else {
+            1.0
+        };
+        let start = (pos_samples as f64 * time_scale).round() as i64;
+        let end = ((pos_samples + num_samples as i64) as f64 * time_scale).round() as i64;
+        let start_idx = recording.events.partition_point(|event| event.time < start);
+        let end_idx = recording.events.partition_point(|event| event.time < end);
+
+        let apply = |param_index: usize, value: f32| {
+            if let Some(param_ptr) = engine.params.get(param_index) {
+                unsafe {
+                    if param_ptr.set_normalized_value(value) {
+                        param_ptr.update_smoother(sample_rate, false);
+                    }
+                }
+            }
+        };
+        let target = |event: &AutomationEvent| {
+            shared
+                .targets
+                .get(event.param_index as usize)
+                .copied()
+                .flatten()
+                .map(|param_index| param_index as usize)
+        };
+
+        if jumped {
+            let mut chased_values = std::mem::take(&mut engine.last_values);
+            chased_values.fill(f32::NAN);
+            for event in &recording.events[..start_idx] {
+                if let Some(value) = target(event).and_then(|idx| chased_values.get_mut(idx)) {
+                    *value = event.normalized_value;
+                }
+            }
+            for (param_index, value) in chased_values.iter().enumerate() {
+                if !value.is_nan() {
+                    apply(param_index, *value);
+                }
+            }
+            engine.last_values = chased_values;
+        }
+
+        for event in &recording.events[start_idx..end_idx] {
+            if let Some(param_index) = target(event) {
+                apply(param_index, event.normalized_value);
+            }
+        }
+    }
+}
+
+/// Map the recording's parameter IDs to the indices in the engine's parameter list.
+fn update_targets(shared: &mut Shared, params: &[(String, ParamPtr)]) {
+    shared.targets = shared
+        .recording
+        .param_ids
+        .iter()
+        .map(|id| {
+            params
+                .iter()
+                .position(|(param_id, _)| param_id == id)
+                .map(|idx| idx as u32)
+        })
+        .collect();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{FloatParam, FloatRange, Param};
+
+    fn transport(pos_samples: i64) -> Transport {
+        let mut transport = Transport::new(44100.0);
+        transport.playing = true;
+        transport.pos_samples = Some(pos_samples);
+        transport
+    }
+
+    #[test]
+    fn test_record_and_play_back() {
+        let param = FloatParam::new("Cutoff", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let recorder = AutomationRecorder::new();
+        unsafe { recorder.set_params(vec![(String::from("cutoff"), param.as_ptr())]) };
+
+        recorder.arm();
+        recorder.process(&transport(0), 64);
+        assert_eq!(recorder.mode(), RecorderMode::Recording);
+        unsafe { param.as_ptr().set_normalized_value(0.75) };
+        recorder.process(&transport(64), 64);
+        recorder.process(&transport(128), 64);
+
+        // Jumping back to the start ends the recording
+        recorder.process(&transport(0), 64);
+        assert_eq!(recorder.mode(), RecorderMode::Idle);
+        assert_eq!(recorder.num_events(), 2);
+
+        recorder.play();
+        recorder.process(&transport(0), 64);
+        approx::assert_relative_eq!(param.unmodulated_normalized_value(), 0.5);
+        recorder.process(&transport(64), 64);
+        approx::assert_relative_eq!(param.unmodulated_normalized_value(), 0.75);
+
+        // Values are chased when the transport jumps
+        recorder.process(&transport(0), 64);
+        approx::assert_relative_eq!(param.unmodulated_normalized_value(), 0.5);
+        recorder.process(&transport(1000), 64);
+        approx::assert_relative_eq!(param.unmodulated_normalized_value(), 0.75);
+    }
+}
@@ -0,0 +1,442 @@
+//! A shared memory message bus for communication between plugin instances. See
+//! [`InterPluginBus`].
+
+use std::io;
+use std::ptr::NonNull;
+use std::sync::atomic::{self, AtomicU32, AtomicU64, Ordering};
+
+/// The maximum size of a single message in bytes. This fits 1024 `f32` values.
+pub const MAX_MESSAGE_SIZE: usize = 4096;
+/// The number of messages stored in the bus. Readers that fall more than this many messages behind
+/// miss the oldest messages.
+const NUM_SLOTS: usize = 32;
+/// The number of 64-bit words a message of [`MAX_MESSAGE_SIZE`] bytes is stored in.
+const MESSAGE_WORDS: usize = MAX_MESSAGE_SIZE / 8;
+/// How many milliseconds [`InterPluginBus::open()`] waits at most for the process that created a
+/// bus' shared memory object to set the object's size.
+#[cfg(unix)]
+const SIZE_WAIT_MS: usize = 100;
+/// Part of the shared memory object's name. This needs to be incremented whenever the memory layout
+/// changes so plugins built with different NIH-plug versions don't interpret each other's memory.
+const LAYOUT_VERSION: u32 = 1;
+
+/// A bus for exchanging small messages between plugin instances, for instance to show the spectrum
+/// of every instance of an EQ in each instance's editor. All buses opened with the same key share
+/// the same messages, even across different plugins and plugin formats. Buses are backed by named
+/// shared memory, so this also works when the instances are hosted in different processes.
+///
+/// Every message sent to the bus is received by all other instances that have the bus open.
+/// Instances don't receive their own messages. Sending and receiving messages is wait-free and
+/// does not allocate, so both can be done from the audio thread. Opening a bus is not realtime-safe
+/// and should be done in [`Plugin::initialize()`][crate::prelude::Plugin::initialize()] or when
+/// the editor is opened.
+///
+/// The bus keeps the last 32 messages. If a reader doesn't keep up, then older messages are
+/// skipped. This makes the bus a good fit for periodically sending the latest state, but not for
+/// messages that must always arrive.
+///
+/// ```ignore
+/// let mut bus = InterPluginBus::open("com.example.eq.spectrum")?;
+///
+/// let bytes: Vec<u8> = spectrum.iter().flat_map(|bin| bin.to_le_bytes()).collect();
+/// bus.send(&bytes);
+///
+/// let mut buffer = [0; MAX_MESSAGE_SIZE];
+/// while let Some(message) = bus.receive(&mut buffer) {
+///     // `message.sender` identifies the instance that sent `message.data`
+/// }
+/// ```
+pub struct InterPluginBus {
+    memory: SharedMemory,
+    /// Used to identify this instance's messages.
+    instance_id: u64,
+    /// The index of the next message this instance will read.
+    read_index: u64,
+}
+
+/// A message received with [`InterPluginBus::receive()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BusMessage<'a> {
+    /// The [`InterPluginBus::instance_id()`] of the instance that sent the message.
+    pub sender: u64,
+    pub data: &'a [u8],
+}
+
+/// The contents of the shared memory object. An all-zero bit pattern is the initial state, which
+/// is what the operating system's shared memory functions produce.
+#[repr(C)]
+struct BusMemory {
+    /// Used to hand out instance IDs. The first instance gets ID 1.
+    next_instance_id: AtomicU64,
+    /// The index of the next message that will be written. Message `i` is stored in slot `i %
+    /// NUM_SLOTS`.
+    write_index: AtomicU64,
+    slots: [Slot; NUM_SLOTS],
+}
+
+/// A slot in the bus' ring buffer. This works like a seqlock. While message `i` is being written
+/// the sequence number is `i * 2 + 1`, and it is set to `i * 2 + 2` once the message is complete.
+/// Readers check the sequence number before and after copying the message to detect torn reads.
+#[repr(C)]
+struct Slot {
+    sequence: AtomicU64,
+    sender: AtomicU64,
+    len: AtomicU32,
+    /// The message's bytes in native endian words. These are atomics so that a read racing with a
+    /// write is not undefined behavior. The torn copy is then discarded using the sequence number.
+    data: [AtomicU64; MESSAGE_WORDS],
+}
+
+// SAFETY: The shared memory is only ever accessed through atomics
+unsafe impl Send for InterPluginBus {}
+unsafe impl Sync for InterPluginBus {}
+
+impl InterPluginBus {
+    /// Open the bus for `key`, creating it if no other instance has opened it yet. The key should
+    /// be unique to your plugin or family of plugins, like a reverse domain name. This fails if the
+    /// shared memory object could not be created, for instance because the plugin is sandboxed.
+    pub fn open(key: &str) -> io::Result<Self> {
+        let memory = SharedMemory::open(&shared_memory_name(key))?;
+        let instance_id = memory
+            .get()
+            .next_instance_id
+            .fetch_add(1, Ordering::Relaxed)
+            + 1;
+        // Messages sent before this instance opened the bus are not received
+        let read_index = memory.get().write_index.load(Ordering::Acquire);
+
+        Ok(Self {
+            memory,
+            instance_id,
+            read_index,
+        })
+    }
+
+    /// The ID that identifies this instance's messages on the bus.
+    pub fn instance_id(&self) -> u64 {
+        self.instance_id
+    }
+
+    /// Send a message to every other instance that has the bus open. Returns `false` if the
+    /// message is larger than [`MAX_MESSAGE_SIZE`].
+    pub fn send(&self, data: &[u8]) -> bool {
+        if data.len() > MAX_MESSAGE_SIZE {
+            return false;
+        }
+
+        let memory = self.memory.get();
+        let index = memory.write_index.fetch_add(1, Ordering::AcqRel);
+        let slot = &memory.slots[index as usize % NUM_SLOTS];
+
+        slot.sequence.store(index * 2 + 1, Ordering::Relaxed);
+        atomic::fence(Ordering::Release);
+        slot.sender.store(self.instance_id, Ordering::Relaxed);
+        slot.len.store(data.len() as u32, Ordering::Relaxed);
+        for (word, chunk) in slot.data.iter().zip(data.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes[..chunk.len()].copy_from_slice(chunk);
+            word.store(u64::from_ne_bytes(bytes), Ordering::Relaxed);
+        }
+        slot.sequence.store(index * 2 + 2, Ordering::Release);
+
+        true
+    }
+
+    /// Receive the next message sent by another instance, if there is one. The message's data is
+    /// copied to `buffer`. Call this in a loop until it returns `None` to receive all pending
+    /// messages.
+    pub fn receive<'a>(
+        &mut self,
+        buffer: &'a mut [u8; MAX_MESSAGE_SIZE],
+    ) -> Option<BusMessage<'a>> {
+        let memory = self.memory.get();
+        loop {
+            let write_index = memory.write_index.load(Ordering::Acquire);
+            if self.read_index >= write_index {
+                return None;
+            }
+            // Messages that have already been overwritten are skipped
+            if write_index - self.read_index > NUM_SLOTS as u64 {
+                self.read_index = write_index - NUM_SLOTS as u64;
+            }
+
+            let index = self.read_index;
+            let slot = &memory.slots[index as usize % NUM_SLOTS];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            if sequence < index * 2 + 2 {
+                // The message is still being written, so it will be read on the next call
+                return None;
+            }
+
+            self.read_index += 1;
+            if sequence > index * 2 + 2 {
+                // A newer message has already been written to this slot
+                continue;
+            }
+
+            let sender = slot.sender.load(Ordering::Relaxed);
+            let len = (slot.len.load(Ordering::Relaxed) as usize).min(MAX_MESSAGE_SIZE);
+            // A concurrent write is detected below, and the copy is discarded in that case
+            for (chunk, word) in buffer[..len].chunks_mut(8).zip(&slot.data) {
+                let bytes = word.load(Ordering::Relaxed).to_ne_bytes();
+                chunk.copy_from_slice(&bytes[..chunk.len()]);
+            }
+            atomic::fence(Ordering::Acquire);
+            if slot.sequence.load(Ordering::Relaxed) != sequence || sender == self.instance_id {
+                continue;
+            }
+
+            return Some(BusMessage {
+                sender,
+                data: &buffer[..len],
+            });
+        }
+    }
+}
+
+/// A mapping of a named shared memory object containing a [`BusMemory`]. The object is created
+/// when it does not yet exist. It is never removed explicitly, but the operating system removes it
+/// on the next reboot.
+struct SharedMemory {
+    ptr: NonNull<BusMemory>,
+    #[cfg(windows)]
+    handle: windows::Win32::Foundation::HANDLE,
+}
+
+impl SharedMemory {
+    #[cfg(unix)]
+    fn open(name: &str) -> io::Result<Self> {
+        let size = std::mem::size_of::<BusMemory>();
+        let name = std::ffi::CString::new(format!("/{name}"))
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+
+        unsafe {
+            // Only the process that creates the object sets its size. Resizing an object that
+            // another process may already have mapped is not allowed on macOS, and it would race
+            // with that process' first writes on Linux. `shm_open()` is variadic on macOS, so the
+            // mode needs to be passed as an unsigned int.
+            let mut created = true;
+            let mut fd = libc::shm_open(
+                name.as_ptr(),
+                libc::O_CREAT | libc::O_EXCL | libc::O_RDWR,
+                0o600 as libc::c_uint,
+            );
+            if fd < 0 && io::Error::last_os_error().raw_os_error() == Some(libc::EEXIST) {
+                created = false;
+                fd = libc::shm_open(name.as_ptr(), libc::O_RDWR, 0o600 as libc::c_uint);
+            }
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let result = if created {
+                if libc::ftruncate(fd, size as libc::off_t) == 0 {
+                    Ok(())
+                } else {
+                    let err = io::Error::last_os_error();
+                    // Otherwise other instances would wait for a size that is never set
+                    libc::shm_unlink(name.as_ptr());
+
+                    Err(err)
+                }
+            } else {
+                wait_for_size(fd, size)
+            };
+            let result = result.and_then(|()| {
+                let ptr = libc::mmap(
+                    std::ptr::null_mut(),
+                    size,
+                    libc::PROT_READ | libc::PROT_WRITE,
+                    libc::MAP_SHARED,
+                    fd,
+                    0,
+                );
+                if ptr == libc::MAP_FAILED {
+                    Err(io::Error::last_os_error())
+                } else {
+                    Ok(Self {
+                        ptr: NonNull::new_unchecked(ptr.cast()),
+                    })
+                }
+            });
+            // The mapping stays valid after the file descriptor has been closed
+            libc::close(fd);
+
+            result
+        }
+    }
+
+    #[cfg(windows)]
+    fn open(name: &str) -> io::Result<Self> {
+        use windows::core::PCWSTR;
+        use windows::Win32::Foundation::{CloseHandle, INVALID_HANDLE_VALUE};
+        use windows::Win32::System::Memory::{
+            CreateFileMappingW, MapViewOfFile, FILE_MAP_ALL_ACCESS, PAGE_READWRITE,
+        };
+
+        let size = std::mem::size_of::<BusMemory>();
+        let name: Vec<u16> = format!("Local\\{name}")
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+
+        unsafe {
+            // This either creates a new zero-initialized object or opens the existing one
+            let handle = CreateFileMappingW(
+                INVALID_HANDLE_VALUE,
+                None,
+                PAGE_READWRITE,
+                0,
+                size as u32,
+                PCWSTR(name.as_ptr()),
+            )
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+
+            let ptr = MapViewOfFile(handle, FILE_MAP_ALL_ACCESS, 0, 0, size);
+            match NonNull::new(ptr.cast()) {
+                Some(ptr) => Ok(Self { ptr, handle }),
+                None => {
+                    let err = io::Error::last_os_error();
+                    CloseHandle(handle);
+
+                    Err(err)
+                }
+            }
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn open(_name: &str) -> io::Result<Self> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "Shared memory is not supported on this platform",
+        ))
+    }
+
+    fn get(&self) -> &BusMemory {
+        // SAFETY: The mapping lives as long as this object, and all fields are only accessed
+        //         through atomics or with the seqlock protocol
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+/// Wait for the process that created the shared memory object behind `fd` to set the object's size
+/// to at least `size` bytes. Mapping the object before that would fail.
+#[cfg(unix)]
+unsafe fn wait_for_size(fd: libc::c_int, size: usize) -> io::Result<()> {
+    for _ in 0..SIZE_WAIT_MS {
+        let mut stat: libc::stat = std::mem::zeroed();
+        if libc::fstat(fd, &mut stat) != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        if stat.st_size as usize >= size {
+            return Ok(());
+        }
+
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::TimedOut,
+        "The shared memory object was not initialized by the process that created it",
+    ))
+}
+
+impl Drop for SharedMemory {
+    #[cfg(unix)]
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr().cast(), std::mem::size_of::<BusMemory>());
+        }
+    }
+
+    #[cfg(windows)]
+    fn drop(&mut self) {
+        use windows::Win32::Foundation::CloseHandle;
+        use windows::Win32::System::Memory::UnmapViewOfFile;
+
+        unsafe {
+            UnmapViewOfFile(self.ptr.as_ptr().cast());
+            CloseHandle(self.handle);
+        }
+    }
+
+    #[cfg(not(any(unix, windows)))]
+    fn drop(&mut self) {}
+}
+
+/// The name of the shared memory object for a bus key.
+fn shared_memory_name(key: &str) -> String {
+    format!("nihbus{LAYOUT_VERSION}-{:016x}", fnv1a_hash(key.as_bytes()))
+}
+
+/// The 64-bit FNV-1a hash. Used to turn arbitrary keys into valid shared memory object names. The
+/// standard library's hasher can't be used for this since its output may differ between Rust
+/// versions, and plugins built with different compilers should still end up on the same bus.
+fn fnv1a_hash(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ *byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Remove the shared memory object for a test's bus so the tests don't leave them behind.
+    fn remove_bus(key: &str) {
+        #[cfg(unix)]
+        {
+            let name = std::ffi::CString::new(format!("/{}", shared_memory_name(key))).unwrap();
+            unsafe { libc::shm_unlink(name.as_ptr()) };
+        }
+        #[cfg(not(unix))]
+        let _ = key;
+    }
+
+    #[test]
+    fn test_send_receive() {
+        let key = format!("nih_plug_test_send_receive_{}", std::process::id());
+        let mut sender = InterPluginBus::open(&key).unwrap();
+        let mut receiver = InterPluginBus::open(&key).unwrap();
+        assert_ne!(sender.instance_id(), receiver.instance_id());
+
+        assert!(sender.send(b"hello"));
+        assert!(!sender.send(&[0; MAX_MESSAGE_SIZE + 1]));
+
+        let mut buffer = [0; MAX_MESSAGE_SIZE];
+        assert_eq!(
+            receiver.receive(&mut buffer),
+            Some(BusMessage {
+                sender: sender.instance_id(),
+                data: b"hello"
+            })
+        );
+        assert_eq!(receiver.receive(&mut buffer), None);
+
+        // Instances don't receive their own messages
+        assert_eq!(sender.receive(&mut buffer), None);
+
+        remove_bus(&key);
+    }
+
+    #[test]
+    fn test_skip_overwritten_messages() {
+        let key = format!("nih_plug_test_skip_overwritten_{}", std::process::id());
+        let sender = InterPluginBus::open(&key).unwrap();
+        let mut receiver = InterPluginBus::open(&key).unwrap();
+
+        for i in 0..(NUM_SLOTS as u32 + 8) {
+            assert!(sender.send(&i.to_le_bytes()));
+        }
+
+        let mut buffer = [0; MAX_MESSAGE_SIZE];
+        let mut received = Vec::new();
+        while let Some(message) = receiver.receive(&mut buffer) {
+            received.push(u32::from_le_bytes(message.data.try_into().unwrap()));
+        }
+        assert_eq!(received, (8..(NUM_SLOTS as u32 + 8)).collect::<Vec<_>>());
+
+        remove_bus(&key);
+    }
+}
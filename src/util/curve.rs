@@ -0,0 +1,320 @@
+//! Breakpoint curves that can be edited from a plugin's GUI and sampled from the audio thread.
+
+use arc_swap::ArcSwap;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// The shape of the segment between two of a [`Curve`]'s points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum CurveShape {
+    /// A straight line between the two points.
+    #[default]
+    Linear,
+    /// Keep the first point's value until the next point is reached.
+    Hold,
+    /// Starts out slowly and speeds up towards the next point.
+    Exponential,
+    /// Starts out quickly and slows down towards the next point.
+    Logarithmic,
+    /// Starts and ends slowly, with a steeper slope in the middle of the segment.
+    SCurve,
+}
+
+/// A single breakpoint in a [`Curve`]. Both coordinates are in the `[0, 1]` range.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct CurvePoint {
+    pub x: f32,
+    pub y: f32,
+    /// The shape of the segment between this point and the next point.
+    #[serde(default)]
+    pub shape: CurveShape,
+}
+
+/// A curve defined by a list of breakpoints, for instance for envelopes or transfer functions. The
+/// points are always sorted by their X-coordinates, and both coordinates are clamped to `[0, 1]`.
+/// Before the first and after the last point the curve keeps that point's value.
+///
+/// Use [`SharedCurve`] to share a curve between the editor and the audio thread.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Curve {
+    points: Vec<CurvePoint>,
+}
+
+/// A [`Curve`] that can be edited from the GUI and sampled from the audio thread at the same time.
+/// Store this in an `Arc<SharedCurve>` on your parameters struct with a `#[persist = "key"]`
+/// attribute to save the curve as part of the plugin's state.
+///
+/// Every change replaces the entire curve, so sampling the curve never has to wait on the editor.
+#[derive(Debug)]
+pub struct SharedCurve {
+    curve: ArcSwap<Curve>,
+    /// The curve that was replaced by the last update. Holding on to this makes it very unlikely
+    /// that the audio thread ends up dropping the last reference to an old curve, which would
+    /// deallocate memory on the audio thread.
+    retired: Mutex<Option<Arc<Curve>>>,
+}
+
+impl CurveShape {
+    /// All available shapes, useful for building a selection menu in a GUI.
+    pub const ALL: [CurveShape; 5] = [
+        CurveShape::Linear,
+        CurveShape::Hold,
+        CurveShape::Exponential,
+        CurveShape::Logarithmic,
+        CurveShape::SCurve,
+    ];
+
+    /// Map a position `t` in `[0, 1]` within a segment to a `[0, 1]` interpolation factor between
+    /// the segment's start and end values.
+    #[inline]
+    pub fn interpolate(self, t: f32) -> f32 {
+        match self {
+            CurveShape::Linear => t,
+            CurveShape::Hold => 0.0,
+            CurveShape::Exponential => t * t * t,
+            CurveShape::Logarithmic => {
+                let inv_t = 1.0 - t;
+                1.0 - (inv_t * inv_t * inv_t)
+            }
+            CurveShape::SCurve => t * t * (3.0 - 2.0 * t),
+        }
+    }
+
+    /// A human readable name for the shape.
+    pub fn name(self) -> &'static str {
+        match self {
+            CurveShape::Linear => "Linear",
+            CurveShape::Hold => "Hold",
+            CurveShape::Exponential => "Exponential",
+            CurveShape::Logarithmic => "Logarithmic",
+            CurveShape::SCurve => "S-curve",
+        }
+    }
+}
+
+impl CurvePoint {
+    /// A new point with a linear segment following it.
+    pub fn new(x: f32, y: f32) -> Self {
+        Self {
+            x,
+            y,
+            shape: CurveShape::Linear,
+        }
+    }
+
+    /// Change the shape of the segment following this point.
+    pub fn with_shape(mut self, shape: CurveShape) -> Self {
+        self.shape = shape;
+        self
+    }
+}
+
+impl Default for Curve {
+    /// A linear ramp from `(0, 0)` to `(1, 1)`.
+    fn default() -> Self {
+        Self::new(vec![CurvePoint::new(0.0, 0.0), CurvePoint::new(1.0, 1.0)])
+    }
+}
+
+impl Curve {
+    /// Create a curve from a list of points. The points are sorted and their coordinates are
+    /// clamped to `[0, 1]`.
+    pub fn new(points: Vec<CurvePoint>) -> Self {
+        let mut curve = Self { points };
+        curve.normalize();
+        curve
+    }
+
+    /// The curve's points, sorted by their X-coordinates.
+    pub fn points(&self) -> &[CurvePoint] {
+        &self.points
+    }
+
+    /// Add a point to the curve and return its index. The new segment uses the shape of the
+    /// segment the point was inserted into.
+    pub fn add_point(&mut self, x: f32, y: f32) -> usize {
+        let x = x.clamp(0.0, 1.0);
+        let idx = self.points.partition_point(|point| point.x <= x);
+        let shape = idx
+            .checked_sub(1)
+            .map(|previous_idx| self.points[previous_idx].shape)
+            .unwrap_or_default();
+        self.points
+            .insert(idx, CurvePoint::new(x, y.clamp(0.0, 1.0)).with_shape(shape));
+
+        idx
+    }
+
+    /// Remove the point at an index, returning the removed point if the index was valid.
+    pub fn remove_point(&mut self, idx: usize) -> Option<CurvePoint> {
+        if idx < self.points.len() {
+            Some(self.points.remove(idx))
+        } else {
+            None
+        }
+    }
+
+    /// Move the point at an index to a new position. The X-coordinate is clamped between the
+    /// neighbouring points' X-coordinates so the point's index never changes while dragging it.
+    pub fn move_point(&mut self, idx: usize, x: f32, y: f32) {
+        let min_x = idx
+            .checked_sub(1)
+            .and_then(|previous_idx| self.points.get(previous_idx))
+            .map(|point| point.x)
+            .unwrap_or(0.0);
+        let max_x = self.points.get(idx + 1).map(|point| point.x).unwrap_or(1.0);
+
+        if let Some(point) = self.points.get_mut(idx) {
+            point.x = x.clamp(min_x, max_x);
+            point.y = y.clamp(0.0, 1.0);
+        }
+    }
+
+    /// Change the shape of the segment starting at the point at an index.
+    pub fn set_shape(&mut self, idx: usize, shape: CurveShape) {
+        if let Some(point) = self.points.get_mut(idx) {
+            point.shape = shape;
+        }
+    }
+
+    /// Compute the curve's value at `x`. This does not allocate, so it can be called from the
+    /// audio thread. An empty curve always returns 0.
+    pub fn value_at(&self, x: f32) -> f32 {
+        let (first, last) = match (self.points.first(), self.points.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return 0.0,
+        };
+        if x <= first.x {
+            return first.y;
+        }
+        if x >= last.x {
+            return last.y;
+        }
+
+        // `x` lies between the first and the last point, so `end_idx` is in `1..points.len()`
+        let end_idx = self.points.partition_point(|point| point.x <= x);
+        let start = &self.points[end_idx - 1];
+        let end = &self.points[end_idx];
+        let width = end.x - start.x;
+        if width <= 0.0 {
+            return end.y;
+        }
+
+        let t = (x - start.x) / width;
+        start.y + (end.y - start.y) * start.shape.interpolate(t)
+    }
+
+    /// Sort the points and clamp their coordinates. Deserialized curves may not uphold these
+    /// invariants.
+    fn normalize(&mut self) {
+        for point in &mut self.points {
+            point.x = point.x.clamp(0.0, 1.0);
+            point.y = point.y.clamp(0.0, 1.0);
+        }
+        self.points.sort_by(|a, b| a.x.total_cmp(&b.x));
+    }
+}
+
+impl Default for SharedCurve {
+    fn default() -> Self {
+        Self::new(Curve::default())
+    }
+}
+
+impl SharedCurve {
+    /// Create a shared curve with an initial value.
+    pub fn new(curve: Curve) -> Self {
+        Self {
+            curve: ArcSwap::from_pointee(curve),
+            retired: Mutex::new(None),
+        }
+    }
+
+    /// Get the current curve. This is realtime-safe, so it can be used from the audio thread to
+    /// sample the curve many times per block. The returned guard should not be held on to for
+    /// longer than a single processing cycle.
+    #[inline]
+    pub fn load(&self) -> arc_swap::Guard<Arc<Curve>> {
+        self.curve.load()
+    }
+
+    /// Compute the curve's value at `x`. See [`Curve::value_at()`]. When sampling the curve more
+    /// than once per processing cycle, using [`load()`][Self::load()] once is more efficient.
+    #[inline]
+    pub fn value_at(&self, x: f32) -> f32 {
+        self.curve.load().value_at(x)
+    }
+
+    /// Replace the curve. This allocates, so it should only be called from the GUI or the main
+    /// thread.
+    pub fn store(&self, curve: Curve) {
+        let mut curve = curve;
+        curve.normalize();
+
+        let old_curve = self.curve.swap(Arc::new(curve));
+        *self.retired.lock() = Some(old_curve);
+    }
+
+    /// Modify a copy of the current curve and then replace the curve with it. This allocates, so it
+    /// should only be called from the GUI or the main thread.
+    pub fn update<R>(&self, f: impl FnOnce(&mut Curve) -> R) -> R {
+        let mut curve = Curve::clone(&self.curve.load());
+        let result = f(&mut curve);
+        self.store(curve);
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_curve() {
+        let curve = Curve::default();
+        assert_eq!(curve.value_at(-1.0), 0.0);
+        assert_eq!(curve.value_at(0.25), 0.25);
+        assert_eq!(curve.value_at(2.0), 1.0);
+    }
+
+    #[test]
+    fn test_hold_segment() {
+        let curve = Curve::new(vec![
+            CurvePoint::new(0.0, 0.5).with_shape(CurveShape::Hold),
+            CurvePoint::new(0.5, 1.0),
+            CurvePoint::new(1.0, 0.0),
+        ]);
+        assert_eq!(curve.value_at(0.49), 0.5);
+        assert_eq!(curve.value_at(0.5), 1.0);
+        assert_eq!(curve.value_at(0.75), 0.5);
+    }
+
+    #[test]
+    fn test_points_stay_sorted() {
+        let mut curve = Curve::new(vec![CurvePoint::new(1.0, 1.0), CurvePoint::new(0.0, 0.0)]);
+        assert_eq!(curve.points()[0].x, 0.0);
+
+        let idx = curve.add_point(0.5, 2.0);
+        assert_eq!(idx, 1);
+        assert_eq!(curve.points()[1].y, 1.0);
+
+        // Points can't be dragged past their neighbours
+        curve.move_point(1, 1.5, 0.5);
+        assert_eq!(curve.points()[1].x, 1.0);
+        assert_eq!(curve.remove_point(1), Some(CurvePoint::new(1.0, 0.5)));
+        assert_eq!(curve.points().len(), 2);
+    }
+
+    #[test]
+    fn test_shared_curve_update() {
+        let shared_curve = SharedCurve::default();
+        let idx = shared_curve.update(|curve| curve.add_point(0.5, 0.0));
+        shared_curve.update(|curve| curve.set_shape(idx, CurveShape::Hold));
+
+        assert_eq!(shared_curve.value_at(0.5), 0.0);
+        assert_eq!(shared_curve.value_at(0.75), 0.0);
+        assert_eq!(shared_curve.load().points().len(), 3);
+    }
+}
@@ -0,0 +1,91 @@
+//! Automatic tail handling based on the plugin's output level.
+
+use crate::buffer::Buffer;
+use crate::plugin::ProcessStatus;
+
+/// Keeps track of whether the plugin is still producing sound so it can be kept alive while its
+/// output decays. This is useful for plugins whose tail length is hard to predict, like reverbs
+/// with modulated feedback. Return the status from [`update()`][Self::update()] from the plugin's
+/// process function. Plugins with a fixed tail length can use
+/// [`ProcessContext::set_tail_seconds()`][crate::prelude::ProcessContext::set_tail_seconds()]
+/// instead.
+#[derive(Debug, Clone)]
+pub struct DecayTracker {
+    /// Output below this gain is considered to be silent.
+    threshold_gain: f32,
+    /// The number of consecutive silent samples needed before the host is allowed to suspend the
+    /// plugin. Prevents the plugin from being suspended during short gaps, like between the
+    /// repeats of a delay.
+    hold_samples: usize,
+    /// The number of consecutive silent samples processed so far.
+    silent_samples: usize,
+}
+
+impl DecayTracker {
+    /// Create a new decay tracker. Output below `threshold_db` is considered to be silent, and the
+    /// output needs to be silent for `hold_samples` samples before the plugin's tail has ended.
+    pub fn new(threshold_db: f32, hold_samples: usize) -> Self {
+        Self {
+            threshold_gain: super::db_to_gain(threshold_db),
+            hold_samples,
+            // The plugin should not be kept alive before it has produced any sound
+            silent_samples: hold_samples,
+        }
+    }
+
+    /// Reset the tracker's state. Call this from [`Plugin::reset()`][crate::prelude::Plugin::reset()].
+    pub fn reset(&mut self) {
+        self.silent_samples = self.hold_samples;
+    }
+
+    /// Check the plugin's output after processing a block. Returns
+    /// [`ProcessStatus::KeepAlive`] while the output is still decaying, and
+    /// [`ProcessStatus::Normal`] once it has been silent for long enough.
+    pub fn update(&mut self, buffer: &Buffer) -> ProcessStatus {
+        let channels = buffer.as_slice_immutable();
+        for sample_idx in 0..buffer.samples() {
+            let is_silent = channels
+                .iter()
+                .all(|channel| channel[sample_idx].abs() <= self.threshold_gain);
+            if is_silent {
+                self.silent_samples = self.silent_samples.saturating_add(1);
+            } else {
+                self.silent_samples = 0;
+            }
+        }
+
+        if self.silent_samples >= self.hold_samples {
+            ProcessStatus::Normal
+        } else {
+            ProcessStatus::KeepAlive
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_alive_until_silent() {
+        let mut tracker = DecayTracker::new(-80.0, 4);
+        let mut samples = vec![0.0f32; 4];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(4, |output_slices| {
+                *output_slices = vec![std::slice::from_raw_parts_mut(samples.as_mut_ptr(), 4)];
+            })
+        };
+        assert_eq!(tracker.update(&buffer), ProcessStatus::Normal);
+
+        buffer.as_slice()[0][3] = 0.5;
+        assert_eq!(tracker.update(&buffer), ProcessStatus::KeepAlive);
+
+        // The output needs to be silent for the entire hold time
+        buffer.as_slice()[0][3] = 0.0;
+        buffer.as_slice()[0][2] = 0.5;
+        assert_eq!(tracker.update(&buffer), ProcessStatus::KeepAlive);
+        buffer.as_slice()[0][2] = 0.0;
+        assert_eq!(tracker.update(&buffer), ProcessStatus::Normal);
+    }
+}
@@ -0,0 +1,215 @@
+//! Latency compensated dry/wet mixing.
+
+use std::f32::consts::FRAC_PI_2;
+
+use crate::buffer::Buffer;
+
+/// How the dry and the wet signals are combined by the [`DryWetMixer`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MixingStyle {
+    /// The gains are linearly interpolated. This keeps the level constant when the dry and wet
+    /// signals are correlated, for instance for a compressor or an EQ.
+    Linear,
+    /// Uses a sine/cosine curve so the combined power stays constant. This works better for
+    /// uncorrelated signals, like a reverb's wet signal.
+    EqualPower,
+}
+
+/// Stores the dry signal and mixes it back in after the plugin has processed the buffer. The dry
+/// signal is delayed by the plugin's latency so it lines up with the processed signal, which would
+/// otherwise cause comb filtering with lookahead processing.
+///
+/// # Example
+///
+/// ```ignore
+/// // In `Plugin::initialize()`
+/// self.dry_wet_mixer.resize(num_channels, buffer_config.max_buffer_size as usize, MAX_LATENCY);
+///
+/// // In `Plugin::process()`
+/// self.dry_wet_mixer.write_dry(buffer);
+/// // Process `buffer`...
+/// self.dry_wet_mixer.mix_in_dry_with(buffer, MixingStyle::Linear, latency, || {
+///     self.params.mix.smoothed.next()
+/// });
+/// ```
+#[derive(Debug, Default, Clone)]
+pub struct DryWetMixer {
+    /// A ring buffer for each channel containing the dry signal. These are large enough to hold the
+    /// maximum latency plus a block of the maximum block size.
+    delay_lines: Vec<Vec<f32>>,
+    /// The largest block the mixer can handle.
+    max_block_size: usize,
+    /// The maximum latency the dry signal can be delayed by.
+    max_latency: usize,
+    /// The position in the delay lines the next block is written to.
+    write_pos: usize,
+}
+
+impl DryWetMixer {
+    /// Create a mixer for `num_channels` channels that can process blocks of up to
+    /// `max_block_size` samples and can compensate for up to `max_latency` samples of latency.
+    pub fn new(num_channels: usize, max_block_size: usize, max_latency: usize) -> Self {
+        let mut mixer = Self::default();
+        mixer.resize(num_channels, max_block_size, max_latency);
+
+        mixer
+    }
+
+    /// Change the number of channels, the maximum block size, or the maximum latency. This
+    /// allocates, so it should be called from
+    /// [`Plugin::initialize()`][crate::prelude::Plugin::initialize()]. Clears the stored dry
+    /// signal.
+    pub fn resize(&mut self, num_channels: usize, max_block_size: usize, max_latency: usize) {
+        self.max_block_size = max_block_size;
+        self.max_latency = max_latency;
+        self.delay_lines.resize_with(num_channels, Vec::new);
+        for delay_line in &mut self.delay_lines {
+            delay_line.resize(max_block_size + max_latency, 0.0);
+        }
+
+        self.reset();
+    }
+
+    /// Clear the stored dry signal. Call this from
+    /// [`Plugin::reset()`][crate::prelude::Plugin::reset()].
+    pub fn reset(&mut self) {
+        for delay_line in &mut self.delay_lines {
+            delay_line.fill(0.0);
+        }
+        self.write_pos = 0;
+    }
+
+    /// Store the dry signal for a block. This needs to be called before the buffer is processed,
+    /// and every call needs to be followed by a call to one of the `mix_in_dry` functions. Channels
+    /// beyond the number of channels the mixer was created with are ignored.
+    pub fn write_dry(&mut self, buffer: &Buffer) {
+        let num_samples = buffer.samples();
+        if num_samples > self.max_block_size {
+            nih_debug_assert_failure!(
+                "The buffer contains {} samples, but the dry/wet mixer can only handle {}",
+                num_samples,
+                self.max_block_size
+            );
+            return;
+        }
+
+        for (delay_line, channel) in self
+            .delay_lines
+            .iter_mut()
+            .zip(buffer.as_slice_immutable().iter())
+        {
+            // The block is written in at most two parts when it wraps around
+            let first_len = num_samples.min(delay_line.len() - self.write_pos);
+            delay_line[self.write_pos..self.write_pos + first_len]
+                .copy_from_slice(&channel[..first_len]);
+            delay_line[..num_samples - first_len].copy_from_slice(&channel[first_len..]);
+        }
+    }
+
+    /// Mix the dry signal stored by [`write_dry()`][Self::write_dry()], delayed by `latency`
+    /// samples, into the processed signal in `buffer`. A `ratio` of 0.0 outputs only the dry
+    /// signal, and 1.0 outputs only the processed signal. Latencies above the maximum latency are
+    /// clamped.
+    pub fn mix_in_dry(
+        &mut self,
+        buffer: &mut Buffer,
+        ratio: f32,
+        style: MixingStyle,
+        latency: usize,
+    ) {
+        if ratio >= 1.0 {
+            // The delay lines still need to advance so they're in sync when the ratio changes
+            self.advance(buffer.samples());
+        } else {
+            self.mix_in_dry_with(buffer, style, latency, || ratio);
+        }
+    }
+
+    /// The same as [`mix_in_dry()`][Self::mix_in_dry()], but `ratio` is called once per sample.
+    /// This can be used with a parameter's smoother.
+    pub fn mix_in_dry_with(
+        &mut self,
+        buffer: &mut Buffer,
+        style: MixingStyle,
+        latency: usize,
+        mut ratio: impl FnMut() -> f32,
+    ) {
+        let num_samples = buffer.samples();
+        if num_samples > self.max_block_size {
+            // `write_dry()` will have already triggered a debug assertion
+            return;
+        }
+
+        let latency = latency.min(self.max_latency);
+        let capacity = self.max_block_size + self.max_latency;
+        let channels = buffer.as_slice();
+        for sample_idx in 0..num_samples {
+            let ratio = ratio().clamp(0.0, 1.0);
+            let (wet_gain, dry_gain) = match style {
+                MixingStyle::Linear => (ratio, 1.0 - ratio),
+                MixingStyle::EqualPower => ((ratio * FRAC_PI_2).sin(), (ratio * FRAC_PI_2).cos()),
+            };
+
+            let read_pos = (self.write_pos + capacity + sample_idx - latency) % capacity;
+            for (channel, delay_line) in channels.iter_mut().zip(self.delay_lines.iter()) {
+                channel[sample_idx] =
+                    (channel[sample_idx] * wet_gain) + (delay_line[read_pos] * dry_gain);
+            }
+        }
+
+        self.advance(num_samples);
+    }
+
+    /// Move the write position past the last block.
+    fn advance(&mut self, num_samples: usize) {
+        if num_samples > 0 && num_samples <= self.max_block_size {
+            self.write_pos =
+                (self.write_pos + num_samples) % (self.max_block_size + self.max_latency);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::test_util::with_buffer;
+
+    #[test]
+    fn test_dry_signal_is_delayed_by_latency() {
+        let mut mixer = DryWetMixer::new(1, 4, 4);
+        for block in 0..3 {
+            let mut samples = [1.0, 2.0, 3.0, 4.0].map(|sample| sample + (block as f32 * 4.0));
+            with_buffer(&mut samples, |buffer| {
+                mixer.write_dry(buffer);
+                // The plugin would normally process the buffer here
+                buffer.as_slice()[0].fill(0.0);
+                mixer.mix_in_dry(buffer, 0.0, MixingStyle::Linear, 2);
+            });
+
+            match block {
+                0 => assert_eq!(samples, [0.0, 0.0, 1.0, 2.0]),
+                _ => assert_eq!(
+                    samples,
+                    [3.0, 4.0, 5.0, 6.0].map(|sample| sample + ((block - 1) as f32 * 4.0))
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_smaller_blocks() {
+        let mut mixer = DryWetMixer::new(1, 4, 1);
+        let mut output = Vec::new();
+        for block in [[1.0, 2.0].as_slice(), &[3.0], &[4.0, 5.0, 6.0]] {
+            let mut samples = block.to_vec();
+            with_buffer(&mut samples, |buffer| {
+                mixer.write_dry(buffer);
+                buffer.as_slice()[0].fill(2.0);
+                mixer.mix_in_dry(buffer, 0.5, MixingStyle::Linear, 1);
+            });
+            output.extend(samples);
+        }
+
+        assert_eq!(output, [1.0, 1.5, 2.0, 2.5, 3.0, 3.5]);
+    }
+}
@@ -0,0 +1,146 @@
+//! Layout helpers for on-screen piano keyboards. Used by the keyboard widgets in the GUI adapters.
+
+use std::ops::RangeInclusive;
+
+/// The width of a black key relative to the width of a white key.
+const BLACK_KEY_WIDTH: f32 = 0.6;
+/// The height of a black key relative to the height of the keyboard.
+const BLACK_KEY_HEIGHT: f32 = 0.6;
+
+/// The number of white keys below a note within an octave, indexed by the note's pitch class.
+const WHITE_KEYS_BELOW: [u8; 12] = [0, 1, 1, 2, 2, 3, 4, 4, 5, 5, 6, 6];
+
+/// A key's bounding box, in whatever units the keyboard's size was specified in. The origin is the
+/// keyboard's top left corner.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct KeyRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// The geometry of a horizontal piano keyboard spanning a range of MIDI notes. Black keys are drawn
+/// on top of the white keys and take precedence when hit testing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardLayout {
+    first_note: u8,
+    last_note: u8,
+    width: f32,
+    height: f32,
+}
+
+/// Whether a MIDI note number corresponds to a black key.
+pub fn is_black_key(note: u8) -> bool {
+    matches!(note % 12, 1 | 3 | 6 | 8 | 10)
+}
+
+/// The number of white keys in the MIDI note range `0..note`.
+fn white_keys_below(note: u8) -> u32 {
+    (note as u32 / 12) * 7 + WHITE_KEYS_BELOW[note as usize % 12] as u32
+}
+
+impl KeyboardLayout {
+    /// Create a layout for a keyboard of the given size that covers `notes`. The range is extended
+    /// to start and end on a white key. Notes above 127 are ignored.
+    pub fn new(notes: RangeInclusive<u8>, width: f32, height: f32) -> Self {
+        let mut first_note = (*notes.start()).min(127);
+        let mut last_note = (*notes.end()).clamp(first_note, 127);
+        // MIDI notes 0 and 127 are both white keys, so these can't go out of bounds
+        if is_black_key(first_note) {
+            first_note -= 1;
+        }
+        if is_black_key(last_note) {
+            last_note += 1;
+        }
+
+        Self {
+            first_note,
+            last_note,
+            width,
+            height,
+        }
+    }
+
+    /// The range of notes covered by this keyboard.
+    pub fn notes(&self) -> RangeInclusive<u8> {
+        self.first_note..=self.last_note
+    }
+
+    /// The number of white keys on this keyboard.
+    pub fn num_white_keys(&self) -> u32 {
+        white_keys_below(self.last_note) - white_keys_below(self.first_note) + 1
+    }
+
+    /// The bounding box for a note's key, or `None` if the note is not on this keyboard.
+    pub fn key_rect(&self, note: u8) -> Option<KeyRect> {
+        if !self.notes().contains(&note) {
+            return None;
+        }
+
+        let white_key_width = self.width / self.num_white_keys() as f32;
+        let white_key_idx = white_keys_below(note) - white_keys_below(self.first_note);
+        if is_black_key(note) {
+            // Black keys are centered on the boundary between the two surrounding white keys
+            let width = white_key_width * BLACK_KEY_WIDTH;
+            Some(KeyRect {
+                x: white_key_idx as f32 * white_key_width - (width / 2.0),
+                y: 0.0,
+                width,
+                height: self.height * BLACK_KEY_HEIGHT,
+            })
+        } else {
+            Some(KeyRect {
+                x: white_key_idx as f32 * white_key_width,
+                y: 0.0,
+                width: white_key_width,
+                height: self.height,
+            })
+        }
+    }
+
+    /// The note at a position relative to the keyboard's top left corner, if any.
+    pub fn note_at(&self, x: f32, y: f32) -> Option<u8> {
+        let contains = |rect: &KeyRect| {
+            x >= rect.x && x < rect.x + rect.width && y >= rect.y && y < rect.y + rect.height
+        };
+
+        self.black_keys()
+            .chain(self.white_keys())
+            .find(|&note| self.key_rect(note).map_or(false, |rect| contains(&rect)))
+    }
+
+    /// All white keys on this keyboard, from low to high.
+    pub fn white_keys(&self) -> impl Iterator<Item = u8> {
+        self.notes().filter(|&note| !is_black_key(note))
+    }
+
+    /// All black keys on this keyboard, from low to high.
+    pub fn black_keys(&self) -> impl Iterator<Item = u8> {
+        self.notes().filter(|&note| is_black_key(note))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_range_snaps_to_white_keys() {
+        let layout = KeyboardLayout::new(61..=66, 100.0, 50.0);
+        assert_eq!(layout.notes(), 60..=67);
+        assert_eq!(layout.num_white_keys(), 5);
+    }
+
+    #[test]
+    fn test_hit_testing() {
+        // One octave starting at middle C with 10 unit wide white keys
+        let layout = KeyboardLayout::new(60..=71, 70.0, 50.0);
+        assert_eq!(layout.note_at(1.0, 45.0), Some(60));
+        assert_eq!(layout.note_at(11.0, 45.0), Some(62));
+        // The C# key covers the top of the C and D keys
+        assert_eq!(layout.note_at(9.0, 10.0), Some(61));
+        assert_eq!(layout.note_at(69.0, 10.0), Some(71));
+        assert_eq!(layout.note_at(70.0, 10.0), None);
+    }
+}
@@ -0,0 +1,481 @@
+//! An optional modulation matrix for modulating parameters from LFOs, an envelope follower, a
+//! random source, and MIDI inputs.
+
+use arc_swap::ArcSwap;
+use atomic_refcell::AtomicRefCell;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::buffer::Buffer;
+use crate::midi::NoteEvent;
+use crate::params::internals::ParamPtr;
+
+pub use crate::dsp::LfoShape;
+
+/// The number of LFOs available as modulation sources.
+pub const NUM_LFOS: usize = 4;
+
+/// A modulation matrix that's evaluated by the plugin wrapper once per processing block, just
+/// before the plugin's `process()` function is called. Every [`ModulationRouting`] modulates a
+/// target parameter by a source's value multiplied by the routing's depth. Routings for the same
+/// target are summed. The modulation is applied using the same mechanism as CLAP's monophonic
+/// parameter modulation, so it's applied on top of the parameter's automated value, it's smoothed
+/// using the parameter's smoother, and it replaces any modulation coming from the host for the
+/// routed parameters.
+///
+/// To enable the modulation matrix, store an `Arc<ModulationMatrix>` on your parameters struct with
+/// a `#[persist = "key"]` attribute so the routings are saved as part of the plugin's state, and
+/// return a clone of that `Arc` from
+/// [`Plugin::modulation_matrix()`][crate::prelude::Plugin::modulation_matrix()]. The configuration
+/// can be changed from the GUI at any time. Every change replaces the entire configuration, so the
+/// audio thread never has to wait on the editor.
+#[derive(Debug)]
+pub struct ModulationMatrix {
+    config: ArcSwap<ModulationConfig>,
+    /// The configuration that was replaced by the last update. Holding on to this makes it very
+    /// unlikely that the audio thread ends up dropping the last reference to an old configuration,
+    /// which would deallocate memory on the audio thread.
+    retired: Mutex<Option<Arc<ModulationConfig>>>,
+    /// The modulation sources' state. This is only accessed from the audio thread.
+    engine: AtomicRefCell<Engine>,
+}
+
+/// The modulation matrix's sources and routings. This is what gets persisted as part of the
+/// plugin's state.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModulationConfig {
+    pub lfos: [LfoSettings; NUM_LFOS],
+    pub envelope_follower: EnvelopeFollowerSettings,
+    /// How often the random source picks a new value, in Hertz.
+    pub random_rate_hz: f32,
+    pub routings: Vec<ModulationRouting>,
+}
+
+/// Modulates a target parameter by a source's value.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModulationRouting {
+    pub source: ModulationSource,
+    /// The target parameter's ID, as set with `#[id = "..."]` including any nested ID prefixes.
+    pub target: String,
+    /// The modulation depth in `[-1, 1]`. This is the offset in normalized parameter values applied
+    /// when the source is at its maximum value.
+    pub depth: f32,
+}
+
+/// A source in the modulation matrix. LFOs, random values, and pitch bend are bipolar and produce
+/// values in `[-1, 1]`. The other sources are unipolar and produce values in `[0, 1]`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModulationSource {
+    /// One of the [`NUM_LFOS`] LFOs.
+    Lfo(usize),
+    /// Follows the peak level of the plugin's main input.
+    EnvelopeFollower,
+    /// A sample-and-hold random value.
+    Random,
+    /// The velocity of the last note on event.
+    Velocity,
+    /// The last MIDI pitch bend value.
+    PitchBend,
+    /// The last MIDI channel pressure value.
+    ChannelPressure,
+    /// The last value of a MIDI CC.
+    MidiCc(u8),
+}
+
+/// The settings for an LFO.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LfoSettings {
+    pub shape: LfoShape,
+    /// The LFO's rate in Hertz.
+    pub rate_hz: f32,
+}
+
+/// The settings for the envelope follower.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct EnvelopeFollowerSettings {
+    pub attack_ms: f32,
+    pub release_ms: f32,
+}
+
+/// The modulation sources' state.
+#[derive(Debug)]
+struct Engine {
+    /// The configuration used during the last block. Used to reset the modulation for targets that
+    /// no longer have any routings.
+    last_config: Option<Arc<ModulationConfig>>,
+
+    /// The LFOs' phases in `[0, 1)`.
+    lfo_phases: [f32; NUM_LFOS],
+    envelope: f32,
+    random_value: f32,
+    /// The random source's phase in `[0, 1)`. A new value is picked when this wraps around.
+    random_phase: f32,
+    /// The xorshift RNG's state. Must never be zero.
+    rng_state: u32,
+
+    velocity: f32,
+    pitch_bend: f32,
+    channel_pressure: f32,
+    midi_ccs: [f32; 128],
+}
+
+impl Default for ModulationConfig {
+    fn default() -> Self {
+        Self {
+            lfos: [LfoSettings::default(); NUM_LFOS],
+            envelope_follower: EnvelopeFollowerSettings::default(),
+            random_rate_hz: 1.0,
+            routings: Vec::new(),
+        }
+    }
+}
+
+impl Default for LfoSettings {
+    fn default() -> Self {
+        Self {
+            shape: LfoShape::Sine,
+            rate_hz: 1.0,
+        }
+    }
+}
+
+impl Default for EnvelopeFollowerSettings {
+    fn default() -> Self {
+        Self {
+            attack_ms: 10.0,
+            release_ms: 200.0,
+        }
+    }
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        Self {
+            last_config: None,
+
+            lfo_phases: [0.0; NUM_LFOS],
+            envelope: 0.0,
+            random_value: 0.0,
+            random_phase: 0.0,
+            rng_state: 0x9E37_79B9,
+
+            velocity: 0.0,
+            pitch_bend: 0.0,
+            channel_pressure: 0.0,
+            midi_ccs: [0.0; 128],
+        }
+    }
+}
+
+impl ModulationSource {
+    /// The built-in sources, useful for building a selection menu in a GUI. This includes the mod
+    /// wheel, breath controller, foot controller, and expression CCs. Other CCs can still be used
+    /// with [`ModulationSource::MidiCc`].
+    pub const ALL: [ModulationSource; 14] = [
+        ModulationSource::Lfo(0),
+        ModulationSource::Lfo(1),
+        ModulationSource::Lfo(2),
+        ModulationSource::Lfo(3),
+        ModulationSource::EnvelopeFollower,
+        ModulationSource::Random,
+        ModulationSource::Velocity,
+        ModulationSource::PitchBend,
+        ModulationSource::ChannelPressure,
+        ModulationSource::MidiCc(1),
+        ModulationSource::MidiCc(2),
+        ModulationSource::MidiCc(4),
+        ModulationSource::MidiCc(11),
+        ModulationSource::MidiCc(74),
+    ];
+
+    /// A human readable name for the source.
+    pub fn name(self) -> String {
+        match self {
+            ModulationSource::Lfo(idx) => format!("LFO {}", idx + 1),
+            ModulationSource::EnvelopeFollower => String::from("Envelope follower"),
+            ModulationSource::Random => String::from("Random"),
+            ModulationSource::Velocity => String::from("Velocity"),
+            ModulationSource::PitchBend => String::from("Pitch bend"),
+            ModulationSource::ChannelPressure => String::from("Channel pressure"),
+            ModulationSource::MidiCc(cc) => format!("CC {cc}"),
+        }
+    }
+}
+
+impl Default for ModulationMatrix {
+    fn default() -> Self {
+        Self::new(ModulationConfig::default())
+    }
+}
+
+impl ModulationMatrix {
+    /// Create a modulation matrix with an initial configuration.
+    pub fn new(config: ModulationConfig) -> Self {
+        Self {
+            config: ArcSwap::from_pointee(config),
+            retired: Mutex::new(None),
+            engine: AtomicRefCell::new(Engine::default()),
+        }
+    }
+
+    /// The current configuration.
+    pub fn config(&self) -> Arc<ModulationConfig> {
+        self.config.load_full()
+    }
+
+    /// Replace the configuration. This should be called from the GUI thread or when loading state,
+    /// and never from the audio thread.
+    pub fn set_config(&self, config: ModulationConfig) {
+        let old = self.config.swap(Arc::new(config));
+        *self.retired.lock() = Some(old);
+    }
+
+    /// Modify a copy of the current configuration and store it. See
+    /// [`set_config()`][Self::set_config()].
+    pub fn update_config(&self, f: impl FnOnce(&mut ModulationConfig)) {
+        let mut config = self.config().as_ref().clone();
+        f(&mut config);
+        self.set_config(config);
+    }
+
+    /// Add a routing. Changes the depth of an existing routing between the same source and target
+    /// instead if one already exists.
+    pub fn add_routing(&self, routing: ModulationRouting) {
+        self.update_config(|config| {
+            match config
+                .routings
+                .iter_mut()
+                .find(|r| r.source == routing.source && r.target == routing.target)
+            {
+                Some(existing) => existing.depth = routing.depth,
+                None => config.routings.push(routing),
+            }
+        });
+    }
+
+    /// Remove the routing at an index in [`ModulationConfig::routings`], if it exists.
+    pub fn remove_routing(&self, index: usize) {
+        self.update_config(|config| {
+            if index < config.routings.len() {
+                config.routings.remove(index);
+            }
+        });
+    }
+
+    /// Update the modulation sources for the next block and apply the modulation to the routed
+    /// parameters. Called by the plugin wrappers just before the plugin processes a block of audio.
+    /// `events` are the note events for this block, and `buffer` still contains the block's input.
+    ///
+    /// # Safety
+    ///
+    /// The parameters returned by `params_getter` need to be alive.
+    pub(crate) unsafe fn process_block<'a, S: 'a>(
+        &self,
+        buffer: &Buffer,
+        events: impl IntoIterator<Item = &'a NoteEvent<S>>,
+        sample_rate: f32,
+        params_getter: impl Fn(&str) -> Option<ParamPtr>,
+    ) {
+        let num_samples = buffer.samples();
+        if num_samples == 0 {
+            return;
+        }
+
+        let mut engine = match self.engine.try_borrow_mut() {
+            Ok(engine) => engine,
+            Err(_) => {
+                nih_debug_assert_failure!("The modulation matrix is being processed concurrently");
+                return;
+            }
+        };
+        let config = self.config.load_full();
+
+        engine.handle_events(events);
+        engine.follow_envelope(buffer, &config.envelope_follower, sample_rate);
+        let source_values = engine.source_values(&config);
+
+        // Routings may have been removed since the last block. Those parameters should go back to
+        // their unmodulated values.
+        if let Some(last_config) = &engine.last_config {
+            if !Arc::ptr_eq(last_config, &config) {
+                for routing in &last_config.routings {
+                    if config.routings.iter().any(|r| r.target == routing.target) {
+                        continue;
+                    }
+
+                    if let Some(param_ptr) = params_getter(&routing.target) {
+                        if param_ptr.modulate_value(0.0) {
+                            param_ptr.update_smoother(sample_rate, false);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (idx, routing) in config.routings.iter().enumerate() {
+            // Every target is only modulated once, using the sum of all of its routings
+            if config.routings[..idx]
+                .iter()
+                .any(|r| r.target == routing.target)
+            {
+                continue;
+            }
+
+            let modulation_offset: f32 = config.routings[idx..]
+                .iter()
+                .filter(|r| r.target == routing.target)
+                .map(|r| r.depth * source_values.get(r.source))
+                .sum();
+            match params_getter(&routing.target) {
+                Some(param_ptr) => {
+                    if param_ptr.modulate_value(modulation_offset.clamp(-1.0, 1.0)) {
+                        param_ptr.update_smoother(sample_rate, false);
+                    }
+                }
+                None => nih_debug_assert_failure!("Unknown modulation target: {}", routing.target),
+            }
+        }
+
+        engine.advance(&config, num_samples as f32 / sample_rate);
+        engine.last_config = Some(config);
+    }
+}
+
+/// The sources' values at the start of a block.
+struct SourceValues<'a> {
+    lfos: [f32; NUM_LFOS],
+    engine: &'a Engine,
+}
+
+impl SourceValues<'_> {
+    fn get(&self, source: ModulationSource) -> f32 {
+        match source {
+            ModulationSource::Lfo(idx) => self.lfos.get(idx).copied().unwrap_or(0.0),
+            ModulationSource::EnvelopeFollower => self.engine.envelope,
+            ModulationSource::Random => self.engine.random_value,
+            ModulationSource::Velocity => self.engine.velocity,
+            ModulationSource::PitchBend => self.engine.pitch_bend,
+            ModulationSource::ChannelPressure => self.engine.channel_pressure,
+            ModulationSource::MidiCc(cc) => self
+                .engine
+                .midi_ccs
+                .get(cc as usize)
+                .copied()
+                .unwrap_or(0.0),
+        }
+    }
+}
+
+impl Engine {
+    fn handle_events<'a, S: 'a>(&mut self, events: impl IntoIterator<Item = &'a NoteEvent<S>>) {
+        for event in events {
+            match *event {
+                NoteEvent::NoteOn { velocity, .. } => self.velocity = velocity,
+                NoteEvent::MidiPitchBend { value, .. } => self.pitch_bend = (value * 2.0) - 1.0,
+                NoteEvent::MidiChannelPressure { pressure, .. } => self.channel_pressure = pressure,
+                NoteEvent::MidiCC { cc, value, .. } => {
+                    if let Some(cc_value) = self.midi_ccs.get_mut(cc as usize) {
+                        *cc_value = value;
+                    }
+                }
+                _ => (),
+            }
+        }
+    }
+
+    fn follow_envelope(
+        &mut self,
+        buffer: &Buffer,
+        settings: &EnvelopeFollowerSettings,
+        sample_rate: f32,
+    ) {
+        let peak = buffer
+            .as_slice_immutable()
+            .iter()
+            .flat_map(|channel| channel.iter())
+            .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+
+        // This is a one pole filter that's only evaluated once per block
+        let time_ms = if peak > self.envelope {
+            settings.attack_ms
+        } else {
+            settings.release_ms
+        };
+        let block_ms = buffer.samples() as f32 / sample_rate * 1000.0;
+        let t = if time_ms > 0.0 {
+            1.0 - (-block_ms / time_ms).exp()
+        } else {
+            1.0
+        };
+
+        self.envelope += (peak.min(1.0) - self.envelope) * t;
+    }
+
+    fn source_values(&self, config: &ModulationConfig) -> SourceValues<'_> {
+        let mut lfos = [0.0; NUM_LFOS];
+        for ((value, settings), phase) in lfos.iter_mut().zip(&config.lfos).zip(&self.lfo_phases) {
+            *value = settings.shape.value(*phase);
+        }
+
+        SourceValues { lfos, engine: self }
+    }
+
+    /// Advance the LFOs and the random source by `seconds`.
+    fn advance(&mut self, config: &ModulationConfig, seconds: f32) {
+        for (phase, settings) in self.lfo_phases.iter_mut().zip(&config.lfos) {
+            *phase = (*phase + (settings.rate_hz * seconds)).rem_euclid(1.0);
+        }
+
+        self.random_phase += config.random_rate_hz * seconds;
+        if self.random_phase >= 1.0 {
+            self.random_phase = self.random_phase.rem_euclid(1.0);
+
+            // xorshift32
+            self.rng_state ^= self.rng_state << 13;
+            self.rng_state ^= self.rng_state >> 17;
+            self.rng_state ^= self.rng_state << 5;
+            self.random_value = ((self.rng_state as f32 / u32::MAX as f32) * 2.0) - 1.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::{FloatParam, FloatRange, Param};
+
+    #[test]
+    fn test_midi_cc_routing() {
+        let param = FloatParam::new("Cutoff", 0.5, FloatRange::Linear { min: 0.0, max: 1.0 });
+        let params_getter = |id: &str| (id == "cutoff").then(|| param.as_ptr());
+
+        let matrix = ModulationMatrix::default();
+        matrix.add_routing(ModulationRouting {
+            source: ModulationSource::MidiCc(1),
+            target: String::from("cutoff"),
+            depth: 0.25,
+        });
+
+        let mut channel = vec![0.0f32; 32];
+        let mut buffer = Buffer::default();
+        unsafe {
+            buffer.set_slices(32, |slices| *slices = vec![channel.as_mut_slice()]);
+        }
+        let events: [NoteEvent<()>; 1] = [NoteEvent::MidiCC {
+            timing: 0,
+            channel: 0,
+            cc: 1,
+            value: 1.0,
+        }];
+        unsafe { matrix.process_block(&buffer, &events, 44100.0, params_getter) };
+        approx::assert_relative_eq!(param.value(), 0.75);
+
+        // Removing the routing resets the modulation
+        matrix.remove_routing(0);
+        unsafe { matrix.process_block::<()>(&buffer, &[], 44100.0, params_getter) };
+        approx::assert_relative_eq!(param.value(), 0.5);
+    }
+}
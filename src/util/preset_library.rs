@@ -0,0 +1,366 @@
+//! A directory of preset files that can be scanned, searched, and tagged. This is the model behind
+//! the preset browser widgets in NIH-plug's GUI adapters.
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use crate::wrapper::state::{self, PluginState};
+
+/// The file in a preset library's directory that stores the tags for each preset, keyed by the
+/// preset's file name.
+const TAGS_FILE_NAME: &str = "tags.json";
+
+/// The file format used for a [`PresetLibrary`]'s presets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PresetFormat {
+    /// `.nihpreset` files in the format written by
+    /// [`serialize_clap_preset()`][crate::wrapper::state::serialize_clap_preset()].
+    Clap,
+    /// `.vstpreset` files for the plugin with this class ID. These can also be loaded through the
+    /// host's preset browser. See [`PresetFormat::vst3()`].
+    Vst3 { class_id: [u8; 16] },
+}
+
+/// A preset file found in a [`PresetLibrary`]'s directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PresetEntry {
+    /// The preset's name. This is the file name without the extension.
+    pub name: String,
+    /// The absolute path to the preset file.
+    pub path: PathBuf,
+    /// The tags assigned to this preset, in alphabetical order.
+    pub tags: Vec<String>,
+}
+
+/// A directory containing a plugin's presets. The directory is scanned on a background thread, so
+/// creating a library and calling [`rescan()`][Self::rescan()] never blocks the GUI. The scanned
+/// presets can then be searched and filtered by their tags. Tags are stored in a `tags.json` file
+/// in the same directory.
+///
+/// Presets are loaded and saved as [`PluginState`] objects, which can be passed to
+/// [`GuiContext::set_state()`][crate::prelude::GuiContext::set_state()] and obtained from
+/// [`GuiContext::get_state()`][crate::prelude::GuiContext::get_state()].
+#[derive(Debug)]
+pub struct PresetLibrary {
+    directory: PathBuf,
+    format: PresetFormat,
+
+    /// The presets found during the last completed scan, sorted by name.
+    presets: Arc<Mutex<Vec<PresetEntry>>>,
+    /// Incremented whenever a new scan is started. A scan's results are only stored if no newer
+    /// scan has been started in the meantime.
+    scan_generation: Arc<AtomicU32>,
+    /// The number of scans that are still running.
+    pending_scans: Arc<AtomicU32>,
+}
+
+impl PresetFormat {
+    /// The `.vstpreset` format for a VST3 plugin.
+    #[cfg(feature = "vst3")]
+    pub fn vst3<P: crate::prelude::Vst3Plugin>() -> Self {
+        Self::Vst3 {
+            class_id: P::VST3_CLASS_ID,
+        }
+    }
+
+    /// The file extension used for presets in this format, without the leading period.
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PresetFormat::Clap => "nihpreset",
+            PresetFormat::Vst3 { .. } => "vstpreset",
+        }
+    }
+
+    fn serialize(&self, state: &PluginState) -> Result<Vec<u8>> {
+        match self {
+            PresetFormat::Clap => state::serialize_clap_preset(state),
+            PresetFormat::Vst3 { class_id } => state::write_vst3_preset(*class_id, state),
+        }
+    }
+
+    fn deserialize(&self, preset: &[u8]) -> Result<PluginState> {
+        match self {
+            PresetFormat::Clap => state::deserialize_clap_preset(preset),
+            PresetFormat::Vst3 { class_id } => state::read_vst3_preset(*class_id, preset),
+        }
+    }
+}
+
+impl PresetEntry {
+    /// Whether this preset's name contains `query`, ignoring case, and whether the preset has all
+    /// of the tags in `tags`.
+    pub fn matches(&self, query: &str, tags: &[String]) -> bool {
+        self.name.to_lowercase().contains(&query.to_lowercase())
+            && tags.iter().all(|tag| self.tags.contains(tag))
+    }
+}
+
+impl PresetLibrary {
+    /// Create a preset library for a directory and start scanning it in the background. The
+    /// directory is created when the first preset is saved.
+    pub fn new(directory: impl Into<PathBuf>, format: PresetFormat) -> Self {
+        let library = Self {
+            directory: directory.into(),
+            format,
+
+            presets: Arc::new(Mutex::new(Vec::new())),
+            scan_generation: Arc::new(AtomicU32::new(0)),
+            pending_scans: Arc::new(AtomicU32::new(0)),
+        };
+        library.rescan();
+
+        library
+    }
+
+    /// The directory containing the presets.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The format the presets are stored in.
+    pub fn format(&self) -> PresetFormat {
+        self.format
+    }
+
+    /// Scan the directory for presets again on a background thread. This is done automatically
+    /// after saving, deleting, or tagging a preset through this object, but it needs to be called
+    /// manually to pick up other changes to the directory.
+    pub fn rescan(&self) {
+        let generation = self.scan_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        self.pending_scans.fetch_add(1, Ordering::SeqCst);
+
+        let directory = self.directory.clone();
+        let extension = self.format.extension();
+        let presets = self.presets.clone();
+        let scan_generation = self.scan_generation.clone();
+        let pending_scans = self.pending_scans.clone();
+        let spawn_result = std::thread::Builder::new()
+            .name(String::from("preset-scan"))
+            .spawn(move || {
+                let scanned_presets = scan_directory(&directory, extension);
+
+                let mut presets = presets.lock();
+                if scan_generation.load(Ordering::SeqCst) == generation {
+                    *presets = scanned_presets;
+                }
+                drop(presets);
+
+                pending_scans.fetch_sub(1, Ordering::SeqCst);
+            });
+
+        if let Err(err) = spawn_result {
+            nih_debug_assert_failure!("Could not spawn the preset scanning thread: {}", err);
+            self.pending_scans.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    /// Scan the directory for presets on the calling thread and return the results. These are also
+    /// stored so they're returned from [`presets()`][Self::presets()]. Background scans that are
+    /// still running will be discarded.
+    pub(crate) fn scan_now(&self) -> Vec<PresetEntry> {
+        self.scan_generation.fetch_add(1, Ordering::SeqCst);
+        let presets = scan_directory(&self.directory, self.format.extension());
+        *self.presets.lock() = presets.clone();
+
+        presets
+    }
+
+    /// Whether the directory is currently being scanned. GUIs can use this to show a loading
+    /// indicator, and to keep redrawing until the scan has finished.
+    pub fn is_scanning(&self) -> bool {
+        self.pending_scans.load(Ordering::SeqCst) > 0
+    }
+
+    /// All presets found during the last scan, sorted by name.
+    pub fn presets(&self) -> Vec<PresetEntry> {
+        self.presets.lock().clone()
+    }
+
+    /// The presets whose names contain `query` and that have all of the tags in `tags`. See
+    /// [`PresetEntry::matches()`].
+    pub fn search(&self, query: &str, tags: &[String]) -> Vec<PresetEntry> {
+        self.presets
+            .lock()
+            .iter()
+            .filter(|preset| preset.matches(query, tags))
+            .cloned()
+            .collect()
+    }
+
+    /// All tags used by at least one preset, in alphabetical order.
+    pub fn tags(&self) -> Vec<String> {
+        let tags: BTreeSet<String> = self
+            .presets
+            .lock()
+            .iter()
+            .flat_map(|preset| preset.tags.iter().cloned())
+            .collect();
+
+        tags.into_iter().collect()
+    }
+
+    /// Read a preset's state. Pass the state to
+    /// [`GuiContext::set_state()`][crate::prelude::GuiContext::set_state()] to load it.
+    pub fn load(&self, preset: &PresetEntry) -> Result<PluginState> {
+        let data = std::fs::read(&preset.path)
+            .with_context(|| format!("Could not read '{}'", preset.path.display()))?;
+
+        self.format.deserialize(&data)
+    }
+
+    /// Save a plugin's state as a preset with a name and a list of tags. An existing preset with
+    /// the same name is overwritten. The name may not be empty or contain path separators.
+    pub fn save(&self, name: &str, tags: &[String], state: &PluginState) -> Result<PresetEntry> {
+        let name = name.trim();
+        anyhow::ensure!(!name.is_empty(), "The preset name cannot be empty");
+        anyhow::ensure!(
+            !name.contains(['/', '\\']) && name != "." && name != "..",
+            "The preset name '{name}' is not a valid file name"
+        );
+
+        let data = self.format.serialize(state)?;
+        std::fs::create_dir_all(&self.directory)
+            .with_context(|| format!("Could not create '{}'", self.directory.display()))?;
+        let path = self
+            .directory
+            .join(format!("{name}.{}", self.format.extension()));
+        std::fs::write(&path, data)
+            .with_context(|| format!("Could not write '{}'", path.display()))?;
+
+        let preset = PresetEntry {
+            name: name.to_owned(),
+            path,
+            tags: normalize_tags(tags),
+        };
+        self.write_tags(&preset, &preset.tags)?;
+        self.rescan();
+
+        Ok(preset)
+    }
+
+    /// Replace a preset's tags.
+    pub fn set_tags(&self, preset: &PresetEntry, tags: &[String]) -> Result<()> {
+        self.write_tags(preset, &normalize_tags(tags))?;
+        self.rescan();
+
+        Ok(())
+    }
+
+    /// Delete a preset and its tags.
+    pub fn delete(&self, preset: &PresetEntry) -> Result<()> {
+        std::fs::remove_file(&preset.path)
+            .with_context(|| format!("Could not delete '{}'", preset.path.display()))?;
+        self.write_tags(preset, &[])?;
+        self.rescan();
+
+        Ok(())
+    }
+
+    fn write_tags(&self, preset: &PresetEntry, tags: &[String]) -> Result<()> {
+        let file_name = preset_file_name(&preset.path)?;
+        let mut all_tags = read_tags(&self.directory);
+        if tags.is_empty() {
+            all_tags.remove(&file_name);
+        } else {
+            all_tags.insert(file_name, tags.to_vec());
+        }
+
+        let tags_path = self.directory.join(TAGS_FILE_NAME);
+        let json = serde_json::to_vec_pretty(&all_tags).context("Could not format as JSON")?;
+        std::fs::write(&tags_path, json)
+            .with_context(|| format!("Could not write '{}'", tags_path.display()))
+    }
+}
+
+/// Find all presets with the specified extension in a directory. Returns an empty list if the
+/// directory does not exist.
+fn scan_directory(directory: &Path, extension: &str) -> Vec<PresetEntry> {
+    let entries = match std::fs::read_dir(directory) {
+        Ok(entries) => entries,
+        Err(err) => {
+            nih_trace!("Could not scan '{}': {}", directory.display(), err);
+            return Vec::new();
+        }
+    };
+
+    let mut all_tags = read_tags(directory);
+    let mut presets: Vec<PresetEntry> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let has_extension = path
+                .extension()
+                .map_or(false, |e| e.eq_ignore_ascii_case(extension));
+            if !has_extension || !path.is_file() {
+                return None;
+            }
+
+            let name = path.file_stem()?.to_string_lossy().into_owned();
+            let tags = preset_file_name(&path)
+                .ok()
+                .and_then(|file_name| all_tags.remove(&file_name))
+                .unwrap_or_default();
+
+            Some(PresetEntry { name, path, tags })
+        })
+        .collect();
+    presets.sort_by_key(|preset| preset.name.to_lowercase());
+
+    presets
+}
+
+/// Read the tags file in a preset directory. Returns an empty map if it does not exist or if it
+/// cannot be parsed.
+fn read_tags(directory: &Path) -> BTreeMap<String, Vec<String>> {
+    std::fs::read(directory.join(TAGS_FILE_NAME))
+        .ok()
+        .and_then(|json| serde_json::from_slice(&json).ok())
+        .unwrap_or_default()
+}
+
+fn preset_file_name(path: &Path) -> Result<String> {
+    path.file_name()
+        .map(|file_name| file_name.to_string_lossy().into_owned())
+        .with_context(|| format!("'{}' is not a file", path.display()))
+}
+
+/// Trim tags, and remove empty and duplicate tags.
+fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let tags: BTreeSet<String> = tags
+        .iter()
+        .map(|tag| tag.trim())
+        .filter(|tag| !tag.is_empty())
+        .map(String::from)
+        .collect();
+
+    tags.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches() {
+        let preset = PresetEntry {
+            name: String::from("Warm Pad"),
+            path: PathBuf::from("Warm Pad.nihpreset"),
+            tags: vec![String::from("pad"), String::from("warm")],
+        };
+
+        assert!(preset.matches("", &[]));
+        assert!(preset.matches("warm p", &[]));
+        assert!(preset.matches("PAD", &[String::from("pad")]));
+        assert!(!preset.matches("lead", &[]));
+        assert!(!preset.matches("", &[String::from("pad"), String::from("bass")]));
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = [" pad", "warm", "", "pad "].map(String::from);
+        assert_eq!(normalize_tags(&tags), ["pad", "warm"]);
+    }
+}
@@ -0,0 +1,146 @@
+//! The lock-free queue backing the [`nih_log_rt!()`][crate::nih_log_rt!()] macro. Messages are
+//! formatted into fixed size records on the calling thread, and those records are written to the
+//! regular logger by the plugin's background thread. The background thread is only woken up when
+//! there are new messages to write.
+
+use crossbeam::channel;
+use crossbeam::queue::ArrayQueue;
+use std::fmt::{self, Write};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// The maximum length of a single message in bytes. Longer messages are truncated.
+pub const MAX_MESSAGE_LEN: usize = 256;
+/// The number of messages that can be queued up before the background thread writes them to the
+/// logger. Messages logged while the queue is full are dropped and counted.
+const QUEUE_CAPACITY: usize = 1024;
+
+/// A formatted message, stored inline so it can be sent to the background thread without
+/// allocating.
+struct Record {
+    len: usize,
+    message: [u8; MAX_MESSAGE_LEN],
+}
+
+/// Formats a message into a [`Record`], truncating it on a character boundary if it does not fit.
+struct RecordWriter<'a> {
+    record: &'a mut Record,
+}
+
+lazy_static::lazy_static! {
+    static ref QUEUE: ArrayQueue<Record> = ArrayQueue::new(QUEUE_CAPACITY);
+    /// Wakes up a background thread so it can drain the queue. Every background thread listens on
+    /// a clone of the receiver, and whichever thread receives the message drains the queue.
+    static ref WAKEUP: (channel::Sender<()>, channel::Receiver<()>) = channel::bounded(1);
+}
+
+/// The number of messages that have been dropped since the queue was last drained.
+static DROPPED_MESSAGES: AtomicUsize = AtomicUsize::new(0);
+/// Set when a message is logged, and cleared when the queue is drained. Only the first message
+/// logged after the queue was drained needs to wake up the background thread.
+static DRAIN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+impl Record {
+    fn new(args: fmt::Arguments) -> Self {
+        let mut record = Record {
+            len: 0,
+            message: [0; MAX_MESSAGE_LEN],
+        };
+        // This only fails when the message has been truncated
+        let _ = RecordWriter {
+            record: &mut record,
+        }
+        .write_fmt(args);
+
+        record
+    }
+
+    fn as_str(&self) -> &str {
+        // The writer only ever copies whole characters
+        std::str::from_utf8(&self.message[..self.len]).unwrap_or_default()
+    }
+}
+
+impl Write for RecordWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let remaining = MAX_MESSAGE_LEN - self.record.len;
+        let (s, truncated) = if s.len() > remaining {
+            let mut len = remaining;
+            while !s.is_char_boundary(len) {
+                len -= 1;
+            }
+
+            (&s[..len], true)
+        } else {
+            (s, false)
+        };
+
+        self.record.message[self.record.len..self.record.len + s.len()]
+            .copy_from_slice(s.as_bytes());
+        self.record.len += s.len();
+
+        if truncated {
+            Err(fmt::Error)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Queue a message for the logger. This is called by [`nih_log_rt!()`][crate::nih_log_rt!()], use
+/// that macro instead. This does not allocate or lock, unless one of the formatted values allocates
+/// in its `Display` or `Debug` implementation.
+pub fn log(args: fmt::Arguments) {
+    if QUEUE.push(Record::new(args)).is_err() {
+        DROPPED_MESSAGES.fetch_add(1, Ordering::Relaxed);
+    }
+
+    if !DRAIN_REQUESTED.swap(true, Ordering::AcqRel) {
+        // If the channel is full then a wakeup is already pending
+        let _ = WAKEUP.0.try_send(());
+    }
+}
+
+/// Allocate the queue ahead of time so the first call to [`log()`] does not need to allocate.
+/// Called when a background thread is spawned.
+pub(crate) fn initialize() {
+    lazy_static::initialize(&QUEUE);
+    lazy_static::initialize(&WAKEUP);
+}
+
+/// A receiver that gets a message when new messages have been logged. The background thread should
+/// call [`drain()`] after receiving from this.
+pub(crate) fn wakeup_receiver() -> channel::Receiver<()> {
+    WAKEUP.1.clone()
+}
+
+/// Write all queued messages to the logger, followed by a warning if any messages had to be dropped
+/// because the queue was full. Called from the background thread when it is woken up through
+/// [`wakeup_receiver()`].
+pub(crate) fn drain() {
+    // This is cleared first so messages logged while draining will wake up the thread again
+    DRAIN_REQUESTED.store(false, Ordering::Release);
+    while let Some(record) = QUEUE.pop() {
+        nih_log!("{}", record.as_str());
+    }
+
+    let dropped_messages = DROPPED_MESSAGES.swap(0, Ordering::Relaxed);
+    if dropped_messages > 0 {
+        nih_warn!("Dropped {dropped_messages} realtime log messages because the queue was full");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_long_messages() {
+        let record = Record::new(format_args!("{} samples", 512));
+        assert_eq!(record.as_str(), "512 samples");
+
+        // The three byte characters don't fit evenly, so the last one is dropped entirely
+        let long_message = "€".repeat(MAX_MESSAGE_LEN);
+        let record = Record::new(format_args!("{long_message}"));
+        assert_eq!(record.as_str(), "€".repeat(MAX_MESSAGE_LEN / 3));
+    }
+}
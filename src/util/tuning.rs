@@ -0,0 +1,327 @@
+//! Microtonal tunings loaded from Scala `.scl` scale files and `.kbm` keyboard mapping files.
+
+use anyhow::{Context, Result};
+use std::path::Path;
+
+lazy_static::lazy_static! {
+    /// A 12-TET tuning table with A4 = 440 Hz, used as a fallback when the host does not provide a
+    /// tuning.
+    static ref EQUAL_TEMPERAMENT: Tuning = Tuning::default();
+}
+
+/// A frequency for each of the 128 MIDI notes. These can be loaded from the Scala file formats,
+/// see <https://www.huygens-fokker.org/scala/scl_format.html> and
+/// <https://www.huygens-fokker.org/scala/help.htm#mappings>. The default is 12-TET with A4 = 440
+/// Hz.
+///
+/// CLAP hosts can also provide tunings directly using the tuning extension. Those can be queried
+/// using [`ProcessContext::note_frequency()`][crate::prelude::ProcessContext::note_frequency()].
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tuning {
+    /// The frequency for each MIDI note, or `None` if the keyboard mapping leaves the note
+    /// unmapped.
+    frequencies: [Option<f32>; 128],
+}
+
+/// The parsed contents of a `.scl` file.
+struct Scale {
+    /// The pitches of the scale's degrees in cents, not including the implicit 1/1 unison. The
+    /// last pitch is the scale's period, usually an octave.
+    pitches: Vec<f64>,
+}
+
+/// The parsed contents of a `.kbm` file.
+struct KeyboardMapping {
+    first_note: i32,
+    last_note: i32,
+    middle_note: i32,
+    reference_note: i32,
+    reference_frequency: f64,
+    /// The scale degree that each repetition of the mapping starts on.
+    octave_degree: i32,
+    /// The scale degree for each key in the mapping, starting at the middle note. `None` means that
+    /// the key is unmapped. An empty mapping maps every key to the next scale degree.
+    mapping: Vec<Option<i32>>,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        let mut frequencies = [None; 128];
+        for (note, frequency) in frequencies.iter_mut().enumerate() {
+            *frequency = Some(super::midi_note_to_freq(note as u8));
+        }
+
+        Self { frequencies }
+    }
+}
+
+impl Tuning {
+    /// Parse a tuning from the contents of a `.scl` file and optionally a `.kbm` file. Without a
+    /// keyboard mapping, the scale's first degree is mapped to middle C (note 60), and A4 (note 69)
+    /// is tuned to 440 Hz.
+    pub fn from_scala(scl: &str, kbm: Option<&str>) -> Result<Self> {
+        let scale = Scale::parse(scl).context("Could not parse the .scl file")?;
+        let keyboard_mapping = match kbm {
+            Some(kbm) => KeyboardMapping::parse(kbm).context("Could not parse the .kbm file")?,
+            None => KeyboardMapping::linear(scale.pitches.len() as i32),
+        };
+
+        let reference_cents = keyboard_mapping
+            .scale_degree(keyboard_mapping.reference_note)
+            .map(|degree| scale.cents(degree))
+            .context("The keyboard mapping's reference note is not mapped")?;
+
+        let mut frequencies = [None; 128];
+        for (note, frequency) in frequencies.iter_mut().enumerate() {
+            *frequency = keyboard_mapping
+                .scale_degree(note as i32)
+                .map(|degree| {
+                    let cents = scale.cents(degree) - reference_cents;
+                    keyboard_mapping.reference_frequency * 2.0f64.powf(cents / 1200.0)
+                })
+                .map(|frequency| frequency as f32);
+        }
+
+        Ok(Self { frequencies })
+    }
+
+    /// Load a tuning from a `.scl` file and optionally a `.kbm` file. See
+    /// [`from_scala()`][Self::from_scala()].
+    pub fn load_scala(scl_path: impl AsRef<Path>, kbm_path: Option<&Path>) -> Result<Self> {
+        let scl_path = scl_path.as_ref();
+        let scl = std::fs::read_to_string(scl_path)
+            .with_context(|| format!("Could not read '{}'", scl_path.display()))?;
+        let kbm = kbm_path
+            .map(|kbm_path| {
+                std::fs::read_to_string(kbm_path)
+                    .with_context(|| format!("Could not read '{}'", kbm_path.display()))
+            })
+            .transpose()?;
+
+        Self::from_scala(&scl, kbm.as_deref())
+    }
+
+    /// The 12-TET tuning with A4 = 440 Hz. This is a shared table, so unlike
+    /// [`Tuning::default()`] this does not need to compute the frequencies again.
+    pub fn equal_temperament() -> &'static Self {
+        &EQUAL_TEMPERAMENT
+    }
+
+    /// Get the frequency for a MIDI note, or `None` if the note is not mapped and should not be
+    /// played. Notes above 127 are never mapped.
+    pub fn frequency(&self, note: u8) -> Option<f32> {
+        self.frequencies.get(note as usize).copied().flatten()
+    }
+}
+
+impl Scale {
+    fn parse(scl: &str) -> Result<Self> {
+        // The first line is the scale's description, which may be empty
+        let mut lines = data_lines(scl).skip(1);
+        let num_pitches: usize = first_word(lines.next().context("Missing the number of notes")?)
+            .parse()
+            .context("Invalid number of notes")?;
+        anyhow::ensure!(num_pitches > 0, "The scale does not contain any notes");
+
+        let pitches = lines
+            .take(num_pitches)
+            .map(|line| parse_pitch(first_word(line)))
+            .collect::<Result<Vec<_>>>()?;
+        anyhow::ensure!(
+            pitches.len() == num_pitches,
+            "Expected {num_pitches} notes, found {}",
+            pitches.len()
+        );
+
+        Ok(Self { pitches })
+    }
+
+    /// The pitch of a scale degree in cents relative to the first degree. Degrees outside of the
+    /// scale are repeated at the scale's period.
+    fn cents(&self, degree: i32) -> f64 {
+        let num_pitches = self.pitches.len() as i32;
+        let period = self.pitches[self.pitches.len() - 1];
+        let repetition = degree.div_euclid(num_pitches);
+        let degree_cents = match degree.rem_euclid(num_pitches) {
+            0 => 0.0,
+            degree => self.pitches[degree as usize - 1],
+        };
+
+        (repetition as f64 * period) + degree_cents
+    }
+}
+
+impl KeyboardMapping {
+    /// The mapping used when no `.kbm` file is provided.
+    fn linear(num_pitches: i32) -> Self {
+        Self {
+            first_note: 0,
+            last_note: 127,
+            middle_note: 60,
+            reference_note: 69,
+            reference_frequency: 440.0,
+            octave_degree: num_pitches,
+            mapping: Vec::new(),
+        }
+    }
+
+    fn parse(kbm: &str) -> Result<Self> {
+        let mut lines = data_lines(kbm).map(first_word);
+        let mut next_int = |name: &str| -> Result<i32> {
+            lines
+                .next()
+                .with_context(|| format!("Missing the {name}"))?
+                .parse()
+                .with_context(|| format!("Invalid {name}"))
+        };
+
+        let map_size = next_int("map size")?;
+        let first_note = next_int("first note")?;
+        let last_note = next_int("last note")?;
+        let middle_note = next_int("middle note")?;
+        let reference_note = next_int("reference note")?;
+        let reference_frequency: f64 = lines
+            .next()
+            .context("Missing the reference frequency")?
+            .parse()
+            .context("Invalid reference frequency")?;
+        let octave_degree: i32 = lines
+            .next()
+            .context("Missing the formal octave degree")?
+            .parse()
+            .context("Invalid formal octave degree")?;
+
+        // Missing entries at the end of the mapping are unmapped
+        let mut mapping = Vec::with_capacity(map_size.max(0) as usize);
+        for _ in 0..map_size {
+            mapping.push(match lines.next() {
+                Some("x") | Some("X") | None => None,
+                Some(degree) => Some(degree.parse().context("Invalid mapping entry")?),
+            });
+        }
+
+        anyhow::ensure!(
+            reference_frequency > 0.0,
+            "The reference frequency must be positive"
+        );
+
+        Ok(Self {
+            first_note,
+            last_note,
+            middle_note,
+            reference_note,
+            reference_frequency,
+            octave_degree,
+            mapping,
+        })
+    }
+
+    /// The scale degree a note is mapped to, or `None` if the note is unmapped.
+    fn scale_degree(&self, note: i32) -> Option<i32> {
+        if note < self.first_note || note > self.last_note {
+            return None;
+        }
+
+        let offset = note - self.middle_note;
+        if self.mapping.is_empty() {
+            return Some(offset);
+        }
+
+        let map_size = self.mapping.len() as i32;
+        let repetition = offset.div_euclid(map_size);
+        self.mapping[offset.rem_euclid(map_size) as usize]
+            .map(|degree| (repetition * self.octave_degree) + degree)
+    }
+}
+
+/// The lines in a Scala file that are not comments.
+fn data_lines(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .filter(|line| !line.starts_with('!'))
+        .map(str::trim)
+}
+
+/// Anything after the first whitespace character in a value line is ignored.
+fn first_word(line: &str) -> &str {
+    line.split_whitespace().next().unwrap_or("")
+}
+
+/// Parse a pitch in a `.scl` file to cents. Values containing a period are in cents, and other
+/// values are ratios like `3/2` or `2`.
+fn parse_pitch(pitch: &str) -> Result<f64> {
+    if pitch.contains('.') {
+        return pitch
+            .parse()
+            .with_context(|| format!("Invalid pitch '{pitch}'"));
+    }
+
+    let (numerator, denominator) = pitch.split_once('/').unwrap_or((pitch, "1"));
+    let numerator: f64 = numerator
+        .parse()
+        .with_context(|| format!("Invalid pitch '{pitch}'"))?;
+    let denominator: f64 = denominator
+        .parse()
+        .with_context(|| format!("Invalid pitch '{pitch}'"))?;
+    anyhow::ensure!(
+        numerator > 0.0 && denominator > 0.0,
+        "Invalid pitch '{pitch}'"
+    );
+
+    Ok(1200.0 * (numerator / denominator).log2())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TWELVE_TET: &str = "! 12-tet.scl
+!
+12 tone equal temperament
+ 12
+!
+ 100.0
+ 200.
+ 300.0 cents
+ 400.0
+ 500.0
+ 600.0
+ 700.0
+ 800.0
+ 900.0
+ 1000.0
+ 1100.0
+ 2/1
+";
+
+    #[test]
+    fn test_twelve_tet_matches_default() {
+        let tuning = Tuning::from_scala(TWELVE_TET, None).unwrap();
+        for note in 0..128 {
+            approx::assert_relative_eq!(
+                tuning.frequency(note).unwrap(),
+                Tuning::equal_temperament().frequency(note).unwrap(),
+                epsilon = 1e-3
+            );
+        }
+    }
+
+    #[test]
+    fn test_keyboard_mapping() {
+        let scl = "Just major pentatonic\n5\n9/8\n5/4\n3/2\n5/3\n2/1\n";
+        // Maps the five degrees to the white keys C, D, E, G, and A, with middle C at 261.63 Hz
+        let kbm =
+            "! pentatonic.kbm\n12\n0\n127\n60\n60\n261.63\n5\n0\nx\n1\nx\n2\nx\nx\n3\nx\n4\nx\nx\n";
+        let tuning = Tuning::from_scala(scl, Some(kbm)).unwrap();
+
+        approx::assert_relative_eq!(tuning.frequency(60).unwrap(), 261.63, epsilon = 1e-3);
+        assert_eq!(tuning.frequency(61), None);
+        approx::assert_relative_eq!(tuning.frequency(67).unwrap(), 261.63 * 1.5, epsilon = 1e-3);
+        approx::assert_relative_eq!(tuning.frequency(72).unwrap(), 261.63 * 2.0, epsilon = 1e-3);
+        approx::assert_relative_eq!(
+            tuning.frequency(57).unwrap(),
+            261.63 * 5.0 / 6.0,
+            epsilon = 1e-3
+        );
+    }
+}
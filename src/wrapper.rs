@@ -1,7 +1,13 @@
 //! Wrappers for different plugin types. Each wrapper has an entry point macro that you can pass the
 //! name of a type that implements `Plugin` to. The macro will handle the rest.
 
+#[cfg(feature = "ara")]
+pub mod ara;
+#[cfg(feature = "au")]
+pub mod au;
 pub mod clap;
+#[cfg(feature = "lv2")]
+pub mod lv2;
 pub mod state;
 pub(crate) mod util;
 
@@ -9,6 +15,8 @@ pub(crate) mod util;
 pub mod standalone;
 #[cfg(feature = "vst3")]
 pub mod vst3;
+#[cfg(feature = "wam")]
+pub mod wam;
 
 // This is used by the wrappers.
 pub use util::setup_logger;
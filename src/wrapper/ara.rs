@@ -0,0 +1,86 @@
+//! ARA 2 support. ARA is not a plugin format on its own. Instead, the ARA factory is exposed
+//! alongside a VST3 or CLAP plugin, and the host then binds plugin instances to the ARA document
+//! controllers it creates through that factory. See [`AraPlugin`][crate::prelude::AraPlugin] for
+//! more information.
+//!
+//! The wrapper has a couple limitations:
+//!
+//! - Plugins cannot export content like notes or tempo to the host, and the host's content readers
+//!   for musical contexts are not exposed to the plugin.
+//! - Musical contexts and region sequences are not part of the [`AraDocument`].
+//! - The host's selection and hidden region sequences are ignored, so the plugin's editor cannot
+//!   follow what the user selected in the host.
+//! - Audio is always read from the host as 32-bit floating point samples.
+
+use parking_lot::Mutex;
+use std::os::raw::c_void;
+use std::ptr;
+
+mod util;
+
+mod archive;
+mod binding;
+mod controller;
+mod document;
+mod factory;
+mod host;
+mod sys;
+
+pub use self::binding::{AraBinding, AraRoles};
+pub use self::document::{
+    AraAudioModification, AraAudioSource, AraDocument, AraObjectId, AraPlaybackRegion,
+    AraPlaybackTransformations,
+};
+pub use self::factory::AraFactory;
+pub use self::host::{AraAnalysisProgress, AraAudioReader, AraHost};
+
+pub(crate) use self::binding::PlugInExtension;
+
+use crate::prelude::Plugin;
+
+/// Bind a plugin instance to an ARA document controller. This is shared between the VST3 and CLAP
+/// wrappers. The resulting plugin extension is stored in `extension` so it lives as long as the
+/// plugin instance. Returns a pointer to the `ARAPlugInExtensionInstance`, or a null pointer if the
+/// plugin does not support ARA or if it has already been bound.
+///
+/// # Safety
+///
+/// `controller_ref` must have been created by the plugin's ARA factory.
+pub(crate) unsafe fn bind_to_document_controller<P: Plugin>(
+    plugin: &Mutex<P>,
+    extension: &Mutex<Option<PlugInExtension>>,
+    controller_ref: *mut c_void,
+    known_roles: u32,
+    assigned_roles: u32,
+) -> *const c_void {
+    let Some(factory) = P::ara_factory() else {
+        nih_debug_assert_failure!("The host tried to bind a plugin without ARA support");
+        return ptr::null();
+    };
+
+    let mut extension = extension.lock();
+    if extension.is_some() {
+        nih_debug_assert_failure!("The host tried to bind a plugin instance twice");
+        return ptr::null();
+    }
+
+    let mut plugin = plugin.lock();
+    match factory.bind(&mut *plugin, controller_ref, known_roles, assigned_roles) {
+        Some(new_extension) => {
+            let instance = new_extension.instance() as *const c_void;
+            *extension = Some(new_extension);
+
+            instance
+        }
+        None => ptr::null(),
+    }
+}
+
+/// Get a pointer to the plugin's `ARAFactory`, or a null pointer if the plugin does not support
+/// ARA.
+pub(crate) fn ara_factory_ptr<P: Plugin>() -> *const c_void {
+    match P::ara_factory() {
+        Some(factory) => factory.ara_factory() as *const c_void,
+        None => ptr::null(),
+    }
+}
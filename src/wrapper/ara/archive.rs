@@ -0,0 +1,90 @@
+//! The format used to store the plugin's per-audio source data in the host's ARA archives. An
+//! archive starts with a magic number and the number of entries, followed by the entries. Every
+//! entry contains an audio source's persistent ID and the data returned by
+//! [`AraDocumentController::store_audio_source()`][crate::prelude::AraDocumentController::store_audio_source()].
+//! All integers are stored in little endian byte order.
+
+/// The magic number at the start of every archive. The last byte is the format's version.
+const MAGIC: &[u8; 8] = b"NIH-ARA\x01";
+
+/// Encode the persistent ID and data pairs to an archive.
+pub fn encode(entries: &[(String, Vec<u8>)]) -> Vec<u8> {
+    let mut archive = Vec::new();
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+    for (persistent_id, data) in entries {
+        archive.extend_from_slice(&(persistent_id.len() as u32).to_le_bytes());
+        archive.extend_from_slice(persistent_id.as_bytes());
+        archive.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        archive.extend_from_slice(data);
+    }
+
+    archive
+}
+
+/// Decode an archive created with [`encode()`]. Returns `None` if the archive is malformed.
+pub fn decode(archive: &[u8]) -> Option<Vec<(String, Vec<u8>)>> {
+    let mut reader = Reader(archive);
+    if reader.take(MAGIC.len())? != MAGIC {
+        return None;
+    }
+
+    let num_entries = u32::from_le_bytes(reader.take(4)?.try_into().ok()?);
+    let mut entries = Vec::new();
+    for _ in 0..num_entries {
+        let id_len = u32::from_le_bytes(reader.take(4)?.try_into().ok()?) as usize;
+        let persistent_id = String::from_utf8(reader.take(id_len)?.to_vec()).ok()?;
+        let data_len = u64::from_le_bytes(reader.take(8)?.try_into().ok()?) as usize;
+        let data = reader.take(data_len)?.to_vec();
+
+        entries.push((persistent_id, data));
+    }
+
+    if reader.0.is_empty() {
+        Some(entries)
+    } else {
+        None
+    }
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        if len > self.0.len() {
+            return None;
+        }
+
+        let (taken, rest) = self.0.split_at(len);
+        self.0 = rest;
+        Some(taken)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let entries = vec![
+            (String::from("source-1"), vec![1, 2, 3]),
+            (String::from("source-2"), Vec::new()),
+        ];
+
+        assert_eq!(decode(&encode(&entries)), Some(entries));
+    }
+
+    #[test]
+    fn empty_archive() {
+        assert_eq!(decode(&encode(&[])), Some(Vec::new()));
+    }
+
+    #[test]
+    fn truncated_archive() {
+        let archive = encode(&[(String::from("source-1"), vec![1, 2, 3])]);
+
+        assert_eq!(decode(&archive[..archive.len() - 1]), None);
+        assert_eq!(decode(b"not an archive"), None);
+    }
+}
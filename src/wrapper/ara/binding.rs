@@ -0,0 +1,248 @@
+//! Binding plugin instances to document controllers. Once bound, the host assigns playback regions
+//! to the plugin instance through the plugin extension's renderer interfaces.
+
+use parking_lot::{Mutex, MutexGuard};
+use std::any::Any;
+use std::sync::Arc;
+
+use super::controller::{DocumentController, DocumentShared};
+use super::document::{AraDocument, AraObjectId};
+use super::sys::*;
+use crate::prelude::AraPlugin;
+use crate::wrapper::util::check_null_ptr;
+
+bitflags::bitflags! {
+    /// The roles the host assigned to a plugin instance when binding it to a document controller.
+    #[repr(transparent)]
+    #[derive(Default)]
+    pub struct AraRoles: u32 {
+        /// The plugin instance renders the playback regions in
+        /// [`AraBinding::playback_regions()`] as part of the host's regular playback.
+        const PLAYBACK_RENDERER = kARAPlaybackRendererRole;
+        /// The plugin instance renders the playback regions in [`AraBinding::editor_regions()`]
+        /// for previewing edits, for instance while the user drags notes in the editor.
+        const EDITOR_RENDERER = kARAEditorRendererRole;
+        /// The plugin instance's editor is used to edit the document.
+        const EDITOR_VIEW = kARAEditorViewRole;
+    }
+}
+
+/// A plugin instance's connection to an ARA document controller, passed to
+/// [`AraPlugin::ara_bound()`][crate::prelude::AraPlugin::ara_bound()]. This can be cloned and moved
+/// to the plugin's editor.
+///
+/// The document and the document controller are protected by locks that are also used by the
+/// host's main thread. From the audio thread, use the `try_*` variants to avoid blocking. When
+/// holding both locks at the same time, always lock the document first.
+pub struct AraBinding<P: AraPlugin> {
+    shared: Arc<DocumentShared<P>>,
+    state: Arc<BindingState>,
+}
+
+impl<P: AraPlugin> Clone for AraBinding<P> {
+    fn clone(&self) -> Self {
+        Self {
+            shared: self.shared.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+/// The playback regions assigned to a plugin instance. A pointer to this object is used as the
+/// renderer and editor view refs.
+struct BindingState {
+    roles: AraRoles,
+    playback_regions: Mutex<Vec<AraObjectId>>,
+    editor_regions: Mutex<Vec<AraObjectId>>,
+}
+
+/// The plugin extension instance returned to the host when binding a plugin instance. The wrappers
+/// store this on the plugin instance so the pointers passed to the host stay valid.
+pub(crate) struct PlugInExtension {
+    /// Boxed so the instance's address doesn't change when this object is moved.
+    instance: Box<ARAPlugInExtensionInstance>,
+    _state: Arc<BindingState>,
+}
+
+unsafe impl Send for PlugInExtension {}
+
+static PLAYBACK_RENDERER_INTERFACE: ARAPlaybackRendererInterface = ARAPlaybackRendererInterface {
+    struct_size: std::mem::size_of::<ARAPlaybackRendererInterface>(),
+    add_playback_region: Some(add_playback_region),
+    remove_playback_region: Some(remove_playback_region),
+};
+
+static EDITOR_RENDERER_INTERFACE: ARAEditorRendererInterface = ARAEditorRendererInterface {
+    struct_size: std::mem::size_of::<ARAEditorRendererInterface>(),
+    add_playback_region: Some(add_editor_region),
+    remove_playback_region: Some(remove_editor_region),
+    add_region_sequence: Some(region_sequence_noop),
+    remove_region_sequence: Some(region_sequence_noop),
+};
+
+static EDITOR_VIEW_INTERFACE: ARAEditorViewInterface = ARAEditorViewInterface {
+    struct_size: std::mem::size_of::<ARAEditorViewInterface>(),
+    notify_selection: Some(notify_selection),
+    notify_hide_region_sequences: Some(notify_hide_region_sequences),
+};
+
+impl<P: AraPlugin> AraBinding<P> {
+    /// The roles the host assigned to this plugin instance.
+    pub fn roles(&self) -> AraRoles {
+        self.state.roles
+    }
+
+    /// Lock the document.
+    pub fn document(&self) -> MutexGuard<'_, AraDocument> {
+        self.shared.document.lock()
+    }
+
+    /// Try to lock the document without blocking. Returns `None` if the host is currently
+    /// modifying the document.
+    pub fn try_document(&self) -> Option<MutexGuard<'_, AraDocument>> {
+        self.shared.document.try_lock()
+    }
+
+    /// Lock the plugin's document controller.
+    pub fn controller(&self) -> MutexGuard<'_, P::DocumentController> {
+        self.shared.controller.lock()
+    }
+
+    /// Try to lock the plugin's document controller without blocking.
+    pub fn try_controller(&self) -> Option<MutexGuard<'_, P::DocumentController>> {
+        self.shared.controller.try_lock()
+    }
+
+    /// The playback regions this plugin instance should render during playback.
+    pub fn playback_regions(&self) -> MutexGuard<'_, Vec<AraObjectId>> {
+        self.state.playback_regions.lock()
+    }
+
+    /// The playback regions this plugin instance should render while the user previews edits.
+    pub fn editor_regions(&self) -> MutexGuard<'_, Vec<AraObjectId>> {
+        self.state.editor_regions.lock()
+    }
+}
+
+impl PlugInExtension {
+    fn new(state: Arc<BindingState>) -> Self {
+        let state_ref = Arc::as_ptr(&state) as *mut std::os::raw::c_void;
+
+        Self {
+            instance: Box::new(ARAPlugInExtensionInstance {
+                struct_size: std::mem::size_of::<ARAPlugInExtensionInstance>(),
+                playback_renderer_ref: state_ref,
+                playback_renderer_interface: &PLAYBACK_RENDERER_INTERFACE,
+                editor_renderer_ref: state_ref,
+                editor_renderer_interface: &EDITOR_RENDERER_INTERFACE,
+                editor_view_ref: state_ref,
+                editor_view_interface: &EDITOR_VIEW_INTERFACE,
+            }),
+            _state: state,
+        }
+    }
+
+    /// The instance passed to the host.
+    pub fn instance(&self) -> *const ARAPlugInExtensionInstance {
+        &*self.instance
+    }
+}
+
+/// Bind a plugin instance to a document controller. This is stored as a type erased function
+/// pointer on the [`AraFactory`][super::AraFactory]. Returns `None` if `plugin` is not of type `P`.
+///
+/// # Safety
+///
+/// `controller_ref` must have been created by the same plugin type's ARA factory.
+pub(crate) unsafe fn bind_plugin<P: AraPlugin>(
+    plugin: &mut dyn Any,
+    controller_ref: ARADocumentControllerRef,
+    known_roles: ARAPlugInInstanceRoleFlags,
+    assigned_roles: ARAPlugInInstanceRoleFlags,
+) -> Option<PlugInExtension> {
+    let plugin = plugin.downcast_mut::<P>()?;
+    check_null_ptr!(None, controller_ref);
+    let controller = DocumentController::<P>::from_ref(controller_ref);
+
+    // Roles the host doesn't know about are implicitly assigned to the plugin
+    let state = Arc::new(BindingState {
+        roles: AraRoles::from_bits_truncate(assigned_roles | !known_roles),
+        playback_regions: Mutex::new(Vec::new()),
+        editor_regions: Mutex::new(Vec::new()),
+    });
+    plugin.ara_bound(AraBinding {
+        shared: controller.shared.clone(),
+        state: state.clone(),
+    });
+
+    Some(PlugInExtension::new(state))
+}
+
+unsafe extern "C" fn add_playback_region(
+    renderer_ref: ARAPlaybackRendererRef,
+    playback_region_ref: ARAPlaybackRegionRef,
+) {
+    check_null_ptr!((), renderer_ref);
+    let state = &*(renderer_ref as *const BindingState);
+
+    state
+        .playback_regions
+        .lock()
+        .push(AraObjectId::from_ref(playback_region_ref));
+}
+
+unsafe extern "C" fn remove_playback_region(
+    renderer_ref: ARAPlaybackRendererRef,
+    playback_region_ref: ARAPlaybackRegionRef,
+) {
+    check_null_ptr!((), renderer_ref);
+    let state = &*(renderer_ref as *const BindingState);
+
+    let id = AraObjectId::from_ref(playback_region_ref);
+    state.playback_regions.lock().retain(|region| *region != id);
+}
+
+unsafe extern "C" fn add_editor_region(
+    renderer_ref: ARAEditorRendererRef,
+    playback_region_ref: ARAPlaybackRegionRef,
+) {
+    check_null_ptr!((), renderer_ref);
+    let state = &*(renderer_ref as *const BindingState);
+
+    state
+        .editor_regions
+        .lock()
+        .push(AraObjectId::from_ref(playback_region_ref));
+}
+
+unsafe extern "C" fn remove_editor_region(
+    renderer_ref: ARAEditorRendererRef,
+    playback_region_ref: ARAPlaybackRegionRef,
+) {
+    check_null_ptr!((), renderer_ref);
+    let state = &*(renderer_ref as *const BindingState);
+
+    let id = AraObjectId::from_ref(playback_region_ref);
+    state.editor_regions.lock().retain(|region| *region != id);
+}
+
+unsafe extern "C" fn region_sequence_noop(
+    _renderer_ref: ARAEditorRendererRef,
+    _region_sequence_ref: ARARegionSequenceRef,
+) {
+    // Region sequences are not exposed to the plugin
+}
+
+unsafe extern "C" fn notify_selection(
+    _editor_view_ref: ARAEditorViewRef,
+    _selection: *const ARAViewSelection,
+) {
+    // The host's selection is not exposed to the plugin, see the limitations in the module docs
+}
+
+unsafe extern "C" fn notify_hide_region_sequences(
+    _editor_view_ref: ARAEditorViewRef,
+    _region_sequences_count: ARASize,
+    _region_sequences: *const ARARegionSequenceRef,
+) {
+}
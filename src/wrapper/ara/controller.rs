@@ -0,0 +1,844 @@
+//! The ARA document controller. The host creates one document controller per document through the
+//! ARA factory, and it then uses the controller to mirror its arrangement to the plugin.
+
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Arc;
+
+use super::archive;
+use super::document::{
+    AraAudioModification, AraAudioSource, AraDocument, AraObjectId, AraPlaybackRegion,
+    AraPlaybackTransformations, HostRef,
+};
+use super::factory::AraFactory;
+use super::host::AraHost;
+use super::sys::*;
+use super::util::string_from_ptr;
+use crate::prelude::{AraDocumentController, AraPlugin};
+use crate::wrapper::util::check_null_ptr;
+
+/// The state shared between a document controller and the plugin instances bound to it. When both
+/// locks are needed, the document must always be locked before the controller.
+pub(crate) struct DocumentShared<P: AraPlugin> {
+    pub document: Mutex<AraDocument>,
+    pub controller: Mutex<P::DocumentController>,
+    /// Data restored from an archive for audio sources the host has not added yet, keyed by the
+    /// audio sources' persistent IDs. This is cleared at the end of the editing cycle.
+    pending_restores: Mutex<HashMap<String, Vec<u8>>>,
+}
+
+/// A document controller for an [`AraPlugin`]. A pointer to this object is used as the
+/// `ARADocumentControllerRef`.
+pub(crate) struct DocumentController<P: AraPlugin> {
+    instance: ARADocumentControllerInstance,
+    host: AraHost,
+    archiving_controller: HostRef,
+    archiving_interface: *const ARAArchivingControllerInterface,
+
+    pub shared: Arc<DocumentShared<P>>,
+}
+
+impl<P: AraPlugin> DocumentController<P> {
+    const INTERFACE: ARADocumentControllerInterface = ARADocumentControllerInterface {
+        struct_size: std::mem::size_of::<ARADocumentControllerInterface>(),
+        destroy_document_controller: Some(Self::destroy_document_controller),
+        get_factory: Some(Self::get_factory),
+        begin_editing: Some(Self::begin_editing),
+        end_editing: Some(Self::end_editing),
+        notify_model_updates: Some(Self::notify_model_updates),
+        begin_restoring_document_from_archive: Some(Self::begin_restoring_document_from_archive),
+        end_restoring_document_from_archive: Some(Self::end_restoring_document_from_archive),
+        store_document_to_archive: Some(Self::store_document_to_archive),
+        update_document_properties: Some(Self::update_document_properties),
+        create_musical_context: Some(Self::create_musical_context),
+        update_musical_context_properties: Some(Self::update_musical_context_properties),
+        update_musical_context_content: Some(Self::update_musical_context_content),
+        destroy_musical_context: Some(Self::destroy_musical_context),
+        create_audio_source: Some(Self::create_audio_source),
+        update_audio_source_properties: Some(Self::update_audio_source_properties),
+        update_audio_source_content: Some(Self::update_audio_source_content),
+        enable_audio_source_samples_access: Some(Self::enable_audio_source_samples_access),
+        deactivate_audio_source_for_undo_history: Some(Self::deactivate_for_undo_history),
+        destroy_audio_source: Some(Self::destroy_audio_source),
+        create_audio_modification: Some(Self::create_audio_modification),
+        clone_audio_modification: Some(Self::clone_audio_modification),
+        update_audio_modification_properties: Some(Self::update_audio_modification_properties),
+        deactivate_audio_modification_for_undo_history: Some(Self::deactivate_for_undo_history),
+        destroy_audio_modification: Some(Self::destroy_audio_modification),
+        create_playback_region: Some(Self::create_playback_region),
+        update_playback_region_properties: Some(Self::update_playback_region_properties),
+        destroy_playback_region: Some(Self::destroy_playback_region),
+        is_audio_source_content_available: Some(Self::is_content_available),
+        is_audio_source_content_analysis_incomplete: Some(Self::is_content_available),
+        request_audio_source_content_analysis: Some(Self::request_audio_source_content_analysis),
+        get_audio_source_content_grade: Some(Self::get_content_grade),
+        create_audio_source_content_reader: Some(Self::create_content_reader),
+        is_audio_modification_content_available: Some(Self::is_content_available),
+        get_audio_modification_content_grade: Some(Self::get_content_grade),
+        create_audio_modification_content_reader: Some(Self::create_content_reader),
+        is_playback_region_content_available: Some(Self::is_content_available),
+        get_playback_region_content_grade: Some(Self::get_content_grade),
+        create_playback_region_content_reader: Some(Self::create_content_reader),
+        get_content_reader_event_count: Some(Self::get_content_reader_event_count),
+        get_content_reader_data_for_event: Some(Self::get_content_reader_data_for_event),
+        destroy_content_reader: Some(Self::destroy_content_reader),
+        create_region_sequence: Some(Self::create_musical_context),
+        update_region_sequence_properties: Some(Self::update_musical_context_properties),
+        destroy_region_sequence: Some(Self::destroy_musical_context),
+        get_playback_region_head_and_tail_time: Some(Self::get_playback_region_head_and_tail_time),
+        restore_objects_from_archive: Some(Self::restore_objects_from_archive),
+        store_objects_to_archive: Some(Self::store_objects_to_archive),
+        get_processing_algorithms_count: Some(Self::get_processing_algorithms_count),
+        get_processing_algorithm_properties: Some(Self::get_processing_algorithm_properties),
+        get_processing_algorithm_for_audio_source: Some(
+            Self::get_processing_algorithm_for_audio_source,
+        ),
+        request_processing_algorithm_for_audio_source: Some(
+            Self::request_processing_algorithm_for_audio_source,
+        ),
+        is_licensed_for_capabilities: Some(Self::is_licensed_for_capabilities),
+    };
+
+    /// The `createDocumentControllerWithDocument()` function from the plugin's ARA factory.
+    pub unsafe extern "C" fn create(
+        host_instance: *const ARADocumentControllerHostInstance,
+        properties: *const ARADocumentProperties,
+    ) -> *const ARADocumentControllerInstance {
+        check_null_ptr!(ptr::null(), host_instance);
+
+        let host = AraHost::new(host_instance);
+        let mut document = AraDocument::default();
+        if !properties.is_null() {
+            document.name = string_from_ptr((*properties).name);
+        }
+
+        let controller = P::create_document_controller(host.clone());
+        let this = Box::into_raw(Box::new(Self {
+            instance: ARADocumentControllerInstance {
+                struct_size: std::mem::size_of::<ARADocumentControllerInstance>(),
+                document_controller_ref: ptr::null_mut(),
+                document_controller_interface: &Self::INTERFACE,
+            },
+            host,
+            archiving_controller: HostRef((*host_instance).archiving_controller_host_ref),
+            archiving_interface: (*host_instance).archiving_controller_interface,
+
+            shared: Arc::new(DocumentShared {
+                document: Mutex::new(document),
+                controller: Mutex::new(controller),
+                pending_restores: Mutex::new(HashMap::new()),
+            }),
+        }));
+        (*this).instance.document_controller_ref = this as ARADocumentControllerRef;
+
+        &(*this).instance
+    }
+
+    /// Get the document controller belonging to a document controller ref.
+    ///
+    /// # Safety
+    ///
+    /// `controller_ref` must have been created by [`Self::create()`] for the same plugin type, and
+    /// it must not have been destroyed yet.
+    pub unsafe fn from_ref<'a>(controller_ref: ARADocumentControllerRef) -> &'a Self {
+        &*(controller_ref as *const Self)
+    }
+
+    /// Read the entire contents of an archive from the host.
+    unsafe fn read_archive(&self, archive_reader: ARAArchiveReaderHostRef) -> Option<Vec<u8>> {
+        let interface = self.archiving_interface.as_ref()?;
+        let size = (interface.get_archive_size?)(self.archiving_controller.0, archive_reader);
+
+        let mut archive = vec![0u8; size];
+        let success = (interface.read_bytes_from_archive?)(
+            self.archiving_controller.0,
+            archive_reader,
+            0,
+            size,
+            archive.as_mut_ptr(),
+        ) != kARAFalse;
+
+        if success {
+            Some(archive)
+        } else {
+            None
+        }
+    }
+
+    /// Write an archive to the host. Returns `false` if the host could not store the archive.
+    unsafe fn write_archive(
+        &self,
+        archive_writer: ARAArchiveWriterHostRef,
+        archive: &[u8],
+    ) -> bool {
+        let Some(write_bytes_to_archive) = self
+            .archiving_interface
+            .as_ref()
+            .and_then(|interface| interface.write_bytes_to_archive)
+        else {
+            return false;
+        };
+
+        write_bytes_to_archive(
+            self.archiving_controller.0,
+            archive_writer,
+            0,
+            archive.len(),
+            archive.as_ptr(),
+        ) != kARAFalse
+    }
+
+    /// Restore the data for the audio sources stored in an archive. `persistent_id_mapping` maps
+    /// the persistent IDs stored in the archive to the IDs of the audio sources in the current
+    /// document. If it is `None`, then all stored audio sources are restored using their stored
+    /// IDs. Data for audio sources that don't exist yet is restored when the host adds them.
+    unsafe fn restore_archive(
+        &self,
+        archive_reader: ARAArchiveReaderHostRef,
+        persistent_id_mapping: Option<HashMap<String, String>>,
+    ) -> bool {
+        let Some(entries) = self
+            .read_archive(archive_reader)
+            .and_then(|archive| archive::decode(&archive))
+        else {
+            nih_debug_assert_failure!("Could not read the ARA archive");
+            return false;
+        };
+
+        let document = self.shared.document.lock();
+        let mut controller = self.shared.controller.lock();
+        let mut pending_restores = self.shared.pending_restores.lock();
+        let mut success = true;
+        for (archived_id, data) in entries {
+            let persistent_id = match &persistent_id_mapping {
+                Some(mapping) => match mapping.get(&archived_id) {
+                    Some(current_id) => current_id.clone(),
+                    None => continue,
+                },
+                None => archived_id,
+            };
+
+            match document.audio_source_by_persistent_id(&persistent_id) {
+                Some((audio_source, _)) => {
+                    success &= controller.restore_audio_source(&document, audio_source, &data)
+                }
+                None => {
+                    pending_restores.insert(persistent_id, data);
+                }
+            }
+        }
+
+        success
+    }
+
+    /// Store the data for the specified audio sources in an archive.
+    unsafe fn store_archive(
+        &self,
+        archive_writer: ARAArchiveWriterHostRef,
+        audio_sources: impl IntoIterator<Item = AraObjectId>,
+    ) -> bool {
+        let entries: Vec<(String, Vec<u8>)> = {
+            let document = self.shared.document.lock();
+            let mut controller = self.shared.controller.lock();
+
+            audio_sources
+                .into_iter()
+                .filter_map(|id| {
+                    let audio_source = document.audio_source(id)?;
+                    let data = controller.store_audio_source(&document, id)?;
+
+                    Some((audio_source.persistent_id.clone(), data))
+                })
+                .collect()
+        };
+
+        self.write_archive(archive_writer, &archive::encode(&entries))
+    }
+
+    unsafe extern "C" fn destroy_document_controller(controller_ref: ARADocumentControllerRef) {
+        check_null_ptr!((), controller_ref);
+
+        drop(Box::from_raw(controller_ref as *mut Self));
+    }
+
+    unsafe extern "C" fn get_factory(
+        _controller_ref: ARADocumentControllerRef,
+    ) -> *const ARAFactory {
+        AraFactory::for_plugin::<P>().ara_factory()
+    }
+
+    unsafe extern "C" fn begin_editing(_controller_ref: ARADocumentControllerRef) {}
+
+    unsafe extern "C" fn end_editing(controller_ref: ARADocumentControllerRef) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        // Restored data for audio sources that were not added in this editing cycle is stale
+        this.shared.pending_restores.lock().clear();
+
+        let document = this.shared.document.lock();
+        this.shared.controller.lock().editing_finished(&document);
+    }
+
+    unsafe extern "C" fn notify_model_updates(controller_ref: ARADocumentControllerRef) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        this.host.flush_notifications();
+    }
+
+    unsafe extern "C" fn begin_restoring_document_from_archive(
+        controller_ref: ARADocumentControllerRef,
+        archive_reader: ARAArchiveReaderHostRef,
+    ) -> ARABool {
+        check_null_ptr!(kARAFalse, controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        this.restore_archive(archive_reader, None) as ARABool
+    }
+
+    unsafe extern "C" fn end_restoring_document_from_archive(
+        _controller_ref: ARADocumentControllerRef,
+        _archive_reader: ARAArchiveReaderHostRef,
+    ) -> ARABool {
+        kARATrue
+    }
+
+    unsafe extern "C" fn store_document_to_archive(
+        controller_ref: ARADocumentControllerRef,
+        archive_writer: ARAArchiveWriterHostRef,
+    ) -> ARABool {
+        check_null_ptr!(kARAFalse, controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        let audio_sources: Vec<AraObjectId> = this
+            .shared
+            .document
+            .lock()
+            .audio_sources
+            .keys()
+            .copied()
+            .collect();
+        this.store_archive(archive_writer, audio_sources) as ARABool
+    }
+
+    unsafe extern "C" fn update_document_properties(
+        controller_ref: ARADocumentControllerRef,
+        properties: *const ARADocumentProperties,
+    ) {
+        check_null_ptr!((), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+
+        this.shared.document.lock().name = string_from_ptr((*properties).name);
+    }
+
+    // Musical contexts and region sequences are not exposed to the plugin, but the host still
+    // needs unique non-null refs for them
+    unsafe extern "C" fn create_musical_context(
+        controller_ref: ARADocumentControllerRef,
+        _host_ref: *mut c_void,
+        _properties: *const c_void,
+    ) -> *mut c_void {
+        check_null_ptr!(ptr::null_mut(), controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        this.shared.document.lock().next_id().to_ref()
+    }
+
+    unsafe extern "C" fn update_musical_context_properties(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+        _properties: *const c_void,
+    ) {
+    }
+
+    unsafe extern "C" fn update_musical_context_content(
+        _controller_ref: ARADocumentControllerRef,
+        _musical_context_ref: ARAMusicalContextRef,
+        _range: *const ARAContentTimeRange,
+        _flags: ARAContentUpdateFlags,
+    ) {
+    }
+
+    unsafe extern "C" fn destroy_musical_context(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+    ) {
+    }
+
+    unsafe extern "C" fn create_audio_source(
+        controller_ref: ARADocumentControllerRef,
+        host_ref: ARAAudioSourceHostRef,
+        properties: *const ARAAudioSourceProperties,
+    ) -> ARAAudioSourceRef {
+        check_null_ptr!(ptr::null_mut(), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+
+        let audio_source = AraAudioSource {
+            name: string_from_ptr((*properties).name),
+            persistent_id: string_from_ptr((*properties).persistent_id).unwrap_or_default(),
+            sample_count: (*properties).sample_count,
+            sample_rate: (*properties).sample_rate,
+            channel_count: (*properties).channel_count.max(0) as u32,
+            samples_access_enabled: false,
+            host_ref: HostRef(host_ref),
+        };
+        let restored_data = this
+            .shared
+            .pending_restores
+            .lock()
+            .remove(&audio_source.persistent_id);
+
+        let mut document = this.shared.document.lock();
+        let id = document.next_id();
+        document.audio_sources.insert(id, audio_source);
+
+        let mut controller = this.shared.controller.lock();
+        controller.audio_source_added(&document, id);
+        if let Some(data) = restored_data {
+            let success = controller.restore_audio_source(&document, id, &data);
+            nih_debug_assert!(success, "Could not restore an audio source's archived data");
+        }
+
+        id.to_ref()
+    }
+
+    unsafe extern "C" fn update_audio_source_properties(
+        controller_ref: ARADocumentControllerRef,
+        audio_source_ref: ARAAudioSourceRef,
+        properties: *const ARAAudioSourceProperties,
+    ) {
+        check_null_ptr!((), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(audio_source_ref);
+
+        let mut document = this.shared.document.lock();
+        let Some(audio_source) = document.audio_sources.get_mut(&id) else {
+            nih_debug_assert_failure!("Unknown audio source ref");
+            return;
+        };
+        audio_source.name = string_from_ptr((*properties).name);
+        audio_source.persistent_id =
+            string_from_ptr((*properties).persistent_id).unwrap_or_default();
+        audio_source.sample_count = (*properties).sample_count;
+        audio_source.sample_rate = (*properties).sample_rate;
+        audio_source.channel_count = (*properties).channel_count.max(0) as u32;
+
+        this.shared
+            .controller
+            .lock()
+            .audio_source_properties_changed(&document, id);
+    }
+
+    unsafe extern "C" fn update_audio_source_content(
+        controller_ref: ARADocumentControllerRef,
+        audio_source_ref: ARAAudioSourceRef,
+        _range: *const ARAContentTimeRange,
+        _flags: ARAContentUpdateFlags,
+    ) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(audio_source_ref);
+
+        let document = this.shared.document.lock();
+        if document.audio_sources.contains_key(&id) {
+            this.shared
+                .controller
+                .lock()
+                .audio_source_content_changed(&document, id);
+        }
+    }
+
+    unsafe extern "C" fn enable_audio_source_samples_access(
+        controller_ref: ARADocumentControllerRef,
+        audio_source_ref: ARAAudioSourceRef,
+        enable: ARABool,
+    ) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(audio_source_ref);
+
+        let mut document = this.shared.document.lock();
+        let Some(audio_source) = document.audio_sources.get_mut(&id) else {
+            nih_debug_assert_failure!("Unknown audio source ref");
+            return;
+        };
+        audio_source.samples_access_enabled = enable != kARAFalse;
+
+        this.shared
+            .controller
+            .lock()
+            .audio_source_samples_access_changed(&document, id);
+    }
+
+    unsafe extern "C" fn deactivate_for_undo_history(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+        _deactivate: ARABool,
+    ) {
+        // Deactivated objects are kept around as is, so they can be reactivated when the user
+        // undoes the deletion
+    }
+
+    unsafe extern "C" fn destroy_audio_source(
+        controller_ref: ARADocumentControllerRef,
+        audio_source_ref: ARAAudioSourceRef,
+    ) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(audio_source_ref);
+
+        let mut document = this.shared.document.lock();
+        if document.audio_sources.contains_key(&id) {
+            this.shared
+                .controller
+                .lock()
+                .audio_source_removed(&document, id);
+            document.audio_sources.remove(&id);
+        }
+    }
+
+    unsafe extern "C" fn create_audio_modification(
+        controller_ref: ARADocumentControllerRef,
+        audio_source_ref: ARAAudioSourceRef,
+        host_ref: ARAAudioModificationHostRef,
+        properties: *const ARAAudioModificationProperties,
+    ) -> ARAAudioModificationRef {
+        check_null_ptr!(ptr::null_mut(), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+
+        let mut document = this.shared.document.lock();
+        let id = document.next_id();
+        document.audio_modifications.insert(
+            id,
+            AraAudioModification {
+                audio_source: AraObjectId::from_ref(audio_source_ref),
+                name: string_from_ptr((*properties).name),
+                persistent_id: string_from_ptr((*properties).persistent_id).unwrap_or_default(),
+                host_ref: HostRef(host_ref),
+            },
+        );
+
+        id.to_ref()
+    }
+
+    unsafe extern "C" fn clone_audio_modification(
+        controller_ref: ARADocumentControllerRef,
+        audio_modification_ref: ARAAudioModificationRef,
+        host_ref: ARAAudioModificationHostRef,
+        properties: *const ARAAudioModificationProperties,
+    ) -> ARAAudioModificationRef {
+        check_null_ptr!(ptr::null_mut(), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+
+        let audio_source = {
+            let document = this.shared.document.lock();
+            match document.audio_modification(AraObjectId::from_ref(audio_modification_ref)) {
+                Some(audio_modification) => audio_modification.audio_source,
+                None => {
+                    nih_debug_assert_failure!("Unknown audio modification ref");
+                    return ptr::null_mut();
+                }
+            }
+        };
+
+        Self::create_audio_modification(controller_ref, audio_source.to_ref(), host_ref, properties)
+    }
+
+    unsafe extern "C" fn update_audio_modification_properties(
+        controller_ref: ARADocumentControllerRef,
+        audio_modification_ref: ARAAudioModificationRef,
+        properties: *const ARAAudioModificationProperties,
+    ) {
+        check_null_ptr!((), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(audio_modification_ref);
+
+        let mut document = this.shared.document.lock();
+        if let Some(audio_modification) = document.audio_modifications.get_mut(&id) {
+            audio_modification.name = string_from_ptr((*properties).name);
+            audio_modification.persistent_id =
+                string_from_ptr((*properties).persistent_id).unwrap_or_default();
+        }
+    }
+
+    unsafe extern "C" fn destroy_audio_modification(
+        controller_ref: ARADocumentControllerRef,
+        audio_modification_ref: ARAAudioModificationRef,
+    ) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        this.shared
+            .document
+            .lock()
+            .audio_modifications
+            .remove(&AraObjectId::from_ref(audio_modification_ref));
+    }
+
+    /// Convert the host's playback region properties to a playback region.
+    unsafe fn playback_region_from_properties(
+        audio_modification: AraObjectId,
+        host_ref: HostRef,
+        properties: *const ARAPlaybackRegionProperties,
+    ) -> AraPlaybackRegion {
+        let properties = &*properties;
+
+        AraPlaybackRegion {
+            audio_modification,
+            transformations: AraPlaybackTransformations::from_bits_truncate(
+                properties.transformation_flags,
+            ),
+            start_in_modification_time: properties.start_in_modification_time,
+            duration_in_modification_time: properties.duration_in_modification_time,
+            start_in_playback_time: properties.start_in_playback_time,
+            duration_in_playback_time: properties.duration_in_playback_time,
+            // The name was added in ARA 2.0, so older hosts may pass a smaller struct
+            name: if properties.struct_size >= std::mem::size_of::<ARAPlaybackRegionProperties>() {
+                string_from_ptr(properties.name)
+            } else {
+                None
+            },
+            host_ref,
+        }
+    }
+
+    unsafe extern "C" fn create_playback_region(
+        controller_ref: ARADocumentControllerRef,
+        audio_modification_ref: ARAAudioModificationRef,
+        host_ref: ARAPlaybackRegionHostRef,
+        properties: *const ARAPlaybackRegionProperties,
+    ) -> ARAPlaybackRegionRef {
+        check_null_ptr!(ptr::null_mut(), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+
+        let playback_region = Self::playback_region_from_properties(
+            AraObjectId::from_ref(audio_modification_ref),
+            HostRef(host_ref),
+            properties,
+        );
+
+        let mut document = this.shared.document.lock();
+        let id = document.next_id();
+        document.playback_regions.insert(id, playback_region);
+        this.shared
+            .controller
+            .lock()
+            .playback_region_added(&document, id);
+
+        id.to_ref()
+    }
+
+    unsafe extern "C" fn update_playback_region_properties(
+        controller_ref: ARADocumentControllerRef,
+        playback_region_ref: ARAPlaybackRegionRef,
+        properties: *const ARAPlaybackRegionProperties,
+    ) {
+        check_null_ptr!((), controller_ref, properties);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(playback_region_ref);
+
+        let mut document = this.shared.document.lock();
+        let Some(playback_region) = document.playback_regions.get_mut(&id) else {
+            nih_debug_assert_failure!("Unknown playback region ref");
+            return;
+        };
+        *playback_region = Self::playback_region_from_properties(
+            playback_region.audio_modification,
+            playback_region.host_ref,
+            properties,
+        );
+
+        this.shared
+            .controller
+            .lock()
+            .playback_region_changed(&document, id);
+    }
+
+    unsafe extern "C" fn destroy_playback_region(
+        controller_ref: ARADocumentControllerRef,
+        playback_region_ref: ARAPlaybackRegionRef,
+    ) {
+        check_null_ptr!((), controller_ref);
+        let this = Self::from_ref(controller_ref);
+        let id = AraObjectId::from_ref(playback_region_ref);
+
+        let mut document = this.shared.document.lock();
+        if document.playback_regions.contains_key(&id) {
+            this.shared
+                .controller
+                .lock()
+                .playback_region_removed(&document, id);
+            document.playback_regions.remove(&id);
+        }
+    }
+
+    // The wrapper does not export any content types, so none of the content reading functions
+    // have anything to read
+    unsafe extern "C" fn is_content_available(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+        _content_type: ARAContentType,
+    ) -> ARABool {
+        kARAFalse
+    }
+
+    unsafe extern "C" fn request_audio_source_content_analysis(
+        _controller_ref: ARADocumentControllerRef,
+        _audio_source_ref: ARAAudioSourceRef,
+        _content_types_count: ARASize,
+        _content_types: *const ARAContentType,
+    ) {
+    }
+
+    unsafe extern "C" fn get_content_grade(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+        _content_type: ARAContentType,
+    ) -> ARAContentGrade {
+        // This is `kARAContentGradeInitial`
+        0
+    }
+
+    unsafe extern "C" fn create_content_reader(
+        _controller_ref: ARADocumentControllerRef,
+        _object_ref: *mut c_void,
+        _content_type: ARAContentType,
+        _range: *const ARAContentTimeRange,
+    ) -> ARAContentReaderRef {
+        ptr::null_mut()
+    }
+
+    unsafe extern "C" fn get_content_reader_event_count(
+        _controller_ref: ARADocumentControllerRef,
+        _content_reader_ref: ARAContentReaderRef,
+    ) -> ARAInt32 {
+        0
+    }
+
+    unsafe extern "C" fn get_content_reader_data_for_event(
+        _controller_ref: ARADocumentControllerRef,
+        _content_reader_ref: ARAContentReaderRef,
+        _event_index: ARAInt32,
+    ) -> *const c_void {
+        ptr::null()
+    }
+
+    unsafe extern "C" fn destroy_content_reader(
+        _controller_ref: ARADocumentControllerRef,
+        _content_reader_ref: ARAContentReaderRef,
+    ) {
+    }
+
+    unsafe extern "C" fn get_playback_region_head_and_tail_time(
+        _controller_ref: ARADocumentControllerRef,
+        _playback_region_ref: ARAPlaybackRegionRef,
+        head_time: *mut ARATimeDuration,
+        tail_time: *mut ARATimeDuration,
+    ) {
+        check_null_ptr!((), head_time, tail_time);
+
+        *head_time = 0.0;
+        *tail_time = 0.0;
+    }
+
+    unsafe extern "C" fn restore_objects_from_archive(
+        controller_ref: ARADocumentControllerRef,
+        archive_reader: ARAArchiveReaderHostRef,
+        filter: *const ARARestoreObjectsFilter,
+    ) -> ARABool {
+        check_null_ptr!(kARAFalse, controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        // When the host only restores some of the archived objects, for instance when importing
+        // audio from another project, the audio sources may have been assigned new persistent IDs
+        let persistent_id_mapping = filter.as_ref().map(|filter| {
+            let num_audio_sources = if filter.audio_source_archive_ids.is_null()
+                || filter.audio_source_current_ids.is_null()
+            {
+                0
+            } else {
+                filter.audio_source_archive_ids_count
+            };
+
+            (0..num_audio_sources)
+                .filter_map(|idx| {
+                    let archived_id = string_from_ptr(*filter.audio_source_archive_ids.add(idx))?;
+                    let current_id = string_from_ptr(*filter.audio_source_current_ids.add(idx))?;
+
+                    Some((archived_id, current_id))
+                })
+                .collect()
+        });
+
+        this.restore_archive(archive_reader, persistent_id_mapping) as ARABool
+    }
+
+    unsafe extern "C" fn store_objects_to_archive(
+        controller_ref: ARADocumentControllerRef,
+        archive_writer: ARAArchiveWriterHostRef,
+        filter: *const ARAStoreObjectsFilter,
+    ) -> ARABool {
+        check_null_ptr!(kARAFalse, controller_ref);
+        let this = Self::from_ref(controller_ref);
+
+        let audio_sources: Vec<AraObjectId> = match filter.as_ref() {
+            Some(filter) if !filter.audio_source_refs.is_null() => {
+                std::slice::from_raw_parts(filter.audio_source_refs, filter.audio_source_refs_count)
+                    .iter()
+                    .map(|audio_source_ref| AraObjectId::from_ref(*audio_source_ref))
+                    .collect()
+            }
+            Some(_) => Vec::new(),
+            None => this
+                .shared
+                .document
+                .lock()
+                .audio_sources
+                .keys()
+                .copied()
+                .collect(),
+        };
+
+        this.store_archive(archive_writer, audio_sources) as ARABool
+    }
+
+    unsafe extern "C" fn get_processing_algorithms_count(
+        _controller_ref: ARADocumentControllerRef,
+    ) -> ARAInt32 {
+        0
+    }
+
+    unsafe extern "C" fn get_processing_algorithm_properties(
+        _controller_ref: ARADocumentControllerRef,
+        _algorithm_index: ARAInt32,
+    ) -> *const ARAProcessingAlgorithmProperties {
+        ptr::null()
+    }
+
+    unsafe extern "C" fn get_processing_algorithm_for_audio_source(
+        _controller_ref: ARADocumentControllerRef,
+        _audio_source_ref: ARAAudioSourceRef,
+    ) -> ARAInt32 {
+        0
+    }
+
+    unsafe extern "C" fn request_processing_algorithm_for_audio_source(
+        _controller_ref: ARADocumentControllerRef,
+        _audio_source_ref: ARAAudioSourceRef,
+        _algorithm_index: ARAInt32,
+    ) {
+    }
+
+    unsafe extern "C" fn is_licensed_for_capabilities(
+        _controller_ref: ARADocumentControllerRef,
+        _run_modal_activation_dialog_if_needed: ARABool,
+        _content_types_count: ARASize,
+        _content_types: *const ARAContentType,
+        _transformation_flags: ARAPlaybackTransformationFlags,
+    ) -> ARABool {
+        kARATrue
+    }
+}
@@ -0,0 +1,194 @@
+//! The plugin side copy of the host's ARA document model. The host describes its arrangement
+//! through audio sources, audio modifications, and playback regions, and the wrapper mirrors these
+//! objects so the plugin can access them without going through the host.
+
+use std::collections::HashMap;
+use std::os::raw::c_void;
+
+bitflags::bitflags! {
+    /// The transformations a plugin can apply when rendering a playback region. See
+    /// [`AraPlugin::ARA_PLAYBACK_TRANSFORMATIONS`][crate::prelude::AraPlugin::ARA_PLAYBACK_TRANSFORMATIONS].
+    #[repr(transparent)]
+    #[derive(Default)]
+    pub struct AraPlaybackTransformations: i32 {
+        /// The playback region's duration in playback time may differ from its duration in
+        /// modification time, in which case the plugin time stretches the region.
+        const TIMESTRETCH = 1 << 0;
+        /// Time stretching follows the tempo map instead of stretching linearly. Requires
+        /// `TIMESTRETCH`.
+        const TIMESTRETCH_REFLECTING_TEMPO = 1 << 1;
+        /// The plugin applies a fade at the end of the playback region based on the region's
+        /// content.
+        const CONTENT_BASED_FADE_AT_TAIL = 1 << 2;
+        /// The plugin applies a fade at the start of the playback region based on the region's
+        /// content.
+        const CONTENT_BASED_FADE_AT_HEAD = 1 << 3;
+    }
+}
+
+/// Identifies an object in an [`AraDocument`]. These IDs are unique within a document and they are
+/// never reused. Use the objects' persistent IDs to identify them across sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct AraObjectId(pub(crate) u64);
+
+/// A host-provided ref for one of the host's objects. These are passed back to the host when
+/// reading audio or when sending notifications about an object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct HostRef(pub *mut c_void);
+
+unsafe impl Send for HostRef {}
+unsafe impl Sync for HostRef {}
+
+/// A piece of audio in the host's arrangement, usually corresponding to an audio file.
+#[derive(Debug, Clone)]
+pub struct AraAudioSource {
+    /// The audio source's name, if the host provided one.
+    pub name: Option<String>,
+    /// An identifier for the audio source that stays the same across sessions. Used to match
+    /// stored analysis results to the audio source.
+    pub persistent_id: String,
+    /// The total number of samples per channel.
+    pub sample_count: i64,
+    pub sample_rate: f64,
+    pub channel_count: u32,
+    /// Whether samples can currently be read from this audio source using an
+    /// [`AraAudioReader`][crate::prelude::AraAudioReader].
+    pub samples_access_enabled: bool,
+
+    pub(crate) host_ref: HostRef,
+}
+
+/// The plugin's edits for an audio source. Multiple audio modifications can exist for the same
+/// audio source, for instance when the user duplicates a region and edits the copy.
+#[derive(Debug, Clone)]
+pub struct AraAudioModification {
+    /// The audio source being modified.
+    pub audio_source: AraObjectId,
+    /// The audio modification's name, if the host provided one.
+    pub name: Option<String>,
+    /// An identifier for the audio modification that stays the same across sessions.
+    pub persistent_id: String,
+
+    pub(crate) host_ref: HostRef,
+}
+
+/// A section of an audio modification placed on the host's timeline. Plugin instances render the
+/// playback regions assigned to them through their [`AraBinding`][crate::prelude::AraBinding].
+#[derive(Debug, Clone)]
+pub struct AraPlaybackRegion {
+    /// The audio modification this playback region plays back.
+    pub audio_modification: AraObjectId,
+    /// The transformations the plugin should apply when rendering this region.
+    pub transformations: AraPlaybackTransformations,
+    /// The start of the region within the audio modification, in seconds.
+    pub start_in_modification_time: f64,
+    /// The region's length within the audio modification, in seconds.
+    pub duration_in_modification_time: f64,
+    /// The position of the region on the host's timeline, in seconds.
+    pub start_in_playback_time: f64,
+    /// The region's length on the host's timeline, in seconds. This only differs from
+    /// [`duration_in_modification_time`][Self::duration_in_modification_time] when the region is
+    /// time stretched.
+    pub duration_in_playback_time: f64,
+    /// The playback region's name, if the host provided one.
+    pub name: Option<String>,
+
+    pub(crate) host_ref: HostRef,
+}
+
+/// The host's ARA document. Objects are added and removed by the host, and the plugin is informed
+/// about these changes through its
+/// [`AraDocumentController`][crate::prelude::AraDocumentController].
+#[derive(Debug, Default)]
+pub struct AraDocument {
+    pub(crate) name: Option<String>,
+    pub(crate) audio_sources: HashMap<AraObjectId, AraAudioSource>,
+    pub(crate) audio_modifications: HashMap<AraObjectId, AraAudioModification>,
+    pub(crate) playback_regions: HashMap<AraObjectId, AraPlaybackRegion>,
+
+    /// The ID for the next object added to the document. Starts at 1 since these IDs are also used
+    /// as the object refs passed to the host, and those should not be null.
+    next_id: u64,
+}
+
+impl AraDocument {
+    /// The document's name, if the host provided one.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Look up an audio source by its ID.
+    pub fn audio_source(&self, id: AraObjectId) -> Option<&AraAudioSource> {
+        self.audio_sources.get(&id)
+    }
+
+    /// Iterate over all audio sources in the document in no particular order.
+    pub fn audio_sources(&self) -> impl Iterator<Item = (AraObjectId, &AraAudioSource)> {
+        self.audio_sources.iter().map(|(id, source)| (*id, source))
+    }
+
+    /// Look up an audio modification by its ID.
+    pub fn audio_modification(&self, id: AraObjectId) -> Option<&AraAudioModification> {
+        self.audio_modifications.get(&id)
+    }
+
+    /// Iterate over all audio modifications in the document in no particular order.
+    pub fn audio_modifications(
+        &self,
+    ) -> impl Iterator<Item = (AraObjectId, &AraAudioModification)> {
+        self.audio_modifications
+            .iter()
+            .map(|(id, modification)| (*id, modification))
+    }
+
+    /// Look up a playback region by its ID.
+    pub fn playback_region(&self, id: AraObjectId) -> Option<&AraPlaybackRegion> {
+        self.playback_regions.get(&id)
+    }
+
+    /// Iterate over all playback regions in the document in no particular order.
+    pub fn playback_regions(&self) -> impl Iterator<Item = (AraObjectId, &AraPlaybackRegion)> {
+        self.playback_regions
+            .iter()
+            .map(|(id, region)| (*id, region))
+    }
+
+    /// Find the audio source a playback region ultimately plays back.
+    pub fn playback_region_audio_source(
+        &self,
+        playback_region: AraObjectId,
+    ) -> Option<(AraObjectId, &AraAudioSource)> {
+        let region = self.playback_regions.get(&playback_region)?;
+        let modification = self.audio_modifications.get(&region.audio_modification)?;
+        let source = self.audio_sources.get(&modification.audio_source)?;
+
+        Some((modification.audio_source, source))
+    }
+
+    /// Find an audio source by its persistent ID.
+    pub fn audio_source_by_persistent_id(
+        &self,
+        persistent_id: &str,
+    ) -> Option<(AraObjectId, &AraAudioSource)> {
+        self.audio_sources()
+            .find(|(_, source)| source.persistent_id == persistent_id)
+    }
+
+    /// Allocate a new object ID.
+    pub(crate) fn next_id(&mut self) -> AraObjectId {
+        self.next_id += 1;
+        AraObjectId(self.next_id)
+    }
+}
+
+impl AraObjectId {
+    /// Convert an object ref passed by the host back to an object ID.
+    pub(crate) fn from_ref(object_ref: *mut c_void) -> Self {
+        Self(object_ref as usize as u64)
+    }
+
+    /// The object ref passed to the host for this object.
+    pub(crate) fn to_ref(self) -> *mut c_void {
+        self.0 as usize as *mut c_void
+    }
+}
@@ -0,0 +1,168 @@
+//! The ARA factory exposed through the VST3 and CLAP wrappers.
+
+use std::any::{Any, TypeId};
+use std::ffi::CString;
+use std::fmt::Debug;
+use std::os::raw::c_char;
+
+use super::binding::{self, PlugInExtension};
+use super::controller::DocumentController;
+use super::sys::*;
+use crate::prelude::AraPlugin;
+use crate::wrapper::util::check_null_ptr;
+
+/// A plugin's ARA factory. Return [`AraFactory::for_plugin()`] from
+/// [`Plugin::ara_factory()`][crate::prelude::Plugin::ara_factory()] to make a plugin available
+/// through ARA.
+///
+/// This type erases the plugin's type so the wrappers can expose and bind to the factory without
+/// knowing whether the plugin implements [`AraPlugin`].
+pub struct AraFactory {
+    // We need [CString]s for all of the factory's strings
+    factory_id: CString,
+    name: CString,
+    vendor: CString,
+    url: CString,
+    version: CString,
+    document_archive_id: CString,
+    compatible_document_archive_ids: Vec<CString>,
+
+    /// Contains pointers to the strings in `compatible_document_archive_ids`.
+    compatible_document_archive_id_ptrs: Vec<*const c_char>,
+    /// Initialized after the `CString` fields above since it contains pointers to those strings.
+    ara_factory: Option<ARAFactory>,
+    /// Binds a plugin instance to one of this factory's document controllers.
+    bind: unsafe fn(
+        &mut dyn Any,
+        ARADocumentControllerRef,
+        ARAPlugInInstanceRoleFlags,
+        ARAPlugInInstanceRoleFlags,
+    ) -> Option<PlugInExtension>,
+}
+
+unsafe impl Send for AraFactory {}
+unsafe impl Sync for AraFactory {}
+
+impl Debug for AraFactory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("AraFactory")
+            .field("factory_id", &self.factory_id)
+            .field("document_archive_id", &self.document_archive_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl AraFactory {
+    /// Get the ARA factory for a plugin. The factory is created the first time this is called for
+    /// a plugin type, and it lives for the rest of the program's lifetime since hosts hold on to
+    /// pointers to the factory.
+    pub fn for_plugin<P: AraPlugin>() -> &'static AraFactory {
+        // Generic functions can't have their own statics, so the factories are stored by type ID
+        static FACTORIES: parking_lot::Mutex<Vec<(TypeId, &'static AraFactory)>> =
+            parking_lot::const_mutex(Vec::new());
+
+        let mut factories = FACTORIES.lock();
+        if let Some((_, factory)) = factories
+            .iter()
+            .find(|(type_id, _)| *type_id == TypeId::of::<P>())
+        {
+            return factory;
+        }
+
+        let factory: &'static AraFactory = Box::leak(Box::new(Self::new::<P>()));
+        factories.push((TypeId::of::<P>(), factory));
+
+        factory
+    }
+
+    fn new<P: AraPlugin>() -> Self {
+        let mut factory = Self {
+            factory_id: CString::new(P::ARA_FACTORY_ID)
+                .expect("`ARA_FACTORY_ID` contained null bytes"),
+            name: CString::new(P::NAME).expect("`NAME` contained null bytes"),
+            vendor: CString::new(P::VENDOR).expect("`VENDOR` contained null bytes"),
+            url: CString::new(P::URL).expect("`URL` contained null bytes"),
+            version: CString::new(P::VERSION).expect("`VERSION` contained null bytes"),
+            document_archive_id: CString::new(P::ARA_DOCUMENT_ARCHIVE_ID)
+                .expect("`ARA_DOCUMENT_ARCHIVE_ID` contained null bytes"),
+            compatible_document_archive_ids: P::ARA_COMPATIBLE_DOCUMENT_ARCHIVE_IDS
+                .iter()
+                .map(|id| {
+                    CString::new(*id)
+                        .expect("`ARA_COMPATIBLE_DOCUMENT_ARCHIVE_IDS` contained null bytes")
+                })
+                .collect(),
+
+            // These need to be initialized later as they contain pointers to the fields in this
+            // factory
+            compatible_document_archive_id_ptrs: Vec::new(),
+            ara_factory: None,
+            bind: binding::bind_plugin::<P>,
+        };
+
+        factory.compatible_document_archive_id_ptrs = factory
+            .compatible_document_archive_ids
+            .iter()
+            .map(|id| id.as_ptr())
+            .collect();
+
+        // NOTE: This is safe without pinning this struct because all of the data is already stored
+        //       on the heap
+        factory.ara_factory = Some(ARAFactory {
+            struct_size: std::mem::size_of::<ARAFactory>(),
+            lowest_supported_api_generation: kARAAPIGeneration_2_0_Final,
+            highest_supported_api_generation: kARAAPIGeneration_2_0_Final,
+            factory_id: factory.factory_id.as_ptr(),
+            initialize_ara_with_configuration: Some(initialize_ara_with_configuration),
+            uninitialize_ara: Some(uninitialize_ara),
+            plug_in_name: factory.name.as_ptr(),
+            manufacturer_name: factory.vendor.as_ptr(),
+            information_url: factory.url.as_ptr(),
+            version: factory.version.as_ptr(),
+            create_document_controller_with_document: Some(DocumentController::<P>::create),
+            document_archive_id: factory.document_archive_id.as_ptr(),
+            compatible_document_archive_ids_count: factory
+                .compatible_document_archive_id_ptrs
+                .len(),
+            compatible_document_archive_ids: factory.compatible_document_archive_id_ptrs.as_ptr(),
+            analyzeable_content_types_count: 0,
+            analyzeable_content_types: std::ptr::null(),
+            supported_playback_transformation_flags: P::ARA_PLAYBACK_TRANSFORMATIONS.bits(),
+            supports_storing_audio_file_chunks: kARAFalse,
+        });
+
+        factory
+    }
+
+    /// The `ARAFactory` passed to the host.
+    pub(crate) fn ara_factory(&self) -> *const ARAFactory {
+        self.ara_factory.as_ref().unwrap()
+    }
+
+    /// Bind a plugin instance to one of this factory's document controllers. Returns `None` if the
+    /// plugin instance does not belong to this factory.
+    ///
+    /// # Safety
+    ///
+    /// `controller_ref` must have been created by this factory.
+    pub(crate) unsafe fn bind(
+        &self,
+        plugin: &mut dyn Any,
+        controller_ref: ARADocumentControllerRef,
+        known_roles: ARAPlugInInstanceRoleFlags,
+        assigned_roles: ARAPlugInInstanceRoleFlags,
+    ) -> Option<PlugInExtension> {
+        (self.bind)(plugin, controller_ref, known_roles, assigned_roles)
+    }
+}
+
+unsafe extern "C" fn initialize_ara_with_configuration(config: *const ARAInterfaceConfiguration) {
+    check_null_ptr!((), config);
+
+    nih_debug_assert!(
+        (*config).desired_api_generation >= kARAAPIGeneration_2_0_Final,
+        "The host requested an unsupported ARA API generation"
+    );
+}
+
+unsafe extern "C" fn uninitialize_ara() {}
@@ -0,0 +1,266 @@
+//! The host's side of an ARA document controller, used to read audio samples and to inform the
+//! host about analysis progress.
+
+use parking_lot::Mutex;
+use std::os::raw::c_void;
+use std::ptr;
+use std::sync::Arc;
+
+use super::document::{AraAudioSource, AraPlaybackRegion, HostRef};
+use super::sys::*;
+
+/// The host's ARA interfaces for a single document. This is passed to
+/// [`AraPlugin::create_document_controller()`][crate::prelude::AraPlugin::create_document_controller()]
+/// and it can be cloned and sent to other threads, for instance to analyze audio sources on a
+/// background thread.
+#[derive(Clone)]
+pub struct AraHost {
+    inner: Arc<HostInner>,
+}
+
+struct HostInner {
+    audio_access_controller: HostRef,
+    audio_access_interface: *const ARAAudioAccessControllerInterface,
+    model_update_controller: HostRef,
+    model_update_interface: *const ARAModelUpdateControllerInterface,
+
+    /// The host only accepts model update notifications from the main thread while it's polling
+    /// for them, so notifications are queued here and sent when the host calls
+    /// `notifyModelUpdates()`.
+    pending_notifications: Mutex<Vec<Notification>>,
+}
+
+unsafe impl Send for HostInner {}
+unsafe impl Sync for HostInner {}
+
+/// The analysis state of an audio source, reported to the host through
+/// [`AraHost::notify_analysis_progress()`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AraAnalysisProgress {
+    /// Analysis has started.
+    Started,
+    /// Analysis is in progress, with the progress being a value in `[0, 1]`.
+    Updated(f32),
+    /// Analysis has finished.
+    Completed,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Notification {
+    AnalysisProgress(HostRef, AraAnalysisProgress),
+    AudioSourceContentChanged(HostRef),
+    PlaybackRegionContentChanged(HostRef),
+}
+
+/// Reads samples from an audio source. Created using [`AraHost::create_audio_reader()`]. A reader
+/// can be used from any thread, but it must only be used from one thread at a time.
+pub struct AraAudioReader {
+    host: AraHost,
+    reader_ref: HostRef,
+    channel_count: usize,
+    /// Pointers to the output buffers, reused between reads.
+    channel_pointers: Vec<*mut c_void>,
+}
+
+unsafe impl Send for AraAudioReader {}
+
+impl AraHost {
+    /// Copy the host's interfaces from the host instance passed when creating a document
+    /// controller.
+    ///
+    /// # Safety
+    ///
+    /// `host_instance` must point to a valid host instance, and the interfaces it points to must
+    /// remain valid for as long as the document controller exists.
+    pub(crate) unsafe fn new(host_instance: *const ARADocumentControllerHostInstance) -> Self {
+        let host_instance = &*host_instance;
+
+        Self {
+            inner: Arc::new(HostInner {
+                audio_access_controller: HostRef(host_instance.audio_access_controller_host_ref),
+                audio_access_interface: host_instance.audio_access_controller_interface,
+                model_update_controller: HostRef(host_instance.model_update_controller_host_ref),
+                model_update_interface: host_instance.model_update_controller_interface,
+                pending_notifications: Mutex::new(Vec::new()),
+            }),
+        }
+    }
+
+    /// Create a reader for an audio source's samples. Returns `None` if the host has not enabled
+    /// samples access for the audio source, or if the host failed to create the reader.
+    pub fn create_audio_reader(&self, audio_source: &AraAudioSource) -> Option<AraAudioReader> {
+        if !audio_source.samples_access_enabled {
+            nih_debug_assert_failure!(
+                "Tried to read from an audio source with samples access disabled"
+            );
+            return None;
+        }
+
+        let interface = unsafe { self.inner.audio_access_interface.as_ref()? };
+        let create_audio_reader_for_source = interface.create_audio_reader_for_source?;
+        let reader_ref = unsafe {
+            create_audio_reader_for_source(
+                self.inner.audio_access_controller.0,
+                audio_source.host_ref.0,
+                kARAFalse,
+            )
+        };
+        if reader_ref.is_null() {
+            return None;
+        }
+
+        Some(AraAudioReader {
+            host: self.clone(),
+            reader_ref: HostRef(reader_ref),
+            channel_count: audio_source.channel_count as usize,
+            channel_pointers: vec![ptr::null_mut(); audio_source.channel_count as usize],
+        })
+    }
+
+    /// Inform the host about an audio source's analysis progress. The notification is sent the
+    /// next time the host polls for model updates.
+    pub fn notify_analysis_progress(
+        &self,
+        audio_source: &AraAudioSource,
+        progress: AraAnalysisProgress,
+    ) {
+        self.inner
+            .pending_notifications
+            .lock()
+            .push(Notification::AnalysisProgress(
+                audio_source.host_ref,
+                progress,
+            ));
+    }
+
+    /// Inform the host that the plugin's analysis results for an audio source have changed. The
+    /// notification is sent the next time the host polls for model updates.
+    pub fn notify_audio_source_content_changed(&self, audio_source: &AraAudioSource) {
+        self.inner
+            .pending_notifications
+            .lock()
+            .push(Notification::AudioSourceContentChanged(
+                audio_source.host_ref,
+            ));
+    }
+
+    /// Inform the host that a playback region will sound different, for instance after the user
+    /// edited it in the plugin's editor. The notification is sent the next time the host polls for
+    /// model updates.
+    pub fn notify_playback_region_content_changed(&self, playback_region: &AraPlaybackRegion) {
+        self.inner
+            .pending_notifications
+            .lock()
+            .push(Notification::PlaybackRegionContentChanged(
+                playback_region.host_ref,
+            ));
+    }
+
+    /// Send all queued notifications to the host. Called from the host's `notifyModelUpdates()`
+    /// call on the main thread.
+    pub(crate) fn flush_notifications(&self) {
+        let notifications = std::mem::take(&mut *self.inner.pending_notifications.lock());
+        let Some(interface) = (unsafe { self.inner.model_update_interface.as_ref() }) else {
+            return;
+        };
+
+        let controller = self.inner.model_update_controller.0;
+        for notification in notifications {
+            unsafe {
+                match notification {
+                    Notification::AnalysisProgress(audio_source, progress) => {
+                        if let Some(notify) = interface.notify_audio_source_analysis_progress {
+                            let (state, value) = match progress {
+                                AraAnalysisProgress::Started => (kARAAnalysisProgressStarted, 0.0),
+                                AraAnalysisProgress::Updated(value) => {
+                                    (kARAAnalysisProgressUpdated, value.clamp(0.0, 1.0))
+                                }
+                                AraAnalysisProgress::Completed => {
+                                    (kARAAnalysisProgressCompleted, 1.0)
+                                }
+                            };
+                            notify(controller, audio_source.0, state, value);
+                        }
+                    }
+                    Notification::AudioSourceContentChanged(audio_source) => {
+                        if let Some(notify) = interface.notify_audio_source_content_changed {
+                            notify(
+                                controller,
+                                audio_source.0,
+                                ptr::null(),
+                                kARAContentUpdateEverythingChanged,
+                            );
+                        }
+                    }
+                    Notification::PlaybackRegionContentChanged(playback_region) => {
+                        if let Some(notify) = interface.notify_playback_region_content_changed {
+                            notify(
+                                controller,
+                                playback_region.0,
+                                ptr::null(),
+                                kARAContentUpdateEverythingChanged,
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl AraAudioReader {
+    /// Read samples starting at `position` into `buffers`, with one buffer per channel. All buffers
+    /// need to have the same length. Returns `false` if the host could not provide the samples, in
+    /// which case the buffers will contain silence.
+    pub fn read(&mut self, position: i64, buffers: &mut [&mut [f32]]) -> bool {
+        nih_debug_assert_eq!(buffers.len(), self.channel_count);
+        if buffers.len() != self.channel_count {
+            return false;
+        }
+        let Some(num_samples) = buffers.first().map(|buffer| buffer.len()) else {
+            return true;
+        };
+        nih_debug_assert!(buffers.iter().all(|buffer| buffer.len() == num_samples));
+
+        for (pointer, buffer) in self.channel_pointers.iter_mut().zip(buffers.iter_mut()) {
+            *pointer = buffer.as_mut_ptr() as *mut c_void;
+        }
+
+        let Some(read_audio_samples) = (unsafe { self.host.inner.audio_access_interface.as_ref() })
+            .and_then(|interface| interface.read_audio_samples)
+        else {
+            return false;
+        };
+        let success = unsafe {
+            read_audio_samples(
+                self.host.inner.audio_access_controller.0,
+                self.reader_ref.0,
+                position,
+                num_samples as i64,
+                self.channel_pointers.as_ptr(),
+            )
+        } != kARAFalse;
+        if !success {
+            for buffer in buffers.iter_mut() {
+                buffer.fill(0.0);
+            }
+        }
+
+        success
+    }
+}
+
+impl Drop for AraAudioReader {
+    fn drop(&mut self) {
+        let Some(destroy_audio_reader) =
+            (unsafe { self.host.inner.audio_access_interface.as_ref() })
+                .and_then(|interface| interface.destroy_audio_reader)
+        else {
+            return;
+        };
+
+        unsafe {
+            destroy_audio_reader(self.host.inner.audio_access_controller.0, self.reader_ref.0)
+        };
+    }
+}
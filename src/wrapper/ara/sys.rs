@@ -0,0 +1,626 @@
+//! Minimal bindings for the parts of the ARA 2 API used by the wrapper, based on the ARA SDK's
+//! `ARAInterface.h`. Only the structs the wrapper reads or fills in are defined in full. The
+//! remaining host interfaces are treated as opaque pointers.
+//!
+//! All function pointers use the C calling convention, and all structs are versioned through their
+//! `struct_size` fields. Hosts may pass older versions of these structs, so fields past the end of
+//! the provided struct size must not be read.
+
+#![allow(non_camel_case_types, non_upper_case_globals)]
+
+use std::os::raw::{c_char, c_float, c_void};
+
+pub type ARABool = u8;
+pub type ARAByte = u8;
+pub type ARASize = usize;
+pub type ARAInt32 = i32;
+pub type ARAAPIGeneration = i32;
+pub type ARAPersistentID = *const c_char;
+pub type ARAUtf8String = *const c_char;
+pub type ARASamplePosition = i64;
+pub type ARASampleCount = i64;
+pub type ARASampleRate = f64;
+pub type ARAChannelCount = i32;
+pub type ARATimePosition = f64;
+pub type ARATimeDuration = f64;
+pub type ARAContentType = ARAPersistentID;
+pub type ARAContentGrade = i32;
+pub type ARAContentUpdateFlags = u32;
+pub type ARAPlaybackTransformationFlags = i32;
+pub type ARAPlugInInstanceRoleFlags = u32;
+pub type ARAAnalysisProgressState = i32;
+
+pub const kARAFalse: ARABool = 0;
+pub const kARATrue: ARABool = 1;
+
+pub const kARAAPIGeneration_2_0_Final: ARAAPIGeneration = 4;
+
+pub const kARAPlaybackRendererRole: ARAPlugInInstanceRoleFlags = 1 << 0;
+pub const kARAEditorRendererRole: ARAPlugInInstanceRoleFlags = 1 << 1;
+pub const kARAEditorViewRole: ARAPlugInInstanceRoleFlags = 1 << 2;
+
+pub const kARAAnalysisProgressStarted: ARAAnalysisProgressState = 0;
+pub const kARAAnalysisProgressUpdated: ARAAnalysisProgressState = 1;
+pub const kARAAnalysisProgressCompleted: ARAAnalysisProgressState = 2;
+
+pub const kARAContentUpdateEverythingChanged: ARAContentUpdateFlags = 0;
+
+// All of these refs are opaque pointers. The `*Ref` types are handed out by the plugin, and the
+// `*HostRef` types are handed out by the host.
+pub type ARADocumentControllerRef = *mut c_void;
+pub type ARAMusicalContextRef = *mut c_void;
+pub type ARARegionSequenceRef = *mut c_void;
+pub type ARAAudioSourceRef = *mut c_void;
+pub type ARAAudioModificationRef = *mut c_void;
+pub type ARAPlaybackRegionRef = *mut c_void;
+pub type ARAContentReaderRef = *mut c_void;
+pub type ARAPlaybackRendererRef = *mut c_void;
+pub type ARAEditorRendererRef = *mut c_void;
+pub type ARAEditorViewRef = *mut c_void;
+
+pub type ARAAudioAccessControllerHostRef = *mut c_void;
+pub type ARAArchivingControllerHostRef = *mut c_void;
+pub type ARAContentAccessControllerHostRef = *mut c_void;
+pub type ARAModelUpdateControllerHostRef = *mut c_void;
+pub type ARAPlaybackControllerHostRef = *mut c_void;
+pub type ARAMusicalContextHostRef = *mut c_void;
+pub type ARARegionSequenceHostRef = *mut c_void;
+pub type ARAAudioSourceHostRef = *mut c_void;
+pub type ARAAudioModificationHostRef = *mut c_void;
+pub type ARAPlaybackRegionHostRef = *mut c_void;
+pub type ARAAudioReaderHostRef = *mut c_void;
+pub type ARAArchiveReaderHostRef = *mut c_void;
+pub type ARAArchiveWriterHostRef = *mut c_void;
+
+/// Opaque structs the wrapper never reads.
+pub type ARAMusicalContextProperties = c_void;
+pub type ARARegionSequenceProperties = c_void;
+pub type ARAContentTimeRange = c_void;
+pub type ARAProcessingAlgorithmProperties = c_void;
+pub type ARAViewSelection = c_void;
+pub type ARAContentAccessControllerInterface = c_void;
+pub type ARAPlaybackControllerInterface = c_void;
+
+#[repr(C)]
+pub struct ARAInterfaceConfiguration {
+    pub struct_size: ARASize,
+    pub desired_api_generation: ARAAPIGeneration,
+    pub assert_function_address: *mut c_void,
+}
+
+#[repr(C)]
+pub struct ARAFactory {
+    pub struct_size: ARASize,
+    pub lowest_supported_api_generation: ARAAPIGeneration,
+    pub highest_supported_api_generation: ARAAPIGeneration,
+    pub factory_id: ARAPersistentID,
+    pub initialize_ara_with_configuration:
+        Option<unsafe extern "C" fn(config: *const ARAInterfaceConfiguration)>,
+    pub uninitialize_ara: Option<unsafe extern "C" fn()>,
+    pub plug_in_name: ARAUtf8String,
+    pub manufacturer_name: ARAUtf8String,
+    pub information_url: ARAUtf8String,
+    pub version: ARAUtf8String,
+    pub create_document_controller_with_document: Option<
+        unsafe extern "C" fn(
+            host_instance: *const ARADocumentControllerHostInstance,
+            properties: *const ARADocumentProperties,
+        ) -> *const ARADocumentControllerInstance,
+    >,
+    pub document_archive_id: ARAPersistentID,
+    pub compatible_document_archive_ids_count: ARASize,
+    pub compatible_document_archive_ids: *const ARAPersistentID,
+    pub analyzeable_content_types_count: ARASize,
+    pub analyzeable_content_types: *const ARAContentType,
+    pub supported_playback_transformation_flags: ARAPlaybackTransformationFlags,
+    pub supports_storing_audio_file_chunks: ARABool,
+}
+
+#[repr(C)]
+pub struct ARADocumentProperties {
+    pub struct_size: ARASize,
+    pub name: ARAUtf8String,
+}
+
+#[repr(C)]
+pub struct ARAAudioSourceProperties {
+    pub struct_size: ARASize,
+    pub name: ARAUtf8String,
+    pub persistent_id: ARAPersistentID,
+    pub sample_count: ARASampleCount,
+    pub sample_rate: ARASampleRate,
+    pub channel_count: ARAChannelCount,
+    pub merits_64_bit_samples: ARABool,
+}
+
+#[repr(C)]
+pub struct ARAAudioModificationProperties {
+    pub struct_size: ARASize,
+    pub name: ARAUtf8String,
+    pub persistent_id: ARAPersistentID,
+}
+
+#[repr(C)]
+pub struct ARAPlaybackRegionProperties {
+    pub struct_size: ARASize,
+    pub transformation_flags: ARAPlaybackTransformationFlags,
+    pub start_in_modification_time: ARATimePosition,
+    pub duration_in_modification_time: ARATimeDuration,
+    pub start_in_playback_time: ARATimePosition,
+    pub duration_in_playback_time: ARATimeDuration,
+    pub musical_context_ref: ARAMusicalContextRef,
+    pub region_sequence_ref: ARARegionSequenceRef,
+    pub name: ARAUtf8String,
+}
+
+#[repr(C)]
+pub struct ARARestoreObjectsFilter {
+    pub struct_size: ARASize,
+    pub document_data: ARABool,
+    pub audio_source_archive_ids_count: ARASize,
+    pub audio_source_archive_ids: *const ARAPersistentID,
+    pub audio_source_current_ids: *const ARAPersistentID,
+    pub audio_modification_archive_ids_count: ARASize,
+    pub audio_modification_archive_ids: *const ARAPersistentID,
+    pub audio_modification_current_ids: *const ARAPersistentID,
+}
+
+#[repr(C)]
+pub struct ARAStoreObjectsFilter {
+    pub struct_size: ARASize,
+    pub document_data: ARABool,
+    pub audio_source_refs_count: ARASize,
+    pub audio_source_refs: *const ARAAudioSourceRef,
+    pub audio_modification_refs_count: ARASize,
+    pub audio_modification_refs: *const ARAAudioModificationRef,
+}
+
+#[repr(C)]
+pub struct ARADocumentControllerHostInstance {
+    pub struct_size: ARASize,
+    pub audio_access_controller_host_ref: ARAAudioAccessControllerHostRef,
+    pub audio_access_controller_interface: *const ARAAudioAccessControllerInterface,
+    pub archiving_controller_host_ref: ARAArchivingControllerHostRef,
+    pub archiving_controller_interface: *const ARAArchivingControllerInterface,
+    pub content_access_controller_host_ref: ARAContentAccessControllerHostRef,
+    pub content_access_controller_interface: *const ARAContentAccessControllerInterface,
+    pub model_update_controller_host_ref: ARAModelUpdateControllerHostRef,
+    pub model_update_controller_interface: *const ARAModelUpdateControllerInterface,
+    pub playback_controller_host_ref: ARAPlaybackControllerHostRef,
+    pub playback_controller_interface: *const ARAPlaybackControllerInterface,
+}
+
+#[repr(C)]
+pub struct ARAAudioAccessControllerInterface {
+    pub struct_size: ARASize,
+    pub create_audio_reader_for_source: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAAudioAccessControllerHostRef,
+            audio_source_host_ref: ARAAudioSourceHostRef,
+            use_64_bit_samples: ARABool,
+        ) -> ARAAudioReaderHostRef,
+    >,
+    pub read_audio_samples: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAAudioAccessControllerHostRef,
+            audio_reader_host_ref: ARAAudioReaderHostRef,
+            sample_position: ARASamplePosition,
+            samples_per_channel: ARASampleCount,
+            buffers: *const *mut c_void,
+        ) -> ARABool,
+    >,
+    pub destroy_audio_reader: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAAudioAccessControllerHostRef,
+            audio_reader_host_ref: ARAAudioReaderHostRef,
+        ),
+    >,
+}
+
+#[repr(C)]
+pub struct ARAArchivingControllerInterface {
+    pub struct_size: ARASize,
+    pub get_archive_size: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAArchivingControllerHostRef,
+            archive_reader_host_ref: ARAArchiveReaderHostRef,
+        ) -> ARASize,
+    >,
+    pub read_bytes_from_archive: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAArchivingControllerHostRef,
+            archive_reader_host_ref: ARAArchiveReaderHostRef,
+            position: ARASize,
+            length: ARASize,
+            buffer: *mut ARAByte,
+        ) -> ARABool,
+    >,
+    pub write_bytes_to_archive: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAArchivingControllerHostRef,
+            archive_writer_host_ref: ARAArchiveWriterHostRef,
+            position: ARASize,
+            length: ARASize,
+            buffer: *const ARAByte,
+        ) -> ARABool,
+    >,
+    pub notify_document_archiving_progress: Option<
+        unsafe extern "C" fn(controller_host_ref: ARAArchivingControllerHostRef, value: c_float),
+    >,
+    pub notify_document_unarchiving_progress: Option<
+        unsafe extern "C" fn(controller_host_ref: ARAArchivingControllerHostRef, value: c_float),
+    >,
+    pub get_document_archive_id: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAArchivingControllerHostRef,
+            archive_reader_host_ref: ARAArchiveReaderHostRef,
+        ) -> ARAPersistentID,
+    >,
+}
+
+#[repr(C)]
+pub struct ARAModelUpdateControllerInterface {
+    pub struct_size: ARASize,
+    pub notify_audio_source_analysis_progress: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAModelUpdateControllerHostRef,
+            audio_source_host_ref: ARAAudioSourceHostRef,
+            state: ARAAnalysisProgressState,
+            value: c_float,
+        ),
+    >,
+    pub notify_audio_source_content_changed: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAModelUpdateControllerHostRef,
+            audio_source_host_ref: ARAAudioSourceHostRef,
+            range: *const ARAContentTimeRange,
+            flags: ARAContentUpdateFlags,
+        ),
+    >,
+    pub notify_audio_modification_content_changed: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAModelUpdateControllerHostRef,
+            audio_modification_host_ref: ARAAudioModificationHostRef,
+            range: *const ARAContentTimeRange,
+            flags: ARAContentUpdateFlags,
+        ),
+    >,
+    pub notify_playback_region_content_changed: Option<
+        unsafe extern "C" fn(
+            controller_host_ref: ARAModelUpdateControllerHostRef,
+            playback_region_host_ref: ARAPlaybackRegionHostRef,
+            range: *const ARAContentTimeRange,
+            flags: ARAContentUpdateFlags,
+        ),
+    >,
+}
+
+#[repr(C)]
+pub struct ARADocumentControllerInstance {
+    pub struct_size: ARASize,
+    pub document_controller_ref: ARADocumentControllerRef,
+    pub document_controller_interface: *const ARADocumentControllerInterface,
+}
+
+#[repr(C)]
+pub struct ARADocumentControllerInterface {
+    pub struct_size: ARASize,
+    pub destroy_document_controller: Option<unsafe extern "C" fn(ARADocumentControllerRef)>,
+    pub get_factory: Option<unsafe extern "C" fn(ARADocumentControllerRef) -> *const ARAFactory>,
+
+    pub begin_editing: Option<unsafe extern "C" fn(ARADocumentControllerRef)>,
+    pub end_editing: Option<unsafe extern "C" fn(ARADocumentControllerRef)>,
+    pub notify_model_updates: Option<unsafe extern "C" fn(ARADocumentControllerRef)>,
+
+    pub begin_restoring_document_from_archive:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAArchiveReaderHostRef) -> ARABool>,
+    pub end_restoring_document_from_archive:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAArchiveReaderHostRef) -> ARABool>,
+    pub store_document_to_archive:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAArchiveWriterHostRef) -> ARABool>,
+
+    pub update_document_properties:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, *const ARADocumentProperties)>,
+
+    pub create_musical_context: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAMusicalContextHostRef,
+            *const ARAMusicalContextProperties,
+        ) -> ARAMusicalContextRef,
+    >,
+    pub update_musical_context_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAMusicalContextRef,
+            *const ARAMusicalContextProperties,
+        ),
+    >,
+    pub update_musical_context_content: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAMusicalContextRef,
+            *const ARAContentTimeRange,
+            ARAContentUpdateFlags,
+        ),
+    >,
+    pub destroy_musical_context:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAMusicalContextRef)>,
+
+    pub create_audio_source: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceHostRef,
+            *const ARAAudioSourceProperties,
+        ) -> ARAAudioSourceRef,
+    >,
+    pub update_audio_source_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            *const ARAAudioSourceProperties,
+        ),
+    >,
+    pub update_audio_source_content: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            *const ARAContentTimeRange,
+            ARAContentUpdateFlags,
+        ),
+    >,
+    pub enable_audio_source_samples_access:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioSourceRef, ARABool)>,
+    pub deactivate_audio_source_for_undo_history:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioSourceRef, ARABool)>,
+    pub destroy_audio_source:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioSourceRef)>,
+
+    pub create_audio_modification: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARAAudioModificationHostRef,
+            *const ARAAudioModificationProperties,
+        ) -> ARAAudioModificationRef,
+    >,
+    pub clone_audio_modification: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            ARAAudioModificationHostRef,
+            *const ARAAudioModificationProperties,
+        ) -> ARAAudioModificationRef,
+    >,
+    pub update_audio_modification_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            *const ARAAudioModificationProperties,
+        ),
+    >,
+    pub deactivate_audio_modification_for_undo_history:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioModificationRef, ARABool)>,
+    pub destroy_audio_modification:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioModificationRef)>,
+
+    pub create_playback_region: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            ARAPlaybackRegionHostRef,
+            *const ARAPlaybackRegionProperties,
+        ) -> ARAPlaybackRegionRef,
+    >,
+    pub update_playback_region_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAPlaybackRegionRef,
+            *const ARAPlaybackRegionProperties,
+        ),
+    >,
+    pub destroy_playback_region:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAPlaybackRegionRef)>,
+
+    pub is_audio_source_content_available: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARAContentType,
+        ) -> ARABool,
+    >,
+    pub is_audio_source_content_analysis_incomplete: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARAContentType,
+        ) -> ARABool,
+    >,
+    pub request_audio_source_content_analysis: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARASize,
+            *const ARAContentType,
+        ),
+    >,
+    pub get_audio_source_content_grade: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARAContentType,
+        ) -> ARAContentGrade,
+    >,
+    pub create_audio_source_content_reader: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioSourceRef,
+            ARAContentType,
+            *const ARAContentTimeRange,
+        ) -> ARAContentReaderRef,
+    >,
+
+    pub is_audio_modification_content_available: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            ARAContentType,
+        ) -> ARABool,
+    >,
+    pub get_audio_modification_content_grade: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            ARAContentType,
+        ) -> ARAContentGrade,
+    >,
+    pub create_audio_modification_content_reader: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAAudioModificationRef,
+            ARAContentType,
+            *const ARAContentTimeRange,
+        ) -> ARAContentReaderRef,
+    >,
+
+    pub is_playback_region_content_available: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAPlaybackRegionRef,
+            ARAContentType,
+        ) -> ARABool,
+    >,
+    pub get_playback_region_content_grade: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAPlaybackRegionRef,
+            ARAContentType,
+        ) -> ARAContentGrade,
+    >,
+    pub create_playback_region_content_reader: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAPlaybackRegionRef,
+            ARAContentType,
+            *const ARAContentTimeRange,
+        ) -> ARAContentReaderRef,
+    >,
+
+    pub get_content_reader_event_count:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAContentReaderRef) -> ARAInt32>,
+    pub get_content_reader_data_for_event: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAContentReaderRef,
+            ARAInt32,
+        ) -> *const c_void,
+    >,
+    pub destroy_content_reader:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAContentReaderRef)>,
+
+    // These were added in ARA 2.0
+    pub create_region_sequence: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARARegionSequenceHostRef,
+            *const ARARegionSequenceProperties,
+        ) -> ARARegionSequenceRef,
+    >,
+    pub update_region_sequence_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARARegionSequenceRef,
+            *const ARARegionSequenceProperties,
+        ),
+    >,
+    pub destroy_region_sequence:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARARegionSequenceRef)>,
+
+    pub get_playback_region_head_and_tail_time: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAPlaybackRegionRef,
+            *mut ARATimeDuration,
+            *mut ARATimeDuration,
+        ),
+    >,
+
+    pub restore_objects_from_archive: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAArchiveReaderHostRef,
+            *const ARARestoreObjectsFilter,
+        ) -> ARABool,
+    >,
+    pub store_objects_to_archive: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAArchiveWriterHostRef,
+            *const ARAStoreObjectsFilter,
+        ) -> ARABool,
+    >,
+
+    pub get_processing_algorithms_count:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef) -> ARAInt32>,
+    pub get_processing_algorithm_properties: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARAInt32,
+        ) -> *const ARAProcessingAlgorithmProperties,
+    >,
+    pub get_processing_algorithm_for_audio_source:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioSourceRef) -> ARAInt32>,
+    pub request_processing_algorithm_for_audio_source:
+        Option<unsafe extern "C" fn(ARADocumentControllerRef, ARAAudioSourceRef, ARAInt32)>,
+
+    pub is_licensed_for_capabilities: Option<
+        unsafe extern "C" fn(
+            ARADocumentControllerRef,
+            ARABool,
+            ARASize,
+            *const ARAContentType,
+            ARAPlaybackTransformationFlags,
+        ) -> ARABool,
+    >,
+}
+
+#[repr(C)]
+pub struct ARAPlugInExtensionInstance {
+    pub struct_size: ARASize,
+    pub playback_renderer_ref: ARAPlaybackRendererRef,
+    pub playback_renderer_interface: *const ARAPlaybackRendererInterface,
+    pub editor_renderer_ref: ARAEditorRendererRef,
+    pub editor_renderer_interface: *const ARAEditorRendererInterface,
+    pub editor_view_ref: ARAEditorViewRef,
+    pub editor_view_interface: *const ARAEditorViewInterface,
+}
+
+#[repr(C)]
+pub struct ARAPlaybackRendererInterface {
+    pub struct_size: ARASize,
+    pub add_playback_region:
+        Option<unsafe extern "C" fn(ARAPlaybackRendererRef, ARAPlaybackRegionRef)>,
+    pub remove_playback_region:
+        Option<unsafe extern "C" fn(ARAPlaybackRendererRef, ARAPlaybackRegionRef)>,
+}
+
+#[repr(C)]
+pub struct ARAEditorRendererInterface {
+    pub struct_size: ARASize,
+    pub add_playback_region:
+        Option<unsafe extern "C" fn(ARAEditorRendererRef, ARAPlaybackRegionRef)>,
+    pub remove_playback_region:
+        Option<unsafe extern "C" fn(ARAEditorRendererRef, ARAPlaybackRegionRef)>,
+    pub add_region_sequence:
+        Option<unsafe extern "C" fn(ARAEditorRendererRef, ARARegionSequenceRef)>,
+    pub remove_region_sequence:
+        Option<unsafe extern "C" fn(ARAEditorRendererRef, ARARegionSequenceRef)>,
+}
+
+#[repr(C)]
+pub struct ARAEditorViewInterface {
+    pub struct_size: ARASize,
+    pub notify_selection: Option<unsafe extern "C" fn(ARAEditorViewRef, *const ARAViewSelection)>,
+    pub notify_hide_region_sequences:
+        Option<unsafe extern "C" fn(ARAEditorViewRef, ARASize, *const ARARegionSequenceRef)>,
+}
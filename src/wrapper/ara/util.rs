@@ -0,0 +1,16 @@
+use std::ffi::CStr;
+use std::os::raw::c_char;
+
+/// Convert a possibly null UTF-8 string passed by the host to an owned string. Invalid UTF-8 is
+/// replaced with the Unicode replacement character.
+///
+/// # Safety
+///
+/// `string` must either be null or point to a null terminated string.
+pub unsafe fn string_from_ptr(string: *const c_char) -> Option<String> {
+    if string.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(string).to_string_lossy().into_owned())
+    }
+}
@@ -0,0 +1,40 @@
+//! An Audio Unit v2 wrapper for macOS. This implements the component based AUv2 C API directly, so
+//! plugins can be used in hosts like Logic and GarageBand without a CLAP to AU bridge.
+//!
+//! The wrapper currently has the following limitations:
+//!
+//! - Editors are not supported, so hosts will show a generic UI for the plugin's parameters.
+//! - Only the main input and output ports are exposed. Auxiliary inputs will be silent.
+//! - Plugins cannot send MIDI.
+//! - A library can only export a single Audio Unit, since the bundle's `Info.plist` file refers to
+//!   a single factory function.
+
+#[cfg(target_os = "macos")]
+mod context;
+#[cfg(target_os = "macos")]
+mod sys;
+#[cfg(target_os = "macos")]
+mod wrapper;
+
+/// Re-export for the macro
+#[cfg(target_os = "macos")]
+pub use wrapper::Wrapper;
+
+/// Export an Audio Unit v2 plugin from this library using the provided plugin type. The plugin
+/// needs to implement [`AuPlugin`][crate::prelude::AuPlugin], and the bundler will register the
+/// component using the type codes from the package's `bundler.toml` entry. This does nothing on
+/// platforms other than macOS.
+#[macro_export]
+macro_rules! nih_export_au {
+    ($plugin_ty:ty) => {
+        /// The factory function referenced by the bundle's `Info.plist` file.
+        #[allow(non_snake_case)]
+        #[no_mangle]
+        #[cfg(target_os = "macos")]
+        pub unsafe extern "C" fn NihPlugAuFactory(
+            desc: *const ::std::ffi::c_void,
+        ) -> *mut ::std::ffi::c_void {
+            $crate::wrapper::au::Wrapper::<$plugin_ty>::factory(desc)
+        }
+    };
+}
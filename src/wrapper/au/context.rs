@@ -0,0 +1,118 @@
+use atomic_refcell::AtomicRefMut;
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use super::wrapper::{Task, Wrapper};
+use crate::prelude::{
+    AuPlugin, InitContext, PluginApi, PluginNoteEvent, ProcessContext, ProcessMode, TrackInfo,
+    Transport,
+};
+use crate::wrapper::util::{equal_temperament_frequency, tail_seconds_to_samples};
+
+/// An [`InitContext`] implementation for the wrapper.
+pub(crate) struct WrapperInitContext<'a, P: AuPlugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+}
+
+/// A [`ProcessContext`] implementation for the wrapper. This is a separate object so it can hold on
+/// to lock guards for event queues. Otherwise reading these events would require constant
+/// unnecessary atomic operations to lock the uncontested locks.
+pub(crate) struct WrapperProcessContext<'a, P: AuPlugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+    pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) transport: Transport,
+    /// The number of auxiliary inputs in the current audio IO layout. Used for
+    /// [`ProcessContext::bus_active()`].
+    pub(super) aux_input_count: usize,
+}
+
+impl<P: AuPlugin> InitContext<P> for WrapperInitContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Au
+    }
+
+    fn execute(&self, task: P::BackgroundTask) {
+        (self.wrapper.task_executor.lock())(task);
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+}
+
+impl<P: AuPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Au
+    }
+
+    fn execute_background(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn execute_gui(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_gui(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.wrapper.cancel_background_tasks(tag);
+    }
+
+    #[inline]
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn process_mode(&self) -> ProcessMode {
+        self.wrapper.current_process_mode.load()
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        // Audio Units don't have a way to query this
+        None
+    }
+
+    fn bypassed(&self) -> bool {
+        self.wrapper.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.aux_input_count
+    }
+
+    fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
+        self.input_events_guard.pop_front()
+    }
+
+    fn send_event(&mut self, _event: PluginNoteEvent<P>) {
+        // MIDI output is not supported by the Audio Unit wrapper
+        nih_debug_assert_failure!("The Audio Unit wrapper does not support MIDI output");
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
+        // This is only supported by CLAP
+        equal_temperament_frequency(note)
+    }
+
+    fn set_tail_seconds(&self, seconds: f32) {
+        self.wrapper
+            .set_tail_samples(tail_seconds_to_samples(seconds, self.transport.sample_rate))
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, _handler: impl FnMut(T)) {
+        // The Audio Unit wrapper does not support editors, so there are never any GUI messages
+    }
+}
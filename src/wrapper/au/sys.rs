@@ -0,0 +1,428 @@
+//! Bindings for the parts of the AudioToolbox Audio Unit v2 C API used by the wrapper. These are
+//! transcribed from `AudioComponent.h`, `AUComponent.h`, `AudioUnitProperties.h`, and
+//! `MusicDevice.h`. The names match the C headers to make cross-referencing them easier.
+
+#![allow(non_camel_case_types, non_snake_case, non_upper_case_globals)]
+
+use core_foundation::string::CFStringRef;
+use std::os::raw::c_void;
+
+pub type OSStatus = i32;
+pub type Boolean = u8;
+
+pub type AudioComponentInstance = *mut c_void;
+pub type AudioUnit = AudioComponentInstance;
+pub type AudioUnitPropertyID = u32;
+pub type AudioUnitScope = u32;
+pub type AudioUnitElement = u32;
+pub type AudioUnitParameterID = u32;
+pub type AudioUnitParameterValue = f32;
+pub type AudioUnitRenderActionFlags = u32;
+
+/// The function pointer type returned from `Lookup`. Hosts cast these to the selector's actual
+/// function signature before calling them, so the concrete functions get transmuted into this type.
+pub type AudioComponentMethod = unsafe extern "C" fn(this: *mut c_void, ...) -> OSStatus;
+
+pub type AudioUnitPropertyListenerProc = unsafe extern "C" fn(
+    in_ref_con: *mut c_void,
+    in_unit: AudioUnit,
+    in_id: AudioUnitPropertyID,
+    in_scope: AudioUnitScope,
+    in_element: AudioUnitElement,
+);
+
+pub type AURenderCallback = unsafe extern "C" fn(
+    in_ref_con: *mut c_void,
+    io_action_flags: *mut AudioUnitRenderActionFlags,
+    in_time_stamp: *const AudioTimeStamp,
+    in_bus_number: u32,
+    in_number_frames: u32,
+    io_data: *mut AudioBufferList,
+) -> OSStatus;
+
+pub type HostCallback_GetBeatAndTempo = Option<
+    unsafe extern "C" fn(
+        in_host_user_data: *mut c_void,
+        out_current_beat: *mut f64,
+        out_current_tempo: *mut f64,
+    ) -> OSStatus,
+>;
+pub type HostCallback_GetMusicalTimeLocation = Option<
+    unsafe extern "C" fn(
+        in_host_user_data: *mut c_void,
+        out_delta_sample_offset_to_next_beat: *mut u32,
+        out_time_sig_numerator: *mut f32,
+        out_time_sig_denominator: *mut u32,
+        out_current_measure_down_beat: *mut f64,
+    ) -> OSStatus,
+>;
+pub type HostCallback_GetTransportState = Option<
+    unsafe extern "C" fn(
+        in_host_user_data: *mut c_void,
+        out_is_playing: *mut Boolean,
+        out_transport_state_changed: *mut Boolean,
+        out_current_sample_in_time_line: *mut f64,
+        out_is_cycling: *mut Boolean,
+        out_cycle_start_beat: *mut f64,
+        out_cycle_end_beat: *mut f64,
+    ) -> OSStatus,
+>;
+pub type HostCallback_GetTransportState2 = Option<
+    unsafe extern "C" fn(
+        in_host_user_data: *mut c_void,
+        out_is_playing: *mut Boolean,
+        out_is_recording: *mut Boolean,
+        out_transport_state_changed: *mut Boolean,
+        out_current_sample_in_time_line: *mut f64,
+        out_is_cycling: *mut Boolean,
+        out_cycle_start_beat: *mut f64,
+        out_cycle_end_beat: *mut f64,
+    ) -> OSStatus,
+>;
+
+#[repr(C)]
+pub struct AudioComponentPlugInInterface {
+    pub Open: unsafe extern "C" fn(this: *mut c_void, instance: AudioComponentInstance) -> OSStatus,
+    pub Close: unsafe extern "C" fn(this: *mut c_void) -> OSStatus,
+    pub Lookup: unsafe extern "C" fn(selector: i16) -> Option<AudioComponentMethod>,
+    pub reserved: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioComponentDescription {
+    pub componentType: u32,
+    pub componentSubType: u32,
+    pub componentManufacturer: u32,
+    pub componentFlags: u32,
+    pub componentFlagsMask: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioStreamBasicDescription {
+    pub mSampleRate: f64,
+    pub mFormatID: u32,
+    pub mFormatFlags: u32,
+    pub mBytesPerPacket: u32,
+    pub mFramesPerPacket: u32,
+    pub mBytesPerFrame: u32,
+    pub mChannelsPerFrame: u32,
+    pub mBitsPerChannel: u32,
+    pub mReserved: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioBuffer {
+    pub mNumberChannels: u32,
+    pub mDataByteSize: u32,
+    pub mData: *mut c_void,
+}
+
+/// The C struct uses a flexible array member for `mBuffers`. See [`OwnedAudioBufferList`] for
+/// allocating one of these with room for more than a single buffer.
+#[repr(C)]
+pub struct AudioBufferList {
+    pub mNumberBuffers: u32,
+    pub mBuffers: [AudioBuffer; 1],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SMPTETime {
+    pub mSubframes: i16,
+    pub mSubframeDivisor: i16,
+    pub mCounter: u32,
+    pub mType: u32,
+    pub mFlags: u32,
+    pub mHours: i16,
+    pub mMinutes: i16,
+    pub mSeconds: i16,
+    pub mFrames: i16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AudioTimeStamp {
+    pub mSampleTime: f64,
+    pub mHostTime: u64,
+    pub mRateScalar: f64,
+    pub mWordClockTime: u64,
+    pub mSMPTETime: SMPTETime,
+    pub mFlags: u32,
+    pub mReserved: u32,
+}
+
+#[repr(C)]
+pub struct AudioUnitParameterInfo {
+    pub name: [std::os::raw::c_char; 52],
+    pub unitName: CFStringRef,
+    pub clumpID: u32,
+    pub cfNameString: CFStringRef,
+    pub unit: u32,
+    pub minValue: AudioUnitParameterValue,
+    pub maxValue: AudioUnitParameterValue,
+    pub defaultValue: AudioUnitParameterValue,
+    pub flags: u32,
+}
+
+#[repr(C)]
+pub struct AudioUnitParameterStringFromValue {
+    pub inParamID: AudioUnitParameterID,
+    pub inValue: *const AudioUnitParameterValue,
+    pub outString: CFStringRef,
+}
+
+#[repr(C)]
+pub struct AudioUnitParameterValueFromString {
+    pub inParamID: AudioUnitParameterID,
+    pub inString: CFStringRef,
+    pub outValue: AudioUnitParameterValue,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioUnitParameterEventRamp {
+    pub startBufferOffset: i32,
+    pub durationInFrames: u32,
+    pub startValue: AudioUnitParameterValue,
+    pub endValue: AudioUnitParameterValue,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioUnitParameterEventImmediate {
+    pub bufferOffset: u32,
+    pub value: AudioUnitParameterValue,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub union AudioUnitParameterEventValues {
+    pub ramp: AudioUnitParameterEventRamp,
+    pub immediate: AudioUnitParameterEventImmediate,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct AudioUnitParameterEvent {
+    pub scope: AudioUnitScope,
+    pub element: AudioUnitElement,
+    pub parameter: AudioUnitParameterID,
+    pub eventType: u32,
+    pub eventValues: AudioUnitParameterEventValues,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AUChannelInfo {
+    pub inChannels: i16,
+    pub outChannels: i16,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AURenderCallbackStruct {
+    pub inputProc: Option<AURenderCallback>,
+    pub inputProcRefCon: *mut c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioUnitConnection {
+    pub sourceAudioUnit: AudioUnit,
+    pub sourceOutputNumber: u32,
+    pub destInputNumber: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct HostCallbackInfo {
+    pub hostUserData: *mut c_void,
+    pub beatAndTempoProc: HostCallback_GetBeatAndTempo,
+    pub musicalTimeLocationProc: HostCallback_GetMusicalTimeLocation,
+    pub transportStateProc: HostCallback_GetTransportState,
+    pub transportStateProc2: HostCallback_GetTransportState2,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct AudioUnitParameter {
+    pub mAudioUnit: AudioUnit,
+    pub mParameterID: AudioUnitParameterID,
+    pub mScope: AudioUnitScope,
+    pub mElement: AudioUnitElement,
+}
+
+#[repr(C)]
+pub struct AUPreset {
+    pub presetNumber: i32,
+    pub presetName: CFStringRef,
+}
+
+#[link(name = "AudioToolbox", kind = "framework")]
+extern "C" {
+    pub fn AudioUnitRender(
+        in_unit: AudioUnit,
+        io_action_flags: *mut AudioUnitRenderActionFlags,
+        in_time_stamp: *const AudioTimeStamp,
+        in_output_bus_number: u32,
+        in_number_frames: u32,
+        io_data: *mut AudioBufferList,
+    ) -> OSStatus;
+
+    pub fn AUParameterListenerNotify(
+        in_sending_listener: *mut c_void,
+        in_sending_object: *mut c_void,
+        in_parameter: *const AudioUnitParameter,
+    ) -> OSStatus;
+}
+
+/// An [`AudioBufferList`] with room for a fixed number of buffers.
+pub struct OwnedAudioBufferList {
+    /// The list's header followed by its buffers. `AudioBuffer` is 16 bytes and the header is
+    /// padded to 8 bytes, so this can be stored as `u64`s while keeping the correct alignment.
+    storage: Vec<u64>,
+}
+
+impl OwnedAudioBufferList {
+    pub fn new(num_buffers: usize) -> Self {
+        let mut list = Self {
+            storage: vec![0; 1 + (num_buffers.max(1) * 2)],
+        };
+        unsafe { (*list.as_mut_ptr()).mNumberBuffers = num_buffers as u32 };
+
+        list
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut AudioBufferList {
+        self.storage.as_mut_ptr() as *mut AudioBufferList
+    }
+
+    pub fn buffers_mut(&mut self) -> &mut [AudioBuffer] {
+        unsafe {
+            let list = self.as_mut_ptr();
+            std::slice::from_raw_parts_mut(
+                (*list).mBuffers.as_mut_ptr(),
+                (*list).mNumberBuffers as usize,
+            )
+        }
+    }
+}
+
+/// Get the buffers from a host provided [`AudioBufferList`].
+///
+/// # Safety
+///
+/// `list` must point to a valid `AudioBufferList`, and the returned slice must not outlive it.
+pub unsafe fn audio_buffer_list_buffers<'a>(list: *mut AudioBufferList) -> &'a mut [AudioBuffer] {
+    std::slice::from_raw_parts_mut(
+        (*list).mBuffers.as_mut_ptr(),
+        (*list).mNumberBuffers as usize,
+    )
+}
+
+/// Convert a four character code to the integer representation used by the Audio Unit API.
+pub const fn four_cc(code: [u8; 4]) -> u32 {
+    u32::from_be_bytes(code)
+}
+
+pub const noErr: OSStatus = 0;
+pub const kAudio_ParamError: OSStatus = -50;
+pub const badComponentSelector: OSStatus = 0x80008002_u32 as i32;
+
+pub const kAudioUnitErr_InvalidProperty: OSStatus = -10879;
+pub const kAudioUnitErr_InvalidParameter: OSStatus = -10878;
+pub const kAudioUnitErr_InvalidElement: OSStatus = -10877;
+pub const kAudioUnitErr_NoConnection: OSStatus = -10876;
+pub const kAudioUnitErr_FailedInitialization: OSStatus = -10875;
+pub const kAudioUnitErr_TooManyFramesToProcess: OSStatus = -10874;
+pub const kAudioUnitErr_InvalidFile: OSStatus = -10871;
+pub const kAudioUnitErr_FormatNotSupported: OSStatus = -10868;
+pub const kAudioUnitErr_Uninitialized: OSStatus = -10867;
+pub const kAudioUnitErr_InvalidScope: OSStatus = -10866;
+pub const kAudioUnitErr_PropertyNotWritable: OSStatus = -10865;
+pub const kAudioUnitErr_CannotDoInCurrentContext: OSStatus = -10863;
+pub const kAudioUnitErr_InvalidPropertyValue: OSStatus = -10851;
+pub const kAudioUnitErr_Initialized: OSStatus = -10849;
+
+pub const kAudioUnitInitializeSelect: i16 = 0x0001;
+pub const kAudioUnitUninitializeSelect: i16 = 0x0002;
+pub const kAudioUnitGetPropertyInfoSelect: i16 = 0x0003;
+pub const kAudioUnitGetPropertySelect: i16 = 0x0004;
+pub const kAudioUnitSetPropertySelect: i16 = 0x0005;
+pub const kAudioUnitGetParameterSelect: i16 = 0x0006;
+pub const kAudioUnitSetParameterSelect: i16 = 0x0007;
+pub const kAudioUnitResetSelect: i16 = 0x0009;
+pub const kAudioUnitAddPropertyListenerSelect: i16 = 0x000A;
+pub const kAudioUnitRemovePropertyListenerSelect: i16 = 0x000B;
+pub const kAudioUnitRenderSelect: i16 = 0x000E;
+pub const kAudioUnitAddRenderNotifySelect: i16 = 0x000F;
+pub const kAudioUnitRemoveRenderNotifySelect: i16 = 0x0010;
+pub const kAudioUnitScheduleParametersSelect: i16 = 0x0011;
+pub const kAudioUnitRemovePropertyListenerWithUserDataSelect: i16 = 0x0012;
+pub const kMusicDeviceMIDIEventSelect: i16 = 0x0101;
+pub const kMusicDeviceSysExSelect: i16 = 0x0102;
+
+pub const kAudioUnitScope_Global: AudioUnitScope = 0;
+pub const kAudioUnitScope_Input: AudioUnitScope = 1;
+pub const kAudioUnitScope_Output: AudioUnitScope = 2;
+
+pub const kAudioUnitProperty_ClassInfo: AudioUnitPropertyID = 0;
+pub const kAudioUnitProperty_MakeConnection: AudioUnitPropertyID = 1;
+pub const kAudioUnitProperty_SampleRate: AudioUnitPropertyID = 2;
+pub const kAudioUnitProperty_ParameterList: AudioUnitPropertyID = 3;
+pub const kAudioUnitProperty_ParameterInfo: AudioUnitPropertyID = 4;
+pub const kAudioUnitProperty_StreamFormat: AudioUnitPropertyID = 8;
+pub const kAudioUnitProperty_ElementCount: AudioUnitPropertyID = 11;
+pub const kAudioUnitProperty_Latency: AudioUnitPropertyID = 12;
+pub const kAudioUnitProperty_SupportedNumChannels: AudioUnitPropertyID = 13;
+pub const kAudioUnitProperty_MaximumFramesPerSlice: AudioUnitPropertyID = 14;
+pub const kAudioUnitProperty_ParameterValueStrings: AudioUnitPropertyID = 16;
+pub const kAudioUnitProperty_TailTime: AudioUnitPropertyID = 20;
+pub const kAudioUnitProperty_BypassEffect: AudioUnitPropertyID = 21;
+pub const kAudioUnitProperty_LastRenderError: AudioUnitPropertyID = 22;
+pub const kAudioUnitProperty_SetRenderCallback: AudioUnitPropertyID = 23;
+pub const kAudioUnitProperty_FactoryPresets: AudioUnitPropertyID = 24;
+pub const kAudioUnitProperty_HostCallbacks: AudioUnitPropertyID = 27;
+pub const kAudioUnitProperty_InPlaceProcessing: AudioUnitPropertyID = 29;
+pub const kAudioUnitProperty_ElementName: AudioUnitPropertyID = 30;
+pub const kAudioUnitProperty_ParameterStringFromValue: AudioUnitPropertyID = 33;
+pub const kAudioUnitProperty_PresentPreset: AudioUnitPropertyID = 36;
+pub const kAudioUnitProperty_OfflineRender: AudioUnitPropertyID = 37;
+pub const kAudioUnitProperty_ParameterValueFromString: AudioUnitPropertyID = 38;
+
+pub const kAudioFormatLinearPCM: u32 = four_cc(*b"lpcm");
+pub const kAudioFormatFlagIsFloat: u32 = 1 << 0;
+pub const kAudioFormatFlagIsPacked: u32 = 1 << 3;
+pub const kAudioFormatFlagIsNonInterleaved: u32 = 1 << 5;
+
+pub const kAudioTimeStampSampleTimeValid: u32 = 1 << 0;
+
+pub const kAudioUnitRenderAction_PreRender: AudioUnitRenderActionFlags = 1 << 2;
+pub const kAudioUnitRenderAction_PostRender: AudioUnitRenderActionFlags = 1 << 3;
+pub const kAudioUnitRenderAction_PostRenderError: AudioUnitRenderActionFlags = 1 << 8;
+
+pub const kAudioUnitParameterUnit_Generic: u32 = 0;
+pub const kAudioUnitParameterUnit_Indexed: u32 = 1;
+
+pub const kAudioUnitParameterFlag_CFNameRelease: u32 = 1 << 4;
+pub const kAudioUnitParameterFlag_ValuesHaveStrings: u32 = 1 << 21;
+pub const kAudioUnitParameterFlag_NonRealTime: u32 = 1 << 24;
+pub const kAudioUnitParameterFlag_CanRamp: u32 = 1 << 25;
+pub const kAudioUnitParameterFlag_HasCFNameString: u32 = 1 << 27;
+pub const kAudioUnitParameterFlag_IsReadable: u32 = 1 << 30;
+pub const kAudioUnitParameterFlag_IsWritable: u32 = 1 << 31;
+
+pub const kAUParameterListener_AnyParameter: AudioUnitParameterID = 0xFFFFFFFF;
+
+pub const kParameterEvent_Immediate: u32 = 1;
+pub const kParameterEvent_Ramped: u32 = 2;
+
+pub const kAUPresetVersionKey: &str = "version";
+pub const kAUPresetTypeKey: &str = "type";
+pub const kAUPresetSubtypeKey: &str = "subtype";
+pub const kAUPresetManufacturerKey: &str = "manufacturer";
+pub const kAUPresetDataKey: &str = "data";
+pub const kAUPresetNameKey: &str = "name";
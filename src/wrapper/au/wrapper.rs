@@ -0,0 +1,2263 @@
+use atomic_refcell::AtomicRefCell;
+use core_foundation::array::{CFArray, CFArrayRef};
+use core_foundation::base::{CFType, TCFType};
+use core_foundation::data::CFData;
+use core_foundation::dictionary::{CFDictionary, CFDictionaryRef};
+use core_foundation::number::CFNumber;
+use core_foundation::string::{CFString, CFStringRef};
+use crossbeam::atomic::AtomicCell;
+use crossbeam::queue::ArrayQueue;
+use parking_lot::Mutex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::mem;
+use std::num::NonZeroU32;
+use std::os::raw::c_void;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicI32, AtomicPtr, AtomicU32, Ordering};
+use std::sync::Arc;
+
+use super::context::{WrapperInitContext, WrapperProcessContext};
+use super::sys::*;
+use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
+use crate::prelude::{
+    AuPlugin, AudioIOLayout, AuxiliaryBuffers, BufferConfig, MidiConfig, NoteEvent, ParamFlags,
+    ParamPtr, Params, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, StateSaveContext,
+    TaskExecutor, TaskOptions, Transport,
+};
+use crate::util::modulation::ModulationMatrix;
+use crate::util::permit_alloc;
+use crate::wrapper::state::{self, PluginState};
+use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::{
+    bypass_param_enabled, clamp_input_event_timing, find_bypass_param, hash_param_id,
+    process_wrapper, reported_tail_samples, setup_logger, strlcpy,
+};
+
+/// The number of MIDI events that can be queued up between two render calls. Hosts send these
+/// through `MusicDeviceMIDIEvent()` just before rendering the block they belong to.
+const MIDI_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// The number of SysEx messages that can be queued up between two render calls.
+const SYSEX_EVENT_QUEUE_CAPACITY: usize = 64;
+/// The number of parameter changes and ramps with a buffer offset that can be queued up between two
+/// render calls. Hosts send these through `AudioUnitSetParameter()` and
+/// `AudioUnitScheduleParameters()` just before rendering the block they belong to.
+const PARAM_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// Parameter ramps are turned into a parameter change every this many samples when
+/// `P::SAMPLE_ACCURATE_AUTOMATION` is set. The parameter's smoother smooths out the steps.
+const PARAM_RAMP_STEP_SIZE: u32 = 32;
+
+/// The default value for `kAudioUnitProperty_MaximumFramesPerSlice`, matching Apple's SDK.
+const DEFAULT_MAX_FRAMES_PER_SLICE: u32 = 1156;
+/// The sample rate used until the host sets a stream format.
+const DEFAULT_SAMPLE_RATE: f64 = 44_100.0;
+
+/// The object returned from the component's factory function. The host treats a pointer to this
+/// object as a pointer to an `AudioComponentPlugInInterface`, and that same pointer is passed as
+/// the first argument to all of the functions returned from `Lookup`.
+#[repr(C)]
+struct AudioUnitInstance<P: AuPlugin> {
+    /// This needs to be the first field.
+    interface: AudioComponentPlugInInterface,
+    wrapper: Arc<Wrapper<P>>,
+}
+
+pub struct Wrapper<P: AuPlugin> {
+    /// The wrapped plugin instance.
+    plugin: Mutex<P>,
+    /// The plugin's background task executor closure.
+    pub task_executor: Mutex<TaskExecutor<P>>,
+    /// The plugin's parameters. These are fetched once during initialization. That way the
+    /// `ParamPtr`s are guaranteed to live at least as long as this object and we can interact with
+    /// the `Params` object without having to acquire a lock on `plugin`.
+    params: Arc<dyn Params>,
+
+    /// A realtime-safe task queue so the plugin can schedule tasks that need to be run later on the
+    /// GUI thread. See the same field in the VST3 wrapper for more information on why this looks
+    /// the way it does.
+    event_loop: AtomicRefCell<Option<OsEventLoop<Task<P>, Self>>>,
+
+    /// The `AudioComponentInstance` handle the host passed to `Open`. This is passed back to the
+    /// host in property listener callbacks and parameter change notifications.
+    component_instance: AtomicPtr<c_void>,
+
+    /// Whether the host has called `AudioUnitInitialize()`.
+    is_initialized: AtomicBool,
+    /// The audio IO layout chosen during `AudioUnitInitialize()` based on the stream formats set by
+    /// the host.
+    current_audio_io_layout: AtomicCell<AudioIOLayout>,
+    /// The number of main input channels from the last input stream format set by the host.
+    input_channels: AtomicU32,
+    /// The number of main output channels from the last output stream format set by the host.
+    output_channels: AtomicU32,
+    /// The sample rate from the last stream format set by the host. Audio Units use the same
+    /// sample rate for all elements.
+    sample_rate: AtomicCell<f64>,
+    /// The maximum block size, set through `kAudioUnitProperty_MaximumFramesPerSlice`.
+    max_frames_per_slice: AtomicU32,
+    /// The current buffer configuration. Set while the plugin is initialized.
+    current_buffer_config: AtomicCell<Option<BufferConfig>>,
+    /// The current audio processing mode. Set through `kAudioUnitProperty_OfflineRender`.
+    pub current_process_mode: AtomicCell<ProcessMode>,
+    /// The last process status returned by the plugin. This is used for tail handling.
+    last_process_status: AtomicCell<ProcessStatus>,
+    /// The error code returned from the last failed render call, reported through
+    /// `kAudioUnitProperty_LastRenderError`.
+    last_render_error: AtomicI32,
+    /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
+    /// [`ProcessContext`].
+    current_latency: AtomicU32,
+    /// The current tail length in samples, as set by the plugin through the [`ProcessContext`].
+    current_tail: AtomicU32,
+
+    /// The hashes of the plugin's parameters, in the order they were defined. These hashes are
+    /// used as the Audio Unit parameter IDs.
+    param_hashes: Vec<u32>,
+    /// A mapping from parameter ID hashes (obtained from the string parameter IDs) to pointers to
+    /// parameters belonging to the plugin.
+    param_by_hash: HashMap<u32, ParamPtr>,
+    /// Mappings from string parameter identifiers to parameter hashes. Useful for debug logging
+    /// and when handling plugin state.
+    param_id_to_hash: HashMap<String, u32>,
+    /// The inverse mapping from [`param_by_hash`][Self::param_by_hash]. This is needed to be able
+    /// to have an ergonomic parameter setting API that uses references to the parameters instead of
+    /// having to add a setter function to the parameter (or even worse, have it be completely
+    /// untyped).
+    param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// The plugin's bypass parameter, if it has one. This is exposed through
+    /// `kAudioUnitProperty_BypassEffect`.
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the dry signal when the plugin is bypassed. This is only
+    /// used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    modulation_matrix: Option<Arc<ModulationMatrix>>,
+
+    /// The incoming events for the plugin, if `P::MIDI_INPUT` is set to `MidiConfig::Basic` or
+    /// higher. These are collected from `midi_events` and `sysex_events` at the start of a render
+    /// call.
+    input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Raw MIDI messages sent through `MusicDeviceMIDIEvent()`, along with their sample offsets.
+    /// These are only parsed during the render call since the offsets need to be clamped to the
+    /// block's length.
+    midi_events: ArrayQueue<(u32, [u8; 3])>,
+    /// SysEx messages sent through `MusicDeviceSysEx()`. These don't have a timing and are always
+    /// handled at the start of the next block.
+    sysex_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// All of the note events for the current render call, sorted by their timing. These are moved
+    /// to `input_events` one block at a time when the render call is split up at parameter changes.
+    block_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Parameter changes and ramps with a buffer offset, sent through `AudioUnitSetParameter()` and
+    /// `AudioUnitScheduleParameters()`. These are applied during the next render call.
+    scheduled_param_events: ArrayQueue<ScheduledParamEvent>,
+    /// The parameter changes for the current render call, sorted by their offset. Ramps from
+    /// `scheduled_param_events` are expanded into multiple changes.
+    param_changes: AtomicRefCell<Vec<ParamChange>>,
+
+    /// The buffers and scratch storage used during rendering. These are reallocated when the plugin
+    /// gets initialized.
+    render_buffers: AtomicRefCell<RenderBuffers>,
+    /// Where the main input's audio is pulled from during rendering.
+    input_source: Mutex<InputSource>,
+    /// The host's transport callbacks, if it provided them through
+    /// `kAudioUnitProperty_HostCallbacks`.
+    host_callbacks: Mutex<Option<HostCallbacks>>,
+    /// Callbacks registered through `AudioUnitAddPropertyListener()`.
+    property_listeners: Mutex<Vec<PropertyListener>>,
+    /// Callbacks registered through `AudioUnitAddRenderNotify()`.
+    render_notify_callbacks: Mutex<Vec<RenderNotifyCallback>>,
+    /// The preset number and name set by the host through `kAudioUnitProperty_PresentPreset`.
+    present_preset: Mutex<(i32, String)>,
+}
+
+/// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
+/// realtime-safe way.
+#[allow(clippy::enum_variant_names)]
+pub enum Task<P: Plugin> {
+    /// Execute one of the plugin's background tasks.
+    PluginTask(P::BackgroundTask),
+    /// Inform the host that all parameter values may have changed, for instance after loading a
+    /// preset.
+    ParameterValuesChanged,
+    /// Inform the host that a parameter's value has changed. This uses the parameter hashes since
+    /// the task will be created from the audio thread.
+    ParameterValueChanged(u32),
+    /// Call the property listeners registered for a property with the global scope.
+    PropertyChanged(AudioUnitPropertyID),
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+}
+
+/// A parameter change or ramp with a buffer offset, queued up for the next render call.
+#[derive(Debug, Clone, Copy)]
+struct ScheduledParamEvent {
+    hash: u32,
+    /// The offset in samples from the start of the next render call. This can be negative for
+    /// ramps that started during an earlier render call.
+    start_offset: i64,
+    /// The length of the ramp in samples, or zero for immediate changes.
+    duration: u32,
+    start_normalized_value: f32,
+    end_normalized_value: f32,
+}
+
+/// A single parameter change during a render call. The render call is split up at these changes
+/// when `P::SAMPLE_ACCURATE_AUTOMATION` is set.
+#[derive(Debug, Clone, Copy)]
+struct ParamChange {
+    offset: u32,
+    hash: u32,
+    normalized_value: f32,
+}
+
+/// The buffers used while rendering. Sized for the current audio IO layout and maximum block size.
+struct RenderBuffers {
+    buffer_manager: BufferManager,
+    /// The buffer list passed upstream when pulling the main input.
+    input_buffer_list: OwnedAudioBufferList,
+    /// Storage for the main input's channels. The upstream unit may either fill these buffers or
+    /// replace the pointers with its own buffers.
+    input_storage: Vec<Vec<f32>>,
+    /// Storage for the main output's channels, used when the host passes null pointers in the
+    /// output buffer list.
+    output_storage: Vec<Vec<f32>>,
+    /// Scratch space for the channel pointers passed to the [`BufferManager`].
+    input_channel_pointers: Vec<*mut f32>,
+    output_channel_pointers: Vec<*mut f32>,
+}
+
+// SAFETY: The raw pointers in the channel pointer vectors are only used as scratch storage inside of
+//         the render call, and `OwnedAudioBufferList` owns its data
+unsafe impl Send for RenderBuffers {}
+unsafe impl Sync for RenderBuffers {}
+
+/// The source the main input gets pulled from. Set through either
+/// `kAudioUnitProperty_SetRenderCallback` or `kAudioUnitProperty_MakeConnection`.
+enum InputSource {
+    None,
+    Callback(AURenderCallbackStruct),
+    Connection(AudioUnitConnection),
+}
+
+#[derive(Clone, Copy)]
+struct HostCallbacks(HostCallbackInfo);
+
+#[derive(Clone, Copy)]
+struct PropertyListener {
+    id: AudioUnitPropertyID,
+    proc_: AudioUnitPropertyListenerProc,
+    user_data: *mut c_void,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct RenderNotifyCallback {
+    proc_: AURenderCallback,
+    ref_con: *mut c_void,
+}
+
+// SAFETY: These only contain pointers and callbacks provided by the host, which are required to
+//         be usable from the threads the Audio Unit API calls the plugin on
+unsafe impl Send for InputSource {}
+unsafe impl Send for HostCallbacks {}
+unsafe impl Send for PropertyListener {}
+unsafe impl Send for RenderNotifyCallback {}
+
+impl<P: AuPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
+    fn execute(&self, task: Task<P>, _is_gui_thread: bool) {
+        match task {
+            Task::PluginTask(task) => (self.task_executor.lock())(task),
+            Task::ParameterValuesChanged => {
+                self.notify_parameter_changed(kAUParameterListener_AnyParameter)
+            }
+            Task::ParameterValueChanged(param_hash) => self.notify_parameter_changed(param_hash),
+            Task::PropertyChanged(id) => self.notify_property_listeners(id, kAudioUnitScope_Global),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
+        }
+    }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
+}
+
+impl<P: AuPlugin> Wrapper<P> {
+    /// The component's factory function. This is called by the function exported by
+    /// [`nih_export_au!()`][crate::nih_export_au!()] and returns a pointer to a newly allocated
+    /// `AudioComponentPlugInInterface`.
+    ///
+    /// # Safety
+    ///
+    /// The returned pointer must only be freed through the interface's `Close` function.
+    pub unsafe fn factory(desc: *const c_void) -> *mut c_void {
+        setup_logger();
+
+        if let Some(desc) = (desc as *const AudioComponentDescription).as_ref() {
+            nih_debug_assert_eq!(
+                desc.componentSubType,
+                four_cc(P::AU_SUBTYPE),
+                "The component's subtype in the bundle's Info.plist file does not match \
+                 AuPlugin::AU_SUBTYPE"
+            );
+        }
+
+        let instance = Box::new(AudioUnitInstance {
+            interface: AudioComponentPlugInInterface {
+                Open: open::<P>,
+                Close: close::<P>,
+                Lookup: lookup::<P>,
+                reserved: ptr::null_mut(),
+            },
+            wrapper: Self::new(),
+        });
+
+        Box::into_raw(instance) as *mut c_void
+    }
+
+    fn new() -> Arc<Self> {
+        let plugin = P::default();
+        let task_executor = Mutex::new(plugin.task_executor());
+        let params = plugin.params();
+        let modulation_matrix = plugin.modulation_matrix();
+
+        nih_debug_assert!(
+            !P::AU_TYPE.receives_midi() || P::MIDI_INPUT >= MidiConfig::Basic,
+            "The plugin is exported as a {:?} Audio Unit, but it does not accept MIDI",
+            P::AU_TYPE
+        );
+
+        let param_map = params.param_map();
+        let param_hashes: Vec<u32> = param_map
+            .iter()
+            .map(|(id, _, _)| hash_param_id(id))
+            .collect();
+        if cfg!(debug_assertions) {
+            let unique_hashes: HashSet<_> = param_hashes.iter().collect();
+            nih_debug_assert_eq!(
+                param_hashes.len(),
+                unique_hashes.len(),
+                "The plugin has duplicate parameter hashes, weird things may happen. Consider using \
+                 6 character parameter IDs to avoid collisions."
+            );
+        }
+        let bypass_param = find_bypass_param::<P>(param_map.iter().map(|(_, ptr, _)| *ptr));
+
+        let default_audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+
+        let wrapper = Arc::new(Self {
+            plugin: Mutex::new(plugin),
+            task_executor,
+            params,
+
+            // Initialized later as it needs a reference to the wrapper for the executor
+            event_loop: AtomicRefCell::new(None),
+
+            component_instance: AtomicPtr::new(ptr::null_mut()),
+
+            is_initialized: AtomicBool::new(false),
+            current_audio_io_layout: AtomicCell::new(default_audio_io_layout),
+            input_channels: AtomicU32::new(
+                default_audio_io_layout
+                    .main_input_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0),
+            ),
+            output_channels: AtomicU32::new(
+                default_audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0),
+            ),
+            sample_rate: AtomicCell::new(DEFAULT_SAMPLE_RATE),
+            max_frames_per_slice: AtomicU32::new(DEFAULT_MAX_FRAMES_PER_SLICE),
+            current_buffer_config: AtomicCell::new(None),
+            current_process_mode: AtomicCell::new(ProcessMode::Realtime),
+            last_process_status: AtomicCell::new(ProcessStatus::Normal),
+            last_render_error: AtomicI32::new(noErr),
+            current_latency: AtomicU32::new(0),
+            current_tail: AtomicU32::new(0),
+
+            param_by_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*hash, *ptr))
+                .collect(),
+            param_id_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (id, _, _))| (id.clone(), *hash))
+                .collect(),
+            param_ptr_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*ptr, *hash))
+                .collect(),
+            param_hashes,
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            modulation_matrix,
+
+            input_events: AtomicRefCell::new(VecDeque::with_capacity(
+                MIDI_EVENT_QUEUE_CAPACITY + SYSEX_EVENT_QUEUE_CAPACITY,
+            )),
+            midi_events: ArrayQueue::new(MIDI_EVENT_QUEUE_CAPACITY),
+            sysex_events: ArrayQueue::new(SYSEX_EVENT_QUEUE_CAPACITY),
+            block_events: AtomicRefCell::new(VecDeque::with_capacity(
+                MIDI_EVENT_QUEUE_CAPACITY + SYSEX_EVENT_QUEUE_CAPACITY,
+            )),
+            scheduled_param_events: ArrayQueue::new(PARAM_EVENT_QUEUE_CAPACITY),
+            // Ramps are expanded into multiple changes, so this may still need to grow if the host
+            // schedules a lot of long ramps
+            param_changes: AtomicRefCell::new(Vec::with_capacity(PARAM_EVENT_QUEUE_CAPACITY * 4)),
+
+            render_buffers: AtomicRefCell::new(RenderBuffers::new(default_audio_io_layout, 0)),
+            input_source: Mutex::new(InputSource::None),
+            host_callbacks: Mutex::new(None),
+            property_listeners: Mutex::new(Vec::new()),
+            render_notify_callbacks: Mutex::new(Vec::new()),
+            present_preset: Mutex::new((-1, String::from("Untitled"))),
+        });
+
+        *wrapper.event_loop.borrow_mut() =
+            Some(OsEventLoop::new_and_spawn(Arc::downgrade(&wrapper)));
+
+        wrapper
+    }
+
+    fn make_init_context(&self) -> WrapperInitContext<'_, P> {
+        WrapperInitContext { wrapper: self }
+    }
+
+    fn make_process_context(&self, transport: Transport) -> WrapperProcessContext<'_, P> {
+        WrapperProcessContext {
+            wrapper: self,
+            input_events_guard: self.input_events.borrow_mut(),
+            transport,
+            aux_input_count: self.current_audio_io_layout.load().aux_input_ports.len(),
+        }
+    }
+
+    /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
+    /// can be run in the background without blocking either the GUI or the audio thread.
+    ///
+    /// The plugin's own tasks are scheduled using the options from
+    /// [`Plugin::background_task_options()`].
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_background(&self, task: Task<P>) -> bool {
+        let options = match &task {
+            Task::PluginTask(task) => P::background_task_options(task),
+            _ => TaskOptions::default(),
+        };
+
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_background(task, options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.cancel_background(tag)
+    }
+
+    /// Posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be delegated
+    /// to the main thread. The task is run directly if this is the GUI thread.
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_gui(&self, task: Task<P>) -> bool {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_gui(task)
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
+    pub fn set_latency_samples(&self, samples: u32) {
+        // Only notify the host if it's actually needed
+        let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
+        if old_latency != samples {
+            let task_posted = self.schedule_gui(Task::PropertyChanged(kAudioUnitProperty_Latency));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    pub fn set_tail_samples(&self, samples: u32) {
+        let old_tail = self.current_tail.swap(samples, Ordering::SeqCst);
+        if old_tail != samples {
+            let task_posted = self.schedule_gui(Task::PropertyChanged(kAudioUnitProperty_TailTime));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Immediately set the plugin state. Returns `false` if the deserialization failed. The plugin
+    /// is reinitialized if it was already initialized.
+    ///
+    /// Implicitly emits `Task::ParameterValuesChanged`.
+    ///
+    /// # Notes
+    ///
+    /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
+    fn set_state_inner(&self, state: &mut PluginState) -> bool {
+        let audio_io_layout = self.current_audio_io_layout.load();
+        let buffer_config = self.current_buffer_config.load();
+
+        // FIXME: This is obviously not realtime-safe, but loading presets without doing this could
+        //        lead to inconsistencies. Hosts normally set `kAudioUnitProperty_ClassInfo` from
+        //        the main thread, so this only allocates on the audio thread if the plugin's
+        //        `deserialize_fields()` implementation allocates.
+        let mut success = permit_alloc(|| unsafe {
+            state::deserialize_object::<P>(
+                state,
+                self.params.clone(),
+                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                buffer_config.as_ref(),
+            )
+        });
+        if !success {
+            nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
+            return false;
+        }
+
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // If the plugin was already initialized then it needs to be reinitialized
+        if let Some(buffer_config) = buffer_config {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = self.make_init_context();
+            let mut plugin = self.plugin.lock();
+
+            // See above
+            success = permit_alloc(|| {
+                plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context)
+            });
+            if success {
+                process_wrapper(|| plugin.reset());
+            }
+        }
+
+        nih_debug_assert!(
+            success,
+            "Plugin returned false when reinitializing after loading state"
+        );
+
+        let task_posted = self.schedule_gui(Task::ParameterValuesChanged);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        success
+    }
+
+    /// Convert a parameter's normalized value to the value used by the Audio Unit API. Continuous
+    /// parameters are exposed with normalized values, and discrete parameters are exposed as
+    /// indexed parameters using their step index.
+    unsafe fn normalized_to_au_value(param_ptr: ParamPtr, normalized: f32) -> f32 {
+        match param_ptr.step_count() {
+            Some(step_count) => (normalized * step_count as f32).round(),
+            None => normalized,
+        }
+    }
+
+    /// The inverse of [`normalized_to_au_value()`][Self::normalized_to_au_value()].
+    unsafe fn au_value_to_normalized(param_ptr: ParamPtr, value: f32) -> f32 {
+        match param_ptr.step_count() {
+            Some(step_count) => value / step_count as f32,
+            None => value,
+        }
+    }
+
+    /// Queue up a parameter change or ramp for the next render call. Returns `false` if the plugin
+    /// is not initialized or if the queue is full, in which case the change should be applied
+    /// immediately instead.
+    fn schedule_param_event(&self, event: ScheduledParamEvent) -> bool {
+        self.is_initialized.load(Ordering::SeqCst)
+            && self.scheduled_param_events.push(event).is_ok()
+    }
+
+    /// Set a parameter's normalized value from the host. Returns `false` if the parameter does not
+    /// exist.
+    fn set_normalized_value_by_hash(&self, hash: u32, normalized: f32) -> bool {
+        let Some(param_ptr) = self.param_by_hash.get(&hash) else {
+            return false;
+        };
+
+        let sample_rate = self
+            .current_buffer_config
+            .load()
+            .map(|buffer_config| buffer_config.sample_rate);
+        if unsafe { param_ptr.set_normalized_value(normalized) } {
+            if let Some(sample_rate) = sample_rate {
+                unsafe { param_ptr.update_smoother(sample_rate, false) };
+            }
+
+            // The host needs to be informed about the macro parameter's targets changing
+            unsafe {
+                param_ptr.apply_macro_mappings(
+                    state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    sample_rate,
+                    |target_ptr, _| {
+                        if let Some(target_hash) = self.param_ptr_to_hash.get(&target_ptr) {
+                            let task_posted =
+                                self.schedule_gui(Task::ParameterValueChanged(*target_hash));
+                            nih_debug_assert!(
+                                task_posted,
+                                "The task queue is full, dropping task..."
+                            );
+                        }
+                    },
+                )
+            };
+        }
+
+        true
+    }
+
+    /// Inform the host's parameter listeners that a parameter has changed. Must be called from the
+    /// main thread.
+    fn notify_parameter_changed(&self, param_hash: u32) {
+        let parameter = AudioUnitParameter {
+            mAudioUnit: self.component_instance.load(Ordering::SeqCst),
+            mParameterID: param_hash,
+            mScope: kAudioUnitScope_Global,
+            mElement: 0,
+        };
+
+        let result =
+            unsafe { AUParameterListenerNotify(ptr::null_mut(), ptr::null_mut(), &parameter) };
+        nih_debug_assert_eq!(result, noErr);
+    }
+
+    /// Call the property listeners registered for a property. Must be called from the main thread.
+    fn notify_property_listeners(&self, id: AudioUnitPropertyID, scope: AudioUnitScope) {
+        // The listeners are copied first since they may add or remove listeners in the callback
+        let listeners: Vec<PropertyListener> = self
+            .property_listeners
+            .lock()
+            .iter()
+            .filter(|listener| listener.id == id)
+            .copied()
+            .collect();
+
+        let instance = self.component_instance.load(Ordering::SeqCst);
+        for listener in listeners {
+            unsafe { (listener.proc_)(listener.user_data, instance, id, scope, 0) };
+        }
+    }
+
+    /// Find the audio IO layout matching the channel counts from the host's stream formats.
+    fn matching_audio_io_layout(
+        input_channels: u32,
+        output_channels: u32,
+    ) -> Option<AudioIOLayout> {
+        P::AUDIO_IO_LAYOUTS.iter().copied().find(|layout| {
+            layout.main_input_channels.map(NonZeroU32::get).unwrap_or(0) == input_channels
+                && layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0)
+                    == output_channels
+        })
+    }
+
+    /// The number of elements in a scope. Only the main input and output ports are exposed.
+    fn element_count(&self, scope: AudioUnitScope) -> u32 {
+        match scope {
+            kAudioUnitScope_Global => 1,
+            kAudioUnitScope_Input => P::AUDIO_IO_LAYOUTS
+                .iter()
+                .any(|layout| layout.main_input_channels.is_some())
+                as u32,
+            kAudioUnitScope_Output => P::AUDIO_IO_LAYOUTS
+                .iter()
+                .any(|layout| layout.main_output_channels.is_some())
+                as u32,
+            _ => 0,
+        }
+    }
+
+    /// The stream format for one of the main ports. NIH-plug always uses non-interleaved 32-bit
+    /// floating point audio.
+    fn stream_format(&self, num_channels: u32) -> AudioStreamBasicDescription {
+        AudioStreamBasicDescription {
+            mSampleRate: self.sample_rate.load(),
+            mFormatID: kAudioFormatLinearPCM,
+            mFormatFlags: kAudioFormatFlagIsFloat
+                | kAudioFormatFlagIsPacked
+                | kAudioFormatFlagIsNonInterleaved,
+            mBytesPerPacket: mem::size_of::<f32>() as u32,
+            mFramesPerPacket: 1,
+            mBytesPerFrame: mem::size_of::<f32>() as u32,
+            mChannelsPerFrame: num_channels,
+            mBitsPerChannel: 32,
+            mReserved: 0,
+        }
+    }
+
+    /// The parameters that should be exposed to the host, in order.
+    fn visible_param_hashes(&self) -> impl Iterator<Item = &u32> {
+        self.param_hashes.iter().filter(|hash| {
+            !unsafe { self.param_by_hash[hash].flags() }.contains(ParamFlags::HIDDEN)
+        })
+    }
+
+    /// The size of a property's value and whether it can be written to, or an error code if the
+    /// property is not supported for this scope and element.
+    fn property_info(
+        &self,
+        id: AudioUnitPropertyID,
+        scope: AudioUnitScope,
+        element: AudioUnitElement,
+    ) -> Result<(u32, bool), OSStatus> {
+        let ptr_size = mem::size_of::<*const c_void>() as u32;
+
+        match (id, scope) {
+            (kAudioUnitProperty_ClassInfo, kAudioUnitScope_Global) => Ok((ptr_size, true)),
+            (kAudioUnitProperty_MakeConnection, kAudioUnitScope_Input) => {
+                Ok((mem::size_of::<AudioUnitConnection>() as u32, true))
+            }
+            (kAudioUnitProperty_SetRenderCallback, kAudioUnitScope_Input) => {
+                Ok((mem::size_of::<AURenderCallbackStruct>() as u32, true))
+            }
+            (kAudioUnitProperty_SampleRate, kAudioUnitScope_Input | kAudioUnitScope_Output)
+                if element < self.element_count(scope) =>
+            {
+                Ok((mem::size_of::<f64>() as u32, true))
+            }
+            (kAudioUnitProperty_StreamFormat, kAudioUnitScope_Input | kAudioUnitScope_Output)
+                if element < self.element_count(scope) =>
+            {
+                Ok((mem::size_of::<AudioStreamBasicDescription>() as u32, true))
+            }
+            (
+                kAudioUnitProperty_SampleRate | kAudioUnitProperty_StreamFormat,
+                kAudioUnitScope_Input | kAudioUnitScope_Output,
+            ) => Err(kAudioUnitErr_InvalidElement),
+            (kAudioUnitProperty_ParameterList, kAudioUnitScope_Global) => Ok((
+                (self.visible_param_hashes().count() * mem::size_of::<AudioUnitParameterID>())
+                    as u32,
+                false,
+            )),
+            // Parameters only exist in the global scope
+            (kAudioUnitProperty_ParameterList, _) => Ok((0, false)),
+            (kAudioUnitProperty_ParameterInfo, kAudioUnitScope_Global) => {
+                if self.param_by_hash.contains_key(&element) {
+                    Ok((mem::size_of::<AudioUnitParameterInfo>() as u32, false))
+                } else {
+                    Err(kAudioUnitErr_InvalidParameter)
+                }
+            }
+            (kAudioUnitProperty_ParameterValueStrings, kAudioUnitScope_Global) => {
+                match self.param_by_hash.get(&element) {
+                    Some(param_ptr) if unsafe { param_ptr.step_count() }.is_some() => {
+                        Ok((ptr_size, false))
+                    }
+                    Some(_) => Err(kAudioUnitErr_InvalidProperty),
+                    None => Err(kAudioUnitErr_InvalidParameter),
+                }
+            }
+            (kAudioUnitProperty_ParameterStringFromValue, kAudioUnitScope_Global) => Ok((
+                mem::size_of::<AudioUnitParameterStringFromValue>() as u32,
+                false,
+            )),
+            (kAudioUnitProperty_ParameterValueFromString, kAudioUnitScope_Global) => Ok((
+                mem::size_of::<AudioUnitParameterValueFromString>() as u32,
+                false,
+            )),
+            (kAudioUnitProperty_ElementCount, _) => Ok((mem::size_of::<u32>() as u32, false)),
+            (kAudioUnitProperty_Latency | kAudioUnitProperty_TailTime, kAudioUnitScope_Global) => {
+                Ok((mem::size_of::<f64>() as u32, false))
+            }
+            (kAudioUnitProperty_SupportedNumChannels, kAudioUnitScope_Global) => Ok((
+                (supported_channel_infos::<P>().len() * mem::size_of::<AUChannelInfo>()) as u32,
+                false,
+            )),
+            (
+                kAudioUnitProperty_MaximumFramesPerSlice
+                | kAudioUnitProperty_InPlaceProcessing
+                | kAudioUnitProperty_OfflineRender,
+                kAudioUnitScope_Global,
+            ) => Ok((mem::size_of::<u32>() as u32, true)),
+            (kAudioUnitProperty_BypassEffect, kAudioUnitScope_Global)
+                if self.bypass_param.is_some() =>
+            {
+                Ok((mem::size_of::<u32>() as u32, true))
+            }
+            (kAudioUnitProperty_LastRenderError, kAudioUnitScope_Global) => {
+                Ok((mem::size_of::<OSStatus>() as u32, false))
+            }
+            (kAudioUnitProperty_HostCallbacks, kAudioUnitScope_Global) => {
+                Ok((mem::size_of::<HostCallbackInfo>() as u32, true))
+            }
+            (kAudioUnitProperty_PresentPreset, kAudioUnitScope_Global) => {
+                Ok((mem::size_of::<AUPreset>() as u32, true))
+            }
+            (kAudioUnitProperty_ElementName, kAudioUnitScope_Input | kAudioUnitScope_Output)
+                if element < self.element_count(scope) =>
+            {
+                Ok((ptr_size, false))
+            }
+            _ => Err(kAudioUnitErr_InvalidProperty),
+        }
+    }
+
+    /// Write a property's value to `out_data`. The size has already been checked against
+    /// [`property_info()`][Self::property_info()].
+    unsafe fn get_property(
+        &self,
+        id: AudioUnitPropertyID,
+        scope: AudioUnitScope,
+        element: AudioUnitElement,
+        out_data: *mut c_void,
+    ) -> OSStatus {
+        match id {
+            kAudioUnitProperty_ClassInfo => {
+                let state = match state::serialize_json::<P>(
+                    self.params.clone(),
+                    state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                    StateSaveContext::Project,
+                ) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        nih_debug_assert_failure!("Could not save state: {:#}", err);
+                        return kAudioUnitErr_InvalidPropertyValue;
+                    }
+                };
+
+                let preset_name = self.present_preset.lock().1.clone();
+                let class_info = CFDictionary::from_CFType_pairs(&[
+                    (
+                        CFString::from_static_string(kAUPresetVersionKey),
+                        CFNumber::from(0i32).as_CFType(),
+                    ),
+                    (
+                        CFString::from_static_string(kAUPresetTypeKey),
+                        CFNumber::from(four_cc(P::AU_TYPE.as_four_cc()) as i32).as_CFType(),
+                    ),
+                    (
+                        CFString::from_static_string(kAUPresetSubtypeKey),
+                        CFNumber::from(four_cc(P::AU_SUBTYPE) as i32).as_CFType(),
+                    ),
+                    (
+                        CFString::from_static_string(kAUPresetManufacturerKey),
+                        CFNumber::from(four_cc(P::AU_MANUFACTURER) as i32).as_CFType(),
+                    ),
+                    (
+                        CFString::from_static_string(kAUPresetNameKey),
+                        CFString::new(&preset_name).as_CFType(),
+                    ),
+                    (
+                        CFString::from_static_string(kAUPresetDataKey),
+                        CFData::from_buffer(&state).as_CFType(),
+                    ),
+                ]);
+
+                // The host takes ownership of the dictionary
+                *(out_data as *mut CFDictionaryRef) = class_info.as_concrete_TypeRef();
+                mem::forget(class_info);
+            }
+            kAudioUnitProperty_SampleRate => *(out_data as *mut f64) = self.sample_rate.load(),
+            kAudioUnitProperty_StreamFormat => {
+                let num_channels = if scope == kAudioUnitScope_Input {
+                    self.input_channels.load(Ordering::SeqCst)
+                } else {
+                    self.output_channels.load(Ordering::SeqCst)
+                };
+
+                *(out_data as *mut AudioStreamBasicDescription) = self.stream_format(num_channels);
+            }
+            kAudioUnitProperty_ParameterList => {
+                let out_data = out_data as *mut AudioUnitParameterID;
+                for (idx, hash) in self.visible_param_hashes().enumerate() {
+                    *out_data.add(idx) = *hash;
+                }
+            }
+            kAudioUnitProperty_ParameterInfo => {
+                let param_ptr = self.param_by_hash[&element];
+                let info = &mut *(out_data as *mut AudioUnitParameterInfo);
+                ptr::write_bytes(info as *mut AudioUnitParameterInfo, 0, 1);
+
+                strlcpy(&mut info.name, param_ptr.name());
+                info.cfNameString = cfstring_into_raw(param_ptr.name());
+                info.flags = kAudioUnitParameterFlag_HasCFNameString
+                    | kAudioUnitParameterFlag_CFNameRelease
+                    | kAudioUnitParameterFlag_ValuesHaveStrings
+                    | kAudioUnitParameterFlag_IsReadable
+                    | kAudioUnitParameterFlag_IsWritable;
+                // Ramps are only handled sample accurately if the render call can be split up
+                if P::SAMPLE_ACCURATE_AUTOMATION && param_ptr.step_count().is_none() {
+                    info.flags |= kAudioUnitParameterFlag_CanRamp;
+                }
+                // Audio Units don't have a way to mark parameters as not automatable. This flag
+                // at least tells hosts not to change the parameter while rendering.
+                if param_ptr.flags().contains(ParamFlags::NON_AUTOMATABLE) {
+                    info.flags |= kAudioUnitParameterFlag_NonRealTime;
+                }
+
+                info.minValue = 0.0;
+                match param_ptr.step_count() {
+                    Some(step_count) => {
+                        info.unit = kAudioUnitParameterUnit_Indexed;
+                        info.maxValue = step_count as f32;
+                    }
+                    None => {
+                        info.unit = kAudioUnitParameterUnit_Generic;
+                        info.maxValue = 1.0;
+                    }
+                }
+                info.defaultValue =
+                    Self::normalized_to_au_value(param_ptr, param_ptr.default_normalized_value());
+            }
+            kAudioUnitProperty_ParameterValueStrings => {
+                let param_ptr = self.param_by_hash[&element];
+                let step_count = param_ptr.step_count().unwrap();
+                let strings: Vec<CFString> = (0..=step_count)
+                    .map(|step| {
+                        CFString::new(
+                            &param_ptr
+                                .normalized_value_to_string(step as f32 / step_count as f32, true),
+                        )
+                    })
+                    .collect();
+                let strings = CFArray::from_CFTypes(&strings);
+
+                // The host takes ownership of the array
+                *(out_data as *mut CFArrayRef) = strings.as_concrete_TypeRef();
+                mem::forget(strings);
+            }
+            kAudioUnitProperty_ParameterStringFromValue => {
+                let info = &mut *(out_data as *mut AudioUnitParameterStringFromValue);
+                let Some(param_ptr) = self.param_by_hash.get(&info.inParamID) else {
+                    return kAudioUnitErr_InvalidParameter;
+                };
+
+                // A null value means that the parameter's current value should be used
+                let normalized = match info.inValue.as_ref() {
+                    Some(value) => Self::au_value_to_normalized(*param_ptr, *value),
+                    None => param_ptr.unmodulated_normalized_value(),
+                };
+                info.outString =
+                    cfstring_into_raw(&param_ptr.normalized_value_to_string(normalized, true));
+            }
+            kAudioUnitProperty_ParameterValueFromString => {
+                let info = &mut *(out_data as *mut AudioUnitParameterValueFromString);
+                let Some(param_ptr) = self.param_by_hash.get(&info.inParamID) else {
+                    return kAudioUnitErr_InvalidParameter;
+                };
+                if info.inString.is_null() {
+                    return kAudio_ParamError;
+                }
+
+                let string = CFString::wrap_under_get_rule(info.inString).to_string();
+                match param_ptr.string_to_normalized_value(&string) {
+                    Some(normalized) => {
+                        info.outValue = Self::normalized_to_au_value(*param_ptr, normalized)
+                    }
+                    None => return kAudioUnitErr_InvalidPropertyValue,
+                }
+            }
+            kAudioUnitProperty_ElementCount => *(out_data as *mut u32) = self.element_count(scope),
+            kAudioUnitProperty_Latency => {
+                *(out_data as *mut f64) =
+                    self.current_latency.load(Ordering::SeqCst) as f64 / self.sample_rate.load()
+            }
+            kAudioUnitProperty_TailTime => {
+                let tail_samples = reported_tail_samples(
+                    self.last_process_status.load(),
+                    self.current_tail.load(Ordering::SeqCst),
+                );
+                *(out_data as *mut f64) = tail_samples as f64 / self.sample_rate.load();
+            }
+            kAudioUnitProperty_SupportedNumChannels => {
+                let out_data = out_data as *mut AUChannelInfo;
+                for (idx, channel_info) in supported_channel_infos::<P>().into_iter().enumerate() {
+                    *out_data.add(idx) = channel_info;
+                }
+            }
+            kAudioUnitProperty_MaximumFramesPerSlice => {
+                *(out_data as *mut u32) = self.max_frames_per_slice.load(Ordering::SeqCst)
+            }
+            kAudioUnitProperty_InPlaceProcessing => {
+                // The buffer manager handles both in-place and out-of-place processing
+                *(out_data as *mut u32) = 1
+            }
+            kAudioUnitProperty_OfflineRender => {
+                *(out_data as *mut u32) =
+                    (self.current_process_mode.load() == ProcessMode::Offline) as u32
+            }
+            kAudioUnitProperty_BypassEffect => *(out_data as *mut u32) = self.is_bypassed() as u32,
+            kAudioUnitProperty_LastRenderError => {
+                // Reading the error also clears it
+                *(out_data as *mut OSStatus) = self.last_render_error.swap(noErr, Ordering::SeqCst)
+            }
+            kAudioUnitProperty_PresentPreset => {
+                let (number, name) = &*self.present_preset.lock();
+                *(out_data as *mut AUPreset) = AUPreset {
+                    presetNumber: *number,
+                    // The host takes ownership of the string
+                    presetName: cfstring_into_raw(name),
+                };
+            }
+            kAudioUnitProperty_ElementName => {
+                let audio_io_layout = self.current_audio_io_layout.load();
+                let name = if scope == kAudioUnitScope_Input {
+                    audio_io_layout.main_input_name()
+                } else {
+                    audio_io_layout.main_output_name()
+                };
+
+                *(out_data as *mut CFStringRef) = cfstring_into_raw(&name);
+            }
+            // These properties can only be set
+            _ => return kAudioUnitErr_InvalidProperty,
+        }
+
+        noErr
+    }
+
+    /// Set a property's value. The size has already been checked against
+    /// [`property_info()`][Self::property_info()].
+    unsafe fn set_property(
+        &self,
+        id: AudioUnitPropertyID,
+        scope: AudioUnitScope,
+        in_data: *const c_void,
+        in_data_size: u32,
+    ) -> OSStatus {
+        match id {
+            kAudioUnitProperty_ClassInfo => {
+                let class_info = *(in_data as *const CFDictionaryRef);
+                if class_info.is_null() {
+                    return kAudio_ParamError;
+                }
+
+                let class_info = CFDictionary::<CFString, CFType>::wrap_under_get_rule(class_info);
+                let Some(data) = class_info
+                    .find(&CFString::from_static_string(kAUPresetDataKey))
+                    .and_then(|data| data.downcast::<CFData>())
+                else {
+                    nih_debug_assert_failure!("The ClassInfo dictionary does not contain any data");
+                    return kAudioUnitErr_InvalidPropertyValue;
+                };
+                if let Some(name) = class_info
+                    .find(&CFString::from_static_string(kAUPresetNameKey))
+                    .and_then(|name| name.downcast::<CFString>())
+                {
+                    self.present_preset.lock().1 = name.to_string();
+                }
+
+                let success = match state::deserialize_json(data.bytes()) {
+                    Some(mut state) => self.set_state_inner(&mut state),
+                    None => false,
+                };
+                if !success {
+                    return kAudioUnitErr_InvalidPropertyValue;
+                }
+
+                self.notify_property_listeners(
+                    kAudioUnitProperty_PresentPreset,
+                    kAudioUnitScope_Global,
+                );
+            }
+            kAudioUnitProperty_MakeConnection => {
+                let connection = *(in_data as *const AudioUnitConnection);
+                if connection.destInputNumber != 0 {
+                    return kAudioUnitErr_InvalidElement;
+                }
+
+                *self.input_source.lock() = if connection.sourceAudioUnit.is_null() {
+                    InputSource::None
+                } else {
+                    InputSource::Connection(connection)
+                };
+            }
+            kAudioUnitProperty_SetRenderCallback => {
+                let callback = *(in_data as *const AURenderCallbackStruct);
+                *self.input_source.lock() = if callback.inputProc.is_none() {
+                    InputSource::None
+                } else {
+                    InputSource::Callback(callback)
+                };
+            }
+            kAudioUnitProperty_SampleRate => {
+                let sample_rate = *(in_data as *const f64);
+                return self.set_stream_format(scope, sample_rate, None);
+            }
+            kAudioUnitProperty_StreamFormat => {
+                let format = &*(in_data as *const AudioStreamBasicDescription);
+                if format.mFormatID != kAudioFormatLinearPCM
+                    || format.mFormatFlags & kAudioFormatFlagIsFloat == 0
+                    || format.mFormatFlags & kAudioFormatFlagIsNonInterleaved == 0
+                    || format.mBitsPerChannel != 32
+                {
+                    return kAudioUnitErr_FormatNotSupported;
+                }
+
+                return self.set_stream_format(
+                    scope,
+                    format.mSampleRate,
+                    Some(format.mChannelsPerFrame),
+                );
+            }
+            kAudioUnitProperty_MaximumFramesPerSlice => {
+                if self.is_initialized.load(Ordering::SeqCst) {
+                    return kAudioUnitErr_Initialized;
+                }
+
+                let max_frames = *(in_data as *const u32);
+                if max_frames == 0 {
+                    return kAudioUnitErr_InvalidPropertyValue;
+                }
+
+                self.max_frames_per_slice
+                    .store(max_frames, Ordering::SeqCst);
+                self.notify_property_listeners(
+                    kAudioUnitProperty_MaximumFramesPerSlice,
+                    kAudioUnitScope_Global,
+                );
+            }
+            kAudioUnitProperty_InPlaceProcessing => {
+                // We always support both, so there's nothing to configure here
+            }
+            kAudioUnitProperty_OfflineRender => {
+                let process_mode = if *(in_data as *const u32) != 0 {
+                    ProcessMode::Offline
+                } else {
+                    ProcessMode::Realtime
+                };
+                self.current_process_mode.store(process_mode);
+            }
+            kAudioUnitProperty_BypassEffect => {
+                let bypassed = *(in_data as *const u32) != 0;
+                if let Some(hash) = self
+                    .bypass_param
+                    .and_then(|param_ptr| self.param_ptr_to_hash.get(&param_ptr))
+                {
+                    self.set_normalized_value_by_hash(*hash, if bypassed { 1.0 } else { 0.0 });
+
+                    let task_posted = self.schedule_gui(Task::ParameterValueChanged(*hash));
+                    nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                }
+            }
+            kAudioUnitProperty_HostCallbacks => {
+                // Older hosts may pass a smaller struct without the newer callbacks
+                let mut host_callbacks: HostCallbackInfo = mem::zeroed();
+                ptr::copy_nonoverlapping(
+                    in_data as *const u8,
+                    &mut host_callbacks as *mut HostCallbackInfo as *mut u8,
+                    (in_data_size as usize).min(mem::size_of::<HostCallbackInfo>()),
+                );
+
+                *self.host_callbacks.lock() = Some(HostCallbacks(host_callbacks));
+            }
+            kAudioUnitProperty_PresentPreset => {
+                let preset = &*(in_data as *const AUPreset);
+                let name = if preset.presetName.is_null() {
+                    String::new()
+                } else {
+                    CFString::wrap_under_get_rule(preset.presetName).to_string()
+                };
+
+                *self.present_preset.lock() = (preset.presetNumber, name);
+            }
+            _ => return kAudioUnitErr_PropertyNotWritable,
+        }
+
+        noErr
+    }
+
+    /// Update the sample rate and optionally the channel count for one of the main ports. Audio
+    /// Units use the same sample rate for all ports.
+    fn set_stream_format(
+        &self,
+        scope: AudioUnitScope,
+        sample_rate: f64,
+        num_channels: Option<u32>,
+    ) -> OSStatus {
+        if sample_rate <= 0.0 {
+            return kAudioUnitErr_InvalidPropertyValue;
+        }
+
+        let current_channels = match scope {
+            kAudioUnitScope_Input => &self.input_channels,
+            _ => &self.output_channels,
+        };
+        let num_channels = num_channels.unwrap_or_else(|| current_channels.load(Ordering::SeqCst));
+        let is_supported = P::AUDIO_IO_LAYOUTS.iter().any(|layout| {
+            let layout_channels = match scope {
+                kAudioUnitScope_Input => layout.main_input_channels,
+                _ => layout.main_output_channels,
+            };
+
+            layout_channels.map(NonZeroU32::get) == Some(num_channels)
+        });
+        if !is_supported {
+            return kAudioUnitErr_FormatNotSupported;
+        }
+
+        let is_unchanged = self.sample_rate.load() == sample_rate
+            && current_channels.load(Ordering::SeqCst) == num_channels;
+        if is_unchanged {
+            return noErr;
+        }
+        if self.is_initialized.load(Ordering::SeqCst) {
+            return kAudioUnitErr_Initialized;
+        }
+
+        self.sample_rate.store(sample_rate);
+        current_channels.store(num_channels, Ordering::SeqCst);
+        self.notify_property_listeners(kAudioUnitProperty_StreamFormat, scope);
+
+        noErr
+    }
+
+    fn initialize(&self) -> OSStatus {
+        if self.is_initialized.load(Ordering::SeqCst) {
+            return noErr;
+        }
+
+        let Some(audio_io_layout) = Self::matching_audio_io_layout(
+            self.input_channels.load(Ordering::SeqCst),
+            self.output_channels.load(Ordering::SeqCst),
+        ) else {
+            nih_debug_assert_failure!(
+                "The host requested an unsupported channel configuration with {} inputs and {} \
+                 outputs",
+                self.input_channels.load(Ordering::SeqCst),
+                self.output_channels.load(Ordering::SeqCst)
+            );
+            return kAudioUnitErr_FormatNotSupported;
+        };
+        let buffer_config = BufferConfig {
+            sample_rate: self.sample_rate.load() as f32,
+            min_buffer_size: None,
+            max_buffer_size: self.max_frames_per_slice.load(Ordering::SeqCst),
+            process_mode: self.current_process_mode.load(),
+        };
+
+        // Before initializing the plugin, make sure all smoothers are set the the default values
+        for param in self.param_by_hash.values() {
+            unsafe { param.update_smoother(buffer_config.sample_rate, true) };
+        }
+
+        {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = self.make_init_context();
+            let mut plugin = self.plugin.lock();
+            if !plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context) {
+                return kAudioUnitErr_FailedInitialization;
+            }
+            process_wrapper(|| plugin.reset());
+        }
+
+        *self.render_buffers.borrow_mut() =
+            RenderBuffers::new(audio_io_layout, buffer_config.max_buffer_size as usize);
+        if P::MANAGED_BYPASS {
+            self.bypass_processor.borrow_mut().initialize(
+                audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+                self.current_latency.load(Ordering::SeqCst),
+                buffer_config.max_buffer_size,
+                buffer_config.sample_rate,
+                self.is_bypassed(),
+            );
+        }
+
+        self.current_audio_io_layout.store(audio_io_layout);
+        self.current_buffer_config.store(Some(buffer_config));
+        self.is_initialized.store(true, Ordering::SeqCst);
+
+        noErr
+    }
+
+    fn uninitialize(&self) -> OSStatus {
+        if self.is_initialized.swap(false, Ordering::SeqCst) {
+            self.current_buffer_config.store(None);
+            self.plugin.lock().deactivate();
+        }
+
+        noErr
+    }
+
+    fn reset(&self) -> OSStatus {
+        if self.is_initialized.load(Ordering::SeqCst) {
+            process_wrapper(|| self.plugin.lock().reset());
+            if P::MANAGED_BYPASS {
+                self.bypass_processor.borrow_mut().reset(self.is_bypassed());
+            }
+        }
+
+        while self.midi_events.pop().is_some() {}
+        while self.sysex_events.pop().is_some() {}
+
+        noErr
+    }
+
+    /// Build the transport information using the host's callbacks.
+    unsafe fn transport(&self, sample_rate: f32) -> Transport {
+        // Some of the fields are left empty because the host does not provide this information,
+        // but the methods on [`Transport`] can reconstruct these values from the other fields
+        let mut transport = Transport::new(sample_rate);
+        let Some(HostCallbacks(callbacks)) = *self.host_callbacks.lock() else {
+            return transport;
+        };
+
+        if let Some(beat_and_tempo_proc) = callbacks.beatAndTempoProc {
+            let mut current_beat = 0.0;
+            let mut current_tempo = 0.0;
+            if beat_and_tempo_proc(
+                callbacks.hostUserData,
+                &mut current_beat,
+                &mut current_tempo,
+            ) == noErr
+            {
+                transport.pos_beats = Some(current_beat);
+                transport.tempo = Some(current_tempo);
+            }
+        }
+
+        if let Some(musical_time_location_proc) = callbacks.musicalTimeLocationProc {
+            let mut delta_sample_offset_to_next_beat = 0;
+            let mut time_sig_numerator = 0.0;
+            let mut time_sig_denominator = 0;
+            let mut current_measure_down_beat = 0.0;
+            if musical_time_location_proc(
+                callbacks.hostUserData,
+                &mut delta_sample_offset_to_next_beat,
+                &mut time_sig_numerator,
+                &mut time_sig_denominator,
+                &mut current_measure_down_beat,
+            ) == noErr
+            {
+                transport.time_sig_numerator = Some(time_sig_numerator as i32);
+                transport.time_sig_denominator = Some(time_sig_denominator as i32);
+                transport.bar_start_pos_beats = Some(current_measure_down_beat);
+            }
+        }
+
+        let mut is_playing = 0;
+        let mut is_recording = 0;
+        let mut transport_state_changed = 0;
+        let mut current_sample_in_time_line = 0.0;
+        let mut is_cycling = 0;
+        let mut cycle_start_beat = 0.0;
+        let mut cycle_end_beat = 0.0;
+        let result = if let Some(transport_state_proc_2) = callbacks.transportStateProc2 {
+            transport_state_proc_2(
+                callbacks.hostUserData,
+                &mut is_playing,
+                &mut is_recording,
+                &mut transport_state_changed,
+                &mut current_sample_in_time_line,
+                &mut is_cycling,
+                &mut cycle_start_beat,
+                &mut cycle_end_beat,
+            )
+        } else if let Some(transport_state_proc) = callbacks.transportStateProc {
+            transport_state_proc(
+                callbacks.hostUserData,
+                &mut is_playing,
+                &mut transport_state_changed,
+                &mut current_sample_in_time_line,
+                &mut is_cycling,
+                &mut cycle_start_beat,
+                &mut cycle_end_beat,
+            )
+        } else {
+            kAudioUnitErr_InvalidProperty
+        };
+        if result == noErr {
+            transport.playing = is_playing != 0;
+            transport.recording = is_recording != 0;
+            transport.pos_samples = Some(current_sample_in_time_line as i64);
+            if is_cycling != 0 {
+                transport.loop_range_beats = Some((cycle_start_beat, cycle_end_beat));
+            }
+        }
+
+        transport
+    }
+
+    /// Call the callbacks registered through `AudioUnitAddRenderNotify()`.
+    unsafe fn call_render_notify_callbacks(
+        &self,
+        flags: AudioUnitRenderActionFlags,
+        in_time_stamp: *const AudioTimeStamp,
+        in_number_frames: u32,
+        io_data: *mut AudioBufferList,
+    ) {
+        for callback in self.render_notify_callbacks.lock().iter() {
+            let mut flags = flags;
+            (callback.proc_)(
+                callback.ref_con,
+                &mut flags,
+                in_time_stamp,
+                0,
+                in_number_frames,
+                io_data,
+            );
+        }
+    }
+
+    /// Pull the main input's audio from the upstream unit or render callback into
+    /// `render_buffers.input_buffer_list`.
+    unsafe fn pull_input(
+        &self,
+        render_buffers: &mut RenderBuffers,
+        in_time_stamp: *const AudioTimeStamp,
+        in_number_frames: u32,
+    ) -> OSStatus {
+        for (buffer, storage) in render_buffers
+            .input_buffer_list
+            .buffers_mut()
+            .iter_mut()
+            .zip(render_buffers.input_storage.iter_mut())
+        {
+            buffer.mNumberChannels = 1;
+            buffer.mDataByteSize = in_number_frames * mem::size_of::<f32>() as u32;
+            buffer.mData = storage.as_mut_ptr() as *mut c_void;
+        }
+
+        let mut flags = 0;
+        let input_buffer_list = render_buffers.input_buffer_list.as_mut_ptr();
+        match *self.input_source.lock() {
+            InputSource::Callback(AURenderCallbackStruct {
+                inputProc: Some(input_proc),
+                inputProcRefCon,
+            }) => input_proc(
+                inputProcRefCon,
+                &mut flags,
+                in_time_stamp,
+                0,
+                in_number_frames,
+                input_buffer_list,
+            ),
+            InputSource::Connection(connection) => AudioUnitRender(
+                connection.sourceAudioUnit,
+                &mut flags,
+                in_time_stamp,
+                connection.sourceOutputNumber,
+                in_number_frames,
+                input_buffer_list,
+            ),
+            _ => kAudioUnitErr_NoConnection,
+        }
+    }
+
+    unsafe fn render(
+        &self,
+        io_action_flags: *mut AudioUnitRenderActionFlags,
+        in_time_stamp: *const AudioTimeStamp,
+        in_output_bus_number: u32,
+        in_number_frames: u32,
+        io_data: *mut AudioBufferList,
+    ) -> OSStatus {
+        if !self.is_initialized.load(Ordering::SeqCst) {
+            return kAudioUnitErr_Uninitialized;
+        }
+        if in_output_bus_number != 0 {
+            return kAudioUnitErr_InvalidElement;
+        }
+        if in_time_stamp.is_null() || io_data.is_null() {
+            return kAudio_ParamError;
+        }
+        let buffer_config = self.current_buffer_config.load().unwrap();
+        if in_number_frames > buffer_config.max_buffer_size {
+            return kAudioUnitErr_TooManyFramesToProcess;
+        }
+
+        let action_flags = io_action_flags.as_ref().copied().unwrap_or(0);
+        self.call_render_notify_callbacks(
+            action_flags | kAudioUnitRenderAction_PreRender,
+            in_time_stamp,
+            in_number_frames,
+            io_data,
+        );
+
+        let result = process_wrapper(|| {
+            let audio_io_layout = self.current_audio_io_layout.load();
+            let num_input_channels = audio_io_layout
+                .main_input_channels
+                .map(NonZeroU32::get)
+                .unwrap_or(0) as usize;
+            let num_output_channels = audio_io_layout
+                .main_output_channels
+                .map(NonZeroU32::get)
+                .unwrap_or(0) as usize;
+            let num_samples = in_number_frames as usize;
+
+            let mut render_buffers = self.render_buffers.borrow_mut();
+            if num_input_channels > 0 {
+                let result = self.pull_input(&mut render_buffers, in_time_stamp, in_number_frames);
+                if result != noErr {
+                    return result;
+                }
+            }
+
+            // The host may pass null pointers in the output buffer list, in which case we need to
+            // provide our own buffers
+            let output_buffers = audio_buffer_list_buffers(io_data);
+            if output_buffers.len() != num_output_channels {
+                nih_debug_assert_failure!(
+                    "Expected {} output buffers, got {}",
+                    num_output_channels,
+                    output_buffers.len()
+                );
+                return kAudio_ParamError;
+            }
+
+            let RenderBuffers {
+                buffer_manager,
+                input_buffer_list,
+                output_storage,
+                input_channel_pointers,
+                output_channel_pointers,
+                ..
+            } = &mut *render_buffers;
+            for (output_buffer, (channel_pointer, storage)) in output_buffers.iter_mut().zip(
+                output_channel_pointers
+                    .iter_mut()
+                    .zip(output_storage.iter_mut()),
+            ) {
+                if output_buffer.mData.is_null() {
+                    output_buffer.mData = storage.as_mut_ptr() as *mut c_void;
+                }
+                output_buffer.mDataByteSize = in_number_frames * mem::size_of::<f32>() as u32;
+                *channel_pointer = output_buffer.mData as *mut f32;
+            }
+            // The upstream unit may have replaced our input buffer pointers with its own
+            for (input_buffer, channel_pointer) in input_buffer_list
+                .buffers_mut()
+                .iter()
+                .zip(input_channel_pointers.iter_mut())
+            {
+                *channel_pointer = input_buffer.mData as *mut f32;
+            }
+
+            // All of this render call's note events are collected first, and they're handed to the
+            // plugin per block since the render call may be split up at parameter changes below
+            let mut block_events = self.block_events.borrow_mut();
+            block_events.clear();
+            while let Some(event) = self.sysex_events.pop() {
+                block_events.push_back(event);
+            }
+            while let Some((offset, midi_data)) = self.midi_events.pop() {
+                let timing = clamp_input_event_timing(offset, in_number_frames);
+
+                // In the Basic note port type, we'll still handle note on, note off, and
+                // polyphonic pressure events. But we'll throw away any other MIDI messages to stay
+                // consistent with the other wrappers.
+                match NoteEvent::from_midi(timing, &midi_data) {
+                    Ok(
+                        note_event @ (NoteEvent::NoteOn { .. }
+                        | NoteEvent::NoteOff { .. }
+                        | NoteEvent::PolyPressure { .. }),
+                    ) if P::MIDI_INPUT >= MidiConfig::Basic => {
+                        block_events.push_back(note_event);
+                    }
+                    Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                        block_events.push_back(note_event);
+                    }
+                    Ok(_) => (),
+                    Err(n) => nih_debug_assert_failure!("Unhandled MIDI message type {}", n),
+                };
+            }
+
+            // If `P::SAMPLE_ACCURATE_AUTOMATION` is set, then we'll split the render call into
+            // blocks at the parameter changes scheduled with a buffer offset. Otherwise all of
+            // them are applied at the start of the render call.
+            let mut param_changes = self.param_changes.borrow_mut();
+            param_changes.clear();
+            // NOTE: This only allocates when the host sends more or longer ramps than what fits in
+            //       the preallocated capacity
+            permit_alloc(|| {
+                while let Some(event) = self.scheduled_param_events.pop() {
+                    expand_param_event(event, in_number_frames, &mut param_changes);
+                }
+            });
+
+            // NOTE: Both sorts need to be stable. Later changes to the same parameter need to win,
+            //       and the MIDI events at the same offset need to stay in order.
+            // FIXME: Apparently stable sort allcoates if the slice is large enough. This should be
+            //        fixed at some point.
+            permit_alloc(|| {
+                param_changes.sort_by_key(|change| change.offset);
+                block_events
+                    .make_contiguous()
+                    .sort_by_key(|event| event.timing());
+            });
+
+            let sample_rate = buffer_config.sample_rate;
+
+            // NOTE: `parking_lot`'s mutexes sometimes allocate because of their use of thread
+            //       locals
+            let mut plugin = permit_alloc(|| self.plugin.lock());
+            let mut bypass_processor = self.bypass_processor.borrow_mut();
+
+            let mut block_start = 0;
+            let mut param_change_idx = 0;
+            while block_start < num_samples {
+                // The parameter changes at the start of this block are applied before processing
+                // it, and the next change marks the end of the block
+                let mut block_end = num_samples;
+                while let Some(change) = param_changes.get(param_change_idx) {
+                    if P::SAMPLE_ACCURATE_AUTOMATION && change.offset as usize > block_start {
+                        block_end = change.offset as usize;
+                        break;
+                    }
+
+                    self.set_normalized_value_by_hash(change.hash, change.normalized_value);
+                    param_change_idx += 1;
+                }
+                let block_len = block_end - block_start;
+
+                // The extra scope is here to make sure we release the borrow on input_events
+                {
+                    let mut input_events = self.input_events.borrow_mut();
+                    input_events.clear();
+                    while let Some(event) = block_events.front() {
+                        if event.timing() as usize >= block_end {
+                            break;
+                        }
+
+                        // The event needs to be compensated for the block splitting
+                        let mut event = block_events.pop_front().unwrap();
+                        event.subtract_timing(block_start as u32);
+                        input_events.push_back(event);
+                    }
+                }
+
+                let buffers =
+                    buffer_manager.create_buffers(block_start, block_len, |buffer_source| {
+                        if num_input_channels > 0 {
+                            *buffer_source.main_input_channel_pointers = Some(ChannelPointers {
+                                ptrs: NonNull::new(input_channel_pointers.as_mut_ptr()).unwrap(),
+                                num_channels: num_input_channels,
+                            });
+                        }
+                        if num_output_channels > 0 {
+                            *buffer_source.main_output_channel_pointers = Some(ChannelPointers {
+                                ptrs: NonNull::new(output_channel_pointers.as_mut_ptr()).unwrap(),
+                                num_channels: num_output_channels,
+                            });
+                        }
+                    });
+
+                // The host's callbacks report the transport information for the start of the
+                // render call, so this needs to be compensated for the block splitting
+                let mut transport = self.transport(sample_rate);
+                if block_start > 0 {
+                    transport.pos_samples = transport
+                        .pos_samples
+                        .map(|pos_samples| pos_samples + block_start as i64);
+                    if let (Some(pos_beats), Some(tempo)) = (transport.pos_beats, transport.tempo) {
+                        transport.pos_beats = Some(
+                            pos_beats + (block_start as f64 / sample_rate as f64 / 60.0 * tempo),
+                        );
+                    }
+                }
+
+                if let Some(modulation_matrix) = &self.modulation_matrix {
+                    modulation_matrix.process_block(
+                        buffers.main_buffer,
+                        self.input_events.borrow().iter(),
+                        sample_rate,
+                        state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    );
+                }
+
+                let mut aux = AuxiliaryBuffers {
+                    inputs: buffers.aux_inputs,
+                    outputs: buffers.aux_outputs,
+                };
+                let mut context = self.make_process_context(transport);
+                if P::MANAGED_BYPASS {
+                    bypass_processor.write_dry(buffers.main_buffer);
+                }
+                let result = plugin.process(buffers.main_buffer, &mut aux, &mut context);
+                if P::MANAGED_BYPASS {
+                    bypass_processor.process(
+                        buffers.main_buffer,
+                        self.current_latency.load(Ordering::Relaxed),
+                        self.is_bypassed(),
+                    );
+                }
+                self.last_process_status.store(result);
+
+                if let ProcessStatus::Error(err) = result {
+                    nih_debug_assert_failure!("Process error: {}", err);
+
+                    // There's no dedicated error code for this
+                    return kAudioUnitErr_CannotDoInCurrentContext;
+                }
+
+                block_start = block_end;
+            }
+
+            // Parameter changes still need to be applied if the host rendered zero samples
+            for change in &param_changes[param_change_idx..] {
+                self.set_normalized_value_by_hash(change.hash, change.normalized_value);
+            }
+
+            noErr
+        });
+
+        let mut post_render_flags = action_flags | kAudioUnitRenderAction_PostRender;
+        if result != noErr {
+            post_render_flags |= kAudioUnitRenderAction_PostRenderError;
+
+            self.last_render_error.store(result, Ordering::SeqCst);
+            let task_posted =
+                self.schedule_gui(Task::PropertyChanged(kAudioUnitProperty_LastRenderError));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+        self.call_render_notify_callbacks(
+            post_render_flags,
+            in_time_stamp,
+            in_number_frames,
+            io_data,
+        );
+
+        result
+    }
+}
+
+/// Turn a scheduled parameter change or ramp into the parameter changes that fall within a render
+/// call of `num_samples` samples. Like with note events, offsets past the end of the render call
+/// are clamped to its last sample.
+fn expand_param_event(
+    event: ScheduledParamEvent,
+    num_samples: u32,
+    changes: &mut Vec<ParamChange>,
+) {
+    let num_samples = num_samples as i64;
+    let mut push = |offset: i64, normalized_value: f32| {
+        changes.push(ParamChange {
+            offset: offset.clamp(0, (num_samples - 1).max(0)) as u32,
+            hash: event.hash,
+            normalized_value,
+        })
+    };
+
+    if event.duration == 0 {
+        push(event.start_offset, event.end_normalized_value);
+        return;
+    }
+
+    // Hosts send ramps that continue past the end of the render call again during the next render
+    // call with a negative start offset, so only the part of the ramp within this render call is
+    // handled here
+    let ramp_end = event.start_offset + event.duration as i64;
+    let mut offset = event.start_offset.max(0);
+    while offset < ramp_end.min(num_samples) {
+        let t = (offset - event.start_offset) as f32 / event.duration as f32;
+        push(
+            offset,
+            event.start_normalized_value
+                + ((event.end_normalized_value - event.start_normalized_value) * t),
+        );
+        offset += PARAM_RAMP_STEP_SIZE as i64;
+    }
+    if ramp_end <= num_samples {
+        push(ramp_end, event.end_normalized_value);
+    }
+}
+
+impl RenderBuffers {
+    fn new(audio_io_layout: AudioIOLayout, max_buffer_size: usize) -> Self {
+        let num_input_channels = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        let num_output_channels = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+
+        Self {
+            buffer_manager: BufferManager::for_audio_io_layout(max_buffer_size, audio_io_layout),
+            input_buffer_list: OwnedAudioBufferList::new(num_input_channels),
+            input_storage: vec![vec![0.0; max_buffer_size]; num_input_channels],
+            output_storage: vec![vec![0.0; max_buffer_size]; num_output_channels],
+            input_channel_pointers: vec![ptr::null_mut(); num_input_channels],
+            output_channel_pointers: vec![ptr::null_mut(); num_output_channels],
+        }
+    }
+}
+
+/// The channel configurations reported through `kAudioUnitProperty_SupportedNumChannels`, based on
+/// the plugin's audio IO layouts.
+fn supported_channel_infos<P: Plugin>() -> Vec<AUChannelInfo> {
+    let mut channel_infos: Vec<AUChannelInfo> = Vec::new();
+    for layout in P::AUDIO_IO_LAYOUTS {
+        let channel_info = AUChannelInfo {
+            inChannels: layout.main_input_channels.map(NonZeroU32::get).unwrap_or(0) as i16,
+            outChannels: layout
+                .main_output_channels
+                .map(NonZeroU32::get)
+                .unwrap_or(0) as i16,
+        };
+
+        // Layouts that only differ in their auxiliary ports map to the same configuration
+        if !channel_infos.iter().any(|info| {
+            info.inChannels == channel_info.inChannels
+                && info.outChannels == channel_info.outChannels
+        }) {
+            channel_infos.push(channel_info);
+        }
+    }
+
+    channel_infos
+}
+
+/// Create a `CFStringRef` whose ownership is transferred to the host.
+fn cfstring_into_raw(string: &str) -> CFStringRef {
+    let string = CFString::new(string);
+    let string_ref = string.as_concrete_TypeRef();
+    mem::forget(string);
+
+    string_ref
+}
+
+/// Get the wrapper from the instance pointer passed to the Audio Unit functions.
+///
+/// # Safety
+///
+/// `this` must be a pointer returned from [`Wrapper::factory()`] that has not yet been closed.
+unsafe fn wrapper<'a, P: AuPlugin>(this: *mut c_void) -> &'a Wrapper<P> {
+    &(*(this as *const AudioUnitInstance<P>)).wrapper
+}
+
+unsafe extern "C" fn open<P: AuPlugin>(
+    this: *mut c_void,
+    instance: AudioComponentInstance,
+) -> OSStatus {
+    wrapper::<P>(this)
+        .component_instance
+        .store(instance, Ordering::SeqCst);
+
+    noErr
+}
+
+unsafe extern "C" fn close<P: AuPlugin>(this: *mut c_void) -> OSStatus {
+    let instance = Box::from_raw(this as *mut AudioUnitInstance<P>);
+    instance.wrapper.uninitialize();
+    drop(instance);
+
+    noErr
+}
+
+unsafe extern "C" fn lookup<P: AuPlugin>(selector: i16) -> Option<AudioComponentMethod> {
+    let method = match selector {
+        kAudioUnitInitializeSelect => au_initialize::<P> as *const (),
+        kAudioUnitUninitializeSelect => au_uninitialize::<P> as *const (),
+        kAudioUnitGetPropertyInfoSelect => au_get_property_info::<P> as *const (),
+        kAudioUnitGetPropertySelect => au_get_property::<P> as *const (),
+        kAudioUnitSetPropertySelect => au_set_property::<P> as *const (),
+        kAudioUnitAddPropertyListenerSelect => au_add_property_listener::<P> as *const (),
+        kAudioUnitRemovePropertyListenerSelect => au_remove_property_listener::<P> as *const (),
+        kAudioUnitRemovePropertyListenerWithUserDataSelect => {
+            au_remove_property_listener_with_user_data::<P> as *const ()
+        }
+        kAudioUnitAddRenderNotifySelect => au_add_render_notify::<P> as *const (),
+        kAudioUnitRemoveRenderNotifySelect => au_remove_render_notify::<P> as *const (),
+        kAudioUnitGetParameterSelect => au_get_parameter::<P> as *const (),
+        kAudioUnitSetParameterSelect => au_set_parameter::<P> as *const (),
+        kAudioUnitScheduleParametersSelect => au_schedule_parameters::<P> as *const (),
+        kAudioUnitRenderSelect => au_render::<P> as *const (),
+        kAudioUnitResetSelect => au_reset::<P> as *const (),
+        kMusicDeviceMIDIEventSelect if P::AU_TYPE.receives_midi() => {
+            music_device_midi_event::<P> as *const ()
+        }
+        kMusicDeviceSysExSelect if P::AU_TYPE.receives_midi() => {
+            music_device_sysex::<P> as *const ()
+        }
+        _ => return None,
+    };
+
+    // SAFETY: The host casts this back to the selector's function signature before calling it
+    Some(mem::transmute::<*const (), AudioComponentMethod>(method))
+}
+
+unsafe extern "C" fn au_initialize<P: AuPlugin>(this: *mut c_void) -> OSStatus {
+    wrapper::<P>(this).initialize()
+}
+
+unsafe extern "C" fn au_uninitialize<P: AuPlugin>(this: *mut c_void) -> OSStatus {
+    wrapper::<P>(this).uninitialize()
+}
+
+unsafe extern "C" fn au_get_property_info<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    scope: AudioUnitScope,
+    element: AudioUnitElement,
+    out_data_size: *mut u32,
+    out_writable: *mut Boolean,
+) -> OSStatus {
+    match wrapper::<P>(this).property_info(id, scope, element) {
+        Ok((size, writable)) => {
+            if !out_data_size.is_null() {
+                *out_data_size = size;
+            }
+            if !out_writable.is_null() {
+                *out_writable = writable as Boolean;
+            }
+
+            noErr
+        }
+        Err(err) => err,
+    }
+}
+
+unsafe extern "C" fn au_get_property<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    scope: AudioUnitScope,
+    element: AudioUnitElement,
+    out_data: *mut c_void,
+    io_data_size: *mut u32,
+) -> OSStatus {
+    if io_data_size.is_null() {
+        return kAudio_ParamError;
+    }
+
+    let wrapper = wrapper::<P>(this);
+    let size = match wrapper.property_info(id, scope, element) {
+        Ok((size, _)) => size,
+        Err(err) => return err,
+    };
+
+    // Hosts may query the size by passing a null pointer
+    if out_data.is_null() {
+        *io_data_size = size;
+        return noErr;
+    }
+    if *io_data_size < size {
+        return kAudioUnitErr_InvalidPropertyValue;
+    }
+
+    let result = wrapper.get_property(id, scope, element, out_data);
+    if result == noErr {
+        *io_data_size = size;
+    }
+
+    result
+}
+
+unsafe extern "C" fn au_set_property<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    scope: AudioUnitScope,
+    element: AudioUnitElement,
+    in_data: *const c_void,
+    in_data_size: u32,
+) -> OSStatus {
+    let wrapper = wrapper::<P>(this);
+    let size = match wrapper.property_info(id, scope, element) {
+        Ok((_, false)) => return kAudioUnitErr_PropertyNotWritable,
+        Ok((size, true)) => size,
+        Err(err) => return err,
+    };
+
+    // Passing a null pointer resets the property, which we only support for connections
+    if in_data.is_null() {
+        return match id {
+            kAudioUnitProperty_MakeConnection | kAudioUnitProperty_SetRenderCallback => {
+                *wrapper.input_source.lock() = InputSource::None;
+                noErr
+            }
+            _ => kAudio_ParamError,
+        };
+    }
+    // The host callbacks struct has grown over time, so older hosts may pass a smaller struct
+    if in_data_size < size && id != kAudioUnitProperty_HostCallbacks {
+        return kAudioUnitErr_InvalidPropertyValue;
+    }
+
+    wrapper.set_property(id, scope, in_data, in_data_size)
+}
+
+unsafe extern "C" fn au_add_property_listener<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    proc_: Option<AudioUnitPropertyListenerProc>,
+    user_data: *mut c_void,
+) -> OSStatus {
+    let Some(proc_) = proc_ else {
+        return kAudio_ParamError;
+    };
+
+    wrapper::<P>(this)
+        .property_listeners
+        .lock()
+        .push(PropertyListener {
+            id,
+            proc_,
+            user_data,
+        });
+
+    noErr
+}
+
+unsafe extern "C" fn au_remove_property_listener<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    proc_: Option<AudioUnitPropertyListenerProc>,
+) -> OSStatus {
+    wrapper::<P>(this)
+        .property_listeners
+        .lock()
+        .retain(|listener| !(listener.id == id && Some(listener.proc_) == proc_));
+
+    noErr
+}
+
+unsafe extern "C" fn au_remove_property_listener_with_user_data<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitPropertyID,
+    proc_: Option<AudioUnitPropertyListenerProc>,
+    user_data: *mut c_void,
+) -> OSStatus {
+    wrapper::<P>(this)
+        .property_listeners
+        .lock()
+        .retain(|listener| {
+            !(listener.id == id && Some(listener.proc_) == proc_ && listener.user_data == user_data)
+        });
+
+    noErr
+}
+
+unsafe extern "C" fn au_add_render_notify<P: AuPlugin>(
+    this: *mut c_void,
+    proc_: Option<AURenderCallback>,
+    ref_con: *mut c_void,
+) -> OSStatus {
+    let Some(proc_) = proc_ else {
+        return kAudio_ParamError;
+    };
+
+    let callback = RenderNotifyCallback { proc_, ref_con };
+    let mut callbacks = wrapper::<P>(this).render_notify_callbacks.lock();
+    if !callbacks.contains(&callback) {
+        callbacks.push(callback);
+    }
+
+    noErr
+}
+
+unsafe extern "C" fn au_remove_render_notify<P: AuPlugin>(
+    this: *mut c_void,
+    proc_: Option<AURenderCallback>,
+    ref_con: *mut c_void,
+) -> OSStatus {
+    wrapper::<P>(this)
+        .render_notify_callbacks
+        .lock()
+        .retain(|callback| !(Some(callback.proc_) == proc_ && callback.ref_con == ref_con));
+
+    noErr
+}
+
+unsafe extern "C" fn au_get_parameter<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitParameterID,
+    scope: AudioUnitScope,
+    _element: AudioUnitElement,
+    out_value: *mut AudioUnitParameterValue,
+) -> OSStatus {
+    if out_value.is_null() {
+        return kAudio_ParamError;
+    }
+    if scope != kAudioUnitScope_Global {
+        return kAudioUnitErr_InvalidScope;
+    }
+
+    match wrapper::<P>(this).param_by_hash.get(&id) {
+        Some(param_ptr) => {
+            *out_value = Wrapper::<P>::normalized_to_au_value(
+                *param_ptr,
+                param_ptr.unmodulated_normalized_value(),
+            );
+
+            noErr
+        }
+        None => kAudioUnitErr_InvalidParameter,
+    }
+}
+
+unsafe extern "C" fn au_set_parameter<P: AuPlugin>(
+    this: *mut c_void,
+    id: AudioUnitParameterID,
+    scope: AudioUnitScope,
+    _element: AudioUnitElement,
+    value: AudioUnitParameterValue,
+    buffer_offset_in_frames: u32,
+) -> OSStatus {
+    if scope != kAudioUnitScope_Global {
+        return kAudioUnitErr_InvalidScope;
+    }
+
+    let wrapper = wrapper::<P>(this);
+    match wrapper.param_by_hash.get(&id) {
+        Some(param_ptr) => {
+            // Changes with a buffer offset are applied at that offset during the next render call.
+            // Everything else, like changes made through the host's generic UI, is applied
+            // immediately.
+            let normalized = Wrapper::<P>::au_value_to_normalized(*param_ptr, value);
+            let scheduled = buffer_offset_in_frames > 0
+                && wrapper.schedule_param_event(ScheduledParamEvent {
+                    hash: id,
+                    start_offset: buffer_offset_in_frames as i64,
+                    duration: 0,
+                    start_normalized_value: normalized,
+                    end_normalized_value: normalized,
+                });
+            if !scheduled {
+                wrapper.set_normalized_value_by_hash(id, normalized);
+            }
+
+            noErr
+        }
+        None => kAudioUnitErr_InvalidParameter,
+    }
+}
+
+unsafe extern "C" fn au_schedule_parameters<P: AuPlugin>(
+    this: *mut c_void,
+    events: *const AudioUnitParameterEvent,
+    num_events: u32,
+) -> OSStatus {
+    if events.is_null() {
+        return kAudio_ParamError;
+    }
+
+    let wrapper = wrapper::<P>(this);
+    for event in std::slice::from_raw_parts(events, num_events as usize) {
+        match event.eventType {
+            kParameterEvent_Immediate => {
+                let immediate = event.eventValues.immediate;
+                let result = au_set_parameter::<P>(
+                    this,
+                    event.parameter,
+                    event.scope,
+                    event.element,
+                    immediate.value,
+                    immediate.bufferOffset,
+                );
+                if result != noErr {
+                    return result;
+                }
+            }
+            kParameterEvent_Ramped => {
+                if event.scope != kAudioUnitScope_Global {
+                    return kAudioUnitErr_InvalidScope;
+                }
+                let Some(param_ptr) = wrapper.param_by_hash.get(&event.parameter) else {
+                    return kAudioUnitErr_InvalidParameter;
+                };
+
+                let ramp = event.eventValues.ramp;
+                let end_normalized_value =
+                    Wrapper::<P>::au_value_to_normalized(*param_ptr, ramp.endValue);
+                let scheduled = wrapper.schedule_param_event(ScheduledParamEvent {
+                    hash: event.parameter,
+                    start_offset: ramp.startBufferOffset as i64,
+                    duration: ramp.durationInFrames,
+                    start_normalized_value: Wrapper::<P>::au_value_to_normalized(
+                        *param_ptr,
+                        ramp.startValue,
+                    ),
+                    end_normalized_value,
+                });
+
+                // If the ramp cannot be applied during the next render call, then the parameter
+                // jumps to its end value instead. The parameter's smoother will still smooth out
+                // the change.
+                if !scheduled {
+                    wrapper.set_normalized_value_by_hash(event.parameter, end_normalized_value);
+                }
+            }
+            _ => (),
+        }
+    }
+
+    noErr
+}
+
+unsafe extern "C" fn au_render<P: AuPlugin>(
+    this: *mut c_void,
+    io_action_flags: *mut AudioUnitRenderActionFlags,
+    in_time_stamp: *const AudioTimeStamp,
+    in_output_bus_number: u32,
+    in_number_frames: u32,
+    io_data: *mut AudioBufferList,
+) -> OSStatus {
+    wrapper::<P>(this).render(
+        io_action_flags,
+        in_time_stamp,
+        in_output_bus_number,
+        in_number_frames,
+        io_data,
+    )
+}
+
+unsafe extern "C" fn au_reset<P: AuPlugin>(
+    this: *mut c_void,
+    _scope: AudioUnitScope,
+    _element: AudioUnitElement,
+) -> OSStatus {
+    wrapper::<P>(this).reset()
+}
+
+unsafe extern "C" fn music_device_midi_event<P: AuPlugin>(
+    this: *mut c_void,
+    status: u32,
+    data1: u32,
+    data2: u32,
+    offset_sample_frame: u32,
+) -> OSStatus {
+    let push_successful = wrapper::<P>(this)
+        .midi_events
+        .push((
+            offset_sample_frame,
+            [status as u8, data1 as u8, data2 as u8],
+        ))
+        .is_ok();
+    nih_debug_assert!(push_successful, "The MIDI event queue is full");
+
+    noErr
+}
+
+unsafe extern "C" fn music_device_sysex<P: AuPlugin>(
+    this: *mut c_void,
+    data: *const u8,
+    length: u32,
+) -> OSStatus {
+    if data.is_null() {
+        return kAudio_ParamError;
+    }
+
+    // `NoteEvent::from_midi` prints some tracing if parsing fails, which is not necessarily an
+    // error
+    let sysex_buffer = std::slice::from_raw_parts(data, length as usize);
+    if let Ok(note_event) = NoteEvent::from_midi(0, sysex_buffer) {
+        let push_successful = wrapper::<P>(this).sysex_events.push(note_event).is_ok();
+        nih_debug_assert!(push_successful, "The SysEx event queue is full");
+    }
+
+    noErr
+}
@@ -1,12 +1,14 @@
 #[macro_use]
 mod util;
 
+mod ara;
 mod context;
 mod descriptor;
 pub mod features;
 mod wrapper;
 
 /// Re-export for the macro
+pub use self::ara::{clap_ara_factory, CLAP_EXT_ARA_FACTORY};
 pub use self::descriptor::PluginDescriptor;
 pub use self::wrapper::Wrapper;
 pub use clap_sys::entry::clap_plugin_entry;
@@ -29,6 +31,7 @@ macro_rules! nih_export_clap {
             use $crate::wrapper::setup_logger;
             use $crate::wrapper::clap::{PluginDescriptor, Wrapper};
             use $crate::wrapper::clap::{CLAP_PLUGIN_FACTORY_ID, clap_host, clap_plugin, clap_plugin_descriptor, clap_plugin_factory};
+            use $crate::wrapper::clap::{CLAP_EXT_ARA_FACTORY, clap_ara_factory};
             use ::std::collections::HashSet;
             use ::std::ffi::{CStr, c_void};
             use ::std::os::raw::c_char;
@@ -44,6 +47,12 @@ macro_rules! nih_export_clap {
                 create_plugin: Some(create_plugin),
             };
 
+            const CLAP_ARA_FACTORY: clap_ara_factory = clap_ara_factory {
+                get_factory_count: Some(get_ara_factory_count),
+                get_ara_factory: Some(get_ara_factory),
+                get_plugin_id: Some(get_ara_plugin_id),
+            };
+
             // Sneaky way to get the number of expanded elements
             const PLUGIN_COUNT: usize = [$(stringify!($plugin_ty)),+].len();
 
@@ -114,6 +123,36 @@ macro_rules! nih_export_clap {
                 ::std::ptr::null()
             }
 
+            /// The descriptors for the plugins that support ARA. These are only ever non-empty when
+            /// NIH-plug's `ara` feature is enabled.
+            fn ara_plugin_descriptors() -> impl Iterator<Item = &'static PluginDescriptor> {
+                plugin_descriptors().iter().filter(|descriptor| !descriptor.ara_factory().is_null())
+            }
+
+            unsafe extern "C" fn get_ara_factory_count(_factory: *const clap_ara_factory) -> u32 {
+                ara_plugin_descriptors().count() as u32
+            }
+
+            unsafe extern "C" fn get_ara_factory(
+                _factory: *const clap_ara_factory,
+                index: u32,
+            ) -> *const c_void {
+                match ara_plugin_descriptors().nth(index as usize) {
+                    Some(descriptor) => descriptor.ara_factory(),
+                    None => ::std::ptr::null()
+                }
+            }
+
+            unsafe extern "C" fn get_ara_plugin_id(
+                _factory: *const clap_ara_factory,
+                index: u32,
+            ) -> *const c_char {
+                match ara_plugin_descriptors().nth(index as usize) {
+                    Some(descriptor) => descriptor.clap_id().as_ptr(),
+                    None => ::std::ptr::null()
+                }
+            }
+
             pub extern "C" fn init(_plugin_path: *const c_char) -> bool {
                 setup_logger();
                 true
@@ -122,8 +161,15 @@ macro_rules! nih_export_clap {
             pub extern "C" fn deinit() {}
 
             pub extern "C" fn get_factory(factory_id: *const c_char) -> *const c_void {
-                if !factory_id.is_null() && unsafe { CStr::from_ptr(factory_id) } == CLAP_PLUGIN_FACTORY_ID {
+                if factory_id.is_null() {
+                    return ::std::ptr::null();
+                }
+
+                let factory_id = unsafe { CStr::from_ptr(factory_id) };
+                if factory_id == CLAP_PLUGIN_FACTORY_ID {
                     &CLAP_PLUGIN_FACTORY as *const _ as *const c_void
+                } else if factory_id == CLAP_EXT_ARA_FACTORY && ara_plugin_descriptors().next().is_some() {
+                    &CLAP_ARA_FACTORY as *const _ as *const c_void
                 } else {
                     ::std::ptr::null()
                 }
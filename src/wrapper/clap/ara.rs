@@ -0,0 +1,54 @@
+//! Definitions for the ARA CLAP extensions from the ARA SDK's `ARA_API/ARACLAP.h`. These are only
+//! used when the `ara` feature is enabled, but the definitions themselves are needed by the
+//! `nih_export_clap!()` macro regardless of the features enabled for NIH-plug. ARA's types are
+//! passed around as void pointers to avoid depending on the ARA bindings.
+
+use std::ffi::{c_void, CStr};
+use std::os::raw::c_char;
+
+/// The factory ID for the ARA factory. This is queried through the CLAP entry point.
+pub const CLAP_EXT_ARA_FACTORY: &CStr =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"com.celemony.ara.factory.2\0") };
+/// The extension ID for binding plugin instances to ARA document controllers.
+#[cfg(feature = "ara")]
+pub const CLAP_EXT_ARA_PLUGINEXTENSION: &CStr =
+    unsafe { CStr::from_bytes_with_nul_unchecked(b"com.celemony.ara.plugin_extension.2\0") };
+
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct clap_ara_factory {
+    pub get_factory_count: Option<unsafe extern "C" fn(factory: *const clap_ara_factory) -> u32>,
+    /// Returns a `const ARAFactory *`.
+    pub get_ara_factory:
+        Option<unsafe extern "C" fn(factory: *const clap_ara_factory, index: u32) -> *const c_void>,
+    pub get_plugin_id:
+        Option<unsafe extern "C" fn(factory: *const clap_ara_factory, index: u32) -> *const c_char>,
+}
+
+unsafe impl Send for clap_ara_factory {}
+unsafe impl Sync for clap_ara_factory {}
+
+#[cfg(feature = "ara")]
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+#[allow(non_camel_case_types)]
+pub struct clap_ara_plugin_extension {
+    /// Returns a `const ARAFactory *`.
+    pub get_factory:
+        Option<unsafe extern "C" fn(plugin: *const clap_sys::plugin::clap_plugin) -> *const c_void>,
+    /// Returns a `const ARAPlugInExtensionInstance *`.
+    pub bind_to_document_controller: Option<
+        unsafe extern "C" fn(
+            plugin: *const clap_sys::plugin::clap_plugin,
+            document_controller_ref: *mut c_void,
+            known_roles: u32,
+            assigned_roles: u32,
+        ) -> *const c_void,
+    >,
+}
+
+#[cfg(feature = "ara")]
+unsafe impl Send for clap_ara_plugin_extension {}
+#[cfg(feature = "ara")]
+unsafe impl Sync for clap_ara_plugin_extension {}
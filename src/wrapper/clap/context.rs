@@ -1,4 +1,4 @@
-use atomic_refcell::AtomicRefMut;
+use atomic_refcell::{AtomicRefCell, AtomicRefMut};
 use clap_sys::ext::draft::remote_controls::{
     clap_remote_controls_page, CLAP_REMOTE_CONTROLS_COUNT,
 };
@@ -7,14 +7,24 @@ use clap_sys::string_sizes::CLAP_NAME_SIZE;
 use std::cell::Cell;
 use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 
-use super::wrapper::{OutputParamEvent, Task, Wrapper};
-use crate::event_loop::EventLoop;
+use super::wrapper::{
+    normalized_to_clap_value, OutputParamEvent, OutputParamValue, Task, Wrapper,
+    OUTPUT_PARAM_VALUE_QUEUE_CAPACITY,
+};
+use crate::context::gui::{GuiMessage, ParamChangeReceiver, StateSlot, TimerHandle};
+use crate::event_loop::{EventLoop, TimerCallback};
 use crate::prelude::{
-    ClapPlugin, GuiContext, InitContext, ParamPtr, PluginApi, PluginNoteEvent, ProcessContext,
-    RemoteControlsContext, RemoteControlsPage, RemoteControlsSection, Transport,
+    ClapPlugin, GuiContext, InitContext, NoteEvent, ParamIndication, ParamPtr, PluginApi,
+    PluginNoteEvent, ProcessContext, ProcessMode, RemoteControlsContext, RemoteControlsPage,
+    RemoteControlsSection, TrackInfo, Transport,
 };
-use crate::wrapper::util::strlcpy;
+use crate::wrapper::util::clipboard::Clipboard;
+use crate::wrapper::util::group_edit::ParamGroupEdit;
+use crate::wrapper::util::param_links::ParamLinks;
+use crate::wrapper::util::window_focus;
+use crate::wrapper::util::{strlcpy, tail_seconds_to_samples};
 
 /// An [`InitContext`] implementation for the wrapper.
 ///
@@ -40,9 +50,13 @@ pub(crate) struct PendingInitContextRequests {
 /// unnecessary atomic operations to lock the uncontested `RwLock`s.
 pub(crate) struct WrapperProcessContext<'a, P: ClapPlugin> {
     pub(super) wrapper: &'a Wrapper<P>,
-    pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
-    pub(super) output_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<(u16, PluginNoteEvent<P>)>>,
+    pub(super) output_events_guard: AtomicRefMut<'a, VecDeque<(u16, PluginNoteEvent<P>)>>,
+    pub(super) output_param_values_guard: AtomicRefMut<'a, VecDeque<OutputParamValue>>,
     pub(super) transport: Transport,
+    /// The number of auxiliary inputs in the current audio IO layout. Used for
+    /// [`ProcessContext::bus_active()`].
+    pub(super) aux_input_count: usize,
 }
 
 /// A [`GuiContext`] implementation for the wrapper. This is passed to the plugin in
@@ -50,6 +64,12 @@ pub(crate) struct WrapperProcessContext<'a, P: ClapPlugin> {
 /// with the host for things like setting parameters.
 pub(crate) struct WrapperGuiContext<P: ClapPlugin> {
     pub(super) wrapper: Arc<Wrapper<P>>,
+    /// Defers the end of parameter gestures while a group edit is active.
+    pub(super) group_edit: AtomicRefCell<ParamGroupEdit>,
+    /// Changes made to linked parameters are also applied to their partners.
+    pub(super) param_links: AtomicRefCell<ParamLinks>,
+    /// The system clipboard, kept alive for as long as the editor exists.
+    pub(super) clipboard: Clipboard,
     #[cfg(debug_assertions)]
     pub(super) param_gesture_checker:
         atomic_refcell::AtomicRefCell<crate::wrapper::util::context_checks::ParamGestureChecker>,
@@ -98,7 +118,7 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
     }
 
     fn execute_background(&self, task: P::BackgroundTask) {
-        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        let task_posted = self.wrapper.schedule_plugin_background_task(task);
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
@@ -107,42 +127,132 @@ impl<P: ClapPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.wrapper.cancel_background_tasks(tag);
+    }
+
     #[inline]
     fn transport(&self) -> &Transport {
         &self.transport
     }
 
+    fn process_mode(&self) -> ProcessMode {
+        self.wrapper.current_process_mode.load()
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        self.wrapper.track_info.load_full()
+    }
+
+    fn bypassed(&self) -> bool {
+        self.wrapper.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.aux_input_count
+    }
+
     fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
-        self.input_events_guard.pop_front()
+        self.input_events_guard.pop_front().map(|(_, event)| event)
     }
 
     fn send_event(&mut self, event: PluginNoteEvent<P>) {
-        self.output_events_guard.push_back(event);
+        self.output_events_guard.push_back((0, event));
+    }
+
+    fn next_event_with_port(&mut self) -> Option<(usize, PluginNoteEvent<P>)> {
+        self.input_events_guard
+            .pop_front()
+            .map(|(port_index, event)| (port_index as usize, event))
+    }
+
+    fn send_event_to_port(&mut self, port_index: usize, event: PluginNoteEvent<P>) {
+        if port_index < P::MIDI_OUTPUT_PORTS.len() {
+            self.output_events_guard
+                .push_back((port_index as u16, event));
+        } else {
+            nih_debug_assert_failure!("Out of bounds note output port {}", port_index);
+        }
+    }
+
+    fn send_event_at(&mut self, sample: u64, event: PluginNoteEvent<P>) {
+        let scheduled = self
+            .wrapper
+            .event_scheduler
+            .borrow_mut()
+            .schedule(sample, event);
+        nih_debug_assert!(scheduled, "Too many scheduled events, dropping event...");
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.wrapper.event_scheduler.borrow().position()
+    }
+
+    fn raw_set_parameter_normalized(&mut self, timing: u32, param: ParamPtr, normalized: f32) {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(&param_hash) => {
+                let clap_plain_value = normalized_to_clap_value(&param, normalized);
+                if self.output_param_values_guard.len() < OUTPUT_PARAM_VALUE_QUEUE_CAPACITY {
+                    self.output_param_values_guard.push_back(OutputParamValue {
+                        timing,
+                        param_hash,
+                        clap_plain_value,
+                    });
+                    return;
+                }
+
+                // The queue is full and growing it would allocate on the audio thread. Merging this
+                // change into the parameter's last queued change at least makes sure the host ends
+                // up with the correct value at the end of the block.
+                match self
+                    .output_param_values_guard
+                    .iter_mut()
+                    .rev()
+                    .find(|change| change.param_hash == param_hash)
+                {
+                    Some(change) => change.clap_plain_value = clap_plain_value,
+                    None => nih_debug_assert_failure!(
+                        "Too many parameter changes in a single block, dropping change..."
+                    ),
+                }
+            }
+            None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+        }
     }
 
     fn set_latency_samples(&self, samples: u32) {
         self.wrapper.set_latency_samples(samples)
     }
 
-    fn set_current_voice_capacity(&self, capacity: u32) {
-        self.wrapper.set_current_voice_capacity(capacity)
+    fn run_tasks_parallel(&self, count: usize, task: impl Fn(usize) + Sync) {
+        self.wrapper.run_tasks_parallel(count, &task)
     }
-}
 
-impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
-    fn plugin_api(&self) -> PluginApi {
-        PluginApi::Clap
+    fn note_frequency(&self, note: u8, channel: u8, tuning_id: Option<u32>) -> f32 {
+        self.wrapper.note_frequency(note, channel, tuning_id)
     }
 
-    fn request_resize(&self) -> bool {
-        self.wrapper.request_resize()
+    fn set_tail_seconds(&self, seconds: f32) {
+        self.wrapper
+            .set_tail_samples(tail_seconds_to_samples(seconds, self.transport.sample_rate))
     }
 
-    // All of these functions are supposed to be called from the main thread, so we'll put some
-    // trust in the caller and assume that this is indeed the case
-    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+    fn set_current_voice_capacity(&self, capacity: u32) {
+        self.wrapper.set_current_voice_capacity(capacity)
+    }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T)) {
+        self.wrapper.gui_messages.drain(handler)
+    }
+}
+
+impl<P: ClapPlugin> WrapperGuiContext<P> {
+    // These are called from the `GuiContext` functions below for every parameter in a link group
+    unsafe fn begin_set_parameter_unlinked(&self, param: ParamPtr) {
         match self.wrapper.param_ptr_to_hash.get(&param) {
-            Some(hash) => {
+            // If the parameter's previous gesture was ended during the current group edit, then the
+            // host still considers that gesture to be active
+            Some(hash) if self.group_edit.borrow().should_begin_gesture(param) => {
                 let success = self
                     .wrapper
                     .queue_parameter_event(OutputParamEvent::BeginGesture { param_hash: *hash });
@@ -153,6 +263,7 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
                      the host"
                 );
             }
+            Some(_) => (),
             None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
         }
 
@@ -168,7 +279,7 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
         }
     }
 
-    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+    unsafe fn set_parameter_normalized_unlinked(&self, param: ParamPtr, normalized: f32) {
         match self.wrapper.param_ptr_to_hash.get(&param) {
             Some(hash) => {
                 // We queue the parameter change event here, and it will be sent to the host either
@@ -176,7 +287,7 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
                 // (when the plugin isn't processing audio). The parameter's actual value will only
                 // be changed when the output event is written to prevent changing parameter values
                 // in the middle of processing audio.
-                let clap_plain_value = normalized as f64 * param.step_count().unwrap_or(1) as f64;
+                let clap_plain_value = normalized_to_clap_value(&param, normalized);
                 let success = self
                     .wrapper
                     .queue_parameter_event(OutputParamEvent::SetValue {
@@ -205,18 +316,12 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
         }
     }
 
-    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+    unsafe fn end_set_parameter_unlinked(&self, param: ParamPtr) {
         match self.wrapper.param_ptr_to_hash.get(&param) {
             Some(hash) => {
-                let success = self
-                    .wrapper
-                    .queue_parameter_event(OutputParamEvent::EndGesture { param_hash: *hash });
-
-                nih_debug_assert!(
-                    success,
-                    "Parameter output event queue was full, parameter change will not be sent to \
-                     the host"
-                );
+                if self.group_edit.borrow_mut().should_end_gesture(param) {
+                    self.queue_end_gesture(*hash);
+                }
             }
             None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
         }
@@ -233,6 +338,114 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
         }
     }
 
+    fn queue_end_gesture(&self, param_hash: u32) {
+        let success = self
+            .wrapper
+            .queue_parameter_event(OutputParamEvent::EndGesture { param_hash });
+
+        nih_debug_assert!(
+            success,
+            "Parameter output event queue was full, parameter change will not be sent to the host"
+        );
+    }
+}
+
+impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Clap
+    }
+
+    fn request_resize(&self) -> bool {
+        self.wrapper.request_resize()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.wrapper.set_ui_scale(scale)
+    }
+
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool {
+        self.wrapper.queue_gui_note_event(event)
+    }
+
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool {
+        self.wrapper.gui_messages.send(message)
+    }
+
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+        self.wrapper.param_changes.subscribe()
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn dsp_load(&self) -> f32 {
+        self.wrapper.dsp_load.load()
+    }
+
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle {
+        self.wrapper.schedule_once(
+            delay,
+            Task::TimerCallback(TimerCallback::new_once(callback)),
+        )
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let callback = TimerCallback::new(callback);
+        self.wrapper
+            .schedule_repeating(interval, move || Task::TimerCallback(callback.clone()))
+    }
+
+    // All of these functions are supposed to be called from the main thread, so we'll put some
+    // trust in the caller and assume that this is indeed the case
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        // Linked parameters also get their own gestures
+        let started = self.param_links.borrow_mut().begin_gesture(param);
+        for param in started {
+            self.begin_set_parameter_unlinked(param);
+        }
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        self.set_parameter_normalized_unlinked(param, normalized);
+
+        let partner_values = self.param_links.borrow().partner_values(param, normalized);
+        for (partner, partner_normalized) in partner_values {
+            self.set_parameter_normalized_unlinked(partner, partner_normalized);
+        }
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        let ended = self.param_links.borrow_mut().end_gesture(param);
+        for param in ended {
+            self.end_set_parameter_unlinked(param);
+        }
+    }
+
+    fn raw_begin_group_edit(&self) {
+        self.group_edit.borrow_mut().begin();
+    }
+
+    fn raw_param_info_changed(&self) {
+        self.wrapper.param_info_changed();
+    }
+
+    fn raw_end_group_edit(&self) {
+        // The gestures ended during the group edit are all ended at the same time, so the host
+        // sees a single set of overlapping gestures
+        let deferred_ends = self.group_edit.borrow_mut().end();
+        for param in deferred_ends.into_iter().flatten() {
+            match self.wrapper.param_ptr_to_hash.get(&param) {
+                Some(hash) => self.queue_end_gesture(*hash),
+                None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+            }
+        }
+    }
+
     fn get_state(&self) -> crate::wrapper::state::PluginState {
         self.wrapper.get_state_object()
     }
@@ -240,6 +453,61 @@ impl<P: ClapPlugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object_from_gui(state)
     }
+
+    fn store_state_slot(&self, slot: StateSlot) {
+        self.wrapper.state_slots.store(self, slot)
+    }
+
+    fn swap_ab(&self) -> StateSlot {
+        self.wrapper.state_slots.swap(self)
+    }
+
+    fn copy_a_to_b(&self) {
+        self.wrapper.state_slots.copy_a_to_b(self)
+    }
+
+    fn active_state_slot(&self) -> StateSlot {
+        self.wrapper.state_slots.active()
+    }
+
+    fn show_param_context_menu(&self, param: ParamPtr, position: (f32, f32)) -> bool {
+        match self.wrapper.param_ptr_to_hash.get(&param) {
+            Some(hash) => self.wrapper.show_param_context_menu(*hash, position),
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                false
+            }
+        }
+    }
+
+    fn request_keyboard_focus(&self) -> bool {
+        // CLAP editors receive keyboard input directly from the OS and there is no extension for
+        // negotiating keyboard focus with the host. The best we can do is to make sure the
+        // editor's window has the OS' keyboard focus.
+        match &*self.wrapper.editor_parent.lock() {
+            Some(editor_parent) => unsafe { window_focus::focus_editor_window(editor_parent) },
+            None => false,
+        }
+    }
+
+    fn release_keyboard_focus(&self) {}
+
+    fn set_clipboard_text(&self, text: &str) -> bool {
+        self.clipboard.set_text(text)
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.clipboard.text()
+    }
+
+    fn param_indication(&self, param: ParamPtr) -> Option<ParamIndication> {
+        let param_hash = self.wrapper.param_ptr_to_hash.get(&param)?;
+        self.wrapper
+            .param_indications
+            .lock()
+            .get(param_hash)
+            .cloned()
+    }
 }
 
 /// A remote control section. The plugin can fill this with information for one or more pages.
@@ -1,6 +1,6 @@
 use clap_sys::plugin::clap_plugin_descriptor;
 use clap_sys::version::CLAP_VERSION;
-use std::ffi::{CStr, CString};
+use std::ffi::{c_void, CStr, CString};
 use std::os::raw::c_char;
 
 use crate::prelude::ClapPlugin;
@@ -28,6 +28,9 @@ pub struct PluginDescriptor {
     /// descriptor upfront. We also need to initialize the `CString` fields above first before we
     /// can initialize this plugin descriptor.
     plugin_descriptor: Option<clap_plugin_descriptor>,
+    /// A pointer to the plugin's `ARAFactory`, or a null pointer if the plugin does not support ARA
+    /// or if the `ara` feature is disabled.
+    ara_factory: *const c_void,
 }
 
 unsafe impl Send for PluginDescriptor {}
@@ -59,6 +62,11 @@ impl PluginDescriptor {
             // descriptor
             clap_features_ptrs: Vec::new(),
             plugin_descriptor: None,
+
+            #[cfg(feature = "ara")]
+            ara_factory: crate::wrapper::ara::ara_factory_ptr::<P>(),
+            #[cfg(not(feature = "ara"))]
+            ara_factory: std::ptr::null(),
         };
 
         // The keyword list is an environ-like list of char pointers terminated by a null pointer.
@@ -107,4 +115,9 @@ impl PluginDescriptor {
     pub fn clap_id(&self) -> &CStr {
         self.clap_id.as_c_str()
     }
+
+    /// The plugin's `ARAFactory`, or a null pointer if the plugin does not support ARA.
+    pub fn ara_factory(&self) -> *const c_void {
+        self.ara_factory
+    }
 }
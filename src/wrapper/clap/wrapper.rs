@@ -1,30 +1,58 @@
+use arc_swap::ArcSwapOption;
 use atomic_float::AtomicF32;
 use atomic_refcell::{AtomicRefCell, AtomicRefMut};
+use clap_sys::color::clap_color;
 use clap_sys::events::{
-    clap_event_header, clap_event_midi, clap_event_midi_sysex, clap_event_note,
+    clap_event_header, clap_event_midi, clap_event_midi2, clap_event_midi_sysex, clap_event_note,
     clap_event_note_expression, clap_event_param_gesture, clap_event_param_mod,
     clap_event_param_value, clap_event_transport, clap_input_events, clap_output_events,
-    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_IS_LIVE, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI_SYSEX,
-    CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION, CLAP_EVENT_NOTE_OFF,
-    CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN, CLAP_EVENT_PARAM_GESTURE_END,
-    CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE, CLAP_EVENT_TRANSPORT,
-    CLAP_NOTE_EXPRESSION_BRIGHTNESS, CLAP_NOTE_EXPRESSION_EXPRESSION, CLAP_NOTE_EXPRESSION_PAN,
-    CLAP_NOTE_EXPRESSION_PRESSURE, CLAP_NOTE_EXPRESSION_TUNING, CLAP_NOTE_EXPRESSION_VIBRATO,
-    CLAP_NOTE_EXPRESSION_VOLUME, CLAP_TRANSPORT_HAS_BEATS_TIMELINE,
+    CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_IS_LIVE, CLAP_EVENT_MIDI, CLAP_EVENT_MIDI2,
+    CLAP_EVENT_MIDI_SYSEX, CLAP_EVENT_NOTE_CHOKE, CLAP_EVENT_NOTE_END, CLAP_EVENT_NOTE_EXPRESSION,
+    CLAP_EVENT_NOTE_OFF, CLAP_EVENT_NOTE_ON, CLAP_EVENT_PARAM_GESTURE_BEGIN,
+    CLAP_EVENT_PARAM_GESTURE_END, CLAP_EVENT_PARAM_MOD, CLAP_EVENT_PARAM_VALUE,
+    CLAP_EVENT_TRANSPORT, CLAP_NOTE_EXPRESSION_BRIGHTNESS, CLAP_NOTE_EXPRESSION_EXPRESSION,
+    CLAP_NOTE_EXPRESSION_PAN, CLAP_NOTE_EXPRESSION_PRESSURE, CLAP_NOTE_EXPRESSION_TUNING,
+    CLAP_NOTE_EXPRESSION_VIBRATO, CLAP_NOTE_EXPRESSION_VOLUME, CLAP_TRANSPORT_HAS_BEATS_TIMELINE,
     CLAP_TRANSPORT_HAS_SECONDS_TIMELINE, CLAP_TRANSPORT_HAS_TEMPO,
     CLAP_TRANSPORT_HAS_TIME_SIGNATURE, CLAP_TRANSPORT_IS_LOOP_ACTIVE, CLAP_TRANSPORT_IS_PLAYING,
     CLAP_TRANSPORT_IS_RECORDING, CLAP_TRANSPORT_IS_WITHIN_PRE_ROLL,
 };
 use clap_sys::ext::audio_ports::{
-    clap_audio_port_info, clap_plugin_audio_ports, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS,
-    CLAP_PORT_MONO, CLAP_PORT_STEREO,
+    clap_audio_port_info, clap_host_audio_ports, clap_plugin_audio_ports,
+    CLAP_AUDIO_PORTS_RESCAN_LIST, CLAP_AUDIO_PORT_IS_MAIN, CLAP_EXT_AUDIO_PORTS, CLAP_PORT_MONO,
+    CLAP_PORT_STEREO,
 };
 use clap_sys::ext::audio_ports_config::{
     clap_audio_ports_config, clap_plugin_audio_ports_config, CLAP_EXT_AUDIO_PORTS_CONFIG,
 };
+use clap_sys::ext::draft::context_menu::{
+    clap_context_menu_target, clap_host_context_menu, CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+    CLAP_EXT_CONTEXT_MENU,
+};
+use clap_sys::ext::draft::param_indication::{
+    clap_plugin_param_indication, CLAP_EXT_PARAM_INDICATION, CLAP_PARAM_INDICATION_AUTOMATION_NONE,
+    CLAP_PARAM_INDICATION_AUTOMATION_OVERRIDING, CLAP_PARAM_INDICATION_AUTOMATION_PLAYING,
+    CLAP_PARAM_INDICATION_AUTOMATION_PRESENT, CLAP_PARAM_INDICATION_AUTOMATION_RECORDING,
+};
 use clap_sys::ext::draft::remote_controls::{
     clap_plugin_remote_controls, clap_remote_controls_page, CLAP_EXT_REMOTE_CONTROLS,
 };
+use clap_sys::ext::draft::state_context::{
+    clap_plugin_state_context, clap_plugin_state_context_type, CLAP_EXT_STATE_CONTEXT,
+    CLAP_STATE_CONTEXT_FOR_DUPLICATE, CLAP_STATE_CONTEXT_FOR_PRESET,
+    CLAP_STATE_CONTEXT_FOR_PROJECT,
+};
+use clap_sys::ext::draft::track_info::{
+    clap_host_track_info, clap_plugin_track_info, clap_track_info, CLAP_EXT_TRACK_INFO,
+    CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL, CLAP_TRACK_INFO_HAS_TRACK_COLOR,
+    CLAP_TRACK_INFO_HAS_TRACK_NAME, CLAP_TRACK_INFO_IS_FOR_BUS, CLAP_TRACK_INFO_IS_FOR_MASTER,
+    CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK,
+};
+use clap_sys::ext::draft::tuning::{
+    clap_host_tuning, clap_plugin_tuning, clap_tuning_info, CLAP_EXT_TUNING,
+};
+#[cfg(all(target_family = "unix", not(target_os = "macos")))]
+use clap_sys::ext::gui::CLAP_WINDOW_API_WAYLAND;
 use clap_sys::ext::gui::{
     clap_gui_resize_hints, clap_host_gui, clap_plugin_gui, clap_window, CLAP_EXT_GUI,
     CLAP_WINDOW_API_COCOA, CLAP_WINDOW_API_WIN32, CLAP_WINDOW_API_X11,
@@ -32,21 +60,34 @@ use clap_sys::ext::gui::{
 use clap_sys::ext::latency::{clap_host_latency, clap_plugin_latency, CLAP_EXT_LATENCY};
 use clap_sys::ext::note_ports::{
     clap_note_port_info, clap_plugin_note_ports, CLAP_EXT_NOTE_PORTS, CLAP_NOTE_DIALECT_CLAP,
-    CLAP_NOTE_DIALECT_MIDI,
+    CLAP_NOTE_DIALECT_MIDI, CLAP_NOTE_DIALECT_MIDI2, CLAP_NOTE_DIALECT_MIDI_MPE,
 };
 use clap_sys::ext::params::{
     clap_host_params, clap_param_info, clap_plugin_params, CLAP_EXT_PARAMS,
     CLAP_PARAM_IS_AUTOMATABLE, CLAP_PARAM_IS_BYPASS, CLAP_PARAM_IS_HIDDEN,
     CLAP_PARAM_IS_MODULATABLE, CLAP_PARAM_IS_MODULATABLE_PER_NOTE_ID, CLAP_PARAM_IS_READONLY,
-    CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_VALUES,
+    CLAP_PARAM_IS_STEPPED, CLAP_PARAM_RESCAN_INFO, CLAP_PARAM_RESCAN_TEXT,
+    CLAP_PARAM_RESCAN_VALUES,
+};
+#[cfg(target_os = "linux")]
+use clap_sys::ext::posix_fd_support::{
+    clap_host_posix_fd_support, clap_plugin_posix_fd_support, clap_posix_fd_flags,
+    CLAP_EXT_POSIX_FD_SUPPORT, CLAP_POSIX_FD_READ,
 };
 use clap_sys::ext::render::{
     clap_plugin_render, clap_plugin_render_mode, CLAP_EXT_RENDER, CLAP_RENDER_OFFLINE,
     CLAP_RENDER_REALTIME,
 };
 use clap_sys::ext::state::{clap_plugin_state, CLAP_EXT_STATE};
-use clap_sys::ext::tail::{clap_plugin_tail, CLAP_EXT_TAIL};
+use clap_sys::ext::tail::{clap_host_tail, clap_plugin_tail, CLAP_EXT_TAIL};
 use clap_sys::ext::thread_check::{clap_host_thread_check, CLAP_EXT_THREAD_CHECK};
+use clap_sys::ext::thread_pool::{
+    clap_host_thread_pool, clap_plugin_thread_pool, CLAP_EXT_THREAD_POOL,
+};
+#[cfg(target_os = "linux")]
+use clap_sys::ext::timer_support::{
+    clap_host_timer_support, clap_plugin_timer_support, CLAP_EXT_TIMER_SUPPORT,
+};
 use clap_sys::ext::voice_info::{
     clap_host_voice_info, clap_plugin_voice_info, clap_voice_info, CLAP_EXT_VOICE_INFO,
     CLAP_VOICE_INFO_SUPPORTS_OVERLAPPING_NOTES,
@@ -57,7 +98,7 @@ use clap_sys::id::{clap_id, CLAP_INVALID_ID};
 use clap_sys::plugin::clap_plugin;
 use clap_sys::process::{
     clap_process, clap_process_status, CLAP_PROCESS_CONTINUE, CLAP_PROCESS_CONTINUE_IF_NOT_QUIET,
-    CLAP_PROCESS_ERROR,
+    CLAP_PROCESS_ERROR, CLAP_PROCESS_TAIL,
 };
 use clap_sys::stream::{clap_istream, clap_ostream};
 use crossbeam::atomic::AtomicCell;
@@ -72,34 +113,69 @@ use std::mem;
 use std::num::NonZeroU32;
 use std::os::raw::c_char;
 use std::ptr::NonNull;
+#[cfg(target_os = "linux")]
+use std::sync::atomic::AtomicI32;
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::{Arc, Weak};
 use std::thread::{self, ThreadId};
 use std::time::Duration;
 
+#[cfg(feature = "ara")]
+use super::ara::{clap_ara_plugin_extension, CLAP_EXT_ARA_PLUGINEXTENSION};
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
 use super::descriptor::PluginDescriptor;
 use super::util::ClapPtr;
-use crate::event_loop::{BackgroundThread, EventLoop, MainThreadExecutor, TASK_QUEUE_CAPACITY};
+use crate::context::gui::{GuiMessageQueue, ParamChange, ParamChangeBroadcaster, StateSlots};
+use crate::event_loop::{
+    BackgroundThread, EventLoop, MainThreadExecutor, TimerCallback, TimerHandle, Timers,
+    TASK_QUEUE_CAPACITY,
+};
 use crate::midi::MidiResult;
 use crate::prelude::{
     AsyncExecutor, AudioIOLayout, AuxiliaryBuffers, BufferConfig, ClapPlugin, Editor, MidiConfig,
-    NoteEvent, ParamFlags, ParamPtr, Params, ParentWindowHandle, Plugin, PluginNoteEvent,
-    ProcessMode, ProcessStatus, SysExMessage, TaskExecutor, Transport,
+    NoteDialects, NoteEvent, NotePort, ParamAutomationState, ParamFlags, ParamIndication,
+    ParamMappingIndication, ParamPtr, Params, ParentWindowHandle, Plugin, PluginNoteEvent,
+    ProcessMode, ProcessStatus, StateSaveContext, SysExMessage, TaskExecutor, TaskOptions,
+    TaskPriority, TrackInfo, Transport,
 };
-use crate::util::permit_alloc;
+use crate::util::modulation::ModulationMatrix;
+use crate::util::{self, permit_alloc};
 use crate::wrapper::clap::context::RemoteControlPages;
 use crate::wrapper::clap::util::{read_stream, write_stream};
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::dsp_load::DspLoadMeter;
+use crate::wrapper::util::event_scheduler::EventScheduler;
+use crate::wrapper::util::output_params::OutputParams;
+use crate::wrapper::util::param_links::ParamLinks;
+use crate::wrapper::util::state_cache::StateCache;
+use crate::wrapper::util::window_focus::EditorParent;
 use crate::wrapper::util::{
-    clamp_input_event_timing, clamp_output_event_timing, hash_param_id, process_wrapper, strlcpy,
+    bypass_param_enabled, clamp_input_event_timing, clamp_output_event_timing,
+    equal_temperament_frequency, find_bypass_param, hash_param_id, process_wrapper,
+    queue_gui_note_event, reported_tail_samples, strlcpy, GUI_NOTE_EVENT_QUEUE_CAPACITY,
 };
 
 /// How many output parameter changes we can store in our output parameter change queue. Storing
 /// more than this many parameters at a time will cause changes to get lost.
 const OUTPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
 
+/// How many parameter changes the plugin can output through `ProcessContext::set_parameter()`
+/// during a single processing cycle. The queue is preallocated with this capacity so it never
+/// allocates on the audio thread. Once it is full, further changes to a parameter overwrite that
+/// parameter's last queued value, and changes to parameters that haven't been queued yet are
+/// dropped.
+pub(super) const OUTPUT_PARAM_VALUE_QUEUE_CAPACITY: usize = 2048;
+
+/// The ID of the remote controls extension after it was stabilized in CLAP 1.2. The interface is
+/// identical to the draft version's, and hosts that support both will only query this ID.
+const CLAP_EXT_REMOTE_CONTROLS_STABLE: &[u8] = b"clap.remote-controls/2";
+
+/// `CLAP_PARAM_IS_ENUM` was added in CLAP 1.2, and it is not yet part of the `clap-sys` version
+/// used here. Hosts that don't know about this flag simply ignore it.
+const CLAP_PARAM_IS_ENUM: u32 = 1 << 16;
+
 pub struct Wrapper<P: ClapPlugin> {
     /// A reference to this object, upgraded to an `Arc<Self>` for the GUI context.
     this: AtomicRefCell<Weak<Self>>,
@@ -119,38 +195,109 @@ pub struct Wrapper<P: ClapPlugin> {
     /// A handle for the currently active editor instance. The plugin should implement `Drop` on
     /// this handle for its closing behavior.
     editor_handle: Mutex<Option<Box<dyn Any + Send>>>,
+    /// Whether the host created the editor as a floating window. In that case the editor is opened
+    /// in [`ext_gui_show()`][Self::ext_gui_show()] instead of being embedded in
+    /// [`ext_gui_set_parent()`][Self::ext_gui_set_parent()].
+    editor_floating: AtomicBool,
+    /// The host window a floating editor window should stay on top of, set through
+    /// `clap_plugin_gui::set_transient()`.
+    editor_transient_parent: Mutex<Option<TransientParent>>,
+    /// The host window an embedded editor was attached to in
+    /// [`ext_gui_set_parent()`][Self::ext_gui_set_parent()]. Used to give the editor's window
+    /// keyboard focus when a text entry field requests it.
+    pub editor_parent: Mutex<Option<EditorParent>>,
+    /// The title for floating editor windows, set through `clap_plugin_gui::suggest_title()`.
+    /// Defaults to the plugin's name.
+    editor_title: Mutex<String>,
     /// The DPI scaling factor as passed to the [IPlugViewContentScaleSupport::set_scale_factor()]
     /// function. Defaults to 1.0, and will be kept there on macOS. When reporting and handling size
     /// the sizes communicated to and from the DAW should be scaled by this factor since NIH-plug's
-    /// APIs only deal in logical pixels.
+    /// APIs only deal in logical pixels. If the user has chosen a UI scale through
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()], then this
+    /// contains that scale instead.
     editor_scaling_factor: AtomicF32,
+    /// The DPI scaling factor last set by the host, regardless of the user's UI scale. This is
+    /// used again when the user's UI scale is reset.
+    editor_host_scaling_factor: AtomicF32,
 
     is_processing: AtomicBool,
+    /// Whether the plugin is currently activated, i.e. between `clap_plugin::activate()` and
+    /// `clap_plugin::deactivate()`. CLAP only allows the latency to change while the plugin is
+    /// being activated.
+    is_activated: AtomicBool,
+    /// Set when the latency changed while the plugin was activated. The host will then be asked to
+    /// restart the plugin, and the host is informed about the new latency while the plugin is
+    /// being reactivated.
+    latency_changed_while_activated: AtomicBool,
     /// The current IO configuration, modified through the `clap_plugin_audio_ports_config`
     /// extension. Initialized to the plugin's first audio IO configuration.
     current_audio_io_layout: AtomicCell<AudioIOLayout>,
+    /// An audio IO layout the host selected while the plugin was still activated. CLAP requires
+    /// the plugin to be deactivated when changing the port configuration, so in that case the host
+    /// is asked to restart the plugin and the layout is applied when the plugin gets deactivated.
+    pending_audio_io_layout: AtomicCell<Option<AudioIOLayout>>,
     /// The current buffer configuration, containing the sample rate and the maximum block size.
     /// Will be set in `clap_plugin::activate()`.
     current_buffer_config: AtomicCell<Option<BufferConfig>>,
     /// The current audio processing mode. Set through the render extension. Defaults to realtime.
     pub current_process_mode: AtomicCell<ProcessMode>,
     /// The incoming events for the plugin, if `P::MIDI_INPUT` is set to `MidiConfig::Basic` or
-    /// higher.
+    /// higher. Each event is stored along with the index of the note port it was received on.
     ///
     /// TODO: Maybe load these lazily at some point instead of needing to spool them all to this
     ///       queue first
-    input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    input_events: AtomicRefCell<VecDeque<(u16, PluginNoteEvent<P>)>>,
     /// Stores any events the plugin has output during the current processing cycle, analogous to
     /// `input_events`.
-    output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    output_events: AtomicRefCell<VecDeque<(u16, PluginNoteEvent<P>)>>,
+    /// Parameter changes the plugin has made through
+    /// [`ProcessContext::set_parameter()`][crate::prelude::ProcessContext::set_parameter()] during
+    /// the current processing cycle. These are interleaved with `output_events` when they're sent
+    /// to the host.
+    output_param_values: AtomicRefCell<VecDeque<OutputParamValue>>,
+    /// Note events the plugin has scheduled for a later block using
+    /// [`ProcessContext::send_event_at()`][crate::prelude::ProcessContext::send_event_at()].
+    pub event_scheduler: AtomicRefCell<EventScheduler<P::SysExMessage>>,
+    /// Note events sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()]. These are
+    /// added to the start of `input_events` at the start of the next processing cycle.
+    gui_note_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// Messages sent from the editor through `send_to_audio()` on the
+    /// [`GuiContext`][crate::prelude::GuiContext]. The plugin receives these through
+    /// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    pub gui_messages: GuiMessageQueue,
+    /// Broadcasts parameter changes to the receivers created through
+    /// [`GuiContext::subscribe_param_changes()`][crate::prelude::GuiContext::subscribe_param_changes()].
+    pub param_changes: ParamChangeBroadcaster,
+    /// The A/B comparison slots used by the [`GuiContext`][crate::prelude::GuiContext]'s
+    /// `swap_ab()` and related functions.
+    pub state_slots: StateSlots,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    pub modulation_matrix: Option<Arc<ModulationMatrix>>,
+    /// Measures the time spent in the plugin's process function. Exposed to the editor through
+    /// [`GuiContext::dsp_load()`][crate::prelude::GuiContext::dsp_load()].
+    pub dsp_load: DspLoadMeter,
     /// The last process status returned by the plugin. This is used for tail handling.
     last_process_status: AtomicCell<ProcessStatus>,
     /// The current latency in samples, as set by the plugin through the [`ProcessContext`]. Uses
     /// the latency extension.
     pub current_latency: AtomicU32,
+    /// The current tail length in samples, as set by the plugin through the [`ProcessContext`].
+    /// Uses the tail extension.
+    current_tail: AtomicU32,
     /// A data structure that helps manage and create buffers for all of the plugin's inputs and
     /// outputs based on channel pointers provided by the host.
     buffer_manager: AtomicRefCell<BufferManager>,
+    /// The plugin's bypass parameter, if it has one. Used for [`ProcessContext::bypassed()`].
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the latency compensated dry signal when the plugin is
+    /// bypassed. This is only used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// Sends the values of read-only parameters changed by the plugin back to the host.
+    output_params: AtomicRefCell<OutputParams>,
+    /// The plugin's large persistent fields, serialized ahead of time on the background thread.
+    state_cache: StateCache,
     /// The plugin is able to restore state through a method on the `GuiContext`. To avoid changing
     /// parameters mid-processing and running into garbled data if the host also tries to load state
     /// at the same time the restoring happens at the end of each processing call. If this zero
@@ -176,10 +323,13 @@ pub struct Wrapper<P: ClapPlugin> {
     _plugin_descriptor: Box<PluginDescriptor>,
 
     clap_plugin_audio_ports: clap_plugin_audio_ports,
+    host_audio_ports: AtomicRefCell<Option<ClapPtr<clap_host_audio_ports>>>,
 
     clap_plugin_gui: clap_plugin_gui,
     host_gui: AtomicRefCell<Option<ClapPtr<clap_host_gui>>>,
 
+    host_context_menu: AtomicRefCell<Option<ClapPtr<clap_host_context_menu>>>,
+
     clap_plugin_latency: clap_plugin_latency,
     host_latency: AtomicRefCell<Option<ClapPtr<clap_host_latency>>>,
 
@@ -223,6 +373,12 @@ pub struct Wrapper<P: ClapPlugin> {
     ///      in the same order, right?
     output_parameter_events: ArrayQueue<OutputParamEvent>,
 
+    clap_plugin_param_indication: clap_plugin_param_indication,
+    /// The mapping and automation indications set by the host through the `param-indication`
+    /// extension, indexed by parameter hash. Exposed to the editor through
+    /// [`GuiContext::param_indication()`][crate::prelude::GuiContext::param_indication()].
+    pub param_indications: Mutex<HashMap<u32, ParamIndication>>,
+
     host_thread_check: AtomicRefCell<Option<ClapPtr<clap_host_thread_check>>>,
 
     clap_plugin_remote_controls: clap_plugin_remote_controls,
@@ -232,8 +388,30 @@ pub struct Wrapper<P: ClapPlugin> {
     clap_plugin_render: clap_plugin_render,
 
     clap_plugin_state: clap_plugin_state,
+    clap_plugin_state_context: clap_plugin_state_context,
 
     clap_plugin_tail: clap_plugin_tail,
+    host_tail: AtomicRefCell<Option<ClapPtr<clap_host_tail>>>,
+
+    clap_plugin_thread_pool: clap_plugin_thread_pool,
+    host_thread_pool: AtomicRefCell<Option<ClapPtr<clap_host_thread_pool>>>,
+    /// The task passed to [`ProcessContext::run_tasks_parallel()`] while the host's thread pool is
+    /// executing it. This is only set for the duration of the `request_exec()` call.
+    thread_pool_task: AtomicRefCell<Option<ThreadPoolTask>>,
+
+    clap_plugin_track_info: clap_plugin_track_info,
+    host_track_info: AtomicRefCell<Option<ClapPtr<clap_host_track_info>>>,
+    /// The last track information provided by the host. Used for
+    /// [`ProcessContext::track_info()`].
+    pub track_info: ArcSwapOption<TrackInfo>,
+
+    clap_plugin_tuning: clap_plugin_tuning,
+    host_tuning: AtomicRefCell<Option<ClapPtr<clap_host_tuning>>>,
+    /// The ID of the host's first tuning, or `CLAP_INVALID_ID` if the host does not provide any
+    /// tunings. The host's tuning information can only be queried from the main thread, so this is
+    /// updated when the plugin is initialized and when the host informs the plugin that its tunings
+    /// have changed.
+    default_tuning_id: AtomicU32,
 
     clap_plugin_voice_info: clap_plugin_voice_info,
     host_voice_info: AtomicRefCell<Option<ClapPtr<clap_host_voice_info>>>,
@@ -242,6 +420,13 @@ pub struct Wrapper<P: ClapPlugin> {
     /// context. This defaults to the maximum number of voices.
     current_voice_capacity: AtomicU32,
 
+    #[cfg(feature = "ara")]
+    clap_ara_plugin_extension: clap_ara_plugin_extension,
+    /// The ARA plugin extension instance, set when the host binds this plugin instance to an ARA
+    /// document controller.
+    #[cfg(feature = "ara")]
+    ara_plugin_extension: Mutex<Option<crate::wrapper::ara::PlugInExtension>>,
+
     /// A queue of tasks that still need to be performed. Because CLAP lets the plugin request a
     /// host callback directly, we don't need to use the OsEventLoop we use in our other plugin
     /// implementations. Instead, we'll post tasks to this queue, ask the host to call
@@ -256,8 +441,37 @@ pub struct Wrapper<P: ClapPlugin> {
     /// A background thread for running tasks independently from the host'main GUI thread. Useful
     /// for longer, blocking tasks. Initialized later as it needs a reference to the wrapper.
     background_thread: AtomicRefCell<Option<BackgroundThread<Task<P>, Self>>>,
+    /// Fires the timers created through `schedule_once()` and `schedule_repeating()`. Fired tasks
+    /// are posted using [`schedule_gui()`][Self::schedule_gui()]. On Linux these timers are fired
+    /// from a host timer instead of from the timer thread if the host supports the timer-support
+    /// extension.
+    timers: AtomicRefCell<Timers>,
+
+    #[cfg(target_os = "linux")]
+    clap_plugin_timer_support: clap_plugin_timer_support,
+    #[cfg(target_os = "linux")]
+    host_timer_support: AtomicRefCell<Option<ClapPtr<clap_host_timer_support>>>,
+    /// The ID of the host timer used to fire [`timers`][Self::timers], or `CLAP_INVALID_ID` if the
+    /// timers are fired from the timer thread.
+    #[cfg(target_os = "linux")]
+    host_timer_id: AtomicU32,
+    #[cfg(target_os = "linux")]
+    clap_plugin_posix_fd_support: clap_plugin_posix_fd_support,
+    #[cfg(target_os = "linux")]
+    host_posix_fd_support: AtomicRefCell<Option<ClapPtr<clap_host_posix_fd_support>>>,
+    /// An eventfd registered with the host's posix-fd-support extension, or -1 if the host does not
+    /// support that extension. If this is set, then [`schedule_gui()`][Self::schedule_gui()] writes
+    /// to this file descriptor to have the host's event loop run the queued tasks instead of
+    /// calling `clap_host::request_callback()`.
+    #[cfg(target_os = "linux")]
+    task_fd: AtomicI32,
 }
 
+/// The period of the host timer used to fire the wrapper's timers when the host supports the
+/// timer-support extension. The timers can't fire more often than this.
+#[cfg(target_os = "linux")]
+const HOST_TIMER_PERIOD_MS: u32 = 10;
+
 /// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
 /// realtime-safe way. Instead of using a random thread or the OS' event loop like in the Linux
 /// implementation, this uses [`clap_host::request_callback()`] instead.
@@ -275,10 +489,22 @@ pub enum Task<P: Plugin> {
     ParameterModulationChanged(u32, f32),
     /// Inform the host that the latency has changed.
     LatencyChanged,
+    /// Inform the host that the tail length has changed.
+    TailChanged,
     /// Inform the host that the voice info has changed.
     VoiceInfoChanged,
     /// Tell the host that it should rescan the current parameter values.
     RescanParamValues,
+    /// Tell the host that it should rescan the parameters' names, value strings, and values.
+    RescanParamInfo,
+    /// Serialize the plugin's changed blobs ahead of time. Runs on the background thread.
+    RefreshStateCache,
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+    /// Run a timer callback registered through the [`GuiContext`][crate::prelude::GuiContext].
+    TimerCallback(TimerCallback),
 }
 
 /// The types of CLAP parameter updates for events.
@@ -292,6 +518,38 @@ pub enum ClapParamUpdate {
     PlainValueMod(f64),
 }
 
+/// Convert a parameter's normalized value to the plain value used by CLAP. Continuous parameters
+/// use normalized values, and discrete parameters use their step index.
+///
+/// # Safety
+///
+/// `param_ptr` needs to point to a parameter that is still alive.
+pub unsafe fn normalized_to_clap_value(param_ptr: &ParamPtr, normalized: f32) -> f64 {
+    param_ptr.to_host_normalized(normalized) as f64
+        * param_ptr.host_step_count().unwrap_or(1) as f64
+}
+
+/// The inverse of [`normalized_to_clap_value()`].
+///
+/// # Safety
+///
+/// `param_ptr` needs to point to a parameter that is still alive.
+pub unsafe fn clap_value_to_normalized(param_ptr: &ParamPtr, clap_value: f64) -> f32 {
+    param_ptr
+        .from_host_normalized((clap_value / param_ptr.host_step_count().unwrap_or(1) as f64) as f32)
+}
+
+/// Convert a modulation offset in CLAP's plain values to a normalized offset. Offsets for discrete
+/// parameters whose steps are not evenly spaced can't be converted exactly, so those are treated
+/// as offsets for the host's evenly spaced normalized values.
+///
+/// # Safety
+///
+/// `param_ptr` needs to point to a parameter that is still alive.
+pub unsafe fn clap_offset_to_normalized(param_ptr: &ParamPtr, clap_offset: f64) -> f32 {
+    (clap_offset / param_ptr.host_step_count().unwrap_or(1) as f64) as f32
+}
+
 /// A parameter event that should be output by the plugin, stored in a queue on the wrapper and
 /// written to the host either at the end of the process function or during a flush.
 #[derive(Debug, Clone)]
@@ -312,6 +570,20 @@ pub enum OutputParamEvent {
     EndGesture { param_hash: u32 },
 }
 
+/// A parameter change made by the plugin from the audio thread using
+/// [`ProcessContext::set_parameter()`][crate::prelude::ProcessContext::set_parameter()]. Unlike
+/// [`OutputParamEvent`]s these are sample accurate, and they're sent without a gesture since they
+/// aren't caused by the user.
+#[derive(Debug, Clone)]
+pub struct OutputParamValue {
+    /// The change's sample index within the block the plugin was processing.
+    pub timing: u32,
+    /// The internal hash for the parameter.
+    pub param_hash: u32,
+    /// The 'plain' value as reported to CLAP. See [`OutputParamEvent::SetValue`].
+    pub clap_plain_value: f64,
+}
+
 /// Because CLAP has this [`clap_host::request_host_callback()`] function, we don't need to use
 /// `OsEventLoop` and can instead just request a main thread callback directly.
 impl<P: ClapPlugin> EventLoop<Task<P>, Wrapper<P>> for Wrapper<P> {
@@ -325,6 +597,10 @@ impl<P: ClapPlugin> EventLoop<Task<P>, Wrapper<P>> for Wrapper<P> {
             true
         } else {
             let success = self.tasks.push(task).is_ok();
+            #[cfg(target_os = "linux")]
+            if success && self.notify_task_fd() {
+                return true;
+            }
             if success {
                 // CLAP lets us use the host's event loop instead of having to implement our own
                 let host = &self.host_callback;
@@ -335,12 +611,44 @@ impl<P: ClapPlugin> EventLoop<Task<P>, Wrapper<P>> for Wrapper<P> {
         }
     }
 
-    fn schedule_background(&self, task: Task<P>) -> bool {
+    fn schedule_background(&self, task: Task<P>, options: TaskOptions) -> bool {
+        self.background_thread
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .schedule(task, options)
+    }
+
+    fn cancel_background(&self, tag: u32) {
         self.background_thread
             .borrow()
             .as_ref()
             .unwrap()
-            .schedule(task)
+            .cancel(tag)
+    }
+
+    fn schedule_once(&self, delay: Duration, task: Task<P>) -> TimerHandle {
+        let this = self.this.borrow().clone();
+        self.timers
+            .borrow()
+            .schedule_once(delay, task, move |task| {
+                this.upgrade()
+                    .map_or(false, |wrapper| wrapper.schedule_gui(task))
+            })
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        make_task: impl FnMut() -> Task<P> + Send + 'static,
+    ) -> TimerHandle {
+        let this = self.this.borrow().clone();
+        self.timers
+            .borrow()
+            .schedule_repeating(interval, make_task, move |task| {
+                this.upgrade()
+                    .map_or(false, |wrapper| wrapper.schedule_gui(task))
+            })
     }
 
     fn is_main_thread(&self) -> bool {
@@ -363,6 +671,7 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
         match task {
             Task::PluginTask(task) => (self.task_executor.lock())(task),
             Task::ParameterValuesChanged => {
+                self.param_changes.broadcast(|| Some(ParamChange::All));
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         editor.lock().param_values_changed();
@@ -370,6 +679,12 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
             }
             Task::ParameterValueChanged(param_hash, normalized_value) => {
+                self.param_changes.broadcast(|| {
+                    Some(ParamChange::Value {
+                        id: self.param_id_by_hash.get(&param_hash)?.clone(),
+                        normalized_value,
+                    })
+                });
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
@@ -380,6 +695,12 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
             }
             Task::ParameterModulationChanged(param_hash, modulation_offset) => {
+                self.param_changes.broadcast(|| {
+                    Some(ParamChange::Modulation {
+                        id: self.param_id_by_hash.get(&param_hash)?.clone(),
+                        modulation_offset,
+                    })
+                });
                 if self.editor_handle.lock().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
@@ -393,11 +714,12 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 Some(host_latency) => {
                     nih_debug_assert!(is_gui_thread);
 
-                    // XXX: The CLAP docs mention that you should request a restart if this happens
-                    //      while the plugin is activated (which is not entirely the same thing as
-                    //      is processing, but we'll treat it as the same thing). In practice just
-                    //      calling the latency changed function also seems to work just fine.
-                    if self.is_processing.load(Ordering::SeqCst) {
+                    // The latency may only change while the plugin is being activated. If the plugin
+                    // is already active, then the host needs to restart it first. The host is then
+                    // informed about the new latency in `activate()`.
+                    if self.is_activated.load(Ordering::SeqCst) {
+                        self.latency_changed_while_activated
+                            .store(true, Ordering::SeqCst);
                         unsafe_clap_call! { &*self.host_callback=>request_restart(&*self.host_callback) };
                     } else {
                         unsafe_clap_call! { host_latency=>changed(&*self.host_callback) };
@@ -405,6 +727,13 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
                 None => nih_debug_assert_failure!("Host does not support the latency extension"),
             },
+            Task::TailChanged => match &*self.host_tail.borrow() {
+                Some(host_tail) => {
+                    nih_debug_assert!(is_gui_thread);
+                    unsafe_clap_call! { host_tail=>changed(&*self.host_callback) };
+                }
+                None => nih_debug_assert_failure!("Host does not support the tail extension"),
+            },
             Task::VoiceInfoChanged => match &*self.host_voice_info.borrow() {
                 Some(host_voice_info) => {
                     nih_debug_assert!(is_gui_thread);
@@ -419,8 +748,29 @@ impl<P: ClapPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
                 }
                 None => nih_debug_assert_failure!("The host does not support parameters? What?"),
             },
+            Task::RescanParamInfo => match &*self.host_params.borrow() {
+                Some(host_params) => {
+                    nih_debug_assert!(is_gui_thread);
+                    // The ranges the host sees never change, so this does not require the plugin
+                    // to be deactivated like `CLAP_PARAM_RESCAN_ALL` would
+                    unsafe_clap_call! {
+                        host_params=>rescan(
+                            &*self.host_callback,
+                            CLAP_PARAM_RESCAN_INFO | CLAP_PARAM_RESCAN_TEXT | CLAP_PARAM_RESCAN_VALUES,
+                        )
+                    };
+                }
+                None => nih_debug_assert_failure!("The host does not support parameters? What?"),
+            },
+            Task::RefreshStateCache => self.state_cache.refresh(&*self.params),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
+            Task::TimerCallback(callback) => callback.call(),
         };
     }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
 }
 
 impl<P: ClapPlugin> Wrapper<P> {
@@ -449,6 +799,8 @@ impl<P: ClapPlugin> Wrapper<P> {
         // we'll calculate from the string ID specified by the plugin. These parameters should also
         // remain in the same order as the one returned by the plugin.
         let params = plugin.params();
+        let state_cache = StateCache::new(&*params, P::COMPRESS_STATE);
+        let modulation_matrix = plugin.modulation_matrix();
         let param_id_hashes_ptrs_groups: Vec<_> = params
             .param_map()
             .into_iter()
@@ -481,6 +833,11 @@ impl<P: ClapPlugin> Wrapper<P> {
             .iter()
             .map(|(_, hash, ptr, _)| (*ptr, *hash))
             .collect();
+        let bypass_param = find_bypass_param::<P>(
+            param_id_hashes_ptrs_groups
+                .iter()
+                .map(|(_, _, ptr, _)| *ptr),
+        );
         let poly_mod_ids_by_hash: HashMap<u32, u32> = param_id_hashes_ptrs_groups
             .iter()
             .filter_map(|(_, hash, ptr, _)| unsafe {
@@ -541,24 +898,47 @@ impl<P: ClapPlugin> Wrapper<P> {
             // Initialized later as it needs a reference to the wrapper for the async executor
             editor: AtomicRefCell::new(None),
             editor_handle: Mutex::new(None),
+            editor_floating: AtomicBool::new(false),
+            editor_transient_parent: Mutex::new(None),
+            editor_parent: Mutex::new(None),
+            editor_title: Mutex::new(String::from(P::NAME)),
             editor_scaling_factor: AtomicF32::new(1.0),
+            editor_host_scaling_factor: AtomicF32::new(1.0),
 
             is_processing: AtomicBool::new(false),
+            is_activated: AtomicBool::new(false),
+            latency_changed_while_activated: AtomicBool::new(false),
             current_audio_io_layout: AtomicCell::new(
                 P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
             ),
+            pending_audio_io_layout: AtomicCell::new(None),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
             input_events: AtomicRefCell::new(VecDeque::with_capacity(512)),
             output_events: AtomicRefCell::new(VecDeque::with_capacity(512)),
+            output_param_values: AtomicRefCell::new(VecDeque::with_capacity(
+                OUTPUT_PARAM_VALUE_QUEUE_CAPACITY,
+            )),
+            event_scheduler: AtomicRefCell::new(EventScheduler::default()),
+            gui_note_events: ArrayQueue::new(GUI_NOTE_EVENT_QUEUE_CAPACITY),
+            gui_messages: GuiMessageQueue::default(),
+            param_changes: ParamChangeBroadcaster::default(),
+            state_slots: StateSlots::default(),
+            modulation_matrix,
+            dsp_load: DspLoadMeter::default(),
             last_process_status: AtomicCell::new(ProcessStatus::Normal),
             current_latency: AtomicU32::new(0),
+            current_tail: AtomicU32::new(0),
             // This is initialized just before calling `Plugin::initialize()` so that during the
             // process call buffers can be initialized without any allocations
             buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
                 0,
                 AudioIOLayout::default(),
             )),
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            output_params: AtomicRefCell::new(OutputParams::new(&param_by_hash)),
+            state_cache,
             updated_state_sender,
             updated_state_receiver,
 
@@ -595,6 +975,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 count: Some(Self::ext_audio_ports_count),
                 get: Some(Self::ext_audio_ports_get),
             },
+            host_audio_ports: AtomicRefCell::new(None),
 
             clap_plugin_gui: clap_plugin_gui {
                 is_api_supported: Some(Self::ext_gui_is_api_supported),
@@ -615,6 +996,8 @@ impl<P: ClapPlugin> Wrapper<P> {
             },
             host_gui: AtomicRefCell::new(None),
 
+            host_context_menu: AtomicRefCell::new(None),
+
             clap_plugin_latency: clap_plugin_latency {
                 get: Some(Self::ext_latency_get),
             },
@@ -643,6 +1026,12 @@ impl<P: ClapPlugin> Wrapper<P> {
             poly_mod_ids_by_hash,
             output_parameter_events: ArrayQueue::new(OUTPUT_EVENT_QUEUE_CAPACITY),
 
+            clap_plugin_param_indication: clap_plugin_param_indication {
+                set_mapping: Some(Self::ext_param_indication_set_mapping),
+                set_automation: Some(Self::ext_param_indication_set_automation),
+            },
+            param_indications: Mutex::new(HashMap::new()),
+
             host_thread_check: AtomicRefCell::new(None),
 
             clap_plugin_remote_controls: clap_plugin_remote_controls {
@@ -660,10 +1049,33 @@ impl<P: ClapPlugin> Wrapper<P> {
                 save: Some(Self::ext_state_save),
                 load: Some(Self::ext_state_load),
             },
+            clap_plugin_state_context: clap_plugin_state_context {
+                save: Some(Self::ext_state_context_save),
+                load: Some(Self::ext_state_context_load),
+            },
 
             clap_plugin_tail: clap_plugin_tail {
                 get: Some(Self::ext_tail_get),
             },
+            host_tail: AtomicRefCell::new(None),
+
+            clap_plugin_thread_pool: clap_plugin_thread_pool {
+                exec: Some(Self::ext_thread_pool_exec),
+            },
+            host_thread_pool: AtomicRefCell::new(None),
+            thread_pool_task: AtomicRefCell::new(None),
+
+            clap_plugin_track_info: clap_plugin_track_info {
+                changed: Some(Self::ext_track_info_changed),
+            },
+            host_track_info: AtomicRefCell::new(None),
+            track_info: ArcSwapOption::empty(),
+
+            clap_plugin_tuning: clap_plugin_tuning {
+                changed: Some(Self::ext_tuning_changed),
+            },
+            host_tuning: AtomicRefCell::new(None),
+            default_tuning_id: AtomicU32::new(CLAP_INVALID_ID),
 
             clap_plugin_voice_info: clap_plugin_voice_info {
                 get: Some(Self::ext_voice_info_get),
@@ -681,10 +1093,36 @@ impl<P: ClapPlugin> Wrapper<P> {
                     .unwrap_or(1),
             ),
 
+            #[cfg(feature = "ara")]
+            clap_ara_plugin_extension: clap_ara_plugin_extension {
+                get_factory: Some(Self::ext_ara_get_factory),
+                bind_to_document_controller: Some(Self::ext_ara_bind_to_document_controller),
+            },
+            #[cfg(feature = "ara")]
+            ara_plugin_extension: Mutex::new(None),
+
             tasks: ArrayQueue::new(TASK_QUEUE_CAPACITY),
             main_thread_id: thread::current().id(),
             // Initialized later as it needs a reference to the wrapper for the executor
             background_thread: AtomicRefCell::new(None),
+            timers: AtomicRefCell::new(Timers::get_or_create()),
+
+            #[cfg(target_os = "linux")]
+            clap_plugin_timer_support: clap_plugin_timer_support {
+                on_timer: Some(Self::ext_timer_support_on_timer),
+            },
+            #[cfg(target_os = "linux")]
+            host_timer_support: AtomicRefCell::new(None),
+            #[cfg(target_os = "linux")]
+            host_timer_id: AtomicU32::new(CLAP_INVALID_ID),
+            #[cfg(target_os = "linux")]
+            clap_plugin_posix_fd_support: clap_plugin_posix_fd_support {
+                on_fd: Some(Self::ext_posix_fd_support_on_fd),
+            },
+            #[cfg(target_os = "linux")]
+            host_posix_fd_support: AtomicRefCell::new(None),
+            #[cfg(target_os = "linux")]
+            task_fd: AtomicI32::new(-1),
         };
 
         // Finally, the wrapper needs to contain a reference to itself so we can create GuiContexts
@@ -705,7 +1143,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                     let wrapper = wrapper.clone();
 
                     move |task| {
-                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        let task_posted = wrapper.schedule_plugin_background_task(task);
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
@@ -717,6 +1155,11 @@ impl<P: ClapPlugin> Wrapper<P> {
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
+                cancel_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |tag| wrapper.cancel_background_tasks(tag)
+                }),
             })
             .map(Mutex::new);
 
@@ -728,8 +1171,13 @@ impl<P: ClapPlugin> Wrapper<P> {
     }
 
     fn make_gui_context(self: Arc<Self>) -> Arc<WrapperGuiContext<P>> {
+        let param_links = ParamLinks::new(self.params.param_links());
+
         Arc::new(WrapperGuiContext {
             wrapper: self,
+            group_edit: Default::default(),
+            param_links: AtomicRefCell::new(param_links),
+            clipboard: Default::default(),
             #[cfg(debug_assertions)]
             param_gesture_checker: Default::default(),
         })
@@ -751,10 +1199,29 @@ impl<P: ClapPlugin> Wrapper<P> {
             wrapper: self,
             input_events_guard: self.input_events.borrow_mut(),
             output_events_guard: self.output_events.borrow_mut(),
+            output_param_values_guard: self.output_param_values.borrow_mut(),
             transport,
+            aux_input_count: self.current_audio_io_layout.load().aux_input_ports.len(),
         }
     }
 
+    /// Posts one of the plugin's tasks to the background thread using
+    /// [`EventLoop::schedule_background()`] with the options from
+    /// [`Plugin::background_task_options()`].
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_plugin_background_task(&self, task: P::BackgroundTask) -> bool {
+        let options = P::background_task_options(&task);
+        self.schedule_background(Task::PluginTask(task), options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
+        self.cancel_background(tag)
+    }
+
     /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
     /// gesture checks.
     #[allow(unused)]
@@ -786,6 +1253,18 @@ impl<P: ClapPlugin> Wrapper<P> {
         result
     }
 
+    /// Inform the plugin that its editor has been opened or closed through
+    /// [`Plugin::editor_opened()`] and [`Plugin::editor_closed()`]. Called from the main thread
+    /// after the editor handle has been created or dropped.
+    fn notify_editor_opened(&self, opened: bool) {
+        let mut plugin = self.plugin.lock();
+        if opened {
+            plugin.editor_opened();
+        } else {
+            plugin.editor_closed();
+        }
+    }
+
     /// Request a resize based on the editor's current reported size. As of CLAP 0.24 this can
     /// safely be called from any thread. If this returns `false`, then the plugin should reset its
     /// size back to the previous value.
@@ -810,6 +1289,78 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// Queue a note event sent from the editor. See
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+    pub fn queue_gui_note_event(&self, event: NoteEvent<()>) -> bool {
+        queue_gui_note_event::<P>(&self.gui_note_events, event)
+    }
+
+    /// Change the UI scale chosen by the user. See
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()].
+    pub fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        // On macOS scaling is done by the OS, and all window sizes are in logical pixels
+        if cfg!(target_os = "macos") {
+            return false;
+        }
+
+        let applied = match self.editor.borrow().as_ref() {
+            Some(editor) => {
+                let editor = editor.lock();
+                editor.set_ui_scale(scale) && self.update_editor_scaling_factor(&**editor)
+            }
+            None => false,
+        };
+
+        // The editor's lock needs to be released again before requesting the resize
+        applied && self.request_resize()
+    }
+
+    /// Pass the user's UI scale to the editor, or the host's scaling factor if the user has not
+    /// chosen a UI scale. Returns `false` if the editor rejected the new scaling factor, for
+    /// instance because it is currently open.
+    fn update_editor_scaling_factor(&self, editor: &dyn Editor) -> bool {
+        let scaling_factor = editor
+            .ui_scale()
+            .unwrap_or_else(|| self.editor_host_scaling_factor.load(Ordering::Relaxed));
+        if editor.set_scale_factor(scaling_factor) {
+            self.editor_scaling_factor
+                .store(scaling_factor, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Ask the host to show its context menu for a parameter at a position in the editor window,
+    /// specified in logical pixels. Returns `false` if the host does not support the
+    /// `context-menu` extension or if it cannot show a popup menu.
+    pub fn show_param_context_menu(&self, param_hash: u32, position: (f32, f32)) -> bool {
+        match &*self.host_context_menu.borrow() {
+            Some(host_context_menu) => {
+                if !unsafe_clap_call! { host_context_menu=>can_popup(&*self.host_callback) } {
+                    return false;
+                }
+
+                let scaling_factor = self.editor_scaling_factor.load(Ordering::Relaxed);
+                let target = clap_context_menu_target {
+                    kind: CLAP_CONTEXT_MENU_TARGET_KIND_PARAM,
+                    id: param_hash,
+                };
+
+                unsafe_clap_call! {
+                    host_context_menu=>popup(
+                        &*self.host_callback,
+                        &target,
+                        0,
+                        (position.0 * scaling_factor).round() as i32,
+                        (position.1 * scaling_factor).round() as i32,
+                    )
+                }
+            }
+            None => false,
+        }
+    }
+
     /// Convenience function for setting a value for a parameter as triggered by a VST3 parameter
     /// update. The same rate is for updating parameter smoothing.
     ///
@@ -832,13 +1383,14 @@ impl<P: ClapPlugin> Wrapper<P> {
             Some(param_ptr) => {
                 match update_type {
                     ClapParamUpdate::PlainValueSet(clap_plain_value) => {
-                        let normalized_value = clap_plain_value as f32
-                            / unsafe { param_ptr.step_count() }.unwrap_or(1) as f32;
+                        let normalized_value =
+                            unsafe { clap_value_to_normalized(param_ptr, clap_plain_value) };
 
                         if unsafe { param_ptr.set_normalized_value(normalized_value) } {
                             if let Some(sample_rate) = sample_rate {
                                 unsafe { param_ptr.update_smoother(sample_rate, false) };
                             }
+                            self.apply_macro_mappings(param_ptr, sample_rate);
 
                             // The GUI needs to be informed about the changed parameter value. This
                             // triggers an `Editor::param_value_changed()` call on the GUI thread.
@@ -853,13 +1405,14 @@ impl<P: ClapPlugin> Wrapper<P> {
                         true
                     }
                     ClapParamUpdate::PlainValueMod(clap_plain_delta) => {
-                        let normalized_delta = clap_plain_delta as f32
-                            / unsafe { param_ptr.step_count() }.unwrap_or(1) as f32;
+                        let normalized_delta =
+                            unsafe { clap_offset_to_normalized(param_ptr, clap_plain_delta) };
 
                         if unsafe { param_ptr.modulate_value(normalized_delta) } {
                             if let Some(sample_rate) = sample_rate {
                                 unsafe { param_ptr.update_smoother(sample_rate, false) };
                             }
+                            self.apply_macro_mappings(param_ptr, sample_rate);
 
                             let task_posted = self.schedule_gui(Task::ParameterModulationChanged(
                                 hash,
@@ -879,6 +1432,26 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    /// If `param_ptr` is a [`MacroParam`][crate::prelude::MacroParam], then update its target
+    /// parameters after its value has changed and inform the GUI about the new target values.
+    fn apply_macro_mappings(&self, param_ptr: &ParamPtr, sample_rate: Option<f32>) {
+        unsafe {
+            param_ptr.apply_macro_mappings(
+                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                sample_rate,
+                |target_ptr, normalized_value| {
+                    if let Some(target_hash) = self.param_ptr_to_hash.get(&target_ptr) {
+                        let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                            *target_hash,
+                            normalized_value,
+                        ));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                },
+            )
+        };
+    }
+
     /// Handle all incoming events from an event queue. This will clear `self.input_events` first.
     ///
     /// # Safety
@@ -1055,16 +1628,77 @@ impl<P: ClapPlugin> Wrapper<P> {
             nih_debug_assert!(push_successful);
         }
 
-        // Also send all note events generated by the plugin
+        // Also send all note events and parameter changes generated by the plugin. Both queues are
+        // in chronological order, so they're interleaved here to keep the host's queue sorted.
         let mut output_events = self.output_events.borrow_mut();
-        while let Some(event) = output_events.pop_front() {
+        let mut output_param_values = self.output_param_values.borrow_mut();
+        while let Some((port_index, event)) = output_events.pop_front() {
             // Out of bounds events are clamped to the buffer's size
             let time = clamp_output_event_timing(
                 event.timing() + current_sample_idx as u32,
                 total_buffer_len as u32,
             );
 
+            while output_param_values
+                .front()
+                .is_some_and(|change| change.timing + current_sample_idx as u32 <= time)
+            {
+                let change = output_param_values.pop_front().unwrap();
+                self.push_output_param_value(
+                    out,
+                    change,
+                    current_sample_idx,
+                    total_buffer_len,
+                    sample_rate,
+                );
+            }
+
+            // Ports that don't support CLAP's own note events get MIDI messages instead
+            let port_supports_clap = P::MIDI_OUTPUT_PORTS
+                .get(port_index as usize)
+                .map(|port| port.dialects.contains(NoteDialects::CLAP))
+                .unwrap_or(true);
+
             let push_successful = match event {
+                midi_event @ (NoteEvent::NoteOn { .. }
+                | NoteEvent::NoteOff { .. }
+                | NoteEvent::PolyPressure { .. })
+                    if P::MIDI_OUTPUT >= MidiConfig::Basic && !port_supports_clap =>
+                {
+                    let midi_data = match midi_event.as_midi() {
+                        Some(MidiResult::Basic(midi_data)) => midi_data,
+                        Some(MidiResult::SysEx(_, _)) => unreachable!(
+                            "Basic MIDI event read as SysEx, something's gone horribly wrong"
+                        ),
+                        None => unreachable!("Missing MIDI conversion for MIDI event"),
+                    };
+
+                    let event = clap_event_midi {
+                        header: clap_event_header {
+                            size: mem::size_of::<clap_event_midi>() as u32,
+                            time,
+                            space_id: CLAP_CORE_EVENT_SPACE_ID,
+                            type_: CLAP_EVENT_MIDI,
+                            flags: 0,
+                        },
+                        port_index,
+                        data: midi_data,
+                    };
+
+                    clap_call! { out=>try_push(out, &event.header) }
+                }
+                // The other polyphonic events don't have a MIDI equivalent
+                NoteEvent::VoiceTerminated { .. }
+                | NoteEvent::PolyVolume { .. }
+                | NoteEvent::PolyPan { .. }
+                | NoteEvent::PolyTuning { .. }
+                | NoteEvent::PolyVibrato { .. }
+                | NoteEvent::PolyExpression { .. }
+                | NoteEvent::PolyBrightness { .. }
+                    if !port_supports_clap =>
+                {
+                    continue;
+                }
                 NoteEvent::NoteOn {
                     timing: _,
                     voice_id,
@@ -1082,7 +1716,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                             flags: 0,
                         },
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         velocity: velocity as f64,
@@ -1106,7 +1740,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                             flags: 0,
                         },
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         velocity: velocity as f64,
@@ -1131,7 +1765,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                             flags: 0,
                         },
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         velocity: 0.0,
@@ -1156,7 +1790,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_PRESSURE,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: pressure as f64,
@@ -1181,7 +1815,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_VOLUME,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: gain as f64,
@@ -1206,7 +1840,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_PAN,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: (pan as f64 + 1.0) / 2.0,
@@ -1231,7 +1865,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_TUNING,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: tuning as f64,
@@ -1256,7 +1890,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_VIBRATO,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: vibrato as f64,
@@ -1281,7 +1915,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_EXPRESSION,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: expression as f64,
@@ -1306,7 +1940,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                         },
                         expression_id: CLAP_NOTE_EXPRESSION_BRIGHTNESS,
                         note_id: voice_id.unwrap_or(-1),
-                        port_index: 0,
+                        port_index: port_index as i16,
                         channel: channel as i16,
                         key: note as i16,
                         value: brightness as f64,
@@ -1338,7 +1972,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                             type_: CLAP_EVENT_MIDI,
                             flags: 0,
                         },
-                        port_index: 0,
+                        port_index,
                         data: midi_data,
                     };
 
@@ -1361,7 +1995,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                             type_: CLAP_EVENT_MIDI_SYSEX,
                             flags: 0,
                         },
-                        port_index: 0,
+                        port_index,
                         // The host _should_ be making a copy of the data if it accepts the event. Should...
                         buffer: sysex_buffer.as_ptr(),
                         size: sysex_buffer.len() as u32,
@@ -1379,6 +2013,63 @@ impl<P: ClapPlugin> Wrapper<P> {
 
             nih_debug_assert!(push_successful, "Could not send note event");
         }
+
+        // Any parameter changes after the last note event still need to be sent
+        while let Some(change) = output_param_values.pop_front() {
+            self.push_output_param_value(
+                out,
+                change,
+                current_sample_idx,
+                total_buffer_len,
+                sample_rate,
+            );
+        }
+    }
+
+    /// Apply a parameter change made by the plugin through
+    /// [`ProcessContext::set_parameter()`][crate::prelude::ProcessContext::set_parameter()] and send
+    /// it to the host as part of [`handle_out_events()`][Self::handle_out_events()].
+    ///
+    /// # Safety
+    ///
+    /// `out` must be a valid object.
+    unsafe fn push_output_param_value(
+        &self,
+        out: &clap_output_events,
+        change: OutputParamValue,
+        current_sample_idx: usize,
+        total_buffer_len: usize,
+        sample_rate: Option<f32>,
+    ) {
+        self.update_plain_value_by_hash(
+            change.param_hash,
+            ClapParamUpdate::PlainValueSet(change.clap_plain_value),
+            sample_rate,
+        );
+
+        let event = clap_event_param_value {
+            header: clap_event_header {
+                size: mem::size_of::<clap_event_param_value>() as u32,
+                time: clamp_output_event_timing(
+                    change.timing + current_sample_idx as u32,
+                    total_buffer_len as u32,
+                ),
+                space_id: CLAP_CORE_EVENT_SPACE_ID,
+                type_: CLAP_EVENT_PARAM_VALUE,
+                // These changes don't come from the user, so they're not live events
+                flags: 0,
+            },
+            param_id: change.param_hash,
+            cookie: std::ptr::null_mut(),
+            port_index: -1,
+            note_id: -1,
+            channel: -1,
+            key: -1,
+            value: change.clap_plain_value,
+        };
+
+        let push_successful = clap_call! { out=>try_push(out, &event.header) };
+        nih_debug_assert!(push_successful, "Could not send parameter change");
     }
 
     /// Handle an incoming CLAP event. The sample index is provided to support block splitting for
@@ -1398,7 +2089,7 @@ impl<P: ClapPlugin> Wrapper<P> {
     pub unsafe fn handle_in_event(
         &self,
         event: *const clap_event_header,
-        input_events: &mut AtomicRefMut<VecDeque<PluginNoteEvent<P>>>,
+        input_events: &mut AtomicRefMut<VecDeque<(u16, PluginNoteEvent<P>)>>,
         transport_info: Option<&mut *const clap_event_transport>,
         current_sample_idx: usize,
         total_buffer_len: usize,
@@ -1411,6 +2102,30 @@ impl<P: ClapPlugin> Wrapper<P> {
             total_buffer_len as u32,
         );
 
+        // Note events are tagged with the index of the note port they were received on. Parameter
+        // events are always sent to the first port.
+        let port_index = match (raw_event.space_id, raw_event.type_) {
+            (
+                CLAP_CORE_EVENT_SPACE_ID,
+                CLAP_EVENT_NOTE_ON | CLAP_EVENT_NOTE_OFF | CLAP_EVENT_NOTE_CHOKE,
+            ) => (*(event as *const clap_event_note)).port_index.max(0) as u16,
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_EXPRESSION) => {
+                (*(event as *const clap_event_note_expression))
+                    .port_index
+                    .max(0) as u16
+            }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI) => {
+                (*(event as *const clap_event_midi)).port_index
+            }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI_SYSEX) => {
+                (*(event as *const clap_event_midi_sysex)).port_index
+            }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI2) => {
+                (*(event as *const clap_event_midi2)).port_index
+            }
+            _ => 0,
+        };
+
         match (raw_event.space_id, raw_event.type_) {
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_VALUE) => {
                 let event = &*(event as *const clap_event_param_value);
@@ -1428,14 +2143,16 @@ impl<P: ClapPlugin> Wrapper<P> {
                     // The modulation offset needs to be normalized to account for modulated
                     // integer or enum parameters
                     let param_ptr = self.param_by_hash[&event.param_id];
-                    let normalized_value =
-                        event.value as f32 / param_ptr.step_count().unwrap_or(1) as f32;
-
-                    input_events.push_back(NoteEvent::MonoAutomation {
-                        timing,
-                        poly_modulation_id: *poly_modulation_id,
-                        normalized_value,
-                    });
+                    let normalized_value = clap_value_to_normalized(&param_ptr, event.value);
+
+                    input_events.push_back((
+                        port_index,
+                        NoteEvent::MonoAutomation {
+                            timing,
+                            poly_modulation_id: *poly_modulation_id,
+                            normalized_value,
+                        },
+                    ));
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_PARAM_MOD) => {
@@ -1448,17 +2165,20 @@ impl<P: ClapPlugin> Wrapper<P> {
                             // integer or enum parameters
                             let param_ptr = self.param_by_hash[&event.param_id];
                             let normalized_offset =
-                                event.amount as f32 / param_ptr.step_count().unwrap_or(1) as f32;
+                                clap_offset_to_normalized(&param_ptr, event.amount);
 
                             // The host may also add key and channel information here, but it may
                             // also pass -1. So not having that information here at all seems like
                             // the safest choice.
-                            input_events.push_back(NoteEvent::PolyModulation {
-                                timing,
-                                voice_id: event.note_id,
-                                poly_modulation_id: *poly_modulation_id,
-                                normalized_offset,
-                            });
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyModulation {
+                                    timing,
+                                    voice_id: event.note_id,
+                                    poly_modulation_id: *poly_modulation_id,
+                                    normalized_offset,
+                                },
+                            ));
 
                             return;
                         }
@@ -1484,51 +2204,60 @@ impl<P: ClapPlugin> Wrapper<P> {
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_ON) => {
                 if P::MIDI_INPUT >= MidiConfig::Basic {
                     let event = &*(event as *const clap_event_note);
-                    input_events.push_back(NoteEvent::NoteOn {
-                        // When splitting up the buffer for sample accurate automation all events
-                        // should be relative to the block
-                        timing,
-                        voice_id: if event.note_id != -1 {
-                            Some(event.note_id)
-                        } else {
-                            None
+                    input_events.push_back((
+                        port_index,
+                        NoteEvent::NoteOn {
+                            // When splitting up the buffer for sample accurate automation all events
+                            // should be relative to the block
+                            timing,
+                            voice_id: if event.note_id != -1 {
+                                Some(event.note_id)
+                            } else {
+                                None
+                            },
+                            channel: event.channel as u8,
+                            note: event.key as u8,
+                            velocity: event.velocity as f32,
                         },
-                        channel: event.channel as u8,
-                        note: event.key as u8,
-                        velocity: event.velocity as f32,
-                    });
+                    ));
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_OFF) => {
                 if P::MIDI_INPUT >= MidiConfig::Basic {
                     let event = &*(event as *const clap_event_note);
-                    input_events.push_back(NoteEvent::NoteOff {
-                        timing,
-                        voice_id: if event.note_id != -1 {
-                            Some(event.note_id)
-                        } else {
-                            None
+                    input_events.push_back((
+                        port_index,
+                        NoteEvent::NoteOff {
+                            timing,
+                            voice_id: if event.note_id != -1 {
+                                Some(event.note_id)
+                            } else {
+                                None
+                            },
+                            channel: event.channel as u8,
+                            note: event.key as u8,
+                            velocity: event.velocity as f32,
                         },
-                        channel: event.channel as u8,
-                        note: event.key as u8,
-                        velocity: event.velocity as f32,
-                    });
+                    ));
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_CHOKE) => {
                 if P::MIDI_INPUT >= MidiConfig::Basic {
                     let event = &*(event as *const clap_event_note);
-                    input_events.push_back(NoteEvent::Choke {
-                        timing,
-                        voice_id: if event.note_id != -1 {
-                            Some(event.note_id)
-                        } else {
-                            None
+                    input_events.push_back((
+                        port_index,
+                        NoteEvent::Choke {
+                            timing,
+                            voice_id: if event.note_id != -1 {
+                                Some(event.note_id)
+                            } else {
+                                None
+                            },
+                            // FIXME: These values are also allowed to be -1, we need to support that
+                            channel: event.channel as u8,
+                            note: event.key as u8,
                         },
-                        // FIXME: These values are also allowed to be -1, we need to support that
-                        channel: event.channel as u8,
-                        note: event.key as u8,
-                    });
+                    ));
                 }
             }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_NOTE_EXPRESSION) => {
@@ -1537,96 +2266,117 @@ impl<P: ClapPlugin> Wrapper<P> {
                     let event = &*(event as *const clap_event_note_expression);
                     match event.expression_id {
                         CLAP_NOTE_EXPRESSION_PRESSURE => {
-                            input_events.push_back(NoteEvent::PolyPressure {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyPressure {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    pressure: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                pressure: event.value as f32,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_VOLUME => {
-                            input_events.push_back(NoteEvent::PolyVolume {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyVolume {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    gain: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                gain: event.value as f32,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_PAN => {
-                            input_events.push_back(NoteEvent::PolyPan {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyPan {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    // In CLAP this value goes from [0, 1] instead of [-1, 1]
+                                    pan: (event.value as f32 * 2.0) - 1.0,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                // In CLAP this value goes from [0, 1] instead of [-1, 1]
-                                pan: (event.value as f32 * 2.0) - 1.0,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_TUNING => {
-                            input_events.push_back(NoteEvent::PolyTuning {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyTuning {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    tuning: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                tuning: event.value as f32,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_VIBRATO => {
-                            input_events.push_back(NoteEvent::PolyVibrato {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyVibrato {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    vibrato: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                vibrato: event.value as f32,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_EXPRESSION => {
-                            input_events.push_back(NoteEvent::PolyExpression {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyExpression {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    expression: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                expression: event.value as f32,
-                            });
+                            ));
                         }
                         CLAP_NOTE_EXPRESSION_BRIGHTNESS => {
-                            input_events.push_back(NoteEvent::PolyBrightness {
-                                timing,
-                                voice_id: if event.note_id != -1 {
-                                    Some(event.note_id)
-                                } else {
-                                    None
+                            input_events.push_back((
+                                port_index,
+                                NoteEvent::PolyBrightness {
+                                    timing,
+                                    voice_id: if event.note_id != -1 {
+                                        Some(event.note_id)
+                                    } else {
+                                        None
+                                    },
+                                    channel: event.channel as u8,
+                                    note: event.key as u8,
+                                    brightness: event.value as f32,
                                 },
-                                channel: event.channel as u8,
-                                note: event.key as u8,
-                                brightness: event.value as f32,
-                            });
+                            ));
                         }
                         n => nih_debug_assert_failure!("Unhandled note expression ID {}", n),
                     }
@@ -1644,15 +2394,35 @@ impl<P: ClapPlugin> Wrapper<P> {
                         | NoteEvent::NoteOff { .. }
                         | NoteEvent::PolyPressure { .. }),
                     ) if P::MIDI_INPUT >= MidiConfig::Basic => {
-                        input_events.push_back(note_event);
+                        input_events.push_back((port_index, note_event));
                     }
                     Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
-                        input_events.push_back(note_event);
+                        input_events.push_back((port_index, note_event));
                     }
                     Ok(_) => (),
                     Err(n) => nih_debug_assert_failure!("Unhandled MIDI message type {}", n),
                 };
             }
+            (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI2) => {
+                // MIDI 2.0 messages are converted to their MIDI 1.0 equivalents, with the same
+                // filtering as above
+                let event = &*(event as *const clap_event_midi2);
+
+                match NoteEvent::from_midi2(timing, &event.data) {
+                    Ok(
+                        note_event @ (NoteEvent::NoteOn { .. }
+                        | NoteEvent::NoteOff { .. }
+                        | NoteEvent::PolyPressure { .. }),
+                    ) if P::MIDI_INPUT >= MidiConfig::Basic => {
+                        input_events.push_back((port_index, note_event));
+                    }
+                    Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                        input_events.push_back((port_index, note_event));
+                    }
+                    Ok(_) => (),
+                    Err(n) => nih_debug_assert_failure!("Unhandled MIDI 2.0 message type {:#x}", n),
+                };
+            }
             (CLAP_CORE_EVENT_SPACE_ID, CLAP_EVENT_MIDI_SYSEX)
                 if P::MIDI_INPUT >= MidiConfig::Basic =>
             {
@@ -1663,7 +2433,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                 assert!(!event.buffer.is_null());
                 let sysex_buffer = std::slice::from_raw_parts(event.buffer, event.size as usize);
                 if let Ok(note_event) = NoteEvent::from_midi(timing, sysex_buffer) {
-                    input_events.push_back(note_event);
+                    input_events.push_back((port_index, note_event));
                 };
             }
             _ => {
@@ -1684,6 +2454,7 @@ impl<P: ClapPlugin> Wrapper<P> {
             state::serialize_object::<P>(
                 self.params.clone(),
                 state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                StateSaveContext::Project,
             )
         }
     }
@@ -1734,6 +2505,11 @@ impl<P: ClapPlugin> Wrapper<P> {
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
     pub fn set_latency_samples(&self, samples: u32) {
         // Only make a callback if it's actually needed
         // XXX: For CLAP we could move this handling to the Plugin struct, but it may be worthwhile
@@ -1745,27 +2521,341 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
-    pub fn set_current_voice_capacity(&self, capacity: u32) {
-        match P::CLAP_POLY_MODULATION_CONFIG {
-            Some(config) => {
-                let clamped_capacity = capacity.clamp(1, config.max_voice_capacity);
-                nih_debug_assert_eq!(
-                    capacity,
-                    clamped_capacity,
-                    "The current voice capacity must be between 1 and the maximum capacity"
-                );
+    /// Tell the host that one or more [`DynamicParam`][crate::prelude::DynamicParam]s have been
+    /// relabeled.
+    pub fn param_info_changed(&self) {
+        let task_posted = self.schedule_gui(Task::RescanParamInfo);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
 
-                if clamped_capacity != self.current_voice_capacity.load(Ordering::Relaxed) {
-                    self.current_voice_capacity
-                        .store(clamped_capacity, Ordering::Relaxed);
-                    let task_posted = self.schedule_gui(Task::VoiceInfoChanged);
-                    nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
-                }
-            }
-            None => nih_debug_assert_failure!(
-                "Configuring the current voice capacity is only possible when \
-                 'ClapPlugin::CLAP_POLY_MODULATION_CONFIG' is set"
-            ),
+    pub fn set_tail_samples(&self, samples: u32) {
+        // Only make a callback if it's actually needed
+        let old_tail = self.current_tail.swap(samples, Ordering::SeqCst);
+        if old_tail != samples {
+            let task_posted = self.schedule_gui(Task::TailChanged);
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Get the frequency for a MIDI note using the host's tuning. See
+    /// [`ProcessContext::note_frequency()`].
+    pub fn note_frequency(&self, note: u8, channel: u8, tuning_id: Option<u32>) -> f32 {
+        let tuning_id = tuning_id.unwrap_or_else(|| self.default_tuning_id.load(Ordering::Relaxed));
+        match &*self.host_tuning.borrow() {
+            Some(host_tuning) if tuning_id != CLAP_INVALID_ID => {
+                // This is the offset in semitones relative to 12-TET with A4 = 440 Hz
+                let relative = unsafe_clap_call! {
+                    host_tuning=>get_relative(
+                        &*self.host_callback,
+                        tuning_id,
+                        channel as i32,
+                        note as i32,
+                        0
+                    )
+                };
+
+                util::f32_midi_note_to_freq(note as f32 + relative as f32)
+            }
+            _ => equal_temperament_frequency(note),
+        }
+    }
+
+    /// Query the track information from the host, and inform the plugin if it changed. Must be
+    /// called from the main thread.
+    fn update_track_info(&self) {
+        let track_info = match &*self.host_track_info.borrow() {
+            Some(host_track_info) => {
+                let mut info: clap_track_info = unsafe { std::mem::zeroed() };
+                let success =
+                    unsafe_clap_call! { host_track_info=>get(&*self.host_callback, &mut info) };
+                if success {
+                    let has_flag = |flag| info.flags & flag != 0;
+                    TrackInfo {
+                        name: has_flag(CLAP_TRACK_INFO_HAS_TRACK_NAME).then(|| unsafe {
+                            CStr::from_ptr(info.name.as_ptr())
+                                .to_string_lossy()
+                                .into_owned()
+                        }),
+                        color: has_flag(CLAP_TRACK_INFO_HAS_TRACK_COLOR).then_some([
+                            info.color.red,
+                            info.color.green,
+                            info.color.blue,
+                            info.color.alpha,
+                        ]),
+                        audio_channel_count: has_flag(CLAP_TRACK_INFO_HAS_AUDIO_CHANNEL)
+                            .then_some(info.audio_channel_count.max(0) as u32),
+                        is_return_track: has_flag(CLAP_TRACK_INFO_IS_FOR_RETURN_TRACK),
+                        is_bus: has_flag(CLAP_TRACK_INFO_IS_FOR_BUS),
+                        is_master: has_flag(CLAP_TRACK_INFO_IS_FOR_MASTER),
+                    }
+                } else {
+                    return;
+                }
+            }
+            None => return,
+        };
+
+        if self.track_info.load().as_deref() != Some(&track_info) {
+            self.plugin.lock().track_info_changed(&track_info);
+            self.track_info.store(Some(Arc::new(track_info)));
+        }
+    }
+
+    /// Query the ID of the host's first tuning. Must be called from the main thread.
+    fn update_default_tuning_id(&self) {
+        let default_tuning_id = match &*self.host_tuning.borrow() {
+            Some(host_tuning) => {
+                let tuning_count =
+                    unsafe_clap_call! { host_tuning=>get_tuning_count(&*self.host_callback) };
+
+                let mut info: clap_tuning_info = unsafe { std::mem::zeroed() };
+                let success = tuning_count > 0
+                    && unsafe_clap_call! {
+                        host_tuning=>get_info(&*self.host_callback, 0, &mut info)
+                    };
+                if success {
+                    info.tuning_id
+                } else {
+                    CLAP_INVALID_ID
+                }
+            }
+            None => CLAP_INVALID_ID,
+        };
+
+        self.default_tuning_id
+            .store(default_tuning_id, Ordering::Relaxed);
+    }
+
+    /// Use the host's timer-support and posix-fd-support extensions if they are available. Some
+    /// hosts require all GUI work to happen from their own event loop on Linux, so timers and
+    /// tasks posted through [`schedule_gui()`][Self::schedule_gui()] are then run from the host's
+    /// timers and file descriptor callbacks instead of from the timer thread. Must be called from
+    /// the main thread during initialization, before any timers are created.
+    #[cfg(target_os = "linux")]
+    fn register_host_event_sources(&self) {
+        *self.host_timer_support.borrow_mut() = unsafe {
+            query_host_extension::<clap_host_timer_support>(
+                &self.host_callback,
+                CLAP_EXT_TIMER_SUPPORT,
+            )
+        };
+        *self.host_posix_fd_support.borrow_mut() = unsafe {
+            query_host_extension::<clap_host_posix_fd_support>(
+                &self.host_callback,
+                CLAP_EXT_POSIX_FD_SUPPORT,
+            )
+        };
+
+        if let Some(host_timer_support) = &*self.host_timer_support.borrow() {
+            let mut timer_id = CLAP_INVALID_ID;
+            let registered = unsafe_clap_call! {
+                host_timer_support=>register_timer(&*self.host_callback, HOST_TIMER_PERIOD_MS, &mut timer_id)
+            };
+            if registered {
+                self.host_timer_id.store(timer_id, Ordering::SeqCst);
+                *self.timers.borrow_mut() = Timers::new_polled();
+            } else {
+                nih_debug_assert_failure!("The host could not register a timer");
+            }
+        }
+
+        if let Some(host_posix_fd_support) = &*self.host_posix_fd_support.borrow() {
+            let fd = unsafe { libc::eventfd(0, libc::EFD_NONBLOCK | libc::EFD_CLOEXEC) };
+            if fd < 0 {
+                nih_debug_assert_failure!("Could not create an eventfd for the task queue");
+                return;
+            }
+
+            let registered = unsafe_clap_call! {
+                host_posix_fd_support=>register_fd(&*self.host_callback, fd, CLAP_POSIX_FD_READ)
+            };
+            if registered {
+                self.task_fd.store(fd, Ordering::SeqCst);
+            } else {
+                nih_debug_assert_failure!("The host could not register the task queue's eventfd");
+                unsafe { libc::close(fd) };
+            }
+        }
+    }
+
+    /// Unregister the host timer and file descriptor registered in
+    /// [`register_host_event_sources()`][Self::register_host_event_sources()]. Must be called from
+    /// the main thread.
+    #[cfg(target_os = "linux")]
+    fn unregister_host_event_sources(&self) {
+        let timer_id = self.host_timer_id.swap(CLAP_INVALID_ID, Ordering::SeqCst);
+        if timer_id != CLAP_INVALID_ID {
+            if let Some(host_timer_support) = &*self.host_timer_support.borrow() {
+                unsafe_clap_call! {
+                    host_timer_support=>unregister_timer(&*self.host_callback, timer_id)
+                };
+            }
+        }
+
+        let fd = self.task_fd.swap(-1, Ordering::SeqCst);
+        if fd >= 0 {
+            if let Some(host_posix_fd_support) = &*self.host_posix_fd_support.borrow() {
+                unsafe_clap_call! {
+                    host_posix_fd_support=>unregister_fd(&*self.host_callback, fd)
+                };
+            }
+            unsafe { libc::close(fd) };
+        }
+    }
+
+    /// Wake up the host's event loop through the task queue's eventfd so the queued tasks get run
+    /// from [`ext_posix_fd_support_on_fd()`][Self::ext_posix_fd_support_on_fd()]. Returns `false`
+    /// if the host does not support the posix-fd-support extension, in which case
+    /// `clap_host::request_callback()` should be used instead.
+    #[cfg(target_os = "linux")]
+    fn notify_task_fd(&self) -> bool {
+        let fd = self.task_fd.load(Ordering::SeqCst);
+        if fd < 0 {
+            return false;
+        }
+
+        let counter = 1u64;
+        let bytes_written = unsafe {
+            libc::write(
+                fd,
+                &counter as *const u64 as *const c_void,
+                mem::size_of::<u64>(),
+            )
+        };
+
+        bytes_written == mem::size_of::<u64>() as libc::ssize_t
+    }
+
+    /// Run `task` for every index in `0..count`, using the host's thread pool if it is available.
+    /// This falls back to running the tasks sequentially on the current thread when the host does
+    /// not support the thread pool extension or when it refuses the request. May only be called
+    /// from the audio thread while the plugin is processing audio.
+    pub fn run_tasks_parallel(&self, count: usize, task: &(dyn Fn(usize) + Sync)) {
+        if count > 1 {
+            if let Some(host_thread_pool) = &*self.host_thread_pool.borrow() {
+                // Nested calls from within a task are not allowed to use the thread pool
+                if let Ok(mut thread_pool_task) = self.thread_pool_task.try_borrow_mut() {
+                    // SAFETY: The host only calls `exec()` while `request_exec()` is blocking, and
+                    //         the task is removed again before this function returns, so the
+                    //         task's lifetime can safely be erased here
+                    *thread_pool_task = Some(ThreadPoolTask(unsafe {
+                        mem::transmute::<
+                            *const (dyn Fn(usize) + Sync + '_),
+                            *const (dyn Fn(usize) + Sync + 'static),
+                        >(task)
+                    }));
+                    drop(thread_pool_task);
+
+                    let success = unsafe_clap_call! {
+                        host_thread_pool=>request_exec(&*self.host_callback, count as u32)
+                    };
+                    *self.thread_pool_task.borrow_mut() = None;
+                    if success {
+                        return;
+                    }
+                }
+            }
+        }
+
+        for task_index in 0..count {
+            task(task_index);
+        }
+    }
+
+    pub fn set_current_voice_capacity(&self, capacity: u32) {
+        match P::CLAP_POLY_MODULATION_CONFIG {
+            Some(config) => {
+                let clamped_capacity = capacity.clamp(1, config.max_voice_capacity);
+                nih_debug_assert_eq!(
+                    capacity,
+                    clamped_capacity,
+                    "The current voice capacity must be between 1 and the maximum capacity"
+                );
+
+                if clamped_capacity != self.current_voice_capacity.load(Ordering::Relaxed) {
+                    self.current_voice_capacity
+                        .store(clamped_capacity, Ordering::Relaxed);
+                    let task_posted = self.schedule_gui(Task::VoiceInfoChanged);
+                    nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                }
+            }
+            None => nih_debug_assert_failure!(
+                "Configuring the current voice capacity is only possible when \
+                 'ClapPlugin::CLAP_POLY_MODULATION_CONFIG' is set"
+            ),
+        }
+    }
+
+    /// Write the plugin's state to a CLAP stream. Used for both the state and the state context
+    /// extensions.
+    unsafe fn save_state(&self, stream: &clap_ostream, context: StateSaveContext) -> bool {
+        let serialized = state::serialize_json_cached::<P>(
+            self.params.clone(),
+            state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+            context,
+            &self.state_cache,
+        );
+        match serialized {
+            Ok(serialized) => {
+                // CLAP does not provide a way to tell how much data there is left in a stream, so
+                // we need to prepend it to our actual state data.
+                let length_bytes = (serialized.len() as u64).to_le_bytes();
+                if !write_stream(stream, &length_bytes) {
+                    nih_debug_assert_failure!(
+                        "Error or end of stream while writing the state length to the stream."
+                    );
+                    return false;
+                }
+                if !write_stream(stream, &serialized) {
+                    nih_debug_assert_failure!(
+                        "Error or end of stream while writing the state buffer to the stream."
+                    );
+                    return false;
+                }
+
+                nih_trace!("Saved state ({} bytes)", serialized.len());
+
+                true
+            }
+            Err(err) => {
+                nih_debug_assert_failure!("Could not save state: {:#}", err);
+                false
+            }
+        }
+    }
+
+    /// Read the plugin's state from a CLAP stream written by [`save_state()`][Self::save_state()].
+    unsafe fn load_state(&self, stream: &clap_istream) -> bool {
+        // CLAP does not have a way to tell how much data there is left in a stream, so we've
+        // prepended the size in front of our JSON state
+        let mut length_bytes = [0u8; 8];
+        if !read_stream(stream, length_bytes.as_mut_slice()) {
+            nih_debug_assert_failure!(
+                "Error or end of stream while reading the state length from the stream."
+            );
+            return false;
+        }
+        let length = u64::from_le_bytes(length_bytes);
+
+        let mut read_buffer: Vec<u8> = Vec::with_capacity(length as usize);
+        if !read_stream(stream, read_buffer.spare_capacity_mut()) {
+            nih_debug_assert_failure!(
+                "Error or end of stream while reading the state buffer from the stream."
+            );
+            return false;
+        }
+        read_buffer.set_len(length as usize);
+
+        match state::deserialize_json(&read_buffer) {
+            Some(mut state) => {
+                let success = self.set_state_inner(&mut state);
+                if success {
+                    nih_trace!("Loaded state ({} bytes)", read_buffer.len());
+                }
+
+                success
+            }
+            None => false,
         }
     }
 
@@ -1802,6 +2892,11 @@ impl<P: ClapPlugin> Wrapper<P> {
             return false;
         }
 
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks, TaskOptions::default());
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
         // If the plugin was already initialized then it needs to be reinitialized
         if let Some(buffer_config) = buffer_config {
             // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
@@ -1843,10 +2938,30 @@ impl<P: ClapPlugin> Wrapper<P> {
         // We weren't allowed to query these in the constructor, so we need to do it now instead.
         *wrapper.host_gui.borrow_mut() =
             query_host_extension::<clap_host_gui>(&wrapper.host_callback, CLAP_EXT_GUI);
+        *wrapper.host_context_menu.borrow_mut() = query_host_extension::<clap_host_context_menu>(
+            &wrapper.host_callback,
+            CLAP_EXT_CONTEXT_MENU,
+        );
+        *wrapper.host_audio_ports.borrow_mut() = query_host_extension::<clap_host_audio_ports>(
+            &wrapper.host_callback,
+            CLAP_EXT_AUDIO_PORTS,
+        );
         *wrapper.host_latency.borrow_mut() =
             query_host_extension::<clap_host_latency>(&wrapper.host_callback, CLAP_EXT_LATENCY);
         *wrapper.host_params.borrow_mut() =
             query_host_extension::<clap_host_params>(&wrapper.host_callback, CLAP_EXT_PARAMS);
+        *wrapper.host_tail.borrow_mut() =
+            query_host_extension::<clap_host_tail>(&wrapper.host_callback, CLAP_EXT_TAIL);
+        *wrapper.host_thread_pool.borrow_mut() = query_host_extension::<clap_host_thread_pool>(
+            &wrapper.host_callback,
+            CLAP_EXT_THREAD_POOL,
+        );
+        *wrapper.host_track_info.borrow_mut() = query_host_extension::<clap_host_track_info>(
+            &wrapper.host_callback,
+            CLAP_EXT_TRACK_INFO,
+        );
+        *wrapper.host_tuning.borrow_mut() =
+            query_host_extension::<clap_host_tuning>(&wrapper.host_callback, CLAP_EXT_TUNING);
         *wrapper.host_voice_info.borrow_mut() = query_host_extension::<clap_host_voice_info>(
             &wrapper.host_callback,
             CLAP_EXT_VOICE_INFO,
@@ -1856,6 +2971,12 @@ impl<P: ClapPlugin> Wrapper<P> {
             CLAP_EXT_THREAD_CHECK,
         );
 
+        #[cfg(target_os = "linux")]
+        wrapper.register_host_event_sources();
+
+        wrapper.update_default_tuning_id();
+        wrapper.update_track_info();
+
         true
     }
 
@@ -1864,6 +2985,9 @@ impl<P: ClapPlugin> Wrapper<P> {
         let this = Arc::from_raw((*plugin).plugin_data as *mut Self);
         nih_debug_assert_eq!(Arc::strong_count(&this), 1);
 
+        #[cfg(target_os = "linux")]
+        this.unregister_host_event_sources();
+
         drop(this);
     }
 
@@ -1904,6 +3028,44 @@ impl<P: ClapPlugin> Wrapper<P> {
             // Also store this for later, so we can reinitialize the plugin after restoring state
             wrapper.current_buffer_config.store(Some(buffer_config));
 
+            // If the latency changed while the plugin was still active, then the host requested
+            // a restart and it can now be informed about the new latency
+            if wrapper
+                .latency_changed_while_activated
+                .swap(false, Ordering::SeqCst)
+            {
+                match &*wrapper.host_latency.borrow() {
+                    Some(host_latency) => {
+                        unsafe_clap_call! { host_latency=>changed(&*wrapper.host_callback) }
+                    }
+                    None => {
+                        nih_debug_assert_failure!("Host does not support the latency extension")
+                    }
+                }
+            }
+
+            // Latency changes made during initialization are sent to the host when the init
+            // context is dropped. This needs to happen before the plugin is marked as activated,
+            // and the plugin lock needs to be released first to avoid deadlocks.
+            drop(plugin);
+            drop(init_context);
+            wrapper.is_activated.store(true, Ordering::SeqCst);
+
+            // The dry signal is delayed by the latency the plugin reported during initialization.
+            // Changing the latency afterwards requires the plugin to be reactivated.
+            if P::MANAGED_BYPASS {
+                wrapper.bypass_processor.borrow_mut().initialize(
+                    audio_io_layout
+                        .main_output_channels
+                        .map(NonZeroU32::get)
+                        .unwrap_or(0) as usize,
+                    wrapper.current_latency.load(Ordering::SeqCst),
+                    max_frames_count,
+                    buffer_config.sample_rate,
+                    wrapper.is_bypassed(),
+                );
+            }
+
             true
         } else {
             false
@@ -1914,7 +3076,24 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!((), plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
+        wrapper.is_activated.store(false, Ordering::SeqCst);
         wrapper.plugin.lock().deactivate();
+
+        // If the host selected a different port configuration while the plugin was still active,
+        // then that configuration can now be applied. The plugin will be reinitialized with the new
+        // layout when the host activates it again.
+        if let Some(audio_io_layout) = wrapper.pending_audio_io_layout.take() {
+            wrapper.current_audio_io_layout.store(audio_io_layout);
+
+            match &*wrapper.host_audio_ports.borrow() {
+                Some(host_audio_ports) => {
+                    unsafe_clap_call! { host_audio_ports=>rescan(&*wrapper.host_callback, CLAP_AUDIO_PORTS_RESCAN_LIST) };
+                }
+                None => {
+                    nih_debug_assert_failure!("Host does not support the audio-ports extension")
+                }
+            }
+        }
     }
 
     unsafe extern "C" fn start_processing(plugin: *const clap_plugin) -> bool {
@@ -1926,9 +3105,17 @@ impl<P: ClapPlugin> Wrapper<P> {
         // Always reset the processing status when the plugin gets activated or deactivated
         wrapper.last_process_status.store(ProcessStatus::Normal);
         wrapper.is_processing.store(true, Ordering::SeqCst);
+        wrapper.dsp_load.reset();
+        if P::MANAGED_BYPASS {
+            wrapper
+                .bypass_processor
+                .borrow_mut()
+                .reset(wrapper.is_bypassed());
+        }
 
         // To be consistent with the VST3 wrapper, we'll also reset the buffers here in addition to
         // the dedicated `reset()` function.
+        wrapper.event_scheduler.borrow_mut().reset();
         process_wrapper(|| wrapper.plugin.lock().reset());
 
         true
@@ -1945,6 +3132,7 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!((), plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
+        wrapper.event_scheduler.borrow_mut().reset();
         process_wrapper(|| wrapper.plugin.lock().reset());
     }
 
@@ -2032,6 +3220,16 @@ impl<P: ClapPlugin> Wrapper<P> {
                     }
                 }
 
+                // Notes played from the editor are handled at the start of the buffer
+                if block_start == 0 {
+                    let mut input_events = wrapper.input_events.borrow_mut();
+                    let mut num_gui_events = 0;
+                    while let Some(event) = wrapper.gui_note_events.pop() {
+                        input_events.insert(num_gui_events, (0, event));
+                        num_gui_events += 1;
+                    }
+                }
+
                 // After processing the events we now know where/if the block should be split, and
                 // we can start preparing audio processing
                 let block_len = block_end - block_start;
@@ -2244,8 +3442,35 @@ impl<P: ClapPlugin> Wrapper<P> {
                         inputs: buffers.aux_inputs,
                         outputs: buffers.aux_outputs,
                     };
+                    if let Some(modulation_matrix) = &wrapper.modulation_matrix {
+                        modulation_matrix.process_block(
+                            buffers.main_buffer,
+                            wrapper.input_events.borrow().iter().map(|(_, event)| event),
+                            sample_rate,
+                            state::make_params_getter(
+                                &wrapper.param_by_hash,
+                                &wrapper.param_id_to_hash,
+                            ),
+                        );
+                    }
                     let mut context = wrapper.make_process_context(transport);
-                    let result = plugin.process(buffers.main_buffer, &mut aux, &mut context);
+                    let mut bypass_processor = wrapper.bypass_processor.borrow_mut();
+                    if P::MANAGED_BYPASS {
+                        bypass_processor.write_dry(buffers.main_buffer);
+                    }
+                    let result = wrapper.dsp_load.measure(
+                        buffers.main_buffer.samples(),
+                        sample_rate,
+                        wrapper.current_process_mode.load(),
+                        || plugin.process(buffers.main_buffer, &mut aux, &mut context),
+                    );
+                    if P::MANAGED_BYPASS {
+                        bypass_processor.process(
+                            buffers.main_buffer,
+                            wrapper.current_latency.load(Ordering::Relaxed),
+                            wrapper.is_bypassed(),
+                        );
+                    }
                     wrapper.last_process_status.store(result);
                     result
                 } else {
@@ -2258,11 +3483,58 @@ impl<P: ClapPlugin> Wrapper<P> {
 
                         return CLAP_PROCESS_ERROR;
                     }
+                    // If the plugin has set a tail length, then the host should use that to decide
+                    // when the plugin can be suspended
+                    ProcessStatus::Normal if wrapper.current_tail.load(Ordering::Relaxed) > 0 => {
+                        CLAP_PROCESS_TAIL
+                    }
                     ProcessStatus::Normal => CLAP_PROCESS_CONTINUE_IF_NOT_QUIET,
                     ProcessStatus::Tail(_) => CLAP_PROCESS_CONTINUE,
                     ProcessStatus::KeepAlive => CLAP_PROCESS_CONTINUE,
                 };
 
+                // Read-only parameters changed by the plugin are sent to the host at a limited rate
+                wrapper.output_params.borrow_mut().update(
+                    block_end - block_start,
+                    sample_rate,
+                    |param_hash, param_ptr, normalized| {
+                        wrapper.queue_parameter_event(OutputParamEvent::SetValue {
+                            param_hash,
+                            clap_plain_value: normalized_to_clap_value(&param_ptr, normalized),
+                        });
+                    },
+                );
+
+                // Large persistent fields are periodically serialized on the background thread so
+                // saving the state doesn't have to. If the task queue is full, then this simply
+                // happens during the next refresh.
+                if wrapper
+                    .state_cache
+                    .should_refresh(block_end - block_start, sample_rate)
+                {
+                    let _ = wrapper.schedule_background(
+                        Task::RefreshStateCache,
+                        TaskOptions {
+                            priority: TaskPriority::Low,
+                            ..TaskOptions::default()
+                        },
+                    );
+                }
+
+                // Events the plugin scheduled for this block are merged into the block's other
+                // output events, keeping them sorted by timing
+                {
+                    let mut output_events = wrapper.output_events.borrow_mut();
+                    wrapper.event_scheduler.borrow_mut().advance(
+                        block_end - block_start,
+                        |event| {
+                            let index = output_events
+                                .partition_point(|(_, e)| e.timing() <= event.timing());
+                            output_events.insert(index, (0, event));
+                        },
+                    );
+                }
+
                 // After processing audio, send all spooled events to the host. This include note
                 // events.
                 if !process.out_events.is_null() {
@@ -2312,6 +3584,19 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         let id = CStr::from_ptr(id);
 
+        #[cfg(feature = "ara")]
+        if id == CLAP_EXT_ARA_PLUGINEXTENSION && P::ara_factory().is_some() {
+            return &wrapper.clap_ara_plugin_extension as *const _ as *const c_void;
+        }
+        #[cfg(target_os = "linux")]
+        if id == CLAP_EXT_POSIX_FD_SUPPORT {
+            return &wrapper.clap_plugin_posix_fd_support as *const _ as *const c_void;
+        }
+        #[cfg(target_os = "linux")]
+        if id == CLAP_EXT_TIMER_SUPPORT {
+            return &wrapper.clap_plugin_timer_support as *const _ as *const c_void;
+        }
+
         if id == CLAP_EXT_AUDIO_PORTS_CONFIG {
             &wrapper.clap_plugin_audio_ports_config as *const _ as *const c_void
         } else if id == CLAP_EXT_AUDIO_PORTS {
@@ -2327,14 +3612,25 @@ impl<P: ClapPlugin> Wrapper<P> {
             &wrapper.clap_plugin_note_ports as *const _ as *const c_void
         } else if id == CLAP_EXT_PARAMS {
             &wrapper.clap_plugin_params as *const _ as *const c_void
-        } else if id == CLAP_EXT_REMOTE_CONTROLS {
+        } else if id == CLAP_EXT_PARAM_INDICATION {
+            &wrapper.clap_plugin_param_indication as *const _ as *const c_void
+        } else if id == CLAP_EXT_REMOTE_CONTROLS || id.to_bytes() == CLAP_EXT_REMOTE_CONTROLS_STABLE
+        {
             &wrapper.clap_plugin_remote_controls as *const _ as *const c_void
         } else if id == CLAP_EXT_RENDER {
             &wrapper.clap_plugin_render as *const _ as *const c_void
         } else if id == CLAP_EXT_STATE {
             &wrapper.clap_plugin_state as *const _ as *const c_void
+        } else if id == CLAP_EXT_STATE_CONTEXT {
+            &wrapper.clap_plugin_state_context as *const _ as *const c_void
         } else if id == CLAP_EXT_TAIL {
             &wrapper.clap_plugin_tail as *const _ as *const c_void
+        } else if id == CLAP_EXT_THREAD_POOL {
+            &wrapper.clap_plugin_thread_pool as *const _ as *const c_void
+        } else if id == CLAP_EXT_TRACK_INFO {
+            &wrapper.clap_plugin_track_info as *const _ as *const c_void
+        } else if id == CLAP_EXT_TUNING {
+            &wrapper.clap_plugin_tuning as *const _ as *const c_void
         } else if id == CLAP_EXT_VOICE_INFO && P::CLAP_POLY_MODULATION_CONFIG.is_some() {
             &wrapper.clap_plugin_voice_info as *const _ as *const c_void
         } else {
@@ -2354,6 +3650,45 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    #[cfg(target_os = "linux")]
+    unsafe extern "C" fn ext_posix_fd_support_on_fd(
+        plugin: *const clap_plugin,
+        fd: libc::c_int,
+        _flags: clap_posix_fd_flags,
+    ) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if fd != wrapper.task_fd.load(Ordering::SeqCst) {
+            nih_debug_assert_failure!("Host called on_fd() for unknown file descriptor {}", fd);
+            return;
+        }
+
+        // Reading from the eventfd resets its counter. This works the same way as
+        // `on_main_thread()` otherwise.
+        let mut counter = 0u64;
+        libc::read(
+            fd,
+            &mut counter as *mut u64 as *mut c_void,
+            mem::size_of::<u64>(),
+        );
+        while let Some(task) = wrapper.tasks.pop() {
+            wrapper.execute(task, true);
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    unsafe extern "C" fn ext_timer_support_on_timer(plugin: *const clap_plugin, timer_id: clap_id) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if timer_id == wrapper.host_timer_id.load(Ordering::SeqCst) {
+            wrapper.timers.borrow().fire_due_timers();
+        } else {
+            nih_debug_assert_failure!("Host called on_timer() for unknown timer {}", timer_id);
+        }
+    }
+
     unsafe extern "C" fn ext_audio_ports_config_count(plugin: *const clap_plugin) -> u32 {
         check_null_ptr!(0, plugin, (*plugin).plugin_data);
 
@@ -2428,7 +3763,24 @@ impl<P: ClapPlugin> Wrapper<P> {
         // We use the vector indices for the config ID
         match P::AUDIO_IO_LAYOUTS.get(config_id as usize) {
             Some(audio_io_layout) => {
-                wrapper.current_audio_io_layout.store(*audio_io_layout);
+                // The host should deactivate the plugin before selecting a new configuration. If it
+                // doesn't, then the plugin is restarted and the new layout is applied in
+                // `deactivate()`. The buffer manager and the plugin itself are reinitialized for
+                // the new layout in `activate()`.
+                if wrapper.is_activated.load(Ordering::SeqCst) {
+                    nih_debug_assert_failure!(
+                        "Host tried to select audio port config {} while the plugin was active",
+                        config_id
+                    );
+
+                    wrapper
+                        .pending_audio_io_layout
+                        .store(Some(*audio_io_layout));
+                    unsafe_clap_call! { &*wrapper.host_callback=>request_restart(&*wrapper.host_callback) };
+                } else {
+                    wrapper.pending_audio_io_layout.store(None);
+                    wrapper.current_audio_io_layout.store(*audio_io_layout);
+                }
 
                 true
             }
@@ -2576,13 +3928,29 @@ impl<P: ClapPlugin> Wrapper<P> {
     }
 
     unsafe extern "C" fn ext_gui_is_api_supported(
-        _plugin: *const clap_plugin,
+        plugin: *const clap_plugin,
         api: *const c_char,
         is_floating: bool,
     ) -> bool {
-        // We don't do standalone floating windows
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, api);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // Floating windows are only supported if the editor can open its own windows. Wayland
+        // doesn't support embedding windows from other processes, so editors can only be shown
+        // there as floating windows.
         if is_floating {
-            return false;
+            let supports_floating_window = match wrapper.editor.borrow().as_ref() {
+                Some(editor) => editor.lock().supports_floating_window(),
+                None => false,
+            };
+            if !supports_floating_window {
+                return false;
+            }
+
+            #[cfg(all(target_family = "unix", not(target_os = "macos")))]
+            if CStr::from_ptr(api) == CLAP_WINDOW_API_WAYLAND {
+                return true;
+            }
         }
 
         #[cfg(all(target_family = "unix", not(target_os = "macos")))]
@@ -2621,7 +3989,8 @@ impl<P: ClapPlugin> Wrapper<P> {
             *api = CLAP_WINDOW_API_WIN32.as_ptr();
         }
 
-        // We don't do standalone floating windows yet
+        // Embedded editors are preferred, floating windows are only used when the host can't embed
+        // the editor
         *is_floating = false;
 
         true
@@ -2645,6 +4014,19 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         let editor_handle = wrapper.editor_handle.lock();
         if editor_handle.is_none() {
+            wrapper.editor_floating.store(is_floating, Ordering::SeqCst);
+            *wrapper.editor_transient_parent.lock() = None;
+
+            // If the user has chosen a UI scale in a previous session, then the host needs to know
+            // about the resulting size before the editor is embedded
+            if !cfg!(target_os = "macos") {
+                let editor = wrapper.editor.borrow();
+                let editor = editor.as_ref().unwrap().lock();
+                if editor.ui_scale().is_some() {
+                    wrapper.update_editor_scaling_factor(&**editor);
+                }
+            }
+
             true
         } else {
             nih_debug_assert_failure!("Tried creating editor while the editor was already active");
@@ -2656,12 +4038,19 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!((), plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        let mut editor_handle = wrapper.editor_handle.lock();
+        // Floating editor windows are only opened while they're shown, so they may already be
+        // closed at this point
+        let editor_handle = wrapper.editor_handle.lock().take();
         if editor_handle.is_some() {
-            *editor_handle = None;
-        } else {
+            drop(editor_handle);
+            wrapper.notify_editor_opened(false);
+        } else if !wrapper.editor_floating.load(Ordering::SeqCst) {
             nih_debug_assert_failure!("Tried destroying editor while the editor was not active");
         }
+
+        wrapper.editor_floating.store(false, Ordering::SeqCst);
+        *wrapper.editor_transient_parent.lock() = None;
+        *wrapper.editor_parent.lock() = None;
     }
 
     unsafe extern "C" fn ext_gui_set_scale(plugin: *const clap_plugin, scale: f64) -> bool {
@@ -2674,21 +4063,11 @@ impl<P: ClapPlugin> Wrapper<P> {
             return false;
         }
 
-        if wrapper
-            .editor
-            .borrow()
-            .as_ref()
-            .unwrap()
-            .lock()
-            .set_scale_factor(scale as f32)
-        {
-            wrapper
-                .editor_scaling_factor
-                .store(scale as f32, std::sync::atomic::Ordering::Relaxed);
-            true
-        } else {
-            false
-        }
+        // The user's UI scale, if set, takes precedence over the host's scaling factor
+        wrapper
+            .editor_host_scaling_factor
+            .store(scale as f32, Ordering::Relaxed);
+        wrapper.update_editor_scaling_factor(&**wrapper.editor.borrow().as_ref().unwrap().lock())
     }
 
     unsafe extern "C" fn ext_gui_get_size(
@@ -2711,26 +4090,94 @@ impl<P: ClapPlugin> Wrapper<P> {
         true
     }
 
-    unsafe extern "C" fn ext_gui_can_resize(_plugin: *const clap_plugin) -> bool {
-        // TODO: Implement Host->Plugin GUI resizing
-        false
+    unsafe extern "C" fn ext_gui_can_resize(plugin: *const clap_plugin) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper
+            .editor
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .lock()
+            .size_constraints()
+            .is_some()
     }
 
     unsafe extern "C" fn ext_gui_get_resize_hints(
-        _plugin: *const clap_plugin,
-        _hints: *mut clap_gui_resize_hints,
+        plugin: *const clap_plugin,
+        hints: *mut clap_gui_resize_hints,
     ) -> bool {
-        // TODO: Implement Host->Plugin GUI resizing
-        false
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, hints);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        let constraints = match wrapper
+            .editor
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .lock()
+            .size_constraints()
+        {
+            Some(constraints) => constraints,
+            None => return false,
+        };
+
+        // CLAP wants the aspect ratio as a fraction, so we'll approximate the ratio with a fixed
+        // denominator
+        const ASPECT_RATIO_DENOMINATOR: u32 = 10_000;
+        let hints = &mut *hints;
+        hints.can_resize_horizontally = constraints.min_size.0 != constraints.max_size.0;
+        hints.can_resize_vertically = constraints.min_size.1 != constraints.max_size.1;
+        match constraints.aspect_ratio {
+            Some(aspect_ratio) => {
+                hints.preserve_aspect_ratio = true;
+                hints.aspect_ratio_width =
+                    (aspect_ratio * ASPECT_RATIO_DENOMINATOR as f32).round() as u32;
+                hints.aspect_ratio_height = ASPECT_RATIO_DENOMINATOR;
+            }
+            None => {
+                hints.preserve_aspect_ratio = false;
+                hints.aspect_ratio_width = 0;
+                hints.aspect_ratio_height = 0;
+            }
+        }
+
+        true
     }
 
     unsafe extern "C" fn ext_gui_adjust_size(
-        _plugin: *const clap_plugin,
-        _width: *mut u32,
-        _height: *mut u32,
+        plugin: *const clap_plugin,
+        width: *mut u32,
+        height: *mut u32,
     ) -> bool {
-        // TODO: Implement Host->Plugin GUI resizing
-        false
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, width, height);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        let constraints = match wrapper
+            .editor
+            .borrow()
+            .as_ref()
+            .unwrap()
+            .lock()
+            .size_constraints()
+        {
+            Some(constraints) => constraints,
+            None => return false,
+        };
+
+        // The host works in physical pixels, while the constraints are in logical pixels
+        let scaling_factor = wrapper.editor_scaling_factor.load(Ordering::Relaxed);
+        let (constrained_width, constrained_height) = constraints.constrain((
+            (*width as f32 / scaling_factor).round() as u32,
+            (*height as f32 / scaling_factor).round() as u32,
+        ));
+        (*width, *height) = (
+            (constrained_width as f32 * scaling_factor).round() as u32,
+            (constrained_height as f32 * scaling_factor).round() as u32,
+        );
+
+        true
     }
 
     unsafe extern "C" fn ext_gui_set_size(
@@ -2738,20 +4185,39 @@ impl<P: ClapPlugin> Wrapper<P> {
         width: u32,
         height: u32,
     ) -> bool {
-        // TODO: Implement Host->Plugin GUI resizing
         // TODO: The host will also call this if an asynchronous (on Linux) resize request fails
         check_null_ptr!(false, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        let (unscaled_width, unscaled_height) =
-            wrapper.editor.borrow().as_ref().unwrap().lock().size();
+        let editor = wrapper.editor.borrow();
+        let editor = editor.as_ref().unwrap().lock();
         let scaling_factor = wrapper.editor_scaling_factor.load(Ordering::Relaxed);
+        let (unscaled_width, unscaled_height) = editor.size();
         let (editor_width, editor_height) = (
             (unscaled_width as f32 * scaling_factor).round() as u32,
             (unscaled_height as f32 * scaling_factor).round() as u32,
         );
+        if width == editor_width && height == editor_height {
+            return true;
+        }
 
-        width == editor_width && height == editor_height
+        match editor.size_constraints() {
+            Some(constraints) => {
+                let new_size = (
+                    (width as f32 / scaling_factor).round() as u32,
+                    (height as f32 / scaling_factor).round() as u32,
+                );
+                let (new_width, new_height) = constraints.constrain(new_size);
+                nih_debug_assert_eq!(
+                    (new_width, new_height),
+                    new_size,
+                    "The host did not call 'clap_plugin_gui::adjust_size()' before resizing"
+                );
+
+                editor.set_size(new_width, new_height)
+            }
+            None => false,
+        }
     }
 
     unsafe extern "C" fn ext_gui_set_parent(
@@ -2762,23 +4228,19 @@ impl<P: ClapPlugin> Wrapper<P> {
         // For this function we need the underlying Arc so we can pass it to the editor
         let wrapper = Arc::from_raw((*plugin).plugin_data as *const Self);
 
-        let window = &*window;
-
         let result = {
             let mut editor_handle = wrapper.editor_handle.lock();
-            if editor_handle.is_none() {
-                let api = CStr::from_ptr(window.api);
-                let parent_handle = if api == CLAP_WINDOW_API_X11 {
-                    ParentWindowHandle::X11Window(window.specific.x11 as u32)
-                } else if api == CLAP_WINDOW_API_COCOA {
-                    ParentWindowHandle::AppKitNsView(window.specific.cocoa)
-                } else if api == CLAP_WINDOW_API_WIN32 {
-                    ParentWindowHandle::Win32Hwnd(window.specific.win32)
-                } else {
-                    nih_debug_assert_failure!("Host passed an invalid API");
-                    return false;
-                };
+            if wrapper.editor_floating.load(Ordering::SeqCst) {
+                nih_debug_assert_failure!("Host tried to embed a floating editor window");
 
+                false
+            } else if editor_handle.is_some() {
+                nih_debug_assert_failure!(
+                    "Host tried to attach editor while the editor is already attached"
+                );
+
+                false
+            } else if let Some(parent_handle) = parent_window_handle(&*window) {
                 // This extension is only exposed when we have an editor
                 *editor_handle = Some(
                     wrapper
@@ -2789,17 +4251,20 @@ impl<P: ClapPlugin> Wrapper<P> {
                         .lock()
                         .spawn(parent_handle, wrapper.clone().make_gui_context()),
                 );
+                *wrapper.editor_parent.lock() = Some(EditorParent(parent_handle));
 
                 true
             } else {
-                nih_debug_assert_failure!(
-                    "Host tried to attach editor while the editor is already attached"
-                );
+                nih_debug_assert_failure!("Host passed an invalid API");
 
                 false
             }
         };
 
+        if result {
+            wrapper.notify_editor_opened(true);
+        }
+
         // Leak the Arc again since we only needed a clone to pass to the GuiContext
         let _ = Arc::into_raw(wrapper);
 
@@ -2807,26 +4272,95 @@ impl<P: ClapPlugin> Wrapper<P> {
     }
 
     unsafe extern "C" fn ext_gui_set_transient(
-        _plugin: *const clap_plugin,
-        _window: *const clap_window,
+        plugin: *const clap_plugin,
+        window: *const clap_window,
     ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, window);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
         // This is only relevant for floating windows
-        false
+        if !wrapper.editor_floating.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        match parent_window_handle(&*window) {
+            Some(parent_handle) => {
+                *wrapper.editor_transient_parent.lock() = Some(TransientParent(parent_handle));
+
+                true
+            }
+            None => false,
+        }
     }
 
-    unsafe extern "C" fn ext_gui_suggest_title(_plugin: *const clap_plugin, _title: *const c_char) {
-        // This is only relevant for floating windows
+    unsafe extern "C" fn ext_gui_suggest_title(plugin: *const clap_plugin, title: *const c_char) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data, title);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // This is only relevant for floating windows. The title is used the next time the window is
+        // opened.
+        *wrapper.editor_title.lock() = CStr::from_ptr(title).to_string_lossy().into_owned();
     }
 
-    unsafe extern "C" fn ext_gui_show(_plugin: *const clap_plugin) -> bool {
-        // TODO: Does this get used? Is this only for the free-standing window extension? (which we
-        //       don't implement) This wouldn't make any sense for embedded editors.
-        false
+    unsafe extern "C" fn ext_gui_show(plugin: *const clap_plugin) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data);
+        // For this function we need the underlying Arc so we can pass it to the editor
+        let wrapper = Arc::from_raw((*plugin).plugin_data as *const Self);
+
+        // Embedded editors are shown as part of the host's window. Floating editors are opened in
+        // their own window here, and they are closed again in `hide()`.
+        let mut opened = false;
+        let result = if wrapper.editor_floating.load(Ordering::SeqCst) {
+            let mut editor_handle = wrapper.editor_handle.lock();
+            if editor_handle.is_none() {
+                let title = wrapper.editor_title.lock().clone();
+                let transient_parent = wrapper
+                    .editor_transient_parent
+                    .lock()
+                    .as_ref()
+                    .map(|parent| parent.0);
+
+                *editor_handle = wrapper
+                    .editor
+                    .borrow()
+                    .as_ref()
+                    .unwrap()
+                    .lock()
+                    .spawn_floating(&title, transient_parent, wrapper.clone().make_gui_context());
+                opened = editor_handle.is_some();
+            }
+
+            editor_handle.is_some()
+        } else {
+            false
+        };
+
+        if opened {
+            wrapper.notify_editor_opened(true);
+        }
+
+        // Leak the Arc again since we only needed a clone to pass to the GuiContext
+        let _ = Arc::into_raw(wrapper);
+
+        result
     }
 
-    unsafe extern "C" fn ext_gui_hide(_plugin: *const clap_plugin) -> bool {
-        // TODO: Same as the above
-        false
+    unsafe extern "C" fn ext_gui_hide(plugin: *const clap_plugin) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // Dropping the handle closes the floating window, it will be reopened in `show()`
+        if wrapper.editor_floating.load(Ordering::SeqCst) {
+            let editor_handle = wrapper.editor_handle.lock().take();
+            if editor_handle.is_some() {
+                drop(editor_handle);
+                wrapper.notify_editor_opened(false);
+            }
+
+            true
+        } else {
+            false
+        }
     }
 
     unsafe extern "C" fn ext_latency_get(plugin: *const clap_plugin) -> u32 {
@@ -2838,8 +4372,8 @@ impl<P: ClapPlugin> Wrapper<P> {
 
     unsafe extern "C" fn ext_note_ports_count(_plugin: *const clap_plugin, is_input: bool) -> u32 {
         match is_input {
-            true if P::MIDI_INPUT >= MidiConfig::Basic => 1,
-            false if P::MIDI_OUTPUT >= MidiConfig::Basic => 1,
+            true if P::MIDI_INPUT >= MidiConfig::Basic => P::MIDI_INPUT_PORTS.len() as u32,
+            false if P::MIDI_OUTPUT >= MidiConfig::Basic => P::MIDI_OUTPUT_PORTS.len() as u32,
             _ => 0,
         }
     }
@@ -2850,35 +4384,42 @@ impl<P: ClapPlugin> Wrapper<P> {
         is_input: bool,
         info: *mut clap_note_port_info,
     ) -> bool {
-        match (index, is_input) {
-            (0, true) if P::MIDI_INPUT >= MidiConfig::Basic => {
-                *info = std::mem::zeroed();
+        check_null_ptr!(false, info);
 
-                let info = &mut *info;
-                info.id = 0;
-                // NOTE: REAPER won't send us SysEx if we don't support the MIDI dialect
-                // TODO: Implement MPE (would just be a toggle for the plugin to expose it) and MIDI2
-                info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
-                info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
-                strlcpy(&mut info.name, "Note Input");
-
-                true
+        let port = match is_input {
+            true if P::MIDI_INPUT >= MidiConfig::Basic => P::MIDI_INPUT_PORTS.get(index as usize),
+            false if P::MIDI_OUTPUT >= MidiConfig::Basic => {
+                P::MIDI_OUTPUT_PORTS.get(index as usize)
             }
-            (0, false) if P::MIDI_OUTPUT >= MidiConfig::Basic => {
+            _ => None,
+        };
+
+        match port {
+            Some(port) => {
                 *info = std::mem::zeroed();
 
                 let info = &mut *info;
-                info.id = 0;
+                info.id = index;
+                // NOTE: REAPER won't send us SysEx if we don't support the MIDI dialect
                 // If `P::MIDI_OUTPUT < MidiConfig::MidiCCs` we'll throw away MIDI CCs, pitch bend
                 // messages, and other messages that are not basic note on, off and polyphonic
                 // pressure messages. This way the behavior is the same as the VST3 wrapper.
-                info.supported_dialects = CLAP_NOTE_DIALECT_CLAP | CLAP_NOTE_DIALECT_MIDI;
-                info.preferred_dialect = CLAP_NOTE_DIALECT_CLAP;
-                strlcpy(&mut info.name, "Note Output");
+                let (supported_dialects, preferred_dialect) = clap_note_dialects(port, is_input);
+                info.supported_dialects = supported_dialects;
+                info.preferred_dialect = preferred_dialect;
+                strlcpy(&mut info.name, port.name);
 
                 true
             }
-            _ => false,
+            None => {
+                nih_debug_assert_failure!(
+                    "Host tried to query out of bounds note port {} (input: {})",
+                    index,
+                    is_input
+                );
+
+                false
+            }
         }
     }
 
@@ -2905,11 +4446,14 @@ impl<P: ClapPlugin> Wrapper<P> {
         let param_group = &wrapper.param_group_by_hash[param_hash];
         let param_ptr = &wrapper.param_by_hash[param_hash];
         let default_value = param_ptr.default_normalized_value();
-        let step_count = param_ptr.step_count();
+        let step_count = param_ptr.host_step_count();
         let flags = param_ptr.flags();
-        let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
+        let read_only = flags.contains(ParamFlags::READ_ONLY);
+        let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE) && !read_only;
         let hidden = flags.contains(ParamFlags::HIDDEN);
         let is_bypass = flags.contains(ParamFlags::BYPASS);
+        // Enums are shown as a list of their variants in the host's automation lanes
+        let is_enum = matches!(param_ptr, ParamPtr::EnumParam(_));
 
         *param_info = std::mem::zeroed();
 
@@ -2928,14 +4472,20 @@ impl<P: ClapPlugin> Wrapper<P> {
         if hidden {
             param_info.flags |= CLAP_PARAM_IS_HIDDEN | CLAP_PARAM_IS_READONLY;
         }
+        if read_only {
+            param_info.flags |= CLAP_PARAM_IS_READONLY;
+        }
         if is_bypass {
             param_info.flags |= CLAP_PARAM_IS_BYPASS
         }
         if step_count.is_some() {
             param_info.flags |= CLAP_PARAM_IS_STEPPED
         }
+        if is_enum {
+            param_info.flags |= CLAP_PARAM_IS_ENUM
+        }
         param_info.cookie = std::ptr::null_mut();
-        strlcpy(&mut param_info.name, param_ptr.name());
+        strlcpy(&mut param_info.name, &param_ptr.display_name());
         strlcpy(&mut param_info.module, param_group);
         // We don't use the actual minimum and maximum values here because that would not scale
         // with skewed integer ranges. Instead, just treat all parameters as `[0, 1]` normalized
@@ -2943,9 +4493,8 @@ impl<P: ClapPlugin> Wrapper<P> {
         param_info.min_value = 0.0;
         // Stepped parameters are unnormalized float parameters since there's no separate step
         // range option
-        // TODO: This should probably be encapsulated in some way so we don't forget about this in one place
         param_info.max_value = step_count.unwrap_or(1) as f64;
-        param_info.default_value = default_value as f64 * step_count.unwrap_or(1) as f64;
+        param_info.default_value = normalized_to_clap_value(param_ptr, default_value);
 
         true
     }
@@ -2960,8 +4509,8 @@ impl<P: ClapPlugin> Wrapper<P> {
 
         match wrapper.param_by_hash.get(&param_id) {
             Some(param_ptr) => {
-                *value = param_ptr.modulated_normalized_value() as f64
-                    * param_ptr.step_count().unwrap_or(1) as f64;
+                *value =
+                    normalized_to_clap_value(param_ptr, param_ptr.modulated_normalized_value());
 
                 true
             }
@@ -2987,7 +4536,7 @@ impl<P: ClapPlugin> Wrapper<P> {
                     dest,
                     // CLAP does not have a separate unit, so we'll include the unit here
                     &param_ptr.normalized_value_to_string(
-                        value as f32 / param_ptr.step_count().unwrap_or(1) as f32,
+                        clap_value_to_normalized(param_ptr, value),
                         true,
                     ),
                 );
@@ -3015,10 +4564,10 @@ impl<P: ClapPlugin> Wrapper<P> {
         match wrapper.param_by_hash.get(&param_id) {
             Some(param_ptr) => {
                 let normalized_value = match param_ptr.string_to_normalized_value(display) {
-                    Some(v) => v as f64,
+                    Some(v) => v,
                     None => return false,
                 };
-                *value = normalized_value * param_ptr.step_count().unwrap_or(1) as f64;
+                *value = normalized_to_clap_value(param_ptr, normalized_value);
 
                 true
             }
@@ -3043,6 +4592,79 @@ impl<P: ClapPlugin> Wrapper<P> {
         }
     }
 
+    unsafe extern "C" fn ext_param_indication_set_mapping(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        has_mapping: bool,
+        color: *const clap_color,
+        label: *const c_char,
+        description: *const c_char,
+    ) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if !wrapper.param_by_hash.contains_key(&param_id) {
+            nih_debug_assert_failure!("Unknown parameter ID {} in set_mapping()", param_id);
+            return;
+        }
+
+        let nullable_string = |ptr: *const c_char| {
+            if ptr.is_null() {
+                None
+            } else {
+                Some(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+            }
+        };
+
+        let mut param_indications = wrapper.param_indications.lock();
+        let indication = param_indications.entry(param_id).or_default();
+        indication.mapping = if has_mapping {
+            Some(ParamMappingIndication {
+                color: color
+                    .as_ref()
+                    .map(|color| [color.red, color.green, color.blue, color.alpha]),
+                label: nullable_string(label),
+                description: nullable_string(description),
+            })
+        } else {
+            None
+        };
+    }
+
+    unsafe extern "C" fn ext_param_indication_set_automation(
+        plugin: *const clap_plugin,
+        param_id: clap_id,
+        automation_state: u32,
+        _color: *const clap_color,
+    ) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        if !wrapper.param_by_hash.contains_key(&param_id) {
+            nih_debug_assert_failure!("Unknown parameter ID {} in set_automation()", param_id);
+            return;
+        }
+
+        let automation = match automation_state {
+            CLAP_PARAM_INDICATION_AUTOMATION_NONE => ParamAutomationState::None,
+            CLAP_PARAM_INDICATION_AUTOMATION_PRESENT => ParamAutomationState::Present,
+            CLAP_PARAM_INDICATION_AUTOMATION_PLAYING => ParamAutomationState::Playing,
+            CLAP_PARAM_INDICATION_AUTOMATION_RECORDING => ParamAutomationState::Recording,
+            CLAP_PARAM_INDICATION_AUTOMATION_OVERRIDING => ParamAutomationState::Overriding,
+            n => {
+                nih_debug_assert_failure!("Unknown automation state {}", n);
+                return;
+            }
+        };
+
+        wrapper
+            .param_indications
+            .lock()
+            .entry(param_id)
+            .or_default()
+            .automation = automation;
+    }
+
     unsafe extern "C" fn ext_remote_controls_count(plugin: *const clap_plugin) -> u32 {
         check_null_ptr!(0, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
@@ -3090,7 +4712,10 @@ impl<P: ClapPlugin> Wrapper<P> {
                 ProcessMode::Realtime
             }
         };
-        wrapper.current_process_mode.store(mode);
+        let old_mode = wrapper.current_process_mode.swap(mode);
+        if old_mode != mode {
+            wrapper.plugin.lock().process_mode_changed(mode);
+        }
 
         true
     }
@@ -3102,37 +4727,7 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        let serialized = state::serialize_json::<P>(
-            wrapper.params.clone(),
-            state::make_params_iter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
-        );
-        match serialized {
-            Ok(serialized) => {
-                // CLAP does not provide a way to tell how much data there is left in a stream, so
-                // we need to prepend it to our actual state data.
-                let length_bytes = (serialized.len() as u64).to_le_bytes();
-                if !write_stream(&*stream, &length_bytes) {
-                    nih_debug_assert_failure!(
-                        "Error or end of stream while writing the state length to the stream."
-                    );
-                    return false;
-                }
-                if !write_stream(&*stream, &serialized) {
-                    nih_debug_assert_failure!(
-                        "Error or end of stream while writing the state buffer to the stream."
-                    );
-                    return false;
-                }
-
-                nih_trace!("Saved state ({} bytes)", serialized.len());
-
-                true
-            }
-            Err(err) => {
-                nih_debug_assert_failure!("Could not save state: {:#}", err);
-                false
-            }
-        }
+        wrapper.save_state(&*stream, StateSaveContext::Project)
     }
 
     unsafe extern "C" fn ext_state_load(
@@ -3142,50 +4737,80 @@ impl<P: ClapPlugin> Wrapper<P> {
         check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        // CLAP does not have a way to tell how much data there is left in a stream, so we've
-        // prepended the size in front of our JSON state
-        let mut length_bytes = [0u8; 8];
-        if !read_stream(&*stream, length_bytes.as_mut_slice()) {
-            nih_debug_assert_failure!(
-                "Error or end of stream while reading the state length from the stream."
-            );
-            return false;
-        }
-        let length = u64::from_le_bytes(length_bytes);
-
-        let mut read_buffer: Vec<u8> = Vec::with_capacity(length as usize);
-        if !read_stream(&*stream, read_buffer.spare_capacity_mut()) {
-            nih_debug_assert_failure!(
-                "Error or end of stream while reading the state buffer from the stream."
-            );
-            return false;
-        }
-        read_buffer.set_len(length as usize);
+        wrapper.load_state(&*stream)
+    }
 
-        match state::deserialize_json(&read_buffer) {
-            Some(mut state) => {
-                let success = wrapper.set_state_inner(&mut state);
-                if success {
-                    nih_trace!("Loaded state ({} bytes)", read_buffer.len());
-                }
+    unsafe extern "C" fn ext_state_context_save(
+        plugin: *const clap_plugin,
+        stream: *const clap_ostream,
+        context_type: clap_plugin_state_context_type,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
 
-                success
+        let context = match context_type {
+            CLAP_STATE_CONTEXT_FOR_PRESET => StateSaveContext::Preset,
+            CLAP_STATE_CONTEXT_FOR_DUPLICATE => StateSaveContext::Duplicate,
+            CLAP_STATE_CONTEXT_FOR_PROJECT => StateSaveContext::Project,
+            n => {
+                nih_debug_assert_failure!("Unknown state context type '{}'", n);
+                StateSaveContext::Project
             }
-            None => false,
-        }
+        };
+
+        wrapper.save_state(&*stream, context)
+    }
+
+    unsafe extern "C" fn ext_state_context_load(
+        plugin: *const clap_plugin,
+        stream: *const clap_istream,
+        _context_type: clap_plugin_state_context_type,
+    ) -> bool {
+        check_null_ptr!(false, plugin, (*plugin).plugin_data, stream);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // Fields that were left out of the saved state keep their current values, so the context
+        // doesn't matter when loading
+        wrapper.load_state(&*stream)
     }
 
     unsafe extern "C" fn ext_tail_get(plugin: *const clap_plugin) -> u32 {
         check_null_ptr!(0, plugin, (*plugin).plugin_data);
         let wrapper = &*((*plugin).plugin_data as *const Self);
 
-        match wrapper.last_process_status.load() {
-            ProcessStatus::Tail(samples) => samples,
-            ProcessStatus::KeepAlive => u32::MAX,
-            _ => 0,
+        reported_tail_samples(
+            wrapper.last_process_status.load(),
+            wrapper.current_tail.load(Ordering::SeqCst),
+        )
+    }
+
+    unsafe extern "C" fn ext_thread_pool_exec(plugin: *const clap_plugin, task_index: u32) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        // The host's worker threads may call this concurrently, hence the shared borrow
+        match &*wrapper.thread_pool_task.borrow() {
+            Some(ThreadPoolTask(task)) => (**task)(task_index as usize),
+            None => nih_debug_assert_failure!(
+                "The host called the thread pool's exec function outside of request_exec()"
+            ),
         }
     }
 
+    unsafe extern "C" fn ext_track_info_changed(plugin: *const clap_plugin) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper.update_track_info();
+    }
+
+    unsafe extern "C" fn ext_tuning_changed(plugin: *const clap_plugin) {
+        check_null_ptr!((), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        wrapper.update_default_tuning_id();
+    }
+
     unsafe extern "C" fn ext_voice_info_get(
         plugin: *const clap_plugin,
         info: *mut clap_voice_info,
@@ -3210,8 +4835,62 @@ impl<P: ClapPlugin> Wrapper<P> {
             None => false,
         }
     }
+
+    #[cfg(feature = "ara")]
+    unsafe extern "C" fn ext_ara_get_factory(_plugin: *const clap_plugin) -> *const c_void {
+        crate::wrapper::ara::ara_factory_ptr::<P>()
+    }
+
+    #[cfg(feature = "ara")]
+    unsafe extern "C" fn ext_ara_bind_to_document_controller(
+        plugin: *const clap_plugin,
+        document_controller_ref: *mut c_void,
+        known_roles: u32,
+        assigned_roles: u32,
+    ) -> *const c_void {
+        check_null_ptr!(std::ptr::null(), plugin, (*plugin).plugin_data);
+        let wrapper = &*((*plugin).plugin_data as *const Self);
+
+        crate::wrapper::ara::bind_to_document_controller(
+            &wrapper.plugin,
+            &wrapper.ara_plugin_extension,
+            document_controller_ref,
+            known_roles,
+            assigned_roles,
+        )
+    }
+}
+
+/// A host window passed to `clap_plugin_gui::set_transient()`. The handle is only used on the main
+/// thread, but it needs to be stored on the wrapper.
+struct TransientParent(ParentWindowHandle);
+
+// SAFETY: The window handle is only dereferenced by the editor on the main thread
+unsafe impl Send for TransientParent {}
+
+/// Convert a CLAP window to a [`ParentWindowHandle`]. Returns `None` if the window uses an API that
+/// can't be represented as a parent window handle, like Wayland.
+unsafe fn parent_window_handle(window: &clap_window) -> Option<ParentWindowHandle> {
+    let api = CStr::from_ptr(window.api);
+    if api == CLAP_WINDOW_API_X11 {
+        Some(ParentWindowHandle::X11Window(window.specific.x11 as u32))
+    } else if api == CLAP_WINDOW_API_COCOA {
+        Some(ParentWindowHandle::AppKitNsView(window.specific.cocoa))
+    } else if api == CLAP_WINDOW_API_WIN32 {
+        Some(ParentWindowHandle::Win32Hwnd(window.specific.win32))
+    } else {
+        None
+    }
 }
 
+/// A pointer to the task passed to [`Wrapper::run_tasks_parallel()`]. The lifetime is erased, see
+/// that function for more information.
+struct ThreadPoolTask(*const (dyn Fn(usize) + Sync));
+
+// SAFETY: The task is `Sync`, and it is only accessed while `run_tasks_parallel()` is blocking
+unsafe impl Send for ThreadPoolTask {}
+unsafe impl Sync for ThreadPoolTask {}
+
 /// Convenience function to query an extension from the host.
 ///
 /// # Safety
@@ -3229,3 +4908,36 @@ unsafe fn query_host_extension<T>(
         None
     }
 }
+
+/// Get the CLAP `(supported_dialects, preferred_dialect)` for a note port. MIDI 2.0 is only
+/// supported for input ports, and the preferred dialect is the first supported dialect in the
+/// order CLAP, MIDI, MPE, MIDI 2.0.
+fn clap_note_dialects(port: &NotePort, is_input: bool) -> (u32, u32) {
+    let mut dialects = port.dialects;
+    if !is_input {
+        nih_debug_assert!(
+            dialects.intersects(NoteDialects::CLAP | NoteDialects::MIDI),
+            "Note output port '{}' should support the CLAP or MIDI dialects",
+            port.name
+        );
+        dialects.remove(NoteDialects::MIDI2);
+    }
+
+    let clap_dialects = [
+        (NoteDialects::CLAP, CLAP_NOTE_DIALECT_CLAP),
+        (NoteDialects::MIDI, CLAP_NOTE_DIALECT_MIDI),
+        (NoteDialects::MIDI_MPE, CLAP_NOTE_DIALECT_MIDI_MPE),
+        (NoteDialects::MIDI2, CLAP_NOTE_DIALECT_MIDI2),
+    ];
+    let supported_dialects = clap_dialects
+        .iter()
+        .filter(|(dialect, _)| dialects.contains(*dialect))
+        .fold(0, |acc, (_, clap_dialect)| acc | clap_dialect);
+    let preferred_dialect = clap_dialects
+        .iter()
+        .find(|(dialect, _)| dialects.contains(*dialect))
+        .map(|(_, clap_dialect)| *clap_dialect)
+        .unwrap_or(CLAP_NOTE_DIALECT_CLAP);
+
+    (supported_dialects, preferred_dialect)
+}
@@ -0,0 +1,113 @@
+//! An LV2 wrapper. Plugins are exported with [`nih_export_lv2!()`][crate::nih_export_lv2!()], and
+//! the bundler generates the bundle's Turtle files by calling into the plugin library.
+//!
+//! The wrapper has a couple limitations compared to the other wrappers:
+//!
+//! - Only the first audio IO layout's main input and output ports are exposed. Auxiliary ports are
+//!   not supported.
+//! - Parameters are exposed as control ports, so automation is only applied once per run call.
+//! - The UI requires the host to support the `instance-access` feature. Parameter changes made in
+//!   the editor are sent to the host through the UI's write function, and LV2 does not have a way
+//!   to report the start and end of a parameter gesture.
+
+mod context;
+mod descriptor;
+mod ports;
+mod sys;
+mod ttl;
+mod ui;
+mod wrapper;
+
+/// Re-export for the macro
+pub use self::descriptor::{write_ttl, PluginDescriptor};
+pub use self::sys::{LV2UI_Descriptor, LV2_Descriptor};
+
+/// Export one or more LV2 plugins from this library using the provided plugin types. Every plugin
+/// type needs to implement [`Lv2Plugin`][crate::prelude::Lv2Plugin] in addition to
+/// [`Plugin`][crate::prelude::Plugin].
+#[macro_export]
+macro_rules! nih_export_lv2 {
+    ($($plugin_ty:ty),+) => {
+        // See the comment in `nih_export_clap!()` for why the implementation is generated inside
+        // of this macro
+        #[doc(hidden)]
+        mod lv2 {
+            use $crate::prelude::nih_debug_assert_eq;
+            use $crate::wrapper::lv2::{LV2UI_Descriptor, LV2_Descriptor, PluginDescriptor};
+            use ::std::collections::HashSet;
+            use ::std::os::raw::c_char;
+            use ::std::sync::OnceLock;
+
+            // Because the `$plugin_ty`s are likely defined in the enclosing scope. This works even
+            // if the types are not public because this is a child module.
+            use super::*;
+
+            // Sneaky way to get the number of expanded elements
+            const PLUGIN_COUNT: usize = [$(stringify!($plugin_ty)),+].len();
+
+            // This is a type erased version of the information stored on the plugin types
+            static PLUGIN_DESCRIPTORS: OnceLock<[PluginDescriptor; PLUGIN_COUNT]> = OnceLock::new();
+
+            pub fn plugin_descriptors() -> &'static [PluginDescriptor; PLUGIN_COUNT] {
+                PLUGIN_DESCRIPTORS.get_or_init(|| {
+                    let descriptors = [$(PluginDescriptor::for_plugin::<$plugin_ty>()),+];
+
+                    if cfg!(debug_assertions) {
+                        let unique_plugin_uris: HashSet<_> = descriptors.iter().map(|d| d.uri()).collect();
+                        nih_debug_assert_eq!(
+                            unique_plugin_uris.len(),
+                            descriptors.len(),
+                            "Duplicate plugin URIs found in `nih_export_lv2!()` call"
+                        );
+                    }
+
+                    descriptors
+                })
+            }
+
+            pub extern "C" fn lv2_descriptor(index: u32) -> *const LV2_Descriptor {
+                match plugin_descriptors().get(index as usize) {
+                    Some(descriptor) => descriptor.lv2_descriptor(),
+                    None => ::std::ptr::null()
+                }
+            }
+
+            pub extern "C" fn lv2ui_descriptor(index: u32) -> *const LV2UI_Descriptor {
+                match plugin_descriptors().get(index as usize) {
+                    Some(descriptor) => descriptor.lv2ui_descriptor(),
+                    None => ::std::ptr::null()
+                }
+            }
+
+            pub unsafe extern "C" fn write_ttl(
+                bundle_path: *const c_char,
+                binary_name: *const c_char,
+            ) -> bool {
+                $crate::wrapper::setup_logger();
+                $crate::wrapper::lv2::write_ttl(plugin_descriptors(), bundle_path, binary_name)
+            }
+        }
+
+        /// The LV2 plugin's entry point.
+        #[no_mangle]
+        pub extern "C" fn lv2_descriptor(index: u32) -> *const $crate::wrapper::lv2::LV2_Descriptor {
+            self::lv2::lv2_descriptor(index)
+        }
+
+        /// The LV2 UI's entry point.
+        #[no_mangle]
+        pub extern "C" fn lv2ui_descriptor(index: u32) -> *const $crate::wrapper::lv2::LV2UI_Descriptor {
+            self::lv2::lv2ui_descriptor(index)
+        }
+
+        /// Used by the bundler to write the LV2 bundle's `manifest.ttl` and plugin description
+        /// files. `binary_name` is the library's file name inside of the bundle.
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_lv2_write_ttl(
+            bundle_path: *const ::std::os::raw::c_char,
+            binary_name: *const ::std::os::raw::c_char,
+        ) -> bool {
+            self::lv2::write_ttl(bundle_path, binary_name)
+        }
+    };
+}
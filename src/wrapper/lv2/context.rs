@@ -0,0 +1,286 @@
+use atomic_refcell::AtomicRefMut;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::wrapper::{Task, Wrapper};
+use crate::context::gui::{GuiMessage, ParamChangeReceiver, StateSlot, TimerHandle};
+use crate::prelude::{
+    GuiContext, InitContext, Lv2Plugin, MidiConfig, NoteEvent, ParamIndication, ParamPtr,
+    PluginApi, PluginNoteEvent, ProcessContext, ProcessMode, TrackInfo, Transport,
+};
+use crate::wrapper::util::clipboard::Clipboard;
+use crate::wrapper::util::equal_temperament_frequency;
+
+/// An [`InitContext`] implementation for the wrapper.
+pub(crate) struct WrapperInitContext<'a, P: Lv2Plugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+}
+
+/// A [`ProcessContext`] implementation for the wrapper. This is a separate object so it can hold on
+/// to lock guards for event queues. Otherwise reading these events would require constant
+/// unnecessary atomic operations to lock the uncontested locks.
+pub(crate) struct WrapperProcessContext<'a, P: Lv2Plugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+    pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) output_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) transport: Transport,
+    /// The number of auxiliary inputs in the current audio IO layout. Used for
+    /// [`ProcessContext::bus_active()`].
+    pub(super) aux_input_count: usize,
+}
+
+/// A [`GuiContext`] implementation for the wrapper. This is passed to the plugin in
+/// [`Editor::spawn()`][crate::prelude::Editor::spawn()] so it can interact with the rest of the plugin and
+/// with the host for things like setting parameters.
+pub(crate) struct WrapperGuiContext<P: Lv2Plugin> {
+    pub(super) wrapper: Arc<Wrapper<P>>,
+    /// The system clipboard, kept alive for as long as the editor exists.
+    pub(super) clipboard: Clipboard,
+    #[cfg(debug_assertions)]
+    pub(super) param_gesture_checker:
+        atomic_refcell::AtomicRefCell<crate::wrapper::util::context_checks::ParamGestureChecker>,
+}
+
+impl<P: Lv2Plugin> InitContext<P> for WrapperInitContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Lv2
+    }
+
+    fn execute(&self, task: P::BackgroundTask) {
+        (self.wrapper.task_executor.lock())(task);
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+}
+
+impl<P: Lv2Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Lv2
+    }
+
+    fn execute_background(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn execute_gui(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_gui(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.wrapper.cancel_background_tasks(tag);
+    }
+
+    #[inline]
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn process_mode(&self) -> ProcessMode {
+        // LV2 only has the optional `lv2:isLive` feature, which doesn't tell us anything about
+        // offline rendering
+        ProcessMode::Realtime
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        // LV2 doesn't have a way to query this
+        None
+    }
+
+    fn bypassed(&self) -> bool {
+        self.wrapper.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.aux_input_count
+    }
+
+    fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
+        self.input_events_guard.pop_front()
+    }
+
+    fn send_event(&mut self, event: PluginNoteEvent<P>) {
+        // The notify port only exists when the plugin outputs MIDI
+        if P::MIDI_OUTPUT != MidiConfig::None {
+            self.output_events_guard.push_back(event);
+        }
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
+        // This is only supported by CLAP
+        equal_temperament_frequency(note)
+    }
+
+    fn set_tail_seconds(&self, _seconds: f32) {
+        // LV2 plugins cannot report their tail length to the host
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T)) {
+        self.wrapper.gui_messages.drain(handler)
+    }
+}
+
+impl<P: Lv2Plugin> GuiContext for WrapperGuiContext<P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Lv2
+    }
+
+    fn request_resize(&self) -> bool {
+        self.wrapper.request_resize()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.wrapper.set_ui_scale(scale)
+    }
+
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool {
+        self.wrapper.queue_gui_note_event(event)
+    }
+
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool {
+        self.wrapper.gui_messages.send(message)
+    }
+
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+        self.wrapper.param_changes.subscribe()
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn dsp_load(&self) -> f32 {
+        self.wrapper.dsp_load.load()
+    }
+
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle {
+        self.wrapper.schedule_timer_once(delay, callback)
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        self.wrapper.schedule_timer_repeating(interval, callback)
+    }
+
+    // LV2 does not have a way to report parameter gestures to the host, so only the parameter
+    // changes themselves are sent through the UI's write function
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .begin_set_parameter(param_id),
+            None => nih_debug_assert_failure!(
+                "raw_begin_set_parameter() called with an unknown ParamPtr"
+            ),
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = param;
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        // The parameter's value is only changed when the host writes the new value to the
+        // parameter's control port. Hidden parameters don't have a port.
+        let success = self.wrapper.queue_ui_port_write(param, normalized);
+        nih_debug_assert!(
+            success,
+            "The parameter has no control port or the UI's port write queue is full, parameter \
+             change will not be sent to the host"
+        );
+
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .set_parameter(param_id),
+            None => {
+                nih_debug_assert_failure!("raw_set_parameter() called with an unknown ParamPtr")
+            }
+        }
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .end_set_parameter(param_id),
+            None => {
+                nih_debug_assert_failure!("raw_end_set_parameter() called with an unknown ParamPtr")
+            }
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = param;
+    }
+
+    fn get_state(&self) -> crate::wrapper::state::PluginState {
+        self.wrapper.get_state_object()
+    }
+
+    fn set_state(&self, state: crate::wrapper::state::PluginState) {
+        self.wrapper.set_state_object_from_gui(state)
+    }
+
+    fn store_state_slot(&self, slot: StateSlot) {
+        self.wrapper.state_slots.store(self, slot)
+    }
+
+    fn swap_ab(&self) -> StateSlot {
+        self.wrapper.state_slots.swap(self)
+    }
+
+    fn copy_a_to_b(&self) {
+        self.wrapper.state_slots.copy_a_to_b(self)
+    }
+
+    fn active_state_slot(&self) -> StateSlot {
+        self.wrapper.state_slots.active()
+    }
+
+    fn show_param_context_menu(&self, _param: ParamPtr, _position: (f32, f32)) -> bool {
+        // LV2 does not have a context menu extension
+        false
+    }
+
+    fn request_keyboard_focus(&self) -> bool {
+        // LV2 UIs receive keyboard input directly from the OS and there is no extension for
+        // negotiating keyboard focus with the host
+        false
+    }
+
+    fn release_keyboard_focus(&self) {}
+
+    fn set_clipboard_text(&self, text: &str) -> bool {
+        self.clipboard.set_text(text)
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.clipboard.text()
+    }
+
+    fn param_indication(&self, _param: ParamPtr) -> Option<ParamIndication> {
+        None
+    }
+}
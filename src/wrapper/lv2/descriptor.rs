@@ -0,0 +1,111 @@
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::path::Path;
+
+use super::sys::{LV2UI_Descriptor, LV2_Descriptor};
+use super::ttl::{self, PluginTtl};
+use super::ui::Ui;
+use super::wrapper::Wrapper;
+use crate::prelude::Lv2Plugin;
+use crate::wrapper::util::check_null_ptr;
+
+/// A static descriptor for a plugin and its UI. Hosts look up plugins and UIs by their URIs, so
+/// every plugin has an `LV2_Descriptor` and an `LV2UI_Descriptor`, even though the UI is only
+/// declared in the plugin's Turtle description if the plugin has an editor.
+///
+/// This cannot be cloned as the descriptors contain pointers to [`Self::uri`] and
+/// [`Self::ui_uri`].
+pub struct PluginDescriptor {
+    uri: CString,
+    ui_uri: CString,
+
+    /// These are initialized after the `CString` fields above since they contain pointers to
+    /// those strings.
+    lv2_descriptor: Option<LV2_Descriptor>,
+    lv2ui_descriptor: Option<LV2UI_Descriptor>,
+    /// Generates the plugin's Turtle description for a library with a certain file name.
+    ttl: fn(&str) -> PluginTtl,
+}
+
+unsafe impl Send for PluginDescriptor {}
+unsafe impl Sync for PluginDescriptor {}
+
+impl PluginDescriptor {
+    /// Construct the plugin descriptor for a specific LV2 plugin.
+    pub fn for_plugin<P: Lv2Plugin>() -> Self {
+        let mut descriptor = Self {
+            uri: CString::new(P::LV2_URI).expect("`LV2_URI` contained null bytes"),
+            ui_uri: CString::new(ttl::ui_uri::<P>()).expect("`LV2_URI` contained null bytes"),
+
+            lv2_descriptor: None,
+            lv2ui_descriptor: None,
+            ttl: PluginTtl::for_plugin::<P>,
+        };
+
+        descriptor.lv2_descriptor = Some(LV2_Descriptor {
+            URI: descriptor.uri.as_ptr(),
+            instantiate: Some(Wrapper::<P>::instantiate),
+            connect_port: Some(Wrapper::<P>::connect_port),
+            activate: Some(Wrapper::<P>::activate),
+            run: Some(Wrapper::<P>::run_plugin),
+            deactivate: Some(Wrapper::<P>::deactivate),
+            cleanup: Some(Wrapper::<P>::cleanup),
+            extension_data: Some(Wrapper::<P>::extension_data),
+        });
+        descriptor.lv2ui_descriptor = Some(LV2UI_Descriptor {
+            URI: descriptor.ui_uri.as_ptr(),
+            instantiate: Some(Ui::<P>::instantiate),
+            cleanup: Some(Ui::<P>::cleanup),
+            port_event: Some(Ui::<P>::port_event),
+            extension_data: Some(Ui::<P>::extension_data),
+        });
+
+        descriptor
+    }
+
+    pub fn uri(&self) -> &CStr {
+        self.uri.as_c_str()
+    }
+
+    pub fn lv2_descriptor(&self) -> &LV2_Descriptor {
+        self.lv2_descriptor.as_ref().unwrap()
+    }
+
+    pub fn lv2ui_descriptor(&self) -> &LV2UI_Descriptor {
+        self.lv2ui_descriptor.as_ref().unwrap()
+    }
+}
+
+/// Write the bundle's `manifest.ttl` file and the Turtle descriptions for `descriptors` to
+/// `bundle_path`. `binary_name` is the file name of the plugin's library inside of the bundle.
+/// Returns `false` if the files could not be written.
+///
+/// # Safety
+///
+/// `bundle_path` and `binary_name` must be valid null terminated strings.
+pub unsafe fn write_ttl(
+    descriptors: &[PluginDescriptor],
+    bundle_path: *const c_char,
+    binary_name: *const c_char,
+) -> bool {
+    check_null_ptr!(false, bundle_path, binary_name);
+    let (Ok(bundle_path), Ok(binary_name)) = (
+        CStr::from_ptr(bundle_path).to_str(),
+        CStr::from_ptr(binary_name).to_str(),
+    ) else {
+        nih_error!("The bundle path or binary name is not valid UTF-8");
+        return false;
+    };
+
+    let plugins: Vec<PluginTtl> = descriptors
+        .iter()
+        .map(|descriptor| (descriptor.ttl)(binary_name))
+        .collect();
+    match ttl::write_bundle(Path::new(bundle_path), &plugins) {
+        Ok(()) => true,
+        Err(err) => {
+            nih_error!("Could not write the LV2 bundle's Turtle files: {}", err);
+            false
+        }
+    }
+}
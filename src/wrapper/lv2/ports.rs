@@ -0,0 +1,132 @@
+//! The plugin's LV2 port layout. This is shared between the Turtle generator and the wrapper so
+//! the port indices in the generated plugin description always match the ones the wrapper expects.
+
+use std::collections::HashSet;
+use std::num::NonZeroU32;
+use std::ops::Range;
+
+use crate::prelude::{MidiConfig, ParamFlags, ParamPtr, Plugin};
+
+/// The indices for all of the plugin's ports. Ports are laid out in the following order:
+///
+/// 1. An atom input sequence for MIDI and transport information.
+/// 2. An atom output sequence for MIDI output, if the plugin outputs MIDI.
+/// 3. A control output port for reporting latency.
+/// 4. The main audio input channels, followed by the main audio output channels.
+/// 5. One control input port for every parameter that is not hidden.
+#[derive(Debug, Clone)]
+pub struct PortLayout {
+    pub control: u32,
+    pub notify: Option<u32>,
+    pub latency: u32,
+    pub audio_inputs: Range<u32>,
+    pub audio_outputs: Range<u32>,
+    pub params: Range<u32>,
+}
+
+/// A parameter exposed as a control input port.
+#[derive(Debug, Clone)]
+pub struct ParamPort {
+    /// The parameter's ID.
+    pub id: String,
+    /// The port's symbol. This is the parameter ID converted to a valid C identifier.
+    pub symbol: String,
+    pub ptr: ParamPtr,
+}
+
+impl PortLayout {
+    /// The port layout for a plugin with `num_param_ports` parameter ports. The audio ports use the
+    /// plugin's first audio IO layout.
+    pub fn for_plugin<P: Plugin>(num_param_ports: usize) -> Self {
+        let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+        let num_inputs = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0);
+        let num_outputs = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0);
+
+        let control = 0;
+        let notify = if P::MIDI_OUTPUT != MidiConfig::None {
+            Some(control + 1)
+        } else {
+            None
+        };
+        let latency = notify.unwrap_or(control) + 1;
+        let audio_inputs = latency + 1..latency + 1 + num_inputs;
+        let audio_outputs = audio_inputs.end..audio_inputs.end + num_outputs;
+        let params = audio_outputs.end..audio_outputs.end + num_param_ports as u32;
+
+        Self {
+            control,
+            notify,
+            latency,
+            audio_inputs,
+            audio_outputs,
+            params,
+        }
+    }
+
+    /// The total number of ports.
+    pub fn num_ports(&self) -> u32 {
+        self.params.end
+    }
+}
+
+/// Get the plugin's parameters that should be exposed as control ports from a parameter map, in
+/// order. Hidden parameters don't get a port, but they are still included in the plugin's state.
+pub fn param_ports(param_map: &[(String, ParamPtr, String)]) -> Vec<ParamPort> {
+    let mut symbols = HashSet::new();
+    param_map
+        .iter()
+        .filter(|(_, ptr, _)| !unsafe { ptr.flags() }.contains(ParamFlags::HIDDEN))
+        .map(|(id, ptr, _)| {
+            // Parameter IDs may contain characters that are not allowed in port symbols, and the
+            // prefix prevents collisions with the other ports' symbols
+            let base_symbol: String = format!("param_{id}")
+                .chars()
+                .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+                .collect();
+            let mut symbol = base_symbol.clone();
+            let mut suffix = 2;
+            while !symbols.insert(symbol.clone()) {
+                symbol = format!("{base_symbol}_{suffix}");
+                suffix += 1;
+            }
+
+            ParamPort {
+                id: id.clone(),
+                symbol,
+                ptr: *ptr,
+            }
+        })
+        .collect()
+}
+
+/// The range of a parameter port's values. Continuous parameters use normalized values, and
+/// discrete parameters use their step index so hosts can show them as integer or enumeration
+/// ports.
+pub unsafe fn port_value_range(param_ptr: ParamPtr) -> (f32, f32) {
+    match param_ptr.step_count() {
+        Some(step_count) => (0.0, step_count as f32),
+        None => (0.0, 1.0),
+    }
+}
+
+/// Convert a parameter's normalized value to a control port value.
+pub unsafe fn normalized_to_port_value(param_ptr: ParamPtr, normalized: f32) -> f32 {
+    match param_ptr.step_count() {
+        Some(step_count) => (normalized * step_count as f32).round(),
+        None => normalized,
+    }
+}
+
+/// The inverse of [`normalized_to_port_value()`]. Out of range port values are clamped.
+pub unsafe fn port_value_to_normalized(param_ptr: ParamPtr, value: f32) -> f32 {
+    match param_ptr.step_count() {
+        Some(step_count) => (value.round() / step_count as f32).clamp(0.0, 1.0),
+        None => value.clamp(0.0, 1.0),
+    }
+}
@@ -0,0 +1,363 @@
+//! Hand written bindings for the parts of the LV2 C API used by the wrapper. The names match the
+//! C headers so they can be looked up in the LV2 specifications.
+
+#![allow(
+    non_camel_case_types,
+    non_snake_case,
+    non_upper_case_globals,
+    dead_code
+)]
+
+use std::ffi::CStr;
+use std::os::raw::{c_char, c_int, c_void};
+
+pub type LV2_Handle = *mut c_void;
+pub type LV2_URID = u32;
+
+#[repr(C)]
+pub struct LV2_Feature {
+    pub URI: *const c_char,
+    pub data: *mut c_void,
+}
+
+#[repr(C)]
+pub struct LV2_Descriptor {
+    pub URI: *const c_char,
+    pub instantiate: Option<
+        unsafe extern "C" fn(
+            descriptor: *const LV2_Descriptor,
+            sample_rate: f64,
+            bundle_path: *const c_char,
+            features: *const *const LV2_Feature,
+        ) -> LV2_Handle,
+    >,
+    pub connect_port:
+        Option<unsafe extern "C" fn(instance: LV2_Handle, port: u32, data_location: *mut c_void)>,
+    pub activate: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub run: Option<unsafe extern "C" fn(instance: LV2_Handle, sample_count: u32)>,
+    pub deactivate: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub cleanup: Option<unsafe extern "C" fn(instance: LV2_Handle)>,
+    pub extension_data: Option<unsafe extern "C" fn(uri: *const c_char) -> *const c_void>,
+}
+
+#[repr(C)]
+pub struct LV2_URID_Map {
+    pub handle: *mut c_void,
+    pub map: Option<unsafe extern "C" fn(handle: *mut c_void, uri: *const c_char) -> LV2_URID>,
+}
+
+#[repr(C)]
+pub struct LV2_Options_Option {
+    pub context: u32,
+    pub subject: u32,
+    pub key: LV2_URID,
+    pub size: u32,
+    pub type_: LV2_URID,
+    pub value: *const c_void,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom {
+    pub size: u32,
+    pub type_: LV2_URID,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Sequence_Body {
+    pub unit: u32,
+    pub pad: u32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Sequence {
+    pub atom: LV2_Atom,
+    pub body: LV2_Atom_Sequence_Body,
+}
+
+/// The C struct's timestamp is a union of a frame offset and a time in beats. Sequences with the
+/// beat time unit are not supported by the wrapper, so this always uses frames.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Event {
+    pub time_frames: i64,
+    pub body: LV2_Atom,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Object_Body {
+    pub id: u32,
+    pub otype: LV2_URID,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Object {
+    pub atom: LV2_Atom,
+    pub body: LV2_Atom_Object_Body,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2_Atom_Property_Body {
+    pub key: LV2_URID,
+    pub context: LV2_URID,
+    pub value: LV2_Atom,
+}
+
+pub type LV2_State_Handle = *mut c_void;
+pub type LV2_State_Status = u32;
+
+pub type LV2_State_Store_Function = Option<
+    unsafe extern "C" fn(
+        handle: LV2_State_Handle,
+        key: LV2_URID,
+        value: *const c_void,
+        size: usize,
+        type_: LV2_URID,
+        flags: u32,
+    ) -> LV2_State_Status,
+>;
+pub type LV2_State_Retrieve_Function = Option<
+    unsafe extern "C" fn(
+        handle: LV2_State_Handle,
+        key: LV2_URID,
+        size: *mut usize,
+        type_: *mut LV2_URID,
+        flags: *mut u32,
+    ) -> *const c_void,
+>;
+
+#[repr(C)]
+pub struct LV2_State_Interface {
+    pub save: Option<
+        unsafe extern "C" fn(
+            instance: LV2_Handle,
+            store: LV2_State_Store_Function,
+            handle: LV2_State_Handle,
+            flags: u32,
+            features: *const *const LV2_Feature,
+        ) -> LV2_State_Status,
+    >,
+    pub restore: Option<
+        unsafe extern "C" fn(
+            instance: LV2_Handle,
+            retrieve: LV2_State_Retrieve_Function,
+            handle: LV2_State_Handle,
+            flags: u32,
+            features: *const *const LV2_Feature,
+        ) -> LV2_State_Status,
+    >,
+}
+
+pub type LV2UI_Handle = *mut c_void;
+pub type LV2UI_Controller = *mut c_void;
+pub type LV2UI_Widget = *mut c_void;
+
+pub type LV2UI_Write_Function = Option<
+    unsafe extern "C" fn(
+        controller: LV2UI_Controller,
+        port_index: u32,
+        buffer_size: u32,
+        port_protocol: u32,
+        buffer: *const c_void,
+    ),
+>;
+
+#[repr(C)]
+pub struct LV2UI_Descriptor {
+    pub URI: *const c_char,
+    pub instantiate: Option<
+        unsafe extern "C" fn(
+            descriptor: *const LV2UI_Descriptor,
+            plugin_uri: *const c_char,
+            bundle_path: *const c_char,
+            write_function: LV2UI_Write_Function,
+            controller: LV2UI_Controller,
+            widget: *mut LV2UI_Widget,
+            features: *const *const LV2_Feature,
+        ) -> LV2UI_Handle,
+    >,
+    pub cleanup: Option<unsafe extern "C" fn(ui: LV2UI_Handle)>,
+    pub port_event: Option<
+        unsafe extern "C" fn(
+            ui: LV2UI_Handle,
+            port_index: u32,
+            buffer_size: u32,
+            format: u32,
+            buffer: *const c_void,
+        ),
+    >,
+    pub extension_data: Option<unsafe extern "C" fn(uri: *const c_char) -> *const c_void>,
+}
+
+#[repr(C)]
+pub struct LV2UI_Idle_Interface {
+    pub idle: Option<unsafe extern "C" fn(ui: LV2UI_Handle) -> c_int>,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct LV2UI_Resize {
+    pub handle: *mut c_void,
+    pub ui_resize:
+        Option<unsafe extern "C" fn(handle: *mut c_void, width: c_int, height: c_int) -> c_int>,
+}
+
+pub const LV2_STATE_SUCCESS: LV2_State_Status = 0;
+pub const LV2_STATE_ERR_UNKNOWN: LV2_State_Status = 1;
+pub const LV2_STATE_ERR_BAD_TYPE: LV2_State_Status = 2;
+pub const LV2_STATE_ERR_NO_PROPERTY: LV2_State_Status = 5;
+
+pub const LV2_STATE_IS_POD: u32 = 1 << 0;
+pub const LV2_STATE_IS_PORTABLE: u32 = 1 << 1;
+
+/// Creates a `&'static CStr` from a string literal. These are needed for comparing URIs passed by
+/// the host.
+macro_rules! uri {
+    ($uri:expr) => {
+        match CStr::from_bytes_with_nul(concat!($uri, "\0").as_bytes()) {
+            Ok(uri) => uri,
+            Err(_) => panic!("Invalid URI"),
+        }
+    };
+}
+
+pub const LV2_CORE_PREFIX: &str = "http://lv2plug.in/ns/lv2core#";
+
+pub const LV2_URID__map: &CStr = uri!("http://lv2plug.in/ns/ext/urid#map");
+pub const LV2_OPTIONS__options: &CStr = uri!("http://lv2plug.in/ns/ext/options#options");
+pub const LV2_STATE__interface: &CStr = uri!("http://lv2plug.in/ns/ext/state#interface");
+pub const LV2_INSTANCE_ACCESS_URI: &CStr = uri!("http://lv2plug.in/ns/ext/instance-access");
+pub const LV2_UI__parent: &CStr = uri!("http://lv2plug.in/ns/extensions/ui#parent");
+pub const LV2_UI__resize: &CStr = uri!("http://lv2plug.in/ns/extensions/ui#resize");
+pub const LV2_UI__idleInterface: &CStr = uri!("http://lv2plug.in/ns/extensions/ui#idleInterface");
+
+pub const LV2_BUF_SIZE__maxBlockLength: &CStr =
+    uri!("http://lv2plug.in/ns/ext/buf-size#maxBlockLength");
+pub const LV2_UI__scaleFactor: &CStr = uri!("http://lv2plug.in/ns/extensions/ui#scaleFactor");
+
+pub const LV2_ATOM__Blank: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Blank");
+pub const LV2_ATOM__Chunk: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Chunk");
+pub const LV2_ATOM__Double: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Double");
+pub const LV2_ATOM__Float: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Float");
+pub const LV2_ATOM__Int: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Int");
+pub const LV2_ATOM__Long: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Long");
+pub const LV2_ATOM__Object: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Object");
+pub const LV2_ATOM__Sequence: &CStr = uri!("http://lv2plug.in/ns/ext/atom#Sequence");
+pub const LV2_MIDI__MidiEvent: &CStr = uri!("http://lv2plug.in/ns/ext/midi#MidiEvent");
+
+pub const LV2_TIME__Position: &CStr = uri!("http://lv2plug.in/ns/ext/time#Position");
+pub const LV2_TIME__bar: &CStr = uri!("http://lv2plug.in/ns/ext/time#bar");
+pub const LV2_TIME__barBeat: &CStr = uri!("http://lv2plug.in/ns/ext/time#barBeat");
+pub const LV2_TIME__beatUnit: &CStr = uri!("http://lv2plug.in/ns/ext/time#beatUnit");
+pub const LV2_TIME__beatsPerBar: &CStr = uri!("http://lv2plug.in/ns/ext/time#beatsPerBar");
+pub const LV2_TIME__beatsPerMinute: &CStr = uri!("http://lv2plug.in/ns/ext/time#beatsPerMinute");
+pub const LV2_TIME__frame: &CStr = uri!("http://lv2plug.in/ns/ext/time#frame");
+pub const LV2_TIME__speed: &CStr = uri!("http://lv2plug.in/ns/ext/time#speed");
+
+/// Find a feature by its URI in a host provided null terminated feature array.
+///
+/// # Safety
+///
+/// `features` must either be a null pointer or a valid null terminated array of features.
+pub unsafe fn find_feature(features: *const *const LV2_Feature, uri: &CStr) -> Option<*mut c_void> {
+    if features.is_null() {
+        return None;
+    }
+
+    let mut feature_ptr = features;
+    while !(*feature_ptr).is_null() {
+        let feature = &**feature_ptr;
+        if !feature.URI.is_null() && CStr::from_ptr(feature.URI) == uri {
+            return Some(feature.data);
+        }
+
+        feature_ptr = feature_ptr.add(1);
+    }
+
+    None
+}
+
+/// Iterate over the options in a host provided `LV2_Options_Option` array, which is terminated by
+/// an option with a zero key.
+///
+/// # Safety
+///
+/// `options` must be a valid option array, and the iterator must not outlive it.
+pub unsafe fn iter_options<'a>(
+    options: *const LV2_Options_Option,
+) -> impl Iterator<Item = &'a LV2_Options_Option> {
+    let mut option_ptr = options;
+    std::iter::from_fn(move || {
+        if option_ptr.is_null() || (*option_ptr).key == 0 {
+            return None;
+        }
+
+        let option = &*option_ptr;
+        option_ptr = option_ptr.add(1);
+        Some(option)
+    })
+}
+
+/// Atoms inside of sequences and objects are padded to 64-bit boundaries.
+pub const fn atom_pad_size(size: u32) -> u32 {
+    (size + 7) & !7
+}
+
+/// Iterate over the events in a host provided atom sequence.
+///
+/// # Safety
+///
+/// `sequence` must point to a valid atom sequence, and the iterator must not outlive it.
+pub unsafe fn iter_sequence<'a>(
+    sequence: *const LV2_Atom_Sequence,
+) -> impl Iterator<Item = &'a LV2_Atom_Event> {
+    let body = &(*sequence).body as *const LV2_Atom_Sequence_Body as *const u8;
+    let end = body.add((*sequence).atom.size as usize);
+    let mut event_ptr = body.add(std::mem::size_of::<LV2_Atom_Sequence_Body>());
+    std::iter::from_fn(move || {
+        if event_ptr >= end {
+            return None;
+        }
+
+        let event = &*(event_ptr as *const LV2_Atom_Event);
+        event_ptr = event_ptr.add(atom_pad_size(
+            std::mem::size_of::<LV2_Atom_Event>() as u32 + event.body.size,
+        ) as usize);
+        Some(event)
+    })
+}
+
+/// Iterate over the properties of an atom object. The property's value directly follows the
+/// property body.
+///
+/// # Safety
+///
+/// `object` must point to a valid atom object, and the iterator must not outlive it.
+pub unsafe fn iter_object<'a>(
+    object: *const LV2_Atom_Object,
+) -> impl Iterator<Item = &'a LV2_Atom_Property_Body> {
+    let body = &(*object).body as *const LV2_Atom_Object_Body as *const u8;
+    let end = body.add((*object).atom.size as usize);
+    let mut property_ptr = body.add(std::mem::size_of::<LV2_Atom_Object_Body>());
+    std::iter::from_fn(move || {
+        if property_ptr >= end {
+            return None;
+        }
+
+        let property = &*(property_ptr as *const LV2_Atom_Property_Body);
+        property_ptr = property_ptr.add(atom_pad_size(
+            std::mem::size_of::<LV2_Atom_Property_Body>() as u32 + property.value.size,
+        ) as usize);
+        Some(property)
+    })
+}
+
+/// Get a pointer to an atom's body, which directly follows the atom's header.
+pub fn atom_body(atom: &LV2_Atom) -> *const u8 {
+    unsafe { (atom as *const LV2_Atom as *const u8).add(std::mem::size_of::<LV2_Atom>()) }
+}
@@ -0,0 +1,353 @@
+//! Generates the Turtle files describing an LV2 bundle. LV2 hosts read these files to discover the
+//! plugin and its ports without loading the plugin's library, so the bundler calls the function
+//! exported by [`nih_export_lv2!()`][crate::nih_export_lv2!()] to write them after building the
+//! plugin.
+
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+
+use super::ports::{self, PortLayout};
+use crate::prelude::{AsyncExecutor, Lv2Plugin, MidiConfig, ParamFlags};
+
+/// The number of steps up to which discrete parameters are exposed as enumerations with a label
+/// for every step.
+const MAX_SCALE_POINTS: usize = 128;
+
+const PREFIXES: &str = "@prefix atom: <http://lv2plug.in/ns/ext/atom#> .
+@prefix bufsz: <http://lv2plug.in/ns/ext/buf-size#> .
+@prefix doap: <http://usefulinc.com/ns/doap#> .
+@prefix foaf: <http://xmlns.com/foaf/0.1/> .
+@prefix lv2: <http://lv2plug.in/ns/lv2core#> .
+@prefix midi: <http://lv2plug.in/ns/ext/midi#> .
+@prefix opts: <http://lv2plug.in/ns/ext/options#> .
+@prefix pprops: <http://lv2plug.in/ns/ext/port-props#> .
+@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#> .
+@prefix rdfs: <http://www.w3.org/2000/01/rdf-schema#> .
+@prefix state: <http://lv2plug.in/ns/ext/state#> .
+@prefix time: <http://lv2plug.in/ns/ext/time#> .
+@prefix ui: <http://lv2plug.in/ns/extensions/ui#> .
+@prefix urid: <http://lv2plug.in/ns/ext/urid#> .
+";
+
+/// The Turtle descriptions for a single plugin in the bundle.
+pub struct PluginTtl {
+    /// The name of the file containing the plugin's full description.
+    file_name: String,
+    /// The plugin's entry in the bundle's `manifest.ttl` file.
+    manifest_entry: String,
+    /// The contents for the `file_name` file.
+    description: String,
+}
+
+impl PluginTtl {
+    /// Describe a plugin whose library is called `binary_name` inside of the bundle. This creates
+    /// an instance of the plugin to query its parameters and editor.
+    pub fn for_plugin<P: Lv2Plugin>(binary_name: &str) -> Self {
+        let plugin = P::default();
+        let param_map = plugin.params().param_map();
+        let param_ports = ports::param_ports(&param_map);
+        let port_layout = PortLayout::for_plugin::<P>(param_ports.len());
+
+        // The editor is only created to check whether the plugin has one, so the tasks it would
+        // schedule are simply dropped
+        let has_editor = plugin
+            .editor(AsyncExecutor {
+                execute_background: Arc::new(|_| ()),
+                execute_gui: Arc::new(|_| ()),
+                cancel_background: Arc::new(|_| ()),
+            })
+            .is_some();
+
+        let file_name = format!("{}.ttl", sanitize_file_name(P::NAME));
+        let ui_uri = ui_uri::<P>();
+
+        let mut manifest_entry = format!(
+            "<{uri}>\n    a lv2:Plugin ;\n    lv2:binary <{binary_name}> ;\n    rdfs:seeAlso \
+             <{file_name}> .\n",
+            uri = P::LV2_URI
+        );
+        if has_editor {
+            write!(
+                manifest_entry,
+                "\n<{ui_uri}>\n    a {ui_class} ;\n    ui:binary <{binary_name}> ;\n    \
+                 rdfs:seeAlso <{file_name}> .\n",
+                ui_class = UI_CLASS,
+            )
+            .unwrap();
+        }
+
+        let mut description = String::new();
+        writeln!(description, "<{}>", P::LV2_URI).unwrap();
+        write!(description, "    a lv2:Plugin").unwrap();
+        for class in P::LV2_CLASSES {
+            write!(description, " , lv2:{class}").unwrap();
+        }
+        writeln!(description, " ;").unwrap();
+        writeln!(description, "    doap:name {} ;", string_literal(P::NAME)).unwrap();
+        writeln!(description, "    doap:maintainer [").unwrap();
+        writeln!(
+            description,
+            "        foaf:name {} ;",
+            string_literal(P::VENDOR)
+        )
+        .unwrap();
+        if !P::URL.is_empty() {
+            writeln!(description, "        foaf:homepage <{}> ;", P::URL).unwrap();
+        }
+        if !P::EMAIL.is_empty() {
+            writeln!(description, "        foaf:mbox <mailto:{}> ;", P::EMAIL).unwrap();
+        }
+        writeln!(description, "    ] ;").unwrap();
+        writeln!(description, "    lv2:requiredFeature urid:map ;").unwrap();
+        writeln!(
+            description,
+            "    lv2:optionalFeature lv2:hardRTCapable , opts:options ;"
+        )
+        .unwrap();
+        writeln!(
+            description,
+            "    opts:supportedOption bufsz:maxBlockLength ;"
+        )
+        .unwrap();
+        writeln!(description, "    lv2:extensionData state:interface ;").unwrap();
+        if has_editor {
+            writeln!(description, "    ui:ui <{ui_uri}> ;").unwrap();
+        }
+
+        let mut port_descriptions = Vec::with_capacity(port_layout.num_ports() as usize);
+        let mut control_port = format!(
+            "a lv2:InputPort , atom:AtomPort ;\n        atom:bufferType atom:Sequence ;\n        \
+             atom:supports time:Position"
+        );
+        if P::MIDI_INPUT >= MidiConfig::Basic {
+            control_port.push_str(" , midi:MidiEvent");
+        }
+        write!(
+            control_port,
+            " ;\n        lv2:designation lv2:control ;\n        lv2:index {} ;\n        \
+             lv2:symbol \"control\" ;\n        lv2:name \"Control\"",
+            port_layout.control
+        )
+        .unwrap();
+        port_descriptions.push(control_port);
+        if let Some(notify) = port_layout.notify {
+            port_descriptions.push(format!(
+                "a lv2:OutputPort , atom:AtomPort ;\n        atom:bufferType atom:Sequence ;\n        \
+                 atom:supports midi:MidiEvent ;\n        lv2:index {notify} ;\n        \
+                 lv2:symbol \"notify\" ;\n        lv2:name \"Notify\""
+            ));
+        }
+        port_descriptions.push(format!(
+            "a lv2:OutputPort , lv2:ControlPort ;\n        lv2:designation lv2:latency ;\n        \
+             lv2:portProperty lv2:reportsLatency , lv2:integer , pprops:notOnGUI ;\n        \
+             lv2:index {} ;\n        lv2:symbol \"latency\" ;\n        lv2:name \"Latency\" ;\n        \
+             lv2:minimum 0 ;\n        lv2:maximum 192000",
+            port_layout.latency
+        ));
+
+        let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+        for (channel_idx, port_idx) in port_layout.audio_inputs.clone().enumerate() {
+            port_descriptions.push(format!(
+                "a lv2:InputPort , lv2:AudioPort ;\n        lv2:index {port_idx} ;\n        \
+                 lv2:symbol \"in_{}\" ;\n        lv2:name {}",
+                channel_idx + 1,
+                string_literal(&format!(
+                    "{} {}",
+                    audio_io_layout.main_input_name(),
+                    channel_idx + 1
+                ))
+            ));
+        }
+        for (channel_idx, port_idx) in port_layout.audio_outputs.clone().enumerate() {
+            port_descriptions.push(format!(
+                "a lv2:OutputPort , lv2:AudioPort ;\n        lv2:index {port_idx} ;\n        \
+                 lv2:symbol \"out_{}\" ;\n        lv2:name {}",
+                channel_idx + 1,
+                string_literal(&format!(
+                    "{} {}",
+                    audio_io_layout.main_output_name(),
+                    channel_idx + 1
+                ))
+            ));
+        }
+
+        for (param_port, port_idx) in param_ports.iter().zip(port_layout.params.clone()) {
+            let param_ptr = param_port.ptr;
+            let (minimum, maximum) = unsafe { ports::port_value_range(param_ptr) };
+            let default = unsafe {
+                ports::normalized_to_port_value(param_ptr, param_ptr.default_normalized_value())
+            };
+
+            let mut port = format!(
+                "a lv2:InputPort , lv2:ControlPort ;\n        lv2:index {port_idx} ;\n        \
+                 lv2:symbol \"{}\" ;\n        lv2:name {} ;\n        lv2:default {default:?} ;\n        \
+                 lv2:minimum {minimum:?} ;\n        lv2:maximum {maximum:?}",
+                param_port.symbol,
+                string_literal(unsafe { param_ptr.name() }),
+            );
+
+            let mut port_properties = Vec::new();
+            let step_count = unsafe { param_ptr.step_count() };
+            match step_count {
+                Some(1) => port_properties.extend(["lv2:integer", "lv2:toggled"]),
+                Some(step_count) if step_count <= MAX_SCALE_POINTS => {
+                    port_properties.extend(["lv2:integer", "lv2:enumeration"])
+                }
+                Some(_) => port_properties.push("lv2:integer"),
+                None => (),
+            }
+            let flags = unsafe { param_ptr.flags() };
+            if flags.contains(ParamFlags::NON_AUTOMATABLE) {
+                port_properties.push("pprops:notAutomatic");
+            }
+            if flags.contains(ParamFlags::HIDE_IN_GENERIC_UI) {
+                port_properties.push("pprops:notOnGUI");
+            }
+            if !port_properties.is_empty() {
+                write!(
+                    port,
+                    " ;\n        lv2:portProperty {}",
+                    port_properties.join(" , ")
+                )
+                .unwrap();
+            }
+
+            match step_count {
+                Some(step_count) if step_count > 1 && step_count <= MAX_SCALE_POINTS => {
+                    let scale_points: Vec<String> = (0..=step_count)
+                        .map(|step| {
+                            let label = unsafe {
+                                param_ptr
+                                    .normalized_value_to_string(step as f32 / step_count as f32, true)
+                            };
+                            format!(
+                                "[\n            rdfs:label {} ;\n            rdf:value {:?}\n        ]",
+                                string_literal(&label),
+                                step as f32
+                            )
+                        })
+                        .collect();
+                    write!(
+                        port,
+                        " ;\n        lv2:scalePoint {}",
+                        scale_points.join(" , ")
+                    )
+                    .unwrap();
+                }
+                _ => (),
+            }
+
+            port_descriptions.push(port);
+        }
+
+        let ports: Vec<String> = port_descriptions
+            .into_iter()
+            .map(|port| format!("[\n        {port}\n    ]"))
+            .collect();
+        writeln!(description, "    lv2:port {} .", ports.join(" , ")).unwrap();
+
+        if has_editor {
+            writeln!(description).unwrap();
+            writeln!(description, "<{ui_uri}>").unwrap();
+            writeln!(description, "    a {UI_CLASS} ;").unwrap();
+            writeln!(
+                description,
+                "    lv2:requiredFeature ui:idleInterface , ui:parent , \
+                 <http://lv2plug.in/ns/ext/instance-access> ;"
+            )
+            .unwrap();
+            writeln!(
+                description,
+                "    lv2:optionalFeature ui:resize , opts:options ;"
+            )
+            .unwrap();
+            writeln!(description, "    opts:supportedOption ui:scaleFactor ;").unwrap();
+            writeln!(description, "    lv2:extensionData ui:idleInterface .").unwrap();
+        }
+
+        Self {
+            file_name,
+            manifest_entry,
+            description,
+        }
+    }
+}
+
+/// The UI type for the current platform.
+#[cfg(target_os = "macos")]
+const UI_CLASS: &str = "ui:CocoaUI";
+#[cfg(target_os = "windows")]
+const UI_CLASS: &str = "ui:WindowsUI";
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+const UI_CLASS: &str = "ui:X11UI";
+
+/// The URI for the plugin's UI.
+pub fn ui_uri<P: Lv2Plugin>() -> String {
+    format!("{}#ui", P::LV2_URI)
+}
+
+/// Write the `manifest.ttl` file and the plugin description files for a bundle containing
+/// `plugins` to `bundle_path`.
+pub fn write_bundle(bundle_path: &Path, plugins: &[PluginTtl]) -> io::Result<()> {
+    let mut manifest = String::from(PREFIXES);
+    for plugin in plugins {
+        manifest.push('\n');
+        manifest.push_str(&plugin.manifest_entry);
+
+        fs::write(
+            bundle_path.join(&plugin.file_name),
+            format!("{PREFIXES}\n{}", plugin.description),
+        )?;
+    }
+
+    fs::write(bundle_path.join("manifest.ttl"), manifest)
+}
+
+/// Format a string as a Turtle string literal.
+fn string_literal(string: &str) -> String {
+    let mut literal = String::with_capacity(string.len() + 2);
+    literal.push('"');
+    for c in string.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+
+    literal
+}
+
+/// Convert a plugin name to a file name that can safely be used inside of the bundle.
+fn sanitize_file_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_string_literals() {
+        assert_eq!(string_literal("Gain"), "\"Gain\"");
+        assert_eq!(
+            string_literal("Say \"hi\"\\\n"),
+            "\"Say \\\"hi\\\"\\\\\\n\""
+        );
+    }
+
+    #[test]
+    fn sanitize_file_names() {
+        assert_eq!(sanitize_file_name("Diopser"), "Diopser");
+        assert_eq!(sanitize_file_name("Gain GUI (Vizia)"), "Gain_GUI__Vizia_");
+    }
+}
@@ -0,0 +1,206 @@
+//! The plugin's LV2 UI. The UI runs in the same process as the plugin and accesses the plugin
+//! instance directly through the `instance-access` feature, so parameter changes only need to be
+//! sent to the host to update the parameters' control ports.
+
+use std::any::Any;
+use std::ffi::CStr;
+use std::marker::PhantomData;
+use std::os::raw::{c_char, c_int, c_void};
+use std::ptr;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use super::sys::*;
+use super::wrapper::Wrapper;
+use crate::prelude::{Lv2Plugin, ParentWindowHandle};
+use crate::wrapper::util::check_null_ptr;
+
+/// An open editor. This is the `LV2UI_Handle` passed to the UI's functions.
+struct UiInstance<P: Lv2Plugin> {
+    wrapper: Arc<Wrapper<P>>,
+    /// The editor's handle. Dropping this closes the editor.
+    editor_handle: Option<Box<dyn Any + Send>>,
+    write_function: LV2UI_Write_Function,
+    controller: LV2UI_Controller,
+    /// The host's `ui:resize` feature, if it supports it.
+    host_resize: Option<LV2UI_Resize>,
+}
+
+/// The UI's descriptor functions. These are only used when the plugin has an editor.
+pub struct Ui<P: Lv2Plugin> {
+    _phantom: PhantomData<P>,
+}
+
+impl<P: Lv2Plugin> Ui<P> {
+    const IDLE_INTERFACE: LV2UI_Idle_Interface = LV2UI_Idle_Interface {
+        idle: Some(Self::idle),
+    };
+
+    pub unsafe extern "C" fn instantiate(
+        _descriptor: *const LV2UI_Descriptor,
+        _plugin_uri: *const c_char,
+        _bundle_path: *const c_char,
+        write_function: LV2UI_Write_Function,
+        controller: LV2UI_Controller,
+        widget: *mut LV2UI_Widget,
+        features: *const *const LV2_Feature,
+    ) -> LV2UI_Handle {
+        check_null_ptr!(ptr::null_mut(), widget);
+
+        // The UI directly uses the plugin instance, so this feature is always required
+        let Some(instance) = find_feature(features, LV2_INSTANCE_ACCESS_URI) else {
+            nih_error!("The host does not support the required 'instance-access' feature");
+            return ptr::null_mut();
+        };
+        let Some(parent) = find_feature(features, LV2_UI__parent) else {
+            nih_error!("The host does not support the required 'ui:parent' feature");
+            return ptr::null_mut();
+        };
+        check_null_ptr!(ptr::null_mut(), instance);
+
+        // The host owns the plugin's reference to the wrapper, so the editor needs its own
+        Arc::increment_strong_count(instance as *const Wrapper<P>);
+        let wrapper = Arc::from_raw(instance as *const Wrapper<P>);
+        let host_resize = find_feature(features, LV2_UI__resize)
+            .filter(|resize| !resize.is_null())
+            .map(|resize| *(resize as *const LV2UI_Resize));
+
+        // On macOS scaling is done by the OS, and all window sizes are in logical pixels
+        if !cfg!(target_os = "macos") {
+            if let Some(scale_factor) = find_feature(features, LV2_OPTIONS__options)
+                .and_then(|options| wrapper.host_scale_factor(options as *const LV2_Options_Option))
+            {
+                wrapper
+                    .editor_host_scaling_factor
+                    .store(scale_factor, Ordering::Relaxed);
+            }
+        }
+
+        let parent_handle = if cfg!(target_os = "macos") {
+            ParentWindowHandle::AppKitNsView(parent)
+        } else if cfg!(target_os = "windows") {
+            ParentWindowHandle::Win32Hwnd(parent)
+        } else {
+            ParentWindowHandle::X11Window(parent as usize as u32)
+        };
+
+        let editor_handle = {
+            let editor = wrapper.editor.borrow();
+            let Some(editor) = editor.as_ref() else {
+                nih_debug_assert_failure!(
+                    "The host tried to open a UI for a plugin without editor"
+                );
+                return ptr::null_mut();
+            };
+
+            let editor = editor.lock();
+            if !cfg!(target_os = "macos") {
+                wrapper.update_editor_scaling_factor(&**editor);
+            }
+            editor.spawn(parent_handle, wrapper.clone().make_gui_context())
+        };
+        wrapper.editor_open.store(true, Ordering::SeqCst);
+        wrapper.notify_editor_opened(true);
+
+        // The editor embeds its own window in the host's parent window, and the editor handle does
+        // not expose that window. Hosts that provide `ui:parent` don't need the widget.
+        *widget = ptr::null_mut();
+        let instance = Box::new(UiInstance {
+            wrapper,
+            editor_handle: Some(editor_handle),
+            write_function,
+            controller,
+            host_resize,
+        });
+        instance.resize();
+
+        Box::into_raw(instance) as LV2UI_Handle
+    }
+
+    pub unsafe extern "C" fn cleanup(ui: LV2UI_Handle) {
+        check_null_ptr!((), ui);
+        let mut instance = Box::from_raw(ui as *mut UiInstance<P>);
+
+        instance.wrapper.editor_open.store(false, Ordering::SeqCst);
+        instance.editor_handle = None;
+        instance.wrapper.notify_editor_opened(false);
+        drop(instance);
+    }
+
+    pub unsafe extern "C" fn port_event(
+        _ui: LV2UI_Handle,
+        _port_index: u32,
+        _buffer_size: u32,
+        _format: u32,
+        _buffer: *const c_void,
+    ) {
+        // The editor reads the parameter values directly from the plugin instance, and it is
+        // informed about changes through the wrapper's event loop
+    }
+
+    pub unsafe extern "C" fn extension_data(uri: *const c_char) -> *const c_void {
+        check_null_ptr!(ptr::null(), uri);
+
+        if CStr::from_ptr(uri) == LV2_UI__idleInterface {
+            &Self::IDLE_INTERFACE as *const LV2UI_Idle_Interface as *const c_void
+        } else {
+            ptr::null()
+        }
+    }
+
+    unsafe extern "C" fn idle(ui: LV2UI_Handle) -> c_int {
+        check_null_ptr!(1, ui);
+        let instance = &*(ui as *const UiInstance<P>);
+
+        // Parameter changes made in the editor are sent to the host, which then writes them to the
+        // parameters' control ports
+        if let Some(write_function) = instance.write_function {
+            while let Some((port_index, value)) = instance.wrapper.ui_port_writes.pop() {
+                write_function(
+                    instance.controller,
+                    port_index,
+                    std::mem::size_of::<f32>() as u32,
+                    0,
+                    &value as *const f32 as *const c_void,
+                );
+            }
+        }
+
+        if instance
+            .wrapper
+            .resize_requested
+            .swap(false, Ordering::SeqCst)
+        {
+            instance.resize();
+        }
+
+        0
+    }
+}
+
+impl<P: Lv2Plugin> UiInstance<P> {
+    /// Ask the host to resize the editor's window to the editor's current size. Returns `false` if
+    /// the host does not support the `ui:resize` feature or if it rejected the new size.
+    fn resize(&self) -> bool {
+        let Some(host_resize) = self.host_resize else {
+            return false;
+        };
+        let Some(ui_resize) = host_resize.ui_resize else {
+            return false;
+        };
+        let editor = self.wrapper.editor.borrow();
+        let Some(editor) = editor.as_ref() else {
+            return false;
+        };
+
+        let (unscaled_width, unscaled_height) = editor.lock().size();
+        let scaling_factor = self.wrapper.editor_scaling_factor.load(Ordering::Relaxed);
+        unsafe {
+            ui_resize(
+                host_resize.handle,
+                (unscaled_width as f32 * scaling_factor).round() as c_int,
+                (unscaled_height as f32 * scaling_factor).round() as c_int,
+            ) == 0
+        }
+    }
+}
@@ -0,0 +1,1490 @@
+use atomic_float::AtomicF32;
+use atomic_refcell::AtomicRefCell;
+use crossbeam::channel::{self, SendTimeoutError};
+use crossbeam::queue::ArrayQueue;
+use parking_lot::Mutex;
+use std::borrow::Borrow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::{CStr, CString};
+use std::mem;
+use std::num::NonZeroU32;
+use std::os::raw::{c_char, c_void};
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
+use super::ports::{self, PortLayout};
+use super::sys::*;
+use crate::context::gui::{
+    AsyncExecutor, GuiMessageQueue, ParamChange, ParamChangeBroadcaster, StateSlots,
+};
+use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop, TimerCallback, TimerHandle};
+use crate::midi::MidiResult;
+use crate::prelude::{
+    AudioIOLayout, AuxiliaryBuffers, BufferConfig, Editor, Lv2Plugin, MidiConfig, NoteEvent,
+    ParamPtr, Params, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, StateSaveContext,
+    TaskExecutor, TaskOptions, Transport,
+};
+use crate::util::modulation::ModulationMatrix;
+use crate::util::permit_alloc;
+use crate::wrapper::state::{self, PluginState};
+use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::dsp_load::DspLoadMeter;
+use crate::wrapper::util::{
+    bypass_param_enabled, check_null_ptr, clamp_input_event_timing, clamp_output_event_timing,
+    find_bypass_param, hash_param_id, param_id_from_hash, prepend_gui_note_events, process_wrapper,
+    queue_gui_note_event, setup_logger, GUI_NOTE_EVENT_QUEUE_CAPACITY,
+};
+
+/// The maximum block size used when the host does not pass the `bufsz:maxBlockLength` option.
+/// Longer blocks are split up into multiple process calls.
+const DEFAULT_MAX_BLOCK_SIZE: u32 = 4096;
+/// The number of MIDI events that can be read from the control port during a single run call.
+const INPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// The number of MIDI events the plugin can output during a single process call.
+const OUTPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// The number of parameter changes from the editor that can be queued up before the host calls the
+/// UI's idle function.
+const UI_PORT_WRITE_QUEUE_CAPACITY: usize = 2048;
+
+pub struct Wrapper<P: Lv2Plugin> {
+    /// The wrapped plugin instance.
+    plugin: Mutex<P>,
+    /// The plugin's background task executor closure.
+    pub task_executor: Mutex<TaskExecutor<P>>,
+    /// The plugin's parameters. These are fetched once during initialization. That way the
+    /// `ParamPtr`s are guaranteed to live at least as long as this object and we can interact with
+    /// the `Params` object without having to acquire a lock on `plugin`.
+    params: Arc<dyn Params>,
+    /// The plugin's editor, if it has one. This object does not do anything on its own, but we need
+    /// to instantiate this in advance so we don't need to lock the entire [`Plugin`] object when
+    /// creating an editor. Wrapped in an `AtomicRefCell` because it needs to be initialized late.
+    pub(super) editor: AtomicRefCell<Option<Mutex<Box<dyn Editor>>>>,
+    /// The editor's current scaling factor. This is the user's UI scale if they have chosen one,
+    /// and the host's scaling factor otherwise.
+    pub(super) editor_scaling_factor: AtomicF32,
+    /// The scaling factor passed by the host through the `ui:scaleFactor` option. Used when the
+    /// user has not chosen a UI scale.
+    pub(super) editor_host_scaling_factor: AtomicF32,
+    /// Whether the editor is currently open. Parameter changes are only sent to the host through the
+    /// UI while this is the case.
+    pub(super) editor_open: AtomicBool,
+    /// Set when the editor requests a resize. The UI handles this during its next idle call, since
+    /// the host's `ui:resize` feature may only be used from the UI thread.
+    pub(super) resize_requested: AtomicBool,
+
+    /// A realtime-safe task queue so the plugin can schedule tasks that need to be run later on the
+    /// GUI thread. See the same field in the VST3 wrapper for more information on why this looks
+    /// the way it does.
+    event_loop: AtomicRefCell<Option<OsEventLoop<Task<P>, Self>>>,
+
+    /// URIDs mapped through the host's `urid:map` feature during instantiation.
+    urids: Urids,
+    /// The indices of all of the plugin's ports.
+    pub(super) port_layout: PortLayout,
+    /// The buffers the host connected to the plugin's ports.
+    ports: AtomicRefCell<PortConnections>,
+    /// The audio IO layout used for the audio ports. This is always the plugin's first layout.
+    audio_io_layout: AudioIOLayout,
+    /// The buffer configuration. LV2 plugins are instantiated for a fixed sample rate, so this
+    /// never changes.
+    buffer_config: BufferConfig,
+    /// Whether the host has activated the plugin.
+    is_active: AtomicBool,
+    /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
+    /// [`ProcessContext`]. This is written to the latency port at the end of every run call.
+    current_latency: AtomicU32,
+
+    /// A mapping from parameter ID hashes (obtained from the string parameter IDs) to pointers to
+    /// parameters belonging to the plugin.
+    param_by_hash: HashMap<u32, ParamPtr>,
+    /// Mappings from string parameter identifiers to parameter hashes. Useful for debug logging
+    /// and when handling plugin state.
+    param_id_to_hash: HashMap<String, u32>,
+    /// The inverse mapping from [`param_by_hash`][Self::param_by_hash]. This is needed to be able
+    /// to have an ergonomic parameter setting API that uses references to the parameters instead of
+    /// having to add a setter function to the parameter (or even worse, have it be completely
+    /// untyped).
+    pub(super) param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// The parameters exposed as control ports, in port order. The first parameter's port index is
+    /// `port_layout.params.start`.
+    param_ports: Vec<ParamPtr>,
+    /// The port index for every parameter in [`param_ports`][Self::param_ports].
+    pub(super) param_ptr_to_port: HashMap<ParamPtr, u32>,
+    /// The control port values the parameters were last set to. A parameter is only updated when
+    /// its port's value changes, since the plugin's parameters can also be changed by loading
+    /// state. These start out as NaN so all values get applied during the first run call.
+    last_param_port_values: AtomicRefCell<Vec<f32>>,
+    /// Parameter changes from the editor as `(port_index, port_value)` pairs. LV2 plugins cannot
+    /// change their own control ports, so these are sent to the host from the UI's idle function
+    /// and the parameter is then updated during the next run call.
+    pub(super) ui_port_writes: ArrayQueue<(u32, f32)>,
+    /// The plugin's bypass parameter, if it has one.
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the dry signal when the plugin is bypassed. This is only
+    /// used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    modulation_matrix: Option<Arc<ModulationMatrix>>,
+
+    /// The events read from the control port during the current run call. These are moved to
+    /// [`input_events`][Self::input_events] when the run call is split into multiple blocks.
+    pending_input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// The incoming events for the plugin for the current block.
+    input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Stores any events the plugin has output during the current block. These are written to the
+    /// notify port after the block has been processed.
+    output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// The host's transport information. The host only sends a `time:Position` object when the
+    /// transport changes, so the position is advanced by the wrapper between those updates.
+    host_transport: AtomicRefCell<HostTransport>,
+    /// The buffer manager used to create the plugin's audio buffers from the connected ports.
+    buffer_manager: AtomicRefCell<BufferManager>,
+
+    /// Note events sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()]. These are
+    /// added to the start of the input events at the start of the next run call.
+    gui_note_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// Messages sent from the editor through `send_to_audio()` on the
+    /// [`GuiContext`][crate::prelude::GuiContext]. The plugin receives these through
+    /// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    pub gui_messages: GuiMessageQueue,
+    /// Broadcasts parameter changes to the receivers created through
+    /// [`GuiContext::subscribe_param_changes()`][crate::prelude::GuiContext::subscribe_param_changes()].
+    pub param_changes: ParamChangeBroadcaster,
+    /// The A/B comparison slots used by the [`GuiContext`][crate::prelude::GuiContext]'s
+    /// `swap_ab()` and related functions.
+    pub state_slots: StateSlots,
+    /// Measures the time spent in the plugin's process function. Exposed to the editor through
+    /// [`GuiContext::dsp_load()`][crate::prelude::GuiContext::dsp_load()].
+    pub dsp_load: DspLoadMeter,
+    /// The plugin is able to restore state through a method on the `GuiContext`. To avoid changing
+    /// parameters mid-processing and running into garbled data if the host also tries to load state
+    /// at the same time the restoring happens at the end of each run call. See the same field in
+    /// the CLAP wrapper for more information.
+    updated_state_sender: channel::Sender<PluginState>,
+    /// The receiver belonging to [`updated_state_sender`][Self::updated_state_sender].
+    updated_state_receiver: channel::Receiver<PluginState>,
+}
+
+/// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
+/// realtime-safe way.
+#[allow(clippy::enum_variant_names)]
+pub enum Task<P: Plugin> {
+    /// Execute one of the plugin's background tasks.
+    PluginTask(P::BackgroundTask),
+    /// Inform the editor that one or more parameter values have changed.
+    ParameterValuesChanged,
+    /// Inform the editor that a parameter's value has changed. This uses the parameter hashes since
+    /// the task will be created from the audio thread.
+    ParameterValueChanged(u32, f32),
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+    /// Run a timer callback registered through the [`GuiContext`][crate::prelude::GuiContext].
+    TimerCallback(TimerCallback),
+}
+
+/// The URIDs used by the wrapper.
+struct Urids {
+    atom_blank: LV2_URID,
+    atom_chunk: LV2_URID,
+    atom_double: LV2_URID,
+    atom_float: LV2_URID,
+    atom_int: LV2_URID,
+    atom_long: LV2_URID,
+    atom_object: LV2_URID,
+    atom_sequence: LV2_URID,
+    midi_event: LV2_URID,
+    time_position: LV2_URID,
+    time_bar: LV2_URID,
+    time_bar_beat: LV2_URID,
+    time_beat_unit: LV2_URID,
+    time_beats_per_bar: LV2_URID,
+    time_beats_per_minute: LV2_URID,
+    time_frame: LV2_URID,
+    time_speed: LV2_URID,
+    buf_size_max_block_length: LV2_URID,
+    ui_scale_factor: LV2_URID,
+    /// The key the plugin's state is stored under, `<LV2_URI>#state`.
+    state_key: LV2_URID,
+}
+
+/// The buffers the host connected to the plugin's ports through `connect_port()`.
+struct PortConnections {
+    control: *const LV2_Atom_Sequence,
+    notify: *mut LV2_Atom_Sequence,
+    latency: *mut f32,
+    audio_inputs: Vec<*mut f32>,
+    audio_outputs: Vec<*mut f32>,
+    params: Vec<*const f32>,
+}
+
+// SAFETY: These pointers are only dereferenced during the run call, and the host guarantees that
+//         they remain valid until they are reconnected
+unsafe impl Send for PortConnections {}
+unsafe impl Sync for PortConnections {}
+
+/// The host's transport information from the last `time:Position` object, advanced by the number
+/// of processed samples after every run call.
+#[derive(Debug, Default, Clone, Copy)]
+struct HostTransport {
+    speed: f64,
+    frame: Option<i64>,
+    bar: Option<i64>,
+    bar_beat: Option<f64>,
+    beats_per_bar: Option<f64>,
+    beat_unit: Option<i64>,
+    beats_per_minute: Option<f64>,
+}
+
+/// Writes events to the host's notify atom sequence.
+struct SequenceWriter {
+    sequence: *mut LV2_Atom_Sequence,
+    /// The space available for the sequence's body in bytes, as set by the host.
+    capacity: u32,
+}
+
+impl<P: Lv2Plugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
+    fn execute(&self, task: Task<P>, _is_gui_thread: bool) {
+        match task {
+            Task::PluginTask(task) => (self.task_executor.lock())(task),
+            Task::ParameterValuesChanged => {
+                self.param_changes.broadcast(|| Some(ParamChange::All));
+                if self.editor_open.load(Ordering::SeqCst) {
+                    if let Some(editor) = self.editor.borrow().as_ref() {
+                        editor.lock().param_values_changed();
+                    }
+                }
+            }
+            Task::ParameterValueChanged(param_hash, normalized_value) => {
+                self.param_changes.broadcast(|| {
+                    let param_id = param_id_from_hash(&self.param_id_to_hash, param_hash)?;
+
+                    Some(ParamChange::Value {
+                        id: param_id.to_owned(),
+                        normalized_value,
+                    })
+                });
+                if self.editor_open.load(Ordering::SeqCst) {
+                    if let Some(editor) = self.editor.borrow().as_ref() {
+                        if let Some(param_id) =
+                            param_id_from_hash(&self.param_id_to_hash, param_hash)
+                        {
+                            editor
+                                .lock()
+                                .param_value_changed(param_id, normalized_value);
+                        }
+                    }
+                }
+            }
+            Task::TimerCallback(callback) => callback.call(),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
+        }
+    }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
+}
+
+impl<P: Lv2Plugin> Wrapper<P> {
+    /// Create a new instance of the plugin. Returns `None` if the host does not support the
+    /// required features or if the plugin failed to initialize.
+    unsafe fn new(sample_rate: f64, features: *const *const LV2_Feature) -> Option<Arc<Self>> {
+        let Some(urid_map) = find_feature(features, LV2_URID__map) else {
+            nih_error!("The host does not support the required 'urid:map' feature");
+            return None;
+        };
+        let urid_map = &*(urid_map as *const LV2_URID_Map);
+        let map = |uri: &CStr| match urid_map.map {
+            Some(map) => map(urid_map.handle, uri.as_ptr()),
+            None => 0,
+        };
+        let state_key_uri = CString::new(format!("{}#state", P::LV2_URI))
+            .expect("The plugin's LV2 URI contains null bytes");
+        let urids = Urids {
+            atom_blank: map(LV2_ATOM__Blank),
+            atom_chunk: map(LV2_ATOM__Chunk),
+            atom_double: map(LV2_ATOM__Double),
+            atom_float: map(LV2_ATOM__Float),
+            atom_int: map(LV2_ATOM__Int),
+            atom_long: map(LV2_ATOM__Long),
+            atom_object: map(LV2_ATOM__Object),
+            atom_sequence: map(LV2_ATOM__Sequence),
+            midi_event: map(LV2_MIDI__MidiEvent),
+            time_position: map(LV2_TIME__Position),
+            time_bar: map(LV2_TIME__bar),
+            time_bar_beat: map(LV2_TIME__barBeat),
+            time_beat_unit: map(LV2_TIME__beatUnit),
+            time_beats_per_bar: map(LV2_TIME__beatsPerBar),
+            time_beats_per_minute: map(LV2_TIME__beatsPerMinute),
+            time_frame: map(LV2_TIME__frame),
+            time_speed: map(LV2_TIME__speed),
+            buf_size_max_block_length: map(LV2_BUF_SIZE__maxBlockLength),
+            ui_scale_factor: map(LV2_UI__scaleFactor),
+            state_key: map(&state_key_uri),
+        };
+
+        let mut max_block_size = DEFAULT_MAX_BLOCK_SIZE;
+        if let Some(options) = find_feature(features, LV2_OPTIONS__options) {
+            for option in iter_options(options as *const LV2_Options_Option) {
+                if option.key == urids.buf_size_max_block_length
+                    && option.type_ == urids.atom_int
+                    && !option.value.is_null()
+                {
+                    max_block_size = (*(option.value as *const i32)).max(1) as u32;
+                }
+            }
+        }
+
+        let plugin = P::default();
+        let task_executor = Mutex::new(plugin.task_executor());
+        let params = plugin.params();
+        let modulation_matrix = plugin.modulation_matrix();
+
+        // This is used to allow the plugin to restore preset data from its editor, see the comment
+        // on `Self::updated_state_sender`
+        let (updated_state_sender, updated_state_receiver) = channel::bounded(0);
+
+        let param_map = params.param_map();
+        let param_hashes: Vec<u32> = param_map
+            .iter()
+            .map(|(id, _, _)| hash_param_id(id))
+            .collect();
+        if cfg!(debug_assertions) {
+            let unique_hashes: HashSet<_> = param_hashes.iter().collect();
+            nih_debug_assert_eq!(
+                param_hashes.len(),
+                unique_hashes.len(),
+                "The plugin has duplicate parameter hashes, weird things may happen. Consider using \
+                 6 character parameter IDs to avoid collisions."
+            );
+        }
+        let bypass_param = find_bypass_param::<P>(param_map.iter().map(|(_, ptr, _)| *ptr));
+
+        let param_ports: Vec<ParamPtr> = ports::param_ports(&param_map)
+            .into_iter()
+            .map(|param_port| param_port.ptr)
+            .collect();
+        let port_layout = PortLayout::for_plugin::<P>(param_ports.len());
+        let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+        let buffer_config = BufferConfig {
+            sample_rate: sample_rate as f32,
+            min_buffer_size: None,
+            max_buffer_size: max_block_size,
+            process_mode: ProcessMode::Realtime,
+        };
+
+        let wrapper = Arc::new(Self {
+            plugin: Mutex::new(plugin),
+            task_executor,
+            params,
+            // Initialized later as it needs a reference to the wrapper for the async executor
+            editor: AtomicRefCell::new(None),
+            editor_scaling_factor: AtomicF32::new(1.0),
+            editor_host_scaling_factor: AtomicF32::new(1.0),
+            editor_open: AtomicBool::new(false),
+            resize_requested: AtomicBool::new(false),
+
+            // Also initialized later as it also needs a reference to the wrapper
+            event_loop: AtomicRefCell::new(None),
+
+            urids,
+            ports: AtomicRefCell::new(PortConnections {
+                control: ptr::null(),
+                notify: ptr::null_mut(),
+                latency: ptr::null_mut(),
+                audio_inputs: vec![ptr::null_mut(); port_layout.audio_inputs.len()],
+                audio_outputs: vec![ptr::null_mut(); port_layout.audio_outputs.len()],
+                params: vec![ptr::null(); param_ports.len()],
+            }),
+            audio_io_layout,
+            buffer_config,
+            is_active: AtomicBool::new(false),
+            current_latency: AtomicU32::new(0),
+
+            param_by_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*hash, *ptr))
+                .collect(),
+            param_id_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (id, _, _))| (id.clone(), *hash))
+                .collect(),
+            param_ptr_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*ptr, *hash))
+                .collect(),
+            param_ptr_to_port: param_ports
+                .iter()
+                .zip(port_layout.params.clone())
+                .map(|(ptr, port_idx)| (*ptr, port_idx))
+                .collect(),
+            last_param_port_values: AtomicRefCell::new(vec![f32::NAN; param_ports.len()]),
+            param_ports,
+            ui_port_writes: ArrayQueue::new(UI_PORT_WRITE_QUEUE_CAPACITY),
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            modulation_matrix,
+
+            pending_input_events: AtomicRefCell::new(VecDeque::with_capacity(
+                INPUT_EVENT_QUEUE_CAPACITY,
+            )),
+            input_events: AtomicRefCell::new(VecDeque::with_capacity(
+                INPUT_EVENT_QUEUE_CAPACITY + GUI_NOTE_EVENT_QUEUE_CAPACITY,
+            )),
+            output_events: AtomicRefCell::new(VecDeque::with_capacity(OUTPUT_EVENT_QUEUE_CAPACITY)),
+            host_transport: AtomicRefCell::new(HostTransport::default()),
+            buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
+                max_block_size as usize,
+                audio_io_layout,
+            )),
+            port_layout,
+
+            gui_note_events: ArrayQueue::new(GUI_NOTE_EVENT_QUEUE_CAPACITY),
+            gui_messages: GuiMessageQueue::default(),
+            param_changes: ParamChangeBroadcaster::default(),
+            state_slots: StateSlots::default(),
+            dsp_load: DspLoadMeter::default(),
+            updated_state_sender,
+            updated_state_receiver,
+        });
+
+        *wrapper.event_loop.borrow_mut() =
+            Some(OsEventLoop::new_and_spawn(Arc::downgrade(&wrapper)));
+
+        // The editor also needs to be initialized later so the Async executor can work.
+        *wrapper.editor.borrow_mut() = wrapper
+            .plugin
+            .lock()
+            .editor(AsyncExecutor {
+                execute_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |task| {
+                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                }),
+                execute_gui: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |task| {
+                        let task_posted = wrapper.schedule_gui(Task::PluginTask(task));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                }),
+                cancel_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |tag| wrapper.cancel_background_tasks(tag)
+                }),
+            })
+            .map(Mutex::new);
+
+        // Before initializing the plugin, make sure all smoothers are set the the default values
+        for param in wrapper.param_by_hash.values() {
+            param.update_smoother(buffer_config.sample_rate, true);
+        }
+
+        {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = wrapper.make_init_context();
+            let mut plugin = wrapper.plugin.lock();
+            if !plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context) {
+                nih_error!("The plugin failed to initialize");
+                return None;
+            }
+            process_wrapper(|| plugin.reset());
+        }
+
+        Some(wrapper)
+    }
+
+    fn make_init_context(&self) -> WrapperInitContext<'_, P> {
+        WrapperInitContext { wrapper: self }
+    }
+
+    fn make_process_context(&self, transport: Transport) -> WrapperProcessContext<'_, P> {
+        WrapperProcessContext {
+            wrapper: self,
+            input_events_guard: self.input_events.borrow_mut(),
+            output_events_guard: self.output_events.borrow_mut(),
+            transport,
+            aux_input_count: self.audio_io_layout.aux_input_ports.len(),
+        }
+    }
+
+    pub(super) fn make_gui_context(self: Arc<Self>) -> Arc<WrapperGuiContext<P>> {
+        Arc::new(WrapperGuiContext {
+            wrapper: self,
+            clipboard: Default::default(),
+            #[cfg(debug_assertions)]
+            param_gesture_checker: Default::default(),
+        })
+    }
+
+    /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
+    /// gesture checks.
+    #[cfg(debug_assertions)]
+    pub fn param_id_from_ptr(&self, param: ParamPtr) -> Option<&str> {
+        param_id_from_hash(&self.param_id_to_hash, *self.param_ptr_to_hash.get(&param)?)
+    }
+
+    /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
+    /// can be run in the background without blocking either the GUI or the audio thread.
+    ///
+    /// The plugin's own tasks are scheduled using the options from
+    /// [`Plugin::background_task_options()`].
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_background(&self, task: Task<P>) -> bool {
+        let options = match &task {
+            Task::PluginTask(task) => P::background_task_options(task),
+            _ => TaskOptions::default(),
+        };
+
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_background(task, options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.cancel_background(tag)
+    }
+
+    /// Posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be delegated
+    /// to the main thread. The task is run directly if this is the GUI thread.
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_gui(&self, task: Task<P>) -> bool {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_gui(task)
+    }
+
+    /// Run `callback` on the GUI thread after `delay` has passed. See
+    /// [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()].
+    pub fn schedule_timer_once(
+        &self,
+        delay: Duration,
+        callback: Box<dyn FnOnce() + Send>,
+    ) -> TimerHandle {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_once(
+            delay,
+            Task::TimerCallback(TimerCallback::new_once(callback)),
+        )
+    }
+
+    /// Run `callback` on the GUI thread every `interval`. See
+    /// [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()].
+    pub fn schedule_timer_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let callback = TimerCallback::new(callback);
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_repeating(interval, move || Task::TimerCallback(callback.clone()))
+    }
+
+    /// Inform the plugin that its editor has been opened or closed through
+    /// [`Plugin::editor_opened()`] and [`Plugin::editor_closed()`]. Called from the main thread
+    /// after the editor handle has been created or dropped.
+    pub fn notify_editor_opened(&self, opened: bool) {
+        let mut plugin = self.plugin.lock();
+        if opened {
+            plugin.editor_opened();
+        } else {
+            plugin.editor_closed();
+        }
+    }
+
+    /// Request the host to resize the editor window to the editor's current size. This happens
+    /// during the UI's next idle call. Returns `false` if the editor is not open.
+    pub fn request_resize(&self) -> bool {
+        if !self.editor_open.load(Ordering::SeqCst) {
+            return false;
+        }
+
+        self.resize_requested.store(true, Ordering::SeqCst);
+        true
+    }
+
+    /// Set the user's UI scale. See
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()].
+    pub fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        // On macOS scaling is done by the OS, and all window sizes are in logical pixels
+        if cfg!(target_os = "macos") {
+            return false;
+        }
+
+        let applied = match self.editor.borrow().as_ref() {
+            Some(editor) => {
+                let editor = editor.lock();
+                editor.set_ui_scale(scale) && self.update_editor_scaling_factor(&**editor)
+            }
+            None => false,
+        };
+
+        applied && self.request_resize()
+    }
+
+    /// Pass the user's UI scale to the editor, or the host's scaling factor if the user has not
+    /// chosen a UI scale. Returns `false` if the editor rejected the new scaling factor, for
+    /// instance because it is currently open.
+    pub(super) fn update_editor_scaling_factor(&self, editor: &dyn Editor) -> bool {
+        let scaling_factor = editor
+            .ui_scale()
+            .unwrap_or_else(|| self.editor_host_scaling_factor.load(Ordering::Relaxed));
+        if editor.set_scale_factor(scaling_factor) {
+            self.editor_scaling_factor
+                .store(scaling_factor, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Read the host's `ui:scaleFactor` option from the options passed to the UI, if it is set.
+    pub(super) unsafe fn host_scale_factor(
+        &self,
+        options: *const LV2_Options_Option,
+    ) -> Option<f32> {
+        iter_options(options)
+            .find(|option| {
+                option.key == self.urids.ui_scale_factor
+                    && option.type_ == self.urids.atom_float
+                    && !option.value.is_null()
+            })
+            .map(|option| *(option.value as *const f32))
+    }
+
+    /// Queue a note event sent from the editor. See
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+    pub fn queue_gui_note_event(&self, event: NoteEvent<()>) -> bool {
+        queue_gui_note_event::<P>(&self.gui_note_events, event)
+    }
+
+    /// Queue a parameter change from the editor so it can be sent to the host during the UI's next
+    /// idle call. Returns `false` if the parameter does not have a port or if the queue is full.
+    pub fn queue_ui_port_write(&self, param: ParamPtr, normalized: f32) -> bool {
+        match self.param_ptr_to_port.get(&param) {
+            Some(port_idx) => self
+                .ui_port_writes
+                .push((*port_idx, unsafe {
+                    ports::normalized_to_port_value(param, normalized)
+                }))
+                .is_ok(),
+            None => false,
+        }
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
+    pub fn set_latency_samples(&self, samples: u32) {
+        // The new latency is written to the latency port at the end of the next run call
+        self.current_latency.store(samples, Ordering::SeqCst);
+    }
+
+    /// Get the plugin's state object, may be called by the plugin's GUI as part of its own preset
+    /// management. The wrapper doesn't use these functions and serializes and deserializes directly
+    /// the JSON in the relevant plugin API methods instead.
+    pub fn get_state_object(&self) -> PluginState {
+        unsafe {
+            state::serialize_object::<P>(
+                self.params.clone(),
+                state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                StateSaveContext::Project,
+            )
+        }
+    }
+
+    /// Update the plugin's internal state, called by the plugin itself from the GUI thread. To
+    /// prevent corrupting data and changing parameters during processing the actual state is only
+    /// updated at the end of the audio processing cycle.
+    pub fn set_state_object_from_gui(&self, mut state: PluginState) {
+        // Use a loop and timeouts to handle the super rare edge case when this function gets called
+        // between a run call and the host deactivating the plugin
+        loop {
+            if self.is_active.load(Ordering::SeqCst) {
+                // If the plugin is currently processing audio, then we'll perform the restore
+                // operation at the end of the run call and send the state back to this thread so it
+                // can be deallocated without blocking the audio thread
+                match self
+                    .updated_state_sender
+                    .send_timeout(state, Duration::from_secs(1))
+                {
+                    Ok(_) => {
+                        let state = self.updated_state_receiver.recv();
+                        drop(state);
+                        break;
+                    }
+                    Err(SendTimeoutError::Timeout(value)) => {
+                        state = value;
+                        continue;
+                    }
+                    Err(SendTimeoutError::Disconnected(_)) => {
+                        nih_debug_assert_failure!("State update channel got disconnected");
+                        return;
+                    }
+                }
+            } else {
+                // Otherwise we'll set the state right here and now, since this function should be
+                // called from a GUI thread
+                self.set_state_inner(&mut state);
+                break;
+            }
+        }
+    }
+
+    /// Immediately set the plugin state. Returns `false` if the deserialization failed. Includes
+    /// `permit_alloc()`s around the deserialization and initialization for the use case where
+    /// `set_state_object_from_gui()` was called while the plugin is process audio.
+    ///
+    /// Implicitly emits `Task::ParameterValuesChanged`.
+    ///
+    /// # Notes
+    ///
+    /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
+    fn set_state_inner(&self, state: &mut PluginState) -> bool {
+        // FIXME: This is obviously not realtime-safe, but loading presets without doing this could
+        //        lead to inconsistencies. It's the plugin's responsibility to not perform any
+        //        realtime-unsafe work when the initialize function is called a second time if it
+        //        supports runtime preset loading. `state::deserialize_object()` normally never
+        //        allocates, but if the plugin has persistent non-parameter data then its
+        //        `deserialize_fields()` implementation may still allocate.
+        let mut success = permit_alloc(|| unsafe {
+            state::deserialize_object::<P>(
+                state,
+                self.params.clone(),
+                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                Some(&self.buffer_config),
+            )
+        });
+        if !success {
+            nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
+            return false;
+        }
+
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // The plugin is always initialized, so it needs to be reinitialized
+        {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = self.make_init_context();
+            let mut plugin = self.plugin.lock();
+
+            // See above
+            success = permit_alloc(|| {
+                plugin.initialize(
+                    &self.audio_io_layout,
+                    &self.buffer_config,
+                    &mut init_context,
+                )
+            });
+            if success {
+                process_wrapper(|| plugin.reset());
+            }
+        }
+
+        nih_debug_assert!(
+            success,
+            "Plugin returned false when reinitializing after loading state"
+        );
+
+        // The host's control port values should match the new parameter values. This only works
+        // while the editor is open, since the values are sent through the UI.
+        if self.editor_open.load(Ordering::SeqCst) {
+            for param_ptr in &self.param_ports {
+                let normalized = unsafe { param_ptr.unmodulated_normalized_value() };
+                self.queue_ui_port_write(*param_ptr, normalized);
+            }
+        }
+
+        let task_posted = self.schedule_gui(Task::ParameterValuesChanged);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // TODO: Right now there's no way to know if loading the state changed the GUI's size. We
+        //       could keep track of the last known size and compare the GUI's current size against
+        //       that but that also seems brittle.
+        self.request_resize();
+
+        success
+    }
+
+    /// Set a parameter's normalized value from its control port. This updates the parameter's
+    /// smoother, applies macro mappings, and informs the editor about the change.
+    fn set_normalized_value(&self, param_ptr: ParamPtr, normalized: f32) {
+        let sample_rate = self.buffer_config.sample_rate;
+        if unsafe { param_ptr.set_normalized_value(normalized) } {
+            unsafe { param_ptr.update_smoother(sample_rate, false) };
+            unsafe {
+                param_ptr.apply_macro_mappings(
+                    state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    Some(sample_rate),
+                    |target_ptr, normalized_value| {
+                        if let Some(target_hash) = self.param_ptr_to_hash.get(&target_ptr) {
+                            let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                                *target_hash,
+                                normalized_value,
+                            ));
+                            nih_debug_assert!(
+                                task_posted,
+                                "The task queue is full, dropping task..."
+                            );
+                        }
+                    },
+                )
+            };
+
+            let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                self.param_ptr_to_hash[&param_ptr],
+                normalized,
+            ));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Update the parameters whose control port values have changed since the last run call.
+    unsafe fn read_param_ports(&self, ports: &PortConnections) {
+        let mut last_param_port_values = self.last_param_port_values.borrow_mut();
+        for ((param_ptr, port), last_value) in self
+            .param_ports
+            .iter()
+            .zip(&ports.params)
+            .zip(last_param_port_values.iter_mut())
+        {
+            if port.is_null() {
+                continue;
+            }
+
+            let value = **port;
+            if value != *last_value {
+                *last_value = value;
+                self.set_normalized_value(
+                    *param_ptr,
+                    ports::port_value_to_normalized(*param_ptr, value),
+                );
+            }
+        }
+    }
+
+    /// Read the MIDI events and transport updates from the control port. MIDI events are stored in
+    /// [`pending_input_events`][Self::pending_input_events].
+    unsafe fn read_control_port(&self, ports: &PortConnections, num_samples: u32) {
+        let mut pending_input_events = self.pending_input_events.borrow_mut();
+        pending_input_events.clear();
+        if ports.control.is_null() {
+            return;
+        }
+
+        let mut host_transport = self.host_transport.borrow_mut();
+        for event in iter_sequence(ports.control) {
+            let timing = clamp_input_event_timing(event.time_frames.max(0) as u32, num_samples);
+            if event.body.type_ == self.urids.midi_event && P::MIDI_INPUT >= MidiConfig::Basic {
+                let midi_data =
+                    std::slice::from_raw_parts(atom_body(&event.body), event.body.size as usize);
+
+                // In the Basic note port type, we'll still handle note on, note off, and polyphonic
+                // pressure events. But we'll throw away any other MIDI messages to stay consistent
+                // with the other wrappers.
+                match NoteEvent::from_midi(timing, midi_data) {
+                    Ok(
+                        note_event @ (NoteEvent::NoteOn { .. }
+                        | NoteEvent::NoteOff { .. }
+                        | NoteEvent::PolyPressure { .. }),
+                    ) => {
+                        pending_input_events.push_back(note_event);
+                    }
+                    Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                        pending_input_events.push_back(note_event);
+                    }
+                    Ok(_) => (),
+                    Err(n) => nih_debug_assert_failure!("Unhandled MIDI message type {}", n),
+                }
+            } else if event.body.type_ == self.urids.atom_object
+                || event.body.type_ == self.urids.atom_blank
+            {
+                let object = &*(&event.body as *const LV2_Atom as *const LV2_Atom_Object);
+                if object.body.otype == self.urids.time_position {
+                    self.read_time_position(object, &mut host_transport);
+                }
+            }
+        }
+    }
+
+    /// Update the host's transport information from a `time:Position` object.
+    unsafe fn read_time_position(
+        &self,
+        object: &LV2_Atom_Object,
+        host_transport: &mut HostTransport,
+    ) {
+        for property in iter_object(object) {
+            let Some(value) = self.atom_number(&property.value) else {
+                continue;
+            };
+
+            let key = property.key;
+            if key == self.urids.time_speed {
+                host_transport.speed = value;
+            } else if key == self.urids.time_frame {
+                host_transport.frame = Some(value as i64);
+            } else if key == self.urids.time_bar {
+                host_transport.bar = Some(value as i64);
+            } else if key == self.urids.time_bar_beat {
+                host_transport.bar_beat = Some(value);
+            } else if key == self.urids.time_beats_per_bar {
+                host_transport.beats_per_bar = Some(value);
+            } else if key == self.urids.time_beat_unit {
+                host_transport.beat_unit = Some(value as i64);
+            } else if key == self.urids.time_beats_per_minute {
+                host_transport.beats_per_minute = Some(value);
+            }
+        }
+    }
+
+    /// Read a numeric atom's value. Hosts are free to choose the number type for the
+    /// `time:Position` object's properties.
+    unsafe fn atom_number(&self, atom: &LV2_Atom) -> Option<f64> {
+        let body = atom_body(atom);
+        if atom.type_ == self.urids.atom_float {
+            Some(*(body as *const f32) as f64)
+        } else if atom.type_ == self.urids.atom_double {
+            Some(*(body as *const f64))
+        } else if atom.type_ == self.urids.atom_int {
+            Some(*(body as *const i32) as f64)
+        } else if atom.type_ == self.urids.atom_long {
+            Some(*(body as *const i64) as f64)
+        } else {
+            None
+        }
+    }
+
+    /// Write the plugin's output events for a block starting at `block_start` to the notify port.
+    fn write_output_events(
+        &self,
+        notify: &mut Option<SequenceWriter>,
+        block_start: u32,
+        block_len: u32,
+    ) {
+        let mut output_events = self.output_events.borrow_mut();
+        let Some(notify) = notify else {
+            output_events.clear();
+            return;
+        };
+
+        for event in output_events.drain(..) {
+            let time = (block_start + clamp_output_event_timing(event.timing(), block_len)) as i64;
+            let is_note_event = matches!(
+                event,
+                NoteEvent::NoteOn { .. }
+                    | NoteEvent::NoteOff { .. }
+                    | NoteEvent::PolyPressure { .. }
+            );
+            if !is_note_event && P::MIDI_OUTPUT < MidiConfig::MidiCCs {
+                continue;
+            }
+
+            let push_successful = match event.as_midi() {
+                Some(MidiResult::Basic(midi_data)) => unsafe {
+                    notify.write(time, self.urids.midi_event, &midi_data)
+                },
+                Some(MidiResult::SysEx(padded_sysex_buffer, length)) => {
+                    let padded_sysex_buffer = padded_sysex_buffer.borrow();
+                    // The SysEx buffer may contain padding
+                    nih_debug_assert!(length <= padded_sysex_buffer.len());
+                    unsafe {
+                        notify.write(time, self.urids.midi_event, &padded_sysex_buffer[..length])
+                    }
+                }
+                // Events without a MIDI equivalent can't be sent to the host
+                None => continue,
+            };
+
+            nih_debug_assert!(
+                push_successful,
+                "The notify port is full, dropping event..."
+            );
+        }
+    }
+
+    /// The transport information for a block starting `sample_offset` samples into the current
+    /// run call.
+    fn transport(&self, sample_offset: u32) -> Transport {
+        let host_transport = self.host_transport.borrow();
+        let sample_rate = self.buffer_config.sample_rate;
+
+        // Some of the fields are left empty because the host does not provide this information,
+        // but the methods on [`Transport`] can reconstruct these values from the other fields
+        let mut transport = Transport::new(sample_rate);
+        transport.playing = host_transport.speed != 0.0;
+        transport.tempo = host_transport.beats_per_minute;
+        if let (Some(beats_per_bar), Some(beat_unit)) =
+            (host_transport.beats_per_bar, host_transport.beat_unit)
+        {
+            transport.time_sig_numerator = Some(beats_per_bar.round() as i32);
+            transport.time_sig_denominator = Some(beat_unit as i32);
+        }
+
+        let advanced = host_transport.advanced(sample_offset, sample_rate);
+        transport.pos_samples = advanced.frame;
+        if let (Some(bar), Some(bar_beat), Some(beats_per_bar), Some(beat_unit)) = (
+            advanced.bar,
+            advanced.bar_beat,
+            advanced.beats_per_bar,
+            advanced.beat_unit,
+        ) {
+            // NIH-plug's beat positions are in quarter notes
+            let quarter_notes_per_beat = 4.0 / beat_unit.max(1) as f64;
+            let bar_start_pos_beats = bar as f64 * beats_per_bar * quarter_notes_per_beat;
+            transport.bar_start_pos_beats = Some(bar_start_pos_beats);
+            transport.pos_beats = Some(bar_start_pos_beats + bar_beat * quarter_notes_per_beat);
+            transport.bar_number = Some(bar as i32);
+        }
+
+        transport
+    }
+
+    /// Process a run call. Blocks longer than the maximum block size are split up.
+    unsafe fn run(&self, num_samples: u32) {
+        process_wrapper(|| {
+            let mut ports = self.ports.borrow_mut();
+            self.read_param_ports(&ports);
+            self.read_control_port(&ports, num_samples);
+
+            let mut notify = match self.port_layout.notify {
+                Some(_) if !ports.notify.is_null() => {
+                    Some(SequenceWriter::new(ports.notify, self.urids.atom_sequence))
+                }
+                _ => None,
+            };
+
+            let audio_ports_connected = ports.audio_inputs.iter().all(|ptr| !ptr.is_null())
+                && ports.audio_outputs.iter().all(|ptr| !ptr.is_null());
+            nih_debug_assert!(
+                audio_ports_connected,
+                "The host did not connect all audio ports"
+            );
+
+            let max_block_size = self.buffer_config.max_buffer_size;
+            let mut block_start = 0;
+            while audio_ports_connected && block_start < num_samples {
+                let block_end = (block_start + max_block_size).min(num_samples);
+                let block_len = block_end - block_start;
+
+                {
+                    let mut input_events = self.input_events.borrow_mut();
+                    let mut pending_input_events = self.pending_input_events.borrow_mut();
+                    input_events.clear();
+                    while pending_input_events
+                        .front()
+                        .map(|event| event.timing() < block_end)
+                        .unwrap_or(false)
+                    {
+                        let mut event = pending_input_events.pop_front().unwrap();
+                        event.subtract_timing(block_start);
+                        input_events.push_back(event);
+                    }
+
+                    // Notes played from the editor are handled at the start of the run call
+                    if block_start == 0 {
+                        prepend_gui_note_events(&self.gui_note_events, &mut input_events);
+                    }
+                }
+
+                let PortConnections {
+                    audio_inputs,
+                    audio_outputs,
+                    ..
+                } = &mut *ports;
+                let mut buffer_manager = self.buffer_manager.borrow_mut();
+                let buffers = buffer_manager.create_buffers(
+                    block_start as usize,
+                    block_len as usize,
+                    |buffer_source| {
+                        if !audio_inputs.is_empty() {
+                            *buffer_source.main_input_channel_pointers = Some(ChannelPointers {
+                                ptrs: NonNull::new(audio_inputs.as_mut_ptr()).unwrap(),
+                                num_channels: audio_inputs.len(),
+                            });
+                        }
+                        if !audio_outputs.is_empty() {
+                            *buffer_source.main_output_channel_pointers = Some(ChannelPointers {
+                                ptrs: NonNull::new(audio_outputs.as_mut_ptr()).unwrap(),
+                                num_channels: audio_outputs.len(),
+                            });
+                        }
+                    },
+                );
+
+                let sample_rate = self.buffer_config.sample_rate;
+                let transport = self.transport(block_start);
+
+                // NOTE: `parking_lot`'s mutexes sometimes allocate because of their use of thread
+                //       locals
+                let mut plugin = permit_alloc(|| self.plugin.lock());
+                if let Some(modulation_matrix) = &self.modulation_matrix {
+                    modulation_matrix.process_block(
+                        buffers.main_buffer,
+                        self.input_events.borrow().iter(),
+                        sample_rate,
+                        state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    );
+                }
+
+                let mut aux = AuxiliaryBuffers {
+                    inputs: buffers.aux_inputs,
+                    outputs: buffers.aux_outputs,
+                };
+                let mut bypass_processor = self.bypass_processor.borrow_mut();
+                if P::MANAGED_BYPASS {
+                    bypass_processor.write_dry(buffers.main_buffer);
+                }
+                let result = {
+                    let mut context = self.make_process_context(transport);
+                    self.dsp_load.measure(
+                        block_len as usize,
+                        sample_rate,
+                        ProcessMode::Realtime,
+                        || plugin.process(buffers.main_buffer, &mut aux, &mut context),
+                    )
+                };
+                if P::MANAGED_BYPASS {
+                    bypass_processor.process(
+                        buffers.main_buffer,
+                        self.current_latency.load(Ordering::Relaxed),
+                        self.is_bypassed(),
+                    );
+                }
+                if let ProcessStatus::Error(err) = result {
+                    nih_debug_assert_failure!("Process error: {}", err);
+                }
+
+                self.write_output_events(&mut notify, block_start, block_len);
+                block_start = block_end;
+            }
+
+            if !ports.latency.is_null() {
+                *ports.latency = self.current_latency.load(Ordering::SeqCst) as f32;
+            }
+
+            self.host_transport
+                .borrow_mut()
+                .advance(num_samples, self.buffer_config.sample_rate);
+
+            // After processing audio, we'll check if the editor has sent us updated plugin state.
+            // We'll restore that here on the audio thread to prevent changing the values during the
+            // process call and also to prevent inconsistent state when the host also wants to load
+            // plugin state.
+            // FIXME: Zero capacity channels allocate on receiving, find a better alternative that
+            //        doesn't do that
+            let updated_state = permit_alloc(|| self.updated_state_receiver.try_recv());
+            if let Ok(mut state) = updated_state {
+                self.set_state_inner(&mut state);
+
+                // We'll pass the state object back to the GUI thread so deallocation can happen
+                // there without potentially blocking the audio thread
+                if let Err(err) = self.updated_state_sender.send(state) {
+                    nih_debug_assert_failure!(
+                        "Failed to send state object back to GUI thread: {}",
+                        err
+                    );
+                };
+            }
+        });
+    }
+
+    pub unsafe extern "C" fn instantiate(
+        _descriptor: *const LV2_Descriptor,
+        sample_rate: f64,
+        _bundle_path: *const c_char,
+        features: *const *const LV2_Feature,
+    ) -> LV2_Handle {
+        setup_logger();
+
+        match Self::new(sample_rate, features) {
+            Some(wrapper) => Arc::into_raw(wrapper) as LV2_Handle,
+            None => ptr::null_mut(),
+        }
+    }
+
+    pub unsafe extern "C" fn connect_port(instance: LV2_Handle, port: u32, data: *mut c_void) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        let port_layout = &wrapper.port_layout;
+        let mut ports = wrapper.ports.borrow_mut();
+        if port == port_layout.control {
+            ports.control = data as *const LV2_Atom_Sequence;
+        } else if Some(port) == port_layout.notify {
+            ports.notify = data as *mut LV2_Atom_Sequence;
+        } else if port == port_layout.latency {
+            ports.latency = data as *mut f32;
+        } else if port_layout.audio_inputs.contains(&port) {
+            ports.audio_inputs[(port - port_layout.audio_inputs.start) as usize] = data as *mut f32;
+        } else if port_layout.audio_outputs.contains(&port) {
+            ports.audio_outputs[(port - port_layout.audio_outputs.start) as usize] =
+                data as *mut f32;
+        } else if port_layout.params.contains(&port) {
+            ports.params[(port - port_layout.params.start) as usize] = data as *const f32;
+        } else {
+            nih_debug_assert_failure!("The host tried to connect unknown port {}", port);
+        }
+    }
+
+    pub unsafe extern "C" fn activate(instance: LV2_Handle) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        process_wrapper(|| wrapper.plugin.lock().reset());
+        if P::MANAGED_BYPASS {
+            // The latency may have changed since the plugin was last activated
+            wrapper.bypass_processor.borrow_mut().initialize(
+                wrapper
+                    .audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+                wrapper.current_latency.load(Ordering::SeqCst),
+                wrapper.buffer_config.max_buffer_size,
+                wrapper.buffer_config.sample_rate,
+                wrapper.is_bypassed(),
+            );
+        }
+        wrapper.dsp_load.reset();
+        *wrapper.host_transport.borrow_mut() = HostTransport::default();
+
+        wrapper.is_active.store(true, Ordering::SeqCst);
+    }
+
+    pub unsafe extern "C" fn run_plugin(instance: LV2_Handle, sample_count: u32) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.run(sample_count);
+    }
+
+    pub unsafe extern "C" fn deactivate(instance: LV2_Handle) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.is_active.store(false, Ordering::SeqCst);
+    }
+
+    pub unsafe extern "C" fn cleanup(instance: LV2_Handle) {
+        check_null_ptr!((), instance);
+        let wrapper = Arc::from_raw(instance as *const Self);
+
+        wrapper.plugin.lock().deactivate();
+        drop(wrapper);
+    }
+
+    pub unsafe extern "C" fn extension_data(uri: *const c_char) -> *const c_void {
+        check_null_ptr!(ptr::null(), uri);
+
+        if CStr::from_ptr(uri) == LV2_STATE__interface {
+            &Self::STATE_INTERFACE as *const LV2_State_Interface as *const c_void
+        } else {
+            ptr::null()
+        }
+    }
+
+    const STATE_INTERFACE: LV2_State_Interface = LV2_State_Interface {
+        save: Some(Self::state_save),
+        restore: Some(Self::state_restore),
+    };
+
+    unsafe extern "C" fn state_save(
+        instance: LV2_Handle,
+        store: LV2_State_Store_Function,
+        handle: LV2_State_Handle,
+        _flags: u32,
+        _features: *const *const LV2_Feature,
+    ) -> LV2_State_Status {
+        check_null_ptr!(LV2_STATE_ERR_UNKNOWN, instance);
+        let wrapper = &*(instance as *const Self);
+        let Some(store) = store else {
+            nih_debug_assert_failure!("The host passed a null store function");
+            return LV2_STATE_ERR_UNKNOWN;
+        };
+
+        let serialized = state::serialize_json::<P>(
+            wrapper.params.clone(),
+            state::make_params_iter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
+            StateSaveContext::Project,
+        );
+        match serialized {
+            Ok(serialized) => {
+                let status = store(
+                    handle,
+                    wrapper.urids.state_key,
+                    serialized.as_ptr() as *const c_void,
+                    serialized.len(),
+                    wrapper.urids.atom_chunk,
+                    LV2_STATE_IS_POD | LV2_STATE_IS_PORTABLE,
+                );
+                if status == LV2_STATE_SUCCESS {
+                    nih_trace!("Saved state ({} bytes)", serialized.len());
+                }
+
+                status
+            }
+            Err(err) => {
+                nih_debug_assert_failure!("Could not save state: {:#}", err);
+                LV2_STATE_ERR_UNKNOWN
+            }
+        }
+    }
+
+    unsafe extern "C" fn state_restore(
+        instance: LV2_Handle,
+        retrieve: LV2_State_Retrieve_Function,
+        handle: LV2_State_Handle,
+        _flags: u32,
+        _features: *const *const LV2_Feature,
+    ) -> LV2_State_Status {
+        check_null_ptr!(LV2_STATE_ERR_UNKNOWN, instance);
+        let wrapper = &*(instance as *const Self);
+        let Some(retrieve) = retrieve else {
+            nih_debug_assert_failure!("The host passed a null retrieve function");
+            return LV2_STATE_ERR_UNKNOWN;
+        };
+
+        let mut size = 0;
+        let mut type_ = 0;
+        let mut flags = 0;
+        let data = retrieve(
+            handle,
+            wrapper.urids.state_key,
+            &mut size,
+            &mut type_,
+            &mut flags,
+        );
+        if data.is_null() {
+            return LV2_STATE_ERR_NO_PROPERTY;
+        }
+        if type_ != wrapper.urids.atom_chunk {
+            nih_debug_assert_failure!("The host returned state with an unexpected type");
+            return LV2_STATE_ERR_BAD_TYPE;
+        }
+
+        let serialized = std::slice::from_raw_parts(data as *const u8, size);
+        match state::deserialize_json(serialized) {
+            Some(mut state) => {
+                if wrapper.set_state_inner(&mut state) {
+                    nih_trace!("Loaded state ({} bytes)", serialized.len());
+                    LV2_STATE_SUCCESS
+                } else {
+                    LV2_STATE_ERR_UNKNOWN
+                }
+            }
+            None => LV2_STATE_ERR_UNKNOWN,
+        }
+    }
+}
+
+impl HostTransport {
+    /// The transport information `num_samples` samples after the last update, assuming the
+    /// transport's speed and tempo stay the same.
+    fn advanced(&self, num_samples: u32, sample_rate: f32) -> Self {
+        let mut transport = *self;
+        if self.speed == 0.0 || num_samples == 0 {
+            return transport;
+        }
+
+        let elapsed_frames = num_samples as f64 * self.speed;
+        transport.frame = self
+            .frame
+            .map(|frame| frame + elapsed_frames.round() as i64);
+        if let (Some(bar), Some(bar_beat), Some(beats_per_bar), Some(beats_per_minute)) = (
+            self.bar,
+            self.bar_beat,
+            self.beats_per_bar,
+            self.beats_per_minute,
+        ) {
+            let elapsed_beats = elapsed_frames / sample_rate as f64 * beats_per_minute / 60.0;
+            let bar_beat = bar_beat + elapsed_beats;
+            if beats_per_bar > 0.0 {
+                let elapsed_bars = (bar_beat / beats_per_bar).floor();
+                transport.bar = Some(bar + elapsed_bars as i64);
+                transport.bar_beat = Some(bar_beat - elapsed_bars * beats_per_bar);
+            } else {
+                transport.bar_beat = Some(bar_beat);
+            }
+        }
+
+        transport
+    }
+
+    /// Advance the transport by `num_samples` samples. The host only sends a new position when
+    /// the transport changes.
+    fn advance(&mut self, num_samples: u32, sample_rate: f32) {
+        *self = self.advanced(num_samples, sample_rate);
+    }
+}
+
+impl SequenceWriter {
+    /// Initialize the host's notify sequence as an empty atom sequence. The host sets the atom's
+    /// size to the available space before calling the plugin's run function.
+    unsafe fn new(sequence: *mut LV2_Atom_Sequence, sequence_urid: LV2_URID) -> Self {
+        let capacity = (*sequence).atom.size;
+        (*sequence).atom = LV2_Atom {
+            size: mem::size_of::<LV2_Atom_Sequence_Body>() as u32,
+            type_: sequence_urid,
+        };
+        (*sequence).body = LV2_Atom_Sequence_Body { unit: 0, pad: 0 };
+
+        Self { sequence, capacity }
+    }
+
+    /// Append an event to the sequence. Events need to be written in order. Returns `false` if the
+    /// sequence is full.
+    unsafe fn write(&mut self, time_frames: i64, type_: LV2_URID, data: &[u8]) -> bool {
+        let atom = &mut (*self.sequence).atom;
+        let event_size = mem::size_of::<LV2_Atom_Event>() as u32 + data.len() as u32;
+        if atom.size + atom_pad_size(event_size) > self.capacity {
+            return false;
+        }
+
+        let event = (&mut (*self.sequence).body as *mut LV2_Atom_Sequence_Body as *mut u8)
+            .add(atom.size as usize) as *mut LV2_Atom_Event;
+        (*event).time_frames = time_frames;
+        (*event).body = LV2_Atom {
+            size: data.len() as u32,
+            type_,
+        };
+        ptr::copy_nonoverlapping(
+            data.as_ptr(),
+            (event as *mut u8).add(mem::size_of::<LV2_Atom_Event>()),
+            data.len(),
+        );
+        atom.size += atom_pad_size(event_size);
+
+        true
+    }
+}
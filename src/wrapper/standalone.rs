@@ -5,6 +5,7 @@ use clap::{CommandFactory, FromArgMatches};
 
 use self::backend::Backend;
 use self::config::WrapperConfig;
+use self::settings::StandaloneSettings;
 use self::wrapper::{Wrapper, WrapperError};
 use super::util::setup_logger;
 use crate::prelude::Plugin;
@@ -12,6 +13,9 @@ use crate::prelude::Plugin;
 mod backend;
 mod config;
 mod context;
+mod osc;
+mod settings;
+mod transport;
 mod wrapper;
 
 /// Open an NIH-plug plugin as a standalone application. If the plugin has an editor, this will open
@@ -43,7 +47,13 @@ mod wrapper;
 /// ```
 ///
 /// By default this will connect to the 'default' audio and MIDI ports. Use the command line options
-/// to change this. `--help` lists all available options.
+/// to change this, or pick the devices in the settings window. That window is shown on the first
+/// launch outside of a terminal, when Shift is held while launching the standalone, and when
+/// `--settings` is passed. The settings are remembered for the next launch. The plugin's state is
+/// also restored on launch and saved again when the editor is closed. `--state <name>` selects a
+/// named session, and `--no-state` disables this. `--headless` runs the plugin without opening its
+/// editor, and `--osc-port <port>` starts an OSC server for controlling the plugin's parameters
+/// remotely. `--help` lists all available options.
 ///
 /// If the wrapped plugin fails to initialize or throws an error during audio processing, then this
 /// function will return `false`.
@@ -63,13 +73,17 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
     // Instead of parsing this directly, we need to take a bit of a roundabout approach to get the
     // plugin's name and vendor in here since they'd otherwise be taken from NIH-plug's own
     // `Cargo.toml` file.
-    let config = WrapperConfig::from_arg_matches(
-        &WrapperConfig::command()
-            .name(P::NAME)
-            .author(P::VENDOR)
-            .get_matches_from(args),
-    )
-    .unwrap_or_else(|err| err.exit());
+    let matches = WrapperConfig::command()
+        .name(P::NAME)
+        .author(P::VENDOR)
+        .get_matches_from(args);
+    let mut config = WrapperConfig::from_arg_matches(&matches).unwrap_or_else(|err| err.exit());
+
+    // The settings from the last launch are used for every option not passed on the command line
+    StandaloneSettings::load::<P>().apply(&mut config, &matches);
+    if settings::settings_requested::<P>(&config) {
+        settings::pick_settings::<P>(&mut config);
+    }
 
     match config.backend {
         config::BackendType::Auto => {
@@ -176,6 +190,8 @@ pub fn nih_export_standalone_with_args<P: Plugin, Args: IntoIterator<Item = Stri
 }
 
 fn run_wrapper<P: Plugin, B: Backend<P>>(backend: B, config: WrapperConfig) -> bool {
+    // The settings are stored once the backend and the plugin have successfully been initialized
+    let settings = StandaloneSettings::from(&config);
     let wrapper = match Wrapper::<P, _>::new(backend, config) {
         Ok(wrapper) => wrapper,
         Err(err) => {
@@ -183,6 +199,7 @@ fn run_wrapper<P: Plugin, B: Backend<P>>(backend: B, config: WrapperConfig) -> b
             return false;
         }
     };
+    settings.save::<P>();
 
     // TODO: Add a repl while the application is running to interact with parameters
     match wrapper.run() {
@@ -14,7 +14,7 @@ use std::num::NonZeroU32;
 use std::ptr::NonNull;
 use std::thread::ScopedJoinHandle;
 
-use super::super::config::WrapperConfig;
+use super::super::config::{MidiPortSpec, WrapperConfig};
 use super::Backend;
 use crate::midi::MidiResult;
 use crate::prelude::{
@@ -33,8 +33,8 @@ pub struct CpalMidir {
     input: Option<CpalDevice>,
     output: CpalDevice,
 
-    midi_input: Mutex<Option<MidirInputDevice>>,
-    midi_output: Mutex<Option<MidirOutputDevice>>,
+    midi_inputs: Mutex<Vec<MidirInputDevice>>,
+    midi_outputs: Mutex<Vec<MidirOutputDevice>>,
 }
 
 /// All data needed for a CPAL input or output stream.
@@ -48,24 +48,30 @@ struct CpalDevice {
 struct MidirInputDevice {
     pub backend: MidiInput,
     pub port: MidiInputPort,
+    /// Determines which channels are received from this device.
+    pub spec: MidiPortSpec,
 }
 
 /// An active `MidirInputDevice`. Transformed back and from this during the `.run()` function.
 struct ActiveMidirInputDevice {
     pub connection: MidiInputConnection<()>,
     pub port: MidiInputPort,
+    pub spec: MidiPortSpec,
 }
 
 /// All data needed to create a Midir output stream.
 struct MidirOutputDevice {
     pub backend: MidiOutput,
     pub port: MidiOutputPort,
+    /// Determines which channels are sent to this device.
+    pub spec: MidiPortSpec,
 }
 
 /// An active `MidirOutputDevice`. Transformed back and from this during the `.run()` function.
 struct ActiveMidirOutputDevice {
     pub connection: MidiOutputConnection,
     pub port: MidiOutputPort,
+    pub spec: MidiPortSpec,
 }
 
 /// Send+Sync wrapper for `Vec<*mut f32>` so we can preallocate channel pointer vectors for use with
@@ -182,35 +188,46 @@ impl<P: Plugin> Backend<P> for CpalMidir {
                 input_parker.park()
             }
 
-            // The output callback can read input events from this ringbuffer
-            let mut midi_input_rb_consumer: Option<rtrb::Consumer<PluginNoteEvent<P>>> = None;
-            let midi_input_connection: Option<ActiveMidirInputDevice> =
-                self.midi_input.lock().take().and_then(|midi_input| {
+            // The output callback can read input events from these ringbuffers, with one
+            // ringbuffer per MIDI input device
+            let mut midi_input_rb_consumers: Vec<rtrb::Consumer<PluginNoteEvent<P>>> = Vec::new();
+            let midi_input_connections: Vec<ActiveMidirInputDevice> = self
+                .midi_inputs
+                .lock()
+                .drain(..)
+                .filter_map(|midi_input| {
                     // Data is sent to the output data callback using a wait-free ring buffer
                     let (rb_producer, rb_consumer) = RingBuffer::new(MIDI_EVENT_QUEUE_CAPACITY);
-                    midi_input_rb_consumer = Some(rb_consumer);
 
                     let result = midi_input.backend.connect(
                         &midi_input.port,
                         "MIDI input",
-                        self.build_midi_input_thread::<P>(rb_producer),
+                        self.build_midi_input_thread::<P>(rb_producer, midi_input.spec.clone()),
                         (),
                     );
 
                     match result {
-                        Ok(connection) => Some(ActiveMidirInputDevice {
-                            connection,
-                            port: midi_input.port,
-                        }),
+                        Ok(connection) => {
+                            midi_input_rb_consumers.push(rb_consumer);
+
+                            Some(ActiveMidirInputDevice {
+                                connection,
+                                port: midi_input.port,
+                                spec: midi_input.spec,
+                            })
+                        }
                         Err(err) => {
                             // We won't retry once this fails
-                            nih_error!("Could not create the MIDI input connection: {err:#}");
-                            midi_input_rb_consumer = None;
+                            nih_error!(
+                                "Could not create the MIDI input connection for '{}': {err:#}",
+                                midi_input.spec.name
+                            );
 
                             None
                         }
                     }
-                });
+                })
+                .collect();
 
             // The output callback can also emit MIDI events. To handle these we'll need to spawn
             // our own thread. This can be simplified a lot by using the `MidiOutputConnection`
@@ -219,62 +236,85 @@ impl<P: Plugin> Backend<P> for CpalMidir {
             // NOTE: This uses crossbeam channels instead of rtrb specifically for the optional
             //        blocking API. This lets the MIDI sending thread sleep when there's no work to
             //        do.
+            //
+            // All MIDI output devices are handled by the same thread. Every event is sent to every
+            // device whose channel filter accepts the event.
+            let midi_output_connections: Vec<ActiveMidirOutputDevice> = self
+                .midi_outputs
+                .lock()
+                .drain(..)
+                .filter_map(|midi_output| {
+                    match midi_output
+                        .backend
+                        .connect(&midi_output.port, "MIDI output")
+                    {
+                        Ok(connection) => Some(ActiveMidirOutputDevice {
+                            connection,
+                            port: midi_output.port,
+                            spec: midi_output.spec,
+                        }),
+                        Err(err) => {
+                            nih_error!(
+                                "Could not create the MIDI output connection for '{}': {err:#}",
+                                midi_output.spec.name
+                            );
+
+                            None
+                        }
+                    }
+                })
+                .collect();
             let mut midi_output_rb_producer: Option<crossbeam::channel::Sender<MidiOutputTask<P>>> =
                 None;
-            let midi_output_connection: Option<ScopedJoinHandle<ActiveMidirOutputDevice>> =
-                self.midi_output.lock().take().and_then(|midi_output| {
+            let midi_output_thread: Option<ScopedJoinHandle<Vec<ActiveMidirOutputDevice>>> =
+                if midi_output_connections.is_empty() {
+                    None
+                } else {
                     // This uses crossbeam channels for the reason mentioned above, but to keep
                     // things cohesive we'll use the same naming scheme as we use for rtrb
                     let (sender, receiver) = crossbeam::channel::bounded(MIDI_EVENT_QUEUE_CAPACITY);
                     midi_output_rb_producer = Some(sender);
 
-                    let result = midi_output
-                        .backend
-                        .connect(&midi_output.port, "MIDI output");
-
-                    match result {
-                        Ok(mut connection) => Some(s.spawn(move || {
-                            while let Ok(task) = receiver.recv() {
-                                match task {
-                                    MidiOutputTask::Send(event) => match event.as_midi() {
-                                        Some(MidiResult::Basic(midi_data)) => {
-                                            if let Err(err) = connection.send(&midi_data) {
-                                                nih_error!("Could not send MIDI event: {err}");
-                                            }
-                                        }
-                                        Some(MidiResult::SysEx(padded_sysex_buffer, length)) => {
-                                            // The SysEx buffer may contain padding
-                                            let padded_sysex_buffer = padded_sysex_buffer.borrow();
-                                            nih_debug_assert!(length <= padded_sysex_buffer.len());
-
-                                            if let Err(err) =
-                                                connection.send(&padded_sysex_buffer[..length])
-                                            {
-                                                nih_error!("Could not send MIDI event: {err}");
-                                            }
-                                        }
-                                        None => (),
-                                    },
-                                    MidiOutputTask::Terminate => break,
+                    let mut midi_output_connections = midi_output_connections;
+                    Some(s.spawn(move || {
+                        while let Ok(task) = receiver.recv() {
+                            let event = match task {
+                                MidiOutputTask::Send(event) => event,
+                                MidiOutputTask::Terminate => break,
+                            };
+
+                            // The channel needs to be read before the event is converted
+                            let channel = event.channel();
+                            let mut send = |midi_data: &[u8]| {
+                                for midi_output in midi_output_connections
+                                    .iter_mut()
+                                    .filter(|midi_output| midi_output.spec.accepts_channel(channel))
+                                {
+                                    if let Err(err) = midi_output.connection.send(midi_data) {
+                                        nih_error!("Could not send MIDI event: {err}");
+                                    }
                                 }
-                            }
+                            };
 
-                            // We'll return the same value from the join handle as what ends up
-                            // being stored in `midi_input_connection` to keep this symmetrical with
-                            // the input handling
-                            ActiveMidirOutputDevice {
-                                connection,
-                                port: midi_output.port,
-                            }
-                        })),
-                        Err(err) => {
-                            nih_error!("Could not create the MIDI output connection: {err:#}");
-                            midi_output_rb_producer = None;
+                            match event.as_midi() {
+                                Some(MidiResult::Basic(midi_data)) => send(&midi_data),
+                                Some(MidiResult::SysEx(padded_sysex_buffer, length)) => {
+                                    // The SysEx buffer may contain padding
+                                    let padded_sysex_buffer = padded_sysex_buffer.borrow();
+                                    nih_debug_assert!(length <= padded_sysex_buffer.len());
 
-                            None
+                                    send(&padded_sysex_buffer[..length]);
+                                }
+                                None => (),
+                            }
                         }
-                    }
-                });
+
+                        // We'll return the same value from the join handle as what ends up being
+                        // stored in `midi_input_connections` to keep this symmetrical with the
+                        // input handling
+                        midi_output_connections
+                    }))
+                };
 
             // This thread needs to be blocked until audio processing ends as CPAL processes the
             // streams on another thread instead of blocking
@@ -296,7 +336,7 @@ impl<P: Plugin> Backend<P> for CpalMidir {
                             self.build_output_data_callback::<P, $primitive_type>(
                                 unparker,
                                 input_rb_consumer,
-                                midi_input_rb_consumer,
+                                midi_input_rb_consumers,
                                 // This is a MPMC crossbeam channel instead of an rtrb ringbuffer, and we
                                 // also need it to terminate the thread
                                 midi_output_rb_producer.clone(),
@@ -334,28 +374,32 @@ impl<P: Plugin> Backend<P> for CpalMidir {
 
             // The Midir API requires us to take things out of Options and transform between these
             // structs
-            *self.midi_input.lock() =
-                midi_input_connection.map(|midi_input_connection| MidirInputDevice {
+            *self.midi_inputs.lock() = midi_input_connections
+                .into_iter()
+                .map(|midi_input_connection| MidirInputDevice {
                     backend: midi_input_connection.connection.close().0,
                     port: midi_input_connection.port,
-                });
-            *self.midi_output.lock() =
-                midi_output_connection.map(move |midi_output_connection_handle| {
-                    // The thread needs to be terminated first
-                    midi_output_rb_producer
-                        .expect("Inconsistent internal MIDI output state")
-                        .send(MidiOutputTask::Terminate)
-                        .expect("Could not terminate the MIDI output thread");
-
-                    let midi_output_connection = midi_output_connection_handle
-                        .join()
-                        .expect("MIDI output thread panicked");
-
-                    MidirOutputDevice {
+                    spec: midi_input_connection.spec,
+                })
+                .collect();
+            if let Some(midi_output_thread) = midi_output_thread {
+                // The thread needs to be terminated first
+                midi_output_rb_producer
+                    .expect("Inconsistent internal MIDI output state")
+                    .send(MidiOutputTask::Terminate)
+                    .expect("Could not terminate the MIDI output thread");
+
+                *self.midi_outputs.lock() = midi_output_thread
+                    .join()
+                    .expect("MIDI output thread panicked")
+                    .into_iter()
+                    .map(|midi_output_connection| MidirOutputDevice {
                         backend: midi_output_connection.connection.close(),
                         port: midi_output_connection.port,
-                    }
-                });
+                        spec: midi_output_connection.spec,
+                    })
+                    .collect();
+            }
         });
     }
 }
@@ -374,10 +418,10 @@ impl CpalMidir {
             )
         }
 
-        if config.midi_input.is_none() && P::MIDI_INPUT >= MidiConfig::Basic {
+        if config.midi_input.is_empty() && P::MIDI_INPUT >= MidiConfig::Basic {
             nih_log!("Use the '--midi-input' option to select a MIDI input device.")
         }
-        if config.midi_output.is_none() && P::MIDI_OUTPUT >= MidiConfig::Basic {
+        if config.midi_output.is_empty() && P::MIDI_OUTPUT >= MidiConfig::Basic {
             nih_log!("Use the '--midi-output' option to select a MIDI output device.")
         }
 
@@ -532,87 +576,86 @@ impl CpalMidir {
             nih_warn!("Auxiliary outputs are not supported with this audio backend");
         }
 
-        let midi_input = match &config.midi_input {
-            Some(midi_input_name) => {
-                // Midir lets us preemptively ignore MIDI messages we'll never use like active
-                // sensing and timing, but for maximum flexibility with NIH-plug's SysEx parsing
-                // types (which could technically be used to also parse those things) we won't do
-                // that.
-                let midi_backend = MidiInput::new(P::NAME)
-                    .context("Could not initialize the MIDI input backend")?;
-                let available_ports = midi_backend.ports();
-
-                // In case there somehow is a MIDI port with an empty name, we'll still want to
-                // preserve the behavior of an empty argument resulting in a listing of options.
-                let found_port = if !midi_input_name.is_empty() {
-                    // This API is a bit weird
-                    available_ports
-                        .iter()
-                        .find(|port| midi_backend.port_name(port).as_deref() == Ok(midi_input_name))
-                } else {
-                    None
-                };
+        let mut midi_inputs = Vec::with_capacity(config.midi_input.len());
+        for spec in &config.midi_input {
+            // Midir lets us preemptively ignore MIDI messages we'll never use like active sensing
+            // and timing, but for maximum flexibility with NIH-plug's SysEx parsing types (which
+            // could technically be used to also parse those things) we won't do that. Every port
+            // needs its own backend instance since connecting to a port consumes the backend.
+            let midi_backend =
+                MidiInput::new(P::NAME).context("Could not initialize the MIDI input backend")?;
+            let available_ports = midi_backend.ports();
+
+            // In case there somehow is a MIDI port with an empty name, we'll still want to
+            // preserve the behavior of an empty argument resulting in a listing of options.
+            let found_port = if !spec.name.is_empty() {
+                // This API is a bit weird
+                available_ports
+                    .iter()
+                    .find(|port| midi_backend.port_name(port).as_deref() == Ok(&spec.name))
+            } else {
+                None
+            };
 
-                match found_port {
-                    Some(port) => Some(MidirInputDevice {
-                        backend: midi_backend,
-                        port: port.clone(),
-                    }),
-                    None => {
-                        let mut message = format!(
-                            "Unknown input MIDI device '{midi_input_name}'. Available devices are:"
-                        );
-                        for port in available_ports {
-                            match midi_backend.port_name(&port) {
-                                Ok(device_name) => message.push_str(&format!("\n{device_name}")),
-                                Err(err) => message.push_str(&format!("\nERROR: {err:#}")),
-                            }
+            match found_port {
+                Some(port) => midi_inputs.push(MidirInputDevice {
+                    backend: midi_backend,
+                    port: port.clone(),
+                    spec: spec.clone(),
+                }),
+                None => {
+                    let mut message = format!(
+                        "Unknown input MIDI device '{}'. Available devices are:",
+                        spec.name
+                    );
+                    for port in available_ports {
+                        match midi_backend.port_name(&port) {
+                            Ok(device_name) => message.push_str(&format!("\n{device_name}")),
+                            Err(err) => message.push_str(&format!("\nERROR: {err:#}")),
                         }
-
-                        anyhow::bail!(message);
                     }
+
+                    anyhow::bail!(message);
                 }
             }
-            None => None,
-        };
+        }
 
-        let midi_output = match &config.midi_output {
-            Some(midi_output_name) => {
-                let midi_backend = MidiOutput::new(P::NAME)
-                    .context("Could not initialize the MIDI output backend")?;
-                let available_ports = midi_backend.ports();
+        let mut midi_outputs = Vec::with_capacity(config.midi_output.len());
+        for spec in &config.midi_output {
+            let midi_backend =
+                MidiOutput::new(P::NAME).context("Could not initialize the MIDI output backend")?;
+            let available_ports = midi_backend.ports();
 
-                let found_port = if !midi_output_name.is_empty() {
-                    available_ports.iter().find(|port| {
-                        midi_backend.port_name(port).as_deref() == Ok(midi_output_name)
-                    })
-                } else {
-                    None
-                };
+            let found_port = if !spec.name.is_empty() {
+                available_ports
+                    .iter()
+                    .find(|port| midi_backend.port_name(port).as_deref() == Ok(&spec.name))
+            } else {
+                None
+            };
 
-                match found_port {
-                    Some(port) => Some(MidirOutputDevice {
-                        backend: midi_backend,
-                        port: port.clone(),
-                    }),
-                    None => {
-                        let mut message = format!(
-                            "Unknown output MIDI device '{midi_output_name}'. Available devices \
-                             are:"
-                        );
-                        for port in available_ports {
-                            match midi_backend.port_name(&port) {
-                                Ok(device_name) => message.push_str(&format!("\n{device_name}")),
-                                Err(err) => message.push_str(&format!("\nERROR: {err:#}")),
-                            }
+            match found_port {
+                Some(port) => midi_outputs.push(MidirOutputDevice {
+                    backend: midi_backend,
+                    port: port.clone(),
+                    spec: spec.clone(),
+                }),
+                None => {
+                    let mut message = format!(
+                        "Unknown output MIDI device '{}'. Available devices are:",
+                        spec.name
+                    );
+                    for port in available_ports {
+                        match midi_backend.port_name(&port) {
+                            Ok(device_name) => message.push_str(&format!("\n{device_name}")),
+                            Err(err) => message.push_str(&format!("\nERROR: {err:#}")),
                         }
-
-                        anyhow::bail!(message);
                     }
+
+                    anyhow::bail!(message);
                 }
             }
-            None => None,
-        };
+        }
 
         Ok(CpalMidir {
             config,
@@ -621,8 +664,8 @@ impl CpalMidir {
             input,
             output,
 
-            midi_input: Mutex::new(midi_input),
-            midi_output: Mutex::new(midi_output),
+            midi_inputs: Mutex::new(midi_inputs),
+            midi_outputs: Mutex::new(midi_outputs),
         })
     }
 
@@ -656,12 +699,17 @@ impl CpalMidir {
     fn build_midi_input_thread<P: Plugin>(
         &self,
         mut midi_input_rb_producer: rtrb::Producer<PluginNoteEvent<P>>,
+        spec: MidiPortSpec,
     ) -> impl FnMut(u64, &[u8], &mut ()) + Send + 'static {
         // This callback parses the received MIDI bytes and sends them to a ring buffer
         move |_timing, midi_data, _data| {
             // Since this is system MIDI there's no real useful timing information and we'll set all
             // the timings to the first sample in the buffer
             if let Ok(event) = NoteEvent::from_midi(0, midi_data) {
+                if !spec.accepts(&event) {
+                    return;
+                }
+
                 if midi_input_rb_producer.push(event).is_err() {
                     nih_error!("The MIDI input event queue was full, dropping event");
                 }
@@ -673,7 +721,7 @@ impl CpalMidir {
         &self,
         unparker: Unparker,
         mut input_rb_consumer: Option<rtrb::Consumer<f32>>,
-        mut input_event_rb_consumer: Option<rtrb::Consumer<PluginNoteEvent<P>>>,
+        mut input_event_rb_consumers: Vec<rtrb::Consumer<PluginNoteEvent<P>>>,
         mut output_event_rb_producer: Option<crossbeam::channel::Sender<MidiOutputTask<P>>>,
         mut cb: impl FnMut(
                 &mut Buffer,
@@ -750,19 +798,15 @@ impl CpalMidir {
             )));
         }
 
-        let mut midi_input_events = Vec::with_capacity(MIDI_EVENT_QUEUE_CAPACITY);
+        let mut midi_input_events =
+            Vec::with_capacity(MIDI_EVENT_QUEUE_CAPACITY * self.config.midi_input.len().max(1));
         let mut midi_output_events = Vec::with_capacity(MIDI_EVENT_QUEUE_CAPACITY);
 
         // Can't borrow from `self` in the callback
         let config = self.config.clone();
-        let mut num_processed_samples = 0usize;
         move |data, _info| {
-            let mut transport = Transport::new(config.sample_rate);
-            transport.pos_samples = Some(num_processed_samples as i64);
-            transport.tempo = Some(config.tempo as f64);
-            transport.time_sig_numerator = Some(config.timesig_num as i32);
-            transport.time_sig_denominator = Some(config.timesig_denom as i32);
-            transport.playing = true;
+            // CPAL doesn't have a transport, so the wrapper's transport simulator fills in the rest
+            let transport = Transport::new(config.sample_rate);
 
             // If an input was configured, then the output buffer is filled with (interleaved) input
             // samples. Otherwise it gets filled with silence. There is no need to zero out any of
@@ -877,8 +921,10 @@ impl CpalMidir {
                 };
 
                 midi_input_events.clear();
-                if let Some(input_event_rb_consumer) = &mut input_event_rb_consumer {
-                    if let Ok(event) = input_event_rb_consumer.pop() {
+                // All events from all MIDI input devices are passed to the plugin. Each ring buffer
+                // holds at most `MIDI_EVENT_QUEUE_CAPACITY` events, so this never reallocates.
+                for input_event_rb_consumer in &mut input_event_rb_consumers {
+                    while let Ok(event) = input_event_rb_consumer.pop() {
                         midi_input_events.push(event);
                     }
                 }
@@ -920,8 +966,6 @@ impl CpalMidir {
                     }
                 }
             }
-
-            num_processed_samples += buffer_size;
         }
     }
 }
@@ -95,16 +95,12 @@ impl<P: Plugin> Backend<P> for Dummy {
 
         // This queue will never actually be used
         let mut midi_output_events = Vec::with_capacity(1024);
-        let mut num_processed_samples = 0usize;
         loop {
             let period_start = Instant::now();
 
-            let mut transport = Transport::new(self.config.sample_rate);
-            transport.pos_samples = Some(num_processed_samples as i64);
-            transport.tempo = Some(self.config.tempo as f64);
-            transport.time_sig_numerator = Some(self.config.timesig_num as i32);
-            transport.time_sig_denominator = Some(self.config.timesig_denom as i32);
-            transport.playing = true;
+            // This backend doesn't have a transport of its own, so the wrapper's transport
+            // simulator fills in the rest
+            let transport = Transport::new(self.config.sample_rate);
 
             for channel in &mut main_io_storage {
                 channel.fill(0.0);
@@ -170,8 +166,6 @@ impl<P: Plugin> Backend<P> for Dummy {
                 break;
             }
 
-            num_processed_samples += num_samples;
-
             let period_end = Instant::now();
             std::thread::sleep((period_start + interval).saturating_duration_since(period_end));
         }
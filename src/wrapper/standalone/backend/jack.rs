@@ -11,7 +11,7 @@ use jack::{
 };
 use parking_lot::Mutex;
 
-use super::super::config::WrapperConfig;
+use super::super::config::{MidiPortSpec, WrapperConfig};
 use super::Backend;
 use crate::midi::MidiResult;
 use crate::prelude::{
@@ -33,8 +33,11 @@ pub struct Jack {
     main_outputs: Arc<Mutex<Vec<Port<AudioOut>>>>,
     aux_input_ports: Arc<Mutex<Vec<Vec<Port<AudioIn>>>>>,
     aux_output_ports: Arc<Mutex<Vec<Vec<Port<AudioOut>>>>>,
-    midi_input: Option<Arc<Port<MidiIn>>>,
-    midi_output: Option<Arc<Mutex<Port<MidiOut>>>>,
+    /// The MIDI input ports, along with the filter for the connection made for each port. There is
+    /// a single unfiltered port when no connections were specified.
+    midi_inputs: Arc<Vec<(Port<MidiIn>, Option<MidiPortSpec>)>>,
+    /// The same as `midi_inputs`, but for the outputs.
+    midi_outputs: Arc<Mutex<Vec<(Port<MidiOut>, Option<MidiPortSpec>)>>>,
 }
 
 /// Send+Sync wrapper for `Vec<*mut f32>` so we can preallocate channel pointer vectors for use with
@@ -108,13 +111,12 @@ impl<P: Plugin> Backend<P> for Jack {
         let parker = Parker::new();
         let unparker = parker.unparker().clone();
 
-        let config = self.config.clone();
         let main_inputs = self.main_inputs.clone();
         let main_outputs = self.main_outputs.clone();
         let aux_input_ports = self.aux_input_ports.clone();
         let aux_output_ports = self.aux_output_ports.clone();
-        let midi_input = self.midi_input.clone();
-        let midi_output = self.midi_output.clone();
+        let midi_inputs = self.midi_inputs.clone();
+        let midi_outputs = self.midi_outputs.clone();
         let process_handler = ClosureProcessHandler::new(move |client, ps| {
             // In theory we could handle `num_frames <= buffer_size`, but JACK will never chop up
             // buffers like that so we'll just make it easier for ourselves by not supporting that
@@ -128,11 +130,9 @@ impl<P: Plugin> Backend<P> for Jack {
                 return Control::Quit;
             }
 
+            // If the JACK transport can't be queried, then the wrapper's transport simulator is used
+            // instead
             let mut transport = Transport::new(client.sample_rate() as f32);
-            transport.tempo = Some(config.tempo as f64);
-            transport.time_sig_numerator = Some(config.timesig_num as i32);
-            transport.time_sig_denominator = Some(config.timesig_denom as i32);
-
             if let Ok(jack_transport) = client.transport().query() {
                 transport.pos_samples = Some(jack_transport.pos.frame() as i64);
                 transport.playing = jack_transport.state == jack::TransportState::Rolling;
@@ -240,12 +240,29 @@ impl<P: Plugin> Backend<P> for Jack {
             };
 
             input_events.clear();
-            if let Some(midi_input) = &midi_input {
-                input_events.extend(midi_input.iter(ps).filter_map(|midi| {
-                    let timing = clamp_input_event_timing(midi.time, num_frames);
-
-                    NoteEvent::from_midi(timing, midi.bytes).ok()
-                }));
+            for (midi_input, spec) in midi_inputs.iter() {
+                input_events.extend(
+                    midi_input
+                        .iter(ps)
+                        .filter_map(|midi| {
+                            let timing = clamp_input_event_timing(midi.time, num_frames);
+
+                            NoteEvent::from_midi(timing, midi.bytes).ok()
+                        })
+                        .filter(|event| spec.as_ref().map_or(true, |spec| spec.accepts(event))),
+                );
+            }
+            if midi_inputs.len() > 1 {
+                // Events from different ports need to be interleaved since the plugin expects them
+                // to be in order. `sort_by_key()` allocates, so this uses an in-place insertion
+                // sort instead. That also keeps simultaneous events in order.
+                for i in 1..input_events.len() {
+                    let mut j = i;
+                    while j > 0 && input_events[j - 1].timing() > input_events[j].timing() {
+                        input_events.swap(j - 1, j);
+                        j -= 1;
+                    }
+                }
             }
 
             output_events.clear();
@@ -260,14 +277,16 @@ impl<P: Plugin> Backend<P> for Jack {
                 &input_events,
                 &mut output_events,
             ) {
-                if let Some(midi_output) = &midi_output {
-                    let mut midi_output = midi_output.lock();
+                for (midi_output, spec) in midi_outputs.lock().iter_mut() {
                     let mut midi_writer = midi_output.writer(ps);
-                    for event in output_events.drain(..) {
+                    for event in output_events
+                        .iter()
+                        .filter(|event| spec.as_ref().map_or(true, |spec| spec.accepts(*event)))
+                    {
                         // Out of bounds events are clamped to the buffer's size
                         let timing = clamp_output_event_timing(event.timing(), num_frames);
 
-                        match event.as_midi() {
+                        match event.clone().as_midi() {
                             Some(MidiResult::Basic(midi_data)) => {
                                 let write_result = midi_writer.write(&jack::RawMidi {
                                     time: timing,
@@ -402,19 +421,35 @@ impl Jack {
             aux_output_ports.push(ports);
         }
 
-        let midi_input = if P::MIDI_INPUT >= MidiConfig::Basic {
-            Some(Arc::new(client.register_port("midi_input", MidiIn)?))
-        } else {
-            None
-        };
+        // Every MIDI connection gets its own port so the channel filters can be applied per
+        // connection. If no connections are specified, then a single unfiltered port is created.
+        let mut midi_inputs = Vec::new();
+        if P::MIDI_INPUT >= MidiConfig::Basic {
+            if config.connect_jack_midi_input.is_empty() {
+                midi_inputs.push((client.register_port("midi_input", MidiIn)?, None));
+            }
+            for (i, spec) in config.connect_jack_midi_input.iter().enumerate() {
+                let port_name = midi_port_name("midi_input", i);
+                midi_inputs.push((
+                    client.register_port(&port_name, MidiIn)?,
+                    Some(spec.clone()),
+                ));
+            }
+        }
 
-        let midi_output = if P::MIDI_OUTPUT >= MidiConfig::Basic {
-            Some(Arc::new(Mutex::new(
-                client.register_port("midi_output", MidiOut)?,
-            )))
-        } else {
-            None
-        };
+        let mut midi_outputs = Vec::new();
+        if P::MIDI_OUTPUT >= MidiConfig::Basic {
+            if config.connect_jack_midi_output.is_empty() {
+                midi_outputs.push((client.register_port("midi_output", MidiOut)?, None));
+            }
+            for (i, spec) in config.connect_jack_midi_output.iter().enumerate() {
+                let port_name = midi_port_name("midi_output", i);
+                midi_outputs.push((
+                    client.register_port(&port_name, MidiOut)?,
+                    Some(spec.clone()),
+                ));
+            }
+        }
 
         Ok(Self {
             audio_io_layout,
@@ -425,8 +460,8 @@ impl Jack {
             main_outputs: Arc::new(Mutex::new(main_outputs)),
             aux_input_ports: Arc::new(Mutex::new(aux_input_ports)),
             aux_output_ports: Arc::new(Mutex::new(aux_output_ports)),
-            midi_input,
-            midi_output,
+            midi_inputs: Arc::new(midi_inputs),
+            midi_outputs: Arc::new(Mutex::new(midi_outputs)),
         })
     }
 
@@ -465,21 +500,31 @@ impl Jack {
             }
         }
 
-        if let (Some(port), Some(port_name)) =
-            (&self.midi_input, &self.config.connect_jack_midi_input)
-        {
-            if let Err(err) = client.connect_ports_by_name(port_name, &port.name()?) {
-                nih_error!("Could not connect to '{port_name}': {err}");
+        for (port, spec) in self.midi_inputs.iter() {
+            if let Some(spec) = spec {
+                if let Err(err) = client.connect_ports_by_name(&spec.name, &port.name()?) {
+                    nih_error!("Could not connect to '{}': {err}", spec.name);
+                }
             }
         }
-        if let (Some(port), Some(port_name)) =
-            (&self.midi_output, &self.config.connect_jack_midi_output)
-        {
-            if let Err(err) = client.connect_ports_by_name(&port.lock().name()?, port_name) {
-                nih_error!("Could not connect to '{port_name}': {err}");
+        for (port, spec) in self.midi_outputs.lock().iter() {
+            if let Some(spec) = spec {
+                if let Err(err) = client.connect_ports_by_name(&port.name()?, &spec.name) {
+                    nih_error!("Could not connect to '{}': {err}", spec.name);
+                }
             }
         }
 
         Ok(())
     }
 }
+
+/// The name for the `index`th MIDI port. The first port is called `base_name` so a single port has
+/// the same name as before, and subsequent ports are numbered starting from 2.
+fn midi_port_name(base_name: &str, index: usize) -> String {
+    if index == 0 {
+        base_name.to_owned()
+    } else {
+        format!("{base_name}_{}", index + 1)
+    }
+}
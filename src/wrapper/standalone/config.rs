@@ -1,7 +1,11 @@
 use clap::{Parser, ValueEnum};
+use std::fmt::Display;
 use std::num::NonZeroU32;
+use std::path::PathBuf;
 
-use crate::prelude::{AudioIOLayout, Plugin};
+use super::transport;
+use crate::prelude::{AudioIOLayout, NoteEvent, Plugin, SysExMessage};
+use crate::util::Tuning;
 
 /// Configuration for a standalone plugin that would normally be provided by the DAW.
 #[derive(Debug, Clone, Parser)]
@@ -31,6 +35,18 @@ pub struct WrapperConfig {
     /// This setting is ignored when using the JACK backend.
     #[clap(value_parser, short = 'p', long, default_value = "512")]
     pub period_size: u32,
+    /// Pick the audio backend, devices, sample rate, buffer size, and MIDI devices in a settings
+    /// window before starting the plugin.
+    ///
+    /// The settings are picked on the terminal instead if the window cannot be shown or when
+    /// running with '--headless'. The window is also shown on the first launch outside of a
+    /// terminal, and when Shift is held while launching the standalone on Windows and macOS.
+    ///
+    /// The settings from the last launch are stored in a per-plugin configuration file and are
+    /// reused for the next launch. Options passed on the command line take precedence over the
+    /// stored settings.
+    #[clap(long)]
+    pub settings: bool,
 
     /// The input device for the ALSA, CoreAudio, and WASAPI backends. No input will be connected if
     /// this is not specified.
@@ -43,16 +59,21 @@ pub struct WrapperConfig {
     /// Specifying an empty string or other invalid value will list all available output devices.
     #[clap(value_parser, long)]
     pub output_device: Option<String>,
-    /// The input MIDI device for the ALSA, CoreAudio, and WASAPI backends.
+    /// The input MIDI device for the ALSA, CoreAudio, and WASAPI backends. Can be used multiple
+    /// times to receive MIDI from more than one device.
     ///
-    /// Specifying an empty string or other invalid value will list all available MIDI inputs.
-    #[clap(value_parser, long)]
-    pub midi_input: Option<String>,
-    /// The output output device for the ALSA, CoreAudio, and WASAPI backends.
+    /// Append '@' followed by a comma separated list of channels or channel ranges ('foo@1,3-4') to
+    /// only receive events on those MIDI channels from this device. Specifying an empty string or
+    /// other invalid value will list all available MIDI inputs.
+    #[clap(value_parser = MidiPortSpec::parse, long)]
+    pub midi_input: Vec<MidiPortSpec>,
+    /// The output output device for the ALSA, CoreAudio, and WASAPI backends. Can be used multiple
+    /// times to send MIDI to more than one device.
     ///
-    /// Specifying an empty string or other invalid value will list all available MIDI output.
-    #[clap(value_parser, long)]
-    pub midi_output: Option<String>,
+    /// Channels can be filtered the same way as with '--midi-input'. Specifying an empty string or
+    /// other invalid value will list all available MIDI output.
+    #[clap(value_parser = MidiPortSpec::parse, long)]
+    pub midi_output: Vec<MidiPortSpec>,
 
     /// If set to a port name ('foo:bar_1'), then all all inputs will be connected to that port. If
     /// the option is set to a comma separated list of port names ('foo:bar_1,foo:bar_2') then the
@@ -64,16 +85,52 @@ pub struct WrapperConfig {
     pub connect_jack_inputs: Option<String>,
 
     /// If set, then the plugin's MIDI input port will be connected to this JACK MIDI output port.
+    /// When used multiple times, a separate MIDI input port is created for every connection.
     ///
-    /// This option is only used with the JACK backend.
-    #[clap(value_parser, long)]
-    pub connect_jack_midi_input: Option<String>,
+    /// Channels can be filtered the same way as with '--midi-input'. This option is only used with
+    /// the JACK backend.
+    #[clap(value_parser = MidiPortSpec::parse, long)]
+    pub connect_jack_midi_input: Vec<MidiPortSpec>,
 
     /// If set, then the plugin's MIDI output port will be connected to this JACK MIDI input port.
+    /// When used multiple times, a separate MIDI output port is created for every connection.
     ///
-    /// This option is only used with the JACK backend.
+    /// Channels can be filtered the same way as with '--midi-input'. This option is only used with
+    /// the JACK backend.
+    #[clap(value_parser = MidiPortSpec::parse, long)]
+    pub connect_jack_midi_output: Vec<MidiPortSpec>,
+
+    /// The session the plugin's state is restored from on launch and saved to on exit.
+    ///
+    /// This can either be the name of a session stored in the plugin's configuration directory, or
+    /// a path to a '.json' file. Uses the 'default' session if not set.
     #[clap(value_parser, long)]
-    pub connect_jack_midi_output: Option<String>,
+    pub state: Option<String>,
+    /// Start with the plugin's default state and don't save the state on exit.
+    #[clap(long, conflicts_with = "state")]
+    pub no_state: bool,
+
+    /// If set, then an OSC server listens on this UDP port for remote control.
+    ///
+    /// Sending a value to '/param/<id>' sets that parameter's normalized value, and a message
+    /// without arguments requests the current value. Parameter changes and the
+    /// '/meter/output' and '/meter/dsp_load' meters are sent back to every client that has sent a
+    /// message to the server.
+    #[clap(value_parser, long)]
+    pub osc_port: Option<u16>,
+    /// An additional address ('host:port') the OSC feedback is always sent to.
+    ///
+    /// This option is only used when '--osc-port' is set.
+    #[clap(value_parser, long, requires = "osc_port")]
+    pub osc_feedback: Option<std::net::SocketAddr>,
+
+    /// Don't open the editor, and process audio and MIDI until the application is interrupted
+    /// with Ctrl+C.
+    ///
+    /// This doesn't require a display server, so it can be used to run plugins on servers and
+    /// embedded devices. A status line with the DSP load is shown when running in a terminal.
+    #[clap(long)]
+    pub headless: bool,
 
     /// The editor's DPI scaling factor.
     ///
@@ -93,6 +150,24 @@ pub struct WrapperConfig {
     /// The time signature's denominator.
     #[clap(value_parser, long, default_value = "4")]
     pub timesig_denom: u32,
+    /// Loop the transport between these one-indexed bars ('1-4'), including the end bar.
+    ///
+    /// This option and the other transport options are ignored when the JACK transport is used.
+    #[clap(value_parser = transport::parse_loop_bars, long = "loop", value_name = "BARS")]
+    pub loop_bars: Option<(u32, u32)>,
+    /// Start with the transport paused. When running in a terminal, typing 'p' followed by enter
+    /// starts and pauses playback. Type '?' for the other transport commands.
+    #[clap(long)]
+    pub paused: bool,
+
+    /// A Scala '.scl' file containing a tuning for the plugin to use instead of 12-TET.
+    #[clap(value_parser, long)]
+    pub scl: Option<PathBuf>,
+    /// A Scala '.kbm' keyboard mapping file for the tuning set with '--scl'.
+    ///
+    /// This option is only used when '--scl' is also set.
+    #[clap(value_parser, long)]
+    pub kbm: Option<PathBuf>,
 }
 
 /// Determines which audio and MIDI backend should be used.
@@ -117,6 +192,103 @@ pub enum BackendType {
     Dummy,
 }
 
+/// A MIDI device or port name with an optional set of MIDI channels, parsed from
+/// `NAME[@CHANNELS]`. Events on other channels are not passed through this port.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MidiPortSpec {
+    pub name: String,
+    /// The MIDI channels events are passed through for as a bit mask. The least significant bit
+    /// corresponds to the first channel.
+    pub channels: u16,
+}
+
+impl MidiPortSpec {
+    /// Parse a `NAME[@CHANNELS]` port specification. `CHANNELS` is a comma separated list of
+    /// one-indexed channels or channel ranges, like `1,3-4`.
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let Some((name, channels_str)) = spec.rsplit_once('@') else {
+            return Ok(Self {
+                name: spec.to_owned(),
+                channels: u16::MAX,
+            });
+        };
+
+        let parse_channel = |channel: &str| match channel.trim().parse::<u16>() {
+            Ok(channel) if (1..=16).contains(&channel) => Ok(channel - 1),
+            _ => Err(format!(
+                "'{channel}' is not a MIDI channel between 1 and 16"
+            )),
+        };
+
+        let mut channels = 0u16;
+        for range in channels_str.split(',') {
+            let (first, last) = match range.split_once('-') {
+                Some((first, last)) => (parse_channel(first)?, parse_channel(last)?),
+                None => (parse_channel(range)?, parse_channel(range)?),
+            };
+            if first > last {
+                return Err(format!("'{range}' is not a valid channel range"));
+            }
+
+            for channel in first..=last {
+                channels |= 1 << channel;
+            }
+        }
+
+        Ok(Self {
+            name: name.to_owned(),
+            channels,
+        })
+    }
+
+    /// Whether `event` should be passed through this port. Events that don't have a channel, like
+    /// SysEx messages, are always passed through.
+    pub fn accepts<S: SysExMessage>(&self, event: &NoteEvent<S>) -> bool {
+        self.accepts_channel(event.channel())
+    }
+
+    /// The same as [`accepts()`][Self::accepts()], but for an event's zero-indexed channel.
+    pub fn accepts_channel(&self, channel: Option<u8>) -> bool {
+        match channel {
+            Some(channel) => self.channels & (1 << channel) != 0,
+            None => true,
+        }
+    }
+}
+
+impl Display for MidiPortSpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name)?;
+        if self.channels == u16::MAX {
+            return Ok(());
+        }
+
+        // Consecutive channels are written as ranges so this round trips through `parse()`
+        let mut ranges = Vec::new();
+        let mut channel = 0;
+        while channel < 16 {
+            if self.channels & (1 << channel) == 0 {
+                channel += 1;
+                continue;
+            }
+
+            let first = channel;
+            while channel + 1 < 16 && self.channels & (1 << (channel + 1)) != 0 {
+                channel += 1;
+            }
+            if first == channel {
+                ranges.push(format!("{}", first + 1));
+            } else {
+                ranges.push(format!("{}-{}", first + 1, channel + 1));
+            }
+
+            channel += 1;
+        }
+
+        write!(f, "@{}", ranges.join(","))
+    }
+}
+
 impl WrapperConfig {
     /// Get the audio IO layout for a plugin based on this configuration. Exits the application if
     /// the IO layout could not be parsed from the config. This doesn't return a `Result` to be able to differentiate between backend-specific errors and config parsing errors.
@@ -179,4 +351,20 @@ impl WrapperConfig {
             _ => P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
         }
     }
+
+    /// Load the tuning specified with the `--scl` and `--kbm` options, or 12-TET if those options
+    /// are not set. Exits the application if the tuning could not be loaded.
+    pub fn tuning_or_exit(&self) -> Tuning {
+        match &self.scl {
+            Some(scl) => match Tuning::load_scala(scl, self.kbm.as_deref()) {
+                Ok(tuning) => tuning,
+                Err(err) => {
+                    nih_error!("Could not load the tuning: {err:#}");
+
+                    std::process::exit(1);
+                }
+            },
+            None => Tuning::default(),
+        }
+    }
 }
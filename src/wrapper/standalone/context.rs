@@ -1,11 +1,15 @@
 use std::sync::Arc;
+use std::time::Duration;
 
 use super::backend::Backend;
 use super::wrapper::{Task, Wrapper};
+use crate::context::gui::{GuiMessage, ParamChangeReceiver, StateSlot, TimerHandle};
 use crate::prelude::{
-    GuiContext, InitContext, ParamPtr, Plugin, PluginApi, PluginNoteEvent, ProcessContext,
-    Transport,
+    GuiContext, InitContext, NoteEvent, ParamIndication, ParamPtr, Plugin, PluginApi,
+    PluginNoteEvent, ProcessContext, ProcessMode, TrackInfo, Transport,
 };
+use crate::wrapper::util::clipboard::Clipboard;
+use crate::wrapper::util::equal_temperament_frequency;
 
 /// An [`InitContext`] implementation for the standalone wrapper.
 pub(crate) struct WrapperInitContext<'a, P: Plugin, B: Backend<P>> {
@@ -24,6 +28,9 @@ pub(crate) struct WrapperProcessContext<'a, P: Plugin, B: Backend<P>> {
     pub(super) input_events_idx: usize,
     pub(super) output_events: &'a mut Vec<PluginNoteEvent<P>>,
     pub(super) transport: Transport,
+    /// The number of auxiliary inputs in the current audio IO layout. Used for
+    /// [`ProcessContext::bus_active()`].
+    pub(super) aux_input_count: usize,
 }
 
 /// A [`GuiContext`] implementation for the wrapper. This is passed to the plugin in
@@ -31,6 +38,8 @@ pub(crate) struct WrapperProcessContext<'a, P: Plugin, B: Backend<P>> {
 /// with the host for things like setting parameters.
 pub(crate) struct WrapperGuiContext<P: Plugin, B: Backend<P>> {
     pub(super) wrapper: Arc<Wrapper<P, B>>,
+    /// The system clipboard, kept alive for as long as the editor exists.
+    pub(super) clipboard: Clipboard,
     #[cfg(debug_assertions)]
     pub(super) param_gesture_checker:
         atomic_refcell::AtomicRefCell<crate::wrapper::util::context_checks::ParamGestureChecker>,
@@ -69,11 +78,32 @@ impl<P: Plugin, B: Backend<P>> ProcessContext<P> for WrapperProcessContext<'_, P
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.wrapper.cancel_background_tasks(tag);
+    }
+
     #[inline]
     fn transport(&self) -> &Transport {
         &self.transport
     }
 
+    fn process_mode(&self) -> ProcessMode {
+        ProcessMode::Realtime
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        // There is no host to get this information from
+        None
+    }
+
+    fn bypassed(&self) -> bool {
+        self.wrapper.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.aux_input_count
+    }
+
     fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
         // We'll pretend we're a queue, choo choo
         if self.input_events_idx < self.input_events.len() {
@@ -94,9 +124,25 @@ impl<P: Plugin, B: Backend<P>> ProcessContext<P> for WrapperProcessContext<'_, P
         self.wrapper.set_latency_samples(samples)
     }
 
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
+        // Notes the keyboard mapping leaves unmapped fall back to 12-TET
+        self.wrapper
+            .tuning
+            .frequency(note)
+            .unwrap_or_else(|| equal_temperament_frequency(note))
+    }
+
+    fn set_tail_seconds(&self, _seconds: f32) {
+        // The standalone backends never suspend the plugin, so there's no tail to report
+    }
+
     fn set_current_voice_capacity(&self, _capacity: u32) {
         // This is only supported by CLAP
     }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T)) {
+        self.wrapper.gui_messages.drain(handler)
+    }
 }
 
 impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
@@ -109,6 +155,48 @@ impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
         true
     }
 
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        // The window can't be rescaled while it is open, so the editor's UI scale is only used
+        // the next time the standalone is started
+        if let Some(editor) = self.wrapper.editor.borrow().as_ref() {
+            editor.lock().set_ui_scale(scale);
+        }
+
+        false
+    }
+
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool {
+        self.wrapper.queue_gui_note_event(event)
+    }
+
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool {
+        self.wrapper.gui_messages.send(message)
+    }
+
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+        self.wrapper.param_changes.subscribe()
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn dsp_load(&self) -> f32 {
+        self.wrapper.dsp_load.load()
+    }
+
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle {
+        self.wrapper.schedule_timer_once(delay, callback)
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        self.wrapper.schedule_timer_repeating(interval, callback)
+    }
+
     unsafe fn raw_begin_set_parameter(&self, _param: ParamPtr) {
         // Since there's no automation being recorded here, gestures don't mean anything
 
@@ -159,4 +247,44 @@ impl<P: Plugin, B: Backend<P>> GuiContext for WrapperGuiContext<P, B> {
     fn set_state(&self, state: crate::wrapper::state::PluginState) {
         self.wrapper.set_state_object_from_gui(state)
     }
+
+    fn store_state_slot(&self, slot: StateSlot) {
+        self.wrapper.state_slots.store(self, slot)
+    }
+
+    fn swap_ab(&self) -> StateSlot {
+        self.wrapper.state_slots.swap(self)
+    }
+
+    fn copy_a_to_b(&self) {
+        self.wrapper.state_slots.copy_a_to_b(self)
+    }
+
+    fn active_state_slot(&self) -> StateSlot {
+        self.wrapper.state_slots.active()
+    }
+
+    fn show_param_context_menu(&self, _param: ParamPtr, _position: (f32, f32)) -> bool {
+        // There's no host to show a context menu for us
+        false
+    }
+
+    fn request_keyboard_focus(&self) -> bool {
+        // The editor owns the window, so there's no host that could steal keystrokes
+        true
+    }
+
+    fn release_keyboard_focus(&self) {}
+
+    fn set_clipboard_text(&self, text: &str) -> bool {
+        self.clipboard.set_text(text)
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.clipboard.text()
+    }
+
+    fn param_indication(&self, _param: ParamPtr) -> Option<ParamIndication> {
+        None
+    }
 }
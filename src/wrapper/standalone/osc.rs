@@ -0,0 +1,286 @@
+//! An OSC server for remote controlling the standalone's parameters, enabled with `--osc-port`.
+//!
+//! The server understands the following messages:
+//!
+//! - `/param/<id> <value>`: sets a parameter. Floats, doubles, and integers are treated as
+//!   normalized values, booleans map to 0 and 1, and strings are parsed using the parameter's
+//!   string conversion functions.
+//! - `/param/<id>` without any arguments: sends the parameter's current value back.
+//! - `/params` without any arguments: sends every parameter's current value back.
+//!
+//! Every client that sends a message is remembered, and it will receive feedback from then on:
+//!
+//! - `/param/<id> <normalized>` and `/param/<id>/text <string>` whenever a parameter changes,
+//!   regardless of whether that change came from OSC, the editor, or a restored state.
+//! - `/meter/output <peak>...` with the peak amplitude for every main output channel since the last
+//!   update, and `/meter/dsp_load <load>` with the plugin's DSP load.
+
+use atomic_float::AtomicF32;
+use rosc::{OscMessage, OscPacket, OscType};
+use std::collections::HashMap;
+use std::io;
+use std::net::{SocketAddr, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use super::backend::Backend;
+use super::wrapper::Wrapper;
+use crate::prelude::{ParamPtr, Plugin};
+
+/// How often parameter and meter feedback is sent to the clients.
+const FEEDBACK_INTERVAL: Duration = Duration::from_millis(33);
+/// The maximum size of a received UDP packet.
+const MAX_PACKET_SIZE: usize = 65507;
+/// Clients that haven't sent anything for this long don't receive feedback anymore.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(60 * 10);
+
+/// Peak amplitudes for the plugin's main output channels. The audio thread only ever increases the
+/// values, and the OSC server resets them when sending the meter feedback.
+#[derive(Debug, Default)]
+pub struct OutputPeaks {
+    peaks: Box<[AtomicF32]>,
+}
+
+impl OutputPeaks {
+    pub fn new(num_channels: usize) -> Self {
+        Self {
+            peaks: (0..num_channels).map(|_| AtomicF32::new(0.0)).collect(),
+        }
+    }
+
+    /// Update the peaks with the contents of a buffer. Does nothing if the meter was created
+    /// without any channels, which is the case when the OSC server is disabled.
+    pub fn update(&self, channels: &[&mut [f32]]) {
+        for (channel, peak) in channels.iter().zip(self.peaks.iter()) {
+            let channel_peak = channel
+                .iter()
+                .fold(0.0f32, |peak, sample| peak.max(sample.abs()));
+            if channel_peak > peak.load(Ordering::Relaxed) {
+                peak.store(channel_peak, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Get the peaks since the last call, resetting them in the process.
+    fn take(&self) -> impl Iterator<Item = f32> + '_ {
+        self.peaks
+            .iter()
+            .map(|peak| peak.swap(0.0, Ordering::Relaxed))
+    }
+}
+
+/// Spawn the OSC server on a new thread. The server stops once `should_terminate` becomes `true`.
+/// Returns an error if the socket could not be bound.
+pub fn spawn<P: Plugin, B: Backend<P>>(
+    wrapper: Arc<Wrapper<P, B>>,
+    port: u16,
+    feedback_address: Option<SocketAddr>,
+    should_terminate: Arc<AtomicBool>,
+) -> io::Result<JoinHandle<()>> {
+    let socket = UdpSocket::bind(("0.0.0.0", port))?;
+    // This makes sure the thread can check `should_terminate` and send feedback regularly
+    socket.set_read_timeout(Some(FEEDBACK_INTERVAL))?;
+    nih_log!("Listening for OSC messages on UDP port {port}");
+
+    Ok(thread::spawn(move || {
+        let mut server = OscServer {
+            wrapper,
+            socket,
+            feedback_address,
+            clients: HashMap::new(),
+            sent_values: HashMap::new(),
+        };
+
+        server.run(&should_terminate)
+    }))
+}
+
+struct OscServer<P: Plugin, B: Backend<P>> {
+    wrapper: Arc<Wrapper<P, B>>,
+    socket: UdpSocket,
+    /// The address passed to `--osc-feedback`, if any.
+    feedback_address: Option<SocketAddr>,
+    /// The clients that receive feedback, along with the last time they sent a message.
+    clients: HashMap<SocketAddr, Instant>,
+    /// The last normalized value that was sent for a parameter. Used to only send changed values.
+    sent_values: HashMap<ParamPtr, f32>,
+}
+
+impl<P: Plugin, B: Backend<P>> OscServer<P, B> {
+    fn run(&mut self, should_terminate: &AtomicBool) {
+        let mut buffer = vec![0u8; MAX_PACKET_SIZE];
+        let mut last_feedback = Instant::now();
+        while !should_terminate.load(Ordering::SeqCst) {
+            match self.socket.recv_from(&mut buffer) {
+                Ok((size, client)) => {
+                    self.clients.insert(client, Instant::now());
+
+                    match rosc::decoder::decode_udp(&buffer[..size]) {
+                        Ok((_, packet)) => self.handle_packet(packet, client),
+                        Err(err) => nih_warn!("Could not decode OSC packet from {client}: {err:?}"),
+                    }
+                }
+                Err(err)
+                    if err.kind() == io::ErrorKind::WouldBlock
+                        || err.kind() == io::ErrorKind::TimedOut => {}
+                Err(err) => {
+                    nih_error!("Error while receiving OSC messages: {err}");
+                    break;
+                }
+            }
+
+            if last_feedback.elapsed() >= FEEDBACK_INTERVAL {
+                self.send_feedback();
+                last_feedback = Instant::now();
+            }
+        }
+    }
+
+    fn handle_packet(&mut self, packet: OscPacket, client: SocketAddr) {
+        match packet {
+            OscPacket::Message(message) => self.handle_message(message, client),
+            OscPacket::Bundle(bundle) => {
+                // Time tags are ignored, everything is handled immediately
+                for packet in bundle.content {
+                    self.handle_packet(packet, client);
+                }
+            }
+        }
+    }
+
+    fn handle_message(&mut self, message: OscMessage, client: SocketAddr) {
+        if message.addr == "/params" {
+            let wrapper = self.wrapper.clone();
+            for (param_id, param_ptr) in wrapper.param_ids() {
+                self.send_param_value(param_id, param_ptr, &[client]);
+            }
+
+            return;
+        }
+
+        let Some(param_id) = message.addr.strip_prefix("/param/") else {
+            nih_warn!("Unknown OSC address '{}'", message.addr);
+            return;
+        };
+        let Some(param_ptr) = self.wrapper.param_ptr_from_id(param_id) else {
+            nih_warn!(
+                "Unknown parameter '{param_id}' in OSC address '{}'",
+                message.addr
+            );
+            return;
+        };
+
+        let normalized_value = match message.args.first() {
+            None => {
+                self.send_param_value(param_id, param_ptr, &[client]);
+                return;
+            }
+            Some(OscType::Float(value)) => Some(*value),
+            Some(OscType::Double(value)) => Some(*value as f32),
+            Some(OscType::Int(value)) => Some(*value as f32),
+            Some(OscType::Long(value)) => Some(*value as f32),
+            Some(OscType::Bool(value)) => Some(if *value { 1.0 } else { 0.0 }),
+            Some(OscType::String(value)) => unsafe { param_ptr.string_to_normalized_value(value) },
+            Some(arg) => {
+                nih_warn!("Unsupported OSC argument for '{}': {arg:?}", message.addr);
+                return;
+            }
+        };
+
+        match normalized_value {
+            Some(normalized_value) => {
+                self.wrapper
+                    .set_parameter(param_ptr, normalized_value.clamp(0.0, 1.0));
+            }
+            None => nih_warn!("Could not parse the value for '{}'", message.addr),
+        }
+    }
+
+    /// Send changed parameter values and the meters to all clients.
+    fn send_feedback(&mut self) {
+        let now = Instant::now();
+        self.clients
+            .retain(|_, last_seen| now.duration_since(*last_seen) < CLIENT_TIMEOUT);
+
+        let clients: Vec<SocketAddr> = self
+            .clients
+            .keys()
+            .copied()
+            .chain(self.feedback_address)
+            .collect();
+        if clients.is_empty() {
+            return;
+        }
+
+        // The values are compared with the previously sent values so this also catches parameter
+        // changes made from the editor or by restoring state
+        let wrapper = self.wrapper.clone();
+        let changed_params: Vec<(&str, ParamPtr)> = wrapper
+            .param_ids()
+            .filter(|(_, param_ptr)| {
+                let value = unsafe { param_ptr.unmodulated_normalized_value() };
+                self.sent_values.get(param_ptr) != Some(&value)
+            })
+            .collect();
+        for (param_id, param_ptr) in changed_params {
+            self.send_param_value(param_id, param_ptr, &clients);
+        }
+
+        let output_peaks: Vec<OscType> = wrapper.output_peaks.take().map(OscType::Float).collect();
+        if !output_peaks.is_empty() {
+            self.send(
+                OscMessage {
+                    addr: String::from("/meter/output"),
+                    args: output_peaks,
+                },
+                &clients,
+            );
+        }
+        self.send(
+            OscMessage {
+                addr: String::from("/meter/dsp_load"),
+                args: vec![OscType::Float(wrapper.dsp_load.load())],
+            },
+            &clients,
+        );
+    }
+
+    fn send_param_value(&mut self, param_id: &str, param_ptr: ParamPtr, clients: &[SocketAddr]) {
+        let value = unsafe { param_ptr.unmodulated_normalized_value() };
+        let text = unsafe { param_ptr.normalized_value_to_string(value, true) };
+        self.sent_values.insert(param_ptr, value);
+
+        self.send(
+            OscMessage {
+                addr: format!("/param/{param_id}"),
+                args: vec![OscType::Float(value)],
+            },
+            clients,
+        );
+        self.send(
+            OscMessage {
+                addr: format!("/param/{param_id}/text"),
+                args: vec![OscType::String(text)],
+            },
+            clients,
+        );
+    }
+
+    fn send(&self, message: OscMessage, clients: &[SocketAddr]) {
+        let packet = match rosc::encoder::encode(&OscPacket::Message(message)) {
+            Ok(packet) => packet,
+            Err(err) => {
+                nih_debug_assert_failure!("Could not encode OSC message: {err:?}");
+                return;
+            }
+        };
+
+        for client in clients {
+            if let Err(err) = self.socket.send_to(&packet, client) {
+                nih_warn!("Could not send OSC message to {client}: {err}");
+            }
+        }
+    }
+}
@@ -0,0 +1,651 @@
+//! Persistent audio and MIDI settings for standalone plugins. The settings used for the last
+//! successful launch are stored in a per-plugin configuration file so they don't need to be passed
+//! on the command line every time. Options that are explicitly passed on the command line always
+//! take precedence over the stored settings. The plugin's state is stored in the same directory,
+//! see [`state_path()`].
+
+use anyhow::{Context, Result};
+use clap::parser::ValueSource;
+use clap::{ArgMatches, ValueEnum};
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use self::window::Choice;
+use super::config::{BackendType, MidiPortSpec, WrapperConfig};
+use crate::prelude::Plugin;
+
+mod window;
+
+/// The sample rates listed in the settings window. The current sample rate is added if it's not
+/// one of these.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+const COMMON_SAMPLE_RATES: [f32; 6] = [44100.0, 48000.0, 88200.0, 96000.0, 176400.0, 192000.0];
+/// The period sizes listed in the settings window.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+const COMMON_PERIOD_SIZES: [u32; 8] = [32, 64, 128, 256, 512, 1024, 2048, 4096];
+
+/// The subset of [`WrapperConfig`] that is persisted between launches.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StandaloneSettings {
+    /// The backend's name as used on the command line.
+    pub backend: Option<String>,
+    pub sample_rate: Option<f32>,
+    pub period_size: Option<u32>,
+    pub input_device: Option<String>,
+    pub output_device: Option<String>,
+    /// The MIDI devices in the `NAME[@CHANNELS]` format used on the command line.
+    pub midi_input: Vec<String>,
+    pub midi_output: Vec<String>,
+}
+
+impl StandaloneSettings {
+    /// Load the stored settings for `P`. Returns the default (empty) settings if the plugin has not
+    /// been launched before or if the settings file could not be read.
+    pub fn load<P: Plugin>() -> Self {
+        let Some(path) = settings_path::<P>() else {
+            return Self::default();
+        };
+        if !path.exists() {
+            return Self::default();
+        }
+
+        match std::fs::read_to_string(&path)
+            .map_err(anyhow::Error::from)
+            .and_then(|json| serde_json::from_str(&json).map_err(anyhow::Error::from))
+        {
+            Ok(settings) => settings,
+            Err(err) => {
+                nih_warn!(
+                    "Could not read the standalone settings from '{}': {err:#}",
+                    path.display()
+                );
+                Self::default()
+            }
+        }
+    }
+
+    /// Store these settings so they are used for the next launch.
+    pub fn save<P: Plugin>(&self) {
+        if let Err(err) = self.write::<P>() {
+            nih_warn!("Could not save the standalone settings: {err:#}");
+        }
+    }
+
+    fn write<P: Plugin>(&self) -> Result<()> {
+        let path = settings_path::<P>().context("Could not determine the config directory")?;
+        std::fs::create_dir_all(path.parent().unwrap())
+            .with_context(|| format!("Could not create '{}'", path.parent().unwrap().display()))?;
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Could not write '{}'", path.display()))
+    }
+
+    /// Apply these settings to `config`, skipping any option that was explicitly passed on the
+    /// command line.
+    pub fn apply(self, config: &mut WrapperConfig, matches: &ArgMatches) {
+        let from_command_line =
+            |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if let Some(backend) = self
+            .backend
+            .and_then(|name| BackendType::from_str(&name, true).ok())
+        {
+            if !from_command_line("backend") {
+                config.backend = backend;
+            }
+        }
+        if let Some(sample_rate) = self.sample_rate {
+            if !from_command_line("sample_rate") {
+                config.sample_rate = sample_rate;
+            }
+        }
+        if let Some(period_size) = self.period_size {
+            if !from_command_line("period_size") {
+                config.period_size = period_size;
+            }
+        }
+        if !from_command_line("input_device") && self.input_device.is_some() {
+            config.input_device = self.input_device;
+        }
+        if !from_command_line("output_device") && self.output_device.is_some() {
+            config.output_device = self.output_device;
+        }
+        if !from_command_line("midi_input") && !self.midi_input.is_empty() {
+            config.midi_input = parse_midi_ports(&self.midi_input);
+        }
+        if !from_command_line("midi_output") && !self.midi_output.is_empty() {
+            config.midi_output = parse_midi_ports(&self.midi_output);
+        }
+    }
+}
+
+impl From<&WrapperConfig> for StandaloneSettings {
+    fn from(config: &WrapperConfig) -> Self {
+        Self {
+            backend: config
+                .backend
+                .to_possible_value()
+                .map(|value| value.get_name().to_owned()),
+            sample_rate: Some(config.sample_rate),
+            period_size: Some(config.period_size),
+            input_device: config.input_device.clone(),
+            output_device: config.output_device.clone(),
+            midi_input: config
+                .midi_input
+                .iter()
+                .map(|port| port.to_string())
+                .collect(),
+            midi_output: config
+                .midi_output
+                .iter()
+                .map(|port| port.to_string())
+                .collect(),
+        }
+    }
+}
+
+/// The path to the settings file for `P`.
+fn settings_path<P: Plugin>() -> Option<PathBuf> {
+    Some(plugin_config_dir::<P>()?.join("standalone.json"))
+}
+
+/// The path the plugin's state should be restored from and saved to, or `None` if the state should
+/// not be persisted. See [`WrapperConfig::state`].
+pub fn state_path<P: Plugin>(config: &WrapperConfig) -> Option<PathBuf> {
+    if config.no_state {
+        return None;
+    }
+
+    match &config.state {
+        // A plain name refers to a named session in the plugin's configuration directory
+        Some(name) if is_session_name(name) => Some(
+            plugin_config_dir::<P>()?
+                .join("sessions")
+                .join(format!("{name}.json")),
+        ),
+        Some(path) => Some(PathBuf::from(path)),
+        None => Some(
+            plugin_config_dir::<P>()?
+                .join("sessions")
+                .join("default.json"),
+        ),
+    }
+}
+
+/// Whether the `--state` argument is a session name rather than a path to a file.
+fn is_session_name(state: &str) -> bool {
+    let path = Path::new(state);
+    path.components().count() == 1 && path.extension().is_none()
+}
+
+/// The directory the standalone settings and sessions for `P` are stored in. This is a directory
+/// named after the plugin in the platform's configuration directory.
+fn plugin_config_dir<P: Plugin>() -> Option<PathBuf> {
+    #[cfg(target_os = "macos")]
+    let config_dir = std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support"));
+    #[cfg(target_os = "windows")]
+    let config_dir = std::env::var_os("APPDATA").map(PathBuf::from);
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let config_dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")));
+
+    // Plugin names can contain characters that are not allowed in file names
+    let plugin_dir_name: String = P::NAME
+        .chars()
+        .map(|c| match c {
+            '/' | '\\' | ':' | '*' | '?' | '"' | '<' | '>' | '|' => '_',
+            c => c,
+        })
+        .collect();
+
+    Some(config_dir?.join(plugin_dir_name.trim()))
+}
+
+/// Whether the settings window should be shown before starting the plugin. This is the case when
+/// `--settings` is passed, when Shift is held while launching the standalone, and on the first
+/// launch if the standalone was not started from a terminal. The last two make the settings
+/// reachable when the standalone is launched from a file manager or an application launcher.
+pub fn settings_requested<P: Plugin>(config: &WrapperConfig) -> bool {
+    if config.settings {
+        return true;
+    }
+    if config.headless {
+        return false;
+    }
+
+    let first_launch = settings_path::<P>().is_some_and(|path| !path.exists());
+    window::shift_held() || (first_launch && !std::io::stdin().is_terminal())
+}
+
+/// Let the user pick the audio backend, the audio devices, the sample rate, the period size, and
+/// the MIDI devices in a settings window. The current values in `config` are used as the defaults,
+/// and cancelling the window keeps them. If the window cannot be shown, or when running headless,
+/// the settings are picked on the terminal instead.
+pub fn pick_settings<P: Plugin>(config: &mut WrapperConfig) {
+    if !config.headless && pick_settings_in_window::<P>(config).is_some() {
+        return;
+    }
+
+    if std::io::stdin().is_terminal() {
+        pick_settings_on_terminal::<P>(config);
+    } else {
+        nih_warn!("Could not show the settings window, using the current settings instead");
+    }
+}
+
+/// Show the settings window. Returns `None` if the window could not be shown.
+fn pick_settings_in_window<P: Plugin>(config: &mut WrapperConfig) -> Option<()> {
+    let title = format!("{} Settings", P::NAME);
+    let backend_name = |backend: &BackendType| {
+        backend
+            .to_possible_value()
+            .map(|value| value.get_name().to_owned())
+            .unwrap_or_default()
+    };
+
+    loop {
+        let current_backend = backend_name(&config.backend);
+        let mut choices = vec![Choice::new(
+            "Audio backend",
+            BackendType::value_variants()
+                .iter()
+                .map(backend_name)
+                .collect(),
+            &current_backend,
+        )];
+
+        // The devices depend on the backend. JACK and the dummy backend don't have any devices to
+        // pick from.
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        let host = cpal_host(&config.backend);
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        if let Some(host) = &host {
+            use cpal::traits::{DeviceTrait, HostTrait};
+
+            let device_choice =
+                |label: &str, none_label: &str, devices: Vec<String>, current: &Option<String>| {
+                    let mut options = vec![none_label.to_owned()];
+                    options.extend(devices);
+                    Choice::new(label, options, current.as_deref().unwrap_or(none_label))
+                };
+            let output_devices: Vec<String> = host
+                .output_devices()
+                .map(|devices| devices.flat_map(|d| d.name()).collect())
+                .unwrap_or_default();
+            let input_devices: Vec<String> = host
+                .input_devices()
+                .map(|devices| devices.flat_map(|d| d.name()).collect())
+                .unwrap_or_default();
+            choices.push(device_choice(
+                "Audio output device",
+                "Default",
+                output_devices,
+                &config.output_device,
+            ));
+            choices.push(device_choice(
+                "Audio input device",
+                "None",
+                input_devices,
+                &config.input_device,
+            ));
+
+            choices.push(Choice::new(
+                "Sample rate",
+                COMMON_SAMPLE_RATES
+                    .iter()
+                    .map(|sample_rate| sample_rate.to_string())
+                    .collect(),
+                &config.sample_rate.to_string(),
+            ));
+            choices.push(Choice::new(
+                "Buffer size",
+                COMMON_PERIOD_SIZES
+                    .iter()
+                    .map(|period_size| period_size.to_string())
+                    .collect(),
+                &config.period_size.to_string(),
+            ));
+
+            let midi_inputs: Vec<String> = midir::MidiInput::new(P::NAME)
+                .map(|midi_backend| {
+                    midi_backend
+                        .ports()
+                        .iter()
+                        .flat_map(|port| midi_backend.port_name(port))
+                        .collect()
+                })
+                .unwrap_or_default();
+            let midi_outputs: Vec<String> = midir::MidiOutput::new(P::NAME)
+                .map(|midi_backend| {
+                    midi_backend
+                        .ports()
+                        .iter()
+                        .flat_map(|port| midi_backend.port_name(port))
+                        .collect()
+                })
+                .unwrap_or_default();
+            choices.push(midi_port_choice(
+                "MIDI input device",
+                midi_inputs,
+                &config.midi_input,
+            ));
+            choices.push(midi_port_choice(
+                "MIDI output device",
+                midi_outputs,
+                &config.midi_output,
+            ));
+        }
+
+        if !window::show(&title, &mut choices)? {
+            return Some(());
+        }
+
+        let mut choices = choices.into_iter();
+        let backend_choice = choices.next().unwrap();
+        let new_backend = BackendType::from_str(backend_choice.selected_option(), true)
+            .unwrap_or_else(|_| config.backend.clone());
+
+        #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+        if host.is_some() {
+            let mut next = || choices.next().unwrap();
+            let device = |choice: Choice| match choice.selected {
+                0 => None,
+                _ => Some(choice.selected_option().to_owned()),
+            };
+
+            config.output_device = device(next());
+            config.input_device = device(next());
+            if let Ok(sample_rate) = next().selected_option().parse() {
+                config.sample_rate = sample_rate;
+            }
+            if let Ok(period_size) = next().selected_option().parse() {
+                config.period_size = period_size;
+            }
+            apply_midi_port_choice(&next(), &mut config.midi_input);
+            apply_midi_port_choice(&next(), &mut config.midi_output);
+        }
+
+        // The window only lists the devices for the backend it was opened with, so it's shown
+        // again when a different backend was selected
+        if backend_name(&new_backend) == current_backend {
+            return Some(());
+        }
+        config.backend = new_backend;
+    }
+}
+
+/// A drop-down for picking a single MIDI device. If more than one device or a channel filter is
+/// currently configured, then that configuration is listed as an option so it can be kept.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn midi_port_choice(label: &str, ports: Vec<String>, current: &[MidiPortSpec]) -> Choice {
+    let mut options = vec![String::from("None")];
+    options.extend(ports);
+
+    Choice::new(label, options, &midi_ports_label(current))
+}
+
+/// Update `ports` to match the selected option from [`midi_port_choice()`].
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn apply_midi_port_choice(choice: &Choice, ports: &mut Vec<MidiPortSpec>) {
+    if choice.selected == 0 {
+        ports.clear();
+    } else if choice.selected_option() != midi_ports_label(ports) {
+        *ports = vec![MidiPortSpec {
+            name: choice.selected_option().to_owned(),
+            channels: u16::MAX,
+        }];
+    }
+}
+
+/// The option used for the currently configured MIDI ports in [`midi_port_choice()`].
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn midi_ports_label(ports: &[MidiPortSpec]) -> String {
+    if ports.is_empty() {
+        String::from("None")
+    } else {
+        ports
+            .iter()
+            .map(|port| port.to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// Interactively pick the audio backend, the audio devices, the sample rate, the period size, and
+/// the MIDI devices on the terminal. The current values in `config` are used as the defaults.
+fn pick_settings_on_terminal<P: Plugin>(config: &mut WrapperConfig) {
+    let stdin = std::io::stdin();
+    let mut lines = stdin.lock().lines();
+    let mut prompt = |question: &str| -> Option<String> {
+        print!("{question}: ");
+        std::io::stdout().flush().ok()?;
+        lines
+            .next()?
+            .ok()
+            .map(|line| line.trim().to_owned())
+            .filter(|line| !line.is_empty())
+    };
+
+    println!("Audio and MIDI settings for {}", P::NAME);
+    println!("Press enter to keep the current value.\n");
+
+    let backends = BackendType::value_variants();
+    let backend_names: Vec<String> = backends
+        .iter()
+        .filter_map(|backend| backend.to_possible_value())
+        .map(|value| value.get_name().to_owned())
+        .collect();
+    let current_backend = config
+        .backend
+        .to_possible_value()
+        .map(|value| value.get_name().to_owned());
+    if let Some(idx) = pick_option(
+        &mut prompt,
+        "Audio backend",
+        current_backend.as_deref(),
+        &backend_names,
+        false,
+    ) {
+        config.backend = backends[idx].clone();
+    }
+
+    #[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+    if let Some(host) = cpal_host(&config.backend) {
+        use cpal::traits::{DeviceTrait, HostTrait};
+
+        let output_devices: Vec<String> = host
+            .output_devices()
+            .map(|devices| devices.flat_map(|d| d.name()).collect())
+            .unwrap_or_default();
+        match pick_option(
+            &mut prompt,
+            "Audio output device",
+            config.output_device.as_deref(),
+            &output_devices,
+            true,
+        ) {
+            Some(idx) if idx < output_devices.len() => {
+                config.output_device = Some(output_devices[idx].clone())
+            }
+            Some(_) => config.output_device = None,
+            None => (),
+        }
+
+        let input_devices: Vec<String> = host
+            .input_devices()
+            .map(|devices| devices.flat_map(|d| d.name()).collect())
+            .unwrap_or_default();
+        match pick_option(
+            &mut prompt,
+            "Audio input device",
+            config.input_device.as_deref(),
+            &input_devices,
+            true,
+        ) {
+            Some(idx) if idx < input_devices.len() => {
+                config.input_device = Some(input_devices[idx].clone())
+            }
+            Some(_) => config.input_device = None,
+            None => (),
+        }
+
+        if let Some(sample_rate) = prompt(&format!("Sample rate [{}]", config.sample_rate))
+            .and_then(|answer| answer.parse().ok())
+        {
+            config.sample_rate = sample_rate;
+        }
+        if let Some(period_size) = prompt(&format!("Buffer size [{}]", config.period_size))
+            .and_then(|answer| answer.parse().ok())
+        {
+            config.period_size = period_size;
+        }
+
+        if let Ok(midi_backend) = midir::MidiInput::new(P::NAME) {
+            let midi_inputs: Vec<String> = midi_backend
+                .ports()
+                .iter()
+                .flat_map(|port| midi_backend.port_name(port))
+                .collect();
+            if let Some(ports) = pick_midi_ports(
+                &mut prompt,
+                "MIDI input devices",
+                &config.midi_input,
+                &midi_inputs,
+            ) {
+                config.midi_input = ports;
+            }
+        }
+        if let Ok(midi_backend) = midir::MidiOutput::new(P::NAME) {
+            let midi_outputs: Vec<String> = midi_backend
+                .ports()
+                .iter()
+                .flat_map(|port| midi_backend.port_name(port))
+                .collect();
+            if let Some(ports) = pick_midi_ports(
+                &mut prompt,
+                "MIDI output devices",
+                &config.midi_output,
+                &midi_outputs,
+            ) {
+                config.midi_output = ports;
+            }
+        }
+    }
+
+    println!();
+}
+
+/// List `options` and ask the user to pick one. If `allow_none` is set, then an additional 'None'
+/// option is listed whose index is `options.len()`. Returns `None` if the user kept the current
+/// value.
+fn pick_option(
+    prompt: &mut impl FnMut(&str) -> Option<String>,
+    title: &str,
+    current: Option<&str>,
+    options: &[String],
+    allow_none: bool,
+) -> Option<usize> {
+    println!("{title} (currently {}):", current.unwrap_or("none"));
+    for (idx, option) in options.iter().enumerate() {
+        println!("  {}: {option}", idx + 1);
+    }
+    if allow_none {
+        println!("  {}: None", options.len() + 1);
+    }
+
+    let num_options = options.len() + allow_none as usize;
+    loop {
+        let answer = prompt(&format!("Choose an option (1-{num_options})"))?;
+        match answer.parse::<usize>() {
+            Ok(n) if (1..=num_options).contains(&n) => {
+                println!();
+                return Some(n - 1);
+            }
+            _ => println!("'{answer}' is not a valid option"),
+        }
+    }
+}
+
+/// List the available MIDI devices and ask the user to pick any number of them. Every choice can
+/// be followed by a channel filter, like `2@1-4`. Returns `None` if the user kept the current
+/// value.
+fn pick_midi_ports(
+    prompt: &mut impl FnMut(&str) -> Option<String>,
+    title: &str,
+    current: &[MidiPortSpec],
+    options: &[String],
+) -> Option<Vec<MidiPortSpec>> {
+    let current: Vec<String> = current.iter().map(|port| port.to_string()).collect();
+    println!(
+        "{title} (currently {}):",
+        if current.is_empty() {
+            String::from("none")
+        } else {
+            current.join(", ")
+        }
+    );
+    println!("  0: None");
+    for (idx, option) in options.iter().enumerate() {
+        println!("  {}: {option}", idx + 1);
+    }
+
+    'prompt: loop {
+        let answer = prompt(
+            "Choose one or more options separated by spaces, optionally followed by '@' and a \
+             list of MIDI channels",
+        )?;
+        if answer == "0" {
+            println!();
+            return Some(Vec::new());
+        }
+
+        let mut ports = Vec::new();
+        for choice in answer.split_whitespace() {
+            let port = MidiPortSpec::parse(choice).and_then(|port| match port.name.parse() {
+                Ok(n) if (1..=options.len()).contains(&n) => Ok(MidiPortSpec {
+                    name: options[n - 1].clone(),
+                    ..port
+                }),
+                _ => Err(format!("'{}' is not a valid option", port.name)),
+            });
+            match port {
+                Ok(port) => ports.push(port),
+                Err(err) => {
+                    println!("{err}");
+                    continue 'prompt;
+                }
+            }
+        }
+
+        println!();
+        return Some(ports);
+    }
+}
+
+/// Parse stored `NAME[@CHANNELS]` MIDI port specifications, skipping invalid ones.
+fn parse_midi_ports(ports: &[String]) -> Vec<MidiPortSpec> {
+    ports
+        .iter()
+        .filter_map(|port| MidiPortSpec::parse(port).ok())
+        .collect()
+}
+
+/// The CPAL host used by a backend, if the backend uses CPAL. The JACK and dummy backends don't
+/// have any devices to pick from.
+#[cfg(any(target_os = "linux", target_os = "macos", target_os = "windows"))]
+fn cpal_host(backend: &BackendType) -> Option<cpal::Host> {
+    let host_id = match backend {
+        #[cfg(target_os = "linux")]
+        BackendType::Auto | BackendType::Alsa => cpal::HostId::Alsa,
+        #[cfg(target_os = "macos")]
+        BackendType::Auto | BackendType::CoreAudio => cpal::HostId::CoreAudio,
+        #[cfg(target_os = "windows")]
+        BackendType::Auto | BackendType::Wasapi => cpal::HostId::Wasapi,
+        _ => return None,
+    };
+
+    cpal::host_from_id(host_id).ok()
+}
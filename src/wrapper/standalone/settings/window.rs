@@ -0,0 +1,500 @@
+//! The standalone's settings window. Standalones don't link against a GUI toolkit, so this uses the
+//! platform's native controls: a Win32 window on Windows, an `NSAlert` with pop-up buttons on
+//! macOS, and a `zenity` form on Linux and the BSDs.
+
+pub use self::platform::shift_held;
+
+/// A drop-down in the settings window.
+pub struct Choice {
+    pub label: String,
+    pub options: Vec<String>,
+    /// The index of the selected option in `options`. This is updated when the user confirms the
+    /// settings window.
+    pub selected: usize,
+}
+
+impl Choice {
+    /// Create a drop-down with `current` selected. `current` is added to the options if it's not
+    /// one of them, so a device that's currently disconnected can still be kept.
+    pub fn new(label: impl Into<String>, mut options: Vec<String>, current: &str) -> Self {
+        let selected = match options.iter().position(|option| option == current) {
+            Some(idx) => idx,
+            None => {
+                options.push(current.to_owned());
+                options.len() - 1
+            }
+        };
+
+        Self {
+            label: label.into(),
+            options,
+            selected,
+        }
+    }
+
+    /// The currently selected option.
+    pub fn selected_option(&self) -> &str {
+        &self.options[self.selected]
+    }
+}
+
+/// Show a modal settings window titled `title` with a drop-down for every choice. Returns
+/// `Some(true)` and updates the choices' selections if the user confirmed the window,
+/// `Some(false)` if the window was cancelled, and `None` if the window could not be shown.
+pub fn show(title: &str, choices: &mut [Choice]) -> Option<bool> {
+    if choices.is_empty() {
+        return Some(true);
+    }
+
+    platform::show(title, choices)
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::cell::Cell;
+    use windows::core::PCWSTR;
+    use windows::Win32::Foundation::{HINSTANCE, HWND, LPARAM, LRESULT, RECT, WPARAM};
+    use windows::Win32::Graphics::Gdi::{GetStockObject, COLOR_BTNFACE, DEFAULT_GUI_FONT, HBRUSH};
+    use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+    use windows::Win32::UI::Input::KeyboardAndMouse::{GetAsyncKeyState, VK_SHIFT};
+    use windows::Win32::UI::WindowsAndMessaging::{
+        AdjustWindowRectEx, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW,
+        GetMessageW, IsDialogMessageW, LoadCursorW, RegisterClassW, SendMessageW,
+        SetForegroundWindow, ShowWindow, TranslateMessage, BS_DEFPUSHBUTTON, CBS_DROPDOWNLIST,
+        CB_ADDSTRING, CB_GETCURSEL, CB_SETCURSEL, CW_USEDEFAULT, HMENU, IDC_ARROW, MSG, SW_SHOW,
+        WINDOW_EX_STYLE, WINDOW_STYLE, WM_CLOSE, WM_COMMAND, WM_SETFONT, WNDCLASSW, WS_CAPTION,
+        WS_CHILD, WS_OVERLAPPED, WS_SYSMENU, WS_TABSTOP, WS_VISIBLE, WS_VSCROLL,
+    };
+
+    use super::Choice;
+
+    /// The same IDs as `IDOK` and `IDCANCEL`, so `IsDialogMessageW()` translates the enter and
+    /// escape keys to these buttons.
+    const OK_BUTTON_ID: usize = 1;
+    const CANCEL_BUTTON_ID: usize = 2;
+
+    const MARGIN: i32 = 12;
+    const ROW_HEIGHT: i32 = 30;
+    const LABEL_WIDTH: i32 = 150;
+    const COMBO_BOX_WIDTH: i32 = 300;
+    /// The height of a combo box including its opened drop-down list.
+    const COMBO_BOX_LIST_HEIGHT: i32 = 240;
+    const BUTTON_WIDTH: i32 = 80;
+    const BUTTON_HEIGHT: i32 = 26;
+    const CLIENT_WIDTH: i32 = MARGIN * 3 + LABEL_WIDTH + COMBO_BOX_WIDTH;
+
+    thread_local! {
+        /// Set by the window procedure when one of the buttons is pressed or when the window is
+        /// closed. The window is modal, so this doesn't need to be tied to a specific window.
+        static CONFIRMED: Cell<Option<bool>> = Cell::new(None);
+    }
+
+    pub fn show(title: &str, choices: &mut [Choice]) -> Option<bool> {
+        unsafe {
+            let instance = GetModuleHandleW(PCWSTR::null()).ok()?;
+            let class_name = wide("nih-plug-standalone-settings");
+            let class = WNDCLASSW {
+                lpfnWndProc: Some(window_proc),
+                hInstance: instance,
+                hCursor: LoadCursorW(HINSTANCE(0), IDC_ARROW).unwrap_or_default(),
+                hbrBackground: HBRUSH((COLOR_BTNFACE.0 + 1) as isize),
+                lpszClassName: PCWSTR(class_name.as_ptr()),
+                ..Default::default()
+            };
+            // This fails if the class has already been registered, which is fine
+            RegisterClassW(&class);
+
+            let style = WS_OVERLAPPED | WS_CAPTION | WS_SYSMENU;
+            let buttons_y = MARGIN + choices.len() as i32 * ROW_HEIGHT + MARGIN;
+            let mut window_rect = RECT {
+                left: 0,
+                top: 0,
+                right: CLIENT_WIDTH,
+                bottom: buttons_y + BUTTON_HEIGHT + MARGIN,
+            };
+            AdjustWindowRectEx(&mut window_rect, style, false, WINDOW_EX_STYLE(0));
+
+            let title = wide(title);
+            let window = CreateWindowExW(
+                WINDOW_EX_STYLE(0),
+                PCWSTR(class_name.as_ptr()),
+                PCWSTR(title.as_ptr()),
+                style,
+                CW_USEDEFAULT,
+                CW_USEDEFAULT,
+                window_rect.right - window_rect.left,
+                window_rect.bottom - window_rect.top,
+                HWND(0),
+                HMENU(0),
+                instance,
+                None,
+            );
+            if window.0 == 0 {
+                return None;
+            }
+
+            let font = GetStockObject(DEFAULT_GUI_FONT);
+            let create_control = |class: &str,
+                                  text: &str,
+                                  style: WINDOW_STYLE,
+                                  (x, y, width, height): (i32, i32, i32, i32),
+                                  id: usize| {
+                let class = wide(class);
+                let text = wide(text);
+                let control = CreateWindowExW(
+                    WINDOW_EX_STYLE(0),
+                    PCWSTR(class.as_ptr()),
+                    PCWSTR(text.as_ptr()),
+                    WS_CHILD | WS_VISIBLE | style,
+                    x,
+                    y,
+                    width,
+                    height,
+                    window,
+                    HMENU(id as isize),
+                    instance,
+                    None,
+                );
+                SendMessageW(control, WM_SETFONT, WPARAM(font.0 as usize), LPARAM(1));
+
+                control
+            };
+
+            let mut combo_boxes = Vec::with_capacity(choices.len());
+            for (row, choice) in choices.iter().enumerate() {
+                let y = MARGIN + row as i32 * ROW_HEIGHT;
+                create_control(
+                    "STATIC",
+                    &choice.label,
+                    WINDOW_STYLE(0),
+                    (MARGIN, y + 4, LABEL_WIDTH, BUTTON_HEIGHT),
+                    0,
+                );
+
+                let combo_box = create_control(
+                    "COMBOBOX",
+                    "",
+                    WS_TABSTOP | WS_VSCROLL | WINDOW_STYLE(CBS_DROPDOWNLIST as u32),
+                    (
+                        MARGIN * 2 + LABEL_WIDTH,
+                        y,
+                        COMBO_BOX_WIDTH,
+                        COMBO_BOX_LIST_HEIGHT,
+                    ),
+                    0,
+                );
+                for option in &choice.options {
+                    let option = wide(option);
+                    SendMessageW(
+                        combo_box,
+                        CB_ADDSTRING,
+                        WPARAM(0),
+                        LPARAM(option.as_ptr() as isize),
+                    );
+                }
+                SendMessageW(combo_box, CB_SETCURSEL, WPARAM(choice.selected), LPARAM(0));
+
+                combo_boxes.push(combo_box);
+            }
+
+            create_control(
+                "BUTTON",
+                "OK",
+                WS_TABSTOP | WINDOW_STYLE(BS_DEFPUSHBUTTON as u32),
+                (
+                    CLIENT_WIDTH - (MARGIN + BUTTON_WIDTH) * 2,
+                    buttons_y,
+                    BUTTON_WIDTH,
+                    BUTTON_HEIGHT,
+                ),
+                OK_BUTTON_ID,
+            );
+            create_control(
+                "BUTTON",
+                "Cancel",
+                WS_TABSTOP,
+                (
+                    CLIENT_WIDTH - MARGIN - BUTTON_WIDTH,
+                    buttons_y,
+                    BUTTON_WIDTH,
+                    BUTTON_HEIGHT,
+                ),
+                CANCEL_BUTTON_ID,
+            );
+
+            ShowWindow(window, SW_SHOW);
+            SetForegroundWindow(window);
+
+            CONFIRMED.with(|confirmed| confirmed.set(None));
+            let mut message = MSG::default();
+            while CONFIRMED.with(Cell::get).is_none()
+                && GetMessageW(&mut message, HWND(0), 0, 0).0 > 0
+            {
+                // This takes care of tab navigation and the enter and escape keys
+                if !IsDialogMessageW(window, &message).as_bool() {
+                    TranslateMessage(&message);
+                    DispatchMessageW(&message);
+                }
+            }
+
+            let confirmed = CONFIRMED.with(Cell::get).unwrap_or(false);
+            if confirmed {
+                for (choice, combo_box) in choices.iter_mut().zip(combo_boxes) {
+                    let selected = SendMessageW(combo_box, CB_GETCURSEL, WPARAM(0), LPARAM(0)).0;
+                    if selected >= 0 {
+                        choice.selected = selected as usize;
+                    }
+                }
+            }
+            DestroyWindow(window);
+
+            Some(confirmed)
+        }
+    }
+
+    pub fn shift_held() -> bool {
+        // The most significant bit is set when the key is currently pressed
+        unsafe { GetAsyncKeyState(VK_SHIFT.0 as i32) < 0 }
+    }
+
+    unsafe extern "system" fn window_proc(
+        window: HWND,
+        message: u32,
+        wparam: WPARAM,
+        lparam: LPARAM,
+    ) -> LRESULT {
+        // The low word contains the ID of the control that sent the command
+        match (message, wparam.0 & 0xffff) {
+            (WM_COMMAND, OK_BUTTON_ID) => {
+                CONFIRMED.with(|confirmed| confirmed.set(Some(true)));
+                LRESULT(0)
+            }
+            (WM_COMMAND, CANCEL_BUTTON_ID) | (WM_CLOSE, _) => {
+                CONFIRMED.with(|confirmed| confirmed.set(Some(false)));
+                LRESULT(0)
+            }
+            _ => DefWindowProcW(window, message, wparam, lparam),
+        }
+    }
+
+    /// Convert a string to a null terminated UTF-16 string.
+    fn wide(string: &str) -> Vec<u16> {
+        string.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use core_foundation::base::TCFType;
+    use core_foundation::string::CFString;
+    use objc::runtime::{Object, BOOL, NO, YES};
+    use objc::{class, msg_send, sel, sel_impl};
+    use std::ffi::c_void;
+
+    use super::Choice;
+
+    /// `NSAlertFirstButtonReturn`, returned by `-[NSAlert runModal]` when the OK button is pressed.
+    const ALERT_FIRST_BUTTON_RETURN: isize = 1000;
+    /// `NSApplicationActivationPolicyRegular`.
+    const ACTIVATION_POLICY_REGULAR: isize = 0;
+    /// `NSEventModifierFlagShift`.
+    const MODIFIER_FLAG_SHIFT: usize = 1 << 17;
+
+    const ROW_HEIGHT: f64 = 30.0;
+    const LABEL_WIDTH: f64 = 150.0;
+    const POP_UP_BUTTON_WIDTH: f64 = 300.0;
+
+    #[repr(C)]
+    struct NSRect {
+        x: f64,
+        y: f64,
+        width: f64,
+        height: f64,
+    }
+
+    pub fn show(title: &str, choices: &mut [Choice]) -> Option<bool> {
+        unsafe {
+            let pool: *mut Object = msg_send![class!(NSAutoreleasePool), new];
+
+            // The standalone's editor has not been opened yet, so the application still needs to
+            // be brought to the front for the alert to receive keyboard input
+            let app: *mut Object = msg_send![class!(NSApplication), sharedApplication];
+            let _: BOOL = msg_send![app, setActivationPolicy: ACTIVATION_POLICY_REGULAR];
+            let _: () = msg_send![app, activateIgnoringOtherApps: YES];
+
+            let view_height = choices.len() as f64 * ROW_HEIGHT;
+            let view: *mut Object = msg_send![class!(NSView), alloc];
+            let view: *mut Object = msg_send![view, initWithFrame: NSRect {
+                x: 0.0,
+                y: 0.0,
+                width: LABEL_WIDTH + POP_UP_BUTTON_WIDTH,
+                height: view_height,
+            }];
+
+            let empty_string = CFString::new("");
+            let mut pop_up_buttons = Vec::with_capacity(choices.len());
+            for (row, choice) in choices.iter().enumerate() {
+                // AppKit's coordinate system starts at the bottom left
+                let y = view_height - (row + 1) as f64 * ROW_HEIGHT;
+
+                let label_text = CFString::new(&choice.label);
+                let label: *mut Object = msg_send![
+                    class!(NSTextField),
+                    labelWithString: label_text.as_concrete_TypeRef()
+                ];
+                let _: () = msg_send![label, setFrame: NSRect {
+                    x: 0.0,
+                    y: y + 4.0,
+                    width: LABEL_WIDTH,
+                    height: 20.0,
+                }];
+                let _: () = msg_send![view, addSubview: label];
+
+                let pop_up_button: *mut Object = msg_send![class!(NSPopUpButton), alloc];
+                let pop_up_button: *mut Object = msg_send![
+                    pop_up_button,
+                    initWithFrame: NSRect {
+                        x: LABEL_WIDTH,
+                        y,
+                        width: POP_UP_BUTTON_WIDTH,
+                        height: 26.0,
+                    }
+                    pullsDown: NO
+                ];
+                // `-[NSPopUpButton addItemWithTitle:]` would remove options with duplicate names,
+                // so the items are added to the menu directly
+                let menu: *mut Object = msg_send![pop_up_button, menu];
+                for option in &choice.options {
+                    let option = CFString::new(option);
+                    let _: *mut Object = msg_send![
+                        menu,
+                        addItemWithTitle: option.as_concrete_TypeRef()
+                        action: std::ptr::null::<c_void>()
+                        keyEquivalent: empty_string.as_concrete_TypeRef()
+                    ];
+                }
+                let _: () = msg_send![pop_up_button, selectItemAtIndex: choice.selected as isize];
+                let _: () = msg_send![view, addSubview: pop_up_button];
+
+                pop_up_buttons.push(pop_up_button);
+            }
+
+            let message_text = CFString::new(title);
+            let informative_text = CFString::new("Choose the audio and MIDI devices to use.");
+            let ok_text = CFString::new("OK");
+            let cancel_text = CFString::new("Cancel");
+            let alert: *mut Object = msg_send![class!(NSAlert), new];
+            let _: () = msg_send![alert, setMessageText: message_text.as_concrete_TypeRef()];
+            let _: () =
+                msg_send![alert, setInformativeText: informative_text.as_concrete_TypeRef()];
+            let _: *mut Object =
+                msg_send![alert, addButtonWithTitle: ok_text.as_concrete_TypeRef()];
+            let _: *mut Object =
+                msg_send![alert, addButtonWithTitle: cancel_text.as_concrete_TypeRef()];
+            let _: () = msg_send![alert, setAccessoryView: view];
+
+            let response: isize = msg_send![alert, runModal];
+            let confirmed = response == ALERT_FIRST_BUTTON_RETURN;
+            if confirmed {
+                for (choice, &pop_up_button) in choices.iter_mut().zip(&pop_up_buttons) {
+                    let selected: isize = msg_send![pop_up_button, indexOfSelectedItem];
+                    if selected >= 0 {
+                        choice.selected = selected as usize;
+                    }
+                }
+            }
+
+            for pop_up_button in pop_up_buttons {
+                let _: () = msg_send![pop_up_button, release];
+            }
+            let _: () = msg_send![view, release];
+            let _: () = msg_send![alert, release];
+            let _: () = msg_send![pool, drain];
+
+            Some(confirmed)
+        }
+    }
+
+    pub fn shift_held() -> bool {
+        let modifier_flags: usize = unsafe { msg_send![class!(NSEvent), modifierFlags] };
+        modifier_flags & MODIFIER_FLAG_SHIFT != 0
+    }
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod platform {
+    use std::process::Command;
+
+    use super::Choice;
+
+    /// The exit code `zenity` uses when the dialog is cancelled.
+    const ZENITY_CANCELLED: i32 = 1;
+
+    pub fn show(title: &str, choices: &mut [Choice]) -> Option<bool> {
+        // Options in `zenity`'s combo boxes are separated by pipes
+        let escape = |option: &str| option.replace('|', "/");
+
+        let mut command = Command::new("zenity");
+        command
+            .arg("--forms")
+            .arg(format!("--title={title}"))
+            .arg("--text=Choose the audio and MIDI devices to use.")
+            .arg("--separator=\n");
+        for choice in choices.iter() {
+            // These combo boxes can't have a preselected option, so the current value is shown
+            // in the label instead and an empty combo box keeps the current value
+            command
+                .arg(format!(
+                    "--add-combo={} ({})",
+                    choice.label,
+                    choice.selected_option()
+                ))
+                .arg(format!(
+                    "--combo-values={}",
+                    choice
+                        .options
+                        .iter()
+                        .map(|option| escape(option))
+                        .collect::<Vec<_>>()
+                        .join("|")
+                ));
+        }
+
+        let output = match command.output() {
+            Ok(output) => output,
+            Err(err) => {
+                nih_log!("Could not run 'zenity' to show the settings window: {err}");
+                return None;
+            }
+        };
+        match output.status.code() {
+            Some(0) => (),
+            Some(ZENITY_CANCELLED) => return Some(false),
+            _ => {
+                nih_log!(
+                    "Could not show the settings window: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+                return None;
+            }
+        }
+
+        let answers = String::from_utf8_lossy(&output.stdout);
+        for (choice, answer) in choices.iter_mut().zip(answers.split('\n')) {
+            if let Some(idx) = choice
+                .options
+                .iter()
+                .position(|option| escape(option) == answer)
+            {
+                choice.selected = idx;
+            }
+        }
+
+        Some(true)
+    }
+
+    pub fn shift_held() -> bool {
+        // Without a connection to the display server there's no way to query the keyboard's state
+        false
+    }
+}
@@ -0,0 +1,238 @@
+//! A simulated transport for the backends that don't have a transport of their own. This behaves
+//! like a DAW's transport with a constant tempo, and it can be controlled from the terminal while
+//! the standalone is running.
+
+use atomic_float::AtomicF64;
+use std::io::BufRead;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
+
+use super::config::WrapperConfig;
+use crate::prelude::Transport;
+
+/// The commands understood by [`TransportSimulator::handle_command()`], printed when an unknown
+/// command is entered.
+const HELP_TEXT: &str = "\
+Transport commands:
+  p           play or pause
+  s           stop and return to the start
+  t <bpm>     set the tempo
+  m <n>/<d>   set the time signature
+  l <a>-<b>   loop bars a through b
+  l           disable the loop";
+
+/// The transport state shared between the audio thread and the thread handling the terminal
+/// commands. The position is stored in quarter notes and it is only ever written to from the audio
+/// thread.
+#[derive(Debug)]
+pub struct TransportSimulator {
+    playing: AtomicBool,
+    /// Set by [`stop()`][Self::stop()]. The audio thread resets the position when this is set.
+    rewind_requested: AtomicBool,
+    tempo: AtomicF64,
+    /// The time signature's numerator and denominator, packed into a single integer so they're
+    /// always updated together.
+    time_sig: AtomicU32,
+    /// The one-indexed start and inclusive end bars for the loop region, packed into a single
+    /// integer. Zero if looping is disabled.
+    loop_bars: AtomicU64,
+    pos_beats: AtomicF64,
+}
+
+impl TransportSimulator {
+    pub fn new(config: &WrapperConfig) -> Self {
+        Self {
+            playing: AtomicBool::new(!config.paused),
+            rewind_requested: AtomicBool::new(false),
+            tempo: AtomicF64::new(config.tempo as f64),
+            time_sig: AtomicU32::new(pack_time_sig(config.timesig_num, config.timesig_denom)),
+            loop_bars: AtomicU64::new(
+                config
+                    .loop_bars
+                    .map(|(start, end)| pack_loop_bars(start, end))
+                    .unwrap_or(0),
+            ),
+            pos_beats: AtomicF64::new(0.0),
+        }
+    }
+
+    /// Fill in the transport information for the next block and advance the position by
+    /// `num_samples` samples if the transport is playing. This should only be called from the audio
+    /// thread. The loop region's end is only checked once per block, just like how some hosts
+    /// handle loops.
+    pub fn process(&self, transport: &mut Transport, num_samples: usize) {
+        let tempo = self.tempo.load(Ordering::Relaxed);
+        let (time_sig_numerator, time_sig_denominator) =
+            unpack_time_sig(self.time_sig.load(Ordering::Relaxed));
+        let beats_per_bar = time_sig_numerator as f64 / time_sig_denominator as f64 * 4.0;
+        let loop_range_beats =
+            unpack_loop_bars(self.loop_bars.load(Ordering::Relaxed)).map(|(start_bar, end_bar)| {
+                (
+                    (start_bar - 1) as f64 * beats_per_bar,
+                    end_bar as f64 * beats_per_bar,
+                )
+            });
+
+        let mut pos_beats = self.pos_beats.load(Ordering::Relaxed);
+        if self.rewind_requested.swap(false, Ordering::Relaxed) {
+            pos_beats = loop_range_beats.map(|(start, _)| start).unwrap_or(0.0);
+        }
+
+        let sample_rate = transport.sample_rate as f64;
+        let beats_to_seconds = |beats: f64| beats * 60.0 / tempo;
+        let beats_to_samples = |beats: f64| (beats_to_seconds(beats) * sample_rate).round() as i64;
+
+        transport.playing = self.playing.load(Ordering::Relaxed);
+        transport.tempo = Some(tempo);
+        transport.time_sig_numerator = Some(time_sig_numerator as i32);
+        transport.time_sig_denominator = Some(time_sig_denominator as i32);
+        transport.pos_samples = Some(beats_to_samples(pos_beats));
+        transport.pos_seconds = Some(beats_to_seconds(pos_beats));
+        transport.pos_beats = Some(pos_beats);
+        let bar_number = (pos_beats / beats_per_bar).floor();
+        transport.bar_number = Some(bar_number as i32);
+        transport.bar_start_pos_beats = Some(bar_number * beats_per_bar);
+        transport.loop_range_beats = loop_range_beats;
+        transport.loop_range_seconds =
+            loop_range_beats.map(|(start, end)| (beats_to_seconds(start), beats_to_seconds(end)));
+        transport.loop_range_samples =
+            loop_range_beats.map(|(start, end)| (beats_to_samples(start), beats_to_samples(end)));
+
+        if transport.playing {
+            pos_beats += num_samples as f64 / sample_rate * tempo / 60.0;
+            if let Some((loop_start, loop_end)) = loop_range_beats {
+                if pos_beats >= loop_end {
+                    pos_beats = loop_start + (pos_beats - loop_end) % (loop_end - loop_start);
+                }
+            }
+        }
+        self.pos_beats.store(pos_beats, Ordering::Relaxed);
+    }
+
+    /// Toggle between playing and paused.
+    pub fn play_pause(&self) {
+        self.playing.fetch_xor(true, Ordering::Relaxed);
+    }
+
+    /// Stop playback and return to the start of the song or the loop region.
+    pub fn stop(&self) {
+        self.playing.store(false, Ordering::Relaxed);
+        self.rewind_requested.store(true, Ordering::Relaxed);
+    }
+
+    /// A short description of the transport's current state for the standalone's status line, e.g.
+    /// `> 3.2 | 120.0 BPM 4/4`.
+    pub fn status(&self) -> String {
+        let (numerator, denominator) = unpack_time_sig(self.time_sig.load(Ordering::Relaxed));
+        let beats_per_bar = numerator as f64 / denominator as f64 * 4.0;
+        let beat_length = 4.0 / denominator as f64;
+        let pos_beats = self.pos_beats.load(Ordering::Relaxed);
+        let bar = (pos_beats / beats_per_bar).floor();
+        let beat = ((pos_beats - (bar * beats_per_bar)) / beat_length).floor();
+
+        format!(
+            "{} {}.{} | {:.1} BPM {numerator}/{denominator}{}",
+            if self.playing.load(Ordering::Relaxed) {
+                '>'
+            } else {
+                '|'
+            },
+            bar as i64 + 1,
+            beat as i64 + 1,
+            self.tempo.load(Ordering::Relaxed),
+            match unpack_loop_bars(self.loop_bars.load(Ordering::Relaxed)) {
+                Some((start_bar, end_bar)) => format!(" | loop {start_bar}-{end_bar}"),
+                None => String::new(),
+            }
+        )
+    }
+
+    /// Handle a single line of input from the terminal. See [`HELP_TEXT`] for the supported
+    /// commands.
+    pub fn handle_command(&self, command: &str) {
+        let mut args = command.split_whitespace();
+        match (args.next(), args.next()) {
+            (Some("p"), None) => self.play_pause(),
+            (Some("s"), None) => self.stop(),
+            (Some("t"), Some(tempo)) => match tempo.parse::<f64>() {
+                Ok(tempo) if tempo > 0.0 => self.tempo.store(tempo, Ordering::Relaxed),
+                _ => nih_error!("'{tempo}' is not a valid tempo"),
+            },
+            (Some("m"), Some(time_sig)) => match parse_time_sig(time_sig) {
+                Ok((numerator, denominator)) => self
+                    .time_sig
+                    .store(pack_time_sig(numerator, denominator), Ordering::Relaxed),
+                Err(err) => nih_error!("{err}"),
+            },
+            (Some("l"), Some(loop_bars)) => match parse_loop_bars(loop_bars) {
+                Ok((start_bar, end_bar)) => self
+                    .loop_bars
+                    .store(pack_loop_bars(start_bar, end_bar), Ordering::Relaxed),
+                Err(err) => nih_error!("{err}"),
+            },
+            (Some("l"), None) => self.loop_bars.store(0, Ordering::Relaxed),
+            (None, _) => (),
+            _ => eprintln!("{HELP_TEXT}"),
+        }
+    }
+
+    /// Read transport commands from STDIN until it is closed. This blocks, so it should be run on
+    /// its own thread.
+    pub fn read_commands(&self) {
+        eprintln!("Type 'p' and press enter to start or pause playback, or '?' for more commands");
+        for line in std::io::stdin().lock().lines() {
+            match line {
+                Ok(line) => self.handle_command(&line),
+                Err(_) => break,
+            }
+        }
+    }
+}
+
+/// Parse a `<numerator>/<denominator>` time signature.
+pub fn parse_time_sig(time_sig: &str) -> Result<(u32, u32), String> {
+    match time_sig
+        .split_once('/')
+        .map(|(numerator, denominator)| (numerator.parse::<u32>(), denominator.parse::<u32>()))
+    {
+        Some((Ok(numerator), Ok(denominator)))
+            if numerator > 0 && denominator.is_power_of_two() =>
+        {
+            Ok((numerator, denominator))
+        }
+        _ => Err(format!("'{time_sig}' is not a valid time signature")),
+    }
+}
+
+/// Parse a loop region in the `<start>-<end>` format, where both bars are one-indexed and the end
+/// bar is included in the loop.
+pub fn parse_loop_bars(loop_bars: &str) -> Result<(u32, u32), String> {
+    match loop_bars
+        .split_once('-')
+        .map(|(start, end)| (start.parse::<u32>(), end.parse::<u32>()))
+    {
+        Some((Ok(start), Ok(end))) if start >= 1 && end >= start => Ok((start, end)),
+        _ => Err(format!(
+            "'{loop_bars}' is not a valid loop region, expected something like '1-4'"
+        )),
+    }
+}
+
+fn pack_time_sig(numerator: u32, denominator: u32) -> u32 {
+    (numerator.min(u16::MAX as u32) << 16) | denominator.min(u16::MAX as u32)
+}
+
+fn unpack_time_sig(time_sig: u32) -> (u32, u32) {
+    (time_sig >> 16, time_sig & 0xFFFF)
+}
+
+fn pack_loop_bars(start_bar: u32, end_bar: u32) -> u64 {
+    ((start_bar as u64) << 32) | end_bar as u64
+}
+
+fn unpack_loop_bars(loop_bars: u64) -> Option<(u32, u32)> {
+    if loop_bars == 0 {
+        None
+    } else {
+        Some(((loop_bars >> 32) as u32, loop_bars as u32))
+    }
+}
@@ -5,27 +5,43 @@ use crossbeam::queue::ArrayQueue;
 use parking_lot::Mutex;
 use raw_window_handle::HasRawWindowHandle;
 use std::any::Any;
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{IsTerminal, Write};
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
 use std::sync::Arc;
 use std::thread;
+use std::time::{Duration, Instant};
 
 use super::backend::Backend;
 use super::config::WrapperConfig;
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
-use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
+use super::osc::{self, OutputPeaks};
+use super::settings;
+use super::transport::TransportSimulator;
+use crate::context::gui::{GuiMessageQueue, ParamChange, ParamChangeBroadcaster, StateSlots};
+use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop, TimerCallback, TimerHandle};
 use crate::prelude::{
-    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, ParamFlags, ParamPtr, Params,
-    ParentWindowHandle, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor,
-    Transport,
+    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, NoteEvent, ParamFlags, ParamPtr, Params,
+    ParentWindowHandle, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, StateSaveContext,
+    TaskExecutor, TaskOptions, Transport,
 };
-use crate::util::permit_alloc;
+use crate::util::modulation::ModulationMatrix;
+use crate::util::{permit_alloc, Tuning};
 use crate::wrapper::state::{self, PluginState};
-use crate::wrapper::util::process_wrapper;
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::dsp_load::DspLoadMeter;
+use crate::wrapper::util::{
+    bypass_param_enabled, find_bypass_param, prepend_gui_note_events, process_wrapper,
+    queue_gui_note_event, GUI_NOTE_EVENT_QUEUE_CAPACITY,
+};
 
 /// How many parameter changes we can store in our unprocessed parameter change queue. Storing more
 /// than this many parameters at a time will cause changes to get lost.
 const EVENT_QUEUE_CAPACITY: usize = 2048;
+/// How often the status line is updated when running without an editor.
+const STATUS_LINE_INTERVAL: Duration = Duration::from_millis(250);
 
 pub struct Wrapper<P: Plugin, B: Backend<P>> {
     backend: AtomicRefCell<B>,
@@ -54,6 +70,9 @@ pub struct Wrapper<P: Plugin, B: Backend<P>> {
     /// This is used to grab the DPI scaling config. Not used on macOS.
     #[allow(unused)]
     config: WrapperConfig,
+    /// The file the plugin's state is restored from when the wrapper is created and saved to when
+    /// the wrapper exits. `None` if the state should not be persisted.
+    state_path: Option<PathBuf>,
 
     /// A mapping from parameter pointers to string parameter IDs. This is used as part of
     /// `Task::ParamValueChanged` to send a parameter change event to the editor from the GUI
@@ -72,6 +91,35 @@ pub struct Wrapper<P: Plugin, B: Backend<P>> {
     /// This queue will be flushed at the end of every processing cycle, just like in the plugin
     /// versions.
     unprocessed_param_changes: ArrayQueue<(ParamPtr, f32)>,
+    /// Note events sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()]. These are
+    /// added to the start of the backend's input events at the start of the next processing cycle.
+    gui_note_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// Messages sent from the editor through `send_to_audio()` on the
+    /// [`GuiContext`][crate::prelude::GuiContext]. The plugin receives these through
+    /// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    pub gui_messages: GuiMessageQueue,
+    /// Broadcasts parameter changes to the receivers created through
+    /// [`GuiContext::subscribe_param_changes()`][crate::prelude::GuiContext::subscribe_param_changes()].
+    pub param_changes: ParamChangeBroadcaster,
+    /// The A/B comparison slots used by the [`GuiContext`][crate::prelude::GuiContext]'s
+    /// `swap_ab()` and related functions.
+    pub state_slots: StateSlots,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    pub modulation_matrix: Option<Arc<ModulationMatrix>>,
+    /// Measures the time spent in the plugin's process function. Exposed to the editor through
+    /// [`GuiContext::dsp_load()`][crate::prelude::GuiContext::dsp_load()].
+    pub dsp_load: DspLoadMeter,
+    /// The peak amplitudes of the main output channels, sent as feedback by the OSC server. This
+    /// doesn't have any channels when `--osc-port` is not set.
+    pub output_peaks: OutputPeaks,
+    /// Provides the transport information when the backend doesn't have a transport of its own.
+    /// Shared with the thread reading the transport commands from the terminal.
+    transport: Arc<TransportSimulator>,
+    /// The backend's input events combined with `gui_note_events`. This is preallocated so the
+    /// events can be combined without allocating on the audio thread.
+    input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
     /// The plugin is able to restore state through a method on the `GuiContext`. To avoid changing
     /// parameters mid-processing and running into garbled data if the host also tries to load state
     /// at the same time the restoring happens at the end of each processing call. If this zero
@@ -88,6 +136,15 @@ pub struct Wrapper<P: Plugin, B: Backend<P>> {
     /// still kept track of to avoid firing debug assertions multiple times for the same latency
     /// value.
     current_latency: AtomicU32,
+    /// The plugin's bypass parameter, if it has one. Used for
+    /// [`ProcessContext::bypassed()`][crate::prelude::ProcessContext::bypassed()].
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the dry signal when the plugin is bypassed. This is only
+    /// used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// The tuning loaded from the `--scl` and `--kbm` command line options. Used for
+    /// [`ProcessContext::note_frequency()`][crate::prelude::ProcessContext::note_frequency()].
+    pub tuning: Tuning,
 }
 
 /// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
@@ -104,6 +161,12 @@ pub enum Task<P: Plugin> {
     /// like in the plugin APIs, so we'll just use the `ParamPtr`s directly. These are used to index
     /// the hashmaps stored on `Wrapper`.
     ParameterValueChanged(ParamPtr, f32),
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+    /// Run a timer callback registered through the [`GuiContext`][crate::prelude::GuiContext].
+    TimerCallback(TimerCallback),
 }
 
 /// Errors that may arise while initializing the wrapped plugins.
@@ -156,11 +219,18 @@ impl<P: Plugin, B: Backend<P>> MainThreadExecutor<Task<P>> for Wrapper<P, B> {
         match task {
             Task::PluginTask(task) => (self.task_executor.lock())(task),
             Task::ParameterValuesChanged => {
+                self.param_changes.broadcast(|| Some(ParamChange::All));
                 if let Some(editor) = self.editor.borrow().as_ref() {
                     editor.lock().param_values_changed();
                 }
             }
             Task::ParameterValueChanged(param_ptr, normalized_value) => {
+                self.param_changes.broadcast(|| {
+                    Some(ParamChange::Value {
+                        id: self.param_ptr_to_id.get(&param_ptr)?.clone(),
+                        normalized_value,
+                    })
+                });
                 if let Some(editor) = self.editor.borrow().as_ref() {
                     let param_id = &self.param_ptr_to_id[&param_ptr];
                     editor
@@ -168,8 +238,14 @@ impl<P: Plugin, B: Backend<P>> MainThreadExecutor<Task<P>> for Wrapper<P, B> {
                         .param_value_changed(param_id, normalized_value);
                 }
             }
+            Task::TimerCallback(callback) => callback.call(),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
         }
     }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
 }
 
 impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
@@ -180,10 +256,12 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         // TODO: Do the validation and parsing in the argument parser so this value can be stored on
         //       the config itself. Right now clap doesn't support this.
         let audio_io_layout = config.audio_io_layout_or_exit::<P>();
+        let tuning = config.tuning_or_exit();
 
         let mut plugin = P::default();
         let task_executor = Mutex::new(plugin.task_executor());
         let params = plugin.params();
+        let modulation_matrix = plugin.modulation_matrix();
 
         // This is used to allow the plugin to restore preset data from its editor, see the comment
         // on `Self::updated_state_sender`
@@ -215,6 +293,20 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                 bypass_param_exists |= is_bypass;
             }
         }
+        let bypass_param = find_bypass_param::<P>(param_map.iter().map(|(_, ptr, _)| *ptr));
+
+        let output_peaks = if config.osc_port.is_some() {
+            OutputPeaks::new(
+                audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+            )
+        } else {
+            OutputPeaks::default()
+        };
+
+        let transport = Arc::new(TransportSimulator::new(&config));
 
         let wrapper = Arc::new(Wrapper {
             backend: AtomicRefCell::new(backend),
@@ -247,22 +339,38 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                 // TODO: Detect JACK freewheeling and report it here
                 process_mode: ProcessMode::Realtime,
             },
+            state_path: settings::state_path::<P>(&config),
             config,
 
             unprocessed_param_changes: ArrayQueue::new(EVENT_QUEUE_CAPACITY),
+            gui_note_events: ArrayQueue::new(GUI_NOTE_EVENT_QUEUE_CAPACITY),
+            gui_messages: GuiMessageQueue::default(),
+            param_changes: ParamChangeBroadcaster::default(),
+            state_slots: StateSlots::default(),
+            modulation_matrix,
+            dsp_load: DspLoadMeter::default(),
+            output_peaks,
+            transport,
+            input_events: AtomicRefCell::new(VecDeque::with_capacity(
+                1024 + GUI_NOTE_EVENT_QUEUE_CAPACITY,
+            )),
             updated_state_sender,
             updated_state_receiver,
             current_latency: AtomicU32::new(0),
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            tuning,
         });
 
         *wrapper.event_loop.borrow_mut() =
             Some(OsEventLoop::new_and_spawn(Arc::downgrade(&wrapper)));
 
-        // The editor needs to be initialized later so the Async executor can work.
-        *wrapper.editor.borrow_mut() = wrapper
-            .plugin
-            .lock()
-            .editor(AsyncExecutor {
+        // The editor needs to be initialized later so the Async executor can work. In headless mode
+        // the editor is never created.
+        let editor = if wrapper.config.headless {
+            None
+        } else {
+            wrapper.plugin.lock().editor(AsyncExecutor {
                 execute_background: Arc::new({
                     let wrapper = wrapper.clone();
 
@@ -279,8 +387,20 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
+                cancel_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |tag| wrapper.cancel_background_tasks(tag)
+                }),
             })
-            .map(|editor| Arc::new(Mutex::new(editor)));
+        };
+        *wrapper.editor.borrow_mut() = editor.map(|editor| Arc::new(Mutex::new(editor)));
+
+        // The state from the last session is restored before the plugin is initialized, so there's
+        // no need to reinitialize the plugin afterwards
+        if let Some(state_path) = &wrapper.state_path {
+            wrapper.restore_state_file(state_path);
+        }
 
         // Before initializing the plugin, make sure all smoothers are set the the default values
         for param in wrapper.param_id_to_ptr.values() {
@@ -299,6 +419,20 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             process_wrapper(|| plugin.reset());
         }
 
+        if P::MANAGED_BYPASS {
+            wrapper.bypass_processor.borrow_mut().initialize(
+                wrapper
+                    .audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+                wrapper.current_latency.load(Ordering::SeqCst),
+                wrapper.buffer_config.max_buffer_size,
+                wrapper.buffer_config.sample_rate,
+                wrapper.is_bypassed(),
+            );
+        }
+
         Ok(wrapper)
     }
 
@@ -320,6 +454,29 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             thread::spawn(move || this.run_audio_thread(terminate_audio_thread, gui_task_sender))
         };
 
+        // The transport can be controlled from the terminal. This thread blocks on reading from
+        // STDIN, so it's detached instead of joined.
+        if std::io::stdin().is_terminal() {
+            let transport = self.transport.clone();
+            thread::spawn(move || transport.read_commands());
+        }
+
+        // The OSC server is stopped together with the audio thread
+        let osc_thread = self.config.osc_port.and_then(|port| {
+            match osc::spawn(
+                self.clone(),
+                port,
+                self.config.osc_feedback,
+                terminate_audio_thread.clone(),
+            ) {
+                Ok(osc_thread) => Some(osc_thread),
+                Err(err) => {
+                    nih_error!("Could not start the OSC server on port {port}: {err}");
+                    None
+                }
+            }
+        });
+
         match self.editor.borrow().clone() {
             Some(editor) => {
                 let context = self.clone().make_gui_context();
@@ -329,11 +486,15 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                 let scaling_policy = baseview::WindowScalePolicy::SystemScaleFactor;
                 #[cfg(not(target_os = "macos"))]
                 let scaling_policy = {
-                    editor.lock().set_scale_factor(self.config.dpi_scale);
-                    baseview::WindowScalePolicy::ScaleFactor(self.config.dpi_scale as f64)
+                    // A UI scale chosen by the user takes precedence over the command line option
+                    let editor = editor.lock();
+                    let scaling_factor = editor.ui_scale().unwrap_or(self.config.dpi_scale);
+                    editor.set_scale_factor(scaling_factor);
+                    baseview::WindowScalePolicy::ScaleFactor(scaling_factor as f64)
                 };
 
                 let (width, height) = editor.lock().size();
+                let this = self.clone();
                 Window::open_blocking(
                     WindowOpenOptions {
                         title: String::from(P::NAME),
@@ -366,24 +527,38 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                         //       immediately close the parent window when this happens so the loop
                         //       can exit.
                         let editor_handle = editor.lock().spawn(parent_handle, context);
+                        this.notify_editor_opened(true);
 
                         WrapperWindowHandler {
                             _editor_handle: editor_handle,
                             gui_task_receiver,
                         }
                     },
-                )
+                );
+
+                // The editor handle is dropped together with the window
+                self.notify_editor_opened(false);
             }
             None => {
-                // TODO: Properly block until SIGINT is received if the plugin does not have an editor
-                // TODO: Make sure to handle `GuiTask::Close` here as well
-                nih_log!("{} does not have a GUI, blocking indefinitely...", P::NAME);
-                std::thread::park();
+                if self.config.headless {
+                    nih_log!("Running {} in headless mode, press Ctrl+C to exit", P::NAME);
+                } else {
+                    nih_log!("{} does not have a GUI, press Ctrl+C to exit", P::NAME);
+                }
+
+                self.block_until_interrupted(gui_task_receiver);
             }
         }
 
         terminate_audio_thread.store(true, Ordering::SeqCst);
         audio_thread.join().unwrap();
+        if let Some(osc_thread) = osc_thread {
+            osc_thread.join().unwrap();
+        }
+
+        if let Some(state_path) = &self.state_path {
+            self.save_state_file(state_path);
+        }
 
         // Some plugins may use this to clean up resources. Should not be needed for the standalone
         // application, but it seems like a good idea to stay consistent.
@@ -392,6 +567,48 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         Ok(())
     }
 
+    /// Block until SIGINT is received or until the audio thread requests the application to close.
+    /// Used when there is no editor window to block on. If this is run from a terminal, then a
+    /// status line with the plugin's DSP load is shown in the meantime.
+    fn block_until_interrupted(&self, gui_task_receiver: channel::Receiver<GuiTask>) {
+        let (interrupt_sender, interrupt_receiver) = channel::bounded(1);
+        if let Err(err) = ctrlc::set_handler(move || {
+            let _ = interrupt_sender.try_send(());
+        }) {
+            nih_error!("Could not install the Ctrl+C handler: {err}");
+        }
+
+        let show_status_line = std::io::stderr().is_terminal();
+        let start_time = Instant::now();
+        loop {
+            match interrupt_receiver.recv_timeout(STATUS_LINE_INTERVAL) {
+                Ok(()) | Err(channel::RecvTimeoutError::Disconnected) => break,
+                Err(channel::RecvTimeoutError::Timeout) => (),
+            }
+            if let Ok(GuiTask::Close) = gui_task_receiver.try_recv() {
+                break;
+            }
+
+            if show_status_line {
+                let elapsed = start_time.elapsed().as_secs();
+                eprint!(
+                    "\r{} | {:02}:{:02}:{:02} | {} | DSP load {:5.1}%  ",
+                    P::NAME,
+                    elapsed / 3600,
+                    (elapsed / 60) % 60,
+                    elapsed % 60,
+                    self.transport.status(),
+                    self.dsp_load.load() * 100.0
+                );
+                let _ = std::io::stderr().flush();
+            }
+        }
+
+        if show_status_line {
+            eprintln!();
+        }
+    }
+
     /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
     /// gesture checks.
     #[allow(unused)]
@@ -399,6 +616,19 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         self.param_ptr_to_id.get(&param).map(|s| s.as_str())
     }
 
+    /// Get a parameter's `ParamPtr` based on its ID. Used by the OSC server.
+    pub fn param_ptr_from_id(&self, param_id: &str) -> Option<ParamPtr> {
+        self.param_id_to_ptr.get(param_id).copied()
+    }
+
+    /// Iterate over all of the plugin's parameter IDs and their `ParamPtr`s. Used by the OSC
+    /// server.
+    pub fn param_ids(&self) -> impl Iterator<Item = (&str, ParamPtr)> {
+        self.param_id_to_ptr
+            .iter()
+            .map(|(param_id, param_ptr)| (param_id.as_str(), *param_ptr))
+    }
+
     /// Set a parameter based on a `ParamPtr`. The value will be updated at the end of the next
     /// processing cycle, and this won't do anything if the parameter has not been registered by the
     /// plugin.
@@ -429,6 +659,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                 self.param_id_to_ptr
                     .iter()
                     .map(|(param_id, param_ptr)| (param_id, *param_ptr)),
+                StateSaveContext::Project,
             )
         }
     }
@@ -456,12 +687,28 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
     /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
     /// can be run in the background without blocking either the GUI or the audio thread.
     ///
+    /// The plugin's own tasks are scheduled using the options from
+    /// [`Plugin::background_task_options()`].
+    ///
     /// If the task queue is full, then this will return false.
     #[must_use]
     pub fn schedule_background(&self, task: Task<P>) -> bool {
+        let options = match &task {
+            Task::PluginTask(task) => P::background_task_options(task),
+            _ => TaskOptions::default(),
+        };
+
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_background(task, options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
         let event_loop = self.event_loop.borrow();
         let event_loop = event_loop.as_ref().unwrap();
-        event_loop.schedule_background(task)
+        event_loop.cancel_background(tag)
     }
 
     /// Posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be delegated
@@ -475,6 +722,46 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         event_loop.schedule_gui(task)
     }
 
+    /// Run `callback` on the GUI thread after `delay` has passed. See
+    /// [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()].
+    pub fn schedule_timer_once(
+        &self,
+        delay: Duration,
+        callback: Box<dyn FnOnce() + Send>,
+    ) -> TimerHandle {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_once(
+            delay,
+            Task::TimerCallback(TimerCallback::new_once(callback)),
+        )
+    }
+
+    /// Run `callback` on the GUI thread every `interval`. See
+    /// [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()].
+    pub fn schedule_timer_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let callback = TimerCallback::new(callback);
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_repeating(interval, move || Task::TimerCallback(callback.clone()))
+    }
+
+    /// Inform the plugin that its editor has been opened or closed through
+    /// [`Plugin::editor_opened()`] and [`Plugin::editor_closed()`]. Called from the main thread
+    /// after the editor handle has been created or dropped.
+    fn notify_editor_opened(&self, opened: bool) {
+        let mut plugin = self.plugin.lock();
+        if opened {
+            plugin.editor_opened();
+        } else {
+            plugin.editor_closed();
+        }
+    }
+
     /// Request the outer window to be resized to the editor's current size.
     pub fn request_resize(&self) {
         if let Some(gui_tasks_sender) = self.gui_tasks_sender.borrow().as_ref() {
@@ -489,6 +776,17 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         }
     }
 
+    /// Queue a note event sent from the editor. See
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+    pub fn queue_gui_note_event(&self, event: NoteEvent<()>) -> bool {
+        queue_gui_note_event::<P>(&self.gui_note_events, event)
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
     pub fn set_latency_samples(&self, samples: u32) {
         // This should only change the value if it's actually needed
         let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
@@ -506,7 +804,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
         gui_task_sender: channel::Sender<GuiTask>,
     ) {
         self.clone().backend.borrow_mut().run(
-            move |buffer, aux, transport, input_events, output_events| {
+            move |buffer, aux, mut transport, input_events, output_events| {
                 // TODO: This process wrapper should actually be in the backends (since the backends
                 //       should also not allocate in their audio callbacks), but that's a bit more
                 //       error prone
@@ -515,14 +813,62 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                         return false;
                     }
 
+                    // Backends that have their own transport, like JACK, set the position
+                    if transport.pos_samples.is_none() {
+                        self.transport.process(&mut transport, buffer.samples());
+                    }
+
+                    // Notes played from the editor are handled at the start of the buffer
+                    let mut combined_input_events = self.input_events.borrow_mut();
+                    combined_input_events.clear();
+                    combined_input_events.extend(input_events.iter().cloned());
+                    prepend_gui_note_events(&self.gui_note_events, &mut combined_input_events);
+
                     let sample_rate = self.buffer_config.sample_rate;
                     {
                         let mut plugin = self.plugin.lock();
-                        if let ProcessStatus::Error(err) = plugin.process(
-                            buffer,
-                            aux,
-                            &mut self.make_process_context(transport, input_events, output_events),
-                        ) {
+                        if let Some(modulation_matrix) = &self.modulation_matrix {
+                            // SAFETY: The parameters are owned by the plugin, which outlives this
+                            //         function call
+                            unsafe {
+                                modulation_matrix.process_block(
+                                    buffer,
+                                    combined_input_events.iter(),
+                                    sample_rate,
+                                    |param_id| self.param_id_to_ptr.get(param_id).copied(),
+                                )
+                            };
+                        }
+                        let num_samples = buffer.samples();
+                        let mut bypass_processor = self.bypass_processor.borrow_mut();
+                        if P::MANAGED_BYPASS {
+                            bypass_processor.write_dry(buffer);
+                        }
+                        let result = self.dsp_load.measure(
+                            num_samples,
+                            sample_rate,
+                            ProcessMode::Realtime,
+                            || {
+                                plugin.process(
+                                    buffer,
+                                    aux,
+                                    &mut self.make_process_context(
+                                        transport,
+                                        combined_input_events.make_contiguous(),
+                                        output_events,
+                                    ),
+                                )
+                            },
+                        );
+                        if P::MANAGED_BYPASS {
+                            bypass_processor.process(
+                                buffer,
+                                self.current_latency.load(Ordering::Relaxed),
+                                self.is_bypassed(),
+                            );
+                        }
+                        self.output_peaks.update(buffer.as_slice_immutable());
+                        if let ProcessStatus::Error(err) = result {
                             nih_error!("The plugin returned an error while processing:");
                             nih_error!("{}", err);
 
@@ -546,6 +892,23 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
                     {
                         if unsafe { param_ptr.set_normalized_value(normalized_value) } {
                             unsafe { param_ptr.update_smoother(sample_rate, false) };
+                            unsafe {
+                                param_ptr.apply_macro_mappings(
+                                    |param_id| self.param_id_to_ptr.get(param_id).copied(),
+                                    Some(sample_rate),
+                                    |target_ptr, normalized_value| {
+                                        let task_posted =
+                                            self.schedule_gui(Task::ParameterValueChanged(
+                                                target_ptr,
+                                                normalized_value,
+                                            ));
+                                        nih_debug_assert!(
+                                            task_posted,
+                                            "The task queue is full, dropping task..."
+                                        );
+                                    },
+                                )
+                            };
                             let task_posted = self.schedule_gui(Task::ParameterValueChanged(
                                 param_ptr,
                                 normalized_value,
@@ -586,6 +949,7 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
     fn make_gui_context(self: Arc<Self>) -> Arc<WrapperGuiContext<P, B>> {
         Arc::new(WrapperGuiContext {
             wrapper: self,
+            clipboard: Default::default(),
             #[cfg(debug_assertions)]
             param_gesture_checker: Default::default(),
         })
@@ -607,6 +971,74 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             input_events_idx: 0,
             output_events,
             transport,
+            aux_input_count: self.audio_io_layout.aux_input_ports.len(),
+        }
+    }
+
+    /// Restore the plugin's state from a file written by [`save_state_file()`][Self::save_state_file()].
+    /// Does nothing if the file does not exist yet. This should only be called before the plugin is
+    /// initialized.
+    fn restore_state_file(&self, path: &Path) {
+        if !path.exists() {
+            return;
+        }
+
+        let mut state = match std::fs::read(path) {
+            Ok(json) => match unsafe { state::deserialize_json(&json) } {
+                Some(state) => state,
+                None => {
+                    nih_error!("Could not parse the state in '{}'", path.display());
+                    return;
+                }
+            },
+            Err(err) => {
+                nih_error!("Could not read '{}': {err}", path.display());
+                return;
+            }
+        };
+
+        let success = unsafe {
+            state::deserialize_object::<P>(
+                &mut state,
+                self.params.clone(),
+                |param_id| self.param_id_to_ptr.get(param_id).copied(),
+                Some(&self.buffer_config),
+            )
+        };
+        if success {
+            let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+            nih_log!("Restored the plugin's state from '{}'", path.display());
+        } else {
+            nih_error!(
+                "Could not restore the plugin's state from '{}'",
+                path.display()
+            );
+        }
+    }
+
+    /// Save the plugin's state to a JSON file so it can be restored the next time the standalone
+    /// application is launched. Binary blobs are appended to the JSON data.
+    fn save_state_file(&self, path: &Path) {
+        let state = self.get_state_object();
+        let result = serde_json::to_vec_pretty(&state)
+            .map_err(anyhow::Error::from)
+            .and_then(|mut json| {
+                state::append_blobs(&mut json, &state.blobs);
+
+                if let Some(parent) = path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+
+                Ok(std::fs::write(path, json)?)
+            });
+        match result {
+            Ok(()) => nih_log!("Saved the plugin's state to '{}'", path.display()),
+            Err(err) => nih_error!(
+                "Could not save the plugin's state to '{}': {err:#}",
+                path.display()
+            ),
         }
     }
 
@@ -641,6 +1073,11 @@ impl<P: Plugin, B: Backend<P>> Wrapper<P, B> {
             return false;
         }
 
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
         // If the plugin was already initialized then it needs to be reinitialized
         {
             // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
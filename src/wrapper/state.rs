@@ -7,7 +7,8 @@ use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
 
 use crate::params::ParamMut;
-use crate::prelude::{BufferConfig, Param, ParamPtr, Params, Plugin};
+use crate::prelude::{BufferConfig, MacroMapping, Param, ParamPtr, Params, Plugin};
+use crate::wrapper::util::state_cache::StateCache;
 
 // These state objects are also exposed directly to the plugin so it can do its own internal preset
 // management
@@ -21,6 +22,14 @@ pub enum ParamValue {
     Bool(bool),
     /// Only used for enum parameters that have the `#[id = "..."]` attribute set.
     String(String),
+    /// A [`MacroParam`][crate::prelude::MacroParam]'s value together with its mappings.
+    Macro {
+        value: f32,
+        mappings: Vec<MacroMapping>,
+    },
+    /// A normalized value. Used for [`DynamicParam`][crate::prelude::DynamicParam]s since their
+    /// plain values depend on the slot's current layout.
+    Normalized(f32),
 }
 
 /// A plugin's state so it can be restored at a later point. This object can be serialized and
@@ -38,6 +47,11 @@ pub struct PluginState {
     /// If the saved state is very old, then this field may be empty.
     #[serde(default)]
     pub version: String,
+    /// The plugin's [`Plugin::STATE_VERSION`] at the time the state was saved. Older states are
+    /// migrated using [`Plugin::migrate_state()`] before they are loaded. States saved before state
+    /// versioning was added have version 0.
+    #[serde(default)]
+    pub state_version: u32,
 
     /// The plugin's parameter values. These are stored unnormalized. This means the old values will
     /// be recalled when when the parameter's range gets increased. Doing so may still mess with
@@ -50,6 +64,42 @@ pub struct PluginState {
     /// The individual fields are also serialized as JSON so they can safely be restored
     /// independently of the other fields.
     pub fields: BTreeMap<String, String>,
+    /// Binary data for fields annotated with `#[persist(key = "stable_name", blob)]`. These are not
+    /// part of the JSON data. Instead they're stored as raw bytes after the JSON data when the
+    /// state is encoded.
+    #[serde(skip)]
+    pub blobs: BTreeMap<String, Vec<u8>>,
+}
+
+/// The format used to encode a [`PluginState`] when the plugin's state is saved. See
+/// [`Plugin::STATE_FORMAT`]. States saved in any of these formats can be loaded regardless of the
+/// plugin's current format, so switching formats does not break existing projects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PluginStateFormat {
+    /// Human readable JSON data. This is the default.
+    #[default]
+    Json,
+    /// Compact binary [MessagePack](https://msgpack.org) data. This is faster to save and load for
+    /// plugins with a lot of parameters. Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MessagePack,
+}
+
+/// Why the host is saving the plugin's state. This is passed to
+/// [`Params::serialize_fields()`] so plugins can leave out per-instance data, like recorded audio,
+/// when the state is saved as a preset.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum StateSaveContext {
+    /// The state is saved as part of the host's project. This is also used when the host does not
+    /// say why it's saving the plugin's state.
+    #[default]
+    Project,
+    /// The state is saved as a preset that may be loaded in other projects or on other plugin
+    /// instances.
+    Preset,
+    /// The state is used to create a copy of the plugin instance, for instance when duplicating a
+    /// track.
+    Duplicate,
 }
 
 /// Create a parameters iterator from the hashtables stored in the plugin wrappers. This avoids
@@ -85,6 +135,21 @@ pub(crate) fn make_params_getter<'a>(
 pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    context: StateSaveContext,
+) -> PluginState {
+    let mut plugin_state =
+        serialize_object_without_blobs::<P>(&*plugin_params, params_iter, context);
+    plugin_state.blobs = plugin_params.serialize_blobs(context);
+
+    plugin_state
+}
+
+/// The same as [`serialize_object()`], but without serializing the plugin's
+/// [`PluginState::blobs`].
+unsafe fn serialize_object_without_blobs<'a, P: Plugin>(
+    plugin_params: &dyn Params,
+    params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    context: StateSaveContext,
 ) -> PluginState {
     // We'll serialize parameter values as a simple `string_param_id: display_value` map.
     // NOTE: If the plugin is being modulated (and the plugin is a CLAP plugin in Bitwig Studio),
@@ -114,36 +179,142 @@ pub(crate) unsafe fn serialize_object<'a, P: Plugin>(
                     None => ParamValue::I32((*p).unmodulated_plain_value()),
                 },
             ),
+            ParamPtr::MacroParam(p) => (
+                param_id_str.clone(),
+                ParamValue::Macro {
+                    value: (*p).unmodulated_plain_value(),
+                    mappings: (*p).mappings().as_ref().clone(),
+                },
+            ),
+            ParamPtr::DynamicParam(p) => (
+                param_id_str.clone(),
+                ParamValue::Normalized((*p).unmodulated_normalized_value()),
+            ),
         })
         .collect();
 
     // The plugin can also persist arbitrary fields alongside its parameters. This is useful for
     // storing things like sample data.
-    let fields = plugin_params.serialize_fields();
+    let fields = plugin_params.serialize_fields(context);
 
     PluginState {
         version: String::from(P::VERSION),
+        state_version: P::STATE_VERSION,
         params,
         fields,
+        blobs: BTreeMap::new(),
     }
 }
 
-/// Serialize a plugin's state to a vector containing JSON data. This can (and should) be shared
-/// across plugin formats. If the `zstd` feature is enabled, then the state will be compressed using
-/// Zstandard.
+/// Serialize a plugin's state to a vector containing JSON data, or data in another format depending
+/// on [`Plugin::STATE_FORMAT`]. This can (and should) be shared across plugin formats. If the
+/// `zstd` feature is enabled and [`Plugin::COMPRESS_STATE`] is set, then the state will be
+/// compressed using Zstandard.
 pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
     plugin_params: Arc<dyn Params>,
     params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    context: StateSaveContext,
+) -> Result<Vec<u8>> {
+    let plugin_state = serialize_object::<P>(plugin_params, params_iter, context);
+
+    encode_state(&plugin_state, P::STATE_FORMAT, P::COMPRESS_STATE)
+}
+
+/// The same as [`serialize_json()`], but the plugin's blobs are taken from `state_cache` if they
+/// have not changed since they were last serialized. See [`StateCache`].
+pub(crate) unsafe fn serialize_json_cached<'a, P: Plugin>(
+    plugin_params: Arc<dyn Params>,
+    params_iter: impl IntoIterator<Item = (&'a String, ParamPtr)>,
+    context: StateSaveContext,
+    state_cache: &StateCache,
+) -> Result<Vec<u8>> {
+    let plugin_state = serialize_object_without_blobs::<P>(&*plugin_params, params_iter, context);
+
+    let mut encoded = encode_object(&plugin_state, P::STATE_FORMAT, P::COMPRESS_STATE)?;
+    encoded.extend_from_slice(&state_cache.encoded_blobs(&*plugin_params, context)?);
+
+    Ok(encoded)
+}
+
+/// Marks the start of the binary blob section that follows the JSON data if the state contains any
+/// [`PluginState::blobs`]. Each blob is stored as its key's length as a little endian 32-bit
+/// integer, the UTF-8 encoded key, the data's length as a little endian 64-bit integer, and the
+/// data itself.
+const BLOB_SECTION_MAGIC: &[u8; 8] = b"NIHBLOBS";
+
+/// The magic number every Zstandard frame starts with. This is used to tell compressed and
+/// uncompressed states apart, since uncompressed states always start with either a curly brace or
+/// [`MSGPACK_MAGIC`].
+const ZSTD_FRAME_MAGIC: &[u8; 4] = &[0x28, 0xB5, 0x2F, 0xFD];
+
+/// Marks the start of a state encoded using [`PluginStateFormat::MessagePack`]. States without this
+/// header are JSON data.
+const MSGPACK_MAGIC: &[u8; 8] = b"NIHMSGPK";
+
+/// Encode a state object followed by its blobs, optionally compressed using Zstandard. Used by
+/// [`serialize_json()`] and by the preset file functions.
+fn encode_state(
+    plugin_state: &PluginState,
+    format: PluginStateFormat,
+    compress: bool,
 ) -> Result<Vec<u8>> {
-    let plugin_state = serialize_object::<P>(plugin_params, params_iter);
-    let json = serde_json::to_vec(&plugin_state).context("Could not format as JSON")?;
+    let mut encoded = encode_object(plugin_state, format, compress)?;
+    encoded.extend_from_slice(&encode_blobs(&plugin_state.blobs, compress)?);
+
+    Ok(encoded)
+}
+
+/// Encode a state object without its blobs, optionally compressed using Zstandard.
+fn encode_object(
+    plugin_state: &PluginState,
+    format: PluginStateFormat,
+    compress: bool,
+) -> Result<Vec<u8>> {
+    let data = match format {
+        PluginStateFormat::Json => {
+            serde_json::to_vec(plugin_state).context("Could not format as JSON")?
+        }
+        #[cfg(feature = "msgpack")]
+        PluginStateFormat::MessagePack => {
+            let mut data = MSGPACK_MAGIC.to_vec();
+            rmp_serde::encode::write_named(&mut data, plugin_state)
+                .context("Could not format as MessagePack")?;
+            data
+        }
+    };
+
+    maybe_compress(data, compress)
+}
 
+/// Encode the blob section for `blobs`, optionally compressed using Zstandard. This is appended to
+/// the output of [`encode_object()`]. When compression is enabled, the state object and the blobs are
+/// stored as two consecutive Zstandard frames, which are decompressed as a single stream. This
+/// allows the blobs to be encoded separately and ahead of time. Returns an empty vector if there
+/// are no blobs.
+pub(crate) fn encode_blobs(blobs: &BTreeMap<String, Vec<u8>>, compress: bool) -> Result<Vec<u8>> {
+    if blobs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut blob_section = Vec::new();
+    append_blobs(&mut blob_section, blobs);
+
+    maybe_compress(blob_section, compress)
+}
+
+/// Compress part of a serialized state using Zstandard if `compress` is set and the `zstd` feature
+/// is enabled. The data is returned as is otherwise.
+fn maybe_compress(data: Vec<u8>, compress: bool) -> Result<Vec<u8>> {
     #[cfg(feature = "zstd")]
     {
-        let compressed = zstd::encode_all(json.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)
+        if !compress {
+            return Ok(data);
+        }
+
+        let compressed = zstd::encode_all(data.as_slice(), zstd::DEFAULT_COMPRESSION_LEVEL)
             .context("Could not compress state")?;
 
-        let state_bytes = json.len();
+        let state_bytes = data.len();
         let compressed_state_bytes = compressed.len();
         let compression_ratio = compressed_state_bytes as f32 / state_bytes as f32 * 100.0;
         nih_trace!(
@@ -155,7 +326,8 @@ pub(crate) unsafe fn serialize_json<'a, P: Plugin>(
     }
     #[cfg(not(feature = "zstd"))]
     {
-        Ok(json)
+        let _ = compress;
+        Ok(data)
     }
 }
 
@@ -178,6 +350,9 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
     current_buffer_config: Option<&BufferConfig>,
 ) -> bool {
     // This lets the plugin perform migrations on old state if needed
+    if !migrate_state::<P>(state) {
+        return false;
+    }
     P::filter_state(state);
 
     let sample_rate = current_buffer_config.map(|c| c.sample_rate);
@@ -215,6 +390,17 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
                     param_id_str,
                 );
             }
+            (ParamPtr::MacroParam(p), ParamValue::Macro { value, mappings }) => {
+                (*p).set_plain_value(*value);
+                (*p).set_mappings(mappings.clone());
+            }
+            // This allows a `FloatParam` to be turned into a macro without breaking old presets
+            (ParamPtr::MacroParam(p), ParamValue::F32(v)) => {
+                (*p).set_plain_value(*v);
+            }
+            (ParamPtr::DynamicParam(p), ParamValue::Normalized(v)) => {
+                (*p).set_normalized_value(*v);
+            }
             (param_ptr, param_value) => {
                 nih_debug_assert_failure!(
                     "Invalid serialized value {:?} for parameter \"{}\" ({:?})",
@@ -234,18 +420,90 @@ pub(crate) unsafe fn deserialize_object<P: Plugin>(
     // The plugin can also persist arbitrary fields alongside its parameters. This is useful for
     // storing things like sample data.
     plugin_params.deserialize_fields(&state.fields);
+    plugin_params.deserialize_blobs(&state.blobs);
+    plugin_params.fields_loaded();
 
     true
 }
 
+/// Migrate a state saved with an older [`Plugin::STATE_VERSION`] to the current version using
+/// [`Plugin::migrate_state()`]. Returns `false` and logs an error if the migrated state is not a
+/// valid [`PluginState`]. States from newer plugin versions are loaded as is.
+fn migrate_state<P: Plugin>(state: &mut PluginState) -> bool {
+    if state.state_version >= P::STATE_VERSION {
+        nih_debug_assert!(
+            state.state_version == P::STATE_VERSION,
+            "The state was saved with state version {}, but the plugin's state version is {}",
+            state.state_version,
+            P::STATE_VERSION
+        );
+
+        return true;
+    }
+
+    // The blobs are not part of the JSON data, so they're passed through as is
+    let blobs = std::mem::take(&mut state.blobs);
+    let mut json = match serde_json::to_value(&*state) {
+        Ok(json) => json,
+        Err(err) => {
+            nih_debug_assert_failure!("Could not convert the state to JSON: {}", err);
+            state.blobs = blobs;
+            return false;
+        }
+    };
+    for from_version in state.state_version..P::STATE_VERSION {
+        json = P::migrate_state(from_version, json);
+    }
+
+    match serde_json::from_value::<PluginState>(json) {
+        Ok(migrated_state) => {
+            nih_trace!(
+                "Migrated state from state version {} to {}",
+                state.state_version,
+                P::STATE_VERSION
+            );
+
+            *state = migrated_state;
+            state.state_version = P::STATE_VERSION;
+            state.blobs = blobs;
+
+            true
+        }
+        Err(err) => {
+            nih_debug_assert_failure!("The migrated state is invalid: {}", err);
+            state.blobs = blobs;
+            false
+        }
+    }
+}
+
 /// Deserialize a plugin's state from a vector containing (compressed) JSON data. Doesn't load the
 /// plugin state since doing so should be accompanied by calls to `Plugin::init()` and
 /// `Plugin::reset()`, and this way all of that behavior can be encapsulated so it can be reused in
 /// multiple places. The returned state object can be passed to [`deserialize_object()`].
 pub(crate) unsafe fn deserialize_json(state: &[u8]) -> Option<PluginState> {
+    // Uncompressed states, including those saved before compression was enabled, are decoded as is
+    if !state.starts_with(ZSTD_FRAME_MAGIC) {
+        return match decode_state(state) {
+            Ok(s) => Some(s),
+            Err(err) => {
+                nih_debug_assert_failure!("Error while deserializing state: {:#}", err);
+                None
+            }
+        };
+    }
+
     #[cfg(feature = "zstd")]
-    let result: Option<PluginState> = match zstd::decode_all(state) {
-        Ok(decompressed) => match serde_json::from_slice(decompressed.as_slice()) {
+    {
+        let decompressed = match zstd::decode_all(state) {
+            Ok(decompressed) => decompressed,
+            Err(err) => {
+                nih_debug_assert_failure!("Error while decompressing state: {}", err);
+                return None;
+            }
+        };
+
+        match decode_state(decompressed.as_slice()) {
             Ok(s) => {
                 let state_bytes = decompressed.len();
                 let compressed_state_bytes = state.len();
@@ -258,37 +516,443 @@ pub(crate) unsafe fn deserialize_json(state: &[u8]) -> Option<PluginState> {
                 Some(s)
             }
             Err(err) => {
-                nih_debug_assert_failure!("Error while deserializing state: {}", err);
-                None
-            }
-        },
-        // Uncompressed state files can still be loaded after enabling this feature to prevent
-        // breaking existing plugin instances
-        Err(zstd_err) => match serde_json::from_slice(state) {
-            Ok(s) => {
-                nih_trace!("Older uncompressed state found");
-                Some(s)
-            }
-            Err(json_err) => {
-                nih_debug_assert_failure!(
-                    "Error while deserializing state as either compressed or uncompressed state: \
-                     {}, {}",
-                    zstd_err,
-                    json_err
-                );
+                nih_debug_assert_failure!("Error while deserializing state: {:#}", err);
                 None
             }
-        },
-    };
+        }
+    }
 
     #[cfg(not(feature = "zstd"))]
-    let result: Option<PluginState> = match serde_json::from_slice(state) {
-        Ok(s) => Some(s),
-        Err(err) => {
-            nih_debug_assert_failure!("Error while deserializing state: {}", err);
-            None
+    {
+        nih_debug_assert_failure!(
+            "The state is compressed, but NIH-plug was compiled without the 'zstd' feature"
+        );
+        None
+    }
+}
+
+/// Append the blob section containing `blobs` to a state's JSON data. Does nothing if there are no
+/// blobs. See [`BLOB_SECTION_MAGIC`].
+pub(crate) fn append_blobs(json: &mut Vec<u8>, blobs: &BTreeMap<String, Vec<u8>>) {
+    if blobs.is_empty() {
+        return;
+    }
+
+    json.extend_from_slice(BLOB_SECTION_MAGIC);
+    for (key, blob) in blobs {
+        json.extend_from_slice(&(key.len() as u32).to_le_bytes());
+        json.extend_from_slice(key.as_bytes());
+        json.extend_from_slice(&(blob.len() as u64).to_le_bytes());
+        json.extend_from_slice(blob);
+    }
+}
+
+/// Decode the (decompressed) data written by [`encode_state()`]. The state object may be followed
+/// by a section containing the state's binary blobs.
+fn decode_state(data: &[u8]) -> Result<PluginState> {
+    let (mut state, mut remaining) = match data.strip_prefix(MSGPACK_MAGIC.as_slice()) {
+        Some(msgpack) => decode_msgpack(msgpack)?,
+        None => {
+            let mut json = serde_json::Deserializer::from_slice(data).into_iter::<PluginState>();
+            let state = json
+                .next()
+                .context("The state is empty")?
+                .context("Could not parse the state as JSON")?;
+
+            (state, &data[json.byte_offset()..])
         }
     };
 
-    result
+    let num_whitespace_bytes = remaining
+        .iter()
+        .take_while(|byte| byte.is_ascii_whitespace())
+        .count();
+    remaining = &remaining[num_whitespace_bytes..];
+    if remaining.is_empty() {
+        return Ok(state);
+    }
+
+    let (magic, mut remaining) = split_at_checked(remaining, BLOB_SECTION_MAGIC.len())
+        .context("Unexpected data after the state object")?;
+    anyhow::ensure!(
+        magic == BLOB_SECTION_MAGIC,
+        "Unexpected data after the state object"
+    );
+    while !remaining.is_empty() {
+        let (key_length, rest) =
+            split_at_checked(remaining, 4).context("The state's blobs are truncated")?;
+        let key_length = u32::from_le_bytes(key_length.try_into().unwrap()) as usize;
+        let (key, rest) =
+            split_at_checked(rest, key_length).context("The state's blobs are truncated")?;
+        let key = String::from_utf8(key.to_vec()).context("Invalid blob key")?;
+        let (blob_length, rest) =
+            split_at_checked(rest, 8).context("The state's blobs are truncated")?;
+        let blob_length = u64::from_le_bytes(blob_length.try_into().unwrap()) as usize;
+        let (blob, rest) =
+            split_at_checked(rest, blob_length).context("The state's blobs are truncated")?;
+
+        state.blobs.insert(key, blob.to_vec());
+        remaining = rest;
+    }
+
+    Ok(state)
+}
+
+/// Decode a state object encoded using [`PluginStateFormat::MessagePack`], without its
+/// [`MSGPACK_MAGIC`] header. Returns the state and the data following it.
+#[cfg(feature = "msgpack")]
+fn decode_msgpack(data: &[u8]) -> Result<(PluginState, &[u8])> {
+    // Reading from a slice advances it, so this leaves exactly the data after the state object
+    let mut remaining = data;
+    let state =
+        rmp_serde::from_read(&mut remaining).context("Could not parse the state as MessagePack")?;
+
+    Ok((state, remaining))
+}
+
+#[cfg(not(feature = "msgpack"))]
+fn decode_msgpack(_data: &[u8]) -> Result<(PluginState, &[u8])> {
+    anyhow::bail!(
+        "The state uses MessagePack, but NIH-plug was compiled without the 'msgpack' feature"
+    )
+}
+
+/// The magic bytes at the start of a `.vstpreset` file.
+const VST3_PRESET_MAGIC: &[u8; 4] = b"VST3";
+/// The `.vstpreset` format version written by the VST3 SDK.
+const VST3_PRESET_VERSION: i32 = 1;
+/// The size of a `.vstpreset` file's header. This consists of the magic bytes, the format version,
+/// the class ID as 32 ASCII hex characters, and the offset to the chunk list.
+const VST3_PRESET_HEADER_SIZE: usize = 4 + 4 + 32 + 8;
+/// The chunk containing the component's state. NIH-plug only uses a single component, so this is
+/// the only data chunk in the file.
+const VST3_PRESET_COMPONENT_CHUNK_ID: &[u8; 4] = b"Comp";
+
+/// Write a plugin state to a VST3 `.vstpreset` file's contents. These files contain the plugin's
+/// class ID, so they can be loaded by the host's preset browser. The state object can be obtained
+/// using [`GuiContext::get_state()`][crate::prelude::GuiContext::get_state()].
+#[cfg(feature = "vst3")]
+pub fn serialize_vst3_preset<P: crate::prelude::Vst3Plugin>(
+    state: &PluginState,
+) -> Result<Vec<u8>> {
+    write_vst3_preset(P::VST3_CLASS_ID, state)
+}
+
+/// Read a plugin state from a VST3 `.vstpreset` file's contents. Returns an error if the file is
+/// malformed or if it was saved by a different plugin. The state can be loaded using
+/// [`GuiContext::set_state()`][crate::prelude::GuiContext::set_state()].
+#[cfg(feature = "vst3")]
+pub fn deserialize_vst3_preset<P: crate::prelude::Vst3Plugin>(
+    preset: &[u8],
+) -> Result<PluginState> {
+    read_vst3_preset(P::VST3_CLASS_ID, preset)
+}
+
+/// Write a plugin state to the contents of a CLAP preset file. CLAP does not define a preset file
+/// format, so this uses the same format as the state saved through CLAP's state extension: the
+/// encoded state prefixed by its length as a little endian 64-bit integer. The state object can be
+/// obtained using [`GuiContext::get_state()`][crate::prelude::GuiContext::get_state()]. The state
+/// is compressed if the `zstd` feature is enabled.
+pub fn serialize_clap_preset(state: &PluginState) -> Result<Vec<u8>> {
+    let encoded = encode_state(state, PluginStateFormat::Json, true)?;
+
+    let mut preset = Vec::with_capacity(8 + encoded.len());
+    preset.extend_from_slice(&(encoded.len() as u64).to_le_bytes());
+    preset.extend_from_slice(&encoded);
+
+    Ok(preset)
+}
+
+/// Read a plugin state from the contents of a CLAP preset file written by
+/// [`serialize_clap_preset()`] or saved by the host through CLAP's state extension.
+pub fn deserialize_clap_preset(preset: &[u8]) -> Result<PluginState> {
+    let (length_bytes, encoded) = split_at_checked(preset, 8).context("The preset is too short")?;
+    let length = u64::from_le_bytes(length_bytes.try_into().unwrap()) as usize;
+    let encoded = encoded
+        .get(..length)
+        .context("The preset's state is truncated")?;
+
+    unsafe { deserialize_json(encoded) }.context("Could not decode the preset's state")
+}
+
+/// The prefix of the strings written by [`serialize_state_string()`]. This makes it possible to
+/// tell plugin states apart from other text on the clipboard.
+const STATE_STRING_PREFIX: &str = "nih-plug-state:";
+/// The characters used for the base64 encoding in state strings.
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// Encode a plugin state as a compact single line string that can be copied to the clipboard and
+/// shared as text. The state is compressed if the `zstd` feature is enabled, and the result is
+/// base64 encoded. Use [`deserialize_state_string()`] to decode the string again.
+pub fn serialize_state_string(state: &PluginState) -> Result<String> {
+    let encoded = encode_state(state, PluginStateFormat::Json, true)?;
+
+    Ok(format!("{STATE_STRING_PREFIX}{}", encode_base64(&encoded)))
+}
+
+/// Decode a state string written by [`serialize_state_string()`]. Leading and trailing whitespace
+/// is ignored.
+pub fn deserialize_state_string(state: &str) -> Result<PluginState> {
+    let encoded = state
+        .trim()
+        .strip_prefix(STATE_STRING_PREFIX)
+        .context("The text is not a plugin state")?;
+    let encoded = decode_base64(encoded).context("The plugin state is not valid base64")?;
+
+    unsafe { deserialize_json(&encoded) }.context("Could not decode the plugin state")
+}
+
+/// Encode data as padded base64.
+fn encode_base64(data: &[u8]) -> String {
+    let mut encoded = String::with_capacity((data.len() + 2) / 3 * 4);
+    for chunk in data.chunks(3) {
+        let bytes = [
+            chunk[0],
+            chunk.get(1).copied().unwrap_or(0),
+            chunk.get(2).copied().unwrap_or(0),
+        ];
+        let indices = [
+            bytes[0] >> 2,
+            ((bytes[0] & 0b11) << 4) | (bytes[1] >> 4),
+            ((bytes[1] & 0b1111) << 2) | (bytes[2] >> 6),
+            bytes[2] & 0b111111,
+        ];
+
+        // A chunk of n bytes results in n + 1 characters, and the rest is padding
+        for (i, index) in indices.into_iter().enumerate() {
+            if i <= chunk.len() {
+                encoded.push(BASE64_ALPHABET[index as usize] as char);
+            } else {
+                encoded.push('=');
+            }
+        }
+    }
+
+    encoded
+}
+
+/// Decode padded base64 data written by [`encode_base64()`]. Returns `None` if the data is not
+/// valid base64.
+fn decode_base64(encoded: &str) -> Option<Vec<u8>> {
+    let encoded = encoded.trim_end_matches('=').as_bytes();
+    let mut data = Vec::with_capacity(encoded.len() * 3 / 4);
+    for chunk in encoded.chunks(4) {
+        // A single character can't encode a full byte
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut bits = 0u32;
+        for (i, character) in chunk.iter().enumerate() {
+            let index = BASE64_ALPHABET.iter().position(|c| c == character)? as u32;
+            bits |= index << (18 - (i * 6));
+        }
+        for i in 0..chunk.len() - 1 {
+            data.push((bits >> (16 - (i * 8))) as u8);
+        }
+    }
+
+    Some(data)
+}
+
+pub(crate) fn write_vst3_preset(class_id: [u8; 16], state: &PluginState) -> Result<Vec<u8>> {
+    let encoded = encode_state(state, PluginStateFormat::Json, true)?;
+    let chunk_list_offset = (VST3_PRESET_HEADER_SIZE + encoded.len()) as i64;
+
+    let mut preset = Vec::with_capacity(VST3_PRESET_HEADER_SIZE + encoded.len() + 32);
+    preset.extend_from_slice(VST3_PRESET_MAGIC);
+    preset.extend_from_slice(&VST3_PRESET_VERSION.to_le_bytes());
+    preset.extend_from_slice(vst3_class_id_string(class_id).as_bytes());
+    preset.extend_from_slice(&chunk_list_offset.to_le_bytes());
+    preset.extend_from_slice(&encoded);
+
+    // The chunk list contains the ID, offset, and size of every data chunk
+    preset.extend_from_slice(b"List");
+    preset.extend_from_slice(&1i32.to_le_bytes());
+    preset.extend_from_slice(VST3_PRESET_COMPONENT_CHUNK_ID);
+    preset.extend_from_slice(&(VST3_PRESET_HEADER_SIZE as i64).to_le_bytes());
+    preset.extend_from_slice(&(encoded.len() as i64).to_le_bytes());
+
+    Ok(preset)
+}
+
+pub(crate) fn read_vst3_preset(class_id: [u8; 16], preset: &[u8]) -> Result<PluginState> {
+    let header = preset
+        .get(..VST3_PRESET_HEADER_SIZE)
+        .context("The preset is too short")?;
+    anyhow::ensure!(
+        &header[..4] == VST3_PRESET_MAGIC,
+        "The file is not a VST3 preset"
+    );
+    anyhow::ensure!(
+        header[8..40].eq_ignore_ascii_case(vst3_class_id_string(class_id).as_bytes()),
+        "The preset belongs to a different plugin ({})",
+        String::from_utf8_lossy(&header[8..40])
+    );
+    let chunk_list_offset = i64::from_le_bytes(header[40..48].try_into().unwrap()) as usize;
+
+    let chunk_list = preset
+        .get(chunk_list_offset..)
+        .context("The preset's chunk list is missing")?;
+    anyhow::ensure!(
+        chunk_list.len() >= 8 && &chunk_list[..4] == b"List",
+        "The preset's chunk list is invalid"
+    );
+    let num_chunks = i32::from_le_bytes(chunk_list[4..8].try_into().unwrap()).max(0) as usize;
+
+    // Each entry in the chunk list consists of a four character ID, an offset, and a size
+    let component_chunk = chunk_list[8..]
+        .chunks_exact(20)
+        .take(num_chunks)
+        .find(|entry| &entry[..4] == VST3_PRESET_COMPONENT_CHUNK_ID)
+        .context("The preset does not contain any component state")?;
+    let offset = i64::from_le_bytes(component_chunk[4..12].try_into().unwrap()) as usize;
+    let size = i64::from_le_bytes(component_chunk[12..20].try_into().unwrap()) as usize;
+    let encoded = offset
+        .checked_add(size)
+        .and_then(|end| preset.get(offset..end))
+        .context("The preset's component state is truncated")?;
+
+    unsafe { deserialize_json(encoded) }.context("Could not decode the preset's state")
+}
+
+/// Format a class ID the same way the VST3 SDK does in preset files. This uses the class ID's
+/// canonical byte order, regardless of the platform.
+fn vst3_class_id_string(class_id: [u8; 16]) -> String {
+    class_id.iter().map(|byte| format!("{byte:02X}")).collect()
+}
+
+/// `<[u8]>::split_at()`, but returning `None` instead of panicking if `mid` is out of bounds.
+fn split_at_checked(slice: &[u8], mid: usize) -> Option<(&[u8], &[u8])> {
+    if mid <= slice.len() {
+        Some(slice.split_at(mid))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_state() -> PluginState {
+        PluginState {
+            version: String::from("1.2.3"),
+            state_version: 2,
+            params: BTreeMap::from([(String::from("gain"), ParamValue::F32(-6.0))]),
+            fields: BTreeMap::from([(String::from("foo"), String::from("\"bar\""))]),
+            blobs: BTreeMap::from([(String::from("recording"), vec![0, 1, 2, 255])]),
+        }
+    }
+
+    #[test]
+    fn test_vst3_preset_roundtrip() {
+        let class_id = *b"NihPresetTest123";
+        let preset = write_vst3_preset(class_id, &test_state()).unwrap();
+        assert_eq!(&preset[..4], b"VST3");
+        assert_eq!(&preset[8..40], b"4E696850726573657454657374313233");
+
+        let state = read_vst3_preset(class_id, &preset).unwrap();
+        assert_eq!(state.version, "1.2.3");
+        assert_eq!(state.state_version, 2);
+        assert!(matches!(state.params["gain"], ParamValue::F32(v) if v == -6.0));
+        assert_eq!(state.fields, test_state().fields);
+        assert_eq!(state.blobs, test_state().blobs);
+
+        assert!(read_vst3_preset(*b"SomeOtherPlugin!", &preset).is_err());
+    }
+
+    #[test]
+    fn test_clap_preset_roundtrip() {
+        let preset = serialize_clap_preset(&test_state()).unwrap();
+        let state = deserialize_clap_preset(&preset).unwrap();
+        assert_eq!(state.version, "1.2.3");
+        assert_eq!(state.fields, test_state().fields);
+        assert_eq!(state.blobs, test_state().blobs);
+
+        assert!(deserialize_clap_preset(&preset[..preset.len() - 1]).is_err());
+    }
+
+    #[test]
+    fn test_state_string_roundtrip() {
+        let state_string = serialize_state_string(&test_state()).unwrap();
+        assert!(state_string.starts_with(STATE_STRING_PREFIX));
+        assert!(!state_string.contains(char::is_whitespace));
+
+        let state = deserialize_state_string(&format!("  {state_string}\n")).unwrap();
+        assert_eq!(state.version, "1.2.3");
+        assert!(matches!(state.params["gain"], ParamValue::F32(v) if v == -6.0));
+        assert_eq!(state.fields, test_state().fields);
+        assert_eq!(state.blobs, test_state().blobs);
+
+        assert!(deserialize_state_string("some other text").is_err());
+        assert!(deserialize_state_string(&format!("{STATE_STRING_PREFIX}not base64!")).is_err());
+    }
+
+    #[test]
+    fn test_base64() {
+        for data in [&b""[..], b"f", b"fo", b"foo", b"foob", b"fooba", b"foobar"] {
+            assert_eq!(decode_base64(&encode_base64(data)).unwrap(), data);
+        }
+        assert_eq!(encode_base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(encode_base64(b"fooba"), "Zm9vYmE=");
+        assert_eq!(encode_base64(b"foob"), "Zm9vYg==");
+    }
+
+    #[test]
+    fn test_state_without_blobs() {
+        let mut state = test_state();
+        state.blobs.clear();
+
+        let encoded = encode_state(&state, PluginStateFormat::Json, true).unwrap();
+        let decoded = unsafe { deserialize_json(&encoded) }.unwrap();
+        assert_eq!(decoded.fields, state.fields);
+        assert!(decoded.blobs.is_empty());
+    }
+
+    #[test]
+    fn test_uncompressed_state() {
+        let state = test_state();
+
+        let encoded = encode_state(&state, PluginStateFormat::Json, false).unwrap();
+        assert_eq!(encoded.first(), Some(&b'{'));
+
+        let decoded = unsafe { deserialize_json(&encoded) }.unwrap();
+        assert_eq!(decoded.fields, state.fields);
+        assert_eq!(decoded.blobs, state.blobs);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_compressed_state() {
+        let state = test_state();
+
+        let encoded = encode_state(&state, PluginStateFormat::Json, true).unwrap();
+        assert!(encoded.starts_with(ZSTD_FRAME_MAGIC));
+
+        let decoded = unsafe { deserialize_json(&encoded) }.unwrap();
+        assert_eq!(decoded.fields, state.fields);
+        assert_eq!(decoded.blobs, state.blobs);
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_msgpack_state() {
+        let state = test_state();
+
+        for compress in [false, true] {
+            let encoded = encode_state(&state, PluginStateFormat::MessagePack, compress).unwrap();
+            if !compress || cfg!(not(feature = "zstd")) {
+                assert!(encoded.starts_with(MSGPACK_MAGIC));
+            }
+
+            let decoded = unsafe { deserialize_json(&encoded) }.unwrap();
+            assert_eq!(decoded.version, state.version);
+            assert_eq!(decoded.state_version, state.state_version);
+            assert!(matches!(decoded.params["gain"], ParamValue::F32(v) if v == -6.0));
+            assert_eq!(decoded.fields, state.fields);
+            assert_eq!(decoded.blobs, state.blobs);
+        }
+    }
 }
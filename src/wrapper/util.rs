@@ -1,13 +1,57 @@
 use backtrace::Backtrace;
+use crossbeam::queue::ArrayQueue;
 use std::cmp;
+#[cfg(any(feature = "lv2", feature = "wam"))]
+use std::collections::HashMap;
+use std::collections::VecDeque;
 use std::marker::PhantomData;
 use std::os::raw::c_char;
 
-use crate::util::permit_alloc;
+use crate::prelude::{
+    MidiConfig, NoteEvent, ParamFlags, ParamPtr, Plugin, PluginNoteEvent, ProcessStatus,
+};
+use crate::util::{self, permit_alloc, Tuning};
 
+#[cfg(all(debug_assertions, feature = "detect_process_allocs"))]
+pub(crate) mod alloc_detector;
 pub(crate) mod buffer_management;
+pub(crate) mod bypass;
+pub(crate) mod clipboard;
 #[cfg(debug_assertions)]
 pub(crate) mod context_checks;
+pub(crate) mod dsp_load;
+pub(crate) mod event_scheduler;
+pub(crate) mod group_edit;
+pub(crate) mod output_params;
+pub(crate) mod param_links;
+pub(crate) mod state_cache;
+pub(crate) mod window_focus;
+
+/// Early exit out of a function with the specified return value when one of the passed pointers is
+/// null. This is shared by the ARA, LV2, and WAM wrappers.
+#[cfg(any(feature = "ara", feature = "lv2", feature = "wam"))]
+macro_rules! check_null_ptr {
+    ($ret:expr, $ptr:expr $(, $ptrs:expr)* $(, )?) => {
+        $crate::wrapper::util::check_null_ptr_msg!("Null pointer passed to function", $ret, $ptr $(, $ptrs)*)
+    };
+}
+
+/// The same as [`check_null_ptr!`], but with a custom message.
+#[cfg(any(feature = "ara", feature = "lv2", feature = "wam"))]
+macro_rules! check_null_ptr_msg {
+    ($msg:expr, $ret:expr, $ptr:expr $(, $ptrs:expr)* $(, )?) => {
+        // Clippy doesn't understand it when we use a unit in our `check_null_ptr!()` macro, even
+        // if we explicitly pattern match on that unit
+        #[allow(clippy::unused_unit)]
+        if $ptr.is_null() $(|| $ptrs.is_null())* {
+            nih_debug_assert_failure!($msg);
+            return $ret;
+        }
+    };
+}
+
+#[cfg(any(feature = "ara", feature = "lv2", feature = "wam"))]
+pub(crate) use {check_null_ptr, check_null_ptr_msg};
 
 /// The bit that controls flush-to-zero behavior for denormals in 32 and 64-bit floating point
 /// numbers on AArch64.
@@ -23,6 +67,13 @@ const AARCH64_FTZ_BIT: u64 = 1 << 24;
 ))]
 compile_error!("The 'assert_process_allocs' feature does not work correctly in combination with the 'x86_64-pc-windows-gnu' target, see https://github.com/Windfisch/rust-assert-no-alloc/issues/7");
 
+#[cfg(all(
+    debug_assertions,
+    feature = "assert_process_allocs",
+    feature = "detect_process_allocs"
+))]
+compile_error!("The 'assert_process_allocs' and 'detect_process_allocs' features cannot be enabled at the same time");
+
 #[cfg(all(debug_assertions, feature = "assert_process_allocs"))]
 #[global_allocator]
 static A: assert_no_alloc::AllocDisabler = assert_no_alloc::AllocDisabler;
@@ -41,6 +92,16 @@ pub fn hash_param_id(id: &str) -> u32 {
     hash
 }
 
+/// Look up a parameter's ID from its hash for the wrappers that only store the ID to hash mapping.
+/// This is a linear search, so it should not be used on the audio thread.
+#[cfg(any(feature = "lv2", feature = "wam"))]
+pub fn param_id_from_hash(param_id_to_hash: &HashMap<String, u32>, hash: u32) -> Option<&str> {
+    param_id_to_hash
+        .iter()
+        .find(|(_, h)| **h == hash)
+        .map(|(id, _)| id.as_str())
+}
+
 /// The equivalent of the `strlcpy()` C function. Copy `src` to `dest` as a null-terminated
 /// C-string. If `dest` does not have enough capacity, add a null terminator at the end to prevent
 /// buffer overflows.
@@ -89,6 +150,100 @@ pub fn clamp_output_event_timing(timing: u32, total_buffer_len: u32) -> u32 {
     timing.min(last_valid_index)
 }
 
+/// Find the plugin's bypass parameter, if it has one. Hosts only use the first bypass parameter, so
+/// the other ones are ignored here as well.
+pub fn find_bypass_param<P: Plugin>(
+    param_ptrs: impl IntoIterator<Item = ParamPtr>,
+) -> Option<ParamPtr> {
+    let bypass_param = param_ptrs
+        .into_iter()
+        .find(|ptr| unsafe { ptr.flags() }.contains(ParamFlags::BYPASS));
+    nih_debug_assert!(
+        !P::MANAGED_BYPASS || bypass_param.is_some(),
+        "Plugin::MANAGED_BYPASS is enabled, but the plugin does not have a bypass parameter"
+    );
+
+    bypass_param
+}
+
+/// Whether the bypass parameter found using [`find_bypass_param()`] is currently enabled.
+pub fn bypass_param_enabled(bypass_param: Option<ParamPtr>) -> bool {
+    match bypass_param {
+        Some(ptr) => unsafe { ptr.modulated_normalized_value() >= 0.5 },
+        None => false,
+    }
+}
+
+/// The frequency of a MIDI note in 12-TET with A4 = 440 Hz. This is used for
+/// [`ProcessContext::note_frequency()`][crate::prelude::ProcessContext::note_frequency()] when the
+/// host does not provide a tuning.
+pub fn equal_temperament_frequency(note: u8) -> f32 {
+    Tuning::equal_temperament()
+        .frequency(note)
+        .unwrap_or_else(|| util::midi_note_to_freq(note))
+}
+
+/// Convert a tail length in seconds as passed to
+/// [`ProcessContext::set_tail_seconds()`][crate::prelude::ProcessContext::set_tail_seconds()] to
+/// samples. An infinite tail is represented by `u32::MAX`.
+pub fn tail_seconds_to_samples(seconds: f32, sample_rate: f32) -> u32 {
+    if seconds.is_infinite() {
+        u32::MAX
+    } else {
+        // This saturates at `u32::MAX`, and negative or NaN lengths become 0
+        (seconds * sample_rate).round() as u32
+    }
+}
+
+/// The tail length in samples that should be reported to the host. A tail returned through the
+/// plugin's [`ProcessStatus`] takes precedence over the tail length set through
+/// [`ProcessContext::set_tail_seconds()`][crate::prelude::ProcessContext::set_tail_seconds()] if it
+/// is longer.
+pub fn reported_tail_samples(last_process_status: ProcessStatus, tail_samples: u32) -> u32 {
+    match last_process_status {
+        ProcessStatus::Tail(samples) => samples.max(tail_samples),
+        ProcessStatus::KeepAlive => u32::MAX,
+        _ => tail_samples,
+    }
+}
+
+/// The number of note events sent from the editor that can be queued up before the next processing
+/// cycle. Used by all wrappers for
+/// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+pub const GUI_NOTE_EVENT_QUEUE_CAPACITY: usize = 512;
+
+/// Queue a note event sent from the editor so it can be handled in the next processing cycle.
+/// Returns `false` if the plugin does not accept note events or if the queue is full.
+pub fn queue_gui_note_event<P: Plugin>(
+    queue: &ArrayQueue<PluginNoteEvent<P>>,
+    event: NoteEvent<()>,
+) -> bool {
+    if P::MIDI_INPUT < MidiConfig::Basic {
+        nih_debug_assert_failure!(
+            "The editor sent a note event, but the plugin does not accept note events"
+        );
+        return false;
+    }
+
+    match event.into_gui_event() {
+        Some(event) => queue.push(event).is_ok(),
+        None => false,
+    }
+}
+
+/// Move all queued note events sent from the editor to the start of a processing cycle's input
+/// events. Their timings are always 0, so the input events remain sorted.
+pub fn prepend_gui_note_events<S>(
+    queue: &ArrayQueue<NoteEvent<S>>,
+    input_events: &mut VecDeque<NoteEvent<S>>,
+) {
+    let mut num_gui_events = 0;
+    while let Some(event) = queue.pop() {
+        input_events.insert(num_gui_events, event);
+        num_gui_events += 1;
+    }
+}
+
 /// Set up the logger so that the `nih_*!()` logging and assertion macros log output to a
 /// centralized location and panics also get written there. By default this logs to STDERR. If a
 /// Windows debugger is attached, then messages will be sent there instead. This uses
@@ -184,6 +339,8 @@ pub fn process_wrapper<T, F: FnOnce() -> T>(f: F) -> T {
     cfg_if::cfg_if! {
         if #[cfg(all(debug_assertions, feature = "assert_process_allocs"))] {
             assert_no_alloc::assert_no_alloc(f)
+        } else if #[cfg(all(debug_assertions, feature = "detect_process_allocs"))] {
+            alloc_detector::detect_allocs(f)
         } else {
             f()
         }
@@ -0,0 +1,91 @@
+//! A global allocator for the `detect_process_allocs` feature. Unlike `assert_process_allocs`, this
+//! doesn't abort the program when the audio thread allocates during the process function. Instead
+//! it reports the allocation with a backtrace pointing at the call site using
+//! `nih_debug_assert_failure!()`, and then carries on as usual.
+
+use backtrace::Backtrace;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+#[global_allocator]
+static A: AllocDetector = AllocDetector;
+
+/// Forwards all allocations to the system allocator, and reports the ones made from within
+/// [`detect_allocs()`].
+struct AllocDetector;
+
+thread_local! {
+    /// Nonzero while the current thread is running [`detect_allocs()`].
+    static DETECT_DEPTH: Cell<u32> = const { Cell::new(0) };
+    /// Nonzero while the current thread is running [`permit_alloc()`].
+    static PERMIT_DEPTH: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Increments a depth counter for as long as this object is alive. Restores the counter when
+/// unwinding.
+struct DepthGuard(&'static std::thread::LocalKey<Cell<u32>>);
+
+impl DepthGuard {
+    fn new(depth: &'static std::thread::LocalKey<Cell<u32>>) -> Self {
+        depth.with(|depth| depth.set(depth.get() + 1));
+        Self(depth)
+    }
+}
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        self.0.with(|depth| depth.set(depth.get() - 1));
+    }
+}
+
+/// Run `func` while reporting every allocation and deallocation it makes.
+pub fn detect_allocs<T, F: FnOnce() -> T>(func: F) -> T {
+    let _guard = DepthGuard::new(&DETECT_DEPTH);
+    func()
+}
+
+/// Allow allocations within `func`, even when it's called from [`detect_allocs()`].
+pub fn permit_alloc<T, F: FnOnce() -> T>(func: F) -> T {
+    let _guard = DepthGuard::new(&PERMIT_DEPTH);
+    func()
+}
+
+/// Report the allocation if it happened within [`detect_allocs()`].
+fn check_alloc(operation: &str, layout: Layout) {
+    // The thread locals may no longer be accessible when the thread is shutting down
+    let should_report = DETECT_DEPTH.try_with(Cell::get).unwrap_or(0) > 0
+        && PERMIT_DEPTH.try_with(Cell::get).unwrap_or(1) == 0;
+    if should_report {
+        // Reporting the allocation allocates, so this should not recurse
+        permit_alloc(|| {
+            nih_debug_assert_failure!(
+                "Detected {} of {} bytes in the process function\n{:?}",
+                operation,
+                layout.size(),
+                Backtrace::new()
+            );
+        });
+    }
+}
+
+unsafe impl GlobalAlloc for AllocDetector {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        check_alloc("an allocation", layout);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        check_alloc("an allocation", layout);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        check_alloc("a reallocation", layout);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        check_alloc("a deallocation", layout);
+        System.dealloc(ptr, layout)
+    }
+}
@@ -265,8 +265,7 @@ impl BufferManager {
             )
         {
             // Since these buffers are backed by our own storage, we can fill them with zeroes if
-            // the pointers are missing for whatever reason that might be
-            nih_debug_assert!(input_channel_pointers.is_some());
+            // the pointers are missing, for instance because the host deactivated the bus
             match input_channel_pointers {
                 Some(input_channel_pointers) => {
                     nih_debug_assert_eq!(input_channel_pointers.num_channels, input_storage.len());
@@ -292,11 +291,13 @@ impl BufferManager {
                         .iter_mut()
                         .skip(input_channel_pointers.num_channels)
                     {
+                        channel.resize(num_samples, 0.0);
                         channel.fill(0.0);
                     }
                 }
                 None => {
                     for channel in input_storage.iter_mut() {
+                        channel.resize(num_samples, 0.0);
                         channel.fill(0.0);
                     }
                 }
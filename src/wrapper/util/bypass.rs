@@ -0,0 +1,135 @@
+//! The framework-managed bypass used when
+//! [`Plugin::MANAGED_BYPASS`][crate::prelude::Plugin::MANAGED_BYPASS] is enabled.
+
+use crate::buffer::Buffer;
+use crate::util::{DryWetMixer, MixingStyle};
+
+/// The length of the crossfade between the processed and the dry signal when the bypass parameter
+/// changes, in seconds.
+const CROSSFADE_TIME: f32 = 0.01;
+
+/// Crossfades the plugin's main output to a copy of its main input when the plugin's bypass
+/// parameter is enabled. The dry signal is delayed by the plugin's latency using a
+/// [`DryWetMixer`] so that it lines up with the processed signal, which avoids comb filtering
+/// during the crossfade and keeps the bypassed signal in sync with the rest of the host's latency
+/// compensated mix.
+#[derive(Debug, Default)]
+pub struct BypassProcessor {
+    mixer: DryWetMixer,
+
+    /// The current mix between the processed signal and the dry signal. 0.0 means that only the
+    /// processed signal is output, and 1.0 means that the plugin is fully bypassed.
+    mix: f32,
+    /// The amount `mix` changes by per sample during a crossfade.
+    mix_step: f32,
+}
+
+impl BypassProcessor {
+    /// Allocate the delay lines for a plugin with `num_channels` main output channels. Latencies
+    /// above `max_latency` samples are clamped to that value. Latency changes require the plugin to
+    /// be reactivated, so the current latency can be used here. This allocates and should be called
+    /// when the plugin gets activated.
+    pub fn initialize(
+        &mut self,
+        num_channels: usize,
+        max_latency: u32,
+        max_buffer_size: u32,
+        sample_rate: f32,
+        bypassed: bool,
+    ) {
+        self.mixer
+            .resize(num_channels, max_buffer_size as usize, max_latency as usize);
+        self.mix_step = 1.0 / (CROSSFADE_TIME * sample_rate).max(1.0);
+        self.reset(bypassed);
+    }
+
+    /// Clear the delay lines and immediately jump to the new bypass state without crossfading.
+    pub fn reset(&mut self, bypassed: bool) {
+        self.mixer.reset();
+        self.mix = if bypassed { 1.0 } else { 0.0 };
+    }
+
+    /// Store the block's dry signal. This needs to be called with the main buffer before the
+    /// plugin processes it.
+    pub fn write_dry(&mut self, buffer: &Buffer) {
+        self.mixer.write_dry(buffer);
+    }
+
+    /// Crossfade the processed signal in `buffer` with the dry signal stored by
+    /// [`write_dry()`][Self::write_dry()], delayed by `latency` samples. This also advances the
+    /// delay lines, so it must be called exactly once after every call to `write_dry()`.
+    pub fn process(&mut self, buffer: &mut Buffer, latency: u32, bypassed: bool) {
+        let target_mix = if bypassed { 1.0 } else { 0.0 };
+        if self.mix == target_mix {
+            self.mixer.mix_in_dry(
+                buffer,
+                1.0 - self.mix,
+                MixingStyle::Linear,
+                latency as usize,
+            );
+        } else {
+            let mix = &mut self.mix;
+            let mix_step = self.mix_step;
+            self.mixer
+                .mix_in_dry_with(buffer, MixingStyle::Linear, latency as usize, || {
+                    *mix = step_towards(*mix, target_mix, mix_step);
+                    1.0 - *mix
+                });
+        }
+    }
+}
+
+/// Move `current` towards `target` by at most `step`.
+fn step_towards(current: f32, target: f32, step: f32) -> f32 {
+    if current < target {
+        (current + step).min(target)
+    } else {
+        (current - step).max(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::buffer::test_util::with_buffer;
+
+    #[test]
+    fn test_bypassed_output_is_delayed_by_latency() {
+        let mut bypass = BypassProcessor::default();
+        bypass.initialize(1, 4, 4, 44_100.0, true);
+
+        for block in 0..3 {
+            let mut samples = [1.0, 2.0, 3.0, 4.0].map(|sample| sample + (block as f32 * 4.0));
+            with_buffer(&mut samples, |buffer| {
+                bypass.write_dry(buffer);
+                // The plugin would normally process the buffer here
+                buffer.as_slice()[0].fill(-1.0);
+                bypass.process(buffer, 2, true);
+            });
+
+            match block {
+                0 => assert_eq!(samples, [0.0, 0.0, 1.0, 2.0]),
+                _ => assert_eq!(
+                    samples,
+                    [3.0, 4.0, 5.0, 6.0].map(|sample| sample + ((block - 1) as f32 * 4.0))
+                ),
+            }
+        }
+    }
+
+    #[test]
+    fn test_crossfade() {
+        let mut bypass = BypassProcessor::default();
+        // This results in a four sample crossfade
+        bypass.initialize(1, 0, 8, 400.0, false);
+
+        let mut samples = [1.0; 8];
+        with_buffer(&mut samples, |buffer| {
+            bypass.write_dry(buffer);
+            buffer.as_slice()[0].fill(0.0);
+            bypass.process(buffer, 0, true);
+        });
+
+        assert_eq!(samples, [0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 1.0, 1.0]);
+    }
+}
@@ -0,0 +1,73 @@
+//! Access to the system clipboard for [`GuiContext`][crate::prelude::GuiContext]'s clipboard
+//! functions. The plugin APIs don't offer clipboard access, so all wrappers use the OS clipboard
+//! directly. This requires the `clipboard` feature.
+
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+use parking_lot::Mutex;
+
+/// A handle to the system clipboard, stored on the wrappers' GUI contexts. On X11 the copied text
+/// is owned by the application that copied it, so the handle is kept around for as long as the
+/// editor exists. Otherwise the copied text would be lost as soon as the handle is dropped unless
+/// the user runs a clipboard manager.
+#[derive(Default)]
+pub struct Clipboard {
+    /// Created the first time the clipboard is accessed.
+    #[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+    clipboard: Mutex<Option<arboard::Clipboard>>,
+}
+
+// SAFETY: The clipboard is only accessed from the GUI thread through the `GuiContext`
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+unsafe impl Send for Clipboard {}
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+unsafe impl Sync for Clipboard {}
+
+#[cfg(all(feature = "clipboard", not(target_arch = "wasm32")))]
+impl Clipboard {
+    /// Replace the clipboard's contents with `text`. Returns `false` if the clipboard could not be
+    /// accessed.
+    pub fn set_text(&self, text: &str) -> bool {
+        match self.with_clipboard(|clipboard| clipboard.set_text(text)) {
+            Ok(()) => true,
+            Err(err) => {
+                nih_debug_assert_failure!("Could not write to the clipboard: {}", err);
+                false
+            }
+        }
+    }
+
+    /// Get the clipboard's contents if it currently contains text.
+    pub fn text(&self) -> Option<String> {
+        self.with_clipboard(|clipboard| clipboard.get_text()).ok()
+    }
+
+    fn with_clipboard<T>(
+        &self,
+        f: impl FnOnce(&mut arboard::Clipboard) -> Result<T, arboard::Error>,
+    ) -> Result<T, arboard::Error> {
+        let mut clipboard = self.clipboard.lock();
+        if clipboard.is_none() {
+            *clipboard = Some(arboard::Clipboard::new()?);
+        }
+
+        f(clipboard.as_mut().unwrap())
+    }
+}
+
+#[cfg(not(all(feature = "clipboard", not(target_arch = "wasm32"))))]
+impl Clipboard {
+    /// Replace the clipboard's contents with `text`. Always returns `false` since the `clipboard`
+    /// feature is disabled.
+    pub fn set_text(&self, _text: &str) -> bool {
+        nih_debug_assert_failure!(
+            "The clipboard can only be accessed with the `clipboard` feature"
+        );
+        false
+    }
+
+    /// Get the clipboard's contents. Always returns `None` since the `clipboard` feature is
+    /// disabled.
+    pub fn text(&self) -> Option<String> {
+        None
+    }
+}
@@ -0,0 +1,89 @@
+//! Measures how much of the available processing time the plugin's process function uses.
+
+use atomic_float::AtomicF32;
+use std::sync::atomic::Ordering;
+use std::time::Instant;
+
+use crate::prelude::ProcessMode;
+
+/// The time constant in seconds for the DSP load's exponential moving average. This is in terms of
+/// audio time, so the average settles at the same speed regardless of the buffer size.
+const AVERAGING_TIME: f32 = 0.3;
+
+/// Keeps track of a rolling average of the time spent in the plugin's process function, relative
+/// to the amount of time that was available for it. A load of 1.0 means that the process function
+/// takes exactly as long as the audio it produces lasts. Every wrapper owns one of these, and it is
+/// exposed to the editor through
+/// [`GuiContext::dsp_load()`][crate::prelude::GuiContext::dsp_load()].
+///
+/// Neither CLAP nor VST3 have a way for plugins to report their load to the host, so this value
+/// is only shown to the plugin's own editor.
+#[derive(Debug, Default)]
+pub struct DspLoadMeter {
+    load: AtomicF32,
+}
+
+impl DspLoadMeter {
+    /// The current DSP load. See [`DspLoadMeter`].
+    pub fn load(&self) -> f32 {
+        self.load.load(Ordering::Relaxed)
+    }
+
+    /// Reset the average, for instance when the plugin is reactivated.
+    pub fn reset(&self) {
+        self.load.store(0.0, Ordering::Relaxed);
+    }
+
+    /// Run `process`, which processes a block of `num_samples` samples, and update the average
+    /// with the time it took. Blocks processed in offline mode are not measured as there is no
+    /// deadline to meet. This should only be called from the audio thread.
+    pub fn measure<T>(
+        &self,
+        num_samples: usize,
+        sample_rate: f32,
+        process_mode: ProcessMode,
+        process: impl FnOnce() -> T,
+    ) -> T {
+        if process_mode == ProcessMode::Offline || num_samples == 0 {
+            return process();
+        }
+
+        let start = Instant::now();
+        let result = process();
+        let elapsed = start.elapsed().as_secs_f32();
+
+        let deadline = num_samples as f32 / sample_rate;
+        self.update(elapsed / deadline, deadline);
+
+        result
+    }
+
+    /// Add a block's load to the moving average, weighted by the block's duration in seconds.
+    fn update(&self, block_load: f32, block_duration: f32) {
+        let weight = 1.0 - (-block_duration / AVERAGING_TIME).exp();
+        let load = self.load();
+        self.load
+            .store(load + ((block_load - load) * weight), Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_average_is_independent_of_block_size() {
+        let small_blocks = DspLoadMeter::default();
+        for _ in 0..64 {
+            small_blocks.update(0.5, 0.01);
+        }
+
+        let large_blocks = DspLoadMeter::default();
+        for _ in 0..8 {
+            large_blocks.update(0.5, 0.08);
+        }
+
+        approx::assert_relative_eq!(small_blocks.load(), large_blocks.load(), epsilon = 1e-4);
+        assert!(small_blocks.load() > 0.4 && small_blocks.load() < 0.5);
+    }
+}
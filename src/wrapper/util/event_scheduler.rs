@@ -0,0 +1,139 @@
+//! Holds on to note events the plugin scheduled for later blocks using
+//! [`ProcessContext::send_event_at()`][crate::prelude::ProcessContext::send_event_at()].
+
+use std::collections::VecDeque;
+
+use crate::midi::sysex::SysExMessage;
+use crate::prelude::NoteEvent;
+
+/// The maximum number of events that can be scheduled at the same time. Scheduling events doesn't
+/// allocate, so events scheduled beyond this limit are dropped.
+const CAPACITY: usize = 2048;
+
+/// Keeps track of the absolute sample position and of the scheduled events that haven't been sent
+/// to the host yet. Every wrapper that supports scheduling events owns one of these.
+#[derive(Debug)]
+pub struct EventScheduler<S: SysExMessage> {
+    /// The scheduled events together with their absolute sample positions, sorted by position.
+    /// Events scheduled at the same position are kept in the order they were scheduled in.
+    events: VecDeque<(u64, NoteEvent<S>)>,
+    /// The number of samples processed since the plugin was last reset, at the start of the block
+    /// that's currently being processed.
+    position: u64,
+}
+
+impl<S: SysExMessage> Default for EventScheduler<S> {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::with_capacity(CAPACITY),
+            position: 0,
+        }
+    }
+}
+
+impl<S: SysExMessage> EventScheduler<S> {
+    /// The absolute sample position at the start of the current block.
+    pub fn position(&self) -> u64 {
+        self.position
+    }
+
+    /// Schedule an event at an absolute sample position. Events scheduled before the start of the
+    /// current block are sent at the start of the current block instead. Returns `false` if too
+    /// many events have been scheduled already. This is realtime-safe.
+    pub fn schedule(&mut self, sample: u64, event: NoteEvent<S>) -> bool {
+        if self.events.len() >= CAPACITY {
+            return false;
+        }
+
+        let sample = sample.max(self.position);
+        let index = self
+            .events
+            .partition_point(|(position, _)| *position <= sample);
+        self.events.insert(index, (sample, event));
+
+        true
+    }
+
+    /// Call this after the plugin has processed a block of `block_len` samples. `send` is called
+    /// for every scheduled event that falls within that block, in order, with the event's timing
+    /// set relative to the start of the block. Afterwards the position is advanced to the start of
+    /// the next block.
+    pub fn advance(&mut self, block_len: usize, mut send: impl FnMut(NoteEvent<S>)) {
+        let block_end = self.position + block_len as u64;
+        while self
+            .events
+            .front()
+            .is_some_and(|(sample, _)| *sample < block_end)
+        {
+            let (sample, mut event) = self.events.pop_front().unwrap();
+            *event.timing_mut() = (sample - self.position) as u32;
+            send(event);
+        }
+
+        self.position = block_end;
+    }
+
+    /// Drop all scheduled events and start counting from zero again. Called when the plugin is
+    /// reset.
+    pub fn reset(&mut self) {
+        self.events.clear();
+        self.position = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn note_on(note: u8) -> NoteEvent<()> {
+        NoteEvent::NoteOn {
+            timing: 0,
+            voice_id: None,
+            channel: 0,
+            note,
+            velocity: 1.0,
+        }
+    }
+
+    fn sent_notes(scheduler: &mut EventScheduler<()>, block_len: usize) -> Vec<(u32, u8)> {
+        let mut sent = Vec::new();
+        scheduler.advance(block_len, |event| match event {
+            NoteEvent::NoteOn { timing, note, .. } => sent.push((timing, note)),
+            _ => unreachable!(),
+        });
+
+        sent
+    }
+
+    #[test]
+    fn test_events_beyond_block() {
+        let mut scheduler = EventScheduler::default();
+        assert!(scheduler.schedule(300, note_on(3)));
+        assert!(scheduler.schedule(10, note_on(1)));
+        assert!(scheduler.schedule(150, note_on(2)));
+
+        assert_eq!(sent_notes(&mut scheduler, 128), [(10, 1)]);
+        assert_eq!(scheduler.position(), 128);
+        assert_eq!(sent_notes(&mut scheduler, 128), [(22, 2)]);
+        assert_eq!(sent_notes(&mut scheduler, 32), []);
+        assert_eq!(sent_notes(&mut scheduler, 64), [(12, 3)]);
+    }
+
+    #[test]
+    fn test_late_and_simultaneous_events() {
+        let mut scheduler = EventScheduler::default();
+        scheduler.advance(128, |_| unreachable!());
+
+        // Events in the past are sent at the start of the block, and events at the same position
+        // keep their order
+        assert!(scheduler.schedule(200, note_on(2)));
+        assert!(scheduler.schedule(200, note_on(3)));
+        assert!(scheduler.schedule(50, note_on(1)));
+        assert_eq!(sent_notes(&mut scheduler, 128), [(0, 1), (72, 2), (72, 3)]);
+
+        scheduler.schedule(1000, note_on(4));
+        scheduler.reset();
+        assert_eq!(scheduler.position(), 0);
+        assert_eq!(sent_notes(&mut scheduler, 2048), []);
+    }
+}
@@ -0,0 +1,65 @@
+//! Bookkeeping for grouped parameter edits made from the GUI.
+
+use crate::prelude::ParamPtr;
+
+/// Keeps track of [`GuiContext::raw_begin_group_edit()`][crate::prelude::GuiContext::raw_begin_group_edit()]
+/// calls. While a group edit is active, the end of every parameter gesture is deferred until the
+/// outermost group ends. The host then sees a single set of overlapping gestures, which hosts
+/// record as a single undo step.
+#[derive(Debug, Default)]
+pub struct ParamGroupEdit {
+    /// The number of nested group edits.
+    depth: usize,
+    /// Parameters whose gestures have been ended by the plugin while a group edit was active. The
+    /// host has not yet received the end gesture for these parameters.
+    deferred_ends: Vec<ParamPtr>,
+}
+
+impl ParamGroupEdit {
+    /// Start a (nested) group edit. Returns `true` if this is the outermost group.
+    pub fn begin(&mut self) -> bool {
+        self.depth += 1;
+        self.depth == 1
+    }
+
+    /// End a group edit. Returns the parameters whose end gestures should now be sent to the host
+    /// if this ended the outermost group, or `None` otherwise.
+    pub fn end(&mut self) -> Option<Vec<ParamPtr>> {
+        match self.depth {
+            0 => {
+                nih_debug_assert_failure!("Group edit ended without a matching begin");
+                None
+            }
+            1 => {
+                self.depth = 0;
+                Some(std::mem::take(&mut self.deferred_ends))
+            }
+            _ => {
+                self.depth -= 1;
+                None
+            }
+        }
+    }
+
+    /// Whether the begin gesture for `param` should be sent to the host. This returns `false` if
+    /// the parameter's previous gesture is still open on the host's side because its end was
+    /// deferred.
+    pub fn should_begin_gesture(&self, param: ParamPtr) -> bool {
+        !self.deferred_ends.contains(&param)
+    }
+
+    /// Whether the end gesture for `param` should be sent to the host. If a group edit is active,
+    /// then this returns `false` and the end gesture will instead be returned from
+    /// [`end()`][Self::end()].
+    pub fn should_end_gesture(&mut self, param: ParamPtr) -> bool {
+        if self.depth == 0 {
+            return true;
+        }
+
+        if !self.deferred_ends.contains(&param) {
+            self.deferred_ends.push(param);
+        }
+
+        false
+    }
+}
@@ -0,0 +1,70 @@
+//! Sending the values of read-only output parameters back to the host.
+
+use std::collections::HashMap;
+
+use crate::prelude::{ParamFlags, ParamPtr};
+
+/// How often the values of output parameters are sent to the host. Meters in host mixers and
+/// control surfaces don't need to be updated any faster than this, and sending them every process
+/// cycle would flood the host's automation system.
+const UPDATE_RATE_HZ: f32 = 30.0;
+
+/// Keeps track of the last values of the plugin's read-only parameters (see
+/// [`ParamFlags::READ_ONLY`]) sent to the host, and decides when they need to be sent again.
+#[derive(Debug, Default)]
+pub struct OutputParams {
+    /// The read-only parameters' hashes and pointers, together with the last normalized value sent
+    /// to the host.
+    params: Vec<(u32, ParamPtr, f32)>,
+    /// The number of samples processed since the last time the values were sent to the host.
+    samples_since_update: usize,
+}
+
+impl OutputParams {
+    /// Find the read-only parameters in a wrapper's parameter map.
+    ///
+    /// # Safety
+    ///
+    /// The parameters need to outlive this object.
+    pub unsafe fn new(param_by_hash: &HashMap<u32, ParamPtr>) -> Self {
+        let params = param_by_hash
+            .iter()
+            .filter(|(_, param_ptr)| param_ptr.flags().contains(ParamFlags::READ_ONLY))
+            .map(|(hash, param_ptr)| (*hash, *param_ptr, param_ptr.modulated_normalized_value()))
+            .collect();
+
+        Self {
+            params,
+            samples_since_update: 0,
+        }
+    }
+
+    /// Call this after every process cycle. If enough time has passed since the last update, then
+    /// `send` is called with the hash, pointer, and normalized value of every read-only parameter
+    /// whose value has changed since it was last sent. This is realtime-safe.
+    pub fn update(
+        &mut self,
+        num_samples: usize,
+        sample_rate: f32,
+        mut send: impl FnMut(u32, ParamPtr, f32),
+    ) {
+        if self.params.is_empty() {
+            return;
+        }
+
+        self.samples_since_update += num_samples;
+        if (self.samples_since_update as f32) < sample_rate / UPDATE_RATE_HZ {
+            return;
+        }
+        self.samples_since_update = 0;
+
+        for (hash, param_ptr, last_value) in &mut self.params {
+            // SAFETY: The caller of `new()` guarantees that the parameters are still alive
+            let value = unsafe { param_ptr.modulated_normalized_value() };
+            if value != *last_value {
+                *last_value = value;
+                send(*hash, *param_ptr, value);
+            }
+        }
+    }
+}
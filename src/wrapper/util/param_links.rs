@@ -0,0 +1,152 @@
+//! Propagates parameter changes made from the GUI to linked parameters.
+
+use std::collections::HashMap;
+
+use crate::prelude::{ParamLinkMode, ParamPtr};
+
+/// The link groups from [`Params::param_links()`][crate::prelude::Params::param_links()]. The GUI
+/// contexts use this to also change a parameter's partners, with their own gestures, when the
+/// editor changes that parameter.
+#[derive(Debug, Default)]
+pub struct ParamLinks {
+    /// For every linked parameter, the group's link mode and the other parameters in the group.
+    partners: HashMap<ParamPtr, (ParamLinkMode, Vec<ParamPtr>)>,
+    /// The unmodulated normalized values of a parameter and its partners at the start of that
+    /// parameter's gesture. Only used for [`ParamLinkMode::Relative`].
+    gesture_start_values: HashMap<ParamPtr, (f32, Vec<f32>)>,
+    /// The number of active gestures for every linked parameter, including gestures started
+    /// because a partner was changed. The gesture is only sent to the host when this goes from zero
+    /// to one, and it's only ended when it goes back to zero.
+    active_gestures: HashMap<ParamPtr, usize>,
+}
+
+impl ParamLinks {
+    /// Group the links returned by
+    /// [`Params::param_links()`][crate::prelude::Params::param_links()].
+    pub fn new(links: Vec<(ParamPtr, String, ParamLinkMode)>) -> Self {
+        let mut groups: HashMap<String, (ParamLinkMode, Vec<ParamPtr>)> = HashMap::new();
+        for (param, group, mode) in links {
+            let (group_mode, params) = groups.entry(group).or_insert_with(|| (mode, Vec::new()));
+            nih_debug_assert_eq!(
+                mode,
+                *group_mode,
+                "All parameters in a link group should use the same link mode"
+            );
+
+            params.push(param);
+        }
+
+        let mut partners = HashMap::new();
+        for (mode, params) in groups.into_values() {
+            for param in &params {
+                let others: Vec<ParamPtr> = params.iter().copied().filter(|p| p != param).collect();
+                if !others.is_empty() {
+                    partners.insert(*param, (mode, others));
+                }
+            }
+        }
+
+        Self {
+            partners,
+            gesture_start_values: HashMap::new(),
+            active_gestures: HashMap::new(),
+        }
+    }
+
+    /// Called when the editor starts a gesture for `param`. Returns the parameters whose gestures
+    /// should now be started with the host. This is `param` and its partners, minus the
+    /// parameters that already have an active gesture because another parameter in the same group
+    /// is being changed.
+    ///
+    /// # Safety
+    ///
+    /// `param` and its partners need to point to live parameters.
+    pub unsafe fn begin_gesture(&mut self, param: ParamPtr) -> Vec<ParamPtr> {
+        let partners = match self.partners.get(&param) {
+            Some((_, partners)) => partners,
+            None => return vec![param],
+        };
+
+        let mut started = Vec::new();
+        for linked_param in std::iter::once(&param).chain(partners) {
+            let count = self.active_gestures.entry(*linked_param).or_insert(0);
+            *count += 1;
+            if *count == 1 {
+                started.push(*linked_param);
+            }
+        }
+
+        if let Some((ParamLinkMode::Relative, partners)) = self.partners.get(&param) {
+            self.gesture_start_values.insert(
+                param,
+                (
+                    param.unmodulated_normalized_value(),
+                    partners
+                        .iter()
+                        .map(|partner| partner.unmodulated_normalized_value())
+                        .collect(),
+                ),
+            );
+        }
+
+        started
+    }
+
+    /// Compute the new normalized values for `param`'s partners when the editor sets `param` to
+    /// `normalized`. The values are snapped to the partners' step sizes.
+    ///
+    /// # Safety
+    ///
+    /// `param` and its partners need to point to live parameters.
+    pub unsafe fn partner_values(&self, param: ParamPtr, normalized: f32) -> Vec<(ParamPtr, f32)> {
+        let (mode, partners) = match self.partners.get(&param) {
+            Some(link) => link,
+            None => return Vec::new(),
+        };
+
+        partners
+            .iter()
+            .enumerate()
+            .map(|(partner_idx, partner)| {
+                let partner_normalized = match (mode, self.gesture_start_values.get(&param)) {
+                    (ParamLinkMode::Relative, Some((start_value, partner_start_values))) => {
+                        (partner_start_values[partner_idx] + (normalized - start_value))
+                            .clamp(0.0, 1.0)
+                    }
+                    // Relative links without an active gesture fall back to mirroring the value
+                    _ => normalized,
+                };
+
+                (
+                    *partner,
+                    partner.preview_normalized(partner.preview_plain(partner_normalized)),
+                )
+            })
+            .collect()
+    }
+
+    /// Called when the editor ends the gesture for `param`. Returns the parameters whose gestures
+    /// should now be ended with the host. See [`begin_gesture()`][Self::begin_gesture()].
+    pub fn end_gesture(&mut self, param: ParamPtr) -> Vec<ParamPtr> {
+        let partners = match self.partners.get(&param) {
+            Some((_, partners)) => partners,
+            None => return vec![param],
+        };
+
+        self.gesture_start_values.remove(&param);
+
+        let mut ended = Vec::new();
+        for linked_param in std::iter::once(&param).chain(partners) {
+            match self.active_gestures.get_mut(linked_param) {
+                Some(count) if *count > 1 => *count -= 1,
+                Some(_) => {
+                    self.active_gestures.remove(linked_param);
+                    ended.push(*linked_param);
+                }
+                None => nih_debug_assert_failure!("Gesture ended without a matching begin"),
+            }
+        }
+
+        ended
+    }
+}
@@ -0,0 +1,146 @@
+//! Serializing a plugin's large persistent fields ahead of time on a background thread.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use crate::prelude::Params;
+use crate::wrapper::state::{self, StateSaveContext};
+
+/// How often the audio thread asks for the cached blobs to be refreshed. The refresh itself is
+/// cheap when nothing has changed.
+const REFRESH_INTERVAL_SECONDS: f32 = 1.0;
+
+/// Neither CLAP nor VST3 allows saving the plugin's state asynchronously, so serializing and
+/// compressing large [`SharedBlob`][crate::params::persist::SharedBlob]s would stall the host's
+/// GUI thread when a project is saved. Instead, the wrappers periodically refresh this cache on
+/// their background thread. The encoded blobs are then reused when the host saves the state, as
+/// long as none of the blobs have changed in the meantime.
+///
+/// This is only used if all of the plugin's blobs keep track of their revisions.
+#[derive(Default)]
+pub struct StateCache {
+    /// Whether the plugin has blobs that can be cached. If this is `false`, then the other fields
+    /// are never used.
+    enabled: bool,
+    /// Whether the blobs should be compressed. See [`Plugin::COMPRESS_STATE`][crate::prelude::Plugin::COMPRESS_STATE].
+    compress: bool,
+    /// The number of samples processed since the last time a refresh was requested.
+    samples_since_refresh: AtomicUsize,
+    cached: Mutex<CachedBlobs>,
+}
+
+#[derive(Default)]
+struct CachedBlobs {
+    /// The encoded blobs along with the context and revisions they were encoded for.
+    encoded: Option<(
+        StateSaveContext,
+        BTreeMap<String, Option<u64>>,
+        Arc<Vec<u8>>,
+    )>,
+    /// The revisions seen during the last refresh. Blobs are only encoded in the background after
+    /// their revisions have stopped changing, so a blob that's being recorded to isn't encoded over
+    /// and over again.
+    last_seen_revisions: BTreeMap<String, Option<u64>>,
+}
+
+impl StateCache {
+    /// Create a cache for a plugin's parameters object. `compress` should be set to the plugin's
+    /// [`Plugin::COMPRESS_STATE`][crate::prelude::Plugin::COMPRESS_STATE].
+    pub fn new(plugin_params: &dyn Params, compress: bool) -> Self {
+        let revisions = plugin_params.blob_revisions(StateSaveContext::Project);
+
+        Self {
+            enabled: !revisions.is_empty() && revisions.values().all(Option::is_some),
+            compress,
+            ..Self::default()
+        }
+    }
+
+    /// Call this after every process cycle. Returns `true` if the cache should be refreshed by
+    /// calling [`refresh()`][Self::refresh()] from a background thread. This is realtime-safe.
+    pub fn should_refresh(&self, num_samples: usize, sample_rate: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+
+        let samples_since_refresh = self
+            .samples_since_refresh
+            .fetch_add(num_samples, Ordering::Relaxed)
+            + num_samples;
+        if (samples_since_refresh as f32) < sample_rate * REFRESH_INTERVAL_SECONDS {
+            return false;
+        }
+
+        self.samples_since_refresh.store(0, Ordering::Relaxed);
+        true
+    }
+
+    /// Encode the plugin's blobs for [`StateSaveContext::Project`] if they have changed since they
+    /// were last encoded, and if they haven't changed since the last refresh.
+    pub fn refresh(&self, plugin_params: &dyn Params) {
+        if !self.enabled {
+            return;
+        }
+
+        let context = StateSaveContext::Project;
+        let revisions = plugin_params.blob_revisions(context);
+        {
+            let mut cached = self.cached.lock();
+            if matches!(&cached.encoded, Some((c, r, _)) if *c == context && *r == revisions) {
+                return;
+            }
+            if cached.last_seen_revisions != revisions {
+                cached.last_seen_revisions = revisions;
+                return;
+            }
+        }
+
+        // The lock is not held while encoding so the host can still save the state in the
+        // meantime. The result is discarded if the blobs have changed while they were encoded.
+        match state::encode_blobs(&plugin_params.serialize_blobs(context), self.compress) {
+            Ok(encoded) => {
+                nih_trace!("Encoded {} bytes of blobs in the background", encoded.len());
+                let mut cached = self.cached.lock();
+                if plugin_params.blob_revisions(context) == revisions {
+                    cached.encoded = Some((context, revisions, Arc::new(encoded)));
+                }
+            }
+            Err(err) => nih_debug_assert_failure!("Could not encode the blobs: {:#}", err),
+        }
+    }
+
+    /// Get the encoded blob section for the plugin's current state. This reuses the cached data if
+    /// the blobs have not changed since they were encoded, and it encodes them otherwise.
+    pub fn encoded_blobs(
+        &self,
+        plugin_params: &dyn Params,
+        context: StateSaveContext,
+    ) -> Result<Arc<Vec<u8>>> {
+        if !self.enabled {
+            return Ok(Arc::new(state::encode_blobs(
+                &plugin_params.serialize_blobs(context),
+                self.compress,
+            )?));
+        }
+
+        // The revisions are read before the blobs are serialized. If a blob changes in between, then
+        // the next save will encode the blobs again.
+        let revisions = plugin_params.blob_revisions(context);
+        if let Some((cached_context, cached_revisions, encoded)) = &self.cached.lock().encoded {
+            if *cached_context == context && *cached_revisions == revisions {
+                return Ok(encoded.clone());
+            }
+        }
+
+        let encoded = Arc::new(state::encode_blobs(
+            &plugin_params.serialize_blobs(context),
+            self.compress,
+        )?);
+        self.cached.lock().encoded = Some((context, revisions, encoded.clone()));
+
+        Ok(encoded)
+    }
+}
@@ -0,0 +1,61 @@
+//! Giving keyboard focus to an embedded editor window. Neither CLAP nor VST3 have a way for the
+//! plugin to ask the host for keyboard focus, so when the editor calls
+//! [`GuiContext::request_keyboard_focus()`][crate::prelude::GuiContext::request_keyboard_focus()]
+//! the wrappers move the OS' keyboard focus to the editor's window directly.
+
+use crate::prelude::ParentWindowHandle;
+
+/// A parent window handle the editor was embedded in, stored on the wrapper so the editor's
+/// window can be focused later.
+pub struct EditorParent(pub ParentWindowHandle);
+
+// SAFETY: The window handle is only used from the GUI thread
+unsafe impl Send for EditorParent {}
+unsafe impl Sync for EditorParent {}
+
+/// Give the editor window embedded in `parent` keyboard focus. Returns `false` if the editor's
+/// window could not be found, or if this is not supported on the current platform.
+///
+/// # Safety
+///
+/// `parent` must be a valid window handle, and this must be called from the GUI thread.
+pub unsafe fn focus_editor_window(parent: &EditorParent) -> bool {
+    match parent.0 {
+        #[cfg(target_os = "windows")]
+        ParentWindowHandle::Win32Hwnd(parent) => {
+            use windows::Win32::Foundation::HWND;
+            use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
+            use windows::Win32::UI::WindowsAndMessaging::{GetWindow, GW_CHILD};
+
+            // The editor's window is the only child of the window passed to the plugin
+            let editor_window = GetWindow(HWND(parent as isize), GW_CHILD);
+            if editor_window.0 == 0 {
+                return false;
+            }
+
+            SetFocus(editor_window);
+            true
+        }
+        #[cfg(target_os = "macos")]
+        ParentWindowHandle::AppKitNsView(parent) => {
+            use objc::runtime::{Object, BOOL, NO};
+            use objc::{msg_send, sel, sel_impl};
+
+            // Same as on Windows, the editor's view is added as a subview of the host's view
+            let parent = parent as *mut Object;
+            let subviews: *mut Object = msg_send![parent, subviews];
+            let editor_view: *mut Object = msg_send![subviews, lastObject];
+            let window: *mut Object = msg_send![parent, window];
+            if editor_view.is_null() || window.is_null() {
+                return false;
+            }
+
+            let result: BOOL = msg_send![window, makeFirstResponder: editor_view];
+            result != NO
+        }
+        // NIH-plug doesn't link against Xlib, but baseview's X11 windows already take the input
+        // focus when they're clicked on
+        ParentWindowHandle::X11Window(_) => false,
+        _ => false,
+    }
+}
@@ -1,8 +1,11 @@
 #[macro_use]
 mod util;
 
+#[cfg(feature = "ara")]
+mod ara;
 mod context;
 mod factory;
+mod host_editing;
 mod inner;
 mod note_expressions;
 mod param_units;
@@ -83,17 +86,18 @@ macro_rules! nih_export_vst3 {
                 }
 
                 unsafe fn count_classes(&self) -> i32 {
-                    self.plugin_infos.len() as i32
+                    // ARA plugins expose an additional class for their ARA factory
+                    PluginInfo::class_count(&self.plugin_infos)
                 }
 
                 unsafe fn get_class_info(&self, index: i32, info: *mut PClassInfo) -> tresult {
-                    if index < 0 || index >= self.plugin_infos.len() as i32 {
-                        return kInvalidArgument;
+                    match PluginInfo::class_info(&self.plugin_infos, index) {
+                        Some(class_info) => {
+                            *info = class_info;
+                            kResultOk
+                        }
+                        None => kInvalidArgument,
                     }
-
-                    *info = self.plugin_infos[index as usize].create_class_info();
-
-                    kResultOk
                 }
 
                 unsafe fn create_instance(
@@ -108,6 +112,10 @@ macro_rules! nih_export_vst3 {
                         return kInvalidArgument;
                     }
 
+                    if let Some(result) = PluginInfo::create_auxiliary_instance(&self.plugin_infos, &(*cid).data, iid, obj) {
+                        return result;
+                    }
+
                     // This is a poor man's way of treating `$plugin_ty` like an indexable array.
                     // Assuming `self.plugin_infos` is in the same order, we can simply check all of
                     // the registered plugin CIDs for matches using an unrolled loop.
@@ -149,13 +157,13 @@ macro_rules! nih_export_vst3 {
 
             impl IPluginFactory2 for Factory {
                 unsafe fn get_class_info2(&self, index: i32, info: *mut PClassInfo2) -> tresult {
-                    if index < 0 || index >= self.plugin_infos.len() as i32 {
-                        return kInvalidArgument;
+                    match PluginInfo::class_info_2(&self.plugin_infos, index) {
+                        Some(class_info) => {
+                            *info = class_info;
+                            kResultOk
+                        }
+                        None => kInvalidArgument,
                     }
-
-                    *info = self.plugin_infos[index as usize].create_class_info_2();
-
-                    kResultOk
                 }
             }
 
@@ -165,13 +173,13 @@ macro_rules! nih_export_vst3 {
                     index: i32,
                     info: *mut PClassInfoW,
                 ) -> tresult {
-                    if index < 0 || index >= self.plugin_infos.len() as i32 {
-                        return kInvalidArgument;
+                    match PluginInfo::class_info_unicode(&self.plugin_infos, index) {
+                        Some(class_info) => {
+                            *info = class_info;
+                            kResultOk
+                        }
+                        None => kInvalidArgument,
                     }
-
-                    *info = self.plugin_infos[index as usize].create_class_info_unicode();
-
-                    kResultOk
                 }
 
                 unsafe fn set_host_context(&self, _context: *mut vst3_sys::c_void) -> tresult {
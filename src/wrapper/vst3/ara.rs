@@ -0,0 +1,147 @@
+//! The VST3 side of the ARA integration, based on the ARA SDK's `ARA_API/ARAVST3.h`. ARA plugins
+//! expose an additional `ARA Main Factory Class` through the VST3 factory, and the plugin's
+//! component implements `IPlugInEntryPoint` and `IPlugInEntryPoint2` so the host can bind it to a
+//! document controller.
+
+use std::ffi::c_void;
+use std::ptr;
+use vst3_sys::base::{
+    kInvalidArgument, kResultOk, tresult, ClassCardinality, IUnknown, PClassInfo, PClassInfo2,
+    PClassInfoW,
+};
+use vst3_sys::{com_interface, VST3};
+
+use super::factory::{PluginInfo, VST3_SDK_VERSION};
+use super::util::u16strlcpy;
+use crate::wrapper::ara::AraFactory;
+use crate::wrapper::util::strlcpy;
+
+// Alias needed for the VST3 attribute and interface macros
+use vst3_sys as vst3_com;
+
+/// The category used for the ARA main factory class.
+const ARA_MAIN_FACTORY_CLASS: &str = "ARA Main Factory Class";
+
+/// Provides the host access to the plugin's ARA factory. Exposed as a separate class in the VST3
+/// factory.
+#[com_interface("DB2A1669-FAFD-42A5-A82F-864F7B6872EA")]
+pub trait IMainFactory: IUnknown {
+    /// Returns a `const ARAFactory *`.
+    unsafe fn get_factory(&self) -> *const c_void;
+}
+
+/// The ARA 1 entry point on the plugin's component.
+#[com_interface("12814E54-A1CE-4076-82B9-681316950BD6")]
+pub trait IPlugInEntryPoint: IUnknown {
+    /// Returns a `const ARAFactory *`.
+    unsafe fn get_factory(&self) -> *const c_void;
+    /// Returns a `const ARAPlugInExtensionInstance *`. This was deprecated in ARA 2, and it is not
+    /// supported by NIH-plug.
+    unsafe fn bind_to_document_controller(
+        &self,
+        document_controller_ref: *mut c_void,
+    ) -> *const c_void;
+}
+
+/// The ARA 2 entry point on the plugin's component.
+#[com_interface("CD9A5913-C9EB-46D7-96CA-53ADD1DB89F5")]
+pub trait IPlugInEntryPoint2: IUnknown {
+    /// Returns a `const ARAPlugInExtensionInstance *`.
+    unsafe fn bind_to_document_controller_with_roles(
+        &self,
+        document_controller_ref: *mut c_void,
+        known_roles: u32,
+        assigned_roles: u32,
+    ) -> *const c_void;
+}
+
+/// The object created for the ARA main factory class.
+#[VST3(implements(IMainFactory))]
+pub struct AraMainFactory {
+    factory: &'static AraFactory,
+}
+
+impl IMainFactory for AraMainFactory {
+    unsafe fn get_factory(&self) -> *const c_void {
+        self.factory.ara_factory() as *const c_void
+    }
+}
+
+/// The class ID for a plugin's ARA main factory class. This is derived from the plugin's class ID
+/// so it doesn't need to be configured separately.
+pub fn main_factory_cid(plugin_cid: &[u8; 16]) -> [u8; 16] {
+    plugin_cid.map(|byte| !byte)
+}
+
+/// Create an ARA main factory object if `cid` belongs to one of the ARA main factory classes. Returns
+/// `None` if it does not.
+///
+/// # Safety
+///
+/// `iid` and `obj` need to be valid pointers.
+pub unsafe fn create_main_factory(
+    plugin_infos: &[PluginInfo],
+    cid: &[u8; 16],
+    iid: *const vst3_sys::IID,
+    obj: *mut *mut c_void,
+) -> Option<tresult> {
+    let factory = plugin_infos
+        .iter()
+        .filter_map(|info| Some((info.ara_factory?, main_factory_cid(info.cid))))
+        .find(|(_, main_factory_cid)| main_factory_cid == cid)
+        .map(|(factory, _)| factory)?;
+
+    let main_factory = AraMainFactory::allocate(factory);
+    let result = main_factory.query_interface(iid, obj);
+    if result == kResultOk {
+        // See the comment in `nih_export_vst3!()`'s `create_instance()`
+        main_factory.release();
+        Box::leak(main_factory);
+
+        Some(kResultOk)
+    } else {
+        *obj = ptr::null_mut();
+        Some(kInvalidArgument)
+    }
+}
+
+impl PluginInfo {
+    /// The [`PClassInfo`] for this plugin's ARA main factory class.
+    pub(super) fn create_ara_class_info(&self) -> PClassInfo {
+        let mut info: PClassInfo = unsafe { std::mem::zeroed() };
+        info.cid.data = main_factory_cid(self.cid);
+        info.cardinality = ClassCardinality::kManyInstances as i32;
+        strlcpy(&mut info.category, ARA_MAIN_FACTORY_CLASS);
+        strlcpy(&mut info.name, self.name);
+
+        info
+    }
+
+    /// The [`PClassInfo2`] for this plugin's ARA main factory class.
+    pub(super) fn create_ara_class_info_2(&self) -> PClassInfo2 {
+        let mut info: PClassInfo2 = unsafe { std::mem::zeroed() };
+        info.cid.data = main_factory_cid(self.cid);
+        info.cardinality = ClassCardinality::kManyInstances as i32;
+        strlcpy(&mut info.category, ARA_MAIN_FACTORY_CLASS);
+        strlcpy(&mut info.name, self.name);
+        strlcpy(&mut info.vendor, self.vendor);
+        strlcpy(&mut info.version, self.version);
+        strlcpy(&mut info.sdk_version, VST3_SDK_VERSION);
+
+        info
+    }
+
+    /// The [`PClassInfoW`] for this plugin's ARA main factory class.
+    pub(super) fn create_ara_class_info_unicode(&self) -> PClassInfoW {
+        let mut info: PClassInfoW = unsafe { std::mem::zeroed() };
+        info.cid.data = main_factory_cid(self.cid);
+        info.cardinality = ClassCardinality::kManyInstances as i32;
+        strlcpy(&mut info.category, ARA_MAIN_FACTORY_CLASS);
+        u16strlcpy(&mut info.name, self.name);
+        u16strlcpy(&mut info.vendor, self.vendor);
+        u16strlcpy(&mut info.version, self.version);
+        u16strlcpy(&mut info.sdk_version, VST3_SDK_VERSION);
+
+        info
+    }
+}
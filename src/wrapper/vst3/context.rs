@@ -1,16 +1,22 @@
-use atomic_refcell::AtomicRefMut;
+use atomic_refcell::{AtomicRefCell, AtomicRefMut};
 use std::cell::Cell;
-use std::collections::VecDeque;
+use std::collections::{HashSet, VecDeque};
 use std::sync::atomic::Ordering;
 use std::sync::Arc;
-use vst3_sys::vst::IComponentHandler;
+use std::time::Duration;
+use vst3_sys::vst::{IComponentHandler, IComponentHandler2};
 
+use crate::context::gui::{GuiMessage, ParamChangeReceiver, StateSlot, TimerHandle};
 use crate::prelude::{
-    GuiContext, InitContext, ParamPtr, PluginApi, PluginNoteEvent, PluginState, ProcessContext,
-    Transport, Vst3Plugin,
+    GuiContext, InitContext, NoteEvent, ParamIndication, ParamPtr, PluginApi, PluginNoteEvent,
+    PluginState, ProcessContext, ProcessMode, TrackInfo, Transport, Vst3Plugin,
 };
 
 use super::inner::{Task, WrapperInner};
+use crate::wrapper::util::clipboard::Clipboard;
+use crate::wrapper::util::group_edit::ParamGroupEdit;
+use crate::wrapper::util::param_links::ParamLinks;
+use crate::wrapper::util::{equal_temperament_frequency, tail_seconds_to_samples};
 
 /// An [`InitContext`] implementation for the wrapper.
 ///
@@ -49,6 +55,17 @@ pub(crate) struct WrapperProcessContext<'a, P: Vst3Plugin> {
 /// with the host for things like setting parameters.
 pub(crate) struct WrapperGuiContext<P: Vst3Plugin> {
     pub(super) inner: Arc<WrapperInner<P>>,
+    /// Defers the end of parameter gestures while a group edit is active.
+    pub(super) group_edit: AtomicRefCell<ParamGroupEdit>,
+    /// Changes made to linked parameters are also applied to their partners.
+    pub(super) param_links: AtomicRefCell<ParamLinks>,
+    /// The hashes of the parameters this context has sent a `begin_edit()` for without a matching
+    /// `end_edit()`. Gestures are suppressed while the host is editing a parameter from its own
+    /// UI, and this makes sure the begin and end of a gesture are either both sent or both
+    /// suppressed.
+    pub(super) open_gestures: AtomicRefCell<HashSet<u32>>,
+    /// The system clipboard, kept alive for as long as the editor exists.
+    pub(super) clipboard: Clipboard,
     #[cfg(debug_assertions)]
     pub(super) param_gesture_checker:
         atomic_refcell::AtomicRefCell<crate::wrapper::util::context_checks::ParamGestureChecker>,
@@ -96,11 +113,38 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.inner.cancel_background_tasks(tag);
+    }
+
     #[inline]
     fn transport(&self) -> &Transport {
         &self.transport
     }
 
+    fn process_mode(&self) -> ProcessMode {
+        self.inner.current_process_mode.load()
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        self.inner.track_info.load_full()
+    }
+
+    fn bypassed(&self) -> bool {
+        self.inner.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index
+            < self
+                .inner
+                .current_audio_io_layout
+                .load()
+                .aux_input_ports
+                .len()
+            && self.inner.is_aux_input_active(index)
+    }
+
     fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
         self.input_events_guard.pop_front()
     }
@@ -109,36 +153,58 @@ impl<P: Vst3Plugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
         self.output_events_guard.push_back(event);
     }
 
+    fn send_event_at(&mut self, sample: u64, event: PluginNoteEvent<P>) {
+        let scheduled = self
+            .inner
+            .event_scheduler
+            .borrow_mut()
+            .schedule(sample, event);
+        nih_debug_assert!(scheduled, "Too many scheduled events, dropping event...");
+    }
+
+    fn sample_position(&self) -> u64 {
+        self.inner.event_scheduler.borrow().position()
+    }
+
     fn set_latency_samples(&self, samples: u32) {
         self.inner.set_latency_samples(samples)
     }
 
-    fn set_current_voice_capacity(&self, _capacity: u32) {
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
         // This is only supported by CLAP
+        equal_temperament_frequency(note)
     }
-}
 
-impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
-    fn plugin_api(&self) -> PluginApi {
-        PluginApi::Vst3
+    fn set_tail_seconds(&self, seconds: f32) {
+        self.inner
+            .set_tail_samples(tail_seconds_to_samples(seconds, self.transport.sample_rate))
     }
 
-    fn request_resize(&self) -> bool {
-        let task_posted = self.inner.schedule_gui(Task::RequestResize);
-        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
 
-        // TODO: We don't handle resize request failures right now. In practice this should however
-        //       not happen.
-        true
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T)) {
+        self.inner.gui_messages.drain(handler)
     }
+}
 
-    // All of these functions are supposed to be called from the main thread, so we'll put some
-    // trust in the caller and assume that this is indeed the case
-    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+impl<P: Vst3Plugin> WrapperGuiContext<P> {
+    // These are called from the `GuiContext` functions below for every parameter in a link group
+    unsafe fn begin_set_parameter_unlinked(&self, param: ParamPtr) {
         match &*self.inner.component_handler.borrow() {
             Some(handler) => match self.inner.param_ptr_to_hash.get(&param) {
                 Some(hash) => {
-                    handler.begin_edit(*hash);
+                    // If the parameter's previous gesture was ended during the current group edit,
+                    // then the host still considers that gesture to be active. The host's own
+                    // edits take precedence, and the matching `end_edit()` is only sent if this
+                    // gesture was actually started.
+                    if self.group_edit.borrow().should_begin_gesture(param)
+                        && !self.inner.is_edited_by_host(*hash)
+                    {
+                        handler.begin_edit(*hash);
+                        self.open_gestures.borrow_mut().insert(*hash);
+                    }
                 }
                 None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
             },
@@ -157,7 +223,7 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
         }
     }
 
-    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+    unsafe fn set_parameter_normalized_unlinked(&self, param: ParamPtr, normalized: f32) {
         match &*self.inner.component_handler.borrow() {
             Some(handler) => match self.inner.param_ptr_to_hash.get(&param) {
                 Some(hash) => {
@@ -179,7 +245,11 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
                         );
                     }
 
-                    handler.perform_edit(*hash, normalized as f64);
+                    if self.open_gestures.borrow().contains(hash)
+                        || !self.inner.is_edited_by_host(*hash)
+                    {
+                        handler.perform_edit(*hash, param.to_host_normalized(normalized) as f64);
+                    }
                 }
                 None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
             },
@@ -198,11 +268,16 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
         }
     }
 
-    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+    unsafe fn end_set_parameter_unlinked(&self, param: ParamPtr) {
         match &*self.inner.component_handler.borrow() {
             Some(handler) => match self.inner.param_ptr_to_hash.get(&param) {
                 Some(hash) => {
-                    handler.end_edit(*hash);
+                    // The group edit needs to see every end gesture to keep its bookkeeping intact
+                    if self.group_edit.borrow_mut().should_end_gesture(param)
+                        && self.open_gestures.borrow_mut().remove(hash)
+                    {
+                        handler.end_edit(*hash);
+                    }
                 }
                 None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
             },
@@ -220,6 +295,135 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
             }
         }
     }
+}
+
+impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Vst3
+    }
+
+    fn request_resize(&self) -> bool {
+        let task_posted = self.inner.schedule_gui(Task::RequestResize);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // TODO: We don't handle resize request failures right now. In practice this should however
+        //       not happen.
+        true
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        let applied = match &*self.inner.plug_view.read() {
+            Some(plug_view) => plug_view.set_ui_scale(scale),
+            None => false,
+        };
+
+        applied && self.request_resize()
+    }
+
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool {
+        self.inner.queue_gui_note_event(event)
+    }
+
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool {
+        self.inner.gui_messages.send(message)
+    }
+
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+        self.inner.param_changes.subscribe()
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.inner.set_latency_samples(samples)
+    }
+
+    fn dsp_load(&self) -> f32 {
+        self.inner.dsp_load.load()
+    }
+
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle {
+        self.inner.schedule_timer_once(delay, callback)
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        self.inner.schedule_timer_repeating(interval, callback)
+    }
+
+    // All of these functions are supposed to be called from the main thread, so we'll put some
+    // trust in the caller and assume that this is indeed the case
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        // Linked parameters also get their own gestures
+        let started = self.param_links.borrow_mut().begin_gesture(param);
+        for param in started {
+            self.begin_set_parameter_unlinked(param);
+        }
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        self.set_parameter_normalized_unlinked(param, normalized);
+
+        let partner_values = self.param_links.borrow().partner_values(param, normalized);
+        for (partner, partner_normalized) in partner_values {
+            self.set_parameter_normalized_unlinked(partner, partner_normalized);
+        }
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        let ended = self.param_links.borrow_mut().end_gesture(param);
+        for param in ended {
+            self.end_set_parameter_unlinked(param);
+        }
+    }
+
+    fn raw_begin_group_edit(&self) {
+        if !self.group_edit.borrow_mut().begin() {
+            return;
+        }
+
+        // Hosts that implement `IComponentHandler2` combine all edits made between these calls
+        // into a single undo step
+        if let Some(handler) = &*self.inner.component_handler.borrow() {
+            if let Some(handler2) = handler.cast::<dyn IComponentHandler2>() {
+                unsafe { handler2.start_group_edit() };
+            }
+        }
+    }
+
+    fn raw_param_info_changed(&self) {
+        self.inner.param_info_changed();
+    }
+
+    fn raw_end_group_edit(&self) {
+        let deferred_ends = match self.group_edit.borrow_mut().end() {
+            Some(deferred_ends) => deferred_ends,
+            None => return,
+        };
+
+        match &*self.inner.component_handler.borrow() {
+            Some(handler) => {
+                // The gestures ended during the group edit are all ended at the same time, so
+                // hosts without `IComponentHandler2` still see a single set of overlapping
+                // gestures
+                for param in deferred_ends {
+                    match self.inner.param_ptr_to_hash.get(&param) {
+                        Some(hash) if self.open_gestures.borrow_mut().remove(hash) => unsafe {
+                            handler.end_edit(*hash)
+                        },
+                        Some(_) => (),
+                        None => nih_debug_assert_failure!("Unknown parameter: {:?}", param),
+                    };
+                }
+
+                if let Some(handler2) = handler.cast::<dyn IComponentHandler2>() {
+                    unsafe { handler2.finish_group_edit() };
+                }
+            }
+            None => nih_debug_assert_failure!("Component handler not yet set"),
+        }
+    }
 
     fn get_state(&self) -> PluginState {
         self.inner.get_state_object()
@@ -228,4 +432,68 @@ impl<P: Vst3Plugin> GuiContext for WrapperGuiContext<P> {
     fn set_state(&self, state: PluginState) {
         self.inner.set_state_object_from_gui(state)
     }
+
+    fn store_state_slot(&self, slot: StateSlot) {
+        self.inner.state_slots.store(self, slot)
+    }
+
+    fn swap_ab(&self) -> StateSlot {
+        self.inner.state_slots.swap(self)
+    }
+
+    fn copy_a_to_b(&self) {
+        self.inner.state_slots.copy_a_to_b(self)
+    }
+
+    fn active_state_slot(&self) -> StateSlot {
+        self.inner.state_slots.active()
+    }
+
+    fn show_param_context_menu(&self, param: ParamPtr, position: (f32, f32)) -> bool {
+        let param_hash = match self.inner.param_ptr_to_hash.get(&param) {
+            Some(hash) => *hash,
+            None => {
+                nih_debug_assert_failure!("Unknown parameter: {:?}", param);
+                return false;
+            }
+        };
+
+        match (
+            &*self.inner.component_handler.borrow(),
+            &*self.inner.plug_view.read(),
+        ) {
+            (Some(handler), Some(plug_view)) => unsafe {
+                plug_view.show_param_context_menu(handler, param_hash, position)
+            },
+            _ => false,
+        }
+    }
+
+    fn request_keyboard_focus(&self) -> bool {
+        match &*self.inner.plug_view.read() {
+            Some(plug_view) => {
+                plug_view.set_keyboard_focus(true);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn release_keyboard_focus(&self) {
+        if let Some(plug_view) = &*self.inner.plug_view.read() {
+            plug_view.set_keyboard_focus(false);
+        }
+    }
+
+    fn set_clipboard_text(&self, text: &str) -> bool {
+        self.clipboard.set_text(text)
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        self.clipboard.text()
+    }
+
+    fn param_indication(&self, _param: ParamPtr) -> Option<ParamIndication> {
+        None
+    }
 }
@@ -5,9 +5,11 @@
 //! frustrating and error prone, most code that does not specifically depend on all of the exposed
 //! plugin types was moved back to this module so it can be compiled and type checked as normal.
 
+use vst3_sys::base::tresult;
 use vst3_sys::base::{
     ClassCardinality, FactoryFlags, PClassInfo, PClassInfo2, PClassInfoW, PFactoryInfo,
 };
+use vst3_sys::c_void;
 
 use super::subcategories::Vst3SubCategory;
 use crate::prelude::Vst3Plugin;
@@ -31,6 +33,32 @@ pub struct PluginInfo {
     // These are used for the factory's own info struct
     pub url: &'static str,
     pub email: &'static str,
+
+    /// The plugin's ARA factory, if it supports ARA. These plugins expose an additional ARA main
+    /// factory class.
+    #[cfg(feature = "ara")]
+    pub ara_factory: Option<&'static crate::wrapper::ara::AraFactory>,
+}
+
+/// One of the classes exposed through the factory. Every plugin is exposed as an audio module
+/// class, and the ARA main factory classes for plugins supporting ARA are listed after those.
+enum FactoryClass<'a> {
+    Plugin(&'a PluginInfo),
+    #[cfg(feature = "ara")]
+    AraMainFactory(&'a PluginInfo),
+}
+
+fn factory_classes(plugin_infos: &[PluginInfo]) -> impl Iterator<Item = FactoryClass<'_>> {
+    let classes = plugin_infos.iter().map(FactoryClass::Plugin);
+    #[cfg(feature = "ara")]
+    let classes = classes.chain(
+        plugin_infos
+            .iter()
+            .filter(|info| info.ara_factory.is_some())
+            .map(FactoryClass::AraMainFactory),
+    );
+
+    classes
 }
 
 impl PluginInfo {
@@ -43,9 +71,65 @@ impl PluginInfo {
             version: P::VERSION,
             url: P::URL,
             email: P::EMAIL,
+
+            #[cfg(feature = "ara")]
+            ara_factory: P::ara_factory(),
+        }
+    }
+
+    /// The number of classes exposed through a factory for these plugins.
+    pub fn class_count(plugin_infos: &[PluginInfo]) -> i32 {
+        factory_classes(plugin_infos).count() as i32
+    }
+
+    /// The [`PClassInfo`] for the class at `index`, if it exists.
+    pub fn class_info(plugin_infos: &[PluginInfo], index: i32) -> Option<PClassInfo> {
+        match factory_classes(plugin_infos).nth(usize::try_from(index).ok()?)? {
+            FactoryClass::Plugin(info) => Some(info.create_class_info()),
+            #[cfg(feature = "ara")]
+            FactoryClass::AraMainFactory(info) => Some(info.create_ara_class_info()),
         }
     }
 
+    /// The [`PClassInfo2`] for the class at `index`, if it exists.
+    pub fn class_info_2(plugin_infos: &[PluginInfo], index: i32) -> Option<PClassInfo2> {
+        match factory_classes(plugin_infos).nth(usize::try_from(index).ok()?)? {
+            FactoryClass::Plugin(info) => Some(info.create_class_info_2()),
+            #[cfg(feature = "ara")]
+            FactoryClass::AraMainFactory(info) => Some(info.create_ara_class_info_2()),
+        }
+    }
+
+    /// The [`PClassInfoW`] for the class at `index`, if it exists.
+    pub fn class_info_unicode(plugin_infos: &[PluginInfo], index: i32) -> Option<PClassInfoW> {
+        match factory_classes(plugin_infos).nth(usize::try_from(index).ok()?)? {
+            FactoryClass::Plugin(info) => Some(info.create_class_info_unicode()),
+            #[cfg(feature = "ara")]
+            FactoryClass::AraMainFactory(info) => Some(info.create_ara_class_info_unicode()),
+        }
+    }
+
+    /// Create an instance of one of the factory's classes that isn't a plugin, like an ARA main
+    /// factory. Returns `None` if `cid` does not belong to one of these classes.
+    ///
+    /// # Safety
+    ///
+    /// `iid` and `obj` need to be valid pointers.
+    #[allow(unused_variables)]
+    pub unsafe fn create_auxiliary_instance(
+        plugin_infos: &[PluginInfo],
+        cid: &[u8; 16],
+        iid: *const vst3_sys::IID,
+        obj: *mut *mut c_void,
+    ) -> Option<tresult> {
+        #[cfg(feature = "ara")]
+        if let Some(result) = super::ara::create_main_factory(plugin_infos, cid, iid, obj) {
+            return Some(result);
+        }
+
+        None
+    }
+
     /// Fill a [`PFactoryInfo`] struct with the information from this library. Used in
     /// `IPluginFactory`.
     pub fn create_factory_info(&self) -> PFactoryInfo {
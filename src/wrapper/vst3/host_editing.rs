@@ -0,0 +1,18 @@
+//! `IEditControllerHostEditing` from `pluginterfaces/vst/ivsteditcontroller.h`. This interface was
+//! added in VST 3.7.1 and it's not exposed by vst3-sys, so it's defined here instead.
+
+use vst3_sys::base::{tresult, IUnknown};
+use vst3_sys::com_interface;
+use vst3_sys::vst::ParamID;
+
+// Alias needed for the VST3 interface macro
+use vst3_sys as vst3_com;
+
+/// Lets the host inform the plugin that it's about to change a parameter through
+/// `IEditController::setParamNormalized()` from its own UI, e.g. a generic editor or a hardware
+/// controller.
+#[com_interface("C1271208-7059-4098-B9DD-34B36BB0195E")]
+pub trait IEditControllerHostEditing: IUnknown {
+    unsafe fn begin_edit_from_host(&self, param_id: ParamID) -> tresult;
+    unsafe fn end_edit_from_host(&self, param_id: ParamID) -> tresult;
+}
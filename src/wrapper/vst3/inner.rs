@@ -1,9 +1,11 @@
+use arc_swap::ArcSwapOption;
 use atomic_refcell::AtomicRefCell;
 use crossbeam::atomic::AtomicCell;
 use crossbeam::channel::{self, SendTimeoutError};
+use crossbeam::queue::ArrayQueue;
 use parking_lot::{Mutex, RwLock};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use vst3_sys::base::{kInvalidArgument, kResultOk, tresult};
@@ -12,17 +14,31 @@ use vst3_sys::vst::{IComponentHandler, RestartFlags};
 use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
 use super::note_expressions::NoteExpressionController;
 use super::param_units::ParamUnits;
-use super::util::{ObjectPtr, VstPtr, VST3_MIDI_PARAMS_END, VST3_MIDI_PARAMS_START};
+use super::util::{
+    ObjectPtr, VstPtr, VST3_MIDI_PARAMS_END, VST3_MIDI_PARAMS_START, VST3_PROGRAM_CHANGE_PARAM_ID,
+};
 use super::view::WrapperView;
-use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop};
+use crate::context::gui::{GuiMessageQueue, ParamChange, ParamChangeBroadcaster, StateSlots};
+use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop, TimerCallback, TimerHandle};
 use crate::prelude::{
-    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, MidiConfig, ParamFlags, ParamPtr, Params,
-    Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, TaskExecutor, Transport, Vst3Plugin,
+    AsyncExecutor, AudioIOLayout, BufferConfig, Editor, MidiConfig, NoteEvent, ParamFlags,
+    ParamPtr, Params, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, StateSaveContext,
+    TaskExecutor, TaskOptions, TaskPriority, TrackInfo, Transport, Vst3Plugin,
 };
-use crate::util::permit_alloc;
+use crate::util::modulation::ModulationMatrix;
+use crate::util::{permit_alloc, PresetEntry, PresetLibrary};
 use crate::wrapper::state::{self, PluginState};
 use crate::wrapper::util::buffer_management::BufferManager;
-use crate::wrapper::util::{hash_param_id, process_wrapper};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::dsp_load::DspLoadMeter;
+use crate::wrapper::util::event_scheduler::EventScheduler;
+use crate::wrapper::util::output_params::OutputParams;
+use crate::wrapper::util::param_links::ParamLinks;
+use crate::wrapper::util::state_cache::StateCache;
+use crate::wrapper::util::{
+    bypass_param_enabled, find_bypass_param, hash_param_id, process_wrapper, queue_gui_note_event,
+    GUI_NOTE_EVENT_QUEUE_CAPACITY,
+};
 
 /// The actual wrapper bits. We need this as an `Arc<T>` so we can safely use our event loop API.
 /// Since we can't combine that with VST3's interior reference counting this just has to be moved to
@@ -68,6 +84,10 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// IO layout is chosen as the default. Because of the way VST3 works it's not possible to
     /// change the number of busses from that default, only the channel counts can change.
     pub current_audio_io_layout: AtomicCell<AudioIOLayout>,
+    /// A bit set containing the active state of each auxiliary input bus, as set through
+    /// `IComponent::activateBus()`. All busses are reported as active by default. Deactivated
+    /// auxiliary inputs are passed to the plugin as silent buffers.
+    pub aux_inputs_active: AtomicU64,
     /// The current buffer configuration, containing the sample rate and the maximum block size.
     /// Will be set in `IAudioProcessor::setupProcessing()`.
     pub current_buffer_config: AtomicCell<Option<BufferConfig>>,
@@ -78,9 +98,23 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
     /// [`ProcessContext`].
     pub current_latency: AtomicU32,
+    /// The current tail length in samples, as set by the plugin through the [`ProcessContext`].
+    pub current_tail: AtomicU32,
     /// A data structure that helps manage and create buffers for all of the plugin's inputs and
     /// outputs based on channel pointers provided by the host.
     pub buffer_manager: AtomicRefCell<BufferManager>,
+    /// The plugin's bypass parameter, if it has one. Used for [`ProcessContext::bypassed()`].
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the latency compensated dry signal when the plugin is
+    /// bypassed. This is only used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    pub bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// Sends the values of read-only parameters changed by the plugin back to the host.
+    pub output_params: AtomicRefCell<OutputParams>,
+    /// The plugin's large persistent fields, serialized ahead of time on the background thread.
+    pub state_cache: StateCache,
+    /// The last track information provided by the host through `IInfoListener`. Used for
+    /// [`ProcessContext::track_info()`].
+    pub track_info: ArcSwapOption<TrackInfo>,
     /// The incoming events for the plugin, if `P::ACCEPTS_MIDI` is set. If
     /// `P::SAMPLE_ACCURATE_AUTOMATION`, this is also read in lockstep with the parameter change
     /// block splitting.
@@ -92,6 +126,42 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// Stores any events the plugin has output during the current processing cycle, analogous to
     /// `input_events`.
     pub output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Note events the plugin has scheduled for a later block using
+    /// [`ProcessContext::send_event_at()`][crate::prelude::ProcessContext::send_event_at()].
+    pub event_scheduler: AtomicRefCell<EventScheduler<P::SysExMessage>>,
+    /// Note events sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()]. These are
+    /// added to the start of `input_events` at the start of the next processing cycle.
+    pub gui_note_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// Messages sent from the editor through `send_to_audio()` on the
+    /// [`GuiContext`][crate::prelude::GuiContext]. The plugin receives these through
+    /// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    pub gui_messages: GuiMessageQueue,
+    /// Broadcasts parameter changes to the receivers created through
+    /// [`GuiContext::subscribe_param_changes()`][crate::prelude::GuiContext::subscribe_param_changes()].
+    pub param_changes: ParamChangeBroadcaster,
+    /// The A/B comparison slots used by the [`GuiContext`][crate::prelude::GuiContext]'s
+    /// `swap_ab()` and related functions.
+    pub state_slots: StateSlots,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    pub modulation_matrix: Option<Arc<ModulationMatrix>>,
+    /// The plugin's preset library, if it has one.
+    pub preset_library: Option<Arc<PresetLibrary>>,
+    /// The presets that were found in the preset library when the plugin was created. These are
+    /// exposed to the host as a program list with a `kIsProgramChange` parameter. This list is not
+    /// updated afterwards since the program change parameter's step count needs to stay fixed.
+    pub programs: Vec<PresetEntry>,
+    /// The index in `programs` of the last program selected through the program change parameter,
+    /// or `u32::MAX` if no program has been selected yet.
+    pub current_program: AtomicU32,
+    /// Measures the time spent in the plugin's process function. Exposed to the editor through
+    /// [`GuiContext::dsp_load()`][crate::prelude::GuiContext::dsp_load()].
+    pub dsp_load: DspLoadMeter,
+    /// The ARA plugin extension instance, set when the host binds this plugin instance to an ARA
+    /// document controller through `IPlugInEntryPoint2`.
+    #[cfg(feature = "ara")]
+    pub ara_plugin_extension: Mutex<Option<crate::wrapper::ara::PlugInExtension>>,
     /// VST3 has several useful predefined note expressions, but for some reason they are the only
     /// note event type that don't have MIDI note ID and channel fields. So we need to keep track of
     /// the most recent VST3 note IDs we've seen, and then map those back to MIDI note IDs and
@@ -137,6 +207,11 @@ pub(crate) struct WrapperInner<P: Vst3Plugin> {
     /// having to add a setter function to the parameter (or even worse, have it be completely
     /// untyped).
     pub param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// The hashes of the parameters the host is currently changing from its own UI, as indicated
+    /// through `IEditControllerHostEditing`. Edits made from the plugin's editor to these
+    /// parameters are not sent to the host until the host is done with them, since that would
+    /// interleave two gestures for the same parameter.
+    pub host_edited_params: Mutex<HashSet<u32>>,
 }
 
 /// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
@@ -157,6 +232,16 @@ pub enum Task<P: Plugin> {
     /// Request the editor to be resized according to its current size. Right now there is no way to
     /// handle "denied resize" requests yet.
     RequestResize,
+    /// Load the program with this index from [`WrapperInner::programs`].
+    LoadProgram(u32),
+    /// Serialize the plugin's changed blobs ahead of time. Runs on the background thread.
+    RefreshStateCache,
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+    /// Run a timer callback registered through the [`GuiContext`][crate::prelude::GuiContext].
+    TimerCallback(TimerCallback),
 }
 
 /// VST3 makes audio processing pretty complicated. In order to support both block splitting for
@@ -201,6 +286,13 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         // we'll calculate from the string ID specified by the plugin. These parameters should also
         // remain in the same order as the one returned by the plugin.
         let params = plugin.params();
+        let state_cache = StateCache::new(&*params, P::COMPRESS_STATE);
+        let modulation_matrix = plugin.modulation_matrix();
+        let preset_library = plugin.preset_library();
+        let programs = preset_library
+            .as_ref()
+            .map(|library| library.scan_now())
+            .unwrap_or_default();
         let param_id_hashes_ptrs_groups: Vec<_> = params
             .param_map()
             .into_iter()
@@ -244,6 +336,14 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                         id
                     );
                 }
+
+                if !programs.is_empty() && *hash == VST3_PROGRAM_CHANGE_PARAM_ID {
+                    nih_debug_assert_failure!(
+                        "Parameter '{}' collides with the automatically generated program change \
+                         parameter, consider giving it a different ID",
+                        id
+                    );
+                }
             }
         }
 
@@ -269,6 +369,11 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             .iter()
             .map(|(id, hash, _, _)| (id.clone(), *hash))
             .collect();
+        let bypass_param = find_bypass_param::<P>(
+            param_id_hashes_ptrs_groups
+                .iter()
+                .map(|(_, _, ptr, _)| *ptr),
+        );
         let param_ptr_to_hash = param_id_hashes_ptrs_groups
             .into_iter()
             .map(|(_, hash, ptr, _)| (ptr, hash))
@@ -295,18 +400,38 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             current_audio_io_layout: AtomicCell::new(
                 P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default(),
             ),
+            aux_inputs_active: AtomicU64::new(u64::MAX),
             current_buffer_config: AtomicCell::new(None),
             current_process_mode: AtomicCell::new(ProcessMode::Realtime),
             last_process_status: AtomicCell::new(ProcessStatus::Normal),
             current_latency: AtomicU32::new(0),
+            current_tail: AtomicU32::new(0),
             // This is initialized just before calling `Plugin::initialize()` so that during the
             // process call buffers can be initialized without any allocations
             buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
                 0,
                 AudioIOLayout::default(),
             )),
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            // SAFETY: The parameters are owned by the plugin, which is stored on this object
+            output_params: AtomicRefCell::new(unsafe { OutputParams::new(&param_by_hash) }),
+            state_cache,
+            track_info: ArcSwapOption::empty(),
             input_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
             output_events: AtomicRefCell::new(VecDeque::with_capacity(1024)),
+            event_scheduler: AtomicRefCell::new(EventScheduler::default()),
+            gui_note_events: ArrayQueue::new(GUI_NOTE_EVENT_QUEUE_CAPACITY),
+            gui_messages: GuiMessageQueue::default(),
+            param_changes: ParamChangeBroadcaster::default(),
+            state_slots: StateSlots::default(),
+            modulation_matrix,
+            preset_library,
+            programs,
+            current_program: AtomicU32::new(u32::MAX),
+            dsp_load: DspLoadMeter::default(),
+            #[cfg(feature = "ara")]
+            ara_plugin_extension: Mutex::new(None),
             note_expression_controller: AtomicRefCell::new(NoteExpressionController::default()),
             process_events: AtomicRefCell::new(Vec::with_capacity(4096)),
             updated_state_sender,
@@ -318,6 +443,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             param_units,
             param_id_to_hash,
             param_ptr_to_hash,
+            host_edited_params: Mutex::new(HashSet::new()),
         });
 
         // FIXME: Right now this is safe, but if we are going to have a singleton main thread queue
@@ -347,6 +473,11 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
                     }
                 }),
+                cancel_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |tag| wrapper.cancel_background_tasks(tag)
+                }),
             })
             .map(|editor| Arc::new(Mutex::new(editor)));
 
@@ -354,8 +485,14 @@ impl<P: Vst3Plugin> WrapperInner<P> {
     }
 
     pub fn make_gui_context(self: Arc<Self>) -> Arc<WrapperGuiContext<P>> {
+        let param_links = ParamLinks::new(self.params.param_links());
+
         Arc::new(WrapperGuiContext {
             inner: self,
+            group_edit: Default::default(),
+            open_gestures: Default::default(),
+            param_links: AtomicRefCell::new(param_links),
+            clipboard: Default::default(),
             #[cfg(debug_assertions)]
             param_gesture_checker: Default::default(),
         })
@@ -384,12 +521,32 @@ impl<P: Vst3Plugin> WrapperInner<P> {
     /// Posts the task to the background task queue using [`EventLoop::schedule_background()`] so it
     /// can be run in the background without blocking either the GUI or the audio thread.
     ///
+    /// The plugin's own tasks are scheduled using the options from
+    /// [`Plugin::background_task_options()`].
+    ///
     /// If the task queue is full, then this will return false.
     #[must_use]
     pub fn schedule_background(&self, task: Task<P>) -> bool {
+        let options = match &task {
+            Task::PluginTask(task) => P::background_task_options(task),
+            Task::RefreshStateCache => TaskOptions {
+                priority: TaskPriority::Low,
+                ..TaskOptions::default()
+            },
+            _ => TaskOptions::default(),
+        };
+
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_background(task, options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
         let event_loop = self.event_loop.borrow();
         let event_loop = event_loop.as_ref().unwrap();
-        event_loop.schedule_background(task)
+        event_loop.cancel_background(tag)
     }
 
     /// Either posts the task to the task queue using [`EventLoop::schedule_gui()`] so it can be
@@ -420,6 +577,40 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         }
     }
 
+    /// Run `callback` on the GUI thread after `delay` has passed. See
+    /// [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()].
+    pub fn schedule_timer_once(
+        &self,
+        delay: Duration,
+        callback: Box<dyn FnOnce() + Send>,
+    ) -> TimerHandle {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_once(
+            delay,
+            Task::TimerCallback(TimerCallback::new_once(callback)),
+        )
+    }
+
+    /// Run `callback` on the GUI thread every `interval`. See
+    /// [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()].
+    pub fn schedule_timer_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let callback = TimerCallback::new(callback);
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_repeating(interval, move || Task::TimerCallback(callback.clone()))
+    }
+
+    /// Queue a note event sent from the editor. See
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+    pub fn queue_gui_note_event(&self, event: NoteEvent<()>) -> bool {
+        queue_gui_note_event::<P>(&self.gui_note_events, event)
+    }
+
     /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
     /// gesture checks.
     #[allow(unused)]
@@ -449,6 +640,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
                     if let Some(sample_rate) = sample_rate {
                         unsafe { param_ptr.update_smoother(sample_rate, false) };
                     }
+                    self.apply_macro_mappings(param_ptr, sample_rate);
 
                     let task_posted =
                         self.schedule_gui(Task::ParameterValueChanged(hash, normalized_value));
@@ -461,6 +653,61 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         }
     }
 
+    /// Convert a normalized value received from the host to the parameter's own normalized value.
+    /// See [`ParamPtr::host_step_count()`]. Unknown parameters are passed through as is.
+    pub fn from_host_normalized(&self, hash: u32, host_normalized: f32) -> f32 {
+        match self.param_by_hash.get(&hash) {
+            Some(param_ptr) => unsafe { param_ptr.from_host_normalized(host_normalized) },
+            None => host_normalized,
+        }
+    }
+
+    /// Select a program from [`Self::programs`] using the program change parameter's normalized
+    /// value. The preset is loaded on the GUI thread, so this can also be called from the audio
+    /// thread.
+    pub fn set_program_normalized(&self, normalized_value: f32) -> tresult {
+        let Some(last_program_idx) = self.programs.len().checked_sub(1) else {
+            return kInvalidArgument;
+        };
+
+        let program_idx =
+            (normalized_value.clamp(0.0, 1.0) * last_program_idx as f32).round() as u32;
+        if self.current_program.swap(program_idx, Ordering::SeqCst) != program_idx {
+            let task_posted = self.schedule_gui(Task::LoadProgram(program_idx));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+
+        kResultOk
+    }
+
+    /// The program change parameter's current normalized value.
+    pub fn program_normalized(&self) -> f32 {
+        match self.current_program.load(Ordering::SeqCst) {
+            u32::MAX => 0.0,
+            program_idx => program_idx as f32 / self.programs.len().saturating_sub(1).max(1) as f32,
+        }
+    }
+
+    /// If `param_ptr` is a [`MacroParam`][crate::prelude::MacroParam], then update its target
+    /// parameters after its value has changed and inform the GUI about the new target values.
+    fn apply_macro_mappings(&self, param_ptr: &ParamPtr, sample_rate: Option<f32>) {
+        unsafe {
+            param_ptr.apply_macro_mappings(
+                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                sample_rate,
+                |target_ptr, normalized_value| {
+                    if let Some(target_hash) = self.param_ptr_to_hash.get(&target_ptr) {
+                        let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                            *target_hash,
+                            normalized_value,
+                        ));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                },
+            )
+        };
+    }
+
     /// Get the plugin's state object, may be called by the plugin's GUI as part of its own preset
     /// management. The wrapper doesn't use these functions and serializes and deserializes directly
     /// the JSON in the relevant plugin API methods instead.
@@ -469,6 +716,7 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             state::serialize_object::<P>(
                 self.params.clone(),
                 state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                StateSaveContext::Project,
             )
         }
     }
@@ -526,6 +774,36 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
     }
 
+    /// Whether the host is currently changing this parameter from its own UI. See
+    /// [`Self::host_edited_params`].
+    pub fn is_edited_by_host(&self, param_hash: u32) -> bool {
+        self.host_edited_params.lock().contains(&param_hash)
+    }
+
+    /// Whether the auxiliary input with this index is currently active. See
+    /// [`Self::aux_inputs_active`]. Indices past the 64th auxiliary input are always active.
+    pub fn is_aux_input_active(&self, aux_input_idx: usize) -> bool {
+        aux_input_idx >= 64
+            || self.aux_inputs_active.load(Ordering::Relaxed) & (1 << aux_input_idx) != 0
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
+    /// Inform the plugin that its editor has been opened or closed through
+    /// [`Plugin::editor_opened()`] and [`Plugin::editor_closed()`]. Called from the main thread
+    /// after the editor handle has been created or dropped.
+    pub fn notify_editor_opened(&self, opened: bool) {
+        let mut plugin = self.plugin.lock();
+        if opened {
+            plugin.editor_opened();
+        } else {
+            plugin.editor_closed();
+        }
+    }
+
     pub fn set_latency_samples(&self, samples: u32) {
         // Only trigger a restart if it's actually needed
         let old_latency = self.current_latency.swap(samples, Ordering::SeqCst);
@@ -536,6 +814,33 @@ impl<P: Vst3Plugin> WrapperInner<P> {
         }
     }
 
+    /// Tell the host that one or more [`DynamicParam`][crate::prelude::DynamicParam]s have been
+    /// relabeled.
+    pub fn param_info_changed(&self) {
+        let task_posted = self.schedule_gui(Task::TriggerRestart(
+            RestartFlags::kParamTitlesChanged as i32 | RestartFlags::kParamValuesChanged as i32,
+        ));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    /// Store the track information provided by the host, and inform the plugin if it changed.
+    pub fn set_track_info(&self, track_info: TrackInfo) {
+        if self.track_info.load().as_deref() != Some(&track_info) {
+            self.plugin.lock().track_info_changed(&track_info);
+            self.track_info.store(Some(Arc::new(track_info)));
+        }
+    }
+
+    pub fn set_tail_samples(&self, samples: u32) {
+        // VST3 uses the same restart flag for latency and tail changes
+        let old_tail = self.current_tail.swap(samples, Ordering::SeqCst);
+        if old_tail != samples {
+            let task_posted =
+                self.schedule_gui(Task::TriggerRestart(RestartFlags::kLatencyChanged as i32));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
     /// Immediately set the plugin state. Returns `false` if the deserialization failed. The plugin
     /// state is set from a couple places, so this function aims to deduplicate that. Includes
     /// `permit_alloc()`s around the deserialization and initialization for the use case where
@@ -569,6 +874,11 @@ impl<P: Vst3Plugin> WrapperInner<P> {
             return false;
         }
 
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
         // If the plugin was already initialized then it needs to be reinitialized
         if let Some(buffer_config) = buffer_config {
             // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
@@ -611,6 +921,7 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
         match task {
             Task::PluginTask(task) => (self.task_executor.lock())(task),
             Task::ParameterValuesChanged => {
+                self.param_changes.broadcast(|| Some(ParamChange::All));
                 if self.plug_view.read().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         editor.lock().param_values_changed();
@@ -618,6 +929,12 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
                 }
             }
             Task::ParameterValueChanged(param_hash, normalized_value) => {
+                self.param_changes.broadcast(|| {
+                    Some(ParamChange::Value {
+                        id: self.param_id_by_hash.get(&param_hash)?.clone(),
+                        normalized_value,
+                    })
+                });
                 if self.plug_view.read().is_some() {
                     if let Some(editor) = self.editor.borrow().as_ref() {
                         let param_id = &self.param_id_by_hash[&param_hash];
@@ -648,6 +965,24 @@ impl<P: Vst3Plugin> MainThreadExecutor<Task<P>> for WrapperInner<P> {
                 },
                 None => nih_debug_assert_failure!("Can't resize a closed editor"),
             },
+            Task::LoadProgram(program_idx) => {
+                if let (Some(preset_library), Some(preset)) = (
+                    &self.preset_library,
+                    self.programs.get(program_idx as usize),
+                ) {
+                    match preset_library.load(preset) {
+                        Ok(state) => self.set_state_object_from_gui(state),
+                        Err(err) => nih_error!("Could not load '{}': {:#}", preset.name, err),
+                    }
+                }
+            }
+            Task::RefreshStateCache => self.state_cache.refresh(&*self.params),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
+            Task::TimerCallback(callback) => callback.call(),
         }
     }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
 }
@@ -1,9 +1,13 @@
 use std::cmp;
+use std::mem;
 use std::ops::Deref;
+use vst3_sys::base::kResultOk;
 use vst3_sys::interfaces::IUnknown;
-use vst3_sys::vst::TChar;
+use vst3_sys::vst::{IAttributeList, TChar};
 use vst3_sys::ComInterface;
-use widestring::U16CString;
+use widestring::{U16CStr, U16CString};
+
+use crate::prelude::NoteEvent;
 
 /// When `Plugin::MIDI_INPUT` is set to `MidiConfig::MidiCCs` or higher then we'll register 130*16
 /// additional parameters to handle MIDI CCs, channel pressure, and pitch bend, in that order.
@@ -18,6 +22,114 @@ pub const VST3_MIDI_NUM_PARAMS: u32 = VST3_MIDI_CCS * VST3_MIDI_CHANNELS;
 pub const VST3_MIDI_PARAMS_START: u32 = VST3_MIDI_PARAMS_END - VST3_MIDI_NUM_PARAMS;
 /// The (exclusive) end of the MIDI CC parameter range. Anything above this is reserved by the host.
 pub const VST3_MIDI_PARAMS_END: u32 = 1 << 31;
+/// The ID of the `kIsProgramChange` parameter used to select a preset from the plugin's
+/// [`PresetLibrary`][crate::util::PresetLibrary]. This comes right before the MIDI CC parameters.
+pub const VST3_PROGRAM_CHANGE_PARAM_ID: u32 = VST3_MIDI_PARAMS_START - 1;
+/// The ID of the program list containing the preset library's presets. There is only ever one.
+pub const VST3_PROGRAM_LIST_ID: i32 = 1;
+/// The controller number VST3 uses for channel pressure. This comes after the 128 regular CCs.
+const VST3_MIDI_CHANNEL_PRESSURE: u8 = 128;
+/// The controller number VST3 uses for pitch bend.
+const VST3_MIDI_PITCH_BEND: u8 = 129;
+
+/// One of the generated MIDI CC, channel pressure, or pitch bend parameters. The host maps MIDI
+/// controllers to these parameters through `IMidiMapping`, and changes to them are translated back
+/// into [`NoteEvent`]s.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MidiCcProxy {
+    /// The zero-indexed MIDI channel.
+    pub channel: u8,
+    /// The CC number, or [`VST3_MIDI_CHANNEL_PRESSURE`] or [`VST3_MIDI_PITCH_BEND`].
+    pub controller: u8,
+}
+
+impl MidiCcProxy {
+    /// Get the proxy for a channel and a VST3 controller number. Returns `None` if either is out of
+    /// range.
+    pub fn new(channel: i16, controller: i16) -> Option<Self> {
+        if (0..VST3_MIDI_CHANNELS as i16).contains(&channel)
+            && (0..VST3_MIDI_CCS as i16).contains(&controller)
+        {
+            Some(Self {
+                channel: channel as u8,
+                controller: controller as u8,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get the proxy at an index in `[0, VST3_MIDI_NUM_PARAMS)`.
+    pub fn from_index(index: u32) -> Option<Self> {
+        if index < VST3_MIDI_NUM_PARAMS {
+            Some(Self {
+                channel: (index / VST3_MIDI_CCS) as u8,
+                controller: (index % VST3_MIDI_CCS) as u8,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Get the proxy for a parameter ID, if the ID falls within the MIDI CC parameter range.
+    pub fn from_param_id(param_id: u32) -> Option<Self> {
+        param_id
+            .checked_sub(VST3_MIDI_PARAMS_START)
+            .and_then(Self::from_index)
+    }
+
+    /// The parameter ID used for this proxy.
+    pub fn param_id(self) -> u32 {
+        VST3_MIDI_PARAMS_START + self.controller as u32 + (self.channel as u32 * VST3_MIDI_CCS)
+    }
+
+    /// The name reported to the host.
+    pub fn name(self) -> String {
+        match self.controller {
+            VST3_MIDI_CHANNEL_PRESSURE => format!("MIDI Ch. {} Channel Pressure", self.channel + 1),
+            VST3_MIDI_PITCH_BEND => format!("MIDI Ch. {} Pitch Bend", self.channel + 1),
+            cc => format!("MIDI Ch. {} CC {}", self.channel + 1, cc),
+        }
+    }
+
+    /// The value the controller has before the host sends anything. Pitch bend is centered.
+    pub fn default_normalized_value(self) -> f32 {
+        match self.controller {
+            VST3_MIDI_PITCH_BEND => 0.5,
+            _ => 0.0,
+        }
+    }
+
+    /// Format a normalized value the way it would be sent over MIDI.
+    pub fn normalized_value_to_string(self, normalized: f32) -> String {
+        match self.controller {
+            VST3_MIDI_PITCH_BEND => format!("{}", (normalized * 16383.0).round() as i32 - 8192),
+            _ => format!("{}", (normalized * 127.0).round() as i32),
+        }
+    }
+
+    /// Translate a change to this proxy parameter into the corresponding MIDI event.
+    pub fn to_note_event<S>(self, timing: u32, value: f32) -> NoteEvent<S> {
+        match self.controller {
+            VST3_MIDI_CHANNEL_PRESSURE => NoteEvent::MidiChannelPressure {
+                timing,
+                channel: self.channel,
+                pressure: value,
+            },
+            VST3_MIDI_PITCH_BEND => NoteEvent::MidiPitchBend {
+                timing,
+                channel: self.channel,
+                value,
+            },
+            cc => NoteEvent::MidiCC {
+                timing,
+                channel: self.channel,
+                cc,
+                value,
+            },
+        }
+    }
+}
 
 /// Early exit out of a VST3 function when one of the passed pointers is null
 macro_rules! check_null_ptr {
@@ -59,6 +171,30 @@ pub fn u16strlcpy(dest: &mut [TChar], src: &str) {
     dest[copy_len] = 0;
 }
 
+/// Read a string from an attribute list. `key` needs to be a null terminated ASCII string. Returns
+/// `None` if the attribute does not exist.
+pub unsafe fn get_attribute_string(
+    list: &vst3_sys::VstPtr<dyn IAttributeList>,
+    key: &[u8],
+) -> Option<String> {
+    nih_debug_assert_eq!(key.last(), Some(&0));
+
+    let mut buffer = [0 as TChar; 128];
+    if list.get_string(
+        key.as_ptr() as *const _,
+        buffer.as_mut_ptr(),
+        mem::size_of_val(&buffer) as u32,
+    ) != kResultOk
+    {
+        return None;
+    }
+
+    let buffer: &[u16] = &*(buffer.as_slice() as *const [TChar] as *const [u16]);
+    U16CStr::from_slice_truncate(buffer)
+        .ok()
+        .map(|string| string.to_string_lossy())
+}
+
 /// Send+Sync wrapper for these interface pointers.
 #[repr(transparent)]
 pub struct VstPtr<T: vst3_sys::ComInterface + ?Sized> {
@@ -135,6 +271,24 @@ mod miri {
         );
     }
 
+    #[test]
+    fn midi_cc_proxy_roundtrip() {
+        for index in 0..VST3_MIDI_NUM_PARAMS {
+            let proxy = MidiCcProxy::from_index(index).unwrap();
+            assert_eq!(proxy.param_id(), VST3_MIDI_PARAMS_START + index);
+            assert_eq!(MidiCcProxy::from_param_id(proxy.param_id()), Some(proxy));
+            assert_eq!(
+                MidiCcProxy::new(proxy.channel as i16, proxy.controller as i16),
+                Some(proxy)
+            );
+        }
+
+        assert_eq!(MidiCcProxy::from_param_id(VST3_MIDI_PARAMS_START - 1), None);
+        assert_eq!(MidiCcProxy::from_param_id(VST3_MIDI_PARAMS_END), None);
+        assert_eq!(MidiCcProxy::new(16, 0), None);
+        assert_eq!(MidiCcProxy::new(0, 130), None);
+    }
+
     #[test]
     fn u16strlcpy_overflow() {
         let mut dest = [0; 6];
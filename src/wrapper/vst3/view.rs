@@ -3,17 +3,19 @@ use parking_lot::{Mutex, RwLock};
 use std::any::Any;
 use std::ffi::{c_void, CStr};
 use std::mem;
-use std::sync::atomic::Ordering;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use vst3_sys::base::{kInvalidArgument, kNotImplemented, kResultFalse, kResultOk, tresult, TBool};
 use vst3_sys::gui::{IPlugFrame, IPlugView, IPlugViewContentScaleSupport, ViewRect};
 use vst3_sys::utils::SharedVstPtr;
+use vst3_sys::vst::{IComponentHandler, IComponentHandler3, IContextMenu};
 use vst3_sys::VST3;
 
 use super::inner::{Task, WrapperInner};
 use super::util::{ObjectPtr, VstPtr};
 use crate::plugin::vst3::Vst3Plugin;
 use crate::prelude::{Editor, ParentWindowHandle};
+use crate::wrapper::util::window_focus::{self, EditorParent};
 
 // Alias needed for the VST3 attribute macro
 use vst3_sys as vst3_com;
@@ -65,8 +67,21 @@ pub(crate) struct WrapperView<P: Vst3Plugin> {
     /// The DPI scaling factor as passed to the [IPlugViewContentScaleSupport::set_scale_factor()]
     /// function. Defaults to 1.0, and will be kept there on macOS. When reporting and handling size
     /// the sizes communicated to and from the DAW should be scaled by this factor since NIH-plug's
-    /// APIs only deal in logical pixels.
+    /// APIs only deal in logical pixels. If the user has chosen a UI scale through
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()], then this
+    /// contains that scale instead.
     scaling_factor: AtomicF32,
+    /// The DPI scaling factor last set by the host, regardless of the user's UI scale. This is
+    /// used again when the user's UI scale is reset.
+    host_scaling_factor: AtomicF32,
+    /// Whether a text entry field in the editor currently has keyboard focus. Set through
+    /// [`GuiContext::request_keyboard_focus()`][crate::prelude::GuiContext::request_keyboard_focus()].
+    /// While this is set, keys passed to [`IPlugView::on_key_down()`] are reported as handled so
+    /// the host doesn't use them as shortcuts.
+    keyboard_focus: AtomicBool,
+    /// The parent window passed to [`IPlugView::attached()`]. Used to give the editor's window
+    /// keyboard focus when a text entry field requests it.
+    editor_parent: RwLock<Option<EditorParent>>,
 }
 
 /// Allow handling tasks on the host's GUI thread on Linux. This doesn't need to be a separate
@@ -100,7 +115,7 @@ struct RunLoopEventHandler<P: Vst3Plugin> {
 
 impl<P: Vst3Plugin> WrapperView<P> {
     pub fn new(inner: Arc<WrapperInner<P>>, editor: Arc<Mutex<Box<dyn Editor>>>) -> Box<Self> {
-        Self::allocate(
+        let view = Self::allocate(
             inner,
             editor,
             RwLock::new(None),
@@ -110,7 +125,60 @@ impl<P: Vst3Plugin> WrapperView<P> {
             #[cfg(not(target_os = "linux"))]
             RunLoopEventHandlerWrapper(Default::default()),
             AtomicF32::new(1.0),
-        )
+            AtomicF32::new(1.0),
+            AtomicBool::new(false),
+            RwLock::new(None),
+        );
+
+        // If the user has chosen a UI scale in a previous session, then the host needs to know
+        // about the resulting size before the editor is attached
+        if !cfg!(target_os = "macos") && view.editor.lock().ui_scale().is_some() {
+            view.update_editor_scaling_factor();
+        }
+
+        view
+    }
+
+    /// Change the UI scale chosen by the user. Returns `true` if the editor accepted the new
+    /// scale, in which case a resize still needs to be requested. See
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()].
+    pub fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        // On macOS scaling is done by the OS, and all window sizes are in logical pixels
+        if cfg!(target_os = "macos") {
+            return false;
+        }
+
+        self.editor.lock().set_ui_scale(scale) && self.update_editor_scaling_factor()
+    }
+
+    /// Pass the user's UI scale to the editor, or the host's scaling factor if the user has not
+    /// chosen a UI scale. Returns `false` if the editor rejected the new scaling factor, for
+    /// instance because it is currently open.
+    fn update_editor_scaling_factor(&self) -> bool {
+        let editor = self.editor.lock();
+        let scaling_factor = editor
+            .ui_scale()
+            .unwrap_or_else(|| self.host_scaling_factor.load(Ordering::Relaxed));
+        if editor.set_scale_factor(scaling_factor) {
+            self.scaling_factor.store(scaling_factor, Ordering::Relaxed);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Claim or release keyboard focus for a text entry field in the editor. When claiming focus,
+    /// the editor's window is also given the OS' keyboard focus since VST3 does not have a way to
+    /// ask the host for it. See
+    /// [`GuiContext::request_keyboard_focus()`][crate::prelude::GuiContext::request_keyboard_focus()].
+    /// This **needs** to be run from the GUI thread.
+    pub fn set_keyboard_focus(&self, focus: bool) {
+        self.keyboard_focus.store(focus, Ordering::Relaxed);
+        if focus {
+            if let Some(editor_parent) = &*self.editor_parent.read() {
+                unsafe { window_focus::focus_editor_window(editor_parent) };
+            }
+        }
     }
 
     /// Ask the host to resize the view to the size specified by [`Editor::size()`]. Will return false
@@ -158,6 +226,43 @@ impl<P: Vst3Plugin> WrapperView<P> {
         }
     }
 
+    /// Ask the host to show its context menu for a parameter using `IComponentHandler3`. The
+    /// position is specified in logical pixels relative to the editor window. Returns `false` if
+    /// the host does not implement that interface.
+    pub unsafe fn show_param_context_menu(
+        &self,
+        handler: &VstPtr<dyn IComponentHandler>,
+        param_hash: u32,
+        position: (f32, f32),
+    ) -> bool {
+        let handler3 = match handler.cast::<dyn IComponentHandler3>() {
+            Some(handler3) => handler3,
+            None => return false,
+        };
+
+        // See `request_resize()` for the reason behind this transmute
+        let plug_view: SharedVstPtr<dyn IPlugView> =
+            mem::transmute(&self.__iplugviewvptr as *const *const _);
+        let context_menu: SharedVstPtr<dyn IContextMenu> =
+            handler3.create_context_menu(plug_view, &param_hash);
+        match context_menu.upgrade() {
+            Some(context_menu) => {
+                let scaling_factor = self.scaling_factor.load(Ordering::Relaxed);
+                let result = context_menu.popup(
+                    (position.0 * scaling_factor).round() as i32,
+                    (position.1 * scaling_factor).round() as i32,
+                );
+
+                // `upgrade()` added a reference, but the host already returned an owned reference
+                // to us that we also need to release
+                context_menu.release();
+
+                result == kResultOk
+            }
+            None => false,
+        }
+    }
+
     /// If the host supports `IRunLoop`, then this will post the task to a task queue that will be
     /// run on the host's UI thread. If not, then this will return an `Err` value containing the
     /// task so it can be run elsewhere.
@@ -304,7 +409,10 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
                     .lock()
                     .spawn(parent_handle, self.inner.clone().make_gui_context()),
             );
+            *self.editor_parent.write() = Some(EditorParent(parent_handle));
             *self.inner.plug_view.write() = Some(ObjectPtr::from(self));
+            drop(editor_handle);
+            self.inner.notify_editor_opened(true);
 
             kResultOk
         } else {
@@ -321,6 +429,10 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         if editor_handle.is_some() {
             *self.inner.plug_view.write() = None;
             *editor_handle = None;
+            *self.editor_parent.write() = None;
+            self.keyboard_focus.store(false, Ordering::Relaxed);
+            drop(editor_handle);
+            self.inner.notify_editor_opened(false);
 
             kResultOk
         } else {
@@ -342,7 +454,14 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         _key_code: i16,
         _modifiers: i16,
     ) -> tresult {
-        kNotImplemented
+        // Hosts call this before handling their own shortcuts. While a text field has keyboard
+        // focus the key is reported as handled so the host doesn't also start playback when the
+        // user types a space. The editor's window receives the key from the OS like any other key.
+        if self.keyboard_focus.load(Ordering::Relaxed) {
+            kResultOk
+        } else {
+            kResultFalse
+        }
     }
 
     unsafe fn on_key_up(
@@ -351,7 +470,11 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         _key_code: i16,
         _modifiers: i16,
     ) -> tresult {
-        kNotImplemented
+        if self.keyboard_focus.load(Ordering::Relaxed) {
+            kResultOk
+        } else {
+            kResultFalse
+        }
     }
 
     unsafe fn get_size(&self, size: *mut ViewRect) -> tresult {
@@ -376,8 +499,8 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
     unsafe fn on_size(&self, new_size: *mut ViewRect) -> tresult {
         check_null_ptr!(new_size);
 
-        // TODO: Implement Host->Plugin resizing
-        let (unscaled_width, unscaled_height) = self.editor.lock().size();
+        let editor = self.editor.lock();
+        let (unscaled_width, unscaled_height) = editor.size();
         let scaling_factor = self.scaling_factor.load(Ordering::Relaxed);
         let (editor_width, editor_height) = (
             (unscaled_width as f32 * scaling_factor).round() as i32,
@@ -387,14 +510,35 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
         let width = (*new_size).right - (*new_size).left;
         let height = (*new_size).bottom - (*new_size).top;
         if width == editor_width && height == editor_height {
-            kResultOk
-        } else {
-            kResultFalse
+            return kResultOk;
+        }
+
+        // If the editor is resizable, then the host should have already called
+        // `check_size_constraint()` with this size
+        match editor.size_constraints() {
+            Some(constraints) => {
+                let (new_width, new_height) = constraints.constrain((
+                    (width as f32 / scaling_factor).round().max(0.0) as u32,
+                    (height as f32 / scaling_factor).round().max(0.0) as u32,
+                ));
+                if editor.set_size(new_width, new_height) {
+                    kResultOk
+                } else {
+                    kResultFalse
+                }
+            }
+            None => kResultFalse,
         }
     }
 
-    unsafe fn on_focus(&self, _state: TBool) -> tresult {
-        kNotImplemented
+    unsafe fn on_focus(&self, state: TBool) -> tresult {
+        // Keyboard focus is given back to the host when the editor loses focus, since the text
+        // field can't receive any keys at that point anyways
+        if state == 0 {
+            self.keyboard_focus.store(false, Ordering::Relaxed);
+        }
+
+        kResultOk
     }
 
     unsafe fn set_frame(&self, frame: *mut c_void) -> tresult {
@@ -425,19 +569,36 @@ impl<P: Vst3Plugin> IPlugView for WrapperView<P> {
     }
 
     unsafe fn can_resize(&self) -> tresult {
-        // TODO: Implement Host->Plugin resizing
-        kResultFalse
+        if self.editor.lock().size_constraints().is_some() {
+            kResultOk
+        } else {
+            kResultFalse
+        }
     }
 
     unsafe fn check_size_constraint(&self, rect: *mut ViewRect) -> tresult {
         check_null_ptr!(rect);
 
-        // TODO: Implement Host->Plugin resizing
-        if (*rect).right - (*rect).left > 0 && (*rect).bottom - (*rect).top > 0 {
-            kResultOk
-        } else {
-            kResultFalse
+        let width = (*rect).right - (*rect).left;
+        let height = (*rect).bottom - (*rect).top;
+        if width <= 0 || height <= 0 {
+            return kResultFalse;
         }
+
+        // The rectangle is in physical pixels, while the constraints are in logical pixels
+        if let Some(constraints) = self.editor.lock().size_constraints() {
+            let scaling_factor = self.scaling_factor.load(Ordering::Relaxed);
+            let (constrained_width, constrained_height) = constraints.constrain((
+                (width as f32 / scaling_factor).round() as u32,
+                (height as f32 / scaling_factor).round() as u32,
+            ));
+
+            let rect = &mut *rect;
+            rect.right = rect.left + (constrained_width as f32 * scaling_factor).round() as i32;
+            rect.bottom = rect.top + (constrained_height as f32 * scaling_factor).round() as i32;
+        }
+
+        kResultOk
     }
 }
 
@@ -456,8 +617,9 @@ impl<P: Vst3Plugin> IPlugViewContentScaleSupport for WrapperView<P> {
             return kResultFalse;
         }
 
-        if self.editor.lock().set_scale_factor(factor) {
-            self.scaling_factor.store(factor, Ordering::Relaxed);
+        // The user's UI scale, if set, takes precedence over the host's scaling factor
+        self.host_scaling_factor.store(factor, Ordering::Relaxed);
+        if self.update_editor_scaling_factor() {
             kResultOk
         } else {
             kResultFalse
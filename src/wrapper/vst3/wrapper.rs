@@ -10,43 +10,74 @@ use vst3_sys::base::{kInvalidArgument, kNoInterface, kResultFalse, kResultOk, tr
 use vst3_sys::base::{IBStream, IPluginBase};
 use vst3_sys::utils::SharedVstPtr;
 use vst3_sys::vst::{
-    kNoParamId, kNoParentUnitId, kNoProgramListId, kRootUnitId, Event, EventTypes, IAudioProcessor,
-    IComponent, IEditController, IEventList, IMidiMapping, INoteExpressionController,
-    IParamValueQueue, IParameterChanges, IProcessContextRequirements, IUnitInfo,
-    LegacyMidiCCOutEvent, NoteExpressionTypeInfo, NoteExpressionValueDescription, NoteOffEvent,
-    NoteOnEvent, ParameterFlags, PolyPressureEvent, ProgramListInfo, TChar, UnitInfo,
+    kNoParamId, kNoParentUnitId, kNoProgramListId, kRootUnitId, Event, EventTypes, IAttributeList,
+    IAudioProcessor, IComponent, IEditController, IEventList, IInfoListener, IMidiMapping,
+    INoteExpressionController, IParamValueQueue, IParameterChanges, IProcessContextRequirements,
+    IStreamAttributes, IUnitInfo, LegacyMidiCCOutEvent, NoteExpressionTypeInfo,
+    NoteExpressionValueDescription, NoteOffEvent, NoteOnEvent, ParameterFlags, PolyPressureEvent,
+    ProgramListInfo, TChar, UnitInfo,
 };
 use vst3_sys::VST3;
 use widestring::U16CStr;
 
-use super::inner::{ProcessEvent, WrapperInner};
+#[cfg(feature = "ara")]
+use super::ara::{IPlugInEntryPoint, IPlugInEntryPoint2};
+use super::host_editing::IEditControllerHostEditing;
+use super::inner::{ProcessEvent, Task, WrapperInner};
 use super::note_expressions::{self, NoteExpressionController};
 use super::util::{
-    u16strlcpy, VstPtr, VST3_MIDI_CCS, VST3_MIDI_NUM_PARAMS, VST3_MIDI_PARAMS_START,
+    get_attribute_string, u16strlcpy, MidiCcProxy, VstPtr, VST3_MIDI_NUM_PARAMS,
+    VST3_PROGRAM_CHANGE_PARAM_ID, VST3_PROGRAM_LIST_ID,
 };
-use super::util::{VST3_MIDI_CHANNELS, VST3_MIDI_PARAMS_END};
 use super::view::WrapperView;
 use crate::prelude::{
-    AuxiliaryBuffers, BufferConfig, MidiConfig, NoteEvent, ParamFlags, ProcessMode, ProcessStatus,
-    SysExMessage, Transport, Vst3Plugin,
+    AuxiliaryBuffers, BufferConfig, MidiConfig, NoteEvent, ParamFlags, ParamPtr, ProcessMode,
+    ProcessStatus, StateSaveContext, SysExMessage, TrackInfo, Transport, Vst3Plugin,
 };
 use crate::util::permit_alloc;
 use crate::wrapper::state;
 use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
-use crate::wrapper::util::{clamp_input_event_timing, clamp_output_event_timing, process_wrapper};
+use crate::wrapper::util::{
+    clamp_input_event_timing, clamp_output_event_timing, prepend_gui_note_events, process_wrapper,
+    reported_tail_samples,
+};
 
 // Alias needed for the VST3 attribute macro
 use vst3_sys as vst3_com;
 
-#[VST3(implements(
-    IComponent,
-    IEditController,
-    IAudioProcessor,
-    IMidiMapping,
-    INoteExpressionController,
-    IProcessContextRequirements,
-    IUnitInfo
-))]
+#[cfg_attr(
+    not(feature = "ara"),
+    VST3(implements(
+        IComponent,
+        IEditController,
+        IAudioProcessor,
+        IMidiMapping,
+        INoteExpressionController,
+        IProcessContextRequirements,
+        IUnitInfo,
+        IInfoListener,
+        IEditControllerHostEditing
+    ))
+)]
+// ARA plugins are bound to the host's document controllers through these additional interfaces.
+// Non-ARA plugins also implement them when the `ara` feature is enabled, but they return null
+// pointers.
+#[cfg_attr(
+    feature = "ara",
+    VST3(implements(
+        IComponent,
+        IEditController,
+        IAudioProcessor,
+        IMidiMapping,
+        INoteExpressionController,
+        IProcessContextRequirements,
+        IUnitInfo,
+        IInfoListener,
+        IEditControllerHostEditing,
+        IPlugInEntryPoint,
+        IPlugInEntryPoint2
+    ))
+)]
 pub struct Wrapper<P: Vst3Plugin> {
     inner: Arc<WrapperInner<P>>,
 }
@@ -55,6 +86,31 @@ impl<P: Vst3Plugin> Wrapper<P> {
     pub fn new() -> Box<Self> {
         Self::allocate(WrapperInner::new())
     }
+
+    /// Get the generated MIDI CC parameter for a parameter ID, if the plugin accepts MIDI CCs and
+    /// the ID belongs to one of those parameters.
+    fn midi_cc_proxy(&self, param_id: u32) -> Option<MidiCcProxy> {
+        if P::MIDI_INPUT >= MidiConfig::MidiCCs {
+            MidiCcProxy::from_param_id(param_id)
+        } else {
+            None
+        }
+    }
+
+    /// Whether the parameter ID belongs to the program change parameter. This parameter only
+    /// exists if the plugin's preset library contained any presets.
+    fn is_program_change_param(&self, param_id: u32) -> bool {
+        param_id == VST3_PROGRAM_CHANGE_PARAM_ID && !self.inner.programs.is_empty()
+    }
+
+    /// The number of program change parameters, which is either zero or one.
+    fn num_program_change_params(&self) -> i32 {
+        if self.inner.programs.is_empty() {
+            0
+        } else {
+            1
+        }
+    }
 }
 
 impl<P: Vst3Plugin> Drop for Wrapper<P> {
@@ -308,12 +364,12 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
         type_: vst3_sys::vst::MediaType,
         dir: vst3_sys::vst::BusDirection,
         index: i32,
-        _state: vst3_sys::base::TBool,
+        state: vst3_sys::base::TBool,
     ) -> tresult {
         let current_audio_io_layout = self.inner.current_audio_io_layout.load();
 
-        // We don't support this, but the validator will get very angry with us if we let it know
-        // that
+        // Only the activation state of auxiliary inputs is tracked. We don't support deactivating
+        // the other busses, but the validator will get very angry with us if we let it know that.
         match (type_, dir, index) {
             (t, d, _)
                 if t == vst3_sys::vst::MediaTypes::kAudio as i32
@@ -326,11 +382,25 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                 };
                 let aux_busses = current_audio_io_layout.aux_input_ports.len() as i32;
 
-                if (0..main_busses + aux_busses).contains(&index) {
-                    kResultOk
-                } else {
-                    kInvalidArgument
+                if !(0..main_busses + aux_busses).contains(&index) {
+                    return kInvalidArgument;
+                }
+
+                let aux_input_idx = index - main_busses;
+                if (0..64).contains(&aux_input_idx) {
+                    let mask = 1u64 << aux_input_idx;
+                    if state != 0 {
+                        self.inner
+                            .aux_inputs_active
+                            .fetch_or(mask, Ordering::SeqCst);
+                    } else {
+                        self.inner
+                            .aux_inputs_active
+                            .fetch_and(!mask, Ordering::SeqCst);
+                    }
                 }
+
+                kResultOk
             }
             (t, d, _)
                 if t == vst3_sys::vst::MediaTypes::kAudio as i32
@@ -395,6 +465,25 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
                         audio_io_layout,
                     );
 
+                    // The dry signal is delayed by the latency the plugin reported during
+                    // initialization, so the init context needs to be dropped first to apply
+                    // those changes. Changing the latency afterwards triggers a restart, after
+                    // which the host reactivates the plugin.
+                    drop(plugin);
+                    drop(init_context);
+                    if P::MANAGED_BYPASS {
+                        self.inner.bypass_processor.borrow_mut().initialize(
+                            audio_io_layout
+                                .main_output_channels
+                                .map(NonZeroU32::get)
+                                .unwrap_or(0) as usize,
+                            self.inner.current_latency.load(Ordering::SeqCst),
+                            buffer_config.max_buffer_size,
+                            buffer_config.sample_rate,
+                            self.inner.is_bypassed(),
+                        );
+                    }
+
                     kResultOk
                 } else {
                     kResultFalse
@@ -467,9 +556,23 @@ impl<P: Vst3Plugin> IComponent for Wrapper<P> {
 
         let state = state.upgrade().unwrap();
 
-        let serialized = state::serialize_json::<P>(
+        // Hosts may tell the plugin whether the state is saved as part of a project or as a preset
+        // through the stream's attributes
+        let context = match state
+            .cast::<dyn IStreamAttributes>()
+            .and_then(|stream_attributes| stream_attributes.get_attributes().upgrade())
+            .and_then(|attributes| get_attribute_string(&attributes, b"StateType\0"))
+            .as_deref()
+        {
+            Some("Default") => StateSaveContext::Preset,
+            _ => StateSaveContext::Project,
+        };
+
+        let serialized = state::serialize_json_cached::<P>(
             self.inner.params.clone(),
             state::make_params_iter(&self.inner.param_by_hash, &self.inner.param_id_to_hash),
+            context,
+            &self.inner.state_cache,
         );
         match serialized {
             Ok(serialized) => {
@@ -515,10 +618,11 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
 
     unsafe fn get_parameter_count(&self) -> i32 {
         // We need to add a whole bunch of parameters if the plugin accepts MIDI CCs
+        let num_params = self.inner.param_hashes.len() as i32 + self.num_program_change_params();
         if P::MIDI_INPUT >= MidiConfig::MidiCCs {
-            self.inner.param_hashes.len() as i32 + VST3_MIDI_NUM_PARAMS as i32
+            num_params + VST3_MIDI_NUM_PARAMS as i32
         } else {
-            self.inner.param_hashes.len() as i32
+            num_params
         }
     }
 
@@ -529,32 +633,36 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
     ) -> tresult {
         check_null_ptr!(info);
 
-        if param_index < 0 || param_index > self.get_parameter_count() {
+        if param_index < 0 || param_index >= self.get_parameter_count() {
             return kInvalidArgument;
         }
 
         *info = std::mem::zeroed();
         let info = &mut *info;
 
-        // If the parameter is a generated MIDI CC/channel pressure/pitch bend then it needs to be
-        // handled separately
+        // The program change parameter and the generated MIDI CC/channel pressure/pitch bend
+        // parameters come after the plugin's own parameters and need to be handled separately
         let num_actual_params = self.inner.param_hashes.len() as i32;
-        if P::MIDI_INPUT >= MidiConfig::MidiCCs && param_index >= num_actual_params {
-            let midi_param_relative_idx = (param_index - num_actual_params) as u32;
-            // This goes up to 130 for the 128 CCs followed by channel pressure and pitch bend
-            let midi_cc = midi_param_relative_idx % VST3_MIDI_CCS;
-            let midi_channel = midi_param_relative_idx / VST3_MIDI_CCS;
-            let name = match midi_cc {
-                // kAfterTouch
-                128 => format!("MIDI Ch. {} Channel Pressure", midi_channel + 1),
-                // kPitchBend
-                129 => format!("MIDI Ch. {} Pitch Bend", midi_channel + 1),
-                n => format!("MIDI Ch. {} CC {}", midi_channel + 1, n),
+        let num_program_change_params = self.num_program_change_params();
+        if num_program_change_params > 0 && param_index == num_actual_params {
+            info.id = VST3_PROGRAM_CHANGE_PARAM_ID;
+            u16strlcpy(&mut info.title, "Program");
+            u16strlcpy(&mut info.short_title, "Program");
+            info.step_count = self.inner.programs.len() as i32 - 1;
+            info.unit_id = kRootUnitId;
+            info.flags = ParameterFlags::kIsProgramChange as i32 | ParameterFlags::kIsList as i32;
+        } else if P::MIDI_INPUT >= MidiConfig::MidiCCs && param_index >= num_actual_params {
+            let Some(proxy) = MidiCcProxy::from_index(
+                (param_index - num_actual_params - num_program_change_params) as u32,
+            ) else {
+                return kInvalidArgument;
             };
+            let name = proxy.name();
 
-            info.id = VST3_MIDI_PARAMS_START + midi_param_relative_idx;
+            info.id = proxy.param_id();
             u16strlcpy(&mut info.title, &name);
             u16strlcpy(&mut info.short_title, &name);
+            info.default_normalized_value = proxy.default_normalized_value() as f64;
             info.flags = ParameterFlags::kIsReadOnly as i32 | (1 << 4); // kIsHidden
         } else {
             let param_hash = &self.inner.param_hashes[param_index as usize];
@@ -566,16 +674,19 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             let param_ptr = &self.inner.param_by_hash[param_hash];
             let default_value = param_ptr.default_normalized_value();
             let flags = param_ptr.flags();
-            let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE);
+            let read_only = flags.contains(ParamFlags::READ_ONLY);
+            let automatable = !flags.contains(ParamFlags::NON_AUTOMATABLE) && !read_only;
             let hidden = flags.contains(ParamFlags::HIDDEN);
             let is_bypass = flags.contains(ParamFlags::BYPASS);
+            // Enums are shown as a list of their variants in the host's generic UI
+            let is_list = matches!(param_ptr, ParamPtr::EnumParam(_));
 
             info.id = *param_hash;
-            u16strlcpy(&mut info.title, param_ptr.name());
-            u16strlcpy(&mut info.short_title, param_ptr.name());
-            u16strlcpy(&mut info.units, param_ptr.unit());
-            info.step_count = param_ptr.step_count().unwrap_or(0) as i32;
-            info.default_normalized_value = default_value as f64;
+            u16strlcpy(&mut info.title, &param_ptr.display_name());
+            u16strlcpy(&mut info.short_title, &param_ptr.display_name());
+            u16strlcpy(&mut info.units, &param_ptr.display_unit());
+            info.step_count = param_ptr.host_step_count().unwrap_or(0) as i32;
+            info.default_normalized_value = param_ptr.to_host_normalized(default_value) as f64;
             info.unit_id = *param_unit;
             info.flags = 0;
             if automatable && !hidden {
@@ -584,6 +695,12 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
             if hidden {
                 info.flags |= ParameterFlags::kIsReadOnly as i32 | (1 << 4); // kIsHidden
             }
+            if read_only {
+                info.flags |= ParameterFlags::kIsReadOnly as i32;
+            }
+            if is_list {
+                info.flags |= ParameterFlags::kIsList as i32;
+            }
             if is_bypass {
                 info.flags |= ParameterFlags::kIsBypass as i32;
             }
@@ -602,18 +719,39 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
 
         let dest = &mut *(string as *mut [TChar; 128]);
 
-        // TODO: We don't implement these methods at all for our generated MIDI CC parameters,
-        //       should be fine right? They should be hidden anyways.
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => {
                 u16strlcpy(
                     dest,
-                    &param_ptr.normalized_value_to_string(value_normalized as f32, false),
+                    &param_ptr.normalized_value_to_string(
+                        param_ptr.from_host_normalized(value_normalized as f32),
+                        false,
+                    ),
                 );
 
                 kResultOk
             }
-            _ => kInvalidArgument,
+            None if self.is_program_change_param(id) => {
+                let last_program_idx = self.inner.programs.len() - 1;
+                let program_idx =
+                    (value_normalized.clamp(0.0, 1.0) * last_program_idx as f64).round() as usize;
+                u16strlcpy(dest, &self.inner.programs[program_idx].name);
+
+                kResultOk
+            }
+            // The generated MIDI CC parameters are hidden, but some hosts still display them in
+            // their MIDI learn dialogs
+            None => match self.midi_cc_proxy(id) {
+                Some(proxy) => {
+                    u16strlcpy(
+                        dest,
+                        &proxy.normalized_value_to_string(value_normalized as f32),
+                    );
+
+                    kResultOk
+                }
+                None => kInvalidArgument,
+            },
         }
     }
 
@@ -633,35 +771,60 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
         match self.inner.param_by_hash.get(&id) {
             Some(param_ptr) => {
                 let value = match param_ptr.string_to_normalized_value(&string) {
-                    Some(v) => v as f64,
+                    Some(v) => param_ptr.to_host_normalized(v) as f64,
                     None => return kResultFalse,
                 };
                 *value_normalized = value;
 
                 kResultOk
             }
+            None if self.is_program_change_param(id) => {
+                let last_program_idx = self.inner.programs.len() - 1;
+                match self
+                    .inner
+                    .programs
+                    .iter()
+                    .position(|preset| preset.name == string)
+                {
+                    Some(program_idx) => {
+                        *value_normalized = program_idx as f64 / last_program_idx.max(1) as f64;
+                        kResultOk
+                    }
+                    None => kResultFalse,
+                }
+            }
             _ => kInvalidArgument,
         }
     }
 
     unsafe fn normalized_param_to_plain(&self, id: u32, value_normalized: f64) -> f64 {
         match self.inner.param_by_hash.get(&id) {
-            Some(param_ptr) => param_ptr.preview_plain(value_normalized as f32) as f64,
+            Some(param_ptr) => param_ptr
+                .preview_plain(param_ptr.from_host_normalized(value_normalized as f32))
+                as f64,
             _ => value_normalized,
         }
     }
 
     unsafe fn plain_param_to_normalized(&self, id: u32, plain_value: f64) -> f64 {
         match self.inner.param_by_hash.get(&id) {
-            Some(param_ptr) => param_ptr.preview_normalized(plain_value as f32) as f64,
+            Some(param_ptr) => param_ptr
+                .to_host_normalized(param_ptr.preview_normalized(plain_value as f32))
+                as f64,
             _ => plain_value,
         }
     }
 
     unsafe fn get_param_normalized(&self, id: u32) -> f64 {
         match self.inner.param_by_hash.get(&id) {
-            Some(param_ptr) => param_ptr.modulated_normalized_value() as f64,
-            _ => 0.5,
+            Some(param_ptr) => {
+                param_ptr.to_host_normalized(param_ptr.modulated_normalized_value()) as f64
+            }
+            None if self.is_program_change_param(id) => self.inner.program_normalized() as f64,
+            None => match self.midi_cc_proxy(id) {
+                Some(proxy) => proxy.default_normalized_value() as f64,
+                None => 0.5,
+            },
         }
     }
 
@@ -671,14 +834,20 @@ impl<P: Vst3Plugin> IEditController for Wrapper<P> {
         if self.inner.is_processing.load(Ordering::SeqCst) {
             return kResultOk;
         }
+        if self.is_program_change_param(id) {
+            return self.inner.set_program_normalized(value as f32);
+        }
 
         let sample_rate = self
             .inner
             .current_buffer_config
             .load()
             .map(|c| c.sample_rate);
-        self.inner
-            .set_normalized_value_by_hash(id, value as f32, sample_rate)
+        self.inner.set_normalized_value_by_hash(
+            id,
+            self.inner.from_host_normalized(id, value as f32),
+            sample_rate,
+        )
     }
 
     unsafe fn set_component_handler(
@@ -782,6 +951,9 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 // This layout is used from hereon onwards, at least until this function is called
                 // again
                 self.inner.current_audio_io_layout.store(layout);
+                self.inner
+                    .aux_inputs_active
+                    .store(u64::MAX, Ordering::SeqCst);
 
                 kResultOk
             }
@@ -863,21 +1035,12 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
     unsafe fn setup_processing(&self, setup: *const vst3_sys::vst::ProcessSetup) -> tresult {
         check_null_ptr!(setup);
 
-        // There's no special handling for offline processing at the moment
         let setup = &*setup;
         nih_debug_assert_eq!(
             setup.symbolic_sample_size,
             vst3_sys::vst::SymbolicSampleSizes::kSample32 as i32
         );
 
-        // This is needed when activating the plugin and when restoring state
-        self.inner.current_buffer_config.store(Some(BufferConfig {
-            sample_rate: setup.sample_rate as f32,
-            min_buffer_size: None,
-            max_buffer_size: setup.max_samples_per_block as u32,
-            process_mode: self.inner.current_process_mode.load(),
-        }));
-
         let mode = match setup.process_mode {
             n if n == ProcessModes::kRealtime as i32 => ProcessMode::Realtime,
             n if n == ProcessModes::kPrefetch as i32 => ProcessMode::Buffered,
@@ -887,7 +1050,18 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 ProcessMode::Realtime
             }
         };
-        self.inner.current_process_mode.store(mode);
+        let old_mode = self.inner.current_process_mode.swap(mode);
+        if old_mode != mode {
+            self.inner.plugin.lock().process_mode_changed(mode);
+        }
+
+        // This is needed when activating the plugin and when restoring state
+        self.inner.current_buffer_config.store(Some(BufferConfig {
+            sample_rate: setup.sample_rate as f32,
+            min_buffer_size: None,
+            max_buffer_size: setup.max_samples_per_block as u32,
+            process_mode: mode,
+        }));
 
         // Initializing the plugin happens in `IAudioProcessor::set_active()` because the host may
         // still change the channel layouts at this point
@@ -901,6 +1075,13 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
         // Always reset the processing status when the plugin gets activated or deactivated
         self.inner.last_process_status.store(ProcessStatus::Normal);
         self.inner.is_processing.store(state, Ordering::SeqCst);
+        self.inner.dsp_load.reset();
+        if state && P::MANAGED_BYPASS {
+            self.inner
+                .bypass_processor
+                .borrow_mut()
+                .reset(self.inner.is_bypassed());
+        }
 
         // This function is also used to reset buffers on the plugin, so we should do the same
         // thing. We don't call `reset()` in `setup_processing()` for that same reason.
@@ -921,6 +1102,7 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                 }
             };
 
+            self.inner.event_scheduler.borrow_mut().reset();
             process_wrapper(|| plugin.reset());
         }
 
@@ -1015,46 +1197,26 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
 
                                 // MIDI CC messages, channel pressure, and pitch bend are also sent
                                 // as parameter changes
-                                if P::MIDI_INPUT >= MidiConfig::MidiCCs
-                                    && (VST3_MIDI_PARAMS_START..VST3_MIDI_PARAMS_END)
-                                        .contains(&param_hash)
-                                {
-                                    let midi_param_relative_idx =
-                                        param_hash - VST3_MIDI_PARAMS_START;
-                                    // This goes up to 130 for the 128 CCs followed by channel pressure and pitch bend
-                                    let midi_cc = (midi_param_relative_idx % VST3_MIDI_CCS) as u8;
-                                    let midi_channel =
-                                        (midi_param_relative_idx / VST3_MIDI_CCS) as u8;
-                                    process_events.push(ProcessEvent::NoteEvent(match midi_cc {
-                                        // kAfterTouch
-                                        128 => NoteEvent::MidiChannelPressure {
-                                            timing,
-                                            channel: midi_channel,
-                                            pressure: value,
-                                        },
-                                        // kPitchBend
-                                        129 => NoteEvent::MidiPitchBend {
-                                            timing,
-                                            channel: midi_channel,
-                                            value,
-                                        },
-                                        n => NoteEvent::MidiCC {
-                                            timing,
-                                            channel: midi_channel,
-                                            cc: n,
-                                            value,
-                                        },
-                                    }));
+                                if let Some(proxy) = self.midi_cc_proxy(param_hash) {
+                                    process_events.push(ProcessEvent::NoteEvent(
+                                        proxy.to_note_event(timing, value),
+                                    ));
+                                } else if self.is_program_change_param(param_hash) {
+                                    // The preset is loaded on the GUI thread, so there's no point
+                                    // in handling this sample accurately
+                                    self.inner.set_program_normalized(value);
                                 } else if P::SAMPLE_ACCURATE_AUTOMATION {
                                     process_events.push(ProcessEvent::ParameterChange {
                                         timing,
                                         hash: param_hash,
-                                        normalized_value: value,
+                                        normalized_value: self
+                                            .inner
+                                            .from_host_normalized(param_hash, value),
                                     });
                                 } else {
                                     self.inner.set_normalized_value_by_hash(
                                         param_hash,
-                                        value,
+                                        self.inner.from_host_normalized(param_hash, value),
                                         Some(sample_rate),
                                     );
                                 }
@@ -1217,6 +1379,11 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                             }
                         }
                     }
+
+                    // Notes played from the editor are handled at the start of the buffer
+                    if block_start == 0 {
+                        prepend_gui_note_events(&self.inner.gui_note_events, &mut input_events);
+                    }
                 }
 
                 let result = if is_param_flush {
@@ -1265,8 +1432,14 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                                     .iter_mut()
                                     .enumerate()
                                 {
+                                    // Deactivated busses are left empty, so the buffer manager
+                                    // will pass silent buffers to the plugin instead
+                                    if !self.inner.is_aux_input_active(aux_input_no) {
+                                        continue;
+                                    }
+
                                     let aux_input_idx = aux_input_no + aux_input_start_idx;
-                                    if aux_input_idx > data.num_outputs as usize {
+                                    if aux_input_idx >= data.num_inputs as usize {
                                         break;
                                     }
 
@@ -1397,8 +1570,35 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                             inputs: buffers.aux_inputs,
                             outputs: buffers.aux_outputs,
                         };
+                        if let Some(modulation_matrix) = &self.inner.modulation_matrix {
+                            modulation_matrix.process_block(
+                                buffers.main_buffer,
+                                self.inner.input_events.borrow().iter(),
+                                sample_rate,
+                                state::make_params_getter(
+                                    &self.inner.param_by_hash,
+                                    &self.inner.param_id_to_hash,
+                                ),
+                            );
+                        }
                         let mut context = self.inner.make_process_context(transport);
-                        let result = plugin.process(buffers.main_buffer, &mut aux, &mut context);
+                        let mut bypass_processor = self.inner.bypass_processor.borrow_mut();
+                        if P::MANAGED_BYPASS {
+                            bypass_processor.write_dry(buffers.main_buffer);
+                        }
+                        let result = self.inner.dsp_load.measure(
+                            buffers.main_buffer.samples(),
+                            sample_rate,
+                            self.inner.current_process_mode.load(),
+                            || plugin.process(buffers.main_buffer, &mut aux, &mut context),
+                        );
+                        if P::MANAGED_BYPASS {
+                            bypass_processor.process(
+                                buffers.main_buffer,
+                                self.inner.current_latency.load(Ordering::Relaxed),
+                                self.inner.is_bypassed(),
+                            );
+                        }
                         self.inner.last_process_status.store(result);
                         result
                     } else {
@@ -1415,6 +1615,53 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
                     }
                 };
 
+                // Read-only parameters changed by the plugin are sent to the host at a limited rate
+                if let Some(param_changes) = data.output_param_changes.upgrade() {
+                    self.inner.output_params.borrow_mut().update(
+                        block_end - block_start,
+                        sample_rate,
+                        |param_hash, param_ptr, normalized| {
+                            let mut queue_index = 0;
+                            if let Some(param_queue) = param_changes
+                                .add_parameter_data(&param_hash, &mut queue_index)
+                                .upgrade()
+                            {
+                                let mut point_index = 0;
+                                param_queue.add_point(
+                                    block_start as i32,
+                                    param_ptr.to_host_normalized(normalized) as f64,
+                                    &mut point_index,
+                                );
+                            }
+                        },
+                    );
+                }
+
+                // Large persistent fields are periodically serialized on the background thread so
+                // saving the state doesn't have to. If the task queue is full, then this simply
+                // happens during the next refresh.
+                if self
+                    .inner
+                    .state_cache
+                    .should_refresh(block_end - block_start, sample_rate)
+                {
+                    let _ = self.inner.schedule_background(Task::RefreshStateCache);
+                }
+
+                // Events the plugin scheduled for this block are merged into the block's other
+                // output events, keeping them sorted by timing
+                {
+                    let mut output_events = self.inner.output_events.borrow_mut();
+                    self.inner.event_scheduler.borrow_mut().advance(
+                        block_end - block_start,
+                        |event| {
+                            let index =
+                                output_events.partition_point(|e| e.timing() <= event.timing());
+                            output_events.insert(index, event);
+                        },
+                    );
+                }
+
                 // Send any events output by the plugin during the process cycle
                 if let Some(events) = data.output_events.upgrade() {
                     let mut output_events = self.inner.output_events.borrow_mut();
@@ -1672,11 +1919,11 @@ impl<P: Vst3Plugin> IAudioProcessor for Wrapper<P> {
 
     unsafe fn get_tail_samples(&self) -> u32 {
         // https://github.com/steinbergmedia/vst3_pluginterfaces/blob/2ad397ade5b51007860bedb3b01b8afd2c5f6fba/vst/ivstaudioprocessor.h#L145-L159
-        match self.inner.last_process_status.load() {
-            ProcessStatus::Tail(samples) => samples,
-            ProcessStatus::KeepAlive => u32::MAX, // kInfiniteTail
-            _ => 0,                               // kNoTail
-        }
+        // `u32::MAX` is `kInfiniteTail`, and 0 is `kNoTail`
+        reported_tail_samples(
+            self.inner.last_process_status.load(),
+            self.inner.current_tail.load(Ordering::SeqCst),
+        )
     }
 }
 
@@ -1688,20 +1935,18 @@ impl<P: Vst3Plugin> IMidiMapping for Wrapper<P> {
         midi_cc_number: vst3_com::vst::CtrlNumber,
         param_id: *mut vst3_com::vst::ParamID,
     ) -> tresult {
-        if P::MIDI_INPUT < MidiConfig::MidiCCs
-            || bus_index != 0
-            || !(0..VST3_MIDI_CHANNELS as i16).contains(&channel)
-            || !(0..VST3_MIDI_CCS as i16).contains(&midi_cc_number)
-        {
+        if P::MIDI_INPUT < MidiConfig::MidiCCs || bus_index != 0 {
             return kResultFalse;
         }
+        let Some(proxy) = MidiCcProxy::new(channel, midi_cc_number) else {
+            return kResultFalse;
+        };
 
         check_null_ptr!(param_id);
 
         // We reserve a contiguous parameter range right at the end of the allowed parameter indices
         // for these MIDI CC parameters
-        *param_id =
-            VST3_MIDI_PARAMS_START + midi_cc_number as u32 + (channel as u32 * VST3_MIDI_CCS);
+        *param_id = proxy.param_id();
 
         kResultOk
     }
@@ -1794,13 +2039,31 @@ impl<P: Vst3Plugin> IProcessContextRequirements for Wrapper<P> {
 
 impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
     unsafe fn get_unit_count(&self) -> i32 {
-        self.inner.param_units.len() as i32
+        // The root unit only needs to be listed explicitly if it has a program list
+        self.inner.param_units.len() as i32 + self.num_program_change_params()
     }
 
     unsafe fn get_unit_info(&self, unit_index: i32, info: *mut UnitInfo) -> tresult {
         check_null_ptr!(info);
 
-        match self.inner.param_units.info(unit_index as usize) {
+        let num_program_change_params = self.num_program_change_params();
+        if num_program_change_params > 0 && unit_index == 0 {
+            *info = mem::zeroed();
+
+            let info = &mut *info;
+            info.id = kRootUnitId;
+            info.parent_unit_id = kNoParentUnitId;
+            u16strlcpy(&mut info.name, "Root");
+            info.program_list_id = VST3_PROGRAM_LIST_ID;
+
+            return kResultOk;
+        }
+
+        match self
+            .inner
+            .param_units
+            .info((unit_index - num_program_change_params) as usize)
+        {
             Some((unit_id, unit_info)) => {
                 *info = mem::zeroed();
 
@@ -1817,25 +2080,42 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
     }
 
     unsafe fn get_program_list_count(&self) -> i32 {
-        // TODO: Do we want program lists? Probably not, CLAP doesn't even support them.
-        0
+        // The presets from the plugin's preset library are exposed as a single program list
+        self.num_program_change_params()
     }
 
-    unsafe fn get_program_list_info(
-        &self,
-        _list_index: i32,
-        _info: *mut ProgramListInfo,
-    ) -> tresult {
-        kInvalidArgument
+    unsafe fn get_program_list_info(&self, list_index: i32, info: *mut ProgramListInfo) -> tresult {
+        check_null_ptr!(info);
+
+        if list_index != 0 || self.inner.programs.is_empty() {
+            return kInvalidArgument;
+        }
+
+        *info = mem::zeroed();
+
+        let info = &mut *info;
+        info.id = VST3_PROGRAM_LIST_ID;
+        u16strlcpy(&mut info.name, "Presets");
+        info.program_count = self.inner.programs.len() as i32;
+
+        kResultOk
     }
 
-    unsafe fn get_program_name(
-        &self,
-        _list_id: i32,
-        _program_index: i32,
-        _name: *mut u16,
-    ) -> tresult {
-        kInvalidArgument
+    unsafe fn get_program_name(&self, list_id: i32, program_index: i32, name: *mut u16) -> tresult {
+        check_null_ptr!(name);
+
+        if list_id != VST3_PROGRAM_LIST_ID || program_index < 0 {
+            return kInvalidArgument;
+        }
+
+        match self.inner.programs.get(program_index as usize) {
+            Some(preset) => {
+                u16strlcpy(&mut *(name as *mut [TChar; 128]), &preset.name);
+
+                kResultOk
+            }
+            None => kInvalidArgument,
+        }
     }
 
     unsafe fn get_program_info(
@@ -1893,3 +2173,86 @@ impl<P: Vst3Plugin> IUnitInfo for Wrapper<P> {
         kInvalidArgument
     }
 }
+
+impl<P: Vst3Plugin> IEditControllerHostEditing for Wrapper<P> {
+    unsafe fn begin_edit_from_host(&self, param_id: u32) -> tresult {
+        if !self.inner.param_by_hash.contains_key(&param_id) {
+            return kInvalidArgument;
+        }
+
+        self.inner.host_edited_params.lock().insert(param_id);
+
+        kResultOk
+    }
+
+    unsafe fn end_edit_from_host(&self, param_id: u32) -> tresult {
+        if self.inner.host_edited_params.lock().remove(&param_id) {
+            kResultOk
+        } else {
+            kResultFalse
+        }
+    }
+}
+
+impl<P: Vst3Plugin> IInfoListener for Wrapper<P> {
+    unsafe fn set_channel_context_infos(&self, list: SharedVstPtr<dyn IAttributeList>) -> tresult {
+        check_null_ptr!(list);
+
+        let list = list.upgrade().unwrap();
+
+        let name = get_attribute_string(&list, b"channel name\0");
+
+        // The color is stored as an ARGB value
+        let mut color = 0i64;
+        let color =
+            if list.get_int(b"channel color\0".as_ptr() as *const _, &mut color) == kResultOk {
+                let [a, r, g, b] = (color as u32).to_be_bytes();
+                Some([r, g, b, a])
+            } else {
+                None
+            };
+
+        self.inner.set_track_info(TrackInfo {
+            name,
+            color,
+            ..TrackInfo::default()
+        });
+
+        kResultOk
+    }
+}
+
+#[cfg(feature = "ara")]
+impl<P: Vst3Plugin> IPlugInEntryPoint for Wrapper<P> {
+    unsafe fn get_factory(&self) -> *const c_void {
+        crate::wrapper::ara::ara_factory_ptr::<P>()
+    }
+
+    unsafe fn bind_to_document_controller(
+        &self,
+        _document_controller_ref: *mut c_void,
+    ) -> *const c_void {
+        // This ARA 1 function is superseded by `bind_to_document_controller_with_roles()`, and the
+        // ARA factory requires ARA 2
+        nih_debug_assert_failure!("The host tried to bind to a document controller using ARA 1");
+        std::ptr::null()
+    }
+}
+
+#[cfg(feature = "ara")]
+impl<P: Vst3Plugin> IPlugInEntryPoint2 for Wrapper<P> {
+    unsafe fn bind_to_document_controller_with_roles(
+        &self,
+        document_controller_ref: *mut c_void,
+        known_roles: u32,
+        assigned_roles: u32,
+    ) -> *const c_void {
+        crate::wrapper::ara::bind_to_document_controller(
+            &self.inner.plugin,
+            &self.inner.ara_plugin_extension,
+            document_controller_ref,
+            known_roles,
+            assigned_roles,
+        )
+    }
+}
@@ -0,0 +1,268 @@
+//! A WebAssembly Web Audio Module (WAM 2) wrapper. Plugins are exported with
+//! [`nih_export_wam!()`][crate::nih_export_wam!()] and compiled for the `wasm32-unknown-unknown`
+//! target, and `cargo xtask wam` then generates the JavaScript glue code that turns the compiled
+//! module into a Web Audio Module.
+//!
+//! The glue code creates two instances of the plugin from the same WebAssembly module. One lives in
+//! the `AudioWorkletGlobalScope` and processes audio, and the other lives on the browser's main
+//! thread and hosts the editor in an HTML canvas. Parameter changes and state changes made in the
+//! editor are forwarded to the audio worklet's instance by the glue code.
+//!
+//! The wrapper has a couple limitations compared to the other wrappers:
+//!
+//! - Only the first audio IO layout's main input and output channels are exposed. Auxiliary
+//!   channels are not supported.
+//! - Parameters are exposed with their normalized values. Automation is applied once per process
+//!   call.
+//! - WebAssembly instances can't spawn threads, so background tasks and GUI tasks run on the
+//!   instance's own thread at the end of every process call, or during the glue code's idle
+//!   callback for the editor's instance.
+//! - The process context's DSP load is always zero, and SysEx messages are not supported.
+
+mod util;
+
+mod context;
+mod descriptor;
+mod wrapper;
+
+/// Re-export for the macro
+pub use self::descriptor::descriptor_json;
+pub use self::util::{alloc, free};
+pub use self::wrapper::Wrapper;
+
+/// Export a Web Audio Module from this library using the provided plugin type. The plugin type
+/// needs to implement [`WamPlugin`][crate::prelude::WamPlugin] in addition to
+/// [`Plugin`][crate::prelude::Plugin]. Unlike the other export macros, this only accepts a single
+/// plugin since every Web Audio Module is its own WebAssembly module.
+#[macro_export]
+macro_rules! nih_export_wam {
+    ($plugin_ty:ty) => {
+        // See the comment in `nih_export_clap!()` for why the implementation is generated inside
+        // of this macro
+        #[doc(hidden)]
+        mod wam {
+            use ::std::ffi::CString;
+            use ::std::os::raw::c_char;
+            use ::std::sync::OnceLock;
+            use $crate::wrapper::wam::Wrapper;
+
+            // Because the `$plugin_ty` is likely defined in the enclosing scope. This works even if
+            // the type is not public because this is a child module.
+            use super::*;
+
+            pub type PluginWrapper = Wrapper<$plugin_ty>;
+
+            // The descriptor instantiates the plugin, so it is only generated once
+            static DESCRIPTOR: OnceLock<CString> = OnceLock::new();
+
+            pub fn descriptor() -> *const c_char {
+                DESCRIPTOR
+                    .get_or_init(|| {
+                        $crate::wrapper::setup_logger();
+                        $crate::wrapper::wam::descriptor_json::<$plugin_ty>()
+                    })
+                    .as_ptr()
+            }
+        }
+
+        /// The module's descriptor as a null terminated JSON string.
+        #[no_mangle]
+        pub extern "C" fn nih_plug_wam_descriptor() -> *const ::std::os::raw::c_char {
+            self::wam::descriptor()
+        }
+
+        /// Allocate `size` bytes in the module's memory. Used by the glue code to pass state to the
+        /// plugin.
+        #[no_mangle]
+        pub extern "C" fn nih_plug_wam_alloc(size: u32) -> *mut u8 {
+            $crate::wrapper::wam::alloc(size)
+        }
+
+        /// Free memory allocated by `nih_plug_wam_alloc()`.
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_free(ptr: *mut u8, size: u32) {
+            $crate::wrapper::wam::free(ptr, size)
+        }
+
+        #[no_mangle]
+        pub extern "C" fn nih_plug_wam_create(
+            sample_rate: f32,
+            max_block_size: u32,
+        ) -> *mut ::std::os::raw::c_void {
+            self::wam::PluginWrapper::create(sample_rate, max_block_size)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_destroy(instance: *mut ::std::os::raw::c_void) {
+            self::wam::PluginWrapper::destroy(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_input_buffer(
+            instance: *mut ::std::os::raw::c_void,
+            channel: u32,
+        ) -> *mut f32 {
+            self::wam::PluginWrapper::input_buffer(instance, channel)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_output_buffer(
+            instance: *mut ::std::os::raw::c_void,
+            channel: u32,
+        ) -> *mut f32 {
+            self::wam::PluginWrapper::output_buffer(instance, channel)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_set_transport(
+            instance: *mut ::std::os::raw::c_void,
+            playing: bool,
+            tempo: f64,
+            time_sig_numerator: i32,
+            time_sig_denominator: i32,
+            bar_number: i32,
+            bar_start_time: f64,
+        ) {
+            self::wam::PluginWrapper::set_transport(
+                instance,
+                playing,
+                tempo,
+                time_sig_numerator,
+                time_sig_denominator,
+                bar_number,
+                bar_start_time,
+            )
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_midi_event(
+            instance: *mut ::std::os::raw::c_void,
+            status: u8,
+            data1: u8,
+            data2: u8,
+        ) {
+            self::wam::PluginWrapper::midi_event(instance, status, data1, data2)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_process(
+            instance: *mut ::std::os::raw::c_void,
+            num_samples: u32,
+            current_time: f64,
+        ) {
+            self::wam::PluginWrapper::process(instance, num_samples, current_time)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_output_events(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> *const u32 {
+            self::wam::PluginWrapper::output_events(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_output_event_count(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> u32 {
+            self::wam::PluginWrapper::output_event_count(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_set_param(
+            instance: *mut ::std::os::raw::c_void,
+            param_idx: u32,
+            normalized: f32,
+        ) {
+            self::wam::PluginWrapper::set_param(instance, param_idx, normalized)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_get_param(
+            instance: *mut ::std::os::raw::c_void,
+            param_idx: u32,
+        ) -> f32 {
+            self::wam::PluginWrapper::get_param(instance, param_idx)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_poll_param_change(
+            instance: *mut ::std::os::raw::c_void,
+            value: *mut f32,
+        ) -> i32 {
+            self::wam::PluginWrapper::poll_param_change(instance, value)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_save_state(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> u32 {
+            self::wam::PluginWrapper::save_state(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_state_ptr(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> *const u8 {
+            self::wam::PluginWrapper::state_ptr(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_load_state(
+            instance: *mut ::std::os::raw::c_void,
+            data: *const u8,
+            len: u32,
+        ) -> bool {
+            self::wam::PluginWrapper::load_state(instance, data, len)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_poll_state_change(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> bool {
+            self::wam::PluginWrapper::poll_state_change(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_has_editor(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> bool {
+            self::wam::PluginWrapper::has_editor(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_editor_open(
+            instance: *mut ::std::os::raw::c_void,
+            canvas_handle: u32,
+            scaling_factor: f32,
+        ) -> bool {
+            self::wam::PluginWrapper::editor_open(instance, canvas_handle, scaling_factor)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_editor_close(instance: *mut ::std::os::raw::c_void) {
+            self::wam::PluginWrapper::editor_close(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_editor_size(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> u32 {
+            self::wam::PluginWrapper::editor_size(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_poll_editor_resize(
+            instance: *mut ::std::os::raw::c_void,
+        ) -> bool {
+            self::wam::PluginWrapper::poll_editor_resize(instance)
+        }
+
+        #[no_mangle]
+        pub unsafe extern "C" fn nih_plug_wam_idle(
+            instance: *mut ::std::os::raw::c_void,
+            now_ms: f64,
+        ) {
+            self::wam::PluginWrapper::idle(instance, now_ms)
+        }
+    };
+}
@@ -0,0 +1,286 @@
+use atomic_refcell::AtomicRefMut;
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::wrapper::{Task, Wrapper};
+use crate::context::gui::{GuiMessage, ParamChangeReceiver, StateSlot, TimerHandle};
+use crate::prelude::{
+    GuiContext, InitContext, MidiConfig, NoteEvent, ParamIndication, ParamPtr, PluginApi,
+    PluginNoteEvent, ProcessContext, ProcessMode, TrackInfo, Transport, WamPlugin,
+};
+use crate::wrapper::util::equal_temperament_frequency;
+
+/// An [`InitContext`] implementation for the wrapper.
+pub(crate) struct WrapperInitContext<'a, P: WamPlugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+}
+
+/// A [`ProcessContext`] implementation for the wrapper. This is a separate object so it can hold on
+/// to lock guards for event queues. Otherwise reading these events would require constant
+/// unnecessary atomic operations to lock the uncontested locks.
+pub(crate) struct WrapperProcessContext<'a, P: WamPlugin> {
+    pub(super) wrapper: &'a Wrapper<P>,
+    pub(super) input_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) output_events_guard: AtomicRefMut<'a, VecDeque<PluginNoteEvent<P>>>,
+    pub(super) transport: Transport,
+    /// The number of auxiliary inputs in the current audio IO layout. Used for
+    /// [`ProcessContext::bus_active()`].
+    pub(super) aux_input_count: usize,
+}
+
+/// A [`GuiContext`] implementation for the wrapper. This is passed to the plugin in
+/// [`Editor::spawn()`][crate::prelude::Editor::spawn()] so it can interact with the rest of the plugin and
+/// with the host for things like setting parameters.
+pub(crate) struct WrapperGuiContext<P: WamPlugin> {
+    pub(super) wrapper: Arc<Wrapper<P>>,
+    #[cfg(debug_assertions)]
+    pub(super) param_gesture_checker:
+        atomic_refcell::AtomicRefCell<crate::wrapper::util::context_checks::ParamGestureChecker>,
+}
+
+impl<P: WamPlugin> InitContext<P> for WrapperInitContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Wam
+    }
+
+    fn execute(&self, task: P::BackgroundTask) {
+        (self.wrapper.task_executor.lock())(task);
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+}
+
+impl<P: WamPlugin> ProcessContext<P> for WrapperProcessContext<'_, P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Wam
+    }
+
+    fn execute_background(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_background(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn execute_gui(&self, task: P::BackgroundTask) {
+        let task_posted = self.wrapper.schedule_gui(Task::PluginTask(task));
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+    }
+
+    fn cancel_background_tasks(&self, tag: u32) {
+        self.wrapper.cancel_background_tasks(tag);
+    }
+
+    #[inline]
+    fn transport(&self) -> &Transport {
+        &self.transport
+    }
+
+    fn process_mode(&self) -> ProcessMode {
+        // Offline rendering uses an `OfflineAudioContext`, which the plugin cannot detect
+        ProcessMode::Realtime
+    }
+
+    fn track_info(&self) -> Option<Arc<TrackInfo>> {
+        // WAM doesn't have a way to query this
+        None
+    }
+
+    fn bypassed(&self) -> bool {
+        self.wrapper.is_bypassed()
+    }
+
+    fn bus_active(&self, index: usize) -> bool {
+        index < self.aux_input_count
+    }
+
+    fn next_event(&mut self) -> Option<PluginNoteEvent<P>> {
+        self.input_events_guard.pop_front()
+    }
+
+    fn send_event(&mut self, event: PluginNoteEvent<P>) {
+        // Output events are only sent to the host when the plugin outputs MIDI
+        if P::MIDI_OUTPUT != MidiConfig::None {
+            self.output_events_guard.push_back(event);
+        }
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn note_frequency(&self, note: u8, _channel: u8, _tuning_id: Option<u32>) -> f32 {
+        // This is only supported by CLAP
+        equal_temperament_frequency(note)
+    }
+
+    fn set_tail_seconds(&self, _seconds: f32) {
+        // WAMs cannot report their tail length to the host
+    }
+
+    fn set_current_voice_capacity(&self, _capacity: u32) {
+        // This is only supported by CLAP
+    }
+
+    fn drain_gui_messages<T: Send + 'static>(&mut self, handler: impl FnMut(T)) {
+        self.wrapper.gui_messages.drain(handler)
+    }
+}
+
+impl<P: WamPlugin> GuiContext for WrapperGuiContext<P> {
+    fn plugin_api(&self) -> PluginApi {
+        PluginApi::Wam
+    }
+
+    fn request_resize(&self) -> bool {
+        self.wrapper.request_resize()
+    }
+
+    fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        self.wrapper.set_ui_scale(scale)
+    }
+
+    fn send_note_event(&self, event: NoteEvent<()>) -> bool {
+        self.wrapper.queue_gui_note_event(event)
+    }
+
+    fn raw_send_to_audio(&self, message: GuiMessage) -> bool {
+        self.wrapper.gui_messages.send(message)
+    }
+
+    fn subscribe_param_changes(&self) -> ParamChangeReceiver {
+        self.wrapper.param_changes.subscribe()
+    }
+
+    fn set_latency_samples(&self, samples: u32) {
+        self.wrapper.set_latency_samples(samples)
+    }
+
+    fn dsp_load(&self) -> f32 {
+        // The editor's instance never processes audio, and the audio worklet does not have access
+        // to a monotonic clock to measure the processing time with
+        0.0
+    }
+
+    fn schedule_once(&self, delay: Duration, callback: Box<dyn FnOnce() + Send>) -> TimerHandle {
+        self.wrapper.schedule_timer_once(delay, callback)
+    }
+
+    fn schedule_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        self.wrapper.schedule_timer_repeating(interval, callback)
+    }
+
+    // WAM does not have a way to report parameter gestures to the host, so only the parameter
+    // changes themselves are sent to the audio worklet's instance
+    unsafe fn raw_begin_set_parameter(&self, param: ParamPtr) {
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .begin_set_parameter(param_id),
+            None => nih_debug_assert_failure!(
+                "raw_begin_set_parameter() called with an unknown ParamPtr"
+            ),
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = param;
+    }
+
+    unsafe fn raw_set_parameter_normalized(&self, param: ParamPtr, normalized: f32) {
+        // The editor runs in its own instance on the browser's main thread, so the parameter is
+        // updated here and the change is then forwarded to the audio worklet's instance by the
+        // glue code. Hidden parameters are not exposed to the host.
+        self.wrapper.set_normalized_value(param, normalized);
+        let success = self.wrapper.queue_param_change(param, normalized);
+        nih_debug_assert!(
+            success,
+            "The parameter is hidden or the parameter change queue is full, parameter change \
+             will not be sent to the audio worklet"
+        );
+
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .set_parameter(param_id),
+            None => {
+                nih_debug_assert_failure!("raw_set_parameter() called with an unknown ParamPtr")
+            }
+        }
+    }
+
+    unsafe fn raw_end_set_parameter(&self, param: ParamPtr) {
+        #[cfg(debug_assertions)]
+        match self.wrapper.param_id_from_ptr(param) {
+            Some(param_id) => self
+                .param_gesture_checker
+                .borrow_mut()
+                .end_set_parameter(param_id),
+            None => {
+                nih_debug_assert_failure!("raw_end_set_parameter() called with an unknown ParamPtr")
+            }
+        }
+        #[cfg(not(debug_assertions))]
+        let _ = param;
+    }
+
+    fn get_state(&self) -> crate::wrapper::state::PluginState {
+        self.wrapper.get_state_object()
+    }
+
+    fn set_state(&self, state: crate::wrapper::state::PluginState) {
+        self.wrapper.set_state_object_from_gui(state)
+    }
+
+    fn store_state_slot(&self, slot: StateSlot) {
+        self.wrapper.state_slots.store(self, slot)
+    }
+
+    fn swap_ab(&self) -> StateSlot {
+        self.wrapper.state_slots.swap(self)
+    }
+
+    fn copy_a_to_b(&self) {
+        self.wrapper.state_slots.copy_a_to_b(self)
+    }
+
+    fn active_state_slot(&self) -> StateSlot {
+        self.wrapper.state_slots.active()
+    }
+
+    fn show_param_context_menu(&self, _param: ParamPtr, _position: (f32, f32)) -> bool {
+        // WAM does not have an API for host context menus
+        false
+    }
+
+    fn request_keyboard_focus(&self) -> bool {
+        // The editor's DOM element receives keyboard input directly from the browser
+        false
+    }
+
+    fn release_keyboard_focus(&self) {}
+
+    fn set_clipboard_text(&self, _text: &str) -> bool {
+        // The browser's clipboard API is asynchronous and requires the user's permission
+        false
+    }
+
+    fn clipboard_text(&self) -> Option<String> {
+        None
+    }
+
+    fn param_indication(&self, _param: ParamPtr) -> Option<ParamIndication> {
+        None
+    }
+}
@@ -0,0 +1,145 @@
+//! The module's descriptor. The JavaScript glue code reads this once when loading the module, and
+//! it uses the information to build the WAM descriptor and the parameter infos for the plugin.
+
+use serde::Serialize;
+use std::ffi::CString;
+use std::num::NonZeroU32;
+
+use crate::prelude::{MidiConfig, ParamFlags, ParamPtr, Params, WamPlugin};
+
+/// The WAM API version implemented by the glue code.
+const WAM_API_VERSION: &str = "2.0.0";
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ModuleDescriptor {
+    descriptor: WamDescriptor,
+    /// The plugin's parameters, in the order used for the parameter indices in the wrapper's
+    /// functions.
+    parameters: Vec<ParameterInfo>,
+    audio_input_channels: u32,
+    audio_output_channels: u32,
+}
+
+/// The `WamDescriptor` from the WAM API.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WamDescriptor {
+    identifier: &'static str,
+    name: &'static str,
+    vendor: &'static str,
+    version: &'static str,
+    api_version: &'static str,
+    thumbnail: &'static str,
+    keywords: &'static [&'static str],
+    is_instrument: bool,
+    website: &'static str,
+    description: &'static str,
+    has_audio_input: bool,
+    has_audio_output: bool,
+    has_automation_input: bool,
+    has_automation_output: bool,
+    has_midi_input: bool,
+    has_midi_output: bool,
+    has_mpe_input: bool,
+    has_mpe_output: bool,
+    has_osc_input: bool,
+    has_osc_output: bool,
+    has_sysex_input: bool,
+    has_sysex_output: bool,
+}
+
+/// The configuration for a `WamParameterInfo` from the WAM SDK. Parameters are exposed using their
+/// normalized values so the host's automation matches the parameters' ranges.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ParameterInfo {
+    id: String,
+    label: String,
+    #[serde(rename = "type")]
+    type_: &'static str,
+    default_value: f32,
+    min_value: f32,
+    max_value: f32,
+    discrete_step: f32,
+    units: String,
+}
+
+/// The parameters that are exposed to the host, in order. Hidden parameters are not exposed, but
+/// they are still included in the plugin's state.
+pub(super) fn exposed_params(params: &dyn Params) -> Vec<(String, ParamPtr)> {
+    params
+        .param_map()
+        .into_iter()
+        .filter(|(_, ptr, _)| !unsafe { ptr.flags() }.contains(ParamFlags::HIDDEN))
+        .map(|(id, ptr, _)| (id, ptr))
+        .collect()
+}
+
+/// Generate the module descriptor for a plugin as a JSON string. This instantiates the plugin to
+/// query its parameters.
+pub fn descriptor_json<P: WamPlugin>() -> CString {
+    let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+    let audio_input_channels = audio_io_layout
+        .main_input_channels
+        .map(NonZeroU32::get)
+        .unwrap_or(0);
+    let audio_output_channels = audio_io_layout
+        .main_output_channels
+        .map(NonZeroU32::get)
+        .unwrap_or(0);
+
+    let plugin = P::default();
+    let params = plugin.params();
+    let parameters = exposed_params(params.as_ref())
+        .into_iter()
+        .map(|(id, ptr)| unsafe {
+            ParameterInfo {
+                id,
+                label: ptr.name().to_owned(),
+                type_: "float",
+                default_value: ptr.default_normalized_value(),
+                min_value: 0.0,
+                max_value: 1.0,
+                discrete_step: match ptr.step_count() {
+                    Some(step_count) if step_count > 0 => 1.0 / step_count as f32,
+                    _ => 0.0,
+                },
+                units: ptr.unit().trim().to_owned(),
+            }
+        })
+        .collect();
+
+    let descriptor = ModuleDescriptor {
+        descriptor: WamDescriptor {
+            identifier: P::WAM_ID,
+            name: P::NAME,
+            vendor: P::VENDOR,
+            version: P::VERSION,
+            api_version: WAM_API_VERSION,
+            thumbnail: "",
+            keywords: P::WAM_KEYWORDS,
+            is_instrument: P::WAM_IS_INSTRUMENT,
+            website: P::URL,
+            description: P::WAM_DESCRIPTION.unwrap_or(""),
+            has_audio_input: audio_input_channels > 0,
+            has_audio_output: audio_output_channels > 0,
+            has_automation_input: true,
+            has_automation_output: false,
+            has_midi_input: P::MIDI_INPUT >= MidiConfig::Basic,
+            has_midi_output: P::MIDI_OUTPUT >= MidiConfig::Basic,
+            has_mpe_input: false,
+            has_mpe_output: false,
+            has_osc_input: false,
+            has_osc_output: false,
+            has_sysex_input: false,
+            has_sysex_output: false,
+        },
+        parameters,
+        audio_input_channels,
+        audio_output_channels,
+    };
+
+    let json = serde_json::to_string(&descriptor).expect("Could not serialize the WAM descriptor");
+    CString::new(json).expect("The WAM descriptor contained null bytes")
+}
@@ -0,0 +1,27 @@
+/// Allocate `size` bytes in the module's memory. The glue code uses this to copy data into the
+/// module, for instance when restoring the plugin's state. Returns a null pointer if `size` is 0.
+pub fn alloc(size: u32) -> *mut u8 {
+    if size == 0 {
+        return std::ptr::null_mut();
+    }
+
+    let mut buffer = Vec::<u8>::with_capacity(size as usize);
+    let ptr = buffer.as_mut_ptr();
+    std::mem::forget(buffer);
+
+    ptr
+}
+
+/// Free memory allocated using [`alloc()`].
+///
+/// # Safety
+///
+/// `ptr` must have been returned by [`alloc()`] with the same `size`, and it must not be used
+/// afterwards.
+pub unsafe fn free(ptr: *mut u8, size: u32) {
+    if ptr.is_null() {
+        return;
+    }
+
+    drop(Vec::from_raw_parts(ptr, 0, size as usize));
+}
@@ -0,0 +1,1294 @@
+use atomic_refcell::AtomicRefCell;
+use crossbeam::queue::ArrayQueue;
+use parking_lot::Mutex;
+use std::any::Any;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::num::NonZeroU32;
+use std::os::raw::c_void;
+use std::ptr::{self, NonNull};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::context::{WrapperGuiContext, WrapperInitContext, WrapperProcessContext};
+use super::descriptor;
+use crate::context::gui::{
+    AsyncExecutor, GuiMessageQueue, ParamChange, ParamChangeBroadcaster, StateSlots,
+};
+use crate::event_loop::{EventLoop, MainThreadExecutor, OsEventLoop, TimerCallback, TimerHandle};
+use crate::midi::MidiResult;
+use crate::prelude::{
+    AudioIOLayout, AuxiliaryBuffers, BufferConfig, Editor, MidiConfig, NoteEvent, ParamPtr, Params,
+    ParentWindowHandle, Plugin, PluginNoteEvent, ProcessMode, ProcessStatus, StateSaveContext,
+    TaskExecutor, TaskOptions, Transport, WamPlugin,
+};
+use crate::util::modulation::ModulationMatrix;
+use crate::util::permit_alloc;
+use crate::wrapper::state::{self, PluginState};
+use crate::wrapper::util::buffer_management::{BufferManager, ChannelPointers};
+use crate::wrapper::util::bypass::BypassProcessor;
+use crate::wrapper::util::{
+    bypass_param_enabled, check_null_ptr, clamp_output_event_timing, find_bypass_param,
+    hash_param_id, param_id_from_hash, prepend_gui_note_events, process_wrapper,
+    queue_gui_note_event, setup_logger, GUI_NOTE_EVENT_QUEUE_CAPACITY,
+};
+
+/// The number of MIDI events that can be queued up before the next process call.
+const INPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// The number of MIDI events the plugin can output during a single process call.
+const OUTPUT_EVENT_QUEUE_CAPACITY: usize = 2048;
+/// The number of parameter changes from the editor that can be queued up before the glue code
+/// polls for them.
+const PARAM_CHANGE_QUEUE_CAPACITY: usize = 2048;
+
+pub struct Wrapper<P: WamPlugin> {
+    /// The wrapped plugin instance.
+    plugin: Mutex<P>,
+    /// The plugin's background task executor closure.
+    pub task_executor: Mutex<TaskExecutor<P>>,
+    /// The plugin's parameters. These are fetched once during initialization. That way the
+    /// `ParamPtr`s are guaranteed to live at least as long as this object and we can interact with
+    /// the `Params` object without having to acquire a lock on `plugin`.
+    params: Arc<dyn Params>,
+    /// The plugin's editor, if it has one. This object does not do anything on its own, but we need
+    /// to instantiate this in advance so we don't need to lock the entire [`Plugin`] object when
+    /// creating an editor. Wrapped in an `AtomicRefCell` because it needs to be initialized late.
+    editor: AtomicRefCell<Option<Mutex<Box<dyn Editor>>>>,
+    /// A handle for the currently active editor instance. The editor is closed when this is
+    /// dropped.
+    editor_handle: Mutex<Option<Box<dyn Any + Send>>>,
+    /// The browser's device pixel ratio, passed when the editor is opened. Used when the user has
+    /// not chosen a UI scale.
+    editor_host_scaling_factor: Mutex<f32>,
+    /// Set when the editor requests a resize. The glue code polls for this and then resizes the
+    /// editor's container.
+    resize_requested: AtomicBool,
+
+    /// A task queue for the plugin's background tasks and for informing the editor about parameter
+    /// changes. The queue is processed at the end of every process call, and during the glue
+    /// code's idle callback on the main thread.
+    event_loop: AtomicRefCell<Option<OsEventLoop<Task<P>, Self>>>,
+
+    /// The audio IO layout. This is always the plugin's first layout.
+    audio_io_layout: AudioIOLayout,
+    /// The buffer configuration. The glue code creates a new instance when the audio context's
+    /// sample rate changes.
+    buffer_config: BufferConfig,
+    /// The audio buffers shared with the glue code. The glue code copies the audio worklet's
+    /// inputs to these buffers before every process call, and it copies the outputs back
+    /// afterwards.
+    audio_buffers: AtomicRefCell<AudioBuffers>,
+    /// The current latency in samples, as set by the plugin through the [`InitContext`] and the
+    /// [`ProcessContext`]. WAM does not have a way to report this to the host, but the managed
+    /// bypass uses it to delay the dry signal.
+    current_latency: AtomicU32,
+
+    /// A mapping from parameter ID hashes (obtained from the string parameter IDs) to pointers to
+    /// parameters belonging to the plugin.
+    param_by_hash: HashMap<u32, ParamPtr>,
+    /// Mappings from string parameter identifiers to parameter hashes. Useful for debug logging
+    /// and when handling plugin state.
+    param_id_to_hash: HashMap<String, u32>,
+    /// The inverse mapping from [`param_by_hash`][Self::param_by_hash]. This is needed to be able
+    /// to have an ergonomic parameter setting API that uses references to the parameters instead of
+    /// having to add a setter function to the parameter (or even worse, have it be completely
+    /// untyped).
+    param_ptr_to_hash: HashMap<ParamPtr, u32>,
+    /// The parameters exposed to the host, in the same order as in the module's descriptor. The
+    /// glue code refers to parameters using their index in this vector.
+    exposed_params: Vec<ParamPtr>,
+    /// The index in [`exposed_params`][Self::exposed_params] for every exposed parameter.
+    param_ptr_to_index: HashMap<ParamPtr, u32>,
+    /// Parameter changes from the editor as `(param_index, normalized_value)` pairs. The glue code
+    /// polls for these and forwards them to the audio worklet's instance.
+    param_changes: ArrayQueue<(u32, f32)>,
+    /// Set when the editor loads new state. The glue code polls for this and then sends the
+    /// editor instance's state to the audio worklet's instance.
+    state_changed: AtomicBool,
+    /// The plugin's bypass parameter, if it has one.
+    bypass_param: Option<ParamPtr>,
+    /// Crossfades the main output to the dry signal when the plugin is bypassed. This is only
+    /// used when [`Plugin::MANAGED_BYPASS`] is enabled.
+    bypass_processor: AtomicRefCell<BypassProcessor>,
+    /// The plugin's modulation matrix, if it has one. This is evaluated just before every call to
+    /// the plugin's process function.
+    modulation_matrix: Option<Arc<ModulationMatrix>>,
+
+    /// The incoming events for the plugin. The WAM processor splits the audio worklet's blocks at
+    /// event boundaries, so all events are handled at the start of the next process call.
+    input_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// Stores any events the plugin has output during the current process call.
+    output_events: AtomicRefCell<VecDeque<PluginNoteEvent<P>>>,
+    /// The plugin's output events from the last process call, as `(timing, midi_message)` pairs.
+    /// MIDI messages are packed into a `u32` with the status byte in the least significant byte.
+    /// The glue code reads these after every process call.
+    output_midi: AtomicRefCell<Vec<u32>>,
+    /// The host's transport information from the last `wam-transport` event.
+    host_transport: AtomicRefCell<HostTransport>,
+    /// The buffer manager used to create the plugin's audio buffers from the shared buffers.
+    buffer_manager: AtomicRefCell<BufferManager>,
+    /// The plugin's serialized state from the last [`save_state()`][Self::save_state()] call. The
+    /// glue code copies this out of the WebAssembly instance's memory.
+    state_buffer: AtomicRefCell<Vec<u8>>,
+
+    /// Note events sent from the editor through
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()]. The editor
+    /// runs in a separate instance, so these only reach the plugin when the editor and the
+    /// processor share an instance.
+    gui_note_events: ArrayQueue<PluginNoteEvent<P>>,
+    /// Messages sent from the editor through `send_to_audio()` on the
+    /// [`GuiContext`][crate::prelude::GuiContext]. The plugin receives these through
+    /// [`ProcessContext::drain_gui_messages()`][crate::prelude::ProcessContext::drain_gui_messages()].
+    pub gui_messages: GuiMessageQueue,
+    /// Broadcasts parameter changes to the receivers created through
+    /// [`GuiContext::subscribe_param_changes()`][crate::prelude::GuiContext::subscribe_param_changes()].
+    pub param_changes: ParamChangeBroadcaster,
+    /// The A/B comparison slots used by the [`GuiContext`][crate::prelude::GuiContext]'s
+    /// `swap_ab()` and related functions.
+    pub state_slots: StateSlots,
+}
+
+/// Tasks that can be sent from the plugin to be executed on the main thread in a non-blocking
+/// realtime-safe way.
+#[allow(clippy::enum_variant_names)]
+pub enum Task<P: Plugin> {
+    /// Execute one of the plugin's background tasks.
+    PluginTask(P::BackgroundTask),
+    /// Inform the editor that one or more parameter values have changed.
+    ParameterValuesChanged,
+    /// Inform the editor that a parameter's value has changed. This uses the parameter hashes since
+    /// the task will be created from the audio thread.
+    ParameterValueChanged(u32, f32),
+    /// Run the callbacks registered on the plugin's
+    /// [`PersistNotifier`][crate::params::persist::PersistNotifier]s after its state has been
+    /// loaded. Runs on the background thread.
+    RunLoadCallbacks,
+    /// Run a timer callback registered through the [`GuiContext`][crate::prelude::GuiContext].
+    TimerCallback(TimerCallback),
+}
+
+/// The buffers the glue code writes the audio worklet's inputs to and reads its outputs from. Every
+/// channel has room for `max_buffer_size` samples.
+struct AudioBuffers {
+    inputs: Vec<Vec<f32>>,
+    outputs: Vec<Vec<f32>>,
+    /// Pointers to the buffers in `inputs`, passed to the buffer manager.
+    input_ptrs: Vec<*mut f32>,
+    /// Pointers to the buffers in `outputs`, passed to the buffer manager.
+    output_ptrs: Vec<*mut f32>,
+}
+
+// SAFETY: The pointers point to the vectors stored alongside them
+unsafe impl Send for AudioBuffers {}
+unsafe impl Sync for AudioBuffers {}
+
+/// The host's transport information from the last `wam-transport` event.
+#[derive(Debug, Default, Clone, Copy)]
+struct HostTransport {
+    playing: bool,
+    tempo: f64,
+    time_sig_numerator: i32,
+    time_sig_denominator: i32,
+    bar_number: i32,
+    /// The audio context's time in seconds at which the current bar started.
+    bar_start_time: f64,
+}
+
+impl<P: WamPlugin> MainThreadExecutor<Task<P>> for Wrapper<P> {
+    fn execute(&self, task: Task<P>, _is_gui_thread: bool) {
+        match task {
+            Task::PluginTask(task) => (self.task_executor.lock())(task),
+            Task::ParameterValuesChanged => {
+                self.param_changes.broadcast(|| Some(ParamChange::All));
+                if self.editor_handle.lock().is_some() {
+                    if let Some(editor) = self.editor.borrow().as_ref() {
+                        editor.lock().param_values_changed();
+                    }
+                }
+            }
+            Task::ParameterValueChanged(param_hash, normalized_value) => {
+                self.param_changes.broadcast(|| {
+                    let param_id = param_id_from_hash(&self.param_id_to_hash, param_hash)?;
+
+                    Some(ParamChange::Value {
+                        id: param_id.to_owned(),
+                        normalized_value,
+                    })
+                });
+                if self.editor_handle.lock().is_some() {
+                    if let Some(editor) = self.editor.borrow().as_ref() {
+                        if let Some(param_id) =
+                            param_id_from_hash(&self.param_id_to_hash, param_hash)
+                        {
+                            editor
+                                .lock()
+                                .param_value_changed(param_id, normalized_value);
+                        }
+                    }
+                }
+            }
+            Task::TimerCallback(callback) => callback.call(),
+            Task::RunLoadCallbacks => self.params.run_load_callbacks(),
+        }
+    }
+
+    fn schedule_timer_task(&self, task: Task<P>) -> bool {
+        self.schedule_gui(task)
+    }
+}
+
+impl<P: WamPlugin> Wrapper<P> {
+    /// Create a new instance of the plugin. Returns `None` if the plugin failed to initialize.
+    fn new(sample_rate: f32, max_block_size: u32) -> Option<Arc<Self>> {
+        let plugin = P::default();
+        let task_executor = Mutex::new(plugin.task_executor());
+        let params = plugin.params();
+        let modulation_matrix = plugin.modulation_matrix();
+
+        let param_map = params.param_map();
+        let param_hashes: Vec<u32> = param_map
+            .iter()
+            .map(|(id, _, _)| hash_param_id(id))
+            .collect();
+        if cfg!(debug_assertions) {
+            let unique_hashes: HashSet<_> = param_hashes.iter().collect();
+            nih_debug_assert_eq!(
+                param_hashes.len(),
+                unique_hashes.len(),
+                "The plugin has duplicate parameter hashes, weird things may happen. Consider using \
+                 6 character parameter IDs to avoid collisions."
+            );
+        }
+        let bypass_param = find_bypass_param::<P>(param_map.iter().map(|(_, ptr, _)| *ptr));
+        let exposed_params: Vec<ParamPtr> = descriptor::exposed_params(params.as_ref())
+            .into_iter()
+            .map(|(_, ptr)| ptr)
+            .collect();
+
+        let audio_io_layout = P::AUDIO_IO_LAYOUTS.first().copied().unwrap_or_default();
+        let max_block_size = max_block_size.max(1);
+        let buffer_config = BufferConfig {
+            sample_rate,
+            min_buffer_size: None,
+            max_buffer_size: max_block_size,
+            process_mode: ProcessMode::Realtime,
+        };
+
+        let wrapper = Arc::new(Self {
+            plugin: Mutex::new(plugin),
+            task_executor,
+            params,
+            // Initialized later as it needs a reference to the wrapper for the async executor
+            editor: AtomicRefCell::new(None),
+            editor_handle: Mutex::new(None),
+            editor_host_scaling_factor: Mutex::new(1.0),
+            resize_requested: AtomicBool::new(false),
+
+            // Also initialized later as it also needs a reference to the wrapper
+            event_loop: AtomicRefCell::new(None),
+
+            audio_io_layout,
+            buffer_config,
+            audio_buffers: AtomicRefCell::new(AudioBuffers::new(
+                audio_io_layout,
+                max_block_size as usize,
+            )),
+            current_latency: AtomicU32::new(0),
+
+            param_by_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*hash, *ptr))
+                .collect(),
+            param_id_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (id, _, _))| (id.clone(), *hash))
+                .collect(),
+            param_ptr_to_hash: param_hashes
+                .iter()
+                .zip(&param_map)
+                .map(|(hash, (_, ptr, _))| (*ptr, *hash))
+                .collect(),
+            param_ptr_to_index: exposed_params
+                .iter()
+                .enumerate()
+                .map(|(idx, ptr)| (*ptr, idx as u32))
+                .collect(),
+            exposed_params,
+            param_changes: ArrayQueue::new(PARAM_CHANGE_QUEUE_CAPACITY),
+            state_changed: AtomicBool::new(false),
+            bypass_param,
+            bypass_processor: AtomicRefCell::new(BypassProcessor::default()),
+            modulation_matrix,
+
+            input_events: AtomicRefCell::new(VecDeque::with_capacity(
+                INPUT_EVENT_QUEUE_CAPACITY + GUI_NOTE_EVENT_QUEUE_CAPACITY,
+            )),
+            output_events: AtomicRefCell::new(VecDeque::with_capacity(OUTPUT_EVENT_QUEUE_CAPACITY)),
+            output_midi: AtomicRefCell::new(Vec::with_capacity(OUTPUT_EVENT_QUEUE_CAPACITY * 2)),
+            host_transport: AtomicRefCell::new(HostTransport::default()),
+            buffer_manager: AtomicRefCell::new(BufferManager::for_audio_io_layout(
+                max_block_size as usize,
+                audio_io_layout,
+            )),
+            state_buffer: AtomicRefCell::new(Vec::new()),
+
+            gui_note_events: ArrayQueue::new(GUI_NOTE_EVENT_QUEUE_CAPACITY),
+            gui_messages: GuiMessageQueue::default(),
+            param_changes: ParamChangeBroadcaster::default(),
+            state_slots: StateSlots::default(),
+        });
+
+        *wrapper.event_loop.borrow_mut() =
+            Some(OsEventLoop::new_and_spawn(Arc::downgrade(&wrapper)));
+
+        // The editor also needs to be initialized later so the Async executor can work.
+        *wrapper.editor.borrow_mut() = wrapper
+            .plugin
+            .lock()
+            .editor(AsyncExecutor {
+                execute_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |task| {
+                        let task_posted = wrapper.schedule_background(Task::PluginTask(task));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                }),
+                execute_gui: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |task| {
+                        let task_posted = wrapper.schedule_gui(Task::PluginTask(task));
+                        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+                    }
+                }),
+                cancel_background: Arc::new({
+                    let wrapper = wrapper.clone();
+
+                    move |tag| wrapper.cancel_background_tasks(tag)
+                }),
+            })
+            .map(Mutex::new);
+
+        // Before initializing the plugin, make sure all smoothers are set the the default values
+        for param in wrapper.param_by_hash.values() {
+            param.update_smoother(buffer_config.sample_rate, true);
+        }
+
+        {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = wrapper.make_init_context();
+            let mut plugin = wrapper.plugin.lock();
+            if !plugin.initialize(&audio_io_layout, &buffer_config, &mut init_context) {
+                nih_error!("The plugin failed to initialize");
+                return None;
+            }
+            process_wrapper(|| plugin.reset());
+        }
+
+        if P::MANAGED_BYPASS {
+            wrapper.bypass_processor.borrow_mut().initialize(
+                audio_io_layout
+                    .main_output_channels
+                    .map(NonZeroU32::get)
+                    .unwrap_or(0) as usize,
+                wrapper.current_latency.load(Ordering::SeqCst),
+                buffer_config.max_buffer_size,
+                buffer_config.sample_rate,
+                wrapper.is_bypassed(),
+            );
+        }
+
+        Some(wrapper)
+    }
+
+    fn make_init_context(&self) -> WrapperInitContext<'_, P> {
+        WrapperInitContext { wrapper: self }
+    }
+
+    fn make_process_context(&self, transport: Transport) -> WrapperProcessContext<'_, P> {
+        WrapperProcessContext {
+            wrapper: self,
+            input_events_guard: self.input_events.borrow_mut(),
+            output_events_guard: self.output_events.borrow_mut(),
+            transport,
+            aux_input_count: self.audio_io_layout.aux_input_ports.len(),
+        }
+    }
+
+    fn make_gui_context(self: Arc<Self>) -> Arc<WrapperGuiContext<P>> {
+        Arc::new(WrapperGuiContext {
+            wrapper: self,
+            #[cfg(debug_assertions)]
+            param_gesture_checker: Default::default(),
+        })
+    }
+
+    /// Get a parameter's ID based on a `ParamPtr`. Used in the `GuiContext` implementation for the
+    /// gesture checks.
+    #[cfg(debug_assertions)]
+    pub fn param_id_from_ptr(&self, param: ParamPtr) -> Option<&str> {
+        param_id_from_hash(&self.param_id_to_hash, *self.param_ptr_to_hash.get(&param)?)
+    }
+
+    /// Posts the task to the background task queue using [`EventLoop::schedule_background()`]. The
+    /// task is run at the end of the next process call or during the next idle call.
+    ///
+    /// The plugin's own tasks are scheduled using the options from
+    /// [`Plugin::background_task_options()`].
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_background(&self, task: Task<P>) -> bool {
+        let options = match &task {
+            Task::PluginTask(task) => P::background_task_options(task),
+            _ => TaskOptions::default(),
+        };
+
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_background(task, options)
+    }
+
+    /// Cancel the pending background tasks with the tag `tag` using
+    /// [`EventLoop::cancel_background()`].
+    pub fn cancel_background_tasks(&self, tag: u32) {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.cancel_background(tag)
+    }
+
+    /// Posts the task to the task queue using [`EventLoop::schedule_gui()`]. The task is run at the
+    /// end of the next process call or during the next idle call.
+    ///
+    /// If the task queue is full, then this will return false.
+    #[must_use]
+    pub fn schedule_gui(&self, task: Task<P>) -> bool {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_gui(task)
+    }
+
+    /// Run `callback` on the GUI thread after `delay` has passed. See
+    /// [`GuiContext::schedule_once()`][crate::prelude::GuiContext::schedule_once()].
+    pub fn schedule_timer_once(
+        &self,
+        delay: Duration,
+        callback: Box<dyn FnOnce() + Send>,
+    ) -> TimerHandle {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_once(
+            delay,
+            Task::TimerCallback(TimerCallback::new_once(callback)),
+        )
+    }
+
+    /// Run `callback` on the GUI thread every `interval`. See
+    /// [`GuiContext::schedule_repeating()`][crate::prelude::GuiContext::schedule_repeating()].
+    pub fn schedule_timer_repeating(
+        &self,
+        interval: Duration,
+        callback: Box<dyn FnMut() + Send>,
+    ) -> TimerHandle {
+        let callback = TimerCallback::new(callback);
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.schedule_repeating(interval, move || Task::TimerCallback(callback.clone()))
+    }
+
+    /// Run the pending tasks and fire the timers whose deadlines have passed. `now` is the time
+    /// according to the host's clock.
+    fn run_pending_tasks(&self, now: Duration) {
+        let event_loop = self.event_loop.borrow();
+        let event_loop = event_loop.as_ref().unwrap();
+        event_loop.run_pending_tasks(now)
+    }
+
+    /// Inform the plugin that its editor has been opened or closed through
+    /// [`Plugin::editor_opened()`] and [`Plugin::editor_closed()`]. Called from the main thread
+    /// after the editor handle has been created or dropped.
+    fn notify_editor_opened(&self, opened: bool) {
+        let mut plugin = self.plugin.lock();
+        if opened {
+            plugin.editor_opened();
+        } else {
+            plugin.editor_closed();
+        }
+    }
+
+    /// Ask the glue code to resize the editor's container to the editor's current size. Returns
+    /// `false` if the editor is not open.
+    pub fn request_resize(&self) -> bool {
+        if self.editor_handle.lock().is_none() {
+            return false;
+        }
+
+        self.resize_requested.store(true, Ordering::SeqCst);
+        true
+    }
+
+    /// Set the user's UI scale. See
+    /// [`GuiContext::set_ui_scale()`][crate::prelude::GuiContext::set_ui_scale()].
+    pub fn set_ui_scale(&self, scale: Option<f32>) -> bool {
+        let applied = match self.editor.borrow().as_ref() {
+            Some(editor) => {
+                let editor = editor.lock();
+                editor.set_ui_scale(scale) && self.update_editor_scaling_factor(&**editor)
+            }
+            None => false,
+        };
+
+        applied && self.request_resize()
+    }
+
+    /// Pass the user's UI scale to the editor, or the browser's device pixel ratio if the user has
+    /// not chosen a UI scale. Returns `false` if the editor rejected the new scaling factor, for
+    /// instance because it is currently open.
+    fn update_editor_scaling_factor(&self, editor: &dyn Editor) -> bool {
+        let scaling_factor = editor
+            .ui_scale()
+            .unwrap_or_else(|| *self.editor_host_scaling_factor.lock());
+        editor.set_scale_factor(scaling_factor)
+    }
+
+    /// Queue a note event sent from the editor. See
+    /// [`GuiContext::send_note_event()`][crate::prelude::GuiContext::send_note_event()].
+    pub fn queue_gui_note_event(&self, event: NoteEvent<()>) -> bool {
+        queue_gui_note_event::<P>(&self.gui_note_events, event)
+    }
+
+    /// Queue a parameter change from the editor so the glue code can forward it to the audio
+    /// worklet's instance. Returns `false` if the parameter is hidden or if the queue is full.
+    pub fn queue_param_change(&self, param: ParamPtr, normalized: f32) -> bool {
+        match self.param_ptr_to_index.get(&param) {
+            Some(param_idx) => self.param_changes.push((*param_idx, normalized)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Whether the plugin's bypass parameter is currently enabled.
+    pub fn is_bypassed(&self) -> bool {
+        bypass_param_enabled(self.bypass_param)
+    }
+
+    pub fn set_latency_samples(&self, samples: u32) {
+        self.current_latency.store(samples, Ordering::SeqCst);
+    }
+
+    /// Get the plugin's state object, may be called by the plugin's GUI as part of its own preset
+    /// management. The wrapper doesn't use these functions and serializes and deserializes directly
+    /// the JSON in the relevant plugin API methods instead.
+    pub fn get_state_object(&self) -> PluginState {
+        unsafe {
+            state::serialize_object::<P>(
+                self.params.clone(),
+                state::make_params_iter(&self.param_by_hash, &self.param_id_to_hash),
+                StateSaveContext::Project,
+            )
+        }
+    }
+
+    /// Update the plugin's internal state, called by the plugin itself from the GUI thread. The
+    /// editor's instance never processes audio, so the state can be set immediately. The glue code
+    /// then sends the new state to the audio worklet's instance.
+    pub fn set_state_object_from_gui(&self, mut state: PluginState) {
+        if self.set_state_inner(&mut state) {
+            self.state_changed.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Immediately set the plugin state. Returns `false` if the deserialization failed.
+    ///
+    /// Implicitly emits `Task::ParameterValuesChanged`.
+    ///
+    /// # Notes
+    ///
+    /// `self.plugin` must _not_ be locked while calling this function or it will deadlock.
+    fn set_state_inner(&self, state: &mut PluginState) -> bool {
+        let mut success = unsafe {
+            state::deserialize_object::<P>(
+                state,
+                self.params.clone(),
+                state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                Some(&self.buffer_config),
+            )
+        };
+        if !success {
+            nih_debug_assert_failure!("Deserializing plugin state from a state object failed");
+            return false;
+        }
+
+        // This may be called from the audio thread, so the plugin's `PersistNotifier` callbacks
+        // are run on the background thread instead
+        let task_posted = self.schedule_background(Task::RunLoadCallbacks);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // The plugin is always initialized, so it needs to be reinitialized
+        {
+            // NOTE: This needs to be dropped after the `plugin` lock to avoid deadlocks
+            let mut init_context = self.make_init_context();
+            let mut plugin = self.plugin.lock();
+            success = plugin.initialize(
+                &self.audio_io_layout,
+                &self.buffer_config,
+                &mut init_context,
+            );
+            if success {
+                process_wrapper(|| plugin.reset());
+            }
+        }
+
+        nih_debug_assert!(
+            success,
+            "Plugin returned false when reinitializing after loading state"
+        );
+
+        let task_posted = self.schedule_gui(Task::ParameterValuesChanged);
+        nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+
+        // TODO: Right now there's no way to know if loading the state changed the GUI's size. We
+        //       could keep track of the last known size and compare the GUI's current size against
+        //       that but that also seems brittle.
+        self.request_resize();
+
+        success
+    }
+
+    /// Set a parameter's normalized value. This updates the parameter's smoother, applies macro
+    /// mappings, and informs the editor about the change.
+    pub(super) fn set_normalized_value(&self, param_ptr: ParamPtr, normalized: f32) {
+        let sample_rate = self.buffer_config.sample_rate;
+        if unsafe { param_ptr.set_normalized_value(normalized) } {
+            unsafe { param_ptr.update_smoother(sample_rate, false) };
+            unsafe {
+                param_ptr.apply_macro_mappings(
+                    state::make_params_getter(&self.param_by_hash, &self.param_id_to_hash),
+                    Some(sample_rate),
+                    |target_ptr, normalized_value| {
+                        if let Some(target_hash) = self.param_ptr_to_hash.get(&target_ptr) {
+                            let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                                *target_hash,
+                                normalized_value,
+                            ));
+                            nih_debug_assert!(
+                                task_posted,
+                                "The task queue is full, dropping task..."
+                            );
+                        }
+                    },
+                )
+            };
+
+            let task_posted = self.schedule_gui(Task::ParameterValueChanged(
+                self.param_ptr_to_hash[&param_ptr],
+                normalized,
+            ));
+            nih_debug_assert!(task_posted, "The task queue is full, dropping task...");
+        }
+    }
+
+    /// Write the plugin's output events to [`output_midi`][Self::output_midi].
+    fn write_output_events(&self, num_samples: u32) {
+        let mut output_events = self.output_events.borrow_mut();
+        let mut output_midi = self.output_midi.borrow_mut();
+        for event in output_events.drain(..) {
+            let is_note_event = matches!(
+                event,
+                NoteEvent::NoteOn { .. }
+                    | NoteEvent::NoteOff { .. }
+                    | NoteEvent::PolyPressure { .. }
+            );
+            if !is_note_event && P::MIDI_OUTPUT < MidiConfig::MidiCCs {
+                continue;
+            }
+
+            // SysEx messages are not supported, and events without a MIDI equivalent can't be sent
+            // to the host
+            let timing = clamp_output_event_timing(event.timing(), num_samples);
+            if let Some(MidiResult::Basic(midi_data)) = event.as_midi() {
+                output_midi.push(timing);
+                output_midi.push(u32::from_le_bytes([
+                    midi_data[0],
+                    midi_data[1],
+                    midi_data[2],
+                    0,
+                ]));
+            }
+        }
+    }
+
+    /// The transport information at the start of a process call. `current_time` is the audio
+    /// context's current time in seconds.
+    fn transport(&self, current_time: f64) -> Transport {
+        let host_transport = self.host_transport.borrow();
+
+        // Some of the fields are left empty because the host does not provide this information,
+        // but the methods on [`Transport`] can reconstruct these values from the other fields
+        let mut transport = Transport::new(self.buffer_config.sample_rate);
+        transport.playing = host_transport.playing;
+        if host_transport.tempo > 0.0 {
+            transport.tempo = Some(host_transport.tempo);
+        }
+        if host_transport.time_sig_numerator > 0 && host_transport.time_sig_denominator > 0 {
+            transport.time_sig_numerator = Some(host_transport.time_sig_numerator);
+            transport.time_sig_denominator = Some(host_transport.time_sig_denominator);
+
+            if host_transport.playing && host_transport.tempo > 0.0 {
+                // NIH-plug's beat positions are in quarter notes
+                let quarter_notes_per_bar = host_transport.time_sig_numerator as f64 * 4.0
+                    / host_transport.time_sig_denominator as f64;
+                let bar_start_pos_beats = host_transport.bar_number as f64 * quarter_notes_per_bar;
+                let elapsed_beats = (current_time - host_transport.bar_start_time).max(0.0)
+                    * host_transport.tempo
+                    / 60.0;
+                transport.bar_start_pos_beats = Some(bar_start_pos_beats);
+                transport.pos_beats = Some(bar_start_pos_beats + elapsed_beats);
+                transport.bar_number = Some(host_transport.bar_number);
+            }
+        }
+
+        transport
+    }
+
+    /// Create a new instance of the plugin. Called by the glue code on both the audio worklet's
+    /// thread and on the browser's main thread for the editor. Returns a null pointer if the
+    /// plugin failed to initialize.
+    pub fn create(sample_rate: f32, max_block_size: u32) -> *mut c_void {
+        setup_logger();
+
+        match Self::new(sample_rate, max_block_size) {
+            Some(wrapper) => Arc::into_raw(wrapper) as *mut c_void,
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Destroy an instance created by [`create()`][Self::create()].
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()] and it must not be used
+    /// afterwards.
+    pub unsafe fn destroy(instance: *mut c_void) {
+        check_null_ptr!((), instance);
+        let wrapper = Arc::from_raw(instance as *const Self);
+
+        // The editor holds on to a reference to the wrapper, so it needs to be closed first
+        let editor_handle = wrapper.editor_handle.lock().take();
+        if editor_handle.is_some() {
+            drop(editor_handle);
+            wrapper.notify_editor_opened(false);
+        }
+        wrapper.plugin.lock().deactivate();
+        drop(wrapper);
+    }
+
+    /// Get a pointer to an input channel's buffer. The buffer has room for `max_block_size`
+    /// samples. Returns a null pointer if the channel does not exist.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn input_buffer(instance: *mut c_void, channel: u32) -> *mut f32 {
+        check_null_ptr!(ptr::null_mut(), instance);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper
+            .audio_buffers
+            .borrow_mut()
+            .inputs
+            .get_mut(channel as usize)
+        {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Get a pointer to an output channel's buffer. The buffer has room for `max_block_size`
+    /// samples. Returns a null pointer if the channel does not exist.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn output_buffer(instance: *mut c_void, channel: u32) -> *mut f32 {
+        check_null_ptr!(ptr::null_mut(), instance);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper
+            .audio_buffers
+            .borrow_mut()
+            .outputs
+            .get_mut(channel as usize)
+        {
+            Some(buffer) => buffer.as_mut_ptr(),
+            None => ptr::null_mut(),
+        }
+    }
+
+    /// Update the transport information from a `wam-transport` event.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn set_transport(
+        instance: *mut c_void,
+        playing: bool,
+        tempo: f64,
+        time_sig_numerator: i32,
+        time_sig_denominator: i32,
+        bar_number: i32,
+        bar_start_time: f64,
+    ) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        *wrapper.host_transport.borrow_mut() = HostTransport {
+            playing,
+            tempo,
+            time_sig_numerator,
+            time_sig_denominator,
+            bar_number,
+            bar_start_time,
+        };
+    }
+
+    /// Queue a MIDI event from a `wam-midi` event. The event is handled at the start of the next
+    /// process call.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn midi_event(instance: *mut c_void, status: u8, data1: u8, data2: u8) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+        if P::MIDI_INPUT < MidiConfig::Basic {
+            return;
+        }
+
+        let mut input_events = wrapper.input_events.borrow_mut();
+        if input_events.len() >= INPUT_EVENT_QUEUE_CAPACITY {
+            nih_debug_assert_failure!("The input event queue is full, dropping event...");
+            return;
+        }
+
+        // In the Basic note port type, we'll still handle note on, note off, and polyphonic
+        // pressure events. But we'll throw away any other MIDI messages to stay consistent with the
+        // other wrappers.
+        match NoteEvent::from_midi(0, &[status, data1, data2]) {
+            Ok(
+                note_event @ (NoteEvent::NoteOn { .. }
+                | NoteEvent::NoteOff { .. }
+                | NoteEvent::PolyPressure { .. }),
+            ) => {
+                input_events.push_back(note_event);
+            }
+            Ok(note_event) if P::MIDI_INPUT >= MidiConfig::MidiCCs => {
+                input_events.push_back(note_event);
+            }
+            Ok(_) => (),
+            Err(n) => nih_debug_assert_failure!("Unhandled MIDI message type {}", n),
+        }
+    }
+
+    /// Process `num_samples` samples from the input buffers to the output buffers. `current_time`
+    /// is the audio context's current time in seconds. Pending tasks are run after processing the
+    /// audio, since the audio worklet does not have an idle callback.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn process(instance: *mut c_void, num_samples: u32, current_time: f64) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        process_wrapper(|| {
+            wrapper.output_midi.borrow_mut().clear();
+
+            nih_debug_assert!(num_samples <= wrapper.buffer_config.max_buffer_size);
+            let num_samples = num_samples.min(wrapper.buffer_config.max_buffer_size);
+            if num_samples == 0 {
+                return;
+            }
+
+            // Notes played from the editor are handled at the start of the process call
+            prepend_gui_note_events(
+                &wrapper.gui_note_events,
+                &mut wrapper.input_events.borrow_mut(),
+            );
+
+            let mut audio_buffers = wrapper.audio_buffers.borrow_mut();
+            let AudioBuffers {
+                input_ptrs,
+                output_ptrs,
+                ..
+            } = &mut *audio_buffers;
+            let mut buffer_manager = wrapper.buffer_manager.borrow_mut();
+            let buffers = buffer_manager.create_buffers(0, num_samples as usize, |buffer_source| {
+                if !input_ptrs.is_empty() {
+                    *buffer_source.main_input_channel_pointers = Some(ChannelPointers {
+                        ptrs: NonNull::new(input_ptrs.as_mut_ptr()).unwrap(),
+                        num_channels: input_ptrs.len(),
+                    });
+                }
+                if !output_ptrs.is_empty() {
+                    *buffer_source.main_output_channel_pointers = Some(ChannelPointers {
+                        ptrs: NonNull::new(output_ptrs.as_mut_ptr()).unwrap(),
+                        num_channels: output_ptrs.len(),
+                    });
+                }
+            });
+
+            let sample_rate = wrapper.buffer_config.sample_rate;
+            let transport = wrapper.transport(current_time);
+
+            let mut plugin = permit_alloc(|| wrapper.plugin.lock());
+            if let Some(modulation_matrix) = &wrapper.modulation_matrix {
+                modulation_matrix.process_block(
+                    buffers.main_buffer,
+                    wrapper.input_events.borrow().iter(),
+                    sample_rate,
+                    state::make_params_getter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
+                );
+            }
+
+            let mut aux = AuxiliaryBuffers {
+                inputs: buffers.aux_inputs,
+                outputs: buffers.aux_outputs,
+            };
+            let mut bypass_processor = wrapper.bypass_processor.borrow_mut();
+            if P::MANAGED_BYPASS {
+                bypass_processor.write_dry(buffers.main_buffer);
+            }
+            // `DspLoadMeter` is not used here since `Instant` is not available in the browser
+            let result = {
+                let mut context = wrapper.make_process_context(transport);
+                plugin.process(buffers.main_buffer, &mut aux, &mut context)
+            };
+            if P::MANAGED_BYPASS {
+                bypass_processor.process(
+                    buffers.main_buffer,
+                    wrapper.current_latency.load(Ordering::Relaxed),
+                    wrapper.is_bypassed(),
+                );
+            }
+            if let ProcessStatus::Error(err) = result {
+                nih_debug_assert_failure!("Process error: {}", err);
+            }
+            drop(plugin);
+
+            // The process context drains the input events, but events the plugin did not read
+            // should not carry over to the next process call
+            wrapper.input_events.borrow_mut().clear();
+            wrapper.write_output_events(num_samples);
+        });
+
+        wrapper.run_pending_tasks(Duration::from_secs_f64(current_time.max(0.0)));
+    }
+
+    /// Get a pointer to the plugin's output events from the last process call. See
+    /// [`output_midi`][Self::output_midi] for the format.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn output_events(instance: *mut c_void) -> *const u32 {
+        check_null_ptr!(ptr::null(), instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.output_midi.borrow().as_ptr()
+    }
+
+    /// The number of output events from the last process call.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn output_event_count(instance: *mut c_void) -> u32 {
+        check_null_ptr!(0, instance);
+        let wrapper = &*(instance as *const Self);
+
+        (wrapper.output_midi.borrow().len() / 2) as u32
+    }
+
+    /// Set an exposed parameter's normalized value.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn set_param(instance: *mut c_void, param_idx: u32, normalized: f32) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper.exposed_params.get(param_idx as usize) {
+            Some(param_ptr) => wrapper.set_normalized_value(*param_ptr, normalized.clamp(0.0, 1.0)),
+            None => nih_debug_assert_failure!("Unknown parameter index {}", param_idx),
+        }
+    }
+
+    /// Get an exposed parameter's unmodulated normalized value. Returns 0 if the parameter does not
+    /// exist.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn get_param(instance: *mut c_void, param_idx: u32) -> f32 {
+        check_null_ptr!(0.0, instance);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper.exposed_params.get(param_idx as usize) {
+            Some(param_ptr) => param_ptr.unmodulated_normalized_value(),
+            None => 0.0,
+        }
+    }
+
+    /// Pop a parameter change made in the editor. The parameter's new normalized value is written
+    /// to `value`. Returns the parameter's index, or -1 if there are no more changes.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()] and `value` must point to
+    /// a writable `f32`.
+    pub unsafe fn poll_param_change(instance: *mut c_void, value: *mut f32) -> i32 {
+        check_null_ptr!(-1, instance, value);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper.param_changes.pop() {
+            Some((param_idx, normalized)) => {
+                *value = normalized;
+                param_idx as i32
+            }
+            None => -1,
+        }
+    }
+
+    /// Serialize the plugin's state. Returns the length of the serialized state in bytes, which can
+    /// then be read using [`state_ptr()`][Self::state_ptr()]. Returns 0 if the state could not be
+    /// serialized.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn save_state(instance: *mut c_void) -> u32 {
+        check_null_ptr!(0, instance);
+        let wrapper = &*(instance as *const Self);
+
+        let serialized = state::serialize_json::<P>(
+            wrapper.params.clone(),
+            state::make_params_iter(&wrapper.param_by_hash, &wrapper.param_id_to_hash),
+            StateSaveContext::Project,
+        );
+        match serialized {
+            Ok(serialized) => {
+                nih_trace!("Saved state ({} bytes)", serialized.len());
+
+                let len = serialized.len() as u32;
+                *wrapper.state_buffer.borrow_mut() = serialized;
+                len
+            }
+            Err(err) => {
+                nih_debug_assert_failure!("Could not save state: {:#}", err);
+                0
+            }
+        }
+    }
+
+    /// Get a pointer to the state serialized during the last [`save_state()`][Self::save_state()]
+    /// call.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn state_ptr(instance: *mut c_void) -> *const u8 {
+        check_null_ptr!(ptr::null(), instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.state_buffer.borrow().as_ptr()
+    }
+
+    /// Restore state previously serialized using [`save_state()`][Self::save_state()]. Returns
+    /// `false` if the state could not be restored.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()] and `data` must point to
+    /// `len` bytes.
+    pub unsafe fn load_state(instance: *mut c_void, data: *const u8, len: u32) -> bool {
+        check_null_ptr!(false, instance, data);
+        let wrapper = &*(instance as *const Self);
+
+        let serialized = std::slice::from_raw_parts(data, len as usize);
+        match state::deserialize_json(serialized) {
+            Some(mut state) => {
+                let success = wrapper.set_state_inner(&mut state);
+                if success {
+                    nih_trace!("Loaded state ({} bytes)", serialized.len());
+                }
+
+                success
+            }
+            None => false,
+        }
+    }
+
+    /// Returns `true` once after the editor has loaded new state.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn poll_state_change(instance: *mut c_void) -> bool {
+        check_null_ptr!(false, instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.state_changed.swap(false, Ordering::SeqCst)
+    }
+
+    /// Whether the plugin has an editor.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn has_editor(instance: *mut c_void) -> bool {
+        check_null_ptr!(false, instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.editor.borrow().is_some()
+    }
+
+    /// Open the editor in the canvas with a `data-raw-handle` attribute matching `canvas_handle`.
+    /// `scaling_factor` is the browser's device pixel ratio. Returns `false` if the plugin does not
+    /// have an editor or if the editor is already open.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn editor_open(
+        instance: *mut c_void,
+        canvas_handle: u32,
+        scaling_factor: f32,
+    ) -> bool {
+        check_null_ptr!(false, instance);
+        Arc::increment_strong_count(instance as *const Self);
+        let wrapper = Arc::from_raw(instance as *const Self);
+
+        let mut editor_handle = wrapper.editor_handle.lock();
+        if editor_handle.is_some() {
+            nih_debug_assert_failure!("The editor is already open");
+            return false;
+        }
+
+        let editor = wrapper.editor.borrow();
+        let Some(editor) = editor.as_ref() else {
+            nih_debug_assert_failure!("Tried to open the editor for a plugin without editor");
+            return false;
+        };
+
+        *wrapper.editor_host_scaling_factor.lock() = scaling_factor;
+        let editor = editor.lock();
+        wrapper.update_editor_scaling_factor(&**editor);
+        *editor_handle = Some(editor.spawn(
+            ParentWindowHandle::WebCanvas(canvas_handle),
+            wrapper.clone().make_gui_context(),
+        ));
+        drop(editor);
+        drop(editor_handle);
+        wrapper.notify_editor_opened(true);
+
+        true
+    }
+
+    /// Close the editor if it is open.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn editor_close(instance: *mut c_void) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        // The handle is dropped outside of the lock since the editor may still run tasks that
+        // check whether the editor is open
+        let editor_handle = wrapper.editor_handle.lock().take();
+        if editor_handle.is_some() {
+            drop(editor_handle);
+            wrapper.notify_editor_opened(false);
+        }
+    }
+
+    /// The editor's current size in logical pixels, packed as `(width << 16) | height`. Returns 0
+    /// if the plugin does not have an editor.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn editor_size(instance: *mut c_void) -> u32 {
+        check_null_ptr!(0, instance);
+        let wrapper = &*(instance as *const Self);
+
+        match wrapper.editor.borrow().as_ref() {
+            Some(editor) => {
+                let (width, height) = editor.lock().size();
+                (width.min(0xFFFF) << 16) | height.min(0xFFFF)
+            }
+            None => 0,
+        }
+    }
+
+    /// Returns `true` once after the editor has requested a resize. The glue code should then
+    /// resize the editor's container to [`editor_size()`][Self::editor_size()].
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn poll_editor_resize(instance: *mut c_void) -> bool {
+        check_null_ptr!(false, instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.resize_requested.swap(false, Ordering::SeqCst)
+    }
+
+    /// Run pending tasks on the editor's instance. Called periodically by the glue code on the
+    /// browser's main thread. `now_ms` is the value of `performance.now()`.
+    ///
+    /// # Safety
+    ///
+    /// `instance` must have been created by [`create()`][Self::create()].
+    pub unsafe fn idle(instance: *mut c_void, now_ms: f64) {
+        check_null_ptr!((), instance);
+        let wrapper = &*(instance as *const Self);
+
+        wrapper.run_pending_tasks(Duration::from_secs_f64(now_ms.max(0.0) / 1000.0));
+    }
+}
+
+impl AudioBuffers {
+    fn new(audio_io_layout: AudioIOLayout, max_buffer_size: usize) -> Self {
+        let num_inputs = audio_io_layout
+            .main_input_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+        let num_outputs = audio_io_layout
+            .main_output_channels
+            .map(NonZeroU32::get)
+            .unwrap_or(0) as usize;
+
+        let mut inputs = vec![vec![0.0; max_buffer_size]; num_inputs];
+        let mut outputs = vec![vec![0.0; max_buffer_size]; num_outputs];
+        let input_ptrs = inputs
+            .iter_mut()
+            .map(|buffer| buffer.as_mut_ptr())
+            .collect();
+        let output_ptrs = outputs
+            .iter_mut()
+            .map(|buffer| buffer.as_mut_ptr())
+            .collect();
+
+        Self {
+            inputs,
+            outputs,
+            input_ptrs,
+            output_ptrs,
+        }
+    }
+}